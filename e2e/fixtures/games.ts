@@ -0,0 +1,66 @@
+import { query } from './db';
+
+export interface FinishedGameOptions {
+  /** Defaults to 'finished'; other statuses check what unfinished games leave out */
+  status?: string;
+  visibility?: 'public' | 'private';
+  seed?: number;
+  /** Each snake's battlesnake_id and placement */
+  placements?: [string, number][];
+  /** Frame data for each turn, starting from turn 0 */
+  frames?: object[];
+}
+
+/**
+ * Record a 7x7 game directly in the database, without running it, and return
+ * its ID.
+ */
+export async function finishedGame(options: FinishedGameOptions = {}): Promise<string> {
+  const games = await query<{ game_id: string }>(
+    `INSERT INTO games (board_size, game_type, status, visibility, seed)
+     VALUES ('7x7', 'Standard', $1, $2, $3) RETURNING game_id`,
+    [options.status ?? 'finished', options.visibility ?? 'public', options.seed ?? null]
+  );
+  const gameId = games[0].game_id;
+
+  for (const [snakeId, placement] of options.placements ?? []) {
+    await query(
+      'INSERT INTO game_battlesnakes (game_id, battlesnake_id, placement) VALUES ($1, $2, $3)',
+      [gameId, snakeId, placement]
+    );
+  }
+  await addFrames(gameId, options.frames ?? []);
+
+  return gameId;
+}
+
+/**
+ * Store frame data for a game's turns, the first one on turn `firstTurn`.
+ */
+export async function addFrames(gameId: string, frames: object[], firstTurn = 0): Promise<void> {
+  for (const [index, frame] of frames.entries()) {
+    await query(
+      'INSERT INTO turns (game_id, turn_number, frame_data) VALUES ($1, $2, $3)',
+      [gameId, firstTurn + index, JSON.stringify(frame)]
+    );
+  }
+}
+
+/**
+ * Frames for a lone snake named Alpha walking up the board from (1, 0).
+ */
+export function walkingSnakeFrames(turns: number, snake: object = {}): object[] {
+  return Array.from({ length: turns }, (_, turn) => ({
+    Turn: turn,
+    Snakes: [{
+      ID: 'a',
+      Name: 'Alpha',
+      Health: 100 - turn,
+      Body: [{ X: 1, Y: turn + 1 }, { X: 1, Y: turn }],
+      Death: null,
+      ...snake,
+    }],
+    Food: [{ X: 5, Y: 5 }],
+    Hazards: [],
+  }));
+}
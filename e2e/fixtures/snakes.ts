@@ -0,0 +1,28 @@
+import { Page } from '@playwright/test';
+
+// Mock snakes server URL (matches playwright.config.ts)
+const MOCK_SNAKES_PORT = process.env.MOCK_SNAKES_PORT || '8082';
+export const MOCK_SNAKES_URL = `http://localhost:${MOCK_SNAKES_PORT}`;
+
+/**
+ * URL of a mock snake that plays the given behavior, like 'up' or 'slow'.
+ */
+export function mockSnakeUrl(behavior: string): string {
+  return `${MOCK_SNAKES_URL}/${behavior}`;
+}
+
+/**
+ * Create a snake through the API as the page's user and return the API's JSON
+ * for it. The name gets a timestamp so reruns don't clash on unique names.
+ */
+export async function createSnake(
+  page: Page,
+  name: string,
+  url: string = mockSnakeUrl('up'),
+  isPublic = false
+) {
+  const response = await page.request.post('/api/snakes', {
+    data: { name: `${name} ${Date.now()}`, url, is_public: isPublic },
+  });
+  return response.json();
+}
@@ -2,6 +2,7 @@ import { defineConfig, devices } from '@playwright/test';
 
 const PORT = process.env.PORT || '3000';
 const MOCK_GITHUB_PORT = process.env.MOCK_GITHUB_PORT || '8081';
+const MOCK_SNAKES_PORT = process.env.MOCK_SNAKES_PORT || '8082';
 const BASE_URL = `http://localhost:${PORT}`;
 const MOCK_GITHUB_URL = `http://localhost:${MOCK_GITHUB_PORT}`;
 const DATABASE_URL = process.env.DATABASE_URL || 'postgresql://localhost:5432/arena_test';
@@ -37,6 +38,17 @@ export default defineConfig({
         MOCK_GITHUB_PORT: MOCK_GITHUB_PORT,
       },
     },
+    // Mock snakes server (snake URLs like http://localhost:8082/up)
+    {
+      command: 'cargo run -p mock-snakes',
+      cwd: '..',
+      port: parseInt(MOCK_SNAKES_PORT),
+      reuseExistingServer: !process.env.CI,
+      timeout: 120 * 1000,
+      env: {
+        MOCK_SNAKES_PORT: MOCK_SNAKES_PORT,
+      },
+    },
     // Main application server
     {
       command: 'cargo run -p arena',
@@ -57,7 +69,7 @@ export default defineConfig({
         GITHUB_CLIENT_SECRET: 'mock_client_secret',
         GITHUB_REDIRECT_URI: `${BASE_URL}/auth/github/callback`,
         // Faster job polling for e2e tests (default is 60 seconds)
-        JOB_POLL_INTERVAL_SECS: '2',
+        ARENA_JOB_POLL_INTERVAL_MS: '500',
       },
     },
   ],
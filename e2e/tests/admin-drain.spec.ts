@@ -0,0 +1,14 @@
+import { test, expect } from '../fixtures/test';
+
+test.describe('Admin drain', () => {
+  // Draining would stop the shared e2e server running games, so only the
+  // disabled endpoint is exercised here
+  test('is hidden when no admin token is configured', async ({ authenticatedPage }) => {
+    // The e2e server runs without ARENA_ADMIN_TOKEN, and a user's session doesn't count
+    const drain = await authenticatedPage.request.post('/api/v1/admin/drain');
+    expect(drain.status()).toBe(404);
+
+    const status = await authenticatedPage.request.get('/api/v1/admin/drain');
+    expect(status.status()).toBe(404);
+  });
+});
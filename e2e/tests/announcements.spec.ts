@@ -0,0 +1,16 @@
+import { test, expect } from '../fixtures/test';
+
+test.describe('Announcements', () => {
+  test('running announcements are listed without logging in', async ({ page }) => {
+    const response = await page.request.get('/api/v1/announcements');
+    expect(response.status()).toBe(200);
+    expect(Array.isArray(await response.json())).toBe(true);
+  });
+
+  test('managing announcements is hidden when no admin token is configured', async ({ authenticatedPage }) => {
+    const response = await authenticatedPage.request.post('/api/v1/admin/announcements', {
+      data: { message: 'Maintenance tonight', severity: 'warning' },
+    });
+    expect(response.status()).toBe(404);
+  });
+});
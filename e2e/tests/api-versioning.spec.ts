@@ -0,0 +1,38 @@
+import { test, expect } from '../fixtures/test';
+
+test.describe('API versioning', () => {
+  test('versioned routes answer with their version', async ({ page }) => {
+    const response = await page.request.get('/api/v1/capabilities');
+    expect(response.status()).toBe(200);
+    expect(response.headers()['arena-api-version']).toBe('1');
+    expect(response.headers()['deprecation']).toBeUndefined();
+
+    const capabilities = await response.json();
+    expect(capabilities.api_versions).toContain(1);
+  });
+
+  test('unversioned routes still work but are marked deprecated', async ({ page }) => {
+    const response = await page.request.get('/api/capabilities');
+    expect(response.status()).toBe(200);
+    expect(response.headers()['arena-api-version']).toBe('1');
+    expect(response.headers()['deprecation']).toMatch(/^@\d+$/);
+    expect(response.headers()['link']).toBe('</api/v1/capabilities>; rel="successor-version"');
+  });
+
+  test('asking for a version the route does not serve is rejected', async ({ page }) => {
+    const unsupported = await page.request.get('/api/v1/capabilities', {
+      headers: { 'Arena-Api-Version': '2' },
+    });
+    expect(unsupported.status()).toBe(400);
+
+    const garbled = await page.request.get('/api/v1/capabilities', {
+      headers: { 'Arena-Api-Version': 'latest' },
+    });
+    expect(garbled.status()).toBe(400);
+
+    const matching = await page.request.get('/api/v1/capabilities', {
+      headers: { 'Arena-Api-Version': '1' },
+    });
+    expect(matching.status()).toBe(200);
+  });
+});
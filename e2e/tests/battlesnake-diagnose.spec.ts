@@ -0,0 +1,49 @@
+import { test, expect } from '../fixtures/test';
+import { MOCK_SNAKES_URL } from '../fixtures/snakes';
+
+test.describe('Battlesnake Diagnose', () => {
+  test('reports each check for a reachable snake', async ({ authenticatedPage }) => {
+    const snake = await (await authenticatedPage.request.post('/api/snakes', {
+      data: { name: `Diagnosed ${Date.now()}`, url: `${MOCK_SNAKES_URL}/up`, is_public: false },
+    })).json();
+
+    const response = await authenticatedPage.request.post(`/api/snakes/${snake.id}/diagnose`);
+    expect(response.status()).toBe(200);
+    const diagnosis = await response.json();
+    expect(diagnosis.healthy).toBe(true);
+    expect(diagnosis.checks.map((c: { name: string }) => c.name)).toEqual([
+      'URL', 'DNS', 'Connection', 'TLS', 'HTTP status', 'Response schema', 'Latency',
+    ]);
+    const status = (name: string) =>
+      diagnosis.checks.find((c: { name: string }) => c.name === name).status;
+    expect(status('HTTP status')).toBe('pass');
+    expect(status('Response schema')).toBe('pass');
+    // Plain http, so there's no TLS to check
+    expect(status('TLS')).toBe('skipped');
+  });
+
+  test('stops at the first failed check and says why', async ({ authenticatedPage }) => {
+    const snakeName = `Unreachable ${Date.now()}`;
+    const snake = await (await authenticatedPage.request.post('/api/snakes', {
+      data: { name: snakeName, url: 'http://127.0.0.1:1/', is_public: false },
+    })).json();
+
+    await authenticatedPage.goto(`/battlesnakes/${snake.id}/edit`);
+    await authenticatedPage.getByRole('button', { name: 'Diagnose this snake' }).click();
+
+    await expect(authenticatedPage.getByRole('heading', { name: `Diagnose Battlesnake: ${snakeName}` })).toBeVisible();
+    await expect(authenticatedPage.locator('.alert-danger')).toBeVisible();
+    const connection = authenticatedPage.locator('tr', { hasText: 'Connection' });
+    await expect(connection).toContainText('fail');
+    await expect(authenticatedPage.locator('tr', { hasText: 'Latency' })).toContainText('skipped');
+  });
+
+  test('diagnosing requires authentication', async ({ authenticatedPage, page }) => {
+    const snake = await (await authenticatedPage.request.post('/api/snakes', {
+      data: { name: `Private Diagnose ${Date.now()}`, url: `${MOCK_SNAKES_URL}/up`, is_public: true },
+    })).json();
+
+    const response = await page.request.post(`/api/snakes/${snake.id}/diagnose`);
+    expect(response.status()).toBe(401);
+  });
+});
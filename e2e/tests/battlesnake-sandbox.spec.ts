@@ -0,0 +1,119 @@
+import { test, expect, createMockUser } from '../fixtures/test';
+import { query } from '../fixtures/db';
+
+test.describe('Battlesnake Sandbox', () => {
+  test('test button runs a quick game against a bot and shows the replay', async ({ authenticatedPage }) => {
+    const snakeName = `Sandbox Snake ${Date.now()}`;
+
+    await authenticatedPage.goto('/battlesnakes/new');
+    await authenticatedPage.getByLabel('Name').fill(snakeName);
+    await authenticatedPage.getByLabel('URL').fill('https://example.com/sandbox');
+    await authenticatedPage.getByLabel('Visibility').selectOption('public');
+    await authenticatedPage.getByRole('button', { name: 'Create Battlesnake' }).click();
+
+    const snakeRow = authenticatedPage.locator('tr', { hasText: snakeName });
+    await snakeRow.getByRole('link', { name: 'Edit', exact: true }).click();
+
+    // No replay until the snake has been tested
+    await expect(authenticatedPage.locator('#sandbox-viewer')).toHaveCount(0);
+
+    await authenticatedPage.getByRole('button', { name: 'Test this snake' }).click();
+    await expect(authenticatedPage).toHaveURL(/\/edit\?sandbox_game=[0-9a-f-]{36}/);
+    const gameId = new URL(authenticatedPage.url()).searchParams.get('sandbox_game')!;
+
+    const iframeSrc = await authenticatedPage.locator('#sandbox-viewer').getAttribute('src');
+    expect(iframeSrc).toContain(`game=${gameId}`);
+
+    // A short 7x7 game against the user's sandbox bot
+    const games = await query<{ board_size: string; max_turns: number }>(
+      'SELECT board_size, max_turns FROM games WHERE game_id = $1',
+      [gameId]
+    );
+    expect(games[0].board_size).toBe('7x7');
+    expect(games[0].max_turns).toBe(150);
+
+    const snakes = await query<{ name: string; url: string }>(
+      `SELECT b.name, b.url FROM game_battlesnakes gb
+       JOIN battlesnakes b ON gb.battlesnake_id = b.battlesnake_id
+       WHERE gb.game_id = $1 ORDER BY b.name`,
+      [gameId]
+    );
+    expect(snakes.map((s) => s.name)).toEqual(['Sandbox Bot', snakeName]);
+    expect(snakes[0].url).toBe('bot:food');
+
+    await expect.poll(async () => {
+      const response = await authenticatedPage.request.get(`/api/games/${gameId}/details`);
+      return (await response.json()).status;
+    }, { timeout: 30000 }).toBe('finished');
+
+    // Testing again reuses the same bot
+    await authenticatedPage.getByRole('button', { name: 'Test this snake' }).click();
+    await expect(authenticatedPage).not.toHaveURL(new RegExp(gameId));
+    const bots = await query<{ count: string }>(
+      `SELECT COUNT(*) AS count FROM battlesnakes b
+       JOIN battlesnakes mine ON mine.user_id = b.user_id
+       WHERE mine.name = $1 AND b.name = 'Sandbox Bot'`,
+      [snakeName]
+    );
+    expect(Number(bots[0].count)).toBe(1);
+  });
+
+  test('a real snake named Sandbox Bot is left alone', async ({ authenticatedPage }) => {
+    const snakeName = `Sandbox Clash ${Date.now()}`;
+
+    for (const [name, url] of [
+      ['Sandbox Bot', 'https://example.com/my-bot'],
+      [snakeName, 'https://example.com/sandbox-clash'],
+    ]) {
+      await authenticatedPage.goto('/battlesnakes/new');
+      await authenticatedPage.getByLabel('Name').fill(name);
+      await authenticatedPage.getByLabel('URL').fill(url);
+      await authenticatedPage.getByLabel('Visibility').selectOption('public');
+      await authenticatedPage.getByRole('button', { name: 'Create Battlesnake' }).click();
+    }
+
+    const snakes = await query<{ battlesnake_id: string }>(
+      'SELECT battlesnake_id FROM battlesnakes WHERE name = $1',
+      [snakeName]
+    );
+
+    const response = await authenticatedPage.request.post(
+      `/battlesnakes/${snakes[0].battlesnake_id}/sandbox`,
+      { maxRedirects: 0 }
+    );
+    expect(response.status()).toBe(409);
+
+    const bots = await query<{ url: string }>(
+      `SELECT b.url FROM battlesnakes b
+       JOIN battlesnakes mine ON mine.user_id = b.user_id
+       WHERE mine.name = $1 AND b.name = 'Sandbox Bot'`,
+      [snakeName]
+    );
+    expect(bots.map((b) => b.url)).toEqual(['https://example.com/my-bot']);
+  });
+
+  test('users cannot test snakes they do not own', async ({ authenticatedPage, browser, loginAsUser }) => {
+    const snakeName = `Sandbox Other ${Date.now()}`;
+
+    await authenticatedPage.goto('/battlesnakes/new');
+    await authenticatedPage.getByLabel('Name').fill(snakeName);
+    await authenticatedPage.getByLabel('URL').fill('https://example.com/sandbox-other');
+    await authenticatedPage.getByLabel('Visibility').selectOption('public');
+    await authenticatedPage.getByRole('button', { name: 'Create Battlesnake' }).click();
+
+    const snakes = await query<{ battlesnake_id: string }>(
+      'SELECT battlesnake_id FROM battlesnakes WHERE name = $1',
+      [snakeName]
+    );
+
+    const context = await browser.newContext();
+    const otherPage = await context.newPage();
+    await otherPage.goto('/');
+    await loginAsUser(otherPage, createMockUser('sandbox_other'));
+
+    const response = await otherPage.request.post(`/battlesnakes/${snakes[0].battlesnake_id}/sandbox`);
+    expect(response.status()).toBe(403);
+
+    await context.close();
+  });
+});
@@ -111,4 +111,40 @@ test.describe('Battlesnake Validation', () => {
     await expect(authenticatedPage).toHaveURL('/battlesnakes');
     await expect(authenticatedPage.getByText(sharedName)).toBeVisible();
   });
+
+  test('accepts IPv6 literals, ports and encoded paths', async ({ authenticatedPage }) => {
+    for (const url of ['http://[::1]:8000', 'http://localhost:8443/api', 'https://example.com/my%20snake?token=a%2Fb']) {
+      const response = await authenticatedPage.request.post('/api/snakes', {
+        data: { name: `URL Shape ${Date.now()} ${url}`, url, is_public: false },
+      });
+      expect(response.status()).toBe(201);
+      expect((await response.json()).url).toBe(url);
+    }
+  });
+
+  test('rejects URL shapes the client cannot call', async ({ authenticatedPage }) => {
+    const cases: [string, string][] = [
+      ['http://example.com:0', 'URL port must be a number from 1 to 65535'],
+      ['http://[fe80::1%25eth0]:8000', 'Invalid IPv6 address'],
+      ['https://example.com/#/snake', "URL can't include a fragment"],
+    ];
+    for (const [url, message] of cases) {
+      const response = await authenticatedPage.request.post('/api/snakes', {
+        data: { name: `Bad URL ${Date.now()}`, url, is_public: false },
+      });
+      expect(response.status()).toBe(400);
+      expect(await response.text()).toContain(message);
+    }
+  });
+
+  test('registration form explains a rejected URL', async ({ authenticatedPage }) => {
+    await authenticatedPage.goto('/battlesnakes/new');
+    await authenticatedPage.getByLabel('Name').fill(`Fragment Snake ${Date.now()}`);
+    await authenticatedPage.getByLabel('URL').fill('https://example.com/#/snake');
+    await authenticatedPage.getByLabel('Visibility').selectOption('public');
+    await authenticatedPage.getByRole('button', { name: 'Create Battlesnake' }).click();
+
+    await expect(authenticatedPage).toHaveURL('/battlesnakes/new');
+    await expect(authenticatedPage.getByText("URL can't include a fragment")).toBeVisible();
+  });
 });
@@ -0,0 +1,20 @@
+import { test, expect } from '../fixtures/test';
+
+test.describe('Capabilities', () => {
+  test('lists supported options, limits and features without logging in', async ({ page }) => {
+    const response = await page.request.get('/api/capabilities');
+    expect(response.status()).toBe(200);
+
+    const capabilities = await response.json();
+    expect(capabilities.api_version).toBe(1);
+    expect(capabilities.game_types).toContain('standard');
+    expect(capabilities.board_sizes).toContain('11x11');
+    expect(capabilities.limits.max_snakes).toBe(4);
+    expect(capabilities.limits.default_move_timeout_ms).toBe(500);
+    expect(capabilities.features.tournaments).toBe(false);
+    expect(capabilities.features.custom_rulesets).toBe(true);
+    expect(capabilities.features.royale).toBe(true);
+    // The e2e server runs without ARENA_FEDERATION_KEY
+    expect(capabilities.features.federation).toBe(false);
+  });
+});
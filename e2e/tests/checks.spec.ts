@@ -0,0 +1,94 @@
+import { test, expect } from '../fixtures/test';
+import { MOCK_SNAKES_URL } from '../fixtures/snakes';
+
+test.describe('Regression checks', () => {
+  test('runs the games and decides the check', async ({ authenticatedPage }) => {
+    const snakeIds: string[] = [];
+    for (const behavior of ['up', 'down']) {
+      const response = await authenticatedPage.request.post('/api/snakes', {
+        data: { name: `Check ${behavior} ${Date.now()}`, url: `${MOCK_SNAKES_URL}/${behavior}`, is_public: true },
+      });
+      snakeIds.push((await response.json()).id);
+    }
+
+    const createResponse = await authenticatedPage.request.post('/api/checks', {
+      data: { snake: snakeIds[0], opponents: [snakeIds[1]], games: 2, required_win_rate: 50, board: '7x7' },
+    });
+    expect(createResponse.status()).toBe(201);
+    const check = await createResponse.json();
+    expect(check.snake_id).toBe(snakeIds[0]);
+    expect(check.game_ids).toHaveLength(2);
+    expect(check.wins_needed).toBe(1);
+
+    await expect.poll(async () => {
+      const response = await authenticatedPage.request.get(`/api/checks/${check.id}`);
+      return (await response.json()).status;
+    }, { timeout: 60000, intervals: [1000] }).not.toBe('running');
+
+    const result = await (await authenticatedPage.request.get(`/api/checks/${check.id}`)).json();
+    expect(['passed', 'failed']).toContain(result.status);
+    expect(result.status === 'passed').toBe(result.wins >= result.wins_needed);
+  });
+
+  test('rejects invalid checks', async ({ authenticatedPage }) => {
+    const response = await authenticatedPage.request.post('/api/snakes', {
+      data: { name: `Check ${Date.now()}`, url: `${MOCK_SNAKES_URL}/up`, is_public: true },
+    });
+    const snake = (await response.json()).id;
+
+    for (const data of [
+      { snake, opponents: [], required_win_rate: 50 },
+      { snake, opponents: [snake], required_win_rate: 50 },
+      { snake, opponents: ['00000000-0000-0000-0000-000000000000'], games: 0, required_win_rate: 50 },
+      { snake, opponents: ['00000000-0000-0000-0000-000000000000'], required_win_rate: 0 },
+    ]) {
+      const checkResponse = await authenticatedPage.request.post('/api/checks', { data });
+      expect(checkResponse.status()).toBe(400);
+    }
+
+    const missing = await authenticatedPage.request.get('/api/checks/00000000-0000-0000-0000-000000000000');
+    expect(missing.status()).toBe(404);
+  });
+});
+
+test.describe('Snake GitHub repositories', () => {
+  test('links and unlinks a repository', async ({ authenticatedPage }) => {
+    const response = await authenticatedPage.request.post('/api/snakes', {
+      data: { name: `GitHub ${Date.now()}`, url: `${MOCK_SNAKES_URL}/up`, is_public: true },
+    });
+    const snake = (await response.json()).id;
+
+    expect((await authenticatedPage.request.get(`/api/snakes/${snake}/github`)).status()).toBe(404);
+
+    const setResponse = await authenticatedPage.request.put(`/api/snakes/${snake}/github`, {
+      data: { repo: 'owner/my-snake', installation_id: 12345 },
+    });
+    expect(setResponse.status()).toBe(200);
+    const linked = await (await authenticatedPage.request.get(`/api/snakes/${snake}/github`)).json();
+    expect(linked.repo).toBe('owner/my-snake');
+    expect(linked.installation_id).toBe(12345);
+
+    const invalid = await authenticatedPage.request.put(`/api/snakes/${snake}/github`, {
+      data: { repo: 'not-a-repo', installation_id: 12345 },
+    });
+    expect(invalid.status()).toBe(400);
+
+    expect((await authenticatedPage.request.delete(`/api/snakes/${snake}/github`)).status()).toBe(204);
+    expect((await authenticatedPage.request.get(`/api/snakes/${snake}/github`)).status()).toBe(404);
+  });
+
+  test('rejects checks for short commit SHAs', async ({ authenticatedPage }) => {
+    const snakeIds: string[] = [];
+    for (const behavior of ['up', 'down']) {
+      const response = await authenticatedPage.request.post('/api/snakes', {
+        data: { name: `Sha ${behavior} ${Date.now()}`, url: `${MOCK_SNAKES_URL}/${behavior}`, is_public: true },
+      });
+      snakeIds.push((await response.json()).id);
+    }
+
+    const response = await authenticatedPage.request.post('/api/checks', {
+      data: { snake: snakeIds[0], opponents: [snakeIds[1]], required_win_rate: 50, commit_sha: 'abc1234' },
+    });
+    expect(response.status()).toBe(400);
+  });
+});
@@ -0,0 +1,27 @@
+import { test, expect } from '../fixtures/test';
+
+test.describe('Demo games', () => {
+  test('visitors can watch a demo game without logging in', async ({ page }) => {
+    await page.goto('/');
+    await page.getByRole('button', { name: 'Watch a demo game' }).click();
+
+    await expect(page).toHaveURL(/\/demo\/[0-9a-f-]{36}$/);
+    await expect(page.getByRole('heading', { name: 'Demo Game' })).toBeVisible();
+    await expect(page.locator('#board-viewer')).toHaveAttribute('src', /board\.battlesnake\.com/);
+    const firstDemo = page.url();
+
+    // A second click right after watches the same game
+    await page.getByRole('button', { name: 'Watch another demo game' }).click();
+    await expect(page).toHaveURL(firstDemo);
+
+    const gameId = firstDemo.split('/').pop();
+    const info = await page.request.get(`/api/v1/games/${gameId}`);
+    expect(info.status()).toBe(200);
+    expect((await info.json()).Game.Width).toBe(11);
+  });
+
+  test('only demo games are shown on the demo page', async ({ page }) => {
+    const response = await page.goto('/demo/00000000-0000-0000-0000-000000000000');
+    expect(response?.status()).toBe(404);
+  });
+});
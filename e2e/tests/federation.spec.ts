@@ -0,0 +1,17 @@
+import { test, expect } from '../fixtures/test';
+
+// The e2e server runs without ARENA_FEDERATION_KEY, so federation is off
+test.describe('Federation', () => {
+  test('endpoints are not found when federation is not configured', async ({ page }) => {
+    expect((await page.request.get('/api/federation')).status()).toBe(404);
+    expect((await page.request.get('/api/federation/export')).status()).toBe(404);
+
+    const response = await page.goto('/leaderboard/federated');
+    expect(response?.status()).toBe(404);
+  });
+
+  test('leaderboard does not link to the combined leaderboard', async ({ page }) => {
+    await page.goto('/leaderboard');
+    await expect(page.getByRole('link', { name: 'See the combined leaderboard' })).toHaveCount(0);
+  });
+});
@@ -0,0 +1,39 @@
+import { test, expect } from '../fixtures/test';
+import { query } from '../fixtures/db';
+
+test.describe('Frame schema versions', () => {
+  test('frames stored in an older format are served in the current one', async ({ authenticatedPage }) => {
+    const games = await query<{ game_id: string }>(
+      `INSERT INTO games (board_size, game_type, status)
+       VALUES ('7x7', 'Standard', 'finished') RETURNING game_id`
+    );
+    const gameId = games[0].game_id;
+    // A version 1 frame, from before frames had every viewer field
+    const legacy = {
+      Turn: 0,
+      Snakes: [
+        { ID: 'legacy-1', Name: 'Legacy', Body: [{ X: 1, Y: 1 }], Health: 100 },
+        { ID: 'legacy-2', Name: 'Fallen', Body: [{ X: 2, Y: 2 }], Health: 0, Death: { Cause: 'wall-collision', Turn: 0 } },
+      ],
+      Food: [],
+    };
+    await query(
+      'INSERT INTO turns (game_id, turn_number, frame_data, frame_schema_version) VALUES ($1, 0, $2, 1)',
+      [gameId, JSON.stringify(legacy)]
+    );
+
+    const details = await (await authenticatedPage.request.get(`/api/games/${gameId}/details`)).json();
+    const frame = details.frames[0];
+    expect(frame.Hazards).toEqual([]);
+    expect(frame.Snakes[0]).toMatchObject({ HeadType: 'default', TailType: 'default', APIVersion: '1', Death: null });
+    expect(frame.Snakes[0].Color).toMatch(/^#[0-9a-f]{6}$/);
+    expect(frame.Snakes[1]).toMatchObject({ EliminatedCause: 'wall-collision', Death: { EliminatedBy: '' } });
+
+    // Reading doesn't rewrite the stored frame; the backfill job does
+    const stored = await query<{ frame_schema_version: number }>(
+      'SELECT frame_schema_version FROM turns WHERE game_id = $1',
+      [gameId]
+    );
+    expect(stored[0].frame_schema_version).toBe(1);
+  });
+});
@@ -0,0 +1,55 @@
+import { test, expect } from '../fixtures/test';
+import { finishedGame, walkingSnakeFrames } from '../fixtures/games';
+
+test.describe('Replay accessibility', () => {
+  test('describes every turn in words', async ({ authenticatedPage }) => {
+    const gameId = await finishedGame({ frames: walkingSnakeFrames(3) });
+
+    const response = await authenticatedPage.request.get(`/api/games/${gameId}/descriptions`);
+    expect(response.status()).toBe(200);
+    const { turns } = await response.json();
+    expect(turns.map((t: { turn: number }) => t.turn)).toEqual([0, 1, 2]);
+    expect(turns[2].description).toContain('Alpha: head at (1, 3), length 2, health 98.');
+    expect(turns[2].description).toContain('Food at (5, 5).');
+  });
+
+  test('steps through the board in words from the keyboard', async ({ authenticatedPage }) => {
+    const gameId = await finishedGame({ frames: walkingSnakeFrames(3) });
+    await authenticatedPage.goto(`/games/${gameId}`);
+
+    await expect(authenticatedPage.locator('.board-viewer-container')).toHaveAttribute('aria-label', 'Game board');
+    const description = authenticatedPage.locator('#board-description');
+    await expect(description).toHaveAttribute('aria-live', 'polite');
+    await expect(description).toContainText('Turn 0: 1 of 1 snakes alive.');
+
+    await authenticatedPage.locator('body').press('ArrowRight');
+    await expect(description).toContainText('Turn 1:');
+    await authenticatedPage.locator('body').press('ArrowRight');
+    await expect(description).toContainText('Turn 2:');
+    await authenticatedPage.locator('body').press('ArrowLeft');
+    await expect(description).toContainText('Turn 1:');
+
+    await authenticatedPage.locator('body').press('9');
+    await expect(authenticatedPage.locator('#describe-speed')).toHaveText('Speed 9');
+
+    // Space plays to the end, then stops
+    await authenticatedPage.locator('body').press(' ');
+    await expect(description).toContainText('Turn 2:');
+    await expect(authenticatedPage.locator('#describe-play')).toHaveText('Play');
+  });
+
+  test('keyboard shortcuts leave form fields alone', async ({ authenticatedPage }) => {
+    const gameId = await finishedGame({ frames: walkingSnakeFrames(3) });
+    await authenticatedPage.goto(`/games/${gameId}`);
+
+    await authenticatedPage.locator('#branch-turn').fill('');
+    await authenticatedPage.locator('#branch-turn').press('2');
+    await expect(authenticatedPage.locator('#branch-turn')).toHaveValue('2');
+    await expect(authenticatedPage.locator('#describe-speed')).toHaveText('Speed 3');
+  });
+
+  test('returns 404 for non-existent game', async ({ authenticatedPage }) => {
+    const response = await authenticatedPage.request.get('/api/games/00000000-0000-0000-0000-000000000000/descriptions');
+    expect(response.status()).toBe(404);
+  });
+});
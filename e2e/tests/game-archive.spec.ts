@@ -0,0 +1,20 @@
+import { test, expect } from '../fixtures/test';
+
+// Archives live in GCS, which isn't configured for e2e runs, so only the
+// checks made before a download are covered here
+test.describe('Game Archive', () => {
+  test('needs auth', async ({ page }) => {
+    const response = await page.request.get('/api/archive/games/some-engine-game');
+    expect(response.status()).toBe(401);
+  });
+
+  test('reports archives as unavailable without a GCS bucket', async ({ authenticatedPage }) => {
+    const response = await authenticatedPage.request.get('/api/archive/games/some-engine-game');
+    expect(response.status()).toBe(503);
+  });
+
+  test('re-encoding is hidden when no admin token is configured', async ({ authenticatedPage }) => {
+    const response = await authenticatedPage.request.post('/api/v1/admin/archives/reencode');
+    expect(response.status()).toBe(404);
+  });
+});
@@ -0,0 +1,87 @@
+import { test, expect } from '../fixtures/test';
+import { query } from '../fixtures/db';
+import { createSnake } from '../fixtures/snakes';
+
+test.describe('Batch Game Creation', () => {
+  test('creates a game for each matchup', async ({ authenticatedPage }) => {
+    const first = await createSnake(authenticatedPage, 'Batch A');
+    const second = await createSnake(authenticatedPage, 'Batch B');
+
+    const response = await authenticatedPage.request.post('/api/games/batch', {
+      data: {
+        matchups: [[first.id, second.id], [second.id, first.id], [first.id, first.id]],
+        board: '7x7',
+        max_turns: 20,
+      },
+    });
+    expect(response.status()).toBe(201);
+    const { ids } = await response.json();
+    expect(ids).toHaveLength(3);
+
+    const games = await query<{ game_id: string; board_size: string; max_turns: number; ranked: boolean }>(
+      'SELECT game_id, board_size, max_turns, ranked FROM games WHERE game_id = ANY($1)',
+      [ids],
+    );
+    expect(games).toHaveLength(3);
+    for (const game of games) {
+      expect(game.board_size).toBe('7x7');
+      expect(game.max_turns).toBe(20);
+      expect(game.ranked).toBe(false);
+    }
+
+    // Snakes play in the order each matchup gives them
+    const details = await (await authenticatedPage.request.get(`/api/games/${ids[1]}/details`)).json();
+    expect(details.snakes.map((s: { id: string }) => s.id)).toEqual([second.id, first.id]);
+
+    // Every game was queued, so the runner plays them all
+    await expect.poll(async () => {
+      const rows = await query<{ count: string }>(
+        "SELECT COUNT(*) AS count FROM games WHERE game_id = ANY($1) AND status = 'finished'",
+        [ids],
+      );
+      return Number(rows[0].count);
+    }, { timeout: 30000, intervals: [500] }).toBe(3);
+  });
+
+  test('plays the same snakes a number of times', async ({ authenticatedPage }) => {
+    const first = await createSnake(authenticatedPage, 'Batch Count A');
+    const second = await createSnake(authenticatedPage, 'Batch Count B');
+
+    const response = await authenticatedPage.request.post('/api/games/batch', {
+      data: { snakes: [first.id, second.id], count: 5 },
+    });
+    expect(response.status()).toBe(201);
+    const { ids } = await response.json();
+    expect(new Set(ids).size).toBe(5);
+
+    const games = await (await authenticatedPage.request.get(`/api/games?snake_id=${first.id}`)).json();
+    expect(games.map((g: { id: string }) => g.id).sort()).toEqual([...ids].sort());
+  });
+
+  test('creates nothing when any matchup is rejected', async ({ authenticatedPage }) => {
+    const mine = await createSnake(authenticatedPage, 'Batch Mine');
+    const missing = '00000000-0000-4000-8000-000000000000';
+
+    const response = await authenticatedPage.request.post('/api/games/batch', {
+      data: { matchups: [[mine.id, mine.id], [mine.id, missing]] },
+    });
+    expect(response.status()).toBe(400);
+    expect(await response.text()).toContain(`Snake ${missing} not found or not accessible`);
+
+    const games = await query<{ count: string }>(
+      'SELECT COUNT(*) AS count FROM game_battlesnakes WHERE battlesnake_id = $1',
+      [mine.id],
+    );
+    expect(Number(games[0].count)).toBe(0);
+  });
+
+  test('rejects batches over the limit', async ({ authenticatedPage }) => {
+    const snake = await createSnake(authenticatedPage, 'Batch Limit');
+
+    const response = await authenticatedPage.request.post('/api/games/batch', {
+      data: { snakes: [snake.id], count: 101 },
+    });
+    expect(response.status()).toBe(400);
+    expect(await response.text()).toContain('between 1 and 100 games');
+  });
+});
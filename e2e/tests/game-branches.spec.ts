@@ -0,0 +1,104 @@
+import { Page } from '@playwright/test';
+import { test, expect } from '../fixtures/test';
+import { createSnake, mockSnakeUrl } from '../fixtures/snakes';
+import { finishedGame } from '../fixtures/games';
+import { query } from '../fixtures/db';
+
+/**
+ * Play a 7x7 game between a snake that always moves up and one that always moves down
+ */
+async function playedGame(page: Page): Promise<string> {
+  const snakeIds: string[] = [];
+  for (const behavior of ['up', 'down']) {
+    snakeIds.push((await createSnake(page, `Branch ${behavior}`, mockSnakeUrl(behavior), true)).id);
+  }
+
+  const createResponse = await page.request.post('/api/games', {
+    data: { snakes: snakeIds, board: '7x7' },
+  });
+  const { id: gameId } = await createResponse.json();
+
+  await expect.poll(async () => {
+    const response = await page.request.get(`/api/games/${gameId}/details`);
+    return (await response.json()).status;
+  }, { timeout: 30000, intervals: [500] }).toBe('finished');
+
+  return gameId;
+}
+
+test.describe('What-if branches', () => {
+  test('branching replays the game with a changed move', async ({ authenticatedPage }) => {
+    const gameId = await playedGame(authenticatedPage);
+    const parent = await (await authenticatedPage.request.get(`/api/games/${gameId}/details`)).json();
+    const snake = parent.frames[0].Snakes[0];
+
+    const response = await authenticatedPage.request.post(`/api/games/${gameId}/branches`, {
+      data: { turn: 0, moves: [{ snake_id: snake.ID, turn: 0, direction: 'left' }] },
+    });
+    expect(response.status()).toBe(201);
+    const branch = await response.json();
+    expect(branch.parent_id).toBe(gameId);
+    expect(branch.branched_from_turn).toBe(0);
+    expect(branch.status).toBe('finished');
+
+    const details = await (await authenticatedPage.request.get(`/api/games/${branch.id}/details`)).json();
+    expect(details.branch).toEqual({ parent_id: gameId, branched_from_turn: 0 });
+    expect(details.certification.status).toBe('verified');
+
+    // Same starting board, then the changed move
+    const branchSnake = (turn: number) =>
+      details.frames[turn].Snakes.find((s: { Name: string }) => s.Name === snake.Name);
+    expect(branchSnake(0).Body).toEqual(snake.Body);
+    expect(branchSnake(1).Body[0]).toEqual({ X: snake.Body[0].X - 1, Y: snake.Body[0].Y });
+
+    const list = await (await authenticatedPage.request.get(`/api/games/${gameId}/branches`)).json();
+    expect(list.map((b: { id: string }) => b.id)).toEqual([branch.id]);
+  });
+
+  test('rejects turns the game never reached', async ({ authenticatedPage }) => {
+    const gameId = await playedGame(authenticatedPage);
+
+    const response = await authenticatedPage.request.post(`/api/games/${gameId}/branches`, {
+      data: { turn: 9999 },
+    });
+    expect(response.status()).toBe(400);
+  });
+
+  test('limits how many branches a user can create', async ({ authenticatedPage, mockUser }) => {
+    const gameId = await playedGame(authenticatedPage);
+    const capabilities = await (await authenticatedPage.request.get('/api/capabilities')).json();
+
+    // Branches the user already created this hour
+    for (let i = 0; i < capabilities.limits.branches_per_user_per_hour; i++) {
+      await query(
+        `UPDATE games
+         SET parent_game_id = $1, branched_from_turn = 0,
+             branched_by_user_id = (SELECT user_id FROM users WHERE github_login = $2)
+         WHERE game_id = $3`,
+        [gameId, mockUser.login, await finishedGame()]
+      );
+    }
+
+    const response = await authenticatedPage.request.post(`/api/games/${gameId}/branches`, {
+      data: { turn: 0 },
+    });
+    expect(response.status()).toBe(429);
+  });
+
+  test('game page branches a game and links back to the parent', async ({ authenticatedPage }) => {
+    const gameId = await playedGame(authenticatedPage);
+
+    await authenticatedPage.goto(`/games/${gameId}`);
+    const whatIf = authenticatedPage.locator('#branches');
+    await whatIf.getByLabel('Turn').fill('1');
+    await whatIf.getByLabel('Move').selectOption('right');
+    await whatIf.getByRole('button', { name: 'Branch' }).click();
+
+    await expect(authenticatedPage).not.toHaveURL(new RegExp(`/games/${gameId}$`));
+    await expect(authenticatedPage.locator('#branch-origin')).toContainText(`game ${gameId}`);
+    await expect(authenticatedPage.locator('#branch-origin')).toContainText('turn 1');
+
+    await authenticatedPage.locator('#branch-origin a').click();
+    await expect(authenticatedPage.locator('#branches')).toContainText('From turn 1');
+  });
+});
@@ -153,7 +153,7 @@ test.describe('Create Game', () => {
     await authenticatedPage.getByRole('button', { name: 'Create Battlesnake' }).click();
 
     // Test each game type
-    const gameTypes = ['Standard', 'Royale', 'Constrictor', 'Snail Mode'];
+    const gameTypes = ['Standard', 'Royale', 'Constrictor', 'Snail Mode', 'Wrapped'];
 
     for (const gameType of gameTypes) {
       await authenticatedPage.goto('/games/new');
@@ -0,0 +1,67 @@
+import { Page } from '@playwright/test';
+import { test, expect } from '../fixtures/test';
+import { MOCK_SNAKES_URL } from '../fixtures/snakes';
+
+type Frame = { Turn: number; Snakes: { Name: string; Color: string; Death: { Turn: number } | null }[] };
+
+/**
+ * Play a 7x7 game where one snake runs into the top wall on turn 2 and the
+ * other two last until turn 5, and return its frames and the early loser's name
+ */
+async function earlyDeathFrames(page: Page, deadSnakes: string): Promise<{ frames: Frame[]; loser: string }> {
+  const ids: string[] = [];
+  const names: string[] = [];
+  for (const behavior of ['up', 'down', 'down']) {
+    const name = `Dead ${deadSnakes} ${behavior} ${ids.length} ${Date.now()}`;
+    const response = await page.request.post('/api/snakes', {
+      data: { name, url: `${MOCK_SNAKES_URL}/${behavior}`, is_public: false },
+    });
+    ids.push((await response.json()).id);
+    names.push(name);
+  }
+
+  const createResponse = await page.request.post('/api/games', {
+    data: {
+      snakes: ids,
+      board: '7x7',
+      spawn: 'manual',
+      spawn_positions: [{ x: 1, y: 5 }, { x: 3, y: 5 }, { x: 5, y: 4 }],
+      dead_snakes: deadSnakes,
+    },
+  });
+  expect(createResponse.status()).toBe(201);
+  const { id } = await createResponse.json();
+
+  await expect.poll(async () => {
+    const response = await page.request.get(`/api/games/${id}/details`);
+    return (await response.json()).status;
+  }, { timeout: 30000, intervals: [500] }).toBe('finished');
+
+  const details = await (await page.request.get(`/api/games/${id}/details`)).json();
+  return { frames: details.frames, loser: names[0] };
+}
+
+test.describe('Dead snakes in frames', () => {
+  test('dropped snakes are shown on the turn they die and then left out', async ({ authenticatedPage }) => {
+    const { frames, loser } = await earlyDeathFrames(authenticatedPage, 'drop');
+    const find = (frame: Frame) => frame.Snakes.find((s) => s.Name === loser);
+
+    const deathFrame = frames.find((frame) => find(frame)?.Death);
+    expect(deathFrame).toBeTruthy();
+    const later = frames.filter((frame) => frame.Turn > deathFrame!.Turn);
+    expect(later.length).toBeGreaterThan(0);
+    for (const frame of later) {
+      expect(find(frame)).toBeUndefined();
+    }
+  });
+
+  test('ghosted snakes stay in later frames in a faded color', async ({ authenticatedPage }) => {
+    const { frames, loser } = await earlyDeathFrames(authenticatedPage, 'ghost');
+    const find = (frame: Frame) => frame.Snakes.find((s) => s.Name === loser)!;
+
+    const last = frames[frames.length - 1];
+    expect(find(last).Death).toBeTruthy();
+    expect(find(last).Death!.Turn).toBeLessThan(last.Turn);
+    expect(find(last).Color).not.toBe(find(frames[0]).Color);
+  });
+});
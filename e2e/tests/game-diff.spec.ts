@@ -0,0 +1,54 @@
+import { test, expect } from '../fixtures/test';
+import { addFrames, finishedGame } from '../fixtures/games';
+
+type Coord = [number, number];
+
+/**
+ * Record a finished game directly, with one snake whose head is at each of `heads` in turn.
+ */
+async function walkedGame(heads: Coord[], seed: number): Promise<string> {
+  const gameId = await finishedGame({ seed });
+  await addFrames(gameId, heads.map(([x, y], turn) => ({
+    Turn: turn,
+    Snakes: [{ ID: `snake-${gameId}`, Name: 'Differ', Body: [{ X: x, Y: y }], Health: 100 - turn }],
+    Food: [{ X: 3, Y: 3 }],
+    Hazards: [],
+  })));
+  return gameId;
+}
+
+test.describe('Game Diffs', () => {
+  test('reports the first turn two games diverge', async ({ authenticatedPage }) => {
+    const a = await walkedGame([[1, 1], [1, 2], [1, 3], [1, 4]], 7);
+    const b = await walkedGame([[1, 1], [1, 2], [2, 2], [3, 2]], 7);
+
+    const response = await authenticatedPage.request.get(`/api/games/diff?game_a=${a}&game_b=${b}`);
+    expect(response.status()).toBe(200);
+    const diff = await response.json();
+
+    expect(diff.setup_differences).toEqual([]);
+    expect(diff.turns_a).toBe(3);
+    expect(diff.turns_b).toBe(3);
+    expect(diff.first_divergence).toEqual({ turn: 2, differences: ['snake 1: head 1,3 vs 2,2'] });
+    expect(diff.differing_turns).toBe(2);
+    expect(diff.snakes).toHaveLength(1);
+    expect(diff.snakes[0].diverged_at).toBe(2);
+  });
+
+  test('identical games have no divergence', async ({ authenticatedPage }) => {
+    const a = await walkedGame([[1, 1], [1, 2]], 8);
+    const b = await walkedGame([[1, 1], [1, 2]], 9);
+
+    const diff = await (await authenticatedPage.request.get(`/api/games/diff?game_a=${a}&game_b=${b}`)).json();
+    expect(diff.first_divergence).toBeNull();
+    expect(diff.setup_differences).toEqual(['seed: 8 vs 9']);
+  });
+
+  test('returns 404 when either game is missing', async ({ authenticatedPage }) => {
+    const a = await walkedGame([[1, 1]], 10);
+    const missing = '00000000-0000-0000-0000-000000000000';
+
+    const response = await authenticatedPage.request.get(`/api/games/diff?game_a=${a}&game_b=${missing}`);
+    expect(response.status()).toBe(404);
+  });
+});
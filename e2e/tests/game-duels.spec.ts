@@ -0,0 +1,51 @@
+import { test, expect } from '../fixtures/test';
+import { createSnake } from '../fixtures/snakes';
+
+test.describe('Duels', () => {
+  test('a duel defaults to an 11x11 board and is reported as a duel', async ({ authenticatedPage }) => {
+    const a = (await createSnake(authenticatedPage, 'Duelist A', 'bot:random')).id;
+    const b = (await createSnake(authenticatedPage, 'Duelist B', 'bot:random')).id;
+
+    const response = await authenticatedPage.request.post('/api/v1/games', {
+      data: { snakes: [a, b], duel: true },
+    });
+    expect(response.status()).toBe(201);
+    const { id } = await response.json();
+
+    const details = await (await authenticatedPage.request.get(`/api/v1/games/${id}/details`)).json();
+    expect(details.duel).toBe(true);
+    expect(details.board).toBe('11x11');
+
+    await authenticatedPage.goto(`/games/${id}`);
+    await expect(authenticatedPage.locator('#duel-game')).toContainText('Duel: 1v1');
+  });
+
+  test('duels need two different snakes on a small enough board', async ({ authenticatedPage }) => {
+    const a = (await createSnake(authenticatedPage, 'Lonely Duelist', 'bot:random')).id;
+    const b = (await createSnake(authenticatedPage, 'Other Duelist', 'bot:random')).id;
+    const c = (await createSnake(authenticatedPage, 'Third Duelist', 'bot:random')).id;
+
+    for (const data of [
+      { snakes: [a, b, c], duel: true },
+      { snakes: [a, a], duel: true },
+      { snakes: [a, b], duel: true, board: '19x19' },
+    ]) {
+      const response = await authenticatedPage.request.post('/api/v1/games', { data });
+      expect(response.status()).toBe(400);
+    }
+  });
+
+  test('the duel leaderboard is arena-wide', async ({ authenticatedPage }) => {
+    const response = await authenticatedPage.request.get('/api/v1/leaderboard?duels=true');
+    expect(response.status()).toBe(200);
+    expect((await response.json()).duels).toBe(true);
+
+    await authenticatedPage.goto('/leaderboard?duels=true');
+    await expect(authenticatedPage.getByRole('heading', { name: 'Duel Leaderboard' })).toBeVisible();
+
+    const withLeague = await authenticatedPage.request.get(
+      '/api/v1/leaderboard?duels=true&league=00000000-0000-0000-0000-000000000000',
+    );
+    expect(withLeague.status()).toBe(400);
+  });
+});
@@ -0,0 +1,99 @@
+import { test, expect } from '../fixtures/test';
+import { query } from '../fixtures/db';
+
+/**
+ * Record a game directly with a snake moving one square right each turn.
+ */
+async function gameWithMoves(status: string, turns: number): Promise<string> {
+  const games = await query<{ game_id: string }>(
+    `INSERT INTO games (board_size, game_type, status, visibility)
+     VALUES ('7x7', 'Standard', $1, 'public') RETURNING game_id`,
+    [status]
+  );
+  const gameId = games[0].game_id;
+  for (let turn = 0; turn < turns; turn++) {
+    const frame = {
+      Turn: turn,
+      Snakes: [{ ID: 'a', Body: [{ X: turn + 1, Y: 0 }, { X: turn, Y: 0 }], Health: 100 - turn }],
+      Food: turn === 0 ? [{ X: 5, Y: 5 }] : [],
+      Hazards: [],
+    };
+    await query(
+      'INSERT INTO turns (game_id, turn_number, frame_data) VALUES ($1, $2, $3)',
+      [gameId, turn, JSON.stringify(frame)]
+    );
+  }
+  return gameId;
+}
+
+test.describe('Game events as patches', () => {
+  test('clients offering arena.patches get a keyframe and then patches', async ({ page }) => {
+    const gameId = await gameWithMoves('finished', 3);
+    await page.goto('/');
+
+    const { protocol, messages } = await page.evaluate(async (gameId) => {
+      const url = `${location.origin.replace(/^http/, 'ws')}/api/games/${gameId}/events`;
+      return new Promise<{ protocol: string; messages: { Type: string; Data: unknown }[] }>((resolve) => {
+        const messages: { Type: string; Data: unknown }[] = [];
+        const socket = new WebSocket(url, ['arena.patches']);
+        socket.onmessage = (message) => messages.push(JSON.parse(message.data));
+        socket.onclose = () => resolve({ protocol: socket.protocol, messages });
+      });
+    }, gameId);
+
+    expect(protocol).toBe('arena.patches');
+    expect(messages.map((m) => m.Type)).toEqual(['keyframe', 'patch', 'patch', 'game_end']);
+    expect(messages[1].Data).toEqual({
+      Turn: 1,
+      FoodEaten: [{ X: 5, Y: 5 }],
+      Snakes: [{ ID: 'a', Head: [{ X: 2, Y: 0 }], Tail: 1, Health: 99 }],
+    });
+  });
+
+  test('clients that ask get the frame they are up to as a keyframe', async ({ page }) => {
+    const gameId = await gameWithMoves('running', 3);
+    await page.goto('/');
+
+    const keyframe = await page.evaluate(async (gameId) => {
+      const url = `${location.origin.replace(/^http/, 'ws')}/api/games/${gameId}/events`;
+      return new Promise<unknown>((resolve) => {
+        const socket = new WebSocket(url, ['arena.patches']);
+        let caughtUp = false;
+        socket.onmessage = (message) => {
+          const event = JSON.parse(message.data);
+          if (event.Type === 'spectators' && !caughtUp) {
+            caughtUp = true;
+            socket.send(JSON.stringify({ Type: 'keyframe' }));
+          } else if (event.Type === 'keyframe' && caughtUp) {
+            socket.close();
+            resolve(event.Data);
+          }
+        };
+      });
+    }, gameId);
+
+    expect(keyframe).toEqual({
+      Turn: 2,
+      Snakes: [{ ID: 'a', Body: [{ X: 3, Y: 0 }, { X: 2, Y: 0 }], Health: 98 }],
+      Food: [],
+      Hazards: [],
+    });
+  });
+
+  test('other clients still get whole frames', async ({ page }) => {
+    const gameId = await gameWithMoves('finished', 2);
+    await page.goto('/');
+
+    const types = await page.evaluate(async (gameId) => {
+      const url = `${location.origin.replace(/^http/, 'ws')}/api/games/${gameId}/events`;
+      return new Promise<string[]>((resolve) => {
+        const types: string[] = [];
+        const socket = new WebSocket(url);
+        socket.onmessage = (message) => types.push(JSON.parse(message.data).Type);
+        socket.onclose = () => resolve(types);
+      });
+    }, gameId);
+
+    expect(types).toEqual(['frame', 'frame', 'game_end']);
+  });
+});
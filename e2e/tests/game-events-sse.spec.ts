@@ -0,0 +1,72 @@
+import { test, expect } from '../fixtures/test';
+import { query } from '../fixtures/db';
+
+/**
+ * Record a game directly with one frame per turn.
+ */
+async function gameWithFrames(status: string, visibility: string, turns: number): Promise<string> {
+  const games = await query<{ game_id: string }>(
+    `INSERT INTO games (board_size, game_type, status, visibility)
+     VALUES ('7x7', 'Standard', $1, $2) RETURNING game_id`,
+    [status, visibility]
+  );
+  const gameId = games[0].game_id;
+  for (let turn = 0; turn < turns; turn++) {
+    const frame = { Turn: turn, Snakes: [], Food: [], Hazards: [] };
+    await query(
+      'INSERT INTO turns (game_id, turn_number, frame_data) VALUES ($1, $2, $3)',
+      [gameId, turn, JSON.stringify(frame)]
+    );
+  }
+  return gameId;
+}
+
+/**
+ * Parse a server-sent event stream into its named events and JSON data.
+ */
+function parseEvents(body: string): { event: string; data: { Type: string; Data: { Turn?: number } } }[] {
+  return body
+    .split('\n\n')
+    .map((block) => block.split('\n'))
+    .map((lines) => ({
+      event: lines.find((l) => l.startsWith('event:'))?.slice('event:'.length).trim() ?? '',
+      data: lines.find((l) => l.startsWith('data:'))?.slice('data:'.length).trim() ?? '',
+    }))
+    .filter((e) => e.event)
+    .map((e) => ({ event: e.event, data: JSON.parse(e.data) }));
+}
+
+test.describe('Game events over SSE', () => {
+  test('streams a finished game\'s frames and then game_end', async ({ page }) => {
+    const gameId = await gameWithFrames('finished', 'public', 3);
+
+    const response = await page.request.get(`/api/games/${gameId}/events/sse`);
+    expect(response.status()).toBe(200);
+    expect(response.headers()['content-type']).toContain('text/event-stream');
+
+    const events = parseEvents(await response.text());
+    expect(events.map((e) => e.event)).toEqual(['frame', 'frame', 'frame', 'game_end']);
+    expect(events.map((e) => e.data.Type)).toEqual(['frame', 'frame', 'frame', 'game_end']);
+    expect(events[2].data.Data.Turn).toBe(2);
+  });
+
+  test('fast-forward starts from the latest keyframe and sends deltas', async ({ page }) => {
+    const gameId = await gameWithFrames('finished', 'public', 53);
+
+    const response = await page.request.get(`/api/games/${gameId}/events/sse?fast_forward=true`);
+    expect(response.status()).toBe(200);
+
+    const events = parseEvents(await response.text());
+    expect(events.map((e) => e.event)).toEqual(['keyframe', 'delta', 'delta', 'game_end']);
+    expect(events[0].data.Data).toEqual({ Turn: 50, Snakes: [], Food: [], Hazards: [] });
+    // Nothing but the turn changed
+    expect(events[2].data.Data).toEqual({ Turn: 52 });
+  });
+
+  test('private games need auth', async ({ page }) => {
+    const gameId = await gameWithFrames('finished', 'private', 1);
+
+    const response = await page.request.get(`/api/games/${gameId}/events/sse`);
+    expect(response.status()).toBe(401);
+  });
+});
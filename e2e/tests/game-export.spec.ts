@@ -0,0 +1,58 @@
+import { test, expect } from '../fixtures/test';
+import { query } from '../fixtures/db';
+import { createSnake, mockSnakeUrl } from '../fixtures/snakes';
+
+test.describe('Game Export', () => {
+  test('exports a finished game in the engine format', async ({ authenticatedPage }) => {
+    const up = await createSnake(authenticatedPage, 'Export Up', mockSnakeUrl('up'));
+    const down = await createSnake(authenticatedPage, 'Export Down', mockSnakeUrl('down'));
+
+    const { id } = await (await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [up.id, down.id], board: '7x7', max_turns: 100 },
+    })).json();
+
+    await expect.poll(async () => {
+      const response = await authenticatedPage.request.get(`/api/games/${id}/details`);
+      return (await response.json()).status;
+    }, { timeout: 30000, intervals: [500] }).toBe('finished');
+
+    const response = await authenticatedPage.request.get(`/api/games/${id}/export`);
+    expect(response.status()).toBe(200);
+    expect(response.headers()['content-disposition']).toContain(`game-${id}.json`);
+
+    const exported = await response.json();
+    expect(exported.game.ID).toBe(id);
+    expect(exported.game.Status).toBe('complete');
+    expect(exported.game.Width).toBe(7);
+    expect(exported.game.Height).toBe(7);
+    expect(exported.game.MaxTurns).toBe(100);
+    expect(exported.game.Ruleset.name).toBe('standard');
+    expect(exported.exported_at).toBeTruthy();
+
+    const turns = exported.frames.map((frame: { Turn: number }) => frame.Turn);
+    expect(turns[0]).toBe(0);
+    expect(turns).toEqual([...turns].sort((a, b) => a - b));
+    const names = exported.frames[0].Snakes.map((snake: { Name: string }) => snake.Name);
+    expect(names).toContain(up.name);
+    expect(names).toContain(down.name);
+
+    await authenticatedPage.goto(`/games/${id}`);
+    await expect(authenticatedPage.locator('#export-link')).toBeVisible();
+  });
+
+  test('refuses games that have not finished', async ({ authenticatedPage }) => {
+    const games = await query<{ game_id: string }>(
+      `INSERT INTO games (board_size, game_type, status)
+       VALUES ('11x11', 'Standard', 'waiting') RETURNING game_id`
+    );
+
+    const response = await authenticatedPage.request.get(`/api/games/${games[0].game_id}/export`);
+    expect(response.status()).toBe(409);
+    expect(await response.text()).toContain('Only finished games');
+  });
+
+  test('returns 404 for non-existent game', async ({ authenticatedPage }) => {
+    const response = await authenticatedPage.request.get('/api/games/00000000-0000-0000-0000-000000000000/export');
+    expect(response.status()).toBe(404);
+  });
+});
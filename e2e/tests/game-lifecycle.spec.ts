@@ -0,0 +1,75 @@
+import { test, expect } from '../fixtures/test';
+import { MOCK_SNAKES_URL } from '../fixtures/snakes';
+
+test.describe('Game Lifecycle', () => {
+  test('login → create snakes → create game → game finishes → frames retrievable', async ({ authenticatedPage, mockUser }) => {
+    // Logged in via mock OAuth
+    await authenticatedPage.goto('/');
+    await expect(authenticatedPage.getByText(`Welcome, ${mockUser.login}!`)).toBeVisible();
+
+    // Create two snakes backed by the mock snakes server.
+    // One always moves up and one always moves down, so both hit a wall within a few turns.
+    const snakeIds: string[] = [];
+    for (const behavior of ['up', 'down']) {
+      const response = await authenticatedPage.request.post('/api/snakes', {
+        data: {
+          name: `Lifecycle ${behavior} ${Date.now()}`,
+          url: `${MOCK_SNAKES_URL}/${behavior}`,
+          is_public: false,
+        },
+      });
+      expect(response.status()).toBe(201);
+      const snake = await response.json();
+      snakeIds.push(snake.id);
+    }
+
+    // Create the game
+    const createResponse = await authenticatedPage.request.post('/api/games', {
+      data: {
+        snakes: snakeIds,
+        board: '7x7',
+        game_type: 'standard',
+      },
+    });
+    expect(createResponse.status()).toBe(201);
+    const { id: gameId } = await createResponse.json();
+
+    // Wait for the job worker to pick up and finish the game
+    await expect
+      .poll(
+        async () => {
+          const response = await authenticatedPage.request.get(`/api/games/${gameId}/details`);
+          const game = await response.json();
+          return game.status;
+        },
+        { timeout: 30000, intervals: [500] }
+      )
+      .toBe('finished');
+
+    // Frames are retrievable and start at turn 0
+    const detailsResponse = await authenticatedPage.request.get(`/api/games/${gameId}/details`);
+    expect(detailsResponse.status()).toBe(200);
+    const details = await detailsResponse.json();
+
+    expect(details.snakes).toHaveLength(2);
+    expect(details.frames.length).toBeGreaterThan(1);
+    expect(details.frames[0].Turn).toBe(0);
+    expect(details.frames[0].Snakes).toHaveLength(2);
+    expect(details.turn_count).toBe(details.frames.length);
+
+    // Frames can be paged through a turn at a time
+    const pageResponse = await authenticatedPage.request.get(`/api/games/${gameId}/details?from_turn=1&limit=1`);
+    const page = await pageResponse.json();
+    expect(page.frames).toEqual([details.frames[1]]);
+    expect(page.turn_count).toBe(details.turn_count);
+    expect(page.next_from_turn).toBe(details.frames.length > 2 ? 2 : null);
+    expect(page.certification).toBeNull();
+
+    // Every snake was told about the game starting and ending
+    const countsResponse = await authenticatedPage.request.get(`${MOCK_SNAKES_URL}/_admin/requests`);
+    const counts = await countsResponse.json();
+    expect(counts['up/start']).toBeGreaterThan(0);
+    expect(counts['up/move']).toBeGreaterThan(0);
+    expect(counts['down/end']).toBeGreaterThan(0);
+  });
+});
@@ -0,0 +1,84 @@
+import { test, expect } from '../fixtures/test';
+import { query } from '../fixtures/db';
+import { createSnake, mockSnakeUrl } from '../fixtures/snakes';
+
+test.describe('Turn Limits and Move Timeouts', () => {
+  test('games are played with their own turn limit and move timeout', async ({ authenticatedPage }) => {
+    // The slow snake takes a second per move, inside this game's timeout
+    const slow = await createSnake(authenticatedPage, 'Limits Slow', mockSnakeUrl('slow'));
+    const other = await createSnake(authenticatedPage, 'Limits Other', mockSnakeUrl('down'));
+
+    const createResponse = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [slow.id, other.id], max_turns: 2, move_timeout_ms: 2000 },
+    });
+    expect(createResponse.status()).toBe(201);
+    const { id } = await createResponse.json();
+
+    await expect.poll(async () => {
+      const response = await authenticatedPage.request.get(`/api/games/${id}/details`);
+      return (await response.json()).status;
+    }, { timeout: 30000, intervals: [500] }).toBe('finished');
+
+    const details = await (await authenticatedPage.request.get(`/api/games/${id}/details`)).json();
+    expect(details.max_turns).toBe(2);
+    expect(details.move_timeout_ms).toBe(2000);
+    expect(details.frames.length).toBeLessThanOrEqual(3);
+
+    const debug = await (await authenticatedPage.request.get(`/api/games/${id}/debug`)).json();
+    expect(debug.moves.length).toBeGreaterThan(0);
+    expect(debug.moves.every((move: { timed_out: boolean }) => !move.timed_out)).toBe(true);
+
+    await authenticatedPage.goto(`/games/${id}`);
+    await expect(authenticatedPage.locator('#turn-limit')).toContainText('Turn Limit: 2');
+    await expect(authenticatedPage.locator('#move-timeout')).toContainText('Move Timeout: 2000ms');
+  });
+
+  test('games without limits use the defaults', async ({ authenticatedPage }) => {
+    const first = await createSnake(authenticatedPage, 'Limits Default A');
+    const second = await createSnake(authenticatedPage, 'Limits Default B');
+
+    const { id } = await (await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [first.id, second.id] },
+    })).json();
+
+    const details = await (await authenticatedPage.request.get(`/api/games/${id}/details`)).json();
+    expect(details.max_turns).toBe(5000);
+    expect(details.move_timeout_ms).toBe(500);
+  });
+
+  test('rejects limits outside the allowed ranges', async ({ authenticatedPage }) => {
+    const snake = await createSnake(authenticatedPage, 'Limits Rejected');
+
+    const tooManyTurns = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [snake.id], max_turns: 5001 },
+    });
+    expect(tooManyTurns.status()).toBe(400);
+    expect(await tooManyTurns.text()).toContain('max_turns must be between 1 and 5000');
+
+    const tooShort = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [snake.id], move_timeout_ms: 10 },
+    });
+    expect(tooShort.status()).toBe(400);
+    expect(await tooShort.text()).toContain('move_timeout_ms must be between 50 and 5000');
+  });
+
+  test('the creation page sets the turn limit and move timeout', async ({ authenticatedPage }) => {
+    const snake = await createSnake(authenticatedPage, 'Limits Flow');
+
+    await authenticatedPage.goto('/games/new');
+    await authenticatedPage.locator('.card', { hasText: snake.name })
+      .getByRole('button', { name: 'Add to Game' }).click();
+    await authenticatedPage.getByLabel('Turn Limit').fill('50');
+    await authenticatedPage.getByLabel('Move Timeout (ms)').fill('750');
+    await authenticatedPage.getByRole('button', { name: 'Create Game' }).click();
+
+    await expect(authenticatedPage).toHaveURL(/\/games\/[0-9a-f-]+$/);
+    const gameId = authenticatedPage.url().split('/').pop();
+    const games = await query<{ max_turns: number; move_timeout_ms: number }>(
+      'SELECT max_turns, move_timeout_ms FROM games WHERE game_id = $1',
+      [gameId],
+    );
+    expect(games[0].max_turns).toBe(50);
+    expect(games[0].move_timeout_ms).toBe(750);
+  });
+});
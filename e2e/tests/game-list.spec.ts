@@ -1,4 +1,5 @@
-import { test, expect } from '../fixtures/test';
+import { test, expect, createMockUser } from '../fixtures/test';
+import { query } from '../fixtures/db';
 
 test.describe('Game List', () => {
   test('shows games list page', async ({ authenticatedPage }) => {
@@ -152,6 +153,41 @@ test.describe('Game List', () => {
     await expect(authenticatedPage.getByText('Game Type: Constrictor')).toBeVisible();
   });
 
+  test('private games are only listed for their snake owners', async ({ authenticatedPage, browser, loginAsUser }) => {
+    const snakeName = `Private List Snake ${Date.now()}`;
+    await authenticatedPage.goto('/battlesnakes/new');
+    await authenticatedPage.getByLabel('Name').fill(snakeName);
+    await authenticatedPage.getByLabel('URL').fill('https://example.com/private-list');
+    await authenticatedPage.getByLabel('Visibility').selectOption('public');
+    await authenticatedPage.getByRole('button', { name: 'Create Battlesnake' }).click();
+
+    const snakes = await query<{ battlesnake_id: string }>(
+      'SELECT battlesnake_id FROM battlesnakes WHERE name = $1',
+      [snakeName]
+    );
+    const response = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [snakes[0].battlesnake_id], visibility: 'private' },
+    });
+    expect(response.status()).toBe(201);
+    const gameId: string = (await response.json()).id;
+
+    // The snake's owner sees the game
+    await authenticatedPage.goto('/games');
+    await expect(authenticatedPage.getByRole('cell', { name: gameId })).toBeVisible();
+
+    // Another user doesn't
+    const context = await browser.newContext();
+    const otherPage = await context.newPage();
+    await otherPage.goto('/');
+    await loginAsUser(otherPage, createMockUser('list_other'));
+
+    await otherPage.goto('/games');
+    await expect(otherPage.getByRole('heading', { name: 'All Games' })).toBeVisible();
+    await expect(otherPage.getByRole('cell', { name: gameId })).toHaveCount(0);
+
+    await context.close();
+  });
+
   test('game list requires authentication', async ({ page }) => {
     const response = await page.goto('/games');
     expect(response?.status()).toBe(401);
@@ -0,0 +1,123 @@
+import { Page } from '@playwright/test';
+import { test, expect } from '../fixtures/test';
+import { MOCK_SNAKES_URL } from '../fixtures/snakes';
+
+/**
+ * Create two mock snakes and return their IDs and names, in the same order
+ */
+async function mockSnakes(page: Page): Promise<{ ids: string[]; names: string[] }> {
+  const ids: string[] = [];
+  const names: string[] = [];
+  for (const behavior of ['up', 'down']) {
+    const name = `Mirror ${behavior} ${Date.now()}`;
+    const response = await page.request.post('/api/snakes', {
+      data: { name, url: `${MOCK_SNAKES_URL}/${behavior}`, is_public: false },
+    });
+    ids.push((await response.json()).id);
+    names.push(name);
+  }
+  return { ids, names };
+}
+
+async function finishedDetails(page: Page, gameId: string) {
+  await expect.poll(async () => {
+    const response = await page.request.get(`/api/games/${gameId}/details`);
+    return (await response.json()).status;
+  }, { timeout: 30000, intervals: [500] }).toBe('finished');
+
+  return (await page.request.get(`/api/games/${gameId}/details`)).json();
+}
+
+test.describe('Mirror matches', () => {
+  test('plays a second game with the spawns swapped and scores both as a round', async ({ authenticatedPage }) => {
+    const { ids, names } = await mockSnakes(authenticatedPage);
+
+    const response = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: ids, board: '7x7', mirror: true },
+    });
+    expect(response.status()).toBe(201);
+    const { id, mirror_id: mirrorId } = await response.json();
+    expect(mirrorId).toBeTruthy();
+
+    const original = await finishedDetails(authenticatedPage, id);
+    const mirror = await finishedDetails(authenticatedPage, mirrorId);
+
+    const head = (details: { frames: { Snakes: { Name: string; Body: unknown[] }[] }[] }, name: string) =>
+      details.frames[0].Snakes.find((s) => s.Name === name)!.Body[0];
+    expect(head(mirror, names[0])).toEqual(head(original, names[1]));
+    expect(head(mirror, names[1])).toEqual(head(original, names[0]));
+
+    for (const details of [original, mirror]) {
+      expect(details.mirror.game_ids).toEqual([id, mirrorId]);
+      expect(details.mirror.complete).toBe(true);
+    }
+    const wins = original.mirror.standings.map((s: { wins: number }) => s.wins);
+    expect(wins.reduce((a: number, b: number) => a + b, 0)).toBe(2);
+
+    await authenticatedPage.goto(`/games/${mirrorId}`);
+    await expect(authenticatedPage.locator('#mirror-round')).toContainText('Mirror match');
+    await expect(authenticatedPage.locator('#mirror-round')).toContainText(`game ${id}`);
+  });
+
+  test('plays a best-of-3 series round by round and totals the standings', async ({ authenticatedPage }) => {
+    const { ids } = await mockSnakes(authenticatedPage);
+
+    const response = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: ids, board: '7x7', mirror: true, best_of: 3 },
+    });
+    expect(response.status()).toBe(201);
+    const { id, series_id: seriesId } = await response.json();
+    expect(seriesId).toBeTruthy();
+
+    await expect.poll(async () => {
+      const details = await (await authenticatedPage.request.get(`/api/games/${id}/details`)).json();
+      return details.series.complete;
+    }, { timeout: 90000, intervals: [1000] }).toBe(true);
+
+    const { series } = await (await authenticatedPage.request.get(`/api/games/${id}/details`)).json();
+    expect(series.series_id).toBe(seriesId);
+    expect(series.best_of).toBe(3);
+    expect(series.rounds[0].game_ids[0]).toBe(id);
+    expect(series.rounds.length).toBeGreaterThanOrEqual(2);
+    expect(series.rounds.length).toBeLessThanOrEqual(3);
+
+    // Each round's games count towards the series
+    const roundWins = series.rounds.flatMap((r: { standings: { wins: number }[] }) => r.standings.map((s) => s.wins));
+    const seriesWins = series.standings.map((s: { games_won: number }) => s.games_won);
+    const sum = (values: number[]) => values.reduce((a, b) => a + b, 0);
+    expect(sum(seriesWins)).toBe(sum(roundWins));
+
+    // Later rounds link back to the same series
+    const lastRound = series.rounds[series.rounds.length - 1];
+    const last = await (await authenticatedPage.request.get(`/api/games/${lastRound.game_ids[1]}/details`)).json();
+    expect(last.series.series_id).toBe(seriesId);
+
+    await authenticatedPage.goto(`/games/${id}`);
+    await expect(authenticatedPage.locator('#mirror-series')).toContainText('Best of 3 series');
+  });
+
+  test('rejects series with an even number of rounds or without mirror', async ({ authenticatedPage }) => {
+    const { ids } = await mockSnakes(authenticatedPage);
+
+    const even = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: ids, board: '7x7', mirror: true, best_of: 4 },
+    });
+    expect(even.status()).toBe(400);
+    expect(await even.text()).toContain('odd number of rounds');
+
+    const notMirrored = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: ids, board: '7x7', best_of: 3 },
+    });
+    expect(notMirrored.status()).toBe(400);
+  });
+
+  test('rejects mirror matches without exactly two snakes', async ({ authenticatedPage }) => {
+    const { ids } = await mockSnakes(authenticatedPage);
+
+    const response = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [...ids, ids[0]], board: '7x7', mirror: true },
+    });
+    expect(response.status()).toBe(400);
+    expect(await response.text()).toContain('exactly 2 snakes');
+  });
+});
@@ -0,0 +1,49 @@
+import { test, expect } from '../fixtures/test';
+import { query } from '../fixtures/db';
+import { createSnake, mockSnakeUrl } from '../fixtures/snakes';
+
+test.describe('Game Notation', () => {
+  test('exports a finished game as notation', async ({ authenticatedPage }) => {
+    const up = await createSnake(authenticatedPage, 'Notation Up', mockSnakeUrl('up'));
+    const down = await createSnake(authenticatedPage, 'Notation Down', mockSnakeUrl('down'));
+
+    const { id } = await (await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [up.id, down.id], board: '7x7' },
+    })).json();
+
+    await expect.poll(async () => {
+      const response = await authenticatedPage.request.get(`/api/games/${id}/details`);
+      return (await response.json()).status;
+    }, { timeout: 30000, intervals: [500] }).toBe('finished');
+
+    const response = await authenticatedPage.request.get(`/api/games/${id}/notation`);
+    expect(response.status()).toBe(200);
+    expect(response.headers()['content-type']).toContain('text/plain');
+
+    const notation = await response.text();
+    expect(notation).toContain(`[Game "${id}"]`);
+    expect(notation).toContain('[Board "7x7"]');
+    expect(notation).toContain(`"${up.name}"`);
+    expect(notation).toMatch(/^1\. A:U B:D/m);
+    expect(notation).toContain('out: wall-collision');
+
+    await authenticatedPage.goto(`/games/${id}`);
+    await expect(authenticatedPage.locator('#notation-link')).toBeVisible();
+  });
+
+  test('refuses games that have not finished', async ({ authenticatedPage }) => {
+    const games = await query<{ game_id: string }>(
+      `INSERT INTO games (board_size, game_type, status)
+       VALUES ('11x11', 'Standard', 'waiting') RETURNING game_id`
+    );
+
+    const response = await authenticatedPage.request.get(`/api/games/${games[0].game_id}/notation`);
+    expect(response.status()).toBe(409);
+    expect(await response.text()).toContain('Only finished games');
+  });
+
+  test('returns 404 for non-existent game', async ({ authenticatedPage }) => {
+    const response = await authenticatedPage.request.get('/api/games/00000000-0000-0000-0000-000000000000/notation');
+    expect(response.status()).toBe(404);
+  });
+});
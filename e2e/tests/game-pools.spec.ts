@@ -0,0 +1,64 @@
+import { Page } from '@playwright/test';
+import { test, expect } from '../fixtures/test';
+import { createSnake } from '../fixtures/snakes';
+
+async function optIn(page: Page, snakeId: string) {
+  const response = await page.request.put(`/api/snakes/${snakeId}/ranked`, { data: { opt_in: true } });
+  expect(response.status()).toBe(200);
+}
+
+test.describe('Ranked and Casual Pools', () => {
+  test('ranked games need every snake opted in', async ({ authenticatedPage }) => {
+    const first = await createSnake(authenticatedPage, 'Pool Opted In');
+    const second = await createSnake(authenticatedPage, 'Pool Not Opted In');
+    await optIn(authenticatedPage, first.id);
+
+    const optInResponse = await authenticatedPage.request.get(`/api/snakes/${first.id}/ranked`);
+    expect((await optInResponse.json()).opt_in).toBe(true);
+
+    const response = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [first.id, second.id], ranked: true },
+    });
+    expect(response.status()).toBe(400);
+    expect(await response.text()).toContain(`${second.id} isn't opted in`);
+  });
+
+  test('ranked games need healthy snakes', async ({ authenticatedPage }) => {
+    const healthy = await createSnake(authenticatedPage, 'Pool Healthy');
+    const unreachable = await createSnake(authenticatedPage, 'Pool Unreachable', 'http://127.0.0.1:1/');
+    await optIn(authenticatedPage, healthy.id);
+    await optIn(authenticatedPage, unreachable.id);
+
+    const response = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [healthy.id, unreachable.id], ranked: true },
+    });
+    expect(response.status()).toBe(400);
+    expect(await response.text()).toContain('failed its health check');
+  });
+
+  test('lists games from one pool', async ({ authenticatedPage }) => {
+    const first = await createSnake(authenticatedPage, 'Pool Ranked A');
+    const second = await createSnake(authenticatedPage, 'Pool Ranked B');
+    await optIn(authenticatedPage, first.id);
+    await optIn(authenticatedPage, second.id);
+
+    const rankedResponse = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [first.id, second.id], ranked: true },
+    });
+    expect(rankedResponse.status()).toBe(201);
+    const ranked = await rankedResponse.json();
+    const casual = await (await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [first.id, second.id] },
+    })).json();
+
+    const ids = async (filter: string) => {
+      const games = await (await authenticatedPage.request.get(`/api/games?snake_id=${first.id}&${filter}`)).json();
+      return games.map((g: { id: string }) => g.id);
+    };
+    expect(await ids('ranked=true')).toEqual([ranked.id]);
+    expect(await ids('ranked=false')).toEqual([casual.id]);
+
+    await authenticatedPage.goto(`/battlesnakes/${first.id}/profile?pool=ranked`);
+    await expect(authenticatedPage.locator('.card-body', { hasText: 'Games Played' })).toContainText('1');
+  });
+});
@@ -0,0 +1,47 @@
+import { test, expect } from '../fixtures/test';
+import { createSnake } from '../fixtures/snakes';
+
+const SNAKE_URL = 'https://example.com/practice';
+
+test.describe('Practice Games', () => {
+  test('self-play games are unranked practice', async ({ authenticatedPage }) => {
+    const snake = await createSnake(authenticatedPage, 'Self Play', SNAKE_URL);
+
+    const response = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [snake.id, snake.id] },
+    });
+    expect(response.status()).toBe(201);
+    const game = await response.json();
+
+    const details = await (await authenticatedPage.request.get(`/api/games/${game.id}/details`)).json();
+    expect(details.ranked).toBe(false);
+
+    await authenticatedPage.goto(`/games/${game.id}`);
+    await expect(authenticatedPage.locator('#practice-game')).toBeVisible();
+  });
+
+  test('self-play games cannot be ranked', async ({ authenticatedPage }) => {
+    const snake = await createSnake(authenticatedPage, 'Ranked Self Play', SNAKE_URL);
+
+    const response = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [snake.id, snake.id], ranked: true },
+    });
+    expect(response.status()).toBe(400);
+    expect(await response.text()).toContain("can't be ranked");
+  });
+
+  test('games against other snakes are casual, not practice', async ({ authenticatedPage }) => {
+    const first = await createSnake(authenticatedPage, 'Casual A', SNAKE_URL);
+    const second = await createSnake(authenticatedPage, 'Casual B', SNAKE_URL);
+
+    const casual = await (await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [first.id, second.id] },
+    })).json();
+    const details = await (await authenticatedPage.request.get(`/api/games/${casual.id}/details`)).json();
+    expect(details.ranked).toBe(false);
+
+    await authenticatedPage.goto(`/games/${casual.id}`);
+    await expect(authenticatedPage.locator('#game-pool')).toContainText('casual');
+    await expect(authenticatedPage.locator('#practice-game')).toHaveCount(0);
+  });
+});
@@ -0,0 +1,33 @@
+import { test, expect } from '../fixtures/test';
+import { MOCK_SNAKES_URL } from '../fixtures/snakes';
+
+test.describe('Win predictions', () => {
+  test('splits chances evenly between unrated snakes', async ({ authenticatedPage }) => {
+    const snakeIds: string[] = [];
+    for (const behavior of ['up', 'down']) {
+      const response = await authenticatedPage.request.post('/api/snakes', {
+        data: { name: `Predict ${behavior} ${Date.now()}`, url: `${MOCK_SNAKES_URL}/${behavior}`, is_public: true },
+      });
+      snakeIds.push((await response.json()).id);
+    }
+
+    const response = await authenticatedPage.request.get(`/api/games/predict?snakes=${snakeIds.join(',')}`);
+    expect(response.status()).toBe(200);
+    const prediction = await response.json();
+
+    expect(prediction.snakes.map((s: { snake_id: string }) => s.snake_id)).toEqual(snakeIds);
+    for (const snake of prediction.snakes) {
+      expect(snake.rating).toBe(1500);
+      expect(snake.win_probability).toBeCloseTo(0.5);
+      expect(snake.placement_probabilities).toHaveLength(2);
+    }
+  });
+
+  test('rejects bad snake lists', async ({ authenticatedPage }) => {
+    expect((await authenticatedPage.request.get('/api/games/predict?snakes=')).status()).toBe(400);
+    expect((await authenticatedPage.request.get('/api/games/predict?snakes=nope')).status()).toBe(400);
+
+    const unknown = '00000000-0000-0000-0000-000000000000';
+    expect((await authenticatedPage.request.get(`/api/games/predict?snakes=${unknown}`)).status()).toBe(400);
+  });
+});
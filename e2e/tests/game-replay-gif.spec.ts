@@ -0,0 +1,69 @@
+import { test, expect } from '../fixtures/test';
+import { query } from '../fixtures/db';
+import { createSnake, mockSnakeUrl } from '../fixtures/snakes';
+
+test.describe('Replay GIFs', () => {
+  test('renders a finished game as an animated GIF', async ({ authenticatedPage, browser }) => {
+    const up = await createSnake(authenticatedPage, 'GIF Up', mockSnakeUrl('up'), true);
+    const down = await createSnake(authenticatedPage, 'GIF Down', mockSnakeUrl('down'), true);
+
+    const { id } = await (await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [up.id, down.id], board: '7x7' },
+    })).json();
+
+    await expect.poll(async () => {
+      const response = await authenticatedPage.request.get(`/api/games/${id}/details`);
+      return (await response.json()).status;
+    }, { timeout: 30000, intervals: [500] }).toBe('finished');
+
+    // Queued when the game ended; the worker renders it shortly after
+    await expect.poll(async () => {
+      const response = await authenticatedPage.request.get(`/games/${id}/replay.gif`);
+      return response.status();
+    }, { timeout: 30000, intervals: [500] }).toBe(200);
+
+    // Public games need no login, so chat apps can fetch them
+    const anonymous = await browser.newContext();
+    const response = await anonymous.request.get(`/games/${id}/replay.gif`);
+    expect(response.status()).toBe(200);
+    expect(response.headers()['content-type']).toBe('image/gif');
+    const gif = await response.body();
+    expect(gif.subarray(0, 6).toString('ascii')).toBe('GIF89a');
+    await anonymous.close();
+
+    const renders = await query<{ frame_count: number; rendered_at: string | null }>(
+      'SELECT frame_count, rendered_at FROM game_renders WHERE game_id = $1',
+      [id]
+    );
+    expect(renders[0].rendered_at).not.toBeNull();
+    expect(renders[0].frame_count).toBeGreaterThan(1);
+
+    await authenticatedPage.goto(`/games/${id}`);
+    await expect(authenticatedPage.locator('#replay-gif-link')).toBeVisible();
+  });
+
+  test('refuses games that have not finished', async ({ authenticatedPage }) => {
+    const games = await query<{ game_id: string }>(
+      `INSERT INTO games (board_size, game_type, status)
+       VALUES ('11x11', 'Standard', 'running') RETURNING game_id`
+    );
+
+    const response = await authenticatedPage.request.get(`/games/${games[0].game_id}/replay.gif`);
+    expect(response.status()).toBe(409);
+  });
+
+  test('keeps private games to their owners', async ({ page }) => {
+    const games = await query<{ game_id: string }>(
+      `INSERT INTO games (board_size, game_type, status, visibility)
+       VALUES ('7x7', 'Standard', 'finished', 'private') RETURNING game_id`
+    );
+
+    const response = await page.request.get(`/games/${games[0].game_id}/replay.gif`);
+    expect(response.status()).toBe(401);
+  });
+
+  test('returns 404 for non-existent game', async ({ page }) => {
+    const response = await page.request.get('/games/00000000-0000-0000-0000-000000000000/replay.gif');
+    expect(response.status()).toBe(404);
+  });
+});
@@ -0,0 +1,67 @@
+import { test, expect } from '../fixtures/test';
+import { finishedGame, walkingSnakeFrames } from '../fixtures/games';
+
+/**
+ * Record a finished game with a lone snake walking up the board.
+ */
+function sharedGame(visibility: 'public' | 'private', turns: number): Promise<string> {
+  return finishedGame({ visibility, frames: walkingSnakeFrames(turns, { Color: '#3366ff' }) });
+}
+
+test.describe('Sharing Games', () => {
+  test('public games unfurl with a preview of the linked turn', async ({ page }) => {
+    const gameId = await sharedGame('public', 3);
+
+    // Chat apps fetch links without a login
+    await page.goto(`/games/${gameId}?turn=1`);
+
+    await expect(page.locator('meta[property="og:title"]')).toHaveAttribute('content', /\(turn 1\)$/);
+    await expect(page.locator('meta[property="og:description"]')).toHaveAttribute('content', /^Turn 1: /);
+    await expect(page.locator('meta[property="og:url"]')).toHaveAttribute('content', new RegExp(`/games/${gameId}\\?turn=1$`));
+    const image = await page.locator('meta[property="og:image"]').getAttribute('content');
+    expect(image).toMatch(new RegExp(`/games/${gameId}/board\\.gif\\?turn=1$`));
+
+    // The replay opens at the linked turn
+    await expect(page.locator('#board-description')).toContainText('Turn 1:');
+    await expect(page.locator('#board-viewer')).toHaveAttribute('data-src', /&turn=1/);
+  });
+
+  test('board images show one turn', async ({ page }) => {
+    const gameId = await sharedGame('public', 3);
+
+    const response = await page.request.get(`/games/${gameId}/board.gif?turn=1`);
+    expect(response.status()).toBe(200);
+    expect(response.headers()['content-type']).toBe('image/gif');
+    expect((await response.body()).subarray(0, 6).toString()).toBe('GIF89a');
+
+    // Past the last turn is the last board
+    expect((await page.request.get(`/games/${gameId}/board.gif?turn=99`)).status()).toBe(200);
+  });
+
+  test('games without turns have no board image', async ({ page }) => {
+    const gameId = await sharedGame('public', 0);
+
+    expect((await page.request.get(`/games/${gameId}/board.gif`)).status()).toBe(404);
+    await page.goto(`/games/${gameId}`);
+    await expect(page.locator('meta[property="og:title"]')).toHaveCount(1);
+    await expect(page.locator('meta[property="og:image"]')).toHaveCount(0);
+  });
+
+  test('stepping through turns keeps the turn in the address bar', async ({ authenticatedPage }) => {
+    const gameId = await sharedGame('public', 3);
+    await authenticatedPage.goto(`/games/${gameId}`);
+
+    await expect(authenticatedPage.locator('#board-description')).toContainText('Turn 0:');
+    await authenticatedPage.locator('body').press('ArrowRight');
+    await expect(authenticatedPage.locator('#board-description')).toContainText('Turn 1:');
+    await expect(authenticatedPage).toHaveURL(new RegExp(`/games/${gameId}\\?turn=1$`));
+  });
+
+  test('private games stay private', async ({ page }) => {
+    const gameId = await sharedGame('private', 2);
+
+    const response = await page.goto(`/games/${gameId}?turn=1`);
+    expect(response?.status()).toBe(401);
+    expect((await page.request.get(`/games/${gameId}/board.gif`)).status()).toBe(401);
+  });
+});
@@ -0,0 +1,50 @@
+import { test, expect } from '../fixtures/test';
+import { query } from '../fixtures/db';
+import { MOCK_SNAKES_URL } from '../fixtures/snakes';
+
+type FrameSnake = { ID: string; Name: string; Color: string; HeadType: string; TailType: string; Author: string };
+
+test.describe('Snake info in frames', () => {
+  test('frames show what each snake\'s GET / returned', async ({ authenticatedPage: page }) => {
+    const names: Record<string, string> = {};
+    const ids: string[] = [];
+    for (const behavior of ['up', 'error']) {
+      const name = `Info ${behavior} ${Date.now()}`;
+      const response = await page.request.post('/api/snakes', {
+        data: { name, url: `${MOCK_SNAKES_URL}/${behavior}`, is_public: false },
+      });
+      ids.push((await response.json()).id);
+      names[behavior] = name;
+    }
+
+    const createResponse = await page.request.post('/api/games', {
+      data: { snakes: ids, board: '7x7' },
+    });
+    expect(createResponse.status()).toBe(201);
+    const { id } = await createResponse.json();
+
+    await expect.poll(async () => {
+      const response = await page.request.get(`/api/games/${id}/details`);
+      return (await response.json()).status;
+    }, { timeout: 30000, intervals: [500] }).toBe('finished');
+
+    const details = await (await page.request.get(`/api/games/${id}/details`)).json();
+    const first: FrameSnake[] = details.frames[0].Snakes;
+    const answered = first.find((s) => s.Name === names.up)!;
+    expect(answered).toMatchObject({ Color: '#888888', HeadType: 'default', TailType: 'default', Author: 'mock-snakes' });
+
+    // A snake whose GET / fails keeps a generated color and no author
+    const failed = first.find((s) => s.Name === names.error)!;
+    expect(failed.Color).toMatch(/^#[0-9a-f]{6}$/);
+    expect(failed.Color).not.toBe('#888888');
+    expect(failed.Author).toBe('');
+
+    const rows = await query<{ battlesnake_id: string; apiversion: string | null; author: string | null; color: string | null }>(
+      'SELECT battlesnake_id, apiversion, author, color FROM game_battlesnakes WHERE game_id = $1',
+      [id]
+    );
+    const byId = new Map(rows.map((row) => [row.battlesnake_id, row]));
+    expect(byId.get(ids[0])).toMatchObject({ apiversion: '1', author: 'mock-snakes', color: '#888888' });
+    expect(byId.get(ids[1])).toMatchObject({ apiversion: null, author: null, color: null });
+  });
+});
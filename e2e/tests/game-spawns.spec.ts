@@ -0,0 +1,77 @@
+import { Page } from '@playwright/test';
+import { test, expect } from '../fixtures/test';
+import { MOCK_SNAKES_URL } from '../fixtures/snakes';
+
+/**
+ * Create two mock snakes and return their IDs and names, in the same order
+ */
+async function mockSnakes(page: Page): Promise<{ ids: string[]; names: string[] }> {
+  const ids: string[] = [];
+  const names: string[] = [];
+  for (const behavior of ['up', 'down']) {
+    const name = `Spawn ${behavior} ${Date.now()}`;
+    const response = await page.request.post('/api/snakes', {
+      data: { name, url: `${MOCK_SNAKES_URL}/${behavior}`, is_public: false },
+    });
+    ids.push((await response.json()).id);
+    names.push(name);
+  }
+  return { ids, names };
+}
+
+/**
+ * Create a game and return each snake's head on turn 0, in the order the snakes were given
+ */
+async function startingHeads(
+  page: Page,
+  names: string[],
+  data: Record<string, unknown>
+): Promise<{ X: number; Y: number }[]> {
+  const createResponse = await page.request.post('/api/games', { data });
+  expect(createResponse.status()).toBe(201);
+  const { id } = await createResponse.json();
+
+  await expect.poll(async () => {
+    const response = await page.request.get(`/api/games/${id}/details`);
+    return (await response.json()).frames.length;
+  }, { timeout: 30000 }).toBeGreaterThan(0);
+
+  const details = await (await page.request.get(`/api/games/${id}/details`)).json();
+  return names.map((name) =>
+    details.frames[0].Snakes.find((s: { Name: string }) => s.Name === name).Body[0]
+  );
+}
+
+test.describe('Spawn strategies', () => {
+  test('fixed spawns are repeatable and swap with the rotation', async ({ authenticatedPage }) => {
+    const { ids, names } = await mockSnakes(authenticatedPage);
+    const game = { snakes: ids, board: '7x7', spawn: 'fixed' };
+
+    const first = await startingHeads(authenticatedPage, names, { ...game, spawn_rotation: 0 });
+    const second = await startingHeads(authenticatedPage, names, { ...game, spawn_rotation: 1 });
+    expect(first).toEqual([{ X: 1, Y: 1 }, { X: 5, Y: 5 }]);
+    expect(second).toEqual([{ X: 5, Y: 5 }, { X: 1, Y: 1 }]);
+  });
+
+  test('manual spawns put snakes where the scenario says', async ({ authenticatedPage }) => {
+    const { ids, names } = await mockSnakes(authenticatedPage);
+
+    const heads = await startingHeads(authenticatedPage, names, {
+      snakes: ids,
+      board: '7x7',
+      spawn: 'manual',
+      spawn_positions: [{ x: 3, y: 2 }, { x: 4, y: 6 }],
+    });
+    expect(heads).toEqual([{ X: 3, Y: 2 }, { X: 4, Y: 6 }]);
+  });
+
+  test('rejects manual spawns that do not fit the game', async ({ authenticatedPage }) => {
+    const { ids } = await mockSnakes(authenticatedPage);
+
+    const response = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: ids, board: '7x7', spawn: 'manual', spawn_positions: [{ x: 3, y: 2 }] },
+    });
+    expect(response.status()).toBe(400);
+    expect(await response.text()).toContain('spawn positions');
+  });
+});
@@ -0,0 +1,64 @@
+import { test, expect } from '../fixtures/test';
+import { query } from '../fixtures/db';
+
+/**
+ * Record a running game directly, so nothing plays it and it stays live.
+ */
+async function runningGame(visibility: string): Promise<string> {
+  const games = await query<{ game_id: string }>(
+    `INSERT INTO games (board_size, game_type, status, visibility)
+     VALUES ('7x7', 'Standard', 'running', $1) RETURNING game_id`,
+    [visibility]
+  );
+  return games[0].game_id;
+}
+
+test.describe('Game spectators', () => {
+  test('counts WebSocket viewers and tells them how many are watching', async ({ page }) => {
+    const gameId = await runningGame('public');
+    await page.goto('/');
+
+    const spectators = () => page.request.get(`/api/games/${gameId}/spectators`).then((r) => r.json());
+    expect(await spectators()).toEqual({ game_id: gameId, spectators: 0 });
+
+    // Two viewers; each is told the count as soon as it's watching
+    const counts = await page.evaluate(async (gameId) => {
+      const url = `${location.origin.replace(/^http/, 'ws')}/api/games/${gameId}/events`;
+      const watch = () =>
+        new Promise<{ socket: WebSocket; count: number }>((resolve) => {
+          const socket = new WebSocket(url);
+          socket.onmessage = (message) => {
+            const event = JSON.parse(message.data);
+            if (event.Type === 'spectators') resolve({ socket, count: event.Data.Count });
+          };
+        });
+      const first = await watch();
+      const second = await watch();
+      (window as unknown as { sockets: WebSocket[] }).sockets = [first.socket, second.socket];
+      return [first.count, second.count];
+    }, gameId);
+    expect(counts).toEqual([1, 2]);
+    expect((await spectators()).spectators).toBe(2);
+
+    const metrics = await (await page.request.get('/_/metrics')).text();
+    expect(metrics).toMatch(/^arena_spectators \d+$/m);
+    expect(metrics).toMatch(/^arena_watched_games \d+$/m);
+
+    await page.evaluate(() => (window as unknown as { sockets: WebSocket[] }).sockets.forEach((s) => s.close()));
+    await expect.poll(async () => (await spectators()).spectators).toBe(0);
+  });
+
+  test('the game page shows how many are watching a live game', async ({ authenticatedPage }) => {
+    const gameId = await runningGame('public');
+
+    await authenticatedPage.goto(`/games/${gameId}`);
+    await expect(authenticatedPage.locator('#spectators')).toContainText('watching');
+  });
+
+  test('private games need auth', async ({ page }) => {
+    const gameId = await runningGame('private');
+
+    const response = await page.request.get(`/api/games/${gameId}/spectators`);
+    expect(response.status()).toBe(401);
+  });
+});
@@ -0,0 +1,150 @@
+import { Page } from '@playwright/test';
+import { test, expect, createMockUser } from '../fixtures/test';
+import { query } from '../fixtures/db';
+
+/**
+ * Create a snake and a game with it via the API, returning the game ID.
+ */
+async function createGame(page: Page, visibility: 'public' | 'private'): Promise<string> {
+  const snakeName = `Stream Auth ${visibility} ${Date.now()}`;
+
+  await page.goto('/battlesnakes/new');
+  await page.getByLabel('Name').fill(snakeName);
+  await page.getByLabel('URL').fill('https://example.com/stream-auth');
+  await page.getByLabel('Visibility').selectOption('public');
+  await page.getByRole('button', { name: 'Create Battlesnake' }).click();
+
+  const snakes = await query<{ battlesnake_id: string }>(
+    'SELECT battlesnake_id FROM battlesnakes WHERE name = $1',
+    [snakeName]
+  );
+
+  const response = await page.request.post('/api/games', {
+    data: { snakes: [snakes[0].battlesnake_id], visibility },
+  });
+  expect(response.status()).toBe(201);
+  return (await response.json()).id;
+}
+
+/**
+ * Open a WebSocket from the page (so the browser sends its cookies) and
+ * report whether the upgrade was accepted.
+ */
+async function openSocket(page: Page, path: string): Promise<'open' | 'rejected'> {
+  return page.evaluate(
+    (path) =>
+      new Promise<'open' | 'rejected'>((resolve) => {
+        const url = new URL(path, window.location.href);
+        url.protocol = url.protocol === 'https:' ? 'wss:' : 'ws:';
+        const socket = new WebSocket(url);
+        socket.onopen = () => {
+          socket.close();
+          resolve('open');
+        };
+        socket.onerror = () => resolve('rejected');
+      }),
+    path
+  );
+}
+
+test.describe('Game event stream authentication', () => {
+  test('anyone can stream a public game', async ({ authenticatedPage, browser }) => {
+    const gameId = await createGame(authenticatedPage, 'public');
+
+    const anonymous = await browser.newPage();
+    await anonymous.goto('/');
+
+    expect(await openSocket(anonymous, `/api/games/${gameId}/events`)).toBe('open');
+    const info = await anonymous.request.get(`/api/games/${gameId}`);
+    expect(info.status()).toBe(200);
+
+    await anonymous.close();
+  });
+
+  test('anonymous viewers cannot stream a private game', async ({ authenticatedPage, browser }) => {
+    const gameId = await createGame(authenticatedPage, 'private');
+
+    const anonymous = await browser.newPage();
+    await anonymous.goto('/');
+
+    expect(await openSocket(anonymous, `/api/games/${gameId}/events`)).toBe('rejected');
+    const info = await anonymous.request.get(`/api/games/${gameId}`);
+    expect(info.status()).toBe(401);
+
+    await anonymous.close();
+  });
+
+  test('snake owners can stream a private game with their session', async ({ authenticatedPage }) => {
+    const gameId = await createGame(authenticatedPage, 'private');
+
+    expect(await openSocket(authenticatedPage, `/api/games/${gameId}/events`)).toBe('open');
+    const info = await authenticatedPage.request.get(`/api/games/${gameId}`);
+    expect(info.status()).toBe(200);
+  });
+
+  test('a stream ticket opens a private game over the API socket path', async ({ authenticatedPage, browser }) => {
+    const gameId = await createGame(authenticatedPage, 'private');
+
+    const ticketResponse = await authenticatedPage.request.post(`/api/games/${gameId}/stream-ticket`);
+    expect(ticketResponse.status()).toBe(201);
+    const { ticket } = await ticketResponse.json();
+    expect(ticket).toBeTruthy();
+
+    // Tickets work without any cookie
+    const anonymous = await browser.newPage();
+    await anonymous.goto('/');
+
+    expect(await openSocket(anonymous, `/api/games/${gameId}/events?ticket=${ticket}`)).toBe('open');
+    expect(await openSocket(anonymous, `/api/games/${gameId}/events?ticket=not-a-ticket`)).toBe('rejected');
+
+    await anonymous.close();
+  });
+
+  test('the board viewer engine URL carries a ticket for private games', async ({ authenticatedPage, browser }) => {
+    const gameId = await createGame(authenticatedPage, 'private');
+
+    const ticketResponse = await authenticatedPage.request.post(`/api/games/${gameId}/stream-ticket`);
+    const { board_viewer_engine: engine } = await ticketResponse.json();
+    const enginePath = new URL(engine).pathname;
+    expect(enginePath).toMatch(/^\/api\/v1\/viewer\//);
+
+    // The game page points the board viewer at the ticketed engine URL
+    await authenticatedPage.goto(`/games/${gameId}`);
+    const iframeSrc = await authenticatedPage.locator('#board-viewer').getAttribute('src');
+    expect(decodeURIComponent(iframeSrc!)).toContain('/api/v1/viewer/');
+
+    const anonymous = await browser.newPage();
+    await anonymous.goto('/');
+
+    const info = await anonymous.request.get(`${enginePath}/games/${gameId}`);
+    expect(info.status()).toBe(200);
+    expect((await info.json()).Game.Width).toBe(11);
+    expect(await openSocket(anonymous, `${enginePath}/games/${gameId}/events`)).toBe('open');
+
+    // A ticket for one game doesn't open another
+    const otherGameId = await createGame(authenticatedPage, 'private');
+    expect(await openSocket(anonymous, `${enginePath}/games/${otherGameId}/events`)).toBe('rejected');
+    expect(await openSocket(anonymous, `/api/viewer/not-a-ticket/games/${gameId}/events`)).toBe('rejected');
+
+    await anonymous.close();
+  });
+
+  test('users without a snake in a private game cannot stream it', async ({ authenticatedPage, browser, loginAsUser }) => {
+    const gameId = await createGame(authenticatedPage, 'private');
+
+    const context = await browser.newContext();
+    const otherPage = await context.newPage();
+    await otherPage.goto('/');
+    await loginAsUser(otherPage, createMockUser('stream_other'));
+
+    expect(await openSocket(otherPage, `/api/games/${gameId}/events`)).toBe('rejected');
+    const ticketResponse = await otherPage.request.post(`/api/games/${gameId}/stream-ticket`);
+    expect(ticketResponse.status()).toBe(403);
+    const details = await otherPage.request.get(`/api/games/${gameId}/details`);
+    expect(details.status()).toBe(403);
+    const page = await otherPage.goto(`/games/${gameId}`);
+    expect(page?.status()).toBe(404);
+
+    await context.close();
+  });
+});
@@ -0,0 +1,62 @@
+import { test, expect } from '../fixtures/test';
+import { query } from '../fixtures/db';
+
+/**
+ * Record a finished game directly, with the stage timings the runner would save.
+ */
+async function timedGame(timings: Record<string, [number, number, number]>): Promise<string> {
+  const games = await query<{ game_id: string }>(
+    `INSERT INTO games (board_size, game_type, status)
+     VALUES ('7x7', 'Standard', 'finished') RETURNING game_id`
+  );
+  const gameId = games[0].game_id;
+  for (const [stage, [turns, totalUs, maxUs]] of Object.entries(timings)) {
+    await query(
+      `INSERT INTO game_stage_timings (game_id, stage, turns, total_us, max_us)
+       VALUES ($1, $2, $3, $4, $5)`,
+      [gameId, stage, turns, totalUs, maxUs]
+    );
+  }
+  return gameId;
+}
+
+test.describe('Game Timing', () => {
+  test('reports where a game\'s time went', async ({ authenticatedPage }) => {
+    const gameId = await timedGame({
+      gather_moves: [10, 900_000, 200_000],
+      apply_turn: [10, 5_000, 1_000],
+      serialize_frame: [10, 15_000, 3_000],
+      persist: [10, 70_000, 12_000],
+      broadcast: [10, 10_000, 2_000],
+    });
+
+    const response = await authenticatedPage.request.get(`/api/games/${gameId}/timing`);
+    expect(response.status()).toBe(200);
+    const report = await response.json();
+
+    expect(report.total_ms).toBe(1000);
+    expect(report.stages.map((s: { stage: string }) => s.stage)).toEqual([
+      'gather_moves', 'apply_turn', 'serialize_frame', 'persist', 'broadcast',
+    ]);
+    expect(report.stages[0]).toMatchObject({ turns: 10, total_ms: 900, mean_ms: 90, max_ms: 200, share: 0.9 });
+    expect(report.slowest_stage).toBe('gather_moves');
+    expect(report.verdict).toBe("Most of this game's time went to snakes (gather_moves, 90%)");
+  });
+
+  test('returns 404 until the game has timings', async ({ authenticatedPage }) => {
+    const gameId = await timedGame({});
+    const response = await authenticatedPage.request.get(`/api/games/${gameId}/timing`);
+    expect(response.status()).toBe(404);
+  });
+
+  test('serves the stage histograms at /_/metrics', async ({ page }) => {
+    const response = await page.request.get('/_/metrics');
+    expect(response.status()).toBe(200);
+    expect(response.headers()['content-type']).toContain('text/plain');
+    const body = await response.text();
+    expect(body).toContain('# TYPE arena_turn_stage_duration_seconds histogram');
+    for (const stage of ['gather_moves', 'apply_turn', 'serialize_frame', 'persist', 'broadcast']) {
+      expect(body).toContain(`arena_turn_stage_duration_seconds_count{stage="${stage}"}`);
+    }
+  });
+});
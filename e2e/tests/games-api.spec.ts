@@ -34,6 +34,64 @@ test.describe('Games API', () => {
       const game = await response.json();
       expect(game.id).toBeDefined();
       expect(game.status).toBe('waiting');
+      // Always at least next in line; the ETA depends on recent throughput
+      expect(game.queue.position).toBeGreaterThanOrEqual(1);
+      expect('eta_seconds' in game.queue).toBe(true);
+    });
+
+    test('reports the seed a game was created with', async ({ authenticatedPage }) => {
+      const snake = await (await authenticatedPage.request.post('/api/snakes', {
+        data: { name: `Seeded Snake ${Date.now()}`, url: 'https://example.com/seeded', is_public: false },
+      })).json();
+
+      const seeded = await (await authenticatedPage.request.post('/api/games', {
+        data: { snakes: [snake.id], seed: 42 },
+      })).json();
+      const details = await (await authenticatedPage.request.get(`/api/games/${seeded.id}/details`)).json();
+      expect(details.seed).toBe(42);
+
+      // Without one the game still gets a seed to replay it from
+      const unseeded = await (await authenticatedPage.request.post('/api/games', {
+        data: { snakes: [snake.id] },
+      })).json();
+      const unseededDetails = await (await authenticatedPage.request.get(`/api/games/${unseeded.id}/details`)).json();
+      expect(Number.isInteger(unseededDetails.seed)).toBe(true);
+    });
+
+    test('reports a draw when the last snakes share first place', async ({ authenticatedPage }) => {
+      const snakeIds: string[] = [];
+      for (const name of ['Draw A', 'Draw B']) {
+        const snake = await (await authenticatedPage.request.post('/api/snakes', {
+          data: { name: `${name} ${Date.now()}`, url: 'https://example.com/draw', is_public: false },
+        })).json();
+        snakeIds.push(snake.id);
+      }
+
+      const game = await (await authenticatedPage.request.post('/api/games', {
+        data: { snakes: snakeIds },
+      })).json();
+      const details = await (await authenticatedPage.request.get(`/api/games/${game.id}/details`)).json();
+      expect(details.draw).toBe(false);
+
+      // Record a finished game directly, as if both snakes went out on the same turn
+      const games = await query<{ game_id: string }>(
+        `INSERT INTO games (board_size, game_type, status)
+         VALUES ('7x7', 'Standard', 'finished') RETURNING game_id`
+      );
+      const drawId = games[0].game_id;
+      for (const snakeId of snakeIds) {
+        await query(
+          'INSERT INTO game_battlesnakes (game_id, battlesnake_id, placement) VALUES ($1, $2, 1)',
+          [drawId, snakeId]
+        );
+      }
+
+      const drawDetails = await (await authenticatedPage.request.get(`/api/games/${drawId}/details`)).json();
+      expect(drawDetails.draw).toBe(true);
+      expect(drawDetails.winner).toBeNull();
+
+      await authenticatedPage.goto(`/games/${drawId}`);
+      await expect(authenticatedPage.locator('#game-draw')).toBeVisible();
     });
 
     test('can create game with multiple snakes', async ({ authenticatedPage }) => {
@@ -119,7 +177,7 @@ test.describe('Games API', () => {
       const snakeId = snakes[0].battlesnake_id;
 
       // Test each game type (case-insensitive)
-      for (const gameType of ['standard', 'ROYALE', 'Constrictor', 'snail']) {
+      for (const gameType of ['standard', 'ROYALE', 'Constrictor', 'snail', 'Wrapped']) {
         const response = await authenticatedPage.request.post('/api/games', {
           data: {
             snakes: [snakeId],
@@ -330,6 +388,33 @@ test.describe('Games API', () => {
       expect(body).toContain('Invalid game type');
     });
 
+    test('rejects game with invalid turn limit tiebreak', async ({ authenticatedPage }) => {
+      const snakeName = `Invalid Tiebreak ${Date.now()}`;
+
+      await authenticatedPage.goto('/battlesnakes/new');
+      await authenticatedPage.getByLabel('Name').fill(snakeName);
+      await authenticatedPage.getByLabel('URL').fill('https://example.com/invalid-tiebreak');
+      await authenticatedPage.getByLabel('Visibility').selectOption('public');
+      await authenticatedPage.getByRole('button', { name: 'Create Battlesnake' }).click();
+
+      const snakes = await query<{ battlesnake_id: string }>(
+        "SELECT battlesnake_id FROM battlesnakes WHERE name = $1",
+        [snakeName]
+      );
+      const snakeId = snakes[0].battlesnake_id;
+
+      const response = await authenticatedPage.request.post('/api/games', {
+        data: {
+          snakes: [snakeId],
+          turn_limit_tiebreak: 'coin_flip'
+        }
+      });
+
+      expect(response.status()).toBe(400);
+      const body = await response.text();
+      expect(body).toContain('Invalid turn limit tiebreak');
+    });
+
     test('can use public snake from another user', async ({ authenticatedPage, loginAsUser }) => {
       const publicSnakeName = `Public API Snake ${Date.now()}`;
 
@@ -564,6 +649,50 @@ test.describe('Games API', () => {
       expect(game.snakes[0].name).toBe(snakeName);
     });
 
+    test('certifies finished games and detects edited frames', async ({ authenticatedPage }) => {
+      const snakeName = `Certified Snake ${Date.now()}`;
+
+      await authenticatedPage.goto('/battlesnakes/new');
+      await authenticatedPage.getByLabel('Name').fill(snakeName);
+      await authenticatedPage.getByLabel('URL').fill('https://example.com/certified');
+      await authenticatedPage.getByLabel('Visibility').selectOption('public');
+      await authenticatedPage.getByRole('button', { name: 'Create Battlesnake' }).click();
+
+      const snakes = await query<{ battlesnake_id: string }>(
+        "SELECT battlesnake_id FROM battlesnakes WHERE name = $1",
+        [snakeName]
+      );
+
+      // A solo game finishes on turn 0
+      const createResponse = await authenticatedPage.request.post('/api/games', {
+        data: { snakes: [snakes[0].battlesnake_id] }
+      });
+      const createdGame = await createResponse.json();
+
+      await expect.poll(async () => {
+        const response = await authenticatedPage.request.get(`/api/games/${createdGame.id}/details`);
+        return (await response.json()).status;
+      }, { timeout: 30000 }).toBe('finished');
+
+      let response = await authenticatedPage.request.get(`/api/games/${createdGame.id}/details`);
+      let game = await response.json();
+      expect(game.certification.status).toBe('verified');
+      expect(game.certification.digest).toMatch(/^[0-9a-f]{64}$/);
+      expect(game.certification.computed_digest).toBe(game.certification.digest);
+
+      // Edit a stored frame behind the arena's back
+      await query(
+        `UPDATE turns SET frame_data = jsonb_set(frame_data, '{Turn}', '99'::jsonb)
+         WHERE game_id = $1 AND turn_number = 0`,
+        [createdGame.id]
+      );
+
+      response = await authenticatedPage.request.get(`/api/games/${createdGame.id}/details`);
+      game = await response.json();
+      expect(game.certification.status).toBe('mismatch');
+      expect(game.certification.computed_digest).not.toBe(game.certification.digest);
+    });
+
     test('returns 404 for non-existent game', async ({ authenticatedPage }) => {
       const fakeGameId = '00000000-0000-0000-0000-000000000000';
 
@@ -605,6 +734,37 @@ test.describe('Games API', () => {
     });
   });
 
+  test.describe('GET /api/games/{id}/frames.ndjson - Stream Frames', () => {
+    test('streams every frame in turn order, one per line', async ({ authenticatedPage }) => {
+      const games = await query<{ game_id: string }>(
+        `INSERT INTO games (board_size, game_type, status)
+         VALUES ('11x11', 'Standard', 'finished') RETURNING game_id`
+      );
+      const gameId = games[0].game_id;
+
+      // More turns than fit in one page of the stream
+      await query(
+        `INSERT INTO turns (game_id, turn_number, frame_data)
+         SELECT $1, n, jsonb_build_object('Turn', n, 'Snakes', '[]'::jsonb, 'Food', '[]'::jsonb, 'Hazards', '[]'::jsonb)
+         FROM generate_series(0, 249) AS n`,
+        [gameId]
+      );
+
+      const response = await authenticatedPage.request.get(`/api/games/${gameId}/frames.ndjson`);
+      expect(response.status()).toBe(200);
+      expect(response.headers()['content-type']).toBe('application/x-ndjson');
+
+      const lines = (await response.text()).split('\n').filter((line) => line.length > 0);
+      expect(lines).toHaveLength(250);
+      expect(lines.map((line) => JSON.parse(line).Turn)).toEqual([...Array(250).keys()]);
+    });
+
+    test('returns 404 for non-existent game', async ({ authenticatedPage }) => {
+      const response = await authenticatedPage.request.get('/api/games/00000000-0000-0000-0000-000000000000/frames.ndjson');
+      expect(response.status()).toBe(404);
+    });
+  });
+
   test.describe('Authentication', () => {
     test('requires authentication for create game', async ({ page }) => {
       // Make request without authentication
@@ -0,0 +1,49 @@
+import { test, expect } from '../fixtures/test';
+import { finishedGame } from '../fixtures/games';
+import { createSnake } from '../fixtures/snakes';
+
+const SNAKE_URL = 'https://example.com/leaderboard';
+
+test.describe('Opponent-adjusted stats', () => {
+  test('leaderboard ranks snakes by rating with performance vs expected', async ({ authenticatedPage }) => {
+    const winner = await createSnake(authenticatedPage, 'Leaderboard Winner', SNAKE_URL, true);
+    const loser = await createSnake(authenticatedPage, 'Leaderboard Loser', SNAKE_URL, true);
+
+    // Two new snakes start level, so the winner gains exactly what the loser drops
+    await finishedGame({ placements: [[winner.id, 1], [loser.id, 2]] });
+
+    await authenticatedPage.goto('/leaderboard');
+    const winnerRow = authenticatedPage.locator('#leaderboard tr', { hasText: winner.name });
+    const loserRow = authenticatedPage.locator('#leaderboard tr', { hasText: loser.name });
+    await expect(winnerRow).toContainText('1516');
+    await expect(winnerRow).toContainText('100.0%');
+    await expect(winnerRow).toContainText('+0.50');
+    await expect(loserRow).toContainText('1484');
+    await expect(loserRow).toContainText('-0.50');
+
+    const rank = async (row: typeof winnerRow) => Number(await row.locator('td').first().textContent());
+    expect(await rank(winnerRow)).toBeLessThan(await rank(loserRow));
+  });
+
+  test('snake profile shows rating alongside win rate', async ({ authenticatedPage }) => {
+    const snake = await createSnake(authenticatedPage, 'Profile Rated', SNAKE_URL, true);
+    const opponent = await createSnake(authenticatedPage, 'Profile Opponent', SNAKE_URL, true);
+
+    await finishedGame({ placements: [[opponent.id, 1], [snake.id, 2]] });
+
+    await authenticatedPage.goto(`/battlesnakes/${snake.id}/profile`);
+    const card = (title: string) => authenticatedPage.locator('.card-body', { hasText: title });
+    await expect(card('Win Rate')).toContainText('0.0%');
+    await expect(card('Rating')).toContainText('1484');
+    await expect(card('Vs. Expected')).toContainText('-0.50');
+  });
+
+  test('snakes without games against others are not ranked', async ({ authenticatedPage }) => {
+    const snake = await createSnake(authenticatedPage, 'Leaderboard Solo', SNAKE_URL, true);
+    await finishedGame({ placements: [[snake.id, 1]] });
+
+    await authenticatedPage.goto('/leaderboard');
+    await expect(authenticatedPage.getByRole('heading', { name: 'Leaderboard' })).toBeVisible();
+    await expect(authenticatedPage.getByText(snake.name)).toHaveCount(0);
+  });
+});
@@ -0,0 +1,154 @@
+import { Page } from '@playwright/test';
+import { test, expect } from '../fixtures/test';
+import { query } from '../fixtures/db';
+import { createSnake } from '../fixtures/snakes';
+
+const SNAKE_URL = 'https://example.com/league';
+
+async function createLeague(page: Page, name: string) {
+  const response = await page.request.post('/api/leagues', { data: { name } });
+  expect(response.status()).toBe(201);
+  return response.json();
+}
+
+test.describe('League Leaderboards', () => {
+  test('rates snakes only from the league\'s games', async ({ authenticatedPage }) => {
+    const leagueName = `Class League ${Date.now()}`;
+    const league = await createLeague(authenticatedPage, leagueName);
+    const winner = await createSnake(authenticatedPage, 'League Winner', SNAKE_URL);
+    const loser = await createSnake(authenticatedPage, 'League Loser', SNAKE_URL);
+
+    const response = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [winner.id, loser.id], league_id: league.id },
+    });
+    expect(response.status()).toBe(201);
+
+    // Record a finished league game directly, with the winner first
+    const games = await query<{ game_id: string }>(
+      `INSERT INTO games (board_size, game_type, status, league_id)
+       VALUES ('7x7', 'Standard', 'finished', $1) RETURNING game_id`,
+      [league.id]
+    );
+    for (const [index, snakeId] of [winner.id, loser.id].entries()) {
+      await query(
+        'INSERT INTO game_battlesnakes (game_id, battlesnake_id, placement) VALUES ($1, $2, $3)',
+        [games[0].game_id, snakeId, index + 1]
+      );
+    }
+
+    const leaderboard = await (await authenticatedPage.request.get(`/api/leaderboard?league=${league.id}`)).json();
+    expect(leaderboard.league_id).toBe(league.id);
+    expect(leaderboard.entries.map((e: { snake_id: string }) => e.snake_id)).toEqual([winner.id, loser.id]);
+    expect(Math.round(leaderboard.entries[0].rating)).toBe(1516);
+
+    // The league's games don't touch the arena's leaderboard
+    const arena = await (await authenticatedPage.request.get('/api/leaderboard')).json();
+    expect(arena.league_id).toBeNull();
+    expect(arena.entries.some((e: { snake_id: string }) => e.snake_id === winner.id)).toBe(false);
+
+    await authenticatedPage.goto(`/leaderboard?league=${league.id}`);
+    await expect(authenticatedPage.getByRole('heading', { name: `Leaderboard: ${leagueName}` })).toBeVisible();
+    await expect(authenticatedPage.locator('#leaderboard tr', { hasText: winner.name })).toContainText('1516');
+    await expect(authenticatedPage.locator('#leaderboard-scope')).toContainText(leagueName);
+  });
+
+  test('league leaderboards are private to members', async ({ authenticatedPage, page }) => {
+    const league = await createLeague(authenticatedPage, `Private League ${Date.now()}`);
+
+    expect((await page.request.get(`/api/leaderboard?league=${league.id}`)).status()).toBe(404);
+    const response = await page.goto(`/leaderboard?league=${league.id}`);
+    expect(response?.status()).toBe(404);
+  });
+
+  test('owners add members by GitHub login', async ({ authenticatedPage, mockUser }) => {
+    const league = await createLeague(authenticatedPage, `Members League ${Date.now()}`);
+
+    const unknown = await authenticatedPage.request.post(`/api/leagues/${league.id}/members`, {
+      data: { github_login: `nobody_${Date.now()}` },
+    });
+    expect(unknown.status()).toBe(400);
+
+    const details = await (await authenticatedPage.request.get(`/api/leagues/${league.id}`)).json();
+    expect(details.members.map((m: { github_login: string }) => m.github_login)).toEqual([mockUser.login]);
+
+    const leagues = await (await authenticatedPage.request.get('/api/leagues')).json();
+    expect(leagues.map((l: { id: string }) => l.id)).toContain(league.id);
+  });
+
+  test('completing a league announces its standings and prizes', async ({ authenticatedPage }) => {
+    const leagueName = `Prize League ${Date.now()}`;
+    const league = await createLeague(authenticatedPage, leagueName);
+    const winner = await createSnake(authenticatedPage, 'Prize Winner', SNAKE_URL);
+    const loser = await createSnake(authenticatedPage, 'Prize Loser', SNAKE_URL);
+
+    const invalid = await authenticatedPage.request.put(`/api/leagues/${league.id}/awards`, {
+      data: { prizes: [{ place: 1, description: 'A' }, { place: 1, description: 'B' }] },
+    });
+    expect(invalid.status()).toBe(400);
+
+    const awards = await authenticatedPage.request.put(`/api/leagues/${league.id}/awards`, {
+      data: {
+        prizes: [{ place: 2, description: 'Stickers' }, { place: 1, description: ' A trophy ' }],
+        writeup: 'Thanks for playing',
+      },
+    });
+    expect(awards.status()).toBe(200);
+    const details = await awards.json();
+    expect(details.prizes.map((p: { description: string }) => p.description)).toEqual(['A trophy', 'Stickers']);
+    expect(details.completed_at).toBeNull();
+
+    expect((await authenticatedPage.request.get(`/api/leagues/${league.id}/announcement`)).status()).toBe(404);
+
+    const games = await query<{ game_id: string }>(
+      `INSERT INTO games (board_size, game_type, status, league_id)
+       VALUES ('7x7', 'Standard', 'finished', $1) RETURNING game_id`,
+      [league.id]
+    );
+    for (const [index, snakeId] of [winner.id, loser.id].entries()) {
+      await query(
+        'INSERT INTO game_battlesnakes (game_id, battlesnake_id, placement) VALUES ($1, $2, $3)',
+        [games[0].game_id, snakeId, index + 1]
+      );
+    }
+
+    const completed = await authenticatedPage.request.post(`/api/leagues/${league.id}/complete`);
+    expect(completed.status()).toBe(200);
+    const announcement = await completed.json();
+    expect(announcement.league_name).toBe(leagueName);
+    expect(announcement.writeup).toBe('Thanks for playing');
+    expect(announcement.standings.map((s: { snake_id: string }) => s.snake_id)).toEqual([winner.id, loser.id]);
+    expect(announcement.standings[0].prize).toBe('A trophy');
+    expect(announcement.standings[1].prize).toBe('Stickers');
+    expect(announcement.games).toHaveLength(1);
+    expect(announcement.games[0].winners).toEqual([winner.name]);
+    expect(announcement.highlights[0]).toContain(`${winner.name} wins the league`);
+
+    // Completed leagues are frozen
+    expect((await authenticatedPage.request.post(`/api/leagues/${league.id}/complete`)).status()).toBe(409);
+    const lateAwards = await authenticatedPage.request.put(`/api/leagues/${league.id}/awards`, {
+      data: { prizes: [] },
+    });
+    expect(lateAwards.status()).toBe(409);
+    const lateGame = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [winner.id, loser.id], league_id: league.id },
+    });
+    expect(lateGame.status()).toBe(409);
+
+    const stored = await (await authenticatedPage.request.get(`/api/leagues/${league.id}/announcement`)).json();
+    expect(stored).toEqual(announcement);
+
+    await authenticatedPage.goto(`/leaderboard?league=${league.id}`);
+    await authenticatedPage.locator('#league-completed a').click();
+    await expect(authenticatedPage.getByRole('heading', { name: `${leagueName}: Final Standings` })).toBeVisible();
+    await expect(authenticatedPage.locator('#league-writeup')).toContainText('Thanks for playing');
+    await expect(authenticatedPage.locator('#league-standings tr', { hasText: winner.name })).toContainText('A trophy');
+    await expect(authenticatedPage.locator('#league-games tr', { hasText: 'Game 1' })).toContainText(winner.name);
+  });
+
+  test('league announcements are private to members', async ({ authenticatedPage, page }) => {
+    const league = await createLeague(authenticatedPage, `Private Prize League ${Date.now()}`);
+    expect((await authenticatedPage.request.post(`/api/leagues/${league.id}/complete`)).status()).toBe(200);
+
+    expect((await page.request.get(`/api/leagues/${league.id}/announcement`)).status()).toBe(401);
+  });
+});
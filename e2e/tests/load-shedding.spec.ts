@@ -0,0 +1,14 @@
+import { test, expect } from '../fixtures/test';
+
+test.describe('Load shedding', () => {
+  test('serves sheddable endpoints while the database is healthy', async ({ authenticatedPage }) => {
+    const response = await authenticatedPage.request.get('/api/leaderboard');
+    expect(response.status()).toBe(200);
+    expect(response.headers()['retry-after']).toBeUndefined();
+  });
+
+  test('hides the load status when no admin token is configured', async ({ authenticatedPage }) => {
+    const status = await authenticatedPage.request.get('/api/v1/admin/load');
+    expect(status.status()).toBe(404);
+  });
+});
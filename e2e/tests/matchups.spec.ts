@@ -0,0 +1,59 @@
+import { test, expect } from '../fixtures/test';
+import { addFrames, finishedGame } from '../fixtures/games';
+import { createSnake } from '../fixtures/snakes';
+
+const SNAKE_URL = 'https://example.com/matchup';
+
+/**
+ * Record a game between snakes with the given placements, ending on turn `turns`.
+ */
+async function matchupGame(placements: [string, number][], turns: number, status = 'finished'): Promise<string> {
+  const gameId = await finishedGame({ status, placements });
+  await addFrames(gameId, [{}], turns);
+  return gameId;
+}
+
+test.describe('Head-to-head matchups', () => {
+  test('counts wins, losses and draws between two snakes', async ({ authenticatedPage }) => {
+    const a = (await createSnake(authenticatedPage, 'Matchup A', SNAKE_URL)).id;
+    const b = (await createSnake(authenticatedPage, 'Matchup B', SNAKE_URL)).id;
+    const other = (await createSnake(authenticatedPage, 'Matchup Other', SNAKE_URL)).id;
+
+    await matchupGame([[a, 1], [b, 2]], 30);
+    await matchupGame([[a, 2], [b, 1], [other, 3]], 50);
+    await matchupGame([[a, 1], [b, 2]], 20);
+    const latest = await matchupGame([[a, 1], [b, 1]], 10);
+    // Games without both snakes, and unfinished ones, don't count
+    await matchupGame([[a, 1], [other, 2]], 99);
+    await matchupGame([[a, 1], [b, 2]], 99, 'running');
+
+    const response = await authenticatedPage.request.get(`/api/matchups?snake_a=${a}&snake_b=${b}`);
+    expect(response.status()).toBe(200);
+    const matchup = await response.json();
+    expect(matchup.games).toBe(4);
+    expect([matchup.wins, matchup.losses, matchup.draws]).toEqual([2, 1, 1]);
+    expect(matchup.average_turns).toBe(27.5);
+    expect(matchup.recent_game_ids).toHaveLength(4);
+    expect(matchup.recent_game_ids[0]).toBe(latest);
+
+    // The other way round, wins and losses swap
+    const reversed = await (await authenticatedPage.request.get(`/api/matchups?snake_a=${b}&snake_b=${a}`)).json();
+    expect([reversed.wins, reversed.losses, reversed.draws]).toEqual([1, 2, 1]);
+  });
+
+  test('rejects comparing a snake with itself', async ({ authenticatedPage }) => {
+    const a = (await createSnake(authenticatedPage, 'Matchup Self', SNAKE_URL)).id;
+
+    const response = await authenticatedPage.request.get(`/api/matchups?snake_a=${a}&snake_b=${a}`);
+    expect(response.status()).toBe(400);
+  });
+
+  test('rejects snakes the caller cannot see', async ({ authenticatedPage }) => {
+    const a = (await createSnake(authenticatedPage, 'Matchup Mine', SNAKE_URL)).id;
+    const missing = '00000000-0000-4000-8000-000000000000';
+
+    const response = await authenticatedPage.request.get(`/api/matchups?snake_a=${a}&snake_b=${missing}`);
+    expect(response.status()).toBe(400);
+    expect(await response.text()).toContain(`Snake ${missing} not found or not accessible`);
+  });
+});
@@ -0,0 +1,56 @@
+import { test, expect } from '../fixtures/test';
+import { finishedGame } from '../fixtures/games';
+
+test.describe('Mobile game pages', () => {
+  test.use({ viewport: { width: 375, height: 667 } });
+
+  test('fits the board and replay controls on a phone screen', async ({ authenticatedPage }) => {
+    const gameId = await finishedGame();
+    await authenticatedPage.goto(`/games/${gameId}`);
+
+    await expect(authenticatedPage.locator('meta[name="viewport"]')).toHaveAttribute('content', /width=device-width/);
+
+    const board = authenticatedPage.locator('.board-viewer-container');
+    const box = await board.boundingBox();
+    expect(box!.width).toBeLessThanOrEqual(375);
+    expect(Math.round(box!.height)).toBe(Math.round(box!.width));
+
+    const restart = authenticatedPage.locator('#replay-controls button[data-action="restart"]');
+    await expect(restart).toBeEnabled();
+    expect((await restart.boundingBox())!.height).toBeGreaterThanOrEqual(44);
+
+    // The page doesn't scroll sideways
+    const overflow = await authenticatedPage.evaluate(
+      () => document.documentElement.scrollWidth - document.documentElement.clientWidth
+    );
+    expect(overflow).toBeLessThanOrEqual(0);
+  });
+
+  test('changing speed reloads the board at the new rate', async ({ authenticatedPage }) => {
+    const gameId = await finishedGame();
+    await authenticatedPage.goto(`/games/${gameId}`);
+
+    const fps = async () =>
+      Number(new URL((await authenticatedPage.locator('#board-viewer').getAttribute('src'))!).searchParams.get('fps'));
+    const before = await fps();
+    await authenticatedPage.locator('#replay-controls button[data-action="faster"]').click();
+    expect(await fps()).toBe(before * 2);
+  });
+
+  test('waits to load the board when the browser asks to save data', async ({ authenticatedPage }) => {
+    const gameId = await finishedGame();
+    await authenticatedPage.setExtraHTTPHeaders({ 'Save-Data': 'on' });
+    await authenticatedPage.goto(`/games/${gameId}`);
+
+    await expect(authenticatedPage.locator('body')).toHaveAttribute('data-reduced-data', '');
+    await expect(authenticatedPage.locator('#reduced-data-note')).toBeVisible();
+    const viewer = authenticatedPage.locator('#board-viewer');
+    await expect(viewer).not.toHaveAttribute('src', /.*/);
+    await expect(authenticatedPage.locator('#replay-controls button[data-action="restart"]')).toBeDisabled();
+
+    await authenticatedPage.locator('#load-board').click();
+    await expect(viewer).toHaveAttribute('src', /board\.battlesnake\.com/);
+    await expect(authenticatedPage.locator('#load-board')).toBeHidden();
+    await expect(authenticatedPage.locator('#replay-controls button[data-action="restart"]')).toBeEnabled();
+  });
+});
@@ -0,0 +1,75 @@
+import { test, expect } from '../fixtures/test';
+import { query } from '../fixtures/db';
+
+async function notifyUser(login: string, title: string, link: string | null) {
+  await query(
+    `INSERT INTO notifications (user_id, kind, title, body, link)
+     SELECT user_id, 'game_finished', $2, 'Something happened', $3 FROM users WHERE github_login = $1`,
+    [login, title, link]
+  );
+}
+
+test.describe('Notifications', () => {
+  test('the bell shows unread notifications and opening one marks it read', async ({ authenticatedPage, mockUser }) => {
+    const league = await (await authenticatedPage.request.post('/api/leagues', {
+      data: { name: `Bell League ${Date.now()}` },
+    })).json();
+    await notifyUser(mockUser.login, 'First news', null);
+    await notifyUser(mockUser.login, 'League news', `/leaderboard?league=${league.id}`);
+
+    await authenticatedPage.goto('/');
+    await expect(authenticatedPage.locator('#notification-count')).toHaveText('2');
+    await authenticatedPage.locator('#notification-bell summary').click();
+    await authenticatedPage.locator('#notification-bell a', { hasText: 'League news' }).click();
+    await expect(authenticatedPage).toHaveURL(new RegExp(`/leaderboard\\?league=${league.id}$`));
+    await expect(authenticatedPage.locator('#notification-count')).toHaveText('1');
+
+    await authenticatedPage.goto('/notifications');
+    await expect(authenticatedPage.getByRole('heading', { name: 'Notifications' })).toBeVisible();
+    await expect(authenticatedPage.locator('#notifications tr.unread')).toHaveCount(1);
+    await authenticatedPage.getByRole('button', { name: 'Mark all as read' }).first().click();
+    await expect(authenticatedPage.locator('#notifications tr.unread')).toHaveCount(0);
+    await expect(authenticatedPage.locator('#notification-count')).toHaveCount(0);
+  });
+
+  test('the API lists notifications and marks them read', async ({ authenticatedPage, mockUser }) => {
+    await notifyUser(mockUser.login, 'API news', '/games');
+
+    const listed = await (await authenticatedPage.request.get('/api/notifications?unread=true')).json();
+    expect(listed.unread_count).toBe(1);
+    expect(listed.notifications.map((n: { title: string }) => n.title)).toEqual(['API news']);
+    const id = listed.notifications[0].notification_id;
+
+    const read = await authenticatedPage.request.post(`/api/notifications/${id}/read`);
+    expect(read.status()).toBe(200);
+    expect((await read.json()).read_at).not.toBeNull();
+    const missing = await authenticatedPage.request.post('/api/notifications/00000000-0000-0000-0000-000000000000/read');
+    expect(missing.status()).toBe(404);
+
+    await notifyUser(mockUser.login, 'More news', null);
+    const all = await (await authenticatedPage.request.post('/api/notifications/read')).json();
+    expect(all.marked).toBe(1);
+    const after = await (await authenticatedPage.request.get('/api/notifications')).json();
+    expect(after.unread_count).toBe(0);
+    expect(after.notifications).toHaveLength(2);
+
+    expect((await authenticatedPage.request.get('/api/notifications?limit=0')).status()).toBe(400);
+  });
+
+  test('completing a league notifies its members', async ({ authenticatedPage }) => {
+    const leagueName = `Notified League ${Date.now()}`;
+    const league = await (await authenticatedPage.request.post('/api/leagues', {
+      data: { name: leagueName },
+    })).json();
+    expect((await authenticatedPage.request.post(`/api/leagues/${league.id}/complete`)).status()).toBe(200);
+
+    const listed = await (await authenticatedPage.request.get('/api/notifications')).json();
+    const notification = listed.notifications.find((n: { kind: string }) => n.kind === 'league_completed');
+    expect(notification.title).toBe(`${leagueName} is over`);
+    expect(notification.link).toBe(`/leagues/${league.id}/announcement`);
+  });
+
+  test('notifications need a login', async ({ page }) => {
+    expect((await page.request.get('/api/notifications')).status()).toBe(401);
+  });
+});
@@ -0,0 +1,58 @@
+import { test, expect } from '../fixtures/test';
+import { MOCK_SNAKES_URL } from '../fixtures/snakes';
+
+test.describe('User preferences', () => {
+  test('games created without a board use the preferred board size', async ({ authenticatedPage }) => {
+    const defaults = await (await authenticatedPage.request.get('/api/v1/me/preferences')).json();
+    expect(defaults.board).toBe('11x11');
+    expect(defaults.theme).toBe('light');
+
+    const update = await authenticatedPage.request.put('/api/v1/me/preferences', {
+      data: { board: '7x7', game_type: 'constrictor' },
+    });
+    expect(update.status()).toBe(200);
+    expect((await update.json()).board).toBe('7x7');
+
+    const snakeIds: string[] = [];
+    for (const behavior of ['up', 'down']) {
+      const response = await authenticatedPage.request.post('/api/v1/snakes', {
+        data: { name: `Prefs ${behavior} ${Date.now()}`, url: `${MOCK_SNAKES_URL}/${behavior}` },
+      });
+      snakeIds.push((await response.json()).id);
+    }
+
+    const createResponse = await authenticatedPage.request.post('/api/v1/games', {
+      data: { snakes: snakeIds },
+    });
+    const { id: gameId } = await createResponse.json();
+
+    const game = await (await authenticatedPage.request.get(`/api/v1/games/${gameId}/details`)).json();
+    expect(game.board).toBe('7x7');
+    expect(game.game_type.toLowerCase()).toBe('constrictor');
+  });
+
+  test('rejects invalid values', async ({ authenticatedPage }) => {
+    const theme = await authenticatedPage.request.put('/api/v1/me/preferences', {
+      data: { theme: 'sepia' },
+    });
+    expect(theme.status()).toBe(400);
+
+    const timezone = await authenticatedPage.request.put('/api/v1/me/preferences', {
+      data: { timezone: 'Mars/Olympus_Mons' },
+    });
+    expect(timezone.status()).toBe(400);
+  });
+
+  test('settings page saves the theme', async ({ authenticatedPage }) => {
+    await authenticatedPage.goto('/settings');
+    await authenticatedPage.selectOption('#theme', 'dark');
+    await authenticatedPage.check('#notify_email');
+    await authenticatedPage.click('button[type="submit"]');
+
+    await expect(authenticatedPage.locator('.alert-success')).toContainText('Settings saved');
+    await expect(authenticatedPage.locator('body')).toHaveAttribute('data-theme', 'dark');
+
+    const preferences = await (await authenticatedPage.request.get('/api/v1/me/preferences')).json();
+    expect(preferences.notification_channels).toEqual(['email']);
+  });
+});
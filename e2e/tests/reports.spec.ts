@@ -0,0 +1,65 @@
+import { test, expect } from '../fixtures/test';
+import { query } from '../fixtures/db';
+
+test.describe('CSV reports', () => {
+  test('games CSV has a row per game with placements, turns and death causes', async ({ authenticatedPage }) => {
+    const snakeName = `Report Snake ${Date.now()}`;
+
+    await authenticatedPage.goto('/battlesnakes/new');
+    await authenticatedPage.getByLabel('Name').fill(snakeName);
+    await authenticatedPage.getByLabel('URL').fill('https://example.com/report');
+    await authenticatedPage.getByLabel('Visibility').selectOption('public');
+    await authenticatedPage.getByRole('button', { name: 'Create Battlesnake' }).click();
+
+    const snakes = await query<{ battlesnake_id: string }>(
+      'SELECT battlesnake_id FROM battlesnakes WHERE name = $1',
+      [snakeName]
+    );
+
+    // A finished two-turn game where the snake ran into a wall
+    const games = await query<{ game_id: string }>(
+      `INSERT INTO games (board_size, game_type, status)
+       VALUES ('7x7', 'Standard', 'finished') RETURNING game_id`
+    );
+    const gameId = games[0].game_id;
+    const entries = await query<{ game_battlesnake_id: string }>(
+      `INSERT INTO game_battlesnakes (game_id, battlesnake_id, placement)
+       VALUES ($1, $2, 1) RETURNING game_battlesnake_id`,
+      [gameId, snakes[0].battlesnake_id]
+    );
+    const snakeId = entries[0].game_battlesnake_id;
+    for (const turn of [0, 1, 2]) {
+      const death = turn === 2 ? { Cause: 'wall-collision', Turn: 2, EliminatedBy: '' } : null;
+      await query(
+        'INSERT INTO turns (game_id, turn_number, frame_data) VALUES ($1, $2, $3)',
+        [gameId, turn, { Turn: turn, Snakes: [{ ID: snakeId, Name: snakeName, Death: death }], Food: [], Hazards: [] }]
+      );
+    }
+
+    const response = await authenticatedPage.request.get('/api/me/games.csv');
+    expect(response.status()).toBe(200);
+    expect(response.headers()['content-type']).toContain('text/csv');
+
+    const lines = (await response.text()).trim().split('\n');
+    expect(lines[0]).toBe('game_id,created_at,board_size,game_type,status,turns,duration_ms,snakes,placements,death_causes');
+
+    const row = lines.find((line) => line.startsWith(gameId))!;
+    expect(row).toContain(',7x7,Standard,finished,2,');
+    expect(row.endsWith(`,${snakeName},1,wall-collision`)).toBe(true);
+  });
+
+  test('games CSV only includes games with your snakes', async ({ authenticatedPage }) => {
+    const games = await query<{ game_id: string }>(
+      `INSERT INTO games (board_size, game_type, status)
+       VALUES ('7x7', 'Standard', 'finished') RETURNING game_id`
+    );
+
+    const response = await authenticatedPage.request.get('/api/me/games.csv');
+    expect(await response.text()).not.toContain(games[0].game_id);
+  });
+
+  test('requires authentication', async ({ page }) => {
+    const response = await page.request.get('/api/me/games.csv');
+    expect(response.status()).toBe(401);
+  });
+});
@@ -0,0 +1,53 @@
+import { test, expect } from '../fixtures/test';
+import { createSnake } from '../fixtures/snakes';
+
+// A ruleset that never changes anything:
+// (module
+//   (memory (export "memory") 1)
+//   (data (i32.const 0) "{}")
+//   (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+//   (func (export "apply") (param i32 i32) (result i64) (i64.const 2)))
+const NOOP_RULESET =
+  'AGFzbQEAAAABDAJgAX8Bf2ACf38BfgMDAgABBQMBAAEHGgMGbWVtb3J5AgAFYWxsb2MAAAVhcHBseQABCgwCBQBBgAgLBABCAgsLCAEAQQALAnt9';
+
+test.describe('Rulesets', () => {
+  test('uploads a ruleset and plays a casual game with it', async ({ authenticatedPage }) => {
+    const name = `noop ${Date.now()}`;
+    const upload = await authenticatedPage.request.post('/api/rulesets', {
+      data: { name, wasm: NOOP_RULESET },
+    });
+    expect(upload.status()).toBe(201);
+    const ruleset = await upload.json();
+
+    const listed = await (await authenticatedPage.request.get('/api/rulesets')).json();
+    expect(listed.map((r: { id: string }) => r.id)).toContain(ruleset.id);
+
+    const duplicate = await authenticatedPage.request.post('/api/rulesets', {
+      data: { name, wasm: NOOP_RULESET },
+    });
+    expect(duplicate.status()).toBe(409);
+
+    const snake = await createSnake(authenticatedPage, 'Ruleset Snake');
+    const game = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [snake.id], ruleset_id: ruleset.id },
+    });
+    expect(game.status()).toBe(201);
+
+    const ranked = await authenticatedPage.request.post('/api/games', {
+      data: { snakes: [snake.id], ruleset_id: ruleset.id, ranked: true },
+    });
+    expect(ranked.status()).toBe(400);
+  });
+
+  test('rejects modules that are not rulesets', async ({ authenticatedPage }) => {
+    const notWasm = await authenticatedPage.request.post('/api/rulesets', {
+      data: { name: `not wasm ${Date.now()}`, wasm: Buffer.from('not wasm').toString('base64') },
+    });
+    expect(notWasm.status()).toBe(400);
+
+    const notBase64 = await authenticatedPage.request.post('/api/rulesets', {
+      data: { name: `not base64 ${Date.now()}`, wasm: '!!!' },
+    });
+    expect(notBase64.status()).toBe(400);
+  });
+});
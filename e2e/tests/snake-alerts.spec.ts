@@ -0,0 +1,43 @@
+import { test, expect } from '../fixtures/test';
+import { MOCK_SNAKES_URL } from '../fixtures/snakes';
+
+test.describe('Win rate alerts', () => {
+  test('creates, lists and deletes an alert', async ({ authenticatedPage }) => {
+    const snake = await (await authenticatedPage.request.post('/api/snakes', {
+      data: { name: `Alerted ${Date.now()}`, url: `${MOCK_SNAKES_URL}/up`, is_public: true },
+    })).json();
+
+    const createResponse = await authenticatedPage.request.post(`/api/snakes/${snake.id}/alerts`, {
+      data: { window_games: 20, drop_percent: 10 },
+    });
+    expect(createResponse.status()).toBe(201);
+    const alert = await createResponse.json();
+    expect(alert.snake_id).toBe(snake.id);
+    expect(alert.window_games).toBe(20);
+    expect(alert.drop_percent).toBe(10);
+    expect(alert.firing).toBe(false);
+
+    const list = await (await authenticatedPage.request.get(`/api/snakes/${snake.id}/alerts`)).json();
+    expect(list.alerts.map((a: { id: string }) => a.id)).toEqual([alert.id]);
+    expect(list.events).toEqual([]);
+
+    await authenticatedPage.goto(`/battlesnakes/${snake.id}/profile`);
+    await expect(authenticatedPage.locator('#win-rate-alerts')).toContainText('last 20 games');
+
+    const deleteResponse = await authenticatedPage.request.delete(`/api/alerts/${alert.id}`);
+    expect(deleteResponse.status()).toBe(204);
+    const after = await (await authenticatedPage.request.get(`/api/snakes/${snake.id}/alerts`)).json();
+    expect(after.alerts).toEqual([]);
+  });
+
+  test('rejects invalid thresholds', async ({ authenticatedPage }) => {
+    const snake = await (await authenticatedPage.request.post('/api/snakes', {
+      data: { name: `Alerted ${Date.now()}`, url: `${MOCK_SNAKES_URL}/up`, is_public: true },
+    })).json();
+
+    for (const data of [{ window_games: 1 }, { drop_percent: 0 }, { drop_percent: 150 }]) {
+      const response = await authenticatedPage.request.post(`/api/snakes/${snake.id}/alerts`, { data });
+      expect(response.status()).toBe(400);
+    }
+  });
+});
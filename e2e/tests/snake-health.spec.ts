@@ -0,0 +1,42 @@
+import { test, expect } from '../fixtures/test';
+import { query } from '../fixtures/db';
+import { createSnake } from '../fixtures/snakes';
+
+const SNAKE_URL = 'https://example.com/health';
+
+/**
+ * Record a health check directly, as the cron job would.
+ */
+async function recordHealth(snakeId: string, healthy: boolean, error: string | null) {
+  await query(
+    `INSERT INTO snake_health (battlesnake_id, healthy, status_code, latency_ms, error)
+     VALUES ($1, $2, $3, $4, $5)`,
+    [snakeId, healthy, healthy ? 200 : 500, 42, error]
+  );
+}
+
+test.describe('Snake health checks', () => {
+  test('shows each snake\'s latest check on the list and in the API', async ({ authenticatedPage }) => {
+    const up = await createSnake(authenticatedPage, 'Health Up', SNAKE_URL);
+    const down = await createSnake(authenticatedPage, 'Health Down', SNAKE_URL);
+    const unchecked = await createSnake(authenticatedPage, 'Health Unchecked', SNAKE_URL);
+    await recordHealth(up.id, true, null);
+    await recordHealth(down.id, false, 'GET / returned 500');
+
+    await authenticatedPage.goto('/battlesnakes');
+    await expect(authenticatedPage.locator('tr', { hasText: up.name }).locator('.snake-health')).toHaveText('● Healthy');
+    const downBadge = authenticatedPage.locator('tr', { hasText: down.name }).locator('.snake-health .badge');
+    await expect(downBadge).toHaveText('● Down');
+    await expect(downBadge).toHaveAttribute('title', /GET \/ returned 500/);
+    await expect(authenticatedPage.locator('tr', { hasText: unchecked.name }).locator('.snake-health')).toHaveText('Not checked yet');
+
+    const snakes = await (await authenticatedPage.request.get('/api/snakes')).json();
+    const byId = new Map(snakes.map((s: { id: string }) => [s.id, s]));
+    expect(byId.get(up.id).health).toMatchObject({ healthy: true, status_code: 200, latency_ms: 42, error: null });
+    expect(byId.get(down.id).health).toMatchObject({ healthy: false, error: 'GET / returned 500' });
+    expect(byId.get(unchecked.id).health).toBeNull();
+
+    const single = await (await authenticatedPage.request.get(`/api/snakes/${down.id}`)).json();
+    expect(single.health.healthy).toBe(false);
+  });
+});
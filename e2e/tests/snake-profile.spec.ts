@@ -0,0 +1,79 @@
+import { test, expect } from '../fixtures/test';
+
+test.describe('Snake profiles', () => {
+  test('descriptions render as markdown and links show on the profile', async ({ authenticatedPage }) => {
+    const created = await authenticatedPage.request.post('/api/v1/snakes', {
+      data: {
+        name: `Profile Snake ${Date.now()}`,
+        url: 'bot:random',
+        is_public: true,
+        description: 'Goes for **food**, then hides.\n\n- Flood fill\n- Minimax\n\n<script>window.pwned = true</script>',
+        homepage_url: 'https://snake.example.com',
+        source_url: 'https://github.com/example/snake',
+      },
+    });
+    expect(created.status()).toBe(201);
+    const snake = await created.json();
+    expect(snake.homepage_url).toBe('https://snake.example.com');
+
+    await authenticatedPage.goto(`/battlesnakes/${snake.id}/profile`);
+
+    const description = authenticatedPage.locator('#snake-description');
+    await expect(description.locator('strong')).toHaveText('food');
+    await expect(description.locator('li')).toHaveText(['Flood fill', 'Minimax']);
+    // Raw HTML is shown as text, never run
+    await expect(description).toContainText('<script>window.pwned = true</script>');
+    await expect(description.locator('script')).toHaveCount(0);
+
+    const links = authenticatedPage.locator('#snake-links');
+    await expect(links.getByRole('link', { name: 'Homepage' })).toHaveAttribute('href', 'https://snake.example.com');
+    await expect(links.getByRole('link', { name: 'Source code' })).toHaveAttribute('rel', 'nofollow ugc noopener');
+  });
+
+  test('updates keep fields left out and clear empty ones', async ({ authenticatedPage }) => {
+    const snake = await (await authenticatedPage.request.post('/api/v1/snakes', {
+      data: {
+        name: `Profile Update Snake ${Date.now()}`,
+        url: 'bot:random',
+        description: 'Hungry',
+        source_url: 'https://github.com/example/snake',
+      },
+    })).json();
+
+    const updated = await (await authenticatedPage.request.put(`/api/v1/snakes/${snake.id}`, {
+      data: { source_url: '' },
+    })).json();
+    expect(updated.description).toBe('Hungry');
+    expect(updated.source_url).toBeNull();
+  });
+
+  test('links must be http or https', async ({ authenticatedPage }) => {
+    const response = await authenticatedPage.request.post('/api/v1/snakes', {
+      data: {
+        name: `Bad Link Snake ${Date.now()}`,
+        url: 'bot:random',
+        homepage_url: 'javascript:alert(1)',
+      },
+    });
+    expect(response.status()).toBe(400);
+  });
+
+  test('the edit form saves the profile', async ({ authenticatedPage }) => {
+    const snake = await (await authenticatedPage.request.post('/api/v1/snakes', {
+      data: { name: `Profile Form Snake ${Date.now()}`, url: 'bot:random' },
+    })).json();
+
+    await authenticatedPage.goto(`/battlesnakes/${snake.id}/edit`);
+    await authenticatedPage.getByLabel('Description').fill('A *careful* snake');
+    await authenticatedPage.getByLabel('Homepage').fill('https://snake.example.com/careful');
+    await authenticatedPage.getByRole('button', { name: 'Update Battlesnake' }).click();
+    await expect(authenticatedPage).toHaveURL('/battlesnakes');
+
+    await authenticatedPage.goto(`/battlesnakes/${snake.id}/edit`);
+    await expect(authenticatedPage.getByLabel('Description')).toHaveValue('A *careful* snake');
+    await expect(authenticatedPage.getByLabel('Homepage')).toHaveValue('https://snake.example.com/careful');
+
+    await authenticatedPage.goto(`/battlesnakes/${snake.id}/profile`);
+    await expect(authenticatedPage.locator('#snake-description em')).toHaveText('careful');
+  });
+});
@@ -0,0 +1,57 @@
+import { test, expect } from '../fixtures/test';
+
+test.describe('Snake rosters', () => {
+  test('import syncs snakes to the roster and export round-trips', async ({ authenticatedPage }) => {
+    const suffix = Date.now();
+    const roster = [
+      { name: `main ${suffix}`, url: 'https://snake.example.com/main', is_public: true },
+      { name: `branch ${suffix}`, url: 'bot:random' },
+    ];
+
+    const dryRun = await authenticatedPage.request.post('/api/v1/snakes/import', {
+      data: { snakes: roster, dry_run: true },
+    });
+    expect(dryRun.status()).toBe(200);
+    const planned = await dryRun.json();
+    expect(planned.created).toBe(2);
+
+    // Nothing was created by the dry run
+    let exported = await (await authenticatedPage.request.get('/api/v1/snakes/export')).json();
+    expect(exported.snakes.map((s: { name: string }) => s.name)).not.toContain(`main ${suffix}`);
+
+    const imported = await (await authenticatedPage.request.post('/api/v1/snakes/import', {
+      data: { snakes: roster },
+    })).json();
+    expect(imported.created).toBe(2);
+
+    // Changing one URL updates just that snake
+    roster[1].url = 'bot:food';
+    const updated = await (await authenticatedPage.request.post('/api/v1/snakes/import', {
+      data: { snakes: roster, prune: true },
+    })).json();
+    expect(updated.created).toBe(0);
+    expect(updated.updated).toBe(1);
+    const change = updated.changes.find((c: { name: string }) => c.name === `branch ${suffix}`);
+    expect(change.previous_url).toBe('bot:random');
+
+    exported = await (await authenticatedPage.request.get('/api/v1/snakes/export')).json();
+    expect(exported.snakes).toEqual(
+      expect.arrayContaining([
+        { name: `main ${suffix}`, url: 'https://snake.example.com/main', is_public: true },
+        { name: `branch ${suffix}`, url: 'bot:food', is_public: false },
+      ])
+    );
+  });
+
+  test('rejects rosters listing a name twice', async ({ authenticatedPage }) => {
+    const response = await authenticatedPage.request.post('/api/v1/snakes/import', {
+      data: {
+        snakes: [
+          { name: 'twice', url: 'bot:food' },
+          { name: 'twice', url: 'bot:random' },
+        ],
+      },
+    });
+    expect(response.status()).toBe(400);
+  });
+});
@@ -0,0 +1,64 @@
+import { test, expect } from '../fixtures/test';
+
+test.describe('Snake tags', () => {
+  test('tags are normalized and can be added and removed', async ({ authenticatedPage }) => {
+    const snake = await (await authenticatedPage.request.post('/api/v1/snakes', {
+      data: { name: `Tagged Snake ${Date.now()}`, url: 'bot:random', is_public: true },
+    })).json();
+
+    const set = await authenticatedPage.request.put(`/api/v1/snakes/${snake.id}/tags`, {
+      data: { tags: ['Tree Search', 'Rust', 'rust'] },
+    });
+    expect(set.status()).toBe(200);
+    expect((await set.json()).tags).toEqual(['rust', 'tree-search']);
+
+    const added = await authenticatedPage.request.post(`/api/v1/snakes/${snake.id}/tags`, {
+      data: { tag: 'ML_based' },
+    });
+    expect(added.status()).toBe(201);
+    expect((await added.json()).tags).toEqual(['ml-based', 'rust', 'tree-search']);
+
+    const removed = await authenticatedPage.request.delete(`/api/v1/snakes/${snake.id}/tags/rust`);
+    expect(removed.status()).toBe(204);
+    const missing = await authenticatedPage.request.delete(`/api/v1/snakes/${snake.id}/tags/rust`);
+    expect(missing.status()).toBe(404);
+
+    await authenticatedPage.goto(`/battlesnakes/${snake.id}/profile`);
+    await expect(authenticatedPage.locator('#snake-tags')).toContainText('tree-search');
+  });
+
+  test('invalid tags are rejected', async ({ authenticatedPage }) => {
+    const snake = await (await authenticatedPage.request.post('/api/v1/snakes', {
+      data: { name: `Bad Tag Snake ${Date.now()}`, url: 'bot:random' },
+    })).json();
+
+    const response = await authenticatedPage.request.put(`/api/v1/snakes/${snake.id}/tags`, {
+      data: { tags: ['<script>'] },
+    });
+    expect(response.status()).toBe(400);
+  });
+
+  test('the public directory filters by tag', async ({ authenticatedPage }) => {
+    const tag = `tag-${Date.now()}`;
+    const tagged = await (await authenticatedPage.request.post('/api/v1/snakes', {
+      data: { name: `Directory Snake ${Date.now()}`, url: 'bot:random', is_public: true },
+    })).json();
+    await authenticatedPage.request.put(`/api/v1/snakes/${tagged.id}/tags`, {
+      data: { tags: [tag, 'rust'] },
+    });
+
+    const response = await authenticatedPage.request.get(`/api/v1/snakes/public?tags=${tag},rust`);
+    expect(response.status()).toBe(200);
+    const snakes = await response.json();
+    expect(snakes.map((s: { id: string }) => s.id)).toEqual([tagged.id]);
+    expect(snakes[0].tags).toEqual(['rust', tag].sort());
+  });
+
+  test('preferences accept preferred opponent tags', async ({ authenticatedPage }) => {
+    const response = await authenticatedPage.request.put('/api/v1/me/preferences', {
+      data: { preferred_opponent_tags: ['Tree Search'] },
+    });
+    expect(response.status()).toBe(200);
+    expect((await response.json()).preferred_opponent_tags).toEqual(['tree-search']);
+  });
+});
@@ -0,0 +1,47 @@
+import { test, expect } from '../fixtures/test';
+
+test.describe('Snake templates', () => {
+  test('instantiating a tag creates one snake per tag', async ({ authenticatedPage }) => {
+    const name = `templated ${Date.now()}`;
+    const createResponse = await authenticatedPage.request.post('/api/v1/snake-templates', {
+      data: { name, url_template: 'https://snake.example.com/{tag}' },
+    });
+    expect(createResponse.status()).toBe(201);
+    const template = await createResponse.json();
+    expect(template.archive_after_days).toBe(14);
+
+    const first = await authenticatedPage.request.post(`/api/v1/snake-templates/${template.id}/variants`, {
+      data: { tag: 'v1.0.0' },
+    });
+    expect(first.status()).toBe(201);
+    const variant = await first.json();
+    expect(variant.snake.name).toBe(`${name}@v1.0.0`);
+    expect(variant.snake.url).toBe('https://snake.example.com/v1.0.0');
+
+    // The same tag again gets the same snake
+    const again = await authenticatedPage.request.post(`/api/v1/snake-templates/${template.id}/variants`, {
+      data: { tag: 'v1.0.0' },
+    });
+    expect(again.status()).toBe(200);
+    expect((await again.json()).snake.id).toBe(variant.snake.id);
+
+    const shown = await (await authenticatedPage.request.get(`/api/v1/snake-templates/${template.id}`)).json();
+    expect(shown.variants).toHaveLength(1);
+    expect(shown.variants[0].archived_at).toBeNull();
+  });
+
+  test('rejects templates without a placeholder and invalid tags', async ({ authenticatedPage }) => {
+    const noPlaceholder = await authenticatedPage.request.post('/api/v1/snake-templates', {
+      data: { name: `no placeholder ${Date.now()}`, url_template: 'https://snake.example.com' },
+    });
+    expect(noPlaceholder.status()).toBe(400);
+
+    const template = await (await authenticatedPage.request.post('/api/v1/snake-templates', {
+      data: { name: `tags ${Date.now()}`, url_template: 'https://snake.example.com/{tag}' },
+    })).json();
+    const badTag = await authenticatedPage.request.post(`/api/v1/snake-templates/${template.id}/variants`, {
+      data: { tag: 'release/1.0' },
+    });
+    expect(badTag.status()).toBe(400);
+  });
+});
@@ -0,0 +1,59 @@
+import { test, expect } from '../fixtures/test';
+import { createSnake, mockSnakeUrl } from '../fixtures/snakes';
+
+test.describe('Snake versions', () => {
+  test('attributes games to the version that played them', async ({ authenticatedPage }) => {
+    const page = authenticatedPage;
+    const snake = await createSnake(page, 'Versioned', mockSnakeUrl('up'));
+    const opponent = await createSnake(page, 'Versioned Opponent', mockSnakeUrl('down'));
+
+    const v1 = await page.request.post(`/api/snakes/${snake.id}/versions`, { data: { label: 'v1' } });
+    expect(v1.status()).toBe(201);
+    const first = await v1.json();
+    expect(first.url).toBe(snake.url);
+    expect(first.active).toBe(true);
+
+    const second = await (await page.request.post(`/api/snakes/${snake.id}/versions`, {
+      data: { label: 'v2', url: mockSnakeUrl('right') },
+    })).json();
+    expect(second.active).toBe(true);
+    expect((await (await page.request.get(`/api/snakes/${snake.id}`)).json()).url).toBe(mockSnakeUrl('right'));
+
+    const { id: gameId } = await (await page.request.post('/api/games', {
+      data: { snakes: [snake.id, opponent.id], board: '7x7' },
+    })).json();
+
+    const v2Games = await (await page.request.get(`/api/games?version_id=${second.id}`)).json();
+    expect(v2Games.map((g: { id: string }) => g.id)).toEqual([gameId]);
+    const v1Games = await (await page.request.get(`/api/games?version_id=${first.id}`)).json();
+    expect(v1Games).toEqual([]);
+
+    const versions = await (await page.request.get(`/api/snakes/${snake.id}/versions`)).json();
+    expect(versions.map((v: { label: string }) => v.label)).toEqual(['v2', 'v1']);
+    expect(versions.map((v: { active: boolean }) => v.active)).toEqual([true, false]);
+
+    // Rolling back points the snake at the old URL again
+    const rollback = await page.request.post(`/api/snakes/${snake.id}/versions/${first.id}/activate`);
+    expect(rollback.status()).toBe(204);
+    expect((await (await page.request.get(`/api/snakes/${snake.id}`)).json()).url).toBe(snake.url);
+  });
+
+  test('rejects a label the snake already has', async ({ authenticatedPage }) => {
+    const snake = await createSnake(authenticatedPage, 'Versioned Twice', mockSnakeUrl('up'));
+
+    await authenticatedPage.request.post(`/api/snakes/${snake.id}/versions`, { data: { label: 'v1' } });
+    const again = await authenticatedPage.request.post(`/api/snakes/${snake.id}/versions`, { data: { label: 'v1' } });
+    expect(again.status()).toBe(409);
+  });
+
+  test('rejects a version filter that does not match the snake', async ({ authenticatedPage }) => {
+    const snake = await createSnake(authenticatedPage, 'Versioned Mismatch', mockSnakeUrl('up'));
+    const other = await createSnake(authenticatedPage, 'Versioned Other', mockSnakeUrl('up'));
+    const version = await (await authenticatedPage.request.post(`/api/snakes/${snake.id}/versions`, {
+      data: { label: 'v1' },
+    })).json();
+
+    const response = await authenticatedPage.request.get(`/api/games?snake_id=${other.id}&version_id=${version.id}`);
+    expect(response.status()).toBe(400);
+  });
+});
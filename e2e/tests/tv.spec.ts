@@ -0,0 +1,28 @@
+import { test, expect } from '../fixtures/test';
+
+test.describe('Arena TV', () => {
+  test('the page is open to visitors who are not logged in', async ({ page }) => {
+    await page.goto('/');
+    await page.getByRole('link', { name: 'Watch Arena TV' }).click();
+
+    await expect(page).toHaveURL(/\/tv$/);
+    await expect(page.getByRole('heading', { name: 'Arena TV' })).toBeVisible();
+  });
+
+  test('the now playing feed lists running public games', async ({ page }) => {
+    const response = await page.request.get('/api/v1/tv');
+    expect(response.status()).toBe(200);
+
+    const tv = await response.json();
+    expect(tv.tv_url).toMatch(/\/tv$/);
+    expect(Array.isArray(tv.games)).toBe(true);
+    if (tv.now_playing) {
+      expect(tv.now_playing.game_id).toBe(tv.games[0].game_id);
+      expect(tv.now_playing.viewer_url).toContain('board.battlesnake.com');
+    } else {
+      expect(tv.games).toHaveLength(0);
+      await page.goto('/tv');
+      await expect(page.locator('#tv-off-air')).toBeVisible();
+    }
+  });
+});
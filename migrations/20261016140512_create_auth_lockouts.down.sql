@@ -0,0 +1,2 @@
+-- Drop the auth_lockouts table
+DROP TABLE IF EXISTS auth_lockouts;
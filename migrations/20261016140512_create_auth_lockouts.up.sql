@@ -0,0 +1,19 @@
+-- Track failed API token authentications per client IP / token prefix for brute-force lockouts
+CREATE TABLE auth_lockouts (
+    lockout_key TEXT PRIMARY KEY,
+    failure_count INTEGER NOT NULL DEFAULT 0,
+    lockout_count INTEGER NOT NULL DEFAULT 0,
+    first_failure_at TIMESTAMPTZ,
+    locked_until TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Index for finding active lockouts
+CREATE INDEX idx_auth_lockouts_locked_until ON auth_lockouts(locked_until);
+
+-- Create a trigger to automatically update the updated_at column
+CREATE TRIGGER update_auth_lockouts_updated_at
+  BEFORE UPDATE ON auth_lockouts
+  FOR EACH ROW
+  EXECUTE FUNCTION update_updated_at_column();
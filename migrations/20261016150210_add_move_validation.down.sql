@@ -0,0 +1,3 @@
+-- Remove move validation columns
+ALTER TABLE snake_turns DROP COLUMN parse_error;
+ALTER TABLE games DROP COLUMN validation_mode;
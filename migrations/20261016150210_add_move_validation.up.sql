@@ -0,0 +1,5 @@
+-- Per-game strictness for parsing snake /move responses
+ALTER TABLE games ADD COLUMN validation_mode TEXT NOT NULL DEFAULT 'lenient';
+
+-- Why a snake's /move response couldn't be parsed (NULL when it parsed fine)
+ALTER TABLE snake_turns ADD COLUMN parse_error TEXT;
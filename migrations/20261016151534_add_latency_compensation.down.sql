@@ -0,0 +1,3 @@
+-- Remove latency compensation columns
+ALTER TABLE snake_turns DROP COLUMN compensated_latency_ms;
+ALTER TABLE games DROP COLUMN latency_compensation;
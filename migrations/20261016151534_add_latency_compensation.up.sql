@@ -0,0 +1,5 @@
+-- Opt-in latency compensation: per-move deadlines are extended by each snake's /start round trip
+ALTER TABLE games ADD COLUMN latency_compensation BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Move latency with the snake's network round trip subtracted (NULL when compensation is off)
+ALTER TABLE snake_turns ADD COLUMN compensated_latency_ms INTEGER;
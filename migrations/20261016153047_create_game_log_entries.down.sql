@@ -0,0 +1,2 @@
+-- Drop the game_log_entries table
+DROP TABLE IF EXISTS game_log_entries;
@@ -0,0 +1,14 @@
+-- Per-game log of what the game runner saw (snake errors, timeouts, fallbacks),
+-- so snake owners can see it without access to the server logs
+CREATE TABLE
+  game_log_entries (
+    game_log_entry_id UUID PRIMARY KEY DEFAULT uuid_generate_v4 (),
+    game_id UUID NOT NULL REFERENCES games (game_id) ON DELETE CASCADE,
+    turn_number INTEGER, -- NULL for entries outside a turn (e.g. /start, /end)
+    game_battlesnake_id UUID REFERENCES game_battlesnakes (game_battlesnake_id) ON DELETE CASCADE,
+    level TEXT NOT NULL, -- 'info', 'warn', 'error'
+    message TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+  );
+
+CREATE INDEX game_log_entries_game_id_idx ON game_log_entries (game_id, created_at);
@@ -0,0 +1,3 @@
+-- Remove game visibility and stream tickets
+DROP TABLE IF EXISTS game_stream_tickets;
+ALTER TABLE games DROP COLUMN visibility;
@@ -0,0 +1,14 @@
+-- Private games only stream frames to owners of the snakes playing in them
+ALTER TABLE games ADD COLUMN visibility TEXT NOT NULL DEFAULT 'public';
+
+-- Short-lived tickets for opening a private game's event stream where headers
+-- and cookies can't be sent (the board viewer, browser WebSocket clients)
+CREATE TABLE
+  game_stream_tickets (
+    game_stream_ticket_id UUID PRIMARY KEY DEFAULT uuid_generate_v4 (),
+    ticket_hash TEXT NOT NULL UNIQUE,
+    game_id UUID NOT NULL REFERENCES games (game_id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+  );
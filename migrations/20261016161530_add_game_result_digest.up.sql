@@ -0,0 +1,2 @@
+-- Final digest of the game's frame hash chain, recorded when the game finishes
+ALTER TABLE games ADD COLUMN result_digest TEXT;
@@ -0,0 +1,2 @@
+-- Remove the per-game turn limit
+ALTER TABLE games DROP COLUMN max_turns;
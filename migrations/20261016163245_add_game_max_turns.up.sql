@@ -0,0 +1,2 @@
+-- Per-game turn limit, for short games like snake sandbox runs (NULL uses the engine default)
+ALTER TABLE games ADD COLUMN max_turns INTEGER;
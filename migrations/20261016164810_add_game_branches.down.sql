@@ -0,0 +1,4 @@
+-- Remove game branches
+DROP INDEX IF EXISTS idx_games_parent_game_id;
+ALTER TABLE games DROP COLUMN branched_from_turn;
+ALTER TABLE games DROP COLUMN parent_game_id;
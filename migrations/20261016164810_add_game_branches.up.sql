@@ -0,0 +1,5 @@
+-- "What if" branches: games re-simulated from a turn of a finished parent game
+ALTER TABLE games ADD COLUMN parent_game_id UUID REFERENCES games(game_id) ON DELETE CASCADE;
+ALTER TABLE games ADD COLUMN branched_from_turn INTEGER;
+
+CREATE INDEX idx_games_parent_game_id ON games(parent_game_id);
@@ -0,0 +1,4 @@
+-- Remove per-game spawn strategies
+ALTER TABLE games DROP COLUMN spawn_positions;
+ALTER TABLE games DROP COLUMN spawn_rotation;
+ALTER TABLE games DROP COLUMN spawn_strategy;
@@ -0,0 +1,5 @@
+-- How snakes are placed at the start of a game: 'random' (official corners/cardinals),
+-- 'fixed' (deterministic, rotated by spawn_rotation) or 'manual' (spawn_positions)
+ALTER TABLE games ADD COLUMN spawn_strategy TEXT NOT NULL DEFAULT 'random';
+ALTER TABLE games ADD COLUMN spawn_rotation INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE games ADD COLUMN spawn_positions JSONB;
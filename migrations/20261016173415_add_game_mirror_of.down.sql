@@ -0,0 +1,3 @@
+-- Remove mirror matches
+DROP INDEX IF EXISTS idx_games_mirror_of_game_id;
+ALTER TABLE games DROP COLUMN mirror_of_game_id;
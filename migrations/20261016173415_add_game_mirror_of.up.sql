@@ -0,0 +1,5 @@
+-- Mirror matches: a second game with the two snakes' spawns swapped, scored
+-- together with the game it mirrors as one round
+ALTER TABLE games ADD COLUMN mirror_of_game_id UUID REFERENCES games(game_id) ON DELETE CASCADE;
+
+CREATE UNIQUE INDEX idx_games_mirror_of_game_id ON games(mirror_of_game_id);
@@ -0,0 +1,3 @@
+-- Remove turn limit tiebreaks
+ALTER TABLE games DROP COLUMN tiebreak_applied;
+ALTER TABLE games DROP COLUMN turn_limit_tiebreak;
@@ -0,0 +1,5 @@
+-- How snakes still alive at the turn limit are placed: 'longest', 'most_food' or 'draw'
+ALTER TABLE games ADD COLUMN turn_limit_tiebreak TEXT NOT NULL DEFAULT 'longest';
+-- The tiebreak that placed the survivors, when the turn limit ended the game
+-- with more than one snake alive
+ALTER TABLE games ADD COLUMN tiebreak_applied TEXT;
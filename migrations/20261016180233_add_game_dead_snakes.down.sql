@@ -0,0 +1,2 @@
+-- Remove the dead snake frame option
+ALTER TABLE games DROP COLUMN dead_snakes;
@@ -0,0 +1,3 @@
+-- What frames show of eliminated snakes: 'drop' (gone after the turn they die,
+-- like the official engine) or 'ghost' (kept, drawn in a faded color)
+ALTER TABLE games ADD COLUMN dead_snakes TEXT NOT NULL DEFAULT 'drop';
@@ -0,0 +1,3 @@
+-- Remove win rate regression alerts
+DROP TABLE IF EXISTS snake_alert_events;
+DROP TABLE IF EXISTS snake_alerts;
@@ -0,0 +1,27 @@
+-- Win rate regression alerts: an alert fires when a snake's win rate over its
+-- last window_games finished games is more than drop_percent points below its
+-- win rate over the window_games before that
+CREATE TABLE snake_alerts (
+    alert_id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    battlesnake_id UUID NOT NULL REFERENCES battlesnakes(battlesnake_id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    window_games INTEGER NOT NULL DEFAULT 50,
+    drop_percent DOUBLE PRECISION NOT NULL DEFAULT 15,
+    -- Set while the drop lasts, so one regression fires once
+    firing BOOLEAN NOT NULL DEFAULT FALSE,
+    last_checked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_snake_alerts_battlesnake_id ON snake_alerts(battlesnake_id);
+
+-- Each time an alert fired, with the win rates that set it off
+CREATE TABLE snake_alert_events (
+    event_id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    alert_id UUID NOT NULL REFERENCES snake_alerts(alert_id) ON DELETE CASCADE,
+    previous_win_rate DOUBLE PRECISION NOT NULL,
+    current_win_rate DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_snake_alert_events_alert_id ON snake_alert_events(alert_id, created_at DESC);
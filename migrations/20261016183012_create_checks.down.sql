@@ -0,0 +1,4 @@
+-- Remove regression checks
+DROP INDEX IF EXISTS idx_games_check_id;
+ALTER TABLE games DROP COLUMN IF EXISTS check_id;
+DROP TABLE IF EXISTS checks;
@@ -0,0 +1,16 @@
+-- Regression checks: a batch of games between one snake and a set of
+-- opponents that passes when the snake wins at least required_win_rate percent
+CREATE TABLE checks (
+    check_id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    battlesnake_id UUID NOT NULL REFERENCES battlesnakes(battlesnake_id) ON DELETE CASCADE,
+    games_requested INTEGER NOT NULL,
+    required_win_rate DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_checks_user_id ON checks(user_id, created_at DESC);
+
+ALTER TABLE games ADD COLUMN check_id UUID REFERENCES checks(check_id) ON DELETE SET NULL;
+
+CREATE INDEX idx_games_check_id ON games(check_id) WHERE check_id IS NOT NULL;
@@ -0,0 +1,5 @@
+-- Remove GitHub commit statuses for regression checks
+DROP INDEX IF EXISTS idx_checks_github_pending;
+ALTER TABLE checks DROP COLUMN IF EXISTS github_state;
+ALTER TABLE checks DROP COLUMN IF EXISTS commit_sha;
+DROP TABLE IF EXISTS snake_github_repos;
@@ -0,0 +1,16 @@
+-- The GitHub repository a snake's code lives in, and the GitHub App
+-- installation that can post regression check results to it
+CREATE TABLE snake_github_repos (
+    battlesnake_id UUID PRIMARY KEY REFERENCES battlesnakes(battlesnake_id) ON DELETE CASCADE,
+    repo TEXT NOT NULL,
+    installation_id BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- The commit a check was run for, and the last commit status posted for it
+ALTER TABLE checks ADD COLUMN commit_sha TEXT;
+ALTER TABLE checks ADD COLUMN github_state TEXT;
+
+CREATE INDEX idx_checks_github_pending ON checks(created_at)
+    WHERE commit_sha IS NOT NULL AND (github_state IS NULL OR github_state = 'pending');
@@ -0,0 +1,4 @@
+-- Remove federation
+DROP TABLE IF EXISTS federation_cursors;
+DROP TABLE IF EXISTS federated_results;
+DROP TABLE IF EXISTS federated_snakes;
@@ -0,0 +1,29 @@
+-- Federation: public snakes and game results synced from peer arenas
+CREATE TABLE federated_snakes (
+    peer_url TEXT NOT NULL,
+    remote_snake_id UUID NOT NULL,
+    name TEXT NOT NULL,
+    owner TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (peer_url, remote_snake_id)
+);
+
+-- placements is a JSON array of {"snake_id", "placement"}, best placement first
+CREATE TABLE federated_results (
+    peer_url TEXT NOT NULL,
+    remote_game_id UUID NOT NULL,
+    finished_at TIMESTAMPTZ NOT NULL,
+    placements JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (peer_url, remote_game_id)
+);
+
+CREATE INDEX idx_federated_results_finished_at ON federated_results(finished_at);
+
+-- How far each peer's results have been synced
+CREATE TABLE federation_cursors (
+    peer_url TEXT PRIMARY KEY,
+    cursor_at TIMESTAMPTZ NOT NULL,
+    cursor_game_id UUID NOT NULL,
+    synced_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
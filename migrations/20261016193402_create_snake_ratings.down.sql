@@ -0,0 +1,4 @@
+-- Remove stored ratings
+DROP TABLE IF EXISTS rating_recalculations;
+DROP TABLE IF EXISTS snake_ratings_shadow;
+DROP TABLE IF EXISTS snake_ratings;
@@ -0,0 +1,34 @@
+-- Stored ratings: a replay of every finished game created before a
+-- recalculation's rated_before, so pages only replay the games since
+CREATE TABLE snake_ratings (
+    battlesnake_id UUID PRIMARY KEY REFERENCES battlesnakes(battlesnake_id) ON DELETE CASCADE,
+    rating DOUBLE PRECISION NOT NULL,
+    games INTEGER NOT NULL,
+    wins INTEGER NOT NULL,
+    rated_games INTEGER NOT NULL,
+    places_gained DOUBLE PRECISION NOT NULL
+);
+
+-- Filled by a running recalculation, then copied into snake_ratings in one
+-- transaction so readers never see a half-built set
+CREATE TABLE snake_ratings_shadow (LIKE snake_ratings INCLUDING ALL);
+
+-- Each recalculation, with its progress; the latest completed one describes
+-- what's in snake_ratings
+CREATE TABLE rating_recalculations (
+    recalculation_id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    -- running, completed or failed
+    status TEXT NOT NULL DEFAULT 'running',
+    -- The rating algorithm version the games were replayed with
+    algorithm_version INTEGER NOT NULL,
+    games_total INTEGER NOT NULL DEFAULT 0,
+    games_rated INTEGER NOT NULL DEFAULT 0,
+    -- Every finished game created before this was replayed
+    rated_before TIMESTAMPTZ,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_rating_recalculations_status ON rating_recalculations(status, finished_at DESC);
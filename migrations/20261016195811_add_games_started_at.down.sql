@@ -0,0 +1,4 @@
+-- Remove started_at from games
+DROP INDEX IF EXISTS idx_games_waiting_enqueued_at;
+DROP INDEX IF EXISTS idx_games_started_at;
+ALTER TABLE games DROP COLUMN IF EXISTS started_at;
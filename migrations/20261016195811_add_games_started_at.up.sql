@@ -0,0 +1,5 @@
+-- When a game runner picked the game up, for estimating how fast the queue moves
+ALTER TABLE games ADD COLUMN started_at TIMESTAMPTZ;
+
+CREATE INDEX idx_games_started_at ON games(started_at) WHERE started_at IS NOT NULL;
+CREATE INDEX idx_games_waiting_enqueued_at ON games(enqueued_at) WHERE status = 'waiting';
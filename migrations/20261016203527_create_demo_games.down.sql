@@ -0,0 +1,2 @@
+-- Drop demo games
+DROP TABLE IF EXISTS demo_games;
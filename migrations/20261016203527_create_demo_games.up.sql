@@ -0,0 +1,7 @@
+-- Bots-only games started from the landing page for visitors who aren't logged in
+CREATE TABLE demo_games (
+    game_id UUID PRIMARY KEY REFERENCES games(game_id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_demo_games_created_at ON demo_games(created_at DESC);
@@ -0,0 +1,2 @@
+-- Drop user preferences
+DROP TABLE IF EXISTS user_preferences;
@@ -0,0 +1,17 @@
+-- Per-user defaults for creating games and display settings
+-- A user without a row has the defaults below
+CREATE TABLE user_preferences (
+    user_id UUID PRIMARY KEY REFERENCES users(user_id) ON DELETE CASCADE,
+    board_size TEXT NOT NULL DEFAULT '11x11',
+    game_type TEXT NOT NULL DEFAULT 'Standard',
+    -- IANA name, e.g. 'Europe/Berlin'
+    timezone TEXT NOT NULL DEFAULT 'UTC',
+    -- Where to send notifications: 'email', 'web'
+    notification_channels TEXT[] NOT NULL DEFAULT '{}',
+    -- Replay speed as a multiple of the board viewer's normal speed
+    playback_speed DOUBLE PRECISION NOT NULL DEFAULT 1.0,
+    -- 'system', 'light' or 'dark'
+    theme TEXT NOT NULL DEFAULT 'light',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,3 @@
+-- Drop snake templates and their variants
+DROP TABLE IF EXISTS snake_variants;
+DROP TABLE IF EXISTS snake_templates;
@@ -0,0 +1,29 @@
+-- Snake templates: a URL with a {tag} placeholder, instantiated as one snake
+-- per deployed tag
+CREATE TABLE snake_templates (
+    template_id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    -- Variants are named "{name}@{tag}"
+    name TEXT NOT NULL,
+    -- e.g. 'https://snake.example.com/{tag}'
+    url_template TEXT NOT NULL,
+    -- Visibility new variants get: 'public' or 'private'
+    visibility TEXT NOT NULL DEFAULT 'private',
+    -- Variants without a game for this many days are archived
+    archive_after_days INTEGER NOT NULL DEFAULT 14,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT unique_snake_template_name_per_user UNIQUE (user_id, name)
+);
+
+-- The snake made for each tag of a template
+CREATE TABLE snake_variants (
+    battlesnake_id UUID PRIMARY KEY REFERENCES battlesnakes(battlesnake_id) ON DELETE CASCADE,
+    template_id UUID NOT NULL REFERENCES snake_templates(template_id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Set when the variant is archived: made private and kept out of new games
+    archived_at TIMESTAMPTZ,
+    UNIQUE (template_id, tag)
+);
+
+CREATE INDEX idx_snake_variants_template_id ON snake_variants(template_id, created_at DESC);
@@ -0,0 +1,2 @@
+-- Drop game leases
+DROP TABLE IF EXISTS game_leases;
@@ -0,0 +1,19 @@
+-- Game leases: which worker is running each game, kept alive by heartbeats so
+-- a game whose worker died can be told apart from one that's just slow
+CREATE TABLE game_leases (
+    game_id UUID PRIMARY KEY REFERENCES games(game_id) ON DELETE CASCADE,
+    -- New for every run of the game, so a worker that lost the lease can't
+    -- heartbeat over the one that took it over
+    lease_id UUID NOT NULL DEFAULT uuid_generate_v4(),
+    -- ARENA_WORKER_ID, or "{hostname}-{pid}"
+    worker_id TEXT NOT NULL,
+    acquired_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    heartbeat_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Set when the run ends, whether it finished, failed or was swept
+    released_at TIMESTAMPTZ,
+    -- True when the sweeper released it after the heartbeats stopped
+    expired BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX idx_game_leases_worker_id ON game_leases(worker_id, released_at);
+CREATE INDEX idx_game_leases_active ON game_leases(heartbeat_at) WHERE released_at IS NULL;
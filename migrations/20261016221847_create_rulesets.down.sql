@@ -0,0 +1,2 @@
+ALTER TABLE games DROP COLUMN IF EXISTS ruleset_id;
+DROP TABLE IF EXISTS rulesets;
@@ -0,0 +1,14 @@
+-- Uploaded WASM rulesets that change food, hazards and eliminations after the
+-- standard rules play each turn
+CREATE TABLE rulesets (
+    ruleset_id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    -- The compiled module, checked against the ruleset interface on upload
+    wasm BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    CONSTRAINT unique_ruleset_name_per_user UNIQUE (user_id, name)
+);
+
+-- The ruleset a game is played with, if any
+ALTER TABLE games ADD COLUMN ruleset_id UUID REFERENCES rulesets(ruleset_id) ON DELETE SET NULL;
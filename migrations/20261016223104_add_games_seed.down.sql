@@ -0,0 +1,2 @@
+-- Remove game seeds
+ALTER TABLE games DROP COLUMN seed;
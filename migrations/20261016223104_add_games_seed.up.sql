@@ -0,0 +1,3 @@
+-- Seed for a game's random draws (spawns, food, built-in bots' moves); NULL
+-- for games created before seeding, which are seeded from their ID
+ALTER TABLE games ADD COLUMN seed BIGINT;
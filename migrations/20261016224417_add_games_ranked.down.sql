@@ -0,0 +1,2 @@
+-- Remove the ranked flag
+ALTER TABLE games DROP COLUMN ranked;
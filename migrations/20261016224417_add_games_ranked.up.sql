@@ -0,0 +1,3 @@
+-- Unranked games are practice: they're played and kept like any other game
+-- but left out of ratings, leaderboards and alerts
+ALTER TABLE games ADD COLUMN ranked BOOLEAN NOT NULL DEFAULT TRUE;
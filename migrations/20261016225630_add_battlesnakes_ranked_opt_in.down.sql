@@ -0,0 +1,2 @@
+-- Remove the ranked opt-in
+ALTER TABLE battlesnakes DROP COLUMN ranked_opt_in;
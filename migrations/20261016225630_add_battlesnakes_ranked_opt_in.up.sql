@@ -0,0 +1,2 @@
+-- Snakes only play ranked games once their owner opts them in
+ALTER TABLE battlesnakes ADD COLUMN ranked_opt_in BOOLEAN NOT NULL DEFAULT FALSE;
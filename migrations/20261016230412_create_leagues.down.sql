@@ -0,0 +1,4 @@
+-- Remove leagues
+ALTER TABLE games DROP COLUMN league_id;
+DROP TABLE league_members;
+DROP TABLE leagues;
@@ -0,0 +1,23 @@
+-- Leagues: an organization or tournament with its own private leaderboard,
+-- rated only from the games played in it
+CREATE TABLE leagues (
+    league_id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name TEXT NOT NULL,
+    owner_user_id UUID NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Users who can see a league's leaderboard and enter its games
+CREATE TABLE league_members (
+    league_id UUID NOT NULL REFERENCES leagues(league_id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (league_id, user_id)
+);
+
+CREATE INDEX idx_league_members_user_id ON league_members(user_id);
+
+-- The league a game was played in, if any
+ALTER TABLE games ADD COLUMN league_id UUID REFERENCES leagues(league_id) ON DELETE SET NULL;
+
+CREATE INDEX idx_games_league_id ON games(league_id) WHERE league_id IS NOT NULL;
@@ -0,0 +1,2 @@
+-- Remove per-game move timeouts
+ALTER TABLE games DROP COLUMN move_timeout_ms;
@@ -0,0 +1,2 @@
+-- Games can give snakes longer or shorter than the default to answer /move
+ALTER TABLE games ADD COLUMN move_timeout_ms INTEGER CHECK (move_timeout_ms > 0);
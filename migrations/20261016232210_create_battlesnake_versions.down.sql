@@ -0,0 +1,4 @@
+-- Remove snake versions
+ALTER TABLE game_battlesnakes DROP COLUMN battlesnake_version_id;
+ALTER TABLE battlesnakes DROP COLUMN active_version_id;
+DROP TABLE battlesnake_versions;
@@ -0,0 +1,23 @@
+-- Versions of a snake: a labelled URL, so results can be attributed to the
+-- code that played them
+CREATE TABLE battlesnake_versions (
+    battlesnake_version_id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    battlesnake_id UUID NOT NULL REFERENCES battlesnakes(battlesnake_id) ON DELETE CASCADE,
+    -- e.g. 'v2' or a commit SHA
+    label TEXT NOT NULL,
+    url TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT unique_battlesnake_version_label UNIQUE (battlesnake_id, label)
+);
+
+CREATE INDEX idx_battlesnake_versions_battlesnake_id ON battlesnake_versions(battlesnake_id, created_at DESC);
+
+-- The version new games are attributed to, while the snake's URL is still its URL
+ALTER TABLE battlesnakes
+    ADD COLUMN active_version_id UUID REFERENCES battlesnake_versions(battlesnake_version_id) ON DELETE SET NULL;
+
+-- The version each snake played a game as, captured when the game is created
+ALTER TABLE game_battlesnakes
+    ADD COLUMN battlesnake_version_id UUID REFERENCES battlesnake_versions(battlesnake_version_id) ON DELETE SET NULL;
+
+CREATE INDEX idx_game_battlesnakes_battlesnake_version_id ON game_battlesnakes(battlesnake_version_id);
@@ -0,0 +1,2 @@
+-- Remove snake health checks
+DROP TABLE snake_health;
@@ -0,0 +1,12 @@
+-- The latest automated health check of each snake's GET /
+CREATE TABLE snake_health (
+    battlesnake_id UUID PRIMARY KEY REFERENCES battlesnakes(battlesnake_id) ON DELETE CASCADE,
+    -- 200 with a valid Battlesnake info response
+    healthy BOOLEAN NOT NULL,
+    -- NULL if no response came back
+    status_code INTEGER,
+    latency_ms INTEGER,
+    -- Why the check failed, NULL when it passed
+    error TEXT,
+    checked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
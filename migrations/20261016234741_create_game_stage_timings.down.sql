@@ -0,0 +1,2 @@
+-- Remove game stage timings
+DROP TABLE game_stage_timings;
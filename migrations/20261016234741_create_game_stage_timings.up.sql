@@ -0,0 +1,10 @@
+-- How long each stage of processing a turn took over a game, for its timing report
+CREATE TABLE game_stage_timings (
+    game_id UUID NOT NULL REFERENCES games(game_id) ON DELETE CASCADE,
+    -- 'gather_moves', 'apply_turn', 'serialize_frame', 'persist' or 'broadcast'
+    stage TEXT NOT NULL,
+    turns INTEGER NOT NULL,
+    total_us BIGINT NOT NULL,
+    max_us BIGINT NOT NULL,
+    PRIMARY KEY (game_id, stage)
+);
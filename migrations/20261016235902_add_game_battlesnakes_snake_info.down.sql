@@ -0,0 +1,7 @@
+-- Remove snake info from game_battlesnakes
+ALTER TABLE game_battlesnakes
+    DROP COLUMN apiversion,
+    DROP COLUMN author,
+    DROP COLUMN color,
+    DROP COLUMN head,
+    DROP COLUMN tail;
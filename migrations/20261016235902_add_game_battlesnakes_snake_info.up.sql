@@ -0,0 +1,8 @@
+-- What each snake's GET / returned when the game started, so frames show its
+-- customizations. NULL where the snake didn't say or couldn't be reached.
+ALTER TABLE game_battlesnakes
+    ADD COLUMN apiversion TEXT,
+    ADD COLUMN author TEXT,
+    ADD COLUMN color TEXT,
+    ADD COLUMN head TEXT,
+    ADD COLUMN tail TEXT;
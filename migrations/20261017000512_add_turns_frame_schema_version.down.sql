@@ -0,0 +1,3 @@
+-- Remove frame schema versions from turns
+DROP INDEX IF EXISTS idx_turns_frame_schema_version;
+ALTER TABLE turns DROP COLUMN frame_schema_version;
@@ -0,0 +1,6 @@
+-- The frame schema version each turn's frame was written at, so frames in older
+-- formats can be upgraded when read. Existing frames predate versioning.
+ALTER TABLE turns ADD COLUMN frame_schema_version INTEGER NOT NULL DEFAULT 1;
+
+-- For the backfill, which looks for frames behind the current version
+CREATE INDEX idx_turns_frame_schema_version ON turns (frame_schema_version);
@@ -0,0 +1,5 @@
+-- Remove league prizes, write-ups and announcements
+ALTER TABLE leagues DROP COLUMN announcement;
+ALTER TABLE leagues DROP COLUMN completed_at;
+ALTER TABLE leagues DROP COLUMN writeup;
+ALTER TABLE leagues DROP COLUMN prizes;
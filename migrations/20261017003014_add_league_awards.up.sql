@@ -0,0 +1,6 @@
+-- Prizes and a write-up organizers attach to a league, and the announcement
+-- generated with the final standings when it's completed
+ALTER TABLE leagues ADD COLUMN prizes JSONB NOT NULL DEFAULT '[]';
+ALTER TABLE leagues ADD COLUMN writeup TEXT;
+ALTER TABLE leagues ADD COLUMN completed_at TIMESTAMPTZ;
+ALTER TABLE leagues ADD COLUMN announcement JSONB;
@@ -0,0 +1,2 @@
+-- Remove in-app notifications
+DROP TABLE IF EXISTS notifications;
@@ -0,0 +1,2 @@
+-- Remove replay GIFs
+DROP TABLE IF EXISTS game_renders;
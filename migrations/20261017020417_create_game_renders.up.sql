@@ -0,0 +1,12 @@
+-- Replay GIFs of finished games, rendered by RenderGameJob. The GIF is in GCS
+-- at gcs_path when a bucket is configured, and in the gif column otherwise.
+CREATE TABLE game_renders (
+    game_id UUID PRIMARY KEY REFERENCES games(game_id) ON DELETE CASCADE,
+    gcs_path TEXT,
+    gif BYTEA,
+    size_bytes INTEGER,
+    frame_count INTEGER,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- NULL while the render is queued
+    rendered_at TIMESTAMPTZ
+);
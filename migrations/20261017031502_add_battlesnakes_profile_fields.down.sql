@@ -0,0 +1,4 @@
+-- Remove the profile fields
+ALTER TABLE battlesnakes DROP COLUMN source_url;
+ALTER TABLE battlesnakes DROP COLUMN homepage_url;
+ALTER TABLE battlesnakes DROP COLUMN description;
@@ -0,0 +1,5 @@
+-- What a snake's owner says about it on its public pages: a markdown
+-- description, and links to its homepage and source code
+ALTER TABLE battlesnakes ADD COLUMN description TEXT;
+ALTER TABLE battlesnakes ADD COLUMN homepage_url TEXT;
+ALTER TABLE battlesnakes ADD COLUMN source_url TEXT;
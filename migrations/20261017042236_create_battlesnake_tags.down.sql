@@ -0,0 +1,3 @@
+-- Remove snake tags and preferred opponent tags
+ALTER TABLE user_preferences DROP COLUMN IF EXISTS preferred_opponent_tags;
+DROP TABLE IF EXISTS battlesnake_tags;
@@ -0,0 +1,13 @@
+-- Tags owners put on their snakes, like rust or tree-search, for filtering
+-- the public snakes and for users' preferred opponents
+CREATE TABLE battlesnake_tags (
+    battlesnake_id UUID NOT NULL REFERENCES battlesnakes(battlesnake_id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (battlesnake_id, tag)
+);
+
+CREATE INDEX idx_battlesnake_tags_tag ON battlesnake_tags(tag);
+
+ALTER TABLE user_preferences
+    ADD COLUMN preferred_opponent_tags TEXT[] NOT NULL DEFAULT '{}';
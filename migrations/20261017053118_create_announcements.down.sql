@@ -0,0 +1,2 @@
+-- Remove arena-wide announcements
+DROP TABLE IF EXISTS announcements;
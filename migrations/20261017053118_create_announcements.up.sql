@@ -0,0 +1,15 @@
+-- Arena-wide announcements, like maintenance windows and tournament
+-- promotions, shown as a banner on every page between starts_at and ends_at
+CREATE TABLE announcements (
+    announcement_id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    message TEXT NOT NULL,
+    severity TEXT NOT NULL DEFAULT 'info' CHECK (severity IN ('info', 'warning', 'critical')),
+    starts_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- NULL runs until it's taken down
+    ends_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (ends_at IS NULL OR ends_at > starts_at)
+);
+
+CREATE INDEX idx_announcements_starts_at ON announcements(starts_at);
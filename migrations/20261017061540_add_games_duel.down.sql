@@ -0,0 +1,3 @@
+-- Remove duels
+DROP INDEX IF EXISTS idx_games_ranked_duels;
+ALTER TABLE games DROP COLUMN IF EXISTS duel;
@@ -0,0 +1,5 @@
+-- Duels are 1v1 games between two different snakes, rated on their own
+-- ladder apart from games with more snakes
+ALTER TABLE games ADD COLUMN duel BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX idx_games_ranked_duels ON games(created_at) WHERE duel AND ranked;
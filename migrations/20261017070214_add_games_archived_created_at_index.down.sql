@@ -0,0 +1,2 @@
+-- Remove the index for bulk archive downloads
+DROP INDEX IF EXISTS idx_games_archived_created_at;
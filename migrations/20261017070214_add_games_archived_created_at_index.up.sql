@@ -0,0 +1,2 @@
+-- Find archived games by the day they were played, for bulk archive downloads
+CREATE INDEX idx_games_archived_created_at ON games(created_at) WHERE archived_at IS NOT NULL;
@@ -0,0 +1,2 @@
+-- Remove the index for archiving finished games' turns
+DROP INDEX IF EXISTS idx_games_unarchived_finished;
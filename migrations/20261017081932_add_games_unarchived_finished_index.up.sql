@@ -0,0 +1,3 @@
+-- Find finished games played here whose turns haven't been archived yet
+CREATE INDEX idx_games_unarchived_finished ON games(updated_at)
+    WHERE status = 'finished' AND archived_at IS NULL AND engine_game_id IS NULL;
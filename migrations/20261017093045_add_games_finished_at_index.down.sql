@@ -0,0 +1,2 @@
+-- Remove the index for exporting game outcomes
+DROP INDEX IF EXISTS idx_games_finished_at;
@@ -0,0 +1,3 @@
+-- Find the games played here that finished on a day, for outcome exports
+CREATE INDEX idx_games_finished_at ON games(updated_at)
+    WHERE status = 'finished' AND engine_game_id IS NULL;
@@ -0,0 +1,3 @@
+-- Remove pausing and stepping running games
+ALTER TABLE games DROP COLUMN IF EXISTS pending_steps;
+ALTER TABLE games DROP COLUMN IF EXISTS paused_at;
@@ -0,0 +1,5 @@
+-- Running games can be paused between turns and stepped a turn at a time.
+-- paused_at is set while a game is paused, and pending_steps counts the turns
+-- it's been asked to play before holding again.
+ALTER TABLE games ADD COLUMN paused_at TIMESTAMPTZ;
+ALTER TABLE games ADD COLUMN pending_steps INTEGER NOT NULL DEFAULT 0 CHECK (pending_steps >= 0);
@@ -0,0 +1,4 @@
+-- Remove the domain event log and its projections
+DROP TABLE IF EXISTS snake_stats;
+DROP TABLE IF EXISTS domain_events;
+DROP FUNCTION IF EXISTS reject_domain_event_changes();
@@ -0,0 +1,42 @@
+-- An append-only log of what happens in games, written as it happens, so
+-- derived tables can be rebuilt by replaying it after a bug or schema change
+CREATE TABLE domain_events (
+    event_id BIGSERIAL PRIMARY KEY,
+    -- game_created, turn_persisted, game_finished, rating_updated or tournament_advanced
+    event_type TEXT NOT NULL,
+    game_id UUID,
+    payload JSONB NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_domain_events_type ON domain_events(event_type, event_id);
+CREATE INDEX idx_domain_events_game_id ON domain_events(game_id) WHERE game_id IS NOT NULL;
+
+-- Events are never changed or removed once written
+CREATE OR REPLACE FUNCTION reject_domain_event_changes()
+RETURNS TRIGGER AS $$
+BEGIN
+   RAISE EXCEPTION 'domain_events is append-only';
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER domain_events_append_only
+  BEFORE UPDATE OR DELETE ON domain_events
+  FOR EACH ROW
+  EXECUTE FUNCTION reject_domain_event_changes();
+
+CREATE TRIGGER domain_events_no_truncate
+  BEFORE TRUNCATE ON domain_events
+  FOR EACH STATEMENT
+  EXECUTE FUNCTION reject_domain_event_changes();
+
+-- Each snake's totals over its finished games, projected from game_finished
+-- events and rebuilt by replaying them
+CREATE TABLE snake_stats (
+    battlesnake_id UUID PRIMARY KEY REFERENCES battlesnakes(battlesnake_id) ON DELETE CASCADE,
+    games INTEGER NOT NULL DEFAULT 0,
+    wins INTEGER NOT NULL DEFAULT 0,
+    placement_sum BIGINT NOT NULL DEFAULT 0,
+    turns BIGINT NOT NULL DEFAULT 0,
+    last_played_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,4 @@
+-- Remove game retries and runner checkpoints
+DROP TABLE IF EXISTS game_checkpoints;
+ALTER TABLE games DROP COLUMN IF EXISTS last_failure;
+ALTER TABLE games DROP COLUMN IF EXISTS retry_count;
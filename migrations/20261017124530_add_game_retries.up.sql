@@ -0,0 +1,13 @@
+-- How many times a game's run has failed and been retried, and why it last failed
+ALTER TABLE games ADD COLUMN retry_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE games ADD COLUMN last_failure TEXT;
+
+-- What a game's runner needs to carry on from its last stored turn, written
+-- after each turn so a retry resumes there instead of starting over
+CREATE TABLE game_checkpoints (
+    game_id UUID PRIMARY KEY REFERENCES games (game_id) ON DELETE CASCADE,
+    turn INTEGER NOT NULL,
+    seed BIGINT NOT NULL,
+    state JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,2 @@
+-- Remove compressed turn frames; frames stored compressed are lost with them
+ALTER TABLE turns DROP COLUMN IF EXISTS frame_compressed;
@@ -0,0 +1,3 @@
+-- A turn's frame as zstd-compressed JSON, for games in the frame compression
+-- rollout. A frame is stored in frame_data or frame_compressed, never both.
+ALTER TABLE turns ADD COLUMN frame_compressed BYTEA;
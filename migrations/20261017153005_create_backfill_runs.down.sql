@@ -0,0 +1,4 @@
+-- Remove backfill runs
+DROP INDEX IF EXISTS idx_backup_batches_run;
+ALTER TABLE backup_batches DROP COLUMN IF EXISTS run_id;
+DROP TABLE IF EXISTS backfill_runs;
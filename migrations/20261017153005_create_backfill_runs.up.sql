@@ -0,0 +1,27 @@
+-- Runs of `arena admin backfill`, each archiving the Engine games played in a
+-- date range. The cursor is the last game of the last finished batch, so an
+-- interrupted run resumes after it.
+CREATE TABLE backfill_runs (
+    id SERIAL PRIMARY KEY,
+    from_date DATE NOT NULL,
+    -- Inclusive
+    to_date DATE NOT NULL,
+    cursor_created TIMESTAMP,
+    cursor_id TEXT,
+    games_archived INT NOT NULL DEFAULT 0,
+    -- Games in the range that were already archived
+    games_skipped INT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+-- One unfinished run per range, which the command picks back up
+CREATE UNIQUE INDEX idx_backfill_runs_unfinished ON backfill_runs (from_date, to_date)
+WHERE completed_at IS NULL;
+
+-- Batches created by a run; the run enqueues the next batch itself instead of
+-- the completed batch enqueuing a discovery job
+ALTER TABLE backup_batches ADD COLUMN run_id INT REFERENCES backfill_runs (id) ON DELETE CASCADE;
+
+CREATE INDEX idx_backup_batches_run ON backup_batches (run_id) WHERE run_id IS NOT NULL;
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS idx_game_stream_tickets_expires_at;
@@ -0,0 +1,2 @@
+-- Expired stream tickets are swept up by the stuck game sweep
+CREATE INDEX idx_game_stream_tickets_expires_at ON game_stream_tickets (expires_at);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_games_branched_by_user_id;
+ALTER TABLE games DROP COLUMN IF EXISTS branched_by_user_id;
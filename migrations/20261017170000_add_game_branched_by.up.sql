@@ -0,0 +1,4 @@
+-- Who asked for each branch, so branching can be limited per user
+ALTER TABLE games ADD COLUMN branched_by_user_id UUID REFERENCES users (user_id) ON DELETE SET NULL;
+
+CREATE INDEX idx_games_branched_by_user_id ON games (branched_by_user_id, created_at);
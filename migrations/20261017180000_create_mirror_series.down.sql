@@ -0,0 +1,4 @@
+-- Remove mirror series
+DROP INDEX IF EXISTS idx_games_mirror_series_id;
+ALTER TABLE games DROP COLUMN IF EXISTS mirror_series_id;
+DROP TABLE IF EXISTS mirror_series;
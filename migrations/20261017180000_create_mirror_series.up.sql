@@ -0,0 +1,21 @@
+-- Best-of-N series of mirror matches between two snakes. Each round is a
+-- mirror match; the next round starts when the last one finishes, until a
+-- snake has won more than half the rounds or every round has been played.
+CREATE TABLE mirror_series (
+    series_id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    best_of INT NOT NULL CHECK (best_of > 0 AND best_of % 2 = 1),
+    -- In the order the first game was created with, so every round matches it
+    battlesnake_ids UUID[] NOT NULL,
+    league_id UUID REFERENCES leagues(league_id) ON DELETE SET NULL,
+    -- Claimed by whichever game finishes a round last, so a round whose two
+    -- games end together starts the next round once
+    rounds_started INT NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+-- The series both games of each round belong to
+ALTER TABLE games ADD COLUMN mirror_series_id UUID REFERENCES mirror_series(series_id) ON DELETE CASCADE;
+
+CREATE INDEX idx_games_mirror_series_id ON games (mirror_series_id) WHERE mirror_series_id IS NOT NULL;
@@ -0,0 +1,4 @@
+-- Remove the final turn and archived moves columns
+ALTER TABLE games DROP COLUMN IF EXISTS moves_gcs_path;
+ALTER TABLE games DROP COLUMN IF EXISTS turns_duration_ms;
+ALTER TABLE games DROP COLUMN IF EXISTS final_turn;
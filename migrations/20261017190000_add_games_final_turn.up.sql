@@ -0,0 +1,26 @@
+-- What a finished game's turn rows say about it, kept on the game so it
+-- outlasts the rows being archived and pruned: its last turn, and the time
+-- from its first stored turn to its last
+ALTER TABLE games ADD COLUMN final_turn INT;
+ALTER TABLE games ADD COLUMN turns_duration_ms BIGINT;
+
+-- Each snake's moves (`snake_turns`), archived beside the frames at `gcs_path`
+ALTER TABLE games ADD COLUMN moves_gcs_path TEXT;
+
+-- Backfilled without the updated_at trigger, since a finished game's
+-- updated_at is when it finished
+ALTER TABLE games DISABLE TRIGGER update_games_updated_at;
+
+UPDATE games
+SET final_turn = t.final_turn, turns_duration_ms = t.duration_ms
+FROM (
+    SELECT
+        game_id,
+        MAX(turn_number) AS final_turn,
+        (EXTRACT(EPOCH FROM MAX(created_at) - MIN(created_at)) * 1000)::BIGINT AS duration_ms
+    FROM turns
+    GROUP BY game_id
+) t
+WHERE t.game_id = games.game_id AND games.status = 'finished';
+
+ALTER TABLE games ENABLE TRIGGER update_games_updated_at;
@@ -0,0 +1,37 @@
+pub mod routes;
+pub mod state;
+
+use axum::{
+    Router,
+    routing::{get, post},
+};
+use state::MockSnakesState;
+
+/// Create the mock snakes router
+///
+/// Each snake lives under `/{behavior}` where behavior is one of the values
+/// understood by [`routes::Behavior`], e.g. `http://localhost:8082/up`.
+pub fn create_router() -> Router {
+    let state = MockSnakesState::new();
+
+    Router::new()
+        // Battlesnake API endpoints
+        .route("/{behavior}", get(routes::info))
+        .route("/{behavior}/", get(routes::info))
+        .route("/{behavior}/start", post(routes::start))
+        .route("/{behavior}/move", post(routes::make_move))
+        .route("/{behavior}/end", post(routes::end))
+        // Admin endpoints for test assertions
+        .route("/_admin/requests", get(routes::request_counts))
+        .route("/_admin/reset", post(routes::reset))
+        .with_state(state)
+}
+
+/// Run the mock snakes server on the specified port
+pub async fn run_server(port: u16) -> color_eyre::Result<()> {
+    let app = create_router();
+    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
+    tracing::info!("Mock snakes server running on port {}", port);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use color_eyre::eyre::Context;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+#[tokio::main]
+async fn main() -> color_eyre::Result<()> {
+    color_eyre::install()?;
+
+    // Initialize tracing
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "mock_snakes=info".into()),
+        )
+        .with(tracing_subscriber::fmt::layer())
+        .init();
+
+    // Get port from env or default to 8082
+    let port: u16 = std::env::var("MOCK_SNAKES_PORT")
+        .unwrap_or_else(|_| "8082".to_string())
+        .parse()
+        .wrap_err("Invalid MOCK_SNAKES_PORT")?;
+
+    tracing::info!("Starting mock snakes server on port {}", port);
+
+    mock_snakes::run_server(port).await
+}
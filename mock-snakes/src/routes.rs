@@ -0,0 +1,183 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use rand::seq::SliceRandom;
+use serde_json::{Value, json};
+
+use crate::state::MockSnakesState;
+
+/// How a mock snake responds to /move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Behavior {
+    /// Always move in a fixed direction (runs into a wall quickly)
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Pick a random direction every turn
+    Random,
+    /// Pick a random direction that stays on the board and off its own body
+    Safe,
+    /// Sleep past the default 500ms timeout before answering
+    Slow,
+    /// Respond with a 500 to every request
+    Error,
+    /// Respond with a move the engine can't parse
+    Invalid,
+}
+
+impl FromStr for Behavior {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "up" => Ok(Behavior::Up),
+            "down" => Ok(Behavior::Down),
+            "left" => Ok(Behavior::Left),
+            "right" => Ok(Behavior::Right),
+            "random" => Ok(Behavior::Random),
+            "safe" => Ok(Behavior::Safe),
+            "slow" => Ok(Behavior::Slow),
+            "error" => Ok(Behavior::Error),
+            "invalid" => Ok(Behavior::Invalid),
+            _ => Err(()),
+        }
+    }
+}
+
+const DIRECTIONS: [&str; 4] = ["up", "down", "left", "right"];
+
+/// Resolve the path segment into a behavior, or a 404
+fn parse_behavior(behavior: &str) -> Result<Behavior, StatusCode> {
+    behavior.parse().map_err(|_| StatusCode::NOT_FOUND)
+}
+
+/// GET /{behavior}
+///
+/// Battlesnake info endpoint.
+pub async fn info(
+    State(state): State<MockSnakesState>,
+    Path(behavior): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let parsed = parse_behavior(&behavior)?;
+    state.record(&behavior, "info").await;
+
+    if parsed == Behavior::Error {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    Ok(Json(json!({
+        "apiversion": "1",
+        "author": "mock-snakes",
+        "color": "#888888",
+        "head": "default",
+        "tail": "default",
+        "version": behavior,
+    })))
+}
+
+/// POST /{behavior}/start
+pub async fn start(
+    State(state): State<MockSnakesState>,
+    Path(behavior): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let parsed = parse_behavior(&behavior)?;
+    state.record(&behavior, "start").await;
+
+    if parsed == Behavior::Error {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// POST /{behavior}/move
+pub async fn make_move(
+    State(state): State<MockSnakesState>,
+    Path(behavior): Path<String>,
+    Json(game): Json<Value>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let parsed = parse_behavior(&behavior)?;
+    state.record(&behavior, "move").await;
+
+    let direction = match parsed {
+        Behavior::Up => "up",
+        Behavior::Down => "down",
+        Behavior::Left => "left",
+        Behavior::Right => "right",
+        Behavior::Random => DIRECTIONS
+            .choose(&mut rand::thread_rng())
+            .copied()
+            .unwrap_or("up"),
+        Behavior::Safe => safe_direction(&game),
+        Behavior::Slow => {
+            tokio::time::sleep(Duration::from_millis(1000)).await;
+            "up"
+        }
+        Behavior::Error => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+        Behavior::Invalid => "sideways",
+    };
+
+    Ok(Json(json!({ "move": direction, "shout": behavior })))
+}
+
+/// POST /{behavior}/end
+pub async fn end(
+    State(state): State<MockSnakesState>,
+    Path(behavior): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    parse_behavior(&behavior)?;
+    state.record(&behavior, "end").await;
+
+    Ok(StatusCode::OK)
+}
+
+/// GET /_admin/requests
+///
+/// Returns request counts keyed by "{behavior}/{endpoint}".
+pub async fn request_counts(State(state): State<MockSnakesState>) -> impl IntoResponse {
+    Json(state.counts().await)
+}
+
+/// POST /_admin/reset
+pub async fn reset(State(state): State<MockSnakesState>) -> impl IntoResponse {
+    state.reset().await;
+    StatusCode::OK
+}
+
+/// Pick a random direction that keeps the snake on the board and off its own body
+fn safe_direction(game: &Value) -> &'static str {
+    let coord = |v: &Value| Some((v.get("x")?.as_i64()?, v.get("y")?.as_i64()?));
+
+    let width = game["board"]["width"].as_i64().unwrap_or(11);
+    let height = game["board"]["height"].as_i64().unwrap_or(11);
+    let Some((head_x, head_y)) = coord(&game["you"]["head"]) else {
+        return "up";
+    };
+    let body: Vec<(i64, i64)> = game["you"]["body"]
+        .as_array()
+        .map(|body| body.iter().filter_map(coord).collect())
+        .unwrap_or_default();
+
+    let mut options: Vec<&'static str> = DIRECTIONS
+        .into_iter()
+        .filter(|direction| {
+            let (x, y) = match *direction {
+                "up" => (head_x, head_y + 1),
+                "down" => (head_x, head_y - 1),
+                "left" => (head_x - 1, head_y),
+                _ => (head_x + 1, head_y),
+            };
+            x >= 0 && y >= 0 && x < width && y < height && !body.contains(&(x, y))
+        })
+        .collect();
+
+    options.shuffle(&mut rand::thread_rng());
+    options.first().copied().unwrap_or("up")
+}
@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Tracks how many times each snake endpoint has been called
+#[derive(Clone, Default)]
+pub struct MockSnakesState {
+    /// Maps "{behavior}/{endpoint}" -> request count
+    counts: Arc<RwLock<HashMap<String, u64>>>,
+}
+
+impl MockSnakesState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a request to an endpoint of a snake
+    pub async fn record(&self, behavior: &str, endpoint: &str) {
+        *self
+            .counts
+            .write()
+            .await
+            .entry(format!("{behavior}/{endpoint}"))
+            .or_default() += 1;
+    }
+
+    /// Snapshot of all request counts
+    pub async fn counts(&self) -> HashMap<String, u64> {
+        self.counts.read().await.clone()
+    }
+
+    /// Clear all request counts
+    pub async fn reset(&self) {
+        self.counts.write().await.clear();
+    }
+}
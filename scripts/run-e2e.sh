@@ -0,0 +1,29 @@
+#!/usr/bin/env bash
+# Runs the full E2E suite locally: prepares the test database, then lets Playwright
+# boot the arena server, the mock GitHub OAuth server and the mock snakes server.
+# Requires: Postgres running locally, sqlx-cli, Node.js.
+#
+# Usage: ./scripts/run-e2e.sh [playwright args...]
+# Examples:
+#   ./scripts/run-e2e.sh                            # run everything headless
+#   ./scripts/run-e2e.sh tests/game-lifecycle.spec.ts --headed
+
+set -euo pipefail
+
+export DATABASE_URL="${DATABASE_URL:-postgresql://localhost:5432/arena_test}"
+
+cd "$(dirname "$0")/.."
+
+echo "Preparing test database..."
+cargo sqlx database create
+cargo sqlx migrate run
+
+cd e2e
+
+if [ ! -d node_modules ]; then
+  echo "Installing E2E dependencies..."
+  npm ci
+  npx playwright install chromium
+fi
+
+exec npx playwright test "$@"
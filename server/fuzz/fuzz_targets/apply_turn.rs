@@ -0,0 +1,8 @@
+//! Plays turns on arbitrary boards, see `arena::engine::fuzz`
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    arena::engine::fuzz::play(data);
+});
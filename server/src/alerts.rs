@@ -0,0 +1,221 @@
+//! Win rate alerts: canary monitoring for snake deploys
+//!
+//! An alert compares a snake's win rate over its latest `window_games`
+//! finished games with the window of games before that, and fires when it
+//! drops by more than `drop_percent` percentage points. It fires once per
+//! regression: it stays quiet until the drop is over, then can fire again.
+//! Firing records an event that the snake's owner sees on the snake's page,
+//! and notifies them.
+
+use crate::engine::hooks::{EngineHook, GameEnded};
+use crate::models::battlesnake::get_battlesnake_by_id;
+use crate::models::game_battlesnake::get_recent_finished_placements;
+use crate::models::snake_alert::{
+    SnakeAlert, create_alert_event, get_all_snake_alerts, get_snake_alerts_for_snakes,
+    record_alert_check,
+};
+use crate::notifications::{notify, win_rate_alert};
+use crate::state::AppState;
+
+/// Win rate, in percent, of a run of placements
+fn win_rate(placements: &[i32]) -> f64 {
+    let wins = placements.iter().filter(|&&p| p == 1).count();
+    wins as f64 / placements.len() as f64 * 100.0
+}
+
+/// The win rates over the window before the latest one and over the latest
+/// window, or None until the snake has finished two full windows of games
+pub fn window_win_rates(placements_newest_first: &[i32], window: usize) -> Option<(f64, f64)> {
+    if window == 0 || placements_newest_first.len() < window * 2 {
+        return None;
+    }
+
+    let (current, previous) = placements_newest_first[..window * 2].split_at(window);
+    Some((win_rate(previous), win_rate(current)))
+}
+
+/// What a check did to an alert
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlertTransition {
+    /// The win rate just dropped past the threshold
+    Fire { previous: f64, current: f64 },
+    /// A drop the alert fired for is over
+    Resolve,
+    /// Nothing changed
+    Unchanged,
+}
+
+/// Check an alert against the snake's latest placements, newest first
+pub fn check_alert(alert: &SnakeAlert, placements_newest_first: &[i32]) -> AlertTransition {
+    let window = usize::try_from(alert.window_games).unwrap_or(0);
+    let Some((previous, current)) = window_win_rates(placements_newest_first, window) else {
+        return AlertTransition::Unchanged;
+    };
+
+    let dropped = previous - current > alert.drop_percent;
+    match (alert.firing, dropped) {
+        (false, true) => AlertTransition::Fire { previous, current },
+        (true, false) => AlertTransition::Resolve,
+        _ => AlertTransition::Unchanged,
+    }
+}
+
+/// Check every alert against its snake's latest games
+pub async fn evaluate_alerts(app_state: &AppState) -> cja::Result<()> {
+    let alerts = get_all_snake_alerts(&app_state.db).await?;
+
+    let mut fired = 0usize;
+    for alert in &alerts {
+        if evaluate_alert(app_state, alert).await? {
+            fired += 1;
+        }
+    }
+
+    tracing::info!("Checked {} win rate alerts, {} fired", alerts.len(), fired);
+    Ok(())
+}
+
+/// Check an alert against its snake's latest games, returning whether it fired
+async fn evaluate_alert(app_state: &AppState, alert: &SnakeAlert) -> cja::Result<bool> {
+    let placements = get_recent_finished_placements(
+        &app_state.db,
+        alert.battlesnake_id,
+        i64::from(alert.window_games) * 2,
+    )
+    .await?;
+
+    let now = app_state.clock.now();
+    match check_alert(alert, &placements) {
+        AlertTransition::Fire { previous, current } => {
+            create_alert_event(&app_state.db, alert.alert_id, previous, current).await?;
+            record_alert_check(&app_state.db, alert.alert_id, true, now).await?;
+            if let Some(snake) = get_battlesnake_by_id(&app_state.db, alert.battlesnake_id).await? {
+                notify(
+                    app_state,
+                    vec![win_rate_alert(
+                        alert.user_id,
+                        alert.battlesnake_id,
+                        &snake.name,
+                        previous,
+                        current,
+                    )],
+                )
+                .await?;
+            }
+            tracing::info!(
+                alert_id = %alert.alert_id,
+                battlesnake_id = %alert.battlesnake_id,
+                "Win rate alert fired: {:.1}% -> {:.1}%",
+                previous,
+                current
+            );
+            return Ok(true);
+        }
+        AlertTransition::Resolve => {
+            record_alert_check(&app_state.db, alert.alert_id, false, now).await?
+        }
+        AlertTransition::Unchanged => {
+            record_alert_check(&app_state.db, alert.alert_id, alert.firing, now).await?
+        }
+    }
+    Ok(false)
+}
+
+/// Checks the alerts on a game's snakes as soon as it ends, rather than at the
+/// next `SnakeAlertsJob`, which still checks every alert as a backstop
+pub struct AlertsHook;
+
+#[async_trait::async_trait]
+impl EngineHook for AlertsHook {
+    fn name(&self) -> &'static str {
+        "alerts"
+    }
+
+    async fn on_game_end(&self, app_state: &AppState, event: &GameEnded<'_>) -> cja::Result<()> {
+        let mut snake_ids: Vec<_> = event.snakes.iter().map(|s| s.battlesnake_id).collect();
+        snake_ids.sort();
+        snake_ids.dedup();
+
+        for alert in get_snake_alerts_for_snakes(&app_state.db, &snake_ids).await? {
+            evaluate_alert(app_state, &alert).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn alert(window_games: i32, drop_percent: f64, firing: bool) -> SnakeAlert {
+        SnakeAlert {
+            alert_id: Uuid::new_v4(),
+            battlesnake_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            window_games,
+            drop_percent,
+            firing,
+            last_checked_at: None,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_window_win_rates() {
+        // Newest first: lost the last 4 after winning 3 of the 4 before
+        let placements = [2, 3, 2, 2, 1, 1, 2, 1];
+        assert_eq!(window_win_rates(&placements, 4), Some((75.0, 0.0)));
+        assert_eq!(window_win_rates(&placements, 2), Some((0.0, 0.0)));
+
+        // Only compares full windows
+        assert_eq!(window_win_rates(&placements[..7], 4), None);
+        assert_eq!(window_win_rates(&placements, 0), None);
+    }
+
+    #[test]
+    fn test_check_alert_fires_once_per_regression() {
+        let dropped = [2, 2, 1, 1];
+        let steady = [1, 2, 1, 2];
+
+        assert_eq!(
+            check_alert(&alert(2, 15.0, false), &dropped),
+            AlertTransition::Fire {
+                previous: 100.0,
+                current: 0.0
+            }
+        );
+        // Still down: already fired
+        assert_eq!(
+            check_alert(&alert(2, 15.0, true), &dropped),
+            AlertTransition::Unchanged
+        );
+        assert_eq!(
+            check_alert(&alert(2, 15.0, true), &steady),
+            AlertTransition::Resolve
+        );
+        assert_eq!(
+            check_alert(&alert(2, 15.0, false), &steady),
+            AlertTransition::Unchanged
+        );
+    }
+
+    #[test]
+    fn test_check_alert_threshold() {
+        // 100% -> 50% is a 50 point drop
+        let placements = [1, 2, 1, 1];
+        assert!(matches!(
+            check_alert(&alert(2, 49.0, false), &placements),
+            AlertTransition::Fire { .. }
+        ));
+        assert_eq!(
+            check_alert(&alert(2, 50.0, false), &placements),
+            AlertTransition::Unchanged
+        );
+        // Not enough games yet
+        assert_eq!(
+            check_alert(&alert(50, 15.0, false), &placements),
+            AlertTransition::Unchanged
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! API versioning and deprecation headers
+//!
+//! The API is served under `/api/v1`. The unversioned `/api` routes are kept as
+//! aliases of the current version so CLI installs and board viewer links from
+//! before the prefix keep working, but their responses are marked deprecated
+//! and link to the versioned route. A breaking change ships as a new prefix,
+//! leaving the old one serving what it always did.
+//!
+//! Clients can also name the version they were written against in the
+//! `Arena-Api-Version` header. A request for a version the route doesn't serve
+//! gets a 400 instead of a response it might misread, and every response says
+//! which version answered it.
+
+use axum::{
+    extract::Request,
+    http::{
+        HeaderMap, HeaderName, HeaderValue, StatusCode,
+        header::{LINK, VARY},
+    },
+    middleware::Next,
+    response::{IntoResponse as _, Response},
+};
+
+use crate::public_url;
+
+/// The version the unversioned aliases serve, and the newest one
+pub const CURRENT_VERSION: u32 = 1;
+
+/// Every version this server has a prefix for
+pub const SUPPORTED_VERSIONS: &[u32] = &[1];
+
+/// Request and response header naming an API version
+pub const VERSION_HEADER: HeaderName = HeaderName::from_static("arena-api-version");
+
+/// RFC 9745 header marking a deprecated resource
+pub const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+
+/// When the unversioned aliases were deprecated (2026-10-16), as a Unix timestamp
+const UNVERSIONED_DEPRECATED_AT: i64 = 1792108800;
+
+/// The version a request asks for, None if it doesn't say
+fn requested_version(headers: &HeaderMap) -> Result<Option<u32>, String> {
+    let Some(value) = headers.get(&VERSION_HEADER) else {
+        return Ok(None);
+    };
+
+    value
+        .to_str()
+        .ok()
+        .map(|s| s.trim().trim_start_matches(['v', 'V']))
+        .and_then(|s| s.parse().ok())
+        .map(Some)
+        .ok_or_else(|| {
+            format!(
+                "{} must be a version number like {}",
+                VERSION_HEADER, CURRENT_VERSION
+            )
+        })
+}
+
+/// Middleware for routes served as `version`: rejects requests asking for a
+/// different version, and labels responses with the version that answered
+pub async fn negotiate_version(version: u32, request: Request, next: Next) -> Response {
+    match requested_version(request.headers()) {
+        Ok(None) => {}
+        Ok(Some(requested)) if requested == version => {}
+        Ok(Some(requested)) => {
+            let message = if SUPPORTED_VERSIONS.contains(&requested) {
+                format!(
+                    "This route serves API version {}, use /api/v{} for version {}",
+                    version, requested, requested
+                )
+            } else {
+                format!(
+                    "API version {} isn't supported, this server supports {:?}",
+                    requested, SUPPORTED_VERSIONS
+                )
+            };
+            return (StatusCode::BAD_REQUEST, message).into_response();
+        }
+        Err(message) => return (StatusCode::BAD_REQUEST, message).into_response(),
+    }
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(VERSION_HEADER, HeaderValue::from(version));
+    headers.append(VARY, HeaderValue::from_static("arena-api-version"));
+    response
+}
+
+/// Middleware for the unversioned aliases: marks responses deprecated and
+/// links the versioned route that replaces them
+pub async fn deprecate_unversioned(request: Request, next: Next) -> Response {
+    // Nested under /api, so the path here is what follows it
+    let successor = public_url::path(&format!(
+        "/api/v{}{}",
+        CURRENT_VERSION,
+        request.uri().path()
+    ));
+
+    let mut response = next.run(request).await;
+    mark_deprecated(
+        response.headers_mut(),
+        UNVERSIONED_DEPRECATED_AT,
+        &successor,
+    );
+    response
+}
+
+/// Mark a response deprecated since `deprecated_at`, pointing clients at `successor`
+pub fn mark_deprecated(headers: &mut HeaderMap, deprecated_at: i64, successor: &str) {
+    if let Ok(value) = HeaderValue::from_str(&format!("@{}", deprecated_at)) {
+        headers.insert(DEPRECATION_HEADER, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", successor))
+    {
+        headers.append(LINK, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers_with_version(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(VERSION_HEADER, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_requested_version() {
+        assert_eq!(requested_version(&HeaderMap::new()), Ok(None));
+        assert_eq!(requested_version(&headers_with_version("1")), Ok(Some(1)));
+        assert_eq!(
+            requested_version(&headers_with_version(" v2 ")),
+            Ok(Some(2))
+        );
+        assert!(requested_version(&headers_with_version("latest")).is_err());
+        assert!(requested_version(&headers_with_version("-1")).is_err());
+    }
+
+    #[test]
+    fn test_mark_deprecated() {
+        let mut headers = HeaderMap::new();
+        mark_deprecated(&mut headers, UNVERSIONED_DEPRECATED_AT, "/api/v1/games");
+
+        assert_eq!(headers[&DEPRECATION_HEADER], "@1792108800");
+        assert_eq!(headers[LINK], "</api/v1/games>; rel=\"successor-version\"");
+    }
+}
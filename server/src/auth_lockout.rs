@@ -0,0 +1,573 @@
+//! Brute-force protection for Bearer token authentication on `/api/*`
+//!
+//! Failed token authentications are tracked per client IP and per token prefix.
+//! Once a key crosses the failure threshold it is locked out for a duration that
+//! doubles with every subsequent lockout. State is kept in memory for the hot path
+//! and written through to the `auth_lockouts` table so lockouts survive restarts
+//! and are picked up by other instances the first time they see a key.
+
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{AUTHORIZATION, RETRY_AFTER},
+    },
+    middleware::Next,
+    response::{IntoResponse as _, Response},
+};
+use chrono::{DateTime, Duration, Utc};
+use tokio::sync::RwLock;
+
+use crate::{
+    clock::{SharedClock, SystemClock},
+    config::env_or,
+    models::auth_lockout,
+    state::AppState,
+};
+
+/// Number of leading token characters used to group failures by token
+const TOKEN_PREFIX_LEN: usize = 8;
+
+/// Maximum number of keys kept in memory before idle entries are pruned
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+/// Tunables for failed-auth lockouts
+#[derive(Debug, Clone)]
+pub struct AuthLockoutConfig {
+    /// Failures within `failure_window` before a key is locked out
+    pub failure_threshold: u32,
+    /// Window over which failures are counted
+    pub failure_window: Duration,
+    /// Duration of the first lockout, doubled for each subsequent lockout
+    pub base_lockout: Duration,
+    /// Upper bound for a single lockout
+    pub max_lockout: Duration,
+    /// Distinct failing token prefixes from a single IP (within `failure_window`)
+    /// before we report a credential stuffing attempt to Sentry
+    pub stuffing_threshold: usize,
+}
+
+impl Default for AuthLockoutConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            failure_window: Duration::minutes(15),
+            base_lockout: Duration::seconds(30),
+            max_lockout: Duration::hours(1),
+            stuffing_threshold: 10,
+        }
+    }
+}
+
+impl AuthLockoutConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let config = Self {
+            failure_threshold: env_or("ARENA_AUTH_LOCKOUT_THRESHOLD", defaults.failure_threshold),
+            failure_window: Duration::seconds(env_or(
+                "ARENA_AUTH_LOCKOUT_WINDOW_SECS",
+                defaults.failure_window.num_seconds(),
+            )),
+            base_lockout: Duration::seconds(env_or(
+                "ARENA_AUTH_LOCKOUT_BASE_SECS",
+                defaults.base_lockout.num_seconds(),
+            )),
+            max_lockout: Duration::seconds(env_or(
+                "ARENA_AUTH_LOCKOUT_MAX_SECS",
+                defaults.max_lockout.num_seconds(),
+            )),
+            stuffing_threshold: env_or(
+                "ARENA_AUTH_STUFFING_THRESHOLD",
+                defaults.stuffing_threshold,
+            ),
+        };
+
+        tracing::info!(
+            threshold = config.failure_threshold,
+            window_secs = config.failure_window.num_seconds(),
+            base_secs = config.base_lockout.num_seconds(),
+            max_secs = config.max_lockout.num_seconds(),
+            "Auth lockout configured"
+        );
+
+        config
+    }
+
+    /// Duration of a lockout given how many lockouts the key has already served
+    fn lockout_duration(&self, previous_lockouts: u32) -> Duration {
+        // Cap the exponent so the multiplier can't overflow
+        let multiplier = 1i32 << previous_lockouts.min(20);
+        self.base_lockout
+            .checked_mul(multiplier)
+            .map_or(self.max_lockout, |d| d.min(self.max_lockout))
+    }
+}
+
+/// Failure/lockout state for a single key
+#[derive(Debug, Clone, Default, PartialEq)]
+struct LockoutEntry {
+    failure_count: u32,
+    lockout_count: u32,
+    first_failure_at: Option<DateTime<Utc>>,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+impl LockoutEntry {
+    fn from_db(row: auth_lockout::AuthLockout) -> Self {
+        Self {
+            failure_count: row.failure_count.max(0) as u32,
+            lockout_count: row.lockout_count.max(0) as u32,
+            first_failure_at: row.first_failure_at,
+            locked_until: row.locked_until,
+        }
+    }
+
+    /// The lockout expiry if the key is currently locked out
+    fn active_lockout(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.locked_until.filter(|until| *until > now)
+    }
+
+    /// Whether this entry has anything worth keeping around
+    fn is_idle(&self, now: DateTime<Utc>, config: &AuthLockoutConfig) -> bool {
+        let window_expired = self
+            .first_failure_at
+            .is_none_or(|start| now - start > config.failure_window);
+        let lockout_expired = self
+            .locked_until
+            .is_none_or(|until| now - until > config.max_lockout);
+        window_expired && lockout_expired
+    }
+
+    /// Record a failed attempt, returning the lockout expiry if this failure triggered a lockout
+    fn register_failure(
+        &mut self,
+        now: DateTime<Utc>,
+        config: &AuthLockoutConfig,
+    ) -> Option<DateTime<Utc>> {
+        // Start a fresh counting window if the previous one has expired
+        if self
+            .first_failure_at
+            .is_none_or(|start| now - start > config.failure_window)
+        {
+            self.first_failure_at = Some(now);
+            self.failure_count = 0;
+        }
+
+        self.failure_count += 1;
+
+        if self.failure_count < config.failure_threshold {
+            return None;
+        }
+
+        let locked_until = now + config.lockout_duration(self.lockout_count);
+        self.lockout_count += 1;
+        self.failure_count = 0;
+        self.first_failure_at = None;
+        self.locked_until = Some(locked_until);
+
+        Some(locked_until)
+    }
+}
+
+/// Distinct failing token prefixes seen from one IP, used to spot credential stuffing
+#[derive(Debug, Clone)]
+struct StuffingTracker {
+    window_start: DateTime<Utc>,
+    token_prefixes: HashSet<String>,
+    alerted: bool,
+}
+
+/// Shared store of failed-auth state, cheap to clone
+#[derive(Debug, Clone)]
+pub struct AuthLockouts {
+    config: Arc<AuthLockoutConfig>,
+    clock: SharedClock,
+    entries: Arc<RwLock<HashMap<String, LockoutEntry>>>,
+    stuffing: Arc<RwLock<HashMap<String, StuffingTracker>>>,
+}
+
+impl Default for AuthLockouts {
+    fn default() -> Self {
+        Self::new(AuthLockoutConfig::default(), Arc::new(SystemClock))
+    }
+}
+
+impl AuthLockouts {
+    pub fn new(config: AuthLockoutConfig, clock: SharedClock) -> Self {
+        Self {
+            config: Arc::new(config),
+            clock,
+            entries: Arc::new(RwLock::new(HashMap::new())),
+            stuffing: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Make sure a key's state is loaded into memory, reading it from the DB on first sight
+    async fn load(&self, db: &sqlx::PgPool, key: &str) -> cja::Result<LockoutEntry> {
+        if let Some(entry) = self.entries.read().await.get(key) {
+            return Ok(entry.clone());
+        }
+
+        let entry = auth_lockout::get_auth_lockout(db, key)
+            .await?
+            .map(LockoutEntry::from_db)
+            .unwrap_or_default();
+
+        let mut entries = self.entries.write().await;
+        if entries.len() >= MAX_TRACKED_KEYS {
+            let now = self.clock.now();
+            entries.retain(|_, e| !e.is_idle(now, &self.config));
+        }
+        Ok(entries.entry(key.to_string()).or_insert(entry).clone())
+    }
+
+    /// Returns the latest lockout expiry across the given keys, if any of them are locked out
+    pub async fn check(
+        &self,
+        db: &sqlx::PgPool,
+        keys: &[String],
+    ) -> cja::Result<Option<DateTime<Utc>>> {
+        let now = self.clock.now();
+        let mut locked_until = None;
+
+        for key in keys {
+            let entry = self.load(db, key).await?;
+            locked_until = locked_until.max(entry.active_lockout(now));
+        }
+
+        Ok(locked_until)
+    }
+
+    /// Record a failed authentication against every key
+    pub async fn record_failure(&self, db: &sqlx::PgPool, keys: &[String]) -> cja::Result<()> {
+        let now = self.clock.now();
+
+        for key in keys {
+            self.load(db, key).await?;
+
+            let (entry, locked_until) = {
+                let mut entries = self.entries.write().await;
+                let entry = entries.entry(key.clone()).or_default();
+                let locked_until = entry.register_failure(now, &self.config);
+                (entry.clone(), locked_until)
+            };
+
+            if let Some(locked_until) = locked_until {
+                tracing::warn!(
+                    lockout_key = %key,
+                    lockout_count = entry.lockout_count,
+                    locked_until = %locked_until,
+                    "Locking out API token authentication after repeated failures"
+                );
+            }
+
+            auth_lockout::upsert_auth_lockout(
+                db,
+                key,
+                entry.failure_count as i32,
+                entry.lockout_count as i32,
+                entry.first_failure_at,
+                entry.locked_until,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Clear failure state for a key after a successful authentication
+    pub async fn record_success(&self, db: &sqlx::PgPool, key: &str) -> cja::Result<()> {
+        let had_state = {
+            let mut entries = self.entries.write().await;
+            entries
+                .insert(key.to_string(), LockoutEntry::default())
+                .is_some_and(|previous| previous != LockoutEntry::default())
+        };
+
+        if had_state {
+            auth_lockout::delete_auth_lockout(db, key).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Track the token prefix that failed from this IP, returning the number of
+    /// distinct prefixes the first time the stuffing threshold is crossed
+    async fn track_stuffing(&self, ip: &str, token_prefix: &str) -> Option<usize> {
+        let now = self.clock.now();
+        let mut stuffing = self.stuffing.write().await;
+
+        if stuffing.len() >= MAX_TRACKED_KEYS {
+            stuffing.retain(|_, t| now - t.window_start <= self.config.failure_window);
+        }
+
+        let tracker = stuffing
+            .entry(ip.to_string())
+            .or_insert_with(|| StuffingTracker {
+                window_start: now,
+                token_prefixes: HashSet::new(),
+                alerted: false,
+            });
+
+        if now - tracker.window_start > self.config.failure_window {
+            tracker.window_start = now;
+            tracker.token_prefixes.clear();
+            tracker.alerted = false;
+        }
+
+        tracker.token_prefixes.insert(token_prefix.to_string());
+
+        if !tracker.alerted && tracker.token_prefixes.len() >= self.config.stuffing_threshold {
+            tracker.alerted = true;
+            Some(tracker.token_prefixes.len())
+        } else {
+            None
+        }
+    }
+}
+
+/// Best-effort client IP for a request
+///
+/// Behind Cloud Run the load balancer appends the address it saw to `X-Forwarded-For`,
+/// so the last entry is the one we can trust; anything before it is client supplied.
+fn client_ip(headers: &HeaderMap, connect_info: Option<&ConnectInfo<SocketAddr>>) -> String {
+    headers
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next())
+        .map(str::trim)
+        .filter(|ip| !ip.is_empty())
+        .map(String::from)
+        .or_else(|| connect_info.map(|ConnectInfo(addr)| addr.ip().to_string()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// The token prefix from a `Bearer` Authorization header, if present
+fn token_prefix(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.chars().take(TOKEN_PREFIX_LEN).collect())
+}
+
+/// Middleware that rejects locked out clients and records failed Bearer token authentications
+///
+/// Only requests carrying an `Authorization` header are tracked, a 401 from such a
+/// request means the token was rejected by the `ApiUser` extractor.
+pub async fn enforce_auth_lockout(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !request.headers().contains_key(AUTHORIZATION) {
+        return next.run(request).await;
+    }
+
+    let ip = client_ip(request.headers(), request.extensions().get());
+    let prefix = token_prefix(request.headers());
+
+    let ip_key = format!("ip:{ip}");
+    let token_key = prefix.as_ref().map(|p| format!("token:{p}"));
+    let keys: Vec<String> = std::iter::once(ip_key).chain(token_key.clone()).collect();
+
+    // Fail open if the lockout store is unavailable - we'd rather not lock everyone out
+    match state.auth_lockouts.check(&state.db, &keys).await {
+        Ok(Some(locked_until)) => {
+            let retry_after = (locked_until - state.clock.now()).num_seconds().max(1);
+            tracing::info!(ip = %ip, retry_after, "Rejecting locked out API authentication");
+
+            let mut response = (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Too many failed authentication attempts, try again later",
+            )
+                .into_response();
+            if let Ok(value) = HeaderValue::from_str(&retry_after.to_string()) {
+                response.headers_mut().insert(RETRY_AFTER, value);
+            }
+            return response;
+        }
+        Ok(None) => {}
+        Err(e) => tracing::error!("Failed to check auth lockout: {:?}", e),
+    }
+
+    let response = next.run(request).await;
+
+    if response.status() == StatusCode::UNAUTHORIZED {
+        if let Err(e) = state.auth_lockouts.record_failure(&state.db, &keys).await {
+            tracing::error!("Failed to record auth failure: {:?}", e);
+        }
+
+        if let Some(prefix) = &prefix
+            && let Some(distinct) = state.auth_lockouts.track_stuffing(&ip, prefix).await
+        {
+            tracing::warn!(ip = %ip, distinct_tokens = distinct, "Possible credential stuffing against API");
+            sentry::capture_message(
+                &format!(
+                    "Possible credential stuffing: {distinct} distinct API tokens rejected from {ip}"
+                ),
+                sentry::Level::Warning,
+            );
+        }
+    } else if response.status().is_success()
+        && let Some(token_key) = &token_key
+    {
+        // Only the token key is cleared - an attacker holding one valid token
+        // shouldn't be able to reset their IP's failure count
+        if let Err(e) = state
+            .auth_lockouts
+            .record_success(&state.db, token_key)
+            .await
+        {
+            tracing::error!("Failed to clear auth lockout: {:?}", e);
+        }
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use chrono::TimeZone;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap()
+    }
+
+    fn test_config() -> AuthLockoutConfig {
+        AuthLockoutConfig {
+            failure_threshold: 3,
+            failure_window: Duration::minutes(5),
+            base_lockout: Duration::seconds(10),
+            max_lockout: Duration::seconds(60),
+            stuffing_threshold: 3,
+        }
+    }
+
+    #[test]
+    fn test_lockout_duration_doubles_and_caps() {
+        let config = test_config();
+
+        assert_eq!(config.lockout_duration(0), Duration::seconds(10));
+        assert_eq!(config.lockout_duration(1), Duration::seconds(20));
+        assert_eq!(config.lockout_duration(2), Duration::seconds(40));
+        assert_eq!(config.lockout_duration(3), Duration::seconds(60));
+        assert_eq!(config.lockout_duration(100), Duration::seconds(60));
+    }
+
+    #[test]
+    fn test_lockout_after_threshold_failures() {
+        let config = test_config();
+        let now = start();
+        let mut entry = LockoutEntry::default();
+
+        assert_eq!(entry.register_failure(now, &config), None);
+        assert_eq!(entry.register_failure(now, &config), None);
+        let locked_until = entry.register_failure(now, &config);
+
+        assert_eq!(locked_until, Some(now + Duration::seconds(10)));
+        assert_eq!(entry.active_lockout(now), locked_until);
+        assert_eq!(entry.active_lockout(now + Duration::seconds(11)), None);
+    }
+
+    #[test]
+    fn test_repeat_lockouts_grow_exponentially() {
+        let config = test_config();
+        let now = start();
+        let mut entry = LockoutEntry::default();
+
+        for _ in 0..3 {
+            entry.register_failure(now, &config);
+        }
+        let later = now + Duration::seconds(11);
+        entry.register_failure(later, &config);
+        entry.register_failure(later, &config);
+        let locked_until = entry.register_failure(later, &config);
+
+        assert_eq!(locked_until, Some(later + Duration::seconds(20)));
+        assert_eq!(entry.lockout_count, 2);
+    }
+
+    #[test]
+    fn test_failures_outside_window_reset_count() {
+        let config = test_config();
+        let now = start();
+        let mut entry = LockoutEntry::default();
+
+        entry.register_failure(now, &config);
+        entry.register_failure(now, &config);
+
+        // Third failure arrives after the window, so it starts a new count
+        let later = now + Duration::minutes(6);
+        assert_eq!(entry.register_failure(later, &config), None);
+        assert_eq!(entry.failure_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_stuffing_alerts_once_per_window() {
+        let clock = MockClock::new(start());
+        let lockouts = AuthLockouts::new(test_config(), Arc::new(clock.clone()));
+
+        assert_eq!(lockouts.track_stuffing("1.2.3.4", "aaaaaaaa").await, None);
+        assert_eq!(lockouts.track_stuffing("1.2.3.4", "aaaaaaaa").await, None);
+        assert_eq!(lockouts.track_stuffing("1.2.3.4", "bbbbbbbb").await, None);
+        assert_eq!(
+            lockouts.track_stuffing("1.2.3.4", "cccccccc").await,
+            Some(3)
+        );
+        assert_eq!(lockouts.track_stuffing("1.2.3.4", "dddddddd").await, None);
+
+        // Other IPs are tracked independently
+        assert_eq!(lockouts.track_stuffing("5.6.7.8", "aaaaaaaa").await, None);
+
+        // Once the window has passed the count starts again and can alert again
+        clock.advance(Duration::minutes(6));
+        assert_eq!(lockouts.track_stuffing("1.2.3.4", "aaaaaaaa").await, None);
+        assert_eq!(lockouts.track_stuffing("1.2.3.4", "bbbbbbbb").await, None);
+        assert_eq!(
+            lockouts.track_stuffing("1.2.3.4", "cccccccc").await,
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_client_ip_uses_last_forwarded_entry() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("10.0.0.1, 203.0.113.7"),
+        );
+
+        assert_eq!(client_ip(&headers, None), "203.0.113.7");
+    }
+
+    #[test]
+    fn test_client_ip_falls_back_to_connect_info() {
+        let headers = HeaderMap::new();
+        let addr: SocketAddr = "192.0.2.1:1234".parse().unwrap();
+
+        assert_eq!(client_ip(&headers, Some(&ConnectInfo(addr))), "192.0.2.1");
+        assert_eq!(client_ip(&headers, None), "unknown");
+    }
+
+    #[test]
+    fn test_token_prefix() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(token_prefix(&headers), None);
+
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_static("Bearer 0123456789abcdef"),
+        );
+        assert_eq!(token_prefix(&headers), Some("01234567".to_string()));
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Basic abc"));
+        assert_eq!(token_prefix(&headers), None);
+    }
+}
@@ -0,0 +1,566 @@
+//! Archiving a date range of Engine games from the command line
+//!
+//! `arena admin backfill --from-date --to-date` archives every finished Engine
+//! game played in a range, in batches of [`HISTORICAL_BATCH_SIZE`]. A batch is
+//! a `backup_batches` row and a [`BackupSingleGameJob`] for each of its games
+//! that isn't archived yet, created in one transaction and run by the job
+//! workers. The command waits for each batch to finish before creating the
+//! next, and shows how far through the range and the batch it is.
+//!
+//! Runs are stored in `backfill_runs`, with a cursor at the last game of the
+//! last finished batch. The command can be stopped at any point: running it
+//! again for the same range waits for the unfinished batch, if there is one,
+//! then carries on after the cursor. Unlike the historical discovery job chain,
+//! a run's batches don't enqueue the next one when they finish.
+
+use std::collections::HashSet;
+use std::io::Write as _;
+use std::time::{Duration, Instant};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use color_eyre::eyre::{Context as _, eyre};
+use sqlx::{FromRow, PgPool};
+
+use crate::backup::{HISTORICAL_BATCH_SIZE, get_archived_game_ids};
+use crate::jobs::{BackupSingleGameJob, LOW_PRIORITY, enqueue_at};
+use crate::state::AppState;
+
+/// How often a batch's progress is checked
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long a batch can go without a game finishing before the command warns
+/// that it looks stuck
+const STALL_WARNING: Duration = Duration::from_secs(10 * 60);
+
+/// Width of the progress bars, in characters
+const BAR_WIDTH: usize = 30;
+
+#[derive(Debug)]
+struct BackfillRun {
+    id: i32,
+    cursor_created: Option<NaiveDateTime>,
+    cursor_id: Option<String>,
+    games_archived: i32,
+    games_skipped: i32,
+}
+
+impl BackfillRun {
+    /// The last game handled, None before the first batch finishes
+    fn cursor(&self) -> Option<(NaiveDateTime, &str)> {
+        self.cursor_created.zip(self.cursor_id.as_deref())
+    }
+}
+
+#[derive(Debug)]
+struct RunBatch {
+    id: i32,
+    jobs_enqueued: i32,
+    jobs_completed: i32,
+    next_cursor_created: Option<NaiveDateTime>,
+    next_cursor_id: Option<String>,
+    completed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, FromRow)]
+struct EngineGameRef {
+    id: String,
+    created: NaiveDateTime,
+}
+
+/// The first instant of `from` and the first instant after `to`, in the
+/// Engine's UTC timestamps. None if the range is empty.
+fn range_bounds(from: NaiveDate, to: NaiveDate) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    if from > to {
+        return None;
+    }
+    Some((
+        from.and_time(chrono::NaiveTime::MIN),
+        to.succ_opt()?.and_time(chrono::NaiveTime::MIN),
+    ))
+}
+
+/// A text progress bar, like `[#####-----]  50/100`
+fn progress_bar(done: i64, total: i64, width: usize) -> String {
+    let filled = if total > 0 {
+        (done.clamp(0, total) as usize * width) / total as usize
+    } else {
+        width
+    };
+    format!(
+        "[{}{}] {}/{}",
+        "#".repeat(filled),
+        "-".repeat(width - filled),
+        done,
+        total
+    )
+}
+
+/// The run a batch belongs to, None for the historical discovery chain's batches
+pub async fn batch_run_id(pool: &PgPool, batch_id: i32) -> cja::Result<Option<i32>> {
+    let run_id = sqlx::query_scalar::<_, Option<i32>>(
+        r#"
+        SELECT run_id FROM backup_batches WHERE id = $1
+        "#,
+    )
+    .bind(batch_id)
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to get run of batch {}", batch_id))?;
+
+    Ok(run_id.flatten())
+}
+
+// Get the unfinished run for a range, starting one if there isn't one. Also
+// returns whether it was started just now.
+async fn get_or_start_run(
+    pool: &PgPool,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> cja::Result<(BackfillRun, bool)> {
+    let started = sqlx::query_scalar!(
+        r#"
+        INSERT INTO backfill_runs (from_date, to_date)
+        VALUES ($1, $2)
+        ON CONFLICT (from_date, to_date) WHERE completed_at IS NULL DO NOTHING
+        RETURNING id
+        "#,
+        from,
+        to
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to start backfill run")?;
+
+    let run = sqlx::query_as!(
+        BackfillRun,
+        r#"
+        SELECT id, cursor_created, cursor_id, games_archived, games_skipped
+        FROM backfill_runs
+        WHERE from_date = $1 AND to_date = $2 AND completed_at IS NULL
+        "#,
+        from,
+        to
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err("Failed to get backfill run")?;
+
+    Ok((run, started.is_some()))
+}
+
+async fn get_run(pool: &PgPool, run_id: i32) -> cja::Result<BackfillRun> {
+    sqlx::query_as!(
+        BackfillRun,
+        r#"
+        SELECT id, cursor_created, cursor_id, games_archived, games_skipped
+        FROM backfill_runs
+        WHERE id = $1
+        "#,
+        run_id
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to get backfill run {}", run_id))
+}
+
+// Get a run's most recently created batch
+async fn get_latest_batch(pool: &PgPool, run_id: i32) -> cja::Result<Option<RunBatch>> {
+    sqlx::query_as!(
+        RunBatch,
+        r#"
+        SELECT id, jobs_enqueued, jobs_completed, next_cursor_created, next_cursor_id, completed_at
+        FROM backup_batches
+        WHERE run_id = $1
+        ORDER BY id DESC
+        LIMIT 1
+        "#,
+        run_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to get latest batch of run {}", run_id))
+}
+
+async fn get_batch(pool: &PgPool, batch_id: i32) -> cja::Result<RunBatch> {
+    sqlx::query_as!(
+        RunBatch,
+        r#"
+        SELECT id, jobs_enqueued, jobs_completed, next_cursor_created, next_cursor_id, completed_at
+        FROM backup_batches
+        WHERE id = $1
+        "#,
+        batch_id
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to get batch {}", batch_id))
+}
+
+/// Move a run's cursor forward to `cursor`, counting the games handled on the
+/// way. Does nothing if the cursor is already there or past it, so finishing
+/// the same batch twice counts it once.
+async fn advance_run(
+    pool: &PgPool,
+    run_id: i32,
+    cursor: (NaiveDateTime, &str),
+    archived: i32,
+    skipped: i32,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE backfill_runs
+        SET cursor_created = $2,
+            cursor_id = $3,
+            games_archived = games_archived + $4,
+            games_skipped = games_skipped + $5,
+            updated_at = $6
+        WHERE id = $1
+          AND (cursor_created IS NULL OR (cursor_created, cursor_id) < ($2, $3))
+        "#,
+        run_id,
+        cursor.0,
+        cursor.1,
+        archived,
+        skipped,
+        now
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to advance backfill run {}", run_id))?;
+
+    Ok(())
+}
+
+async fn finish_run(
+    pool: &PgPool,
+    run_id: i32,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<BackfillRun> {
+    sqlx::query_as!(
+        BackfillRun,
+        r#"
+        UPDATE backfill_runs
+        SET completed_at = $2, updated_at = $2
+        WHERE id = $1
+        RETURNING id, cursor_created, cursor_id, games_archived, games_skipped
+        "#,
+        run_id,
+        now
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to finish backfill run {}", run_id))
+}
+
+/// Create a batch of a run with a backup job for each unarchived game, all or
+/// nothing, so an interrupted command never leaves a batch that can't finish.
+async fn create_batch(
+    pool: &PgPool,
+    run_id: i32,
+    next_cursor: (NaiveDateTime, &str),
+    unarchived: &[&str],
+    skipped: i32,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<i32> {
+    let mut tx = pool.begin().await.wrap_err("Failed to start transaction")?;
+
+    let batch_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO backup_batches (next_cursor_created, next_cursor_id, jobs_enqueued, run_id)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id
+        "#,
+        next_cursor.0,
+        next_cursor.1,
+        (unarchived.len() as i32),
+        run_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .wrap_err("Failed to create batch record")?;
+
+    sqlx::query!(
+        r#"
+        UPDATE backfill_runs
+        SET games_skipped = games_skipped + $2, updated_at = $3
+        WHERE id = $1
+        "#,
+        run_id,
+        skipped,
+        now
+    )
+    .execute(&mut *tx)
+    .await
+    .wrap_err("Failed to count skipped games")?;
+
+    for game_id in unarchived {
+        enqueue_at(
+            &mut *tx,
+            BackupSingleGameJob {
+                engine_game_id: game_id.to_string(),
+                batch_id: Some(batch_id),
+            },
+            format!("backup game {}", game_id),
+            LOW_PRIORITY,
+            now,
+        )
+        .await
+        .wrap_err_with(|| format!("Failed to enqueue backup job for game {}", game_id))?;
+    }
+
+    tx.commit().await.wrap_err("Failed to commit batch")?;
+    Ok(batch_id)
+}
+
+/// Up to `limit` finished Engine games played in `[start, end)` after `after`,
+/// oldest first
+async fn fetch_range_page(
+    engine_db: &PgPool,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    after: Option<(NaiveDateTime, &str)>,
+    limit: i32,
+) -> cja::Result<Vec<EngineGameRef>> {
+    // Not the macro: the Engine database's schema isn't known at compile time
+    sqlx::query_as::<_, EngineGameRef>(
+        r#"
+        SELECT id, created
+        FROM games
+        WHERE value->>'Status' IN ('complete', 'error')
+          AND created >= $1 AND created < $2
+          AND ($3::TIMESTAMP IS NULL OR (created, id) > ($3, $4))
+        ORDER BY created ASC, id ASC
+        LIMIT $5
+        "#,
+    )
+    .bind(start)
+    .bind(end)
+    .bind(after.map(|(created, _)| created))
+    .bind(after.map(|(_, id)| id))
+    .bind(limit)
+    .fetch_all(engine_db)
+    .await
+    .wrap_err("Failed to fetch Engine games in range")
+}
+
+/// How many finished Engine games were played in `[start, end)`, only counting
+/// those up to `through` when it's given
+async fn count_range_games(
+    engine_db: &PgPool,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    through: Option<(NaiveDateTime, &str)>,
+) -> cja::Result<i64> {
+    sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*)
+        FROM games
+        WHERE value->>'Status' IN ('complete', 'error')
+          AND created >= $1 AND created < $2
+          AND ($3::TIMESTAMP IS NULL OR (created, id) <= ($3, $4))
+        "#,
+    )
+    .bind(start)
+    .bind(end)
+    .bind(through.map(|(created, _)| created))
+    .bind(through.map(|(_, id)| id))
+    .fetch_one(engine_db)
+    .await
+    .wrap_err("Failed to count Engine games in range")
+}
+
+/// Wait for a batch to finish, redrawing the progress bars as its games are archived
+async fn watch_batch(
+    app_state: &AppState,
+    run_id: i32,
+    mut batch: RunBatch,
+    games_before: i64,
+    total: i64,
+) -> cja::Result<RunBatch> {
+    let mut last_progress = (batch.jobs_completed, Instant::now());
+    let mut warned = false;
+
+    loop {
+        print!(
+            "\rRun {}: {}  Batch {}: {}",
+            run_id,
+            progress_bar(
+                games_before + i64::from(batch.jobs_completed),
+                total,
+                BAR_WIDTH
+            ),
+            batch.id,
+            progress_bar(
+                batch.jobs_completed.into(),
+                batch.jobs_enqueued.into(),
+                BAR_WIDTH
+            ),
+        );
+        std::io::stdout().flush().ok();
+
+        if batch.completed_at.is_some() {
+            println!();
+            return Ok(batch);
+        }
+
+        if batch.jobs_completed != last_progress.0 {
+            last_progress = (batch.jobs_completed, Instant::now());
+        } else if !warned && last_progress.1.elapsed() >= STALL_WARNING {
+            println!(
+                "\nNo game in batch {} has been archived for {} minutes; check the job workers for failing BackupSingleGameJobs",
+                batch.id,
+                STALL_WARNING.as_secs() / 60
+            );
+            warned = true;
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+        batch = get_batch(&app_state.db, batch.id).await?;
+    }
+}
+
+/// Archive every finished Engine game played from `from` to `to`, inclusive,
+/// for `arena admin backfill`
+pub async fn run_backfill_command(
+    app_state: &AppState,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> cja::Result<()> {
+    let engine_db = app_state
+        .engine_db
+        .as_ref()
+        .ok_or_else(|| eyre!("ENGINE_DATABASE_URL isn't set, so there's nothing to backfill"))?;
+    if app_state.storage.is_none() {
+        println!("Warning: STORAGE_URL isn't set here; the job workers need it to archive games");
+    }
+    let (start, end) = range_bounds(from, to)
+        .ok_or_else(|| eyre!("--from-date must be on or before --to-date"))?;
+
+    let (mut run, started) = get_or_start_run(&app_state.db, from, to).await?;
+    if started {
+        println!(
+            "Started backfill run {} for games played {} to {}",
+            run.id, from, to
+        );
+    } else {
+        println!(
+            "Resuming backfill run {} for games played {} to {} ({} archived, {} already archived so far)",
+            run.id, from, to, run.games_archived, run.games_skipped
+        );
+    }
+    let total = count_range_games(engine_db, start, end, None).await?;
+    println!("{} finished Engine games in range", total);
+
+    loop {
+        // Finish what the last batch started before finding more games
+        if let Some(batch) = get_latest_batch(&app_state.db, run.id).await? {
+            let batch = if batch.completed_at.is_none() {
+                let games_before = count_range_games(engine_db, start, end, run.cursor()).await?;
+                watch_batch(app_state, run.id, batch, games_before, total).await?
+            } else {
+                batch
+            };
+            if let Some(cursor) = batch
+                .next_cursor_created
+                .zip(batch.next_cursor_id.as_deref())
+            {
+                advance_run(
+                    &app_state.db,
+                    run.id,
+                    cursor,
+                    batch.jobs_enqueued,
+                    0,
+                    app_state.clock.now(),
+                )
+                .await?;
+            }
+            run = get_run(&app_state.db, run.id).await?;
+        }
+
+        let games =
+            fetch_range_page(engine_db, start, end, run.cursor(), HISTORICAL_BATCH_SIZE).await?;
+        let Some(last) = games.last() else {
+            let run = finish_run(&app_state.db, run.id, app_state.clock.now()).await?;
+            println!(
+                "Done: archived {} games; {} were already archived",
+                run.games_archived, run.games_skipped
+            );
+            return Ok(());
+        };
+        let next_cursor = (last.created, last.id.as_str());
+
+        let game_ids: Vec<String> = games.iter().map(|g| g.id.clone()).collect();
+        let archived = get_archived_game_ids(&app_state.db, &game_ids).await?;
+        let archived: HashSet<&str> = archived.iter().map(|id| id.as_str()).collect();
+        let unarchived: Vec<&str> = game_ids
+            .iter()
+            .map(|id| id.as_str())
+            .filter(|id| !archived.contains(id))
+            .collect();
+        let skipped = (games.len() - unarchived.len()) as i32;
+
+        if unarchived.is_empty() {
+            advance_run(
+                &app_state.db,
+                run.id,
+                next_cursor,
+                0,
+                skipped,
+                app_state.clock.now(),
+            )
+            .await?;
+            run = get_run(&app_state.db, run.id).await?;
+            continue;
+        }
+
+        let batch_id = create_batch(
+            &app_state.db,
+            run.id,
+            next_cursor,
+            &unarchived,
+            skipped,
+            app_state.clock.now(),
+        )
+        .await?;
+        tracing::info!(
+            run_id = run.id,
+            batch_id,
+            jobs = unarchived.len(),
+            skipped,
+            "Created backfill batch"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_bounds_include_the_last_day() {
+        let from = NaiveDate::from_ymd_opt(2024, 2, 28).unwrap();
+        let to = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let (start, end) = range_bounds(from, to).unwrap();
+        assert_eq!(start, from.and_hms_opt(0, 0, 0).unwrap());
+        assert_eq!(
+            end,
+            NaiveDate::from_ymd_opt(2024, 3, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        );
+
+        assert!(range_bounds(from, from).is_some());
+        assert!(range_bounds(to, from).is_none());
+    }
+
+    #[test]
+    fn test_progress_bar() {
+        assert_eq!(progress_bar(0, 4, 4), "[----] 0/4");
+        assert_eq!(progress_bar(2, 4, 4), "[##--] 2/4");
+        assert_eq!(progress_bar(4, 4, 4), "[####] 4/4");
+        // An empty range is already done
+        assert_eq!(progress_bar(0, 0, 4), "[####] 0/0");
+    }
+}
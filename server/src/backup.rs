@@ -1,22 +1,24 @@
-//! Game backup module for archiving games from the Engine database to GCS.
+//! Game backup module for archiving games from the Engine database to storage.
 
-use std::io::Write;
+use std::io::{self, Write};
 
-use chrono::{Duration, Utc};
+use axum::body::Bytes;
+use chrono::{DateTime, Duration, Utc};
 use color_eyre::eyre::{Context as _, eyre};
-use google_cloud_storage::{
-    client::{Client as GcsClient, ClientConfig},
-    http::objects::upload::{Media, UploadObjectRequest, UploadType},
-};
+use futures::{Stream, StreamExt};
 use sqlx::{FromRow, PgPool};
+use zstd::stream::raw::{Decoder, InBuffer, Operation, OutBuffer};
 
 use crate::engine_models::{EngineGame, EngineGameFrame, GameExport};
 use crate::jobs::{BackupSingleGameJob, HistoricalBackupDiscoveryJob};
+use crate::models::game::{ArchivedGame, get_archived_game, set_archive_version};
 use crate::state::AppState;
+use crate::storage::{SharedStorage, StorageBackend};
+use crate::tarball;
 use cja::jobs::Job;
 
 /// Batch size for historical backfill discovery
-const HISTORICAL_BATCH_SIZE: i32 = 500;
+pub(crate) const HISTORICAL_BATCH_SIZE: i32 = 500;
 
 /// Row from Engine's games table
 #[derive(FromRow)]
@@ -27,14 +29,18 @@ struct EngineGameRow {
     created: chrono::NaiveDateTime,
 }
 
-/// Fetch completed games from the Engine database within the given time window.
+/// Start of the lookback window ending at `now`.
+///
+/// Engine DB uses TIMESTAMP (no timezone), so this returns a NaiveDateTime in UTC.
+fn backup_window_start(now: DateTime<Utc>, hours_ago: i64) -> chrono::NaiveDateTime {
+    (now - Duration::hours(hours_ago)).naive_utc()
+}
+
+/// Fetch completed games from the Engine database created at or after `since`.
 async fn fetch_completed_games(
     engine_db: &PgPool,
-    hours_ago: i64,
+    since: chrono::NaiveDateTime,
 ) -> cja::Result<Vec<EngineGameRow>> {
-    // Engine DB uses TIMESTAMP (no timezone), so use NaiveDateTime
-    let since = (Utc::now() - Duration::hours(hours_ago)).naive_utc();
-
     // Note: We use query_as (not the macro) because this is a different database
     // with a different schema that sqlx doesn't know about at compile time.
     // Limit to 5000 as a safety valve - if we hit this, we'll catch the rest next run.
@@ -115,25 +121,48 @@ async fn is_already_archived(db: &PgPool, engine_game_id: &str) -> cja::Result<b
     Ok(result)
 }
 
-/// Generate the GCS path for a game based on its creation date.
+/// Generate the storage path for a game based on its creation date.
 fn gcs_path(game: &EngineGame) -> String {
+    archive_path(game, ARCHIVE_VERSION)
+}
+
+/// The storage path for a game's archive in a given format version.
+///
+/// Version 1 archives have no version in their path. Later versions add one,
+/// so re-encoding an archive never overwrites the object a row still points at.
+pub fn archive_path(game: &EngineGame, version: i32) -> String {
     let created = game.created_at();
+    let suffix = if version == 1 {
+        String::new()
+    } else {
+        format!(".v{}", version)
+    };
     format!(
-        "games/{}/{:02}/{:02}/{}.json.zst",
+        "games/{}/{:02}/{:02}/{}{}.json.zst",
         created.format("%Y"),
         created.format("%m"),
         created.format("%d"),
-        game.id
+        game.id,
+        suffix
     )
 }
 
-/// Compress JSON with zstd and upload to GCS.
-async fn compress_and_upload_to_gcs(
-    client: &GcsClient,
-    bucket: &str,
+/// Compress JSON with zstd and upload to storage.
+async fn compress_and_upload(
+    storage: &dyn StorageBackend,
     path: &str,
     export: &GameExport,
 ) -> cja::Result<()> {
+    let compressed = encode_archive(export)?;
+
+    storage
+        .upload(path, compressed, "application/zstd")
+        .await
+        .wrap_err_with(|| format!("Failed to upload archive to {}", storage.describe()))
+}
+
+/// Encode a game export in the current archive format.
+pub fn encode_archive(export: &GameExport) -> cja::Result<Vec<u8>> {
     // Serialize to JSON
     let json = serde_json::to_vec(export).wrap_err("Failed to serialize game export")?;
 
@@ -155,29 +184,39 @@ async fn compress_and_upload_to_gcs(
         "Compressed game for upload"
     );
 
-    // Upload to GCS
-    let upload_type = UploadType::Simple(Media::new(path.to_string()));
-    client
-        .upload_object(
-            &UploadObjectRequest {
-                bucket: bucket.to_string(),
-                ..Default::default()
-            },
-            compressed,
-            &upload_type,
-        )
-        .await
-        .wrap_err("Failed to upload to GCS")?;
+    Ok(compressed)
+}
 
-    Ok(())
+/// Decode an archive written in format `version`.
+///
+/// When ARCHIVE_VERSION goes up, the previous format keeps an arm here so
+/// [`reencode_archive`] can read old archives and write them in the new one.
+pub fn decode_archive(version: i32, compressed: &[u8]) -> cja::Result<GameExport> {
+    match version {
+        // zstd-compressed GameExport JSON
+        1 => {
+            let json = zstd::decode_all(compressed).wrap_err("Failed to decompress archive")?;
+            serde_json::from_slice(&json).wrap_err("Failed to parse archived game export")
+        }
+        version if version > ARCHIVE_VERSION => Err(eyre!(
+            "Archive version {} is newer than this server's {}",
+            version,
+            ARCHIVE_VERSION
+        )),
+        version => Err(eyre!("No decoder for archive version {}", version)),
+    }
 }
 
 /// Current archive format version. Increment when changing the export format.
-const ARCHIVE_VERSION: i32 = 1;
+pub const ARCHIVE_VERSION: i32 = 1;
 
 /// Insert or update a game record in the local database after archiving.
-async fn upsert_game_record(db: &PgPool, game: &EngineGame, gcs_path: &str) -> cja::Result<()> {
-    let now = Utc::now();
+async fn upsert_game_record(
+    db: &PgPool,
+    game: &EngineGame,
+    gcs_path: &str,
+    now: DateTime<Utc>,
+) -> cja::Result<()> {
     let board_size = game.board_size();
     let game_type = game.game_type();
     let created_at = game.created_at();
@@ -244,7 +283,8 @@ pub async fn run_backup_discovery(app_state: &AppState) -> Result<(), BackupErro
     };
 
     // Fetch games from the lookback window
-    let games = fetch_completed_games(engine_db, BACKUP_WINDOW_HOURS).await?;
+    let since = backup_window_start(app_state.clock.now(), BACKUP_WINDOW_HOURS);
+    let games = fetch_completed_games(engine_db, since).await?;
     tracing::info!(
         count = games.len(),
         "Found completed games to check for archival"
@@ -281,10 +321,10 @@ pub async fn run_backup_discovery(app_state: &AppState) -> Result<(), BackupErro
     Ok(())
 }
 
-/// Backup a single game from the Engine database to GCS.
+/// Backup a single game from the Engine database to storage.
 ///
 /// Called by BackupSingleGameJob. Fetches the game and frames from Engine,
-/// compresses and uploads to GCS, and records the archival in the local database.
+/// compresses and uploads to storage, and records the archival in the local database.
 ///
 /// If `batch_id` is provided, this is part of a historical backfill batch.
 /// On completion, the batch's completed count will be incremented, and if this
@@ -294,9 +334,13 @@ pub async fn backup_single_game(
     engine_game_id: &str,
     batch_id: Option<i32>,
 ) -> Result<(), BackupError> {
-    // Check if already archived (idempotency)
+    // Check if already archived (idempotency). It still counts towards its
+    // batch, or the batch would never finish.
     if is_already_archived(&app_state.db, engine_game_id).await? {
         tracing::debug!(game_id = %engine_game_id, "Game already archived, skipping");
+        if let Some(batch_id) = batch_id {
+            handle_batch_completion(app_state, batch_id).await?;
+        }
         return Ok(());
     }
 
@@ -307,10 +351,10 @@ pub async fn backup_single_game(
         }
     };
 
-    let bucket = match &app_state.gcs_bucket {
-        Some(b) => b.clone(),
+    let storage = match &app_state.storage {
+        Some(storage) => storage.clone(),
         None => {
-            return Err(eyre!("GCS bucket not configured").into());
+            return Err(eyre!("Archive storage not configured").into());
         }
     };
 
@@ -330,22 +374,15 @@ pub async fn backup_single_game(
     let export = GameExport {
         game: game.clone(),
         frames,
-        exported_at: Utc::now(),
+        exported_at: app_state.clock.now(),
     };
 
-    // Initialize GCS client
-    let config = ClientConfig::default()
-        .with_auth()
-        .await
-        .wrap_err("Failed to configure GCS client")?;
-    let gcs_client = GcsClient::new(config);
-
     // Generate path and upload
     let path = gcs_path(&game);
-    compress_and_upload_to_gcs(&gcs_client, &bucket, &path, &export).await?;
+    compress_and_upload(storage.as_ref(), &path, &export).await?;
 
     // Record in local database
-    upsert_game_record(&app_state.db, &game, &path).await?;
+    upsert_game_record(&app_state.db, &game, &path, app_state.clock.now()).await?;
 
     tracing::info!(game_id = %game.id, path = %path, "Archived game");
 
@@ -458,8 +495,17 @@ async fn handle_batch_completion(app_state: &AppState, batch_id: i32) -> cja::Re
         "Batch job completed"
     );
 
-    // If this was the last job, enqueue the next discovery
+    // If this was the last job, enqueue the next discovery, unless the batch is
+    // part of a backfill run, which creates its next batch itself
     if result.jobs_completed == result.jobs_enqueued {
+        if crate::backfill::batch_run_id(&app_state.db, batch_id)
+            .await?
+            .is_some()
+        {
+            tracing::info!(batch_id = batch_id, "Backfill run batch complete");
+            return Ok(());
+        }
+
         tracing::info!(
             batch_id = batch_id,
             "Batch complete, enqueuing next discovery"
@@ -526,7 +572,7 @@ async fn fetch_oldest_completed_games(
 }
 
 /// Batch check which game IDs are already archived in our database.
-async fn get_archived_game_ids(
+pub(crate) async fn get_archived_game_ids(
     db: &PgPool,
     engine_game_ids: &[String],
 ) -> cja::Result<Vec<String>> {
@@ -651,3 +697,520 @@ pub async fn run_historical_backup_discovery(
 
     Ok(())
 }
+
+// =============================================================================
+// Archive Retrieval
+// =============================================================================
+
+/// Most decompressed bytes produced per step while reading an archive back
+const ARCHIVE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Decompresses an archive a piece at a time, as it's downloaded.
+struct ArchiveDecoder {
+    decoder: Decoder<'static>,
+    /// Whether the input so far ends at the end of a zstd frame
+    frame_complete: bool,
+}
+
+impl ArchiveDecoder {
+    fn new() -> io::Result<Self> {
+        Ok(Self {
+            decoder: Decoder::new()?,
+            frame_complete: false,
+        })
+    }
+
+    /// Decompress the next piece of the archive, returning whatever output it completes.
+    fn feed(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        let mut decompressed = Vec::new();
+        let mut input = InBuffer::around(chunk);
+        let mut buffer = vec![0; ARCHIVE_CHUNK_SIZE];
+
+        loop {
+            let read_from = input.pos();
+            let mut output = OutBuffer::around(buffer.as_mut_slice());
+            let hint = self.decoder.run(&mut input, &mut output)?;
+            let written = output.pos();
+            decompressed.extend_from_slice(&buffer[..written]);
+            // A call with nothing left to do looks like the start of the next frame
+            if written > 0 || input.pos() > read_from {
+                self.frame_complete = hint == 0;
+            }
+
+            // A full buffer can leave output behind even once the input is used up
+            if input.pos() == chunk.len() && written < buffer.len() {
+                return Ok(decompressed);
+            }
+        }
+    }
+
+    /// Check the archive wasn't cut off part way through.
+    fn finish(&self) -> io::Result<()> {
+        if self.frame_complete {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Archive ended part way through a zstd frame",
+            ))
+        }
+    }
+}
+
+/// Decompress a stream of zstd-compressed chunks.
+///
+/// Errors end the stream, since by then a response built from it has already
+/// started.
+fn decompress_stream<S, E>(compressed: S) -> impl Stream<Item = io::Result<Bytes>>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+{
+    let decoder = ArchiveDecoder::new().map(|decoder| (compressed, decoder));
+    futures::stream::unfold(Some(decoder), |state| async move {
+        let (mut compressed, mut decoder) = match state? {
+            Ok(state) => state,
+            Err(e) => return Some((Err(e), None)),
+        };
+
+        loop {
+            match compressed.next().await {
+                Some(Ok(chunk)) => match decoder.feed(&chunk) {
+                    // Not enough input for any output yet
+                    Ok(decompressed) if decompressed.is_empty() => continue,
+                    Ok(decompressed) => {
+                        return Some((
+                            Ok(Bytes::from(decompressed)),
+                            Some(Ok((compressed, decoder))),
+                        ));
+                    }
+                    Err(e) => return Some((Err(e), None)),
+                },
+                Some(Err(e)) => return Some((Err(io::Error::other(e.to_string())), None)),
+                None => return decoder.finish().err().map(|e| (Err(e), None)),
+            }
+        }
+    })
+}
+
+/// Read an archived game back from storage.
+///
+/// Returns the archive's GameExport JSON, decompressed as it's downloaded so
+/// large games are never held in memory whole.
+pub async fn download_archive(
+    storage: &dyn StorageBackend,
+    path: &str,
+) -> cja::Result<impl Stream<Item = io::Result<Bytes>> + use<>> {
+    let compressed = storage.download_stream(path).await?;
+
+    Ok(decompress_stream(compressed))
+}
+
+// =============================================================================
+// Bulk Archive Downloads
+// =============================================================================
+
+/// A day or month of archived games, by when they were played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchivePeriod {
+    Day { year: i32, month: u32, day: u32 },
+    Month { year: i32, month: u32 },
+}
+
+impl ArchivePeriod {
+    /// Parse `YYYY-MM-DD` as a day or `YYYY-MM` as a month
+    pub fn parse(period: &str) -> Option<Self> {
+        let mut parts = period.split('-');
+        let year = parts.next()?;
+        let month = parts.next()?;
+        let day = parts.next();
+        if parts.next().is_some()
+            || year.len() != 4
+            || month.len() != 2
+            || day.is_some_and(|day| day.len() != 2)
+        {
+            return None;
+        }
+
+        let year = year.parse().ok()?;
+        let month = month.parse().ok()?;
+        let period = match day {
+            Some(day) => ArchivePeriod::Day {
+                year,
+                month,
+                day: day.parse().ok()?,
+            },
+            None => ArchivePeriod::Month { year, month },
+        };
+        // Rules out months and days that don't exist
+        period.bounds()?;
+        Some(period)
+    }
+
+    /// The period's first instant, and the first instant after it
+    pub fn bounds(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let (start, end) = match *self {
+            ArchivePeriod::Day { year, month, day } => {
+                let start = chrono::NaiveDate::from_ymd_opt(year, month, day)?;
+                (start, start.succ_opt()?)
+            }
+            ArchivePeriod::Month { year, month } => {
+                let start = chrono::NaiveDate::from_ymd_opt(year, month, 1)?;
+                let end = if month == 12 {
+                    chrono::NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+                } else {
+                    chrono::NaiveDate::from_ymd_opt(year, month + 1, 1)?
+                };
+                (start, end)
+            }
+        };
+        Some((
+            start.and_hms_opt(0, 0, 0)?.and_utc(),
+            end.and_hms_opt(0, 0, 0)?.and_utc(),
+        ))
+    }
+}
+
+impl std::fmt::Display for ArchivePeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchivePeriod::Day { year, month, day } => {
+                write!(f, "{:04}-{:02}-{:02}", year, month, day)
+            }
+            ArchivePeriod::Month { year, month } => write!(f, "{:04}-{:02}", year, month),
+        }
+    }
+}
+
+/// Stream a tar of archived games, each as its zstd-compressed object in
+/// storage under the same path.
+///
+/// Objects are fetched one at a time as the tar is read, so only one is ever
+/// held in memory. Errors end the stream before the end-of-archive blocks, so
+/// a failed download is a truncated tar rather than a quietly incomplete one.
+pub fn archive_tarball(
+    storage: SharedStorage,
+    archives: Vec<ArchivedGame>,
+) -> impl Stream<Item = io::Result<Bytes>> {
+    let entries = futures::stream::iter(archives).then(move |archived| {
+        let storage = storage.clone();
+        async move {
+            let object = storage
+                .download(&archived.gcs_path)
+                .await
+                .map_err(|e| io::Error::other(format!("{:#}", e)))?;
+            let header = tarball::file_header(
+                &archived.gcs_path,
+                object.len() as u64,
+                archived.created_at.timestamp(),
+            )
+            .map_err(|e| io::Error::other(e.to_string()))?;
+
+            let mut entry = Vec::with_capacity(header.len() + object.len() + 512);
+            entry.extend_from_slice(&header);
+            entry.extend_from_slice(&object);
+            entry.extend_from_slice(&tarball::padding(object.len()));
+            Ok(Bytes::from(entry))
+        }
+    });
+
+    let end = futures::stream::once(async { Ok(Bytes::from(tarball::end_of_archive())) });
+
+    // Stop at the first error, without the end-of-archive blocks after it
+    entries
+        .chain(end)
+        .scan(false, |failed, entry: io::Result<Bytes>| {
+            if *failed {
+                return futures::future::ready(None);
+            }
+            *failed = entry.is_err();
+            futures::future::ready(Some(entry))
+        })
+}
+
+// =============================================================================
+// Archive Re-encoding
+// =============================================================================
+
+/// Rewrite a game's archive in the current format.
+///
+/// Called by ReencodeArchiveJob. Reads the old archive, writes the new one
+/// alongside it, then points the game's row at the new one, only if the row
+/// still points at the archive that was read. The old object is deleted once
+/// the row no longer refers to it. Returns whether the archive was rewritten;
+/// games already in the current format are left alone.
+pub async fn reencode_archive(
+    app_state: &AppState,
+    engine_game_id: &str,
+) -> Result<bool, BackupError> {
+    let archived = get_archived_game(&app_state.db, engine_game_id)
+        .await?
+        .ok_or_else(|| eyre!("Game {} isn't archived", engine_game_id))?;
+    // Archives from before versioning are in the first format
+    let version = archived.archive_version.unwrap_or(1);
+    if version >= ARCHIVE_VERSION {
+        tracing::debug!(game_id = %engine_game_id, version, "Archive already current, skipping");
+        return Ok(false);
+    }
+
+    let storage = app_state
+        .storage
+        .clone()
+        .ok_or_else(|| eyre!("Archive storage not configured"))?;
+
+    let compressed = storage.download(&archived.gcs_path).await?;
+    let export = decode_archive(version, &compressed)
+        .wrap_err_with(|| format!("Failed to decode archive {}", archived.gcs_path))?;
+
+    let path = archive_path(&export.game, ARCHIVE_VERSION);
+    compress_and_upload(storage.as_ref(), &path, &export).await?;
+
+    let updated = set_archive_version(
+        &app_state.db,
+        engine_game_id,
+        &archived.gcs_path,
+        archived.archive_version,
+        &path,
+        ARCHIVE_VERSION,
+        app_state.clock.now(),
+    )
+    .await?;
+    if !updated {
+        // Archived again while this ran; the row points at that archive now
+        tracing::warn!(game_id = %engine_game_id, "Archive changed while re-encoding, leaving it");
+        return Ok(false);
+    }
+
+    if path != archived.gcs_path {
+        storage
+            .delete(&archived.gcs_path)
+            .await
+            .wrap_err_with(|| format!("Failed to delete old archive {}", archived.gcs_path))?;
+    }
+
+    tracing::info!(
+        game_id = %engine_game_id,
+        from = version,
+        to = ARCHIVE_VERSION,
+        path = %path,
+        "Re-encoded archive"
+    );
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, MockClock};
+    use chrono::TimeZone;
+    use futures::TryStreamExt;
+
+    #[test]
+    fn test_backup_window_moves_with_clock() {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap());
+
+        let start = backup_window_start(clock.now(), BACKUP_WINDOW_HOURS);
+        assert_eq!(
+            start,
+            Utc.with_ymd_and_hms(2026, 3, 10, 8, 0, 0)
+                .unwrap()
+                .naive_utc()
+        );
+
+        // Cron runs hourly, so the next window overlaps the previous one
+        clock.advance(Duration::hours(1));
+        let next_start = backup_window_start(clock.now(), BACKUP_WINDOW_HOURS);
+
+        assert_eq!(next_start - start, Duration::hours(1));
+        assert!(next_start < start + Duration::hours(BACKUP_WINDOW_HOURS));
+    }
+
+    #[test]
+    fn test_backup_window_crosses_midnight() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 1, 30, 0).unwrap();
+
+        assert_eq!(
+            backup_window_start(now, BACKUP_WINDOW_HOURS),
+            Utc.with_ymd_and_hms(2026, 3, 9, 21, 30, 0)
+                .unwrap()
+                .naive_utc()
+        );
+    }
+
+    fn compress(json: &[u8]) -> Vec<u8> {
+        zstd::encode_all(json, 3).unwrap()
+    }
+
+    #[test]
+    fn test_archive_decoder_reads_chunks() {
+        let json: Vec<u8> = serde_json::to_vec(&serde_json::json!({
+            "frames": (0..2000).map(|turn| serde_json::json!({"Turn": turn})).collect::<Vec<_>>(),
+        }))
+        .unwrap();
+        let compressed = compress(&json);
+
+        let mut decoder = ArchiveDecoder::new().unwrap();
+        let mut decompressed = Vec::new();
+        for chunk in compressed.chunks(7) {
+            decompressed.extend(decoder.feed(chunk).unwrap());
+        }
+        decoder.finish().unwrap();
+
+        assert_eq!(decompressed, json);
+    }
+
+    #[test]
+    fn test_archive_decoder_flushes_large_output() {
+        // Highly compressible, so one small chunk decompresses past the buffer size
+        let json = vec![b' '; ARCHIVE_CHUNK_SIZE * 5];
+        let compressed = compress(&json);
+        assert!(compressed.len() < ARCHIVE_CHUNK_SIZE);
+
+        let mut decoder = ArchiveDecoder::new().unwrap();
+        let decompressed = decoder.feed(&compressed).unwrap();
+        decoder.finish().unwrap();
+
+        assert_eq!(decompressed, json);
+    }
+
+    #[test]
+    fn test_archive_decoder_rejects_truncated_archive() {
+        let compressed = compress(br#"{"game": {}, "frames": []}"#);
+
+        let mut decoder = ArchiveDecoder::new().unwrap();
+        decoder.feed(&compressed[..compressed.len() - 3]).unwrap();
+
+        assert_eq!(
+            decoder.finish().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    fn engine_game() -> EngineGame {
+        serde_json::from_value(serde_json::json!({
+            "ID": "game-1",
+            "Status": "complete",
+            "Width": 11,
+            "Height": 11,
+            // 2026-03-10T12:00:00Z
+            "Created": 1_773_144_000_000_000i64,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_archive_path_versions() {
+        let game = engine_game();
+        assert_eq!(archive_path(&game, 1), "games/2026/03/10/game-1.json.zst");
+        assert_eq!(
+            archive_path(&game, 2),
+            "games/2026/03/10/game-1.v2.json.zst"
+        );
+        assert_eq!(gcs_path(&game), archive_path(&game, ARCHIVE_VERSION));
+    }
+
+    #[test]
+    fn test_archive_round_trips() {
+        let export = GameExport {
+            game: engine_game(),
+            frames: vec![],
+            exported_at: Utc.with_ymd_and_hms(2026, 3, 10, 13, 0, 0).unwrap(),
+        };
+
+        let decoded = decode_archive(ARCHIVE_VERSION, &encode_archive(&export).unwrap()).unwrap();
+        assert_eq!(decoded.game.id, "game-1");
+        assert_eq!(decoded.exported_at, export.exported_at);
+    }
+
+    #[test]
+    fn test_decode_archive_rejects_unknown_versions() {
+        let compressed = compress(b"{}");
+        assert!(decode_archive(ARCHIVE_VERSION + 1, &compressed).is_err());
+        assert!(decode_archive(0, &compressed).is_err());
+    }
+
+    #[test]
+    fn test_archive_period_parses_days_and_months() {
+        let day = ArchivePeriod::parse("2026-03-10").unwrap();
+        assert_eq!(
+            day.bounds().unwrap(),
+            (
+                Utc.with_ymd_and_hms(2026, 3, 10, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2026, 3, 11, 0, 0, 0).unwrap()
+            )
+        );
+        assert_eq!(day.to_string(), "2026-03-10");
+
+        let december = ArchivePeriod::parse("2025-12").unwrap();
+        assert_eq!(
+            december.bounds().unwrap(),
+            (
+                Utc.with_ymd_and_hms(2025, 12, 1, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()
+            )
+        );
+        assert_eq!(december.to_string(), "2025-12");
+
+        for invalid in [
+            "",
+            "2026",
+            "2026-13",
+            "2026-02-30",
+            "2026-3-10",
+            "2026-03-10-01",
+        ] {
+            assert_eq!(ArchivePeriod::parse(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_archive_tarball_holds_each_object() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage: SharedStorage =
+            std::sync::Arc::new(crate::storage::LocalStorage::new(dir.path().to_path_buf()));
+        let compressed = compress(br#"{"game": {}, "frames": []}"#);
+        storage
+            .upload(
+                "games/2026/03/10/game-1.v2.json.zst",
+                compressed.clone(),
+                "application/zstd",
+            )
+            .await
+            .unwrap();
+
+        let archived = |path: &str| ArchivedGame {
+            game_id: uuid::Uuid::new_v4(),
+            engine_game_id: "game-1".to_string(),
+            gcs_path: path.to_string(),
+            archive_version: Some(ARCHIVE_VERSION),
+            created_at: Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap(),
+        };
+
+        let chunks: Vec<Bytes> = archive_tarball(
+            storage.clone(),
+            vec![archived("games/2026/03/10/game-1.v2.json.zst")],
+        )
+        .try_collect()
+        .await
+        .unwrap();
+        let tar = chunks.concat();
+        assert_eq!(tar.len(), 512 + 512 + 1024);
+        assert_eq!(&tar[..36], b"games/2026/03/10/game-1.v2.json.zst\0");
+        assert_eq!(&tar[512..512 + compressed.len()], compressed.as_slice());
+        assert!(tar[512 + compressed.len()..].iter().all(|&b| b == 0));
+
+        // A missing object ends the stream with an error and no end-of-archive blocks
+        let results: Vec<io::Result<Bytes>> = archive_tarball(
+            storage,
+            vec![
+                archived("games/2026/03/10/missing.json.zst"),
+                archived("games/2026/03/10/game-1.v2.json.zst"),
+            ],
+        )
+        .collect()
+        .await;
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+    }
+}
@@ -1,12 +1,19 @@
 use clap::{Parser, Subcommand};
 use color_eyre::eyre::{Context as _, eyre};
+use colored::Colorize as _;
+use std::io::Write as _;
+use std::path::PathBuf;
 use std::time::Duration;
 
 // Include the cli module from the library
+use arena::certification::{VerificationStatus, chain_digest};
 use arena::cli::config::{AuthConfig, CliConfig};
+use arena::cli::doctor::{self, DoctorCheck, DoctorStatus};
 use arena::cli::output::{
     OutputFormat, format_timestamp, print_field, print_success, print_table, status_colored,
 };
+use arena::cli::roster::{Roster, RosterFormat};
+use arena::game_settings::{self, GameLimits};
 
 #[derive(Parser)]
 #[command(name = "arena")]
@@ -32,11 +39,54 @@ enum Commands {
         #[command(subcommand)]
         command: SnakesCommands,
     },
+    /// Snake URL templates, instantiated as one snake per deployed tag
+    Templates {
+        #[command(subcommand)]
+        command: TemplatesCommands,
+    },
     /// Game management commands
     Games {
         #[command(subcommand)]
         command: GamesCommands,
     },
+    /// Regression checks for gating deploys on arena matches
+    Checks {
+        #[command(subcommand)]
+        command: ChecksCommands,
+    },
+    /// Show what the server supports: game options, limits and features
+    Capabilities,
+    /// Check the CLI's config, the server, your token and your clock, and print fixes
+    Doctor {
+        /// Also play a turn against a snake at this URL, e.g. http://localhost:8000
+        #[arg(long)]
+        snake: Option<String>,
+    },
+    /// Your preferences, shared with the settings page
+    Preferences {
+        #[command(subcommand)]
+        command: PreferencesCommands,
+    },
+    /// Archived Engine games, for operators
+    Archives {
+        #[command(subcommand)]
+        command: ArchivesCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum ArchivesCommands {
+    /// Download every game archived for a day or month as one tar of zstd-compressed exports
+    Download {
+        /// A day (YYYY-MM-DD) or a month (YYYY-MM)
+        period: String,
+        /// File to write. Defaults to arena-games-PERIOD.tar
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// The server's ARENA_ADMIN_TOKEN
+        #[arg(long, env = "ARENA_ADMIN_TOKEN", hide_env_values = true)]
+        admin_token: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -82,6 +132,15 @@ enum SnakesCommands {
         /// Make the snake public (visible to other users)
         #[arg(long)]
         public: bool,
+        /// Markdown describing the snake, shown on its profile
+        #[arg(long)]
+        description: Option<String>,
+        /// Link to the snake's homepage
+        #[arg(long)]
+        homepage: Option<String>,
+        /// Link to the snake's source code
+        #[arg(long)]
+        source: Option<String>,
     },
     /// Show details of a snake
     Show {
@@ -104,12 +163,93 @@ enum SnakesCommands {
         /// Make the snake private
         #[arg(long, conflicts_with = "public")]
         private: bool,
+        /// New markdown description; pass "" to clear it
+        #[arg(long)]
+        description: Option<String>,
+        /// New homepage link; pass "" to clear it
+        #[arg(long)]
+        homepage: Option<String>,
+        /// New source code link; pass "" to clear it
+        #[arg(long)]
+        source: Option<String>,
     },
     /// Delete a snake
     Delete {
         /// Snake ID
         id: String,
     },
+    /// Show a snake's tags, or replace them
+    Tags {
+        /// Snake ID
+        id: String,
+        /// Comma-separated tags to replace the snake's tags with, e.g. rust,tree-search; empty for none
+        #[arg(long)]
+        set: Option<String>,
+    },
+    /// Search public snakes by name and tags
+    Search {
+        /// Part of the snake's name
+        query: Option<String>,
+        /// Comma-separated tags the snakes must all have
+        #[arg(long)]
+        tags: Option<String>,
+    },
+    /// Opt a snake in to ranked games, which count towards ratings and leaderboards
+    Ranked {
+        /// Snake ID
+        id: String,
+        /// Opt the snake out instead
+        #[arg(long)]
+        opt_out: bool,
+    },
+    /// Sync your snakes to a roster file (.toml or .json), matching snakes by name
+    Import {
+        /// Roster file listing snakes as [[snakes]] entries with name, url and is_public
+        file: PathBuf,
+        /// Show what would change without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Delete snakes that aren't in the roster
+        #[arg(long)]
+        prune: bool,
+    },
+    /// Write your snakes as a roster file for import
+    Export {
+        /// File to write (.toml or .json). Prints TOML to stdout when left out
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplatesCommands {
+    /// List your templates
+    List,
+    /// Create a template
+    Create {
+        /// Name for the template; variants are named NAME@TAG
+        name: String,
+        /// Snake URL with a {tag} placeholder, e.g. https://snake.example.com/{tag}
+        url_template: String,
+        /// Make variants public (visible to other users)
+        #[arg(long)]
+        public: bool,
+        /// Days a variant can go without a game before it's archived
+        #[arg(long)]
+        archive_after_days: Option<i32>,
+    },
+    /// Show a template and its variants
+    Show {
+        /// Template ID
+        id: String,
+    },
+    /// Get or create the snake for a deployed tag, bringing it back if archived
+    Instantiate {
+        /// Template ID
+        id: String,
+        /// The deployed version, e.g. a git tag or branch name
+        tag: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -119,21 +259,51 @@ enum GamesCommands {
         /// Filter by snake ID
         #[arg(long)]
         snake: Option<String>,
+        /// Only ranked games
+        #[arg(long, conflicts_with = "casual")]
+        ranked: bool,
+        /// Only casual and practice games
+        #[arg(long, conflicts_with = "ranked")]
+        casual: bool,
         /// Maximum number of games to return
         #[arg(long, default_value = "20")]
         limit: u32,
     },
     /// Create a new game
     Create {
-        /// Comma-separated snake IDs (required)
+        /// Comma-separated snake IDs (required unless --snake-url is given)
+        #[arg(long, required_unless_present = "snake_url", conflicts_with = "vs")]
+        snakes: Option<String>,
+        /// Register a temporary private snake for this URL and play it, without
+        /// creating the snake first
         #[arg(long)]
-        snakes: String,
-        /// Board size (7x7, 11x11, 19x19)
-        #[arg(long, default_value = "11x11")]
-        board: String,
-        /// Game type (standard, royale, constrictor, snail)
-        #[arg(long = "type", default_value = "standard")]
-        game_type: String,
+        snake_url: Option<String>,
+        /// Comma-separated snake IDs to play the --snake-url snake against
+        #[arg(long, requires = "snake_url")]
+        vs: Option<String>,
+        /// Wait for the game to finish, then delete the temporary snake. The
+        /// game's record loses the snake with it
+        #[arg(long, requires = "snake_url")]
+        delete_snake: bool,
+        /// Board size (7x7, 11x11, 19x19). Defaults to your preferred board size
+        #[arg(long)]
+        board: Option<String>,
+        /// Game type (standard, royale, constrictor, snail, wrapped). Defaults to your preferred game type
+        #[arg(long = "type")]
+        game_type: Option<String>,
+        /// Play in the ranked pool; every snake must be opted in and healthy
+        #[arg(long)]
+        ranked: bool,
+        /// Play a 1v1 duel between two different snakes, on an 11x11 board unless --board says
+        /// otherwise. Ranked duels also count towards the duel leaderboard
+        #[arg(long)]
+        duel: bool,
+        /// End the game on this turn if more than one snake is still alive
+        #[arg(long)]
+        max_turns: Option<i32>,
+        /// Milliseconds each snake has to answer /move
+        #[arg(long)]
+        timeout: Option<i32>,
     },
     /// Show game details
     Show {
@@ -148,6 +318,110 @@ enum GamesCommands {
         #[arg(long)]
         web: bool,
     },
+    /// Check a finished game's frames against its certified result digest
+    Verify {
+        /// Game ID
+        id: String,
+    },
+    /// Print a game's frames as NDJSON, one frame per line, as they download
+    Frames {
+        /// Game ID
+        id: String,
+    },
+    /// Print a finished game in text notation, for diffing or sharing
+    Notation {
+        /// Game ID
+        id: String,
+    },
+    /// Download a finished game and all its frames as JSON, for offline replay on the board
+    Export {
+        /// Game ID
+        id: String,
+        /// File to write. Prints the JSON to stdout when left out
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Compare two games turn by turn, e.g. the same seed with two versions of a snake
+    Diff {
+        /// The first game's ID
+        game_a: String,
+        /// The game to compare it with
+        game_b: String,
+    },
+    /// Show where a finished game's processing time went, stage by stage
+    Timing {
+        /// Game ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum PreferencesCommands {
+    /// Show your preferences
+    Show,
+    /// Change your preferences; options left out keep their current value
+    Set {
+        /// Board size new games default to (7x7, 11x11, 19x19)
+        #[arg(long)]
+        board: Option<String>,
+        /// Game type new games default to (standard, royale, constrictor, snail, wrapped)
+        #[arg(long = "type")]
+        game_type: Option<String>,
+        /// IANA time zone, e.g. Europe/Berlin
+        #[arg(long)]
+        timezone: Option<String>,
+        /// Comma-separated notification channels (email, web); empty for none
+        #[arg(long)]
+        notify: Option<String>,
+        /// Replay speed, as a multiple of the board viewer's normal speed
+        #[arg(long)]
+        playback_speed: Option<f64>,
+        /// Theme (system, light, dark)
+        #[arg(long)]
+        theme: Option<String>,
+        /// Comma-separated tags to prefer in opponents, e.g. tree-search; empty for none
+        #[arg(long)]
+        prefer_tags: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ChecksCommands {
+    /// Start a check: play your snake against opponents and require a win rate
+    Run {
+        /// ID of your snake to check
+        #[arg(long)]
+        snake: String,
+        /// Comma-separated opponent snake IDs (1-3)
+        #[arg(long)]
+        opponents: String,
+        /// Number of games to play
+        #[arg(long, default_value = "10")]
+        games: u32,
+        /// Percent of games the snake must win to pass
+        #[arg(long)]
+        win_rate: f64,
+        /// Board size (7x7, 11x11, 19x19)
+        #[arg(long, default_value = "11x11")]
+        board: String,
+        /// Game type (standard, royale, constrictor, snail, wrapped)
+        #[arg(long = "type", default_value = "standard")]
+        game_type: String,
+        /// Commit SHA to post the result to on GitHub (defaults to $GITHUB_SHA in GitHub Actions)
+        #[arg(long, env = "GITHUB_SHA")]
+        commit: Option<String>,
+        /// Wait for the result and exit nonzero if the check fails
+        #[arg(long)]
+        wait: bool,
+    },
+    /// Show a check's status
+    Show {
+        /// Check ID
+        id: String,
+        /// Wait for the result and exit nonzero if the check fails
+        #[arg(long)]
+        wait: bool,
+    },
 }
 
 #[tokio::main]
@@ -163,7 +437,15 @@ async fn main() -> color_eyre::Result<()> {
     match cli.command {
         Commands::Auth { command } => handle_auth_command(command).await?,
         Commands::Snakes { command } => handle_snakes_command(command, output_format).await?,
+        Commands::Templates { command } => handle_templates_command(command, output_format).await?,
         Commands::Games { command } => handle_games_command(command).await?,
+        Commands::Checks { command } => handle_checks_command(command, output_format).await?,
+        Commands::Capabilities => handle_capabilities_command(output_format).await?,
+        Commands::Doctor { snake } => handle_doctor_command(snake, output_format).await?,
+        Commands::Preferences { command } => {
+            handle_preferences_command(command, output_format).await?
+        }
+        Commands::Archives { command } => handle_archives_command(command).await?,
     }
 
     Ok(())
@@ -205,7 +487,7 @@ async fn handle_token_command(command: TokenCommands) -> color_eyre::Result<()>
             });
 
             let response = client
-                .post(format!("{}/api/tokens", base_url))
+                .post(format!("{}/api/v1/tokens", base_url))
                 .bearer_auth(token)
                 .json(&serde_json::json!({ "name": name }))
                 .send()
@@ -227,7 +509,7 @@ async fn handle_token_command(command: TokenCommands) -> color_eyre::Result<()>
         }
         TokenCommands::List => {
             let response = client
-                .get(format!("{}/api/tokens", base_url))
+                .get(format!("{}/api/v1/tokens", base_url))
                 .bearer_auth(token)
                 .send()
                 .await
@@ -259,7 +541,7 @@ async fn handle_token_command(command: TokenCommands) -> color_eyre::Result<()>
         }
         TokenCommands::Revoke { id } => {
             let response = client
-                .delete(format!("{}/api/tokens/{}", base_url, id))
+                .delete(format!("{}/api/v1/tokens/{}", base_url, id))
                 .bearer_auth(token)
                 .send()
                 .await
@@ -297,7 +579,7 @@ async fn handle_snakes_command(
     match command {
         SnakesCommands::List => {
             let response = client
-                .get(format!("{}/api/snakes", base_url))
+                .get(format!("{}/api/v1/snakes", base_url))
                 .bearer_auth(token)
                 .send()
                 .await
@@ -328,26 +610,42 @@ async fn handle_snakes_command(
                                 } else {
                                     "private"
                                 };
+                                let health = match snake["health"]["healthy"].as_bool() {
+                                    Some(true) => "healthy",
+                                    Some(false) => "down",
+                                    None => "unchecked",
+                                };
                                 vec![
                                     snake["name"].as_str().unwrap_or("").to_string(),
                                     snake["url"].as_str().unwrap_or("").to_string(),
                                     status_colored(visibility),
+                                    status_colored(health),
                                 ]
                             })
                             .collect();
-                        print_table(vec!["NAME", "URL", "VISIBILITY"], rows);
+                        print_table(vec!["NAME", "URL", "VISIBILITY", "HEALTH"], rows);
                     }
                 }
             }
         }
-        SnakesCommands::Create { name, url, public } => {
+        SnakesCommands::Create {
+            name,
+            url,
+            public,
+            description,
+            homepage,
+            source,
+        } => {
             let response = client
-                .post(format!("{}/api/snakes", base_url))
+                .post(format!("{}/api/v1/snakes", base_url))
                 .bearer_auth(token)
                 .json(&serde_json::json!({
                     "name": name,
                     "url": url,
-                    "is_public": public
+                    "is_public": public,
+                    "description": description,
+                    "homepage_url": homepage,
+                    "source_url": source
                 }))
                 .send()
                 .await
@@ -373,7 +671,7 @@ async fn handle_snakes_command(
         }
         SnakesCommands::Show { id } => {
             let response = client
-                .get(format!("{}/api/snakes/{}", base_url, id))
+                .get(format!("{}/api/v1/snakes/{}", base_url, id))
                 .bearer_auth(token)
                 .send()
                 .await
@@ -404,6 +702,9 @@ async fn handle_snakes_command(
             url,
             public,
             private,
+            description,
+            homepage,
+            source,
         } => {
             // Build the update payload with only provided fields
             let mut update: serde_json::Map<String, serde_json::Value> = serde_json::Map::new();
@@ -418,9 +719,18 @@ async fn handle_snakes_command(
             } else if private {
                 update.insert("is_public".to_string(), serde_json::Value::Bool(false));
             }
+            for (field, value) in [
+                ("description", description),
+                ("homepage_url", homepage),
+                ("source_url", source),
+            ] {
+                if let Some(value) = value {
+                    update.insert(field.to_string(), serde_json::Value::String(value));
+                }
+            }
 
             let response = client
-                .put(format!("{}/api/snakes/{}", base_url, id))
+                .put(format!("{}/api/v1/snakes/{}", base_url, id))
                 .bearer_auth(token)
                 .json(&update)
                 .send()
@@ -449,7 +759,7 @@ async fn handle_snakes_command(
         }
         SnakesCommands::Delete { id } => {
             let response = client
-                .delete(format!("{}/api/snakes/{}", base_url, id))
+                .delete(format!("{}/api/v1/snakes/{}", base_url, id))
                 .bearer_auth(token)
                 .send()
                 .await
@@ -478,215 +788,1825 @@ async fn handle_snakes_command(
                 return Err(eyre!("Failed to delete snake: {} - {}", status, body));
             }
         }
-    }
-
-    Ok(())
-}
-
-/// Print snake details in human-readable format.
-fn print_snake_details(snake: &serde_json::Value) {
-    print_field("Name", snake["name"].as_str().unwrap_or(""));
-    print_field("ID", snake["id"].as_str().unwrap_or(""));
-    print_field("URL", snake["url"].as_str().unwrap_or(""));
-
-    let visibility = if snake["is_public"].as_bool().unwrap_or(false) {
-        "public"
-    } else {
-        "private"
-    };
-    print_field("Visibility", &status_colored(visibility));
-
-    if let Some(created) = snake["created_at"].as_str()
-        && let Ok(dt) = chrono::DateTime::parse_from_rfc3339(created)
-    {
-        print_field("Created", &format_timestamp(dt.with_timezone(&chrono::Utc)));
-    }
-
-    if let Some(updated) = snake["updated_at"].as_str()
-        && let Ok(dt) = chrono::DateTime::parse_from_rfc3339(updated)
-    {
-        print_field("Updated", &format_timestamp(dt.with_timezone(&chrono::Utc)));
-    }
-}
-
-async fn login() -> color_eyre::Result<()> {
-    let config = CliConfig::load()?;
-    let base_url = config.api_url();
-
-    println!("Opening browser for GitHub authentication...");
-    println!(
-        "If the browser doesn't open, visit: {}/auth/github?cli=true",
-        base_url
-    );
-
-    // Try to open browser
-    let _ = open::that(format!("{}/auth/github?cli=true", base_url));
-
-    // For now, prompt user to enter the token manually
-    println!("\nAfter authenticating, you'll receive an API token.");
-    println!("Enter your API token:");
-
-    let mut token = String::new();
-    std::io::stdin().read_line(&mut token)?;
-    let token = token.trim().to_string();
-
-    if token.is_empty() {
-        return Err(eyre!("No token provided"));
-    }
-
-    // Validate the token by trying to list tokens
-    let client = reqwest::Client::new();
-    let response = client
-        .get(format!("{}/api/tokens", base_url))
-        .bearer_auth(&token)
-        .send()
-        .await
-        .wrap_err("Failed to validate token")?;
-
-    if !response.status().is_success() {
-        return Err(eyre!("Invalid token"));
-    }
-
-    // Save the token
-    let mut config = config;
-    config.auth = Some(AuthConfig { token: Some(token) });
-    config.save()?;
-
-    println!("Login successful! Token saved.");
-    Ok(())
-}
-
-fn logout() -> color_eyre::Result<()> {
-    let mut config = CliConfig::load()?;
-    config.auth = None;
-    config.save()?;
-    println!("Logged out successfully.");
-    Ok(())
-}
-
-async fn handle_games_command(command: GamesCommands) -> color_eyre::Result<()> {
-    let config = CliConfig::load()?;
-    let token = config
-        .auth
-        .as_ref()
-        .and_then(|a| a.token.as_ref())
-        .ok_or_else(|| eyre!("Not logged in. Run 'arena auth login' first."))?;
-
-    let client = reqwest::Client::new();
-    let base_url = config.api_url();
-
-    match command {
-        GamesCommands::List { snake, limit } => {
-            let mut url = format!("{}/api/games?limit={}", base_url, limit);
-            if let Some(snake_id) = snake {
-                url.push_str(&format!("&snake_id={}", snake_id));
-            }
-
-            let response = client
-                .get(&url)
+        SnakesCommands::Tags { id, set } => {
+            let url = format!("{}/api/v1/snakes/{}/tags", base_url, id);
+            let request = match &set {
+                Some(tags) => {
+                    let tags: Vec<&str> = tags
+                        .split(',')
+                        .map(|t| t.trim())
+                        .filter(|t| !t.is_empty())
+                        .collect();
+                    client.put(&url).json(&serde_json::json!({ "tags": tags }))
+                }
+                None => client.get(&url),
+            };
+            let response = request
                 .bearer_auth(token)
                 .send()
                 .await
-                .wrap_err("Failed to list games")?;
+                .wrap_err("Failed to sync snake tags")?;
 
-            if !response.status().is_success() {
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Err(eyre!("Snake not found."));
+            } else if !response.status().is_success() {
                 let status = response.status();
                 let body = response.text().await.unwrap_or_default();
-                return Err(eyre!("Failed to list games: {} - {}", status, body));
+                return Err(eyre!("Failed to sync snake tags: {} - {}", status, body));
             }
 
-            let games: Vec<serde_json::Value> = response.json().await?;
-            println!("{}", serde_json::to_string_pretty(&games)?);
-        }
-        GamesCommands::Create {
-            snakes,
-            board,
-            game_type,
-        } => {
-            // Parse comma-separated snake IDs
-            let snake_ids: Vec<&str> = snakes.split(',').map(|s| s.trim()).collect();
+            let result: serde_json::Value = response.json().await?;
 
+            match output_format {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&result)?);
+                }
+                OutputFormat::Human => {
+                    if set.is_some() {
+                        print_success("Tags updated.\n");
+                    }
+                    print_field("Tags", &json_string_list(&result["tags"]));
+                }
+            }
+        }
+        SnakesCommands::Search { query, tags } => {
+            let mut params = Vec::new();
+            if let Some(query) = &query {
+                params.push(("q", query.as_str()));
+            }
+            if let Some(tags) = &tags {
+                params.push(("tags", tags.as_str()));
+            }
             let response = client
-                .post(format!("{}/api/games", base_url))
+                .get(format!("{}/api/v1/snakes/public", base_url))
+                .query(&params)
                 .bearer_auth(token)
-                .json(&serde_json::json!({
-                    "snakes": snake_ids,
-                    "board": board,
-                    "game_type": game_type
-                }))
                 .send()
                 .await
-                .wrap_err("Failed to create game")?;
+                .wrap_err("Failed to search snakes")?;
 
             if !response.status().is_success() {
                 let status = response.status();
                 let body = response.text().await.unwrap_or_default();
-                return Err(eyre!("Failed to create game: {} - {}", status, body));
+                return Err(eyre!("Failed to search snakes: {} - {}", status, body));
             }
 
-            let game: serde_json::Value = response.json().await?;
-            println!("{}", serde_json::to_string_pretty(&game)?);
-        }
-        GamesCommands::Show { id } => {
-            let response = client
-                .get(format!("{}/api/games/{}/details", base_url, id))
-                .bearer_auth(token)
-                .send()
-                .await
-                .wrap_err("Failed to get game")?;
+            let snakes: serde_json::Value = response.json().await?;
+
+            match output_format {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&snakes)?);
+                }
+                OutputFormat::Human => {
+                    let snakes = snakes.as_array().cloned().unwrap_or_default();
+                    if snakes.is_empty() {
+                        println!("No public snakes found.");
+                    } else {
+                        let rows: Vec<Vec<String>> = snakes
+                            .iter()
+                            .map(|snake| {
+                                vec![
+                                    snake["name"].as_str().unwrap_or("").to_string(),
+                                    snake["id"].as_str().unwrap_or("").to_string(),
+                                    json_string_list(&snake["tags"]),
+                                ]
+                            })
+                            .collect();
+                        print_table(vec!["NAME", "ID", "TAGS"], rows);
+                    }
+                }
+            }
+        }
+        SnakesCommands::Ranked { id, opt_out } => {
+            let response = client
+                .put(format!("{}/api/v1/snakes/{}/ranked", base_url, id))
+                .bearer_auth(token)
+                .json(&serde_json::json!({ "opt_in": !opt_out }))
+                .send()
+                .await
+                .wrap_err("Failed to set ranked opt-in")?;
 
             if response.status() == reqwest::StatusCode::NOT_FOUND {
-                return Err(eyre!("Game not found."));
+                return Err(eyre!("Snake not found."));
             } else if !response.status().is_success() {
                 let status = response.status();
                 let body = response.text().await.unwrap_or_default();
-                return Err(eyre!("Failed to get game: {} - {}", status, body));
+                return Err(eyre!("Failed to set ranked opt-in: {} - {}", status, body));
             }
 
-            let game: serde_json::Value = response.json().await?;
-            println!("{}", serde_json::to_string_pretty(&game)?);
+            let result: serde_json::Value = response.json().await?;
+            match output_format {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&result)?);
+                }
+                OutputFormat::Human => {
+                    if opt_out {
+                        print_success("Snake opted out of ranked games.");
+                    } else {
+                        print_success("Snake opted in to ranked games.");
+                    }
+                }
+            }
         }
-        GamesCommands::Watch { id, web } => {
-            if web {
-                // Open in browser
-                let url = format!("{}/games/{}", base_url, id);
-                println!("Opening game in browser...");
-                open::that(&url).wrap_err("Failed to open browser")?;
-            } else {
-                // Poll loop
-                loop {
-                    let response = client
-                        .get(format!("{}/api/games/{}/details", base_url, id))
-                        .bearer_auth(token)
-                        .send()
-                        .await
-                        .wrap_err("Failed to get game")?;
+        SnakesCommands::Import {
+            file,
+            dry_run,
+            prune,
+        } => {
+            let roster = Roster::load(&file)?;
 
-                    if response.status() == reqwest::StatusCode::NOT_FOUND {
-                        return Err(eyre!("Game not found."));
-                    } else if !response.status().is_success() {
-                        let status = response.status();
-                        let body = response.text().await.unwrap_or_default();
-                        return Err(eyre!("Failed to get game: {} - {}", status, body));
-                    }
+            let response = client
+                .post(format!("{}/api/v1/snakes/import", base_url))
+                .bearer_auth(token)
+                .json(&serde_json::json!({
+                    "snakes": roster.snakes,
+                    "dry_run": dry_run,
+                    "prune": prune,
+                }))
+                .send()
+                .await
+                .wrap_err("Failed to import snakes")?;
 
-                    let game: serde_json::Value = response.json().await?;
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(eyre!("Failed to import snakes: {} - {}", status, body));
+            }
 
-                    // Clear screen and print current state
-                    print!("\x1B[2J\x1B[1;1H");
-                    println!("{}", serde_json::to_string_pretty(&game)?);
+            let result: serde_json::Value = response.json().await?;
 
-                    // Check if game is finished
-                    if game["status"] == "finished" {
-                        println!("\nGame finished!");
-                        break;
+            match output_format {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&result)?);
+                }
+                OutputFormat::Human => {
+                    print_roster_changes(&result);
+                }
+            }
+        }
+        SnakesCommands::Export { output } => {
+            let response = client
+                .get(format!("{}/api/v1/snakes/export", base_url))
+                .bearer_auth(token)
+                .send()
+                .await
+                .wrap_err("Failed to export snakes")?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(eyre!("Failed to export snakes: {} - {}", status, body));
+            }
+
+            let roster: Roster = response.json().await?;
+
+            match output {
+                Some(path) => {
+                    let contents = roster.serialize(RosterFormat::from_path(&path)?)?;
+                    std::fs::write(&path, contents)
+                        .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+                    if output_format == OutputFormat::Human {
+                        print_success(&format!(
+                            "Exported {} snakes to {}",
+                            roster.snakes.len(),
+                            path.display()
+                        ));
                     }
+                }
+                None => {
+                    let format = match output_format {
+                        OutputFormat::Json => RosterFormat::Json,
+                        OutputFormat::Human => RosterFormat::Toml,
+                    };
+                    print!("{}", roster.serialize(format)?);
+                }
+            }
+        }
+    }
 
-                    tokio::time::sleep(Duration::from_secs(2)).await;
+    Ok(())
+}
+
+async fn handle_templates_command(
+    command: TemplatesCommands,
+    output_format: OutputFormat,
+) -> color_eyre::Result<()> {
+    let config = CliConfig::load()?;
+    let token = config
+        .auth
+        .as_ref()
+        .and_then(|a| a.token.as_ref())
+        .ok_or_else(|| eyre!("Not logged in. Run 'arena auth login' first."))?;
+
+    let client = reqwest::Client::new();
+    let base_url = config.api_url();
+
+    let response = match &command {
+        TemplatesCommands::List => client
+            .get(format!("{}/api/v1/snake-templates", base_url))
+            .bearer_auth(token)
+            .send()
+            .await
+            .wrap_err("Failed to list templates")?,
+        TemplatesCommands::Create {
+            name,
+            url_template,
+            public,
+            archive_after_days,
+        } => client
+            .post(format!("{}/api/v1/snake-templates", base_url))
+            .bearer_auth(token)
+            .json(&serde_json::json!({
+                "name": name,
+                "url_template": url_template,
+                "is_public": public,
+                "archive_after_days": archive_after_days,
+            }))
+            .send()
+            .await
+            .wrap_err("Failed to create template")?,
+        TemplatesCommands::Show { id } => client
+            .get(format!("{}/api/v1/snake-templates/{}", base_url, id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .wrap_err("Failed to get template")?,
+        TemplatesCommands::Instantiate { id, tag } => client
+            .post(format!(
+                "{}/api/v1/snake-templates/{}/variants",
+                base_url, id
+            ))
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "tag": tag }))
+            .send()
+            .await
+            .wrap_err("Failed to instantiate template")?,
+    };
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(eyre!("Template not found."));
+    } else if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(eyre!("Template request failed: {} - {}", status, body));
+    }
+
+    let body: serde_json::Value = response.json().await?;
+
+    match (output_format, &command) {
+        (OutputFormat::Json, _) => {
+            println!("{}", serde_json::to_string_pretty(&body)?);
+        }
+        (OutputFormat::Human, TemplatesCommands::List) => {
+            let templates = body.as_array().cloned().unwrap_or_default();
+            if templates.is_empty() {
+                println!("No templates found. Create one with 'arena templates create'.");
+            } else {
+                let rows = templates
+                    .iter()
+                    .map(|t| {
+                        vec![
+                            t["id"].as_str().unwrap_or("").to_string(),
+                            t["name"].as_str().unwrap_or("").to_string(),
+                            t["url_template"].as_str().unwrap_or("").to_string(),
+                            t["archive_after_days"].to_string(),
+                        ]
+                    })
+                    .collect();
+                print_table(
+                    vec!["ID", "Name", "URL Template", "Archive After (days)"],
+                    rows,
+                );
+            }
+        }
+        (OutputFormat::Human, TemplatesCommands::Instantiate { tag, .. }) => {
+            let snake = &body["snake"];
+            let what = if body["created"].as_bool().unwrap_or(false) {
+                "Created"
+            } else if body["unarchived"].as_bool().unwrap_or(false) {
+                "Unarchived"
+            } else {
+                "Already had"
+            };
+            print_success(&format!("{} the variant for {}", what, tag));
+            print_snake_details(snake);
+        }
+        (OutputFormat::Human, _) => {
+            print_field("Name", body["name"].as_str().unwrap_or(""));
+            print_field("ID", body["id"].as_str().unwrap_or(""));
+            print_field("URL template", body["url_template"].as_str().unwrap_or(""));
+            print_field(
+                "Archive after",
+                &format!("{} days without a game", body["archive_after_days"]),
+            );
+            if let Some(variants) = body["variants"].as_array() {
+                let rows = variants
+                    .iter()
+                    .map(|v| {
+                        vec![
+                            v["tag"].as_str().unwrap_or("").to_string(),
+                            v["snake_id"].as_str().unwrap_or("").to_string(),
+                            if v["archived_at"].is_null() {
+                                "active".to_string()
+                            } else {
+                                "archived".to_string()
+                            },
+                        ]
+                    })
+                    .collect();
+                print_table(vec!["Tag", "Snake ID", "Status"], rows);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print an import's changes as a diff: + created, ~ updated, - deleted
+fn print_roster_changes(result: &serde_json::Value) {
+    let visibility = |is_public: &serde_json::Value| {
+        if is_public.as_bool().unwrap_or(false) {
+            "public"
+        } else {
+            "private"
+        }
+    };
+
+    for change in result["changes"].as_array().into_iter().flatten() {
+        let name = change["name"].as_str().unwrap_or("");
+        let url = change["url"].as_str().unwrap_or("");
+        match change["action"].as_str() {
+            Some("create") => println!(
+                "{}",
+                format!("+ {} {} ({})", name, url, visibility(&change["is_public"])).green()
+            ),
+            Some("update") => {
+                println!("{}", format!("~ {}", name).yellow());
+                if let Some(previous) = change["previous_url"].as_str() {
+                    println!("    url: {} -> {}", previous, url);
+                }
+                if !change["previous_is_public"].is_null() {
+                    println!(
+                        "    visibility: {} -> {}",
+                        visibility(&change["previous_is_public"]),
+                        visibility(&change["is_public"])
+                    );
                 }
             }
+            Some("delete") => println!("{}", format!("- {} {}", name, url).red()),
+            _ => {}
         }
     }
 
+    let summary = format!(
+        "{} created, {} updated, {} deleted, {} unchanged",
+        result["created"], result["updated"], result["deleted"], result["unchanged"]
+    );
+    if result["dry_run"].as_bool().unwrap_or(false) {
+        println!("Dry run, nothing changed: {}", summary);
+    } else {
+        print_success(&summary);
+    }
+}
+
+/// A JSON array of strings as "a, b, c", or "none" when it's empty.
+fn json_string_list(values: &serde_json::Value) -> String {
+    let values: Vec<&str> = values
+        .as_array()
+        .map(|values| values.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    if values.is_empty() {
+        "none".to_string()
+    } else {
+        values.join(", ")
+    }
+}
+
+/// Print snake details in human-readable format.
+fn print_snake_details(snake: &serde_json::Value) {
+    print_field("Name", snake["name"].as_str().unwrap_or(""));
+    print_field("ID", snake["id"].as_str().unwrap_or(""));
+    print_field("URL", snake["url"].as_str().unwrap_or(""));
+
+    let visibility = if snake["is_public"].as_bool().unwrap_or(false) {
+        "public"
+    } else {
+        "private"
+    };
+    print_field("Visibility", &status_colored(visibility));
+
+    if let Some(homepage) = snake["homepage_url"].as_str() {
+        print_field("Homepage", homepage);
+    }
+    if let Some(source) = snake["source_url"].as_str() {
+        print_field("Source", source);
+    }
+    if let Some(description) = snake["description"].as_str() {
+        print_field("Description", description);
+    }
+    if snake["tags"]
+        .as_array()
+        .is_some_and(|tags| !tags.is_empty())
+    {
+        print_field("Tags", &json_string_list(&snake["tags"]));
+    }
+
+    if let Some(created) = snake["created_at"].as_str()
+        && let Ok(dt) = chrono::DateTime::parse_from_rfc3339(created)
+    {
+        print_field("Created", &format_timestamp(dt.with_timezone(&chrono::Utc)));
+    }
+
+    if let Some(updated) = snake["updated_at"].as_str()
+        && let Ok(dt) = chrono::DateTime::parse_from_rfc3339(updated)
+    {
+        print_field("Updated", &format_timestamp(dt.with_timezone(&chrono::Utc)));
+    }
+}
+
+async fn login() -> color_eyre::Result<()> {
+    let config = CliConfig::load()?;
+    let base_url = config.api_url().to_string();
+
+    println!("Opening browser for GitHub authentication...");
+    println!(
+        "If the browser doesn't open, visit: {}/auth/github?cli=true",
+        base_url
+    );
+
+    // Try to open browser
+    let _ = open::that(format!("{}/auth/github?cli=true", base_url));
+
+    // For now, prompt user to enter the token manually
+    println!("\nAfter authenticating, you'll receive an API token.");
+    println!("Enter your API token:");
+
+    let mut token = String::new();
+    std::io::stdin().read_line(&mut token)?;
+    let token = token.trim().to_string();
+
+    if token.is_empty() {
+        return Err(eyre!("No token provided"));
+    }
+
+    // Validate the token by trying to list tokens
+    let client = reqwest::Client::new();
+    let response = client
+        .get(format!("{}/api/v1/tokens", base_url))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .wrap_err("Failed to validate token")?;
+
+    if !response.status().is_success() {
+        return Err(eyre!("Invalid token"));
+    }
+
+    // Save the token
+    let mut config = config;
+    config.auth = Some(AuthConfig { token: Some(token) });
+    config.save()?;
+
+    println!("Login successful! Token saved.");
+    print_announcements(&client, &base_url).await;
+    Ok(())
+}
+
+/// Print the arena's running announcements, like maintenance windows. Best
+/// effort: logging in has worked whether or not they can be fetched.
+async fn print_announcements(client: &reqwest::Client, base_url: &str) {
+    let response = client
+        .get(format!("{}/api/v1/announcements", base_url))
+        .send()
+        .await;
+    let announcements: Vec<serde_json::Value> = match response {
+        Ok(response) if response.status().is_success() => response.json().await.unwrap_or_default(),
+        _ => return,
+    };
+
+    for announcement in announcements {
+        let message = announcement["message"].as_str().unwrap_or("");
+        let label = match announcement["severity"].as_str() {
+            Some("critical") => "Critical".red().bold(),
+            Some("warning") => "Warning".yellow().bold(),
+            _ => "Announcement".cyan().bold(),
+        };
+        println!("\n{}: {}", label, message);
+    }
+}
+
+fn logout() -> color_eyre::Result<()> {
+    let mut config = CliConfig::load()?;
+    config.auth = None;
+    config.save()?;
+    println!("Logged out successfully.");
     Ok(())
 }
+
+/// Frames `arena games watch` fetches per request while catching up
+const WATCH_PAGE_SIZE: i64 = 500;
+
+async fn handle_games_command(command: GamesCommands) -> color_eyre::Result<()> {
+    let config = CliConfig::load()?;
+    let token = config
+        .auth
+        .as_ref()
+        .and_then(|a| a.token.as_ref())
+        .ok_or_else(|| eyre!("Not logged in. Run 'arena auth login' first."))?;
+
+    let client = reqwest::Client::new();
+    let base_url = config.api_url();
+
+    match command {
+        GamesCommands::List {
+            snake,
+            ranked,
+            casual,
+            limit,
+        } => {
+            let mut url = format!("{}/api/v1/games?limit={}", base_url, limit);
+            if let Some(snake_id) = snake {
+                url.push_str(&format!("&snake_id={}", snake_id));
+            }
+            if ranked || casual {
+                url.push_str(&format!("&ranked={}", ranked));
+            }
+
+            let response = client
+                .get(&url)
+                .bearer_auth(token)
+                .send()
+                .await
+                .wrap_err("Failed to list games")?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(eyre!("Failed to list games: {} - {}", status, body));
+            }
+
+            let games: Vec<serde_json::Value> = response.json().await?;
+            println!("{}", serde_json::to_string_pretty(&games)?);
+        }
+        GamesCommands::Create {
+            snakes,
+            snake_url,
+            vs,
+            delete_snake,
+            board,
+            game_type,
+            ranked,
+            duel,
+            max_turns,
+            timeout,
+        } => {
+            // Turn away settings no server accepts before sending them; the
+            // server checks them again against its own, possibly tighter, limits
+            game_settings::validate(&GameLimits::default(), board.as_deref(), max_turns, timeout)
+                .map_err(|e| eyre!(e))?;
+
+            // Parse comma-separated snake IDs
+            let mut snake_ids: Vec<String> = snakes
+                .or(vs)
+                .iter()
+                .flat_map(|ids| ids.split(','))
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty())
+                .collect();
+
+            // The temporary snake plays first, ahead of its opponents
+            let temp_snake = match &snake_url {
+                Some(url) => {
+                    let snake = create_temp_snake(&client, base_url, token, url).await?;
+                    eprintln!("Registered temporary snake {} ({})", snake.name, snake.id);
+                    snake_ids.insert(0, snake.id.clone());
+                    Some(snake)
+                }
+                None => None,
+            };
+
+            let response = client
+                .post(format!("{}/api/v1/games", base_url))
+                .bearer_auth(token)
+                .json(&serde_json::json!({
+                    "snakes": snake_ids,
+                    "board": board,
+                    "game_type": game_type,
+                    "ranked": ranked,
+                    "duel": duel,
+                    "max_turns": max_turns,
+                    "move_timeout_ms": timeout
+                }))
+                .send()
+                .await;
+
+            let response = match response {
+                Ok(response) if response.status().is_success() => response,
+                failed => {
+                    // Don't leave a snake behind for a game that was never created
+                    if let Some(snake) = &temp_snake {
+                        delete_temp_snake(&client, base_url, token, snake).await?;
+                    }
+                    let response = failed.wrap_err("Failed to create game")?;
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(eyre!("Failed to create game: {} - {}", status, body));
+                }
+            };
+
+            let game: serde_json::Value = response.json().await?;
+            println!("{}", serde_json::to_string_pretty(&game)?);
+
+            if let Some(snake) = temp_snake.filter(|_| delete_snake) {
+                let game_id = game["id"]
+                    .as_str()
+                    .ok_or_else(|| eyre!("Game response has no id"))?;
+                eprintln!("Waiting for the game to finish...");
+                wait_for_game(&client, base_url, token, game_id).await?;
+                delete_temp_snake(&client, base_url, token, &snake).await?;
+            }
+        }
+        GamesCommands::Show { id } => {
+            let response = client
+                .get(format!("{}/api/v1/games/{}/details", base_url, id))
+                .bearer_auth(token)
+                .send()
+                .await
+                .wrap_err("Failed to get game")?;
+
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Err(eyre!("Game not found."));
+            } else if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(eyre!("Failed to get game: {} - {}", status, body));
+            }
+
+            let game: serde_json::Value = response.json().await?;
+            println!("{}", serde_json::to_string_pretty(&game)?);
+        }
+        GamesCommands::Watch { id, web } => {
+            if web {
+                // Open in browser
+                let url = format!("{}/games/{}", base_url, id);
+                println!("Opening game in browser...");
+                open::that(&url).wrap_err("Failed to open browser")?;
+            } else {
+                // Poll loop, fetching only the frames played since the last poll
+                let mut from_turn = 0;
+                let mut latest_frame = serde_json::Value::Null;
+                loop {
+                    let response = client
+                        .get(format!(
+                            "{}/api/v1/games/{}/details?from_turn={}&limit={}",
+                            base_url, id, from_turn, WATCH_PAGE_SIZE
+                        ))
+                        .bearer_auth(token)
+                        .send()
+                        .await
+                        .wrap_err("Failed to get game")?;
+
+                    if response.status() == reqwest::StatusCode::NOT_FOUND {
+                        return Err(eyre!("Game not found."));
+                    } else if !response.status().is_success() {
+                        let status = response.status();
+                        let body = response.text().await.unwrap_or_default();
+                        return Err(eyre!("Failed to get game: {} - {}", status, body));
+                    }
+
+                    let mut game: serde_json::Value = response.json().await?;
+                    if let Some(frame) = game["frames"].as_array().and_then(|f| f.last()) {
+                        latest_frame = frame.clone();
+                    }
+                    let next_from_turn = game["next_from_turn"].as_i64();
+                    from_turn = next_from_turn
+                        .or_else(|| game["turn_count"].as_i64())
+                        .unwrap_or(from_turn);
+
+                    // Keep paging until caught up to the latest turn
+                    if next_from_turn.is_some() {
+                        continue;
+                    }
+
+                    // Clear screen and print current state with the latest frame
+                    game["frames"] = serde_json::Value::Array(
+                        Some(latest_frame.clone())
+                            .filter(|frame| !frame.is_null())
+                            .into_iter()
+                            .collect(),
+                    );
+                    print!("\x1B[2J\x1B[1;1H");
+                    println!("{}", serde_json::to_string_pretty(&game)?);
+
+                    // Check if game is finished
+                    if game["status"] == "finished" {
+                        println!("\nGame finished!");
+                        break;
+                    }
+
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                }
+            }
+        }
+        GamesCommands::Verify { id } => {
+            let game_id: uuid::Uuid = id.parse().wrap_err("Invalid game ID")?;
+
+            let response = client
+                .get(format!("{}/api/v1/games/{}/details", base_url, game_id))
+                .bearer_auth(token)
+                .send()
+                .await
+                .wrap_err("Failed to get game")?;
+
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Err(eyre!("Game not found."));
+            } else if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(eyre!("Failed to get game: {} - {}", status, body));
+            }
+
+            let game: serde_json::Value = response.json().await?;
+
+            // Recompute the chain here rather than trusting the server's own check
+            let frames = game["frames"]
+                .as_array()
+                .ok_or_else(|| eyre!("Game response has no frames"))?;
+            let computed = chain_digest(game_id, frames);
+            let certified = game["certification"]["digest"].as_str();
+
+            println!("Frames:   {}", frames.len());
+            println!("Computed: {}", computed);
+            match VerificationStatus::check(certified, &computed) {
+                VerificationStatus::Verified => {
+                    println!("Verified: frames match the certified result digest");
+                }
+                VerificationStatus::Uncertified => {
+                    println!("Uncertified: no result digest recorded (has the game finished?)");
+                }
+                VerificationStatus::Mismatch => {
+                    return Err(eyre!(
+                        "Frames don't match the certified result digest {}",
+                        certified.unwrap_or_default()
+                    ));
+                }
+            }
+        }
+        GamesCommands::Frames { id } => {
+            let mut response = client
+                .get(format!("{}/api/v1/games/{}/frames.ndjson", base_url, id))
+                .bearer_auth(token)
+                .send()
+                .await
+                .wrap_err("Failed to get game frames")?;
+
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Err(eyre!("Game not found."));
+            } else if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(eyre!("Failed to get game frames: {} - {}", status, body));
+            }
+
+            // Pass chunks straight through, so big games never sit in memory
+            let mut stdout = std::io::stdout().lock();
+            while let Some(chunk) = response
+                .chunk()
+                .await
+                .wrap_err("Failed to read game frames")?
+            {
+                stdout
+                    .write_all(&chunk)
+                    .wrap_err("Failed to write game frames")?;
+            }
+        }
+        GamesCommands::Notation { id } => {
+            let response = client
+                .get(format!("{}/api/v1/games/{}/notation", base_url, id))
+                .bearer_auth(token)
+                .send()
+                .await
+                .wrap_err("Failed to get game notation")?;
+
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Err(eyre!("Game not found."));
+            } else if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(eyre!("Failed to get game notation: {} - {}", status, body));
+            }
+
+            let notation = response
+                .text()
+                .await
+                .wrap_err("Failed to read game notation")?;
+            print!("{}", notation);
+        }
+        GamesCommands::Export { id, out } => {
+            let response = client
+                .get(format!("{}/api/v1/games/{}/export", base_url, id))
+                .bearer_auth(token)
+                .send()
+                .await
+                .wrap_err("Failed to export game")?;
+
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Err(eyre!("Game not found."));
+            } else if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(eyre!("Failed to export game: {} - {}", status, body));
+            }
+
+            let export = response
+                .bytes()
+                .await
+                .wrap_err("Failed to read game export")?;
+            match out {
+                Some(path) => {
+                    std::fs::write(&path, &export)
+                        .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+                    print_success(&format!("Exported game {} to {}", id, path.display()));
+                }
+                None => {
+                    std::io::stdout()
+                        .write_all(&export)
+                        .wrap_err("Failed to write game export")?;
+                }
+            }
+        }
+        GamesCommands::Diff { game_a, game_b } => {
+            let response = client
+                .get(format!(
+                    "{}/api/v1/games/diff?game_a={}&game_b={}",
+                    base_url, game_a, game_b
+                ))
+                .bearer_auth(token)
+                .send()
+                .await
+                .wrap_err("Failed to diff games")?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(eyre!("Failed to diff games: {} - {}", status, body));
+            }
+
+            let diff: serde_json::Value = response.json().await?;
+            print_game_diff(&diff);
+        }
+        GamesCommands::Timing { id } => {
+            let response = client
+                .get(format!("{}/api/v1/games/{}/timing", base_url, id))
+                .bearer_auth(token)
+                .send()
+                .await
+                .wrap_err("Failed to get game timing")?;
+
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                let body = response.text().await.unwrap_or_default();
+                return Err(eyre!("{}", body));
+            } else if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(eyre!("Failed to get game timing: {} - {}", status, body));
+            }
+
+            let report: serde_json::Value = response.json().await?;
+            let rows = report["stages"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|stage| {
+                    let ms = |field: &str| format!("{:.1}", stage[field].as_f64().unwrap_or(0.0));
+                    vec![
+                        stage["stage"].as_str().unwrap_or("-").to_string(),
+                        stage["turns"].to_string(),
+                        ms("total_ms"),
+                        ms("mean_ms"),
+                        ms("max_ms"),
+                        format!("{:.0}%", stage["share"].as_f64().unwrap_or(0.0) * 100.0),
+                    ]
+                })
+                .collect();
+            print_table(
+                vec!["STAGE", "TURNS", "TOTAL MS", "MEAN MS", "MAX MS", "SHARE"],
+                rows,
+            );
+            println!("\n{}", report["verdict"].as_str().unwrap_or_default());
+        }
+    }
+
+    Ok(())
+}
+
+/// A snake registered by `arena games create --snake-url` for one game
+struct TempSnake {
+    id: String,
+    name: String,
+}
+
+/// Register a private snake for `url`, named so it's clearly throwaway
+async fn create_temp_snake(
+    client: &reqwest::Client,
+    base_url: &str,
+    token: &str,
+    url: &str,
+) -> color_eyre::Result<TempSnake> {
+    let name = format!("adhoc-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    let response = client
+        .post(format!("{}/api/v1/snakes", base_url))
+        .bearer_auth(token)
+        .json(&serde_json::json!({
+            "name": name,
+            "url": url,
+            "is_public": false
+        }))
+        .send()
+        .await
+        .wrap_err("Failed to create temporary snake")?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(eyre!(
+            "Failed to create temporary snake: {} - {}",
+            status,
+            body
+        ));
+    }
+
+    let snake: serde_json::Value = response.json().await?;
+    let id = snake["id"]
+        .as_str()
+        .ok_or_else(|| eyre!("Snake response has no id"))?
+        .to_string();
+    Ok(TempSnake { id, name })
+}
+
+async fn delete_temp_snake(
+    client: &reqwest::Client,
+    base_url: &str,
+    token: &str,
+    snake: &TempSnake,
+) -> color_eyre::Result<()> {
+    let response = client
+        .delete(format!("{}/api/v1/snakes/{}", base_url, snake.id))
+        .bearer_auth(token)
+        .send()
+        .await
+        .wrap_err("Failed to delete temporary snake")?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(eyre!(
+            "Failed to delete temporary snake {}: {} - {}",
+            snake.id,
+            status,
+            body
+        ));
+    }
+
+    eprintln!("Deleted temporary snake {} ({})", snake.name, snake.id);
+    Ok(())
+}
+
+/// Poll a game until it's finished
+async fn wait_for_game(
+    client: &reqwest::Client,
+    base_url: &str,
+    token: &str,
+    game_id: &str,
+) -> color_eyre::Result<()> {
+    loop {
+        let response = client
+            .get(format!(
+                "{}/api/v1/games/{}/details?limit=1",
+                base_url, game_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .wrap_err("Failed to get game")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(eyre!("Failed to get game: {} - {}", status, body));
+        }
+
+        let game: serde_json::Value = response.json().await?;
+        if game["status"] == "finished" {
+            return Ok(());
+        }
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+fn print_game_diff(diff: &serde_json::Value) {
+    println!(
+        "A: {} ({} turns)",
+        diff["game_a"].as_str().unwrap_or("-"),
+        diff["turns_a"]
+    );
+    println!(
+        "B: {} ({} turns)",
+        diff["game_b"].as_str().unwrap_or("-"),
+        diff["turns_b"]
+    );
+    for difference in diff["setup_differences"].as_array().into_iter().flatten() {
+        println!("Setup: {}", difference.as_str().unwrap_or_default());
+    }
+
+    let divergence = &diff["first_divergence"];
+    if divergence.is_null() {
+        println!("\nThe games are identical.");
+        return;
+    }
+    println!("\nFirst divergence on turn {}:", divergence["turn"]);
+    for difference in divergence["differences"].as_array().into_iter().flatten() {
+        println!("  {}", difference.as_str().unwrap_or_default());
+    }
+    println!("{} turn(s) differ in all", diff["differing_turns"]);
+
+    let outcome = |snake: &serde_json::Value| {
+        if snake.is_null() {
+            return "-".to_string();
+        }
+        let placement = snake["placement"]
+            .as_i64()
+            .map_or("unplaced".to_string(), |p| format!("#{}", p));
+        let eliminated = match snake["eliminated_turn"].as_i64() {
+            Some(turn) => format!(
+                ", out on turn {} ({})",
+                turn,
+                snake["eliminated_cause"].as_str().unwrap_or("unknown")
+            ),
+            None => String::new(),
+        };
+        format!(
+            "{} {} length {} health {}{}",
+            snake["name"].as_str().unwrap_or("-"),
+            placement,
+            snake["length"],
+            snake["health"],
+            eliminated
+        )
+    };
+    println!("\nSnakes:");
+    for snake in diff["snakes"].as_array().into_iter().flatten() {
+        let diverged = match snake["diverged_at"].as_i64() {
+            Some(turn) => format!("differs from turn {}", turn),
+            None => "same in both".to_string(),
+        };
+        println!("  {}. {}", snake["slot"], diverged);
+        println!("     A: {}", outcome(&snake["a"]));
+        println!("     B: {}", outcome(&snake["b"]));
+    }
+}
+
+async fn handle_checks_command(
+    command: ChecksCommands,
+    output_format: OutputFormat,
+) -> color_eyre::Result<()> {
+    let config = CliConfig::load()?;
+    let token = config
+        .auth
+        .as_ref()
+        .and_then(|a| a.token.as_ref())
+        .ok_or_else(|| eyre!("Not logged in. Run 'arena auth login' first."))?;
+
+    let client = reqwest::Client::new();
+    let base_url = config.api_url();
+
+    let (check, wait) = match command {
+        ChecksCommands::Run {
+            snake,
+            opponents,
+            games,
+            win_rate,
+            board,
+            game_type,
+            commit,
+            wait,
+        } => {
+            // Parse comma-separated snake IDs
+            let opponent_ids: Vec<&str> = opponents.split(',').map(|s| s.trim()).collect();
+
+            let response = client
+                .post(format!("{}/api/v1/checks", base_url))
+                .bearer_auth(token)
+                .json(&serde_json::json!({
+                    "snake": snake,
+                    "opponents": opponent_ids,
+                    "games": games,
+                    "required_win_rate": win_rate,
+                    "board": board,
+                    "game_type": game_type,
+                    "commit_sha": commit
+                }))
+                .send()
+                .await
+                .wrap_err("Failed to start check")?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(eyre!("Failed to start check: {} - {}", status, body));
+            }
+
+            let check: serde_json::Value = response.json().await?;
+            (check, wait)
+        }
+        ChecksCommands::Show { id, wait } => {
+            (get_check(&client, base_url, token, &id).await?, wait)
+        }
+    };
+
+    let check = if wait {
+        let id = check["id"].as_str().unwrap_or_default().to_string();
+        let mut check = check;
+        let mut last_finished = None;
+        while check["status"] == "running" {
+            // Progress goes to stderr so stdout stays clean for --format json
+            let finished = check["games_finished"].as_u64();
+            if finished != last_finished {
+                eprintln!(
+                    "{}/{} games finished, {} wins ({} needed)",
+                    finished.unwrap_or(0),
+                    check["games"],
+                    check["wins"],
+                    check["wins_needed"]
+                );
+                last_finished = finished;
+            }
+
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            check = get_check(&client, base_url, token, &id).await?;
+        }
+        check
+    } else {
+        check
+    };
+
+    match output_format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&check)?);
+        }
+        OutputFormat::Human => {
+            print_check_details(&check);
+        }
+    }
+
+    if wait && check["status"] == "failed" {
+        return Err(eyre!(
+            "Check failed: {} of {} games won, {} needed",
+            check["wins"],
+            check["games"],
+            check["wins_needed"]
+        ));
+    }
+
+    Ok(())
+}
+
+async fn handle_capabilities_command(output_format: OutputFormat) -> color_eyre::Result<()> {
+    let config = CliConfig::load()?;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("{}/api/v1/capabilities", config.api_url()))
+        .send()
+        .await
+        .wrap_err("Failed to get capabilities")?;
+
+    // Servers from before the endpoint existed
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(eyre!(
+            "This server doesn't report its capabilities; it predates them."
+        ));
+    } else if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(eyre!("Failed to get capabilities: {} - {}", status, body));
+    }
+
+    let capabilities: serde_json::Value = response.json().await?;
+
+    match output_format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&capabilities)?);
+        }
+        OutputFormat::Human => {
+            let list = |key: &str| {
+                capabilities[key]
+                    .as_array()
+                    .map(|values| {
+                        values
+                            .iter()
+                            .filter_map(|v| v.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    })
+                    .unwrap_or_default()
+            };
+            let limits = &capabilities["limits"];
+
+            print_field(
+                "Server",
+                capabilities["server_version"].as_str().unwrap_or(""),
+            );
+            print_field("API version", &capabilities["api_version"].to_string());
+            print_field("Game types", &list("game_types"));
+            print_field("Board sizes", &list("board_sizes"));
+            print_field(
+                "Snakes",
+                &format!("{}-{}", limits["min_snakes"], limits["max_snakes"]),
+            );
+            print_field("Max turns", &limits["max_turns"].to_string());
+            print_field(
+                "Move timeout",
+                &format!(
+                    "{}-{}ms (default {}ms)",
+                    limits["min_move_timeout_ms"],
+                    limits["max_move_timeout_ms"],
+                    limits["default_move_timeout_ms"]
+                ),
+            );
+            if !limits["max_game_minutes"].is_null() {
+                print_field(
+                    "Max game length",
+                    &format!("{} minutes", limits["max_game_minutes"]),
+                );
+            }
+            print_field(
+                "Check games",
+                &format!("up to {}", limits["max_check_games"]),
+            );
+
+            let enabled: Vec<&str> = capabilities["features"]
+                .as_object()
+                .map(|features| {
+                    features
+                        .iter()
+                        .filter(|(_, on)| on.as_bool() == Some(true))
+                        .map(|(name, _)| name.as_str())
+                        .collect()
+                })
+                .unwrap_or_default();
+            print_field("Features", &enabled.join(", "));
+        }
+    }
+
+    Ok(())
+}
+
+/// How long `arena doctor` waits for each request
+const DOCTOR_TIMEOUT: Duration = Duration::from_secs(10);
+
+async fn handle_doctor_command(
+    snake: Option<String>,
+    output_format: OutputFormat,
+) -> color_eyre::Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(DOCTOR_TIMEOUT)
+        .build()
+        .wrap_err("Failed to build HTTP client")?;
+    let mut checks = Vec::new();
+
+    let config_path = CliConfig::config_path()?;
+    let config = match CliConfig::load() {
+        Ok(config) => {
+            checks.push(DoctorCheck::pass(
+                "Config",
+                if config_path.exists() {
+                    config_path.display().to_string()
+                } else {
+                    "No config file yet; using the defaults".to_string()
+                },
+            ));
+            config
+        }
+        Err(e) => {
+            checks.push(DoctorCheck::fail(
+                "Config",
+                format!("{:#}", e),
+                format!(
+                    "Fix the TOML in {}, or delete it and run `arena auth login`",
+                    config_path.display()
+                ),
+            ));
+            CliConfig::default()
+        }
+    };
+    let base_url = config.api_url();
+    checks.push(doctor::check_api_url(base_url));
+
+    checks.extend(doctor_server_checks(&client, base_url, &config).await);
+
+    if let Some(url) = snake {
+        checks.extend(doctor_snake_checks(&client, &url).await);
+    }
+
+    let healthy = doctor::all_passed(&checks);
+    match output_format {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "healthy": healthy,
+                    "checks": checks,
+                }))?
+            );
+        }
+        OutputFormat::Human => {
+            for check in &checks {
+                let status = match check.status {
+                    DoctorStatus::Pass => "pass".green(),
+                    DoctorStatus::Warn => "warn".yellow(),
+                    DoctorStatus::Fail => "fail".red(),
+                    DoctorStatus::Skipped => "skip".dimmed(),
+                };
+                println!("[{}] {}: {}", status, check.name.bold(), check.detail);
+                if let Some(fix) = &check.fix {
+                    println!("       {} {}", "Fix:".bold(), fix);
+                }
+            }
+        }
+    }
+
+    if !healthy {
+        let failed = checks
+            .iter()
+            .filter(|c| c.status == DoctorStatus::Fail)
+            .count();
+        return Err(eyre!("{} of {} checks failed", failed, checks.len()));
+    }
+    Ok(())
+}
+
+/// Reachability, API version, clock skew and token checks against the server
+async fn doctor_server_checks(
+    client: &reqwest::Client,
+    base_url: &str,
+    config: &CliConfig,
+) -> Vec<DoctorCheck> {
+    let mut checks = Vec::new();
+    let started = std::time::Instant::now();
+    let response = client
+        .get(format!("{}/api/v1/capabilities", base_url))
+        .header("Arena-Api-Version", doctor::CLI_API_VERSION.to_string())
+        .send()
+        .await;
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            checks.push(DoctorCheck::fail(
+                "Server",
+                format!("Couldn't reach {}: {}", base_url, e),
+                "Check your network connection, and that api_url in the config file is right",
+            ));
+            for name in ["API version", "Clock", "Token"] {
+                checks.push(DoctorCheck::skipped(name, "The server couldn't be reached"));
+            }
+            return checks;
+        }
+    };
+    checks.push(DoctorCheck::pass(
+        "Server",
+        format!(
+            "Reached {} in {}ms",
+            base_url,
+            started.elapsed().as_millis()
+        ),
+    ));
+
+    let now = chrono::Utc::now();
+    let date = response
+        .headers()
+        .get(reqwest::header::DATE)
+        .and_then(|d| d.to_str().ok())
+        .map(str::to_string);
+
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_FOUND {
+        checks.push(DoctorCheck::warn(
+            "API version",
+            "The server doesn't report its capabilities; it predates them",
+            "Ask the arena's operator to upgrade; some commands may not work",
+        ));
+    } else if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        checks.push(DoctorCheck::fail(
+            "API version",
+            format!("The server answered {}: {}", status, body),
+            "Upgrade the arena CLI",
+        ));
+    } else {
+        match response.json::<serde_json::Value>().await {
+            Ok(capabilities) => checks.push(doctor::check_api_version(&capabilities)),
+            Err(e) => checks.push(DoctorCheck::fail(
+                "API version",
+                format!("The capabilities response wasn't JSON: {}", e),
+                "Check api_url points at the arena, not a different site",
+            )),
+        }
+    }
+
+    checks.push(match date {
+        Some(date) => doctor::check_clock_skew(&date, now),
+        None => DoctorCheck::skipped("Clock", "The server didn't send a Date header"),
+    });
+
+    let token = config.auth.as_ref().and_then(|a| a.token.as_deref());
+    let token_status = match token {
+        Some(token) => Some(doctor_status(client, base_url, "/api/v1/tokens", token).await),
+        None => None,
+    };
+    checks.push(doctor::check_token(token_status));
+    if let (Some(token), Some(200..=299)) = (token, token_status) {
+        let mut access = Vec::new();
+        for (area, path) in [
+            ("snakes", "/api/v1/snakes"),
+            ("games", "/api/v1/games?limit=1"),
+            ("preferences", "/api/v1/me/preferences"),
+        ] {
+            access.push((area, doctor_status(client, base_url, path, token).await));
+        }
+        checks.push(doctor::check_token_access(&access));
+    }
+
+    if let Ok(admin_token) = std::env::var("ARENA_ADMIN_TOKEN")
+        && !admin_token.is_empty()
+    {
+        let status = doctor_status(client, base_url, "/api/v1/admin/load", &admin_token).await;
+        checks.push(doctor::check_admin_token(status));
+    }
+
+    checks
+}
+
+/// The status of a GET with a bearer token, 0 if the request didn't complete
+async fn doctor_status(client: &reqwest::Client, base_url: &str, path: &str, token: &str) -> u16 {
+    client
+        .get(format!("{}{}", base_url, path))
+        .bearer_auth(token)
+        .send()
+        .await
+        .map(|r| r.status().as_u16())
+        .unwrap_or(0)
+}
+
+/// Play a turn against a snake the way the arena would: GET /, then /start,
+/// /move and /end with a sample game
+async fn doctor_snake_checks(client: &reqwest::Client, url: &str) -> Vec<DoctorCheck> {
+    let mut checks = Vec::new();
+    let base = url.trim_end_matches('/');
+    let game = doctor::sample_game_request();
+
+    let info = match client.get(format!("{}/", base)).send().await {
+        Ok(response) if response.status().is_success() => {
+            let body = response.text().await.unwrap_or_default();
+            doctor::check_snake_info(&body).map_err(|e| {
+                (
+                    e,
+                    "Answer GET / with {\"apiversion\": \"1\"} and optional color, head and tail"
+                        .to_string(),
+                )
+            })
+        }
+        Ok(response) => Err((
+            format!("GET / answered {}", response.status()),
+            "Answer GET / with a 200 and the snake's info".to_string(),
+        )),
+        Err(e) => Err((
+            format!("Couldn't reach {}: {}", base, e),
+            "Start your snake, and check the URL and port it listens on".to_string(),
+        )),
+    };
+    match info {
+        Ok(detail) => checks.push(DoctorCheck::pass("Snake info", detail)),
+        Err((detail, fix)) => {
+            checks.push(DoctorCheck::fail("Snake info", detail, fix));
+            for name in ["Snake /start", "Snake /move", "Snake latency", "Snake /end"] {
+                checks.push(DoctorCheck::skipped(name, "GET / failed"));
+            }
+            return checks;
+        }
+    }
+
+    checks.push(doctor_snake_post(client, base, "/start", &game).await);
+
+    let started = std::time::Instant::now();
+    let response = client
+        .post(format!("{}/move", base))
+        .json(&game)
+        .send()
+        .await;
+    let latency_ms = started.elapsed().as_millis();
+    match response {
+        Ok(response) if response.status().is_success() => {
+            let body = response.text().await.unwrap_or_default();
+            match doctor::check_snake_move(&body) {
+                Ok(direction) => {
+                    checks.push(DoctorCheck::pass(
+                        "Snake /move",
+                        format!("Moved {}", direction),
+                    ));
+                    checks.push(doctor::check_snake_latency(latency_ms));
+                }
+                Err(e) => {
+                    checks.push(DoctorCheck::fail(
+                        "Snake /move",
+                        e,
+                        "Answer POST /move with {\"move\": \"up\"}, using up, down, left or right",
+                    ));
+                    checks.push(DoctorCheck::skipped("Snake latency", "/move failed"));
+                }
+            }
+        }
+        Ok(response) => {
+            checks.push(DoctorCheck::fail(
+                "Snake /move",
+                format!("POST /move answered {}", response.status()),
+                "Handle POST /move and answer with a 200",
+            ));
+            checks.push(DoctorCheck::skipped("Snake latency", "/move failed"));
+        }
+        Err(e) => {
+            checks.push(DoctorCheck::fail(
+                "Snake /move",
+                format!("POST /move failed: {}", e),
+                "Answer POST /move within the game's timeout",
+            ));
+            checks.push(DoctorCheck::skipped("Snake latency", "/move failed"));
+        }
+    }
+
+    checks.push(doctor_snake_post(client, base, "/end", &game).await);
+    checks
+}
+
+/// POST /start or /end, which only need to succeed
+async fn doctor_snake_post(
+    client: &reqwest::Client,
+    base: &str,
+    path: &'static str,
+    game: &serde_json::Value,
+) -> DoctorCheck {
+    let name = match path {
+        "/start" => "Snake /start",
+        _ => "Snake /end",
+    };
+    match client
+        .post(format!("{}{}", base, path))
+        .json(game)
+        .send()
+        .await
+    {
+        Ok(response) if response.status().is_success() => {
+            DoctorCheck::pass(name, format!("Answered {}", response.status()))
+        }
+        Ok(response) => DoctorCheck::fail(
+            name,
+            format!("POST {} answered {}", path, response.status()),
+            format!(
+                "Handle POST {} and answer with a 200; the body is ignored",
+                path
+            ),
+        ),
+        Err(e) => DoctorCheck::fail(
+            name,
+            format!("POST {} failed: {}", path, e),
+            format!("Handle POST {} and answer promptly", path),
+        ),
+    }
+}
+
+async fn handle_archives_command(command: ArchivesCommands) -> color_eyre::Result<()> {
+    let config = CliConfig::load()?;
+    let client = reqwest::Client::new();
+
+    match command {
+        ArchivesCommands::Download {
+            period,
+            out,
+            admin_token,
+        } => {
+            let mut response = client
+                .get(format!(
+                    "{}/api/v1/admin/archives/tarball",
+                    config.api_url()
+                ))
+                .query(&[("period", period.as_str())])
+                .bearer_auth(admin_token)
+                .send()
+                .await
+                .wrap_err("Failed to download archives")?;
+
+            match response.status() {
+                reqwest::StatusCode::NOT_FOUND => {
+                    return Err(eyre!("Admin endpoints aren't enabled on this server."));
+                }
+                reqwest::StatusCode::UNAUTHORIZED => {
+                    return Err(eyre!("Invalid admin token."));
+                }
+                status if !status.is_success() => {
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(eyre!("Failed to download archives: {} - {}", status, body));
+                }
+                _ => {}
+            }
+
+            let games = response
+                .headers()
+                .get("x-archive-count")
+                .and_then(|count| count.to_str().ok())
+                .unwrap_or("?")
+                .to_string();
+            let path = out.unwrap_or_else(|| PathBuf::from(format!("arena-games-{}.tar", period)));
+            let mut file = std::fs::File::create(&path)
+                .wrap_err_with(|| format!("Failed to create {}", path.display()))?;
+
+            // Written as it downloads, so a month of games never sits in memory
+            let mut bytes = 0;
+            while let Some(chunk) = response
+                .chunk()
+                .await
+                .wrap_err("Failed to read archives; the tar is incomplete")?
+            {
+                file.write_all(&chunk)
+                    .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+                bytes += chunk.len();
+            }
+
+            print_success(&format!(
+                "Downloaded {} games ({} bytes) to {}",
+                games,
+                bytes,
+                path.display()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_preferences_command(
+    command: PreferencesCommands,
+    output_format: OutputFormat,
+) -> color_eyre::Result<()> {
+    let config = CliConfig::load()?;
+    let token = config
+        .auth
+        .as_ref()
+        .and_then(|a| a.token.as_ref())
+        .ok_or_else(|| eyre!("Not logged in. Run 'arena auth login' first."))?;
+
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/v1/me/preferences", config.api_url());
+
+    let response = match command {
+        PreferencesCommands::Show => client
+            .get(&url)
+            .bearer_auth(token)
+            .send()
+            .await
+            .wrap_err("Failed to get preferences")?,
+        PreferencesCommands::Set {
+            board,
+            game_type,
+            timezone,
+            notify,
+            playback_speed,
+            theme,
+            prefer_tags,
+        } => {
+            let preferred_opponent_tags: Option<Vec<&str>> = prefer_tags.as_deref().map(|tags| {
+                tags.split(',')
+                    .map(|t| t.trim())
+                    .filter(|t| !t.is_empty())
+                    .collect()
+            });
+            let notification_channels: Option<Vec<&str>> = notify.as_deref().map(|notify| {
+                notify
+                    .split(',')
+                    .map(|c| c.trim())
+                    .filter(|c| !c.is_empty())
+                    .collect()
+            });
+
+            client
+                .put(&url)
+                .bearer_auth(token)
+                .json(&serde_json::json!({
+                    "board": board,
+                    "game_type": game_type,
+                    "timezone": timezone,
+                    "notification_channels": notification_channels,
+                    "playback_speed": playback_speed,
+                    "theme": theme,
+                    "preferred_opponent_tags": preferred_opponent_tags,
+                }))
+                .send()
+                .await
+                .wrap_err("Failed to update preferences")?
+        }
+    };
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(eyre!("Failed to sync preferences: {} - {}", status, body));
+    }
+
+    let preferences: serde_json::Value = response.json().await?;
+
+    match output_format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&preferences)?);
+        }
+        OutputFormat::Human => {
+            let channels = preferences["notification_channels"]
+                .as_array()
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(|v| v.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default();
+
+            print_field("Board", preferences["board"].as_str().unwrap_or(""));
+            print_field("Game type", preferences["game_type"].as_str().unwrap_or(""));
+            print_field("Timezone", preferences["timezone"].as_str().unwrap_or(""));
+            print_field(
+                "Notifications",
+                if channels.is_empty() {
+                    "none"
+                } else {
+                    &channels
+                },
+            );
+            print_field(
+                "Playback speed",
+                &format!("{}x", preferences["playback_speed"]),
+            );
+            print_field("Theme", preferences["theme"].as_str().unwrap_or(""));
+            print_field(
+                "Preferred tags",
+                &json_string_list(&preferences["preferred_opponent_tags"]),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn get_check(
+    client: &reqwest::Client,
+    base_url: &str,
+    token: &str,
+    id: &str,
+) -> color_eyre::Result<serde_json::Value> {
+    let response = client
+        .get(format!("{}/api/v1/checks/{}", base_url, id))
+        .bearer_auth(token)
+        .send()
+        .await
+        .wrap_err("Failed to get check")?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(eyre!("Check not found."));
+    } else if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(eyre!("Failed to get check: {} - {}", status, body));
+    }
+
+    Ok(response.json().await?)
+}
+
+/// Print check details in human-readable format.
+fn print_check_details(check: &serde_json::Value) {
+    print_field("ID", check["id"].as_str().unwrap_or(""));
+    print_field("Snake", check["snake_id"].as_str().unwrap_or(""));
+    print_field(
+        "Status",
+        &status_colored(check["status"].as_str().unwrap_or("")),
+    );
+    print_field(
+        "Games",
+        &format!("{} of {} finished", check["games_finished"], check["games"]),
+    );
+    print_field(
+        "Wins",
+        &format!(
+            "{} ({} needed for {}%)",
+            check["wins"], check["wins_needed"], check["required_win_rate"]
+        ),
+    );
+}
@@ -2,6 +2,11 @@
 //!
 //! Supports configurable load patterns (steady stream, batch), periodic stats output,
 //! and structured tracing events for Eyes integration.
+//!
+//! With `--shadow` it skips HTTP altogether: the load patterns become a game
+//! count and rate for `arena admin simulate`, which creates and runs the games
+//! in-process against the local database with bot snakes, and reports the
+//! engine's and database's throughput ceilings.
 
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
@@ -28,18 +33,23 @@ struct Cli {
     url: String,
 
     /// Comma-separated snake UUIDs to use for games
-    #[arg(long)]
-    snakes: String,
+    #[arg(long, required_unless_present = "shadow")]
+    snakes: Option<String>,
 
     /// API token for authentication
-    #[arg(long, env = "ARENA_TOKEN")]
-    token: String,
+    #[arg(long, env = "ARENA_TOKEN", required_unless_present = "shadow")]
+    token: Option<String>,
+
+    /// Run the games in-process with `arena admin simulate` instead of over HTTP
+    #[arg(long)]
+    shadow: bool,
 
     /// Steady stream rate: N/s (e.g., "10/s" for 10 games per second)
     #[arg(long)]
     steady: Option<String>,
 
-    /// Batch pattern: games,interval (e.g., "100,30s" for 100 games every 30 seconds)
+    /// Batch pattern: games,interval (e.g., "100,30s" for 100 games every 30 seconds),
+    /// created through the batch endpoint
     #[arg(long)]
     batch: Option<String>,
 
@@ -132,7 +142,7 @@ async fn create_game(
     let start = Instant::now();
 
     let response = client
-        .post(format!("{}/api/games", base_url))
+        .post(format!("{}/api/v1/games", base_url))
         .bearer_auth(token)
         .json(&serde_json::json!({
             "snakes": snakes,
@@ -167,6 +177,69 @@ async fn create_game(
     }
 }
 
+/// Most games the batch endpoint creates per request
+const MAX_BATCH_GAMES: u32 = 100;
+
+#[derive(Debug)]
+struct CreateBatchResult {
+    game_ids: Vec<Uuid>,
+    latency: Duration,
+}
+
+async fn create_game_batch(
+    client: &reqwest::Client,
+    base_url: &str,
+    token: &str,
+    snakes: &[Uuid],
+    board: &str,
+    game_type: &str,
+    count: u32,
+) -> Result<CreateBatchResult, GameCreationError> {
+    let start = Instant::now();
+
+    let response = client
+        .post(format!("{}/api/v1/games/batch", base_url))
+        .bearer_auth(token)
+        .json(&serde_json::json!({
+            "snakes": snakes,
+            "count": count,
+            "board": board,
+            "game_type": game_type,
+        }))
+        .send()
+        .await;
+
+    let latency = start.elapsed();
+
+    match response {
+        Ok(resp) if resp.status().is_success() => {
+            let body: serde_json::Value = resp
+                .json()
+                .await
+                .map_err(|e| GameCreationError::Parse(e.to_string()))?;
+
+            let game_ids = body["ids"]
+                .as_array()
+                .ok_or_else(|| GameCreationError::Parse("Missing game ids".to_string()))?
+                .iter()
+                .map(|id| {
+                    id.as_str()
+                        .and_then(|s| Uuid::parse_str(s).ok())
+                        .ok_or_else(|| GameCreationError::Parse("Invalid game id".to_string()))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(CreateBatchResult { game_ids, latency })
+        }
+        Ok(resp) => {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            Err(GameCreationError::Api { status, body })
+        }
+        Err(e) => Err(GameCreationError::Request(e)),
+    }
+}
+
 // ============================================================================
 // Stats Tracking
 // ============================================================================
@@ -406,41 +479,46 @@ impl LoadPattern for BatchPattern {
             tokio::select! {
                 _ = cancel.cancelled() => break,
                 _ = interval.tick() => {
-                    // Spawn batch_size concurrent requests
-                    let futures: Vec<_> = (0..self.batch_size)
-                        .map(|_| {
-                            let client = client.clone();
-                            let config = config.clone();
-                            let stats = stats.clone();
-                            async move {
-                                match create_game(
-                                    &client,
-                                    &config.base_url,
-                                    &config.token,
-                                    &config.snakes,
-                                    &config.board,
-                                    &config.game_type,
-                                )
-                                .await
-                                {
-                                    Ok(result) => {
+                    // Create the batch through the batch endpoint, up to
+                    // MAX_BATCH_GAMES per request
+                    let mut remaining = self.batch_size;
+                    let mut requests = Vec::new();
+                    while remaining > 0 {
+                        let count = remaining.min(MAX_BATCH_GAMES);
+                        remaining -= count;
+                        requests.push(async move {
+                            match create_game_batch(
+                                client,
+                                &config.base_url,
+                                &config.token,
+                                &config.snakes,
+                                &config.board,
+                                &config.game_type,
+                                count,
+                            )
+                            .await
+                            {
+                                Ok(result) => {
+                                    for game_id in &result.game_ids {
                                         stats.record_success(result.latency);
                                         tracing::info!(
-                                            game_id = %result.game_id,
+                                            game_id = %game_id,
                                             latency_ms = result.latency.as_millis() as u64,
                                             "game_created"
                                         );
                                     }
-                                    Err(e) => {
+                                }
+                                Err(e) => {
+                                    for _ in 0..count {
                                         stats.record_failure();
-                                        tracing::warn!(error = %e, "game_creation_failed");
                                     }
+                                    tracing::warn!(error = %e, count, "game_batch_creation_failed");
                                 }
                             }
-                        })
-                        .collect();
+                        });
+                    }
 
-                    futures::future::join_all(futures).await;
+                    futures::future::join_all(requests).await;
                 }
             }
         }
@@ -500,6 +578,76 @@ fn format_duration(d: Duration) -> String {
     format!("{:02}:{:02}:{:02}", hours, mins, secs)
 }
 
+// ============================================================================
+// Shadow Mode
+// ============================================================================
+
+/// Games the load patterns would create over `duration`, and the average rate
+/// they'd be created at
+fn shadow_load(
+    steady: Option<&SteadyStreamPattern>,
+    batch: Option<&BatchPattern>,
+    duration: Duration,
+) -> (usize, f64) {
+    let secs = duration.as_secs_f64();
+    let mut games = 0.0;
+    if let Some(steady) = steady {
+        games += steady.rate_per_second * secs;
+    }
+    if let Some(batch) = batch {
+        // A batch goes out at the start and after every full interval
+        let batches = (secs / batch.interval.as_secs_f64().max(1.0))
+            .ceil()
+            .max(1.0);
+        games += f64::from(batch.batch_size) * batches;
+    }
+    let games = (games.round() as usize).max(1);
+    (games, games as f64 / secs.max(1.0))
+}
+
+/// Arguments for `arena` to simulate `games` games at `rate` a second
+fn shadow_args(cli: &Cli, games: usize, rate: f64, duration: Duration) -> Vec<String> {
+    vec![
+        "admin".to_string(),
+        "simulate".to_string(),
+        "--games".to_string(),
+        games.to_string(),
+        "--rate".to_string(),
+        format!("{:.3}", rate),
+        "--board".to_string(),
+        cli.board.clone(),
+        "--type".to_string(),
+        cli.game_type.clone(),
+        // Creating the games takes the whole duration, so leave time to finish them
+        "--timeout-secs".to_string(),
+        (duration.as_secs() + 600).to_string(),
+    ]
+}
+
+/// The `arena` binary: `ARENA_BIN`, or the one built next to this one
+fn arena_binary() -> color_eyre::Result<std::path::PathBuf> {
+    if let Ok(path) = std::env::var("ARENA_BIN") {
+        return Ok(path.into());
+    }
+    let exe = std::env::current_exe().wrap_err("Failed to find the stress test binary")?;
+    Ok(exe.with_file_name("arena"))
+}
+
+fn run_shadow(args: Vec<String>) -> color_eyre::Result<()> {
+    let arena = arena_binary()?;
+    println!("Shadow mode: {} {}", arena.display(), args.join(" "));
+    println!();
+
+    let status = std::process::Command::new(&arena)
+        .args(&args)
+        .status()
+        .wrap_err_with(|| format!("Failed to run {}, set ARENA_BIN", arena.display()))?;
+    if !status.success() {
+        return Err(eyre!("Simulation failed: {}", status));
+    }
+    Ok(())
+}
+
 // ============================================================================
 // Main
 // ============================================================================
@@ -519,9 +667,38 @@ async fn main() -> color_eyre::Result<()> {
 
     let cli = Cli::parse();
 
+    // Parse duration
+    let duration = parse_duration(&cli.duration).map_err(|e| eyre!("Invalid duration: {}", e))?;
+
+    let steady = cli
+        .steady
+        .as_deref()
+        .map(SteadyStreamPattern::from_str)
+        .transpose()
+        .map_err(|e| eyre!("Invalid steady pattern: {}", e))?;
+    let batch = cli
+        .batch
+        .as_deref()
+        .map(BatchPattern::from_str)
+        .transpose()
+        .map_err(|e| eyre!("Invalid batch pattern: {}", e))?;
+
+    if steady.is_none() && batch.is_none() {
+        return Err(eyre!(
+            "At least one load pattern (--steady or --batch) is required"
+        ));
+    }
+
+    if cli.shadow {
+        let (games, rate) = shadow_load(steady.as_ref(), batch.as_ref(), duration);
+        return run_shadow(shadow_args(&cli, games, rate, duration));
+    }
+
     // Parse and validate snake UUIDs
     let snakes: Vec<Uuid> = cli
         .snakes
+        .as_deref()
+        .unwrap_or_default()
         .split(',')
         .map(|s| s.trim())
         .filter(|s| !s.is_empty())
@@ -533,28 +710,13 @@ async fn main() -> color_eyre::Result<()> {
         return Err(eyre!("At least one snake UUID is required"));
     }
 
-    // Parse duration
-    let duration = parse_duration(&cli.duration).map_err(|e| eyre!("Invalid duration: {}", e))?;
-
     // Build load patterns
     let mut patterns: Vec<Box<dyn LoadPattern>> = Vec::new();
-
-    if let Some(ref steady) = cli.steady {
-        let pattern = SteadyStreamPattern::from_str(steady)
-            .map_err(|e| eyre!("Invalid steady pattern: {}", e))?;
-        patterns.push(Box::new(pattern));
+    if let Some(steady) = steady {
+        patterns.push(Box::new(steady));
     }
-
-    if let Some(ref batch) = cli.batch {
-        let pattern =
-            BatchPattern::from_str(batch).map_err(|e| eyre!("Invalid batch pattern: {}", e))?;
-        patterns.push(Box::new(pattern));
-    }
-
-    if patterns.is_empty() {
-        return Err(eyre!(
-            "At least one load pattern (--steady or --batch) is required"
-        ));
+    if let Some(batch) = batch {
+        patterns.push(Box::new(batch));
     }
 
     // Create shared state
@@ -564,7 +726,7 @@ async fn main() -> color_eyre::Result<()> {
 
     let config = LoadConfig {
         base_url: cli.url.clone(),
-        token: cli.token.clone(),
+        token: cli.token.clone().unwrap_or_default(),
         snakes,
         board: cli.board.clone(),
         game_type: cli.game_type.clone(),
@@ -731,4 +893,46 @@ mod tests {
         assert_eq!(format_duration(Duration::from_secs(3661)), "01:01:01");
         assert_eq!(format_duration(Duration::from_secs(90)), "00:01:30");
     }
+
+    #[test]
+    fn test_shadow_load() {
+        let steady = SteadyStreamPattern::from_str("2/s").unwrap();
+        let batch = BatchPattern::from_str("50,30s").unwrap();
+        let minute = Duration::from_secs(60);
+
+        assert_eq!(shadow_load(Some(&steady), None, minute), (120, 2.0));
+        assert_eq!(shadow_load(None, Some(&batch), minute), (100, 100.0 / 60.0));
+        assert_eq!(shadow_load(Some(&steady), Some(&batch), minute).0, 220);
+
+        // A batch shorter than its interval still sends its first batch
+        assert_eq!(
+            shadow_load(None, Some(&batch), Duration::from_secs(10)).0,
+            50
+        );
+    }
+
+    #[test]
+    fn test_shadow_args() {
+        // No snakes or token needed when shadowing
+        let cli = Cli::parse_from(["stress-test", "--shadow", "--steady", "2/s"]);
+
+        let args = shadow_args(&cli, 120, 2.0, Duration::from_secs(60));
+        assert_eq!(
+            args,
+            [
+                "admin",
+                "simulate",
+                "--games",
+                "120",
+                "--rate",
+                "2.000",
+                "--board",
+                "11x11",
+                "--type",
+                "standard",
+                "--timeout-secs",
+                "660"
+            ]
+        );
+    }
 }
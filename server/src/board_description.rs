@@ -0,0 +1,142 @@
+//! Text descriptions of board states, for screen readers
+//!
+//! The board viewer draws frames on a canvas inside an iframe, which screen
+//! readers can't see into. The game page reads these descriptions out as the
+//! replay moves from turn to turn instead, one per stored frame.
+
+use crate::engine_models::{EngineGameFrame, Point};
+
+/// A turn's board, in words: who's alive, where their heads are, how long and
+/// healthy they are, who's been eliminated and where the food and hazards are
+pub fn describe_frame(frame: &EngineGameFrame) -> String {
+    let alive: Vec<_> = frame
+        .snakes
+        .iter()
+        .filter(|snake| snake.death.is_none())
+        .collect();
+
+    let mut sentences = vec![format!(
+        "Turn {}: {} of {} snakes alive.",
+        frame.turn,
+        alive.len(),
+        frame.snakes.len()
+    )];
+
+    for snake in &alive {
+        let head = match snake.body.first() {
+            Some(head) => format!("head at {}", coords(head)),
+            None => "not on the board".to_string(),
+        };
+        sentences.push(format!(
+            "{}: {}, length {}, health {}.",
+            snake.name,
+            head,
+            snake.body.len(),
+            snake.health
+        ));
+    }
+
+    for snake in &frame.snakes {
+        let Some(death) = &snake.death else {
+            continue;
+        };
+        let by = death
+            .eliminated_by
+            .as_deref()
+            .filter(|id| !id.is_empty())
+            .and_then(|id| frame.snakes.iter().find(|other| other.id == id))
+            .map(|other| format!(" into {}", other.name))
+            .unwrap_or_default();
+        sentences.push(format!(
+            "{} was eliminated on turn {} by {}{}.",
+            snake.name,
+            death.turn,
+            death.cause.replace('-', " "),
+            by
+        ));
+    }
+
+    sentences.push(match frame.food.as_slice() {
+        [] => "No food.".to_string(),
+        food => format!("Food at {}.", list(food)),
+    });
+    if !frame.hazards.is_empty() {
+        sentences.push(format!(
+            "{} hazard {}.",
+            frame.hazards.len(),
+            if frame.hazards.len() == 1 {
+                "square"
+            } else {
+                "squares"
+            }
+        ));
+    }
+
+    sentences.join(" ")
+}
+
+fn coords(point: &Point) -> String {
+    format!("({}, {})", point.x, point.y)
+}
+
+/// "(1, 2)", "(1, 2) and (3, 4)", "(1, 2), (3, 4) and (5, 6)"
+fn list(points: &[Point]) -> String {
+    let coords: Vec<String> = points.iter().map(coords).collect();
+    match coords.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => coords.join(""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn frame(value: serde_json::Value) -> EngineGameFrame {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_describe_frame() {
+        let frame = frame(json!({
+            "Turn": 12,
+            "Snakes": [
+                {
+                    "ID": "a",
+                    "Name": "Alpha",
+                    "Health": 88,
+                    "Body": [{"X": 3, "Y": 4}, {"X": 3, "Y": 3}, {"X": 3, "Y": 2}],
+                    "Death": null,
+                },
+                {
+                    "ID": "b",
+                    "Name": "Beta",
+                    "Health": 0,
+                    "Body": [{"X": 0, "Y": 0}],
+                    "Death": {"Cause": "head-collision", "Turn": 10, "EliminatedBy": "a"},
+                },
+            ],
+            "Food": [{"X": 1, "Y": 1}, {"X": 5, "Y": 5}, {"X": 6, "Y": 0}],
+            "Hazards": [{"X": 0, "Y": 6}],
+        }));
+
+        assert_eq!(
+            describe_frame(&frame),
+            "Turn 12: 1 of 2 snakes alive. \
+             Alpha: head at (3, 4), length 3, health 88. \
+             Beta was eliminated on turn 10 by head collision into Alpha. \
+             Food at (1, 1), (5, 5) and (6, 0). \
+             1 hazard square."
+        );
+    }
+
+    #[test]
+    fn test_describe_empty_frame() {
+        let frame = frame(json!({"Turn": 0, "Snakes": [], "Food": [{"X": 2, "Y": 2}]}));
+        assert_eq!(
+            describe_frame(&frame),
+            "Turn 0: 0 of 0 snakes alive. Food at (2, 2)."
+        );
+    }
+}
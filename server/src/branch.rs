@@ -0,0 +1,505 @@
+//! "What if" branches of finished games
+//!
+//! A branch restarts a finished game from the board at one of its turns and
+//! plays it forward again, with some of the snakes' moves changed. Every other
+//! move is the one the snake actually made, and once a snake runs past its
+//! recorded moves it keeps going in the direction it last moved. No snake is
+//! called, so a branch is deterministic and quick enough to simulate while the
+//! request waits. The whole branch is stored in one transaction once it has
+//! played out, so one that fails part way leaves nothing behind.
+//!
+//! Branches are stored as regular games linked to their parent, so they're
+//! watched in the same board viewer. They're left out of ratings. Each user
+//! can ask for [`BRANCHES_PER_USER_PER_HOUR`] of them an hour.
+
+use battlesnake_game_types::types::Move;
+use color_eyre::eyre::{Context as _, eyre};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::certification::FrameHashChain;
+use crate::engine::frame::{DeathInfo, StoredFrame, game_to_frame};
+use crate::engine::move_provider::{MoveProvider, MoveProviders, ReplayMoves, request_moves};
+use crate::engine::wasm_ruleset::load_ruleset;
+use crate::engine::{
+    FoodTally, apply_turn_with_eliminations, create_game_from_board, engine_seed, final_placements,
+};
+use crate::models::game::{
+    CreateGameWithSnakes, GameBranchOrigin, GameSettings, GameStatus, count_branches_since,
+    get_game_by_id, get_game_settings, insert_game_with_snakes, record_final_turn,
+    set_game_branch_origin, set_game_result_digest, set_game_settings, set_game_tiebreak_applied,
+    update_game_status,
+};
+use crate::models::game_battlesnake::{
+    get_battlesnakes_by_game_id, get_snake_info_by_game_id, set_game_result_by_id, set_snake_info,
+};
+use crate::models::turn::{CreateSnakeTurn, CreateTurn, SnakeTurnWithNumber, insert_turns};
+use crate::snake_client::parse_direction;
+use crate::state::AppState;
+use crate::turn_archive::{load_snake_turns, load_turns};
+
+/// Most turns a branch is simulated past the turn it starts from
+pub const MAX_BRANCH_TURNS: i32 = 500;
+
+/// Most branches one user can ask for in an hour
+pub const BRANCHES_PER_USER_PER_HOUR: i64 = 30;
+
+/// A request to branch a finished game
+#[derive(Debug, Deserialize)]
+pub struct BranchRequest {
+    /// The turn whose board the branch starts from
+    pub turn: i32,
+    /// Moves to change, on or after `turn`
+    #[serde(default)]
+    pub moves: Vec<MoveOverride>,
+}
+
+/// One snake's move on one turn of a branch
+#[derive(Debug, Clone, Deserialize)]
+pub struct MoveOverride {
+    /// The snake's ID in the parent game's frames (its game_battlesnake ID)
+    pub snake_id: Uuid,
+    /// The turn the move is made on, taking the board to `turn + 1`
+    pub turn: i32,
+    /// "up", "down", "left" or "right"
+    pub direction: String,
+}
+
+#[derive(Debug)]
+pub enum BranchError {
+    /// The request can't be branched, with a message for the user
+    Invalid(String),
+    /// The user has asked for too many branches lately, with a message for them
+    RateLimited(String),
+    Internal(color_eyre::Report),
+}
+
+impl From<color_eyre::Report> for BranchError {
+    fn from(e: color_eyre::Report) -> Self {
+        BranchError::Internal(e)
+    }
+}
+
+/// Each snake's moves from the recorded ones with `overrides` applied,
+/// indexed by the turn the move was made on
+///
+/// Only moves from `from_turn` on are kept, since earlier turns are already
+/// on the board the branch starts from.
+fn branch_moves(
+    recorded: &[SnakeTurnWithNumber],
+    overrides: &[MoveOverride],
+    snake_ids: &[Uuid],
+    from_turn: i32,
+) -> Result<HashMap<Uuid, Vec<Option<Move>>>, BranchError> {
+    let mut moves: HashMap<Uuid, Vec<Option<Move>>> =
+        snake_ids.iter().map(|&id| (id, Vec::new())).collect();
+    let mut set_move = |snake_id: Uuid, turn: i32, direction: Move| {
+        if let (Some(snake_moves), Ok(turn)) = (moves.get_mut(&snake_id), usize::try_from(turn)) {
+            if snake_moves.len() <= turn {
+                snake_moves.resize(turn + 1, None);
+            }
+            snake_moves[turn] = Some(direction);
+        }
+    };
+
+    // Moves stored with turn N were made on turn N - 1
+    for snake_turn in recorded {
+        let made_on = snake_turn.turn_number - 1;
+        if made_on < from_turn {
+            continue;
+        }
+        if let Some(direction) = parse_direction(&snake_turn.direction) {
+            set_move(snake_turn.game_battlesnake_id, made_on, direction);
+        }
+    }
+
+    for o in overrides {
+        if !snake_ids.contains(&o.snake_id) {
+            return Err(BranchError::Invalid(format!(
+                "Snake {} isn't in this game",
+                o.snake_id
+            )));
+        }
+        if o.turn < from_turn {
+            return Err(BranchError::Invalid(format!(
+                "Move on turn {} is before the branch starts at turn {}",
+                o.turn, from_turn
+            )));
+        }
+        if o.turn >= from_turn + MAX_BRANCH_TURNS {
+            return Err(BranchError::Invalid(format!(
+                "Move on turn {} is past the branch's last turn",
+                o.turn
+            )));
+        }
+        let direction = parse_direction(&o.direction)
+            .ok_or_else(|| BranchError::Invalid(format!("Invalid direction {:?}", o.direction)))?;
+        set_move(o.snake_id, o.turn, direction);
+    }
+
+    Ok(moves)
+}
+
+/// Branch a finished game from a turn for a user, returning the branch's game ID
+///
+/// The branch is simulated to the end before this returns.
+pub async fn create_branch(
+    app_state: &AppState,
+    user_id: Uuid,
+    parent_id: Uuid,
+    request: &BranchRequest,
+) -> Result<Uuid, BranchError> {
+    let pool = &app_state.db;
+    let branch_turn = request.turn;
+
+    let since = app_state.clock.now() - chrono::Duration::hours(1);
+    if count_branches_since(pool, user_id, since).await? >= BRANCHES_PER_USER_PER_HOUR {
+        return Err(BranchError::RateLimited(format!(
+            "You can branch {} games an hour; try again later",
+            BRANCHES_PER_USER_PER_HOUR
+        )));
+    }
+
+    let parent = get_game_by_id(pool, parent_id)
+        .await?
+        .ok_or_else(|| BranchError::Invalid(format!("Game {} not found", parent_id)))?;
+    if parent.status != GameStatus::Finished {
+        return Err(BranchError::Invalid(
+            "Only finished games can be branched".to_string(),
+        ));
+    }
+
+    let frame_data = load_turns(app_state, parent_id)
+        .await?
+        .into_iter()
+        .find(|t| t.turn_number == branch_turn)
+        .and_then(|t| t.frame_data)
+        .ok_or_else(|| {
+            BranchError::Invalid(format!("Game {} has no turn {}", parent_id, branch_turn))
+        })?;
+    let frame: StoredFrame = serde_json::from_value(frame_data).wrap_err_with(|| {
+        format!(
+            "Failed to parse frame {} of game {}",
+            branch_turn, parent_id
+        )
+    })?;
+
+    let parent_snakes = get_battlesnakes_by_game_id(pool, parent_id).await?;
+    let parent_ids: Vec<Uuid> = parent_snakes
+        .iter()
+        .map(|gb| gb.game_battlesnake_id)
+        .collect();
+    let recorded = load_snake_turns(app_state, parent_id).await?;
+    let moves = branch_moves(&recorded, &request.moves, &parent_ids, branch_turn)?;
+
+    // The branch gets the same snakes and settings as its parent, and its seed,
+    // so food spawns where it would have in the parent
+    let settings = get_game_settings(pool, parent_id).await?;
+    let seed = engine_seed(&parent_id.to_string(), settings.seed);
+    let settings = GameSettings {
+        seed: Some(seed as i64),
+        ..settings
+    };
+    let ruleset = match settings.ruleset_id {
+        Some(ruleset_id) => Some(load_ruleset(pool, ruleset_id).await?),
+        None => None,
+    };
+    let mut tx = pool.begin().await.wrap_err("Failed to start transaction")?;
+    let branch = insert_game_with_snakes(
+        &mut tx,
+        CreateGameWithSnakes {
+            board_size: parent.board_size,
+            game_type: parent.game_type,
+            battlesnake_ids: parent_snakes.iter().map(|gb| gb.battlesnake_id).collect(),
+        },
+    )
+    .await
+    .wrap_err("Failed to create branch game")?;
+    let branch_id = branch.game_id;
+    set_game_settings(&mut *tx, branch_id, settings).await?;
+    set_game_branch_origin(
+        &mut *tx,
+        branch_id,
+        GameBranchOrigin {
+            parent_game_id: parent_id,
+            branched_from_turn: branch_turn,
+        },
+        user_id,
+    )
+    .await?;
+
+    // Match each of the parent's snake instances with one of the branch's
+    let mut unmatched = get_battlesnakes_by_game_id(&mut *tx, branch_id).await?;
+    let mut branch_ids: HashMap<String, String> = HashMap::new();
+    for gb in &parent_snakes {
+        let index = unmatched
+            .iter()
+            .position(|b| b.battlesnake_id == gb.battlesnake_id)
+            .ok_or_else(|| {
+                eyre!(
+                    "Branch {} is missing snake {}",
+                    branch_id,
+                    gb.battlesnake_id
+                )
+            })?;
+        branch_ids.insert(
+            gb.game_battlesnake_id.to_string(),
+            unmatched.remove(index).game_battlesnake_id.to_string(),
+        );
+    }
+    let to_branch_id = |id: &str| branch_ids.get(id).cloned().unwrap_or_default();
+
+    // Snakes look the way they did in the parent
+    let mut snake_info = HashMap::new();
+    for (parent_snake_id, info) in get_snake_info_by_game_id(pool, parent_id).await? {
+        let branch_snake_id = to_branch_id(&parent_snake_id.to_string());
+        if let Ok(game_battlesnake_id) = Uuid::parse_str(&branch_snake_id) {
+            set_snake_info(&mut *tx, game_battlesnake_id, &info).await?;
+            snake_info.insert(branch_snake_id, info);
+        }
+    }
+
+    let (width, height) = parent.board_size.dimensions();
+    let mut board = frame.to_board(width, height);
+    for snake in &mut board.snakes {
+        snake.id = to_branch_id(&snake.id);
+    }
+    let mut game = create_game_from_board(branch_id, parent.game_type, board);
+    game.turn = branch_turn;
+    game.game.timeout = settings.move_timeout_ms() as _;
+
+    let mut death_info: Vec<DeathInfo> = frame
+        .death_info()
+        .into_iter()
+        .map(|d| DeathInfo {
+            snake_id: to_branch_id(&d.snake_id),
+            eliminated_by: if d.eliminated_by.is_empty() {
+                d.eliminated_by
+            } else {
+                to_branch_id(&d.eliminated_by)
+            },
+            ..d
+        })
+        .collect();
+    // Snakes that were dropped from the parent's frame after dying aren't on
+    // the board at all, so anyone not alive on it is already out
+    let alive: HashSet<&str> = game
+        .board
+        .snakes
+        .iter()
+        .filter(|s| s.health > 0)
+        .map(|s| s.id.as_str())
+        .collect();
+    let already_dead: HashSet<String> = parent_snakes
+        .iter()
+        .map(|gb| to_branch_id(&gb.game_battlesnake_id.to_string()))
+        .filter(|id| !alive.contains(id.as_str()))
+        .collect();
+
+    let providers: MoveProviders = moves
+        .into_iter()
+        .map(|(id, snake_moves)| {
+            let provider: Box<dyn MoveProvider> = Box::new(ReplayMoves::new(snake_moves));
+            (to_branch_id(&id.to_string()), provider)
+        })
+        .collect();
+
+    // Snakes that run out of moves keep going the way they last moved
+    let mut last_moves: HashMap<String, Move> = recorded
+        .iter()
+        .filter(|st| st.turn_number == branch_turn)
+        .filter_map(|st| {
+            parse_direction(&st.direction)
+                .map(|m| (to_branch_id(&st.game_battlesnake_id.to_string()), m))
+        })
+        .collect();
+
+    let frame = game_to_frame(&game, &death_info, &[])
+        .with_snake_info(&snake_info)
+        .with_dead_snakes(settings.dead_snakes);
+    let frame_json =
+        serde_json::to_value(frame).wrap_err("Failed to serialize branch's first frame")?;
+    let mut hash_chain = FrameHashChain::new(branch_id);
+    hash_chain.push(&frame_json);
+    let mut turns = vec![CreateTurn {
+        turn_number: game.turn,
+        frame_data: frame_json,
+        snake_turns: Vec::new(),
+    }];
+
+    let max_turns = settings.turn_limit().min(branch_turn + MAX_BRANCH_TURNS);
+    let mut eliminated: Vec<(String, i32)> = Vec::new();
+    // Only food eaten in the branch counts towards the most-food tiebreak
+    let mut food = FoodTally::default();
+    while game.board.snakes.iter().filter(|s| s.health > 0).count() > 1 && game.turn < max_turns {
+        let results = request_moves(
+            &providers,
+            &game,
+            &last_moves,
+            settings.validation_mode,
+            &HashMap::new(),
+        )
+        .await;
+        let turn_moves: Vec<(String, Move)> = results
+            .iter()
+            .map(|r| (r.snake_id.clone(), r.direction))
+            .collect();
+        for (snake_id, direction) in &turn_moves {
+            last_moves.insert(snake_id.clone(), *direction);
+        }
+
+        let food_before = game.board.food.clone();
+        let (next_game, mut eliminations) = apply_turn_with_eliminations(game, &turn_moves, seed);
+        game = next_game;
+        game.turn += 1;
+        food.record_turn(&food_before, &game);
+        if let Some(ruleset) = &ruleset {
+            match ruleset.apply(&mut game, seed) {
+                Ok(ruleset_eliminations) => eliminations.extend(ruleset_eliminations),
+                Err(e) => tracing::warn!(
+                    game_id = %branch_id,
+                    turn = game.turn,
+                    error = %e,
+                    "Ruleset left a branch turn unchanged"
+                ),
+            }
+        }
+
+        for elimination in eliminations {
+            death_info.push(elimination.death_info(game.turn));
+            eliminated.push((elimination.snake_id, game.turn));
+        }
+
+        let frame = game_to_frame(&game, &death_info, &results)
+            .with_snake_info(&snake_info)
+            .with_dead_snakes(settings.dead_snakes);
+        let frame_json = serde_json::to_value(frame)
+            .wrap_err_with(|| format!("Failed to serialize branch frame {}", game.turn))?;
+        hash_chain.push(&frame_json);
+        turns.push(CreateTurn {
+            turn_number: game.turn,
+            frame_data: frame_json,
+            snake_turns: results
+                .iter()
+                .filter_map(|result| {
+                    Uuid::parse_str(&result.snake_id)
+                        .ok()
+                        .map(|game_battlesnake_id| CreateSnakeTurn {
+                            game_battlesnake_id,
+                            direction: result.direction.to_string(),
+                            latency_ms: None,
+                            compensated_latency_ms: None,
+                            timed_out: false,
+                            parse_error: None,
+                        })
+                })
+                .collect(),
+        });
+    }
+    insert_turns(&mut tx, branch_id, &turns).await?;
+
+    // Survivors first, then the last eliminated, then snakes that were already
+    // out when the branch started, in the order they placed in the parent
+    let mut placements = final_placements(&game, &eliminated, &food, settings.turn_limit_tiebreak);
+    let placed = placements.len();
+    placements.extend(
+        parent_snakes
+            .iter()
+            .map(|gb| to_branch_id(&gb.game_battlesnake_id.to_string()))
+            .filter(|id| already_dead.contains(id))
+            .enumerate()
+            .map(|(i, id)| (id, (placed + i + 1) as i32)),
+    );
+    if game.board.snakes.iter().filter(|s| s.health > 0).count() > 1 {
+        set_game_tiebreak_applied(&mut *tx, branch_id, settings.turn_limit_tiebreak).await?;
+    }
+
+    for (snake_id, placement) in placements {
+        let game_battlesnake_id: Uuid = snake_id
+            .parse()
+            .wrap_err_with(|| format!("Invalid game_battlesnake ID: {}", snake_id))?;
+        set_game_result_by_id(&mut *tx, game_battlesnake_id, placement).await?;
+    }
+
+    set_game_result_digest(&mut *tx, branch_id, &hash_chain.digest()).await?;
+    record_final_turn(&mut *tx, branch_id).await?;
+    update_game_status(&mut *tx, branch_id, GameStatus::Finished).await?;
+    tx.commit().await.wrap_err("Failed to commit branch")?;
+
+    tracing::info!(
+        game_id = %branch_id,
+        parent_game_id = %parent_id,
+        branched_from_turn = branch_turn,
+        final_turn = game.turn,
+        "Branch simulated"
+    );
+
+    Ok(branch_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded(turn_number: i32, snake: Uuid, direction: &str) -> SnakeTurnWithNumber {
+        SnakeTurnWithNumber {
+            turn_number,
+            game_battlesnake_id: snake,
+            direction: direction.to_string(),
+            latency_ms: None,
+            compensated_latency_ms: None,
+            timed_out: false,
+            parse_error: None,
+        }
+    }
+
+    fn change(snake_id: Uuid, turn: i32, direction: &str) -> MoveOverride {
+        MoveOverride {
+            snake_id,
+            turn,
+            direction: direction.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_branch_moves_replace_recorded_moves() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let history = vec![
+            recorded(1, a, "up"),
+            recorded(1, b, "down"),
+            recorded(2, a, "up"),
+            recorded(2, b, "down"),
+            recorded(3, a, "up"),
+        ];
+
+        let moves = branch_moves(&history, &[change(a, 1, "left")], &[a, b], 1).unwrap();
+
+        // Turn 0's moves are already on the board the branch starts from
+        assert_eq!(moves[&a], vec![None, Some(Move::Left), Some(Move::Up)]);
+        assert_eq!(moves[&b], vec![None, Some(Move::Down)]);
+    }
+
+    #[test]
+    fn test_branch_moves_can_extend_past_recorded_moves() {
+        let a = Uuid::new_v4();
+        let moves = branch_moves(&[], &[change(a, 3, "right")], &[a], 2).unwrap();
+        assert_eq!(moves[&a], vec![None, None, None, Some(Move::Right)]);
+    }
+
+    #[test]
+    fn test_branch_moves_rejects_invalid_overrides() {
+        let a = Uuid::new_v4();
+        let invalid = |o: MoveOverride| {
+            matches!(
+                branch_moves(&[], &[o], &[a], 5),
+                Err(BranchError::Invalid(_))
+            )
+        };
+
+        assert!(invalid(change(Uuid::new_v4(), 5, "up")));
+        assert!(invalid(change(a, 4, "up")));
+        assert!(invalid(change(a, 5 + MAX_BRANCH_TURNS, "up")));
+        assert!(invalid(change(a, 5, "sideways")));
+    }
+}
@@ -0,0 +1,202 @@
+//! Tamper-evident certification of game results
+//!
+//! As a game runs, each frame is folded into a SHA-256 hash chain: every link
+//! hashes the previous link together with the frame's canonical JSON (object
+//! keys sorted, no whitespace). The chain is seeded with the game ID, and its
+//! final digest is stored with the result. Recomputing the chain from the stored
+//! frames and comparing digests shows whether a replay has been edited since
+//! the game finished. The CLI recomputes it client-side from the game details
+//! API (`arena games verify <id>`), so it doesn't have to trust the server's
+//! own check.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Versions the chain construction, so it can change without old digests verifying by accident
+const CHAIN_DOMAIN: &[u8] = b"arena-frame-chain-v1";
+
+/// Running hash over a game's frames, in turn order
+#[derive(Debug, Clone)]
+pub struct FrameHashChain {
+    head: [u8; 32],
+}
+
+impl FrameHashChain {
+    pub fn new(game_id: Uuid) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(CHAIN_DOMAIN);
+        hasher.update(game_id.as_bytes());
+        Self {
+            head: hasher.finalize().into(),
+        }
+    }
+
+    /// Extend the chain with the next frame
+    pub fn push(&mut self, frame: &Value) {
+        let mut hasher = Sha256::new();
+        hasher.update(self.head);
+        hasher.update(canonical_json(frame).as_bytes());
+        self.head = hasher.finalize().into();
+    }
+
+    /// Hex digest of every frame pushed so far
+    pub fn digest(&self) -> String {
+        hex::encode(self.head)
+    }
+}
+
+/// Digest of a complete sequence of frames
+pub fn chain_digest<'a>(game_id: Uuid, frames: impl IntoIterator<Item = &'a Value>) -> String {
+    let mut chain = FrameHashChain::new(game_id);
+    for frame in frames {
+        chain.push(frame);
+    }
+    chain.digest()
+}
+
+/// Serialize JSON with object keys sorted, so the digest doesn't depend on
+/// how the frame was stored (Postgres `jsonb` reorders keys)
+pub fn canonical_json(value: &Value) -> String {
+    let mut out = String::new();
+    write_canonical(value, &mut out);
+    out
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+/// Outcome of checking a game's frames against its certified digest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationStatus {
+    /// The frames hash to the certified digest
+    Verified,
+    /// The frames have changed since the result was certified
+    Mismatch,
+    /// No digest was recorded, e.g. the game hasn't finished
+    Uncertified,
+}
+
+impl VerificationStatus {
+    pub fn check(certified_digest: Option<&str>, computed_digest: &str) -> Self {
+        match certified_digest {
+            None => VerificationStatus::Uncertified,
+            Some(digest) if digest.eq_ignore_ascii_case(computed_digest) => {
+                VerificationStatus::Verified
+            }
+            Some(_) => VerificationStatus::Mismatch,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn frames() -> Vec<Value> {
+        vec![
+            json!({"Turn": 0, "Snakes": [], "Food": [{"X": 1, "Y": 2}], "Hazards": []}),
+            json!({"Turn": 1, "Snakes": [], "Food": [], "Hazards": []}),
+        ]
+    }
+
+    #[test]
+    fn test_canonical_json_sorts_keys() {
+        let value: Value =
+            serde_json::from_str(r#"{"b": 1, "a": {"d": [true, null], "c": "x\"y"}}"#).unwrap();
+        assert_eq!(
+            canonical_json(&value),
+            r#"{"a":{"c":"x\"y","d":[true,null]},"b":1}"#
+        );
+    }
+
+    #[test]
+    fn test_digest_is_stable_across_key_order() {
+        let game_id = Uuid::new_v4();
+        let reordered: Vec<Value> = frames()
+            .iter()
+            .map(|f| serde_json::from_str(&canonical_json(f)).unwrap())
+            .collect();
+        assert_eq!(
+            chain_digest(game_id, &frames()),
+            chain_digest(game_id, &reordered)
+        );
+    }
+
+    #[test]
+    fn test_any_change_breaks_the_chain() {
+        let game_id = Uuid::new_v4();
+        let original = chain_digest(game_id, &frames());
+
+        let mut edited = frames();
+        edited[0]["Food"][0]["X"] = json!(3);
+        assert_ne!(chain_digest(game_id, &edited), original);
+
+        let mut truncated = frames();
+        truncated.pop();
+        assert_ne!(chain_digest(game_id, &truncated), original);
+
+        let mut swapped = frames();
+        swapped.swap(0, 1);
+        assert_ne!(chain_digest(game_id, &swapped), original);
+
+        // Frames can't be replayed under another game's ID
+        assert_ne!(chain_digest(Uuid::new_v4(), &frames()), original);
+    }
+
+    #[test]
+    fn test_incremental_chain_matches_digest() {
+        let game_id = Uuid::new_v4();
+        let mut chain = FrameHashChain::new(game_id);
+        for frame in &frames() {
+            chain.push(frame);
+        }
+        assert_eq!(chain.digest(), chain_digest(game_id, &frames()));
+    }
+
+    #[test]
+    fn test_verification_status() {
+        assert_eq!(
+            VerificationStatus::check(Some("ABC"), "abc"),
+            VerificationStatus::Verified
+        );
+        assert_eq!(
+            VerificationStatus::check(Some("abc"), "abd"),
+            VerificationStatus::Mismatch
+        );
+        assert_eq!(
+            VerificationStatus::check(None, "abc"),
+            VerificationStatus::Uncertified
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Regression checks: gate a snake's deploys on arena matches
+//!
+//! A check plays a batch of games between one snake and a set of opponents
+//! and passes when the snake wins at least the required share of them. The
+//! result is decided as soon as it can't change, so CI doesn't wait for the
+//! games that no longer matter; those still finish and show up in the
+//! snake's history like any other game.
+//!
+//! A check run for a commit of a snake linked to a GitHub repository also
+//! shows up on that commit as a status, posted through the GitHub App. A
+//! cron job keeps the status up to date until the check is decided, for up
+//! to a day.
+
+use serde::Serialize;
+
+use crate::github::app::CommitState;
+use crate::models::check::{
+    Check, CheckGame, get_check_games, get_checks_pending_on_github, set_check_github_state,
+};
+use crate::models::snake_github_repo::get_snake_github_repo;
+use crate::state::AppState;
+
+/// Where a check stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    /// Not enough games have finished to decide
+    Running,
+    /// The snake has won enough games
+    Passed,
+    /// The snake can't win enough of the remaining games
+    Failed,
+}
+
+impl CheckStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckStatus::Running => "running",
+            CheckStatus::Passed => "passed",
+            CheckStatus::Failed => "failed",
+        }
+    }
+
+    /// The commit status a check in this state shows on GitHub
+    pub fn commit_state(&self) -> CommitState {
+        match self {
+            CheckStatus::Running => CommitState::Pending,
+            CheckStatus::Passed => CommitState::Success,
+            CheckStatus::Failed => CommitState::Failure,
+        }
+    }
+}
+
+/// A check's games tallied up
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CheckProgress {
+    pub status: CheckStatus,
+    pub games_finished: u32,
+    pub wins: u32,
+    /// Wins needed to pass
+    pub wins_needed: u32,
+}
+
+/// Wins out of `games` needed for a win rate of at least `required_win_rate` percent
+pub fn wins_needed(games: u32, required_win_rate: f64) -> u32 {
+    // Allow for float error, so 70% of 10 games is 7 wins and not 8
+    let needed = (f64::from(games) * required_win_rate / 100.0 - 1e-9).ceil();
+    needed.clamp(0.0, f64::from(games)) as u32
+}
+
+/// Tally a check's games and decide it if the result can no longer change
+pub fn check_progress(check: &Check, games: &[CheckGame]) -> CheckProgress {
+    let total = u32::try_from(check.games_requested).unwrap_or(0);
+    let games_finished = games.iter().filter(|g| g.is_finished()).count() as u32;
+    let wins = games
+        .iter()
+        .filter(|g| g.is_finished() && g.placement == Some(1))
+        .count() as u32;
+    let needed = wins_needed(total, check.required_win_rate);
+    let remaining = total.saturating_sub(games_finished);
+
+    let status = if wins >= needed {
+        CheckStatus::Passed
+    } else if wins + remaining < needed {
+        CheckStatus::Failed
+    } else {
+        CheckStatus::Running
+    };
+
+    CheckProgress {
+        status,
+        games_finished,
+        wins,
+        wins_needed: needed,
+    }
+}
+
+/// Post a check's result to its commit on GitHub, if it changed since it was
+/// last posted
+///
+/// Does nothing unless the GitHub App is configured, the check was run for a
+/// commit and its snake is linked to a repository.
+pub async fn report_check_to_github(app_state: &AppState, check: &Check) -> cja::Result<()> {
+    let (Some(app), Some(sha)) = (&app_state.github_app, &check.commit_sha) else {
+        return Ok(());
+    };
+    let Some(repo) = get_snake_github_repo(&app_state.db, check.battlesnake_id).await? else {
+        return Ok(());
+    };
+
+    let games = get_check_games(&app_state.db, check).await?;
+    let progress = check_progress(check, &games);
+    let state = progress.status.commit_state();
+    if check.github_state.as_deref() == Some(state.as_str()) {
+        return Ok(());
+    }
+
+    let description = format!(
+        "{} of {} games won, {} needed",
+        progress.wins, check.games_requested, progress.wins_needed
+    );
+    let target_url = app_state
+        .public_url
+        .configured_base_url()
+        .map(|base_url| format!("{}/battlesnakes/{}/profile", base_url, check.battlesnake_id));
+    let token = app
+        .installation_token(repo.installation_id, app_state.clock.now())
+        .await?;
+    app.post_commit_status(
+        &token,
+        &repo.repo,
+        sha,
+        state,
+        &description,
+        target_url.as_deref(),
+    )
+    .await?;
+
+    set_check_github_state(&app_state.db, check.check_id, state.as_str()).await
+}
+
+/// Post the results of recent checks that are still pending on GitHub
+pub async fn report_pending_checks_to_github(app_state: &AppState) -> cja::Result<()> {
+    if app_state.github_app.is_none() {
+        return Ok(());
+    }
+
+    let checks = get_checks_pending_on_github(&app_state.db, app_state.clock.now()).await?;
+    for check in &checks {
+        // One repo revoking the app shouldn't hold up everyone else's
+        if let Err(e) = report_check_to_github(app_state, check).await {
+            tracing::warn!(check_id = %check.check_id, "Failed to post check to GitHub: {:?}", e);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn check(games_requested: i32, required_win_rate: f64) -> Check {
+        Check {
+            check_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            battlesnake_id: Uuid::new_v4(),
+            games_requested,
+            required_win_rate,
+            commit_sha: None,
+            github_state: None,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    fn game(status: &str, placement: Option<i32>) -> CheckGame {
+        CheckGame {
+            game_id: Uuid::new_v4(),
+            status: status.to_string(),
+            placement,
+        }
+    }
+
+    #[test]
+    fn test_wins_needed() {
+        assert_eq!(wins_needed(10, 70.0), 7);
+        assert_eq!(wins_needed(3, 70.0), 3);
+        assert_eq!(wins_needed(3, 50.0), 2);
+        assert_eq!(wins_needed(10, 0.0), 0);
+        assert_eq!(wins_needed(10, 100.0), 10);
+    }
+
+    #[test]
+    fn test_check_progress_decides_early() {
+        let check = check(4, 50.0);
+
+        let games = [
+            game("finished", Some(1)),
+            game("running", None),
+            game("waiting", None),
+            game("waiting", None),
+        ];
+        let progress = check_progress(&check, &games);
+        assert_eq!(progress.status, CheckStatus::Running);
+        assert_eq!((progress.games_finished, progress.wins), (1, 1));
+        assert_eq!(progress.wins_needed, 2);
+
+        // Two wins out of four is already enough
+        let games = [
+            game("finished", Some(1)),
+            game("finished", Some(1)),
+            game("running", None),
+            game("waiting", None),
+        ];
+        assert_eq!(check_progress(&check, &games).status, CheckStatus::Passed);
+
+        // After three losses, one game left can't make two wins
+        let games = [
+            game("finished", Some(2)),
+            game("finished", Some(2)),
+            game("finished", Some(3)),
+            game("running", None),
+        ];
+        assert_eq!(check_progress(&check, &games).status, CheckStatus::Failed);
+    }
+
+    #[test]
+    fn test_commit_state() {
+        assert_eq!(CheckStatus::Running.commit_state(), CommitState::Pending);
+        assert_eq!(CheckStatus::Passed.commit_state(), CommitState::Success);
+        assert_eq!(CheckStatus::Failed.commit_state(), CommitState::Failure);
+    }
+
+    #[test]
+    fn test_check_progress_ignores_unfinished_placements() {
+        let check = check(2, 50.0);
+        let games = [game("running", Some(1)), game("waiting", None)];
+        assert_eq!(check_progress(&check, &games).wins, 0);
+    }
+}
@@ -67,8 +67,11 @@ impl CliConfig {
         Ok(())
     }
 
-    /// Get the API base URL
+    /// Get the API base URL, which may include a path prefix (e.g. `https://example.com/arena`)
     pub fn api_url(&self) -> &str {
-        self.api_url.as_deref().unwrap_or(DEFAULT_API_URL)
+        self.api_url
+            .as_deref()
+            .unwrap_or(DEFAULT_API_URL)
+            .trim_end_matches('/')
     }
 }
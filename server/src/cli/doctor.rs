@@ -0,0 +1,459 @@
+//! Verdicts for `arena doctor`, which checks why the CLI isn't working
+//!
+//! The command makes the requests: it loads the config, asks the server for
+//! its capabilities, tries the stored token, and optionally plays a turn
+//! against a snake running locally. This module turns what came back into a
+//! pass, a warning or a failure, each failure with a fix to try. Keeping the
+//! verdicts apart from the requests lets them be tested without a server.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use url::Url;
+
+/// The API version this CLI is written against
+pub const CLI_API_VERSION: u32 = 1;
+
+/// Clock skew past this many seconds gets a warning
+pub const CLOCK_SKEW_WARN_SECS: i64 = 30;
+
+/// Clock skew past this many seconds fails the check
+pub const CLOCK_SKEW_FAIL_SECS: i64 = 300;
+
+/// The move timeout games use by default, which a snake's answer has to fit inside
+pub const MOVE_TIMEOUT_MS: u128 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DoctorStatus {
+    Pass,
+    Warn,
+    Fail,
+    /// Not run, because an earlier check failed or it wasn't asked for
+    Skipped,
+}
+
+impl DoctorStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DoctorStatus::Pass => "pass",
+            DoctorStatus::Warn => "warn",
+            DoctorStatus::Fail => "fail",
+            DoctorStatus::Skipped => "skipped",
+        }
+    }
+}
+
+/// One check's verdict
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DoctorCheck {
+    pub name: &'static str,
+    pub status: DoctorStatus,
+    pub detail: String,
+    /// What to try, for warnings and failures
+    pub fix: Option<String>,
+}
+
+impl DoctorCheck {
+    pub fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: DoctorStatus::Pass,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    pub fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: DoctorStatus::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    pub fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: DoctorStatus::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    pub fn skipped(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: DoctorStatus::Skipped,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+}
+
+/// Whether no check failed; warnings don't count
+pub fn all_passed(checks: &[DoctorCheck]) -> bool {
+    checks.iter().all(|c| c.status != DoctorStatus::Fail)
+}
+
+/// Check the configured API URL is one the CLI can send requests to
+pub fn check_api_url(api_url: &str) -> DoctorCheck {
+    const NAME: &str = "API URL";
+    const FIX: &str = "Set api_url in the config file to the arena's address, \
+                       like https://arena.battlesnake.com";
+
+    let url = match Url::parse(api_url) {
+        Ok(url) => url,
+        Err(e) => return DoctorCheck::fail(NAME, format!("{:?} isn't a URL: {}", api_url, e), FIX),
+    };
+    match url.scheme() {
+        "https" => DoctorCheck::pass(NAME, api_url),
+        "http" if is_local(&url) => DoctorCheck::pass(NAME, api_url),
+        "http" => DoctorCheck::warn(
+            NAME,
+            format!("{} isn't HTTPS, so your token is sent unencrypted", api_url),
+            "Use the https:// address for api_url in the config file",
+        ),
+        other => DoctorCheck::fail(
+            NAME,
+            format!("{} uses {}, not http or https", api_url, other),
+            FIX,
+        ),
+    }
+}
+
+fn is_local(url: &Url) -> bool {
+    matches!(
+        url.host_str(),
+        Some("localhost" | "127.0.0.1" | "[::1]" | "::1")
+    )
+}
+
+/// Check the server still serves the API version this CLI speaks, from
+/// its `/api/v1/capabilities` response
+pub fn check_api_version(capabilities: &serde_json::Value) -> DoctorCheck {
+    const NAME: &str = "API version";
+    let server = capabilities["server_version"].as_str().unwrap_or("unknown");
+    let versions: Vec<u64> = capabilities["api_versions"]
+        .as_array()
+        .map(|v| v.iter().filter_map(|v| v.as_u64()).collect())
+        .or_else(|| capabilities["api_version"].as_u64().map(|v| vec![v]))
+        .unwrap_or_default();
+
+    if versions.contains(&u64::from(CLI_API_VERSION)) {
+        return DoctorCheck::pass(
+            NAME,
+            format!(
+                "Server {} serves version {} of the API, which this CLI uses",
+                server, CLI_API_VERSION
+            ),
+        );
+    }
+
+    let detail = format!(
+        "Server {} serves API versions {:?}, but this CLI uses version {}",
+        server, versions, CLI_API_VERSION
+    );
+    if versions.iter().all(|v| *v > u64::from(CLI_API_VERSION)) {
+        DoctorCheck::fail(NAME, detail, "Upgrade the arena CLI")
+    } else {
+        DoctorCheck::fail(
+            NAME,
+            detail,
+            "The server is older than this CLI; install the CLI release that matches it, \
+             or ask the arena's operator to upgrade",
+        )
+    }
+}
+
+/// Compare the local clock with the server's `Date` header
+pub fn check_clock_skew(server_date: &str, local: DateTime<Utc>) -> DoctorCheck {
+    const NAME: &str = "Clock";
+    const FIX: &str = "Sync your clock with a time server, e.g. `sudo timedatectl set-ntp true` \
+                       on Linux or Settings > Date & Time > Set automatically";
+
+    let server = match DateTime::parse_from_rfc2822(server_date) {
+        Ok(server) => server.with_timezone(&Utc),
+        Err(_) => {
+            return DoctorCheck::skipped(
+                NAME,
+                format!(
+                    "The server's Date header {:?} couldn't be read",
+                    server_date
+                ),
+            );
+        }
+    };
+
+    let skew = (local - server).num_seconds();
+    let direction = if skew > 0 { "ahead of" } else { "behind" };
+    let detail = format!("Your clock is {}s {} the server's", skew.abs(), direction);
+    // The header only has whole seconds, and the response took time to arrive
+    if skew.abs() > CLOCK_SKEW_FAIL_SECS {
+        DoctorCheck::fail(NAME, detail, FIX)
+    } else if skew.abs() > CLOCK_SKEW_WARN_SECS {
+        DoctorCheck::warn(NAME, detail, FIX)
+    } else {
+        DoctorCheck::pass(NAME, "Within a few seconds of the server's")
+    }
+}
+
+/// Judge the stored token from the status of a request made with it, None
+/// when there's no token
+pub fn check_token(status: Option<u16>) -> DoctorCheck {
+    const NAME: &str = "Token";
+    match status {
+        None => DoctorCheck::fail(NAME, "Not logged in", "Run `arena auth login`"),
+        Some(200..=299) => DoctorCheck::pass(NAME, "Accepted by the server"),
+        Some(401) => DoctorCheck::fail(
+            NAME,
+            "The server says the token is invalid or revoked",
+            "Run `arena auth login` to store a new token",
+        ),
+        Some(status) => DoctorCheck::fail(
+            NAME,
+            format!("The server answered {} when checking the token", status),
+            "Try again later; if it persists, check the server's status",
+        ),
+    }
+}
+
+/// Judge what the token can reach, from the status of one request to each
+/// area the CLI uses. Tokens act with all their user's access, so anything
+/// but a success means the account, not the token, is missing something.
+pub fn check_token_access(results: &[(&str, u16)]) -> DoctorCheck {
+    const NAME: &str = "Token access";
+    let denied: Vec<String> = results
+        .iter()
+        .filter(|(_, status)| !(200..300).contains(status))
+        .map(|(area, status)| format!("{} ({})", area, status))
+        .collect();
+    let areas: Vec<&str> = results.iter().map(|(area, _)| *area).collect();
+
+    if denied.is_empty() {
+        DoctorCheck::pass(NAME, format!("Can use {}", areas.join(", ")))
+    } else {
+        DoctorCheck::fail(
+            NAME,
+            format!("Refused: {}", denied.join(", ")),
+            "Log in again with `arena auth login`; if it persists, your account may be \
+             locked, so contact the arena's operator",
+        )
+    }
+}
+
+/// Judge `ARENA_ADMIN_TOKEN` from the status of an admin request made with it
+pub fn check_admin_token(status: u16) -> DoctorCheck {
+    const NAME: &str = "Admin token";
+    match status {
+        200..=299 => DoctorCheck::pass(NAME, "ARENA_ADMIN_TOKEN is accepted"),
+        401 => DoctorCheck::fail(
+            NAME,
+            "ARENA_ADMIN_TOKEN is set but the server refuses it",
+            "Set ARENA_ADMIN_TOKEN to the server's value, or unset it if you aren't an operator",
+        ),
+        status => DoctorCheck::warn(
+            NAME,
+            format!("The server answered {} to an admin request", status),
+            "The server may not have an admin token configured",
+        ),
+    }
+}
+
+/// Check a snake's `GET /` response against the Battlesnake API
+pub fn check_snake_info(body: &str) -> Result<String, String> {
+    let value: serde_json::Value = serde_json::from_str(body)
+        .map_err(|_| format!("GET / didn't return JSON. It returned: {}", snippet(body)))?;
+    match value.get("apiversion").and_then(|v| v.as_str()) {
+        Some("1") => Ok(format!(
+            "apiversion 1{}",
+            value["author"]
+                .as_str()
+                .map(|author| format!(", by {}", author))
+                .unwrap_or_default()
+        )),
+        Some(other) => Err(format!(
+            "apiversion is \"{}\", but the arena speaks version \"1\"",
+            other
+        )),
+        None => Err("The response has no \"apiversion\": \"1\" field".to_string()),
+    }
+}
+
+/// Check a snake's `POST /move` response: an object with a lowercase `move`
+pub fn check_snake_move(body: &str) -> Result<String, String> {
+    let value: serde_json::Value = serde_json::from_str(body)
+        .map_err(|_| format!("/move didn't return JSON. It returned: {}", snippet(body)))?;
+    match value.get("move").and_then(|v| v.as_str()) {
+        Some(direction @ ("up" | "down" | "left" | "right")) => Ok(direction.to_string()),
+        Some(other) => Err(format!(
+            "\"move\" is {:?}; it must be one of up, down, left or right, in lowercase",
+            other
+        )),
+        None => Err(format!(
+            "/move returned {}, with no \"move\" field",
+            snippet(body)
+        )),
+    }
+}
+
+/// Judge how long a snake took to answer /move
+pub fn check_snake_latency(latency_ms: u128) -> DoctorCheck {
+    const NAME: &str = "Snake latency";
+    let detail = format!("/move answered in {}ms", latency_ms);
+    if latency_ms >= MOVE_TIMEOUT_MS {
+        DoctorCheck::fail(
+            NAME,
+            detail,
+            format!(
+                "Answer within {}ms, the default move timeout, with time to spare for the network",
+                MOVE_TIMEOUT_MS
+            ),
+        )
+    } else if latency_ms >= MOVE_TIMEOUT_MS / 2 {
+        DoctorCheck::warn(
+            NAME,
+            detail,
+            "Leave more room under the move timeout; the arena adds network time on top",
+        )
+    } else {
+        DoctorCheck::pass(NAME, detail)
+    }
+}
+
+/// A turn-zero game on an 11x11 board, for playing a turn against a snake
+pub fn sample_game_request() -> serde_json::Value {
+    let you = serde_json::json!({
+        "id": "arena-doctor-snake",
+        "name": "You",
+        "health": 100,
+        "body": [{"x": 5, "y": 5}, {"x": 5, "y": 5}, {"x": 5, "y": 5}],
+        "latency": "0",
+        "head": {"x": 5, "y": 5},
+        "length": 3,
+        "shout": "",
+        "customizations": {"color": "#888888", "head": "default", "tail": "default"}
+    });
+    serde_json::json!({
+        "game": {
+            "id": "arena-doctor",
+            "ruleset": {
+                "name": "standard",
+                "version": "v1.0.0",
+                "settings": {
+                    "foodSpawnChance": 15,
+                    "minimumFood": 1,
+                    "hazardDamagePerTurn": 0
+                }
+            },
+            "map": "standard",
+            "timeout": MOVE_TIMEOUT_MS,
+            "source": "custom"
+        },
+        "turn": 0,
+        "board": {
+            "height": 11,
+            "width": 11,
+            "food": [{"x": 4, "y": 6}, {"x": 8, "y": 2}],
+            "hazards": [],
+            "snakes": [you.clone()]
+        },
+        "you": you
+    })
+}
+
+fn snippet(body: &str) -> String {
+    let trimmed = body.trim();
+    if trimmed.is_empty() {
+        return "an empty body".to_string();
+    }
+    let mut snippet: String = trimmed.chars().take(80).collect();
+    if trimmed.chars().count() > 80 {
+        snippet.push('…');
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_api_url() {
+        let status = |url| check_api_url(url).status;
+        assert_eq!(status("https://arena.battlesnake.com"), DoctorStatus::Pass);
+        assert_eq!(status("http://localhost:3000"), DoctorStatus::Pass);
+        assert_eq!(status("http://arena.example.com"), DoctorStatus::Warn);
+        assert_eq!(status("arena.battlesnake.com"), DoctorStatus::Fail);
+        assert_eq!(status("ftp://arena.example.com"), DoctorStatus::Fail);
+    }
+
+    #[test]
+    fn test_check_api_version() {
+        let capabilities = serde_json::json!({"server_version": "abc", "api_versions": [1, 2]});
+        assert_eq!(check_api_version(&capabilities).status, DoctorStatus::Pass);
+
+        let newer = serde_json::json!({"api_versions": [2, 3]});
+        let check = check_api_version(&newer);
+        assert_eq!(check.status, DoctorStatus::Fail);
+        assert_eq!(check.fix.as_deref(), Some("Upgrade the arena CLI"));
+
+        // Servers from before api_versions only report their current version
+        let old = serde_json::json!({"api_version": 1});
+        assert_eq!(check_api_version(&old).status, DoctorStatus::Pass);
+    }
+
+    #[test]
+    fn test_check_clock_skew() {
+        let server = "Fri, 16 Oct 2026 12:00:00 GMT";
+        let at = |s| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        assert_eq!(
+            check_clock_skew(server, at("2026-10-16T12:00:02Z")).status,
+            DoctorStatus::Pass
+        );
+        let check = check_clock_skew(server, at("2026-10-16T11:58:00Z"));
+        assert_eq!(check.status, DoctorStatus::Warn);
+        assert_eq!(check.detail, "Your clock is 120s behind the server's");
+        assert_eq!(
+            check_clock_skew(server, at("2026-10-16T12:10:00Z")).status,
+            DoctorStatus::Fail
+        );
+        assert_eq!(
+            check_clock_skew("yesterday", at("2026-10-16T12:00:00Z")).status,
+            DoctorStatus::Skipped
+        );
+    }
+
+    #[test]
+    fn test_check_token() {
+        assert_eq!(check_token(None).status, DoctorStatus::Fail);
+        assert_eq!(check_token(Some(200)).status, DoctorStatus::Pass);
+        assert_eq!(check_token(Some(401)).status, DoctorStatus::Fail);
+
+        let access = check_token_access(&[("snakes", 200), ("games", 403)]);
+        assert_eq!(access.status, DoctorStatus::Fail);
+        assert_eq!(access.detail, "Refused: games (403)");
+    }
+
+    #[test]
+    fn test_check_snake_responses() {
+        assert_eq!(
+            check_snake_info(r#"{"apiversion": "1", "author": "me"}"#),
+            Ok("apiversion 1, by me".to_string())
+        );
+        assert!(check_snake_info(r#"{"apiversion": "2"}"#).is_err());
+        assert!(check_snake_info("<html></html>").is_err());
+
+        assert_eq!(
+            check_snake_move(r#"{"move": "left"}"#),
+            Ok("left".to_string())
+        );
+        assert!(check_snake_move(r#"{"move": "Left"}"#).is_err());
+        assert!(check_snake_move(r#"{"direction": "left"}"#).is_err());
+        assert!(check_snake_move("left").is_err());
+
+        assert_eq!(check_snake_latency(40).status, DoctorStatus::Pass);
+        assert_eq!(check_snake_latency(300).status, DoctorStatus::Warn);
+        assert_eq!(check_snake_latency(600).status, DoctorStatus::Fail);
+    }
+}
@@ -1,2 +1,4 @@
 pub mod config;
+pub mod doctor;
 pub mod output;
+pub mod roster;
@@ -65,10 +65,12 @@ pub fn format_uuid_short(id: Uuid) -> String {
 /// Apply color to a status string based on its value.
 pub fn status_colored(status: &str) -> String {
     match status.to_lowercase().as_str() {
-        "complete" | "finished" | "done" | "public" => status.green().to_string(),
+        "complete" | "finished" | "done" | "passed" | "public" | "healthy" => {
+            status.green().to_string()
+        }
         "running" | "active" | "in_progress" => status.yellow().to_string(),
-        "waiting" | "pending" | "private" => status.dimmed().to_string(),
-        "error" | "failed" => status.red().to_string(),
+        "waiting" | "pending" | "private" | "unchecked" => status.dimmed().to_string(),
+        "error" | "failed" | "down" => status.red().to_string(),
         _ => status.to_string(),
     }
 }
@@ -0,0 +1,122 @@
+use color_eyre::eyre::{Context as _, eyre};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A roster of snakes, for `arena snakes import` and `arena snakes export`
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Roster {
+    #[serde(default)]
+    pub snakes: Vec<RosterSnake>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RosterSnake {
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub is_public: bool,
+}
+
+/// File formats a roster can be written in, picked by extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RosterFormat {
+    Toml,
+    Json,
+}
+
+impl RosterFormat {
+    pub fn from_path(path: &Path) -> color_eyre::Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Ok(RosterFormat::Toml),
+            Some("json") => Ok(RosterFormat::Json),
+            Some("yaml" | "yml") => Err(eyre!(
+                "YAML rosters aren't supported yet; use a .toml or .json file"
+            )),
+            _ => Err(eyre!(
+                "Can't tell the roster format of {}; use a .toml or .json file",
+                path.display()
+            )),
+        }
+    }
+}
+
+impl Roster {
+    pub fn parse(contents: &str, format: RosterFormat) -> color_eyre::Result<Self> {
+        match format {
+            RosterFormat::Toml => toml::from_str(contents).wrap_err("Failed to parse roster"),
+            RosterFormat::Json => serde_json::from_str(contents).wrap_err("Failed to parse roster"),
+        }
+    }
+
+    pub fn serialize(&self, format: RosterFormat) -> color_eyre::Result<String> {
+        match format {
+            RosterFormat::Toml => {
+                toml::to_string_pretty(self).wrap_err("Failed to serialize roster")
+            }
+            RosterFormat::Json => {
+                serde_json::to_string_pretty(self).wrap_err("Failed to serialize roster")
+            }
+        }
+    }
+
+    /// Read a roster file, in the format its extension names
+    pub fn load(path: &Path) -> color_eyre::Result<Self> {
+        let format = RosterFormat::from_path(path)?;
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read roster: {}", path.display()))?;
+        Self::parse(&contents, format)
+            .wrap_err_with(|| format!("Invalid roster: {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roster_round_trips() {
+        let roster = Roster {
+            snakes: vec![
+                RosterSnake {
+                    name: "main".to_string(),
+                    url: "https://snake.example.com/main".to_string(),
+                    is_public: true,
+                },
+                RosterSnake {
+                    name: "feature-x".to_string(),
+                    url: "https://snake.example.com/feature-x".to_string(),
+                    is_public: false,
+                },
+            ],
+        };
+
+        for format in [RosterFormat::Toml, RosterFormat::Json] {
+            let serialized = roster.serialize(format).unwrap();
+            assert_eq!(Roster::parse(&serialized, format).unwrap(), roster);
+        }
+    }
+
+    #[test]
+    fn test_roster_toml_defaults_to_private() {
+        let roster = Roster::parse(
+            "[[snakes]]\nname = \"main\"\nurl = \"https://snake.example.com\"\n",
+            RosterFormat::Toml,
+        )
+        .unwrap();
+        assert!(!roster.snakes[0].is_public);
+    }
+
+    #[test]
+    fn test_roster_format_from_path() {
+        assert_eq!(
+            RosterFormat::from_path(Path::new("snakes.toml")).unwrap(),
+            RosterFormat::Toml
+        );
+        assert_eq!(
+            RosterFormat::from_path(Path::new("snakes.json")).unwrap(),
+            RosterFormat::Json
+        );
+        assert!(RosterFormat::from_path(Path::new("snakes.yaml")).is_err());
+        assert!(RosterFormat::from_path(Path::new("snakes")).is_err());
+    }
+}
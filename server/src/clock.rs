@@ -0,0 +1,102 @@
+//! Time source abstraction
+//!
+//! Anything that depends on "now" (session expiry, backup windows, scheduling)
+//! should read the time from `AppState::clock` instead of calling `Utc::now()`
+//! directly, so tests can swap in a `MockClock` and move time forward.
+
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+
+/// Source of the current time
+pub trait Clock: std::fmt::Debug + Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Shared handle to a clock, as stored on `AppState`
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Clock backed by the system time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Manually controlled clock for tests
+///
+/// Clones share the same underlying time, so a test can keep a handle
+/// and advance the clock that was handed to the code under test.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl MockClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    /// Jump to a specific point in time
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    /// Move the clock forward (or backward, with a negative duration)
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+        *now += by;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_mock_clock_is_frozen_until_advanced() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+        let clock = MockClock::new(start);
+
+        assert_eq!(clock.now(), start);
+        assert_eq!(clock.now(), start);
+
+        clock.advance(Duration::minutes(5));
+        assert_eq!(clock.now(), start + Duration::minutes(5));
+    }
+
+    #[test]
+    fn test_mock_clock_clones_share_time() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+        let clock = MockClock::new(start);
+        let shared: SharedClock = Arc::new(clock.clone());
+
+        clock.advance(Duration::hours(1));
+        assert_eq!(shared.now(), start + Duration::hours(1));
+
+        let later = Utc.with_ymd_and_hms(2026, 6, 1, 0, 0, 0).unwrap();
+        clock.set(later);
+        assert_eq!(shared.now(), later);
+    }
+
+    #[test]
+    fn test_system_clock_tracks_real_time() {
+        let before = Utc::now();
+        let now = SystemClock.now();
+        let after = Utc::now();
+
+        assert!(before <= now && now <= after);
+    }
+}
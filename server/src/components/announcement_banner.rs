@@ -0,0 +1,34 @@
+use maud::{Markup, Render, html};
+
+use crate::markdown;
+use crate::models::announcement::{Announcement, Severity};
+use crate::public_url::base_path;
+
+/// Banners at the top of every page for the announcements running now.
+/// static/announcements.js hides the ones a visitor has dismissed.
+pub struct AnnouncementBanner {
+    pub announcements: Vec<Announcement>,
+}
+
+impl Render for AnnouncementBanner {
+    fn render(&self) -> Markup {
+        html! {
+            @if !self.announcements.is_empty() {
+                div class="announcements" id="announcements" {
+                    @for announcement in &self.announcements {
+                        // Critical announcements interrupt screen readers, the rest wait
+                        div class={"announcement announcement-"(announcement.severity.as_str())}
+                            role=(if announcement.severity == Severity::Critical { "alert" } else { "status" })
+                            data-announcement-id=(announcement.announcement_id) {
+                            div class="announcement-message" {
+                                (markdown::render(&announcement.message))
+                            }
+                            button type="button" class="announcement-dismiss" aria-label="Dismiss announcement" { "×" }
+                        }
+                    }
+                }
+                script src={(base_path())"/static/announcements.js"} {}
+            }
+        }
+    }
+}
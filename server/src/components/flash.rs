@@ -222,18 +222,25 @@ impl IntoResponse for FlashRedirect {
         fn inner(s: FlashRedirect) -> ServerResult<axum::response::Response, StatusCode> {
             let mut redirect_response = s.redirect.into_response();
 
+            // Scope the cookies to the app's base path
+            let cookie_path = match crate::public_url::base_path() {
+                "" => "/",
+                p => p,
+            };
+
             // Set the flash message cookie
             let encoded_flash = urlencoding::encode(&s.flash_message);
             let message_cookie = format!(
-                "{}={}; Path=/; HttpOnly; SameSite=Lax",
-                FLASH_COOKIE_NAME, encoded_flash
+                "{}={}; Path={}; HttpOnly; SameSite=Lax",
+                FLASH_COOKIE_NAME, encoded_flash, cookie_path
             );
 
             // Set the flash type cookie
             let type_cookie = format!(
-                "{}={}; Path=/; HttpOnly; SameSite=Lax",
+                "{}={}; Path={}; HttpOnly; SameSite=Lax",
                 FLASH_TYPE_COOKIE_NAME,
-                s.flash_type.to_str()
+                s.flash_type.to_str(),
+                cookie_path
             );
 
             // Add message cookie
@@ -1,4 +1,6 @@
+pub mod announcement_banner;
 pub mod flash;
+pub mod notification_bell;
 pub mod page;
 pub mod page_factory;
 
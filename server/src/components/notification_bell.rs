@@ -0,0 +1,55 @@
+use maud::{Markup, Render, html};
+
+use crate::models::notification::Notification;
+use crate::public_url::base_path;
+
+/// Notifications shown under the bell
+pub const BELL_NOTIFICATIONS: i64 = 5;
+
+/// The bell at the top of each page for logged in users, with their unread
+/// count and latest notifications
+pub struct NotificationBell {
+    pub unread: i64,
+    /// The latest notifications, read or not, newest first
+    pub latest: Vec<Notification>,
+}
+
+impl Render for NotificationBell {
+    fn render(&self) -> Markup {
+        html! {
+            details class="notification-bell" id="notification-bell" {
+                summary aria-label="Notifications" {
+                    "🔔"
+                    @if self.unread > 0 {
+                        span class="notification-count" id="notification-count" { (self.unread) }
+                    }
+                }
+                div class="notification-menu" {
+                    @if self.latest.is_empty() {
+                        p class="notification-empty" { "No notifications yet" }
+                    } @else {
+                        ul {
+                            @for notification in &self.latest {
+                                li class=(if notification.read_at.is_none() { "notification unread" } else { "notification" }) {
+                                    a href={(base_path())"/notifications/"(notification.notification_id)} {
+                                        strong { (notification.title) }
+                                        br;
+                                        (notification.body)
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    div class="notification-actions" {
+                        a href={(base_path())"/notifications"} { "See all" }
+                        @if self.unread > 0 {
+                            form action={(base_path())"/notifications/read"} method="post" {
+                                button type="submit" { "Mark all as read" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,9 +1,33 @@
 use maud::{Markup, Render, html};
 
+use crate::components::announcement_banner::AnnouncementBanner;
+use crate::components::notification_bell::NotificationBell;
+use crate::models::user_preferences::Theme;
+use crate::public_url::base_path;
+
 pub struct Page {
     pub title: String,
     pub content: Box<dyn Render>,
     pub flash: Option<String>,
+    pub theme: Theme,
+    /// None for visitors who aren't logged in
+    pub notifications: Option<NotificationBell>,
+    /// Arena-wide announcements running now
+    pub announcements: AnnouncementBanner,
+    /// The browser asked to save data, so pages poll less and load boards on request
+    pub reduced_data: bool,
+    /// How links to the page unfurl in chat apps, None for no preview
+    pub open_graph: Option<OpenGraph>,
+}
+
+/// Open Graph tags, for link previews in Slack, Discord and Twitter
+pub struct OpenGraph {
+    pub title: String,
+    pub description: String,
+    /// Absolute URL of the page
+    pub url: String,
+    /// Absolute URL of the preview image
+    pub image: Option<String>,
 }
 
 impl Page {
@@ -12,20 +36,54 @@ impl Page {
             title,
             content,
             flash,
+            theme: Theme::default(),
+            notifications: None,
+            announcements: AnnouncementBanner {
+                announcements: Vec::new(),
+            },
+            reduced_data: false,
+            open_graph: None,
         }
     }
+
+    /// Give links to the page a preview when they're shared
+    pub fn with_open_graph(mut self, open_graph: OpenGraph) -> Self {
+        self.open_graph = Some(open_graph);
+        self
+    }
 }
 
 impl Render for Page {
     fn render(&self) -> Markup {
         html! {
             head {
+                meta charset="utf-8";
+                meta name="viewport" content="width=device-width, initial-scale=1";
                 title { (self.title) }
-                link rel="stylesheet" href="/static/styles.css";
-                script src="/static/viewTransition.js" {}
+                @if let Some(og) = &self.open_graph {
+                    meta property="og:type" content="website";
+                    meta property="og:title" content=(og.title);
+                    meta property="og:description" content=(og.description);
+                    meta property="og:url" content=(og.url);
+                    @if let Some(image) = &og.image {
+                        meta property="og:image" content=(image);
+                        meta name="twitter:card" content="summary_large_image";
+                    } @else {
+                        meta name="twitter:card" content="summary";
+                    }
+                }
+                link rel="stylesheet" href={(base_path())"/static/styles.css"};
+                script src={(base_path())"/static/viewTransition.js"} {}
+                script src={(base_path())"/static/reducedData.js"} {}
             }
 
-            body {
+            body data-theme=(self.theme.as_str()) data-reduced-data[self.reduced_data] {
+                (self.announcements)
+
+                @if let Some(bell) = &self.notifications {
+                    (bell)
+                }
+
                 @if let Some(flash_message) = &self.flash {
                     div class="flash-message" {
                         (flash_message)
@@ -1,8 +1,21 @@
-use axum::{extract::FromRequestParts, http::request::Parts, response::Response};
+use axum::{
+    extract::FromRequestParts,
+    http::{HeaderMap, request::Parts},
+    response::Response,
+};
 use maud::Render;
 
 use crate::{
-    components::{flash::Flash, page::Page},
+    components::{
+        announcement_banner::AnnouncementBanner,
+        flash::Flash,
+        notification_bell::{BELL_NOTIFICATIONS, NotificationBell},
+        page::Page,
+    },
+    models::announcement::get_active_announcements,
+    models::notification::{count_unread_notifications, get_notifications},
+    models::user_preferences::{Theme, get_user_preferences},
+    routes::auth::OptionalUser,
     state::AppState,
 };
 
@@ -13,6 +26,14 @@ use crate::{
 pub struct PageFactory {
     /// The flash message extracted from the session (already cleared from DB)
     pub flash: Flash,
+    /// The logged in user's theme, the default for everyone else
+    pub theme: Theme,
+    /// The logged in user's notifications, None for everyone else
+    pub notifications: Option<NotificationBell>,
+    /// Announcements running now, for everyone
+    pub announcements: AnnouncementBanner,
+    /// The request carried `Save-Data: on`
+    pub reduced_data: bool,
 }
 
 impl PageFactory {
@@ -22,6 +43,11 @@ impl PageFactory {
             title,
             content,
             flash: self.flash.message,
+            theme: self.theme,
+            notifications: self.notifications,
+            announcements: self.announcements,
+            reduced_data: self.reduced_data,
+            open_graph: None,
         }
     }
 
@@ -38,6 +64,11 @@ impl PageFactory {
             title,
             content,
             flash: flash.message,
+            theme: self.theme,
+            notifications: self.notifications,
+            announcements: self.announcements,
+            reduced_data: self.reduced_data,
+            open_graph: None,
         }
     }
 }
@@ -50,6 +81,87 @@ impl FromRequestParts<AppState> for PageFactory {
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
         let flash = Flash::from_request_parts(parts, state).await?;
-        Ok(Self { flash })
+
+        // A page is still worth showing in the default theme if preferences can't be loaded
+        let OptionalUser(user) = OptionalUser::from_request_parts(parts, state).await?;
+        let theme = match &user {
+            Some(user) => match get_user_preferences(&state.db, user.user_id).await {
+                Ok(preferences) => preferences.theme,
+                Err(e) => {
+                    tracing::error!("Failed to get preferences for page theme: {:?}", e);
+                    Theme::default()
+                }
+            },
+            None => Theme::default(),
+        };
+
+        // Likewise without the bell if notifications can't be loaded
+        let notifications = match &user {
+            Some(user) => notification_bell(state, user.user_id).await,
+            None => None,
+        };
+
+        // And without announcements
+        let announcements = get_active_announcements(&state.db, state.clock.now())
+            .await
+            .unwrap_or_else(|e| {
+                tracing::error!("Failed to get announcements for page: {:?}", e);
+                Vec::new()
+            });
+
+        Ok(Self {
+            flash,
+            theme,
+            notifications,
+            announcements: AnnouncementBanner { announcements },
+            reduced_data: save_data_requested(&parts.headers),
+        })
+    }
+}
+
+/// Whether the browser's data saver is on, from its `Save-Data` header
+pub fn save_data_requested(headers: &HeaderMap) -> bool {
+    headers
+        .get("save-data")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(';')
+                .next()
+                .is_some_and(|token| token.trim().eq_ignore_ascii_case("on"))
+        })
+}
+
+async fn notification_bell(state: &AppState, user_id: uuid::Uuid) -> Option<NotificationBell> {
+    let unread = count_unread_notifications(&state.db, user_id).await;
+    let latest = get_notifications(&state.db, user_id, false, BELL_NOTIFICATIONS).await;
+    match (unread, latest) {
+        (Ok(unread), Ok(latest)) => Some(NotificationBell { unread, latest }),
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::error!("Failed to get notifications for page: {:?}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(save_data: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = save_data {
+            headers.insert("save-data", value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_save_data_requested() {
+        assert!(save_data_requested(&headers(Some("on"))));
+        assert!(save_data_requested(&headers(Some("On"))));
+        assert!(save_data_requested(&headers(Some("on; foo=bar"))));
+        assert!(!save_data_requested(&headers(Some("off"))));
+        assert!(!save_data_requested(&headers(None)));
     }
 }
@@ -0,0 +1,16 @@
+//! Reading settings from the environment
+//!
+//! Tunables are optional environment variables with built-in defaults, so a
+//! missing or malformed value falls back to the default instead of stopping
+//! the server.
+
+use std::str::FromStr;
+
+/// The environment variable `name` parsed as a `T`, or `default` when it's
+/// unset or doesn't parse
+pub fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(default)
+}
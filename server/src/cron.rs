@@ -3,7 +3,11 @@ use std::time::Duration;
 use cja::cron::{CronRegistry, Worker};
 use tokio_util::sync::CancellationToken;
 
-use crate::jobs::GameBackupJob;
+use crate::jobs::{
+    CheckStatusesJob, FederationSyncJob, FrameCompressionBackfillJob, FrameSchemaBackfillJob,
+    GameBackupJob, OutcomeExportJob, RatingRecalculationJob, SnakeAlertsJob, SnakeHealthCheckJob,
+    SnakeVariantArchiveJob, StuckGameSweepJob, TurnArchiveJob,
+};
 use crate::state::AppState;
 
 fn cron_registry() -> CronRegistry<AppState> {
@@ -16,6 +20,92 @@ fn cron_registry() -> CronRegistry<AppState> {
         Duration::from_secs(60 * 60),
     );
 
+    // Win rate alerts: runs every 15 minutes, fires or resolves alerts on recent results
+    registry.register_job(
+        SnakeAlertsJob,
+        Some("Check snakes' recent win rates against their alerts"),
+        Duration::from_secs(15 * 60),
+    );
+
+    // Check statuses: runs every minute, posts finished and unposted checks to GitHub
+    registry.register_job(
+        CheckStatusesJob,
+        Some("Post regression check results to GitHub"),
+        Duration::from_secs(60),
+    );
+
+    // Federation sync: runs every 15 minutes, pulls new results from each peer
+    registry.register_job(
+        FederationSyncJob,
+        Some("Sync public snakes and results from federation peers"),
+        Duration::from_secs(15 * 60),
+    );
+
+    // Rating recalculation: runs daily, so pages replay at most a day of games
+    // on top of the stored ratings
+    registry.register_job(
+        RatingRecalculationJob::default(),
+        Some("Recalculate stored ratings from every finished game"),
+        Duration::from_secs(24 * 60 * 60),
+    );
+
+    // Snake variant archiving: runs hourly, archives variants past their
+    // template's inactivity limit
+    registry.register_job(
+        SnakeVariantArchiveJob,
+        Some("Archive snake variants that haven't played recently"),
+        Duration::from_secs(60 * 60),
+    );
+
+    // Stuck game sweep: runs every minute, requeues running games whose
+    // worker has stopped heartbeating their lease and deletes expired stream
+    // tickets
+    registry.register_job(
+        StuckGameSweepJob,
+        Some("Requeue games whose worker died mid-run"),
+        Duration::from_secs(60),
+    );
+
+    // Snake health checks: runs every 10 minutes, calls each snake's GET / and
+    // records whether it answered with valid info
+    registry.register_job(
+        SnakeHealthCheckJob,
+        Some("Check every snake's GET / responds with valid info"),
+        Duration::from_secs(10 * 60),
+    );
+
+    // Frame schema backfill: runs every hour, rewrites frames stored in an
+    // older frame format (they're upgraded on read until then)
+    registry.register_job(
+        FrameSchemaBackfillJob,
+        Some("Upgrade stored frames to the current frame schema version"),
+        Duration::from_secs(60 * 60),
+    );
+
+    // Frame compression backfill: runs every hour, compresses the stored frames
+    // of games in the ARENA_COMPRESS_FRAMES_PERCENT rollout
+    registry.register_job(
+        FrameCompressionBackfillJob,
+        Some("Compress the stored frames of games in the compression rollout"),
+        Duration::from_secs(60 * 60),
+    );
+
+    // Turn archiving: runs every hour, archives the turns of games that finished
+    // long enough ago to storage and deletes them from the database
+    registry.register_job(
+        TurnArchiveJob,
+        Some("Archive and prune the turns of long-finished games"),
+        Duration::from_secs(60 * 60),
+    );
+
+    // Outcome export: runs daily, writes yesterday's finished games to storage
+    // as Parquet for analysis
+    registry.register_job(
+        OutcomeExportJob,
+        Some("Export yesterday's game outcomes as Parquet"),
+        Duration::from_secs(24 * 60 * 60),
+    );
+
     registry
 }
 
@@ -0,0 +1,252 @@
+//! Demo games for visitors who aren't logged in
+//!
+//! "Watch a demo game" on the landing page plays built-in bots against each
+//! other, so newcomers can see the arena without registering a snake. The bots
+//! belong to a demo user nobody can log in as, and they're private, so they
+//! stay off the leaderboard and out of federation. Demo games have a short turn
+//! limit and are enqueued behind regular games.
+//!
+//! Visitors share demo games: a click soon after another watches the same
+//! game, and once a few are waiting or running, clicks get a recent game
+//! instead of starting another.
+
+use color_eyre::eyre::Context as _;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::github::auth::{GitHubTokenResponse, GitHubUser};
+use crate::jobs::{GameRunnerJob, LOW_PRIORITY, enqueue_at};
+use crate::models::battlesnake::{
+    CreateBattlesnake, Visibility, create_battlesnake, get_battlesnakes_by_user_id,
+};
+use crate::models::demo_game::{DemoGame, create_demo_game, get_demo_games_since, lock_demo_games};
+use crate::models::game::{
+    CreateGameWithSnakes, GameBoardSize, GameSettings, GameStatus, GameType,
+    insert_game_with_snakes, set_game_enqueued_at, set_game_settings,
+};
+use crate::models::user;
+use crate::state::AppState;
+
+/// Turn limit for demo games, long enough to see snakes grow and collide
+pub const DEMO_MAX_TURNS: i32 = 200;
+
+/// Waiting or running demo games past which clicks reuse a recent game
+const MAX_ACTIVE_DEMO_GAMES: usize = 3;
+
+/// A click this soon after a demo game started watches that game
+const SHARE_WINDOW_SECONDS: i64 = 60;
+
+/// Demo games older than this aren't reused, and unfinished ones are assumed stuck
+const RECYCLE_WINDOW_MINUTES: i64 = 60;
+
+/// GitHub ID of the demo user, well away from real GitHub IDs and the seed users'
+const DEMO_GITHUB_ID: i64 = 8_000_000_000;
+const DEMO_LOGIN: &str = "arena-demo";
+
+/// The bots demo games are played by: (name, URL)
+const DEMO_BOTS: &[(&str, &str)] = &[
+    ("Demo Food Bot 1", "bot:food"),
+    ("Demo Food Bot 2", "bot:food"),
+    ("Demo Random Bot 1", "bot:random"),
+    ("Demo Random Bot 2", "bot:random"),
+];
+
+/// Whether to reuse a demo game or start a new one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DemoChoice {
+    Reuse(Uuid),
+    Start,
+}
+
+/// Pick the demo game to show, given the recent demo games newest first
+pub fn choose_demo_game(recent: &[DemoGame], now: chrono::DateTime<chrono::Utc>) -> DemoChoice {
+    if let Some(newest) = recent
+        .first()
+        .filter(|g| now - g.created_at < chrono::Duration::seconds(SHARE_WINDOW_SECONDS))
+    {
+        return DemoChoice::Reuse(newest.game_id);
+    }
+
+    let (finished, active): (Vec<&DemoGame>, Vec<&DemoGame>) = recent
+        .iter()
+        .partition(|g| g.status == GameStatus::Finished.as_str());
+    if active.len() >= MAX_ACTIVE_DEMO_GAMES {
+        // A finished game can be watched right away, a waiting one can't
+        let recycled = finished.first().or(active.first());
+        if let Some(game) = recycled {
+            return DemoChoice::Reuse(game.game_id);
+        }
+    }
+
+    DemoChoice::Start
+}
+
+/// IDs of the demo bots, creating the demo user and its bots the first time
+async fn demo_bot_ids(pool: &PgPool) -> cja::Result<Vec<Uuid>> {
+    let github_user = GitHubUser {
+        id: DEMO_GITHUB_ID,
+        login: DEMO_LOGIN.to_string(),
+        name: Some("Arena Demo".to_string()),
+        email: None,
+        avatar_url: String::new(),
+    };
+    let token = GitHubTokenResponse {
+        access_token: "demo".to_string(),
+        token_type: "bearer".to_string(),
+        scope: String::new(),
+        refresh_token: None,
+        expires_in: None,
+    };
+    let demo_user = user::create_or_update_user(pool, github_user, token)
+        .await
+        .wrap_err("Failed to create demo user")?;
+
+    let existing = get_battlesnakes_by_user_id(pool, demo_user.user_id).await?;
+    let mut bot_ids = Vec::new();
+    for (name, url) in DEMO_BOTS {
+        let bot_id = match existing.iter().find(|s| s.name == *name && s.url == *url) {
+            Some(bot) => bot.battlesnake_id,
+            None => {
+                create_battlesnake(
+                    pool,
+                    demo_user.user_id,
+                    CreateBattlesnake {
+                        name: name.to_string(),
+                        url: url.to_string(),
+                        visibility: Visibility::Private,
+                    },
+                )
+                .await
+                .wrap_err_with(|| format!("Failed to create demo bot {}", name))?
+                .battlesnake_id
+            }
+        };
+        bot_ids.push(bot_id);
+    }
+
+    Ok(bot_ids)
+}
+
+/// Find a demo game to watch, starting one if none fits, and return its ID
+pub async fn start_or_reuse_demo_game(app_state: &AppState) -> cja::Result<Uuid> {
+    let pool = &app_state.db;
+    let now = app_state.clock.now();
+
+    let mut tx = pool.begin().await.wrap_err("Failed to start transaction")?;
+    lock_demo_games(&mut *tx).await?;
+
+    let recent = get_demo_games_since(
+        &mut *tx,
+        now - chrono::Duration::minutes(RECYCLE_WINDOW_MINUTES),
+    )
+    .await?;
+    if let DemoChoice::Reuse(game_id) = choose_demo_game(&recent, now) {
+        return Ok(game_id);
+    }
+
+    let bot_ids = demo_bot_ids(pool).await?;
+    let game = insert_game_with_snakes(
+        &mut tx,
+        CreateGameWithSnakes {
+            board_size: GameBoardSize::Medium,
+            game_type: GameType::Standard,
+            battlesnake_ids: bot_ids,
+        },
+    )
+    .await
+    .wrap_err("Failed to create demo game")?;
+
+    set_game_settings(
+        &mut *tx,
+        game.game_id,
+        GameSettings {
+            max_turns: Some(DEMO_MAX_TURNS),
+            visibility: Visibility::Public,
+            ..GameSettings::default()
+        },
+    )
+    .await?;
+    create_demo_game(&mut *tx, game.game_id, now).await?;
+    set_game_enqueued_at(&mut *tx, game.game_id, now).await?;
+    enqueue_at(
+        &mut *tx,
+        GameRunnerJob {
+            game_id: game.game_id,
+        },
+        format!("Demo game {}", game.game_id),
+        LOW_PRIORITY,
+        now,
+    )
+    .await?;
+    tx.commit().await.wrap_err("Failed to commit transaction")?;
+
+    Ok(game.game_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn demo_game(status: GameStatus, seconds_ago: i64) -> DemoGame {
+        DemoGame {
+            game_id: Uuid::new_v4(),
+            status: status.as_str().to_string(),
+            created_at: now() - chrono::Duration::seconds(seconds_ago),
+        }
+    }
+
+    fn now() -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::from_timestamp(1_800_000_000, 0).unwrap()
+    }
+
+    #[test]
+    fn test_starts_a_game_when_there_are_none() {
+        assert_eq!(choose_demo_game(&[], now()), DemoChoice::Start);
+    }
+
+    #[test]
+    fn test_shares_a_game_that_just_started() {
+        let recent = vec![demo_game(GameStatus::Running, 10)];
+        assert_eq!(
+            choose_demo_game(&recent, now()),
+            DemoChoice::Reuse(recent[0].game_id)
+        );
+    }
+
+    #[test]
+    fn test_starts_another_under_the_cap() {
+        let recent = vec![
+            demo_game(GameStatus::Running, 120),
+            demo_game(GameStatus::Finished, 300),
+        ];
+        assert_eq!(choose_demo_game(&recent, now()), DemoChoice::Start);
+    }
+
+    #[test]
+    fn test_recycles_a_finished_game_at_the_cap() {
+        let recent = vec![
+            demo_game(GameStatus::Waiting, 120),
+            demo_game(GameStatus::Waiting, 180),
+            demo_game(GameStatus::Finished, 200),
+            demo_game(GameStatus::Running, 240),
+            demo_game(GameStatus::Finished, 400),
+        ];
+        assert_eq!(
+            choose_demo_game(&recent, now()),
+            DemoChoice::Reuse(recent[2].game_id)
+        );
+    }
+
+    #[test]
+    fn test_recycles_an_active_game_at_the_cap_with_none_finished() {
+        let recent = vec![
+            demo_game(GameStatus::Waiting, 120),
+            demo_game(GameStatus::Running, 180),
+            demo_game(GameStatus::Running, 240),
+        ];
+        assert_eq!(
+            choose_demo_game(&recent, now()),
+            DemoChoice::Reuse(recent[0].game_id)
+        );
+    }
+}
@@ -0,0 +1,490 @@
+//! Step-by-step checks of a snake's URL, for "my snake never moves"
+//!
+//! A snake that can't be reached just times out every turn, so its games show
+//! nothing but default moves. Diagnosing the URL walks the path a move request
+//! takes from this server: resolve the host, open a connection, complete the
+//! TLS handshake, get `GET /` with a 200, check the info response against the
+//! Battlesnake API, then time a few more requests. Each step's result says what
+//! went wrong in plain words, and steps after a failure are skipped.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use url::{Host, Url};
+
+use crate::engine::DEFAULT_MOVE_TIMEOUT_MS;
+use crate::engine::move_provider::BuiltInBot;
+
+/// How long each network step can take before it's reported as a failure
+const STEP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Requests timed for the latency check, after the first one
+const LATENCY_SAMPLES: usize = 3;
+
+/// The move timeout games use by default, which a snake's round trip has to fit inside
+const MOVE_TIMEOUT_MS: u128 = DEFAULT_MOVE_TIMEOUT_MS as u128;
+
+/// Round trips above this leave little room to compute a move
+const SLOW_LATENCY_MS: u128 = 250;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    /// Not run, because an earlier step failed or it doesn't apply
+    Skipped,
+}
+
+impl CheckStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "pass",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "fail",
+            CheckStatus::Skipped => "skipped",
+        }
+    }
+}
+
+/// One step of a diagnosis
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosisCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+/// Every step's result for a URL, in the order they ran
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnosis {
+    pub url: String,
+    pub healthy: bool,
+    pub checks: Vec<DiagnosisCheck>,
+}
+
+/// Steps in the order they run, so the ones after a failure can be listed as skipped
+const STEPS: &[&str] = &[
+    "URL",
+    "DNS",
+    "Connection",
+    "TLS",
+    "HTTP status",
+    "Response schema",
+    "Latency",
+];
+
+struct Report {
+    url: String,
+    checks: Vec<DiagnosisCheck>,
+}
+
+impl Report {
+    fn pass(&mut self, name: &'static str, detail: impl Into<String>) {
+        self.push(name, CheckStatus::Pass, detail);
+    }
+
+    fn warn(&mut self, name: &'static str, detail: impl Into<String>) {
+        self.push(name, CheckStatus::Warn, detail);
+    }
+
+    fn push(&mut self, name: &'static str, status: CheckStatus, detail: impl Into<String>) {
+        self.checks.push(DiagnosisCheck {
+            name,
+            status,
+            detail: detail.into(),
+        });
+    }
+
+    /// Record a failed step and skip the rest
+    fn fail(mut self, name: &'static str, detail: impl Into<String>) -> Diagnosis {
+        self.push(name, CheckStatus::Fail, detail);
+        for step in STEPS.iter().skip_while(|step| **step != name).skip(1) {
+            self.push(
+                step,
+                CheckStatus::Skipped,
+                "Skipped after an earlier failure",
+            );
+        }
+        self.finish()
+    }
+
+    fn finish(self) -> Diagnosis {
+        Diagnosis {
+            healthy: self.checks.iter().all(|c| c.status != CheckStatus::Fail),
+            url: self.url,
+            checks: self.checks,
+        }
+    }
+}
+
+/// Run every check against a snake URL
+pub async fn diagnose_url(url: &str) -> Diagnosis {
+    let mut report = Report {
+        url: url.to_string(),
+        checks: Vec::new(),
+    };
+
+    if BuiltInBot::from_url(url).is_some() {
+        report.pass(
+            "URL",
+            format!(
+                "{} is a built-in bot, which runs inside the arena",
+                url.trim()
+            ),
+        );
+        return report.finish();
+    }
+
+    // URL
+    let parsed = match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => parsed,
+        Ok(parsed) => {
+            return report.fail(
+                "URL",
+                format!(
+                    "The URL uses '{}'; snake URLs must start with http:// or https://",
+                    parsed.scheme()
+                ),
+            );
+        }
+        Err(e) => return report.fail("URL", format!("Not a valid URL: {}", e)),
+    };
+    let (Some(host), Some(port)) = (parsed.host(), parsed.port_or_known_default()) else {
+        return report.fail("URL", "The URL has no host");
+    };
+    report.pass("URL", format!("{} URL for {}", parsed.scheme(), host));
+
+    // DNS
+    let addrs: Vec<std::net::SocketAddr> = match &host {
+        Host::Domain(domain) => {
+            match tokio::time::timeout(STEP_TIMEOUT, tokio::net::lookup_host((*domain, port))).await
+            {
+                Ok(Ok(addrs)) => addrs.collect(),
+                Ok(Err(e)) => {
+                    return report.fail(
+                        "DNS",
+                        format!("{} doesn't resolve from the arena's network: {}", domain, e),
+                    );
+                }
+                Err(_) => {
+                    return report.fail(
+                        "DNS",
+                        format!(
+                            "Looking up {} took over {}s",
+                            domain,
+                            STEP_TIMEOUT.as_secs()
+                        ),
+                    );
+                }
+            }
+        }
+        Host::Ipv4(ip) => vec![(*ip, port).into()],
+        Host::Ipv6(ip) => vec![(*ip, port).into()],
+    };
+    if addrs.is_empty() {
+        return report.fail("DNS", format!("{} has no addresses", host));
+    }
+    if let Some(loopback) = addrs.iter().find(|addr| addr.ip().is_loopback()) {
+        report.warn(
+            "DNS",
+            format!(
+                "Resolves to {}, the arena's own machine, so this only works if the snake runs alongside the arena",
+                loopback.ip()
+            ),
+        );
+    } else {
+        report.pass("DNS", format!("Resolves to {}", join_ips(&addrs)));
+    }
+
+    // Connection
+    match tokio::time::timeout(STEP_TIMEOUT, tokio::net::TcpStream::connect(&addrs[..])).await {
+        Ok(Ok(stream)) => {
+            let peer = stream
+                .peer_addr()
+                .map_or_else(|_| host.to_string(), |addr| addr.to_string());
+            report.pass("Connection", format!("Connected to {}", peer));
+        }
+        Ok(Err(e)) => {
+            return report.fail(
+                "Connection",
+                format!(
+                    "Couldn't connect to port {}: {}. Check the server is running and reachable from the internet",
+                    port, e
+                ),
+            );
+        }
+        Err(_) => {
+            return report.fail(
+                "Connection",
+                format!(
+                    "No answer on port {} within {}s; a firewall may be dropping connections",
+                    port,
+                    STEP_TIMEOUT.as_secs()
+                ),
+            );
+        }
+    }
+
+    let client = match reqwest::Client::builder().timeout(STEP_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => return report.fail("TLS", format!("Couldn't start an HTTP client: {}", e)),
+    };
+
+    // TLS, through the first real request since that's where the handshake
+    // happens. The connection already worked, so a connect error here is TLS.
+    let https = parsed.scheme() == "https";
+    let tls_ok = |report: &mut Report| {
+        if https {
+            report.pass("TLS", "The certificate is valid for the host");
+        } else {
+            report.push(
+                "TLS",
+                CheckStatus::Skipped,
+                "Plain http:// URL, so there's no TLS to check",
+            );
+        }
+    };
+    let started = Instant::now();
+    let response = client.get(url).send().await;
+    let first_latency = started.elapsed();
+    let response = match response {
+        Ok(response) => {
+            tls_ok(&mut report);
+            response
+        }
+        Err(e) if https && e.is_connect() => {
+            return report.fail(
+                "TLS",
+                format!("The TLS handshake failed: {}", error_chain(&e)),
+            );
+        }
+        Err(e) => {
+            tls_ok(&mut report);
+            let detail = if e.is_timeout() {
+                format!("GET / got no response within {}s", STEP_TIMEOUT.as_secs())
+            } else {
+                format!("GET / failed: {}", error_chain(&e))
+            };
+            return report.fail("HTTP status", detail);
+        }
+    };
+
+    // HTTP status
+    let status = response.status();
+    if status != reqwest::StatusCode::OK {
+        return report.fail(
+            "HTTP status",
+            format!(
+                "GET / returned {}; snakes must answer it with 200 and their info",
+                status
+            ),
+        );
+    }
+    report.pass("HTTP status", "GET / returned 200 OK");
+
+    // Response schema
+    let body = match response.text().await {
+        Ok(body) => body,
+        Err(e) => {
+            return report.fail(
+                "Response schema",
+                format!("Couldn't read the response: {}", error_chain(&e)),
+            );
+        }
+    };
+    match check_info_response(&body) {
+        Ok(warnings) if warnings.is_empty() => {
+            report.pass("Response schema", "A valid Battlesnake info response")
+        }
+        Ok(warnings) => report.warn("Response schema", warnings.join(" ")),
+        Err(problem) => return report.fail("Response schema", problem),
+    }
+
+    // Latency
+    let mut samples = Vec::with_capacity(LATENCY_SAMPLES);
+    for _ in 0..LATENCY_SAMPLES {
+        let started = Instant::now();
+        if client.get(url).send().await.is_ok() {
+            samples.push(started.elapsed());
+        }
+    }
+    let (status, detail) = latency_verdict(first_latency, &samples);
+    report.push("Latency", status, detail);
+
+    report.finish()
+}
+
+/// Check a `GET /` body is the info response the Battlesnake API describes.
+/// Returns warnings about optional fields, or why it can't be used.
+pub fn check_info_response(body: &str) -> Result<Vec<String>, String> {
+    let value: serde_json::Value = serde_json::from_str(body)
+        .map_err(|_| format!("GET / didn't return JSON. It returned: {}", snippet(body)))?;
+    let info = value
+        .as_object()
+        .ok_or_else(|| "GET / returned JSON, but not an object".to_string())?;
+
+    match info.get("apiversion").and_then(|v| v.as_str()) {
+        Some("1") => {}
+        Some(other) => {
+            return Err(format!(
+                "apiversion is \"{}\", but the arena speaks version \"1\"",
+                other
+            ));
+        }
+        None => return Err("The response has no \"apiversion\": \"1\" field".to_string()),
+    }
+
+    let mut warnings = Vec::new();
+    if let Some(color) = info.get("color") {
+        let valid = color.as_str().is_some_and(|c| {
+            c.len() == 7 && c.starts_with('#') && c[1..].chars().all(|ch| ch.is_ascii_hexdigit())
+        });
+        if !valid {
+            warnings.push(format!(
+                "color should be a hex color like \"#ff8800\", not {}.",
+                color
+            ));
+        }
+    }
+    for field in ["author", "head", "tail", "version"] {
+        if let Some(value) = info.get(field)
+            && !value.is_string()
+        {
+            warnings.push(format!("{} should be a string, not {}.", field, value));
+        }
+    }
+    Ok(warnings)
+}
+
+/// Judge a snake's round trips against the move timeout
+fn latency_verdict(first: Duration, samples: &[Duration]) -> (CheckStatus, String) {
+    let mut sorted = samples.to_vec();
+    sorted.sort();
+    let Some(median) = sorted.get(sorted.len() / 2).copied() else {
+        return (
+            CheckStatus::Warn,
+            format!(
+                "The first request took {}ms, but the follow-up requests failed",
+                first.as_millis()
+            ),
+        );
+    };
+
+    let median_ms = median.as_millis();
+    let measured = format!(
+        "Median round trip {}ms over {} requests (the first, with connection setup, took {}ms)",
+        median_ms,
+        samples.len(),
+        first.as_millis()
+    );
+    if median_ms >= MOVE_TIMEOUT_MS {
+        (
+            CheckStatus::Fail,
+            format!(
+                "{}. That's over the {}ms move timeout, so every move will time out",
+                measured, MOVE_TIMEOUT_MS
+            ),
+        )
+    } else if median_ms >= SLOW_LATENCY_MS {
+        (
+            CheckStatus::Warn,
+            format!(
+                "{}. That leaves under {}ms of the {}ms move timeout to pick a move",
+                measured,
+                MOVE_TIMEOUT_MS - median_ms,
+                MOVE_TIMEOUT_MS
+            ),
+        )
+    } else {
+        (CheckStatus::Pass, measured)
+    }
+}
+
+fn join_ips(addrs: &[std::net::SocketAddr]) -> String {
+    let mut ips: Vec<String> = addrs.iter().map(|addr| addr.ip().to_string()).collect();
+    ips.dedup();
+    ips.join(", ")
+}
+
+/// An error and its causes, which is where reqwest puts the useful part
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+fn snippet(body: &str) -> String {
+    let trimmed = body.trim();
+    if trimmed.is_empty() {
+        return "an empty body".to_string();
+    }
+    let mut snippet: String = trimmed.chars().take(80).collect();
+    if trimmed.chars().count() > 80 {
+        snippet.push('…');
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_info_response() {
+        assert_eq!(
+            check_info_response(r##"{"apiversion": "1", "color": "#FF8800", "head": "default"}"##),
+            Ok(vec![])
+        );
+        assert!(check_info_response(r#"{"apiversion": "2"}"#).is_err());
+        assert!(check_info_response(r##"{"color": "#ff8800"}"##).is_err());
+        assert!(check_info_response("<html>Hello</html>").is_err());
+        assert!(check_info_response("[]").is_err());
+
+        let warnings =
+            check_info_response(r#"{"apiversion": "1", "color": "red", "author": 5}"#).unwrap();
+        assert_eq!(warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_latency_verdict() {
+        let ms = Duration::from_millis;
+        let first = ms(300);
+        assert_eq!(
+            latency_verdict(first, &[ms(40), ms(60), ms(50)]).0,
+            CheckStatus::Pass
+        );
+        assert_eq!(
+            latency_verdict(first, &[ms(300), ms(260), ms(280)]).0,
+            CheckStatus::Warn
+        );
+        assert_eq!(
+            latency_verdict(first, &[ms(600), ms(40), ms(700)]).0,
+            CheckStatus::Fail
+        );
+        assert_eq!(latency_verdict(first, &[]).0, CheckStatus::Warn);
+    }
+
+    #[tokio::test]
+    async fn test_diagnose_stops_at_first_failure() {
+        let diagnosis = diagnose_url("ftp://snake.example.com").await;
+        assert!(!diagnosis.healthy);
+        assert_eq!(diagnosis.checks.len(), STEPS.len());
+        assert_eq!(diagnosis.checks[0].status, CheckStatus::Fail);
+        assert!(
+            diagnosis.checks[1..]
+                .iter()
+                .all(|c| c.status == CheckStatus::Skipped)
+        );
+
+        let bot = diagnose_url("bot:food").await;
+        assert!(bot.healthy);
+        assert_eq!(bot.checks.len(), 1);
+    }
+}
@@ -0,0 +1,615 @@
+//! The domain event log: what happened in games, in the order it happened
+//!
+//! [`DomainEventsHook`] appends an event to the `domain_events` table as each
+//! game starts, after each turn is played and when the game finishes. Rating
+//! recalculations and completed leagues append their own. The table is
+//! append-only; a trigger refuses updates and deletes.
+//!
+//! Derived tables are [`Projection`]s of the log. Each appended event is
+//! applied to the projections that want it in the same transaction, so they
+//! stay current as games are played. After a bug in a projection or a change
+//! to its schema, [`rebuild`] empties it and replays every event it's built
+//! from. A rebuild locks the projection's tables, so games finishing while it
+//! runs wait for it and then apply on top of the rebuilt table, rather than
+//! being counted twice or lost.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{Context as _, eyre};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::engine::hooks::{EngineHook, GameEnded, GameStarted, TurnApplied};
+use crate::state::AppState;
+
+/// Events replayed between progress updates when rebuilding
+const REBUILD_BATCH: i64 = 1000;
+
+/// A snake entered in a game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventSnake {
+    pub game_battlesnake_id: Uuid,
+    pub battlesnake_id: Uuid,
+    pub user_id: Uuid,
+}
+
+/// The move a snake made on a turn
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventMove {
+    /// The snake's game_battlesnake_id
+    pub snake_id: String,
+    pub direction: String,
+    pub latency_ms: Option<i64>,
+    pub timed_out: bool,
+}
+
+/// Where a snake finished a game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventPlacement {
+    pub game_battlesnake_id: Uuid,
+    pub battlesnake_id: Uuid,
+    pub placement: i32,
+}
+
+/// Something that happened, as it's stored in the log's `payload`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DomainEvent {
+    /// A game's first event, written as its runner starts it
+    GameCreated {
+        board: String,
+        game_type: String,
+        snakes: Vec<EventSnake>,
+    },
+    /// A turn that's been applied, stored with the runner's next batch
+    TurnPersisted { turn: i32, moves: Vec<EventMove> },
+    /// A game whose results have been stored
+    GameFinished {
+        turns: i32,
+        ranked: bool,
+        duel: bool,
+        league_id: Option<Uuid>,
+        /// Best first
+        placements: Vec<EventPlacement>,
+    },
+    /// A rating recalculation swapped in new stored ratings
+    RatingUpdated {
+        recalculation_id: Uuid,
+        algorithm_version: i32,
+        snakes: usize,
+    },
+    /// A league finished another of its games, or was completed
+    TournamentAdvanced { league_id: Uuid, completed: bool },
+}
+
+impl DomainEvent {
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            DomainEvent::GameCreated { .. } => "game_created",
+            DomainEvent::TurnPersisted { .. } => "turn_persisted",
+            DomainEvent::GameFinished { .. } => "game_finished",
+            DomainEvent::RatingUpdated { .. } => "rating_updated",
+            DomainEvent::TournamentAdvanced { .. } => "tournament_advanced",
+        }
+    }
+}
+
+/// An event read back from the log
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredEvent {
+    pub event_id: i64,
+    pub game_id: Option<Uuid>,
+    pub occurred_at: DateTime<Utc>,
+    pub event: DomainEvent,
+}
+
+#[derive(Debug)]
+struct EventRow {
+    event_id: i64,
+    game_id: Option<Uuid>,
+    payload: serde_json::Value,
+    occurred_at: DateTime<Utc>,
+}
+
+impl TryFrom<EventRow> for StoredEvent {
+    type Error = color_eyre::Report;
+
+    fn try_from(row: EventRow) -> Result<Self, Self::Error> {
+        let event = serde_json::from_value(row.payload)
+            .wrap_err_with(|| format!("Failed to parse domain event {}", row.event_id))?;
+        Ok(Self {
+            event_id: row.event_id,
+            game_id: row.game_id,
+            occurred_at: row.occurred_at,
+            event,
+        })
+    }
+}
+
+/// A table derived from the event log
+#[async_trait::async_trait]
+pub trait Projection: Send + Sync {
+    /// What `arena admin rebuild-projections` calls it
+    fn name(&self) -> &'static str;
+
+    /// The event types it's built from
+    fn event_types(&self) -> &'static [&'static str];
+
+    /// Lock the projection's tables against live updates and empty them
+    async fn reset(&self, conn: &mut PgConnection) -> cja::Result<()>;
+
+    /// Update the projection for one event
+    async fn apply(&self, conn: &mut PgConnection, event: &StoredEvent) -> cja::Result<()>;
+}
+
+/// Every projection of the log
+pub fn projections() -> Vec<Box<dyn Projection>> {
+    vec![Box::new(SnakeStatsProjection)]
+}
+
+/// Append an event, applying it to the projections built from its type
+pub async fn append(
+    app_state: &AppState,
+    game_id: Option<Uuid>,
+    event: DomainEvent,
+) -> cja::Result<StoredEvent> {
+    let payload = serde_json::to_value(&event).wrap_err("Failed to serialize domain event")?;
+    let occurred_at = app_state.clock.now();
+    let projections: Vec<_> = projections()
+        .into_iter()
+        .filter(|p| p.event_types().contains(&event.event_type()))
+        .collect();
+
+    let mut tx = app_state
+        .db
+        .begin()
+        .await
+        .wrap_err("Failed to start transaction")?;
+    let event_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO domain_events (event_type, game_id, payload, occurred_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING event_id
+        "#,
+        event.event_type(),
+        game_id,
+        &payload,
+        occurred_at
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .wrap_err_with(|| format!("Failed to append {} event", event.event_type()))?;
+
+    let stored = StoredEvent {
+        event_id,
+        game_id,
+        occurred_at,
+        event,
+    };
+    for projection in &projections {
+        projection
+            .apply(&mut tx, &stored)
+            .await
+            .wrap_err_with(|| format!("Failed to apply event to {}", projection.name()))?;
+    }
+    tx.commit().await.wrap_err("Failed to commit transaction")?;
+
+    Ok(stored)
+}
+
+/// Append an event that isn't worth failing the caller over, logging any error
+pub async fn record(app_state: &AppState, game_id: Option<Uuid>, event: DomainEvent) {
+    let event_type = event.event_type();
+    if let Err(e) = append(app_state, game_id, event).await {
+        tracing::error!(event_type, error = ?e, "Failed to record domain event");
+    }
+}
+
+/// Up to `limit` events of `event_types` after `after`, oldest first
+async fn events_after(
+    conn: &mut PgConnection,
+    event_types: &[&str],
+    after: i64,
+    limit: i64,
+) -> cja::Result<Vec<StoredEvent>> {
+    let rows = sqlx::query_as!(
+        EventRow,
+        r#"
+        SELECT event_id, game_id, payload, occurred_at
+        FROM domain_events
+        WHERE event_id > $1 AND event_type = ANY($2)
+        ORDER BY event_id
+        LIMIT $3
+        "#,
+        after,
+        event_types as &[&str],
+        limit
+    )
+    .fetch_all(conn)
+    .await
+    .wrap_err("Failed to read domain events")?;
+
+    rows.into_iter().map(StoredEvent::try_from).collect()
+}
+
+/// A game's events, oldest first
+pub async fn game_events(pool: &PgPool, game_id: Uuid) -> cja::Result<Vec<StoredEvent>> {
+    let rows = sqlx::query_as!(
+        EventRow,
+        r#"
+        SELECT event_id, game_id, payload, occurred_at
+        FROM domain_events
+        WHERE game_id = $1
+        ORDER BY event_id
+        "#,
+        game_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to read the game's domain events")?;
+
+    rows.into_iter().map(StoredEvent::try_from).collect()
+}
+
+/// Empty a projection and replay every event it's built from, in one
+/// transaction. Returns how many events were replayed.
+pub async fn rebuild(pool: &PgPool, projection: &dyn Projection) -> cja::Result<usize> {
+    let mut tx = pool.begin().await.wrap_err("Failed to start transaction")?;
+    projection.reset(&mut tx).await?;
+
+    let mut replayed = 0;
+    let mut after = 0;
+    loop {
+        let events = events_after(&mut tx, projection.event_types(), after, REBUILD_BATCH).await?;
+        let Some(last) = events.last() else {
+            break;
+        };
+        after = last.event_id;
+
+        for event in &events {
+            projection.apply(&mut tx, event).await.wrap_err_with(|| {
+                format!(
+                    "Failed to replay event {} into {}",
+                    event.event_id,
+                    projection.name()
+                )
+            })?;
+        }
+        replayed += events.len();
+        tracing::info!(
+            projection = projection.name(),
+            replayed,
+            "Replaying domain events"
+        );
+    }
+
+    tx.commit().await.wrap_err("Failed to commit transaction")?;
+    Ok(replayed)
+}
+
+/// `arena admin rebuild-projections`: rebuild one projection, or all of them
+pub async fn run_rebuild_command(app_state: &AppState, name: Option<&str>) -> cja::Result<()> {
+    let all = projections();
+    let selected: Vec<_> = match name {
+        Some(name) => all.iter().filter(|p| p.name() == name).collect(),
+        None => all.iter().collect(),
+    };
+    if selected.is_empty() {
+        let names: Vec<_> = all.iter().map(|p| p.name()).collect();
+        return Err(eyre!(
+            "No projection named {}; projections are {}",
+            name.unwrap_or_default(),
+            names.join(", ")
+        ));
+    }
+
+    for projection in selected {
+        let started = std::time::Instant::now();
+        let replayed = rebuild(&app_state.db, projection.as_ref()).await?;
+        println!(
+            "Rebuilt {} from {} events in {:.1}s",
+            projection.name(),
+            replayed,
+            started.elapsed().as_secs_f64()
+        );
+    }
+    Ok(())
+}
+
+/// Each snake's totals over its finished games, in `snake_stats`
+pub struct SnakeStatsProjection;
+
+/// A snake's row in `snake_stats`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnakeStatsRollup {
+    pub battlesnake_id: Uuid,
+    pub games: i32,
+    pub wins: i32,
+    pub placement_sum: i64,
+    pub turns: i64,
+    pub last_played_at: DateTime<Utc>,
+}
+
+impl SnakeStatsRollup {
+    pub fn average_placement(&self) -> Option<f64> {
+        (self.games > 0).then(|| self.placement_sum as f64 / f64::from(self.games))
+    }
+}
+
+/// How each snake's totals change with one finished game. A snake entered
+/// more than once counts once for each entry, as it does in its game history.
+fn stats_deltas(turns: i32, placements: &[EventPlacement]) -> HashMap<Uuid, (i32, i32, i64, i64)> {
+    let mut deltas: HashMap<Uuid, (i32, i32, i64, i64)> = HashMap::new();
+    for placement in placements {
+        let delta = deltas.entry(placement.battlesnake_id).or_default();
+        delta.0 += 1;
+        delta.1 += i32::from(placement.placement == 1);
+        delta.2 += i64::from(placement.placement);
+        delta.3 += i64::from(turns);
+    }
+    deltas
+}
+
+#[async_trait::async_trait]
+impl Projection for SnakeStatsProjection {
+    fn name(&self) -> &'static str {
+        "snake_stats"
+    }
+
+    fn event_types(&self) -> &'static [&'static str] {
+        &["game_finished"]
+    }
+
+    async fn reset(&self, conn: &mut PgConnection) -> cja::Result<()> {
+        sqlx::query!("LOCK TABLE snake_stats IN EXCLUSIVE MODE")
+            .execute(&mut *conn)
+            .await
+            .wrap_err("Failed to lock snake_stats")?;
+        sqlx::query!("DELETE FROM snake_stats")
+            .execute(&mut *conn)
+            .await
+            .wrap_err("Failed to empty snake_stats")?;
+        Ok(())
+    }
+
+    async fn apply(&self, conn: &mut PgConnection, event: &StoredEvent) -> cja::Result<()> {
+        let DomainEvent::GameFinished {
+            turns, placements, ..
+        } = &event.event
+        else {
+            return Ok(());
+        };
+
+        for (battlesnake_id, (games, wins, placement_sum, turns)) in
+            stats_deltas(*turns, placements)
+        {
+            // Deleted snakes' games are still in the log, but not in the stats
+            sqlx::query!(
+                r#"
+                INSERT INTO snake_stats (battlesnake_id, games, wins, placement_sum, turns, last_played_at)
+                SELECT $1, $2::INT, $3::INT, $4::BIGINT, $5::BIGINT, $6::TIMESTAMPTZ
+                WHERE EXISTS (SELECT 1 FROM battlesnakes WHERE battlesnake_id = $1)
+                ON CONFLICT (battlesnake_id) DO UPDATE SET
+                    games = snake_stats.games + EXCLUDED.games,
+                    wins = snake_stats.wins + EXCLUDED.wins,
+                    placement_sum = snake_stats.placement_sum + EXCLUDED.placement_sum,
+                    turns = snake_stats.turns + EXCLUDED.turns,
+                    last_played_at = GREATEST(snake_stats.last_played_at, EXCLUDED.last_played_at)
+                "#,
+                battlesnake_id,
+                games,
+                wins,
+                placement_sum,
+                turns,
+                event.occurred_at
+            )
+            .execute(&mut *conn)
+            .await
+            .wrap_err("Failed to update snake_stats")?;
+        }
+        Ok(())
+    }
+}
+
+/// A snake's totals from `snake_stats`, None if it hasn't finished a game
+pub async fn get_snake_stats(
+    pool: &PgPool,
+    battlesnake_id: Uuid,
+) -> cja::Result<Option<SnakeStatsRollup>> {
+    sqlx::query_as!(
+        SnakeStatsRollup,
+        r#"
+        SELECT battlesnake_id, games, wins, placement_sum, turns, last_played_at
+        FROM snake_stats
+        WHERE battlesnake_id = $1
+        "#,
+        battlesnake_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to get snake stats")
+}
+
+#[derive(Debug)]
+struct GameEventSettings {
+    ranked: bool,
+    duel: bool,
+    league_id: Option<Uuid>,
+}
+
+/// Writes games' events to the log as they're played
+pub struct DomainEventsHook;
+
+#[async_trait::async_trait]
+impl EngineHook for DomainEventsHook {
+    fn name(&self) -> &'static str {
+        "domain_events"
+    }
+
+    async fn on_game_start(
+        &self,
+        app_state: &AppState,
+        event: &GameStarted<'_>,
+    ) -> cja::Result<()> {
+        let board = &event.game.board;
+        append(
+            app_state,
+            Some(event.game_id),
+            DomainEvent::GameCreated {
+                board: format!("{}x{}", board.width, board.height),
+                game_type: event.game.game.ruleset.name.clone(),
+                snakes: event
+                    .snakes
+                    .iter()
+                    .map(|s| EventSnake {
+                        game_battlesnake_id: s.game_battlesnake_id,
+                        battlesnake_id: s.battlesnake_id,
+                        user_id: s.user_id,
+                    })
+                    .collect(),
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn on_turn_applied(
+        &self,
+        app_state: &AppState,
+        event: &TurnApplied<'_>,
+    ) -> cja::Result<()> {
+        append(
+            app_state,
+            Some(event.game_id),
+            DomainEvent::TurnPersisted {
+                turn: event.game.turn,
+                moves: event
+                    .moves
+                    .iter()
+                    .map(|m| EventMove {
+                        snake_id: m.snake_id.clone(),
+                        direction: m.direction.to_string(),
+                        latency_ms: m.latency_ms,
+                        timed_out: m.timed_out,
+                    })
+                    .collect(),
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn on_game_end(&self, app_state: &AppState, event: &GameEnded<'_>) -> cja::Result<()> {
+        let settings = sqlx::query_as!(
+            GameEventSettings,
+            "SELECT ranked, duel, league_id FROM games WHERE game_id = $1",
+            event.game_id
+        )
+        .fetch_one(&app_state.db)
+        .await
+        .wrap_err("Failed to get game settings for its finished event")?;
+
+        let battlesnake_ids: HashMap<String, Uuid> = event
+            .snakes
+            .iter()
+            .map(|s| (s.game_battlesnake_id.to_string(), s.battlesnake_id))
+            .collect();
+        let placements = event
+            .placements
+            .iter()
+            .filter_map(|(snake_id, placement)| {
+                Some(EventPlacement {
+                    game_battlesnake_id: snake_id.parse().ok()?,
+                    battlesnake_id: *battlesnake_ids.get(snake_id)?,
+                    placement: *placement,
+                })
+            })
+            .collect();
+
+        append(
+            app_state,
+            Some(event.game_id),
+            DomainEvent::GameFinished {
+                turns: event.game.turn,
+                ranked: settings.ranked,
+                duel: settings.duel,
+                league_id: settings.league_id,
+                placements,
+            },
+        )
+        .await?;
+
+        if let Some(league_id) = settings.league_id {
+            append(
+                app_state,
+                Some(event.game_id),
+                DomainEvent::TournamentAdvanced {
+                    league_id,
+                    completed: false,
+                },
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_payloads_round_trip() {
+        let event = DomainEvent::GameFinished {
+            turns: 42,
+            ranked: true,
+            duel: false,
+            league_id: None,
+            placements: vec![EventPlacement {
+                game_battlesnake_id: Uuid::from_u128(1),
+                battlesnake_id: Uuid::from_u128(2),
+                placement: 1,
+            }],
+        };
+        let payload = serde_json::to_value(&event).unwrap();
+        assert_eq!(payload["type"], event.event_type());
+        assert_eq!(
+            serde_json::from_value::<DomainEvent>(payload).unwrap(),
+            event
+        );
+
+        let event = DomainEvent::TournamentAdvanced {
+            league_id: Uuid::from_u128(3),
+            completed: true,
+        };
+        let payload = serde_json::to_value(&event).unwrap();
+        assert_eq!(payload["type"], "tournament_advanced");
+        assert_eq!(
+            serde_json::from_value::<DomainEvent>(payload).unwrap(),
+            event
+        );
+    }
+
+    #[test]
+    fn test_stats_deltas() {
+        let snake = |id: u128, battlesnake: u128, placement| EventPlacement {
+            game_battlesnake_id: Uuid::from_u128(id),
+            battlesnake_id: Uuid::from_u128(battlesnake),
+            placement,
+        };
+        // The same snake entered twice counts twice
+        let deltas = stats_deltas(100, &[snake(1, 10, 1), snake(2, 10, 3), snake(3, 20, 2)]);
+        assert_eq!(deltas[&Uuid::from_u128(10)], (2, 1, 4, 200));
+        assert_eq!(deltas[&Uuid::from_u128(20)], (1, 0, 2, 100));
+    }
+
+    #[test]
+    fn test_projection_names_are_unique() {
+        let mut names: Vec<_> = projections().iter().map(|p| p.name()).collect();
+        let count = names.len();
+        names.dedup();
+        assert_eq!(names.len(), count);
+    }
+}
@@ -3,12 +3,12 @@
 //! This module converts the internal game state to the PascalCase JSON format
 //! expected by the board viewer.
 
-use battlesnake_game_types::wire_representation::{Game, Position};
-use serde::Serialize;
-use std::collections::VecDeque;
+use battlesnake_game_types::wire_representation::{BattleSnake, Board, Game, Position};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 
 /// Information about a snake's death
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeathInfo {
     /// The snake's ID
     pub snake_id: String,
@@ -16,8 +16,8 @@ pub struct DeathInfo {
     pub turn: i32,
     /// The cause of death (e.g., "wall-collision", "head-collision")
     pub cause: String,
-    /// The ID of the snake that eliminated this snake (if applicable)
-    /// TODO: Pass eliminated_by from the game engine once head-to-head collision tracking is implemented
+    /// The ID of the snake that eliminated this snake: the one whose head or
+    /// body it hit. Empty for walls and running out of health.
     pub eliminated_by: String,
 }
 
@@ -58,7 +58,7 @@ pub struct FrameSnake {
     pub eliminated_by: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct FrameCoord {
     #[serde(rename = "X")]
@@ -81,7 +81,85 @@ impl From<Position> for FrameCoord {
     }
 }
 
-use crate::snake_client::MoveResult;
+impl From<FrameCoord> for Position {
+    fn from(coord: FrameCoord) -> Self {
+        Position::new(coord.x, coord.y)
+    }
+}
+
+/// A stored frame read back, with just what's needed to rebuild its board
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StoredFrame {
+    pub snakes: Vec<StoredSnake>,
+    pub food: Vec<FrameCoord>,
+    #[serde(default)]
+    pub hazards: Vec<FrameCoord>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StoredSnake {
+    #[serde(rename = "ID")]
+    pub id: String,
+    pub name: String,
+    pub body: Vec<FrameCoord>,
+    pub health: i32,
+    #[serde(default)]
+    pub death: Option<StoredDeath>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StoredDeath {
+    pub cause: String,
+    pub turn: i32,
+    #[serde(default)]
+    pub eliminated_by: String,
+}
+
+impl StoredFrame {
+    /// The board this frame shows, on a `width` x `height` board
+    pub fn to_board(&self, width: u32, height: u32) -> Board {
+        Board {
+            height,
+            width,
+            food: self.food.iter().map(|&c| c.into()).collect(),
+            hazards: self.hazards.iter().map(|&c| c.into()).collect(),
+            snakes: self
+                .snakes
+                .iter()
+                .map(|s| BattleSnake {
+                    id: s.id.clone(),
+                    name: s.name.clone(),
+                    head: s.body.first().map_or(Position::new(0, 0), |&c| c.into()),
+                    body: s.body.iter().map(|&c| c.into()).collect(),
+                    health: s.health,
+                    shout: None,
+                    actual_length: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// How each snake that was already eliminated in this frame died
+    pub fn death_info(&self) -> Vec<DeathInfo> {
+        self.snakes
+            .iter()
+            .filter_map(|s| {
+                s.death.as_ref().map(|d| DeathInfo {
+                    snake_id: s.id.clone(),
+                    turn: d.turn,
+                    cause: d.cause.clone(),
+                    eliminated_by: d.eliminated_by.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+use crate::models::game::DeadSnakes;
+use crate::snake_client::{MoveResult, SnakeInfo};
 
 /// Convert a Game state to a frame for the board viewer
 ///
@@ -164,8 +242,66 @@ pub fn game_to_frame(
     }
 }
 
+impl EngineGameFrame {
+    /// Show each snake the way its GET / describes it, keyed by snake ID
+    ///
+    /// Snakes without info, or with a color the viewer can't draw, keep the
+    /// generated color and default head and tail.
+    pub fn with_snake_info(mut self, infos: &HashMap<String, SnakeInfo>) -> Self {
+        for snake in &mut self.snakes {
+            let Some(info) = infos.get(&snake.id) else {
+                continue;
+            };
+            if let Some(color) = info.hex_color() {
+                snake.color = color.to_string();
+            }
+            if let Some(head) = &info.head {
+                snake.head_type = head.clone();
+            }
+            if let Some(tail) = &info.tail {
+                snake.tail_type = tail.clone();
+            }
+            if let Some(apiversion) = &info.apiversion {
+                snake.api_version = apiversion.clone();
+            }
+            if let Some(author) = &info.author {
+                snake.author = author.clone();
+            }
+        }
+        self
+    }
+
+    /// Apply a game's dead snakes option to the frame
+    ///
+    /// Snakes are always shown on the turn they die, so the viewer can show
+    /// how. After that they're either left out or kept in a faded color.
+    pub fn with_dead_snakes(mut self, dead_snakes: DeadSnakes) -> Self {
+        let turn = self.turn;
+        let died_earlier = |s: &FrameSnake| s.death.as_ref().is_some_and(|death| death.turn < turn);
+
+        match dead_snakes {
+            DeadSnakes::Drop => self.snakes.retain(|s| !died_earlier(s)),
+            DeadSnakes::Ghost => {
+                for snake in self.snakes.iter_mut().filter(|s| died_earlier(s)) {
+                    snake.color = ghost_snake_color(&snake.id);
+                }
+            }
+        }
+        self
+    }
+}
+
 /// Generate a consistent color for a snake based on its ID
-fn generate_snake_color(id: &str) -> String {
+pub(crate) fn generate_snake_color(id: &str) -> String {
+    hsl_snake_color(id, 0.7, 0.5)
+}
+
+/// A faded version of a snake's color, for snakes kept after they die
+fn ghost_snake_color(id: &str) -> String {
+    hsl_snake_color(id, 0.25, 0.85)
+}
+
+fn hsl_snake_color(id: &str, saturation: f32, lightness: f32) -> String {
     // Generate a color from the hash of the ID
     let hash: u32 = id
         .bytes()
@@ -173,8 +309,6 @@ fn generate_snake_color(id: &str) -> String {
 
     // Use the hash to generate a hue
     let hue = (hash % 360) as f32;
-    let saturation: f32 = 0.7;
-    let lightness: f32 = 0.5;
 
     // HSL to RGB conversion
     let c: f32 = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
@@ -200,9 +334,11 @@ fn generate_snake_color(id: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use battlesnake_game_types::types::Move;
     use battlesnake_game_types::wire_representation::{
         BattleSnake, Board, Game, NestedGame, Ruleset,
     };
+    use std::path::{Path, PathBuf};
 
     #[test]
     fn test_frame_coord_serialization() {
@@ -298,6 +434,95 @@ mod tests {
         assert_eq!(frame.snakes[0].eliminated_cause, "wall-collision");
     }
 
+    #[test]
+    fn test_dead_snakes_dropped_after_their_death_turn() {
+        let mut game = create_test_game();
+        game.board.snakes[0].health = 0;
+        let death_info = vec![DeathInfo {
+            snake_id: "snake-1".to_string(),
+            turn: 5,
+            cause: "wall-collision".to_string(),
+            eliminated_by: "".to_string(),
+        }];
+
+        // Still shown on the turn it died
+        game.turn = 5;
+        let frame = game_to_frame(&game, &death_info, &[]).with_dead_snakes(DeadSnakes::Drop);
+        assert_eq!(frame.snakes.len(), 1);
+
+        game.turn = 6;
+        let frame = game_to_frame(&game, &death_info, &[]).with_dead_snakes(DeadSnakes::Drop);
+        assert!(frame.snakes.is_empty());
+
+        // Ghosts stay, faded but still a valid viewer color
+        let frame = game_to_frame(&game, &death_info, &[]).with_dead_snakes(DeadSnakes::Ghost);
+        assert_eq!(frame.snakes.len(), 1);
+        assert_eq!(frame.snakes[0].color, ghost_snake_color("snake-1"));
+        assert_ne!(frame.snakes[0].color, generate_snake_color("snake-1"));
+        assert_eq!(frame.snakes[0].color.len(), 7);
+    }
+
+    #[test]
+    fn test_frame_uses_snake_info() {
+        let game = create_test_game();
+        let infos = HashMap::from([(
+            "snake-1".to_string(),
+            SnakeInfo {
+                apiversion: Some("1".to_string()),
+                author: Some("coreyja".to_string()),
+                color: Some("#ff8800".to_string()),
+                head: Some("smart-caterpillar".to_string()),
+                tail: Some("bolt".to_string()),
+            },
+        )]);
+
+        let frame = game_to_frame(&game, &[], &[]).with_snake_info(&infos);
+        let snake = &frame.snakes[0];
+        assert_eq!(snake.color, "#ff8800");
+        assert_eq!(snake.head_type, "smart-caterpillar");
+        assert_eq!(snake.tail_type, "bolt");
+        assert_eq!(snake.author, "coreyja");
+
+        // A color the viewer can't draw keeps the generated one
+        let infos = HashMap::from([(
+            "snake-1".to_string(),
+            SnakeInfo {
+                color: Some("red".to_string()),
+                ..Default::default()
+            },
+        )]);
+        let frame = game_to_frame(&game, &[], &[]).with_snake_info(&infos);
+        assert_eq!(frame.snakes[0].color, generate_snake_color("snake-1"));
+        assert_eq!(frame.snakes[0].head_type, "default");
+    }
+
+    #[test]
+    fn test_stored_frame_rebuilds_board() {
+        let mut game = create_test_game();
+        game.board.snakes[0].health = 0;
+        let death_info = vec![DeathInfo {
+            snake_id: "snake-1".to_string(),
+            turn: 5,
+            cause: "wall-collision".to_string(),
+            eliminated_by: "".to_string(),
+        }];
+        let json = serde_json::to_value(game_to_frame(&game, &death_info, &[])).unwrap();
+
+        let stored: StoredFrame = serde_json::from_value(json).unwrap();
+        let board = stored.to_board(game.board.width, game.board.height);
+        assert_eq!(board.food, game.board.food);
+        assert_eq!(board.snakes.len(), 1);
+        assert_eq!(board.snakes[0].id, "snake-1");
+        assert_eq!(board.snakes[0].head, game.board.snakes[0].head);
+        assert_eq!(board.snakes[0].body, game.board.snakes[0].body);
+        assert_eq!(board.snakes[0].health, 0);
+
+        let deaths = stored.death_info();
+        assert_eq!(deaths.len(), 1);
+        assert_eq!(deaths[0].turn, 5);
+        assert_eq!(deaths[0].cause, "wall-collision");
+    }
+
     #[test]
     fn test_game_to_frame_with_eliminated_by() {
         let mut game = create_test_game();
@@ -445,8 +670,11 @@ mod tests {
             snake_id: "snake-1".to_string(),
             direction: Move::Up,
             latency_ms: Some(42),
+            compensated_latency_ms: None,
             timed_out: false,
             shout: None,
+            parse_error: None,
+            request_error: None,
         }];
 
         let frame = game_to_frame(&game, &death_info, &move_results);
@@ -465,8 +693,11 @@ mod tests {
             snake_id: "snake-1".to_string(),
             direction: Move::Up,
             latency_ms: None,
+            compensated_latency_ms: None,
             timed_out: true,
             shout: None,
+            parse_error: None,
+            request_error: None,
         }];
 
         let frame = game_to_frame(&game, &death_info, &move_results);
@@ -485,8 +716,11 @@ mod tests {
             snake_id: "snake-1".to_string(),
             direction: Move::Up,
             latency_ms: Some(100),
+            compensated_latency_ms: None,
             timed_out: false,
             shout: Some("Hello from move!".to_string()),
+            parse_error: None,
+            request_error: None,
         }];
 
         let frame = game_to_frame(&game, &death_info, &move_results);
@@ -506,8 +740,11 @@ mod tests {
             snake_id: "snake-1".to_string(),
             direction: Move::Up,
             latency_ms: Some(100),
+            compensated_latency_ms: None,
             timed_out: false,
             shout: None, // No shout in move result
+            parse_error: None,
+            request_error: None,
         }];
 
         let frame = game_to_frame(&game, &death_info, &move_results);
@@ -528,8 +765,11 @@ mod tests {
             snake_id: "other-snake".to_string(),
             direction: Move::Down,
             latency_ms: Some(50),
+            compensated_latency_ms: None,
             timed_out: false,
             shout: None,
+            parse_error: None,
+            request_error: None,
         }];
 
         let frame = game_to_frame(&game, &death_info, &move_results);
@@ -576,4 +816,166 @@ mod tests {
             },
         }
     }
+
+    fn goldens_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/frames")
+    }
+
+    fn snake(id: &str, health: i32, body: &[(i32, i32)]) -> BattleSnake {
+        let body: VecDeque<Position> = body.iter().map(|&(x, y)| Position::new(x, y)).collect();
+        BattleSnake {
+            id: id.to_string(),
+            name: id.to_string(),
+            head: body[0],
+            body,
+            health,
+            shout: None,
+            actual_length: None,
+        }
+    }
+
+    fn board_game(
+        turn: i32,
+        snakes: Vec<BattleSnake>,
+        food: &[(i32, i32)],
+        hazards: &[(i32, i32)],
+    ) -> Game {
+        let mut game = create_test_game();
+        game.turn = turn;
+        game.board.food = food.iter().map(|&(x, y)| Position::new(x, y)).collect();
+        game.board.hazards = hazards.iter().map(|&(x, y)| Position::new(x, y)).collect();
+        game.board.snakes = snakes;
+        game
+    }
+
+    fn death(snake_id: &str, turn: i32, cause: &str, eliminated_by: &str) -> DeathInfo {
+        DeathInfo {
+            snake_id: snake_id.to_string(),
+            turn,
+            cause: cause.to_string(),
+            eliminated_by: eliminated_by.to_string(),
+        }
+    }
+
+    fn result(
+        snake_id: &str,
+        latency_ms: Option<i64>,
+        timed_out: bool,
+        shout: Option<&str>,
+    ) -> MoveResult {
+        MoveResult {
+            snake_id: snake_id.to_string(),
+            direction: Move::Up,
+            latency_ms,
+            compensated_latency_ms: None,
+            timed_out,
+            shout: shout.map(str::to_string),
+            parse_error: None,
+            request_error: None,
+        }
+    }
+
+    /// Representative frames, by the name of their golden file
+    fn golden_frames() -> Vec<(&'static str, EngineGameFrame)> {
+        // Turn 0: bodies stacked on the starting squares, no moves made yet
+        let opening = board_game(
+            0,
+            vec![
+                snake("alpha", 100, &[(1, 1), (1, 1), (1, 1)]),
+                snake("bravo", 100, &[(9, 9), (9, 9), (9, 9)]),
+            ],
+            &[(0, 2), (10, 8), (5, 5)],
+            &[],
+        );
+
+        // A head-to-head on this turn, an earlier wall collision, a shout and a timeout
+        let mut midgame = board_game(
+            37,
+            vec![
+                snake("alpha", 88, &[(4, 5), (4, 4), (3, 4), (3, 3), (3, 2)]),
+                snake("bravo", 61, &[(7, 7), (7, 6), (8, 6)]),
+                snake("charlie", 0, &[(4, 5), (5, 5), (6, 5)]),
+                snake("delta", 0, &[(0, 3), (0, 2), (0, 1)]),
+            ],
+            &[(1, 9), (10, 0)],
+            &[],
+        );
+        midgame.board.snakes[1].shout = Some("stale shout".to_string());
+        let midgame_deaths = [
+            death("charlie", 37, "head-collision", "alpha"),
+            death("delta", 20, "wall-collision", ""),
+        ];
+        let midgame_results = [
+            result("alpha", Some(42), false, Some("coming through")),
+            result("bravo", Some(500), true, None),
+        ];
+
+        // Shrunk to a safe area in the top right, one snake starved in the hazards
+        let hazards: Vec<(i32, i32)> = (0..11)
+            .flat_map(|y| (0..11).map(move |x| (x, y)))
+            .filter(|&(x, y)| x < 3 || y < 2)
+            .collect();
+        let mut royale = board_game(
+            112,
+            vec![
+                snake("alpha", 23, &[(5, 6), (5, 7), (6, 7), (7, 7)]),
+                snake("bravo", 0, &[(1, 4), (1, 5), (1, 6)]),
+            ],
+            &[(8, 8)],
+            &hazards,
+        );
+        royale.board.snakes[0].shout = Some("last one standing".to_string());
+        let royale_deaths = [death("bravo", 104, "out-of-health", "")];
+
+        vec![
+            ("opening", game_to_frame(&opening, &[], &[])),
+            (
+                "midgame",
+                game_to_frame(&midgame, &midgame_deaths, &midgame_results),
+            ),
+            ("royale", game_to_frame(&royale, &royale_deaths, &[])),
+        ]
+    }
+
+    /// The board viewer reads frames exactly as `game_to_frame` writes them, so
+    /// any change to key names, key order, or what's written for missing values
+    /// has to be made on purpose. Run with `UPDATE_GOLDENS=1` to rewrite the
+    /// files in `fixtures/frames` after an intended change, and review the diff.
+    #[test]
+    fn test_frames_match_goldens() {
+        let update = std::env::var("UPDATE_GOLDENS").is_ok_and(|v| v == "1");
+
+        for (name, frame) in golden_frames() {
+            let path = goldens_dir().join(format!("{}.json", name));
+            let actual = serde_json::to_string_pretty(&frame).unwrap() + "\n";
+            if update {
+                std::fs::write(&path, &actual).unwrap();
+                continue;
+            }
+
+            let expected = std::fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+            if actual != expected {
+                let lines = expected.lines().count().max(actual.lines().count());
+                let (line, (want, got)) = expected
+                    .lines()
+                    .chain(std::iter::repeat("<end of file>"))
+                    .zip(actual.lines().chain(std::iter::repeat("<end of file>")))
+                    .take(lines)
+                    .enumerate()
+                    .find(|(_, (want, got))| want != got)
+                    .unwrap_or((lines, ("<line endings differ>", "")));
+                panic!(
+                    "Frame {} no longer matches {}, which the board viewer depends on.\n\
+                     First difference on line {}:\n  expected: {}\n  actual:   {}\n\
+                     If the change is intended, rerun with UPDATE_GOLDENS=1 and commit the new file.",
+                    name,
+                    path.display(),
+                    line + 1,
+                    want,
+                    got
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,256 @@
+//! Playing turns on boards built from arbitrary bytes
+//!
+//! [`play`] turns whatever bytes it's given into a board and a few turns of
+//! moves, plays them with [`apply_seeded_turn`](super::rules::apply_seeded_turn)'s
+//! rules and checks the results still make sense. The cargo-fuzz target in
+//! `fuzz/` feeds it libFuzzer's inputs, and `test_bounded_fuzz` feeds it
+//! random bytes for a fixed number of rounds, so `cargo test` covers the same
+//! ground in a few seconds.
+//!
+//! Boards skip every check game creation makes: heads off the board or away
+//! from their bodies, bodies that overlap, empty bodies, food and hazards
+//! stacked on one square or under snakes, and moves for snakes that don't
+//! exist, are already out or are listed twice. Only what the engine can never
+//! be given is ruled out: boards with no squares, duplicate snake IDs, and
+//! turns past the turn limit.
+
+use std::collections::{HashSet, VecDeque};
+
+use battlesnake_game_types::types::Move;
+use battlesnake_game_types::wire_representation::{
+    BattleSnake, Board, Game, NestedGame, Position, RoyaleSettings, Ruleset, Settings,
+};
+
+use super::rules::{Elimination, MAX_TURNS, SNAKE_MAX_HEALTH, apply_turn_with_eliminations};
+
+/// Largest board side, past the official 25x25 boards
+const MAX_SIDE: u8 = 25;
+const MAX_SNAKES: u8 = 8;
+const MAX_BODY: u8 = 8;
+const MAX_TURNS_PLAYED: u8 = 16;
+
+const RULESETS: [&str; 5] = ["standard", "royale", "constrictor", "snail_mode", "wrapped"];
+
+/// Reads the input a byte at a time, as zeros once it runs out
+struct Input<'a>(&'a [u8]);
+
+impl Input<'_> {
+    fn byte(&mut self) -> u8 {
+        match self.0.split_first() {
+            Some((&byte, rest)) => {
+                self.0 = rest;
+                byte
+            }
+            None => 0,
+        }
+    }
+
+    fn below(&mut self, n: u8) -> u8 {
+        self.byte() % n
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_le_bytes([self.byte(), self.byte()])
+    }
+
+    /// A coordinate up to two squares past either edge of a `side` square wide board
+    fn coordinate(&mut self, side: u8) -> i32 {
+        i32::from(self.below(side + 4)) - 2
+    }
+
+    fn position(&mut self, width: u8, height: u8) -> Position {
+        Position::new(self.coordinate(width), self.coordinate(height))
+    }
+
+    fn positions(&mut self, width: u8, height: u8, max: u8) -> Vec<Position> {
+        let count = self.below(max + 1);
+        (0..count).map(|_| self.position(width, height)).collect()
+    }
+
+    fn direction(&mut self) -> Move {
+        match self.below(4) {
+            0 => Move::Up,
+            1 => Move::Down,
+            2 => Move::Left,
+            _ => Move::Right,
+        }
+    }
+}
+
+/// Build a board and moves from `data`, play them, and panic if the engine
+/// panics or breaks one of the rules' invariants
+pub fn play(data: &[u8]) {
+    let mut input = Input(data);
+    let mut game = game(&mut input);
+
+    for _ in 0..=input.below(MAX_TURNS_PLAYED) {
+        let moves: Vec<(String, Move)> = (0..input.below(MAX_SNAKES * 2))
+            .map(|_| {
+                let id = format!("snake-{}", input.below(MAX_SNAKES + 2));
+                (id, input.direction())
+            })
+            .collect();
+        let seed = u64::from(input.u16());
+
+        let before = game.board.snakes.clone();
+        let (next, eliminations) = apply_turn_with_eliminations(game, &moves, seed);
+        check_turn(&before, &next.board.snakes, &eliminations);
+        game = next;
+        game.turn += 1;
+    }
+}
+
+fn game(input: &mut Input) -> Game {
+    let width = 1 + input.below(MAX_SIDE);
+    let height = 1 + input.below(MAX_SIDE);
+
+    let snakes: Vec<BattleSnake> = (0..input.below(MAX_SNAKES + 1))
+        .map(|i| {
+            let body: VecDeque<Position> = input.positions(width, height, MAX_BODY).into();
+            let head = match input.below(4) {
+                // Usually where a snake's head is, but not always
+                0 => input.position(width, height),
+                _ => body.front().copied().unwrap_or(Position::new(0, 0)),
+            };
+            BattleSnake {
+                id: format!("snake-{}", i),
+                name: format!("Snake {}", i),
+                head,
+                body,
+                health: i32::from(input.below(SNAKE_MAX_HEALTH as u8 + 1)),
+                shout: None,
+                actual_length: None,
+            }
+        })
+        .collect();
+
+    let food = input.positions(width, height, 12);
+    let hazards = input.positions(width, height, 12);
+
+    let name = RULESETS[usize::from(input.below(RULESETS.len() as u8))];
+    let settings = (input.below(4) != 0).then(|| Settings {
+        food_spawn_chance: i32::from(input.below(101)),
+        minimum_food: i32::from(input.below(6)),
+        hazard_damage_per_turn: i32::from(input.below(101)),
+        hazard_map: None,
+        hazard_map_author: None,
+        royale: (input.below(2) == 0).then(|| RoyaleSettings {
+            shrink_every_n_turns: i32::from(input.below(30)),
+        }),
+    });
+
+    let you = snakes.first().cloned().unwrap_or_else(|| BattleSnake {
+        id: "dummy".to_string(),
+        name: "Dummy".to_string(),
+        head: Position::new(0, 0),
+        body: VecDeque::new(),
+        health: 0,
+        shout: None,
+        actual_length: None,
+    });
+
+    Game {
+        you,
+        board: Board {
+            height: height.into(),
+            width: width.into(),
+            food,
+            snakes,
+            hazards,
+        },
+        turn: i32::from(input.u16() % MAX_TURNS as u16),
+        game: NestedGame {
+            id: format!("fuzz-{}", input.byte()),
+            ruleset: Ruleset {
+                name: name.to_string(),
+                version: "v1.0.0".to_string(),
+                settings,
+            },
+            timeout: 500,
+            map: None,
+            source: None,
+        },
+    }
+}
+
+/// Panic if a turn broke one of the rules' invariants
+fn check_turn(before: &[BattleSnake], after: &[BattleSnake], eliminations: &[Elimination]) {
+    assert_eq!(before.len(), after.len(), "snakes were added or removed");
+
+    let mut eliminated = HashSet::new();
+    for elimination in eliminations {
+        assert!(
+            eliminated.insert(elimination.snake_id.as_str()),
+            "{} was eliminated twice",
+            elimination.snake_id
+        );
+        let was_alive = before
+            .iter()
+            .any(|s| s.id == elimination.snake_id && s.health > 0);
+        assert!(
+            was_alive,
+            "{} was out before it was eliminated",
+            elimination.snake_id
+        );
+    }
+
+    for (old, new) in before.iter().zip(after) {
+        assert_eq!(old.id, new.id, "snakes were reordered");
+        assert!(
+            (0..=SNAKE_MAX_HEALTH).contains(&new.health),
+            "{} has {} health",
+            new.id,
+            new.health
+        );
+        if old.health <= 0 {
+            assert_eq!(old.body, new.body, "{} moved while out", old.id);
+            continue;
+        }
+
+        assert_eq!(
+            new.health <= 0,
+            eliminated.contains(new.id.as_str()),
+            "{} went out without an elimination, or the other way around",
+            new.id
+        );
+        let grown = new.body.len().saturating_sub(old.body.len());
+        assert!(
+            new.body.len() >= old.body.len() && grown <= 1,
+            "{} went from length {} to {}",
+            new.id,
+            old.body.len(),
+            new.body.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    /// Random inputs `test_bounded_fuzz` plays, unless `ARENA_FUZZ_ITERATIONS` says otherwise
+    const DEFAULT_ITERATIONS: usize = 2000;
+
+    #[test]
+    fn test_bounded_fuzz() {
+        let iterations = std::env::var("ARENA_FUZZ_ITERATIONS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_ITERATIONS);
+
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        for _ in 0..iterations {
+            let len = rng.gen_range(0..512);
+            let data: Vec<u8> = (0..len).map(|_| rng.r#gen()).collect();
+            play(&data);
+        }
+    }
+
+    #[test]
+    fn test_play_handles_empty_and_short_inputs() {
+        play(&[]);
+        play(&[0xff]);
+        play(&[0xff; 64]);
+    }
+}
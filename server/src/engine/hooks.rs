@@ -0,0 +1,181 @@
+//! Hooks for features that react to games as they're played
+//!
+//! The game runner tells every registered [`EngineHook`] when a game starts,
+//! after each turn, when a snake is eliminated and when the game ends, with
+//! the engine's own state. Features subscribe here rather than polling the
+//! database for games that finished since they last looked.
+//!
+//! Hooks run in the runner, in registration order, so they should be quick;
+//! anything slow belongs in a job the hook enqueues. A hook's error is logged
+//! and never stops the game or the other hooks.
+
+use std::sync::Arc;
+
+use battlesnake_game_types::wire_representation::Game;
+use uuid::Uuid;
+
+use crate::models::game_battlesnake::GameBattlesnakeWithDetails;
+use crate::snake_client::MoveResult;
+use crate::state::AppState;
+
+/// A game about to play its first turn
+pub struct GameStarted<'a> {
+    pub game_id: Uuid,
+    pub game: &'a Game,
+    pub snakes: &'a [GameBattlesnakeWithDetails],
+}
+
+/// A turn that's been applied and stored
+pub struct TurnApplied<'a> {
+    pub game_id: Uuid,
+    /// The board after the turn, with `turn` already advanced
+    pub game: &'a Game,
+    pub moves: &'a [MoveResult],
+}
+
+/// A snake eliminated on a turn
+pub struct Eliminated<'a> {
+    pub game_id: Uuid,
+    pub turn: i32,
+    /// The snake's game_battlesnake_id
+    pub snake_id: &'a str,
+    pub cause: &'a str,
+    /// The game_battlesnake_id of the snake it hit, if it hit one
+    pub eliminated_by: &'a str,
+}
+
+/// A game whose results have been stored
+pub struct GameEnded<'a> {
+    pub game_id: Uuid,
+    pub game: &'a Game,
+    pub snakes: &'a [GameBattlesnakeWithDetails],
+    /// (game_battlesnake_id, placement), best first
+    pub placements: &'a [(String, i32)],
+}
+
+/// Something that wants to know what happens in games as they're played
+///
+/// Every callback does nothing by default, so a hook implements only the
+/// ones it cares about.
+#[async_trait::async_trait]
+pub trait EngineHook: Send + Sync {
+    /// Shown in logs when the hook fails
+    fn name(&self) -> &'static str;
+
+    async fn on_game_start(
+        &self,
+        _app_state: &AppState,
+        _event: &GameStarted<'_>,
+    ) -> cja::Result<()> {
+        Ok(())
+    }
+
+    async fn on_turn_applied(
+        &self,
+        _app_state: &AppState,
+        _event: &TurnApplied<'_>,
+    ) -> cja::Result<()> {
+        Ok(())
+    }
+
+    async fn on_elimination(
+        &self,
+        _app_state: &AppState,
+        _event: &Eliminated<'_>,
+    ) -> cja::Result<()> {
+        Ok(())
+    }
+
+    async fn on_game_end(&self, _app_state: &AppState, _event: &GameEnded<'_>) -> cja::Result<()> {
+        Ok(())
+    }
+}
+
+/// The hooks the game runner calls, in the order they were registered
+#[derive(Clone, Default)]
+pub struct EngineHooks {
+    hooks: Vec<Arc<dyn EngineHook>>,
+}
+
+impl EngineHooks {
+    /// The hooks built into the arena
+    pub fn builtin() -> Self {
+        let mut hooks = Self::default();
+        hooks.register(crate::domain_events::DomainEventsHook);
+        hooks.register(crate::alerts::AlertsHook);
+        hooks.register(crate::notifications::NotificationsHook);
+        hooks.register(crate::replay_gif::ReplayGifHook);
+        hooks.register(crate::mirror::MirrorSeriesHook);
+        hooks
+    }
+
+    pub fn register(&mut self, hook: impl EngineHook + 'static) {
+        self.hooks.push(Arc::new(hook));
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.hooks.iter().map(|hook| hook.name()).collect()
+    }
+
+    pub async fn game_started(&self, app_state: &AppState, event: &GameStarted<'_>) {
+        for hook in &self.hooks {
+            if let Err(e) = hook.on_game_start(app_state, event).await {
+                log_failure(hook.name(), "on_game_start", event.game_id, &e);
+            }
+        }
+    }
+
+    pub async fn turn_applied(&self, app_state: &AppState, event: &TurnApplied<'_>) {
+        for hook in &self.hooks {
+            if let Err(e) = hook.on_turn_applied(app_state, event).await {
+                log_failure(hook.name(), "on_turn_applied", event.game_id, &e);
+            }
+        }
+    }
+
+    pub async fn eliminated(&self, app_state: &AppState, event: &Eliminated<'_>) {
+        for hook in &self.hooks {
+            if let Err(e) = hook.on_elimination(app_state, event).await {
+                log_failure(hook.name(), "on_elimination", event.game_id, &e);
+            }
+        }
+    }
+
+    pub async fn game_ended(&self, app_state: &AppState, event: &GameEnded<'_>) {
+        for hook in &self.hooks {
+            if let Err(e) = hook.on_game_end(app_state, event).await {
+                log_failure(hook.name(), "on_game_end", event.game_id, &e);
+            }
+        }
+    }
+}
+
+fn log_failure(hook: &str, callback: &str, game_id: Uuid, error: &cja::color_eyre::Report) {
+    tracing::error!(
+        hook,
+        callback,
+        game_id = %game_id,
+        error = ?error,
+        "Engine hook failed"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_hooks() {
+        assert_eq!(
+            EngineHooks::builtin().names(),
+            vec![
+                "domain_events",
+                "alerts",
+                "notifications",
+                "replay_gif",
+                "mirror_series"
+            ]
+        );
+        assert!(EngineHooks::default().names().is_empty());
+    }
+}
@@ -4,38 +4,82 @@
 //! It uses the wire representation types directly for simplicity.
 
 pub mod frame;
+pub mod fuzz;
+pub mod hooks;
+pub mod move_provider;
+pub mod rules;
+pub mod wasm_ruleset;
 
-use battlesnake_game_types::types::{Move, RandomReasonableMovesGame};
+use battlesnake_game_types::types::Move;
 use battlesnake_game_types::wire_representation::{
-    BattleSnake, Board, Game, NestedGame, Position, Ruleset, Settings,
+    BattleSnake, Board, Game, NestedGame, Position, RoyaleSettings, Ruleset, Settings,
 };
 use rand::Rng;
 use rand::seq::SliceRandom;
-use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use uuid::Uuid;
 
-use crate::models::game::{GameBoardSize, GameType};
-use crate::models::game_battlesnake::GameBattlesnakeWithDetails;
+use crate::models::game::{
+    GameBoardSize, GameType, MoveValidationMode, SpawnPoint, SpawnStrategy, TurnLimitTiebreak,
+};
+use crate::models::game_battlesnake::{GameBattlesnakeWithDetails, snake_instances};
+use frame::DeathInfo;
+use move_provider::{BuiltInBot, MoveProviders, all_bots, request_moves};
+use rules::{HAZARD_DAMAGE_PER_TURN, SNAKE_MAX_HEALTH};
+
+pub(crate) use rules::turn_rng;
+pub use rules::{
+    Elimination, MAX_TURNS, ROYALE_SHRINK_EVERY_N_TURNS, apply_turn_with_eliminations, engine_seed,
+};
 
-const SNAKE_MAX_HEALTH: i32 = 100;
 const SNAKE_START_SIZE: usize = 3;
-pub const MAX_TURNS: i32 = 5000;
+
+pub use crate::game_settings::DEFAULT_MOVE_TIMEOUT_MS;
+
+/// Percent chance of a piece of food spawning on a turn with enough food already
+const FOOD_SPAWN_CHANCE: i32 = 15;
+
+/// Food kept on the board, spawning more as it's eaten
+const MINIMUM_FOOD: i32 = 1;
 
 /// Result of running a game
 #[derive(Debug)]
 pub struct GameResult {
     /// Snake IDs in order of placement (index 0 = winner/last alive)
     pub placements: Vec<String>,
+    /// More than one snake shares first place, so nobody won: the last snakes
+    /// were eliminated on the same turn, or the turn limit tiebreak couldn't
+    /// separate the survivors
+    pub draw: bool,
     /// Final turn number
     pub final_turn: i32,
+    /// Board at the end of the game
+    pub final_board: Board,
+}
+
+impl Elimination {
+    /// The death the frames show for this elimination on `turn`
+    pub fn death_info(&self, turn: i32) -> DeathInfo {
+        DeathInfo {
+            snake_id: self.snake_id.clone(),
+            turn,
+            cause: self.cause.to_string(),
+            eliminated_by: self.eliminated_by.clone(),
+        }
+    }
 }
 
 /// Create the initial game state from database models
+///
+/// Random spawns and the starting food are drawn from `seed` (see [`engine_seed`]).
 pub fn create_initial_game(
     game_id: Uuid,
     board_size: GameBoardSize,
     game_type: GameType,
     battlesnakes: &[GameBattlesnakeWithDetails],
+    spawn: &SpawnStrategy,
+    seed: u64,
 ) -> Game {
     let (width, height) = match board_size {
         GameBoardSize::Small => (7, 7),
@@ -43,19 +87,14 @@ pub fn create_initial_game(
         GameBoardSize::Large => (19, 19),
     };
 
-    let ruleset_name = match game_type {
-        GameType::Standard => "standard",
-        GameType::Royale => "royale",
-        GameType::Constrictor => "constrictor",
-        GameType::SnailMode => "snail_mode",
-    };
-
     // Generate spawn positions
-    let spawn_positions = generate_spawn_positions(width, height, battlesnakes.len());
+    let spawn_positions = generate_spawn_positions(width, height, battlesnakes.len(), spawn, seed);
 
     // Create snakes at spawn positions
     // Use game_battlesnake_id as the snake ID to ensure uniqueness when the same
-    // battlesnake appears multiple times in a game (duplicate snakes)
+    // battlesnake appears multiple times in a game (duplicate snakes), and number
+    // the names of duplicates so the viewer can tell them apart
+    let instances = snake_instances(battlesnakes);
     let snakes: Vec<BattleSnake> = battlesnakes
         .iter()
         .zip(spawn_positions.iter())
@@ -63,7 +102,10 @@ pub fn create_initial_game(
             let body: VecDeque<Position> = (0..SNAKE_START_SIZE).map(|_| *pos).collect();
             BattleSnake {
                 id: bs.game_battlesnake_id.to_string(),
-                name: bs.name.clone(),
+                name: instances
+                    .get(&bs.game_battlesnake_id)
+                    .map(|i| i.display_name.clone())
+                    .unwrap_or_else(|| bs.name.clone()),
                 head: *pos,
                 body,
                 health: SNAKE_MAX_HEALTH,
@@ -74,78 +116,177 @@ pub fn create_initial_game(
         .collect();
 
     // Place initial food - one near each snake plus center
-    let food = generate_initial_food(width, height, &snakes);
+    let food = generate_initial_food(width, height, &snakes, seed);
 
     let board = Board {
         height: height as u32,
         width: width as u32,
         food,
-        snakes: snakes.clone(),
+        snakes,
         hazards: vec![],
     };
 
-    // Use first snake as "you" (arbitrary for simulation purposes)
-    let you = snakes.first().cloned().unwrap_or_else(|| BattleSnake {
-        id: "dummy".to_string(),
-        name: "Dummy".to_string(),
-        head: Position::new(0, 0),
-        body: VecDeque::new(),
-        health: 0,
-        shout: None,
-        actual_length: None,
+    create_game_from_board(game_id, game_type, board)
+}
+
+/// The ruleset a game type is played with, as sent to snakes
+pub fn game_ruleset(game_type: GameType) -> Ruleset {
+    let name = match game_type {
+        GameType::Standard => "standard",
+        GameType::Royale => "royale",
+        GameType::Constrictor => "constrictor",
+        GameType::SnailMode => "snail_mode",
+        GameType::Wrapped => "wrapped",
+    };
+    let royale = (game_type == GameType::Royale).then_some(RoyaleSettings {
+        shrink_every_n_turns: ROYALE_SHRINK_EVERY_N_TURNS,
     });
 
+    Ruleset {
+        name: name.to_string(),
+        version: "v1.0.0".to_string(),
+        settings: Some(Settings {
+            food_spawn_chance: FOOD_SPAWN_CHANCE,
+            minimum_food: MINIMUM_FOOD,
+            hazard_damage_per_turn: HAZARD_DAMAGE_PER_TURN,
+            hazard_map: None,
+            hazard_map_author: None,
+            royale,
+        }),
+    }
+}
+
+/// Create a game starting from a given board, e.g. one recorded from an earlier game
+pub fn create_game_from_board(game_id: Uuid, game_type: GameType, board: Board) -> Game {
+    // Use first snake as "you" (arbitrary for simulation purposes)
+    let you = board
+        .snakes
+        .first()
+        .cloned()
+        .unwrap_or_else(|| BattleSnake {
+            id: "dummy".to_string(),
+            name: "Dummy".to_string(),
+            head: Position::new(0, 0),
+            body: VecDeque::new(),
+            health: 0,
+            shout: None,
+            actual_length: None,
+        });
+
     Game {
         you,
         board,
         turn: 0,
         game: NestedGame {
             id: game_id.to_string(),
-            ruleset: Ruleset {
-                name: ruleset_name.to_string(),
-                version: "v1.0.0".to_string(),
-                settings: Some(Settings {
-                    food_spawn_chance: 15,
-                    minimum_food: 1,
-                    hazard_damage_per_turn: 15,
-                    hazard_map: None,
-                    hazard_map_author: None,
-                    royale: None,
-                }),
-            },
-            timeout: 500,
+            ruleset: game_ruleset(game_type),
+            timeout: DEFAULT_MOVE_TIMEOUT_MS as _,
             map: None,
             source: None,
         },
     }
 }
 
-/// Generate spawn positions using the official Battlesnake algorithm
-/// For <=8 snakes on boards >=7x7, uses fixed corner/cardinal positions
-fn generate_spawn_positions(width: i32, _height: i32, num_snakes: usize) -> Vec<Position> {
-    let mut rng = rand::thread_rng();
-
+/// The official corner and cardinal spawn points for a board
+///
+/// Ordered so that any prefix is spread evenly: opposite corners first, then
+/// the other two corners, and the same for the cardinal points.
+fn spawn_points(width: i32) -> (Vec<Position>, Vec<Position>) {
     // mn = 1, md = (width-1)/2, mx = width-2
     let mn = 1;
     let md = (width - 1) / 2;
     let mx = width - 2;
 
-    // Corner positions
-    let mut corner_points = vec![
+    let corner_points = vec![
         Position::new(mn, mn),
+        Position::new(mx, mx),
         Position::new(mn, mx),
         Position::new(mx, mn),
-        Position::new(mx, mx),
     ];
-
-    // Cardinal positions (edges)
-    let mut cardinal_points = vec![
+    let cardinal_points = vec![
         Position::new(mn, md),
+        Position::new(mx, md),
         Position::new(md, mn),
         Position::new(md, mx),
-        Position::new(mx, md),
     ];
 
+    (corner_points, cardinal_points)
+}
+
+/// Check a scenario's spawn positions: one per snake, on the board and distinct
+pub fn validate_spawn_points(
+    width: i32,
+    height: i32,
+    num_snakes: usize,
+    points: &[SpawnPoint],
+) -> Result<(), String> {
+    if points.len() != num_snakes {
+        return Err(format!(
+            "Expected {} spawn positions, one per snake, got {}",
+            num_snakes,
+            points.len()
+        ));
+    }
+    for (i, p) in points.iter().enumerate() {
+        if p.x < 0 || p.x >= width || p.y < 0 || p.y >= height {
+            return Err(format!(
+                "Spawn position ({}, {}) is off the board",
+                p.x, p.y
+            ));
+        }
+        if points[..i].contains(p) {
+            return Err(format!("Spawn position ({}, {}) is used twice", p.x, p.y));
+        }
+    }
+    Ok(())
+}
+
+/// Generate spawn positions for `num_snakes` snakes, in the order they joined
+fn generate_spawn_positions(
+    width: i32,
+    height: i32,
+    num_snakes: usize,
+    spawn: &SpawnStrategy,
+    seed: u64,
+) -> Vec<Position> {
+    match spawn {
+        SpawnStrategy::Random => random_spawn_positions(width, num_snakes, seed),
+        SpawnStrategy::Fixed { rotation } => fixed_spawn_positions(width, num_snakes, *rotation),
+        SpawnStrategy::Manual(points)
+            if validate_spawn_points(width, height, num_snakes, points).is_ok() =>
+        {
+            points.iter().map(|p| Position::new(p.x, p.y)).collect()
+        }
+        SpawnStrategy::Manual(_) => {
+            tracing::warn!("Invalid manual spawn positions, using fixed spawns instead");
+            fixed_spawn_positions(width, num_snakes, 0)
+        }
+    }
+}
+
+/// The same spawn points every game, corners first, with each snake moved
+/// `rotation` points along
+///
+/// Rotating by one each game of a series cycles every snake through every
+/// start, so no snake keeps a spawn advantage.
+fn fixed_spawn_positions(width: i32, num_snakes: usize, rotation: i32) -> Vec<Position> {
+    let (mut start_points, cardinal_points) = spawn_points(width);
+    start_points.extend(cardinal_points);
+    start_points.truncate(num_snakes);
+
+    if !start_points.is_empty() {
+        let shift = rotation.rem_euclid(start_points.len() as i32) as usize;
+        start_points.rotate_left(shift);
+    }
+    start_points
+}
+
+/// Spawn positions using the official Battlesnake algorithm
+/// For <=8 snakes on boards >=7x7, uses fixed corner/cardinal positions
+fn random_spawn_positions(width: i32, num_snakes: usize, seed: u64) -> Vec<Position> {
+    let mut rng = turn_rng(seed, 0, "spawn");
+    let (mut corner_points, mut cardinal_points) = spawn_points(width);
+
     // Shuffle both lists
     corner_points.shuffle(&mut rng);
     cardinal_points.shuffle(&mut rng);
@@ -167,8 +308,13 @@ fn generate_spawn_positions(width: i32, _height: i32, num_snakes: usize) -> Vec<
 }
 
 /// Generate initial food positions
-fn generate_initial_food(width: i32, height: i32, snakes: &[BattleSnake]) -> Vec<Position> {
-    let mut rng = rand::thread_rng();
+fn generate_initial_food(
+    width: i32,
+    height: i32,
+    snakes: &[BattleSnake],
+    seed: u64,
+) -> Vec<Position> {
+    let mut rng = turn_rng(seed, 0, "initial-food");
     let mut food: Vec<Position> = Vec::new();
     let center = Position::new((width - 1) / 2, (height - 1) / 2);
 
@@ -212,197 +358,147 @@ fn generate_initial_food(width: i32, height: i32, snakes: &[BattleSnake]) -> Vec
 }
 
 /// Run a complete game with random moves, returning placements
-pub fn run_game_with_random_moves(mut game: Game) -> GameResult {
-    let mut rng = rand::thread_rng();
-    let mut elimination_order: Vec<String> = Vec::new();
+pub fn run_game_with_random_moves(game: Game) -> GameResult {
+    let seed = engine_seed(&game.game.id, None);
+    let providers = all_bots(&game, BuiltInBot::Random, seed);
 
-    while !is_game_over(&game) && game.turn < MAX_TURNS {
-        // Get random reasonable moves for each alive snake
-        let moves: Vec<(String, Move)> = game
-            .random_reasonable_move_for_each_snake(&mut rng)
-            .collect();
+    // Built-in bots never await anything, so there's no need for a runtime
+    futures::executor::block_on(simulate_game(game, &providers, seed))
+}
 
-        // Apply the moves
-        game = apply_turn(game, &moves);
-        game.turn += 1;
+/// Food eaten by each snake over a game, for the most-food tiebreak
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct FoodTally(HashMap<String, u32>);
 
-        // Track newly eliminated snakes
-        for snake in &game.board.snakes {
-            if snake.health <= 0 && !elimination_order.contains(&snake.id) {
-                elimination_order.push(snake.id.clone());
+impl FoodTally {
+    /// Count the food eaten on a turn, given the food on the board before it
+    pub fn record_turn(&mut self, food_before: &[Position], game: &Game) {
+        for snake in game.board.snakes.iter().filter(|s| s.health > 0) {
+            if food_before.contains(&snake.head) {
+                *self.0.entry(snake.id.clone()).or_default() += 1;
             }
         }
     }
 
-    // Build placements: last eliminated = winner (placement 1)
-    // Snakes still alive at the end go first
-    let mut placements: Vec<String> = game
+    pub fn eaten(&self, snake_id: &str) -> u32 {
+        self.0.get(snake_id).copied().unwrap_or_default()
+    }
+}
+
+/// Each snake's placement once a game is over, best first
+///
+/// Snakes still alive go first, then eliminated snakes, last eliminated first.
+/// `eliminated` holds each eliminated snake with the turn it went out on, in
+/// the order they went out. Snakes eliminated on the same turn share a
+/// placement, so when the last snakes die together they share first place: a
+/// draw. More than one snake alive means the turn limit ended the game, so
+/// `tiebreak` orders the survivors, and survivors it can't separate share a
+/// placement.
+pub fn final_placements(
+    game: &Game,
+    eliminated: &[(String, i32)],
+    food: &FoodTally,
+    tiebreak: TurnLimitTiebreak,
+) -> Vec<(String, i32)> {
+    // Higher scores place higher
+    let score = |snake: &BattleSnake| match tiebreak {
+        TurnLimitTiebreak::Longest => snake.body.len() as u32,
+        TurnLimitTiebreak::MostFood => food.eaten(&snake.id),
+        TurnLimitTiebreak::Draw => 0,
+    };
+    let mut survivors: Vec<(u32, &str)> = game
         .board
         .snakes
         .iter()
         .filter(|s| s.health > 0)
-        .map(|s| s.id.clone())
+        .map(|s| (score(s), s.id.as_str()))
         .collect();
+    survivors.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+
+    let mut placements: Vec<(String, i32)> = Vec::new();
+    for (i, &(score, id)) in survivors.iter().enumerate() {
+        let placement = match placements.last() {
+            Some(&(_, previous)) if survivors[i - 1].0 == score => previous,
+            _ => i as i32 + 1,
+        };
+        placements.push((id.to_string(), placement));
+    }
 
-    // Then add eliminated snakes in reverse order (last eliminated = better placement)
-    elimination_order.reverse();
-    placements.extend(elimination_order);
-
-    GameResult {
-        placements,
-        final_turn: game.turn,
+    let mut previous_turn = None;
+    for (id, turn) in eliminated.iter().rev() {
+        let placement = match placements.last() {
+            Some(&(_, previous)) if previous_turn == Some(*turn) => previous,
+            _ => placements.len() as i32 + 1,
+        };
+        placements.push((id.clone(), placement));
+        previous_turn = Some(*turn);
     }
+    placements
 }
 
-/// Check if the game is over (1 or fewer snakes alive)
-fn is_game_over(game: &Game) -> bool {
-    let alive_count = game.board.snakes.iter().filter(|s| s.health > 0).count();
-    alive_count <= 1
+/// Whether more than one snake shares first place
+pub fn is_draw(placements: &[(String, i32)]) -> bool {
+    placements.iter().filter(|(_, p)| *p == 1).count() > 1
 }
 
-/// Apply a single turn: move snakes, reduce health, feed, eliminate
-pub fn apply_turn(mut game: Game, moves: &[(String, Move)]) -> Game {
-    // 1. Move snakes
-    for snake in &mut game.board.snakes {
-        if snake.health <= 0 {
-            continue;
-        }
+/// Run a complete game in memory with the given move providers, returning placements
+pub async fn simulate_game(mut game: Game, providers: &MoveProviders, seed: u64) -> GameResult {
+    let mut eliminated: Vec<(String, i32)> = Vec::new();
+    let mut last_moves: HashMap<String, Move> = HashMap::new();
+    let mut food = FoodTally::default();
 
-        // Find the move for this snake
-        let snake_move = moves
+    while !is_game_over(&game) && game.turn < MAX_TURNS {
+        let results = request_moves(
+            providers,
+            &game,
+            &last_moves,
+            MoveValidationMode::Lenient,
+            &HashMap::new(),
+        )
+        .await;
+        let moves: Vec<(String, Move)> = results
             .iter()
-            .find(|(id, _)| id == &snake.id)
-            .map(|(_, m)| *m)
-            .unwrap_or(Move::Up);
-
-        // Calculate new head position
-        let new_head = snake.head.add_vec(snake_move.to_vector());
-
-        // Move: add new head, remove tail
-        snake.body.push_front(new_head);
-        snake.body.pop_back();
-        snake.head = new_head;
-    }
-
-    // 2. Reduce health
-    for snake in &mut game.board.snakes {
-        if snake.health > 0 {
-            snake.health -= 1;
-        }
-    }
-
-    // 3. Feed snakes (before elimination check)
-    let mut eaten_food = Vec::new();
-    for snake in &mut game.board.snakes {
-        if snake.health <= 0 {
-            continue;
-        }
+            .map(|r| (r.snake_id.clone(), r.direction))
+            .collect();
+        last_moves.extend(moves.iter().cloned());
 
-        // Check if head is on food
-        if let Some(food_idx) = game.board.food.iter().position(|f| *f == snake.head) {
-            // Eat the food
-            eaten_food.push(food_idx);
-            snake.health = SNAKE_MAX_HEALTH;
-            // Grow by duplicating tail
-            if let Some(tail) = snake.body.back().copied() {
-                snake.body.push_back(tail);
-            }
-        }
-    }
+        // Apply the moves
+        let food_before = game.board.food.clone();
+        let (next, eliminations) = apply_turn_with_eliminations(game, &moves, seed);
+        game = next;
+        game.turn += 1;
+        food.record_turn(&food_before, &game);
 
-    // Remove eaten food (in reverse order to preserve indices)
-    // Deduplicate in case multiple snakes ate the same food (head-to-head on food)
-    eaten_food.sort();
-    eaten_food.dedup();
-    eaten_food.reverse();
-    for idx in eaten_food {
-        game.board.food.remove(idx);
+        eliminated.extend(eliminations.into_iter().map(|e| (e.snake_id, game.turn)));
     }
 
-    // 4. Eliminate snakes
-    eliminate_snakes(&mut game);
+    let placements = final_placements(&game, &eliminated, &food, TurnLimitTiebreak::default());
 
-    // Update "you" to match the board state
-    if let Some(you_snake) = game.board.snakes.iter().find(|s| s.id == game.you.id) {
-        game.you = you_snake.clone();
+    GameResult {
+        draw: is_draw(&placements),
+        placements: placements
+            .into_iter()
+            .map(|(snake_id, _)| snake_id)
+            .collect(),
+        final_turn: game.turn,
+        final_board: game.board,
     }
-
-    game
 }
 
-/// Eliminate snakes that are out of health, out of bounds, or have collided
-fn eliminate_snakes(game: &mut Game) {
-    let width = game.board.width as i32;
-    let height = game.board.height as i32;
-
-    // Collect elimination info first (can't mutate while iterating)
-    let mut eliminations: Vec<(String, &'static str)> = Vec::new();
-
-    // Check each snake
-    for snake in &game.board.snakes {
-        if snake.health <= 0 {
-            continue; // Already eliminated
-        }
-
-        let head = snake.head;
-
-        // Out of bounds check
-        if head.x < 0 || head.x >= width || head.y < 0 || head.y >= height {
-            eliminations.push((snake.id.clone(), "wall-collision"));
-            continue;
-        }
-
-        // Out of health check (should already be 0 if starved)
-        if snake.health <= 0 {
-            eliminations.push((snake.id.clone(), "out-of-health"));
-            continue;
-        }
-
-        // Self collision check (head hitting own body, excluding head position)
-        let self_collision = snake.body.iter().skip(1).any(|p| *p == head);
-        if self_collision {
-            eliminations.push((snake.id.clone(), "snake-self-collision"));
-            continue;
-        }
-
-        // Body collision with other snakes
-        let body_collision = game.board.snakes.iter().any(|other| {
-            other.id != snake.id
-                && other.health > 0
-                && other.body.iter().skip(1).any(|p| *p == head)
-        });
-        if body_collision {
-            eliminations.push((snake.id.clone(), "snake-collision"));
-            continue;
-        }
-
-        // Head-to-head collision (lose if same size or smaller)
-        let head_collision = game.board.snakes.iter().any(|other| {
-            other.id != snake.id
-                && other.health > 0
-                && other.head == head
-                && snake.body.len() <= other.body.len()
-        });
-        if head_collision {
-            eliminations.push((snake.id.clone(), "head-collision"));
-        }
-    }
-
-    // Apply eliminations
-    for (snake_id, _cause) in eliminations {
-        if let Some(snake) = game.board.snakes.iter_mut().find(|s| s.id == snake_id) {
-            snake.health = 0;
-        }
-    }
+/// Check if the game is over (1 or fewer snakes alive)
+fn is_game_over(game: &Game) -> bool {
+    let alive_count = game.board.snakes.iter().filter(|s| s.health > 0).count();
+    alive_count <= 1
 }
 
 #[cfg(test)]
 mod tests {
+    use super::rules::{apply_turn, royale_hazards};
     use super::*;
 
     #[test]
     fn test_generate_spawn_positions() {
-        let positions = generate_spawn_positions(11, 11, 4);
+        let positions = generate_spawn_positions(11, 11, 4, &SpawnStrategy::Random, 0);
         assert_eq!(positions.len(), 4);
 
         // All positions should be unique
@@ -421,6 +517,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_fixed_spawn_positions_alternate_with_rotation() {
+        let first = generate_spawn_positions(11, 11, 2, &SpawnStrategy::Fixed { rotation: 0 }, 0);
+        let again = generate_spawn_positions(11, 11, 2, &SpawnStrategy::Fixed { rotation: 0 }, 0);
+        let swapped = generate_spawn_positions(11, 11, 2, &SpawnStrategy::Fixed { rotation: 1 }, 0);
+
+        // Deterministic, in opposite corners, and swapped by the next rotation
+        assert_eq!(first, again);
+        assert_eq!(first, vec![Position::new(1, 1), Position::new(9, 9)]);
+        assert_eq!(swapped, vec![Position::new(9, 9), Position::new(1, 1)]);
+        assert_eq!(
+            generate_spawn_positions(11, 11, 2, &SpawnStrategy::Fixed { rotation: 2 }, 0),
+            first
+        );
+    }
+
+    #[test]
+    fn test_manual_spawn_positions() {
+        let points = vec![SpawnPoint { x: 3, y: 4 }, SpawnPoint { x: 0, y: 6 }];
+        assert_eq!(
+            generate_spawn_positions(7, 7, 2, &SpawnStrategy::Manual(points), 0),
+            vec![Position::new(3, 4), Position::new(0, 6)]
+        );
+
+        // Positions that don't fit the game fall back to fixed spawns
+        let off_board = vec![SpawnPoint { x: 7, y: 0 }, SpawnPoint { x: 0, y: 0 }];
+        assert_eq!(
+            generate_spawn_positions(7, 7, 2, &SpawnStrategy::Manual(off_board), 0),
+            generate_spawn_positions(7, 7, 2, &SpawnStrategy::Fixed { rotation: 0 }, 0)
+        );
+    }
+
+    #[test]
+    fn test_validate_spawn_points() {
+        let p = |x, y| SpawnPoint { x, y };
+        assert!(validate_spawn_points(7, 7, 2, &[p(1, 1), p(5, 5)]).is_ok());
+        assert!(validate_spawn_points(7, 7, 3, &[p(1, 1), p(5, 5)]).is_err());
+        assert!(validate_spawn_points(7, 7, 2, &[p(1, 1), p(1, 1)]).is_err());
+        assert!(validate_spawn_points(7, 7, 2, &[p(1, 1), p(-1, 5)]).is_err());
+        assert!(validate_spawn_points(7, 7, 2, &[p(1, 1), p(5, 7)]).is_err());
+    }
+
     #[test]
     fn test_is_game_over() {
         let game = create_test_game(2);
@@ -539,6 +677,39 @@ mod tests {
         assert_eq!(game.board.snakes[0].health, 0);
     }
 
+    #[test]
+    fn test_wrapped_board_wraps_instead_of_wall_collision() {
+        let mut game = create_test_game(1);
+        game.game.ruleset.name = "wrapped".to_string();
+        game.board.snakes[0].head = Position::new(0, 5);
+        game.board.snakes[0].body = VecDeque::from([
+            Position::new(0, 5),
+            Position::new(1, 5),
+            Position::new(2, 5),
+        ]);
+
+        let moves = vec![("snake-0".to_string(), Move::Left)];
+        let mut game = apply_turn(game, &moves);
+
+        let width = game.board.width as i32;
+        assert_eq!(game.board.snakes[0].head, Position::new(width - 1, 5));
+        assert_eq!(game.board.snakes[0].body[0], Position::new(width - 1, 5));
+        assert!(game.board.snakes[0].health > 0);
+
+        // And across the top edge onto the bottom row
+        let height = game.board.height as i32;
+        game.board.snakes[0].head = Position::new(3, height - 1);
+        game.board.snakes[0].body = VecDeque::from([
+            Position::new(3, height - 1),
+            Position::new(3, height - 2),
+            Position::new(3, height - 3),
+        ]);
+        let moves = vec![("snake-0".to_string(), Move::Up)];
+        let game = apply_turn(game, &moves);
+        assert_eq!(game.board.snakes[0].head, Position::new(3, 0));
+        assert!(game.board.snakes[0].health > 0);
+    }
+
     #[test]
     fn test_head_to_head_collision_on_food() {
         // Regression test: two snakes colliding head-to-head on a food tile
@@ -632,10 +803,14 @@ mod tests {
             ("snake-0".to_string(), Move::Up),
             ("snake-1".to_string(), Move::Right),
         ];
-        let game = apply_turn(game, &moves);
+        let (game, eliminations) = apply_turn_with_eliminations(game, &moves, 0);
 
         // Snake-0 should be eliminated (hit snake-1's body at (5,6))
         assert_eq!(game.board.snakes[0].health, 0);
+        assert_eq!(
+            eliminations,
+            vec![Elimination::new("snake-0", "snake-collision", "snake-1")]
+        );
         // Snake-1 should survive
         assert!(game.board.snakes[1].health > 0);
     }
@@ -663,10 +838,14 @@ mod tests {
             ("snake-0".to_string(), Move::Up),
             ("snake-1".to_string(), Move::Down),
         ];
-        let game = apply_turn(game, &moves);
+        let (game, eliminations) = apply_turn_with_eliminations(game, &moves, 0);
 
-        // Smaller snake loses
+        // Smaller snake loses, to the larger one
         assert_eq!(game.board.snakes[0].health, 0);
+        assert_eq!(
+            eliminations,
+            vec![Elimination::new("snake-0", "head-collision", "snake-1")]
+        );
         // Larger snake survives
         assert!(game.board.snakes[1].health > 0);
     }
@@ -693,11 +872,18 @@ mod tests {
             ("snake-0".to_string(), Move::Up),
             ("snake-1".to_string(), Move::Down),
         ];
-        let game = apply_turn(game, &moves);
+        let (game, eliminations) = apply_turn_with_eliminations(game, &moves, 0);
 
-        // Both snakes should die
+        // Both snakes should die, each eliminated by the other
         assert_eq!(game.board.snakes[0].health, 0);
         assert_eq!(game.board.snakes[1].health, 0);
+        assert_eq!(
+            eliminations,
+            vec![
+                Elimination::new("snake-0", "head-collision", "snake-1"),
+                Elimination::new("snake-1", "head-collision", "snake-0"),
+            ]
+        );
     }
 
     #[test]
@@ -706,10 +892,18 @@ mod tests {
         game.board.snakes[0].health = 1; // Will reach 0 after move
 
         let moves = vec![("snake-0".to_string(), Move::Up)];
-        let game = apply_turn(game, &moves);
+        let (game, eliminations) = apply_turn_with_eliminations(game, &moves, 0);
 
-        // Snake should starve (health becomes 0)
+        // Snake should starve (health becomes 0), with no snake to blame
         assert_eq!(game.board.snakes[0].health, 0);
+        assert_eq!(
+            eliminations,
+            vec![Elimination::new("snake-0", "out-of-health", "")]
+        );
+
+        // It's only eliminated on the turn it starves
+        let (_, eliminations) = apply_turn_with_eliminations(game, &moves, 0);
+        assert!(eliminations.is_empty());
     }
 
     #[test]
@@ -821,6 +1015,162 @@ mod tests {
         assert_eq!(game.board.snakes[0].head, Position::new(5, 6));
     }
 
+    #[test]
+    fn test_final_placements_at_turn_limit() {
+        // snake-0 and snake-1 survive the turn limit, snake-1 longer; snake-2 was eliminated
+        let mut game = create_test_game(3);
+        game.board.snakes[1].body.push_back(Position::new(3, 4));
+        game.board.snakes[2].health = 0;
+        let eliminated = vec![("snake-2".to_string(), 7)];
+        let mut food = FoodTally::default();
+        food.record_turn(&[game.board.snakes[0].head], &game);
+
+        let placements = |tiebreak| final_placements(&game, &eliminated, &food, tiebreak);
+        let expected = |order: &[(&str, i32)]| -> Vec<(String, i32)> {
+            order.iter().map(|&(id, p)| (id.to_string(), p)).collect()
+        };
+
+        assert_eq!(
+            placements(TurnLimitTiebreak::Longest),
+            expected(&[("snake-1", 1), ("snake-0", 2), ("snake-2", 3)])
+        );
+        assert_eq!(
+            placements(TurnLimitTiebreak::MostFood),
+            expected(&[("snake-0", 1), ("snake-1", 2), ("snake-2", 3)])
+        );
+        assert_eq!(
+            placements(TurnLimitTiebreak::Draw),
+            expected(&[("snake-0", 1), ("snake-1", 1), ("snake-2", 3)])
+        );
+    }
+
+    #[test]
+    fn test_final_placements_last_eliminated_first() {
+        let mut game = create_test_game(3);
+        game.board.snakes[0].health = 0;
+        game.board.snakes[2].health = 0;
+        let eliminated = vec![("snake-2".to_string(), 3), ("snake-0".to_string(), 9)];
+
+        assert_eq!(
+            final_placements(
+                &game,
+                &eliminated,
+                &FoodTally::default(),
+                TurnLimitTiebreak::Draw
+            ),
+            vec![
+                ("snake-1".to_string(), 1),
+                ("snake-0".to_string(), 2),
+                ("snake-2".to_string(), 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_final_placements_same_turn_eliminations_share_a_placement() {
+        let mut game = create_test_game(4);
+        for snake in &mut game.board.snakes {
+            snake.health = 0;
+        }
+        let placements = |eliminated: &[(&str, i32)]| {
+            let eliminated: Vec<(String, i32)> = eliminated
+                .iter()
+                .map(|&(id, turn)| (id.to_string(), turn))
+                .collect();
+            final_placements(
+                &game,
+                &eliminated,
+                &FoodTally::default(),
+                TurnLimitTiebreak::Longest,
+            )
+        };
+
+        // The last two go out together: a draw, and the next snake places third
+        let draw = placements(&[
+            ("snake-3", 2),
+            ("snake-2", 5),
+            ("snake-0", 9),
+            ("snake-1", 9),
+        ]);
+        assert_eq!(
+            draw,
+            vec![
+                ("snake-1".to_string(), 1),
+                ("snake-0".to_string(), 1),
+                ("snake-2".to_string(), 3),
+                ("snake-3".to_string(), 4),
+            ]
+        );
+        assert!(is_draw(&draw));
+
+        // Going out together lower down isn't a draw
+        let tied_for_third = placements(&[
+            ("snake-3", 2),
+            ("snake-2", 2),
+            ("snake-0", 5),
+            ("snake-1", 9),
+        ]);
+        assert_eq!(
+            tied_for_third,
+            vec![
+                ("snake-1".to_string(), 1),
+                ("snake-0".to_string(), 2),
+                ("snake-2".to_string(), 3),
+                ("snake-3".to_string(), 3),
+            ]
+        );
+        assert!(!is_draw(&tied_for_third));
+    }
+
+    #[test]
+    fn test_simulate_game_last_snakes_out_together_share_first() {
+        use move_provider::ScriptedMoves;
+
+        let mut game = create_test_game(3);
+        game.board.food = vec![];
+        game.board.snakes[0].head = Position::new(5, 4);
+        game.board.snakes[0].body = VecDeque::from([
+            Position::new(5, 4),
+            Position::new(5, 3),
+            Position::new(5, 2),
+        ]);
+        game.board.snakes[1].head = Position::new(5, 8);
+        game.board.snakes[1].body = VecDeque::from([
+            Position::new(5, 8),
+            Position::new(5, 9),
+            Position::new(5, 10),
+        ]);
+        game.board.snakes[2].head = Position::new(0, 0);
+        game.board.snakes[2].body = VecDeque::from([
+            Position::new(0, 0),
+            Position::new(1, 0),
+            Position::new(2, 0),
+        ]);
+
+        // snake-2 hits the wall on turn 1, then snake-0 and snake-1 meet head
+        // to head at (5, 6) on turn 2
+        let mut providers: MoveProviders = HashMap::new();
+        providers.insert(
+            "snake-0".to_string(),
+            Box::new(ScriptedMoves::new(vec![Move::Up, Move::Up])),
+        );
+        providers.insert(
+            "snake-1".to_string(),
+            Box::new(ScriptedMoves::new(vec![Move::Down, Move::Down])),
+        );
+        providers.insert(
+            "snake-2".to_string(),
+            Box::new(ScriptedMoves::new(vec![Move::Left])),
+        );
+
+        let result = futures::executor::block_on(simulate_game(game, &providers, 0));
+
+        assert_eq!(result.final_turn, 2);
+        assert!(result.draw);
+        assert_eq!(result.placements[2], "snake-2");
+        assert!(result.final_board.snakes.iter().all(|s| s.health == 0));
+    }
+
     #[test]
     fn test_max_turns_constant() {
         assert_eq!(MAX_TURNS, 5000);
@@ -842,6 +1192,253 @@ mod tests {
         assert_eq!(game.board.snakes[0].body, original_body);
     }
 
+    #[test]
+    fn test_royale_hazards_shrink_safe_area() {
+        let every = ROYALE_SHRINK_EVERY_N_TURNS;
+        assert!(royale_hazards(11, 11, every - 1, every, 42).is_empty());
+
+        // One row or column per shrink, each turn's hazards containing the last's
+        let first = royale_hazards(11, 11, every, every, 42);
+        assert_eq!(first.len(), 11);
+        assert_eq!(royale_hazards(11, 11, every * 2 - 1, every, 42), first);
+        let second = royale_hazards(11, 11, every * 2, every, 42);
+        assert!(second.len() == 21 || second.len() == 22);
+        assert!(first.iter().all(|p| second.contains(p)));
+
+        // Deterministic for a seed
+        assert_eq!(
+            royale_hazards(11, 11, every * 4, every, 7),
+            royale_hazards(11, 11, every * 4, every, 7)
+        );
+    }
+
+    #[test]
+    fn test_apply_turn_royale_populates_hazards() {
+        let mut game = create_test_game(2);
+        game.game.ruleset.name = "royale".to_string();
+        game.turn = ROYALE_SHRINK_EVERY_N_TURNS - 2;
+        let mut game = apply_turn(game, &[]);
+        assert!(game.board.hazards.is_empty());
+
+        game.turn += 1;
+        let game = apply_turn(game, &[]);
+        assert_eq!(game.board.hazards.len(), 11);
+
+        // Standard games never get hazards
+        let mut standard = create_test_game(2);
+        standard.turn = ROYALE_SHRINK_EVERY_N_TURNS * 4;
+        assert!(apply_turn(standard, &[]).board.hazards.is_empty());
+    }
+
+    #[test]
+    fn test_hazard_damage() {
+        // A snake moving up into a hazard at (5, 6)
+        let game_with = |health: i32, food: Vec<Position>| {
+            let mut game = create_test_game(1);
+            game.board.snakes[0].health = health;
+            game.board.snakes[0].head = Position::new(5, 5);
+            game.board.snakes[0].body = VecDeque::from([
+                Position::new(5, 5),
+                Position::new(5, 4),
+                Position::new(5, 3),
+            ]);
+            game.board.food = food;
+            game.board.hazards = vec![Position::new(5, 6)];
+            game
+        };
+        let moves = vec![("snake-0".to_string(), Move::Up)];
+
+        let damaged = apply_turn(game_with(100, vec![]), &moves);
+        assert_eq!(
+            damaged.board.snakes[0].health,
+            100 - 1 - HAZARD_DAMAGE_PER_TURN
+        );
+
+        // Food in the hazard cancels the damage
+        let fed = apply_turn(game_with(100, vec![Position::new(5, 6)]), &moves);
+        assert_eq!(fed.board.snakes[0].health, SNAKE_MAX_HEALTH);
+
+        // Enough damage eliminates the snake
+        let eliminated = apply_turn(game_with(HAZARD_DAMAGE_PER_TURN, vec![]), &moves);
+        assert_eq!(eliminated.board.snakes[0].health, 0);
+    }
+
+    #[test]
+    fn test_stacked_hazards_deal_damage_per_listing() {
+        let mut game = create_test_game(2);
+        game.board.food.clear();
+        game.board.snakes[0].head = Position::new(5, 5);
+        game.board.snakes[0].body = VecDeque::from([Position::new(5, 5); 3]);
+        // (5, 6) is listed twice, (3, 4) once
+        game.board.hazards = vec![
+            Position::new(5, 6),
+            Position::new(5, 6),
+            Position::new(3, 4),
+        ];
+        let moves = vec![
+            ("snake-0".to_string(), Move::Up),
+            ("snake-1".to_string(), Move::Up),
+        ];
+
+        let game = apply_turn(game, &moves);
+        assert_eq!(
+            game.board.snakes[0].health,
+            100 - 1 - 2 * HAZARD_DAMAGE_PER_TURN
+        );
+        assert_eq!(
+            game.board.snakes[1].health,
+            100 - 1 - HAZARD_DAMAGE_PER_TURN
+        );
+    }
+
+    #[test]
+    fn test_food_in_hazard_is_eaten() {
+        let mut game = create_test_game(1);
+        game.board.snakes[0].health = 20;
+        game.board.food = vec![Position::new(1, 2)];
+        game.board.hazards = vec![Position::new(1, 2), Position::new(1, 2)];
+        let food_before = game.board.food.clone();
+        let moves = vec![("snake-0".to_string(), Move::Up)];
+
+        let (game, eliminations) = apply_turn_with_eliminations(game, &moves, 0);
+        assert!(eliminations.is_empty());
+        let snake = &game.board.snakes[0];
+        assert_eq!(snake.health, SNAKE_MAX_HEALTH);
+        assert_eq!(snake.body.len(), 4);
+        assert!(!game.board.food.contains(&Position::new(1, 2)));
+
+        let mut food = FoodTally::default();
+        food.record_turn(&food_before, &game);
+        assert_eq!(food.eaten("snake-0"), 1);
+    }
+
+    #[test]
+    fn test_hazards_exhaust_a_snake() {
+        // Snake 0 walks up a column of hazards, snake 1 stays clear of it.
+        // No food spawns to top either of them up.
+        let mut game = with_food_settings(create_test_game(2), 0, 0);
+        game.board.food.clear();
+        game.board.snakes[0].health = 40;
+        game.board.hazards = (0..11).map(|y| Position::new(1, y)).collect();
+        let moves = vec![
+            ("snake-0".to_string(), Move::Up),
+            ("snake-1".to_string(), Move::Up),
+        ];
+
+        let per_turn = 1 + HAZARD_DAMAGE_PER_TURN;
+        for turn in 1..=3 {
+            let (next, eliminations) = apply_turn_with_eliminations(game, &moves, 0);
+            game = next;
+            let health = game.board.snakes[0].health;
+            if turn < 3 {
+                assert_eq!(health, 40 - per_turn * turn);
+                assert!(eliminations.is_empty());
+            } else {
+                // The last hit takes it below zero, which counts as running out
+                assert_eq!(health, 0);
+                assert_eq!(
+                    eliminations,
+                    vec![Elimination::new("snake-0", "out-of-health", "")]
+                );
+            }
+        }
+        assert_eq!(game.board.snakes[1].health, 97);
+    }
+
+    #[test]
+    fn test_royale_shrink_damages_from_the_next_turn() {
+        let mut game = with_food_settings(create_test_game(2), 0, 0);
+        game.game.ruleset.name = "royale".to_string();
+        game.board.food.clear();
+        game.turn = ROYALE_SHRINK_EVERY_N_TURNS - 1;
+        let moves = vec![
+            ("snake-0".to_string(), Move::Right),
+            ("snake-1".to_string(), Move::Right),
+        ];
+
+        // The turn that shrinks the safe area doesn't damage anyone yet
+        let mut game = apply_turn(game, &moves);
+        assert_eq!(game.board.hazards.len(), 11);
+        assert!(game.board.snakes.iter().all(|s| s.health == 99));
+
+        // A snake stepping into the new hazards takes damage on the turn after
+        let hazard = game.board.hazards[5];
+        let (from, step) = if hazard.y > 0 {
+            (Position::new(hazard.x, hazard.y - 1), Move::Up)
+        } else {
+            (Position::new(hazard.x, hazard.y + 1), Move::Down)
+        };
+        game.board.snakes[0].head = from;
+        game.board.snakes[0].body = VecDeque::from([from; 3]);
+        game.turn += 1;
+        let game = apply_turn(game, &[("snake-0".to_string(), step)]);
+        assert_eq!(game.board.snakes[0].head, hazard);
+        assert_eq!(game.board.snakes[0].health, 99 - 1 - HAZARD_DAMAGE_PER_TURN);
+    }
+
+    fn with_food_settings(mut game: Game, food_spawn_chance: i32, minimum_food: i32) -> Game {
+        game.game.ruleset.settings = Some(Settings {
+            food_spawn_chance,
+            minimum_food,
+            hazard_damage_per_turn: HAZARD_DAMAGE_PER_TURN,
+            hazard_map: None,
+            hazard_map_author: None,
+            royale: None,
+        });
+        game
+    }
+
+    #[test]
+    fn test_spawn_food_tops_up_to_minimum() {
+        let mut game = with_food_settings(create_test_game(2), 0, 3);
+        game.board.food.clear();
+        let game = apply_turn(game, &[]);
+
+        assert_eq!(game.board.food.len(), 3);
+        for food in &game.board.food {
+            for snake in &game.board.snakes {
+                assert!(!snake.body.contains(food));
+                assert_ne!(
+                    (snake.head.x - food.x).abs() + (snake.head.y - food.y).abs(),
+                    1
+                );
+            }
+        }
+
+        // Already at the minimum with no chance of more
+        let game = apply_turn(game, &[]);
+        assert_eq!(game.board.food.len(), 3);
+    }
+
+    #[test]
+    fn test_spawn_food_chance() {
+        let always = apply_turn(with_food_settings(create_test_game(2), 100, 0), &[]);
+        assert_eq!(always.board.food.len(), 2);
+
+        let never = apply_turn(with_food_settings(create_test_game(2), 0, 0), &[]);
+        assert_eq!(never.board.food.len(), 1);
+
+        // Games without settings don't spawn food
+        let mut unset = create_test_game(2);
+        unset.board.food.clear();
+        assert!(apply_turn(unset, &[]).board.food.is_empty());
+    }
+
+    #[test]
+    fn test_spawn_food_is_seeded_by_game_and_turn() {
+        let spawned = |game_id: &str, turn: i32| {
+            let mut game = with_food_settings(create_test_game(2), 0, 5);
+            game.game.id = game_id.to_string();
+            game.turn = turn;
+            game.board.food.clear();
+            apply_turn(game, &[]).board.food
+        };
+
+        assert_eq!(spawned("game-a", 3), spawned("game-a", 3));
+        assert_ne!(spawned("game-a", 3), spawned("game-b", 3));
+        assert_ne!(spawned("game-a", 3), spawned("game-a", 4));
+    }
+
     fn create_test_game(num_snakes: usize) -> Game {
         let snakes: Vec<BattleSnake> = (0..num_snakes)
             .map(|i| BattleSnake {
@@ -919,6 +1516,8 @@ mod tests {
             GameBoardSize::Medium,
             GameType::Standard,
             &battlesnakes,
+            &SpawnStrategy::Random,
+            0,
         );
 
         // Verify we have 2 snakes
@@ -941,4 +1540,52 @@ mod tests {
             battlesnakes[1].game_battlesnake_id.to_string()
         );
     }
+
+    #[test]
+    fn test_same_seed_replays_identically() {
+        use crate::models::game::{GameBoardSize, GameType};
+        use crate::models::game_battlesnake::GameBattlesnakeWithDetails;
+
+        let battlesnakes: Vec<GameBattlesnakeWithDetails> = (0..4)
+            .map(|i| GameBattlesnakeWithDetails {
+                game_battlesnake_id: Uuid::from_u128(i),
+                game_id: Uuid::nil(),
+                battlesnake_id: Uuid::from_u128(100 + i),
+                placement: None,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+                name: format!("Snake {}", i),
+                url: "bot:random".to_string(),
+                user_id: Uuid::nil(),
+            })
+            .collect();
+
+        // Everything random in a game: spawns, food and the random bot's moves
+        let play = |game_id: Uuid, seed: u64| {
+            let game = create_initial_game(
+                game_id,
+                GameBoardSize::Medium,
+                GameType::Standard,
+                &battlesnakes,
+                &SpawnStrategy::Random,
+                seed,
+            );
+            let start: Vec<Position> = game.board.snakes.iter().map(|s| s.head).collect();
+            let start_food = game.board.food.clone();
+            let providers = all_bots(&game, BuiltInBot::Random, seed);
+            let result = futures::executor::block_on(simulate_game(game, &providers, seed));
+            (
+                start,
+                start_food,
+                result.placements,
+                result.final_turn,
+                result.final_board.food,
+            )
+        };
+
+        // The seed decides the game, not the game's ID
+        assert_eq!(play(Uuid::new_v4(), 42), play(Uuid::new_v4(), 42));
+        assert_eq!(engine_seed("any-game", Some(-1)), u64::MAX);
+        assert_ne!(engine_seed("game-a", None), engine_seed("game-b", None));
+    }
 }
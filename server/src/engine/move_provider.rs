@@ -0,0 +1,726 @@
+//! Where each snake's moves come from
+//!
+//! The game loop asks a [`MoveProvider`] per snake for its moves and doesn't
+//! care whether that's a real snake over HTTP or something running in-process.
+//! That lets games mix real snakes with bots, and lets engine tests script
+//! exact moves instead of standing up HTTP servers.
+//!
+//! Snakes whose URL is `bot:random` or `bot:food` are played by the matching
+//! [`BuiltInBot`] rather than called over HTTP.
+
+use battlesnake_game_types::types::Move;
+use battlesnake_game_types::wire_representation::{BattleSnake, Game, Position};
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::engine::turn_rng;
+use crate::models::game::MoveValidationMode;
+use crate::snake_client::{
+    MoveDeadline, MoveResult, SnakeInfo, request_end, request_info, request_move, request_start,
+};
+use crate::snake_http::SnakeHttpClient;
+
+const ALL_MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+/// What a snake needs to know to pick this turn's move
+#[derive(Debug, Clone, Copy)]
+pub struct MoveContext {
+    pub deadline: MoveDeadline,
+    /// The snake's previous move, the fallback when it fails to answer
+    pub last_direction: Option<Move>,
+    pub mode: MoveValidationMode,
+}
+
+/// Source of moves for one snake in a game
+#[async_trait::async_trait]
+pub trait MoveProvider: Send + Sync {
+    /// The snake's info from its GET /, asked for once when the game starts
+    ///
+    /// None when there's nothing to ask, and the frames use their defaults.
+    async fn info(&self, _game: &Game) -> Result<Option<SnakeInfo>, String> {
+        Ok(None)
+    }
+
+    /// Called once before the first move
+    ///
+    /// Returns the round trip time when there's a network to measure, which
+    /// latency compensation adds to the snake's move deadline.
+    async fn start(&self, _game: &Game, _snake: &BattleSnake) -> Result<Option<Duration>, String> {
+        Ok(None)
+    }
+
+    /// Pick the snake's move for the current turn
+    async fn next_move(&self, game: &Game, snake: &BattleSnake, ctx: MoveContext) -> MoveResult;
+
+    /// Called once after the game is over
+    async fn end(&self, _game: &Game, _snake: &BattleSnake) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Move providers for every snake in a game, keyed by snake ID
+pub type MoveProviders = HashMap<String, Box<dyn MoveProvider>>;
+
+/// A snake's own server, called over HTTP
+pub struct HttpSnake {
+    client: SnakeHttpClient,
+    url: String,
+}
+
+impl HttpSnake {
+    pub fn new(client: SnakeHttpClient, url: impl Into<String>) -> Self {
+        Self {
+            client,
+            url: url.into(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MoveProvider for HttpSnake {
+    async fn info(&self, game: &Game) -> Result<Option<SnakeInfo>, String> {
+        request_info(&self.client, &self.url, game_timeout(game))
+            .await
+            .map(Some)
+    }
+
+    async fn start(&self, game: &Game, snake: &BattleSnake) -> Result<Option<Duration>, String> {
+        request_start(&self.client, &self.url, game, snake, game_timeout(game))
+            .await
+            .map(Some)
+    }
+
+    async fn next_move(&self, game: &Game, snake: &BattleSnake, ctx: MoveContext) -> MoveResult {
+        request_move(
+            &self.client,
+            &self.url,
+            game,
+            snake,
+            ctx.deadline,
+            ctx.last_direction,
+            ctx.mode,
+        )
+        .await
+    }
+
+    async fn end(&self, game: &Game, snake: &BattleSnake) -> Result<(), String> {
+        request_end(&self.client, &self.url, game, snake, game_timeout(game)).await
+    }
+}
+
+/// Bots that play in-process, for mixed games and simulations
+///
+/// Both stay out of hazards when they can, and head back out when a royale
+/// safe area shrinks over them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltInBot {
+    /// A random move that doesn't immediately run into a wall, snake or hazard
+    Random,
+    /// Heads for the nearest food, avoiding walls, snakes and hazards
+    Food,
+}
+
+impl BuiltInBot {
+    /// The bot a snake URL refers to, if it's a `bot:` URL
+    pub fn from_url(url: &str) -> Option<Self> {
+        match url.trim().to_lowercase().as_str() {
+            "bot:random" => Some(BuiltInBot::Random),
+            "bot:food" => Some(BuiltInBot::Food),
+            _ => None,
+        }
+    }
+
+    /// The bot playing a snake in a game, drawing its random moves from the
+    /// game's seed
+    pub fn player(self, seed: u64) -> BotPlayer {
+        BotPlayer { bot: self, seed }
+    }
+
+    fn choose(&self, game: &Game, snake: &BattleSnake, seed: u64) -> Option<Move> {
+        let safe = hazard_free_moves(game, snake, safe_moves(game, snake));
+        let preferred = match self {
+            BuiltInBot::Random => {
+                let mut rng = turn_rng(seed, game.turn, &format!("bot:{}", snake.id));
+                safe.choose(&mut rng).copied()
+            }
+            BuiltInBot::Food => game
+                .board
+                .food
+                .iter()
+                .min_by_key(|food| distance(**food, snake.head))
+                .and_then(|food| {
+                    safe.iter()
+                        .copied()
+                        .min_by_key(|m| distance(snake.head.add_vec(m.to_vector()), *food))
+                }),
+        };
+        preferred.or_else(|| safe.first().copied())
+    }
+}
+
+/// A built-in bot playing one snake, with its game's seed
+#[derive(Debug, Clone, Copy)]
+pub struct BotPlayer {
+    bot: BuiltInBot,
+    seed: u64,
+}
+
+#[async_trait::async_trait]
+impl MoveProvider for BotPlayer {
+    async fn next_move(&self, game: &Game, snake: &BattleSnake, ctx: MoveContext) -> MoveResult {
+        let direction = self
+            .bot
+            .choose(game, snake, self.seed)
+            .or(ctx.last_direction)
+            .unwrap_or(Move::Up);
+        in_process_result(snake, direction)
+    }
+}
+
+/// Plays a fixed list of moves, one per turn, for tests and fixtures
+///
+/// Once the script runs out the snake keeps going in its last direction.
+pub struct ScriptedMoves {
+    moves: Vec<Move>,
+}
+
+impl ScriptedMoves {
+    pub fn new(moves: Vec<Move>) -> Self {
+        Self { moves }
+    }
+}
+
+#[async_trait::async_trait]
+impl MoveProvider for ScriptedMoves {
+    async fn next_move(&self, game: &Game, snake: &BattleSnake, ctx: MoveContext) -> MoveResult {
+        let direction = usize::try_from(game.turn)
+            .ok()
+            .and_then(|turn| self.moves.get(turn).copied())
+            .or(ctx.last_direction)
+            .unwrap_or(Move::Up);
+        in_process_result(snake, direction)
+    }
+}
+
+/// Plays back a snake's moves from a recorded game, for replay fixtures
+///
+/// Unlike [`ScriptedMoves`], running out of recorded moves is reported as a
+/// request error: it means the replay has diverged from the original game.
+pub struct ReplayMoves {
+    /// Indexed by the turn the move was made on, `None` where nothing was recorded
+    moves: Vec<Option<Move>>,
+}
+
+impl ReplayMoves {
+    pub fn new(moves: Vec<Option<Move>>) -> Self {
+        Self { moves }
+    }
+}
+
+#[async_trait::async_trait]
+impl MoveProvider for ReplayMoves {
+    async fn next_move(&self, game: &Game, snake: &BattleSnake, ctx: MoveContext) -> MoveResult {
+        let recorded = usize::try_from(game.turn)
+            .ok()
+            .and_then(|turn| self.moves.get(turn).copied().flatten());
+        match recorded {
+            Some(direction) => in_process_result(snake, direction),
+            None => MoveResult {
+                request_error: Some(format!("no recorded move for turn {}", game.turn)),
+                ..in_process_result(snake, ctx.last_direction.unwrap_or(Move::Up))
+            },
+        }
+    }
+}
+
+/// The provider for a snake with the given URL: a built-in bot for `bot:` URLs,
+/// its HTTP server otherwise
+pub fn provider_for_url(client: &SnakeHttpClient, url: &str, seed: u64) -> Box<dyn MoveProvider> {
+    match BuiltInBot::from_url(url) {
+        Some(bot) => Box::new(bot.player(seed)),
+        None => Box::new(HttpSnake::new(client.clone(), url)),
+    }
+}
+
+/// Have the same built-in bot play every snake in a game
+pub fn all_bots(game: &Game, bot: BuiltInBot, seed: u64) -> MoveProviders {
+    game.board
+        .snakes
+        .iter()
+        .map(|s| {
+            let provider: Box<dyn MoveProvider> = Box::new(bot.player(seed));
+            (s.id.clone(), provider)
+        })
+        .collect()
+}
+
+/// Ask every snake for its info in parallel
+///
+/// Returns each snake's info (when it has any), or why the call failed.
+pub async fn info_all(
+    providers: &MoveProviders,
+    game: &Game,
+) -> HashMap<String, Result<Option<SnakeInfo>, String>> {
+    let futures: Vec<_> = game
+        .board
+        .snakes
+        .iter()
+        .filter_map(|snake| {
+            providers
+                .get(&snake.id)
+                .map(|provider| async move { (snake.id.clone(), provider.info(game).await) })
+        })
+        .collect();
+
+    futures::future::join_all(futures)
+        .await
+        .into_iter()
+        .collect()
+}
+
+/// Call `start` for every snake in parallel
+///
+/// Returns each snake's round trip time (when it has one), or why the call failed.
+pub async fn start_all(
+    providers: &MoveProviders,
+    game: &Game,
+) -> HashMap<String, Result<Option<Duration>, String>> {
+    let futures: Vec<_> = game
+        .board
+        .snakes
+        .iter()
+        .filter_map(|snake| {
+            providers.get(&snake.id).map(|provider| async move {
+                (snake.id.clone(), provider.start(game, snake).await)
+            })
+        })
+        .collect();
+
+    futures::future::join_all(futures)
+        .await
+        .into_iter()
+        .collect()
+}
+
+/// Ask every alive snake for its move in parallel
+///
+/// `start_rtts` holds the /start round trip per snake when latency compensation is
+/// on, and should be empty otherwise. Snakes without a measured round trip get a
+/// flat deadline.
+pub async fn request_moves(
+    providers: &MoveProviders,
+    game: &Game,
+    last_moves: &HashMap<String, Move>,
+    mode: MoveValidationMode,
+    start_rtts: &HashMap<String, Duration>,
+) -> Vec<MoveResult> {
+    let timeout = game_timeout(game);
+    let futures: Vec<_> = game
+        .board
+        .snakes
+        .iter()
+        .filter(|s| s.health > 0)
+        .filter_map(|snake| {
+            providers.get(&snake.id).map(|provider| {
+                let ctx = MoveContext {
+                    deadline: match start_rtts.get(&snake.id) {
+                        Some(rtt) => MoveDeadline::compensated(timeout, *rtt),
+                        None => MoveDeadline::flat(timeout),
+                    },
+                    last_direction: last_moves.get(&snake.id).copied(),
+                    mode,
+                };
+                provider.next_move(game, snake, ctx)
+            })
+        })
+        .collect();
+
+    futures::future::join_all(futures).await
+}
+
+/// Call `end` for every snake in parallel
+///
+/// Returns (snake_id, error) for every snake whose call failed.
+pub async fn end_all(providers: &MoveProviders, game: &Game) -> Vec<(String, String)> {
+    let futures: Vec<_> = game
+        .board
+        .snakes
+        .iter()
+        .filter_map(|snake| {
+            providers.get(&snake.id).map(|provider| async move {
+                provider
+                    .end(game, snake)
+                    .await
+                    .err()
+                    .map(|e| (snake.id.clone(), e))
+            })
+        })
+        .collect();
+
+    futures::future::join_all(futures)
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// The game's per-move timeout
+fn game_timeout(game: &Game) -> Duration {
+    Duration::from_millis(game.game.timeout as u64)
+}
+
+fn in_process_result(snake: &BattleSnake, direction: Move) -> MoveResult {
+    MoveResult {
+        snake_id: snake.id.clone(),
+        direction,
+        latency_ms: Some(0),
+        compensated_latency_ms: None,
+        timed_out: false,
+        shout: None,
+        parse_error: None,
+        request_error: None,
+    }
+}
+
+/// Moves that keep the snake on the board and off every snake's body this turn
+///
+/// Tails are treated as free, since they move out of the way unless the snake eats.
+fn safe_moves(game: &Game, snake: &BattleSnake) -> Vec<Move> {
+    let (width, height) = (game.board.width as i32, game.board.height as i32);
+    ALL_MOVES
+        .into_iter()
+        .filter(|m| {
+            let next = snake.head.add_vec(m.to_vector());
+            next.x >= 0
+                && next.x < width
+                && next.y >= 0
+                && next.y < height
+                && !game.board.snakes.iter().filter(|s| s.health > 0).any(|s| {
+                    s.body
+                        .iter()
+                        .take(s.body.len().saturating_sub(1))
+                        .any(|p| *p == next)
+                })
+        })
+        .collect()
+}
+
+/// Narrow safe moves down to those that don't take hazard damage, eating
+/// food in a hazard being free
+///
+/// When every safe move is into a hazard, as when a royale safe area has
+/// shrunk over the snake, keeps the ones heading closest to a square that
+/// isn't. Left with no way out, the snake takes the damage.
+fn hazard_free_moves(game: &Game, snake: &BattleSnake, safe: Vec<Move>) -> Vec<Move> {
+    let hazards = &game.board.hazards;
+    if hazards.is_empty() {
+        return safe;
+    }
+    let damaged = |p: &Position| hazards.contains(p) && !game.board.food.contains(p);
+
+    let clear: Vec<Move> = safe
+        .iter()
+        .copied()
+        .filter(|m| !damaged(&snake.head.add_vec(m.to_vector())))
+        .collect();
+    if !clear.is_empty() {
+        return clear;
+    }
+
+    let (width, height) = (game.board.width as i32, game.board.height as i32);
+    let clear_squares: Vec<Position> = (0..width)
+        .flat_map(|x| (0..height).map(move |y| Position::new(x, y)))
+        .filter(|p| !damaged(p))
+        .collect();
+    let to_clear = |m: &Move| {
+        let next = snake.head.add_vec(m.to_vector());
+        clear_squares.iter().map(|p| distance(*p, next)).min()
+    };
+    match safe.iter().filter_map(to_clear).min() {
+        Some(closest) => safe
+            .into_iter()
+            .filter(|m| to_clear(m) == Some(closest))
+            .collect(),
+        None => safe,
+    }
+}
+
+fn distance(a: Position, b: Position) -> i32 {
+    (a.x - b.x).abs() + (a.y - b.y).abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{create_initial_game, simulate_game};
+    use crate::models::game::{GameBoardSize, GameType, SpawnStrategy};
+    use crate::models::game_battlesnake::GameBattlesnakeWithDetails;
+    use uuid::Uuid;
+
+    fn battlesnake(name: &str) -> GameBattlesnakeWithDetails {
+        let now = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        GameBattlesnakeWithDetails {
+            game_battlesnake_id: Uuid::new_v4(),
+            game_id: Uuid::nil(),
+            battlesnake_id: Uuid::new_v4(),
+            placement: None,
+            created_at: now,
+            updated_at: now,
+            name: name.to_string(),
+            url: "http://example.com".to_string(),
+            user_id: Uuid::nil(),
+        }
+    }
+
+    fn two_snake_game() -> Game {
+        create_initial_game(
+            Uuid::new_v4(),
+            GameBoardSize::Small,
+            GameType::Standard,
+            &[battlesnake("Alpha"), battlesnake("Beta")],
+            &SpawnStrategy::Random,
+            0,
+        )
+    }
+
+    fn ctx() -> MoveContext {
+        MoveContext {
+            deadline: MoveDeadline::flat(Duration::from_millis(500)),
+            last_direction: None,
+            mode: MoveValidationMode::Lenient,
+        }
+    }
+
+    #[test]
+    fn test_built_in_bot_from_url() {
+        assert_eq!(BuiltInBot::from_url("bot:random"), Some(BuiltInBot::Random));
+        assert_eq!(BuiltInBot::from_url(" BOT:Food "), Some(BuiltInBot::Food));
+        assert_eq!(BuiltInBot::from_url("bot:unknown"), None);
+        assert_eq!(BuiltInBot::from_url("https://example.com"), None);
+    }
+
+    #[tokio::test]
+    async fn test_scripted_moves_follow_the_script() {
+        let mut game = two_snake_game();
+        let snake = game.board.snakes[0].clone();
+        let script = ScriptedMoves::new(vec![Move::Left, Move::Down]);
+
+        assert_eq!(
+            script.next_move(&game, &snake, ctx()).await.direction,
+            Move::Left
+        );
+        game.turn = 1;
+        assert_eq!(
+            script.next_move(&game, &snake, ctx()).await.direction,
+            Move::Down
+        );
+
+        // Past the end of the script the snake keeps going
+        game.turn = 2;
+        let keep_going = MoveContext {
+            last_direction: Some(Move::Down),
+            ..ctx()
+        };
+        assert_eq!(
+            script.next_move(&game, &snake, keep_going).await.direction,
+            Move::Down
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_moves_report_missing_turns() {
+        let mut game = two_snake_game();
+        let snake = game.board.snakes[0].clone();
+        let replay = ReplayMoves::new(vec![Some(Move::Left), None]);
+
+        let result = replay.next_move(&game, &snake, ctx()).await;
+        assert_eq!(result.direction, Move::Left);
+        assert_eq!(result.request_error, None);
+
+        for turn in [1, 2] {
+            game.turn = turn;
+            let keep_going = MoveContext {
+                last_direction: Some(Move::Left),
+                ..ctx()
+            };
+            let result = replay.next_move(&game, &snake, keep_going).await;
+            assert_eq!(result.direction, Move::Left);
+            assert_eq!(
+                result.request_error,
+                Some(format!("no recorded move for turn {turn}"))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bots_avoid_walls() {
+        let mut game = two_snake_game();
+        // Put Alpha in the bottom left corner, facing nothing but walls on two sides
+        let corner = Position::new(0, 0);
+        let snake = &mut game.board.snakes[0];
+        snake.head = corner;
+        snake.body = [corner; 3].into_iter().collect();
+        let snake = snake.clone();
+
+        for bot in [BuiltInBot::Random, BuiltInBot::Food] {
+            for seed in 0..20 {
+                let direction = bot
+                    .player(seed)
+                    .next_move(&game, &snake, ctx())
+                    .await
+                    .direction;
+                assert!(
+                    matches!(direction, Move::Up | Move::Right),
+                    "{:?} moved {:?} from the corner",
+                    bot,
+                    direction
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bots_avoid_hazards() {
+        let mut game = two_snake_game();
+        let head = Position::new(3, 3);
+        let snake = &mut game.board.snakes[0];
+        snake.head = head;
+        snake.body = [head; 3].into_iter().collect();
+        let snake = snake.clone();
+        // Hazards on every side but the left, with food past the one above
+        game.board.hazards = vec![
+            Position::new(3, 4),
+            Position::new(3, 2),
+            Position::new(4, 3),
+        ];
+        game.board.food = vec![Position::new(3, 6)];
+
+        for bot in [BuiltInBot::Random, BuiltInBot::Food] {
+            for seed in 0..20 {
+                let direction = bot
+                    .player(seed)
+                    .next_move(&game, &snake, ctx())
+                    .await
+                    .direction;
+                assert_eq!(direction, Move::Left, "{:?} moved into a hazard", bot);
+            }
+        }
+
+        // Food in a hazard cancels its damage, so the food bot goes for it
+        game.board.food = vec![Position::new(3, 4)];
+        let result = BuiltInBot::Food
+            .player(0)
+            .next_move(&game, &snake, ctx())
+            .await;
+        assert_eq!(result.direction, Move::Up);
+    }
+
+    #[tokio::test]
+    async fn test_bots_leave_a_shrunk_safe_area() {
+        let mut game = two_snake_game();
+        let head = Position::new(1, 3);
+        let snake = &mut game.board.snakes[0];
+        snake.head = head;
+        snake.body = [head; 3].into_iter().collect();
+        let snake = snake.clone();
+        game.board.food.clear();
+        // The three leftmost columns have turned into hazards, and the way
+        // out is right
+        game.board.hazards = (0..3)
+            .flat_map(|x| (0..game.board.height as i32).map(move |y| Position::new(x, y)))
+            .collect();
+
+        for bot in [BuiltInBot::Random, BuiltInBot::Food] {
+            for seed in 0..20 {
+                let direction = bot
+                    .player(seed)
+                    .next_move(&game, &snake, ctx())
+                    .await
+                    .direction;
+                assert_eq!(direction, Move::Right, "{:?} stayed in the hazards", bot);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_random_bot_is_seeded() {
+        let mut game = two_snake_game();
+        let snake = game.board.snakes[0].clone();
+
+        let mut runs = Vec::new();
+        for seed in [7, 7] {
+            let mut moves = Vec::new();
+            for turn in 0..10 {
+                game.turn = turn;
+                let result = BuiltInBot::Random
+                    .player(seed)
+                    .next_move(&game, &snake, ctx())
+                    .await;
+                moves.push(result.direction);
+            }
+            runs.push(moves);
+        }
+        assert_eq!(runs[0], runs[1]);
+    }
+
+    #[tokio::test]
+    async fn test_food_bot_heads_for_food() {
+        let mut game = two_snake_game();
+        let head = Position::new(3, 3);
+        let snake = &mut game.board.snakes[0];
+        snake.head = head;
+        snake.body = [head; 3].into_iter().collect();
+        let snake = snake.clone();
+        game.board.food = vec![Position::new(3, 6)];
+
+        let result = BuiltInBot::Food
+            .player(0)
+            .next_move(&game, &snake, ctx())
+            .await;
+        assert_eq!(result.direction, Move::Up);
+    }
+
+    #[tokio::test]
+    async fn test_mixed_providers_in_one_game() {
+        let mut game = two_snake_game();
+        for (snake, head) in game
+            .board
+            .snakes
+            .iter_mut()
+            .zip([Position::new(1, 1), Position::new(5, 5)])
+        {
+            snake.head = head;
+            snake.body = [head; 3].into_iter().collect();
+        }
+        game.board.food = vec![Position::new(5, 2)];
+        let (alpha, beta) = (
+            game.board.snakes[0].id.clone(),
+            game.board.snakes[1].id.clone(),
+        );
+
+        let mut providers: MoveProviders = HashMap::new();
+        providers.insert(alpha.clone(), Box::new(ScriptedMoves::new(vec![])));
+        providers.insert(beta.clone(), Box::new(BuiltInBot::Food.player(0)));
+
+        let results = request_moves(
+            &providers,
+            &game,
+            &HashMap::new(),
+            MoveValidationMode::Lenient,
+            &HashMap::new(),
+        )
+        .await;
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| !r.timed_out));
+
+        let starts = start_all(&providers, &game).await;
+        assert!(starts.values().all(|r| matches!(r, Ok(None))));
+        assert!(end_all(&providers, &game).await.is_empty());
+
+        // A snake that only goes up walks into the wall on turn 6, the food bot outlasts it
+        let result = simulate_game(game, &providers, 0).await;
+        assert_eq!(result.placements, vec![beta, alpha]);
+        assert_eq!(result.final_turn, 6);
+    }
+}
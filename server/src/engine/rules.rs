@@ -0,0 +1,404 @@
+//! The rules of a turn, free of anything but the board
+//!
+//! Moving, feeding, hazards, eliminations and spawning food only depend on
+//! the wire representation of a game, so the library crate exposes this
+//! module for the fuzz targets in `fuzz/` to play turns on arbitrary boards.
+
+use battlesnake_game_types::types::Move;
+use battlesnake_game_types::wire_representation::{BattleSnake, Board, Game, Position};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+pub(crate) const SNAKE_MAX_HEALTH: i32 = 100;
+pub const MAX_TURNS: i32 = 5000;
+
+/// Health a snake loses on each turn its head ends in a hazard
+pub(crate) const HAZARD_DAMAGE_PER_TURN: i32 = 14;
+
+/// In royale games the safe area shrinks by a row or column this often
+pub const ROYALE_SHRINK_EVERY_N_TURNS: i32 = 25;
+
+/// A snake eliminated on a turn, how, and by whom
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Elimination {
+    pub snake_id: String,
+    /// The official cause, e.g. "wall-collision" or "head-collision"
+    pub cause: &'static str,
+    /// The snake whose head or body it hit (itself for a self collision);
+    /// empty for walls and running out of health
+    pub eliminated_by: String,
+}
+
+impl Elimination {
+    pub(crate) fn new(snake_id: &str, cause: &'static str, eliminated_by: &str) -> Self {
+        Self {
+            snake_id: snake_id.to_string(),
+            cause,
+            eliminated_by: eliminated_by.to_string(),
+        }
+    }
+}
+
+/// The hazards of a royale board on `turn`: every square outside a safe area
+/// that loses a row or column from a random side every `shrink_every_n_turns`
+/// turns
+///
+/// Follows the official royale rules. The sides are drawn from a generator
+/// seeded the same way every turn, so each turn's hazards contain the last's.
+pub fn royale_hazards(
+    width: i32,
+    height: i32,
+    turn: i32,
+    shrink_every_n_turns: i32,
+    seed: u64,
+) -> Vec<Position> {
+    if shrink_every_n_turns < 1 || turn < shrink_every_n_turns {
+        return vec![];
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (mut min_x, mut max_x) = (0, width - 1);
+    let (mut min_y, mut max_y) = (0, height - 1);
+    for _ in 0..turn / shrink_every_n_turns {
+        match rng.gen_range(0..4) {
+            0 => min_x += 1,
+            1 => max_x -= 1,
+            2 => min_y += 1,
+            _ => max_y -= 1,
+        }
+    }
+
+    let mut hazards = Vec::new();
+    for x in 0..width {
+        for y in 0..height {
+            if x < min_x || x > max_x || y < min_y || y > max_y {
+                hazards.push(Position::new(x, y));
+            }
+        }
+    }
+    hazards
+}
+
+/// The seed every random draw in a game comes from: the seed the game was
+/// created with, or one derived from its ID for games created without one
+///
+/// Replaying a game's moves with the same seed reproduces it exactly, down to
+/// the spawns, the food and the built-in bots' moves.
+pub fn engine_seed(game_id: &str, seed: Option<i64>) -> u64 {
+    seed.map_or_else(|| fnv1a(FNV_OFFSET, game_id.as_bytes()), |seed| seed as u64)
+}
+
+/// A generator for one kind of draw on one turn, from a game's seed
+///
+/// Each kind draws from its own stream, so a change to how many draws one
+/// kind makes doesn't shift any other. `StdRng` is ChaCha12, whose output is
+/// stable for a given seed across runs and platforms.
+pub(crate) fn turn_rng(seed: u64, turn: i32, stream: &str) -> StdRng {
+    let hash = fnv1a(FNV_OFFSET, &seed.to_le_bytes());
+    let hash = fnv1a(hash, &turn.to_le_bytes());
+    StdRng::seed_from_u64(fnv1a(hash, stream.as_bytes()))
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// FNV-1a, which unlike `DefaultHasher` gives the same hash in every build
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// How often the safe area shrinks, or None if the game isn't royale
+fn royale_shrink_every_n_turns(game: &Game) -> Option<i32> {
+    if game.game.ruleset.name != "royale" {
+        return None;
+    }
+    Some(
+        game.game
+            .ruleset
+            .settings
+            .as_ref()
+            .and_then(|settings| settings.royale.as_ref())
+            .map(|royale| royale.shrink_every_n_turns)
+            .unwrap_or(ROYALE_SHRINK_EVERY_N_TURNS),
+    )
+}
+
+/// Spawn food after a turn the way the standard rules do: top the board up to
+/// `minimum_food`, or otherwise place one piece with a `food_spawn_chance`
+/// percent chance
+///
+/// Food only goes on squares with no snake, food or hazard that no snake's
+/// head can move onto next turn. The draws come from the game's seed and the
+/// turn, so replaying a game spawns the same food. Games without ruleset
+/// settings don't spawn food.
+fn spawn_food(game: &mut Game, seed: u64) {
+    let Some(settings) = game.game.ruleset.settings.as_ref() else {
+        return;
+    };
+
+    let mut rng = turn_rng(seed, game.turn, "food");
+    let current = game.board.food.len();
+    let minimum = settings.minimum_food.max(0) as usize;
+    let count = if current < minimum {
+        minimum - current
+    } else if rng.gen_range(0..100) < settings.food_spawn_chance {
+        1
+    } else {
+        0
+    };
+    if count == 0 {
+        return;
+    }
+
+    let mut free = food_spawn_points(&game.board);
+    for _ in 0..count {
+        if free.is_empty() {
+            break;
+        }
+        let idx = rng.gen_range(0..free.len());
+        game.board.food.push(free.swap_remove(idx));
+    }
+}
+
+/// Squares food can spawn on, in a fixed order so seeded draws repeat
+fn food_spawn_points(board: &Board) -> Vec<Position> {
+    let alive: Vec<&BattleSnake> = board.snakes.iter().filter(|s| s.health > 0).collect();
+    let next_to_head = |p: &Position| {
+        alive
+            .iter()
+            .any(|s| (s.head.x - p.x).abs() + (s.head.y - p.y).abs() == 1)
+    };
+
+    let mut points = Vec::new();
+    for x in 0..board.width as i32 {
+        for y in 0..board.height as i32 {
+            let p = Position::new(x, y);
+            if !board.food.contains(&p)
+                && !board.hazards.contains(&p)
+                && !alive.iter().any(|s| s.body.contains(&p))
+                && !next_to_head(&p)
+            {
+                points.push(p);
+            }
+        }
+    }
+    points
+}
+
+/// Apply a single turn, seeded from the game's ID (see [`apply_seeded_turn`])
+pub fn apply_turn(game: Game, moves: &[(String, Move)]) -> Game {
+    let seed = engine_seed(&game.game.id, None);
+    apply_seeded_turn(game, moves, seed)
+}
+
+/// Apply a single turn: move snakes, reduce health, damage snakes in hazards,
+/// feed, eliminate, spawn food, then spread royale hazards. Food and hazards
+/// are drawn from `seed`.
+pub fn apply_seeded_turn(game: Game, moves: &[(String, Move)], seed: u64) -> Game {
+    apply_turn_with_eliminations(game, moves, seed).0
+}
+
+/// Apply a single turn like [`apply_seeded_turn`], also returning the snakes
+/// it eliminated, in the order the rules eliminate them
+pub fn apply_turn_with_eliminations(
+    mut game: Game,
+    moves: &[(String, Move)],
+    seed: u64,
+) -> (Game, Vec<Elimination>) {
+    let alive_before: Vec<String> = game
+        .board
+        .snakes
+        .iter()
+        .filter(|s| s.health > 0)
+        .map(|s| s.id.clone())
+        .collect();
+    let wrapped = game.game.ruleset.name == "wrapped";
+    let (width, height) = (game.board.width as i32, game.board.height as i32);
+
+    // 1. Move snakes
+    for snake in &mut game.board.snakes {
+        if snake.health <= 0 {
+            continue;
+        }
+
+        // Find the move for this snake
+        let snake_move = moves
+            .iter()
+            .find(|(id, _)| id == &snake.id)
+            .map(|(_, m)| *m)
+            .unwrap_or(Move::Up);
+
+        // Calculate new head position, coming back in on the opposite edge
+        // when a wrapped board's snake moves off it
+        let mut new_head = snake.head.add_vec(snake_move.to_vector());
+        if wrapped {
+            new_head = Position::new(new_head.x.rem_euclid(width), new_head.y.rem_euclid(height));
+        }
+
+        // Move: add new head, remove tail
+        snake.body.push_front(new_head);
+        snake.body.pop_back();
+        snake.head = new_head;
+    }
+
+    // 2. Reduce health
+    for snake in &mut game.board.snakes {
+        if snake.health > 0 {
+            snake.health -= 1;
+        }
+    }
+
+    // 3. Damage snakes in hazards, unless they're eating there. As in the
+    // official rules, a square listed as a hazard more than once deals its
+    // damage once per listing.
+    let hazard_damage = game
+        .game
+        .ruleset
+        .settings
+        .as_ref()
+        .map_or(HAZARD_DAMAGE_PER_TURN, |settings| {
+            settings.hazard_damage_per_turn
+        });
+    for snake in &mut game.board.snakes {
+        if snake.health <= 0 || game.board.food.contains(&snake.head) {
+            continue;
+        }
+        let stacked = game
+            .board
+            .hazards
+            .iter()
+            .filter(|h| **h == snake.head)
+            .count() as i32;
+        snake.health = (snake.health - hazard_damage * stacked).max(0);
+    }
+
+    // 4. Feed snakes (before elimination check)
+    let mut eaten_food = Vec::new();
+    for snake in &mut game.board.snakes {
+        if snake.health <= 0 {
+            continue;
+        }
+
+        // Check if head is on food
+        if let Some(food_idx) = game.board.food.iter().position(|f| *f == snake.head) {
+            // Eat the food
+            eaten_food.push(food_idx);
+            snake.health = SNAKE_MAX_HEALTH;
+            // Grow by duplicating tail
+            if let Some(tail) = snake.body.back().copied() {
+                snake.body.push_back(tail);
+            }
+        }
+    }
+
+    // Remove eaten food (in reverse order to preserve indices)
+    // Deduplicate in case multiple snakes ate the same food (head-to-head on food)
+    eaten_food.sort();
+    eaten_food.dedup();
+    eaten_food.reverse();
+    for idx in eaten_food {
+        game.board.food.remove(idx);
+    }
+
+    // 5. Eliminate snakes, those out of health first
+    let mut eliminations: Vec<Elimination> = game
+        .board
+        .snakes
+        .iter()
+        .filter(|s| s.health <= 0 && alive_before.contains(&s.id))
+        .map(|s| Elimination::new(&s.id, "out-of-health", ""))
+        .collect();
+    eliminations.extend(eliminate_snakes(&mut game));
+
+    // 6. Spawn food
+    spawn_food(&mut game, seed);
+
+    // 7. Shrink the royale safe area for the turn this produces
+    if let Some(shrink_every_n_turns) = royale_shrink_every_n_turns(&game) {
+        game.board.hazards = royale_hazards(
+            game.board.width as i32,
+            game.board.height as i32,
+            game.turn + 1,
+            shrink_every_n_turns,
+            seed,
+        );
+    }
+
+    // Update "you" to match the board state
+    if let Some(you_snake) = game.board.snakes.iter().find(|s| s.id == game.you.id) {
+        game.you = you_snake.clone();
+    }
+
+    (game, eliminations)
+}
+
+/// Eliminate snakes that are out of bounds or have collided, returning who
+/// was eliminated and by which snake
+fn eliminate_snakes(game: &mut Game) -> Vec<Elimination> {
+    let width = game.board.width as i32;
+    let height = game.board.height as i32;
+
+    // Collect elimination info first (can't mutate while iterating)
+    let mut eliminations: Vec<Elimination> = Vec::new();
+
+    // Check each snake
+    for snake in &game.board.snakes {
+        if snake.health <= 0 {
+            continue; // Already eliminated
+        }
+
+        let head = snake.head;
+
+        // Out of bounds check
+        if head.x < 0 || head.x >= width || head.y < 0 || head.y >= height {
+            eliminations.push(Elimination::new(&snake.id, "wall-collision", ""));
+            continue;
+        }
+
+        // Self collision check (head hitting own body, excluding head position)
+        let self_collision = snake.body.iter().skip(1).any(|p| *p == head);
+        if self_collision {
+            eliminations.push(Elimination::new(
+                &snake.id,
+                "snake-self-collision",
+                &snake.id,
+            ));
+            continue;
+        }
+
+        // Body collision with other snakes
+        let body_owner = game.board.snakes.iter().find(|other| {
+            other.id != snake.id
+                && other.health > 0
+                && other.body.iter().skip(1).any(|p| *p == head)
+        });
+        if let Some(owner) = body_owner {
+            eliminations.push(Elimination::new(&snake.id, "snake-collision", &owner.id));
+            continue;
+        }
+
+        // Head-to-head collision (lose if same size or smaller)
+        let head_winner = game.board.snakes.iter().find(|other| {
+            other.id != snake.id
+                && other.health > 0
+                && other.head == head
+                && snake.body.len() <= other.body.len()
+        });
+        if let Some(other) = head_winner {
+            eliminations.push(Elimination::new(&snake.id, "head-collision", &other.id));
+        }
+    }
+
+    // Apply eliminations
+    for elimination in &eliminations {
+        if let Some(snake) = game
+            .board
+            .snakes
+            .iter_mut()
+            .find(|s| s.id == elimination.snake_id)
+        {
+            snake.health = 0;
+        }
+    }
+    eliminations
+}
@@ -0,0 +1,473 @@
+//! Uploaded WASM rulesets
+//!
+//! A ruleset is a WebAssembly module that changes a game after the standard
+//! rules have played each turn: it can replace the food and the hazards, and
+//! eliminate snakes. Modules are untrusted, so they run in `wasmi`, an
+//! interpreter, and can't import anything; all a module can do is compute on
+//! the board it's given. Each turn gets a fresh instance with
+//! [`FUEL_PER_TURN`] fuel and at most [`MAX_MEMORY_BYTES`] of memory, so a
+//! module that loops forever or keeps allocating fails that turn instead of
+//! stalling the runner. Starting fresh also means a module can't carry state
+//! from one turn to the next, so a replay with the same seed plays the same.
+//!
+//! A module exports:
+//!
+//! - `memory`, its linear memory
+//! - `alloc(len: i32) -> i32`, returning where the host can write `len` bytes
+//! - `apply(ptr: i32, len: i32) -> i64`, which reads a [`RulesetInput`] as JSON
+//!   from `ptr` and returns where its [`RulesetChanges`] JSON is, as
+//!   `ptr << 32 | len`
+
+use std::collections::VecDeque;
+
+use battlesnake_game_types::wire_representation::{Game, Position};
+use color_eyre::eyre::{Context as _, bail, eyre};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+use wasmi::core::{TrapCode, ValType};
+use wasmi::{Config, Engine, ExternType, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use super::rules::Elimination;
+use crate::models::ruleset::get_ruleset_wasm;
+
+/// Largest module that can be uploaded
+pub const MAX_RULESET_BYTES: usize = 1024 * 1024;
+
+/// Fuel a ruleset gets for each turn; most instructions cost one
+pub const FUEL_PER_TURN: u64 = 10_000_000;
+
+/// Most memory a ruleset's instance can grow to
+pub const MAX_MEMORY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Largest change list a ruleset can return
+const MAX_CHANGES_BYTES: usize = 1024 * 1024;
+
+/// The cause given for snakes a ruleset eliminates
+pub const RULESET_ELIMINATION_CAUSE: &str = "ruleset";
+
+/// The board a ruleset is given, after the standard rules have played a turn
+#[derive(Debug, Serialize)]
+pub struct RulesetInput<'a> {
+    /// The turn the board is on
+    pub turn: i32,
+    /// The game's seed, for rulesets that make random draws
+    pub seed: u64,
+    pub width: u32,
+    pub height: u32,
+    /// Every snake on the board; eliminated ones have no health
+    pub snakes: Vec<RulesetSnake<'a>>,
+    pub food: &'a [Position],
+    pub hazards: &'a [Position],
+}
+
+#[derive(Debug, Serialize)]
+pub struct RulesetSnake<'a> {
+    pub id: &'a str,
+    pub health: i32,
+    pub body: &'a VecDeque<Position>,
+}
+
+/// What a ruleset changes; anything left out stays as the standard rules left it
+#[derive(Debug, Default, Deserialize)]
+pub struct RulesetChanges {
+    /// Replaces the food on the board
+    #[serde(default)]
+    pub food: Option<Vec<Position>>,
+    /// Replaces the hazards on the board; a square can be listed more than once
+    #[serde(default)]
+    pub hazards: Option<Vec<Position>>,
+    /// IDs of snakes to eliminate
+    #[serde(default)]
+    pub eliminate: Vec<String>,
+}
+
+/// A compiled ruleset, checked against the interface
+#[derive(Debug)]
+pub struct WasmRuleset {
+    engine: Engine,
+    module: Module,
+}
+
+impl WasmRuleset {
+    /// Compile a module, checking it imports nothing and exports the interface
+    pub fn compile(wasm: &[u8]) -> cja::Result<Self> {
+        if wasm.len() > MAX_RULESET_BYTES {
+            bail!(
+                "Module is {} bytes; rulesets can be at most {}",
+                wasm.len(),
+                MAX_RULESET_BYTES
+            );
+        }
+
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module =
+            Module::new(&engine, wasm).map_err(|e| eyre!("Not a valid WASM module: {}", e))?;
+
+        if let Some(import) = module.imports().next() {
+            bail!(
+                "Rulesets can't import anything, but this one imports {}.{}",
+                import.module(),
+                import.name()
+            );
+        }
+        if !matches!(module.get_export("memory"), Some(ExternType::Memory(_))) {
+            bail!("Rulesets must export their memory as \"memory\"");
+        }
+        let check_func =
+            |name: &str, params: &[ValType], results: &[ValType]| match module.get_export(name) {
+                Some(ExternType::Func(ty)) if ty.params() == params && ty.results() == results => {
+                    Ok(())
+                }
+                _ => Err(eyre!(
+                    "Rulesets must export {}({:?}) -> {:?}",
+                    name,
+                    params,
+                    results
+                )),
+            };
+        check_func("alloc", &[ValType::I32], &[ValType::I32])?;
+        check_func("apply", &[ValType::I32, ValType::I32], &[ValType::I64])?;
+
+        Ok(Self { engine, module })
+    }
+
+    /// Let the ruleset change a game after the standard rules have played a
+    /// turn, returning the snakes it eliminated
+    ///
+    /// Nothing is changed if the ruleset fails or asks for a change that
+    /// doesn't fit the board.
+    pub fn apply(&self, game: &mut Game, seed: u64) -> cja::Result<Vec<Elimination>> {
+        let input = RulesetInput {
+            turn: game.turn,
+            seed,
+            width: game.board.width,
+            height: game.board.height,
+            snakes: game
+                .board
+                .snakes
+                .iter()
+                .map(|s| RulesetSnake {
+                    id: &s.id,
+                    health: s.health,
+                    body: &s.body,
+                })
+                .collect(),
+            food: &game.board.food,
+            hazards: &game.board.hazards,
+        };
+        let input = serde_json::to_vec(&input).wrap_err("Failed to serialize ruleset input")?;
+        let changes = self.call(&input)?;
+        apply_changes(game, changes)
+    }
+
+    /// Run `apply` on a fresh instance, returning the changes it asked for
+    fn call(&self, input: &[u8]) -> cja::Result<RulesetChanges> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(MAX_MEMORY_BYTES)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits: &mut StoreLimits| limits);
+        store
+            .set_fuel(FUEL_PER_TURN)
+            .map_err(|e| eyre!("Failed to fuel ruleset: {}", e))?;
+
+        let instance = Linker::<StoreLimits>::new(&self.engine)
+            .instantiate(&mut store, &self.module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(ruleset_error)?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| eyre!("Ruleset has no memory"))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(ruleset_error)?;
+        let apply = instance
+            .get_typed_func::<(i32, i32), i64>(&store, "apply")
+            .map_err(ruleset_error)?;
+
+        let len = i32::try_from(input.len()).wrap_err("Ruleset input is too large")?;
+        let ptr = alloc.call(&mut store, len).map_err(ruleset_error)?;
+        memory
+            .write(&mut store, ptr as u32 as usize, input)
+            .map_err(|e| eyre!("Ruleset's alloc returned memory it doesn't have: {}", e))?;
+
+        let output = apply.call(&mut store, (ptr, len)).map_err(ruleset_error)? as u64;
+        let (output_ptr, output_len) = ((output >> 32) as usize, (output & 0xffff_ffff) as usize);
+        if output_len > MAX_CHANGES_BYTES {
+            bail!(
+                "Ruleset returned {} bytes of changes; the most is {}",
+                output_len,
+                MAX_CHANGES_BYTES
+            );
+        }
+        let mut changes = vec![0; output_len];
+        memory
+            .read(&store, output_ptr, &mut changes)
+            .map_err(|e| eyre!("Ruleset returned changes outside its memory: {}", e))?;
+
+        serde_json::from_slice(&changes).wrap_err("Ruleset returned invalid changes")
+    }
+}
+
+/// Load and compile an uploaded ruleset
+pub async fn load_ruleset(pool: &PgPool, ruleset_id: Uuid) -> cja::Result<WasmRuleset> {
+    let wasm = get_ruleset_wasm(pool, ruleset_id)
+        .await?
+        .ok_or_else(|| eyre!("Ruleset {} not found", ruleset_id))?;
+    WasmRuleset::compile(&wasm)
+        .wrap_err_with(|| format!("Failed to compile ruleset {}", ruleset_id))
+}
+
+fn ruleset_error(error: wasmi::Error) -> cja::color_eyre::Report {
+    match error.as_trap_code() {
+        Some(TrapCode::OutOfFuel) => eyre!("Ruleset ran out of fuel"),
+        _ => eyre!("Ruleset failed: {}", error),
+    }
+}
+
+/// Apply a ruleset's changes to the board, or none of them if any doesn't fit it
+fn apply_changes(game: &mut Game, changes: RulesetChanges) -> cja::Result<Vec<Elimination>> {
+    let (width, height) = (game.board.width as i32, game.board.height as i32);
+    let off_board = |positions: &Option<Vec<Position>>| {
+        positions
+            .iter()
+            .flatten()
+            .copied()
+            .find(|p| p.x < 0 || p.y < 0 || p.x >= width || p.y >= height)
+    };
+    if let Some(p) = off_board(&changes.food) {
+        bail!("Ruleset placed food off the board at ({}, {})", p.x, p.y);
+    }
+    if let Some(p) = off_board(&changes.hazards) {
+        bail!(
+            "Ruleset placed a hazard off the board at ({}, {})",
+            p.x,
+            p.y
+        );
+    }
+    if let Some(id) = changes
+        .eliminate
+        .iter()
+        .find(|id| !game.board.snakes.iter().any(|s| &s.id == *id))
+    {
+        bail!("Ruleset eliminated snake {}, which isn't in the game", id);
+    }
+
+    if let Some(food) = changes.food {
+        game.board.food = food;
+    }
+    if let Some(hazards) = changes.hazards {
+        game.board.hazards = hazards;
+    }
+    let mut eliminations = Vec::new();
+    for snake in &mut game.board.snakes {
+        if snake.health > 0 && changes.eliminate.contains(&snake.id) {
+            snake.health = 0;
+            eliminations.push(Elimination::new(&snake.id, RULESET_ELIMINATION_CAUSE, ""));
+        }
+    }
+    if let Some(you) = game.board.snakes.iter().find(|s| s.id == game.you.id) {
+        game.you = you.clone();
+    }
+
+    Ok(eliminations)
+}
+
+#[cfg(test)]
+mod tests {
+    use battlesnake_game_types::wire_representation::{BattleSnake, Board};
+
+    use super::*;
+    use crate::engine::create_game_from_board;
+    use crate::models::game::GameType;
+
+    /// A ruleset whose `apply` returns `changes` whatever the board
+    fn ruleset_returning(changes: &str) -> Vec<u8> {
+        let escaped = changes.replace('\\', "\\\\").replace('"', "\\\"");
+        wat::parse_str(format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{escaped}")
+                (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+                (func (export "apply") (param i32 i32) (result i64)
+                    (i64.const {len})))"#,
+            len = changes.len(),
+        ))
+        .unwrap()
+    }
+
+    fn two_snake_game() -> Game {
+        let snake = |id: &str, x: i32| BattleSnake {
+            id: id.to_string(),
+            name: id.to_string(),
+            head: Position::new(x, 1),
+            body: VecDeque::from([Position::new(x, 1); 3]),
+            health: 100,
+            shout: None,
+            actual_length: None,
+        };
+        let board = Board {
+            height: 7,
+            width: 7,
+            food: vec![Position::new(5, 5)],
+            snakes: vec![snake("snake-a", 1), snake("snake-b", 5)],
+            hazards: vec![],
+        };
+        create_game_from_board(uuid::Uuid::new_v4(), GameType::Standard, board)
+    }
+
+    /// The parts of a board a ruleset can change
+    fn changeable(game: &Game) -> (Vec<Position>, Vec<Position>, Vec<i32>) {
+        (
+            game.board.food.clone(),
+            game.board.hazards.clone(),
+            game.board.snakes.iter().map(|s| s.health).collect(),
+        )
+    }
+
+    #[test]
+    fn test_ruleset_changes_food_hazards_and_eliminations() {
+        let ruleset = WasmRuleset::compile(&ruleset_returning(
+            r#"{"food":[{"x":3,"y":3}],"hazards":[{"x":0,"y":0},{"x":0,"y":0}],"eliminate":["snake-b"]}"#,
+        ))
+        .unwrap();
+        let mut game = two_snake_game();
+
+        let eliminations = ruleset.apply(&mut game, 7).unwrap();
+
+        assert_eq!(game.board.food, vec![Position::new(3, 3)]);
+        assert_eq!(game.board.hazards, vec![Position::new(0, 0); 2]);
+        assert_eq!(
+            eliminations,
+            vec![Elimination::new("snake-b", RULESET_ELIMINATION_CAUSE, "")]
+        );
+        let health = |id: &str| {
+            game.board
+                .snakes
+                .iter()
+                .find(|s| s.id == id)
+                .unwrap()
+                .health
+        };
+        assert_eq!(health("snake-b"), 0);
+        assert!(health("snake-a") > 0);
+    }
+
+    #[test]
+    fn test_ruleset_leaves_out_what_it_doesnt_change() {
+        let ruleset = WasmRuleset::compile(&ruleset_returning("{}")).unwrap();
+        let mut game = two_snake_game();
+        let before = changeable(&game);
+
+        assert!(ruleset.apply(&mut game, 7).unwrap().is_empty());
+        assert_eq!(changeable(&game), before);
+    }
+
+    #[test]
+    fn test_ruleset_reads_the_board() {
+        // Echoes its input back, so the changes are the board's own food
+        let echo = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                (func (export "apply") (param $ptr i32) (param $len i32) (result i64)
+                    (i64.or
+                        (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                        (i64.extend_i32_u (local.get $len)))))"#,
+        )
+        .unwrap();
+        let ruleset = WasmRuleset::compile(&echo).unwrap();
+        let mut game = two_snake_game();
+        game.board.food = vec![Position::new(1, 2)];
+
+        assert!(ruleset.apply(&mut game, 7).unwrap().is_empty());
+        assert_eq!(game.board.food, vec![Position::new(1, 2)]);
+    }
+
+    #[test]
+    fn test_ruleset_that_loops_forever_runs_out_of_fuel() {
+        let looping = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                (func (export "apply") (param i32 i32) (result i64)
+                    (loop $forever (br $forever))
+                    (i64.const 0)))"#,
+        )
+        .unwrap();
+        let ruleset = WasmRuleset::compile(&looping).unwrap();
+        let mut game = two_snake_game();
+        let before = changeable(&game);
+
+        let error = ruleset.apply(&mut game, 7).unwrap_err();
+        assert!(error.to_string().contains("ran out of fuel"), "{}", error);
+        assert_eq!(changeable(&game), before);
+    }
+
+    #[test]
+    fn test_ruleset_cant_grow_memory_past_the_limit() {
+        let pages = MAX_MEMORY_BYTES / 65536 + 1;
+        let greedy = wat::parse_str(format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                (func (export "apply") (param i32 i32) (result i64)
+                    (if (i32.eq (memory.grow (i32.const {pages})) (i32.const -1))
+                        (then unreachable))
+                    (i64.const 0)))"#
+        ))
+        .unwrap();
+        let ruleset = WasmRuleset::compile(&greedy).unwrap();
+
+        assert!(ruleset.apply(&mut two_snake_game(), 7).is_err());
+    }
+
+    #[test]
+    fn test_rulesets_cant_import_anything() {
+        let importing = wat::parse_str(
+            r#"(module
+                (import "env" "random" (func (result i32)))
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                (func (export "apply") (param i32 i32) (result i64) (i64.const 0)))"#,
+        )
+        .unwrap();
+
+        let error = WasmRuleset::compile(&importing).unwrap_err();
+        assert!(error.to_string().contains("env.random"), "{}", error);
+    }
+
+    #[test]
+    fn test_rulesets_must_export_the_interface() {
+        let no_apply = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) (i32.const 0)))"#,
+        )
+        .unwrap();
+
+        assert!(WasmRuleset::compile(&no_apply).is_err());
+        assert!(WasmRuleset::compile(b"not wasm").is_err());
+    }
+
+    #[test]
+    fn test_ruleset_changes_off_the_board_are_rejected() {
+        let off_board = [
+            r#"{"food":[{"x":3,"y":3},{"x":7,"y":0}]}"#,
+            r#"{"hazards":[{"x":-1,"y":0}]}"#,
+            r#"{"eliminate":["snake-z"]}"#,
+        ];
+        for changes in off_board {
+            let ruleset = WasmRuleset::compile(&ruleset_returning(changes)).unwrap();
+            let mut game = two_snake_game();
+            let before = changeable(&game);
+
+            assert!(ruleset.apply(&mut game, 7).is_err(), "{}", changes);
+            assert_eq!(changeable(&game), before, "{}", changes);
+        }
+    }
+}
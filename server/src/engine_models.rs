@@ -235,7 +235,7 @@ pub struct PointState {
 }
 
 /// Combined export format for archiving a complete game to GCS.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameExport {
     pub game: EngineGame,
     pub frames: Vec<EngineGameFrame>,
@@ -0,0 +1,447 @@
+//! Federation: share public snakes and results between arenas
+//!
+//! Each arena in a federation signs what it exports with its own Ed25519 key
+//! and pulls from the peers it's configured with, checking their signatures
+//! against the public keys it was given. An arena only vouches for its own
+//! snakes: a peer's result is recognized only if every snake in it is one of
+//! the public snakes that peer lists. Synced snakes and results feed a
+//! combined leaderboard; they never show up in this arena's own games or
+//! ratings.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use color_eyre::eyre::{Context as _, eyre};
+use ring::signature::{ED25519, Ed25519KeyPair, KeyPair as _, UnparsedPublicKey};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::federation::{
+    FederatedResult, FederatedSnake, get_federation_cursor, replace_peer_snakes,
+    set_federation_cursor, upsert_peer_results,
+};
+use crate::models::game_battlesnake::FinishedPlacement;
+use crate::public_url::PublicUrlConfig;
+use crate::state::AppState;
+
+/// Header carrying the base64 Ed25519 signature of an export's body
+pub const SIGNATURE_HEADER: &str = "x-arena-signature";
+
+/// Results per export page
+pub const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// Most pages pulled from one peer per sync, so one busy peer can't hog the job
+const MAX_PAGES_PER_SYNC: usize = 20;
+
+/// A peer arena this one pulls from
+#[derive(Debug, Clone, PartialEq)]
+pub struct FederationPeer {
+    /// The peer's `BASE_URL`, without a trailing slash
+    pub base_url: String,
+    /// The peer's Ed25519 public key
+    pub public_key: Vec<u8>,
+}
+
+/// This arena's place in a federation
+#[derive(Clone)]
+pub struct FederationConfig {
+    /// This arena's `BASE_URL`, which peers know it by
+    pub instance_url: String,
+    key_pair: Arc<Ed25519KeyPair>,
+    pub peers: Vec<FederationPeer>,
+}
+
+impl std::fmt::Debug for FederationConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FederationConfig")
+            .field("instance_url", &self.instance_url)
+            .field("peers", &self.peers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// One page of what an arena shares with its peers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederationExport {
+    /// The exporting arena's `BASE_URL`
+    pub instance: String,
+    /// Every public snake on the exporting arena
+    pub snakes: Vec<FederatedSnake>,
+    /// Finished games between public snakes, in the order they finished
+    pub results: Vec<FederatedResult>,
+    /// Where the next page starts, None on the last page
+    pub next: Option<ExportCursor>,
+}
+
+/// Position in an arena's results, as query parameters of the export
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ExportCursor {
+    pub after: chrono::DateTime<chrono::Utc>,
+    pub after_game_id: Uuid,
+}
+
+/// Parse peers from `ARENA_FEDERATION_PEERS`: comma-separated entries of a
+/// peer's base URL and its base64 public key, separated by a space
+pub fn parse_peers(value: &str) -> cja::Result<Vec<FederationPeer>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (url, key) = entry
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| eyre!("Federation peer '{}' needs a URL and a public key", entry))?;
+            let public_key = STANDARD
+                .decode(key.trim())
+                .wrap_err_with(|| format!("Invalid public key for federation peer {}", url))?;
+            if public_key.len() != 32 {
+                return Err(eyre!(
+                    "Public key for federation peer {} isn't Ed25519",
+                    url
+                ));
+            }
+            Ok(FederationPeer {
+                base_url: url.trim_end_matches('/').to_string(),
+                public_key,
+            })
+        })
+        .collect()
+}
+
+/// A new Ed25519 key for `ARENA_FEDERATION_KEY`, and its public key, both base64
+pub fn generate_key() -> cja::Result<(String, String)> {
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new())
+        .map_err(|e| eyre!("Failed to generate federation key: {}", e))?;
+    let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref())
+        .map_err(|e| eyre!("Failed to load generated federation key: {}", e))?;
+
+    Ok((
+        STANDARD.encode(pkcs8.as_ref()),
+        STANDARD.encode(key_pair.public_key().as_ref()),
+    ))
+}
+
+/// Print a new key for `arena admin federation-key`
+pub fn print_new_key() -> cja::Result<()> {
+    let (key, public_key) = generate_key()?;
+    println!("ARENA_FEDERATION_KEY={key}");
+    println!("Public key (give this to peers): {public_key}");
+    Ok(())
+}
+
+/// Whether `signature` (base64) is `public_key`'s signature of `body`
+pub fn verify_signature(public_key: &[u8], body: &[u8], signature: &str) -> bool {
+    let Ok(signature) = STANDARD.decode(signature.trim()) else {
+        return false;
+    };
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(body, &signature)
+        .is_ok()
+}
+
+impl FederationConfig {
+    /// Federation is off unless `ARENA_FEDERATION_KEY` (a base64 PKCS#8
+    /// Ed25519 key) is set; it also needs `BASE_URL`, which peers know this
+    /// arena by
+    pub fn from_env(public_url: &PublicUrlConfig) -> cja::Result<Option<Self>> {
+        let Ok(key) = std::env::var("ARENA_FEDERATION_KEY") else {
+            return Ok(None);
+        };
+        let instance_url = public_url
+            .configured_base_url()
+            .ok_or_else(|| eyre!("BASE_URL must be set to join a federation"))?;
+
+        let pkcs8 = STANDARD
+            .decode(key.trim())
+            .wrap_err("ARENA_FEDERATION_KEY isn't valid base64")?;
+        let key_pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(&pkcs8)
+            .map_err(|e| eyre!("ARENA_FEDERATION_KEY isn't an Ed25519 key: {}", e))?;
+        let peers = parse_peers(&std::env::var("ARENA_FEDERATION_PEERS").unwrap_or_default())?;
+
+        Ok(Some(Self {
+            instance_url,
+            key_pair: Arc::new(key_pair),
+            peers,
+        }))
+    }
+
+    /// This arena's public key, base64, for peers to configure
+    pub fn public_key(&self) -> String {
+        STANDARD.encode(self.key_pair.public_key().as_ref())
+    }
+
+    /// Sign an export body, base64
+    pub fn sign(&self, body: &[u8]) -> String {
+        STANDARD.encode(self.key_pair.sign(body).as_ref())
+    }
+}
+
+/// The results in a peer's export it can vouch for: those where every snake
+/// is one of its own public snakes
+pub fn recognized_results(export: &FederationExport) -> Vec<FederatedResult> {
+    let listed: HashSet<Uuid> = export.snakes.iter().map(|s| s.snake_id).collect();
+    export
+        .results
+        .iter()
+        .filter(|result| {
+            !result.placements.is_empty()
+                && result
+                    .placements
+                    .iter()
+                    .all(|p| listed.contains(&p.snake_id))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Pull one page of a peer's export and check its signature
+async fn fetch_export(
+    client: &reqwest::Client,
+    peer: &FederationPeer,
+    cursor: Option<(chrono::DateTime<chrono::Utc>, Uuid)>,
+) -> cja::Result<FederationExport> {
+    let mut request = client.get(format!("{}/api/v1/federation/export", peer.base_url));
+    if let Some((after, after_game_id)) = cursor {
+        request = request.query(&ExportCursor {
+            after,
+            after_game_id,
+        });
+    }
+    let response = request
+        .send()
+        .await
+        .wrap_err_with(|| format!("Failed to reach federation peer {}", peer.base_url))?;
+
+    if !response.status().is_success() {
+        return Err(eyre!(
+            "Federation peer {} returned {}",
+            peer.base_url,
+            response.status()
+        ));
+    }
+
+    let signature = response
+        .headers()
+        .get(SIGNATURE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .ok_or_else(|| eyre!("Federation peer {} didn't sign its export", peer.base_url))?;
+    let body = response
+        .bytes()
+        .await
+        .wrap_err_with(|| format!("Failed to read export from peer {}", peer.base_url))?;
+    if !verify_signature(&peer.public_key, &body, &signature) {
+        return Err(eyre!(
+            "Export from federation peer {} has a bad signature",
+            peer.base_url
+        ));
+    }
+
+    let export: FederationExport = serde_json::from_slice(&body)
+        .wrap_err_with(|| format!("Invalid export from peer {}", peer.base_url))?;
+    if export.instance.trim_end_matches('/') != peer.base_url {
+        return Err(eyre!(
+            "Federation peer {} sent an export for {}",
+            peer.base_url,
+            export.instance
+        ));
+    }
+
+    Ok(export)
+}
+
+/// Pull a peer's snakes and new results, returning how many results were synced
+pub async fn sync_peer(app_state: &AppState, peer: &FederationPeer) -> cja::Result<usize> {
+    let client = reqwest::Client::new();
+    let mut cursor = get_federation_cursor(&app_state.db, &peer.base_url).await?;
+    let mut synced = 0;
+
+    for page in 0..MAX_PAGES_PER_SYNC {
+        let export = fetch_export(&client, peer, cursor).await?;
+
+        // Every page lists all the peer's snakes, so the first is enough
+        if page == 0 {
+            replace_peer_snakes(
+                &app_state.db,
+                &peer.base_url,
+                &export.snakes,
+                app_state.clock.now(),
+            )
+            .await?;
+        }
+
+        let results = recognized_results(&export);
+        upsert_peer_results(&app_state.db, &peer.base_url, &results).await?;
+        synced += results.len();
+
+        if let Some(last) = export.results.last() {
+            let next = (last.finished_at, last.game_id);
+            set_federation_cursor(&app_state.db, &peer.base_url, next, app_state.clock.now())
+                .await?;
+            cursor = Some(next);
+        }
+        if export.next.is_none() {
+            break;
+        }
+    }
+
+    Ok(synced)
+}
+
+/// Pull from every configured peer
+pub async fn sync_peers(app_state: &AppState) -> cja::Result<()> {
+    let Some(config) = &app_state.federation else {
+        return Ok(());
+    };
+
+    for peer in &config.peers {
+        // One peer being down shouldn't stop the others syncing
+        match sync_peer(app_state, peer).await {
+            Ok(synced) => {
+                tracing::info!(peer = %peer.base_url, "Synced {} federated results", synced)
+            }
+            Err(e) => {
+                tracing::warn!(peer = %peer.base_url, "Failed to sync federation peer: {:?}", e)
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Merge results from every arena into placements for rating, oldest first
+pub fn combined_placements(mut results: Vec<FederatedResult>) -> Vec<FinishedPlacement> {
+    results.sort_by(|a, b| {
+        a.finished_at
+            .cmp(&b.finished_at)
+            .then_with(|| a.game_id.cmp(&b.game_id))
+    });
+
+    results
+        .into_iter()
+        .flat_map(|result| {
+            result
+                .placements
+                .into_iter()
+                .map(move |p| FinishedPlacement {
+                    game_id: result.game_id,
+                    battlesnake_id: p.snake_id,
+                    placement: p.placement,
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::federation::FederatedPlacement;
+
+    fn result(finished_at: i64, placements: &[(Uuid, i32)]) -> FederatedResult {
+        FederatedResult {
+            game_id: Uuid::new_v4(),
+            finished_at: chrono::DateTime::from_timestamp(finished_at, 0).unwrap(),
+            placements: placements
+                .iter()
+                .map(|&(snake_id, placement)| FederatedPlacement {
+                    snake_id,
+                    placement,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let (key, public_key) = generate_key().unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(&STANDARD.decode(key).unwrap()).unwrap();
+        let config = FederationConfig {
+            instance_url: "https://arena.example.com".to_string(),
+            key_pair: Arc::new(key_pair),
+            peers: Vec::new(),
+        };
+        assert_eq!(config.public_key(), public_key);
+
+        let public_key = STANDARD.decode(public_key).unwrap();
+        let signature = config.sign(b"{\"results\":[]}");
+        assert!(verify_signature(
+            &public_key,
+            b"{\"results\":[]}",
+            &signature
+        ));
+        assert!(!verify_signature(
+            &public_key,
+            b"{\"results\":[1]}",
+            &signature
+        ));
+        assert!(!verify_signature(
+            &public_key,
+            b"{\"results\":[]}",
+            "not base64!"
+        ));
+    }
+
+    #[test]
+    fn test_parse_peers() {
+        let key = STANDARD.encode([7u8; 32]);
+        let peers = parse_peers(&format!(
+            "https://a.example.com/ {key}, https://b.example.com/arena {key}"
+        ))
+        .unwrap();
+        assert_eq!(
+            peers
+                .iter()
+                .map(|p| p.base_url.as_str())
+                .collect::<Vec<_>>(),
+            vec!["https://a.example.com", "https://b.example.com/arena"]
+        );
+        assert_eq!(peers[0].public_key, vec![7u8; 32]);
+
+        assert!(parse_peers("").unwrap().is_empty());
+        assert!(parse_peers("https://a.example.com").is_err());
+        assert!(parse_peers("https://a.example.com c2hvcnQ=").is_err());
+    }
+
+    #[test]
+    fn test_recognized_results_need_the_peers_own_snakes() {
+        let (own, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let export = FederationExport {
+            instance: "https://a.example.com".to_string(),
+            snakes: vec![FederatedSnake {
+                snake_id: own,
+                name: "Own".to_string(),
+                owner: "someone".to_string(),
+            }],
+            results: vec![
+                result(1, &[(own, 1)]),
+                result(2, &[(own, 1), (other, 2)]),
+                result(3, &[]),
+            ],
+            next: None,
+        };
+
+        let recognized = recognized_results(&export);
+        assert_eq!(recognized, vec![export.results[0].clone()]);
+    }
+
+    #[test]
+    fn test_combined_placements_orders_by_finish() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let later = result(20, &[(b, 1), (a, 2)]);
+        let earlier = result(10, &[(a, 1), (b, 2)]);
+
+        let placements = combined_placements(vec![later.clone(), earlier.clone()]);
+        assert_eq!(
+            placements.iter().map(|p| p.game_id).collect::<Vec<_>>(),
+            vec![
+                earlier.game_id,
+                earlier.game_id,
+                later.game_id,
+                later.game_id
+            ]
+        );
+        assert_eq!(placements[0].battlesnake_id, a);
+    }
+}
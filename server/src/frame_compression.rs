@@ -0,0 +1,182 @@
+//! Storing turn frames zstd-compressed
+//!
+//! Frame JSON is most of the turns table, and a 500-turn 19x19 game's frames
+//! run to megabytes. A turn's frame is stored either as JSONB in `frame_data`
+//! or as zstd-compressed JSON in `frame_compressed`, never both. Reads through
+//! [`crate::models::turn`] decompress transparently, so callers only ever see
+//! `frame_data`.
+//!
+//! Compression is rolled out gradually with `ARENA_COMPRESS_FRAMES_PERCENT`
+//! (0 by default). That share of games, picked by game ID, store their new
+//! frames compressed, and [`backfill_compression`], run by a cron job,
+//! compresses the frames those games already have. Lowering the percentage
+//! only stops new frames being compressed; compressed frames still read fine.
+
+use std::sync::OnceLock;
+
+use color_eyre::eyre::Context as _;
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// zstd level frames are compressed at, the same as game archives
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Turns the backfill compresses per query
+const BACKFILL_BATCH_SIZE: i64 = 500;
+
+/// Most batches one backfill run compresses, so a large backlog is spread over runs
+const BACKFILL_MAX_BATCHES: usize = 20;
+
+static COMPRESS_FRAMES_PERCENT: OnceLock<u8> = OnceLock::new();
+
+/// Share of games whose frames are compressed: `ARENA_COMPRESS_FRAMES_PERCENT`,
+/// clamped to 0-100
+pub fn compress_frames_percent() -> u8 {
+    *COMPRESS_FRAMES_PERCENT.get_or_init(|| {
+        let percent = std::env::var("ARENA_COMPRESS_FRAMES_PERCENT")
+            .ok()
+            .and_then(|s| s.trim().parse::<u8>().ok())
+            .unwrap_or(0)
+            .min(100);
+        tracing::info!(percent, "Frame compression configured");
+        percent
+    })
+}
+
+/// A game's rollout bucket, 0-99. The backfill's SQL picks games the same way.
+fn rollout_bucket(game_id: Uuid) -> u8 {
+    game_id.as_bytes()[15] % 100
+}
+
+/// Whether a game's frames are stored compressed with `percent` rolled out
+fn compressed_at(game_id: Uuid, percent: u8) -> bool {
+    rollout_bucket(game_id) < percent
+}
+
+/// Whether a game's new frames are stored compressed
+pub fn should_compress(game_id: Uuid) -> bool {
+    compressed_at(game_id, compress_frames_percent())
+}
+
+pub fn compress_frame(frame: &Value) -> cja::Result<Vec<u8>> {
+    let json = serde_json::to_vec(frame).wrap_err("Failed to serialize frame")?;
+    zstd::encode_all(json.as_slice(), COMPRESSION_LEVEL).wrap_err("Failed to compress frame")
+}
+
+pub fn decompress_frame(compressed: &[u8]) -> cja::Result<Value> {
+    let json = zstd::decode_all(compressed).wrap_err("Failed to decompress frame")?;
+    serde_json::from_slice(&json).wrap_err("Failed to parse decompressed frame")
+}
+
+/// A frame as it's written to the turns table: `(frame_data, frame_compressed)`,
+/// with at most one of them set
+pub fn encode_frame(
+    game_id: Uuid,
+    frame: Option<Value>,
+) -> cja::Result<(Option<Value>, Option<Vec<u8>>)> {
+    match frame {
+        Some(frame) if should_compress(game_id) => Ok((None, Some(compress_frame(&frame)?))),
+        frame => Ok((frame, None)),
+    }
+}
+
+#[derive(Debug)]
+struct UncompressedTurn {
+    turn_id: Uuid,
+    frame_data: Value,
+}
+
+/// Compress the stored JSON frames of games in the rollout, a batch at a time.
+/// Returns how many turns were compressed.
+pub async fn backfill_compression(pool: &PgPool) -> cja::Result<usize> {
+    let percent = compress_frames_percent();
+    if percent == 0 {
+        tracing::debug!("Frame compression not rolled out, nothing to backfill");
+        return Ok(0);
+    }
+
+    let mut compressed = 0;
+    for _ in 0..BACKFILL_MAX_BATCHES {
+        let turns = sqlx::query_as!(
+            UncompressedTurn,
+            r#"
+            SELECT turn_id, frame_data AS "frame_data!"
+            FROM turns
+            WHERE frame_data IS NOT NULL
+              AND get_byte(uuid_send(game_id), 15) % 100 < $1
+            LIMIT $2
+            "#,
+            (percent as i32),
+            BACKFILL_BATCH_SIZE
+        )
+        .fetch_all(pool)
+        .await
+        .wrap_err("Failed to fetch uncompressed frames")?;
+        if turns.is_empty() {
+            break;
+        }
+
+        let turn_ids: Vec<Uuid> = turns.iter().map(|t| t.turn_id).collect();
+        let frames = turns
+            .iter()
+            .map(|t| compress_frame(&t.frame_data))
+            .collect::<cja::Result<Vec<Vec<u8>>>>()?;
+
+        // Skips turns whose frame was rewritten since it was read
+        let result = sqlx::query!(
+            r#"
+            UPDATE turns t
+            SET frame_compressed = c.frame_compressed, frame_data = NULL
+            FROM UNNEST($1::UUID[], $2::BYTEA[]) AS c (turn_id, frame_compressed)
+            WHERE t.turn_id = c.turn_id AND t.frame_data IS NOT NULL
+            "#,
+            &turn_ids,
+            &frames
+        )
+        .execute(pool)
+        .await
+        .wrap_err("Failed to store compressed frames")?;
+        compressed += result.rows_affected() as usize;
+    }
+
+    tracing::info!(compressed, percent, "Backfilled frame compression");
+    Ok(compressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_frames_round_trip_through_compression() {
+        let frame = json!({
+            "Turn": 12,
+            "Snakes": [{"ID": "a", "Body": [{"X": 1, "Y": 2}], "Health": 90}],
+            "Food": [{"X": 5, "Y": 5}],
+            "Hazards": []
+        });
+        let compressed = compress_frame(&frame).unwrap();
+        assert_eq!(decompress_frame(&compressed).unwrap(), frame);
+        assert!(decompress_frame(b"not zstd").is_err());
+    }
+
+    #[test]
+    fn test_rollout_picks_games_by_id() {
+        let game_ids: Vec<Uuid> = (0..1000).map(|_| Uuid::new_v4()).collect();
+
+        assert!(game_ids.iter().all(|id| !compressed_at(*id, 0)));
+        assert!(game_ids.iter().all(|id| compressed_at(*id, 100)));
+
+        // A game in the rollout stays in it as the percentage goes up
+        for id in &game_ids {
+            if compressed_at(*id, 10) {
+                assert!(compressed_at(*id, 50));
+            }
+        }
+        let half = game_ids.iter().filter(|id| compressed_at(**id, 50)).count();
+        assert!((350..650).contains(&half), "{half} of 1000 games at 50%");
+    }
+}
@@ -0,0 +1,441 @@
+//! Deltas between a game's consecutive frames
+//!
+//! Spectators that ask to fast-forward get a keyframe, a whole frame, and then
+//! a delta for each turn after it, instead of every frame since turn 0. A delta
+//! has the frame's `Turn`, each other top-level field that changed (such as
+//! `Food` or `Hazards`) in full, and `Snakes` with only the snakes that
+//! changed: each one's `ID` and the fields that differ from the frame before.
+//! Snakes that didn't change are left out, and `Snakes` is left out when none
+//! did. Applying each delta to the frame before it gives back the full frame.
+//!
+//! A patch is a smaller delta for clients that negotiate it. A moved snake's
+//! `Body` becomes the points added at its head and how many were dropped from
+//! its tail, and `Food` and `Hazards` become the points added and removed.
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+/// Turns between keyframes on a fast-forwarded stream
+pub const KEYFRAME_INTERVAL: i32 = 50;
+
+const TURN: &str = "Turn";
+const SNAKES: &str = "Snakes";
+const ID: &str = "ID";
+const BODY: &str = "Body";
+const HEAD: &str = "Head";
+const TAIL: &str = "Tail";
+const GROW: &str = "Grow";
+
+/// Point lists a patch sends as changes, with its keys for the points added
+/// and the points removed
+const POINT_FIELDS: [(&str, &str, &str); 2] = [
+    ("Food", "FoodSpawned", "FoodEaten"),
+    ("Hazards", "HazardsAdded", "HazardsRemoved"),
+];
+
+/// The latest keyframe turn at or before `turn`
+pub fn keyframe_turn(turn: i32) -> i32 {
+    turn - turn.rem_euclid(KEYFRAME_INTERVAL)
+}
+
+/// Whether a stream should send `turn` as a keyframe rather than a delta
+pub fn is_keyframe(turn: i32) -> bool {
+    turn.rem_euclid(KEYFRAME_INTERVAL) == 0
+}
+
+/// The delta that turns frame `prev` into frame `next`
+pub fn diff(prev: &Value, next: &Value) -> Value {
+    let (Some(prev), Some(next)) = (prev.as_object(), next.as_object()) else {
+        return next.clone();
+    };
+
+    let mut delta = Map::new();
+    for (key, value) in next {
+        if key != SNAKES && (key == TURN || prev.get(key) != Some(value)) {
+            delta.insert(key.clone(), value.clone());
+        }
+    }
+
+    if let Some(snakes) = next.get(SNAKES).and_then(Value::as_array) {
+        let before: HashMap<&str, &Map<String, Value>> = prev
+            .get(SNAKES)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|snake| Some((snake.get(ID)?.as_str()?, snake.as_object()?)))
+            .collect();
+
+        let changed: Vec<Value> = snakes
+            .iter()
+            .filter_map(|snake| {
+                let (Some(id), Some(fields)) =
+                    (snake.get(ID).and_then(Value::as_str), snake.as_object())
+                else {
+                    return Some(snake.clone());
+                };
+                let Some(before) = before.get(id) else {
+                    return Some(snake.clone());
+                };
+
+                let changes: Map<String, Value> = fields
+                    .iter()
+                    .filter(|(key, value)| *key == ID || before.get(*key) != Some(*value))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect();
+                (changes.len() > 1).then_some(Value::Object(changes))
+            })
+            .collect();
+
+        if !changed.is_empty() {
+            delta.insert(SNAKES.to_string(), Value::Array(changed));
+        }
+    }
+
+    Value::Object(delta)
+}
+
+/// The frame after `prev`, from its delta
+pub fn apply(prev: &Value, delta: &Value) -> Value {
+    let (Some(prev), Some(delta)) = (prev.as_object(), delta.as_object()) else {
+        return delta.clone();
+    };
+
+    let mut frame = prev.clone();
+    for (key, value) in delta {
+        if key != SNAKES {
+            frame.insert(key.clone(), value.clone());
+        }
+    }
+
+    if let Some(changes) = delta.get(SNAKES).and_then(Value::as_array) {
+        let snakes = frame
+            .entry(SNAKES)
+            .or_insert_with(|| Value::Array(Vec::new()));
+        if let Some(snakes) = snakes.as_array_mut() {
+            for change in changes {
+                let existing = snakes
+                    .iter_mut()
+                    .find(|snake| snake.get(ID).is_some() && snake.get(ID) == change.get(ID));
+                match (existing, change.as_object()) {
+                    (Some(Value::Object(snake)), Some(fields)) => {
+                        for (key, value) in fields {
+                            snake.insert(key.clone(), value.clone());
+                        }
+                    }
+                    _ => snakes.push(change.clone()),
+                }
+            }
+        }
+    }
+
+    Value::Object(frame)
+}
+
+/// How a snake's body changed: `(head, tail, grow)`, for the new body made of
+/// its first `head` points, the old body less its last `tail` points, and
+/// `grow` copies of the point before them stacked at the end
+fn body_change(before: &[Value], after: &[Value]) -> (usize, usize, usize) {
+    (0..=after.len())
+        .find_map(|head| {
+            let keep = after[head..]
+                .iter()
+                .zip(before)
+                .take_while(|(a, b)| a == b)
+                .count();
+            let grown = &after[head + keep..];
+            let last = after[..head + keep].last();
+            grown.iter().all(|point| Some(point) == last).then_some((
+                head,
+                before.len() - keep,
+                grown.len(),
+            ))
+        })
+        .unwrap_or((after.len(), before.len(), 0))
+}
+
+/// `before` without the `removed` points, then with the `added` ones
+fn apply_points(before: &[Value], added: &[Value], removed: &[Value]) -> Vec<Value> {
+    before
+        .iter()
+        .filter(|point| !removed.contains(point))
+        .chain(added)
+        .cloned()
+        .collect()
+}
+
+fn points(value: Option<&Value>) -> &[Value] {
+    value
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn count(value: Option<Value>) -> usize {
+    value.and_then(|v| v.as_u64()).unwrap_or(0) as usize
+}
+
+/// The patch that turns frame `prev` into frame `next`: its [`diff`], with
+/// moved snakes' bodies and changed point lists sent as what changed. A point
+/// list whose changes wouldn't rebuild it exactly, such as one that was
+/// reordered, is sent in full.
+pub fn patch(prev: &Value, next: &Value) -> Value {
+    let mut delta = diff(prev, next);
+    let (Some(prev), Some(fields)) = (prev.as_object(), delta.as_object_mut()) else {
+        return delta;
+    };
+
+    for (field, added_key, removed_key) in POINT_FIELDS {
+        let Some(after) = fields.get(field).and_then(Value::as_array) else {
+            continue;
+        };
+        let before = points(prev.get(field));
+        let added: Vec<Value> = after
+            .iter()
+            .filter(|point| !before.contains(point))
+            .cloned()
+            .collect();
+        let removed: Vec<Value> = before
+            .iter()
+            .filter(|point| !after.contains(point))
+            .cloned()
+            .collect();
+        if apply_points(before, &added, &removed) != *after {
+            continue;
+        }
+
+        fields.remove(field);
+        if !added.is_empty() {
+            fields.insert(added_key.to_string(), Value::Array(added));
+        }
+        if !removed.is_empty() {
+            fields.insert(removed_key.to_string(), Value::Array(removed));
+        }
+    }
+
+    if let Some(Value::Array(changes)) = fields.get_mut(SNAKES) {
+        for change in changes.iter_mut().filter_map(Value::as_object_mut) {
+            let Some(before) = snake_body(prev, change.get(ID)) else {
+                continue;
+            };
+            let Some(after) = change.get(BODY).and_then(Value::as_array).cloned() else {
+                continue;
+            };
+            change.remove(BODY);
+
+            let (head, tail, grow) = body_change(before, &after);
+            if head > 0 {
+                change.insert(HEAD.to_string(), Value::Array(after[..head].to_vec()));
+            }
+            for (key, count) in [(TAIL, tail), (GROW, grow)] {
+                if count > 0 {
+                    change.insert(key.to_string(), Value::from(count));
+                }
+            }
+        }
+    }
+
+    delta
+}
+
+/// The body of the snake with ID `id` in `frame`
+fn snake_body<'a>(frame: &'a Map<String, Value>, id: Option<&Value>) -> Option<&'a [Value]> {
+    let id = id?;
+    frame
+        .get(SNAKES)?
+        .as_array()?
+        .iter()
+        .find(|snake| snake.get(ID) == Some(id))?
+        .get(BODY)?
+        .as_array()
+        .map(Vec::as_slice)
+}
+
+/// The frame after `prev`, from its [`patch`]
+pub fn apply_patch(prev: &Value, patch: &Value) -> Value {
+    let (Some(before), Some(patch)) = (prev.as_object(), patch.as_object()) else {
+        return patch.clone();
+    };
+
+    let mut delta = patch.clone();
+    for (field, added_key, removed_key) in POINT_FIELDS {
+        let added = delta.remove(added_key);
+        let removed = delta.remove(removed_key);
+        if added.is_some() || removed.is_some() {
+            let points = apply_points(
+                points(before.get(field)),
+                points(added.as_ref()),
+                points(removed.as_ref()),
+            );
+            delta.insert(field.to_string(), Value::Array(points));
+        }
+    }
+
+    if let Some(Value::Array(changes)) = delta.get_mut(SNAKES) {
+        for change in changes.iter_mut().filter_map(Value::as_object_mut) {
+            let head = change.remove(HEAD);
+            let tail = count(change.remove(TAIL));
+            let grow = count(change.remove(GROW));
+            if head.is_none() && tail == 0 && grow == 0 {
+                continue;
+            }
+
+            let old_body = snake_body(before, change.get(ID)).unwrap_or_default();
+            let mut body = points(head.as_ref()).to_vec();
+            body.extend_from_slice(&old_body[..old_body.len().saturating_sub(tail)]);
+            if let Some(last) = body.last().cloned() {
+                body.extend(std::iter::repeat_n(last, grow));
+            }
+            change.insert(BODY.to_string(), Value::Array(body));
+        }
+    }
+
+    apply(prev, &Value::Object(delta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn frame(turn: i32, food: Value, snakes: Value) -> Value {
+        json!({ "Turn": turn, "Food": food, "Hazards": [], "Snakes": snakes })
+    }
+
+    #[test]
+    fn test_keyframe_turns() {
+        assert_eq!(keyframe_turn(0), 0);
+        assert_eq!(keyframe_turn(49), 0);
+        assert_eq!(keyframe_turn(50), 50);
+        assert_eq!(keyframe_turn(437), 400);
+
+        assert!(is_keyframe(0));
+        assert!(is_keyframe(400));
+        assert!(!is_keyframe(401));
+    }
+
+    #[test]
+    fn test_diff_keeps_only_changes() {
+        let prev = frame(
+            3,
+            json!([{ "X": 5, "Y": 5 }]),
+            json!([
+                { "ID": "a", "Body": [{ "X": 1, "Y": 1 }], "Health": 97, "Death": null },
+                { "ID": "b", "Body": [{ "X": 9, "Y": 9 }], "Health": 0, "Death": { "Cause": "wall-collision", "Turn": 2 } }
+            ]),
+        );
+        let next = frame(
+            4,
+            json!([{ "X": 5, "Y": 5 }]),
+            json!([
+                { "ID": "a", "Body": [{ "X": 1, "Y": 2 }], "Health": 96, "Death": null },
+                { "ID": "b", "Body": [{ "X": 9, "Y": 9 }], "Health": 0, "Death": { "Cause": "wall-collision", "Turn": 2 } }
+            ]),
+        );
+
+        assert_eq!(
+            diff(&prev, &next),
+            json!({
+                "Turn": 4,
+                "Snakes": [{ "ID": "a", "Body": [{ "X": 1, "Y": 2 }], "Health": 96 }]
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_rebuilds_each_frame() {
+        let frames = [
+            frame(
+                0,
+                json!([{ "X": 5, "Y": 5 }]),
+                json!([
+                    { "ID": "a", "Body": [{ "X": 1, "Y": 1 }], "Health": 100, "Death": null },
+                    { "ID": "b", "Body": [{ "X": 9, "Y": 9 }], "Health": 100, "Death": null }
+                ]),
+            ),
+            frame(
+                1,
+                json!([]),
+                json!([
+                    { "ID": "a", "Body": [{ "X": 1, "Y": 2 }], "Health": 99, "Death": null },
+                    { "ID": "b", "Body": [{ "X": 9, "Y": 10 }], "Health": 99, "Death": { "Cause": "wall-collision", "Turn": 1 } }
+                ]),
+            ),
+            frame(
+                2,
+                json!([{ "X": 0, "Y": 0 }]),
+                json!([
+                    { "ID": "a", "Body": [{ "X": 1, "Y": 3 }], "Health": 98, "Death": null },
+                    { "ID": "b", "Body": [{ "X": 9, "Y": 10 }], "Health": 99, "Death": { "Cause": "wall-collision", "Turn": 1 } }
+                ]),
+            ),
+        ];
+
+        let mut rebuilt = frames[0].clone();
+        for pair in frames.windows(2) {
+            rebuilt = apply(&rebuilt, &diff(&pair[0], &pair[1]));
+            assert_eq!(rebuilt, pair[1]);
+        }
+    }
+
+    #[test]
+    fn test_patch_sends_moves_and_changed_points() {
+        let prev = json!({
+            "Turn": 7,
+            "Food": [{ "X": 1, "Y": 3 }, { "X": 6, "Y": 6 }],
+            "Hazards": [{ "X": 0, "Y": 0 }],
+            "Snakes": [
+                { "ID": "a", "Body": [{ "X": 1, "Y": 2 }, { "X": 1, "Y": 1 }, { "X": 1, "Y": 0 }], "Health": 90 },
+                { "ID": "b", "Body": [{ "X": 5, "Y": 5 }, { "X": 5, "Y": 4 }, { "X": 5, "Y": 3 }], "Health": 80 }
+            ]
+        });
+        let next = json!({
+            "Turn": 8,
+            "Food": [{ "X": 6, "Y": 6 }],
+            "Hazards": [{ "X": 0, "Y": 0 }, { "X": 0, "Y": 1 }],
+            "Snakes": [
+                { "ID": "a", "Body": [{ "X": 1, "Y": 3 }, { "X": 1, "Y": 2 }, { "X": 1, "Y": 1 }, { "X": 1, "Y": 1 }], "Health": 100 },
+                { "ID": "b", "Body": [{ "X": 4, "Y": 5 }, { "X": 5, "Y": 5 }, { "X": 5, "Y": 4 }], "Health": 79 }
+            ]
+        });
+
+        // a ate, so its tail stacks; b just moved
+        let patch = patch(&prev, &next);
+        assert_eq!(
+            patch,
+            json!({
+                "Turn": 8,
+                "FoodEaten": [{ "X": 1, "Y": 3 }],
+                "HazardsAdded": [{ "X": 0, "Y": 1 }],
+                "Snakes": [
+                    { "ID": "a", "Head": [{ "X": 1, "Y": 3 }], "Tail": 1, "Grow": 1, "Health": 100 },
+                    { "ID": "b", "Head": [{ "X": 4, "Y": 5 }], "Tail": 1, "Health": 79 }
+                ]
+            })
+        );
+        assert_eq!(apply_patch(&prev, &patch), next);
+    }
+
+    #[test]
+    fn test_patch_falls_back_to_whole_fields() {
+        let prev = json!({
+            "Turn": 1,
+            "Food": [{ "X": 1, "Y": 1 }, { "X": 2, "Y": 2 }],
+            "Snakes": [{ "ID": "a", "Body": [{ "X": 3, "Y": 3 }], "Health": 99 }]
+        });
+        let next = json!({
+            "Turn": 2,
+            "Food": [{ "X": 2, "Y": 2 }, { "X": 1, "Y": 1 }],
+            "Snakes": [
+                { "ID": "a", "Body": [{ "X": 0, "Y": 0 }, { "X": 0, "Y": 1 }], "Health": 98 },
+                { "ID": "c", "Body": [{ "X": 6, "Y": 6 }], "Health": 100 }
+            ]
+        });
+
+        // Reordered food and a snake that joined are sent whole
+        let patch = patch(&prev, &next);
+        assert_eq!(patch["Food"], next["Food"]);
+        assert_eq!(patch["Snakes"][1], next["Snakes"][1]);
+        assert_eq!(apply_patch(&prev, &patch), next);
+    }
+}
@@ -0,0 +1,248 @@
+//! Versioned schema for stored frames
+//!
+//! Every turn records the `frame_schema_version` its frame was written at.
+//! When the frame format changes, the version goes up and a converter that
+//! upgrades frames from the previous version joins [`CONVERTERS`]. Turns are
+//! upgraded lazily as they're read, so old games are always served in the
+//! current format, and [`backfill_frames`], run by a cron job, rewrites them
+//! eagerly a batch at a time.
+//!
+//! A finished game's certified digest covers its frames as they were written,
+//! so converters only add what a frame is missing and leave frames that
+//! already have it untouched.
+//!
+//! Versions:
+//! 1. Frames from before versioning. Only `Snakes` (with `ID`, `Name`, `Body`
+//!    and `Health`) and `Food` are guaranteed.
+//! 2. Every field the board viewer reads is present, as `game_to_frame` writes them.
+
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+
+use crate::engine::frame::generate_snake_color;
+use crate::models::turn;
+
+/// The version frames are written at
+pub const CURRENT_FRAME_SCHEMA_VERSION: i32 = 2;
+
+/// Turns the backfill upgrades per query
+const BACKFILL_BATCH_SIZE: i64 = 500;
+
+/// Most batches one backfill run upgrades, so a large backlog is spread over runs
+const BACKFILL_MAX_BATCHES: usize = 20;
+
+/// Upgrades a frame from `from` to the next version
+struct FrameConverter {
+    from: i32,
+    convert: fn(&mut Map<String, Value>),
+}
+
+/// Converters in version order, one per version below the current one
+const CONVERTERS: &[FrameConverter] = &[FrameConverter {
+    from: 1,
+    convert: fill_viewer_fields,
+}];
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum FrameSchemaError {
+    #[error("frame schema version {0} is newer than this server's {CURRENT_FRAME_SCHEMA_VERSION}")]
+    TooNew(i32),
+    #[error("no converter from frame schema version {0}")]
+    NoConverter(i32),
+}
+
+/// Upgrade a frame written at `version` to the current version, in place
+///
+/// Frames that aren't JSON objects have nothing to upgrade and are left alone.
+pub fn upgrade_frame(frame: &mut Value, version: i32) -> Result<(), FrameSchemaError> {
+    if version > CURRENT_FRAME_SCHEMA_VERSION {
+        return Err(FrameSchemaError::TooNew(version));
+    }
+    for from in version..CURRENT_FRAME_SCHEMA_VERSION {
+        let converter = CONVERTERS
+            .iter()
+            .find(|c| c.from == from)
+            .ok_or(FrameSchemaError::NoConverter(from))?;
+        if let Value::Object(fields) = frame {
+            (converter.convert)(fields);
+        }
+    }
+    Ok(())
+}
+
+/// 1 -> 2: fill in the viewer fields older frames could be missing
+fn fill_viewer_fields(frame: &mut Map<String, Value>) {
+    frame
+        .entry("Hazards")
+        .or_insert_with(|| Value::Array(vec![]));
+
+    let Some(Value::Array(snakes)) = frame.get_mut("Snakes") else {
+        return;
+    };
+    for snake in snakes.iter_mut() {
+        let Value::Object(snake) = snake else {
+            continue;
+        };
+        let id = snake
+            .get("ID")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let (cause, eliminated_by) = match snake.get_mut("Death") {
+            Some(Value::Object(death)) => {
+                let eliminated_by = death
+                    .entry("EliminatedBy")
+                    .or_insert_with(|| Value::String(String::new()))
+                    .clone();
+                let cause = death
+                    .get("Cause")
+                    .cloned()
+                    .unwrap_or_else(|| Value::String(String::new()));
+                (cause, eliminated_by)
+            }
+            _ => (Value::String(String::new()), Value::String(String::new())),
+        };
+
+        let mut fill = |key: &str, value: Value| {
+            snake.entry(key).or_insert(value);
+        };
+        fill("Color", Value::String(generate_snake_color(&id)));
+        fill("HeadType", Value::String("default".to_string()));
+        fill("TailType", Value::String("default".to_string()));
+        fill("Latency", Value::String("0".to_string()));
+        fill("Shout", Value::String(String::new()));
+        fill("Squad", Value::String(String::new()));
+        fill("APIVersion", Value::String("1".to_string()));
+        fill("Author", Value::String(String::new()));
+        fill("Death", Value::Null);
+        fill("EliminatedCause", cause);
+        fill("EliminatedBy", eliminated_by);
+    }
+}
+
+/// Upgrade stored frames that are behind the current version, a batch at a
+/// time. Returns how many turns were upgraded.
+pub async fn backfill_frames(pool: &PgPool) -> cja::Result<usize> {
+    let mut upgraded = 0;
+    for _ in 0..BACKFILL_MAX_BATCHES {
+        let turns =
+            turn::get_outdated_turns(pool, CURRENT_FRAME_SCHEMA_VERSION, BACKFILL_BATCH_SIZE)
+                .await?;
+        if turns.is_empty() {
+            break;
+        }
+
+        for outdated in turns {
+            let frame = match outdated.frame_data {
+                Some(stored) => {
+                    let mut frame = stored.clone();
+                    upgrade_frame(&mut frame, outdated.frame_schema_version)?;
+                    // Leave frames the upgrade didn't change as they are
+                    (frame != stored).then_some(frame)
+                }
+                None => None,
+            };
+            turn::set_turn_frame_schema(
+                pool,
+                outdated.game_id,
+                outdated.turn_id,
+                outdated.frame_schema_version,
+                CURRENT_FRAME_SCHEMA_VERSION,
+                frame,
+            )
+            .await?;
+            upgraded += 1;
+        }
+    }
+
+    tracing::info!(upgraded, "Backfilled frame schema versions");
+    Ok(upgraded)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::engine::create_game_from_board;
+    use crate::engine::frame::{DeathInfo, StoredFrame, game_to_frame};
+    use crate::models::game::GameType;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_every_old_version_has_a_converter() {
+        for version in 1..CURRENT_FRAME_SCHEMA_VERSION {
+            assert!(
+                CONVERTERS.iter().any(|c| c.from == version),
+                "no converter from version {version}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_upgrade_fills_missing_fields() {
+        let mut frame = json!({
+            "Turn": 3,
+            "Snakes": [
+                {"ID": "snake-1", "Name": "Old", "Body": [{"X": 1, "Y": 1}], "Health": 90},
+                {
+                    "ID": "snake-2", "Name": "Gone", "Body": [], "Health": 0,
+                    "Death": {"Cause": "wall-collision", "Turn": 2}
+                }
+            ],
+            "Food": []
+        });
+        upgrade_frame(&mut frame, 1).unwrap();
+
+        assert_eq!(frame["Hazards"], json!([]));
+        let alive = &frame["Snakes"][0];
+        assert_eq!(alive["Color"], json!(generate_snake_color("snake-1")));
+        assert_eq!(alive["HeadType"], "default");
+        assert_eq!(alive["APIVersion"], "1");
+        assert_eq!(alive["Death"], Value::Null);
+        assert_eq!(alive["EliminatedCause"], "");
+        let dead = &frame["Snakes"][1];
+        assert_eq!(dead["Death"]["EliminatedBy"], "");
+        assert_eq!(dead["EliminatedCause"], "wall-collision");
+    }
+
+    #[test]
+    fn test_upgrade_leaves_complete_frames_alone() {
+        let stored: StoredFrame = serde_json::from_value(json!({
+            "Snakes": [
+                {"ID": "snake-1", "Name": "New", "Body": [{"X": 1, "Y": 1}], "Health": 90},
+                {"ID": "snake-2", "Name": "Gone", "Body": [{"X": 0, "Y": 0}], "Health": 0}
+            ],
+            "Food": [{"X": 3, "Y": 3}]
+        }))
+        .unwrap();
+        let game = create_game_from_board(Uuid::nil(), GameType::Standard, stored.to_board(7, 7));
+        let death_info = [DeathInfo {
+            snake_id: "snake-2".to_string(),
+            turn: 0,
+            cause: "head-collision".to_string(),
+            eliminated_by: "snake-1".to_string(),
+        }];
+        let written = serde_json::to_value(game_to_frame(&game, &death_info, &[])).unwrap();
+
+        let mut upgraded = written.clone();
+        upgrade_frame(&mut upgraded, 1).unwrap();
+        assert_eq!(upgraded, written);
+
+        // Already current, and not a frame at all
+        upgrade_frame(&mut upgraded, CURRENT_FRAME_SCHEMA_VERSION).unwrap();
+        assert_eq!(upgraded, written);
+        let mut odd = json!("not a frame");
+        upgrade_frame(&mut odd, 1).unwrap();
+        assert_eq!(odd, json!("not a frame"));
+    }
+
+    #[test]
+    fn test_upgrade_rejects_newer_versions() {
+        let mut frame = json!({});
+        assert_eq!(
+            upgrade_frame(&mut frame, CURRENT_FRAME_SCHEMA_VERSION + 1),
+            Err(FrameSchemaError::TooNew(CURRENT_FRAME_SCHEMA_VERSION + 1))
+        );
+    }
+}
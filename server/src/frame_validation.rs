@@ -0,0 +1,249 @@
+//! Checks stored frames against the board viewer's frame contract
+//!
+//! The board viewer is strict about frame shape: keys must be PascalCase and a
+//! missing field (an `EliminatedBy` dropped by a serializer change, say) breaks
+//! replays silently. `arena admin validate-frames <game-id>...` loads a game's
+//! stored frames and checks each one against `engine/frame.schema.json`, with
+//! coordinates bounded by the game's board size.
+
+use color_eyre::eyre::{Context as _, eyre};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::models::game::get_game_by_id;
+use crate::state::AppState;
+use crate::turn_archive::load_turns;
+
+const FRAME_SCHEMA: &str = include_str!("engine/frame.schema.json");
+
+/// Something wrong with one stored frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameIssue {
+    pub turn_number: i32,
+    /// JSON pointer to the offending value, empty for the frame itself
+    pub path: String,
+    pub message: String,
+}
+
+/// Build a validator for frames of a `width` x `height` board
+pub fn frame_validator(width: u32, height: u32) -> cja::Result<jsonschema::Validator> {
+    let mut schema: Value =
+        serde_json::from_str(FRAME_SCHEMA).wrap_err("Frame schema is not valid JSON")?;
+
+    let bounds = schema
+        .pointer_mut("/$defs/BoardCoord/properties")
+        .ok_or_else(|| eyre!("Frame schema is missing $defs.BoardCoord"))?;
+    bounds["X"]["maximum"] = (width.saturating_sub(1)).into();
+    bounds["Y"]["maximum"] = (height.saturating_sub(1)).into();
+
+    jsonschema::validator_for(&schema).map_err(|e| eyre!("Invalid frame schema: {}", e))
+}
+
+/// Check a single frame, returning every problem found
+pub fn validate_frame(
+    validator: &jsonschema::Validator,
+    turn_number: i32,
+    frame: &Value,
+) -> Vec<FrameIssue> {
+    let mut issues: Vec<FrameIssue> = validator
+        .iter_errors(frame)
+        .map(|e| FrameIssue {
+            turn_number,
+            path: e.instance_path.to_string(),
+            message: e.to_string(),
+        })
+        .collect();
+
+    if let Some(frame_turn) = frame.get("Turn").and_then(Value::as_i64)
+        && frame_turn != i64::from(turn_number)
+    {
+        issues.push(FrameIssue {
+            turn_number,
+            path: "/Turn".to_string(),
+            message: format!("frame is for turn {frame_turn}, stored as turn {turn_number}"),
+        });
+    }
+
+    issues
+}
+
+/// Validate every stored frame of a game
+pub async fn validate_game_frames(
+    app_state: &AppState,
+    game_id: Uuid,
+) -> cja::Result<Vec<FrameIssue>> {
+    let game = get_game_by_id(&app_state.db, game_id)
+        .await?
+        .ok_or_else(|| eyre!("Game {} not found", game_id))?;
+
+    let (width, height) = game.board_size.dimensions();
+    let validator = frame_validator(width, height)?;
+
+    let turns = load_turns(app_state, game_id).await?;
+
+    let mut issues = Vec::new();
+    for turn in &turns {
+        match &turn.frame_data {
+            Some(frame) => issues.extend(validate_frame(&validator, turn.turn_number, frame)),
+            None => issues.push(FrameIssue {
+                turn_number: turn.turn_number,
+                path: String::new(),
+                message: "no frame data stored".to_string(),
+            }),
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Validate the given games' frames, printing each problem, and fail if any were found
+pub async fn run(app_state: &AppState, game_ids: &[Uuid]) -> cja::Result<()> {
+    let mut invalid_games = 0;
+
+    for &game_id in game_ids {
+        let issues = validate_game_frames(app_state, game_id).await?;
+
+        if issues.is_empty() {
+            println!("{game_id}: ok");
+            continue;
+        }
+
+        invalid_games += 1;
+        println!("{game_id}: {} problem(s)", issues.len());
+        for issue in &issues {
+            if issue.path.is_empty() {
+                println!("  turn {}: {}", issue.turn_number, issue.message);
+            } else {
+                println!(
+                    "  turn {} at {}: {}",
+                    issue.turn_number, issue.path, issue.message
+                );
+            }
+        }
+    }
+
+    if invalid_games > 0 {
+        return Err(eyre!(
+            "{} of {} game(s) have frames that don't match the board viewer schema",
+            invalid_games,
+            game_ids.len()
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::create_initial_game;
+    use crate::engine::frame::{DeathInfo, game_to_frame};
+    use crate::models::game::{GameBoardSize, GameType, SpawnStrategy};
+    use crate::models::game_battlesnake::GameBattlesnakeWithDetails;
+    use battlesnake_game_types::wire_representation::Position;
+
+    fn battlesnake(name: &str) -> GameBattlesnakeWithDetails {
+        let now = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        GameBattlesnakeWithDetails {
+            game_battlesnake_id: Uuid::new_v4(),
+            game_id: Uuid::nil(),
+            battlesnake_id: Uuid::new_v4(),
+            placement: None,
+            created_at: now,
+            updated_at: now,
+            name: name.to_string(),
+            url: "http://example.com".to_string(),
+            user_id: Uuid::nil(),
+        }
+    }
+
+    fn initial_frame() -> Value {
+        let game = create_initial_game(
+            Uuid::new_v4(),
+            GameBoardSize::Medium,
+            GameType::Standard,
+            &[battlesnake("Alpha"), battlesnake("Beta")],
+            &SpawnStrategy::Random,
+            0,
+        );
+        serde_json::to_value(game_to_frame(&game, &[], &[])).unwrap()
+    }
+
+    #[test]
+    fn test_serialized_frames_match_schema() {
+        let validator = frame_validator(11, 11).unwrap();
+        assert_eq!(validate_frame(&validator, 0, &initial_frame()), vec![]);
+
+        // Eliminated snakes may be off the board
+        let mut game = create_initial_game(
+            Uuid::new_v4(),
+            GameBoardSize::Medium,
+            GameType::Standard,
+            &[battlesnake("Alpha"), battlesnake("Beta")],
+            &SpawnStrategy::Random,
+            0,
+        );
+        game.turn = 4;
+        let dead = &mut game.board.snakes[0];
+        dead.health = 0;
+        dead.body.push_front(Position::new(-1, 5));
+        let death_info = vec![DeathInfo {
+            snake_id: dead.id.clone(),
+            turn: 4,
+            cause: "wall-collision".to_string(),
+            eliminated_by: String::new(),
+        }];
+        let frame = serde_json::to_value(game_to_frame(&game, &death_info, &[])).unwrap();
+        assert_eq!(validate_frame(&validator, 4, &frame), vec![]);
+    }
+
+    #[test]
+    fn test_missing_field_is_reported() {
+        let validator = frame_validator(11, 11).unwrap();
+        let mut frame = initial_frame();
+        frame["Snakes"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("EliminatedBy");
+
+        let issues = validate_frame(&validator, 0, &frame);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "/Snakes/0");
+        assert!(issues[0].message.contains("EliminatedBy"));
+    }
+
+    #[test]
+    fn test_camel_case_key_is_reported() {
+        let validator = frame_validator(11, 11).unwrap();
+        let mut frame = initial_frame();
+        let food = frame.as_object_mut().unwrap().remove("Food").unwrap();
+        frame["food"] = food;
+
+        let issues = validate_frame(&validator, 0, &frame);
+        assert!(issues.iter().any(|i| i.message.contains("Food")));
+        assert!(issues.iter().any(|i| i.message.contains("food")));
+    }
+
+    #[test]
+    fn test_out_of_bounds_coordinates_are_reported() {
+        let validator = frame_validator(7, 7).unwrap();
+        let mut frame = initial_frame();
+        frame["Food"] = serde_json::json!([{ "X": 7, "Y": 0 }]);
+        frame["Snakes"][0]["Body"][0] = serde_json::json!({ "X": -1, "Y": 3 });
+
+        let paths: Vec<String> = validate_frame(&validator, 0, &frame)
+            .into_iter()
+            .map(|i| i.path)
+            .collect();
+        assert!(paths.contains(&"/Food/0/X".to_string()));
+        assert!(paths.contains(&"/Snakes/0/Body/0/X".to_string()));
+    }
+
+    #[test]
+    fn test_turn_mismatch_is_reported() {
+        let validator = frame_validator(11, 11).unwrap();
+        let issues = validate_frame(&validator, 3, &initial_frame());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "/Turn");
+    }
+}
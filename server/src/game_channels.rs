@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use tokio::sync::{RwLock, broadcast};
 use uuid::Uuid;
@@ -16,6 +16,9 @@ pub struct TurnNotification {
 pub struct GameChannels {
     /// Map from game_id to broadcast sender for that game
     channels: Arc<RwLock<HashMap<Uuid, broadcast::Sender<TurnNotification>>>>,
+    /// Frames of turns the runner has played but not stored yet, by game and
+    /// turn number, so subscribers don't wait for the runner's next batch
+    unstored: Arc<RwLock<HashMap<Uuid, BTreeMap<i32, serde_json::Value>>>>,
 }
 
 impl Default for GameChannels {
@@ -28,6 +31,7 @@ impl GameChannels {
     pub fn new() -> Self {
         Self {
             channels: Arc::new(RwLock::new(HashMap::new())),
+            unstored: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -57,9 +61,48 @@ impl GameChannels {
         }
     }
 
+    /// Hold a turn's frame until it's stored, for subscribers notified of it
+    pub async fn stage_frame(&self, game_id: Uuid, turn_number: i32, frame: serde_json::Value) {
+        let mut unstored = self.unstored.write().await;
+        unstored
+            .entry(game_id)
+            .or_default()
+            .insert(turn_number, frame);
+    }
+
+    /// Drop the held frames of a game's turns up to `turn_number`, once they're stored
+    pub async fn frames_stored(&self, game_id: Uuid, turn_number: i32) {
+        let mut unstored = self.unstored.write().await;
+        if let Some(frames) = unstored.get_mut(&game_id) {
+            frames.retain(|turn, _| *turn > turn_number);
+            if frames.is_empty() {
+                unstored.remove(&game_id);
+            }
+        }
+    }
+
+    /// The held frames of a game's turns from `from_turn` on, in turn order
+    pub async fn unstored_frames(
+        &self,
+        game_id: Uuid,
+        from_turn: i32,
+    ) -> Vec<(i32, serde_json::Value)> {
+        let unstored = self.unstored.read().await;
+        unstored
+            .get(&game_id)
+            .map(|frames| {
+                frames
+                    .range(from_turn..)
+                    .map(|(turn, frame)| (*turn, frame.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// Clean up a game's channel if no receivers are listening
     /// Call this periodically or when a game ends
     pub async fn cleanup(&self, game_id: Uuid) {
+        self.unstored.write().await.remove(&game_id);
         let mut channels = self.channels.write().await;
 
         if let Some(sender) = channels.get(&game_id)
@@ -70,8 +113,43 @@ impl GameChannels {
         }
     }
 
+    /// How many clients are streaming a game's events right now
+    ///
+    /// Every WebSocket and SSE client holds a receiver on the game's channel
+    /// while it's connected, so the spectators are its receivers.
+    pub async fn spectator_count(&self, game_id: Uuid) -> usize {
+        let channels = self.channels.read().await;
+        channels
+            .get(&game_id)
+            .map(|sender| sender.receiver_count())
+            .unwrap_or(0)
+    }
+
+    /// Spectator gauges across every game, in Prometheus' text exposition format
+    pub async fn render_spectator_metrics(&self) -> String {
+        let channels = self.channels.read().await;
+        let counts: Vec<usize> = channels
+            .values()
+            .map(|sender| sender.receiver_count())
+            .filter(|count| *count > 0)
+            .collect();
+
+        let mut out = String::new();
+        out.push_str("# HELP arena_spectators Clients streaming game events right now\n");
+        out.push_str("# TYPE arena_spectators gauge\n");
+        out.push_str(&format!(
+            "arena_spectators {}\n",
+            counts.iter().sum::<usize>()
+        ));
+        out.push_str("# HELP arena_watched_games Games with at least one spectator\n");
+        out.push_str("# TYPE arena_watched_games gauge\n");
+        out.push_str(&format!("arena_watched_games {}\n", counts.len()));
+        out
+    }
+
     /// Remove a game's channel entirely (call when game ends)
     pub async fn remove(&self, game_id: Uuid) {
+        self.unstored.write().await.remove(&game_id);
         let mut channels = self.channels.write().await;
         channels.remove(&game_id);
         tracing::debug!(game_id = %game_id, "Removed game channel");
@@ -187,6 +265,32 @@ mod tests {
         assert_eq!(notif_2.turn_number, 100);
     }
 
+    #[tokio::test]
+    async fn test_unstored_frames_are_held_until_stored() {
+        let channels = GameChannels::new();
+        let game_id = Uuid::new_v4();
+
+        for turn in 1..=3 {
+            channels
+                .stage_frame(game_id, turn, serde_json::json!({ "Turn": turn }))
+                .await;
+        }
+        let frames = channels.unstored_frames(game_id, 2).await;
+        assert_eq!(
+            frames,
+            vec![
+                (2, serde_json::json!({ "Turn": 2 })),
+                (3, serde_json::json!({ "Turn": 3 })),
+            ]
+        );
+
+        channels.frames_stored(game_id, 2).await;
+        assert_eq!(channels.unstored_frames(game_id, 0).await.len(), 1);
+        channels.frames_stored(game_id, 3).await;
+        assert!(channels.unstored.read().await.is_empty());
+        assert!(channels.unstored_frames(Uuid::new_v4(), 0).await.is_empty());
+    }
+
     #[tokio::test]
     async fn test_notify_without_subscribers() {
         let channels = GameChannels::new();
@@ -251,6 +355,25 @@ mod tests {
         assert!(channels.channels.read().await.contains_key(&game_id));
     }
 
+    #[tokio::test]
+    async fn test_spectator_count_follows_subscribers() {
+        let channels = GameChannels::new();
+        let game_id = Uuid::new_v4();
+        assert_eq!(channels.spectator_count(game_id).await, 0);
+
+        let first = channels.subscribe(game_id).await;
+        let _second = channels.subscribe(game_id).await;
+        let _other_game = channels.subscribe(Uuid::new_v4()).await;
+        assert_eq!(channels.spectator_count(game_id).await, 2);
+
+        let metrics = channels.render_spectator_metrics().await;
+        assert!(metrics.contains("arena_spectators 3\n"));
+        assert!(metrics.contains("arena_watched_games 2\n"));
+
+        drop(first);
+        assert_eq!(channels.spectator_count(game_id).await, 1);
+    }
+
     #[test]
     fn test_turn_notification_clone() {
         let notification = TurnNotification {
@@ -0,0 +1,299 @@
+//! Pausing, stepping and resuming running games
+//!
+//! The controls live on the game's row rather than in the runner, since the
+//! request to pause a game can land on a different instance from the one
+//! running it. Between turns the runner checks them with a [`TurnGate`]: a
+//! game that isn't paused carries straight on, a paused one waits until it's
+//! stepped or resumed. Stepping plays exactly one turn and holds again, so a
+//! snake developer can watch the turn they're debugging arrive one move at a
+//! time.
+//!
+//! A paused game keeps its lease and its place in the runner pool, so a game
+//! left paused for [`MAX_PAUSE_MINUTES`] is resumed by its runner.
+
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{Context as _, eyre};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::game_log::GameLog;
+use crate::state::AppState;
+use crate::turn_batch::TurnBatch;
+use crate::worker::Lease;
+
+/// How often a paused game's runner checks whether it's been stepped or resumed
+pub const CONTROL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Longest a game stays paused before its runner resumes it
+pub const MAX_PAUSE_MINUTES: i64 = 15;
+
+/// Most turns a paused game can be asked to step ahead
+pub const MAX_PENDING_STEPS: i32 = 50;
+
+/// What to do with a running game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlAction {
+    /// Hold the game before its next turn
+    Pause,
+    /// Play one more turn, then hold; pauses a game that isn't paused yet
+    Step,
+    /// Carry on playing turns
+    Resume,
+}
+
+impl ControlAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ControlAction::Pause => "pause",
+            ControlAction::Step => "step",
+            ControlAction::Resume => "resume",
+        }
+    }
+}
+
+/// A game's pause controls
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GameControl {
+    pub game_id: Uuid,
+    /// When the game was paused, None while it's playing
+    pub paused_at: Option<DateTime<Utc>>,
+    /// Turns the game has been stepped that it hasn't played yet
+    pub pending_steps: i32,
+}
+
+impl GameControl {
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+}
+
+#[derive(Debug)]
+pub enum ControlError {
+    NotFound,
+    /// The game can't be controlled, with a message for the user
+    Invalid(String),
+    Internal(color_eyre::Report),
+}
+
+impl From<color_eyre::Report> for ControlError {
+    fn from(e: color_eyre::Report) -> Self {
+        ControlError::Internal(e)
+    }
+}
+
+// Get a game's pause controls
+pub async fn get_game_control(pool: &PgPool, game_id: Uuid) -> cja::Result<Option<GameControl>> {
+    sqlx::query_as!(
+        GameControl,
+        "SELECT game_id, paused_at, pending_steps FROM games WHERE game_id = $1",
+        game_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to get game controls")
+}
+
+/// Pause, step or resume a game that hasn't finished
+pub async fn control_game(
+    app_state: &AppState,
+    game_id: Uuid,
+    action: ControlAction,
+) -> Result<GameControl, ControlError> {
+    let now = app_state.clock.now();
+    let control = match action {
+        ControlAction::Pause => {
+            sqlx::query_as!(
+                GameControl,
+                r#"
+                UPDATE games SET paused_at = COALESCE(paused_at, $2)
+                WHERE game_id = $1 AND status <> 'finished'
+                RETURNING game_id, paused_at, pending_steps
+                "#,
+                game_id,
+                now
+            )
+            .fetch_optional(&app_state.db)
+            .await
+        }
+        ControlAction::Step => {
+            sqlx::query_as!(
+                GameControl,
+                r#"
+                UPDATE games
+                SET paused_at = COALESCE(paused_at, $2),
+                    pending_steps = LEAST(pending_steps + 1, $3)
+                WHERE game_id = $1 AND status <> 'finished'
+                RETURNING game_id, paused_at, pending_steps
+                "#,
+                game_id,
+                now,
+                MAX_PENDING_STEPS
+            )
+            .fetch_optional(&app_state.db)
+            .await
+        }
+        ControlAction::Resume => {
+            sqlx::query_as!(
+                GameControl,
+                r#"
+                UPDATE games SET paused_at = NULL, pending_steps = 0
+                WHERE game_id = $1 AND status <> 'finished'
+                RETURNING game_id, paused_at, pending_steps
+                "#,
+                game_id
+            )
+            .fetch_optional(&app_state.db)
+            .await
+        }
+    }
+    .wrap_err_with(|| format!("Failed to {} game {}", action.as_str(), game_id))?;
+
+    match control {
+        Some(control) => {
+            tracing::info!(
+                game_id = %game_id,
+                action = action.as_str(),
+                pending_steps = control.pending_steps,
+                "Game control changed"
+            );
+            Ok(control)
+        }
+        None if get_game_control(&app_state.db, game_id).await?.is_some() => {
+            Err(ControlError::Invalid("The game has finished".to_string()))
+        }
+        None => Err(ControlError::NotFound),
+    }
+}
+
+/// Use up one of a paused game's pending steps, false if it had none left
+async fn take_step(pool: &PgPool, game_id: Uuid) -> cja::Result<bool> {
+    let taken = sqlx::query!(
+        r#"
+        UPDATE games SET pending_steps = pending_steps - 1
+        WHERE game_id = $1 AND paused_at IS NOT NULL AND pending_steps > 0
+        "#,
+        game_id
+    )
+    .execute(pool)
+    .await
+    .wrap_err("Failed to take a game step")?;
+    Ok(taken.rows_affected() > 0)
+}
+
+/// Whether a game paused at `paused_at` has been paused too long
+fn pause_expired(paused_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    now - paused_at > chrono::Duration::minutes(MAX_PAUSE_MINUTES)
+}
+
+/// Holds a game's runner between turns while the game is paused
+pub struct TurnGate {
+    game_id: Uuid,
+    /// When the runner found the game paused, while it stays paused
+    paused_since: Option<Instant>,
+    paused_for: Duration,
+}
+
+impl TurnGate {
+    pub fn new(game_id: Uuid) -> Self {
+        Self {
+            game_id,
+            paused_since: None,
+            paused_for: Duration::ZERO,
+        }
+    }
+
+    /// How long the game has spent paused, up to its last resume
+    pub fn paused_for(&self) -> Duration {
+        self.paused_for
+    }
+
+    /// Wait until `turn` may be played: straight away unless the game is
+    /// paused, otherwise until it's stepped or resumed
+    ///
+    /// A paused game's played turns are stored while it waits, so the game's
+    /// page shows the board it's paused on.
+    pub async fn wait(
+        &mut self,
+        app_state: &AppState,
+        lease: &Lease,
+        game_log: &mut GameLog,
+        turns: &mut TurnBatch,
+        turn: i32,
+    ) -> cja::Result<()> {
+        let pool = &app_state.db;
+        loop {
+            let control = get_game_control(pool, self.game_id)
+                .await?
+                .ok_or_else(|| eyre!("Game {} not found", self.game_id))?;
+
+            let Some(paused_at) = control.paused_at else {
+                if let Some(since) = self.paused_since.take() {
+                    self.paused_for += since.elapsed();
+                    game_log.info(Some(turn), None, "Resumed");
+                    game_log.flush(pool).await?;
+                }
+                return Ok(());
+            };
+
+            turns
+                .flush(pool, &app_state.game_channels, app_state.clock.now())
+                .await?;
+            if self.paused_since.is_none() {
+                self.paused_since = Some(Instant::now());
+                game_log.info(Some(turn), None, format!("Paused on turn {}", turn));
+                game_log.flush(pool).await?;
+            }
+
+            if control.pending_steps > 0 && take_step(pool, self.game_id).await? {
+                game_log.info(Some(turn), None, format!("Stepped to turn {}", turn + 1));
+                return Ok(());
+            }
+
+            if pause_expired(paused_at, app_state.clock.now()) {
+                tracing::info!(game_id = %self.game_id, "Game paused too long, resuming it");
+                game_log.warn(
+                    Some(turn),
+                    None,
+                    format!("Paused for over {} minutes", MAX_PAUSE_MINUTES),
+                );
+                control_game(app_state, self.game_id, ControlAction::Resume)
+                    .await
+                    .map_err(|e| match e {
+                        ControlError::Internal(e) => e,
+                        _ => eyre!("Failed to resume game {}", self.game_id),
+                    })?;
+                continue;
+            }
+
+            if lease.is_lost() {
+                return Err(eyre!(
+                    "Lost the lease on game {} while it was paused",
+                    self.game_id
+                ));
+            }
+            tokio::time::sleep(CONTROL_POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_expired() {
+        let paused_at = Utc::now();
+        assert!(!pause_expired(paused_at, paused_at));
+        assert!(!pause_expired(
+            paused_at,
+            paused_at + chrono::Duration::minutes(MAX_PAUSE_MINUTES)
+        ));
+        assert!(pause_expired(
+            paused_at,
+            paused_at + chrono::Duration::minutes(MAX_PAUSE_MINUTES) + chrono::Duration::seconds(1)
+        ));
+    }
+}
@@ -0,0 +1,506 @@
+//! Turn-by-turn diffs of two games
+//!
+//! Two games are lined up by turn number, and their snakes by the order they
+//! joined each game, so the first snake of one is compared with the first of
+//! the other whatever their IDs. Diffing two games played from the same seed,
+//! one with a new version of a snake, shows the first turn the change made a
+//! difference and what it led to: which snakes' games changed from when, and
+//! how each one finished in both.
+
+use color_eyre::eyre::{Context as _, eyre};
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::engine::frame::{FrameCoord, StoredFrame, StoredSnake};
+use crate::models::game::{get_game_by_id, get_game_settings};
+use crate::models::game_battlesnake::{GameBattlesnakeWithDetails, get_battlesnakes_by_game_id};
+use crate::state::AppState;
+use crate::turn_archive::load_turns;
+
+/// How two games differ
+#[derive(Debug, Serialize)]
+pub struct GameDiff {
+    pub game_a: Uuid,
+    pub game_b: Uuid,
+    /// Board size, game type, seed or number of snakes, where they differ.
+    /// Any of these usually means the games diverge from the start.
+    pub setup_differences: Vec<String>,
+    /// Each game's last turn
+    pub turns_a: i32,
+    pub turns_b: i32,
+    /// The first turn whose boards differ, None if the games are identical
+    pub first_divergence: Option<Divergence>,
+    /// How many of the turns both games reached have different boards
+    pub differing_turns: usize,
+    pub snakes: Vec<SnakeDiff>,
+}
+
+/// The first turn two games' boards differ on
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Divergence {
+    pub turn: i32,
+    pub differences: Vec<String>,
+}
+
+/// One snake's place in both games, matched by the order they joined
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SnakeDiff {
+    /// 1 for the first snake to join each game, 2 for the second, ...
+    pub slot: usize,
+    /// The first turn the snake's body, health or survival differs, if it ever does
+    pub diverged_at: Option<i32>,
+    pub a: Option<SnakeOutcome>,
+    pub b: Option<SnakeOutcome>,
+}
+
+/// How a snake finished one of the games
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SnakeOutcome {
+    pub name: String,
+    pub placement: Option<i32>,
+    pub length: usize,
+    pub health: i32,
+    pub eliminated_turn: Option<i32>,
+    pub eliminated_cause: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum DiffError {
+    /// The games can't be compared, with a message for the user
+    Invalid(String),
+    Internal(color_eyre::Report),
+}
+
+impl From<color_eyre::Report> for DiffError {
+    fn from(e: color_eyre::Report) -> Self {
+        DiffError::Internal(e)
+    }
+}
+
+/// One game's frames, indexed by turn, with its snakes in the order they joined
+struct GameFrames<'a> {
+    frames: &'a [StoredFrame],
+    snake_ids: Vec<&'a str>,
+}
+
+impl<'a> GameFrames<'a> {
+    fn new(frames: &'a [StoredFrame]) -> Self {
+        let snake_ids = frames
+            .first()
+            .map(|f| f.snakes.iter().map(|s| s.id.as_str()).collect())
+            .unwrap_or_default();
+        Self { frames, snake_ids }
+    }
+
+    /// The snake in `slot` on `turn`, None once dropped from the board
+    fn snake(&self, turn: usize, slot: usize) -> Option<&'a StoredSnake> {
+        let id = self.snake_ids.get(slot)?;
+        self.frames.get(turn)?.snakes.iter().find(|s| s.id == *id)
+    }
+
+    /// The snake in `slot` as last seen on the board
+    fn last_seen(&self, slot: usize) -> Option<&'a StoredSnake> {
+        (0..self.frames.len())
+            .rev()
+            .find_map(|turn| self.snake(turn, slot))
+    }
+}
+
+fn coord(c: &FrameCoord) -> String {
+    format!("{},{}", c.x, c.y)
+}
+
+fn coord_set(coords: &[FrameCoord]) -> Vec<(i32, i32)> {
+    let mut set: Vec<(i32, i32)> = coords.iter().map(|c| (c.x, c.y)).collect();
+    set.sort_unstable();
+    set
+}
+
+fn coord_list(coords: &[(i32, i32)]) -> String {
+    if coords.is_empty() {
+        return "none".to_string();
+    }
+    coords
+        .iter()
+        .map(|(x, y)| format!("{},{}", x, y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// What differs about the snake in one slot on one turn, empty if nothing
+fn snake_differences(a: Option<&StoredSnake>, b: Option<&StoredSnake>) -> Vec<String> {
+    let alive = |s: Option<&StoredSnake>| s.is_some_and(|s| s.death.is_none() && s.health > 0);
+    let (Some(a), Some(b)) = (a, b) else {
+        return if alive(a) != alive(b) {
+            vec![format!(
+                "{} vs {}",
+                if alive(a) { "alive" } else { "eliminated" },
+                if alive(b) { "alive" } else { "eliminated" }
+            )]
+        } else {
+            vec![]
+        };
+    };
+
+    let mut differences = Vec::new();
+    if alive(Some(a)) != alive(Some(b)) {
+        let state = |s: &StoredSnake| match &s.death {
+            Some(death) => format!("eliminated ({})", death.cause),
+            None if s.health <= 0 => "eliminated".to_string(),
+            None => "alive".to_string(),
+        };
+        differences.push(format!("{} vs {}", state(a), state(b)));
+    }
+    if let (Some(head_a), Some(head_b)) = (a.body.first(), b.body.first())
+        && head_a != head_b
+    {
+        differences.push(format!("head {} vs {}", coord(head_a), coord(head_b)));
+    } else if a.body != b.body {
+        differences.push("body differs".to_string());
+    }
+    if a.body.len() != b.body.len() {
+        differences.push(format!("length {} vs {}", a.body.len(), b.body.len()));
+    }
+    if a.health != b.health {
+        differences.push(format!("health {} vs {}", a.health, b.health));
+    }
+    differences
+}
+
+/// Everything that differs between the two games' boards on `turn`
+fn turn_differences(a: &GameFrames, b: &GameFrames, turn: usize) -> Vec<String> {
+    let mut differences = Vec::new();
+    let (Some(frame_a), Some(frame_b)) = (a.frames.get(turn), b.frames.get(turn)) else {
+        return differences;
+    };
+
+    let (food_a, food_b) = (coord_set(&frame_a.food), coord_set(&frame_b.food));
+    if food_a != food_b {
+        differences.push(format!(
+            "food: {} vs {}",
+            coord_list(&food_a),
+            coord_list(&food_b)
+        ));
+    }
+    let (hazards_a, hazards_b) = (coord_set(&frame_a.hazards), coord_set(&frame_b.hazards));
+    if hazards_a != hazards_b {
+        differences.push(format!(
+            "hazards: {} vs {} squares",
+            hazards_a.len(),
+            hazards_b.len()
+        ));
+    }
+
+    for slot in 0..a.snake_ids.len().max(b.snake_ids.len()) {
+        let changes = snake_differences(a.snake(turn, slot), b.snake(turn, slot));
+        if !changes.is_empty() {
+            differences.push(format!("snake {}: {}", slot + 1, changes.join(", ")));
+        }
+    }
+
+    differences
+}
+
+/// Compare two games' frames, each indexed by turn, with each snake's placement
+/// by its game_battlesnake_id
+fn diff_frames(
+    frames_a: &[StoredFrame],
+    frames_b: &[StoredFrame],
+    placements_a: &HashMap<String, i32>,
+    placements_b: &HashMap<String, i32>,
+) -> (Option<Divergence>, usize, Vec<SnakeDiff>) {
+    let (a, b) = (GameFrames::new(frames_a), GameFrames::new(frames_b));
+    let shared_turns = frames_a.len().min(frames_b.len());
+
+    let mut first_divergence = None;
+    let mut differing_turns = 0;
+    for turn in 0..shared_turns {
+        let differences = turn_differences(&a, &b, turn);
+        if differences.is_empty() {
+            continue;
+        }
+        differing_turns += 1;
+        if first_divergence.is_none() {
+            first_divergence = Some(Divergence {
+                turn: turn as i32,
+                differences,
+            });
+        }
+    }
+    // Games that match for as long as both last still diverge where one stops
+    if first_divergence.is_none() && frames_a.len() != frames_b.len() {
+        first_divergence = Some(Divergence {
+            turn: shared_turns as i32,
+            differences: vec![format!(
+                "game {} ended on turn {}",
+                if frames_a.len() < frames_b.len() {
+                    "A"
+                } else {
+                    "B"
+                },
+                shared_turns as i32 - 1
+            )],
+        });
+    }
+
+    let outcome = |game: &GameFrames, placements: &HashMap<String, i32>, slot: usize| {
+        game.last_seen(slot).map(|s| SnakeOutcome {
+            name: s.name.clone(),
+            placement: placements.get(&s.id).copied(),
+            length: s.body.len(),
+            health: s.health.max(0),
+            eliminated_turn: s.death.as_ref().map(|d| d.turn),
+            eliminated_cause: s.death.as_ref().map(|d| d.cause.clone()),
+        })
+    };
+    let snakes = (0..a.snake_ids.len().max(b.snake_ids.len()))
+        .map(|slot| SnakeDiff {
+            slot: slot + 1,
+            diverged_at: (0..shared_turns)
+                .find(|&turn| {
+                    !snake_differences(a.snake(turn, slot), b.snake(turn, slot)).is_empty()
+                })
+                .map(|turn| turn as i32),
+            a: outcome(&a, placements_a, slot),
+            b: outcome(&b, placements_b, slot),
+        })
+        .collect();
+
+    (first_divergence, differing_turns, snakes)
+}
+
+/// A game's stored frames, indexed by turn
+async fn load_frames(app_state: &AppState, game_id: Uuid) -> Result<Vec<StoredFrame>, DiffError> {
+    let mut turns = load_turns(app_state, game_id).await?;
+    turns.sort_by_key(|t| t.turn_number);
+
+    let mut frames = Vec::with_capacity(turns.len());
+    for (expected, turn) in turns.into_iter().enumerate() {
+        let data = turn
+            .frame_data
+            .filter(|_| usize::try_from(turn.turn_number).ok() == Some(expected))
+            .ok_or_else(|| eyre!("Game {} has no frame for turn {}", game_id, expected))?;
+        frames.push(
+            serde_json::from_value(data).wrap_err_with(|| {
+                format!("Failed to parse frame {} of game {}", expected, game_id)
+            })?,
+        );
+    }
+
+    if frames.is_empty() {
+        return Err(DiffError::Invalid(format!(
+            "Game {} hasn't started, so has nothing to compare",
+            game_id
+        )));
+    }
+    Ok(frames)
+}
+
+/// Diff two games from the database
+pub async fn diff_games(
+    app_state: &AppState,
+    game_a: Uuid,
+    game_b: Uuid,
+) -> Result<GameDiff, DiffError> {
+    let pool = &app_state.db;
+    let mut setup_differences = Vec::new();
+    let (a, b) = (
+        get_game_by_id(pool, game_a)
+            .await?
+            .ok_or_else(|| eyre!("Game {} not found", game_a))?,
+        get_game_by_id(pool, game_b)
+            .await?
+            .ok_or_else(|| eyre!("Game {} not found", game_b))?,
+    );
+    if a.board_size != b.board_size {
+        setup_differences.push(format!(
+            "board: {} vs {}",
+            a.board_size.as_str(),
+            b.board_size.as_str()
+        ));
+    }
+    if a.game_type != b.game_type {
+        setup_differences.push(format!(
+            "game type: {} vs {}",
+            a.game_type.as_str(),
+            b.game_type.as_str()
+        ));
+    }
+    let (seed_a, seed_b) = (
+        get_game_settings(pool, game_a).await?.seed,
+        get_game_settings(pool, game_b).await?.seed,
+    );
+    match (seed_a, seed_b) {
+        (Some(a), Some(b)) if a == b => {}
+        (None, None) => setup_differences
+            .push("seed: neither game has one, so each is seeded from its own ID".to_string()),
+        _ => {
+            let seed = |seed: Option<i64>| seed.map_or("none".to_string(), |s| s.to_string());
+            setup_differences.push(format!("seed: {} vs {}", seed(seed_a), seed(seed_b)));
+        }
+    }
+
+    let placements = |snakes: Vec<GameBattlesnakeWithDetails>| {
+        snakes
+            .into_iter()
+            .filter_map(|s| Some((s.game_battlesnake_id.to_string(), s.placement?)))
+            .collect::<HashMap<_, _>>()
+    };
+    let (snakes_a, snakes_b) = (
+        get_battlesnakes_by_game_id(pool, game_a).await?,
+        get_battlesnakes_by_game_id(pool, game_b).await?,
+    );
+    if snakes_a.len() != snakes_b.len() {
+        setup_differences.push(format!("snakes: {} vs {}", snakes_a.len(), snakes_b.len()));
+    }
+    let (placements_a, placements_b) = (placements(snakes_a), placements(snakes_b));
+
+    let (frames_a, frames_b) = (
+        load_frames(app_state, game_a).await?,
+        load_frames(app_state, game_b).await?,
+    );
+    let (first_divergence, differing_turns, snakes) =
+        diff_frames(&frames_a, &frames_b, &placements_a, &placements_b);
+
+    Ok(GameDiff {
+        game_a,
+        game_b,
+        setup_differences,
+        turns_a: frames_a.len() as i32 - 1,
+        turns_b: frames_b.len() as i32 - 1,
+        first_divergence,
+        differing_turns,
+        snakes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(snakes: serde_json::Value, food: serde_json::Value) -> StoredFrame {
+        serde_json::from_value(serde_json::json!({
+            "Snakes": snakes,
+            "Food": food,
+            "Hazards": []
+        }))
+        .unwrap()
+    }
+
+    fn snake(id: &str, body: &[(i32, i32)], health: i32) -> serde_json::Value {
+        let body: Vec<_> = body
+            .iter()
+            .map(|(x, y)| serde_json::json!({"X": x, "Y": y}))
+            .collect();
+        serde_json::json!({"ID": id, "Name": id.to_uppercase(), "Body": body, "Health": health})
+    }
+
+    /// Two snakes on a 7x7 board; on turn 2 the second one either turns or doesn't
+    fn game(prefix: &str, turn_on_2: bool) -> Vec<StoredFrame> {
+        let (one, two) = (format!("{prefix}1"), format!("{prefix}2"));
+        let food = serde_json::json!([{"X": 3, "Y": 3}]);
+        let second_head = if turn_on_2 { (4, 4) } else { (5, 3) };
+        vec![
+            frame(
+                serde_json::json!([
+                    snake(&one, &[(1, 1), (1, 1)], 100),
+                    snake(&two, &[(5, 5), (5, 5)], 100)
+                ]),
+                food.clone(),
+            ),
+            frame(
+                serde_json::json!([
+                    snake(&one, &[(1, 2), (1, 1)], 99),
+                    snake(&two, &[(5, 4), (5, 5)], 99)
+                ]),
+                food.clone(),
+            ),
+            frame(
+                serde_json::json!([
+                    snake(&one, &[(1, 3), (1, 2)], 98),
+                    snake(&two, &[second_head, (5, 4)], 98)
+                ]),
+                food,
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_identical_games_have_no_divergence() {
+        let (a, b) = (game("a", false), game("b", false));
+        let (divergence, differing_turns, snakes) =
+            diff_frames(&a, &b, &HashMap::new(), &HashMap::new());
+
+        assert_eq!(divergence, None);
+        assert_eq!(differing_turns, 0);
+        assert_eq!(snakes.len(), 2);
+        assert!(snakes.iter().all(|s| s.diverged_at.is_none()));
+    }
+
+    #[test]
+    fn test_first_divergence_is_reported() {
+        let (a, b) = (game("a", false), game("b", true));
+        let placements_a = HashMap::from([("a1".to_string(), 1), ("a2".to_string(), 2)]);
+        let (divergence, differing_turns, snakes) =
+            diff_frames(&a, &b, &placements_a, &HashMap::new());
+
+        assert_eq!(
+            divergence,
+            Some(Divergence {
+                turn: 2,
+                differences: vec!["snake 2: head 5,3 vs 4,4".to_string()],
+            })
+        );
+        assert_eq!(differing_turns, 1);
+        assert_eq!(snakes[0].diverged_at, None);
+        assert_eq!(snakes[1].diverged_at, Some(2));
+        assert_eq!(snakes[1].a.as_ref().unwrap().placement, Some(2));
+        assert_eq!(snakes[1].b.as_ref().unwrap().placement, None);
+        assert_eq!(snakes[1].a.as_ref().unwrap().name, "A2");
+    }
+
+    #[test]
+    fn test_game_ending_early_diverges_where_it_stops() {
+        let (a, mut b) = (game("a", false), game("b", false));
+        b.pop();
+
+        let (divergence, differing_turns, _) =
+            diff_frames(&a, &b, &HashMap::new(), &HashMap::new());
+        assert_eq!(
+            divergence,
+            Some(Divergence {
+                turn: 2,
+                differences: vec!["game B ended on turn 1".to_string()],
+            })
+        );
+        assert_eq!(differing_turns, 0);
+    }
+
+    #[test]
+    fn test_food_and_eliminations_are_compared() {
+        let a = vec![frame(
+            serde_json::json!([snake("a1", &[(1, 1)], 100)]),
+            serde_json::json!([{"X": 3, "Y": 3}, {"X": 0, "Y": 0}]),
+        )];
+        let mut eliminated = snake("b1", &[(1, 1)], 0);
+        eliminated["Death"] = serde_json::json!({"Cause": "out-of-health", "Turn": 0});
+        let b = vec![frame(
+            serde_json::json!([eliminated]),
+            serde_json::json!([{"X": 0, "Y": 0}, {"X": 3, "Y": 3}, {"X": 6, "Y": 6}]),
+        )];
+
+        let (divergence, _, snakes) = diff_frames(&a, &b, &HashMap::new(), &HashMap::new());
+        assert_eq!(
+            divergence.unwrap().differences,
+            vec![
+                "food: 0,0 3,3 vs 0,0 3,3 6,6".to_string(),
+                "snake 1: alive vs eliminated (out-of-health), health 100 vs 0".to_string(),
+            ]
+        );
+        assert_eq!(
+            snakes[0].b.as_ref().unwrap().eliminated_cause.as_deref(),
+            Some("out-of-health")
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Game exports in the Battlesnake engine's format
+//!
+//! A finished game can be downloaded as one JSON document: its metadata and
+//! every frame, with the field names and shapes the engine stores them in, so
+//! the board's offline replay importer can load it. It's the same
+//! [`GameExport`] game backups write for games archived from the engine.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::Context as _;
+use serde_json::Value;
+
+use crate::engine::game_ruleset;
+use crate::engine_models::{EngineGame, EngineGameFrame, GameExport};
+use crate::models::game::{Game, GameSettings, GameStatus};
+
+/// The game's metadata as the engine describes a game
+pub fn engine_game(game: &Game, settings: &GameSettings) -> EngineGame {
+    let (width, height) = game.board_size.dimensions();
+    let ruleset = game_ruleset(game.game_type);
+
+    // The engine keeps ruleset settings as strings, keyed the way its rules name them
+    let mut params = HashMap::from([("name".to_string(), ruleset.name.clone())]);
+    if let Some(ruleset_settings) = &ruleset.settings {
+        params.insert(
+            "foodSpawnChance".to_string(),
+            ruleset_settings.food_spawn_chance.to_string(),
+        );
+        params.insert(
+            "minimumFood".to_string(),
+            ruleset_settings.minimum_food.to_string(),
+        );
+        params.insert(
+            "damagePerTurn".to_string(),
+            ruleset_settings.hazard_damage_per_turn.to_string(),
+        );
+        if let Some(royale) = &ruleset_settings.royale {
+            params.insert(
+                "shrinkEveryNTurns".to_string(),
+                royale.shrink_every_n_turns.to_string(),
+            );
+        }
+    }
+
+    EngineGame {
+        id: game.game_id.to_string(),
+        status: match game.status {
+            GameStatus::Finished => "complete",
+            _ => "running",
+        }
+        .to_string(),
+        width: width as i32,
+        height: height as i32,
+        ruleset: params,
+        snake_timeout: settings.move_timeout_ms(),
+        max_turns: settings.turn_limit(),
+        food_spawns: Vec::new(),
+        hazard_spawns: Vec::new(),
+        source: Some("arena".to_string()),
+        ruleset_name: Some(ruleset.name),
+        rules_stages: None,
+        map: None,
+        created: game.created_at.timestamp_micros(),
+    }
+}
+
+/// Build a game's export from its stored frames, in turn order
+pub fn build_export(
+    game: &Game,
+    settings: &GameSettings,
+    frames: Vec<Value>,
+    exported_at: DateTime<Utc>,
+) -> cja::Result<GameExport> {
+    let frames = frames
+        .into_iter()
+        .map(|frame| {
+            serde_json::from_value::<EngineGameFrame>(frame)
+                .wrap_err_with(|| format!("Failed to read a frame of game {}", game.game_id))
+        })
+        .collect::<cja::Result<Vec<_>>>()?;
+
+    Ok(GameExport {
+        game: engine_game(game, settings),
+        frames,
+        exported_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::game::{GameBoardSize, GameType};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn game(game_type: GameType) -> Game {
+        Game {
+            game_id: Uuid::new_v4(),
+            board_size: GameBoardSize::Small,
+            game_type,
+            status: GameStatus::Finished,
+            enqueued_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn frame(turn: i32) -> Value {
+        json!({
+            "Turn": turn,
+            "Snakes": [{
+                "ID": "snake-1",
+                "Name": "Alpha",
+                "Body": [{"X": 1, "Y": 1}, {"X": 1, "Y": 2}],
+                "Health": 100 - turn,
+                "Color": "#ff0000",
+                "Latency": "12",
+                "Death": null,
+            }],
+            "Food": [{"X": 3, "Y": 3}],
+            "Hazards": [],
+        })
+    }
+
+    #[test]
+    fn test_export_uses_engine_format() {
+        let game = game(GameType::Royale);
+        let settings = GameSettings {
+            max_turns: Some(200),
+            ..Default::default()
+        };
+
+        let export = build_export(&game, &settings, vec![frame(0), frame(1)], Utc::now()).unwrap();
+        let value = serde_json::to_value(&export).unwrap();
+
+        assert_eq!(value["game"]["ID"], game.game_id.to_string());
+        assert_eq!(value["game"]["Status"], "complete");
+        assert_eq!(value["game"]["Width"], 7);
+        assert_eq!(value["game"]["Height"], 7);
+        assert_eq!(value["game"]["MaxTurns"], 200);
+        assert_eq!(value["game"]["RulesetName"], "royale");
+        assert_eq!(value["game"]["Ruleset"]["name"], "royale");
+        assert_eq!(value["game"]["Ruleset"]["shrinkEveryNTurns"], "25");
+        assert_eq!(value["frames"].as_array().unwrap().len(), 2);
+        assert_eq!(value["frames"][1]["Turn"], 1);
+        assert_eq!(value["frames"][1]["Snakes"][0]["Health"], 99);
+        assert_eq!(value["frames"][1]["Snakes"][0]["Body"][1]["Y"], 2);
+    }
+
+    #[test]
+    fn test_standard_games_have_no_shrink_setting() {
+        let exported = engine_game(&game(GameType::Standard), &GameSettings::default());
+        assert_eq!(exported.ruleset["name"], "standard");
+        assert!(!exported.ruleset.contains_key("shrinkEveryNTurns"));
+    }
+
+    #[test]
+    fn test_frames_that_arent_frames_fail() {
+        let result = build_export(
+            &game(GameType::Standard),
+            &GameSettings::default(),
+            vec![json!({"Snakes": [{"Health": 1}]})],
+            Utc::now(),
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,133 @@
+//! Per-game log of what the game runner saw
+//!
+//! Snake errors, timeouts and fallback moves are mostly invisible in the board
+//! replay, so the runner records them here as it goes. Entries are buffered
+//! in memory and written alongside each turn, then served to the owners of the
+//! participating snakes from `GET /api/games/{id}/log`.
+
+use std::fmt::Write as _;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::clock::SharedClock;
+use crate::models::game_log::{
+    CreateGameLogEntry, GameLogEntry, GameLogLevel, create_game_log_entries,
+};
+
+/// Buffer of log entries for a running game
+#[derive(Debug)]
+pub struct GameLog {
+    game_id: Uuid,
+    clock: SharedClock,
+    pending: Vec<CreateGameLogEntry>,
+}
+
+impl GameLog {
+    pub fn new(game_id: Uuid, clock: SharedClock) -> Self {
+        Self {
+            game_id,
+            clock,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn info(&mut self, turn: Option<i32>, snake: Option<Uuid>, message: impl Into<String>) {
+        self.push(GameLogLevel::Info, turn, snake, message.into());
+    }
+
+    pub fn warn(&mut self, turn: Option<i32>, snake: Option<Uuid>, message: impl Into<String>) {
+        self.push(GameLogLevel::Warn, turn, snake, message.into());
+    }
+
+    fn push(
+        &mut self,
+        level: GameLogLevel,
+        turn: Option<i32>,
+        snake: Option<Uuid>,
+        message: String,
+    ) {
+        self.pending.push(CreateGameLogEntry {
+            turn_number: turn,
+            game_battlesnake_id: snake,
+            level,
+            message,
+            created_at: self.clock.now(),
+        });
+    }
+
+    /// Write buffered entries to the database
+    pub async fn flush(&mut self, pool: &PgPool) -> cja::Result<()> {
+        create_game_log_entries(pool, self.game_id, &self.pending).await?;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+/// Render a game's log as plain text, one entry per line
+pub fn format_game_log(entries: &[GameLogEntry]) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        let _ = write!(out, "{} ", entry.created_at.to_rfc3339());
+        if let Some(turn) = entry.turn_number {
+            let _ = write!(out, "[turn {}] ", turn);
+        }
+        let _ = writeln!(out, "{:<5} {}", entry.level.to_uppercase(), entry.message);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use chrono::{TimeZone, Utc};
+
+    fn entry(turn_number: Option<i32>, level: &str, message: &str) -> GameLogEntry {
+        GameLogEntry {
+            game_log_entry_id: Uuid::new_v4(),
+            game_id: Uuid::new_v4(),
+            turn_number,
+            game_battlesnake_id: None,
+            level: level.to_string(),
+            message: message.to_string(),
+            created_at: Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_format_game_log() {
+        let text = format_game_log(&[
+            entry(None, "info", "Game started with 2 snakes"),
+            entry(Some(3), "warn", "Alpha: timed out after 500ms, moved up"),
+        ]);
+
+        assert_eq!(
+            text,
+            "2026-10-16T12:00:00+00:00 INFO  Game started with 2 snakes\n\
+             2026-10-16T12:00:00+00:00 [turn 3] WARN  Alpha: timed out after 500ms, moved up\n"
+        );
+    }
+
+    #[test]
+    fn test_game_log_buffers_with_clock_time() {
+        let start = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let clock = MockClock::new(start);
+        let mut log = GameLog::new(Uuid::new_v4(), std::sync::Arc::new(clock.clone()));
+
+        let snake = Uuid::new_v4();
+        log.info(None, None, "started");
+        clock.advance(chrono::Duration::seconds(1));
+        log.warn(Some(1), Some(snake), "timed out");
+
+        assert_eq!(log.pending.len(), 2);
+        assert_eq!(log.pending[0].level, GameLogLevel::Info);
+        assert_eq!(log.pending[0].created_at, start);
+        assert_eq!(log.pending[1].turn_number, Some(1));
+        assert_eq!(log.pending[1].game_battlesnake_id, Some(snake));
+        assert_eq!(
+            log.pending[1].created_at,
+            start + chrono::Duration::seconds(1)
+        );
+    }
+}
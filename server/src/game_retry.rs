@@ -0,0 +1,300 @@
+//! Retrying games whose run failed part way through
+//!
+//! After storing each batch of turns, the runner saves a [`Checkpoint`] of
+//! what it needs to carry on: the engine's board, each snake's last move,
+//! who's been eliminated and the food each snake has eaten. Everything random
+//! in a game is drawn from its seed and the turn number, so a new run that
+//! starts from the checkpoint plays on as the failed one would have.
+//!
+//! When a run fails, or its worker stops heartbeating, a [`RetryGameJob`]
+//! runs the game again after a short backoff. The runner resumes from the
+//! checkpoint and drops any turn stored after it. A game without a usable
+//! checkpoint, because its run died before turn 0 was stored, restarts
+//! cleanly. A game that fails [`MAX_GAME_RETRIES`] times is left where it
+//! stopped, with its last failure recorded for operators.
+//!
+//! [`RetryGameJob`]: crate::jobs::RetryGameJob
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use battlesnake_game_types::types::Move;
+use battlesnake_game_types::wire_representation::Game;
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::engine::FoodTally;
+use crate::engine::frame::DeathInfo;
+use crate::game_log::GameLog;
+use crate::jobs::{HIGH_PRIORITY, RetryGameJob, enqueue_at};
+use crate::snake_client::parse_direction;
+use crate::state::AppState;
+
+/// Failed runs a game gets retried after before it's given up on
+pub const MAX_GAME_RETRIES: i32 = 3;
+
+/// Wait before the first retry, doubled for each one after
+const RETRY_BASE_DELAY_SECS: i64 = 5;
+
+/// A running game's runner state after its last stored turn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The engine's state, with `turn` the last stored turn
+    pub game: Game,
+    /// Each snake's last move, which it repeats if it times out
+    pub last_moves: HashMap<String, String>,
+    /// Eliminated snakes and the turn each went out on, in order
+    pub eliminated: Vec<(String, i32)>,
+    pub death_info: Vec<DeathInfo>,
+    pub food: FoodTally,
+    /// Each snake's /start round trip, when latency compensation is on
+    pub start_rtts_ms: HashMap<String, u64>,
+}
+
+impl Checkpoint {
+    pub fn new(
+        game: &Game,
+        last_moves: &HashMap<String, Move>,
+        eliminated: &[(String, i32)],
+        death_info: &[DeathInfo],
+        food: &FoodTally,
+        start_rtts: &HashMap<String, Duration>,
+    ) -> Self {
+        Self {
+            game: game.clone(),
+            last_moves: last_moves
+                .iter()
+                .map(|(id, m)| (id.clone(), m.to_string()))
+                .collect(),
+            eliminated: eliminated.to_vec(),
+            death_info: death_info.to_vec(),
+            food: food.clone(),
+            start_rtts_ms: start_rtts
+                .iter()
+                .map(|(id, rtt)| (id.clone(), rtt.as_millis() as u64))
+                .collect(),
+        }
+    }
+
+    pub fn turn(&self) -> i32 {
+        self.game.turn
+    }
+
+    pub fn last_moves(&self) -> HashMap<String, Move> {
+        self.last_moves
+            .iter()
+            .filter_map(|(id, m)| parse_direction(m).map(|m| (id.clone(), m)))
+            .collect()
+    }
+
+    pub fn start_rtts(&self) -> HashMap<String, Duration> {
+        self.start_rtts_ms
+            .iter()
+            .map(|(id, ms)| (id.clone(), Duration::from_millis(*ms)))
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+struct CheckpointRow {
+    seed: i64,
+    state: serde_json::Value,
+}
+
+// Save a game's checkpoint, replacing its last one
+pub async fn save_checkpoint(
+    pool: &PgPool,
+    game_id: Uuid,
+    seed: u64,
+    checkpoint: &Checkpoint,
+    now: DateTime<Utc>,
+) -> cja::Result<()> {
+    let state = serde_json::to_value(checkpoint).wrap_err("Failed to serialize checkpoint")?;
+    sqlx::query!(
+        r#"
+        INSERT INTO game_checkpoints (game_id, turn, seed, state, updated_at)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (game_id) DO UPDATE
+        SET turn = EXCLUDED.turn, seed = EXCLUDED.seed, state = EXCLUDED.state,
+            updated_at = EXCLUDED.updated_at
+        "#,
+        game_id,
+        checkpoint.turn(),
+        (seed as i64),
+        state,
+        now
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to save checkpoint for game {}", game_id))?;
+    Ok(())
+}
+
+// A game's checkpoint, if it has one a run with `seed` can carry on from
+pub async fn get_checkpoint(
+    pool: &PgPool,
+    game_id: Uuid,
+    seed: u64,
+) -> cja::Result<Option<Checkpoint>> {
+    let row = sqlx::query_as!(
+        CheckpointRow,
+        "SELECT seed, state FROM game_checkpoints WHERE game_id = $1",
+        game_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to get game checkpoint")?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    // Played on with another seed, the game would diverge from its stored turns
+    if row.seed != seed as i64 {
+        tracing::warn!(game_id = %game_id, "Checkpoint has a different seed, not resuming from it");
+        return Ok(None);
+    }
+    match serde_json::from_value(row.state) {
+        Ok(checkpoint) => Ok(Some(checkpoint)),
+        Err(e) => {
+            tracing::warn!(game_id = %game_id, error = %e, "Couldn't read checkpoint, not resuming from it");
+            Ok(None)
+        }
+    }
+}
+
+// Delete a game's checkpoint once it's finished
+pub async fn delete_checkpoint(pool: &PgPool, game_id: Uuid) -> cja::Result<()> {
+    sqlx::query!("DELETE FROM game_checkpoints WHERE game_id = $1", game_id)
+        .execute(pool)
+        .await
+        .wrap_err("Failed to delete game checkpoint")?;
+    Ok(())
+}
+
+// How many times a game's run has been retried
+pub async fn get_retry_count(pool: &PgPool, game_id: Uuid) -> cja::Result<i32> {
+    sqlx::query_scalar!("SELECT retry_count FROM games WHERE game_id = $1", game_id)
+        .fetch_one(pool)
+        .await
+        .wrap_err("Failed to get game retry count")
+}
+
+/// How long to wait before a game's `retry`th retry
+fn retry_delay(retry: i32) -> chrono::Duration {
+    chrono::Duration::seconds(RETRY_BASE_DELAY_SECS << (retry - 1).clamp(0, 6))
+}
+
+/// Record that a game's run failed and queue its retry. Returns false if the
+/// game has finished or run out of retries, so nothing was queued.
+pub async fn retry_failed_game(
+    app_state: &AppState,
+    game_id: Uuid,
+    failure: &str,
+) -> cja::Result<bool> {
+    let pool = &app_state.db;
+    let retry_count = sqlx::query_scalar!(
+        r#"
+        UPDATE games SET retry_count = retry_count + 1, last_failure = $2
+        WHERE game_id = $1 AND status <> 'finished'
+        RETURNING retry_count
+        "#,
+        game_id,
+        failure
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to record game failure")?;
+    let Some(retry_count) = retry_count else {
+        return Ok(false);
+    };
+
+    // Snake owners see that the run failed, but not the server's error
+    let mut game_log = GameLog::new(game_id, app_state.clock.clone());
+    if retry_count > MAX_GAME_RETRIES {
+        tracing::error!(
+            game_id = %game_id,
+            retry_count,
+            failure,
+            "Game failed too many times, giving up on it"
+        );
+        game_log.warn(
+            None,
+            None,
+            format!(
+                "The game's run failed on the server {} times, so it was stopped",
+                retry_count
+            ),
+        );
+        game_log.flush(pool).await?;
+        return Ok(false);
+    }
+
+    tracing::warn!(game_id = %game_id, retry_count, failure, "Game run failed, retrying");
+    game_log.warn(
+        None,
+        None,
+        format!(
+            "The game's run failed on the server, retrying from the last stored turn (retry {} of {})",
+            retry_count, MAX_GAME_RETRIES
+        ),
+    );
+    game_log.flush(pool).await?;
+
+    enqueue_at(
+        pool,
+        RetryGameJob { game_id },
+        format!("Retry {} of game {}", retry_count, game_id),
+        HIGH_PRIORITY,
+        app_state.clock.now() + retry_delay(retry_count),
+    )
+    .await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        assert_eq!(retry_delay(1), chrono::Duration::seconds(5));
+        assert_eq!(retry_delay(2), chrono::Duration::seconds(10));
+        assert_eq!(retry_delay(3), chrono::Duration::seconds(20));
+        assert_eq!(retry_delay(100), chrono::Duration::seconds(320));
+    }
+
+    #[test]
+    fn test_checkpoint_round_trips_moves_and_latencies() {
+        let game = crate::engine::create_game_from_board(
+            Uuid::from_u128(1),
+            crate::models::game::GameType::Standard,
+            battlesnake_game_types::wire_representation::Board {
+                height: 11,
+                width: 11,
+                food: vec![],
+                hazards: vec![],
+                snakes: vec![],
+            },
+        );
+        let last_moves = HashMap::from([("a".to_string(), Move::Left)]);
+        let start_rtts = HashMap::from([("a".to_string(), Duration::from_millis(42))]);
+        let checkpoint = Checkpoint::new(
+            &game,
+            &last_moves,
+            &[("b".to_string(), 3)],
+            &[],
+            &FoodTally::default(),
+            &start_rtts,
+        );
+
+        let json = serde_json::to_value(&checkpoint).unwrap();
+        let restored: Checkpoint = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.last_moves(), last_moves);
+        assert_eq!(restored.start_rtts(), start_rtts);
+        assert_eq!(restored.eliminated, vec![("b".to_string(), 3)]);
+        assert_eq!(restored.turn(), 0);
+    }
+}
@@ -4,17 +4,144 @@ use uuid::Uuid;
 
 use battlesnake_game_types::types::Move;
 
-use crate::engine::MAX_TURNS;
+use crate::certification::FrameHashChain;
 use crate::engine::frame::{DeathInfo, game_to_frame};
-use crate::models::game::{GameStatus, get_game_by_id, update_game_status};
-use crate::snake_client::{request_end_parallel, request_moves_parallel, request_start_parallel};
+use crate::engine::hooks::{Eliminated, GameEnded, GameStarted, TurnApplied};
+use crate::engine::move_provider::{
+    MoveProviders, end_all, info_all, provider_for_url, request_moves, start_all,
+};
+use crate::engine::wasm_ruleset::load_ruleset;
+use crate::engine::{
+    FoodTally, apply_turn_with_eliminations, engine_seed, final_placements, is_draw,
+};
+use crate::game_channels::TurnNotification;
+use crate::game_control::TurnGate;
+use crate::game_log::GameLog;
+use crate::game_retry::{
+    Checkpoint, delete_checkpoint, get_checkpoint, get_retry_count, retry_failed_game,
+    save_checkpoint,
+};
+use crate::models::game::{
+    GameStatus, MoveValidationMode, get_game_by_id, get_game_settings, get_game_spawn_strategy,
+    record_final_turn, reset_game_for_rerun, set_game_result_digest, set_game_started_at,
+    set_game_tiebreak_applied, update_game_status,
+};
+use crate::models::game_battlesnake::{get_snake_info_by_game_id, set_snake_info, snake_instances};
+use crate::models::game_stage_timing::save_game_stage_timings;
+use crate::models::turn::{CreateSnakeTurn, CreateTurn, delete_turns_after, get_turns_by_game_id};
+use crate::snake_client::SnakeInfo;
 use crate::state::AppState;
+use crate::turn_batch::TurnBatch;
+use crate::turn_metrics::{Stage, StageTimings};
+use crate::worker::Lease;
 
 /// Run a game with turn-by-turn DB persistence and WebSocket notifications
 ///
 /// This function calls the actual snake APIs to get moves, with timeout handling.
 /// On timeout, snakes continue in the same direction as their last move.
+///
+/// The run holds a heartbeated lease on the game for as long as it lasts, so
+/// the stuck-game sweeper can tell if this worker dies part way through.
 pub async fn run_game(app_state: &AppState, game_id: Uuid) -> cja::Result<()> {
+    let Some(lease) = Lease::acquire(app_state, game_id).await? else {
+        tracing::warn!(
+            game_id = %game_id,
+            "Another worker holds a live lease on this game, not running it"
+        );
+        return Ok(());
+    };
+
+    let result = run_leased_game(app_state, game_id, &lease).await;
+    let lost = lease.is_lost();
+
+    if let Err(e) = lease.release(app_state).await {
+        tracing::error!(game_id = %game_id, error = %e, "Failed to release game lease");
+    }
+
+    match result {
+        Ok(()) => Ok(()),
+        // The run that took the game over carries on with it
+        Err(e) if lost => Err(e),
+        // Retried from the last checkpoint rather than by the job queue, which
+        // would start the game over
+        Err(e) => {
+            tracing::error!(game_id = %game_id, error = ?e, "Game run failed");
+            retry_failed_game(app_state, game_id, &format!("{:#}", e)).await?;
+            Ok(())
+        }
+    }
+}
+
+/// What a run carries from turn to turn
+struct RunState {
+    engine_game: battlesnake_game_types::wire_representation::Game,
+    snake_info: HashMap<String, SnakeInfo>,
+    start_rtts: HashMap<String, std::time::Duration>,
+    death_info: Vec<DeathInfo>,
+    eliminated: Vec<(String, i32)>,
+    last_moves: HashMap<String, Move>,
+    food: FoodTally,
+    hash_chain: FrameHashChain,
+}
+
+/// Pick a game back up from its checkpoint, dropping turns a failed run
+/// stored after it. The snakes aren't sent /start again.
+async fn resume_run(
+    app_state: &AppState,
+    game_id: Uuid,
+    checkpoint: Checkpoint,
+    game_log: &mut GameLog,
+) -> cja::Result<RunState> {
+    let pool = &app_state.db;
+    let turn = checkpoint.turn();
+    let dropped = delete_turns_after(pool, game_id, turn).await?;
+
+    // The chain carries on from the frames already stored
+    let mut hash_chain = FrameHashChain::new(game_id);
+    for stored in get_turns_by_game_id(pool, game_id).await? {
+        if let Some(frame) = &stored.frame_data {
+            hash_chain.push(frame);
+        }
+    }
+
+    let snake_info = get_snake_info_by_game_id(pool, game_id)
+        .await?
+        .into_iter()
+        .map(|(id, info)| (id.to_string(), info))
+        .collect();
+
+    let retry_count = get_retry_count(pool, game_id).await?;
+    tracing::info!(game_id = %game_id, turn, dropped, retry_count, "Resuming game from checkpoint");
+    game_log.info(
+        Some(turn),
+        None,
+        format!("Resumed from turn {} (retry {})", turn, retry_count),
+    );
+
+    Ok(RunState {
+        last_moves: checkpoint.last_moves(),
+        start_rtts: checkpoint.start_rtts(),
+        snake_info,
+        death_info: checkpoint.death_info,
+        eliminated: checkpoint.eliminated,
+        food: checkpoint.food,
+        engine_game: checkpoint.game,
+        hash_chain,
+    })
+}
+
+/// Error out if the sweeper has handed the game to another run
+fn check_lease(lease: &Lease, game_id: Uuid) -> cja::Result<()> {
+    if lease.is_lost() {
+        return Err(cja::color_eyre::eyre::eyre!(
+            "Lost the lease on game {}, stopping this run",
+            game_id
+        ));
+    }
+    Ok(())
+}
+
+async fn run_leased_game(app_state: &AppState, game_id: Uuid, lease: &Lease) -> cja::Result<()> {
     let pool = &app_state.db;
     let game_channels = &app_state.game_channels;
     let http_client = &app_state.http_client;
@@ -26,9 +153,15 @@ pub async fn run_game(app_state: &AppState, game_id: Uuid) -> cja::Result<()> {
         .await?
         .ok_or_else(|| cja::color_eyre::eyre::eyre!("Game not found"))?;
 
+    // A retry queued alongside a run that went on to finish the game
+    if game.status == GameStatus::Finished {
+        tracing::warn!(game_id = %game_id, "Game has already finished, not running it again");
+        return Ok(());
+    }
+
     // Emit queue_wait metric if enqueued_at is available
     if let Some(enqueued_at) = game.enqueued_at {
-        let queue_wait = chrono::Utc::now().signed_duration_since(enqueued_at);
+        let queue_wait = app_state.clock.now().signed_duration_since(enqueued_at);
         tracing::info!(
             metric_type = "queue_wait",
             game_id = %game_id,
@@ -37,9 +170,6 @@ pub async fn run_game(app_state: &AppState, game_id: Uuid) -> cja::Result<()> {
         );
     }
 
-    // Update status to running
-    update_game_status(pool, game_id, GameStatus::Running).await?;
-
     // Get all the battlesnakes in the game with their URLs
     let battlesnakes = crate::models::game_battlesnake::get_battlesnakes_by_game_id(pool, game_id)
         .await
@@ -49,52 +179,251 @@ pub async fn run_game(app_state: &AppState, game_id: Uuid) -> cja::Result<()> {
         return Err(cja::color_eyre::eyre::eyre!("No battlesnakes in the game"));
     }
 
-    // Build snake_id -> url mapping using game_battlesnake_id as the key
+    // Per-game options (move validation, latency compensation, seed)
+    let settings = get_game_settings(pool, game_id).await?;
+    let seed = engine_seed(&game_id.to_string(), settings.seed);
+    let ruleset = match settings.ruleset_id {
+        Some(ruleset_id) => Some(load_ruleset(pool, ruleset_id).await?),
+        None => None,
+    };
+
+    // A game still running here had a run fail part way through. It carries
+    // on from its checkpoint, or starts over if the run failed before saving one.
+    let checkpoint = match game.status {
+        GameStatus::Running => get_checkpoint(pool, game_id, seed).await?,
+        _ => None,
+    };
+    if checkpoint.is_none() {
+        if game.status == GameStatus::Running {
+            tracing::warn!(game_id = %game_id, "No checkpoint to resume from, restarting game");
+            reset_game_for_rerun(pool, game_id).await?;
+        }
+        update_game_status(pool, game_id, GameStatus::Running).await?;
+        set_game_started_at(pool, game_id, app_state.clock.now()).await?;
+    }
+
+    // Build snake_id -> move provider mapping using game_battlesnake_id as the key
     // This ensures uniqueness when the same battlesnake appears multiple times
-    let snake_urls: Vec<(String, String)> = battlesnakes
+    let providers: MoveProviders = battlesnakes
         .iter()
-        .map(|bs| (bs.game_battlesnake_id.to_string(), bs.url.clone()))
+        .map(|bs| {
+            (
+                bs.game_battlesnake_id.to_string(),
+                provider_for_url(http_client, &bs.url, seed),
+            )
+        })
         .collect();
 
-    // Create the initial game state
-    let mut engine_game =
-        crate::engine::create_initial_game(game_id, game.board_size, game.game_type, &battlesnakes);
+    // Display names per game_battlesnake_id, for the game log
+    let snake_names: HashMap<String, String> = snake_instances(&battlesnakes)
+        .into_iter()
+        .map(|(id, instance)| (id.to_string(), instance.display_name))
+        .collect();
+    let snake_name = |snake_id: &str| {
+        snake_names
+            .get(snake_id)
+            .cloned()
+            .unwrap_or_else(|| snake_id.to_string())
+    };
+
+    let mut game_log = GameLog::new(game_id, app_state.clock.clone());
+    let hooks = &app_state.engine_hooks;
+
+    let RunState {
+        mut engine_game,
+        snake_info,
+        start_rtts,
+        mut death_info,
+        mut eliminated,
+        mut last_moves,
+        mut food,
+        mut hash_chain,
+    } = match checkpoint {
+        Some(checkpoint) => resume_run(app_state, game_id, checkpoint, &mut game_log).await?,
+        None => {
+            // Create the initial game state
+            let spawn = get_game_spawn_strategy(pool, game_id).await?;
+            let mut engine_game = crate::engine::create_initial_game(
+                game_id,
+                game.board_size,
+                game.game_type,
+                &battlesnakes,
+                &spawn,
+                seed,
+            );
+
+            // Snakes are sent the game's own move timeout, and held to it
+            engine_game.game.timeout = settings.move_timeout_ms() as _;
+            let timeout = std::time::Duration::from_millis(engine_game.game.timeout as u64);
+
+            game_log.info(
+                None,
+                None,
+                format!(
+                    "Game started with {} snakes ({}ms timeout, {} move validation, latency compensation {})",
+                    battlesnakes.len(),
+                    timeout.as_millis(),
+                    settings.validation_mode.as_str(),
+                    if settings.latency_compensation {
+                        "on"
+                    } else {
+                        "off"
+                    }
+                ),
+            );
+
+            // Ask every snake for its info, so the frames show its color, head and tail
+            tracing::info!(game_id = %game_id, "Calling GET / for all snakes");
+            let mut snake_info = HashMap::new();
+            for (snake_id, result) in info_all(&providers, &engine_game).await {
+                match result {
+                    Ok(Some(info)) => {
+                        if let Ok(game_battlesnake_id) = Uuid::parse_str(&snake_id) {
+                            set_snake_info(pool, game_battlesnake_id, &info).await?;
+                        }
+                        snake_info.insert(snake_id, info);
+                    }
+                    Ok(None) => {}
+                    Err(e) => game_log.warn(
+                        None,
+                        Uuid::parse_str(&snake_id).ok(),
+                        format!("{}: GET / {}", snake_name(&snake_id), e),
+                    ),
+                }
+            }
 
-    // Get timeout from game settings (default 500ms)
-    let timeout = std::time::Duration::from_millis(engine_game.game.timeout as u64);
+            // Call /start for all snakes in parallel, measuring each snake's round trip
+            tracing::info!(game_id = %game_id, "Calling /start for all snakes");
+            let start_results = start_all(&providers, &engine_game).await;
+
+            let mut start_rtts = HashMap::new();
+            for (snake_id, result) in start_results {
+                match result {
+                    Ok(Some(rtt)) => {
+                        start_rtts.insert(snake_id, rtt);
+                    }
+                    Ok(None) => {}
+                    Err(e) => game_log.warn(
+                        None,
+                        Uuid::parse_str(&snake_id).ok(),
+                        format!("{}: /start {}", snake_name(&snake_id), e),
+                    ),
+                }
+            }
 
-    // Call /start for all snakes in parallel (fire and forget)
-    tracing::info!(game_id = %game_id, "Calling /start for all snakes");
-    request_start_parallel(http_client, &engine_game, &snake_urls, timeout).await;
+            // With latency compensation, each snake's /move deadline is extended by its
+            // /start round trip; without it everyone gets the same flat timeout
+            let start_rtts = if settings.latency_compensation {
+                for (snake_id, rtt) in &start_rtts {
+                    tracing::info!(
+                        metric_type = "start_rtt",
+                        game_id = %game_id,
+                        snake_id = %snake_id,
+                        duration_ms = rtt.as_millis() as u64,
+                        "snake /start round trip"
+                    );
+                }
+                start_rtts
+            } else {
+                HashMap::new()
+            };
+
+            // Store turn 0 (initial state, no moves yet)
+            let frame_0 = game_to_frame(&engine_game, &[], &[]).with_snake_info(&snake_info);
+            let frame_0_json =
+                serde_json::to_value(&frame_0).wrap_err("Failed to serialize initial frame")?;
+
+            // Certify the frames as they're produced, so later edits to stored turns show up
+            let mut hash_chain = FrameHashChain::new(game_id);
+            hash_chain.push(&frame_0_json);
+
+            tracing::info!(game_id = %game_id, "Storing turn 0");
+            crate::models::turn::create_turn(pool, game_channels, game_id, 0, Some(frame_0_json))
+                .await?;
+            tracing::info!(game_id = %game_id, "Turn 0 stored successfully");
+
+            save_checkpoint(
+                pool,
+                game_id,
+                seed,
+                &Checkpoint::new(
+                    &engine_game,
+                    &HashMap::new(),
+                    &[],
+                    &[],
+                    &FoodTally::default(),
+                    &start_rtts,
+                ),
+                app_state.clock.now(),
+            )
+            .await?;
+
+            hooks
+                .game_started(
+                    app_state,
+                    &GameStarted {
+                        game_id,
+                        game: &engine_game,
+                        snakes: &battlesnakes,
+                    },
+                )
+                .await;
 
-    let mut death_info: Vec<DeathInfo> = Vec::new();
-    let mut elimination_order: Vec<String> = Vec::new();
-    let mut last_moves: HashMap<String, Move> = HashMap::new();
+            RunState {
+                engine_game,
+                snake_info,
+                start_rtts,
+                death_info: Vec::new(),
+                eliminated: Vec::new(),
+                last_moves: HashMap::new(),
+                food: FoodTally::default(),
+                hash_chain,
+            }
+        }
+    };
 
     // Helper to check if game is over
     let is_game_over = |g: &battlesnake_game_types::wire_representation::Game| {
         g.board.snakes.iter().filter(|s| s.health > 0).count() <= 1
     };
 
-    // Store turn 0 (initial state, no moves yet)
-    let frame_0 = game_to_frame(&engine_game, &death_info, &[]);
-    let frame_0_json =
-        serde_json::to_value(&frame_0).wrap_err("Failed to serialize initial frame")?;
-
-    tracing::info!(game_id = %game_id, "Storing turn 0");
-    crate::models::turn::create_turn(pool, game_channels, game_id, 0, Some(frame_0_json)).await?;
-    tracing::info!(game_id = %game_id, "Turn 0 stored successfully");
-
     // Track timing for processing_overhead metric
     let game_start = std::time::Instant::now();
     let mut total_snake_wait_ms: i64 = 0;
 
+    // Time each stage of every turn, for the histograms and the timing report
+    let mut timings = StageTimings::new(app_state.turn_metrics.clone());
+
+    // Paused games wait between turns until they're stepped or resumed
+    let mut gate = TurnGate::new(game_id);
+
+    // Turns are stored in batches rather than one at a time
+    let mut turns = TurnBatch::new(game_id, seed);
+
     // Run the game turn by turn
-    while !is_game_over(&engine_game) && engine_game.turn < MAX_TURNS {
+    let max_turns = settings.turn_limit();
+    while !is_game_over(&engine_game) && engine_game.turn < max_turns {
+        check_lease(lease, game_id)?;
+        gate.wait(
+            app_state,
+            lease,
+            &mut game_log,
+            &mut turns,
+            engine_game.turn,
+        )
+        .await?;
+
         // Request moves from all alive snakes in parallel
-        let move_results =
-            request_moves_parallel(http_client, &engine_game, &snake_urls, timeout, &last_moves)
-                .await;
+        let stage_start = std::time::Instant::now();
+        let move_results = request_moves(
+            &providers,
+            &engine_game,
+            &last_moves,
+            settings.validation_mode,
+            &start_rtts,
+        )
+        .await;
+        timings.record(Stage::GatherMoves, stage_start.elapsed());
 
         // Accumulate snake wait time from latency measurements
         for result in &move_results {
@@ -115,57 +444,142 @@ pub async fn run_game(app_state: &AppState, game_id: Uuid) -> cja::Result<()> {
         }
 
         // Apply the moves using the engine
-        engine_game = crate::engine::apply_turn(engine_game, &moves);
+        let stage_start = std::time::Instant::now();
+        let food_before = engine_game.board.food.clone();
+        let (next_game, mut newly_eliminated) =
+            apply_turn_with_eliminations(engine_game, &moves, seed);
+        engine_game = next_game;
         engine_game.turn += 1;
+        food.record_turn(&food_before, &engine_game);
+        // An uploaded ruleset gets the last word; if it fails, the turn stands as played
+        if let Some(ruleset) = &ruleset {
+            match ruleset.apply(&mut engine_game, seed) {
+                Ok(eliminations) => newly_eliminated.extend(eliminations),
+                Err(e) => game_log.warn(
+                    Some(engine_game.turn),
+                    None,
+                    format!("Ruleset left this turn unchanged: {}", e),
+                ),
+            }
+        }
+        timings.record(Stage::ApplyTurn, stage_start.elapsed());
 
-        // Track newly eliminated snakes
-        for snake in &engine_game.board.snakes {
-            if snake.health <= 0 && !elimination_order.contains(&snake.id) {
-                elimination_order.push(snake.id.clone());
-                death_info.push(DeathInfo {
-                    snake_id: snake.id.clone(),
-                    turn: engine_game.turn,
-                    cause: "eliminated".to_string(),
-                    eliminated_by: String::new(),
-                });
+        // Record requests that fell back to a default move
+        for result in &move_results {
+            let snake = Uuid::parse_str(&result.snake_id).ok();
+            if let Some(error) = &result.request_error {
+                game_log.warn(
+                    Some(engine_game.turn),
+                    snake,
+                    format!(
+                        "{}: {}, moved {}",
+                        snake_name(&result.snake_id),
+                        error,
+                        result.direction
+                    ),
+                );
+            } else if let Some(error) = &result.parse_error {
+                let outcome = if settings.validation_mode == MoveValidationMode::Strict {
+                    ", counted as a timeout"
+                } else {
+                    ""
+                };
+                game_log.warn(
+                    Some(engine_game.turn),
+                    snake,
+                    format!(
+                        "{}: bad move response ({}){}, moved {}",
+                        snake_name(&result.snake_id),
+                        error,
+                        outcome,
+                        result.direction
+                    ),
+                );
             }
         }
 
-        // Store the turn frame with latency info and notify subscribers
-        let frame = game_to_frame(&engine_game, &death_info, &move_results);
+        // Track newly eliminated snakes, and who eliminated them
+        for elimination in &newly_eliminated {
+            let by = match elimination.eliminated_by.as_str() {
+                "" => String::new(),
+                by if by == elimination.snake_id => " by itself".to_string(),
+                by => format!(" by {}", snake_name(by)),
+            };
+            game_log.info(
+                Some(engine_game.turn),
+                Uuid::parse_str(&elimination.snake_id).ok(),
+                format!(
+                    "{} was eliminated{} ({})",
+                    snake_name(&elimination.snake_id),
+                    by,
+                    elimination.cause
+                ),
+            );
+            eliminated.push((elimination.snake_id.clone(), engine_game.turn));
+            death_info.push(elimination.death_info(engine_game.turn));
+        }
+
+        // Build the turn frame with latency info
+        let stage_start = std::time::Instant::now();
+        let frame = game_to_frame(&engine_game, &death_info, &move_results)
+            .with_snake_info(&snake_info)
+            .with_dead_snakes(settings.dead_snakes);
         let frame_json = serde_json::to_value(&frame)
             .wrap_err_with(|| format!("Failed to serialize frame {}", engine_game.turn))?;
+        hash_chain.push(&frame_json);
+        timings.record(Stage::SerializeFrame, stage_start.elapsed());
 
         // Measure DB write latency
         let db_write_start = std::time::Instant::now();
 
-        tracing::debug!(game_id = %game_id, turn = engine_game.turn, "Storing turn");
-        let turn = crate::models::turn::create_turn(
-            pool,
-            game_channels,
-            game_id,
-            engine_game.turn,
-            Some(frame_json),
-        )
-        .await?;
-
-        // Store individual snake moves with latency
+        // Individual snake moves with latency and any parse failure
         // The snake_id in move_results is now the game_battlesnake_id (UUID string)
-        for result in &move_results {
-            if let Ok(game_battlesnake_id) = Uuid::parse_str(&result.snake_id) {
-                crate::models::turn::create_snake_turn(
-                    pool,
-                    turn.turn_id,
+        let snake_turns = move_results
+            .iter()
+            .filter_map(|result| {
+                let game_battlesnake_id = Uuid::parse_str(&result.snake_id).ok()?;
+                Some(CreateSnakeTurn {
                     game_battlesnake_id,
-                    &result.direction.to_string(),
-                    result.latency_ms,
-                    result.timed_out,
-                )
+                    direction: result.direction.to_string(),
+                    latency_ms: result.latency_ms,
+                    compensated_latency_ms: result.compensated_latency_ms,
+                    timed_out: result.timed_out,
+                    parse_error: result.parse_error.clone(),
+                })
+            })
+            .collect();
+
+        // Stored with the rest of its batch; a retry carries on from the
+        // checkpoint saved after the last stored one
+        turns
+            .push(
+                game_channels,
+                CreateTurn {
+                    turn_number: engine_game.turn,
+                    frame_data: frame_json,
+                    snake_turns,
+                },
+                Checkpoint::new(
+                    &engine_game,
+                    &last_moves,
+                    &eliminated,
+                    &death_info,
+                    &food,
+                    &start_rtts,
+                ),
+            )
+            .await;
+        if turns.is_due(std::time::Instant::now()) {
+            tracing::debug!(game_id = %game_id, turn = engine_game.turn, "Storing turns");
+            turns
+                .flush(pool, game_channels, app_state.clock.now())
                 .await?;
-            }
         }
 
+        game_log.flush(pool).await?;
+
         let db_write_duration = db_write_start.elapsed();
+        timings.record(Stage::Persist, db_write_duration);
         tracing::info!(
             metric_type = "db_write_latency",
             game_id = %game_id,
@@ -174,6 +588,40 @@ pub async fn run_game(app_state: &AppState, game_id: Uuid) -> cja::Result<()> {
             "turn persistence latency"
         );
 
+        // Tell subscribers and the engine hooks about the turn, stored or not
+        let stage_start = std::time::Instant::now();
+        game_channels
+            .notify(TurnNotification {
+                game_id,
+                turn_number: engine_game.turn,
+            })
+            .await;
+        for elimination in &newly_eliminated {
+            hooks
+                .eliminated(
+                    app_state,
+                    &Eliminated {
+                        game_id,
+                        turn: engine_game.turn,
+                        snake_id: &elimination.snake_id,
+                        cause: elimination.cause,
+                        eliminated_by: &elimination.eliminated_by,
+                    },
+                )
+                .await;
+        }
+        hooks
+            .turn_applied(
+                app_state,
+                &TurnApplied {
+                    game_id,
+                    game: &engine_game,
+                    moves: &move_results,
+                },
+            )
+            .await;
+        timings.record(Stage::Broadcast, stage_start.elapsed());
+
         // Measure async scheduler jitter
         let before_yield = std::time::Instant::now();
         tokio::task::yield_now().await;
@@ -187,8 +635,13 @@ pub async fn run_game(app_state: &AppState, game_id: Uuid) -> cja::Result<()> {
         );
     }
 
-    // Emit processing_overhead metric
-    let total_time = game_start.elapsed();
+    // Store the last batch of turns
+    turns
+        .flush(pool, game_channels, app_state.clock.now())
+        .await?;
+
+    // Emit processing_overhead metric, leaving out time spent paused
+    let total_time = game_start.elapsed().saturating_sub(gate.paused_for());
     let total_time_ms = total_time.as_millis() as i64;
     let overhead_ms = total_time_ms - total_snake_wait_ms;
     tracing::info!(
@@ -202,7 +655,13 @@ pub async fn run_game(app_state: &AppState, game_id: Uuid) -> cja::Result<()> {
 
     // Call /end for all snakes in parallel (fire and forget)
     tracing::info!(game_id = %game_id, "Calling /end for all snakes");
-    request_end_parallel(http_client, &engine_game, &snake_urls, timeout).await;
+    for (snake_id, e) in end_all(&providers, &engine_game).await {
+        game_log.warn(
+            None,
+            Uuid::parse_str(&snake_id).ok(),
+            format!("{}: /end {}", snake_name(&snake_id), e),
+        );
+    }
 
     tracing::info!(
         game_id = %game_id,
@@ -210,25 +669,44 @@ pub async fn run_game(app_state: &AppState, game_id: Uuid) -> cja::Result<()> {
         "Game completed with persistence"
     );
 
-    // Build placements: last eliminated = winner (placement 1)
-    // Snakes still alive at the end go first
-    let mut placements: Vec<String> = engine_game
+    // Don't record results over a rerun that's already started
+    check_lease(lease, game_id)?;
+
+    // Build placements: survivors first, then last eliminated = better placement
+    let survivors = engine_game
         .board
         .snakes
         .iter()
         .filter(|s| s.health > 0)
-        .map(|s| s.id.clone())
-        .collect();
-
-    // Then add eliminated snakes in reverse order (last eliminated = better placement)
-    elimination_order.reverse();
-    placements.extend(elimination_order);
+        .count();
+    let placements = final_placements(
+        &engine_game,
+        &eliminated,
+        &food,
+        settings.turn_limit_tiebreak,
+    );
+    if survivors > 1 {
+        game_log.info(
+            Some(engine_game.turn),
+            None,
+            format!(
+                "Turn limit reached with {} snakes alive, tiebreak: {}",
+                survivors,
+                settings.turn_limit_tiebreak.label()
+            ),
+        );
+        set_game_tiebreak_applied(pool, game_id, settings.turn_limit_tiebreak).await?;
+    } else if is_draw(&placements) {
+        game_log.info(
+            Some(engine_game.turn),
+            None,
+            "The last snakes were eliminated together; the game is a draw".to_string(),
+        );
+    }
 
     // Assign placements to database
     // snake_id is now game_battlesnake_id (unique per game instance)
-    for (i, snake_id) in placements.iter().enumerate() {
-        let placement = (i + 1) as i32;
-
+    for (snake_id, placement) in &placements {
         let game_battlesnake_id: Uuid = snake_id
             .parse()
             .wrap_err_with(|| format!("Invalid game_battlesnake ID: {}", snake_id))?;
@@ -236,7 +714,7 @@ pub async fn run_game(app_state: &AppState, game_id: Uuid) -> cja::Result<()> {
         crate::models::game_battlesnake::set_game_result_by_id(
             pool,
             game_battlesnake_id,
-            placement,
+            *placement,
         )
         .await
         .wrap_err_with(|| {
@@ -247,8 +725,32 @@ pub async fn run_game(app_state: &AppState, game_id: Uuid) -> cja::Result<()> {
         })?;
     }
 
+    game_log.info(
+        Some(engine_game.turn),
+        None,
+        format!("Game finished on turn {}", engine_game.turn),
+    );
+    game_log.flush(pool).await?;
+
+    set_game_result_digest(pool, game_id, &hash_chain.digest()).await?;
+    save_game_stage_timings(pool, game_id, &timings).await?;
+
     // Update status to finished
+    record_final_turn(pool, game_id).await?;
     update_game_status(pool, game_id, GameStatus::Finished).await?;
+    delete_checkpoint(pool, game_id).await?;
+
+    hooks
+        .game_ended(
+            app_state,
+            &GameEnded {
+                game_id,
+                game: &engine_game,
+                snakes: &battlesnakes,
+                placements: &placements,
+            },
+        )
+        .await;
 
     // Clean up game channel (will be removed when no subscribers)
     game_channels.cleanup(game_id).await;
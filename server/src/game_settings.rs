@@ -0,0 +1,202 @@
+//! Bounds on the game settings users can pick
+//!
+//! Board size, turn limit and move timeout all come from whoever creates the
+//! game, so every way of creating one (the API, the game creation flow, batch
+//! creation and the CLI) checks them here with [`validate`]. The engine has
+//! hard limits that always apply, and operators can tighten them with
+//! [`GameLimits::from_env`]. On top of the per-setting ranges, a game's worst
+//! case length, with every turn played and every snake taking its full
+//! timeout, has to fit in `max_game_minutes`, so nobody can queue a game that
+//! ties up a runner for hours.
+//!
+//! The library crate exposes this module so the CLI can turn away settings the
+//! server would refuse before sending them.
+
+use crate::config::env_or;
+use crate::engine::rules::MAX_TURNS;
+
+/// How long a snake has to answer /move, unless a game sets its own timeout
+pub const DEFAULT_MOVE_TIMEOUT_MS: i32 = 500;
+
+/// The range a game's own move timeout has to fall in
+pub const MIN_MOVE_TIMEOUT_MS: i32 = 50;
+pub const MAX_MOVE_TIMEOUT_MS: i32 = 5000;
+
+/// Every board size the engine plays
+pub const ENGINE_BOARD_SIZES: &[&str] = &["7x7", "11x11", "19x19"];
+
+/// Settings a new game may use
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameLimits {
+    pub min_move_timeout_ms: i32,
+    pub max_move_timeout_ms: i32,
+    pub max_turns: i32,
+    /// Longest a game could take if every snake used its full timeout every turn
+    pub max_game_minutes: i64,
+    pub board_sizes: Vec<&'static str>,
+}
+
+impl Default for GameLimits {
+    fn default() -> Self {
+        Self {
+            min_move_timeout_ms: MIN_MOVE_TIMEOUT_MS,
+            max_move_timeout_ms: MAX_MOVE_TIMEOUT_MS,
+            max_turns: MAX_TURNS,
+            max_game_minutes: 120,
+            board_sizes: ENGINE_BOARD_SIZES.to_vec(),
+        }
+    }
+}
+
+impl GameLimits {
+    /// The engine's limits, tightened by any of `ARENA_MIN_MOVE_TIMEOUT_MS`,
+    /// `ARENA_MAX_MOVE_TIMEOUT_MS`, `ARENA_MAX_GAME_TURNS`,
+    /// `ARENA_MAX_GAME_MINUTES` and `ARENA_BOARD_SIZES` (comma separated).
+    /// Values outside the engine's limits are clamped back inside them.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let max_move_timeout_ms = env_or("ARENA_MAX_MOVE_TIMEOUT_MS", defaults.max_move_timeout_ms)
+            .clamp(MIN_MOVE_TIMEOUT_MS, MAX_MOVE_TIMEOUT_MS);
+        let board_sizes: Vec<&'static str> = match std::env::var("ARENA_BOARD_SIZES") {
+            Ok(sizes) => ENGINE_BOARD_SIZES
+                .iter()
+                .copied()
+                .filter(|size| sizes.split(',').any(|s| s.trim() == *size))
+                .collect(),
+            Err(_) => defaults.board_sizes.clone(),
+        };
+
+        let limits = Self {
+            min_move_timeout_ms: env_or("ARENA_MIN_MOVE_TIMEOUT_MS", defaults.min_move_timeout_ms)
+                .clamp(MIN_MOVE_TIMEOUT_MS, max_move_timeout_ms),
+            max_move_timeout_ms,
+            max_turns: env_or("ARENA_MAX_GAME_TURNS", defaults.max_turns).clamp(1, MAX_TURNS),
+            max_game_minutes: env_or("ARENA_MAX_GAME_MINUTES", defaults.max_game_minutes).max(1),
+            // An empty or unrecognised list would refuse every game
+            board_sizes: if board_sizes.is_empty() {
+                defaults.board_sizes
+            } else {
+                board_sizes
+            },
+        };
+
+        tracing::info!(
+            min_move_timeout_ms = limits.min_move_timeout_ms,
+            max_move_timeout_ms = limits.max_move_timeout_ms,
+            max_turns = limits.max_turns,
+            max_game_minutes = limits.max_game_minutes,
+            board_sizes = ?limits.board_sizes,
+            "Game setting limits configured"
+        );
+
+        limits
+    }
+
+    /// The turn limit to store for a game that asked for `requested`. Games
+    /// that don't ask get the configured limit when it's below the engine's.
+    pub fn turn_limit(&self, requested: Option<i32>) -> Option<i32> {
+        match requested {
+            Some(turns) => Some(turns.min(self.max_turns)),
+            None => (self.max_turns < MAX_TURNS).then_some(self.max_turns),
+        }
+    }
+}
+
+/// Check a game's board size, turn limit and move timeout against `limits`.
+/// A `None` board isn't checked; the others fall back to the defaults the
+/// game would be played with.
+pub fn validate(
+    limits: &GameLimits,
+    board: Option<&str>,
+    max_turns: Option<i32>,
+    move_timeout_ms: Option<i32>,
+) -> Result<(), String> {
+    if let Some(board) = board
+        && !limits
+            .board_sizes
+            .iter()
+            .any(|size| size.eq_ignore_ascii_case(board))
+    {
+        return Err(format!(
+            "board must be one of {}",
+            limits.board_sizes.join(", ")
+        ));
+    }
+    if max_turns.is_some_and(|turns| !(1..=limits.max_turns).contains(&turns)) {
+        return Err(format!(
+            "max_turns must be between 1 and {}",
+            limits.max_turns
+        ));
+    }
+    if move_timeout_ms
+        .is_some_and(|ms| !(limits.min_move_timeout_ms..=limits.max_move_timeout_ms).contains(&ms))
+    {
+        return Err(format!(
+            "move_timeout_ms must be between {} and {}",
+            limits.min_move_timeout_ms, limits.max_move_timeout_ms
+        ));
+    }
+
+    let turns = max_turns.unwrap_or(limits.max_turns);
+    let timeout_ms = move_timeout_ms.unwrap_or(DEFAULT_MOVE_TIMEOUT_MS);
+    let worst_case_minutes = i64::from(turns) * i64::from(timeout_ms) / 60_000;
+    if worst_case_minutes > limits.max_game_minutes {
+        return Err(format!(
+            "{} turns with a {}ms move timeout could take {} minutes, over the {} minute limit; \
+             lower max_turns or move_timeout_ms",
+            turns, timeout_ms, worst_case_minutes, limits.max_game_minutes
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_ranges() {
+        let limits = GameLimits::default();
+        assert!(validate(&limits, Some("11x11"), None, None).is_ok());
+        assert!(validate(&limits, None, Some(300), Some(MIN_MOVE_TIMEOUT_MS)).is_ok());
+
+        assert!(validate(&limits, Some("13x13"), None, None).is_err());
+        assert!(validate(&limits, None, Some(0), None).is_err());
+        assert!(validate(&limits, None, Some(MAX_TURNS + 1), None).is_err());
+        assert!(validate(&limits, None, None, Some(5)).is_err());
+        assert!(validate(&limits, None, None, Some(MAX_MOVE_TIMEOUT_MS + 1)).is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_games_that_could_run_for_hours() {
+        let limits = GameLimits::default();
+
+        // 5000 turns at 5 seconds each is nearly 7 hours
+        let err = validate(&limits, None, Some(MAX_TURNS), Some(MAX_MOVE_TIMEOUT_MS)).unwrap_err();
+        assert!(err.contains("416 minutes"), "{}", err);
+        assert!(validate(&limits, None, None, Some(2000)).is_err());
+
+        // The default turn limit and timeout fit, as does a slow snake in a short game
+        assert!(validate(&limits, None, None, None).is_ok());
+        assert!(validate(&limits, None, Some(500), Some(MAX_MOVE_TIMEOUT_MS)).is_ok());
+    }
+
+    #[test]
+    fn test_tightened_limits() {
+        let limits = GameLimits {
+            max_turns: 1000,
+            min_move_timeout_ms: 200,
+            board_sizes: vec!["11x11"],
+            ..GameLimits::default()
+        };
+        assert!(validate(&limits, Some("19x19"), None, None).is_err());
+        assert!(validate(&limits, None, Some(1001), None).is_err());
+        assert!(validate(&limits, None, None, Some(100)).is_err());
+        assert!(validate(&limits, Some("11x11"), Some(1000), Some(200)).is_ok());
+
+        assert_eq!(limits.turn_limit(None), Some(1000));
+        assert_eq!(limits.turn_limit(Some(500)), Some(500));
+        assert_eq!(limits.turn_limit(Some(4000)), Some(1000));
+        assert_eq!(GameLimits::default().turn_limit(None), None);
+    }
+}
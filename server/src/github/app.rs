@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use color_eyre::eyre::{Context as _, eyre};
+use reqwest::header::{ACCEPT, USER_AGENT};
+use ring::signature::{RSA_PKCS1_SHA256, RsaKeyPair};
+use serde::{Deserialize, Serialize};
+
+/// The name regression check results are posted under on commits
+pub const STATUS_CONTEXT: &str = "arena/regression-check";
+
+// Config for the GitHub App that posts check results to snake repositories
+#[derive(Clone)]
+pub struct GitHubAppConfig {
+    pub app_id: String,
+    private_key: Arc<RsaKeyPair>,
+    pub api_url: String,
+}
+
+impl std::fmt::Debug for GitHubAppConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GitHubAppConfig")
+            .field("app_id", &self.app_id)
+            .field("api_url", &self.api_url)
+            .finish_non_exhaustive()
+    }
+}
+
+// The state of a commit status on GitHub
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitState {
+    Pending,
+    Success,
+    Failure,
+}
+
+impl CommitState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommitState::Pending => "pending",
+            CommitState::Success => "success",
+            CommitState::Failure => "failure",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct InstallationTokenResponse {
+    token: String,
+}
+
+/// Parse a PEM private key, PKCS#1 as GitHub generates them or PKCS#8
+fn parse_private_key(pem: &str) -> cja::Result<RsaKeyPair> {
+    // Env vars often carry the key on one line with escaped newlines
+    let pem = pem.replace("\\n", "\n");
+    let body: String = pem
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .map(str::trim)
+        .collect();
+    let der = STANDARD
+        .decode(body)
+        .wrap_err("GitHub App private key isn't valid PEM")?;
+
+    let key = if pem.contains("BEGIN RSA PRIVATE KEY") {
+        RsaKeyPair::from_der(&der)
+    } else {
+        RsaKeyPair::from_pkcs8(&der)
+    };
+    key.map_err(|e| eyre!("GitHub App private key isn't a valid RSA key: {}", e))
+}
+
+impl GitHubAppConfig {
+    /// Needs `GITHUB_APP_ID` and `GITHUB_APP_PRIVATE_KEY` (the PEM contents);
+    /// the API URL is shared with OAuth through `GITHUB_API_URL`
+    pub fn from_env() -> cja::Result<Self> {
+        let app_id = std::env::var("GITHUB_APP_ID").wrap_err("GITHUB_APP_ID must be set")?;
+        let private_key = std::env::var("GITHUB_APP_PRIVATE_KEY")
+            .wrap_err("GITHUB_APP_PRIVATE_KEY must be set")?;
+        let api_url = std::env::var("GITHUB_API_URL")
+            .unwrap_or_else(|_| "https://api.github.com".to_string());
+
+        Ok(Self {
+            app_id,
+            private_key: Arc::new(parse_private_key(&private_key)?),
+            api_url,
+        })
+    }
+
+    /// A short-lived JWT that authenticates as the app itself
+    fn app_jwt(&self, now: chrono::DateTime<chrono::Utc>) -> cja::Result<String> {
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"RS256","typ":"JWT"}"#);
+        // Backdated a minute to allow for clock drift; GitHub caps expiry at ten minutes
+        let claims = URL_SAFE_NO_PAD.encode(
+            serde_json::json!({
+                "iat": now.timestamp() - 60,
+                "exp": now.timestamp() + 9 * 60,
+                "iss": self.app_id,
+            })
+            .to_string(),
+        );
+        let message = format!("{}.{}", header, claims);
+
+        let mut signature = vec![0; self.private_key.public().modulus_len()];
+        self.private_key
+            .sign(
+                &RSA_PKCS1_SHA256,
+                &ring::rand::SystemRandom::new(),
+                message.as_bytes(),
+                &mut signature,
+            )
+            .map_err(|e| eyre!("Failed to sign GitHub App JWT: {}", e))?;
+
+        Ok(format!("{}.{}", message, URL_SAFE_NO_PAD.encode(signature)))
+    }
+
+    /// A token that acts as the app's installation on a user's repositories
+    pub async fn installation_token(
+        &self,
+        installation_id: i64,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> cja::Result<String> {
+        let response = reqwest::Client::new()
+            .post(format!(
+                "{}/app/installations/{}/access_tokens",
+                self.api_url, installation_id
+            ))
+            .bearer_auth(self.app_jwt(now)?)
+            .header(ACCEPT, "application/vnd.github+json")
+            .header(USER_AGENT, "arena-app")
+            .send()
+            .await
+            .wrap_err("Failed to request GitHub installation token")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(eyre!(
+                "GitHub refused an installation token for {}: {} - {}",
+                installation_id,
+                status,
+                body
+            ));
+        }
+
+        Ok(response
+            .json::<InstallationTokenResponse>()
+            .await
+            .wrap_err("Failed to parse GitHub installation token response")?
+            .token)
+    }
+
+    /// Set a commit status on `repo` ("owner/name") with an installation token
+    pub async fn post_commit_status(
+        &self,
+        token: &str,
+        repo: &str,
+        sha: &str,
+        state: CommitState,
+        description: &str,
+        target_url: Option<&str>,
+    ) -> cja::Result<()> {
+        let mut body = serde_json::json!({
+            "state": state,
+            "description": description,
+            "context": STATUS_CONTEXT,
+        });
+        if let Some(target_url) = target_url {
+            body["target_url"] = target_url.into();
+        }
+
+        let response = reqwest::Client::new()
+            .post(format!("{}/repos/{}/statuses/{}", self.api_url, repo, sha))
+            .bearer_auth(token)
+            .header(ACCEPT, "application/vnd.github+json")
+            .header(USER_AGENT, "arena-app")
+            .json(&body)
+            .send()
+            .await
+            .wrap_err("Failed to send commit status to GitHub")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(eyre!(
+                "GitHub refused commit status for {}@{}: {} - {}",
+                repo,
+                sha,
+                status,
+                body
+            ));
+        }
+
+        Ok(())
+    }
+}
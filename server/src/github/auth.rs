@@ -1,6 +1,8 @@
 use color_eyre::eyre::Context as _;
 use serde::Deserialize;
 
+use crate::public_url::PublicUrlConfig;
+
 // Config structure for GitHub OAuth
 #[derive(Clone, Debug)]
 pub struct GitHubOAuthConfig {
@@ -14,13 +16,21 @@ pub struct GitHubOAuthConfig {
 }
 
 impl GitHubOAuthConfig {
-    pub fn from_env() -> cja::Result<Self> {
+    /// `GITHUB_REDIRECT_URI` defaults to the callback route under `BASE_URL`
+    pub fn from_env(public_url: &PublicUrlConfig) -> cja::Result<Self> {
         let client_id =
             std::env::var("GITHUB_CLIENT_ID").wrap_err("GITHUB_CLIENT_ID must be set")?;
         let client_secret =
             std::env::var("GITHUB_CLIENT_SECRET").wrap_err("GITHUB_CLIENT_SECRET must be set")?;
-        let redirect_uri =
-            std::env::var("GITHUB_REDIRECT_URI").wrap_err("GITHUB_REDIRECT_URI must be set")?;
+        let redirect_uri = match std::env::var("GITHUB_REDIRECT_URI") {
+            Ok(uri) => uri,
+            Err(_) => public_url
+                .configured_base_url()
+                .map(|base_url| format!("{}/auth/github/callback", base_url))
+                .ok_or_else(|| {
+                    color_eyre::eyre::eyre!("GITHUB_REDIRECT_URI or BASE_URL must be set")
+                })?,
+        };
 
         // Configurable URLs with defaults pointing to real GitHub
         let oauth_url = std::env::var("GITHUB_OAUTH_URL")
@@ -1,9 +1,69 @@
 use crate::state::AppState;
 
 use cja::jobs::Job;
+use color_eyre::eyre::Context as _;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// Priority for jobs a user is waiting on, like snake sandbox games
+///
+/// `Job::enqueue` always uses priority 0, and the worker picks the highest
+/// priority job first.
+pub const HIGH_PRIORITY: i32 = 10;
+
+/// Priority for jobs nobody is waiting on, like demo games, which run once
+/// the queue is otherwise empty
+pub const LOW_PRIORITY: i32 = -10;
+
+/// Enqueue a job ahead of or behind normal priority jobs
+pub async fn enqueue_with_priority<J>(
+    job: J,
+    app_state: &AppState,
+    context: String,
+    priority: i32,
+) -> cja::Result<()>
+where
+    J: Job<AppState> + Serialize,
+{
+    enqueue_at(&app_state.db, job, context, priority, app_state.clock.now()).await
+}
+
+/// Enqueue a job that won't run before `run_at`
+///
+/// This takes any executor, so a job can be queued in the same transaction as
+/// the rows it works on. Pass the clock's now to run it as soon as possible.
+pub async fn enqueue_at<'e, E, J>(
+    executor: E,
+    job: J,
+    context: String,
+    priority: i32,
+    run_at: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    J: Job<AppState> + Serialize,
+{
+    let payload = serde_json::to_value(&job).wrap_err("Failed to serialize job payload")?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO jobs (job_id, name, payload, priority, run_at, context)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        Uuid::new_v4(),
+        J::NAME,
+        payload,
+        priority,
+        run_at,
+        context
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| format!("Failed to enqueue {} job", J::NAME))?;
+
+    Ok(())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct NoopJob;
 
@@ -26,8 +86,26 @@ impl Job<AppState> for GameRunnerJob {
     const NAME: &'static str = "GameRunnerJob";
 
     async fn run(&self, app_state: AppState) -> cja::Result<()> {
-        // Run the game with HTTP calls to snake APIs, turn-by-turn persistence, and WebSocket notifications
-        crate::game_runner::run_game(&app_state, self.game_id).await?;
+        // Run the game with HTTP calls to snake APIs, turn-by-turn persistence, and WebSocket notifications,
+        // or queue it again if the runner pool or a snake owner's quota is full
+        crate::runner_pool::run_or_defer(&app_state, self.game_id).await?;
+        Ok(())
+    }
+}
+
+/// Job to run a game again after its run failed part way through, carrying on
+/// from its last stored turn when it can (see `game_retry`)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RetryGameJob {
+    pub game_id: Uuid,
+}
+
+#[async_trait::async_trait]
+impl Job<AppState> for RetryGameJob {
+    const NAME: &'static str = "RetryGameJob";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        crate::runner_pool::run_or_defer(&app_state, self.game_id).await?;
         Ok(())
     }
 }
@@ -93,11 +171,247 @@ impl Job<AppState> for HistoricalBackupDiscoveryJob {
     }
 }
 
+/// Job to check every win rate alert against its snake's latest games.
+/// Runs as a cron job every 15 minutes.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SnakeAlertsJob;
+
+#[async_trait::async_trait]
+impl Job<AppState> for SnakeAlertsJob {
+    const NAME: &'static str = "SnakeAlertsJob";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        crate::alerts::evaluate_alerts(&app_state).await?;
+        Ok(())
+    }
+}
+
+/// Job to post the results of regression checks to their commits on GitHub.
+/// Runs as a cron job every minute.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CheckStatusesJob;
+
+#[async_trait::async_trait]
+impl Job<AppState> for CheckStatusesJob {
+    const NAME: &'static str = "CheckStatusesJob";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        crate::checks::report_pending_checks_to_github(&app_state).await?;
+        Ok(())
+    }
+}
+
+/// Job to pull public snakes and results from federation peers.
+/// Runs as a cron job every 15 minutes.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FederationSyncJob;
+
+#[async_trait::async_trait]
+impl Job<AppState> for FederationSyncJob {
+    const NAME: &'static str = "FederationSyncJob";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        crate::federation::sync_peers(&app_state).await?;
+        Ok(())
+    }
+}
+
+/// Job to recalculate stored ratings from every finished game.
+/// Enqueued by `arena admin recalculate-ratings` with the recalculation to
+/// run; as a cron job it runs daily and starts its own.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RatingRecalculationJob {
+    pub recalculation_id: Option<Uuid>,
+}
+
+#[async_trait::async_trait]
+impl Job<AppState> for RatingRecalculationJob {
+    const NAME: &'static str = "RatingRecalculationJob";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        let recalculation_id = match self.recalculation_id {
+            Some(id) => id,
+            None => match crate::models::rating::create_recalculation(
+                &app_state.db,
+                crate::stats::RATING_ALGORITHM_VERSION,
+                app_state.clock.now(),
+            )
+            .await?
+            {
+                Some(recalculation) => recalculation.recalculation_id,
+                None => {
+                    tracing::info!("A rating recalculation is already running, skipping");
+                    return Ok(());
+                }
+            },
+        };
+
+        crate::ratings::run_recalculation(&app_state, recalculation_id).await
+    }
+}
+
+/// Job to archive snake variants that haven't played for a while.
+/// Runs as a cron job every hour.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SnakeVariantArchiveJob;
+
+#[async_trait::async_trait]
+impl Job<AppState> for SnakeVariantArchiveJob {
+    const NAME: &'static str = "SnakeVariantArchiveJob";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        crate::snake_templates::archive_inactive_variants(&app_state).await?;
+        Ok(())
+    }
+}
+
+/// Job to requeue running games whose worker has stopped heartbeating, and to
+/// delete expired game stream tickets. Runs as a cron job every minute.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct StuckGameSweepJob;
+
+#[async_trait::async_trait]
+impl Job<AppState> for StuckGameSweepJob {
+    const NAME: &'static str = "StuckGameSweepJob";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        crate::worker::sweep_stuck_games(&app_state).await?;
+        crate::models::game_stream_ticket::delete_expired_game_stream_tickets(
+            &app_state.db,
+            app_state.clock.now(),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Job to call every snake's GET / and record whether it's healthy.
+/// Runs as a cron job every 10 minutes.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SnakeHealthCheckJob;
+
+#[async_trait::async_trait]
+impl Job<AppState> for SnakeHealthCheckJob {
+    const NAME: &'static str = "SnakeHealthCheckJob";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        crate::snake_health::check_all_snakes(&app_state).await?;
+        Ok(())
+    }
+}
+
+/// Job to upgrade stored frames that are behind the current frame schema version.
+/// Runs as a cron job every hour.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FrameSchemaBackfillJob;
+
+#[async_trait::async_trait]
+impl Job<AppState> for FrameSchemaBackfillJob {
+    const NAME: &'static str = "FrameSchemaBackfillJob";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        crate::frame_schema::backfill_frames(&app_state.db).await?;
+        Ok(())
+    }
+}
+
+/// Job to compress the stored frames of games in the frame compression rollout.
+/// Runs as a cron job every hour.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FrameCompressionBackfillJob;
+
+#[async_trait::async_trait]
+impl Job<AppState> for FrameCompressionBackfillJob {
+    const NAME: &'static str = "FrameCompressionBackfillJob";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        crate::frame_compression::backfill_compression(&app_state.db).await?;
+        Ok(())
+    }
+}
+
+/// Job to draw a finished game's replay GIF.
+/// Enqueued when a game ends, or when its GIF is first asked for.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RenderGameJob {
+    pub game_id: Uuid,
+}
+
+#[async_trait::async_trait]
+impl Job<AppState> for RenderGameJob {
+    const NAME: &'static str = "RenderGameJob";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        crate::replay_gif::render_game(&app_state, self.game_id).await?;
+        Ok(())
+    }
+}
+
+/// Job to rewrite one game's GCS archive in the current archive format.
+/// Enqueued by POST /api/admin/archives/reencode for each outdated archive.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ReencodeArchiveJob {
+    pub engine_game_id: String,
+}
+
+#[async_trait::async_trait]
+impl Job<AppState> for ReencodeArchiveJob {
+    const NAME: &'static str = "ReencodeArchiveJob";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        crate::backup::reencode_archive(&app_state, &self.engine_game_id).await?;
+        Ok(())
+    }
+}
+
+/// Job to archive finished games' turns to storage and prune them.
+/// Runs hourly from cron; does nothing without storage configured.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TurnArchiveJob;
+
+#[async_trait::async_trait]
+impl Job<AppState> for TurnArchiveJob {
+    const NAME: &'static str = "TurnArchiveJob";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        crate::turn_archive::archive_finished_games(&app_state).await?;
+        Ok(())
+    }
+}
+
+/// Job to write yesterday's game outcomes to storage as Parquet.
+/// Runs daily from cron; does nothing without storage configured.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct OutcomeExportJob;
+
+#[async_trait::async_trait]
+impl Job<AppState> for OutcomeExportJob {
+    const NAME: &'static str = "OutcomeExportJob";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        crate::outcome_export::export_yesterdays_outcomes(&app_state).await
+    }
+}
+
 cja::impl_job_registry!(
     AppState,
     NoopJob,
     GameRunnerJob,
+    RetryGameJob,
     GameBackupJob,
     BackupSingleGameJob,
-    HistoricalBackupDiscoveryJob
+    HistoricalBackupDiscoveryJob,
+    SnakeAlertsJob,
+    CheckStatusesJob,
+    FederationSyncJob,
+    RatingRecalculationJob,
+    SnakeVariantArchiveJob,
+    StuckGameSweepJob,
+    SnakeHealthCheckJob,
+    FrameSchemaBackfillJob,
+    FrameCompressionBackfillJob,
+    RenderGameJob,
+    ReencodeArchiveJob,
+    TurnArchiveJob,
+    OutcomeExportJob
 );
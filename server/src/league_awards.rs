@@ -0,0 +1,375 @@
+//! League prizes and winner announcements
+//!
+//! While a league runs, its owner can attach prizes for finishing places and
+//! a write-up. Completing the league freezes both and generates its
+//! announcement: the final standings with who won each prize, a summary of
+//! every game and a few highlights. Leagues don't have brackets or rounds, so
+//! each game stands in for a round. The announcement is stored rather than
+//! recomputed, so later rating changes don't rewrite it. Every member is
+//! notified, with a link to the league's announcement page.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::league::{self, League, LeagueGamePlacement, LeaguePrize};
+use crate::notifications;
+use crate::routes::leaderboard::{LeaderboardRow, LeaderboardScope, leaderboard_rows};
+use crate::state::AppState;
+
+/// Most prizes a league can have
+pub const MAX_PRIZES: usize = 10;
+
+/// Longest prize description, in characters
+pub const MAX_PRIZE_LEN: usize = 200;
+
+/// Longest write-up, in characters
+pub const MAX_WRITEUP_LEN: usize = 10_000;
+
+/// A snake's final place in a league
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Standing {
+    pub rank: usize,
+    pub snake_id: Uuid,
+    pub name: String,
+    pub rating: f64,
+    pub games: usize,
+    pub wins: usize,
+    /// The prize for this place, if there is one
+    pub prize: Option<String>,
+}
+
+/// One of a league's games, as the announcement summarizes it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameSummary {
+    pub game_id: Uuid,
+    pub played_at: chrono::DateTime<chrono::Utc>,
+    /// Snakes that finished first; more than one when they went out together
+    pub winners: Vec<String>,
+    pub snakes: usize,
+    pub turns: Option<i32>,
+}
+
+/// What's announced when a league is completed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeagueAnnouncement {
+    pub league_name: String,
+    pub completed_at: chrono::DateTime<chrono::Utc>,
+    pub writeup: Option<String>,
+    pub standings: Vec<Standing>,
+    /// Every finished game, oldest first
+    pub games: Vec<GameSummary>,
+    pub highlights: Vec<String>,
+}
+
+/// Check and tidy prizes and a write-up: descriptions are trimmed, prizes
+/// sorted by place, and a blank write-up dropped
+pub fn normalize_awards(
+    mut prizes: Vec<LeaguePrize>,
+    writeup: Option<String>,
+) -> Result<(Vec<LeaguePrize>, Option<String>), String> {
+    if prizes.len() > MAX_PRIZES {
+        return Err(format!("A league can have at most {} prizes", MAX_PRIZES));
+    }
+    for prize in &mut prizes {
+        if prize.place < 1 {
+            return Err("Prize places start at 1".to_string());
+        }
+        prize.description = prize.description.trim().to_string();
+        if prize.description.is_empty() || prize.description.chars().count() > MAX_PRIZE_LEN {
+            return Err(format!(
+                "Prize descriptions must be 1 to {} characters",
+                MAX_PRIZE_LEN
+            ));
+        }
+    }
+    prizes.sort_by_key(|prize| prize.place);
+    if prizes.windows(2).any(|pair| pair[0].place == pair[1].place) {
+        return Err("Each place can only have one prize".to_string());
+    }
+
+    let writeup = writeup
+        .map(|w| w.trim().to_string())
+        .filter(|w| !w.is_empty());
+    if writeup
+        .as_ref()
+        .is_some_and(|w| w.chars().count() > MAX_WRITEUP_LEN)
+    {
+        return Err(format!(
+            "The write-up can be at most {} characters",
+            MAX_WRITEUP_LEN
+        ));
+    }
+
+    Ok((prizes, writeup))
+}
+
+/// Build a league's announcement from its leaderboard, best first, and the
+/// placements in its finished games, oldest game first
+pub fn build_announcement(
+    league_name: &str,
+    completed_at: chrono::DateTime<chrono::Utc>,
+    prizes: &[LeaguePrize],
+    writeup: Option<String>,
+    rows: &[LeaderboardRow],
+    placements: &[LeagueGamePlacement],
+) -> LeagueAnnouncement {
+    let prize_for: HashMap<usize, &str> = prizes
+        .iter()
+        .filter_map(|p| Some((usize::try_from(p.place).ok()?, p.description.as_str())))
+        .collect();
+    let standings: Vec<Standing> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| Standing {
+            rank: i + 1,
+            snake_id: row.battlesnake_id,
+            name: row.name.clone(),
+            rating: row.rating.rating,
+            games: row.rating.games,
+            wins: row.rating.wins,
+            prize: prize_for.get(&(i + 1)).map(|p| p.to_string()),
+        })
+        .collect();
+
+    let mut games: Vec<GameSummary> = Vec::new();
+    for placement in placements {
+        if games.last().is_none_or(|g| g.game_id != placement.game_id) {
+            games.push(GameSummary {
+                game_id: placement.game_id,
+                played_at: placement.played_at,
+                winners: Vec::new(),
+                snakes: 0,
+                turns: placement.turns,
+            });
+        }
+        let game = games.last_mut().expect("just pushed");
+        game.snakes += 1;
+        if placement.placement == 1 {
+            game.winners.push(placement.name.clone());
+        }
+    }
+
+    let highlights = highlights(&standings, &games);
+    LeagueAnnouncement {
+        league_name: league_name.to_string(),
+        completed_at,
+        writeup,
+        standings,
+        games,
+        highlights,
+    }
+}
+
+fn highlights(standings: &[Standing], games: &[GameSummary]) -> Vec<String> {
+    let mut highlights = Vec::new();
+    if let Some(champion) = standings.first() {
+        let mut line = format!(
+            "{} wins the league with a rating of {:.0}",
+            champion.name, champion.rating
+        );
+        if let Some(prize) = &champion.prize {
+            line.push_str(&format!(" and takes {}", prize));
+        }
+        highlights.push(line);
+    }
+    if let Some(most_wins) = standings
+        .iter()
+        .filter(|s| s.wins > 0)
+        .max_by_key(|s| (s.wins, std::cmp::Reverse(s.rank)))
+    {
+        highlights.push(format!(
+            "{} won the most games: {} of {}",
+            most_wins.name, most_wins.wins, most_wins.games
+        ));
+    }
+
+    let timed = || games.iter().filter_map(|g| Some((g, g.turns?)));
+    let longest = timed().max_by_key(|(_, turns)| *turns);
+    // The shortest game, unless it's also the longest
+    let shortest = timed()
+        .min_by_key(|(_, turns)| *turns)
+        .filter(|(short, _)| longest.is_none_or(|(long, _)| long.game_id != short.game_id));
+    if let Some((game, turns)) = longest {
+        highlights.push(format!(
+            "The longest game went {} turns, won by {}",
+            turns,
+            winners(game)
+        ));
+    }
+    if let Some((game, turns)) = shortest {
+        highlights.push(format!(
+            "The shortest game was over in {} turns, won by {}",
+            turns,
+            winners(game)
+        ));
+    }
+
+    if games.is_empty() {
+        highlights.push("No games were finished in this league".to_string());
+    }
+    highlights
+}
+
+fn winners(game: &GameSummary) -> String {
+    if game.winners.is_empty() {
+        "nobody".to_string()
+    } else {
+        game.winners.join(" and ")
+    }
+}
+
+/// Complete a league, store its announcement and notify its members. Returns
+/// None if it was already completed.
+pub async fn complete_league(
+    app_state: &AppState,
+    league: &League,
+) -> cja::Result<Option<LeagueAnnouncement>> {
+    let pool = &app_state.db;
+    let awards = league::get_league_awards(pool, league.league_id).await?;
+    if awards.completed_at.is_some() {
+        return Ok(None);
+    }
+
+    let rows = leaderboard_rows(pool, &LeaderboardScope::League(league.clone())).await?;
+    let placements = league::get_league_game_placements(pool, league.league_id).await?;
+    let announcement = build_announcement(
+        &league.name,
+        app_state.clock.now(),
+        &awards.prizes,
+        awards.writeup,
+        &rows,
+        &placements,
+    );
+
+    if !league::complete_league(pool, league.league_id, &announcement).await? {
+        return Ok(None);
+    }
+
+    // The league is completed either way, so failing to notify is only logged
+    let members = league::get_league_members(pool, league.league_id).await?;
+    let notifications = notifications::league_completed(league.league_id, &members, &announcement);
+    if let Err(e) = notifications::notify(app_state, notifications).await {
+        tracing::error!(
+            league_id = %league.league_id,
+            "Failed to notify league members: {:#}",
+            e
+        );
+    }
+    crate::domain_events::record(
+        app_state,
+        None,
+        crate::domain_events::DomainEvent::TournamentAdvanced {
+            league_id: league.league_id,
+            completed: true,
+        },
+    )
+    .await;
+    Ok(Some(announcement))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::SnakeRating;
+
+    fn prize(place: i32, description: &str) -> LeaguePrize {
+        LeaguePrize {
+            place,
+            description: description.to_string(),
+        }
+    }
+
+    fn row(name: &str, rating: f64, games: usize, wins: usize) -> LeaderboardRow {
+        LeaderboardRow {
+            battlesnake_id: Uuid::new_v4(),
+            name: name.to_string(),
+            rating: SnakeRating {
+                rating,
+                games,
+                wins,
+                rated_games: games,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn placement(game: u128, turns: i32, name: &str, place: i32) -> LeagueGamePlacement {
+        LeagueGamePlacement {
+            game_id: Uuid::from_u128(game),
+            played_at: chrono::DateTime::UNIX_EPOCH,
+            battlesnake_id: Uuid::nil(),
+            name: name.to_string(),
+            placement: place,
+            turns: Some(turns),
+        }
+    }
+
+    #[test]
+    fn test_normalize_awards() {
+        let (prizes, writeup) = normalize_awards(
+            vec![prize(2, " Stickers "), prize(1, "A trophy")],
+            Some("   ".to_string()),
+        )
+        .unwrap();
+        assert_eq!(prizes, vec![prize(1, "A trophy"), prize(2, "Stickers")]);
+        assert_eq!(writeup, None);
+
+        assert!(normalize_awards(vec![prize(0, "Nothing")], None).is_err());
+        assert!(normalize_awards(vec![prize(1, " ")], None).is_err());
+        assert!(normalize_awards(vec![prize(1, "A"), prize(1, "B")], None).is_err());
+        assert!(normalize_awards(vec![], Some("x".repeat(MAX_WRITEUP_LEN + 1))).is_err());
+    }
+
+    #[test]
+    fn test_announcement_awards_prizes_and_summarizes_games() {
+        let rows = [row("Alpha", 1540.4, 2, 2), row("Beta", 1460.0, 2, 0)];
+        let placements = [
+            placement(1, 40, "Alpha", 1),
+            placement(1, 40, "Beta", 2),
+            placement(2, 120, "Alpha", 1),
+            placement(2, 120, "Beta", 2),
+        ];
+        let announcement = build_announcement(
+            "Class League",
+            chrono::DateTime::UNIX_EPOCH,
+            &[prize(1, "A trophy"), prize(3, "Stickers")],
+            Some("Thanks for playing".to_string()),
+            &rows,
+            &placements,
+        );
+
+        assert_eq!(announcement.standings[0].prize.as_deref(), Some("A trophy"));
+        assert_eq!(announcement.standings[1].prize, None);
+        assert_eq!(announcement.games.len(), 2);
+        assert_eq!(announcement.games[1].winners, vec!["Alpha".to_string()]);
+        assert_eq!(announcement.games[1].snakes, 2);
+        assert_eq!(
+            announcement.highlights,
+            vec![
+                "Alpha wins the league with a rating of 1540 and takes A trophy",
+                "Alpha won the most games: 2 of 2",
+                "The longest game went 120 turns, won by Alpha",
+                "The shortest game was over in 40 turns, won by Alpha",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_announcement_without_games() {
+        let announcement = build_announcement(
+            "Empty League",
+            chrono::DateTime::UNIX_EPOCH,
+            &[],
+            None,
+            &[],
+            &[],
+        );
+        assert!(announcement.standings.is_empty());
+        assert_eq!(
+            announcement.highlights,
+            vec!["No games were finished in this league"]
+        );
+    }
+}
@@ -1,5 +1,14 @@
 //! Arena library crate
 //!
-//! This exposes modules needed by the CLI binary.
+//! This exposes modules needed by the CLI binary, and the engine's turn rules
+//! for the fuzz targets in `fuzz/`.
 
+pub mod certification;
 pub mod cli;
+pub mod config;
+pub mod game_settings;
+
+pub mod engine {
+    pub mod fuzz;
+    pub mod rules;
+}
@@ -0,0 +1,370 @@
+//! Load shedding for non-essential endpoints
+//!
+//! Every few seconds a sampler times a trivial query against the database,
+//! including the wait for a pooled connection, and counts the jobs that are due
+//! but no worker has picked up. While either is over its threshold the arena
+//! sheds load: endpoints that are nice to have rather than needed (leaderboards
+//! and other stats, snake search, game exports and diffs, the TV channel)
+//! answer 503 with a `Retry-After`, so they stop competing for connections with
+//! game creation and the runner saving turns. Everything else is served as
+//! normal, and shedding stops at the first healthy sample.
+
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{StatusCode, header::RETRY_AFTER},
+    middleware::Next,
+    response::{IntoResponse as _, Response},
+};
+use color_eyre::eyre::{Context as _, eyre};
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::config::env_or;
+use crate::state::AppState;
+
+/// Routes shed under pressure, matched against the end of the route's path so
+/// they cover the versioned and unversioned API alike
+const SHEDDABLE_ROUTES: &[&str] = &[
+    "/leaderboard",
+    "/leaderboard/federated",
+    "/matchups",
+    "/games/predict",
+    "/games/diff",
+    "/games/flow/{id}/search",
+    "/games/{id}/frames.ndjson",
+    "/games/{id}/notation",
+    "/games/{id}/export",
+    "/archive/games/{engine_game_id}",
+    "/me/games.csv",
+    "/tv",
+];
+
+/// Thresholds for shedding load
+#[derive(Debug, Clone)]
+pub struct LoadSheddingConfig {
+    /// Shed while a query takes at least this long, connection wait included
+    pub max_db_latency: Duration,
+    /// Shed while at least this many due jobs are waiting for a worker
+    pub max_job_backlog: i64,
+    /// How often the database is sampled, and how long shed clients are told to wait
+    pub sample_interval: Duration,
+}
+
+impl Default for LoadSheddingConfig {
+    fn default() -> Self {
+        Self {
+            max_db_latency: Duration::from_millis(250),
+            max_job_backlog: 500,
+            sample_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+impl LoadSheddingConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let config = Self {
+            max_db_latency: Duration::from_millis(env_or(
+                "ARENA_SHED_DB_LATENCY_MS",
+                defaults.max_db_latency.as_millis() as u64,
+            )),
+            max_job_backlog: env_or("ARENA_SHED_JOB_BACKLOG", defaults.max_job_backlog),
+            sample_interval: Duration::from_secs(
+                env_or(
+                    "ARENA_SHED_SAMPLE_INTERVAL_SECS",
+                    defaults.sample_interval.as_secs(),
+                )
+                .max(1),
+            ),
+        };
+
+        tracing::info!(
+            max_db_latency_ms = config.max_db_latency.as_millis() as u64,
+            max_job_backlog = config.max_job_backlog,
+            sample_interval_secs = config.sample_interval.as_secs(),
+            "Load shedding configured"
+        );
+
+        config
+    }
+
+    /// Why `sample` calls for shedding load, empty if it doesn't
+    fn pressure(&self, sample: &HealthSample) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if sample.db_latency_ms >= self.max_db_latency.as_millis() as u64 {
+            reasons.push(format!(
+                "database latency {}ms (limit {}ms)",
+                sample.db_latency_ms,
+                self.max_db_latency.as_millis()
+            ));
+        }
+        if sample.job_backlog >= self.max_job_backlog {
+            reasons.push(format!(
+                "{} jobs waiting (limit {})",
+                sample.job_backlog, self.max_job_backlog
+            ));
+        }
+        reasons
+    }
+}
+
+/// One reading of the database's health
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HealthSample {
+    /// How long `SELECT 1` took, waiting for a connection included
+    pub db_latency_ms: u64,
+    /// Connections the pool has open, and how many of those are idle
+    pub pool_size: u32,
+    pub pool_idle: usize,
+    /// Jobs due to run that no worker has locked
+    pub job_backlog: i64,
+}
+
+/// Response format for GET /api/admin/load
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoadStatus {
+    pub shedding: bool,
+    /// What's over its threshold while shedding
+    pub reasons: Vec<String>,
+    pub shedding_since: Option<chrono::DateTime<chrono::Utc>>,
+    /// The latest sample, None until the first one is taken or if it failed
+    pub sample: Option<HealthSample>,
+    pub sampled_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Whether this instance is shedding load, from the latest health sample
+#[derive(Clone)]
+pub struct LoadShedder {
+    inner: Arc<LoadShedderInner>,
+}
+
+struct LoadShedderInner {
+    config: LoadSheddingConfig,
+    shedding: AtomicBool,
+    status: Mutex<LoadStatus>,
+}
+
+impl LoadShedder {
+    pub fn new(config: LoadSheddingConfig) -> Self {
+        Self {
+            inner: Arc::new(LoadShedderInner {
+                config,
+                shedding: AtomicBool::new(false),
+                status: Mutex::new(LoadStatus {
+                    shedding: false,
+                    reasons: Vec::new(),
+                    shedding_since: None,
+                    sample: None,
+                    sampled_at: None,
+                }),
+            }),
+        }
+    }
+
+    pub fn config(&self) -> &LoadSheddingConfig {
+        &self.inner.config
+    }
+
+    pub fn is_shedding(&self) -> bool {
+        self.inner.shedding.load(Ordering::Relaxed)
+    }
+
+    pub fn status(&self) -> LoadStatus {
+        self.inner
+            .status
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Take in a sample, or the error from trying to take one, which sheds
+    /// load too since a database that can't answer is under pressure
+    pub fn record(&self, sample: Result<HealthSample, String>, now: chrono::DateTime<chrono::Utc>) {
+        let reasons = match &sample {
+            Ok(sample) => self.inner.config.pressure(sample),
+            Err(e) => vec![format!("health sample failed: {}", e)],
+        };
+        let shedding = !reasons.is_empty();
+
+        let mut status = self.inner.status.lock().unwrap_or_else(|e| e.into_inner());
+        if shedding && !status.shedding {
+            tracing::warn!(reasons = ?reasons, "Shedding load from non-essential endpoints");
+        } else if !shedding && status.shedding {
+            tracing::info!("No longer shedding load");
+        }
+        *status = LoadStatus {
+            shedding,
+            shedding_since: if shedding {
+                status.shedding_since.or(Some(now))
+            } else {
+                None
+            },
+            reasons,
+            sample: sample.ok(),
+            sampled_at: Some(now),
+        };
+        self.inner.shedding.store(shedding, Ordering::Relaxed);
+    }
+}
+
+/// Whether the route at `path` (a route template, e.g. `/api/games/{id}/notation`)
+/// is shed under pressure
+pub fn is_sheddable(path: &str) -> bool {
+    SHEDDABLE_ROUTES.iter().any(|route| path.ends_with(route))
+}
+
+/// Time `SELECT 1` and count the job backlog
+pub async fn sample_health(
+    pool: &PgPool,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<HealthSample> {
+    let started = Instant::now();
+    sqlx::query_scalar!(r#"SELECT 1 AS "one!""#)
+        .fetch_one(pool)
+        .await
+        .wrap_err("Failed to time a query")?;
+    let db_latency_ms = started.elapsed().as_millis() as u64;
+
+    let job_backlog = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM jobs
+        WHERE locked_at IS NULL AND run_at <= $1
+        "#,
+        now
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err("Failed to count waiting jobs")?;
+
+    Ok(HealthSample {
+        db_latency_ms,
+        pool_size: pool.size(),
+        pool_idle: pool.num_idle(),
+        job_backlog,
+    })
+}
+
+/// Sample the database's health forever, updating the app's [`LoadShedder`]
+///
+/// A sample that takes longer than the sample interval counts as failed.
+pub async fn run_sampler(app_state: AppState) -> cja::Result<()> {
+    let shedder = app_state.load_shedder.clone();
+    let interval = shedder.config().sample_interval;
+
+    loop {
+        let sample = tokio::time::timeout(
+            interval,
+            sample_health(&app_state.db, app_state.clock.now()),
+        )
+        .await
+        .unwrap_or_else(|_| Err(eyre!("timed out after {}s", interval.as_secs())))
+        .map_err(|e| format!("{:#}", e));
+        shedder.record(sample, app_state.clock.now());
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Answer sheddable routes with 503 while the arena is under pressure
+pub async fn shed_load(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let sheddable = request
+        .extensions()
+        .get::<MatchedPath>()
+        .is_some_and(|path| is_sheddable(path.as_str()));
+    if sheddable && state.load_shedder.is_shedding() {
+        let retry_after = state.load_shedder.config().sample_interval.as_secs();
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(RETRY_AFTER, retry_after.to_string())],
+            "The arena is under heavy load, so this page is temporarily unavailable. Games are still being created and played; try again shortly.",
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(db_latency_ms: u64, job_backlog: i64) -> HealthSample {
+        HealthSample {
+            db_latency_ms,
+            pool_size: 5,
+            pool_idle: 0,
+            job_backlog,
+        }
+    }
+
+    #[test]
+    fn test_sheddable_routes() {
+        for path in [
+            "/leaderboard",
+            "/api/v1/leaderboard",
+            "/api/matchups",
+            "/api/v1/games/{id}/notation",
+            "/games/flow/{id}/search",
+            "/arena/tv",
+        ] {
+            assert!(is_sheddable(path), "{path} should be sheddable");
+        }
+        for path in [
+            "/api/v1/games",
+            "/api/games/batch",
+            "/games/flow/{id}/create",
+            "/api/v1/games/{id}/events",
+            "/games/{id}",
+            "/api/admin/load",
+        ] {
+            assert!(!is_sheddable(path), "{path} should never be shed");
+        }
+    }
+
+    #[test]
+    fn test_pressure_reasons() {
+        let config = LoadSheddingConfig::default();
+        assert!(config.pressure(&sample(10, 3)).is_empty());
+        assert_eq!(
+            config.pressure(&sample(300, 3)),
+            vec!["database latency 300ms (limit 250ms)".to_string()]
+        );
+        assert_eq!(
+            config.pressure(&sample(10, 500)),
+            vec!["500 jobs waiting (limit 500)".to_string()]
+        );
+        assert_eq!(config.pressure(&sample(250, 900)).len(), 2);
+    }
+
+    #[test]
+    fn test_shedding_follows_samples() {
+        let shedder = LoadShedder::new(LoadSheddingConfig::default());
+        let start = chrono::Utc::now();
+        assert!(!shedder.is_shedding());
+
+        shedder.record(Ok(sample(400, 0)), start);
+        assert!(shedder.is_shedding());
+        let later = start + chrono::Duration::seconds(5);
+        shedder.record(Err("connection refused".to_string()), later);
+        let status = shedder.status();
+        assert!(status.shedding);
+        assert_eq!(status.shedding_since, Some(start));
+        assert_eq!(
+            status.reasons,
+            vec!["health sample failed: connection refused".to_string()]
+        );
+        assert_eq!(status.sample, None);
+
+        shedder.record(Ok(sample(5, 0)), later);
+        let status = shedder.status();
+        assert!(!shedder.is_shedding());
+        assert_eq!(status.shedding_since, None);
+        assert_eq!(status.sample, Some(sample(5, 0)));
+    }
+}
@@ -5,35 +5,214 @@ use cja::{
     server::run_server,
     setup::{setup_sentry, setup_tracing},
 };
+use clap::{Parser, Subcommand};
 use color_eyre::eyre::eyre;
 use state::AppState;
-use tokio_util::sync::CancellationToken;
 use tracing::info;
 
+mod alerts;
+mod api_version;
+mod auth_lockout;
+mod backfill;
 mod backup;
+mod board_description;
+mod branch;
+mod certification;
+mod checks;
+mod clock;
+mod config;
 mod cron;
+mod demo;
+mod diagnose;
+mod domain_events;
 mod engine;
 mod engine_models;
 mod errors;
+mod federation;
 mod flasher;
+mod frame_compression;
+mod frame_delta;
+mod frame_schema;
+mod frame_validation;
 mod game_channels;
+mod game_control;
+mod game_diff;
+mod game_export;
+mod game_log;
+mod game_retry;
 mod game_runner;
+mod game_settings;
 mod github;
 mod jobs;
+mod league_awards;
+mod load_shedding;
+mod markdown;
+mod mirror;
 mod models;
+mod notation;
+mod notifications;
+mod outcome_export;
+mod parquet;
+mod public_url;
+mod queue;
+mod ratings;
+mod replay_fixture;
+mod replay_gif;
+mod reports;
 mod routes;
+mod runner_pool;
+mod sandbox;
+mod seed;
+mod simulation;
 mod snake_client;
+mod snake_health;
+mod snake_http;
+mod snake_templates;
 mod state;
 mod static_assets;
+mod stats;
+mod storage;
+mod tarball;
+mod turn_archive;
+mod turn_batch;
+mod turn_metrics;
+mod worker;
 
 /// Frontend UI components only - do not place backend logic here
 mod components {
+    pub mod announcement_banner;
     pub mod flash;
+    pub mod notification_bell;
     pub mod page;
     pub mod page_factory;
 }
 
+/// Arena server
+///
+/// With no subcommand this runs the server, job worker and cron (see the `*_DISABLED` env vars).
+#[derive(Parser)]
+#[command(name = "arena")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Administrative tasks
+    Admin {
+        #[command(subcommand)]
+        command: AdminCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum AdminCommand {
+    /// Populate a local database with development fixtures
+    Seed {
+        /// Base URL of the mock snakes server the seeded snakes point at
+        #[arg(long, default_value = "http://localhost:8082")]
+        mock_snakes_url: String,
+
+        /// Number of finished games to create
+        #[arg(long, default_value_t = 10)]
+        games: usize,
+
+        /// Seed even if DATABASE_URL isn't a local database
+        #[arg(long)]
+        force: bool,
+    },
+    /// Check games' stored frames against the board viewer frame schema
+    ValidateFrames {
+        /// Games to check
+        #[arg(required = true)]
+        game_ids: Vec<uuid::Uuid>,
+    },
+    /// Record a finished game as a replay fixture
+    RecordFixture {
+        /// Game to record
+        game_id: uuid::Uuid,
+
+        /// File to write the fixture to
+        #[arg(long)]
+        out: std::path::PathBuf,
+    },
+    /// Replay fixtures through the engine and check they reach their recorded outcome
+    ReplayFixtures {
+        /// Fixture files, or directories of them
+        #[arg(required = true)]
+        paths: Vec<std::path::PathBuf>,
+    },
+    /// Generate a signing key for joining a federation of arenas
+    FederationKey,
+    /// Recalculate stored ratings from every finished game, in the background
+    RecalculateRatings {
+        /// Show progress until the recalculation finishes
+        #[arg(long)]
+        wait: bool,
+    },
+    /// Archive every finished Engine game played in a date range, a batch at a
+    /// time, showing progress. Safe to stop; run it again to resume
+    Backfill {
+        /// First day of games to archive (YYYY-MM-DD)
+        #[arg(long)]
+        from_date: chrono::NaiveDate,
+
+        /// Last day of games to archive, inclusive (YYYY-MM-DD)
+        #[arg(long)]
+        to_date: chrono::NaiveDate,
+    },
+    /// Rebuild tables derived from the domain event log by replaying it
+    RebuildProjections {
+        /// Only rebuild this projection
+        #[arg(long)]
+        projection: Option<String>,
+    },
+    /// Show each game runner worker's load and the games being run now
+    Workers,
+    /// Run games between bot snakes in this process and report throughput ceilings
+    Simulate {
+        /// Number of games to create
+        #[arg(long, default_value_t = 100)]
+        games: usize,
+
+        /// Games to create per second; all at once if not set
+        #[arg(long)]
+        rate: Option<f64>,
+
+        /// Bot snakes in each game
+        #[arg(long, default_value_t = 4)]
+        snakes_per_game: usize,
+
+        /// Board size for games
+        #[arg(long, default_value = "11x11")]
+        board: String,
+
+        /// Game type
+        #[arg(long = "type", default_value = "standard")]
+        game_type: String,
+
+        /// Turn limit for each game
+        #[arg(long)]
+        max_turns: Option<i32>,
+
+        /// Job workers to run, like running that many instances
+        #[arg(long, default_value_t = 1)]
+        workers: usize,
+
+        /// Seconds to wait for the games to finish
+        #[arg(long, default_value_t = 600)]
+        timeout_secs: u64,
+
+        /// Simulate even if DATABASE_URL isn't a local database
+        #[arg(long)]
+        force: bool,
+    },
+}
+
 fn main() -> color_eyre::Result<()> {
+    let cli = Cli::parse();
+
     // Initialize Sentry for error tracking
     let _sentry_guard = setup_sentry();
 
@@ -55,7 +234,91 @@ fn main() -> color_eyre::Result<()> {
         .worker_threads(worker_threads)
         .enable_all()
         .build()?
-        .block_on(async { run_application().await })
+        .block_on(async {
+            match cli.command {
+                None => run_application().await,
+                Some(Command::Admin { command }) => run_admin_command(command).await,
+            }
+        })
+}
+
+/// Run a one-off admin command against the configured database and exit
+async fn run_admin_command(command: AdminCommand) -> cja::Result<()> {
+    let _eyes_shutdown_handle = setup_tracing("arent")?;
+
+    // Replaying runs entirely in memory and key generation needs nothing, so
+    // both work without a database
+    if let AdminCommand::ReplayFixtures { paths } = &command {
+        return replay_fixture::run_replay(paths).await;
+    }
+    if matches!(command, AdminCommand::FederationKey) {
+        return federation::print_new_key();
+    }
+
+    let app_state = AppState::from_env().await?;
+
+    match command {
+        AdminCommand::Seed {
+            mock_snakes_url,
+            games,
+            force,
+        } => {
+            seed::run(
+                &app_state,
+                seed::SeedOptions {
+                    mock_snakes_url,
+                    games,
+                    force,
+                },
+            )
+            .await
+        }
+        AdminCommand::ValidateFrames { game_ids } => {
+            frame_validation::run(&app_state, &game_ids).await
+        }
+        AdminCommand::RecordFixture { game_id, out } => {
+            replay_fixture::run_record(&app_state, game_id, &out).await
+        }
+        AdminCommand::ReplayFixtures { paths } => replay_fixture::run_replay(&paths).await,
+        AdminCommand::FederationKey => federation::print_new_key(),
+        AdminCommand::RecalculateRatings { wait } => {
+            ratings::run_recalculate_command(&app_state, wait).await
+        }
+        AdminCommand::Backfill { from_date, to_date } => {
+            backfill::run_backfill_command(&app_state, from_date, to_date).await
+        }
+        AdminCommand::RebuildProjections { projection } => {
+            domain_events::run_rebuild_command(&app_state, projection.as_deref()).await
+        }
+        AdminCommand::Workers => worker::run_workers_command(&app_state).await,
+        AdminCommand::Simulate {
+            games,
+            rate,
+            snakes_per_game,
+            board,
+            game_type,
+            max_turns,
+            workers,
+            timeout_secs,
+            force,
+        } => {
+            simulation::run(
+                &app_state,
+                simulation::SimulationOptions {
+                    games,
+                    rate,
+                    snakes_per_game,
+                    board,
+                    game_type,
+                    max_turns,
+                    workers,
+                    timeout: std::time::Duration::from_secs(timeout_secs),
+                    force,
+                },
+            )
+            .await
+        }
+    }
 }
 
 async fn run_application() -> cja::Result<()> {
@@ -139,6 +402,10 @@ async fn spawn_application_tasks(app_state: AppState) -> cja::Result<Vec<NamedTa
             "server",
             run_server(routes::routes(app_state.clone())),
         ));
+        tasks.push(NamedTask::spawn(
+            "load-shedding",
+            load_shedding::run_sampler(app_state.clone()),
+        ));
     } else {
         info!("Server Disabled");
     }
@@ -167,17 +434,28 @@ async fn spawn_application_tasks(app_state: AppState) -> cja::Result<Vec<NamedTa
             .unwrap_or(DEFAULT_MAX_RETRIES);
         info!("Job max retries: {}", job_max_retries);
 
-        tasks.push(NamedTask::spawn(
-            "jobs",
-            cja::jobs::worker::job_worker(
-                app_state.clone(),
-                jobs::Jobs,
-                std::time::Duration::from_millis(job_poll_interval_ms),
-                job_max_retries,
-                CancellationToken::new(),
-                std::time::Duration::from_secs(job_lock_timeout_secs),
-            ),
-        ));
+        // Draining cancels the worker's token; it finishes its current job and
+        // returns, and the task then waits for the process to be stopped
+        let drain = app_state.drain.clone();
+        let job_worker = cja::jobs::worker::job_worker(
+            app_state.clone(),
+            jobs::Jobs,
+            std::time::Duration::from_millis(job_poll_interval_ms),
+            job_max_retries,
+            drain.cancellation_token(),
+            std::time::Duration::from_secs(job_lock_timeout_secs),
+        );
+        drain.set_job_worker_running(true);
+        tasks.push(NamedTask::spawn("jobs", async move {
+            let result = job_worker.await;
+            drain.set_job_worker_running(false);
+            result?;
+            if !drain.is_draining() {
+                return Ok(());
+            }
+            info!("Job worker drained");
+            std::future::pending().await
+        }));
     } else {
         info!("Jobs Disabled");
     }
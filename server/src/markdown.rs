@@ -0,0 +1,321 @@
+//! A small, safe markdown renderer for text users write about their snakes
+//!
+//! Supports paragraphs, `#` headings, `-`/`*` and numbered lists, fenced code
+//! blocks, and inline code, `**bold**`, `*emphasis*` and `[links](https://...)`.
+//! Raw HTML isn't supported: everything is escaped as text, and only http and
+//! https links are made, so nothing a user writes can run script on the page.
+
+use maud::{Markup, html};
+
+/// Render markdown to HTML
+pub fn render(source: &str) -> Markup {
+    let blocks = parse_blocks(source);
+    html! {
+        @for block in &blocks {
+            (render_block(block))
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Block {
+    Paragraph(String),
+    /// Level 1 to 3
+    Heading(usize, String),
+    List {
+        ordered: bool,
+        items: Vec<String>,
+    },
+    Code(String),
+}
+
+#[derive(Debug, PartialEq)]
+enum Inline {
+    Text(String),
+    Code(String),
+    Strong(Vec<Inline>),
+    Emphasis(Vec<Inline>),
+    Link { text: Vec<Inline>, url: String },
+}
+
+fn parse_blocks(source: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut list: Option<(bool, Vec<String>)> = None;
+    let mut code: Option<Vec<&str>> = None;
+
+    fn flush(
+        blocks: &mut Vec<Block>,
+        paragraph: &mut Vec<&str>,
+        list: &mut Option<(bool, Vec<String>)>,
+    ) {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(paragraph.join(" ")));
+            paragraph.clear();
+        }
+        if let Some((ordered, items)) = list.take() {
+            blocks.push(Block::List { ordered, items });
+        }
+    }
+
+    for line in source.lines() {
+        if let Some(lines) = &mut code {
+            if line.trim_start().starts_with("```") {
+                blocks.push(Block::Code(lines.join("\n")));
+                code = None;
+            } else {
+                lines.push(line);
+            }
+            continue;
+        }
+
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            flush(&mut blocks, &mut paragraph, &mut list);
+            code = Some(Vec::new());
+        } else if trimmed.is_empty() {
+            flush(&mut blocks, &mut paragraph, &mut list);
+        } else if let Some((level, text)) = heading(trimmed) {
+            flush(&mut blocks, &mut paragraph, &mut list);
+            blocks.push(Block::Heading(level, text.to_string()));
+        } else if let Some((ordered, text)) = list_item(trimmed) {
+            if !paragraph.is_empty() || list.as_ref().is_some_and(|(o, _)| *o != ordered) {
+                flush(&mut blocks, &mut paragraph, &mut list);
+            }
+            list.get_or_insert_with(|| (ordered, Vec::new()))
+                .1
+                .push(text.to_string());
+        } else if let Some((_, items)) = &mut list {
+            // A continuation of the last item
+            if let Some(last) = items.last_mut() {
+                last.push(' ');
+                last.push_str(trimmed);
+            }
+        } else {
+            paragraph.push(trimmed);
+        }
+    }
+
+    // An unclosed code block runs to the end
+    if let Some(lines) = code {
+        blocks.push(Block::Code(lines.join("\n")));
+    }
+    flush(&mut blocks, &mut paragraph, &mut list);
+    blocks
+}
+
+/// "## Strategy" as (2, "Strategy")
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=3).contains(&level).then(|| (level, text.trim()))
+}
+
+/// "- item" or "* item" as (false, "item"), "1. item" as (true, "item")
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    if let Some(text) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+        return Some((false, text.trim()));
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    line[digits..]
+        .strip_prefix(". ")
+        .map(|text| (true, text.trim()))
+}
+
+fn parse_inline(text: &str) -> Vec<Inline> {
+    let mut inlines = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let parsed = match c {
+            '\\' => {
+                // The next character, as is
+                let mut chars = rest[1..].chars();
+                match chars.next() {
+                    Some(escaped) => {
+                        plain.push(escaped);
+                        rest = chars.as_str();
+                    }
+                    None => {
+                        plain.push('\\');
+                        rest = "";
+                    }
+                }
+                continue;
+            }
+            '`' => {
+                delimited(rest, "`").map(|(inner, after)| (Inline::Code(inner.to_string()), after))
+            }
+            '*' if rest.starts_with("**") => delimited(rest, "**")
+                .map(|(inner, after)| (Inline::Strong(parse_inline(inner)), after)),
+            '*' => delimited(rest, "*")
+                .map(|(inner, after)| (Inline::Emphasis(parse_inline(inner)), after)),
+            '[' => link(rest),
+            _ => None,
+        };
+
+        match parsed {
+            Some((inline, after)) => {
+                if !plain.is_empty() {
+                    inlines.push(Inline::Text(std::mem::take(&mut plain)));
+                }
+                inlines.push(inline);
+                rest = after;
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    if !plain.is_empty() {
+        inlines.push(Inline::Text(plain));
+    }
+    inlines
+}
+
+/// The text between `marker` at the start of `text` and the next `marker`,
+/// and what's after that. None when there's no closing marker, or the text
+/// between them is blank or starts or ends with a space, like `2 * 3 * 4`.
+fn delimited<'a>(text: &'a str, marker: &str) -> Option<(&'a str, &'a str)> {
+    let after_open = &text[marker.len()..];
+    let close = after_open.find(marker)?;
+    let inner = &after_open[..close];
+    if inner.trim().is_empty() || inner.trim() != inner {
+        return None;
+    }
+    Some((inner, &after_open[close + marker.len()..]))
+}
+
+/// "[text](https://...)" at the start of `text`; anything but an http or
+/// https URL stays plain text
+fn link(text: &str) -> Option<(Inline, &str)> {
+    let close_text = text.find("](")?;
+    let label = &text[1..close_text];
+    let after_text = &text[close_text + 2..];
+    let close_url = after_text.find(')')?;
+    let url = after_text[..close_url].trim();
+
+    let parsed = url::Url::parse(url).ok()?;
+    if !matches!(parsed.scheme(), "http" | "https") || label.trim().is_empty() {
+        return None;
+    }
+    Some((
+        Inline::Link {
+            text: parse_inline(label),
+            url: url.to_string(),
+        },
+        &after_text[close_url + 1..],
+    ))
+}
+
+fn render_block(block: &Block) -> Markup {
+    match block {
+        Block::Paragraph(text) => html! { p { (render_inline(&parse_inline(text))) } },
+        // Below the page's own headings
+        Block::Heading(1, text) => html! { h4 { (render_inline(&parse_inline(text))) } },
+        Block::Heading(2, text) => html! { h5 { (render_inline(&parse_inline(text))) } },
+        Block::Heading(_, text) => html! { h6 { (render_inline(&parse_inline(text))) } },
+        Block::List { ordered, items } => {
+            let items = html! {
+                @for item in items {
+                    li { (render_inline(&parse_inline(item))) }
+                }
+            };
+            if *ordered {
+                html! { ol { (items) } }
+            } else {
+                html! { ul { (items) } }
+            }
+        }
+        Block::Code(code) => html! { pre { code { (code) } } },
+    }
+}
+
+fn render_inline(inlines: &[Inline]) -> Markup {
+    html! {
+        @for inline in inlines {
+            @match inline {
+                Inline::Text(text) => (text),
+                Inline::Code(code) => code { (code) },
+                Inline::Strong(inner) => strong { (render_inline(inner)) },
+                Inline::Emphasis(inner) => em { (render_inline(inner)) },
+                Inline::Link { text, url } => a href=(url) rel="nofollow ugc noopener" target="_blank" { (render_inline(text)) },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(source: &str) -> String {
+        render(source).into_string()
+    }
+
+    #[test]
+    fn test_render_blocks() {
+        assert_eq!(
+            rendered(
+                "# Strategy\n\nGoes for food,\nthen hides.\n\n- Flood fill\n- Minimax\n\n1. Eat\n2. Survive"
+            ),
+            "<h4>Strategy</h4>\
+             <p>Goes for food, then hides.</p>\
+             <ul><li>Flood fill</li><li>Minimax</li></ul>\
+             <ol><li>Eat</li><li>Survive</li></ol>"
+        );
+    }
+
+    #[test]
+    fn test_render_inline() {
+        assert_eq!(
+            rendered(
+                "A **fast** snake, *mostly*, in `rust`. See [the repo](https://github.com/a/b)."
+            ),
+            "<p>A <strong>fast</strong> snake, <em>mostly</em>, in <code>rust</code>. \
+             See <a href=\"https://github.com/a/b\" rel=\"nofollow ugc noopener\" target=\"_blank\">the repo</a>.</p>"
+        );
+    }
+
+    #[test]
+    fn test_render_code_block() {
+        assert_eq!(
+            rendered("```\nfn main() {\n    *x* <b>\n}\n```"),
+            "<pre><code>fn main() {\n    *x* &lt;b&gt;\n}</code></pre>"
+        );
+    }
+
+    #[test]
+    fn test_render_escapes_html() {
+        assert_eq!(
+            rendered("<script>alert(1)</script> & <img src=x onerror=alert(1)>"),
+            "<p>&lt;script&gt;alert(1)&lt;/script&gt; &amp; &lt;img src=x onerror=alert(1)&gt;</p>"
+        );
+    }
+
+    #[test]
+    fn test_render_only_links_http() {
+        assert_eq!(
+            rendered("[click](javascript:alert(1)) [x](data:text/html,hi)"),
+            "<p>[click](javascript:alert(1)) [x](data:text/html,hi)</p>"
+        );
+        assert_eq!(
+            rendered("[\"quoted\"](https://example.com/?a=1&b=\"2\")"),
+            "<p><a href=\"https://example.com/?a=1&amp;b=&quot;2&quot;\" rel=\"nofollow ugc noopener\" target=\"_blank\">&quot;quoted&quot;</a></p>"
+        );
+    }
+
+    #[test]
+    fn test_render_unclosed_markers_as_text() {
+        assert_eq!(
+            rendered("2 * 3 = 6, snake_case and ** alone \\*literal\\*"),
+            "<p>2 * 3 = 6, snake_case and ** alone *literal*</p>"
+        );
+    }
+}
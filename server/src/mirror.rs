@@ -0,0 +1,513 @@
+//! Mirror matches: two games between the same two snakes with their spawns swapped
+//!
+//! Which corner a snake starts in can decide a game between evenly matched
+//! snakes. A mirror match plays the game a second time with the spawns
+//! swapped and scores the pair as one round, so neither snake keeps the
+//! better start.
+//!
+//! A mirror match can also be the first round of a best-of-N series.
+//! [`MirrorSeriesHook`] starts each following round when the last one
+//! finishes, until a snake has won more than half the rounds or every round
+//! has been played. Series standings count rounds won, then games won.
+
+use color_eyre::eyre::{Context as _, eyre};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::engine::hooks::{EngineHook, GameEnded};
+use crate::models::game::{
+    CreateGameWithSnakes, GameSettings, SpawnStrategy, get_game_by_id, get_game_mirror_pair,
+    get_game_settings, get_game_spawn_strategy,
+};
+use crate::models::game_battlesnake::{GameBattlesnakeWithDetails, get_battlesnakes_by_game_id};
+use crate::models::mirror_series::{
+    claim_next_round, get_game_mirror_series_id, get_mirror_series, get_series_round_game_ids,
+};
+use crate::routes::api::games::start_mirror_round;
+use crate::state::AppState;
+
+/// Most rounds a series can be played over
+pub const MAX_SERIES_ROUNDS: i32 = 9;
+
+/// The spawns for the second game of a mirror match, with the two snakes'
+/// starts swapped
+///
+/// Random spawns can't be swapped, since they aren't stored, so a mirror
+/// match always starts from fixed or manual spawns.
+pub fn mirrored_spawn(spawn: &SpawnStrategy) -> SpawnStrategy {
+    match spawn {
+        SpawnStrategy::Random => SpawnStrategy::Fixed { rotation: 1 },
+        SpawnStrategy::Fixed { rotation } => SpawnStrategy::Fixed {
+            rotation: rotation + 1,
+        },
+        SpawnStrategy::Manual(points) => {
+            SpawnStrategy::Manual(points.iter().rev().copied().collect())
+        }
+    }
+}
+
+/// One snake's score across both games of a round
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RoundStanding {
+    pub battlesnake_id: Uuid,
+    pub name: String,
+    pub wins: u32,
+}
+
+/// Both games of a mirror match and the snakes' combined standings
+#[derive(Debug, Serialize)]
+pub struct MirrorRound {
+    /// The original game, then its mirror
+    pub game_ids: [Uuid; 2],
+    /// Most wins first
+    pub standings: Vec<RoundStanding>,
+    /// Whether both games have finished
+    pub complete: bool,
+}
+
+impl MirrorRound {
+    /// The snake that won more of the two games, None for a split round or
+    /// one still being played
+    pub fn winner(&self) -> Option<&RoundStanding> {
+        match self.standings.as_slice() {
+            [first, second, ..] if self.complete && first.wins > second.wins => Some(first),
+            _ => None,
+        }
+    }
+}
+
+/// Combine the snakes' results from both games of a round, most wins first
+pub fn round_standings(games: [&[GameBattlesnakeWithDetails]; 2]) -> Vec<RoundStanding> {
+    let mut standings: Vec<RoundStanding> = Vec::new();
+    for snake in games.iter().flat_map(|game| game.iter()) {
+        let won = u32::from(snake.placement == Some(1));
+        match standings
+            .iter_mut()
+            .find(|s| s.battlesnake_id == snake.battlesnake_id)
+        {
+            Some(standing) => standing.wins += won,
+            None => standings.push(RoundStanding {
+                battlesnake_id: snake.battlesnake_id,
+                name: snake.name.clone(),
+                wins: won,
+            }),
+        }
+    }
+
+    standings.sort_by(|a, b| b.wins.cmp(&a.wins).then_with(|| a.name.cmp(&b.name)));
+    standings
+}
+
+/// The mirror match a game is part of, if any
+pub async fn get_mirror_round(pool: &PgPool, game_id: Uuid) -> cja::Result<Option<MirrorRound>> {
+    let Some((original, mirror)) = get_game_mirror_pair(pool, game_id).await? else {
+        return Ok(None);
+    };
+
+    let original_snakes = get_battlesnakes_by_game_id(pool, original)
+        .await
+        .wrap_err("Failed to get battlesnakes for mirror match")?;
+    let mirror_snakes = get_battlesnakes_by_game_id(pool, mirror)
+        .await
+        .wrap_err("Failed to get battlesnakes for mirror match")?;
+
+    let complete = original_snakes
+        .iter()
+        .chain(&mirror_snakes)
+        .all(|s| s.placement.is_some());
+
+    Ok(Some(MirrorRound {
+        game_ids: [original, mirror],
+        standings: round_standings([&original_snakes, &mirror_snakes]),
+        complete,
+    }))
+}
+
+/// Check a series length: an odd number of rounds, so the series can't end
+/// level unless rounds are split
+pub fn validate_best_of(best_of: i32) -> Result<(), String> {
+    if !(1..=MAX_SERIES_ROUNDS).contains(&best_of) || best_of % 2 == 0 {
+        return Err(format!(
+            "best_of must be an odd number of rounds from 1 to {}",
+            MAX_SERIES_ROUNDS
+        ));
+    }
+    Ok(())
+}
+
+/// One snake's score across every round of a series
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SeriesStanding {
+    pub battlesnake_id: Uuid,
+    pub name: String,
+    pub rounds_won: u32,
+    pub games_won: u32,
+}
+
+/// Every round of a series and the snakes' standings across them
+#[derive(Debug, Serialize)]
+pub struct SeriesResults {
+    pub series_id: Uuid,
+    pub best_of: i32,
+    /// Oldest round first
+    pub rounds: Vec<MirrorRound>,
+    /// Most rounds won first, then most games won
+    pub standings: Vec<SeriesStanding>,
+    /// Whether a snake has won more than half the rounds, or every round has
+    /// been played
+    pub complete: bool,
+}
+
+impl SeriesResults {
+    /// The snake that won the most rounds, None for a series still being
+    /// played or one that ended level on rounds
+    pub fn winner(&self) -> Option<&SeriesStanding> {
+        match self.standings.as_slice() {
+            [first, second, ..] if self.complete && first.rounds_won > second.rounds_won => {
+                Some(first)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the last round has finished and the series needs another one
+    fn needs_next_round(&self) -> bool {
+        !self.complete && self.rounds.iter().all(|round| round.complete)
+    }
+}
+
+/// Combine the results of a series' rounds, most rounds won first
+pub fn series_standings(rounds: &[MirrorRound]) -> Vec<SeriesStanding> {
+    let mut standings: Vec<SeriesStanding> = Vec::new();
+    for round in rounds {
+        let winner = round.winner().map(|standing| standing.battlesnake_id);
+        for snake in &round.standings {
+            let won = u32::from(winner == Some(snake.battlesnake_id));
+            match standings
+                .iter_mut()
+                .find(|s| s.battlesnake_id == snake.battlesnake_id)
+            {
+                Some(standing) => {
+                    standing.rounds_won += won;
+                    standing.games_won += snake.wins;
+                }
+                None => standings.push(SeriesStanding {
+                    battlesnake_id: snake.battlesnake_id,
+                    name: snake.name.clone(),
+                    rounds_won: won,
+                    games_won: snake.wins,
+                }),
+            }
+        }
+    }
+
+    standings.sort_by(|a, b| {
+        b.rounds_won
+            .cmp(&a.rounds_won)
+            .then_with(|| b.games_won.cmp(&a.games_won))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    standings
+}
+
+/// Whether a series is over: a snake has won more than half its rounds, or
+/// every round has been played
+pub fn series_complete(best_of: i32, rounds: &[MirrorRound], standings: &[SeriesStanding]) -> bool {
+    let clinched = standings
+        .first()
+        .is_some_and(|standing| standing.rounds_won as i32 > best_of / 2);
+    let played = rounds.iter().filter(|round| round.complete).count() as i32;
+    clinched || played >= best_of
+}
+
+/// The series a game's mirror match is a round of, if any
+pub async fn get_series_results(
+    pool: &PgPool,
+    game_id: Uuid,
+) -> cja::Result<Option<SeriesResults>> {
+    let Some(series_id) = get_game_mirror_series_id(pool, game_id).await? else {
+        return Ok(None);
+    };
+    let Some(series) = get_mirror_series(pool, series_id).await? else {
+        return Ok(None);
+    };
+
+    let mut rounds = Vec::new();
+    for round_game_id in get_series_round_game_ids(pool, series_id).await? {
+        if let Some(round) = get_mirror_round(pool, round_game_id).await? {
+            rounds.push(round);
+        }
+    }
+    let standings = series_standings(&rounds);
+    let complete = series_complete(series.best_of, &rounds, &standings);
+
+    Ok(Some(SeriesResults {
+        series_id,
+        best_of: series.best_of,
+        rounds,
+        standings,
+        complete,
+    }))
+}
+
+/// Starts the next round of a series when the last one finishes
+pub struct MirrorSeriesHook;
+
+#[async_trait::async_trait]
+impl EngineHook for MirrorSeriesHook {
+    fn name(&self) -> &'static str {
+        "mirror_series"
+    }
+
+    async fn on_game_end(&self, app_state: &AppState, event: &GameEnded<'_>) -> cja::Result<()> {
+        let Some(results) = get_series_results(&app_state.db, event.game_id).await? else {
+            return Ok(());
+        };
+        if !results.needs_next_round() {
+            return Ok(());
+        }
+
+        // Both games of a round can finish at once, and each sees the round
+        // complete; only the one that claims the next round starts it
+        let rounds_started = results.rounds.len() as i32;
+        if !claim_next_round(
+            &app_state.db,
+            results.series_id,
+            rounds_started,
+            app_state.clock.now(),
+        )
+        .await?
+        {
+            return Ok(());
+        }
+
+        start_next_round(app_state, &results).await
+    }
+}
+
+/// Play the series' first game again with a new seed, and its mirror
+async fn start_next_round(app_state: &AppState, results: &SeriesResults) -> cja::Result<()> {
+    let series = get_mirror_series(&app_state.db, results.series_id)
+        .await?
+        .ok_or_else(|| eyre!("Mirror series {} not found", results.series_id))?;
+    let first_game_id = results
+        .rounds
+        .first()
+        .map(|round| round.game_ids[0])
+        .ok_or_else(|| eyre!("Mirror series {} has no rounds", results.series_id))?;
+
+    let first_game = get_game_by_id(&app_state.db, first_game_id)
+        .await?
+        .ok_or_else(|| eyre!("Game {} not found", first_game_id))?;
+    let settings = GameSettings {
+        seed: Some(rand::random()),
+        ..get_game_settings(&app_state.db, first_game_id).await?
+    };
+    let spawn = get_game_spawn_strategy(&app_state.db, first_game_id).await?;
+    let create_request = CreateGameWithSnakes {
+        board_size: first_game.board_size,
+        game_type: first_game.game_type,
+        battlesnake_ids: series.battlesnake_ids,
+    };
+
+    start_mirror_round(
+        app_state,
+        create_request,
+        settings,
+        &spawn,
+        Some(series.series_id),
+        series.league_id,
+    )
+    .await
+    .map_err(|(status, message)| {
+        eyre!(
+            "Failed to start round {} of mirror series {} ({}): {}",
+            results.rounds.len() + 1,
+            series.series_id,
+            status,
+            message
+        )
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::game::SpawnPoint;
+
+    fn snake(
+        battlesnake_id: Uuid,
+        name: &str,
+        placement: Option<i32>,
+    ) -> GameBattlesnakeWithDetails {
+        GameBattlesnakeWithDetails {
+            game_battlesnake_id: Uuid::new_v4(),
+            game_id: Uuid::nil(),
+            battlesnake_id,
+            placement,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            name: name.to_string(),
+            url: "http://example.com".to_string(),
+            user_id: Uuid::nil(),
+        }
+    }
+
+    #[test]
+    fn test_mirrored_spawn_swaps_two_snakes() {
+        assert_eq!(
+            mirrored_spawn(&SpawnStrategy::Fixed { rotation: 0 }),
+            SpawnStrategy::Fixed { rotation: 1 }
+        );
+        assert_eq!(
+            mirrored_spawn(&SpawnStrategy::Random),
+            SpawnStrategy::Fixed { rotation: 1 }
+        );
+
+        let points = vec![SpawnPoint { x: 1, y: 1 }, SpawnPoint { x: 5, y: 5 }];
+        assert_eq!(
+            mirrored_spawn(&SpawnStrategy::Manual(points)),
+            SpawnStrategy::Manual(vec![SpawnPoint { x: 5, y: 5 }, SpawnPoint { x: 1, y: 1 }])
+        );
+    }
+
+    #[test]
+    fn test_round_standings() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        // Each snake wins from one side of the board: a split round
+        let first = [snake(a, "Alpha", Some(1)), snake(b, "Beta", Some(2))];
+        let second = [snake(b, "Beta", Some(1)), snake(a, "Alpha", Some(2))];
+        let round = MirrorRound {
+            game_ids: [Uuid::new_v4(), Uuid::new_v4()],
+            standings: round_standings([&first, &second]),
+            complete: true,
+        };
+        assert_eq!(
+            round.standings.iter().map(|s| s.wins).collect::<Vec<_>>(),
+            vec![1, 1]
+        );
+        assert!(round.winner().is_none());
+
+        // Winning both games wins the round
+        let second = [snake(a, "Alpha", Some(1)), snake(b, "Beta", Some(2))];
+        let round = MirrorRound {
+            standings: round_standings([&first, &second]),
+            ..round
+        };
+        assert_eq!(round.winner().map(|s| s.battlesnake_id), Some(a));
+        assert_eq!(round.standings[0].wins, 2);
+
+        // No winner until both games finish
+        let second = [snake(a, "Alpha", None), snake(b, "Beta", None)];
+        let round = MirrorRound {
+            standings: round_standings([&first, &second]),
+            complete: false,
+            ..round
+        };
+        assert!(round.winner().is_none());
+    }
+
+    fn round(a: Uuid, b: Uuid, a_wins: [bool; 2], complete: bool) -> MirrorRound {
+        let game = |a_won: bool| {
+            let placement = |won: bool| complete.then_some(if won { 1 } else { 2 });
+            [
+                snake(a, "Alpha", placement(a_won)),
+                snake(b, "Beta", placement(!a_won)),
+            ]
+        };
+        let (first, second) = (game(a_wins[0]), game(a_wins[1]));
+        MirrorRound {
+            game_ids: [Uuid::new_v4(), Uuid::new_v4()],
+            standings: round_standings([&first, &second]),
+            complete,
+        }
+    }
+
+    #[test]
+    fn test_validate_best_of() {
+        assert!(validate_best_of(1).is_ok());
+        assert!(validate_best_of(MAX_SERIES_ROUNDS).is_ok());
+        assert!(validate_best_of(0).is_err());
+        assert!(validate_best_of(4).is_err());
+        assert!(validate_best_of(MAX_SERIES_ROUNDS + 2).is_err());
+    }
+
+    #[test]
+    fn test_series_standings() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        // Alpha sweeps a round, then a split round: one round and three games to one
+        let rounds = vec![
+            round(a, b, [true, true], true),
+            round(a, b, [true, false], true),
+        ];
+        let standings = series_standings(&rounds);
+        assert_eq!(standings[0].battlesnake_id, a);
+        assert_eq!((standings[0].rounds_won, standings[0].games_won), (1, 3));
+        assert_eq!((standings[1].rounds_won, standings[1].games_won), (0, 1));
+
+        // Best of 3 isn't decided by one round, and needs a third
+        let series = SeriesResults {
+            series_id: Uuid::new_v4(),
+            best_of: 3,
+            complete: series_complete(3, &rounds, &standings),
+            rounds,
+            standings,
+        };
+        assert!(!series.complete);
+        assert!(series.needs_next_round());
+        assert!(series.winner().is_none());
+    }
+
+    #[test]
+    fn test_series_complete() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        // Winning two rounds of three clinches the series early
+        let rounds = vec![
+            round(a, b, [true, true], true),
+            round(a, b, [true, true], true),
+        ];
+        let standings = series_standings(&rounds);
+        assert!(series_complete(3, &rounds, &standings));
+        let series = SeriesResults {
+            series_id: Uuid::new_v4(),
+            best_of: 3,
+            complete: true,
+            rounds,
+            standings,
+        };
+        assert!(!series.needs_next_round());
+        assert_eq!(series.winner().map(|s| s.battlesnake_id), Some(a));
+
+        // A round still being played holds up the next one
+        let rounds = vec![round(a, b, [true, true], false)];
+        let standings = series_standings(&rounds);
+        let series = SeriesResults {
+            series_id: Uuid::new_v4(),
+            best_of: 3,
+            complete: series_complete(3, &rounds, &standings),
+            rounds,
+            standings,
+        };
+        assert!(!series.complete);
+        assert!(!series.needs_next_round());
+
+        // Every round split: the series is over, and tied
+        let rounds = vec![round(a, b, [true, false], true)];
+        let standings = series_standings(&rounds);
+        let series = SeriesResults {
+            series_id: Uuid::new_v4(),
+            best_of: 1,
+            complete: series_complete(1, &rounds, &standings),
+            rounds,
+            standings,
+        };
+        assert!(series.complete);
+        assert!(series.winner().is_none());
+    }
+}
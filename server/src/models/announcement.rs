@@ -0,0 +1,237 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Type};
+use uuid::Uuid;
+
+// Longest an announcement's message can be
+pub const MAX_MESSAGE_CHARS: usize = 500;
+
+// How loudly an announcement is shown
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Type)]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Info,
+    Warning,
+    // Outages and maintenance that stops games
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = color_eyre::eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "critical" => Ok(Severity::Critical),
+            _ => Err(color_eyre::eyre::eyre!("Invalid severity: {}", s)),
+        }
+    }
+}
+
+// An arena-wide announcement, shown as a banner on every page while it's running
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Announcement {
+    pub announcement_id: Uuid,
+    // Markdown, rendered like snake descriptions
+    pub message: String,
+    pub severity: Severity,
+    pub starts_at: DateTime<Utc>,
+    // None runs until it's taken down
+    pub ends_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// What an admin sets on an announcement
+#[derive(Debug, Clone)]
+pub struct AnnouncementFields {
+    pub message: String,
+    pub severity: Severity,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+impl AnnouncementFields {
+    // The fields with the message trimmed, or why they can't be saved
+    pub fn validated(self) -> Result<Self, String> {
+        let message = self.message.trim().to_string();
+        if message.is_empty() {
+            return Err("Announcements need a message".to_string());
+        }
+        if message.chars().count() > MAX_MESSAGE_CHARS {
+            return Err(format!(
+                "Announcements can be up to {} characters",
+                MAX_MESSAGE_CHARS
+            ));
+        }
+        if self
+            .ends_at
+            .is_some_and(|ends_at| ends_at <= self.starts_at)
+        {
+            return Err("An announcement has to end after it starts".to_string());
+        }
+        Ok(Self { message, ..self })
+    }
+}
+
+// Create an announcement from validated fields
+pub async fn create_announcement(
+    pool: &PgPool,
+    fields: &AnnouncementFields,
+) -> cja::Result<Announcement> {
+    sqlx::query_as!(
+        Announcement,
+        r#"
+        INSERT INTO announcements (message, severity, starts_at, ends_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING announcement_id, message, severity AS "severity: Severity", starts_at, ends_at,
+            created_at, updated_at
+        "#,
+        &fields.message,
+        fields.severity.as_str(),
+        fields.starts_at,
+        fields.ends_at
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err("Failed to create announcement")
+}
+
+// Replace an announcement's fields, None if there's no such announcement
+pub async fn update_announcement(
+    pool: &PgPool,
+    announcement_id: Uuid,
+    fields: &AnnouncementFields,
+    now: DateTime<Utc>,
+) -> cja::Result<Option<Announcement>> {
+    sqlx::query_as!(
+        Announcement,
+        r#"
+        UPDATE announcements
+        SET message = $2, severity = $3, starts_at = $4, ends_at = $5, updated_at = $6
+        WHERE announcement_id = $1
+        RETURNING announcement_id, message, severity AS "severity: Severity", starts_at, ends_at,
+            created_at, updated_at
+        "#,
+        announcement_id,
+        &fields.message,
+        fields.severity.as_str(),
+        fields.starts_at,
+        fields.ends_at,
+        now
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to update announcement {}", announcement_id))
+}
+
+// Delete an announcement, returning whether there was one
+pub async fn delete_announcement(pool: &PgPool, announcement_id: Uuid) -> cja::Result<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM announcements WHERE announcement_id = $1",
+        announcement_id
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to delete announcement {}", announcement_id))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Every announcement, past, running and upcoming, latest start first
+pub async fn get_all_announcements(pool: &PgPool) -> cja::Result<Vec<Announcement>> {
+    sqlx::query_as!(
+        Announcement,
+        r#"
+        SELECT announcement_id, message, severity AS "severity: Severity", starts_at, ends_at,
+            created_at, updated_at
+        FROM announcements
+        ORDER BY starts_at DESC
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch announcements")
+}
+
+// Announcements running at `now`, most severe first and then latest start first
+pub async fn get_active_announcements(
+    pool: &PgPool,
+    now: DateTime<Utc>,
+) -> cja::Result<Vec<Announcement>> {
+    sqlx::query_as!(
+        Announcement,
+        r#"
+        SELECT announcement_id, message, severity AS "severity: Severity", starts_at, ends_at,
+            created_at, updated_at
+        FROM announcements
+        WHERE starts_at <= $1 AND (ends_at IS NULL OR ends_at > $1)
+        ORDER BY
+            CASE severity WHEN 'critical' THEN 0 WHEN 'warning' THEN 1 ELSE 2 END,
+            starts_at DESC
+        "#,
+        now
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch active announcements")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(message: &str, ends_in_hours: Option<i64>) -> AnnouncementFields {
+        let starts_at = Utc::now();
+        AnnouncementFields {
+            message: message.to_string(),
+            severity: Severity::Warning,
+            starts_at,
+            ends_at: ends_in_hours.map(|hours| starts_at + chrono::Duration::hours(hours)),
+        }
+    }
+
+    #[test]
+    fn test_validated() {
+        let valid = fields("  Maintenance at 02:00 UTC  ", Some(2))
+            .validated()
+            .unwrap();
+        assert_eq!(valid.message, "Maintenance at 02:00 UTC");
+
+        assert!(fields("Until it's taken down", None).validated().is_ok());
+        assert!(fields("   ", None).validated().is_err());
+        assert!(
+            fields(&"a".repeat(MAX_MESSAGE_CHARS + 1), None)
+                .validated()
+                .is_err()
+        );
+        assert!(
+            fields("Ends before it starts", Some(-1))
+                .validated()
+                .is_err()
+        );
+        assert!(fields("Ends as it starts", Some(0)).validated().is_err());
+    }
+
+    #[test]
+    fn test_severity_from_str() {
+        assert_eq!("Critical".parse::<Severity>().unwrap(), Severity::Critical);
+        assert!("urgent".parse::<Severity>().is_err());
+    }
+}
@@ -25,14 +25,14 @@ pub struct NewApiToken {
 }
 
 /// Generate a random 32-byte token and return it as a hex string (64 chars)
-fn generate_token_secret() -> String {
+pub(crate) fn generate_token_secret() -> String {
     let mut bytes = [0u8; 32];
     rand::thread_rng().fill_bytes(&mut bytes);
     hex::encode(bytes)
 }
 
 /// Hash a token secret using SHA-256, returning the hex-encoded hash
-fn hash_token(secret: &str) -> String {
+pub(crate) fn hash_token(secret: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(secret.as_bytes());
     hex::encode(hasher.finalize())
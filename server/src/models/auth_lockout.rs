@@ -0,0 +1,80 @@
+use color_eyre::eyre::Context as _;
+use sqlx::PgPool;
+
+/// Persisted failed-auth state for a single lockout key (client IP or token prefix)
+#[derive(Debug, Clone)]
+pub struct AuthLockout {
+    pub lockout_key: String,
+    pub failure_count: i32,
+    pub lockout_count: i32,
+    pub first_failure_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub locked_until: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Get the persisted lockout state for a key, if any
+pub async fn get_auth_lockout(
+    pool: &PgPool,
+    lockout_key: &str,
+) -> cja::Result<Option<AuthLockout>> {
+    let lockout = sqlx::query_as!(
+        AuthLockout,
+        r#"
+        SELECT lockout_key, failure_count, lockout_count, first_failure_at, locked_until, created_at, updated_at
+        FROM auth_lockouts
+        WHERE lockout_key = $1
+        "#,
+        lockout_key
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to get auth lockout")?;
+
+    Ok(lockout)
+}
+
+/// Insert or update the lockout state for a key
+pub async fn upsert_auth_lockout(
+    pool: &PgPool,
+    lockout_key: &str,
+    failure_count: i32,
+    lockout_count: i32,
+    first_failure_at: Option<chrono::DateTime<chrono::Utc>>,
+    locked_until: Option<chrono::DateTime<chrono::Utc>>,
+) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO auth_lockouts (lockout_key, failure_count, lockout_count, first_failure_at, locked_until)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (lockout_key) DO UPDATE SET
+            failure_count = EXCLUDED.failure_count,
+            lockout_count = EXCLUDED.lockout_count,
+            first_failure_at = EXCLUDED.first_failure_at,
+            locked_until = EXCLUDED.locked_until
+        "#,
+        lockout_key,
+        failure_count,
+        lockout_count,
+        first_failure_at,
+        locked_until
+    )
+    .execute(pool)
+    .await
+    .wrap_err("Failed to upsert auth lockout")?;
+
+    Ok(())
+}
+
+/// Clear the lockout state for a key (e.g. after a successful authentication)
+pub async fn delete_auth_lockout(pool: &PgPool, lockout_key: &str) -> cja::Result<()> {
+    sqlx::query!(
+        "DELETE FROM auth_lockouts WHERE lockout_key = $1",
+        lockout_key
+    )
+    .execute(pool)
+    .await
+    .wrap_err("Failed to delete auth lockout")?;
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use color_eyre::eyre::Context as _;
 use serde::{Deserialize, Serialize};
-use sqlx::{PgPool, Type};
+use sqlx::{Executor, PgPool, Postgres, Type};
 use std::str::FromStr;
 use uuid::Uuid;
 
@@ -39,7 +39,7 @@ impl FromStr for Visibility {
 // Default implementation for Visibility - default to Public
 
 // Battlesnake model for our application
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Battlesnake {
     pub battlesnake_id: Uuid,
     pub user_id: Uuid,
@@ -126,11 +126,14 @@ pub async fn get_battlesnake_by_id(
 }
 
 // Create a new battlesnake
-pub async fn create_battlesnake(
-    pool: &PgPool,
+pub async fn create_battlesnake<'e, E>(
+    executor: E,
     user_id: Uuid,
     data: CreateBattlesnake,
-) -> cja::Result<Battlesnake> {
+) -> cja::Result<Battlesnake>
+where
+    E: Executor<'e, Database = Postgres>,
+{
     let visibility_str = data.visibility.as_str();
 
     let result = sqlx::query_as!(
@@ -157,7 +160,7 @@ pub async fn create_battlesnake(
         data.url,
         visibility_str
     )
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await;
 
     match result {
@@ -181,12 +184,15 @@ pub async fn create_battlesnake(
 }
 
 // Update an existing battlesnake
-pub async fn update_battlesnake(
-    pool: &PgPool,
+pub async fn update_battlesnake<'e, E>(
+    executor: E,
     battlesnake_id: Uuid,
     user_id: Uuid,
     data: UpdateBattlesnake,
-) -> cja::Result<Battlesnake> {
+) -> cja::Result<Battlesnake>
+where
+    E: Executor<'e, Database = Postgres>,
+{
     let visibility_str = data.visibility.as_str();
 
     let result = sqlx::query_as!(
@@ -215,7 +221,7 @@ pub async fn update_battlesnake(
         data.url,
         visibility_str
     )
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await;
 
     match result {
@@ -239,11 +245,14 @@ pub async fn update_battlesnake(
 }
 
 // Delete a battlesnake
-pub async fn delete_battlesnake(
-    pool: &PgPool,
+pub async fn delete_battlesnake<'e, E>(
+    executor: E,
     battlesnake_id: Uuid,
     user_id: Uuid,
-) -> cja::Result<()> {
+) -> cja::Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
     sqlx::query!(
         r#"
         DELETE FROM battlesnakes
@@ -254,7 +263,7 @@ pub async fn delete_battlesnake(
         battlesnake_id,
         user_id
     )
-    .execute(pool)
+    .execute(executor)
     .await
     .wrap_err("Failed to delete battlesnake from database")?;
 
@@ -340,3 +349,239 @@ pub async fn get_available_battlesnakes(
 
     Ok(battlesnakes)
 }
+
+// Whether a battlesnake's owner has opted it in to ranked games
+pub async fn get_ranked_opt_in(pool: &PgPool, battlesnake_id: Uuid) -> cja::Result<bool> {
+    let opt_in = sqlx::query_scalar!(
+        r#"
+        SELECT ranked_opt_in
+        FROM battlesnakes
+        WHERE battlesnake_id = $1
+        "#,
+        battlesnake_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to fetch ranked opt-in")?;
+
+    Ok(opt_in.unwrap_or(false))
+}
+
+// Opt a battlesnake in to, or out of, ranked games
+pub async fn set_ranked_opt_in(
+    pool: &PgPool,
+    battlesnake_id: Uuid,
+    opt_in: bool,
+) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE battlesnakes
+        SET ranked_opt_in = $2
+        WHERE battlesnake_id = $1
+        "#,
+        battlesnake_id,
+        opt_in
+    )
+    .execute(pool)
+    .await
+    .wrap_err("Failed to set ranked opt-in")?;
+
+    Ok(())
+}
+
+// Of the given battlesnakes, the ones that haven't opted in to ranked games
+pub async fn get_snakes_not_opted_in(
+    pool: &PgPool,
+    battlesnake_ids: &[Uuid],
+) -> cja::Result<Vec<Uuid>> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT battlesnake_id
+        FROM battlesnakes
+        WHERE battlesnake_id = ANY($1) AND NOT ranked_opt_in
+        ORDER BY battlesnake_id
+        "#,
+        battlesnake_ids
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to check ranked opt-ins")
+}
+
+// Most characters in a snake's description
+pub const MAX_DESCRIPTION_CHARS: usize = 2000;
+
+// Most characters in a snake's homepage or source URL
+pub const MAX_PROFILE_URL_CHARS: usize = 500;
+
+// What a snake's owner says about it on its public pages
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnakeProfile {
+    // Markdown, rendered by crate::markdown
+    pub description: Option<String>,
+    pub homepage_url: Option<String>,
+    // Where the snake's code is, such as its GitHub repository
+    pub source_url: Option<String>,
+}
+
+impl SnakeProfile {
+    // Trim each field, treating blanks as unset, and check the limits
+    pub fn normalized(self) -> Result<Self, &'static str> {
+        let clean = |value: Option<String>| {
+            value
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let profile = Self {
+            description: clean(self.description),
+            homepage_url: clean(self.homepage_url),
+            source_url: clean(self.source_url),
+        };
+
+        if profile
+            .description
+            .as_ref()
+            .is_some_and(|d| d.chars().count() > MAX_DESCRIPTION_CHARS)
+        {
+            return Err("Description can be at most 2000 characters");
+        }
+        for url in [&profile.homepage_url, &profile.source_url]
+            .into_iter()
+            .flatten()
+        {
+            if url.chars().count() > MAX_PROFILE_URL_CHARS {
+                return Err("Homepage and source URLs can be at most 500 characters");
+            }
+            match url::Url::parse(url) {
+                Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+                _ => return Err("Homepage and source URLs must be http or https URLs"),
+            }
+        }
+
+        Ok(profile)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none() && self.homepage_url.is_none() && self.source_url.is_none()
+    }
+}
+
+// Get a battlesnake's profile, empty if it has none or doesn't exist
+pub async fn get_snake_profile(pool: &PgPool, battlesnake_id: Uuid) -> cja::Result<SnakeProfile> {
+    let profile = sqlx::query_as!(
+        SnakeProfile,
+        r#"
+        SELECT description, homepage_url, source_url
+        FROM battlesnakes
+        WHERE battlesnake_id = $1
+        "#,
+        battlesnake_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to fetch snake profile")?;
+
+    Ok(profile.unwrap_or_default())
+}
+
+// Get the profiles of several battlesnakes, keyed by ID
+pub async fn get_snake_profiles(
+    pool: &PgPool,
+    battlesnake_ids: &[Uuid],
+) -> cja::Result<std::collections::HashMap<Uuid, SnakeProfile>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT battlesnake_id, description, homepage_url, source_url
+        FROM battlesnakes
+        WHERE battlesnake_id = ANY($1)
+        "#,
+        battlesnake_ids
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch snake profiles")?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            (
+                row.battlesnake_id,
+                SnakeProfile {
+                    description: row.description,
+                    homepage_url: row.homepage_url,
+                    source_url: row.source_url,
+                },
+            )
+        })
+        .collect())
+}
+
+// Replace a battlesnake's profile
+pub async fn set_snake_profile<'e, E>(
+    executor: E,
+    battlesnake_id: Uuid,
+    profile: &SnakeProfile,
+) -> cja::Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query!(
+        r#"
+        UPDATE battlesnakes
+        SET description = $2, homepage_url = $3, source_url = $4
+        WHERE battlesnake_id = $1
+        "#,
+        battlesnake_id,
+        profile.description.as_deref(),
+        profile.homepage_url.as_deref(),
+        profile.source_url.as_deref()
+    )
+    .execute(executor)
+    .await
+    .wrap_err("Failed to set snake profile")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snake_profile_normalized() {
+        let profile = SnakeProfile {
+            description: Some("  Goes for **food**  ".to_string()),
+            homepage_url: Some("   ".to_string()),
+            source_url: Some("https://github.com/example/snake".to_string()),
+        }
+        .normalized()
+        .unwrap();
+
+        assert_eq!(profile.description.as_deref(), Some("Goes for **food**"));
+        assert_eq!(profile.homepage_url, None);
+        assert_eq!(
+            profile.source_url.as_deref(),
+            Some("https://github.com/example/snake")
+        );
+    }
+
+    #[test]
+    fn test_snake_profile_rejects_bad_urls() {
+        for url in ["javascript:alert(1)", "not a url", "ftp://example.com"] {
+            let profile = SnakeProfile {
+                homepage_url: Some(url.to_string()),
+                ..Default::default()
+            };
+            assert!(profile.normalized().is_err(), "{url}");
+        }
+    }
+
+    #[test]
+    fn test_snake_profile_limits_description() {
+        let profile = SnakeProfile {
+            description: Some("a".repeat(MAX_DESCRIPTION_CHARS + 1)),
+            ..Default::default()
+        };
+        assert!(profile.normalized().is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use color_eyre::eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::models::game::GameStatus;
+
+// A labelled URL a snake has been deployed at, so results can be attributed
+// to the code that played them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BattlesnakeVersion {
+    pub battlesnake_version_id: Uuid,
+    pub battlesnake_id: Uuid,
+    pub label: String,
+    pub url: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// A version with how it has done in finished games
+#[derive(Debug, Clone)]
+pub struct VersionSummary {
+    pub battlesnake_version_id: Uuid,
+    pub label: String,
+    pub url: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    // Whether new games are attributed to this version
+    pub active: bool,
+    pub games: i64,
+    // Games it placed first in, shared first places included
+    pub wins: i64,
+    pub average_placement: Option<f64>,
+}
+
+// Create a version of a snake, or None if the snake already has one with this
+// label. Activating it points the snake at the version's URL.
+pub async fn create_version(
+    pool: &PgPool,
+    battlesnake_id: Uuid,
+    label: &str,
+    url: &str,
+    activate: bool,
+) -> cja::Result<Option<BattlesnakeVersion>> {
+    let mut tx = pool.begin().await.wrap_err("Failed to start transaction")?;
+
+    let version = sqlx::query_as!(
+        BattlesnakeVersion,
+        r#"
+        INSERT INTO battlesnake_versions (battlesnake_id, label, url)
+        VALUES ($1, $2, $3)
+        ON CONFLICT ON CONSTRAINT unique_battlesnake_version_label DO NOTHING
+        RETURNING battlesnake_version_id, battlesnake_id, label, url, created_at
+        "#,
+        battlesnake_id,
+        label,
+        url
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .wrap_err_with(|| format!("Failed to create version of snake {}", battlesnake_id))?;
+
+    if let Some(version) = &version
+        && activate
+    {
+        set_active_version(&mut tx, battlesnake_id, version.battlesnake_version_id).await?;
+    }
+
+    tx.commit().await.wrap_err("Failed to commit transaction")?;
+    Ok(version)
+}
+
+// Get a version by ID
+pub async fn get_version(
+    pool: &PgPool,
+    battlesnake_version_id: Uuid,
+) -> cja::Result<Option<BattlesnakeVersion>> {
+    sqlx::query_as!(
+        BattlesnakeVersion,
+        r#"
+        SELECT battlesnake_version_id, battlesnake_id, label, url, created_at
+        FROM battlesnake_versions
+        WHERE battlesnake_version_id = $1
+        "#,
+        battlesnake_version_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch snake version {}", battlesnake_version_id))
+}
+
+// Make one of a snake's versions the active one, or None if the snake has no
+// such version
+pub async fn activate_version(
+    pool: &PgPool,
+    battlesnake_id: Uuid,
+    battlesnake_version_id: Uuid,
+) -> cja::Result<Option<BattlesnakeVersion>> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .wrap_err("Failed to acquire connection")?;
+    set_active_version(&mut conn, battlesnake_id, battlesnake_version_id).await
+}
+
+// Point a snake at a version's URL and attribute its new games to the version
+async fn set_active_version(
+    conn: &mut PgConnection,
+    battlesnake_id: Uuid,
+    battlesnake_version_id: Uuid,
+) -> cja::Result<Option<BattlesnakeVersion>> {
+    sqlx::query_as!(
+        BattlesnakeVersion,
+        r#"
+        UPDATE battlesnakes b
+        SET url = v.url, active_version_id = v.battlesnake_version_id
+        FROM battlesnake_versions v
+        WHERE b.battlesnake_id = $1
+          AND v.battlesnake_version_id = $2
+          AND v.battlesnake_id = b.battlesnake_id
+        RETURNING v.battlesnake_version_id, v.battlesnake_id, v.label, v.url, v.created_at
+        "#,
+        battlesnake_id,
+        battlesnake_version_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .wrap_err_with(|| {
+        format!(
+            "Failed to activate version {} of snake {}",
+            battlesnake_version_id, battlesnake_id
+        )
+    })
+}
+
+// Get a snake's versions, newest first, with their results
+//
+// A version is only active while the snake's URL is still the version's URL, so
+// editing the URL directly stops attributing games to it. Results count the
+// snake's best placement in each finished game, leaving out branches.
+pub async fn get_version_summaries(
+    pool: &PgPool,
+    battlesnake_id: Uuid,
+) -> cja::Result<Vec<VersionSummary>> {
+    sqlx::query_as!(
+        VersionSummary,
+        r#"
+        WITH played AS (
+            SELECT gb.battlesnake_version_id, gb.game_id, MIN(gb.placement) AS placement
+            FROM game_battlesnakes gb
+            JOIN games g ON g.game_id = gb.game_id
+            WHERE gb.battlesnake_id = $1
+              AND gb.battlesnake_version_id IS NOT NULL
+              AND gb.placement IS NOT NULL
+              AND g.status = $2
+              AND g.parent_game_id IS NULL
+            GROUP BY gb.battlesnake_version_id, gb.game_id
+        )
+        SELECT v.battlesnake_version_id, v.label, v.url, v.created_at,
+            COALESCE(b.active_version_id = v.battlesnake_version_id AND b.url = v.url, FALSE) AS "active!",
+            COUNT(p.game_id) AS "games!",
+            COUNT(p.game_id) FILTER (WHERE p.placement = 1) AS "wins!",
+            AVG(p.placement)::FLOAT8 AS average_placement
+        FROM battlesnake_versions v
+        JOIN battlesnakes b ON b.battlesnake_id = v.battlesnake_id
+        LEFT JOIN played p ON p.battlesnake_version_id = v.battlesnake_version_id
+        WHERE v.battlesnake_id = $1
+        GROUP BY v.battlesnake_version_id, b.active_version_id, b.url
+        ORDER BY v.created_at DESC, v.label ASC
+        "#,
+        battlesnake_id,
+        GameStatus::Finished.as_str()
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch versions of snake {}", battlesnake_id))
+}
@@ -0,0 +1,162 @@
+use color_eyre::eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::game::GameStatus;
+
+// A regression check: a batch of games a snake has to win enough of
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Check {
+    pub check_id: Uuid,
+    pub user_id: Uuid,
+    // The snake being checked
+    pub battlesnake_id: Uuid,
+    pub games_requested: i32,
+    // Percent of the games the snake has to win to pass
+    pub required_win_rate: f64,
+    // The commit being checked, for posting the result to GitHub
+    pub commit_sha: Option<String>,
+    // The last commit status posted to GitHub, None if none was
+    pub github_state: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// One of a check's games, from the checked snake's side
+#[derive(Debug, Clone)]
+pub struct CheckGame {
+    pub game_id: Uuid,
+    pub status: String,
+    pub placement: Option<i32>,
+}
+
+impl CheckGame {
+    pub fn is_finished(&self) -> bool {
+        self.status == GameStatus::Finished.as_str()
+    }
+}
+
+// Create a check for one of a user's snakes
+pub async fn create_check(
+    pool: &PgPool,
+    user_id: Uuid,
+    battlesnake_id: Uuid,
+    games_requested: i32,
+    required_win_rate: f64,
+    commit_sha: Option<&str>,
+) -> cja::Result<Check> {
+    sqlx::query_as!(
+        Check,
+        r#"
+        INSERT INTO checks (user_id, battlesnake_id, games_requested, required_win_rate, commit_sha)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING check_id, user_id, battlesnake_id, games_requested, required_win_rate, commit_sha,
+            github_state, created_at
+        "#,
+        user_id,
+        battlesnake_id,
+        games_requested,
+        required_win_rate,
+        commit_sha
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err("Failed to create check")
+}
+
+// Get a check by ID
+pub async fn get_check_by_id(pool: &PgPool, check_id: Uuid) -> cja::Result<Option<Check>> {
+    sqlx::query_as!(
+        Check,
+        r#"
+        SELECT check_id, user_id, battlesnake_id, games_requested, required_win_rate, commit_sha,
+            github_state, created_at
+        FROM checks
+        WHERE check_id = $1
+        "#,
+        check_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch check {}", check_id))
+}
+
+// Count a game towards a check
+pub async fn add_game_to_check(pool: &PgPool, check_id: Uuid, game_id: Uuid) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE games
+        SET check_id = $1
+        WHERE game_id = $2
+        "#,
+        check_id,
+        game_id
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to add game {} to check {}", game_id, check_id))?;
+
+    Ok(())
+}
+
+// Get a check's games with the checked snake's placement in each, oldest first
+pub async fn get_check_games(pool: &PgPool, check: &Check) -> cja::Result<Vec<CheckGame>> {
+    sqlx::query_as!(
+        CheckGame,
+        r#"
+        SELECT g.game_id, g.status, gb.placement
+        FROM games g
+        JOIN game_battlesnakes gb
+          ON gb.game_id = g.game_id AND gb.battlesnake_id = $2
+        WHERE g.check_id = $1
+        ORDER BY g.created_at ASC, g.game_id ASC
+        "#,
+        check.check_id,
+        check.battlesnake_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch games for check {}", check.check_id))
+}
+
+// Record the commit status last posted to GitHub for a check
+pub async fn set_check_github_state(pool: &PgPool, check_id: Uuid, state: &str) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE checks
+        SET github_state = $2
+        WHERE check_id = $1
+        "#,
+        check_id,
+        state
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to set GitHub state of check {}", check_id))?;
+
+    Ok(())
+}
+
+// Get the last day's checks for commits whose status on GitHub is still
+// pending or was never posted, oldest first
+pub async fn get_checks_pending_on_github(
+    pool: &PgPool,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<Vec<Check>> {
+    sqlx::query_as!(
+        Check,
+        r#"
+        SELECT check_id, user_id, battlesnake_id, games_requested, required_win_rate, commit_sha,
+            github_state, created_at
+        FROM checks
+        WHERE commit_sha IS NOT NULL
+          AND (github_state IS NULL OR github_state = 'pending')
+          AND created_at > $1
+        ORDER BY created_at ASC
+        "#,
+        now - chrono::Duration::days(1)
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch checks pending on GitHub")
+}
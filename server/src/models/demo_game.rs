@@ -0,0 +1,85 @@
+use color_eyre::eyre::Context as _;
+use sqlx::{Executor, PgPool, Postgres};
+use uuid::Uuid;
+
+// A bots-only game started from the landing page
+#[derive(Debug, Clone)]
+pub struct DemoGame {
+    pub game_id: Uuid,
+    // The game's status, as stored on games
+    pub status: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Serialize demo game starts until the transaction ends, so concurrent
+// visitors can't each see room for one more
+pub async fn lock_demo_games<'e, E>(executor: E) -> cja::Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query!("LOCK TABLE demo_games IN SHARE ROW EXCLUSIVE MODE")
+        .execute(executor)
+        .await
+        .wrap_err("Failed to lock demo games")?;
+
+    Ok(())
+}
+
+// Get the demo games created since `since`, newest first
+pub async fn get_demo_games_since<'e, E>(
+    executor: E,
+    since: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<Vec<DemoGame>>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        DemoGame,
+        r#"
+        SELECT d.game_id, g.status, d.created_at
+        FROM demo_games d
+        JOIN games g ON g.game_id = d.game_id
+        WHERE d.created_at >= $1
+        ORDER BY d.created_at DESC
+        "#,
+        since
+    )
+    .fetch_all(executor)
+    .await
+    .wrap_err("Failed to fetch demo games")
+}
+
+// Record a game as a demo game
+pub async fn create_demo_game<'e, E>(
+    executor: E,
+    game_id: Uuid,
+    created_at: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query!(
+        r#"
+        INSERT INTO demo_games (game_id, created_at)
+        VALUES ($1, $2)
+        "#,
+        game_id,
+        created_at
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| format!("Failed to record demo game {}", game_id))?;
+
+    Ok(())
+}
+
+// Whether a game is a demo game
+pub async fn is_demo_game(pool: &PgPool, game_id: Uuid) -> cja::Result<bool> {
+    sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM demo_games WHERE game_id = $1) AS "exists!""#,
+        game_id
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to check whether game {} is a demo", game_id))
+}
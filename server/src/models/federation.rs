@@ -0,0 +1,291 @@
+use color_eyre::eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::game::GameStatus;
+
+// A public snake as one arena lists it to its peers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FederatedSnake {
+    pub snake_id: Uuid,
+    pub name: String,
+    // The owner's GitHub login
+    pub owner: String,
+}
+
+// One snake's placement in a federated game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FederatedPlacement {
+    pub snake_id: Uuid,
+    pub placement: i32,
+}
+
+// A finished game between public snakes, as one arena reports it to its peers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FederatedResult {
+    pub game_id: Uuid,
+    pub finished_at: chrono::DateTime<chrono::Utc>,
+    // Best placement first
+    pub placements: Vec<FederatedPlacement>,
+}
+
+// A snake synced from a peer
+#[derive(Debug, Clone)]
+pub struct PeerSnake {
+    pub peer_url: String,
+    pub remote_snake_id: Uuid,
+    pub name: String,
+    pub owner: String,
+}
+
+#[derive(Debug)]
+struct LocalResultRow {
+    game_id: Uuid,
+    finished_at: chrono::DateTime<chrono::Utc>,
+    battlesnake_id: Uuid,
+    placement: i32,
+}
+
+#[derive(Debug)]
+struct PeerResultRow {
+    remote_game_id: Uuid,
+    finished_at: chrono::DateTime<chrono::Utc>,
+    placements: serde_json::Value,
+}
+
+// Get this arena's public snakes, as listed to peers
+pub async fn get_local_federated_snakes(pool: &PgPool) -> cja::Result<Vec<FederatedSnake>> {
+    sqlx::query_as!(
+        FederatedSnake,
+        r#"
+        SELECT b.battlesnake_id AS snake_id, b.name, COALESCE(u.github_login, '') AS "owner!"
+        FROM battlesnakes b
+        LEFT JOIN users u ON u.user_id = b.user_id
+        WHERE b.visibility = 'public'
+        ORDER BY b.name ASC
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch public snakes for federation")
+}
+
+// Get a page of this arena's finished public games where every snake is
+// public, in the order they finished, starting after `cursor`
+// Branches and unranked games are left out, like they are from ratings
+pub async fn get_local_federated_results(
+    pool: &PgPool,
+    cursor: Option<(chrono::DateTime<chrono::Utc>, Uuid)>,
+    limit: i64,
+) -> cja::Result<Vec<FederatedResult>> {
+    let rows = sqlx::query_as!(
+        LocalResultRow,
+        r#"
+        SELECT g.game_id, g.updated_at AS finished_at, gb.battlesnake_id,
+            gb.placement AS "placement!"
+        FROM games g
+        JOIN game_battlesnakes gb ON gb.game_id = g.game_id
+        WHERE g.game_id IN (
+            SELECT g2.game_id
+            FROM games g2
+            WHERE g2.status = $1
+              AND g2.parent_game_id IS NULL
+              AND g2.ranked
+              AND g2.visibility = 'public'
+              AND ($2::TIMESTAMPTZ IS NULL OR (g2.updated_at, g2.game_id) > ($2, $3))
+              AND NOT EXISTS (
+                  SELECT 1
+                  FROM game_battlesnakes x
+                  JOIN battlesnakes b ON b.battlesnake_id = x.battlesnake_id
+                  WHERE x.game_id = g2.game_id
+                    AND (b.visibility <> 'public' OR x.placement IS NULL)
+              )
+            ORDER BY g2.updated_at ASC, g2.game_id ASC
+            LIMIT $4
+        )
+        ORDER BY g.updated_at ASC, g.game_id ASC, gb.placement ASC
+        "#,
+        GameStatus::Finished.as_str(),
+        cursor.map(|(at, _)| at),
+        cursor.map(|(_, game_id)| game_id),
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch results for federation")?;
+
+    let mut results: Vec<FederatedResult> = Vec::new();
+    for row in rows {
+        let placement = FederatedPlacement {
+            snake_id: row.battlesnake_id,
+            placement: row.placement,
+        };
+        match results.last_mut() {
+            Some(result) if result.game_id == row.game_id => result.placements.push(placement),
+            _ => results.push(FederatedResult {
+                game_id: row.game_id,
+                finished_at: row.finished_at,
+                placements: vec![placement],
+            }),
+        }
+    }
+
+    Ok(results)
+}
+
+// Store the snakes a peer lists, replacing what it listed before
+pub async fn replace_peer_snakes(
+    pool: &PgPool,
+    peer_url: &str,
+    snakes: &[FederatedSnake],
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<()> {
+    let mut tx = pool.begin().await.wrap_err("Failed to start transaction")?;
+
+    sqlx::query!("DELETE FROM federated_snakes WHERE peer_url = $1", peer_url)
+        .execute(&mut *tx)
+        .await
+        .wrap_err_with(|| format!("Failed to clear snakes from peer {}", peer_url))?;
+
+    for snake in snakes {
+        sqlx::query!(
+            r#"
+            INSERT INTO federated_snakes (peer_url, remote_snake_id, name, owner, updated_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (peer_url, remote_snake_id) DO UPDATE
+            SET name = EXCLUDED.name,
+                owner = EXCLUDED.owner,
+                updated_at = EXCLUDED.updated_at
+            "#,
+            peer_url,
+            snake.snake_id,
+            &snake.name,
+            &snake.owner,
+            now
+        )
+        .execute(&mut *tx)
+        .await
+        .wrap_err_with(|| format!("Failed to store snake from peer {}", peer_url))?;
+    }
+
+    tx.commit().await.wrap_err("Failed to commit transaction")?;
+    Ok(())
+}
+
+// Store results from a peer; a result synced twice is updated in place
+pub async fn upsert_peer_results(
+    pool: &PgPool,
+    peer_url: &str,
+    results: &[FederatedResult],
+) -> cja::Result<()> {
+    for result in results {
+        sqlx::query!(
+            r#"
+            INSERT INTO federated_results (peer_url, remote_game_id, finished_at, placements)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (peer_url, remote_game_id) DO UPDATE
+            SET finished_at = EXCLUDED.finished_at,
+                placements = EXCLUDED.placements
+            "#,
+            peer_url,
+            result.game_id,
+            result.finished_at,
+            serde_json::to_value(&result.placements)?
+        )
+        .execute(pool)
+        .await
+        .wrap_err_with(|| format!("Failed to store result from peer {}", peer_url))?;
+    }
+
+    Ok(())
+}
+
+// Get how far a peer's results have been synced
+pub async fn get_federation_cursor(
+    pool: &PgPool,
+    peer_url: &str,
+) -> cja::Result<Option<(chrono::DateTime<chrono::Utc>, Uuid)>> {
+    let row = sqlx::query!(
+        r#"
+        SELECT cursor_at, cursor_game_id
+        FROM federation_cursors
+        WHERE peer_url = $1
+        "#,
+        peer_url
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch sync cursor for peer {}", peer_url))?;
+
+    Ok(row.map(|row| (row.cursor_at, row.cursor_game_id)))
+}
+
+// Record how far a peer's results have been synced
+pub async fn set_federation_cursor(
+    pool: &PgPool,
+    peer_url: &str,
+    cursor: (chrono::DateTime<chrono::Utc>, Uuid),
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO federation_cursors (peer_url, cursor_at, cursor_game_id, synced_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (peer_url) DO UPDATE
+        SET cursor_at = EXCLUDED.cursor_at,
+            cursor_game_id = EXCLUDED.cursor_game_id,
+            synced_at = EXCLUDED.synced_at
+        "#,
+        peer_url,
+        cursor.0,
+        cursor.1,
+        now
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to set sync cursor for peer {}", peer_url))?;
+
+    Ok(())
+}
+
+// Get every snake synced from peers
+pub async fn get_peer_snakes(pool: &PgPool) -> cja::Result<Vec<PeerSnake>> {
+    sqlx::query_as!(
+        PeerSnake,
+        r#"
+        SELECT peer_url, remote_snake_id, name, owner
+        FROM federated_snakes
+        ORDER BY peer_url, name
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch federated snakes")
+}
+
+// Get every result synced from peers, in the order they finished
+pub async fn get_peer_results(pool: &PgPool) -> cja::Result<Vec<FederatedResult>> {
+    let rows = sqlx::query_as!(
+        PeerResultRow,
+        r#"
+        SELECT remote_game_id, finished_at, placements
+        FROM federated_results
+        ORDER BY finished_at ASC, remote_game_id ASC
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch federated results")?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(FederatedResult {
+                game_id: row.remote_game_id,
+                finished_at: row.finished_at,
+                placements: serde_json::from_value(row.placements)
+                    .wrap_err("Invalid placements in federated result")?,
+            })
+        })
+        .collect()
+}
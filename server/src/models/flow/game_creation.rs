@@ -4,7 +4,9 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::models::battlesnake::{self, Battlesnake};
-use crate::models::game::{self, CreateGameWithSnakes, GameBoardSize, GameType};
+use crate::models::game::{self, CreateGameWithSnakes, GameBoardSize, GameSettings, GameType};
+use crate::models::snake_tag::{self, SnakeSearch};
+use crate::models::user_preferences;
 use crate::state::AppState;
 
 // Flow model for the game creation process
@@ -23,7 +25,12 @@ pub struct GameCreationFlow {
 
 impl GameCreationFlow {
     // Create a new flow for a user
-    pub async fn create_for_user(pool: &PgPool, user_id: Uuid) -> cja::Result<Self> {
+    pub async fn create_for_user(
+        pool: &PgPool,
+        user_id: Uuid,
+        board_size: GameBoardSize,
+        game_type: GameType,
+    ) -> cja::Result<Self> {
         // Insert a new flow with default values
         let flow = sqlx::query_as!(
             GameCreationFlowRaw,
@@ -47,8 +54,8 @@ impl GameCreationFlow {
                 updated_at
             "#,
             user_id,
-            GameBoardSize::Medium.as_str(),
-            GameType::Standard.as_str(),
+            board_size.as_str(),
+            game_type.as_str(),
             &Vec::<Uuid>::new(),
             None::<String>
         )
@@ -215,16 +222,39 @@ impl GameCreationFlow {
         })
     }
 
-    // Create the game from the flow and enqueue a job to run it
-    pub async fn create_game_and_enqueue(&self, app_state: AppState) -> cja::Result<Uuid> {
+    // Create the game from the flow and enqueue a job to run it, with the
+    // turn limit and move timeout chosen on the page
+    pub async fn create_game_and_enqueue(
+        &self,
+        app_state: AppState,
+        max_turns: Option<i32>,
+        move_timeout_ms: Option<i32>,
+    ) -> cja::Result<Uuid> {
         let create_request = self.to_create_game_request()?;
+        let self_play = game::is_self_play(&create_request.battlesnake_ids);
 
         let game = game::create_game_with_snakes(&app_state.db, create_request)
             .await
             .wrap_err("Failed to create game")?;
 
+        // Games from the creation page are casual, and a snake playing only
+        // itself is practice with a shorter turn limit unless it picks one
+        game::set_game_settings(
+            &app_state.db,
+            game.game_id,
+            GameSettings {
+                max_turns: app_state
+                    .game_limits
+                    .turn_limit(max_turns.or(self_play.then_some(game::PRACTICE_MAX_TURNS))),
+                move_timeout_ms,
+                ranked: false,
+                ..GameSettings::default()
+            },
+        )
+        .await?;
+
         // Set enqueued_at timestamp before enqueueing the job
-        game::set_game_enqueued_at(&app_state.db, game.game_id, chrono::Utc::now())
+        game::set_game_enqueued_at(&app_state.db, game.game_id, app_state.clock.now())
             .await
             .wrap_err("Failed to set enqueued_at")?;
 
@@ -250,46 +280,30 @@ impl GameCreationFlow {
             .wrap_err("Failed to get user's battlesnakes")
     }
 
-    // Search for public battlesnakes
+    // Search for public battlesnakes by name and "tag:" filters, with snakes
+    // tagged with the user's preferred opponent tags first
     pub async fn search_public_battlesnakes(&self, pool: &PgPool) -> cja::Result<Vec<Battlesnake>> {
-        if let Some(query) = &self.search_query {
-            if query.is_empty() {
-                return Ok(Vec::new());
-            }
-
-            // Search for public battlesnakes by name (case-insensitive)
-            // This SQL query finds public battlesnakes that match the search query
-            // and are not owned by the current user
-            let battlesnakes = sqlx::query_as!(
-                Battlesnake,
-                r#"
-                SELECT
-                    battlesnake_id,
-                    user_id,
-                    name,
-                    url,
-                    visibility as "visibility: _",
-                    created_at,
-                    updated_at
-                FROM battlesnakes
-                WHERE 
-                    visibility = 'public'
-                    AND user_id != $1
-                    AND name ILIKE $2
-                ORDER BY name ASC
-                LIMIT 10
-                "#,
-                self.user_id,
-                format!("%{}%", query)
-            )
-            .fetch_all(pool)
-            .await
-            .wrap_err("Failed to search public battlesnakes")?;
+        let Some(query) = self
+            .search_query
+            .as_deref()
+            .filter(|q| !q.trim().is_empty())
+        else {
+            return Ok(Vec::new());
+        };
 
-            Ok(battlesnakes)
-        } else {
-            Ok(Vec::new())
-        }
+        let (name, tags) = snake_tag::split_search_query(query);
+        let preferences = user_preferences::get_user_preferences(pool, self.user_id).await?;
+        snake_tag::search_public_battlesnakes(
+            pool,
+            &SnakeSearch {
+                exclude_user_id: Some(self.user_id),
+                name: &name,
+                tags: &tags,
+                preferred_tags: &preferences.preferred_opponent_tags,
+                limit: 10,
+            },
+        )
+        .await
     }
 
     // Get details of the selected battlesnakes
@@ -1,10 +1,12 @@
 use color_eyre::eyre::Context as _;
 use serde::{Deserialize, Serialize};
-use sqlx::{Executor, PgPool, Postgres};
+use sqlx::{Executor, PgConnection, PgPool, Postgres};
 use std::str::FromStr;
 use uuid::Uuid;
 
+use super::battlesnake::Visibility;
 use super::game_battlesnake::AddBattlesnakeToGame;
+use crate::engine::{DEFAULT_MOVE_TIMEOUT_MS, MAX_TURNS};
 
 // Game board size enum
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
@@ -53,6 +55,7 @@ pub enum GameType {
     Royale,
     Constrictor,
     SnailMode,
+    Wrapped,
 }
 
 impl GameType {
@@ -62,6 +65,7 @@ impl GameType {
             GameType::Royale => "Royale",
             GameType::Constrictor => "Constrictor",
             GameType::SnailMode => "Snail Mode",
+            GameType::Wrapped => "Wrapped",
         }
     }
 }
@@ -75,6 +79,7 @@ impl FromStr for GameType {
             "Royale" => Ok(GameType::Royale),
             "Constrictor" => Ok(GameType::Constrictor),
             "Snail Mode" => Ok(GameType::SnailMode),
+            "Wrapped" => Ok(GameType::Wrapped),
             _ => Err(color_eyre::eyre::eyre!("Invalid game type: {}", s)),
         }
     }
@@ -111,6 +116,232 @@ impl FromStr for GameStatus {
     }
 }
 
+// How strictly snake /move responses are parsed
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MoveValidationMode {
+    // Anything that isn't exactly `{"move": "up|down|left|right", "shout": ...}`
+    // is rejected and treated as a timeout
+    Strict,
+    // Coerce casing, whitespace and extra fields into a move where possible
+    #[default]
+    Lenient,
+}
+
+impl MoveValidationMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MoveValidationMode::Strict => "strict",
+            MoveValidationMode::Lenient => "lenient",
+        }
+    }
+}
+
+impl FromStr for MoveValidationMode {
+    type Err = color_eyre::eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(MoveValidationMode::Strict),
+            "lenient" => Ok(MoveValidationMode::Lenient),
+            _ => Err(color_eyre::eyre::eyre!("Invalid validation mode: {}", s)),
+        }
+    }
+}
+
+// How snakes still alive when the turn limit ends a game are placed
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TurnLimitTiebreak {
+    // The longest snake places highest
+    #[default]
+    Longest,
+    // The snake that ate the most food places highest
+    MostFood,
+    // Every survivor shares first place
+    Draw,
+}
+
+impl TurnLimitTiebreak {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TurnLimitTiebreak::Longest => "longest",
+            TurnLimitTiebreak::MostFood => "most_food",
+            TurnLimitTiebreak::Draw => "draw",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TurnLimitTiebreak::Longest => "longest snake",
+            TurnLimitTiebreak::MostFood => "most food eaten",
+            TurnLimitTiebreak::Draw => "draw",
+        }
+    }
+}
+
+impl FromStr for TurnLimitTiebreak {
+    type Err = color_eyre::eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "longest" => Ok(TurnLimitTiebreak::Longest),
+            "most_food" => Ok(TurnLimitTiebreak::MostFood),
+            "draw" => Ok(TurnLimitTiebreak::Draw),
+            _ => Err(color_eyre::eyre::eyre!(
+                "Invalid turn limit tiebreak: {}",
+                s
+            )),
+        }
+    }
+}
+
+// What frames show of snakes after they're eliminated
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeadSnakes {
+    // Shown on the turn they die, then left out, like the official engine
+    #[default]
+    Drop,
+    // Kept in every later frame, in a faded color
+    Ghost,
+}
+
+impl DeadSnakes {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeadSnakes::Drop => "drop",
+            DeadSnakes::Ghost => "ghost",
+        }
+    }
+}
+
+impl FromStr for DeadSnakes {
+    type Err = color_eyre::eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drop" => Ok(DeadSnakes::Drop),
+            "ghost" => Ok(DeadSnakes::Ghost),
+            _ => Err(color_eyre::eyre::eyre!("Invalid dead snakes option: {}", s)),
+        }
+    }
+}
+
+// A spawn position on the board
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct SpawnPoint {
+    pub x: i32,
+    pub y: i32,
+}
+
+// Where snakes start on the board
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub enum SpawnStrategy {
+    // The official algorithm: shuffled corners and cardinal points
+    #[default]
+    Random,
+    // The same points every game, with each snake moved `rotation` points along
+    // so a series can alternate who gets which start
+    Fixed {
+        rotation: i32,
+    },
+    // Explicit positions from a scenario, one per snake in the order they joined
+    Manual(Vec<SpawnPoint>),
+}
+
+impl SpawnStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpawnStrategy::Random => "random",
+            SpawnStrategy::Fixed { .. } => "fixed",
+            SpawnStrategy::Manual(_) => "manual",
+        }
+    }
+}
+
+// Per-game options that change how the game runner plays a game
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct GameSettings {
+    pub validation_mode: MoveValidationMode,
+    // Extend each snake's /move deadline by its /start round trip
+    pub latency_compensation: bool,
+    // Private games only stream frames to owners of the snakes in them
+    pub visibility: Visibility,
+    // Turn limit, when shorter than the engine's MAX_TURNS
+    pub max_turns: Option<i32>,
+    // Each snake's /move deadline, when it isn't the engine's default
+    pub move_timeout_ms: Option<i32>,
+    // How snakes still alive at the turn limit are placed
+    pub turn_limit_tiebreak: TurnLimitTiebreak,
+    // What frames show of eliminated snakes
+    pub dead_snakes: DeadSnakes,
+    // Seed for the game's random draws; games without one are seeded from their ID
+    pub seed: Option<i64>,
+    // Only ranked games count towards ratings and leaderboards; casual and
+    // practice games don't
+    pub ranked: bool,
+    // A 1v1 game between two different snakes; ranked duels are rated on
+    // their own ladder as well as the arena's
+    pub duel: bool,
+    // Uploaded WASM ruleset that changes the board after each turn
+    #[serde(default)]
+    pub ruleset_id: Option<Uuid>,
+}
+
+// Turn limit for self-play practice games
+pub const PRACTICE_MAX_TURNS: i32 = 500;
+
+impl GameSettings {
+    // The turn the game ends on if snakes are still alive
+    pub fn turn_limit(&self) -> i32 {
+        self.max_turns.unwrap_or(MAX_TURNS).min(MAX_TURNS)
+    }
+
+    // How long each snake has to answer /move, before any latency compensation
+    pub fn move_timeout_ms(&self) -> i32 {
+        self.move_timeout_ms.unwrap_or(DEFAULT_MOVE_TIMEOUT_MS)
+    }
+}
+
+// Board a duel is played on when it doesn't ask for one
+pub const DUEL_BOARD_SIZE: GameBoardSize = GameBoardSize::Medium;
+
+// Check a duel is two different snakes on a board made for two; 19x19 leaves
+// two snakes too much room to ever meet
+pub fn validate_duel(battlesnake_ids: &[Uuid], board_size: GameBoardSize) -> Result<(), String> {
+    if battlesnake_ids.len() != 2 {
+        return Err("Duels need exactly 2 snakes".to_string());
+    }
+    if battlesnake_ids[0] == battlesnake_ids[1] {
+        return Err("A snake can't duel itself".to_string());
+    }
+    if board_size == GameBoardSize::Large {
+        return Err("Duels are played on 7x7 or 11x11 boards".to_string());
+    }
+    Ok(())
+}
+
+// Whether one snake fills every slot of a game; self-play can only be practice
+pub fn is_self_play(battlesnake_ids: &[Uuid]) -> bool {
+    battlesnake_ids.len() > 1 && battlesnake_ids.iter().all(|id| *id == battlesnake_ids[0])
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            validation_mode: MoveValidationMode::default(),
+            latency_compensation: false,
+            visibility: Visibility::default(),
+            max_turns: None,
+            move_timeout_ms: None,
+            turn_limit_tiebreak: TurnLimitTiebreak::default(),
+            dead_snakes: DeadSnakes::default(),
+            seed: None,
+            ranked: true,
+            duel: false,
+            ruleset_id: None,
+        }
+    }
+}
+
 // Game model for our application
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Game {
@@ -264,6 +495,28 @@ pub async fn delete_game(pool: &PgPool, game_id: Uuid) -> cja::Result<()> {
 pub async fn create_game_with_snakes(
     pool: &PgPool,
     data: CreateGameWithSnakes,
+) -> cja::Result<Game> {
+    // Start a transaction
+    let mut tx = pool
+        .begin()
+        .await
+        .wrap_err("Failed to start database transaction")?;
+
+    let game = insert_game_with_snakes(&mut tx, data).await?;
+
+    // Commit the transaction
+    tx.commit()
+        .await
+        .wrap_err("Failed to commit database transaction")?;
+
+    Ok(game)
+}
+
+// Insert a game and its battlesnakes on a connection that's already in a
+// transaction, so several games can be created together
+pub async fn insert_game_with_snakes(
+    conn: &mut PgConnection,
+    data: CreateGameWithSnakes,
 ) -> cja::Result<Game> {
     // Validate number of battlesnakes
     if data.battlesnake_ids.is_empty() {
@@ -278,12 +531,6 @@ pub async fn create_game_with_snakes(
         ));
     }
 
-    // Start a transaction
-    let mut tx = pool
-        .begin()
-        .await
-        .wrap_err("Failed to start database transaction")?;
-
     // Create the game
     let board_size_str = data.board_size.as_str();
     let game_type_str = data.game_type.as_str();
@@ -310,7 +557,7 @@ pub async fn create_game_with_snakes(
         game_type_str,
         status_str
     )
-    .fetch_one(&mut *tx) // Access the connection inside the transaction
+    .fetch_one(&mut *conn)
     .await
     .wrap_err("Failed to create game in database")?;
 
@@ -325,29 +572,39 @@ pub async fn create_game_with_snakes(
         updated_at: row.updated_at,
     };
 
-    // Add each battlesnake to the game
+    // Add each battlesnake to the game, as the version it's active at
+    // NOW() is fixed for the whole transaction, so use the clock to keep the
+    // snakes in the order given; spawn positions are handed out in that order
     for battlesnake_id in data.battlesnake_ids {
         sqlx::query!(
             r#"
             INSERT INTO game_battlesnakes (
                 game_id,
-                battlesnake_id
+                battlesnake_id,
+                battlesnake_version_id,
+                created_at
+            )
+            VALUES (
+                $1,
+                $2,
+                (
+                    SELECT v.battlesnake_version_id
+                    FROM battlesnakes b
+                    JOIN battlesnake_versions v
+                      ON v.battlesnake_version_id = b.active_version_id AND v.url = b.url
+                    WHERE b.battlesnake_id = $2
+                ),
+                clock_timestamp()
             )
-            VALUES ($1, $2)
             "#,
             game.game_id,
             battlesnake_id
         )
-        .execute(&mut *tx) // Access the connection inside the transaction
+        .execute(&mut *conn)
         .await
         .wrap_err_with(|| format!("Failed to add battlesnake {} to game", battlesnake_id))?;
     }
 
-    // Commit the transaction
-    tx.commit()
-        .await
-        .wrap_err("Failed to commit database transaction")?;
-
     Ok(game)
 }
 
@@ -424,12 +681,43 @@ where
     Ok(())
 }
 
+// Record a finished game's last turn and how long its turns took, from its
+// turn rows, so both outlast the rows being archived and pruned
+pub async fn record_final_turn<'e, E>(executor: E, game_id: Uuid) -> cja::Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query!(
+        r#"
+        UPDATE games
+        SET final_turn = t.final_turn, turns_duration_ms = t.duration_ms
+        FROM (
+            SELECT
+                MAX(turn_number) AS final_turn,
+                (EXTRACT(EPOCH FROM MAX(created_at) - MIN(created_at)) * 1000)::BIGINT AS duration_ms
+            FROM turns
+            WHERE game_id = $1
+        ) t
+        WHERE games.game_id = $1 AND t.final_turn IS NOT NULL
+        "#,
+        game_id
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| format!("Failed to record final turn of game {}", game_id))?;
+
+    Ok(())
+}
+
 // Update the status of a game
-pub async fn update_game_status(
-    pool: &PgPool,
+pub async fn update_game_status<'e, E>(
+    executor: E,
     game_id: Uuid,
     status: GameStatus,
-) -> cja::Result<Game> {
+) -> cja::Result<Game>
+where
+    E: Executor<'e, Database = Postgres>,
+{
     let status_str = status.as_str();
 
     let row = sqlx::query!(
@@ -449,7 +737,7 @@ pub async fn update_game_status(
         game_id,
         status_str
     )
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await
     .wrap_err_with(|| format!("Failed to update status for game {}", game_id))?;
 
@@ -472,11 +760,14 @@ pub async fn update_game_status(
 }
 
 // Set the enqueued_at timestamp for a game
-pub async fn set_game_enqueued_at(
-    pool: &PgPool,
+pub async fn set_game_enqueued_at<'e, E>(
+    executor: E,
     game_id: Uuid,
     enqueued_at: chrono::DateTime<chrono::Utc>,
-) -> cja::Result<()> {
+) -> cja::Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
     sqlx::query!(
         r#"
         UPDATE games
@@ -486,15 +777,240 @@ pub async fn set_game_enqueued_at(
         game_id,
         enqueued_at
     )
-    .execute(pool)
+    .execute(executor)
     .await
     .wrap_err_with(|| format!("Failed to set enqueued_at for game {}", game_id))?;
 
     Ok(())
 }
 
-// Get all games with their winners (if available)
-pub async fn get_all_games_with_winners(pool: &PgPool) -> cja::Result<Vec<(Game, Option<String>)>> {
+// Record when a game runner picked a game up
+pub async fn set_game_started_at(
+    pool: &PgPool,
+    game_id: Uuid,
+    started_at: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE games
+        SET started_at = $2
+        WHERE game_id = $1
+        "#,
+        game_id,
+        started_at
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to set started_at for game {}", game_id))?;
+
+    Ok(())
+}
+
+// Put a running game back to waiting, throwing away the turns and log of the
+// run that stopped, so it can be run again from the start
+pub async fn reset_game_for_rerun<'e, E>(executor: E, game_id: Uuid) -> cja::Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query!(
+        r#"
+        WITH deleted_turns AS (
+            DELETE FROM turns WHERE game_id = $1
+        ),
+        deleted_log AS (
+            DELETE FROM game_log_entries WHERE game_id = $1
+        )
+        UPDATE games
+        SET status = $2, started_at = NULL
+        WHERE game_id = $1
+        "#,
+        game_id,
+        GameStatus::Waiting.as_str()
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| format!("Failed to reset game {}", game_id))?;
+
+    Ok(())
+}
+
+// Count waiting games enqueued ahead of a game, since `since`
+// Games enqueued before `since` and still waiting are assumed stuck
+pub async fn count_games_queued_ahead(
+    pool: &PgPool,
+    game_id: Uuid,
+    enqueued_at: chrono::DateTime<chrono::Utc>,
+    since: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<i64> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM games
+        WHERE status = $1
+          AND enqueued_at >= $4
+          AND (enqueued_at, game_id) < ($3, $2)
+        "#,
+        GameStatus::Waiting.as_str(),
+        game_id,
+        enqueued_at,
+        since
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to count games queued ahead of {}", game_id))
+}
+
+// Count games game runners picked up since `since`
+pub async fn count_games_started_since(
+    pool: &PgPool,
+    since: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<i64> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM games
+        WHERE started_at >= $1
+        "#,
+        since
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err("Failed to count recently started games")
+}
+
+// A running public game, for the TV channel
+#[derive(Debug, Serialize)]
+pub struct LiveGame {
+    pub game_id: Uuid,
+    pub board_size: String,
+    pub game_type: String,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    // The latest turn with a frame, None before the first
+    pub turn: Option<i32>,
+    // In the order the snakes were added to the game
+    pub snake_names: Vec<String>,
+}
+
+// Get the running public games, the longest running first
+pub async fn get_live_public_games(pool: &PgPool, limit: i64) -> cja::Result<Vec<LiveGame>> {
+    sqlx::query_as!(
+        LiveGame,
+        r#"
+        SELECT
+            g.game_id,
+            g.board_size,
+            g.game_type,
+            g.started_at,
+            (
+                SELECT MAX(t.turn_number)
+                FROM turns t
+                WHERE t.game_id = g.game_id
+                  AND (t.frame_data IS NOT NULL OR t.frame_compressed IS NOT NULL)
+            ) AS turn,
+            ARRAY(
+                SELECT b.name
+                FROM game_battlesnakes gb
+                JOIN battlesnakes b ON b.battlesnake_id = gb.battlesnake_id
+                WHERE gb.game_id = g.game_id
+                ORDER BY gb.created_at ASC, gb.game_battlesnake_id ASC
+            ) AS "snake_names!"
+        FROM games g
+        WHERE g.status = $1 AND g.visibility = $2
+        ORDER BY g.started_at ASC NULLS LAST, g.created_at ASC, g.game_id ASC
+        LIMIT $3
+        "#,
+        GameStatus::Running.as_str(),
+        Visibility::Public.as_str(),
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch live games")
+}
+
+// Set the options a game is run with
+pub async fn set_game_settings<'e, E>(
+    executor: E,
+    game_id: Uuid,
+    settings: GameSettings,
+) -> cja::Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query!(
+        r#"
+        UPDATE games
+        SET validation_mode = $2,
+            latency_compensation = $3,
+            visibility = $4,
+            max_turns = $5,
+            turn_limit_tiebreak = $6,
+            dead_snakes = $7,
+            seed = $8,
+            ranked = $9,
+            move_timeout_ms = $10,
+            duel = $11,
+            ruleset_id = $12
+        WHERE game_id = $1
+        "#,
+        game_id,
+        settings.validation_mode.as_str(),
+        settings.latency_compensation,
+        settings.visibility.as_str(),
+        settings.max_turns,
+        settings.turn_limit_tiebreak.as_str(),
+        settings.dead_snakes.as_str(),
+        settings.seed,
+        settings.ranked,
+        settings.move_timeout_ms,
+        settings.duel,
+        settings.ruleset_id
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| format!("Failed to set settings for game {}", game_id))?;
+
+    Ok(())
+}
+
+// Get the options a game is run with
+pub async fn get_game_settings(pool: &PgPool, game_id: Uuid) -> cja::Result<GameSettings> {
+    let row = sqlx::query!(
+        r#"
+        SELECT validation_mode, latency_compensation, visibility, max_turns, turn_limit_tiebreak,
+            dead_snakes, seed, ranked, move_timeout_ms, duel, ruleset_id
+        FROM games
+        WHERE game_id = $1
+        "#,
+        game_id
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch settings for game {}", game_id))?;
+
+    Ok(GameSettings {
+        validation_mode: MoveValidationMode::from_str(&row.validation_mode)?,
+        latency_compensation: row.latency_compensation,
+        visibility: Visibility::from_str(&row.visibility)?,
+        max_turns: row.max_turns,
+        move_timeout_ms: row.move_timeout_ms,
+        turn_limit_tiebreak: TurnLimitTiebreak::from_str(&row.turn_limit_tiebreak)?,
+        dead_snakes: DeadSnakes::from_str(&row.dead_snakes)?,
+        seed: row.seed,
+        ranked: row.ranked,
+        duel: row.duel,
+        ruleset_id: row.ruleset_id,
+    })
+}
+
+// Get all games with the names of the snakes that placed first. That's one
+// name for a win, more than one for a draw and none while a game is running.
+// Private games are only listed for `viewer_id` when they own a snake in them,
+// the same rule as `can_view_game`.
+pub async fn get_all_games_with_winners(
+    pool: &PgPool,
+    viewer_id: Uuid,
+) -> cja::Result<Vec<(Game, Vec<String>)>> {
     let rows = sqlx::query_as!(
         GameWithWinnerRow,
         r#"
@@ -510,36 +1026,602 @@ pub async fn get_all_games_with_winners(pool: &PgPool) -> cja::Result<Vec<(Game,
         FROM games g
         LEFT JOIN game_battlesnakes gb ON g.game_id = gb.game_id AND gb.placement = 1
         LEFT JOIN battlesnakes b ON gb.battlesnake_id = b.battlesnake_id
+        WHERE g.visibility = 'public'
+            OR EXISTS (
+                SELECT 1
+                FROM game_battlesnakes owned
+                JOIN battlesnakes ob ON owned.battlesnake_id = ob.battlesnake_id
+                WHERE owned.game_id = g.game_id AND ob.user_id = $1
+            )
         ORDER BY g.created_at DESC
-        "#
+        "#,
+        viewer_id
     )
     .fetch_all(pool)
     .await
     .wrap_err("Failed to fetch games with winners from database")?;
 
-    let games_with_winners = rows
-        .into_iter()
-        .map(|row| {
-            let board_size = GameBoardSize::from_str(&row.board_size)
-                .wrap_err_with(|| format!("Invalid board size: {}", row.board_size))?;
-            let game_type = GameType::from_str(&row.game_type)
-                .wrap_err_with(|| format!("Invalid game type: {}", row.game_type))?;
-            let status = GameStatus::from_str(&row.status)
-                .wrap_err_with(|| format!("Invalid game status: {}", row.status))?;
+    // A draw joins a row per snake sharing first place, so fold those into one
+    let mut games_with_winners: Vec<(Game, Vec<String>)> = Vec::new();
+    let mut positions: std::collections::HashMap<Uuid, usize> = std::collections::HashMap::new();
+    for row in rows {
+        if let Some(&position) = positions.get(&row.game_id) {
+            games_with_winners[position].1.extend(row.winner_name);
+            continue;
+        }
 
-            let game = Game {
-                game_id: row.game_id,
-                board_size,
-                game_type,
-                status,
-                enqueued_at: row.enqueued_at,
-                created_at: row.created_at,
-                updated_at: row.updated_at,
-            };
+        let board_size = GameBoardSize::from_str(&row.board_size)
+            .wrap_err_with(|| format!("Invalid board size: {}", row.board_size))?;
+        let game_type = GameType::from_str(&row.game_type)
+            .wrap_err_with(|| format!("Invalid game type: {}", row.game_type))?;
+        let status = GameStatus::from_str(&row.status)
+            .wrap_err_with(|| format!("Invalid game status: {}", row.status))?;
 
-            Ok((game, row.winner_name))
-        })
-        .collect::<cja::Result<Vec<_>>>()?;
+        let game = Game {
+            game_id: row.game_id,
+            board_size,
+            game_type,
+            status,
+            enqueued_at: row.enqueued_at,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        };
+
+        positions.insert(row.game_id, games_with_winners.len());
+        games_with_winners.push((game, row.winner_name.into_iter().collect()));
+    }
 
     Ok(games_with_winners)
 }
+
+// Count the ranked games a battlesnake has been entered in since a time
+pub async fn count_ranked_games_since(
+    pool: &PgPool,
+    battlesnake_id: Uuid,
+    since: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<i64> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(DISTINCT g.game_id) AS "count!"
+        FROM games g
+        JOIN game_battlesnakes gb ON gb.game_id = g.game_id
+        WHERE gb.battlesnake_id = $1 AND g.ranked AND g.created_at >= $2
+        "#,
+        battlesnake_id,
+        since
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to count ranked games for snake {}", battlesnake_id))
+}
+
+// Record the digest certifying a finished game's frames
+pub async fn set_game_result_digest<'e, E>(
+    executor: E,
+    game_id: Uuid,
+    digest: &str,
+) -> cja::Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query!(
+        r#"
+        UPDATE games
+        SET result_digest = $2
+        WHERE game_id = $1
+        "#,
+        game_id,
+        digest
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| format!("Failed to set result digest for game {}", game_id))?;
+
+    Ok(())
+}
+
+// Get the digest certifying a game's frames, if it has finished
+pub async fn get_game_result_digest(pool: &PgPool, game_id: Uuid) -> cja::Result<Option<String>> {
+    let digest: Option<String> = sqlx::query_scalar!(
+        r#"
+        SELECT result_digest
+        FROM games
+        WHERE game_id = $1
+        "#,
+        game_id
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch result digest for game {}", game_id))?;
+
+    Ok(digest)
+}
+
+// Where a branch game was re-simulated from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct GameBranchOrigin {
+    pub parent_game_id: Uuid,
+    pub branched_from_turn: i32,
+}
+
+// A branch of a game, for listing alongside its parent
+#[derive(Debug, Serialize)]
+pub struct GameBranch {
+    pub game_id: Uuid,
+    pub branched_from_turn: i32,
+    pub status: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Mark a game as a branch of another, asked for by `branched_by_user_id`
+pub async fn set_game_branch_origin<'e, E>(
+    executor: E,
+    game_id: Uuid,
+    origin: GameBranchOrigin,
+    branched_by_user_id: Uuid,
+) -> cja::Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query!(
+        r#"
+        UPDATE games
+        SET parent_game_id = $2,
+            branched_from_turn = $3,
+            branched_by_user_id = $4
+        WHERE game_id = $1
+        "#,
+        game_id,
+        origin.parent_game_id,
+        origin.branched_from_turn,
+        branched_by_user_id
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| format!("Failed to set branch origin for game {}", game_id))?;
+
+    Ok(())
+}
+
+// Count the branches a user has asked for since a time
+pub async fn count_branches_since(
+    pool: &PgPool,
+    user_id: Uuid,
+    since: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<i64> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM games
+        WHERE branched_by_user_id = $1 AND created_at >= $2
+        "#,
+        user_id,
+        since
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to count branches for user {}", user_id))
+}
+
+// Get the game and turn a game was branched from, if it's a branch
+pub async fn get_game_branch_origin(
+    pool: &PgPool,
+    game_id: Uuid,
+) -> cja::Result<Option<GameBranchOrigin>> {
+    let row = sqlx::query!(
+        r#"
+        SELECT parent_game_id, branched_from_turn
+        FROM games
+        WHERE game_id = $1
+        "#,
+        game_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch branch origin for game {}", game_id))?;
+
+    Ok(row.and_then(|row| {
+        Some(GameBranchOrigin {
+            parent_game_id: row.parent_game_id?,
+            branched_from_turn: row.branched_from_turn?,
+        })
+    }))
+}
+
+// Get the branches of a game, oldest first
+pub async fn get_game_branches(
+    pool: &PgPool,
+    parent_game_id: Uuid,
+) -> cja::Result<Vec<GameBranch>> {
+    sqlx::query_as!(
+        GameBranch,
+        r#"
+        SELECT game_id, branched_from_turn AS "branched_from_turn!", status, created_at
+        FROM games
+        WHERE parent_game_id = $1 AND branched_from_turn IS NOT NULL
+        ORDER BY created_at ASC
+        "#,
+        parent_game_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch branches of game {}", parent_game_id))
+}
+
+// Set how a game's snakes are placed at the start
+pub async fn set_game_spawn_strategy(
+    pool: &PgPool,
+    game_id: Uuid,
+    spawn: &SpawnStrategy,
+) -> cja::Result<()> {
+    let (rotation, positions) = match spawn {
+        SpawnStrategy::Random => (0, None),
+        SpawnStrategy::Fixed { rotation } => (*rotation, None),
+        SpawnStrategy::Manual(points) => (
+            0,
+            Some(serde_json::to_value(points).wrap_err("Failed to serialize spawn positions")?),
+        ),
+    };
+
+    sqlx::query!(
+        r#"
+        UPDATE games
+        SET spawn_strategy = $2,
+            spawn_rotation = $3,
+            spawn_positions = $4
+        WHERE game_id = $1
+        "#,
+        game_id,
+        spawn.as_str(),
+        rotation,
+        positions
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to set spawn strategy for game {}", game_id))?;
+
+    Ok(())
+}
+
+// Get how a game's snakes are placed at the start
+pub async fn get_game_spawn_strategy(pool: &PgPool, game_id: Uuid) -> cja::Result<SpawnStrategy> {
+    let row = sqlx::query!(
+        r#"
+        SELECT spawn_strategy, spawn_rotation, spawn_positions
+        FROM games
+        WHERE game_id = $1
+        "#,
+        game_id
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch spawn strategy for game {}", game_id))?;
+
+    match row.spawn_strategy.as_str() {
+        "random" => Ok(SpawnStrategy::Random),
+        "fixed" => Ok(SpawnStrategy::Fixed {
+            rotation: row.spawn_rotation,
+        }),
+        "manual" => {
+            let points = serde_json::from_value(row.spawn_positions.unwrap_or_default())
+                .wrap_err_with(|| format!("Invalid spawn positions for game {}", game_id))?;
+            Ok(SpawnStrategy::Manual(points))
+        }
+        _ => Err(color_eyre::eyre::eyre!(
+            "Invalid spawn strategy: {}",
+            row.spawn_strategy
+        )),
+    }
+}
+
+// Mark a game as the mirror of another: the same snakes with their spawns swapped
+pub async fn set_game_mirror_of(pool: &PgPool, game_id: Uuid, mirror_of: Uuid) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE games
+        SET mirror_of_game_id = $2
+        WHERE game_id = $1
+        "#,
+        game_id,
+        mirror_of
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to set mirror of game {}", game_id))?;
+
+    Ok(())
+}
+
+// Get both games of the mirror match a game is part of, the original first
+pub async fn get_game_mirror_pair(
+    pool: &PgPool,
+    game_id: Uuid,
+) -> cja::Result<Option<(Uuid, Uuid)>> {
+    let row = sqlx::query!(
+        r#"
+        SELECT mirror_of_game_id AS "mirror_of_game_id!", game_id
+        FROM games
+        WHERE mirror_of_game_id IS NOT NULL
+          AND (game_id = $1 OR mirror_of_game_id = $1)
+        "#,
+        game_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch mirror of game {}", game_id))?;
+
+    Ok(row.map(|row| (row.mirror_of_game_id, row.game_id)))
+}
+
+// Record the tiebreak that placed the snakes still alive at the turn limit
+pub async fn set_game_tiebreak_applied<'e, E>(
+    executor: E,
+    game_id: Uuid,
+    tiebreak: TurnLimitTiebreak,
+) -> cja::Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query!(
+        r#"
+        UPDATE games
+        SET tiebreak_applied = $2
+        WHERE game_id = $1
+        "#,
+        game_id,
+        tiebreak.as_str()
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| format!("Failed to set tiebreak for game {}", game_id))?;
+
+    Ok(())
+}
+
+// Get the tiebreak that placed the survivors, if the turn limit ended the game
+// with more than one snake alive
+pub async fn get_game_tiebreak_applied(
+    pool: &PgPool,
+    game_id: Uuid,
+) -> cja::Result<Option<TurnLimitTiebreak>> {
+    let tiebreak = sqlx::query_scalar!(
+        r#"
+        SELECT tiebreak_applied
+        FROM games
+        WHERE game_id = $1
+        "#,
+        game_id
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch tiebreak for game {}", game_id))?;
+
+    tiebreak
+        .map(|t| TurnLimitTiebreak::from_str(&t))
+        .transpose()
+}
+
+// A game imported from the Engine and archived to storage
+#[derive(Debug, Clone)]
+pub struct ArchivedGame {
+    pub game_id: Uuid,
+    pub engine_game_id: String,
+    // The zstd-compressed GameExport in storage
+    pub gcs_path: String,
+    pub archive_version: Option<i32>,
+    // When the game was played in the Engine, which its archive's path is dated by
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Get an archived game by its Engine game ID, None until it's been archived
+pub async fn get_archived_game(
+    pool: &PgPool,
+    engine_game_id: &str,
+) -> cja::Result<Option<ArchivedGame>> {
+    sqlx::query_as!(
+        ArchivedGame,
+        r#"
+        SELECT game_id, engine_game_id AS "engine_game_id!", gcs_path AS "gcs_path!",
+            archive_version, created_at
+        FROM games
+        WHERE engine_game_id = $1
+          AND archived_at IS NOT NULL
+          AND gcs_path IS NOT NULL
+        "#,
+        engine_game_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch archived game {}", engine_game_id))
+}
+
+// Archived games played from `start` up to `end`, oldest first
+pub async fn get_archived_games_between(
+    pool: &PgPool,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<Vec<ArchivedGame>> {
+    sqlx::query_as!(
+        ArchivedGame,
+        r#"
+        SELECT game_id, engine_game_id AS "engine_game_id!", gcs_path AS "gcs_path!",
+            archive_version, created_at
+        FROM games
+        WHERE engine_game_id IS NOT NULL
+          AND archived_at IS NOT NULL
+          AND gcs_path IS NOT NULL
+          AND created_at >= $1
+          AND created_at < $2
+        ORDER BY created_at, engine_game_id
+        "#,
+        start,
+        end
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch archived games")
+}
+
+// Where a game's frames are archived, for any archived game: one backed up
+// from the Engine, or one played here whose turns were pruned
+#[derive(Debug, Clone)]
+pub struct GameArchive {
+    pub gcs_path: String,
+    pub archive_version: Option<i32>,
+    // Each snake's moves, for games played here that had any
+    pub moves_gcs_path: Option<String>,
+}
+
+// A game's archive, None unless it's been archived
+pub async fn get_game_archive(pool: &PgPool, game_id: Uuid) -> cja::Result<Option<GameArchive>> {
+    sqlx::query_as!(
+        GameArchive,
+        r#"
+        SELECT gcs_path AS "gcs_path!", archive_version, moves_gcs_path
+        FROM games
+        WHERE game_id = $1
+          AND archived_at IS NOT NULL
+          AND gcs_path IS NOT NULL
+        "#,
+        game_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch archive of game {}", game_id))
+}
+
+// Games played here that finished before `finished_before` and haven't been
+// archived, oldest first
+pub async fn get_games_to_archive(
+    pool: &PgPool,
+    finished_before: chrono::DateTime<chrono::Utc>,
+    limit: i64,
+) -> cja::Result<Vec<Uuid>> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT game_id
+        FROM games
+        WHERE status = 'finished'
+          AND engine_game_id IS NULL
+          AND archived_at IS NULL
+          AND updated_at < $1
+        ORDER BY updated_at
+        LIMIT $2
+        "#,
+        finished_before,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch games to archive")
+}
+
+// Record that a game played here is archived at `gcs_path`, with its moves at
+// `moves_gcs_path`. Only updates a game that isn't archived yet, and returns
+// whether it did.
+pub async fn mark_game_archived<'e, E>(
+    executor: E,
+    game_id: Uuid,
+    gcs_path: &str,
+    moves_gcs_path: Option<&str>,
+    archive_version: i32,
+    archived_at: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<bool>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        r#"
+        UPDATE games
+        SET archived_at = $2, gcs_path = $3, archive_version = $4, moves_gcs_path = $5
+        WHERE game_id = $1
+          AND archived_at IS NULL
+        "#,
+        game_id,
+        archived_at,
+        gcs_path,
+        archive_version,
+        moves_gcs_path
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| format!("Failed to mark game {} archived", game_id))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Engine game IDs of archived games written in a format older than `version`
+pub async fn get_outdated_archives(
+    pool: &PgPool,
+    version: i32,
+    limit: i64,
+) -> cja::Result<Vec<String>> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT engine_game_id AS "engine_game_id!"
+        FROM games
+        WHERE engine_game_id IS NOT NULL
+          AND archived_at IS NOT NULL
+          AND gcs_path IS NOT NULL
+          AND COALESCE(archive_version, 1) < $1
+        ORDER BY archived_at, engine_game_id
+        LIMIT $2
+        "#,
+        version,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch outdated archives")
+}
+
+// How many archived games are written in a format older than `version`
+pub async fn count_outdated_archives(pool: &PgPool, version: i32) -> cja::Result<i64> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM games
+        WHERE engine_game_id IS NOT NULL
+          AND archived_at IS NOT NULL
+          AND gcs_path IS NOT NULL
+          AND COALESCE(archive_version, 1) < $1
+        "#,
+        version
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err("Failed to count outdated archives")
+}
+
+// Point an archived game at its re-encoded archive. Only updates the row if it
+// still points at the archive that was re-encoded, and returns whether it did.
+pub async fn set_archive_version(
+    pool: &PgPool,
+    engine_game_id: &str,
+    old_gcs_path: &str,
+    old_version: Option<i32>,
+    gcs_path: &str,
+    version: i32,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE games
+        SET gcs_path = $4, archive_version = $5, updated_at = $6
+        WHERE engine_game_id = $1
+          AND gcs_path = $2
+          AND archive_version IS NOT DISTINCT FROM $3
+        "#,
+        engine_game_id,
+        old_gcs_path,
+        old_version,
+        gcs_path,
+        version,
+        now
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to update archive of game {}", engine_game_id))?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -1,10 +1,12 @@
 use color_eyre::eyre::Context as _;
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{Executor, PgPool, Postgres};
+use std::collections::HashMap;
 use std::str::FromStr;
 use uuid::Uuid;
 
 use super::game::{Game, GameBoardSize, GameStatus, GameType};
+use crate::snake_client::SnakeInfo;
 
 // GameBattlesnake model for our application
 #[derive(Debug, Serialize, Deserialize)]
@@ -30,7 +32,7 @@ pub struct SetGameResult {
 }
 
 // Extended GameBattlesnake with battlesnake details
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameBattlesnakeWithDetails {
     pub game_battlesnake_id: Uuid,
     pub game_id: Uuid,
@@ -44,13 +46,66 @@ pub struct GameBattlesnakeWithDetails {
     pub user_id: Uuid,
 }
 
+// How a single snake instance in a game is identified to players and frame consumers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnakeInstance {
+    // "{battlesnake_id}-{n}", numbered per battlesnake within the game
+    pub instance_id: String,
+    // The battlesnake's name, with " (n)" appended when it's entered more than once
+    pub display_name: String,
+}
+
+// Label every snake instance in a game, keyed by game_battlesnake_id
+//
+// The same battlesnake can be entered into a game several times. Each entry already
+// has its own game_battlesnake_id, but they share a name, which makes them impossible
+// to tell apart in the viewer. Instances are numbered by (created_at, game_battlesnake_id)
+// so the labels are the same however the list was ordered.
+pub fn snake_instances(
+    battlesnakes: &[GameBattlesnakeWithDetails],
+) -> HashMap<Uuid, SnakeInstance> {
+    let mut ordered: Vec<&GameBattlesnakeWithDetails> = battlesnakes.iter().collect();
+    ordered.sort_by_key(|bs| (bs.created_at, bs.game_battlesnake_id));
+
+    let mut totals: HashMap<Uuid, usize> = HashMap::new();
+    for bs in &ordered {
+        *totals.entry(bs.battlesnake_id).or_default() += 1;
+    }
+
+    let mut seen: HashMap<Uuid, usize> = HashMap::new();
+    ordered
+        .into_iter()
+        .map(|bs| {
+            let n = seen.entry(bs.battlesnake_id).or_default();
+            *n += 1;
+
+            let display_name = if totals[&bs.battlesnake_id] > 1 {
+                format!("{} ({})", bs.name, n)
+            } else {
+                bs.name.clone()
+            };
+
+            (
+                bs.game_battlesnake_id,
+                SnakeInstance {
+                    instance_id: format!("{}-{}", bs.battlesnake_id, n),
+                    display_name,
+                },
+            )
+        })
+        .collect()
+}
+
 // Database functions for game battlesnake management
 
 // Get all battlesnakes in a game
-pub async fn get_battlesnakes_by_game_id(
-    pool: &PgPool,
+pub async fn get_battlesnakes_by_game_id<'e, E>(
+    executor: E,
     game_id: Uuid,
-) -> cja::Result<Vec<GameBattlesnakeWithDetails>> {
+) -> cja::Result<Vec<GameBattlesnakeWithDetails>>
+where
+    E: Executor<'e, Database = Postgres>,
+{
     let game_battlesnakes = sqlx::query_as!(
         GameBattlesnakeWithDetails,
         r#"
@@ -71,7 +126,7 @@ pub async fn get_battlesnakes_by_game_id(
         "#,
         game_id
     )
-    .fetch_all(pool)
+    .fetch_all(executor)
     .await
     .wrap_err("Failed to fetch battlesnakes for game from database")?;
 
@@ -243,11 +298,14 @@ pub async fn set_game_result(
 }
 
 // Set the result for a specific game_battlesnake (supports duplicate snakes)
-pub async fn set_game_result_by_id(
-    pool: &PgPool,
+pub async fn set_game_result_by_id<'e, E>(
+    executor: E,
     game_battlesnake_id: Uuid,
     placement: i32,
-) -> cja::Result<GameBattlesnake> {
+) -> cja::Result<GameBattlesnake>
+where
+    E: Executor<'e, Database = Postgres>,
+{
     // Validate placement is between 1 and 4
     if !(1..=4).contains(&placement) {
         return Err(cja::color_eyre::eyre::eyre!(
@@ -272,13 +330,87 @@ pub async fn set_game_result_by_id(
         game_battlesnake_id,
         placement
     )
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await
     .wrap_err("Failed to set game result")?;
 
     Ok(game_battlesnake)
 }
 
+// Record what a snake's GET / returned when its game started
+pub async fn set_snake_info<'e, E>(
+    executor: E,
+    game_battlesnake_id: Uuid,
+    info: &SnakeInfo,
+) -> cja::Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query!(
+        r#"
+        UPDATE game_battlesnakes
+        SET apiversion = $2, author = $3, color = $4, head = $5, tail = $6
+        WHERE game_battlesnake_id = $1
+        "#,
+        game_battlesnake_id,
+        info.apiversion.as_deref(),
+        info.author.as_deref(),
+        info.color.as_deref(),
+        info.head.as_deref(),
+        info.tail.as_deref()
+    )
+    .execute(executor)
+    .await
+    .wrap_err("Failed to set snake info")?;
+
+    Ok(())
+}
+
+struct SnakeInfoRow {
+    game_battlesnake_id: Uuid,
+    apiversion: Option<String>,
+    author: Option<String>,
+    color: Option<String>,
+    head: Option<String>,
+    tail: Option<String>,
+}
+
+// Get the recorded info of each snake in a game that has any, by game_battlesnake_id
+pub async fn get_snake_info_by_game_id(
+    pool: &PgPool,
+    game_id: Uuid,
+) -> cja::Result<HashMap<Uuid, SnakeInfo>> {
+    let rows = sqlx::query_as!(
+        SnakeInfoRow,
+        r#"
+        SELECT game_battlesnake_id, apiversion, author, color, head, tail
+        FROM game_battlesnakes
+        WHERE game_id = $1
+          AND COALESCE(apiversion, author, color, head, tail) IS NOT NULL
+        "#,
+        game_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch snake info for game")?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            (
+                row.game_battlesnake_id,
+                SnakeInfo {
+                    apiversion: row.apiversion,
+                    author: row.author,
+                    color: row.color,
+                    head: row.head,
+                    tail: row.tail,
+                },
+            )
+        })
+        .collect())
+}
+
 // Game history entry for snake profile page
 #[derive(Debug)]
 pub struct GameHistoryEntry {
@@ -288,16 +420,196 @@ pub struct GameHistoryEntry {
     pub status: GameStatus,
     pub placement: Option<i32>,
     pub snake_count: i64,
+    // None for a draw, when more than one snake placed first
     pub winner_name: Option<String>,
+    pub draw: bool,
+    // Played in the ranked pool rather than a casual or practice game
+    pub ranked: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+// One row of a snake's game history, before draws are folded together
+#[derive(Debug)]
+struct GameHistoryRow {
+    game_id: Uuid,
+    board_size: String,
+    game_type: String,
+    status: String,
+    placement: Option<i32>,
+    snake_count: i64,
+    winner_name: Option<String>,
+    ranked: bool,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// A snake's placement in a finished game, for computing ratings
+#[derive(Debug, Clone)]
+pub struct FinishedPlacement {
+    pub game_id: Uuid,
+    pub battlesnake_id: Uuid,
+    pub placement: i32,
+}
+
+// Get every placement in finished games, grouped by game with the oldest game first
+// Branches are left out, since they replay moves rather than play them
+// and unranked (practice) games, which don't count towards ratings
+pub async fn get_finished_game_placements(pool: &PgPool) -> cja::Result<Vec<FinishedPlacement>> {
+    let placements = sqlx::query_as!(
+        FinishedPlacement,
+        r#"
+        SELECT gb.game_id, gb.battlesnake_id, gb.placement AS "placement!"
+        FROM game_battlesnakes gb
+        JOIN games g ON g.game_id = gb.game_id
+        WHERE g.status = $1 AND gb.placement IS NOT NULL AND g.parent_game_id IS NULL
+          AND g.ranked
+        ORDER BY g.created_at ASC, g.game_id ASC, gb.placement ASC
+        "#,
+        GameStatus::Finished.as_str()
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch finished game placements")?;
+
+    Ok(placements)
+}
+
+// Get every placement in finished ranked duels, grouped by game with the
+// oldest game first, for the duel ladder
+pub async fn get_duel_placements(pool: &PgPool) -> cja::Result<Vec<FinishedPlacement>> {
+    sqlx::query_as!(
+        FinishedPlacement,
+        r#"
+        SELECT gb.game_id, gb.battlesnake_id, gb.placement AS "placement!"
+        FROM game_battlesnakes gb
+        JOIN games g ON g.game_id = gb.game_id
+        WHERE g.status = $1 AND gb.placement IS NOT NULL AND g.parent_game_id IS NULL
+          AND g.ranked AND g.duel
+        ORDER BY g.created_at ASC, g.game_id ASC, gb.placement ASC
+        "#,
+        GameStatus::Finished.as_str()
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch duel placements")
+}
+
+// Get a snake's placements in its latest finished games, newest first
+// Branches and unranked games are left out, like they are from ratings
+pub async fn get_recent_finished_placements(
+    pool: &PgPool,
+    battlesnake_id: Uuid,
+    limit: i64,
+) -> cja::Result<Vec<i32>> {
+    let placements = sqlx::query_scalar!(
+        r#"
+        SELECT gb.placement AS "placement!"
+        FROM game_battlesnakes gb
+        JOIN games g ON g.game_id = gb.game_id
+        WHERE gb.battlesnake_id = $1
+          AND g.status = $2
+          AND gb.placement IS NOT NULL
+          AND g.parent_game_id IS NULL
+          AND g.ranked
+        ORDER BY g.created_at DESC, g.game_id DESC
+        LIMIT $3
+        "#,
+        battlesnake_id,
+        GameStatus::Finished.as_str(),
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| {
+        format!(
+            "Failed to fetch recent placements for snake {}",
+            battlesnake_id
+        )
+    })?;
+
+    Ok(placements)
+}
+
+// A finished game two snakes both played in, with each one's best placement
+#[derive(Debug, Clone)]
+pub struct HeadToHeadGame {
+    pub game_id: Uuid,
+    pub placement_a: i32,
+    pub placement_b: i32,
+    // The game's last turn, None if it never stored one
+    pub turns: Option<i32>,
+}
+
+// Get the finished games two snakes played against each other, newest first
+// Branches are left out, and private games only count for viewers who own a
+// snake in them, like everywhere else private games are shown. Either snake
+// can be narrowed to the games one of its versions played.
+pub async fn get_head_to_head_games(
+    pool: &PgPool,
+    snake_a: Uuid,
+    snake_b: Uuid,
+    version_a: Option<Uuid>,
+    version_b: Option<Uuid>,
+    viewer_id: Uuid,
+) -> cja::Result<Vec<HeadToHeadGame>> {
+    sqlx::query_as!(
+        HeadToHeadGame,
+        r#"
+        WITH a AS (
+            SELECT game_id, MIN(placement) AS placement
+            FROM game_battlesnakes
+            WHERE battlesnake_id = $1
+              AND ($5::UUID IS NULL OR battlesnake_version_id = $5)
+            GROUP BY game_id
+        ), b AS (
+            SELECT game_id, MIN(placement) AS placement
+            FROM game_battlesnakes
+            WHERE battlesnake_id = $2
+              AND ($6::UUID IS NULL OR battlesnake_version_id = $6)
+            GROUP BY game_id
+        )
+        SELECT g.game_id, a.placement AS "placement_a!", b.placement AS "placement_b!", g.final_turn AS turns
+        FROM a
+        JOIN b ON b.game_id = a.game_id
+        JOIN games g ON g.game_id = a.game_id
+        WHERE g.status = $3
+          AND g.parent_game_id IS NULL
+          AND a.placement IS NOT NULL
+          AND b.placement IS NOT NULL
+          AND (
+            g.visibility = 'public'
+            OR EXISTS (
+                SELECT 1
+                FROM game_battlesnakes gb
+                JOIN battlesnakes s ON s.battlesnake_id = gb.battlesnake_id
+                WHERE gb.game_id = g.game_id AND s.user_id = $4
+            )
+          )
+        ORDER BY g.created_at DESC, g.game_id DESC
+        "#,
+        snake_a,
+        snake_b,
+        GameStatus::Finished.as_str(),
+        viewer_id,
+        version_a,
+        version_b
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| {
+        format!(
+            "Failed to fetch head-to-head games for snakes {} and {}",
+            snake_a, snake_b
+        )
+    })
+}
+
 // Get game history for a battlesnake (for profile page)
 pub async fn get_game_history_for_battlesnake(
     pool: &PgPool,
     battlesnake_id: Uuid,
 ) -> cja::Result<Vec<GameHistoryEntry>> {
-    let rows = sqlx::query!(
+    let rows = sqlx::query_as!(
+        GameHistoryRow,
         r#"
         SELECT
             g.game_id,
@@ -307,6 +619,7 @@ pub async fn get_game_history_for_battlesnake(
             gb_self.placement,
             (SELECT COUNT(*) FROM game_battlesnakes gb2 WHERE gb2.game_id = g.game_id) as "snake_count!",
             winner_b.name as "winner_name?",
+            g.ranked,
             g.created_at
         FROM games g
         JOIN game_battlesnakes gb_self ON g.game_id = gb_self.game_id AND gb_self.battlesnake_id = $1
@@ -320,28 +633,50 @@ pub async fn get_game_history_for_battlesnake(
     .await
     .wrap_err("Failed to fetch game history for battlesnake")?;
 
-    let entries = rows
-        .into_iter()
-        .map(|row| {
-            let board_size = GameBoardSize::from_str(&row.board_size)
-                .wrap_err_with(|| format!("Invalid board size: {}", row.board_size))?;
-            let game_type = GameType::from_str(&row.game_type)
-                .wrap_err_with(|| format!("Invalid game type: {}", row.game_type))?;
-            let status = GameStatus::from_str(&row.status)
-                .wrap_err_with(|| format!("Invalid game status: {}", row.status))?;
+    // A draw joins a row per snake sharing first place, so fold those into
+    // one entry, keeping each distinct winner until we know whether it's a draw
+    let mut entries: Vec<GameHistoryEntry> = Vec::new();
+    let mut winners: Vec<Vec<String>> = Vec::new();
+    let mut positions: HashMap<(Uuid, Option<i32>), usize> = HashMap::new();
+    for row in rows {
+        if let Some(&position) = positions.get(&(row.game_id, row.placement)) {
+            if let Some(name) = row.winner_name
+                && !winners[position].contains(&name)
+            {
+                winners[position].push(name);
+            }
+            continue;
+        }
+
+        let board_size = GameBoardSize::from_str(&row.board_size)
+            .wrap_err_with(|| format!("Invalid board size: {}", row.board_size))?;
+        let game_type = GameType::from_str(&row.game_type)
+            .wrap_err_with(|| format!("Invalid game type: {}", row.game_type))?;
+        let status = GameStatus::from_str(&row.status)
+            .wrap_err_with(|| format!("Invalid game status: {}", row.status))?;
+
+        positions.insert((row.game_id, row.placement), entries.len());
+        winners.push(row.winner_name.into_iter().collect());
+        entries.push(GameHistoryEntry {
+            game_id: row.game_id,
+            board_size,
+            game_type,
+            status,
+            placement: row.placement,
+            snake_count: row.snake_count,
+            winner_name: None,
+            draw: false,
+            ranked: row.ranked,
+            created_at: row.created_at,
+        });
+    }
 
-            Ok(GameHistoryEntry {
-                game_id: row.game_id,
-                board_size,
-                game_type,
-                status,
-                placement: row.placement,
-                snake_count: row.snake_count,
-                winner_name: row.winner_name,
-                created_at: row.created_at,
-            })
-        })
-        .collect::<cja::Result<Vec<_>>>()?;
+    for (entry, mut names) in entries.iter_mut().zip(winners) {
+        entry.draw = names.len() > 1;
+        if !entry.draw {
+            entry.winner_name = names.pop();
+        }
+    }
 
     Ok(entries)
 }
@@ -361,3 +696,56 @@ pub async fn get_game_with_battlesnakes(
 
     Ok((game, battlesnakes))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(battlesnake_id: Uuid, name: &str, seconds: i64) -> GameBattlesnakeWithDetails {
+        let created_at = chrono::DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap();
+        GameBattlesnakeWithDetails {
+            game_battlesnake_id: Uuid::new_v4(),
+            game_id: Uuid::nil(),
+            battlesnake_id,
+            placement: None,
+            created_at,
+            updated_at: created_at,
+            name: name.to_string(),
+            url: "http://example.com".to_string(),
+            user_id: Uuid::nil(),
+        }
+    }
+
+    #[test]
+    fn test_snake_instances_unique_snakes_keep_their_names() {
+        let a = entry(Uuid::new_v4(), "Alpha", 0);
+        let b = entry(Uuid::new_v4(), "Beta", 1);
+        let instances = snake_instances(&[a.clone(), b.clone()]);
+
+        assert_eq!(instances[&a.game_battlesnake_id].display_name, "Alpha");
+        assert_eq!(
+            instances[&a.game_battlesnake_id].instance_id,
+            format!("{}-1", a.battlesnake_id)
+        );
+        assert_eq!(instances[&b.game_battlesnake_id].display_name, "Beta");
+    }
+
+    #[test]
+    fn test_snake_instances_numbers_duplicates() {
+        let snake_id = Uuid::new_v4();
+        let first = entry(snake_id, "Dupe", 0);
+        let second = entry(snake_id, "Dupe", 1);
+        let other = entry(Uuid::new_v4(), "Other", 2);
+
+        // Order of the input (e.g. by placement) doesn't change the numbering
+        let instances = snake_instances(&[second.clone(), other.clone(), first.clone()]);
+
+        let first = &instances[&first.game_battlesnake_id];
+        let second = &instances[&second.game_battlesnake_id];
+        assert_eq!(first.display_name, "Dupe (1)");
+        assert_eq!(first.instance_id, format!("{snake_id}-1"));
+        assert_eq!(second.display_name, "Dupe (2)");
+        assert_eq!(second.instance_id, format!("{snake_id}-2"));
+        assert_eq!(instances[&other.game_battlesnake_id].display_name, "Other");
+    }
+}
@@ -0,0 +1,181 @@
+use color_eyre::eyre::Context as _;
+use serde::Serialize;
+use sqlx::{Executor, PgPool, Postgres};
+use uuid::Uuid;
+
+use super::game::GameStatus;
+
+// The worker running a game, and when it last said it was still at it
+#[derive(Debug, Clone, Serialize)]
+pub struct GameLease {
+    pub game_id: Uuid,
+    pub lease_id: Uuid,
+    pub worker_id: String,
+    pub acquired_at: chrono::DateTime<chrono::Utc>,
+    pub heartbeat_at: chrono::DateTime<chrono::Utc>,
+    pub released_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub expired: bool,
+}
+
+// A worker's games, for the admin workers listing
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerLoad {
+    pub worker_id: String,
+    // Games it holds a lease on now
+    pub running_games: i64,
+    // Games it finished, or gave up on, since the cutoff
+    pub finished_games: i64,
+    pub last_heartbeat_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Take the lease on a game for a worker, replacing an earlier run's lease if
+// it's been released or hasn't had a heartbeat since `cutoff`. Returns the new
+// lease's ID, or None while another run still holds a live lease.
+pub async fn acquire_game_lease(
+    pool: &PgPool,
+    game_id: Uuid,
+    worker_id: &str,
+    now: chrono::DateTime<chrono::Utc>,
+    cutoff: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<Option<Uuid>> {
+    sqlx::query_scalar!(
+        r#"
+        INSERT INTO game_leases (game_id, worker_id, acquired_at, heartbeat_at)
+        VALUES ($1, $2, $3, $3)
+        ON CONFLICT (game_id) DO UPDATE
+        SET lease_id = uuid_generate_v4(),
+            worker_id = EXCLUDED.worker_id,
+            acquired_at = EXCLUDED.acquired_at,
+            heartbeat_at = EXCLUDED.heartbeat_at,
+            released_at = NULL,
+            expired = FALSE
+        WHERE game_leases.released_at IS NOT NULL OR game_leases.heartbeat_at < $4
+        RETURNING lease_id
+        "#,
+        game_id,
+        worker_id,
+        now,
+        cutoff
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to acquire lease on game {}", game_id))
+}
+
+// Record a heartbeat. False if the lease has been released or taken over.
+pub async fn heartbeat_game_lease(
+    pool: &PgPool,
+    lease_id: Uuid,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE game_leases
+        SET heartbeat_at = $2
+        WHERE lease_id = $1 AND released_at IS NULL
+        "#,
+        lease_id,
+        now
+    )
+    .execute(pool)
+    .await
+    .wrap_err("Failed to heartbeat game lease")?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Release a lease at the end of its run; a no-op if it's already gone
+pub async fn release_game_lease(
+    pool: &PgPool,
+    lease_id: Uuid,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE game_leases
+        SET released_at = $2
+        WHERE lease_id = $1 AND released_at IS NULL
+        "#,
+        lease_id,
+        now
+    )
+    .execute(pool)
+    .await
+    .wrap_err("Failed to release game lease")?;
+
+    Ok(())
+}
+
+// Release, as expired, the running games' leases that haven't had a heartbeat
+// since `cutoff`. Returns the expired leases.
+pub async fn expire_stale_game_leases<'e, E>(
+    executor: E,
+    cutoff: chrono::DateTime<chrono::Utc>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<Vec<GameLease>>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        GameLease,
+        r#"
+        UPDATE game_leases l
+        SET released_at = $3, expired = TRUE
+        FROM games g
+        WHERE l.game_id = g.game_id
+            AND g.status = $1
+            AND l.released_at IS NULL
+            AND l.heartbeat_at < $2
+        RETURNING l.game_id, l.lease_id, l.worker_id, l.acquired_at, l.heartbeat_at,
+            l.released_at, l.expired
+        "#,
+        GameStatus::Running.as_str(),
+        cutoff,
+        now
+    )
+    .fetch_all(executor)
+    .await
+    .wrap_err("Failed to expire stale game leases")
+}
+
+// Get the leases of games being run now, the oldest first
+pub async fn get_active_game_leases(pool: &PgPool) -> cja::Result<Vec<GameLease>> {
+    sqlx::query_as!(
+        GameLease,
+        r#"
+        SELECT game_id, lease_id, worker_id, acquired_at, heartbeat_at, released_at, expired
+        FROM game_leases
+        WHERE released_at IS NULL
+        ORDER BY acquired_at ASC
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch active game leases")
+}
+
+// Get each worker's load: the games it's running now and those it's let go
+// since `since`. Workers with neither aren't listed.
+pub async fn get_worker_loads(
+    pool: &PgPool,
+    since: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<Vec<WorkerLoad>> {
+    sqlx::query_as!(
+        WorkerLoad,
+        r#"
+        SELECT
+            worker_id,
+            COUNT(*) FILTER (WHERE released_at IS NULL) AS "running_games!",
+            COUNT(*) FILTER (WHERE released_at IS NOT NULL AND NOT expired) AS "finished_games!",
+            MAX(heartbeat_at) AS "last_heartbeat_at!"
+        FROM game_leases
+        WHERE released_at IS NULL OR released_at >= $1
+        GROUP BY worker_id
+        ORDER BY worker_id ASC
+        "#,
+        since
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch worker loads")
+}
@@ -0,0 +1,121 @@
+use color_eyre::eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Severity of a game log entry
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum GameLogLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+impl GameLogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GameLogLevel::Info => "info",
+            GameLogLevel::Warn => "warn",
+            GameLogLevel::Error => "error",
+        }
+    }
+}
+
+impl FromStr for GameLogLevel {
+    type Err = color_eyre::eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(GameLogLevel::Info),
+            "warn" => Ok(GameLogLevel::Warn),
+            "error" => Ok(GameLogLevel::Error),
+            _ => Err(color_eyre::eyre::eyre!("Invalid game log level: {}", s)),
+        }
+    }
+}
+
+/// A line in a game's log
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GameLogEntry {
+    pub game_log_entry_id: Uuid,
+    pub game_id: Uuid,
+    pub turn_number: Option<i32>,
+    pub game_battlesnake_id: Option<Uuid>,
+    pub level: String,
+    pub message: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// For recording a line in a game's log
+#[derive(Debug, Clone)]
+pub struct CreateGameLogEntry {
+    pub turn_number: Option<i32>,
+    pub game_battlesnake_id: Option<Uuid>,
+    pub level: GameLogLevel,
+    pub message: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Append entries to a game's log in a single insert
+pub async fn create_game_log_entries(
+    pool: &PgPool,
+    game_id: Uuid,
+    entries: &[CreateGameLogEntry],
+) -> cja::Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let turn_numbers: Vec<Option<i32>> = entries.iter().map(|e| e.turn_number).collect();
+    let game_battlesnake_ids: Vec<Option<Uuid>> =
+        entries.iter().map(|e| e.game_battlesnake_id).collect();
+    let levels: Vec<&str> = entries.iter().map(|e| e.level.as_str()).collect();
+    let messages: Vec<&str> = entries.iter().map(|e| e.message.as_str()).collect();
+    let created_ats: Vec<chrono::DateTime<chrono::Utc>> =
+        entries.iter().map(|e| e.created_at).collect();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO game_log_entries (game_id, turn_number, game_battlesnake_id, level, message, created_at)
+        SELECT $1, * FROM UNNEST($2::INTEGER[], $3::UUID[], $4::TEXT[], $5::TEXT[], $6::TIMESTAMPTZ[])
+        "#,
+        game_id,
+        &turn_numbers as &[Option<i32>],
+        &game_battlesnake_ids as &[Option<Uuid>],
+        &levels as &[&str],
+        &messages as &[&str],
+        &created_ats
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to write log entries for game {}", game_id))?;
+
+    Ok(())
+}
+
+/// Get a game's log in the order it was written
+pub async fn get_game_log_entries(pool: &PgPool, game_id: Uuid) -> cja::Result<Vec<GameLogEntry>> {
+    let entries = sqlx::query_as!(
+        GameLogEntry,
+        r#"
+        SELECT
+            game_log_entry_id,
+            game_id,
+            turn_number,
+            game_battlesnake_id,
+            level,
+            message,
+            created_at
+        FROM game_log_entries
+        WHERE game_id = $1
+        ORDER BY created_at ASC, turn_number ASC NULLS FIRST
+        "#,
+        game_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch game log")?;
+
+    Ok(entries)
+}
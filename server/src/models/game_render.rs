@@ -0,0 +1,98 @@
+use color_eyre::eyre::Context as _;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// A game's replay GIF, queued or rendered
+#[derive(Debug, Clone)]
+pub struct GameRender {
+    pub game_id: Uuid,
+    // Where the GIF is in GCS, when it was uploaded there
+    pub gcs_path: Option<String>,
+    // The GIF itself, when there's no GCS bucket
+    pub gif: Option<Vec<u8>>,
+    pub size_bytes: Option<i32>,
+    pub frame_count: Option<i32>,
+    pub requested_at: chrono::DateTime<chrono::Utc>,
+    // None while the render is queued
+    pub rendered_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+// Get a game's render, if one has been queued
+pub async fn get_game_render(pool: &PgPool, game_id: Uuid) -> cja::Result<Option<GameRender>> {
+    sqlx::query_as!(
+        GameRender,
+        r#"
+        SELECT game_id, gcs_path, gif, size_bytes, frame_count, requested_at, rendered_at
+        FROM game_renders
+        WHERE game_id = $1
+        "#,
+        game_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch render for game {}", game_id))
+}
+
+// Mark a game's render as queued. Returns false if it's already rendered, or
+// was queued less than an hour ago and should still be on its way.
+pub async fn request_game_render<'e, E>(
+    executor: E,
+    game_id: Uuid,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<bool>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO game_renders (game_id, requested_at)
+        VALUES ($1, $2)
+        ON CONFLICT (game_id) DO UPDATE SET requested_at = EXCLUDED.requested_at
+        WHERE game_renders.rendered_at IS NULL
+          AND game_renders.requested_at < $3
+        "#,
+        game_id,
+        now,
+        now - chrono::Duration::hours(1)
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| format!("Failed to queue render for game {}", game_id))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Record a finished render, stored either in GCS or in the row
+pub async fn save_game_render(
+    pool: &PgPool,
+    game_id: Uuid,
+    gcs_path: Option<&str>,
+    gif: Option<&[u8]>,
+    size_bytes: usize,
+    frame_count: usize,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO game_renders (game_id, gcs_path, gif, size_bytes, frame_count, rendered_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (game_id) DO UPDATE SET
+            gcs_path = EXCLUDED.gcs_path,
+            gif = EXCLUDED.gif,
+            size_bytes = EXCLUDED.size_bytes,
+            frame_count = EXCLUDED.frame_count,
+            rendered_at = EXCLUDED.rendered_at
+        "#,
+        game_id,
+        gcs_path,
+        gif,
+        size_bytes as i32,
+        frame_count as i32,
+        now
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to save render for game {}", game_id))?;
+
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use color_eyre::eyre::Context as _;
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::turn_metrics::{Stage, StageTimings, StageTotal};
+
+// How long one stage of processing a turn took over a game
+#[derive(Debug, Clone)]
+pub struct GameStageTiming {
+    pub stage: String,
+    pub turns: i32,
+    pub total_us: i64,
+    pub max_us: i64,
+}
+
+// Save a game's stage timings, replacing any from an earlier run of it
+pub async fn save_game_stage_timings(
+    pool: &PgPool,
+    game_id: Uuid,
+    timings: &StageTimings,
+) -> cja::Result<()> {
+    let mut tx = pool.begin().await.wrap_err("Failed to start transaction")?;
+
+    for (stage, total) in timings.totals() {
+        sqlx::query!(
+            r#"
+            INSERT INTO game_stage_timings (game_id, stage, turns, total_us, max_us)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (game_id, stage) DO UPDATE
+            SET turns = EXCLUDED.turns,
+                total_us = EXCLUDED.total_us,
+                max_us = EXCLUDED.max_us
+            "#,
+            game_id,
+            stage.as_str(),
+            total.turns,
+            total.total.as_micros() as i64,
+            total.max.as_micros() as i64
+        )
+        .execute(&mut *tx)
+        .await
+        .wrap_err_with(|| {
+            format!(
+                "Failed to save {} timing for game {}",
+                stage.as_str(),
+                game_id
+            )
+        })?;
+    }
+
+    tx.commit().await.wrap_err("Failed to commit transaction")?;
+    Ok(())
+}
+
+// Get a game's stage timings, in the order the stages run; empty if the game
+// hasn't finished since timings were recorded
+pub async fn get_game_stage_timings(
+    pool: &PgPool,
+    game_id: Uuid,
+) -> cja::Result<Vec<(Stage, StageTotal)>> {
+    let rows = sqlx::query_as!(
+        GameStageTiming,
+        r#"
+        SELECT stage, turns, total_us, max_us
+        FROM game_stage_timings
+        WHERE game_id = $1
+        "#,
+        game_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch stage timings for game {}", game_id))?;
+
+    let mut timings: Vec<(Stage, StageTotal)> = rows
+        .into_iter()
+        .filter_map(|row| {
+            let stage = Stage::from_name(&row.stage)?;
+            Some((
+                stage,
+                StageTotal {
+                    turns: row.turns,
+                    total: Duration::from_micros(row.total_us.max(0) as u64),
+                    max: Duration::from_micros(row.max_us.max(0) as u64),
+                },
+            ))
+        })
+        .collect();
+    timings.sort_by_key(|(stage, _)| *stage as usize);
+    Ok(timings)
+}
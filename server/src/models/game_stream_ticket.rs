@@ -0,0 +1,91 @@
+use chrono::{DateTime, Duration, Utc};
+use color_eyre::eyre::Context as _;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::api_token::{generate_token_secret, hash_token};
+
+/// How long a stream ticket can be used to open a private game's event stream
+///
+/// Long enough to cover a board viewer page left open for a whole game; the
+/// ticket is only checked when a connection is opened.
+pub const GAME_STREAM_TICKET_TTL_SECONDS: i64 = 60 * 60;
+
+/// A freshly issued ticket - the raw secret is only available here
+pub struct NewGameStreamTicket {
+    pub ticket: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Issue a ticket letting `user_id` open `game_id`'s event stream without a header or cookie
+pub async fn create_game_stream_ticket(
+    pool: &PgPool,
+    game_id: Uuid,
+    user_id: Uuid,
+    now: DateTime<Utc>,
+) -> cja::Result<NewGameStreamTicket> {
+    let ticket = generate_token_secret();
+    let expires_at = now + Duration::seconds(GAME_STREAM_TICKET_TTL_SECONDS);
+
+    sqlx::query!(
+        r#"
+        INSERT INTO game_stream_tickets (ticket_hash, game_id, user_id, expires_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        hash_token(&ticket),
+        game_id,
+        user_id,
+        expires_at
+    )
+    .execute(pool)
+    .await
+    .wrap_err("Failed to create game stream ticket")?;
+
+    Ok(NewGameStreamTicket { ticket, expires_at })
+}
+
+/// The user a ticket was issued to, if it's for this game and hasn't expired
+pub async fn validate_game_stream_ticket(
+    pool: &PgPool,
+    ticket: &str,
+    game_id: Uuid,
+    now: DateTime<Utc>,
+) -> cja::Result<Option<Uuid>> {
+    let user_id = sqlx::query_scalar!(
+        r#"
+        SELECT user_id
+        FROM game_stream_tickets
+        WHERE ticket_hash = $1 AND game_id = $2 AND expires_at > $3
+        "#,
+        hash_token(ticket),
+        game_id,
+        now
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to validate game stream ticket")?;
+
+    Ok(user_id)
+}
+
+/// Delete tickets that expired before `now`, returning how many were deleted
+///
+/// A ticket is issued on every view of a private game, so they're swept up
+/// rather than left to accumulate.
+pub async fn delete_expired_game_stream_tickets(
+    pool: &PgPool,
+    now: DateTime<Utc>,
+) -> cja::Result<u64> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM game_stream_tickets
+        WHERE expires_at <= $1
+        "#,
+        now
+    )
+    .execute(pool)
+    .await
+    .wrap_err("Failed to delete expired game stream tickets")?;
+
+    Ok(result.rows_affected())
+}
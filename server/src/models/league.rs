@@ -0,0 +1,368 @@
+use color_eyre::eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::league_awards::LeagueAnnouncement;
+use crate::models::game::GameStatus;
+use crate::models::game_battlesnake::FinishedPlacement;
+
+// An organization or tournament with its own private leaderboard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct League {
+    pub league_id: Uuid,
+    pub name: String,
+    pub owner_user_id: Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// A user in a league
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeagueMember {
+    pub user_id: Uuid,
+    pub github_login: String,
+    pub joined_at: chrono::DateTime<chrono::Utc>,
+}
+
+// A snake that has played in a league's games
+#[derive(Debug, Clone)]
+pub struct LeagueSnake {
+    pub battlesnake_id: Uuid,
+    pub name: String,
+}
+
+// Create a league, with its owner as the first member
+pub async fn create_league(pool: &PgPool, owner_user_id: Uuid, name: &str) -> cja::Result<League> {
+    let mut tx = pool.begin().await.wrap_err("Failed to start transaction")?;
+
+    let league = sqlx::query_as!(
+        League,
+        r#"
+        INSERT INTO leagues (name, owner_user_id)
+        VALUES ($1, $2)
+        RETURNING league_id, name, owner_user_id, created_at
+        "#,
+        name,
+        owner_user_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .wrap_err("Failed to create league")?;
+
+    sqlx::query!(
+        "INSERT INTO league_members (league_id, user_id) VALUES ($1, $2)",
+        league.league_id,
+        owner_user_id
+    )
+    .execute(&mut *tx)
+    .await
+    .wrap_err("Failed to add league owner as a member")?;
+
+    tx.commit().await.wrap_err("Failed to commit transaction")?;
+    Ok(league)
+}
+
+// Get a league by ID
+pub async fn get_league_by_id(pool: &PgPool, league_id: Uuid) -> cja::Result<Option<League>> {
+    sqlx::query_as!(
+        League,
+        r#"
+        SELECT league_id, name, owner_user_id, created_at
+        FROM leagues
+        WHERE league_id = $1
+        "#,
+        league_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch league {}", league_id))
+}
+
+// Get the leagues a user is a member of, by name
+pub async fn get_leagues_for_user(pool: &PgPool, user_id: Uuid) -> cja::Result<Vec<League>> {
+    sqlx::query_as!(
+        League,
+        r#"
+        SELECT l.league_id, l.name, l.owner_user_id, l.created_at
+        FROM leagues l
+        JOIN league_members m ON m.league_id = l.league_id
+        WHERE m.user_id = $1
+        ORDER BY l.name ASC, l.created_at ASC
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch leagues for user")
+}
+
+// Whether a user is a member of a league
+pub async fn is_league_member(pool: &PgPool, league_id: Uuid, user_id: Uuid) -> cja::Result<bool> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM league_members WHERE league_id = $1 AND user_id = $2
+        ) AS "exists!"
+        "#,
+        league_id,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err("Failed to check league membership")
+}
+
+// Add a user to a league; a no-op if they're already in it
+pub async fn add_league_member(pool: &PgPool, league_id: Uuid, user_id: Uuid) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO league_members (league_id, user_id)
+        VALUES ($1, $2)
+        ON CONFLICT DO NOTHING
+        "#,
+        league_id,
+        user_id
+    )
+    .execute(pool)
+    .await
+    .wrap_err("Failed to add league member")?;
+
+    Ok(())
+}
+
+// Get a league's members, in the order they joined
+pub async fn get_league_members(pool: &PgPool, league_id: Uuid) -> cja::Result<Vec<LeagueMember>> {
+    sqlx::query_as!(
+        LeagueMember,
+        r#"
+        SELECT m.user_id, u.github_login, m.joined_at
+        FROM league_members m
+        JOIN users u ON u.user_id = m.user_id
+        WHERE m.league_id = $1
+        ORDER BY m.joined_at ASC
+        "#,
+        league_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch members of league {}", league_id))
+}
+
+// Of the given snakes, the ones whose owners aren't in the league
+pub async fn get_snakes_outside_league(
+    pool: &PgPool,
+    league_id: Uuid,
+    battlesnake_ids: &[Uuid],
+) -> cja::Result<Vec<Uuid>> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT b.battlesnake_id
+        FROM battlesnakes b
+        WHERE b.battlesnake_id = ANY($2)
+          AND NOT EXISTS (
+              SELECT 1 FROM league_members m WHERE m.league_id = $1 AND m.user_id = b.user_id
+          )
+        ORDER BY b.battlesnake_id
+        "#,
+        league_id,
+        battlesnake_ids
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to check snakes against league members")
+}
+
+// Record the league a game is played in
+pub async fn set_game_league(pool: &PgPool, game_id: Uuid, league_id: Uuid) -> cja::Result<()> {
+    sqlx::query!(
+        "UPDATE games SET league_id = $2 WHERE game_id = $1",
+        game_id,
+        league_id
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to set league for game {}", game_id))?;
+
+    Ok(())
+}
+
+// Get every placement in a league's finished games, grouped by game with the
+// oldest game first, like get_finished_game_placements for the whole arena
+pub async fn get_league_placements(
+    pool: &PgPool,
+    league_id: Uuid,
+) -> cja::Result<Vec<FinishedPlacement>> {
+    sqlx::query_as!(
+        FinishedPlacement,
+        r#"
+        SELECT gb.game_id, gb.battlesnake_id, gb.placement AS "placement!"
+        FROM game_battlesnakes gb
+        JOIN games g ON g.game_id = gb.game_id
+        WHERE g.league_id = $1 AND g.status = $2 AND gb.placement IS NOT NULL
+          AND g.parent_game_id IS NULL
+        ORDER BY g.created_at ASC, g.game_id ASC, gb.placement ASC
+        "#,
+        league_id,
+        GameStatus::Finished.as_str()
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch placements for league {}", league_id))
+}
+
+// Get the snakes that have played in a league's games, private ones included
+pub async fn get_league_snakes(pool: &PgPool, league_id: Uuid) -> cja::Result<Vec<LeagueSnake>> {
+    sqlx::query_as!(
+        LeagueSnake,
+        r#"
+        SELECT DISTINCT b.battlesnake_id, b.name
+        FROM battlesnakes b
+        JOIN game_battlesnakes gb ON gb.battlesnake_id = b.battlesnake_id
+        JOIN games g ON g.game_id = gb.game_id
+        WHERE g.league_id = $1
+        "#,
+        league_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch snakes for league {}", league_id))
+}
+
+// A prize for finishing a league in a given place
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeaguePrize {
+    pub place: i32,
+    pub description: String,
+}
+
+// What a league's organizers are awarding, and its announcement once it's completed
+#[derive(Debug, Clone)]
+pub struct LeagueAwards {
+    pub prizes: Vec<LeaguePrize>,
+    pub writeup: Option<String>,
+    pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub announcement: Option<LeagueAnnouncement>,
+}
+
+#[derive(Debug)]
+struct LeagueAwardsRow {
+    prizes: serde_json::Value,
+    writeup: Option<String>,
+    completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    announcement: Option<serde_json::Value>,
+}
+
+// A snake's placement in one of a league's finished games
+#[derive(Debug, Clone)]
+pub struct LeagueGamePlacement {
+    pub game_id: Uuid,
+    pub played_at: chrono::DateTime<chrono::Utc>,
+    pub battlesnake_id: Uuid,
+    pub name: String,
+    pub placement: i32,
+    // The game's last turn, None if it never stored one
+    pub turns: Option<i32>,
+}
+
+// Get a league's prizes, write-up and announcement
+pub async fn get_league_awards(pool: &PgPool, league_id: Uuid) -> cja::Result<LeagueAwards> {
+    let row = sqlx::query_as!(
+        LeagueAwardsRow,
+        r#"
+        SELECT prizes, writeup, completed_at, announcement
+        FROM leagues
+        WHERE league_id = $1
+        "#,
+        league_id
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch awards for league {}", league_id))?;
+
+    Ok(LeagueAwards {
+        prizes: serde_json::from_value(row.prizes).wrap_err("Invalid prizes in league")?,
+        writeup: row.writeup,
+        completed_at: row.completed_at,
+        announcement: row
+            .announcement
+            .map(serde_json::from_value)
+            .transpose()
+            .wrap_err("Invalid announcement in league")?,
+    })
+}
+
+// Set a league's prizes and write-up; false if it's already completed
+pub async fn set_league_awards(
+    pool: &PgPool,
+    league_id: Uuid,
+    prizes: &[LeaguePrize],
+    writeup: Option<&str>,
+) -> cja::Result<bool> {
+    let prizes = serde_json::to_value(prizes).wrap_err("Failed to serialize prizes")?;
+    let result = sqlx::query!(
+        r#"
+        UPDATE leagues
+        SET prizes = $2, writeup = $3
+        WHERE league_id = $1 AND completed_at IS NULL
+        "#,
+        league_id,
+        prizes,
+        writeup
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to set awards for league {}", league_id))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Mark a league completed with its announcement; false if it already was
+pub async fn complete_league(
+    pool: &PgPool,
+    league_id: Uuid,
+    announcement: &LeagueAnnouncement,
+) -> cja::Result<bool> {
+    let announcement_json =
+        serde_json::to_value(announcement).wrap_err("Failed to serialize announcement")?;
+    let result = sqlx::query!(
+        r#"
+        UPDATE leagues
+        SET completed_at = $2, announcement = $3
+        WHERE league_id = $1 AND completed_at IS NULL
+        "#,
+        league_id,
+        announcement.completed_at,
+        announcement_json
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to complete league {}", league_id))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Get every placement in a league's finished games with the snakes' names,
+// oldest game first
+pub async fn get_league_game_placements(
+    pool: &PgPool,
+    league_id: Uuid,
+) -> cja::Result<Vec<LeagueGamePlacement>> {
+    sqlx::query_as!(
+        LeagueGamePlacement,
+        r#"
+        SELECT g.game_id, g.created_at AS played_at, gb.battlesnake_id, b.name,
+               gb.placement AS "placement!", g.final_turn AS turns
+        FROM game_battlesnakes gb
+        JOIN games g ON g.game_id = gb.game_id
+        JOIN battlesnakes b ON b.battlesnake_id = gb.battlesnake_id
+        WHERE g.league_id = $1 AND g.status = $2 AND gb.placement IS NOT NULL
+          AND g.parent_game_id IS NULL
+        ORDER BY g.created_at ASC, g.game_id ASC, gb.placement ASC
+        "#,
+        league_id,
+        GameStatus::Finished.as_str()
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch game results for league {}", league_id))
+}
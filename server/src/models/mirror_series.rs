@@ -0,0 +1,134 @@
+use color_eyre::eyre::Context as _;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// A best-of-N series of mirror matches between two snakes
+#[derive(Debug, Clone, Serialize)]
+pub struct MirrorSeries {
+    pub series_id: Uuid,
+    pub user_id: Uuid,
+    pub best_of: i32,
+    // In the order every round's first game is created with
+    pub battlesnake_ids: Vec<Uuid>,
+    pub league_id: Option<Uuid>,
+    pub rounds_started: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Create a series; its first round is started by the caller
+pub async fn create_mirror_series(
+    pool: &PgPool,
+    user_id: Uuid,
+    best_of: i32,
+    battlesnake_ids: &[Uuid],
+    league_id: Option<Uuid>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<MirrorSeries> {
+    sqlx::query_as!(
+        MirrorSeries,
+        r#"
+        INSERT INTO mirror_series (user_id, best_of, battlesnake_ids, league_id, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $5)
+        RETURNING series_id, user_id, best_of, battlesnake_ids, league_id, rounds_started, created_at
+        "#,
+        user_id,
+        best_of,
+        battlesnake_ids,
+        league_id,
+        now
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err("Failed to create mirror series")
+}
+
+pub async fn get_mirror_series(
+    pool: &PgPool,
+    series_id: Uuid,
+) -> cja::Result<Option<MirrorSeries>> {
+    sqlx::query_as!(
+        MirrorSeries,
+        r#"
+        SELECT series_id, user_id, best_of, battlesnake_ids, league_id, rounds_started, created_at
+        FROM mirror_series
+        WHERE series_id = $1
+        "#,
+        series_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch mirror series {}", series_id))
+}
+
+// Record the series a game is a round of
+pub async fn set_game_mirror_series(
+    pool: &PgPool,
+    game_id: Uuid,
+    series_id: Uuid,
+) -> cja::Result<()> {
+    sqlx::query!(
+        "UPDATE games SET mirror_series_id = $2 WHERE game_id = $1",
+        game_id,
+        series_id
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to set mirror series for game {}", game_id))?;
+
+    Ok(())
+}
+
+// Get the series a game is a round of, if any
+pub async fn get_game_mirror_series_id(pool: &PgPool, game_id: Uuid) -> cja::Result<Option<Uuid>> {
+    let series_id = sqlx::query_scalar!(
+        "SELECT mirror_series_id FROM games WHERE game_id = $1",
+        game_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch mirror series of game {}", game_id))?;
+
+    Ok(series_id.flatten())
+}
+
+// Get the first game of each of a series' rounds, oldest round first
+pub async fn get_series_round_game_ids(pool: &PgPool, series_id: Uuid) -> cja::Result<Vec<Uuid>> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT game_id
+        FROM games
+        WHERE mirror_series_id = $1 AND mirror_of_game_id IS NULL
+        ORDER BY created_at, game_id
+        "#,
+        series_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch rounds of mirror series {}", series_id))
+}
+
+// Claim the next round of a series, if `rounds_started` rounds have been
+// started so far. Returns false when another game already claimed it.
+pub async fn claim_next_round(
+    pool: &PgPool,
+    series_id: Uuid,
+    rounds_started: i32,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE mirror_series
+        SET rounds_started = rounds_started + 1, updated_at = $3
+        WHERE series_id = $1 AND rounds_started = $2
+        "#,
+        series_id,
+        rounds_started,
+        now
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to claim next round of mirror series {}", series_id))?;
+
+    Ok(result.rows_affected() == 1)
+}
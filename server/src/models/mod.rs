@@ -1,8 +1,30 @@
+pub mod announcement;
 pub mod api_token;
+pub mod auth_lockout;
 pub mod battlesnake;
+pub mod battlesnake_version;
+pub mod check;
+pub mod demo_game;
+pub mod federation;
 pub mod flow;
 pub mod game;
 pub mod game_battlesnake;
+pub mod game_lease;
+pub mod game_log;
+pub mod game_render;
+pub mod game_stage_timing;
+pub mod game_stream_ticket;
+pub mod league;
+pub mod mirror_series;
+pub mod notification;
+pub mod rating;
+pub mod ruleset;
 pub mod session;
+pub mod snake_alert;
+pub mod snake_github_repo;
+pub mod snake_health;
+pub mod snake_tag;
+pub mod snake_template;
 pub mod turn;
 pub mod user;
+pub mod user_preferences;
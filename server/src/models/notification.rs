@@ -0,0 +1,163 @@
+use color_eyre::eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// What a notification is about
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    // A game one of the user's snakes played in finished
+    GameFinished,
+    // One of the user's snakes started failing its health checks
+    SnakeUnhealthy,
+    // One of the user's win rate alerts fired
+    WinRateAlert,
+    // A league the user is in was completed and its winners announced
+    LeagueCompleted,
+}
+
+impl NotificationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::GameFinished => "game_finished",
+            NotificationKind::SnakeUnhealthy => "snake_unhealthy",
+            NotificationKind::WinRateAlert => "win_rate_alert",
+            NotificationKind::LeagueCompleted => "league_completed",
+        }
+    }
+}
+
+// A notification in a user's inbox
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub notification_id: Uuid,
+    pub kind: String,
+    pub title: String,
+    pub body: String,
+    // Where the notification leads, as a path without the BASE_URL prefix
+    pub link: Option<String>,
+    pub read_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// A notification to add to a user's inbox
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewNotification {
+    pub user_id: Uuid,
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    pub link: Option<String>,
+}
+
+// Add notifications to their users' inboxes
+pub async fn create_notifications(
+    pool: &PgPool,
+    notifications: &[NewNotification],
+) -> cja::Result<()> {
+    let mut tx = pool.begin().await.wrap_err("Failed to start transaction")?;
+
+    for notification in notifications {
+        sqlx::query!(
+            r#"
+            INSERT INTO notifications (user_id, kind, title, body, link)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            notification.user_id,
+            notification.kind.as_str(),
+            &notification.title,
+            &notification.body,
+            notification.link.as_deref()
+        )
+        .execute(&mut *tx)
+        .await
+        .wrap_err_with(|| format!("Failed to notify user {}", notification.user_id))?;
+    }
+
+    tx.commit().await.wrap_err("Failed to commit transaction")?;
+    Ok(())
+}
+
+// Get a user's latest notifications, newest first
+pub async fn get_notifications(
+    pool: &PgPool,
+    user_id: Uuid,
+    unread_only: bool,
+    limit: i64,
+) -> cja::Result<Vec<Notification>> {
+    sqlx::query_as!(
+        Notification,
+        r#"
+        SELECT notification_id, kind, title, body, link, read_at, created_at
+        FROM notifications
+        WHERE user_id = $1 AND (NOT $2 OR read_at IS NULL)
+        ORDER BY created_at DESC, notification_id DESC
+        LIMIT $3
+        "#,
+        user_id,
+        unread_only,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch notifications for user {}", user_id))
+}
+
+// Count a user's unread notifications
+pub async fn count_unread_notifications(pool: &PgPool, user_id: Uuid) -> cja::Result<i64> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM notifications
+        WHERE user_id = $1 AND read_at IS NULL
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to count unread notifications for user {}", user_id))
+}
+
+// Mark one of a user's notifications read, returning it; None if the user
+// doesn't have it
+pub async fn mark_notification_read(
+    pool: &PgPool,
+    user_id: Uuid,
+    notification_id: Uuid,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<Option<Notification>> {
+    sqlx::query_as!(
+        Notification,
+        r#"
+        UPDATE notifications
+        SET read_at = COALESCE(read_at, $3)
+        WHERE notification_id = $2 AND user_id = $1
+        RETURNING notification_id, kind, title, body, link, read_at, created_at
+        "#,
+        user_id,
+        notification_id,
+        now
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to mark notification {} read", notification_id))
+}
+
+// Mark every one of a user's notifications read, returning how many were unread
+pub async fn mark_all_notifications_read(
+    pool: &PgPool,
+    user_id: Uuid,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<u64> {
+    let result = sqlx::query!(
+        "UPDATE notifications SET read_at = $2 WHERE user_id = $1 AND read_at IS NULL",
+        user_id,
+        now
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to mark notifications read for user {}", user_id))?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::Context as _;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::game::GameStatus;
+use crate::models::game_battlesnake::FinishedPlacement;
+use crate::stats::SnakeRating;
+
+// A recalculation of the stored ratings from the full game history
+#[derive(Debug, Clone)]
+pub struct RatingRecalculation {
+    pub recalculation_id: Uuid,
+    // "running", "completed" or "failed"
+    pub status: String,
+    pub algorithm_version: i32,
+    pub games_total: i32,
+    pub games_rated: i32,
+    // Every finished game created before this was replayed
+    pub rated_before: Option<chrono::DateTime<chrono::Utc>>,
+    pub error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug)]
+struct StoredRatingRow {
+    battlesnake_id: Uuid,
+    rating: f64,
+    games: i32,
+    wins: i32,
+    rated_games: i32,
+    places_gained: f64,
+}
+
+// Start a recalculation, unless one is already running
+// A running one that hasn't reported progress in ten minutes is assumed dead
+pub async fn create_recalculation(
+    pool: &PgPool,
+    algorithm_version: i32,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<Option<RatingRecalculation>> {
+    let mut tx = pool.begin().await.wrap_err("Failed to start transaction")?;
+
+    // Serialize concurrent starts so only one can see no running recalculation
+    sqlx::query!("LOCK TABLE rating_recalculations IN SHARE ROW EXCLUSIVE MODE")
+        .execute(&mut *tx)
+        .await
+        .wrap_err("Failed to lock rating recalculations")?;
+
+    let running = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM rating_recalculations
+            WHERE status = 'running' AND updated_at > $1
+        ) AS "running!"
+        "#,
+        now - chrono::Duration::minutes(10)
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .wrap_err("Failed to check for running rating recalculations")?;
+    if running {
+        return Ok(None);
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE rating_recalculations
+        SET status = 'failed', error = 'Stopped reporting progress', finished_at = $1
+        WHERE status = 'running'
+        "#,
+        now
+    )
+    .execute(&mut *tx)
+    .await
+    .wrap_err("Failed to fail stale rating recalculations")?;
+
+    let recalculation = sqlx::query_as!(
+        RatingRecalculation,
+        r#"
+        INSERT INTO rating_recalculations (algorithm_version, created_at, updated_at)
+        VALUES ($1, $2, $2)
+        RETURNING recalculation_id, status, algorithm_version, games_total, games_rated, rated_before,
+            error, created_at, updated_at, finished_at
+        "#,
+        algorithm_version,
+        now
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .wrap_err("Failed to create rating recalculation")?;
+
+    tx.commit().await.wrap_err("Failed to commit transaction")?;
+    Ok(Some(recalculation))
+}
+
+// Get a recalculation by ID
+pub async fn get_recalculation(
+    pool: &PgPool,
+    recalculation_id: Uuid,
+) -> cja::Result<Option<RatingRecalculation>> {
+    sqlx::query_as!(
+        RatingRecalculation,
+        r#"
+        SELECT recalculation_id, status, algorithm_version, games_total, games_rated, rated_before,
+            error, created_at, updated_at, finished_at
+        FROM rating_recalculations
+        WHERE recalculation_id = $1
+        "#,
+        recalculation_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch rating recalculation {}", recalculation_id))
+}
+
+// Get the recalculation whose ratings are in snake_ratings, if any has completed
+pub async fn get_latest_completed_recalculation(
+    pool: &PgPool,
+) -> cja::Result<Option<RatingRecalculation>> {
+    sqlx::query_as!(
+        RatingRecalculation,
+        r#"
+        SELECT recalculation_id, status, algorithm_version, games_total, games_rated, rated_before,
+            error, created_at, updated_at, finished_at
+        FROM rating_recalculations
+        WHERE status = 'completed'
+        ORDER BY finished_at DESC
+        LIMIT 1
+        "#
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to fetch latest rating recalculation")
+}
+
+// Pick where a recalculation stops: before the oldest game that hasn't
+// finished yet, so no game it skips can finish ahead of one it rated
+pub async fn get_rating_cutoff(
+    pool: &PgPool,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<chrono::DateTime<chrono::Utc>> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COALESCE(MIN(created_at), $2) AS "cutoff!"
+        FROM games
+        WHERE status <> $1 AND parent_game_id IS NULL AND ranked
+        "#,
+        GameStatus::Finished.as_str(),
+        now
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err("Failed to fetch rating cutoff")
+}
+
+// Record what a recalculation is about to replay
+pub async fn start_recalculation(
+    pool: &PgPool,
+    recalculation_id: Uuid,
+    rated_before: chrono::DateTime<chrono::Utc>,
+    games_total: i32,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE rating_recalculations
+        SET rated_before = $2, games_total = $3, updated_at = $4
+        WHERE recalculation_id = $1
+        "#,
+        recalculation_id,
+        rated_before,
+        games_total,
+        now
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to start rating recalculation {}", recalculation_id))?;
+
+    Ok(())
+}
+
+// Record how many games a recalculation has replayed so far
+pub async fn record_recalculation_progress(
+    pool: &PgPool,
+    recalculation_id: Uuid,
+    games_rated: i32,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE rating_recalculations
+        SET games_rated = $2, updated_at = $3
+        WHERE recalculation_id = $1
+        "#,
+        recalculation_id,
+        games_rated,
+        now
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| {
+        format!(
+            "Failed to record progress of rating recalculation {}",
+            recalculation_id
+        )
+    })?;
+
+    Ok(())
+}
+
+// Mark a recalculation failed
+pub async fn fail_recalculation(
+    pool: &PgPool,
+    recalculation_id: Uuid,
+    error: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE rating_recalculations
+        SET status = 'failed', error = $2, updated_at = $3, finished_at = $3
+        WHERE recalculation_id = $1
+        "#,
+        recalculation_id,
+        error,
+        now
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fail rating recalculation {}", recalculation_id))?;
+
+    Ok(())
+}
+
+// Get finished placements from games created in [from, before), grouped by
+// game with the oldest first; either end can be left open
+// Branches and unranked games are left out, like they are from ratings
+pub async fn get_placements_created_between(
+    pool: &PgPool,
+    from: Option<chrono::DateTime<chrono::Utc>>,
+    before: Option<chrono::DateTime<chrono::Utc>>,
+) -> cja::Result<Vec<FinishedPlacement>> {
+    sqlx::query_as!(
+        FinishedPlacement,
+        r#"
+        SELECT gb.game_id, gb.battlesnake_id, gb.placement AS "placement!"
+        FROM game_battlesnakes gb
+        JOIN games g ON g.game_id = gb.game_id
+        WHERE g.status = $1 AND gb.placement IS NOT NULL AND g.parent_game_id IS NULL
+          AND g.ranked
+          AND ($2::TIMESTAMPTZ IS NULL OR g.created_at >= $2)
+          AND ($3::TIMESTAMPTZ IS NULL OR g.created_at < $3)
+        ORDER BY g.created_at ASC, g.game_id ASC, gb.placement ASC
+        "#,
+        GameStatus::Finished.as_str(),
+        from,
+        before
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch finished game placements")
+}
+
+// Get the stored ratings
+pub async fn get_stored_ratings(pool: &PgPool) -> cja::Result<HashMap<Uuid, SnakeRating>> {
+    let rows = sqlx::query_as!(
+        StoredRatingRow,
+        r#"
+        SELECT battlesnake_id, rating, games, wins, rated_games, places_gained
+        FROM snake_ratings
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch stored ratings")?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            (
+                row.battlesnake_id,
+                SnakeRating {
+                    rating: row.rating,
+                    games: row.games as usize,
+                    wins: row.wins as usize,
+                    rated_games: row.rated_games as usize,
+                    places_gained: row.places_gained,
+                },
+            )
+        })
+        .collect())
+}
+
+// Write recalculated ratings to the shadow table, replacing whatever an
+// earlier recalculation left there
+pub async fn write_shadow_ratings(
+    pool: &PgPool,
+    ratings: &HashMap<Uuid, SnakeRating>,
+) -> cja::Result<()> {
+    let mut tx = pool.begin().await.wrap_err("Failed to start transaction")?;
+
+    sqlx::query!("DELETE FROM snake_ratings_shadow")
+        .execute(&mut *tx)
+        .await
+        .wrap_err("Failed to clear shadow ratings")?;
+
+    let ratings: Vec<(&Uuid, &SnakeRating)> = ratings.iter().collect();
+    for chunk in ratings.chunks(1000) {
+        let ids: Vec<Uuid> = chunk.iter().map(|(id, _)| **id).collect();
+        let rating_values: Vec<f64> = chunk.iter().map(|(_, r)| r.rating).collect();
+        let games: Vec<i32> = chunk.iter().map(|(_, r)| r.games as i32).collect();
+        let wins: Vec<i32> = chunk.iter().map(|(_, r)| r.wins as i32).collect();
+        let rated_games: Vec<i32> = chunk.iter().map(|(_, r)| r.rated_games as i32).collect();
+        let places_gained: Vec<f64> = chunk.iter().map(|(_, r)| r.places_gained).collect();
+        sqlx::query!(
+            r#"
+            INSERT INTO snake_ratings_shadow
+                (battlesnake_id, rating, games, wins, rated_games, places_gained)
+            SELECT * FROM UNNEST($1::UUID[], $2::FLOAT8[], $3::INT[], $4::INT[], $5::INT[], $6::FLOAT8[])
+            "#,
+            &ids,
+            &rating_values,
+            &games,
+            &wins,
+            &rated_games,
+            &places_gained
+        )
+        .execute(&mut *tx)
+        .await
+        .wrap_err("Failed to write shadow ratings")?;
+    }
+
+    tx.commit().await.wrap_err("Failed to commit transaction")?;
+    Ok(())
+}
+
+// Swap the shadow ratings in and complete the recalculation, in one
+// transaction so readers see either the old ratings or the new ones
+// Snakes deleted while the recalculation ran are dropped
+pub async fn swap_in_shadow_ratings(
+    pool: &PgPool,
+    recalculation_id: Uuid,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<()> {
+    let mut tx = pool.begin().await.wrap_err("Failed to start transaction")?;
+
+    sqlx::query!("DELETE FROM snake_ratings")
+        .execute(&mut *tx)
+        .await
+        .wrap_err("Failed to clear stored ratings")?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO snake_ratings
+            (battlesnake_id, rating, games, wins, rated_games, places_gained)
+        SELECT s.battlesnake_id, s.rating, s.games, s.wins, s.rated_games, s.places_gained
+        FROM snake_ratings_shadow s
+        JOIN battlesnakes b ON b.battlesnake_id = s.battlesnake_id
+        "#
+    )
+    .execute(&mut *tx)
+    .await
+    .wrap_err("Failed to swap in recalculated ratings")?;
+
+    sqlx::query!(
+        r#"
+        UPDATE rating_recalculations
+        SET status = 'completed', games_rated = games_total, updated_at = $2, finished_at = $2
+        WHERE recalculation_id = $1
+        "#,
+        recalculation_id,
+        now
+    )
+    .execute(&mut *tx)
+    .await
+    .wrap_err_with(|| {
+        format!(
+            "Failed to complete rating recalculation {}",
+            recalculation_id
+        )
+    })?;
+
+    tx.commit().await.wrap_err("Failed to commit transaction")?;
+    Ok(())
+}
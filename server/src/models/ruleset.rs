@@ -0,0 +1,102 @@
+use color_eyre::eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// An uploaded WASM ruleset, without its module
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ruleset {
+    pub ruleset_id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Store a ruleset, or None if the user already has one with this name
+pub async fn create_ruleset(
+    pool: &PgPool,
+    user_id: Uuid,
+    name: &str,
+    wasm: &[u8],
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<Option<Ruleset>> {
+    sqlx::query_as!(
+        Ruleset,
+        r#"
+        INSERT INTO rulesets (user_id, name, wasm, created_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT ON CONSTRAINT unique_ruleset_name_per_user DO NOTHING
+        RETURNING ruleset_id, user_id, name, created_at
+        "#,
+        user_id,
+        name,
+        wasm,
+        now
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to create ruleset")
+}
+
+// Get a user's rulesets, by name
+pub async fn get_rulesets_by_user_id(pool: &PgPool, user_id: Uuid) -> cja::Result<Vec<Ruleset>> {
+    sqlx::query_as!(
+        Ruleset,
+        r#"
+        SELECT ruleset_id, user_id, name, created_at
+        FROM rulesets
+        WHERE user_id = $1
+        ORDER BY name ASC
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch rulesets for user {}", user_id))
+}
+
+// Get a ruleset by ID
+pub async fn get_ruleset_by_id(pool: &PgPool, ruleset_id: Uuid) -> cja::Result<Option<Ruleset>> {
+    sqlx::query_as!(
+        Ruleset,
+        r#"
+        SELECT ruleset_id, user_id, name, created_at
+        FROM rulesets
+        WHERE ruleset_id = $1
+        "#,
+        ruleset_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch ruleset {}", ruleset_id))
+}
+
+// Get a ruleset's compiled module
+pub async fn get_ruleset_wasm(pool: &PgPool, ruleset_id: Uuid) -> cja::Result<Option<Vec<u8>>> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT wasm
+        FROM rulesets
+        WHERE ruleset_id = $1
+        "#,
+        ruleset_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch module for ruleset {}", ruleset_id))
+}
+
+// Count a user's rulesets
+pub async fn count_rulesets_by_user_id(pool: &PgPool, user_id: Uuid) -> cja::Result<i64> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM rulesets
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to count rulesets for user {}", user_id))
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use color_eyre::eyre::{Context as _, eyre};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
@@ -23,6 +24,10 @@ pub struct Session {
 pub const SESSION_COOKIE_NAME: &str = "arena_session_id";
 // Default session expiration in seconds (30 days)
 pub const SESSION_EXPIRATION_SECONDS: i64 = 60 * 60 * 24 * 30;
+// Expiration for new anonymous sessions in seconds (7 days)
+pub const ANONYMOUS_SESSION_EXPIRATION_SECONDS: i64 = 60 * 60 * 24 * 7;
+// Expiration for sessions after logout in seconds (1 hour)
+pub const LOGGED_OUT_SESSION_EXPIRATION_SECONDS: i64 = 60 * 60;
 
 // Flash message types as constants
 pub const FLASH_TYPE_SUCCESS: &str = "success";
@@ -43,17 +48,27 @@ impl Session {
             None => "",
         }
     }
+
+    /// Whether the session has expired as of `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Expiration time for a session extended by `seconds` from `now`
+fn expires_in(now: DateTime<Utc>, seconds: i64) -> DateTime<Utc> {
+    now + chrono::Duration::seconds(seconds)
 }
 
 /// Create a new session
 ///
 /// Creates a new anonymous session with no user attached.
-pub async fn create_session(pool: &PgPool) -> cja::Result<Session> {
+pub async fn create_session(pool: &PgPool, now: DateTime<Utc>) -> cja::Result<Session> {
     let session = sqlx::query_as!(
         Session,
         r#"
-        INSERT INTO sessions (github_oauth_state, flash_message, flash_type)
-        VALUES (NULL, NULL, NULL)
+        INSERT INTO sessions (github_oauth_state, flash_message, flash_type, expires_at)
+        VALUES (NULL, NULL, NULL, $1)
         RETURNING
             session_id,
             user_id,
@@ -64,7 +79,8 @@ pub async fn create_session(pool: &PgPool) -> cja::Result<Session> {
             created_at,
             updated_at,
             expires_at
-        "#
+        "#,
+        expires_in(now, ANONYMOUS_SESSION_EXPIRATION_SECONDS)
     )
     .fetch_one(pool)
     .await
@@ -78,6 +94,7 @@ pub async fn create_session(pool: &PgPool) -> cja::Result<Session> {
 pub async fn get_active_session_by_id(
     pool: &PgPool,
     session_id: Uuid,
+    now: DateTime<Utc>,
 ) -> cja::Result<Option<Session>> {
     let session = sqlx::query_as!(
         Session,
@@ -95,9 +112,10 @@ pub async fn get_active_session_by_id(
         FROM sessions
         WHERE
             session_id = $1
-            AND expires_at > NOW()
+            AND expires_at > $2
         "#,
-        session_id
+        session_id,
+        now
     )
     .fetch_optional(pool)
     .await
@@ -177,6 +195,7 @@ pub async fn clear_flash_message(pool: &PgPool, session_id: Uuid) -> cja::Result
 pub async fn get_session_with_user(
     pool: &PgPool,
     session_id: Uuid,
+    now: DateTime<Utc>,
 ) -> cja::Result<Option<(Session, Option<User>)>> {
     let result = sqlx::query!(
         r#"
@@ -202,9 +221,10 @@ pub async fn get_session_with_user(
         LEFT JOIN users u ON s.user_id = u.user_id
         WHERE
             s.session_id = $1
-            AND s.expires_at > NOW()
+            AND s.expires_at > $2
         "#,
-        session_id
+        session_id,
+        now
     )
     .fetch_optional(pool)
     .await
@@ -365,6 +385,7 @@ pub async fn associate_user_with_session(
     pool: &PgPool,
     session_id: Uuid,
     user_id: Uuid,
+    now: DateTime<Utc>,
 ) -> cja::Result<Session> {
     let session = sqlx::query_as!(
         Session,
@@ -373,7 +394,7 @@ pub async fn associate_user_with_session(
         SET
             user_id = $2,
             github_oauth_state = NULL,
-            expires_at = NOW() + INTERVAL '30 days'
+            expires_at = $3
         WHERE session_id = $1
         RETURNING
             session_id,
@@ -387,7 +408,8 @@ pub async fn associate_user_with_session(
             expires_at
         "#,
         session_id,
-        user_id
+        user_id,
+        expires_in(now, SESSION_EXPIRATION_SECONDS)
     )
     .fetch_one(pool)
     .await
@@ -400,6 +422,7 @@ pub async fn associate_user_with_session(
 pub async fn disassociate_user_from_session(
     pool: &PgPool,
     session_id: Uuid,
+    now: DateTime<Utc>,
 ) -> cja::Result<Session> {
     let session = sqlx::query_as!(
         Session,
@@ -409,7 +432,7 @@ pub async fn disassociate_user_from_session(
             user_id = NULL,
             github_oauth_state = NULL,
             is_cli_auth = FALSE,
-            expires_at = NOW() + INTERVAL '1 hour'
+            expires_at = $2
         WHERE session_id = $1
         RETURNING
             session_id,
@@ -422,7 +445,8 @@ pub async fn disassociate_user_from_session(
             updated_at,
             expires_at
         "#,
-        session_id
+        session_id,
+        expires_in(now, LOGGED_OUT_SESSION_EXPIRATION_SECONDS)
     )
     .fetch_one(pool)
     .await
@@ -432,13 +456,17 @@ pub async fn disassociate_user_from_session(
 }
 
 /// Refresh a session's expiration time
-pub async fn refresh_session(pool: &PgPool, session_id: Uuid) -> cja::Result<Session> {
+pub async fn refresh_session(
+    pool: &PgPool,
+    session_id: Uuid,
+    now: DateTime<Utc>,
+) -> cja::Result<Session> {
     let session = sqlx::query_as!(
         Session,
         r#"
         UPDATE sessions
         SET
-            expires_at = GREATEST(expires_at, NOW() + INTERVAL '30 days')
+            expires_at = GREATEST(expires_at, $2)
         WHERE session_id = $1
         RETURNING
             session_id,
@@ -451,7 +479,8 @@ pub async fn refresh_session(pool: &PgPool, session_id: Uuid) -> cja::Result<Ses
             updated_at,
             expires_at
         "#,
-        session_id
+        session_id,
+        expires_in(now, SESSION_EXPIRATION_SECONDS)
     )
     .fetch_one(pool)
     .await
@@ -477,12 +506,13 @@ pub async fn delete_session(pool: &PgPool, session_id: Uuid) -> cja::Result<()>
 }
 
 /// Clean expired sessions
-pub async fn clean_expired_sessions(pool: &PgPool) -> cja::Result<u64> {
+pub async fn clean_expired_sessions(pool: &PgPool, now: DateTime<Utc>) -> cja::Result<u64> {
     let result = sqlx::query!(
         r#"
         DELETE FROM sessions
-        WHERE expires_at < NOW()
-        "#
+        WHERE expires_at < $1
+        "#,
+        now
     )
     .execute(pool)
     .await
@@ -490,3 +520,66 @@ pub async fn clean_expired_sessions(pool: &PgPool) -> cja::Result<u64> {
 
     Ok(result.rows_affected())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, MockClock};
+    use chrono::TimeZone;
+
+    fn session_expiring_at(expires_at: DateTime<Utc>) -> Session {
+        Session {
+            session_id: Uuid::new_v4(),
+            user_id: None,
+            github_oauth_state: None,
+            flash_message: None,
+            flash_type: None,
+            is_cli_auth: false,
+            created_at: expires_at,
+            updated_at: expires_at,
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn test_session_expires_as_clock_advances() {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap());
+        let session = session_expiring_at(expires_in(
+            clock.now(),
+            ANONYMOUS_SESSION_EXPIRATION_SECONDS,
+        ));
+
+        assert!(!session.is_expired(clock.now()));
+
+        clock.advance(chrono::Duration::days(6));
+        assert!(!session.is_expired(clock.now()));
+
+        clock.advance(chrono::Duration::days(1));
+        assert!(session.is_expired(clock.now()));
+    }
+
+    #[test]
+    fn test_logged_out_session_expires_after_an_hour() {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap());
+        let session = session_expiring_at(expires_in(
+            clock.now(),
+            LOGGED_OUT_SESSION_EXPIRATION_SECONDS,
+        ));
+
+        clock.advance(chrono::Duration::minutes(59));
+        assert!(!session.is_expired(clock.now()));
+
+        clock.advance(chrono::Duration::minutes(1));
+        assert!(session.is_expired(clock.now()));
+    }
+
+    #[test]
+    fn test_expires_in() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+
+        assert_eq!(
+            expires_in(now, SESSION_EXPIRATION_SECONDS),
+            Utc.with_ymd_and_hms(2026, 1, 31, 0, 0, 0).unwrap()
+        );
+    }
+}
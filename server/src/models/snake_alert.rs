@@ -0,0 +1,205 @@
+use color_eyre::eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// An alert on a drop in a snake's rolling win rate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnakeAlert {
+    pub alert_id: Uuid,
+    pub battlesnake_id: Uuid,
+    pub user_id: Uuid,
+    // Games in each rolling window
+    pub window_games: i32,
+    // Percentage points the win rate has to drop by for the alert to fire
+    pub drop_percent: f64,
+    // Whether the drop is still going on since the alert last fired
+    pub firing: bool,
+    pub last_checked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// One time an alert fired
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnakeAlertEvent {
+    pub event_id: Uuid,
+    pub alert_id: Uuid,
+    // Win rate, in percent, over the window before the latest one
+    pub previous_win_rate: f64,
+    // Win rate, in percent, over the latest window
+    pub current_win_rate: f64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Create an alert on one of a user's snakes
+pub async fn create_snake_alert(
+    pool: &PgPool,
+    battlesnake_id: Uuid,
+    user_id: Uuid,
+    window_games: i32,
+    drop_percent: f64,
+) -> cja::Result<SnakeAlert> {
+    sqlx::query_as!(
+        SnakeAlert,
+        r#"
+        INSERT INTO snake_alerts (battlesnake_id, user_id, window_games, drop_percent)
+        VALUES ($1, $2, $3, $4)
+        RETURNING alert_id, battlesnake_id, user_id, window_games, drop_percent, firing,
+            last_checked_at, created_at
+        "#,
+        battlesnake_id,
+        user_id,
+        window_games,
+        drop_percent
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err("Failed to create snake alert")
+}
+
+// Get a snake's alerts, oldest first
+pub async fn get_alerts_for_battlesnake(
+    pool: &PgPool,
+    battlesnake_id: Uuid,
+) -> cja::Result<Vec<SnakeAlert>> {
+    sqlx::query_as!(
+        SnakeAlert,
+        r#"
+        SELECT alert_id, battlesnake_id, user_id, window_games, drop_percent, firing,
+            last_checked_at, created_at
+        FROM snake_alerts
+        WHERE battlesnake_id = $1
+        ORDER BY created_at ASC
+        "#,
+        battlesnake_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch alerts for snake {}", battlesnake_id))
+}
+
+// Get every alert, for the cron job that checks them
+pub async fn get_all_snake_alerts(pool: &PgPool) -> cja::Result<Vec<SnakeAlert>> {
+    sqlx::query_as!(
+        SnakeAlert,
+        r#"
+        SELECT alert_id, battlesnake_id, user_id, window_games, drop_percent, firing,
+            last_checked_at, created_at
+        FROM snake_alerts
+        ORDER BY battlesnake_id, created_at ASC
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch snake alerts")
+}
+
+// Get the alerts on any of these snakes
+pub async fn get_snake_alerts_for_snakes(
+    pool: &PgPool,
+    battlesnake_ids: &[Uuid],
+) -> cja::Result<Vec<SnakeAlert>> {
+    sqlx::query_as!(
+        SnakeAlert,
+        r#"
+        SELECT alert_id, battlesnake_id, user_id, window_games, drop_percent, firing,
+            last_checked_at, created_at
+        FROM snake_alerts
+        WHERE battlesnake_id = ANY($1)
+        ORDER BY battlesnake_id, created_at ASC
+        "#,
+        battlesnake_ids
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch snake alerts")
+}
+
+// Delete one of a user's alerts, returning whether it existed
+pub async fn delete_snake_alert(pool: &PgPool, alert_id: Uuid, user_id: Uuid) -> cja::Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM snake_alerts
+        WHERE alert_id = $1 AND user_id = $2
+        "#,
+        alert_id,
+        user_id
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to delete alert {}", alert_id))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Record that an alert was checked, and whether it's firing now
+pub async fn record_alert_check(
+    pool: &PgPool,
+    alert_id: Uuid,
+    firing: bool,
+    checked_at: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE snake_alerts
+        SET firing = $2,
+            last_checked_at = $3
+        WHERE alert_id = $1
+        "#,
+        alert_id,
+        firing,
+        checked_at
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to record check of alert {}", alert_id))?;
+
+    Ok(())
+}
+
+// Record an alert firing
+pub async fn create_alert_event(
+    pool: &PgPool,
+    alert_id: Uuid,
+    previous_win_rate: f64,
+    current_win_rate: f64,
+) -> cja::Result<SnakeAlertEvent> {
+    sqlx::query_as!(
+        SnakeAlertEvent,
+        r#"
+        INSERT INTO snake_alert_events (alert_id, previous_win_rate, current_win_rate)
+        VALUES ($1, $2, $3)
+        RETURNING event_id, alert_id, previous_win_rate, current_win_rate, created_at
+        "#,
+        alert_id,
+        previous_win_rate,
+        current_win_rate
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to record event for alert {}", alert_id))
+}
+
+// Get the latest times any of a snake's alerts fired, newest first
+pub async fn get_recent_alert_events(
+    pool: &PgPool,
+    battlesnake_id: Uuid,
+    limit: i64,
+) -> cja::Result<Vec<SnakeAlertEvent>> {
+    sqlx::query_as!(
+        SnakeAlertEvent,
+        r#"
+        SELECT e.event_id, e.alert_id, e.previous_win_rate, e.current_win_rate, e.created_at
+        FROM snake_alert_events e
+        JOIN snake_alerts a ON a.alert_id = e.alert_id
+        WHERE a.battlesnake_id = $1
+        ORDER BY e.created_at DESC
+        LIMIT $2
+        "#,
+        battlesnake_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch alert events for snake {}", battlesnake_id))
+}
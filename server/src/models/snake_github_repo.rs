@@ -0,0 +1,80 @@
+use color_eyre::eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// Where a snake's code lives on GitHub, for posting check results to commits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnakeGitHubRepo {
+    pub battlesnake_id: Uuid,
+    // "owner/name"
+    pub repo: String,
+    // The GitHub App's installation on the repo's owner
+    pub installation_id: i64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Link a snake to its repository, replacing any existing link
+pub async fn set_snake_github_repo(
+    pool: &PgPool,
+    battlesnake_id: Uuid,
+    repo: &str,
+    installation_id: i64,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<SnakeGitHubRepo> {
+    sqlx::query_as!(
+        SnakeGitHubRepo,
+        r#"
+        INSERT INTO snake_github_repos (battlesnake_id, repo, installation_id, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $4)
+        ON CONFLICT (battlesnake_id) DO UPDATE
+        SET repo = EXCLUDED.repo,
+            installation_id = EXCLUDED.installation_id,
+            updated_at = EXCLUDED.updated_at
+        RETURNING battlesnake_id, repo, installation_id, created_at, updated_at
+        "#,
+        battlesnake_id,
+        repo,
+        installation_id,
+        now
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to set GitHub repo for snake {}", battlesnake_id))
+}
+
+// Get the repository a snake is linked to, if any
+pub async fn get_snake_github_repo(
+    pool: &PgPool,
+    battlesnake_id: Uuid,
+) -> cja::Result<Option<SnakeGitHubRepo>> {
+    sqlx::query_as!(
+        SnakeGitHubRepo,
+        r#"
+        SELECT battlesnake_id, repo, installation_id, created_at, updated_at
+        FROM snake_github_repos
+        WHERE battlesnake_id = $1
+        "#,
+        battlesnake_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch GitHub repo for snake {}", battlesnake_id))
+}
+
+// Unlink a snake from its repository, returning whether it was linked
+pub async fn delete_snake_github_repo(pool: &PgPool, battlesnake_id: Uuid) -> cja::Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM snake_github_repos
+        WHERE battlesnake_id = $1
+        "#,
+        battlesnake_id
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to delete GitHub repo for snake {}", battlesnake_id))?;
+
+    Ok(result.rows_affected() > 0)
+}
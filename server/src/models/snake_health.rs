@@ -0,0 +1,94 @@
+use color_eyre::eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// The latest automated health check of a snake
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnakeHealth {
+    pub battlesnake_id: Uuid,
+    // GET / answered 200 with a valid Battlesnake info response
+    pub healthy: bool,
+    // None if no response came back
+    pub status_code: Option<i32>,
+    pub latency_ms: Option<i32>,
+    // Why the check failed, None when it passed
+    pub error: Option<String>,
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+}
+
+// A snake due a health check
+#[derive(Debug, Clone)]
+pub struct SnakeToCheck {
+    pub battlesnake_id: Uuid,
+    pub name: String,
+    pub url: String,
+    pub user_id: Uuid,
+    // How its last check went, None if it hasn't had one
+    pub was_healthy: Option<bool>,
+}
+
+// Record a snake's latest health check, replacing the one before it
+pub async fn record_snake_health(pool: &PgPool, health: &SnakeHealth) -> cja::Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO snake_health (battlesnake_id, healthy, status_code, latency_ms, error, checked_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (battlesnake_id) DO UPDATE
+        SET healthy = EXCLUDED.healthy,
+            status_code = EXCLUDED.status_code,
+            latency_ms = EXCLUDED.latency_ms,
+            error = EXCLUDED.error,
+            checked_at = EXCLUDED.checked_at
+        "#,
+        health.battlesnake_id,
+        health.healthy,
+        health.status_code,
+        health.latency_ms,
+        health.error.as_deref(),
+        health.checked_at
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to record health of snake {}", health.battlesnake_id))?;
+
+    Ok(())
+}
+
+// Get the latest health check of each of the given snakes that has had one
+pub async fn get_snake_health(
+    pool: &PgPool,
+    battlesnake_ids: &[Uuid],
+) -> cja::Result<Vec<SnakeHealth>> {
+    sqlx::query_as!(
+        SnakeHealth,
+        r#"
+        SELECT battlesnake_id, healthy, status_code, latency_ms, error, checked_at
+        FROM snake_health
+        WHERE battlesnake_id = ANY($1)
+        "#,
+        battlesnake_ids
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch snake health")
+}
+
+// Get every snake to health check, leaving out archived template variants,
+// least recently checked first
+pub async fn get_snakes_to_check(pool: &PgPool) -> cja::Result<Vec<SnakeToCheck>> {
+    sqlx::query_as!(
+        SnakeToCheck,
+        r#"
+        SELECT b.battlesnake_id, b.name, b.url, b.user_id, h.healthy AS "was_healthy?"
+        FROM battlesnakes b
+        LEFT JOIN snake_variants v ON v.battlesnake_id = b.battlesnake_id
+        LEFT JOIN snake_health h ON h.battlesnake_id = b.battlesnake_id
+        WHERE v.archived_at IS NULL
+        ORDER BY h.checked_at ASC NULLS FIRST, b.battlesnake_id
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch snakes to health check")
+}
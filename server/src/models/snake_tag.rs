@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::Context as _;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::battlesnake::{Battlesnake, Visibility};
+
+// Most tags one snake can have
+pub const MAX_TAGS_PER_SNAKE: usize = 10;
+// Longest a tag can be
+pub const MAX_TAG_CHARS: usize = 32;
+
+// Clean up a tag as typed: trimmed, lowercased, with spaces and underscores as
+// dashes, so "Tree Search" and "tree_search" are both "tree-search". Tags can
+// contain letters, digits, dashes, dots, + and #, like "c++" or "f#".
+pub fn normalize_tag(tag: &str) -> Result<String, String> {
+    let tag: String = tag
+        .trim()
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if tag.is_empty() {
+        return Err("Tags can't be blank".to_string());
+    }
+    if tag.chars().count() > MAX_TAG_CHARS {
+        return Err(format!(
+            "Tag {} is too long; tags can be up to {} characters",
+            tag, MAX_TAG_CHARS
+        ));
+    }
+    if !tag.starts_with(|c: char| c.is_alphanumeric())
+        || !tag
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '.' | '+' | '#'))
+    {
+        return Err(format!(
+            "Invalid tag {}. Tags start with a letter or digit and can contain letters, digits, -, ., + and #",
+            tag
+        ));
+    }
+
+    Ok(tag)
+}
+
+// Normalize a list of tags, dropping repeats and keeping their order
+pub fn normalize_tags<S: AsRef<str>>(tags: &[S]) -> Result<Vec<String>, String> {
+    let mut normalized = Vec::new();
+    for tag in tags {
+        let tag = normalize_tag(tag.as_ref())?;
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    if normalized.len() > MAX_TAGS_PER_SNAKE {
+        return Err(format!(
+            "A snake can have up to {} tags",
+            MAX_TAGS_PER_SNAKE
+        ));
+    }
+    Ok(normalized)
+}
+
+// Split a comma separated list of tags, as typed into a form
+pub fn split_tags(tags: &str) -> Vec<&str> {
+    tags.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+// Split a snake search into the name to look for and "tag:" filters, so
+// "tag:rust hungry tag:tree-search" is the name "hungry" with two tags
+pub fn split_search_query(query: &str) -> (String, Vec<String>) {
+    let mut name = Vec::new();
+    let mut tags = Vec::new();
+    for word in query.split_whitespace() {
+        match word.strip_prefix("tag:").map(normalize_tag) {
+            Some(Ok(tag)) => {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            // Not a usable tag, so it's part of the name
+            Some(Err(_)) | None => name.push(word),
+        }
+    }
+    (name.join(" "), tags)
+}
+
+// A snake's tags, alphabetically
+pub async fn get_snake_tags(pool: &PgPool, battlesnake_id: Uuid) -> cja::Result<Vec<String>> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT tag
+        FROM battlesnake_tags
+        WHERE battlesnake_id = $1
+        ORDER BY tag
+        "#,
+        battlesnake_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch tags for snake {}", battlesnake_id))
+}
+
+#[derive(Debug)]
+struct SnakeTagRow {
+    battlesnake_id: Uuid,
+    tag: String,
+}
+
+// Tags of several snakes at once, alphabetically. Snakes without tags are left out.
+pub async fn get_tags_for_snakes(
+    pool: &PgPool,
+    battlesnake_ids: &[Uuid],
+) -> cja::Result<HashMap<Uuid, Vec<String>>> {
+    let rows = sqlx::query_as!(
+        SnakeTagRow,
+        r#"
+        SELECT battlesnake_id, tag
+        FROM battlesnake_tags
+        WHERE battlesnake_id = ANY($1)
+        ORDER BY battlesnake_id, tag
+        "#,
+        battlesnake_ids
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch snake tags")?;
+
+    let mut tags: HashMap<Uuid, Vec<String>> = HashMap::new();
+    for row in rows {
+        tags.entry(row.battlesnake_id).or_default().push(row.tag);
+    }
+    Ok(tags)
+}
+
+// Replace a snake's tags, which should already be normalized
+pub async fn set_snake_tags<'e, E>(
+    executor: E,
+    battlesnake_id: Uuid,
+    tags: &[String],
+) -> cja::Result<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    sqlx::query!(
+        r#"
+        WITH removed AS (
+            DELETE FROM battlesnake_tags
+            WHERE battlesnake_id = $1 AND NOT (tag = ANY($2))
+        )
+        INSERT INTO battlesnake_tags (battlesnake_id, tag)
+        SELECT $1, UNNEST($2::TEXT[])
+        ON CONFLICT (battlesnake_id, tag) DO NOTHING
+        "#,
+        battlesnake_id,
+        tags
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| format!("Failed to set tags for snake {}", battlesnake_id))?;
+
+    Ok(())
+}
+
+// Remove one tag from a snake, returning whether it had it
+pub async fn remove_snake_tag(pool: &PgPool, battlesnake_id: Uuid, tag: &str) -> cja::Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM battlesnake_tags
+        WHERE battlesnake_id = $1 AND tag = $2
+        "#,
+        battlesnake_id,
+        tag
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to remove tag from snake {}", battlesnake_id))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// A tag and how many public snakes have it
+#[derive(Debug, Clone, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub snakes: i64,
+}
+
+// Tags on public snakes, most used first
+pub async fn get_public_tags(pool: &PgPool) -> cja::Result<Vec<TagCount>> {
+    sqlx::query_as!(
+        TagCount,
+        r#"
+        SELECT t.tag, COUNT(*) AS "snakes!"
+        FROM battlesnake_tags t
+        JOIN battlesnakes b ON b.battlesnake_id = t.battlesnake_id
+        WHERE b.visibility = 'public'
+        GROUP BY t.tag
+        ORDER BY COUNT(*) DESC, t.tag
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch public tags")
+}
+
+// A search of public snakes
+#[derive(Debug, Default)]
+pub struct SnakeSearch<'a> {
+    // Leave out this user's snakes, when looking for their opponents
+    pub exclude_user_id: Option<Uuid>,
+    // Part of the name, matched case-insensitively; empty matches every name
+    pub name: &'a str,
+    // Only snakes with every one of these tags
+    pub tags: &'a [String],
+    // Snakes with any of these tags come first
+    pub preferred_tags: &'a [String],
+    pub limit: i64,
+}
+
+// Public snakes matching a search, those with a preferred tag first and then by name
+pub async fn search_public_battlesnakes(
+    pool: &PgPool,
+    search: &SnakeSearch<'_>,
+) -> cja::Result<Vec<Battlesnake>> {
+    sqlx::query_as!(
+        Battlesnake,
+        r#"
+        SELECT b.battlesnake_id, b.user_id, b.name, b.url,
+            b.visibility AS "visibility: Visibility", b.created_at, b.updated_at
+        FROM battlesnakes b
+        WHERE b.visibility = 'public'
+          AND ($1::UUID IS NULL OR b.user_id != $1)
+          AND b.name ILIKE $2
+          AND (
+              SELECT COUNT(*) FROM battlesnake_tags t
+              WHERE t.battlesnake_id = b.battlesnake_id AND t.tag = ANY($3)
+          ) = CARDINALITY($3::TEXT[])
+        ORDER BY
+            EXISTS (
+                SELECT 1 FROM battlesnake_tags t
+                WHERE t.battlesnake_id = b.battlesnake_id AND t.tag = ANY($4)
+            ) DESC,
+            b.name ASC
+        LIMIT $5
+        "#,
+        search.exclude_user_id,
+        format!("%{}%", search.name),
+        search.tags,
+        search.preferred_tags,
+        search.limit
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to search public battlesnakes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(normalize_tag("  Tree Search ").unwrap(), "tree-search");
+        assert_eq!(normalize_tag("tree_search").unwrap(), "tree-search");
+        assert_eq!(normalize_tag("C++").unwrap(), "c++");
+        assert_eq!(normalize_tag("ML-based").unwrap(), "ml-based");
+
+        assert!(normalize_tag("   ").is_err());
+        assert!(normalize_tag("-rust").is_err());
+        assert!(normalize_tag("<script>").is_err());
+        assert!(normalize_tag(&"a".repeat(MAX_TAG_CHARS + 1)).is_err());
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        assert_eq!(
+            normalize_tags(&["Rust", "tree search", "rust"]).unwrap(),
+            vec!["rust", "tree-search"]
+        );
+
+        let too_many: Vec<String> = (0..=MAX_TAGS_PER_SNAKE)
+            .map(|i| format!("t{}", i))
+            .collect();
+        assert!(normalize_tags(&too_many).is_err());
+    }
+
+    #[test]
+    fn test_split_search_query() {
+        assert_eq!(
+            split_search_query("tag:Rust hungry tag:tree-search snake tag:rust"),
+            (
+                "hungry snake".to_string(),
+                vec!["rust".to_string(), "tree-search".to_string()]
+            )
+        );
+        // "tag:" with nothing usable after it is just part of the name
+        assert_eq!(
+            split_search_query("tag: tag:-x"),
+            ("tag: tag:-x".to_string(), vec![])
+        );
+    }
+}
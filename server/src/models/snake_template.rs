@@ -0,0 +1,282 @@
+use color_eyre::eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, PgPool, Postgres};
+use uuid::Uuid;
+
+use super::battlesnake::Visibility;
+
+// A snake URL with a {tag} placeholder, instantiated once per deployed tag
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnakeTemplate {
+    pub template_id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub url_template: String,
+    // Visibility new variants get
+    pub visibility: Visibility,
+    // Variants without a game for this many days are archived
+    pub archive_after_days: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// The snake made for one tag of a template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnakeVariant {
+    pub battlesnake_id: Uuid,
+    pub template_id: Uuid,
+    pub tag: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub archived_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+// For creating a new template
+#[derive(Debug, Clone)]
+pub struct CreateSnakeTemplate {
+    pub name: String,
+    pub url_template: String,
+    pub visibility: Visibility,
+    pub archive_after_days: i32,
+}
+
+// Create a template, or None if the user already has one with this name
+pub async fn create_snake_template(
+    pool: &PgPool,
+    user_id: Uuid,
+    data: CreateSnakeTemplate,
+) -> cja::Result<Option<SnakeTemplate>> {
+    sqlx::query_as!(
+        SnakeTemplate,
+        r#"
+        INSERT INTO snake_templates (user_id, name, url_template, visibility, archive_after_days)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT ON CONSTRAINT unique_snake_template_name_per_user DO NOTHING
+        RETURNING template_id, user_id, name, url_template,
+            visibility AS "visibility: Visibility", archive_after_days, created_at
+        "#,
+        user_id,
+        &data.name,
+        &data.url_template,
+        data.visibility.as_str(),
+        data.archive_after_days
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to create snake template")
+}
+
+// Get a user's templates, by name
+pub async fn get_snake_templates_by_user_id(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> cja::Result<Vec<SnakeTemplate>> {
+    sqlx::query_as!(
+        SnakeTemplate,
+        r#"
+        SELECT template_id, user_id, name, url_template,
+            visibility AS "visibility: Visibility", archive_after_days, created_at
+        FROM snake_templates
+        WHERE user_id = $1
+        ORDER BY name ASC
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch snake templates")
+}
+
+pub async fn get_snake_template_by_id(
+    pool: &PgPool,
+    template_id: Uuid,
+) -> cja::Result<Option<SnakeTemplate>> {
+    sqlx::query_as!(
+        SnakeTemplate,
+        r#"
+        SELECT template_id, user_id, name, url_template,
+            visibility AS "visibility: Visibility", archive_after_days, created_at
+        FROM snake_templates
+        WHERE template_id = $1
+        "#,
+        template_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to fetch snake template")
+}
+
+// Delete a template; its variants stay on as ordinary snakes
+pub async fn delete_snake_template(
+    pool: &PgPool,
+    template_id: Uuid,
+    user_id: Uuid,
+) -> cja::Result<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM snake_templates WHERE template_id = $1 AND user_id = $2",
+        template_id,
+        user_id
+    )
+    .execute(pool)
+    .await
+    .wrap_err("Failed to delete snake template")?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Get a template's variants, newest first
+pub async fn get_snake_variants(
+    pool: &PgPool,
+    template_id: Uuid,
+) -> cja::Result<Vec<SnakeVariant>> {
+    sqlx::query_as!(
+        SnakeVariant,
+        r#"
+        SELECT battlesnake_id, template_id, tag, created_at, archived_at
+        FROM snake_variants
+        WHERE template_id = $1
+        ORDER BY created_at DESC
+        "#,
+        template_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch snake variants")
+}
+
+pub async fn get_snake_variant_by_tag<'e, E>(
+    executor: E,
+    template_id: Uuid,
+    tag: &str,
+) -> cja::Result<Option<SnakeVariant>>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        SnakeVariant,
+        r#"
+        SELECT battlesnake_id, template_id, tag, created_at, archived_at
+        FROM snake_variants
+        WHERE template_id = $1 AND tag = $2
+        "#,
+        template_id,
+        tag
+    )
+    .fetch_optional(executor)
+    .await
+    .wrap_err("Failed to fetch snake variant")
+}
+
+pub async fn create_snake_variant<'e, E>(
+    executor: E,
+    battlesnake_id: Uuid,
+    template_id: Uuid,
+    tag: &str,
+) -> cja::Result<SnakeVariant>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        SnakeVariant,
+        r#"
+        INSERT INTO snake_variants (battlesnake_id, template_id, tag)
+        VALUES ($1, $2, $3)
+        RETURNING battlesnake_id, template_id, tag, created_at, archived_at
+        "#,
+        battlesnake_id,
+        template_id,
+        tag
+    )
+    .fetch_one(executor)
+    .await
+    .wrap_err("Failed to create snake variant")
+}
+
+// Bring an archived variant back, with its template's visibility
+pub async fn unarchive_snake_variant<'e, E>(
+    executor: E,
+    battlesnake_id: Uuid,
+    visibility: Visibility,
+) -> cja::Result<()>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query!(
+        r#"
+        WITH variant AS (
+            UPDATE snake_variants
+            SET archived_at = NULL
+            WHERE battlesnake_id = $1
+            RETURNING battlesnake_id
+        )
+        UPDATE battlesnakes
+        SET visibility = $2
+        FROM variant
+        WHERE battlesnakes.battlesnake_id = variant.battlesnake_id
+        "#,
+        battlesnake_id,
+        visibility.as_str()
+    )
+    .execute(executor)
+    .await
+    .wrap_err("Failed to unarchive snake variant")?;
+
+    Ok(())
+}
+
+// Archive variants that haven't played a game, or been created, within their
+// template's archive_after_days. A template's newest variant is never
+// archived. Returns the archived snakes' IDs.
+pub async fn archive_inactive_snake_variants(
+    pool: &PgPool,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<Vec<Uuid>> {
+    sqlx::query_scalar!(
+        r#"
+        WITH inactive AS (
+            UPDATE snake_variants v
+            SET archived_at = $1
+            FROM snake_templates t
+            WHERE v.template_id = t.template_id
+                AND v.archived_at IS NULL
+                AND GREATEST(
+                    v.created_at,
+                    (SELECT MAX(gb.created_at) FROM game_battlesnakes gb
+                     WHERE gb.battlesnake_id = v.battlesnake_id)
+                ) < $1::TIMESTAMPTZ - make_interval(days => t.archive_after_days)
+                AND v.battlesnake_id <> (
+                    SELECT newest.battlesnake_id FROM snake_variants newest
+                    WHERE newest.template_id = v.template_id
+                    ORDER BY newest.created_at DESC
+                    LIMIT 1
+                )
+            RETURNING v.battlesnake_id
+        )
+        UPDATE battlesnakes
+        SET visibility = 'private'
+        FROM inactive
+        WHERE battlesnakes.battlesnake_id = inactive.battlesnake_id
+        RETURNING battlesnakes.battlesnake_id
+        "#,
+        now
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to archive inactive snake variants")
+}
+
+// Which of these snakes are archived variants
+pub async fn get_archived_snake_ids(
+    pool: &PgPool,
+    battlesnake_ids: &[Uuid],
+) -> cja::Result<Vec<Uuid>> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT battlesnake_id
+        FROM snake_variants
+        WHERE battlesnake_id = ANY($1) AND archived_at IS NOT NULL
+        "#,
+        battlesnake_ids
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to check for archived snakes")
+}
@@ -1,41 +1,163 @@
+use std::collections::HashMap;
+
 use color_eyre::eyre::Context as _;
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, PgPool};
+use sqlx::{FromRow, PgConnection, PgPool};
 use uuid::Uuid;
 
+use crate::frame_compression::{decompress_frame, encode_frame};
+use crate::frame_schema::{CURRENT_FRAME_SCHEMA_VERSION, upgrade_frame};
 use crate::game_channels::{GameChannels, TurnNotification};
 
 /// A turn in a game with its frame data
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Turn {
     pub turn_id: Uuid,
     pub game_id: Uuid,
     pub turn_number: i32,
     pub frame_data: Option<serde_json::Value>,
+    /// The frame schema version `frame_data` is in, see [`crate::frame_schema`]
+    #[serde(default = "current_frame_schema_version")]
+    pub frame_schema_version: i32,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+fn current_frame_schema_version() -> i32 {
+    CURRENT_FRAME_SCHEMA_VERSION
+}
+
+impl Turn {
+    /// Bring the frame up to the current schema version, in memory
+    ///
+    /// A frame from a newer server is served as it is.
+    fn upgrade(&mut self) {
+        if self.frame_schema_version == CURRENT_FRAME_SCHEMA_VERSION {
+            return;
+        }
+        if let Some(frame) = &mut self.frame_data
+            && let Err(e) = upgrade_frame(frame, self.frame_schema_version)
+        {
+            tracing::warn!(turn_id = %self.turn_id, "Serving frame as stored: {}", e);
+            return;
+        }
+        self.frame_schema_version = CURRENT_FRAME_SCHEMA_VERSION;
+    }
+}
+
+/// A turn as stored, with its frame in `frame_data` or `frame_compressed`,
+/// see [`crate::frame_compression`]
+#[derive(Debug)]
+struct TurnRow {
+    turn_id: Uuid,
+    game_id: Uuid,
+    turn_number: i32,
+    frame_data: Option<serde_json::Value>,
+    frame_compressed: Option<Vec<u8>>,
+    frame_schema_version: i32,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<TurnRow> for Turn {
+    fn from(row: TurnRow) -> Self {
+        let frame_data = match row.frame_compressed {
+            Some(compressed) => match decompress_frame(&compressed) {
+                Ok(frame) => Some(frame),
+                Err(e) => {
+                    tracing::warn!(
+                        turn_id = %row.turn_id,
+                        "Serving turn without its frame: {}",
+                        e
+                    );
+                    None
+                }
+            },
+            None => row.frame_data,
+        };
+
+        Turn {
+            turn_id: row.turn_id,
+            game_id: row.game_id,
+            turn_number: row.turn_number,
+            frame_data,
+            frame_schema_version: row.frame_schema_version,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// Decompress fetched turns' frames, as stored
+fn decoded(rows: Vec<TurnRow>) -> Vec<Turn> {
+    rows.into_iter().map(Turn::from).collect()
+}
+
+/// Decompress fetched turns' frames and upgrade them to the current schema version
+fn upgraded(rows: Vec<TurnRow>) -> Vec<Turn> {
+    let mut turns = decoded(rows);
+    for turn in &mut turns {
+        turn.upgrade();
+    }
+    turns
+}
+
 /// Get all turns for a game, ordered by turn number
 pub async fn get_turns_by_game_id(pool: &PgPool, game_id: Uuid) -> cja::Result<Vec<Turn>> {
-    let turns = sqlx::query_as::<_, Turn>(
+    let turns = sqlx::query_as!(
+        TurnRow,
         r#"
         SELECT
             turn_id,
             game_id,
             turn_number,
             frame_data,
+            frame_compressed,
+            frame_schema_version,
             created_at
         FROM turns
         WHERE game_id = $1
         ORDER BY turn_number ASC
         "#,
+        game_id
     )
-    .bind(game_id)
     .fetch_all(pool)
     .await
     .wrap_err("Failed to fetch turns from database")?;
 
-    Ok(turns)
+    Ok(upgraded(turns))
+}
+
+/// Get up to `limit` turns for a game starting from a specific turn number
+/// Used to stream a game's frames a page at a time
+pub async fn get_turns_page(
+    pool: &PgPool,
+    game_id: Uuid,
+    from_turn: i32,
+    limit: i64,
+) -> cja::Result<Vec<Turn>> {
+    let turns = sqlx::query_as!(
+        TurnRow,
+        r#"
+        SELECT
+            turn_id,
+            game_id,
+            turn_number,
+            frame_data,
+            frame_compressed,
+            frame_schema_version,
+            created_at
+        FROM turns
+        WHERE game_id = $1 AND turn_number >= $2
+        ORDER BY turn_number ASC
+        LIMIT $3
+        "#,
+        game_id,
+        from_turn,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch page of turns from database")?;
+
+    Ok(upgraded(turns))
 }
 
 /// Get turns for a game starting from a specific turn number
@@ -45,26 +167,64 @@ pub async fn get_turns_from(
     game_id: Uuid,
     from_turn: i32,
 ) -> cja::Result<Vec<Turn>> {
-    let turns = sqlx::query_as::<_, Turn>(
+    let turns = sqlx::query_as!(
+        TurnRow,
         r#"
         SELECT
             turn_id,
             game_id,
             turn_number,
             frame_data,
+            frame_compressed,
+            frame_schema_version,
             created_at
         FROM turns
         WHERE game_id = $1 AND turn_number >= $2
         ORDER BY turn_number ASC
         "#,
+        game_id,
+        from_turn
     )
-    .bind(game_id)
-    .bind(from_turn)
     .fetch_all(pool)
     .await
     .wrap_err("Failed to fetch turns from database")?;
 
-    Ok(turns)
+    Ok(upgraded(turns))
+}
+
+/// Get the latest turn of a game that has a frame, up to `turn` when given
+/// Used to show the board at a turn, clamped to the turns played so far
+pub async fn get_turn_at(
+    pool: &PgPool,
+    game_id: Uuid,
+    turn: Option<i32>,
+) -> cja::Result<Option<Turn>> {
+    let turn = sqlx::query_as!(
+        TurnRow,
+        r#"
+        SELECT
+            turn_id,
+            game_id,
+            turn_number,
+            frame_data,
+            frame_compressed,
+            frame_schema_version,
+            created_at
+        FROM turns
+        WHERE game_id = $1
+          AND (frame_data IS NOT NULL OR frame_compressed IS NOT NULL)
+          AND ($2::INTEGER IS NULL OR turn_number <= $2)
+        ORDER BY turn_number DESC
+        LIMIT 1
+        "#,
+        game_id,
+        turn
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to fetch turn from database")?;
+
+    Ok(upgraded(turn.into_iter().collect()).pop())
 }
 
 /// Create a new turn for a game and notify WebSocket subscribers
@@ -75,28 +235,256 @@ pub async fn create_turn(
     turn_number: i32,
     frame_data: Option<serde_json::Value>,
 ) -> cja::Result<Turn> {
-    let turn = sqlx::query_as::<_, Turn>(
+    let turn = insert_turn(pool, game_id, turn_number, frame_data).await?;
+
+    game_channels
+        .notify(TurnNotification {
+            game_id,
+            turn_number,
+        })
+        .await;
+
+    Ok(turn)
+}
+
+/// Insert a turn without notifying subscribers, for callers that notify
+/// once the rest of the turn is stored
+pub async fn insert_turn(
+    pool: &PgPool,
+    game_id: Uuid,
+    turn_number: i32,
+    frame_data: Option<serde_json::Value>,
+) -> cja::Result<Turn> {
+    let (frame_data, frame_compressed) = encode_frame(game_id, frame_data)?;
+    let turn = sqlx::query_as!(
+        TurnRow,
         r#"
-        INSERT INTO turns (game_id, turn_number, frame_data)
-        VALUES ($1, $2, $3)
-        RETURNING turn_id, game_id, turn_number, frame_data, created_at
+        INSERT INTO turns (game_id, turn_number, frame_data, frame_compressed, frame_schema_version)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING turn_id, game_id, turn_number, frame_data, frame_compressed, frame_schema_version, created_at
         "#,
+        game_id,
+        turn_number,
+        frame_data,
+        frame_compressed,
+        CURRENT_FRAME_SCHEMA_VERSION
     )
-    .bind(game_id)
-    .bind(turn_number)
-    .bind(frame_data)
     .fetch_one(pool)
     .await
     .wrap_err("Failed to create turn")?;
 
-    game_channels
-        .notify(TurnNotification {
+    Ok(turn.into())
+}
+
+/// A played turn to store, with each snake's move on it
+#[derive(Debug, Clone)]
+pub struct CreateTurn {
+    pub turn_number: i32,
+    pub frame_data: serde_json::Value,
+    pub snake_turns: Vec<CreateSnakeTurn>,
+}
+
+/// Store a batch of a game's turns and their snake turns in one transaction,
+/// with a single insert for each table
+pub async fn create_turns(pool: &PgPool, game_id: Uuid, turns: &[CreateTurn]) -> cja::Result<()> {
+    if turns.is_empty() {
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await.wrap_err("Failed to start transaction")?;
+    insert_turns(&mut tx, game_id, turns).await?;
+    tx.commit().await.wrap_err("Failed to commit transaction")?;
+    Ok(())
+}
+
+/// Insert a batch of a game's turns and their snake turns on a connection
+/// that's already in a transaction, without notifying subscribers
+pub async fn insert_turns(
+    conn: &mut PgConnection,
+    game_id: Uuid,
+    turns: &[CreateTurn],
+) -> cja::Result<()> {
+    if turns.is_empty() {
+        return Ok(());
+    }
+
+    let turn_numbers: Vec<i32> = turns.iter().map(|t| t.turn_number).collect();
+    let mut frames: Vec<Option<serde_json::Value>> = Vec::with_capacity(turns.len());
+    let mut compressed_frames: Vec<Option<Vec<u8>>> = Vec::with_capacity(turns.len());
+    for turn in turns {
+        let (frame, compressed) = encode_frame(game_id, Some(turn.frame_data.clone()))?;
+        frames.push(frame);
+        compressed_frames.push(compressed);
+    }
+
+    let turn_ids: HashMap<i32, Uuid> = sqlx::query!(
+        r#"
+        INSERT INTO turns (game_id, turn_number, frame_data, frame_compressed, frame_schema_version)
+        SELECT $1, turn_number, frame_data, frame_compressed, $5
+        FROM UNNEST($2::INTEGER[], $3::JSONB[], $4::BYTEA[]) AS t (turn_number, frame_data, frame_compressed)
+        RETURNING turn_number, turn_id
+        "#,
+        game_id,
+        &turn_numbers,
+        &frames as &[Option<serde_json::Value>],
+        &compressed_frames as &[Option<Vec<u8>>],
+        CURRENT_FRAME_SCHEMA_VERSION
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .wrap_err_with(|| format!("Failed to create turns for game {}", game_id))?
+    .into_iter()
+    .map(|row| (row.turn_number, row.turn_id))
+    .collect();
+
+    let snake_turns: Vec<(Uuid, &CreateSnakeTurn)> = turns
+        .iter()
+        .flat_map(|t| {
+            let turn_id = turn_ids[&t.turn_number];
+            t.snake_turns.iter().map(move |st| (turn_id, st))
+        })
+        .collect();
+    let ids: Vec<Uuid> = snake_turns.iter().map(|(id, _)| *id).collect();
+    let game_battlesnake_ids: Vec<Uuid> = snake_turns
+        .iter()
+        .map(|(_, st)| st.game_battlesnake_id)
+        .collect();
+    let directions: Vec<&str> = snake_turns
+        .iter()
+        .map(|(_, st)| st.direction.as_str())
+        .collect();
+    let latencies: Vec<Option<i32>> = snake_turns
+        .iter()
+        .map(|(_, st)| st.latency_ms.map(|ms| ms as i32))
+        .collect();
+    let compensated_latencies: Vec<Option<i32>> = snake_turns
+        .iter()
+        .map(|(_, st)| st.compensated_latency_ms.map(|ms| ms as i32))
+        .collect();
+    let timed_outs: Vec<bool> = snake_turns.iter().map(|(_, st)| st.timed_out).collect();
+    let parse_errors: Vec<Option<&str>> = snake_turns
+        .iter()
+        .map(|(_, st)| st.parse_error.as_deref())
+        .collect();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO snake_turns (turn_id, game_battlesnake_id, direction, latency_ms, compensated_latency_ms, timed_out, parse_error)
+        SELECT * FROM UNNEST($1::UUID[], $2::UUID[], $3::TEXT[], $4::INTEGER[], $5::INTEGER[], $6::BOOLEAN[], $7::TEXT[])
+        "#,
+        &ids,
+        &game_battlesnake_ids,
+        &directions as &[&str],
+        &latencies as &[Option<i32>],
+        &compensated_latencies as &[Option<i32>],
+        &timed_outs,
+        &parse_errors as &[Option<&str>]
+    )
+    .execute(&mut *conn)
+    .await
+    .wrap_err_with(|| format!("Failed to create snake turns for game {}", game_id))?;
+
+    Ok(())
+}
+
+/// Delete a game's turns, and their snake turns with them, once its frames are
+/// archived to storage. Returns how many turns were deleted.
+pub async fn delete_game_turns<'e, E>(executor: E, game_id: Uuid) -> cja::Result<u64>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM turns
+        WHERE game_id = $1
+        "#,
+        game_id
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| format!("Failed to delete turns of game {}", game_id))?;
+
+    Ok(result.rows_affected())
+}
+
+/// Delete a game's turns after `turn`, so a retried run can store them again
+pub async fn delete_turns_after(pool: &PgPool, game_id: Uuid, turn: i32) -> cja::Result<u64> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM turns
+        WHERE game_id = $1 AND turn_number > $2
+        "#,
+        game_id,
+        turn
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to delete turns of game {} after {}", game_id, turn))?;
+
+    Ok(result.rows_affected())
+}
+
+/// Get up to `limit` turns whose frames are behind `version`, for the backfill.
+/// Frames are returned as stored, decompressed.
+pub async fn get_outdated_turns(pool: &PgPool, version: i32, limit: i64) -> cja::Result<Vec<Turn>> {
+    let turns = sqlx::query_as!(
+        TurnRow,
+        r#"
+        SELECT
+            turn_id,
             game_id,
             turn_number,
-        })
-        .await;
+            frame_data,
+            frame_compressed,
+            frame_schema_version,
+            created_at
+        FROM turns
+        WHERE frame_schema_version < $1
+        ORDER BY turn_id
+        LIMIT $2
+        "#,
+        version,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch turns with outdated frames")?;
 
-    Ok(turn)
+    Ok(decoded(turns))
+}
+
+/// Move a turn from schema version `from` to `to`, replacing its frame if the
+/// upgrade changed it. Does nothing if the turn is no longer at `from`.
+pub async fn set_turn_frame_schema(
+    pool: &PgPool,
+    game_id: Uuid,
+    turn_id: Uuid,
+    from: i32,
+    to: i32,
+    frame_data: Option<serde_json::Value>,
+) -> cja::Result<()> {
+    let replaced = frame_data.is_some();
+    let (frame_data, frame_compressed) = encode_frame(game_id, frame_data)?;
+    sqlx::query!(
+        r#"
+        UPDATE turns
+        SET frame_data = CASE WHEN $6 THEN $4 ELSE frame_data END,
+            frame_compressed = CASE WHEN $6 THEN $5 ELSE frame_compressed END,
+            frame_schema_version = $3
+        WHERE turn_id = $1 AND frame_schema_version = $2
+        "#,
+        turn_id,
+        from,
+        to,
+        frame_data,
+        frame_compressed,
+        replaced
+    )
+    .execute(pool)
+    .await
+    .wrap_err("Failed to upgrade turn frame")?;
+
+    Ok(())
 }
 
 /// Update turn frame data (used after computing game state)
@@ -129,45 +517,65 @@ pub struct SnakeTurn {
     pub game_battlesnake_id: Uuid,
     pub direction: String,
     pub latency_ms: Option<i32>,
+    /// `latency_ms` minus the snake's network allowance, when latency compensation is on
+    pub compensated_latency_ms: Option<i32>,
     pub timed_out: bool,
+    /// Why the snake's /move response couldn't be parsed, if it couldn't
+    pub parse_error: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// For recording a snake's move on a turn
+#[derive(Debug, Clone)]
+pub struct CreateSnakeTurn {
+    pub game_battlesnake_id: Uuid,
+    pub direction: String,
+    pub latency_ms: Option<i64>,
+    pub compensated_latency_ms: Option<i64>,
+    pub timed_out: bool,
+    pub parse_error: Option<String>,
+}
+
+/// A snake's move along with the turn number it was made on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnakeTurnWithNumber {
+    pub turn_number: i32,
+    pub game_battlesnake_id: Uuid,
+    pub direction: String,
+    pub latency_ms: Option<i32>,
+    pub compensated_latency_ms: Option<i32>,
+    pub timed_out: bool,
+    pub parse_error: Option<String>,
+}
+
 /// Create a snake turn record
 pub async fn create_snake_turn(
     pool: &PgPool,
     turn_id: Uuid,
-    game_battlesnake_id: Uuid,
-    direction: &str,
-    latency_ms: Option<i64>,
-    timed_out: bool,
+    data: CreateSnakeTurn,
 ) -> cja::Result<SnakeTurn> {
-    let latency_i32 = latency_ms.map(|ms| ms as i32);
-    let row = sqlx::query!(
+    let latency_i32 = data.latency_ms.map(|ms| ms as i32);
+    let compensated_latency_i32 = data.compensated_latency_ms.map(|ms| ms as i32);
+    let snake_turn = sqlx::query_as!(
+        SnakeTurn,
         r#"
-        INSERT INTO snake_turns (turn_id, game_battlesnake_id, direction, latency_ms, timed_out)
-        VALUES ($1, $2, $3, $4, $5)
-        RETURNING snake_turn_id, turn_id, game_battlesnake_id, direction, latency_ms, timed_out, created_at
+        INSERT INTO snake_turns (turn_id, game_battlesnake_id, direction, latency_ms, compensated_latency_ms, timed_out, parse_error)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING snake_turn_id, turn_id, game_battlesnake_id, direction, latency_ms, compensated_latency_ms, timed_out, parse_error, created_at
         "#,
         turn_id,
-        game_battlesnake_id,
-        direction,
+        data.game_battlesnake_id,
+        data.direction,
         latency_i32,
-        timed_out
+        compensated_latency_i32,
+        data.timed_out,
+        data.parse_error
     )
     .fetch_one(pool)
     .await
     .wrap_err("Failed to create snake turn")?;
 
-    Ok(SnakeTurn {
-        snake_turn_id: row.snake_turn_id,
-        turn_id: row.turn_id,
-        game_battlesnake_id: row.game_battlesnake_id,
-        direction: row.direction,
-        latency_ms: row.latency_ms,
-        timed_out: row.timed_out,
-        created_at: row.created_at,
-    })
+    Ok(snake_turn)
 }
 
 /// Get all snake turns for a specific turn
@@ -175,7 +583,8 @@ pub async fn get_snake_turns_by_turn_id(
     pool: &PgPool,
     turn_id: Uuid,
 ) -> cja::Result<Vec<SnakeTurn>> {
-    let rows = sqlx::query!(
+    let turns = sqlx::query_as!(
+        SnakeTurn,
         r#"
         SELECT
             snake_turn_id,
@@ -183,7 +592,9 @@ pub async fn get_snake_turns_by_turn_id(
             game_battlesnake_id,
             direction,
             latency_ms,
+            compensated_latency_ms,
             timed_out,
+            parse_error,
             created_at
         FROM snake_turns
         WHERE turn_id = $1
@@ -194,18 +605,35 @@ pub async fn get_snake_turns_by_turn_id(
     .await
     .wrap_err("Failed to fetch snake turns")?;
 
-    let turns = rows
-        .into_iter()
-        .map(|row| SnakeTurn {
-            snake_turn_id: row.snake_turn_id,
-            turn_id: row.turn_id,
-            game_battlesnake_id: row.game_battlesnake_id,
-            direction: row.direction,
-            latency_ms: row.latency_ms,
-            timed_out: row.timed_out,
-            created_at: row.created_at,
-        })
-        .collect();
+    Ok(turns)
+}
+
+/// Get every snake move in a game, ordered by turn number
+pub async fn get_snake_turns_by_game_id(
+    pool: &PgPool,
+    game_id: Uuid,
+) -> cja::Result<Vec<SnakeTurnWithNumber>> {
+    let turns = sqlx::query_as!(
+        SnakeTurnWithNumber,
+        r#"
+        SELECT
+            t.turn_number,
+            st.game_battlesnake_id,
+            st.direction,
+            st.latency_ms,
+            st.compensated_latency_ms,
+            st.timed_out,
+            st.parse_error
+        FROM snake_turns st
+        JOIN turns t ON st.turn_id = t.turn_id
+        WHERE t.game_id = $1
+        ORDER BY t.turn_number ASC, st.game_battlesnake_id ASC
+        "#,
+        game_id
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch snake turns for game")?;
 
     Ok(turns)
 }
@@ -221,6 +649,7 @@ mod tests {
             game_id: Uuid::parse_str("550e8400-e29b-41d4-a716-446655440001").unwrap(),
             turn_number: 42,
             frame_data: Some(serde_json::json!({"test": "data"})),
+            frame_schema_version: CURRENT_FRAME_SCHEMA_VERSION,
             created_at: chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
                 .unwrap()
                 .with_timezone(&chrono::Utc),
@@ -262,6 +691,7 @@ mod tests {
             game_id: Uuid::new_v4(),
             turn_number: 5,
             frame_data: Some(frame_data.clone()),
+            frame_schema_version: CURRENT_FRAME_SCHEMA_VERSION,
             created_at: chrono::Utc::now(),
         };
 
@@ -269,6 +699,31 @@ mod tests {
         assert!(turn.frame_data.as_ref().unwrap()["Snakes"].is_array());
     }
 
+    #[test]
+    fn test_turn_row_decompresses_frame() {
+        let frame = serde_json::json!({"Turn": 7, "Snakes": [], "Food": [], "Hazards": []});
+        let row = |frame_data, frame_compressed| TurnRow {
+            turn_id: Uuid::new_v4(),
+            game_id: Uuid::new_v4(),
+            turn_number: 7,
+            frame_data,
+            frame_compressed,
+            frame_schema_version: CURRENT_FRAME_SCHEMA_VERSION,
+            created_at: chrono::Utc::now(),
+        };
+
+        let compressed = crate::frame_compression::compress_frame(&frame).unwrap();
+        let turn = Turn::from(row(None, Some(compressed)));
+        assert_eq!(turn.frame_data, Some(frame.clone()));
+
+        let turn = Turn::from(row(Some(frame.clone()), None));
+        assert_eq!(turn.frame_data, Some(frame));
+
+        // A corrupt frame is served as missing rather than failing the read
+        let turn = Turn::from(row(None, Some(b"corrupt".to_vec())));
+        assert!(turn.frame_data.is_none());
+    }
+
     #[test]
     fn test_snake_turn_struct_serialization() {
         let snake_turn = SnakeTurn {
@@ -277,7 +732,9 @@ mod tests {
             game_battlesnake_id: Uuid::new_v4(),
             direction: "up".to_string(),
             latency_ms: Some(123),
+            compensated_latency_ms: None,
             timed_out: false,
+            parse_error: None,
             created_at: chrono::Utc::now(),
         };
 
@@ -296,7 +753,9 @@ mod tests {
                 game_battlesnake_id: Uuid::new_v4(),
                 direction: direction.to_string(),
                 latency_ms: None,
+                compensated_latency_ms: None,
                 timed_out: false,
+                parse_error: None,
                 created_at: chrono::Utc::now(),
             };
             assert_eq!(snake_turn.direction, direction);
@@ -311,10 +770,13 @@ mod tests {
             game_battlesnake_id: Uuid::new_v4(),
             direction: "up".to_string(),
             latency_ms: None,
+            compensated_latency_ms: None,
             timed_out: true,
+            parse_error: Some("invalid move \"sideways\"".to_string()),
             created_at: chrono::Utc::now(),
         };
         assert!(snake_turn.timed_out);
         assert!(snake_turn.latency_ms.is_none());
+        assert!(snake_turn.parse_error.is_some());
     }
 }
@@ -100,3 +100,21 @@ pub async fn create_or_update_user(
 
     Ok(user)
 }
+
+// Find a user's ID from their GitHub login, ignoring case
+pub async fn get_user_id_by_github_login(
+    pool: &PgPool,
+    github_login: &str,
+) -> cja::Result<Option<Uuid>> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT user_id
+        FROM users
+        WHERE LOWER(github_login) = LOWER($1)
+        "#,
+        github_login
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to look up user by GitHub login")
+}
@@ -0,0 +1,232 @@
+use color_eyre::eyre::{Context as _, eyre};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::game::{GameBoardSize, GameType};
+
+// Slowest and fastest replay speeds, as multiples of the board viewer's normal speed
+pub const MIN_PLAYBACK_SPEED: f64 = 0.25;
+pub const MAX_PLAYBACK_SPEED: f64 = 4.0;
+
+// The board viewer's frame rate at normal speed
+const BOARD_VIEWER_FPS: f64 = 6.0;
+
+// Color theme for pages and the board viewer
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    // Follow the browser's light or dark setting
+    System,
+    #[default]
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Theme::System => "system",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = color_eyre::eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "system" => Ok(Theme::System),
+            "light" => Ok(Theme::Light),
+            "dark" => Ok(Theme::Dark),
+            _ => Err(eyre!("Invalid theme: {}", s)),
+        }
+    }
+}
+
+// Where a user wants notifications sent
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationChannel {
+    Email,
+    // Browser notifications while the arena is open
+    Web,
+}
+
+impl NotificationChannel {
+    pub const ALL: [NotificationChannel; 2] =
+        [NotificationChannel::Email, NotificationChannel::Web];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationChannel::Email => "email",
+            NotificationChannel::Web => "web",
+        }
+    }
+}
+
+impl FromStr for NotificationChannel {
+    type Err = color_eyre::eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "email" => Ok(NotificationChannel::Email),
+            "web" => Ok(NotificationChannel::Web),
+            _ => Err(eyre!("Invalid notification channel: {}", s)),
+        }
+    }
+}
+
+// A user's defaults for creating games, and display settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserPreferences {
+    pub board_size: GameBoardSize,
+    pub game_type: GameType,
+    // IANA time zone name
+    pub timezone: String,
+    pub notification_channels: Vec<NotificationChannel>,
+    pub playback_speed: f64,
+    pub theme: Theme,
+    // Snakes with any of these tags come first when looking for opponents
+    pub preferred_opponent_tags: Vec<String>,
+}
+
+impl Default for UserPreferences {
+    fn default() -> Self {
+        Self {
+            board_size: GameBoardSize::Medium,
+            game_type: GameType::Standard,
+            timezone: "UTC".to_string(),
+            notification_channels: Vec::new(),
+            playback_speed: 1.0,
+            theme: Theme::Light,
+            preferred_opponent_tags: Vec::new(),
+        }
+    }
+}
+
+impl UserPreferences {
+    // Board viewer query parameters for these preferences, to append to its URL
+    pub fn board_viewer_params(&self) -> String {
+        let fps = (BOARD_VIEWER_FPS * self.playback_speed).round().max(1.0);
+        let mut params = format!("&fps={}", fps);
+        if self.theme != Theme::System {
+            params.push_str(&format!("&theme={}", self.theme.as_str()));
+        }
+        params
+    }
+}
+
+#[derive(Debug)]
+struct UserPreferencesRow {
+    board_size: String,
+    game_type: String,
+    timezone: String,
+    notification_channels: Vec<String>,
+    playback_speed: f64,
+    theme: String,
+    preferred_opponent_tags: Vec<String>,
+}
+
+impl TryFrom<UserPreferencesRow> for UserPreferences {
+    type Error = color_eyre::eyre::Report;
+
+    fn try_from(row: UserPreferencesRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            board_size: GameBoardSize::from_str(&row.board_size)?,
+            game_type: GameType::from_str(&row.game_type)?,
+            timezone: row.timezone,
+            notification_channels: row
+                .notification_channels
+                .iter()
+                .map(|c| NotificationChannel::from_str(c))
+                .collect::<Result<_, _>>()?,
+            playback_speed: row.playback_speed,
+            theme: Theme::from_str(&row.theme)?,
+            preferred_opponent_tags: row.preferred_opponent_tags,
+        })
+    }
+}
+
+// Get a user's preferences, the defaults if they haven't saved any
+pub async fn get_user_preferences(pool: &PgPool, user_id: Uuid) -> cja::Result<UserPreferences> {
+    let row = sqlx::query_as!(
+        UserPreferencesRow,
+        r#"
+        SELECT board_size, game_type, timezone, notification_channels, playback_speed, theme,
+               preferred_opponent_tags
+        FROM user_preferences
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch preferences for user {}", user_id))?;
+
+    match row {
+        Some(row) => row
+            .try_into()
+            .wrap_err_with(|| format!("Invalid stored preferences for user {}", user_id)),
+        None => Ok(UserPreferences::default()),
+    }
+}
+
+// Save a user's preferences, replacing any saved before
+pub async fn save_user_preferences(
+    pool: &PgPool,
+    user_id: Uuid,
+    preferences: &UserPreferences,
+    now: chrono::DateTime<chrono::Utc>,
+) -> cja::Result<()> {
+    let notification_channels: Vec<&str> = preferences
+        .notification_channels
+        .iter()
+        .map(|c| c.as_str())
+        .collect();
+    sqlx::query!(
+        r#"
+        INSERT INTO user_preferences
+            (user_id, board_size, game_type, timezone, notification_channels, playback_speed, theme,
+             preferred_opponent_tags, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT (user_id) DO UPDATE
+        SET board_size = EXCLUDED.board_size,
+            game_type = EXCLUDED.game_type,
+            timezone = EXCLUDED.timezone,
+            notification_channels = EXCLUDED.notification_channels,
+            playback_speed = EXCLUDED.playback_speed,
+            theme = EXCLUDED.theme,
+            preferred_opponent_tags = EXCLUDED.preferred_opponent_tags,
+            updated_at = EXCLUDED.updated_at
+        "#,
+        user_id,
+        preferences.board_size.as_str(),
+        preferences.game_type.as_str(),
+        &preferences.timezone,
+        &notification_channels as &[&str],
+        preferences.playback_speed,
+        preferences.theme.as_str(),
+        &preferences.preferred_opponent_tags,
+        now
+    )
+    .execute(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to save preferences for user {}", user_id))?;
+
+    Ok(())
+}
+
+// Whether Postgres knows a time zone by this name
+pub async fn is_known_timezone(pool: &PgPool, timezone: &str) -> cja::Result<bool> {
+    sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1) AS "exists!""#,
+        timezone
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err("Failed to check time zone")
+}
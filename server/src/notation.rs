@@ -0,0 +1,568 @@
+//! Game notation: a compact text form of a game for reading, diffing and sharing
+//!
+//! Like chess's PGN, a game is a few `[Tag "value"]` headers describing the
+//! board, the snakes and the outcome, followed by the moves, one turn per line,
+//! with what happened on the turn in `{braces}`:
+//!
+//! ```text
+//! [Board "7x7"]
+//! [Type "Standard"]
+//! [SpawnFood "false"]
+//! [Food "5,2"]
+//! [Snake "A" "alpha" "Alpha" "100" "1,1 1,1 1,1"]
+//! [Snake "B" "beta" "Beta" "100" "5,5 5,5 5,5"]
+//! [Turns "6"]
+//! [Result "B A"]
+//! [Final "A 0 3" "B 97 4"]
+//!
+//! 1. A:U B:D
+//! 2. A:U B:D
+//! 3. A:U B:D {B eats 5,2}
+//! 4. A:U B:L
+//! 5. A:U B:L
+//! 6. A:U B:L {A out: wall-collision}
+//! ```
+//!
+//! Snakes go by letters in the order they're listed, bodies head first.
+//! Coordinates are `x,y` from the bottom left, as in the board viewer. Each
+//! line is numbered with the turn its moves lead to, and `Result` lists the
+//! snakes winner first, `Final` each one's health and length at the end.
+//!
+//! The comments (food eaten, food spawned, eliminations) are worked out by
+//! playing the moves through the engine when a game is written, and ignored
+//! when it's read back. Everything else maps onto a [`ReplayFixture`], so
+//! notation replays like any other fixture.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+
+use battlesnake_game_types::types::Move;
+use battlesnake_game_types::wire_representation::Position;
+use color_eyre::eyre::{Context as _, eyre};
+use uuid::Uuid;
+
+use crate::engine::apply_turn_with_eliminations;
+use crate::replay_fixture::{
+    FIXTURE_VERSION, FixtureBoard, FixtureCoord, FixtureOutcome, FixtureSnake, FixtureSnakeOutcome,
+    ReplayFixture,
+};
+use crate::snake_client::parse_direction;
+
+/// File extension for games written in notation
+pub const NOTATION_EXTENSION: &str = "sgn";
+
+/// The letter a snake goes by, from its place in the list of snakes
+fn snake_letter(index: usize) -> cja::Result<char> {
+    u8::try_from(index)
+        .ok()
+        .filter(|&i| i < 26)
+        .map(|i| char::from(b'A' + i))
+        .ok_or_else(|| eyre!("Notation can only letter 26 snakes"))
+}
+
+fn move_letter(direction: Move) -> char {
+    match direction {
+        Move::Up => 'U',
+        Move::Down => 'D',
+        Move::Left => 'L',
+        Move::Right => 'R',
+    }
+}
+
+fn parse_move_letter(letter: &str) -> Option<Move> {
+    match letter {
+        "U" => Some(Move::Up),
+        "D" => Some(Move::Down),
+        "L" => Some(Move::Left),
+        "R" => Some(Move::Right),
+        _ => None,
+    }
+}
+
+fn coords(list: &[FixtureCoord]) -> String {
+    list.iter()
+        .map(|c| format!("{},{}", c.x, c.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn position(p: &Position) -> String {
+    format!("{},{}", p.x, p.y)
+}
+
+fn parse_coords(text: &str) -> cja::Result<Vec<FixtureCoord>> {
+    text.split_whitespace()
+        .map(|coord| {
+            let (x, y) = coord
+                .split_once(',')
+                .ok_or_else(|| eyre!("Invalid coordinate {:?}", coord))?;
+            Ok(FixtureCoord {
+                x: x.parse()
+                    .wrap_err_with(|| format!("Invalid coordinate {:?}", coord))?,
+                y: y.parse()
+                    .wrap_err_with(|| format!("Invalid coordinate {:?}", coord))?,
+            })
+        })
+        .collect()
+}
+
+fn write_tag(out: &mut String, name: &str, values: &[String]) {
+    let quoted: Vec<String> = values
+        .iter()
+        .map(|v| format!("\"{}\"", v.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    let _ = writeln!(out, "[{} {}]", name, quoted.join(" "));
+}
+
+/// Split `[Name "value" "value"]` into its name and unescaped values
+fn parse_tag(line: &str) -> cja::Result<(String, Vec<String>)> {
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or_else(|| eyre!("Tags look like [Name \"value\"]"))?;
+    let (name, mut rest) = inner.split_once(' ').unwrap_or((inner, ""));
+
+    let mut values = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let mut chars = rest
+            .strip_prefix('"')
+            .ok_or_else(|| eyre!("Tag {} has an unquoted value", name))?
+            .char_indices();
+        let mut value = String::new();
+        let end = loop {
+            match chars.next() {
+                Some((_, '\\')) => match chars.next() {
+                    Some((_, escaped)) => value.push(escaped),
+                    None => break None,
+                },
+                Some((i, '"')) => break Some(i),
+                Some((_, c)) => value.push(c),
+                None => break None,
+            }
+        };
+        let end = end.ok_or_else(|| eyre!("Tag {} has an unterminated value", name))?;
+        values.push(value);
+        // Skip the opening quote, the value and the closing quote
+        rest = &rest[end + 2..];
+    }
+
+    Ok((name.to_string(), values))
+}
+
+/// The tags at the head of a game, in the order they were written
+struct Tags(Vec<(String, Vec<String>)>);
+
+impl Tags {
+    fn values(&self, name: &str) -> Option<&[String]> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, values)| values.as_slice())
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.values(name)
+            .and_then(|values| values.first())
+            .map(String::as_str)
+    }
+
+    fn required(&self, name: &str) -> cja::Result<&str> {
+        self.value(name)
+            .ok_or_else(|| eyre!("Missing the [{}] tag", name))
+    }
+
+    fn all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a [String]> {
+        self.0
+            .iter()
+            .filter(move |(n, _)| n == name)
+            .map(|(_, values)| values.as_slice())
+    }
+}
+
+/// Write a game out as notation
+///
+/// Plays the moves through the engine to annotate each turn, the same way
+/// [`ReplayFixture::play`] does.
+pub fn to_notation(fixture: &ReplayFixture) -> cja::Result<String> {
+    let letters: HashMap<&str, char> = fixture
+        .initial
+        .snakes
+        .iter()
+        .enumerate()
+        .map(|(i, s)| Ok((s.id.as_str(), snake_letter(i)?)))
+        .collect::<cja::Result<_>>()?;
+    let letter = |id: &str| -> cja::Result<char> {
+        letters
+            .get(id)
+            .copied()
+            .ok_or_else(|| eyre!("Snake {} isn't one of the game's snakes", id))
+    };
+
+    let mut out = String::new();
+    if let Some(game_id) = fixture.source_game_id {
+        write_tag(&mut out, "Game", &[game_id.to_string()]);
+    }
+    write_tag(
+        &mut out,
+        "Board",
+        &[format!("{}x{}", fixture.width, fixture.height)],
+    );
+    write_tag(&mut out, "Type", std::slice::from_ref(&fixture.game_type));
+    if let Some(seed) = fixture.seed {
+        write_tag(&mut out, "Seed", &[seed.to_string()]);
+    }
+    if !fixture.spawn_food {
+        write_tag(&mut out, "SpawnFood", &["false".to_string()]);
+    }
+    write_tag(&mut out, "Food", &[coords(&fixture.initial.food)]);
+    if !fixture.initial.hazards.is_empty() {
+        write_tag(&mut out, "Hazards", &[coords(&fixture.initial.hazards)]);
+    }
+    for snake in &fixture.initial.snakes {
+        write_tag(
+            &mut out,
+            "Snake",
+            &[
+                letter(&snake.id)?.to_string(),
+                snake.id.clone(),
+                snake.name.clone(),
+                snake.health.to_string(),
+                coords(&snake.body),
+            ],
+        );
+    }
+    write_tag(&mut out, "Turns", &[fixture.outcome.final_turn.to_string()]);
+    write_tag(
+        &mut out,
+        "Result",
+        &[fixture
+            .outcome
+            .placements
+            .iter()
+            .map(|id| letter(id).map(String::from))
+            .collect::<cja::Result<Vec<_>>>()?
+            .join(" ")],
+    );
+    write_tag(
+        &mut out,
+        "Final",
+        &fixture
+            .outcome
+            .snakes
+            .iter()
+            .map(|s| Ok(format!("{} {} {}", letter(&s.id)?, s.health, s.length)))
+            .collect::<cja::Result<Vec<_>>>()?,
+    );
+    out.push('\n');
+
+    let (mut game, seed) = fixture.start()?;
+    let mut last_moves: HashMap<String, Move> = HashMap::new();
+    for (turn, recorded) in fixture.moves.iter().enumerate() {
+        let _ = write!(out, "{}.", turn + 1);
+
+        // Alive snakes without a recorded move carry on as the replay would
+        let mut moves = Vec::new();
+        for snake in game.board.snakes.iter().filter(|s| s.health > 0) {
+            let direction = match recorded.get(&snake.id) {
+                Some(m) => {
+                    let direction = parse_direction(m).ok_or_else(|| {
+                        eyre!("Invalid move {:?} for {} on turn {}", m, snake.id, turn)
+                    })?;
+                    let _ = write!(out, " {}:{}", letter(&snake.id)?, move_letter(direction));
+                    direction
+                }
+                None => last_moves.get(&snake.id).copied().unwrap_or(Move::Up),
+            };
+            moves.push((snake.id.clone(), direction));
+        }
+        last_moves.extend(moves.iter().cloned());
+
+        let food_before = game.board.food.clone();
+        let (next, eliminations) = apply_turn_with_eliminations(game, &moves, seed);
+        game = next;
+        game.turn += 1;
+
+        for snake in game
+            .board
+            .snakes
+            .iter()
+            .filter(|s| s.health > 0 && food_before.contains(&s.head))
+        {
+            let _ = write!(
+                out,
+                " {{{} eats {}}}",
+                letter(&snake.id)?,
+                position(&snake.head)
+            );
+        }
+        for elimination in &eliminations {
+            let _ = write!(
+                out,
+                " {{{} out: {}",
+                letter(&elimination.snake_id)?,
+                elimination.cause
+            );
+            if !elimination.eliminated_by.is_empty()
+                && elimination.eliminated_by != elimination.snake_id
+            {
+                let _ = write!(out, " by {}", letter(&elimination.eliminated_by)?);
+            }
+            out.push('}');
+        }
+        for food in game.board.food.iter().filter(|f| !food_before.contains(f)) {
+            let _ = write!(out, " {{food {}}}", position(food));
+        }
+        out.push('\n');
+    }
+
+    Ok(out)
+}
+
+/// Read a game back from notation, ignoring its comments
+pub fn from_notation(text: &str) -> cja::Result<ReplayFixture> {
+    let mut tags = Tags(Vec::new());
+    let mut move_text = String::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            tags.0
+                .push(parse_tag(line).wrap_err_with(|| format!("Line {}", number + 1))?);
+        } else {
+            move_text.push_str(line);
+            move_text.push('\n');
+        }
+    }
+
+    let source_game_id = tags
+        .value("Game")
+        .map(|id| Uuid::parse_str(id).wrap_err("Invalid [Game] tag"))
+        .transpose()?;
+    let (width, height) = tags
+        .required("Board")?
+        .split_once('x')
+        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+        .ok_or_else(|| eyre!("[Board] looks like \"11x11\""))?;
+    let seed = tags
+        .value("Seed")
+        .map(|seed| seed.parse().wrap_err("Invalid [Seed] tag"))
+        .transpose()?;
+    let spawn_food = match tags.value("SpawnFood") {
+        None | Some("true") => true,
+        Some("false") => false,
+        Some(other) => {
+            return Err(eyre!(
+                "[SpawnFood] is \"true\" or \"false\", not {:?}",
+                other
+            ));
+        }
+    };
+
+    let mut ids: HashMap<String, String> = HashMap::new();
+    let mut snakes = Vec::new();
+    for snake in tags.all("Snake") {
+        let [letter, id, name, health, body] = snake else {
+            return Err(eyre!(
+                "[Snake] has a letter, ID, name, health and body, got {:?}",
+                snake
+            ));
+        };
+        ids.insert(letter.clone(), id.clone());
+        snakes.push(FixtureSnake {
+            id: id.clone(),
+            name: name.clone(),
+            health: health
+                .parse()
+                .wrap_err_with(|| format!("Invalid health for snake {}", letter))?,
+            body: parse_coords(body)
+                .wrap_err_with(|| format!("Invalid body for snake {}", letter))?,
+        });
+    }
+    let snake_id = |letter: &str| {
+        ids.get(letter)
+            .cloned()
+            .ok_or_else(|| eyre!("No snake is lettered {}", letter))
+    };
+
+    // Strip the comments, then read the turn numbers and moves that are left
+    let mut uncommented = String::new();
+    let mut in_comment = false;
+    for c in move_text.chars() {
+        match (in_comment, c) {
+            (false, '{') => in_comment = true,
+            (true, '{') => return Err(eyre!("Unclosed {{ in the moves")),
+            (true, '}') => in_comment = false,
+            (false, '}') => return Err(eyre!("Unopened }} in the moves")),
+            (false, c) => uncommented.push(c),
+            (true, _) => {}
+        }
+    }
+    if in_comment {
+        return Err(eyre!("Unclosed {{ in the moves"));
+    }
+
+    let mut moves: Vec<BTreeMap<String, String>> = Vec::new();
+    for token in uncommented.split_whitespace() {
+        if let Some(number) = token.strip_suffix('.') {
+            let turn: usize = number
+                .parse()
+                .wrap_err_with(|| format!("Invalid turn number {:?}", token))?;
+            if turn <= moves.len() {
+                return Err(eyre!("Turn {} is out of order", turn));
+            }
+            moves.resize(turn, Default::default());
+            continue;
+        }
+
+        let (letter, direction) = token
+            .split_once(':')
+            .ok_or_else(|| eyre!("Moves look like A:U, got {:?}", token))?;
+        let direction = parse_move_letter(direction)
+            .ok_or_else(|| eyre!("Invalid move {:?}, expected U, D, L or R", token))?;
+        let turn = moves
+            .last_mut()
+            .ok_or_else(|| eyre!("Move {} comes before the first turn number", token))?;
+        if turn
+            .insert(snake_id(letter)?, direction.to_string())
+            .is_some()
+        {
+            return Err(eyre!("Snake {} moves twice on one turn", letter));
+        }
+    }
+
+    let final_turn = match tags.value("Turns") {
+        Some(turns) => turns.parse().wrap_err("Invalid [Turns] tag")?,
+        None => i32::try_from(moves.len())?,
+    };
+    if usize::try_from(final_turn).ok() < Some(moves.len()) {
+        return Err(eyre!(
+            "The moves run to turn {}, past [Turns] {}",
+            moves.len(),
+            final_turn
+        ));
+    }
+    moves.resize(
+        usize::try_from(final_turn).unwrap_or_default(),
+        Default::default(),
+    );
+
+    let placements = tags
+        .required("Result")?
+        .split_whitespace()
+        .map(snake_id)
+        .collect::<cja::Result<_>>()?;
+    let outcome_snakes = tags
+        .values("Final")
+        .ok_or_else(|| eyre!("Missing the [Final] tag"))?
+        .iter()
+        .map(|snake| {
+            let parts: Vec<&str> = snake.split_whitespace().collect();
+            let [letter, health, length] = parts.as_slice() else {
+                return Err(eyre!(
+                    "[Final] snakes look like \"A 97 4\", got {:?}",
+                    snake
+                ));
+            };
+            Ok(FixtureSnakeOutcome {
+                id: snake_id(letter)?,
+                health: health.parse().wrap_err("Invalid health in [Final]")?,
+                length: length.parse().wrap_err("Invalid length in [Final]")?,
+            })
+        })
+        .collect::<cja::Result<_>>()?;
+
+    Ok(ReplayFixture {
+        version: FIXTURE_VERSION,
+        source_game_id,
+        width,
+        height,
+        game_type: tags.required("Type")?.to_string(),
+        spawn_food,
+        seed,
+        initial: FixtureBoard {
+            food: parse_coords(tags.value("Food").unwrap_or_default())
+                .wrap_err("Invalid [Food] tag")?,
+            hazards: parse_coords(tags.value("Hazards").unwrap_or_default())
+                .wrap_err("Invalid [Hazards] tag")?,
+            snakes,
+        },
+        moves,
+        outcome: FixtureOutcome {
+            final_turn,
+            placements,
+            snakes: outcome_snakes,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn wall_and_food() -> ReplayFixture {
+        ReplayFixture::load(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/replays/wall-and-food.json"),
+        )
+        .unwrap()
+    }
+
+    const WALL_AND_FOOD: &str = r#"[Board "7x7"]
+[Type "Standard"]
+[SpawnFood "false"]
+[Food "5,2"]
+[Snake "A" "alpha" "Alpha" "100" "1,1 1,1 1,1"]
+[Snake "B" "beta" "Beta" "100" "5,5 5,5 5,5"]
+[Turns "6"]
+[Result "B A"]
+[Final "A 0 3" "B 97 4"]
+
+1. A:U B:D
+2. A:U B:D
+3. A:U B:D {B eats 5,2}
+4. A:U B:L
+5. A:U B:L
+6. A:U B:L {A out: wall-collision}
+"#;
+
+    #[test]
+    fn test_writes_annotated_notation() {
+        assert_eq!(to_notation(&wall_and_food()).unwrap(), WALL_AND_FOOD);
+    }
+
+    #[test]
+    fn test_notation_round_trips() {
+        let mut fixture = wall_and_food();
+        fixture.source_game_id = Some(Uuid::nil());
+        fixture.seed = Some(42);
+        fixture.initial.snakes[1].name = "Beta \"the\" \\ Snake".to_string();
+
+        let text = to_notation(&fixture).unwrap();
+        assert_eq!(from_notation(&text).unwrap(), fixture);
+    }
+
+    #[test]
+    fn test_reading_ignores_comments_and_layout() {
+        let text = WALL_AND_FOOD
+            .replace("{B eats 5,2}", "{B should have turned here}")
+            .replace("4. A:U B:L\n5.", "4. A:U B:L 5.");
+        assert_eq!(from_notation(&text).unwrap(), wall_and_food());
+    }
+
+    #[test]
+    fn test_invalid_notation_is_rejected() {
+        let error = |text: &str| format!("{:#}", from_notation(text).unwrap_err());
+
+        assert!(error(&WALL_AND_FOOD.replace("B:D", "B:X")).contains("Invalid move"));
+        assert!(error(&WALL_AND_FOOD.replace("A:U", "C:U")).contains("No snake is lettered C"));
+        assert!(
+            error(&WALL_AND_FOOD.replace("[Turns \"6\"]", "[Turns \"5\"]"))
+                .contains("past [Turns] 5")
+        );
+        assert!(error(&WALL_AND_FOOD.replace("{B eats 5,2}", "{B eats")).contains("Unclosed"));
+        assert!(error(&WALL_AND_FOOD.replace("[Type \"Standard\"]\n", "")).contains("[Type]"));
+    }
+}
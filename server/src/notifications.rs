@@ -0,0 +1,216 @@
+//! In-app notifications
+//!
+//! Every user has an inbox of things that happened to their games, snakes and
+//! leagues, behind the bell at the top of each page and in full at
+//! `/notifications`. Notifications come from the places that already react to
+//! those events: an engine hook when a game ends, the snake health checks, win
+//! rate alerts and league completion. They all go through [`notify`], so the
+//! email and browser channels users can pick in their settings, which aren't
+//! delivered yet, have one place to hang off. Challenges and tournament rounds
+//! aren't modelled, so there's nothing to notify about them yet.
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::engine::hooks::{EngineHook, GameEnded};
+use crate::league_awards::LeagueAnnouncement;
+use crate::models::game_battlesnake::GameBattlesnakeWithDetails;
+use crate::models::league::LeagueMember;
+use crate::models::notification::{NewNotification, NotificationKind, create_notifications};
+use crate::state::AppState;
+
+/// Deliver notifications to their users' inboxes
+pub async fn notify(app_state: &AppState, notifications: Vec<NewNotification>) -> cja::Result<()> {
+    if notifications.is_empty() {
+        return Ok(());
+    }
+    create_notifications(&app_state.db, &notifications).await?;
+    tracing::debug!(count = notifications.len(), "Sent notifications");
+    Ok(())
+}
+
+/// "1st", "2nd", "3rd", "11th" and so on
+pub fn ordinal(n: i32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+/// One notification per snake owner when a game ends, with how each of their
+/// snakes placed
+pub fn game_finished(
+    game_id: Uuid,
+    snakes: &[GameBattlesnakeWithDetails],
+    placements: &[(String, i32)],
+) -> Vec<NewNotification> {
+    let placement_of: HashMap<&str, i32> = placements
+        .iter()
+        .map(|(id, placement)| (id.as_str(), *placement))
+        .collect();
+
+    // Owners in the order their first snake joined the game
+    let mut results: Vec<(Uuid, Vec<String>)> = Vec::new();
+    for snake in snakes {
+        let result = match placement_of.get(snake.game_battlesnake_id.to_string().as_str()) {
+            Some(placement) => format!(
+                "{} placed {} of {}",
+                snake.name,
+                ordinal(*placement),
+                snakes.len()
+            ),
+            None => format!("{} didn't place", snake.name),
+        };
+        match results
+            .iter_mut()
+            .find(|(user_id, _)| *user_id == snake.user_id)
+        {
+            Some((_, lines)) => lines.push(result),
+            None => results.push((snake.user_id, vec![result])),
+        }
+    }
+
+    results
+        .into_iter()
+        .map(|(user_id, lines)| NewNotification {
+            user_id,
+            kind: NotificationKind::GameFinished,
+            title: "Game finished".to_string(),
+            body: lines.join("; "),
+            link: Some(format!("/games/{}", game_id)),
+        })
+        .collect()
+}
+
+/// Tell a snake's owner it just started failing its health checks
+pub fn snake_unhealthy(user_id: Uuid, snake_name: &str, error: Option<&str>) -> NewNotification {
+    NewNotification {
+        user_id,
+        kind: NotificationKind::SnakeUnhealthy,
+        title: format!("{} is failing health checks", snake_name),
+        body: error.unwrap_or("GET / didn't answer").to_string(),
+        link: Some("/battlesnakes".to_string()),
+    }
+}
+
+/// Tell a snake's owner one of their win rate alerts fired
+pub fn win_rate_alert(
+    user_id: Uuid,
+    battlesnake_id: Uuid,
+    snake_name: &str,
+    previous: f64,
+    current: f64,
+) -> NewNotification {
+    NewNotification {
+        user_id,
+        kind: NotificationKind::WinRateAlert,
+        title: format!("{}'s win rate dropped", snake_name),
+        body: format!("Down from {:.1}% to {:.1}%", previous, current),
+        link: Some(format!("/battlesnakes/{}/profile", battlesnake_id)),
+    }
+}
+
+/// Tell every member of a league that it's been completed
+pub fn league_completed(
+    league_id: Uuid,
+    members: &[LeagueMember],
+    announcement: &LeagueAnnouncement,
+) -> Vec<NewNotification> {
+    let body = match announcement.standings.first() {
+        Some(champion) => format!("{} won. See the final standings.", champion.name),
+        None => "See the final standings.".to_string(),
+    };
+    members
+        .iter()
+        .map(|member| NewNotification {
+            user_id: member.user_id,
+            kind: NotificationKind::LeagueCompleted,
+            title: format!("{} is over", announcement.league_name),
+            body: body.clone(),
+            link: Some(format!("/leagues/{}/announcement", league_id)),
+        })
+        .collect()
+}
+
+/// Notifies snake owners when their games end
+pub struct NotificationsHook;
+
+#[async_trait::async_trait]
+impl EngineHook for NotificationsHook {
+    fn name(&self) -> &'static str {
+        "notifications"
+    }
+
+    async fn on_game_end(&self, app_state: &AppState, event: &GameEnded<'_>) -> cja::Result<()> {
+        notify(
+            app_state,
+            game_finished(event.game_id, event.snakes, event.placements),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snake(user_id: Uuid, name: &str) -> GameBattlesnakeWithDetails {
+        GameBattlesnakeWithDetails {
+            game_battlesnake_id: Uuid::new_v4(),
+            game_id: Uuid::nil(),
+            battlesnake_id: Uuid::new_v4(),
+            placement: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            name: name.to_string(),
+            url: "https://example.com".to_string(),
+            user_id,
+        }
+    }
+
+    #[test]
+    fn test_ordinal() {
+        let ordinals: Vec<_> = [1, 2, 3, 4, 11, 12, 13, 21, 22, 101, 111]
+            .into_iter()
+            .map(ordinal)
+            .collect();
+        assert_eq!(
+            ordinals,
+            vec![
+                "1st", "2nd", "3rd", "4th", "11th", "12th", "13th", "21st", "22nd", "101st",
+                "111th"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_game_finished_notifies_each_owner_once() {
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let snakes = vec![
+            snake(alice, "Alpha"),
+            snake(bob, "Beta"),
+            snake(alice, "Gamma"),
+        ];
+        let placements = vec![
+            (snakes[2].game_battlesnake_id.to_string(), 1),
+            (snakes[0].game_battlesnake_id.to_string(), 2),
+        ];
+        let game_id = Uuid::new_v4();
+
+        let notifications = game_finished(game_id, &snakes, &placements);
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(notifications[0].user_id, alice);
+        assert_eq!(
+            notifications[0].body,
+            "Alpha placed 2nd of 3; Gamma placed 1st of 3"
+        );
+        assert_eq!(notifications[1].user_id, bob);
+        assert_eq!(notifications[1].body, "Beta didn't place");
+        assert_eq!(notifications[1].link, Some(format!("/games/{}", game_id)));
+    }
+}
@@ -0,0 +1,335 @@
+//! Daily Parquet exports of game outcomes, for analysis in DuckDB or BigQuery
+//!
+//! Each day's finished games are written to storage as one Parquet file with
+//! a row per game, under a `date=` directory so readers can treat the whole
+//! prefix as one table partitioned by day. A day's file is rewritten whenever
+//! it's exported again, so rerunning a day is harmless.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use color_eyre::eyre::{Context as _, eyre};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::parquet::{Column, ColumnValues, write_table};
+use crate::state::AppState;
+
+/// Where in storage a day's outcomes go
+pub fn outcomes_path(day: NaiveDate) -> String {
+    format!(
+        "analytics/game_outcomes/date={}/outcomes.parquet",
+        day.format("%Y-%m-%d")
+    )
+}
+
+#[derive(Debug)]
+struct OutcomeRow {
+    game_id: Uuid,
+    board_size: String,
+    game_type: String,
+    created_at: DateTime<Utc>,
+    finished_at: DateTime<Utc>,
+    /// Last stored turn, None if the game never stored one
+    turns: Option<i32>,
+    /// From the game starting to it finishing, None for games from before
+    /// start times were recorded
+    duration_ms: Option<i64>,
+    /// Moves that timed out, across every snake
+    timeouts: i64,
+}
+
+#[derive(Debug)]
+struct OutcomeSnakeRow {
+    game_id: Uuid,
+    battlesnake_id: Uuid,
+    placement: Option<i32>,
+}
+
+/// One game's snakes and their placements, in placement order
+#[derive(Debug, Default)]
+struct OutcomeSnakes {
+    ids: Vec<Uuid>,
+    placements: Vec<Option<i32>>,
+}
+
+/// Games played here that finished on `day`, oldest first
+async fn outcomes_on(pool: &PgPool, day: NaiveDate) -> cja::Result<Vec<OutcomeRow>> {
+    let (start, end) = day_bounds(day)?;
+    sqlx::query_as!(
+        OutcomeRow,
+        r#"
+        SELECT
+            g.game_id,
+            g.board_size,
+            g.game_type,
+            g.created_at,
+            g.updated_at AS finished_at,
+            g.final_turn AS turns,
+            (EXTRACT(EPOCH FROM g.updated_at - g.started_at) * 1000)::BIGINT AS duration_ms,
+            (
+                SELECT COUNT(*)
+                FROM snake_turns st
+                JOIN turns t ON st.turn_id = t.turn_id
+                WHERE t.game_id = g.game_id AND st.timed_out
+            ) AS "timeouts!"
+        FROM games g
+        WHERE g.status = 'finished'
+          AND g.engine_game_id IS NULL
+          AND g.updated_at >= $1
+          AND g.updated_at < $2
+        ORDER BY g.updated_at, g.game_id
+        "#,
+        start,
+        end
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err_with(|| format!("Failed to fetch game outcomes for {}", day))
+}
+
+/// The snakes in each of `game_ids`, best placement first
+async fn outcome_snakes(
+    pool: &PgPool,
+    game_ids: &[Uuid],
+) -> cja::Result<HashMap<Uuid, OutcomeSnakes>> {
+    let snakes = sqlx::query_as!(
+        OutcomeSnakeRow,
+        r#"
+        SELECT game_id, battlesnake_id, placement
+        FROM game_battlesnakes
+        WHERE game_id = ANY($1)
+        ORDER BY game_id, placement NULLS LAST, created_at ASC
+        "#,
+        game_ids
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch snakes for game outcomes")?;
+
+    let mut by_game: HashMap<Uuid, OutcomeSnakes> = HashMap::new();
+    for snake in snakes {
+        let entry = by_game.entry(snake.game_id).or_default();
+        entry.ids.push(snake.battlesnake_id);
+        entry.placements.push(snake.placement);
+    }
+    Ok(by_game)
+}
+
+/// The first instant of `day` and the first instant after it, in UTC
+fn day_bounds(day: NaiveDate) -> cja::Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let next = day
+        .succ_opt()
+        .ok_or_else(|| eyre!("No day after {}", day))?;
+    Ok((
+        day.and_time(chrono::NaiveTime::MIN).and_utc(),
+        next.and_time(chrono::NaiveTime::MIN).and_utc(),
+    ))
+}
+
+/// The outcomes as a Parquet table
+///
+/// `snakes` and `placements` are JSON arrays in the same order, so the Nth
+/// entry of each describes the same snake. Placements are null for snakes
+/// that weren't placed.
+fn outcomes_table(
+    outcomes: &[OutcomeRow],
+    snakes: &HashMap<Uuid, OutcomeSnakes>,
+) -> cja::Result<Vec<u8>> {
+    let no_snakes = OutcomeSnakes::default();
+    let snakes_of = |game_id: &Uuid| snakes.get(game_id).unwrap_or(&no_snakes);
+
+    let json = |value: serde_json::Value| Some(value.to_string());
+    let columns = vec![
+        Column {
+            name: "game_id",
+            values: ColumnValues::Utf8(
+                outcomes
+                    .iter()
+                    .map(|o| Some(o.game_id.to_string()))
+                    .collect(),
+            ),
+        },
+        Column {
+            name: "snakes",
+            values: ColumnValues::Utf8(
+                outcomes
+                    .iter()
+                    .map(|o| json(serde_json::json!(snakes_of(&o.game_id).ids)))
+                    .collect(),
+            ),
+        },
+        Column {
+            name: "placements",
+            values: ColumnValues::Utf8(
+                outcomes
+                    .iter()
+                    .map(|o| json(serde_json::json!(snakes_of(&o.game_id).placements)))
+                    .collect(),
+            ),
+        },
+        Column {
+            name: "turns",
+            values: ColumnValues::Int32(outcomes.iter().map(|o| o.turns).collect()),
+        },
+        Column {
+            name: "board",
+            values: ColumnValues::Utf8(
+                outcomes
+                    .iter()
+                    .map(|o| Some(o.board_size.clone()))
+                    .collect(),
+            ),
+        },
+        Column {
+            name: "game_type",
+            values: ColumnValues::Utf8(
+                outcomes.iter().map(|o| Some(o.game_type.clone())).collect(),
+            ),
+        },
+        Column {
+            name: "duration_ms",
+            values: ColumnValues::Int64(outcomes.iter().map(|o| o.duration_ms).collect()),
+        },
+        Column {
+            name: "timeouts",
+            values: ColumnValues::Int64(outcomes.iter().map(|o| Some(o.timeouts)).collect()),
+        },
+        Column {
+            name: "created_at",
+            values: ColumnValues::TimestampMillis(
+                outcomes
+                    .iter()
+                    .map(|o| Some(o.created_at.timestamp_millis()))
+                    .collect(),
+            ),
+        },
+        Column {
+            name: "finished_at",
+            values: ColumnValues::TimestampMillis(
+                outcomes
+                    .iter()
+                    .map(|o| Some(o.finished_at.timestamp_millis()))
+                    .collect(),
+            ),
+        },
+    ];
+
+    write_table(&columns)
+}
+
+/// Write the outcomes of the games that finished on `day` to storage.
+/// Returns how many games were written.
+pub async fn export_outcomes(app_state: &AppState, day: NaiveDate) -> cja::Result<usize> {
+    let storage = app_state
+        .storage
+        .as_ref()
+        .ok_or_else(|| eyre!("Storage not configured"))?;
+
+    let outcomes = outcomes_on(&app_state.db, day).await?;
+    let game_ids: Vec<Uuid> = outcomes.iter().map(|o| o.game_id).collect();
+    let snakes = outcome_snakes(&app_state.db, &game_ids).await?;
+    let table = outcomes_table(&outcomes, &snakes)?;
+
+    let path = outcomes_path(day);
+    storage
+        .upload(&path, table, "application/vnd.apache.parquet")
+        .await
+        .wrap_err_with(|| format!("Failed to upload game outcomes for {}", day))?;
+
+    tracing::info!(day = %day, path = %path, games = outcomes.len(), "Exported game outcomes");
+    Ok(outcomes.len())
+}
+
+/// Export yesterday's outcomes, the last whole day in UTC. Does nothing
+/// without storage configured.
+pub async fn export_yesterdays_outcomes(app_state: &AppState) -> cja::Result<()> {
+    if app_state.storage.is_none() {
+        tracing::debug!("Storage not configured, not exporting game outcomes");
+        return Ok(());
+    }
+
+    let yesterday = app_state
+        .clock
+        .now()
+        .date_naive()
+        .pred_opt()
+        .ok_or_else(|| eyre!("No day before today"))?;
+    export_outcomes(app_state, yesterday).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::{Field, Row};
+
+    use super::*;
+
+    #[test]
+    fn test_outcomes_path_is_partitioned_by_day() {
+        let day = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        assert_eq!(
+            outcomes_path(day),
+            "analytics/game_outcomes/date=2026-03-09/outcomes.parquet"
+        );
+
+        let (start, end) = day_bounds(day).unwrap();
+        assert_eq!(start.to_rfc3339(), "2026-03-09T00:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2026-03-10T00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_outcomes_table() {
+        let game_id = Uuid::nil();
+        let winner = Uuid::from_u128(1);
+        let outcomes = [OutcomeRow {
+            game_id,
+            board_size: "11x11".to_string(),
+            game_type: "Standard".to_string(),
+            created_at: DateTime::from_timestamp_millis(1_773_100_800_000).unwrap(),
+            finished_at: DateTime::from_timestamp_millis(1_773_100_860_000).unwrap(),
+            turns: Some(42),
+            duration_ms: None,
+            timeouts: 3,
+        }];
+        let snakes = HashMap::from([(
+            game_id,
+            OutcomeSnakes {
+                ids: vec![winner],
+                placements: vec![Some(1)],
+            },
+        )]);
+
+        let table = outcomes_table(&outcomes, &snakes).unwrap();
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&table).unwrap();
+        let reader = SerializedFileReader::new(file).unwrap();
+        let rows: Vec<Row> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(rows.len(), 1);
+        let field = |name: &str| {
+            rows[0]
+                .get_column_iter()
+                .find(|(column, _)| column.as_str() == name)
+                .map(|(_, field)| field.clone())
+                .unwrap()
+        };
+        assert_eq!(field("snakes"), Field::Str(format!("[\"{}\"]", winner)));
+        assert_eq!(field("placements"), Field::Str("[1]".to_string()));
+        assert_eq!(field("turns"), Field::Int(42));
+        assert_eq!(field("duration_ms"), Field::Null);
+        assert_eq!(field("timeouts"), Field::Long(3));
+        assert_eq!(
+            field("finished_at"),
+            Field::TimestampMillis(1_773_100_860_000)
+        );
+
+        assert!(outcomes_table(&[], &HashMap::new()).is_ok());
+    }
+}
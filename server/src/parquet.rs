@@ -0,0 +1,243 @@
+//! Flat tables as Parquet files
+//!
+//! A thin layer over the `parquet` crate's writer for the tables exported
+//! here, which are small enough to be one row group. Every column is
+//! optional, and pages are Snappy compressed, which every reader understands.
+
+use std::sync::Arc;
+
+use color_eyre::eyre::{Context as _, eyre};
+use parquet::basic::{Compression, LogicalType, Repetition, TimeUnit, Type as PhysicalType};
+use parquet::data_type::{ByteArray, ByteArrayType, Int32Type, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+
+/// One column's values, None where a row has no value
+#[derive(Debug, Clone)]
+pub enum ColumnValues {
+    Int32(Vec<Option<i32>>),
+    Int64(Vec<Option<i64>>),
+    /// Milliseconds since the Unix epoch, in UTC
+    TimestampMillis(Vec<Option<i64>>),
+    Utf8(Vec<Option<String>>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub name: &'static str,
+    pub values: ColumnValues,
+}
+
+impl ColumnValues {
+    fn len(&self) -> usize {
+        match self {
+            ColumnValues::Int32(values) => values.len(),
+            ColumnValues::Int64(values) | ColumnValues::TimestampMillis(values) => values.len(),
+            ColumnValues::Utf8(values) => values.len(),
+        }
+    }
+
+    /// The column's schema field
+    fn field(&self, name: &str) -> cja::Result<Type> {
+        let (physical_type, logical_type) = match self {
+            ColumnValues::Int32(_) => (PhysicalType::INT32, None),
+            ColumnValues::Int64(_) => (PhysicalType::INT64, None),
+            ColumnValues::TimestampMillis(_) => (
+                PhysicalType::INT64,
+                Some(LogicalType::Timestamp {
+                    is_adjusted_to_u_t_c: true,
+                    unit: TimeUnit::MILLIS(Default::default()),
+                }),
+            ),
+            ColumnValues::Utf8(_) => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+        };
+        Type::primitive_type_builder(name, physical_type)
+            .with_repetition(Repetition::OPTIONAL)
+            .with_logical_type(logical_type)
+            .build()
+            .wrap_err_with(|| format!("Invalid Parquet column {}", name))
+    }
+}
+
+/// Definition levels for an optional column: 1 where a row has a value
+fn definition_levels<T>(values: &[Option<T>]) -> Vec<i16> {
+    values.iter().map(|v| i16::from(v.is_some())).collect()
+}
+
+/// A table as a Parquet file. Every column needs the same number of rows.
+pub fn write_table(columns: &[Column]) -> cja::Result<Vec<u8>> {
+    let rows = columns.first().map(|c| c.values.len()).unwrap_or(0);
+    if let Some(column) = columns.iter().find(|c| c.values.len() != rows) {
+        return Err(eyre!(
+            "Column {} has {} rows, expected {}",
+            column.name,
+            column.values.len(),
+            rows
+        ));
+    }
+
+    let fields = columns
+        .iter()
+        .map(|c| c.values.field(c.name).map(Arc::new))
+        .collect::<cja::Result<Vec<_>>>()?;
+    let schema = Type::group_type_builder("schema")
+        .with_fields(fields)
+        .build()
+        .wrap_err("Invalid Parquet schema")?;
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .set_created_by("arena".to_string())
+        .build();
+
+    let mut writer = SerializedFileWriter::new(Vec::new(), Arc::new(schema), Arc::new(properties))
+        .wrap_err("Failed to start Parquet file")?;
+    let mut row_group = writer
+        .next_row_group()
+        .wrap_err("Failed to start Parquet row group")?;
+    for column in columns {
+        let mut column_writer = row_group
+            .next_column()
+            .wrap_err_with(|| format!("Failed to start Parquet column {}", column.name))?
+            .ok_or_else(|| eyre!("Parquet schema has no column {}", column.name))?;
+        let written = match &column.values {
+            ColumnValues::Int32(values) => column_writer.typed::<Int32Type>().write_batch(
+                &values.iter().flatten().copied().collect::<Vec<_>>(),
+                Some(&definition_levels(values)),
+                None,
+            ),
+            ColumnValues::Int64(values) | ColumnValues::TimestampMillis(values) => {
+                column_writer.typed::<Int64Type>().write_batch(
+                    &values.iter().flatten().copied().collect::<Vec<_>>(),
+                    Some(&definition_levels(values)),
+                    None,
+                )
+            }
+            ColumnValues::Utf8(values) => column_writer.typed::<ByteArrayType>().write_batch(
+                &values
+                    .iter()
+                    .flatten()
+                    .map(|v| ByteArray::from(v.as_str()))
+                    .collect::<Vec<_>>(),
+                Some(&definition_levels(values)),
+                None,
+            ),
+        };
+        written.wrap_err_with(|| format!("Failed to write Parquet column {}", column.name))?;
+        column_writer
+            .close()
+            .wrap_err_with(|| format!("Failed to finish Parquet column {}", column.name))?;
+    }
+    row_group
+        .close()
+        .wrap_err("Failed to finish Parquet row group")?;
+
+    writer
+        .into_inner()
+        .wrap_err("Failed to finish Parquet file")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use parquet::basic::ConvertedType;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::Field;
+
+    use super::*;
+
+    /// Read a file back with the crate's own reader, one Vec of fields per row
+    fn read_rows(file: &[u8]) -> (SerializedFileReader<std::fs::File>, Vec<Vec<Field>>) {
+        let mut tmp = tempfile::tempfile().unwrap();
+        tmp.write_all(file).unwrap();
+        let reader = SerializedFileReader::new(tmp).unwrap();
+        let rows = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                row.unwrap()
+                    .get_column_iter()
+                    .map(|(_, field)| field.clone())
+                    .collect()
+            })
+            .collect();
+        (reader, rows)
+    }
+
+    #[test]
+    fn test_write_table_round_trips() {
+        let columns = [
+            Column {
+                name: "game_id",
+                values: ColumnValues::Utf8(vec![Some("a".to_string()), None]),
+            },
+            Column {
+                name: "turns",
+                values: ColumnValues::Int32(vec![Some(42), Some(7)]),
+            },
+            Column {
+                name: "duration_ms",
+                values: ColumnValues::Int64(vec![None, Some(1500)]),
+            },
+            Column {
+                name: "finished_at",
+                values: ColumnValues::TimestampMillis(vec![Some(1_700_000_000_000), None]),
+            },
+        ];
+        let file = write_table(&columns).unwrap();
+        let (reader, rows) = read_rows(&file);
+
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    Field::Str("a".to_string()),
+                    Field::Int(42),
+                    Field::Null,
+                    Field::TimestampMillis(1_700_000_000_000),
+                ],
+                vec![Field::Null, Field::Int(7), Field::Long(1500), Field::Null],
+            ]
+        );
+
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 2);
+        let schema = metadata.schema_descr();
+        let names: Vec<&str> = schema.columns().iter().map(|c| c.name()).collect();
+        assert_eq!(names, ["game_id", "turns", "duration_ms", "finished_at"]);
+        assert_eq!(schema.column(0).converted_type(), ConvertedType::UTF8);
+        assert_eq!(
+            schema.column(3).converted_type(),
+            ConvertedType::TIMESTAMP_MILLIS
+        );
+    }
+
+    #[test]
+    fn test_write_table_without_rows() {
+        let columns = [Column {
+            name: "game_id",
+            values: ColumnValues::Utf8(vec![]),
+        }];
+        let file = write_table(&columns).unwrap();
+        let (reader, rows) = read_rows(&file);
+
+        assert!(rows.is_empty());
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 0);
+    }
+
+    #[test]
+    fn test_write_table_needs_equal_columns() {
+        let columns = [
+            Column {
+                name: "a",
+                values: ColumnValues::Int64(vec![Some(1)]),
+            },
+            Column {
+                name: "b",
+                values: ColumnValues::Int64(vec![]),
+            },
+        ];
+        assert!(write_table(&columns).is_err());
+    }
+}
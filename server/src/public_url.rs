@@ -0,0 +1,302 @@
+//! Public URLs and cookie settings for running behind a reverse proxy
+//!
+//! `BASE_URL` is the address users reach the app at, e.g. `https://example.com/arena`.
+//! Its path is a prefix every route is served under, and links and redirects are
+//! built with [`path`] so they keep the prefix. Absolute URLs (OAuth callbacks,
+//! the board viewer's engine URL, CLI instructions) come from [`PublicUrl`], which
+//! uses `BASE_URL` when set and otherwise the request's `Host` header, or the
+//! `X-Forwarded-Proto`/`X-Forwarded-Host` headers when
+//! `ARENA_TRUST_FORWARDED_HEADERS` is on.
+
+use std::sync::OnceLock;
+
+use axum::{
+    extract::FromRequestParts,
+    http::{HeaderMap, header::HOST, request::Parts},
+};
+use cja::server::cookies::{Cookie, SameSite};
+use color_eyre::eyre::{Context as _, eyre};
+use url::Url;
+
+use crate::state::AppState;
+
+static BASE_PATH: OnceLock<String> = OnceLock::new();
+
+/// The path prefix the app is served under, `""` when served from the root
+pub fn base_path() -> &'static str {
+    BASE_PATH.get().map(String::as_str).unwrap_or("")
+}
+
+/// Prefix an app-relative path (starting with `/`) with the base path, for links and redirects
+pub fn path(path: &str) -> String {
+    format!("{}{}", base_path(), path)
+}
+
+/// Where the app is publicly reachable, and how its cookies are scoped
+#[derive(Debug, Clone)]
+pub struct PublicUrlConfig {
+    /// Public URL of the app, including any path prefix
+    pub base_url: Option<Url>,
+    /// Build absolute URLs from `X-Forwarded-Proto`/`X-Forwarded-Host` when `base_url` isn't set.
+    /// Only enable this behind a proxy that overwrites those headers.
+    pub trust_forwarded_headers: bool,
+    pub cookies: CookieConfig,
+}
+
+/// Attributes for the cookies the app sets
+#[derive(Debug, Clone)]
+pub struct CookieConfig {
+    pub secure: bool,
+    pub same_site: SameSite,
+    /// Defaults to the host the request was made to
+    pub domain: Option<String>,
+}
+
+impl Default for CookieConfig {
+    fn default() -> Self {
+        Self {
+            secure: true,
+            same_site: SameSite::Lax,
+            domain: None,
+        }
+    }
+}
+
+impl PublicUrlConfig {
+    pub fn from_env() -> cja::Result<Self> {
+        let base_url = match std::env::var("BASE_URL") {
+            Ok(s) if !s.trim().is_empty() => Some(
+                Url::parse(s.trim()).wrap_err_with(|| format!("BASE_URL is not a URL: {}", s))?,
+            ),
+            _ => None,
+        };
+
+        let trust_forwarded_headers = std::env::var("ARENA_TRUST_FORWARDED_HEADERS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(false);
+
+        let defaults = CookieConfig::default();
+        let cookies = CookieConfig {
+            secure: std::env::var("ARENA_COOKIE_SECURE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.secure),
+            same_site: match std::env::var("ARENA_COOKIE_SAME_SITE") {
+                Ok(s) => parse_same_site(&s)?,
+                Err(_) => defaults.same_site,
+            },
+            domain: std::env::var("ARENA_COOKIE_DOMAIN")
+                .ok()
+                .filter(|s| !s.is_empty()),
+        };
+
+        Ok(Self {
+            base_url,
+            trust_forwarded_headers,
+            cookies,
+        })
+    }
+
+    /// Path prefix from `BASE_URL`, without a trailing slash (`""` for none)
+    pub fn base_path(&self) -> String {
+        self.base_url
+            .as_ref()
+            .map(|url| normalize_base_path(url.path()))
+            .unwrap_or_default()
+    }
+
+    /// Make the base path available to [`path`] for link generation
+    ///
+    /// Only the first call has any effect.
+    pub fn install(&self) {
+        let _ = BASE_PATH.set(self.base_path());
+    }
+
+    /// `BASE_URL` without a trailing slash, if configured
+    pub fn configured_base_url(&self) -> Option<String> {
+        self.base_url
+            .as_ref()
+            .map(|url| url.as_str().trim_end_matches('/').to_string())
+    }
+
+    /// Public URL of the app, including the base path, as seen by the request
+    fn base_url_for(&self, headers: &HeaderMap) -> String {
+        if let Some(base_url) = self.configured_base_url() {
+            return base_url;
+        }
+
+        let forwarded = |name: &str| {
+            self.trust_forwarded_headers
+                .then(|| first_header_value(headers, name))
+                .flatten()
+        };
+
+        let scheme = forwarded("x-forwarded-proto").unwrap_or_else(|| "http".to_string());
+        let host = forwarded("x-forwarded-host")
+            .or_else(|| first_header_value(headers, HOST.as_str()))
+            .unwrap_or_else(|| "localhost:3000".to_string());
+
+        format!("{}://{}{}", scheme, host, self.base_path())
+    }
+
+    /// Apply the configured attributes to a cookie, scoping it to the base path
+    pub fn apply_cookie_attributes(&self, cookie: &mut Cookie<'_>) {
+        cookie.set_path(match self.base_path() {
+            p if p.is_empty() => "/".to_string(),
+            p => p,
+        });
+        cookie.set_secure(self.cookies.secure);
+        cookie.set_same_site(self.cookies.same_site);
+        if let Some(domain) = &self.cookies.domain {
+            cookie.set_domain(domain.clone());
+        }
+    }
+}
+
+fn normalize_base_path(path: &str) -> String {
+    let trimmed = path.trim_matches('/');
+    if trimmed.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", trimmed)
+    }
+}
+
+fn parse_same_site(s: &str) -> cja::Result<SameSite> {
+    match s.to_ascii_lowercase().as_str() {
+        "lax" => Ok(SameSite::Lax),
+        "strict" => Ok(SameSite::Strict),
+        "none" => Ok(SameSite::None),
+        _ => Err(eyre!(
+            "ARENA_COOKIE_SAME_SITE must be lax, strict or none, got {}",
+            s
+        )),
+    }
+}
+
+/// First entry of a possibly comma separated header, as proxies append to `X-Forwarded-*`
+fn first_header_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Extractor for building absolute URLs to the app as the client sees it
+pub struct PublicUrl {
+    base_url: String,
+}
+
+impl PublicUrl {
+    /// Absolute URL of an app-relative path (starting with `/`)
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+}
+
+impl FromRequestParts<AppState> for PublicUrl {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self {
+            base_url: state.public_url.base_url_for(&parts.headers),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn config(base_url: Option<&str>, trust_forwarded_headers: bool) -> PublicUrlConfig {
+        PublicUrlConfig {
+            base_url: base_url.map(|s| Url::parse(s).unwrap()),
+            trust_forwarded_headers,
+            cookies: CookieConfig::default(),
+        }
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_base_path() {
+        assert_eq!(config(None, false).base_path(), "");
+        assert_eq!(config(Some("https://example.com"), false).base_path(), "");
+        assert_eq!(config(Some("https://example.com/"), false).base_path(), "");
+        assert_eq!(
+            config(Some("https://example.com/arena"), false).base_path(),
+            "/arena"
+        );
+        assert_eq!(
+            config(Some("https://example.com/tools/arena/"), false).base_path(),
+            "/tools/arena"
+        );
+    }
+
+    #[test]
+    fn test_base_url_prefers_configured_url() {
+        let config = config(Some("https://example.com/arena/"), true);
+        let headers = headers(&[
+            ("host", "internal:3000"),
+            ("x-forwarded-host", "other.example.com"),
+        ]);
+        assert_eq!(config.base_url_for(&headers), "https://example.com/arena");
+    }
+
+    #[test]
+    fn test_base_url_from_forwarded_headers() {
+        let headers = headers(&[
+            ("host", "internal:3000"),
+            ("x-forwarded-proto", "https, http"),
+            ("x-forwarded-host", "arena.example.com"),
+        ]);
+
+        assert_eq!(
+            config(None, true).base_url_for(&headers),
+            "https://arena.example.com"
+        );
+        // Forwarded headers are ignored unless trusted
+        assert_eq!(
+            config(None, false).base_url_for(&headers),
+            "http://internal:3000"
+        );
+    }
+
+    #[test]
+    fn test_cookie_attributes() {
+        let mut config = config(Some("https://example.com/arena"), false);
+        config.cookies = CookieConfig {
+            secure: false,
+            same_site: SameSite::Strict,
+            domain: Some("example.com".to_string()),
+        };
+
+        let mut cookie = Cookie::new("session", "abc");
+        config.apply_cookie_attributes(&mut cookie);
+
+        assert_eq!(cookie.path(), Some("/arena"));
+        assert_eq!(cookie.secure(), Some(false));
+        assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+        assert_eq!(cookie.domain(), Some("example.com"));
+    }
+
+    #[test]
+    fn test_parse_same_site() {
+        assert_eq!(parse_same_site("Lax").unwrap(), SameSite::Lax);
+        assert_eq!(parse_same_site("none").unwrap(), SameSite::None);
+        assert!(parse_same_site("sometimes").is_err());
+    }
+}
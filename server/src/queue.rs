@@ -0,0 +1,114 @@
+//! Queue position and start estimates for waiting games
+//!
+//! A waiting game's position is how many games were enqueued ahead of it and
+//! are still waiting. How soon it starts is estimated from how many games the
+//! workers picked up over the last few minutes; with none picked up there's
+//! nothing to go on, so there's no estimate.
+
+use serde::Serialize;
+
+use crate::models::game::{Game, GameStatus, count_games_queued_ahead, count_games_started_since};
+use crate::state::AppState;
+
+/// How far back throughput is measured
+const THROUGHPUT_WINDOW_SECONDS: i64 = 15 * 60;
+
+/// Waiting games enqueued longer ago than this are assumed stuck, not queued
+const STALE_AFTER_HOURS: i64 = 24;
+
+/// Where a waiting game is in the queue
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct QueueEstimate {
+    /// 1 when the game is next to start
+    pub position: i64,
+    /// Seconds until the game is expected to start, None without recent throughput
+    pub eta_seconds: Option<i64>,
+}
+
+/// Estimate when the game at `position` starts, given `started` games were
+/// picked up over the last `window_seconds`
+pub fn estimate(position: i64, started: i64, window_seconds: i64) -> QueueEstimate {
+    let eta_seconds = (started > 0).then(|| {
+        // Rounded up: a game that's next still waits for a worker to free up
+        (position * window_seconds + started - 1) / started
+    });
+
+    QueueEstimate {
+        position,
+        eta_seconds,
+    }
+}
+
+/// Where a game is in the queue, None unless it's waiting
+pub async fn queue_estimate(
+    app_state: &AppState,
+    game: &Game,
+) -> cja::Result<Option<QueueEstimate>> {
+    let Some(enqueued_at) = game.enqueued_at else {
+        return Ok(None);
+    };
+    if game.status != GameStatus::Waiting {
+        return Ok(None);
+    }
+
+    let now = app_state.clock.now();
+    let ahead = count_games_queued_ahead(
+        &app_state.db,
+        game.game_id,
+        enqueued_at,
+        now - chrono::Duration::hours(STALE_AFTER_HOURS),
+    )
+    .await?;
+    let started = count_games_started_since(
+        &app_state.db,
+        now - chrono::Duration::seconds(THROUGHPUT_WINDOW_SECONDS),
+    )
+    .await?;
+
+    Ok(Some(estimate(
+        ahead + 1,
+        started,
+        THROUGHPUT_WINDOW_SECONDS,
+    )))
+}
+
+/// Show an ETA as a rough duration, e.g. "about 3 minutes"
+pub fn format_eta(eta_seconds: i64) -> String {
+    match eta_seconds {
+        s if s < 60 => "under a minute".to_string(),
+        s if s < 120 => "about a minute".to_string(),
+        s if s < 60 * 60 => format!("about {} minutes", s / 60),
+        s if s < 2 * 60 * 60 => "about an hour".to_string(),
+        s => format!("about {} hours", s / (60 * 60)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate() {
+        // 30 games in 15 minutes is one every 30 seconds
+        assert_eq!(
+            estimate(1, 30, 900),
+            QueueEstimate {
+                position: 1,
+                eta_seconds: Some(30)
+            }
+        );
+        assert_eq!(estimate(10, 30, 900).eta_seconds, Some(300));
+        // Rounded up
+        assert_eq!(estimate(1, 7, 900).eta_seconds, Some(129));
+        assert_eq!(estimate(3, 0, 900).eta_seconds, None);
+    }
+
+    #[test]
+    fn test_format_eta() {
+        assert_eq!(format_eta(20), "under a minute");
+        assert_eq!(format_eta(90), "about a minute");
+        assert_eq!(format_eta(5 * 60 + 10), "about 5 minutes");
+        assert_eq!(format_eta(65 * 60), "about an hour");
+        assert_eq!(format_eta(3 * 60 * 60), "about 3 hours");
+    }
+}
@@ -0,0 +1,238 @@
+//! Stored ratings and recalculating them from the full game history
+//!
+//! Ratings replay every finished game in order, which gets slow as history
+//! grows. A recalculation replays everything once into a shadow table and
+//! swaps it in, remembering where it stopped; pages then start from the
+//! stored ratings and replay only the games since. Stored ratings from an
+//! older [`RATING_ALGORITHM_VERSION`] are ignored, so after changing the
+//! algorithm pages replay everything until a recalculation has run.
+
+use std::collections::HashMap;
+
+use cja::jobs::Job as _;
+use color_eyre::eyre::{Context as _, eyre};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::jobs::RatingRecalculationJob;
+use crate::models::game_battlesnake::{self, FinishedPlacement};
+use crate::models::league;
+use crate::models::rating::{
+    self, RatingRecalculation, create_recalculation, fail_recalculation,
+    get_latest_completed_recalculation, get_placements_created_between, get_rating_cutoff,
+    get_stored_ratings, record_recalculation_progress, start_recalculation, swap_in_shadow_ratings,
+    write_shadow_ratings,
+};
+use crate::state::AppState;
+use crate::stats::{RATING_ALGORITHM_VERSION, SnakeRating, compute_ratings, rate_games};
+
+/// Games replayed between progress updates
+const PROGRESS_EVERY_GAMES: usize = 1000;
+
+/// Every snake's rating, from the stored ratings plus the games since
+pub async fn current_ratings(pool: &PgPool) -> cja::Result<HashMap<Uuid, SnakeRating>> {
+    let latest = get_latest_completed_recalculation(pool).await?;
+    if let Some(recalculation) = latest.filter(|r| r.algorithm_version == RATING_ALGORITHM_VERSION)
+    {
+        let mut ratings = get_stored_ratings(pool).await?;
+
+        // A swap between the two reads would pair ratings with the wrong cutoff
+        let still_latest = get_latest_completed_recalculation(pool)
+            .await?
+            .is_some_and(|r| r.recalculation_id == recalculation.recalculation_id);
+        if still_latest {
+            let placements =
+                get_placements_created_between(pool, recalculation.rated_before, None).await?;
+            rate_games(&mut ratings, &placements);
+            return Ok(ratings);
+        }
+    }
+
+    let placements = game_battlesnake::get_finished_game_placements(pool).await?;
+    Ok(compute_ratings(&placements))
+}
+
+/// Every snake's rating from a league's games alone
+///
+/// Leagues are small enough to replay from scratch, so they have no stored ratings.
+pub async fn league_ratings(
+    pool: &PgPool,
+    league_id: Uuid,
+) -> cja::Result<HashMap<Uuid, SnakeRating>> {
+    let placements = league::get_league_placements(pool, league_id).await?;
+    Ok(compute_ratings(&placements))
+}
+
+/// Every snake's rating from ranked duels alone
+///
+/// Duels are rated apart so 1v1 results aren't mixed with placements in games
+/// with more snakes. They're replayed from scratch, like leagues.
+pub async fn duel_ratings(pool: &PgPool) -> cja::Result<HashMap<Uuid, SnakeRating>> {
+    let placements = game_battlesnake::get_duel_placements(pool).await?;
+    Ok(compute_ratings(&placements))
+}
+
+/// Start a recalculation in the background, or None if one is already running
+pub async fn enqueue_recalculation(
+    app_state: &AppState,
+) -> cja::Result<Option<RatingRecalculation>> {
+    let Some(recalculation) = create_recalculation(
+        &app_state.db,
+        RATING_ALGORITHM_VERSION,
+        app_state.clock.now(),
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    RatingRecalculationJob {
+        recalculation_id: Some(recalculation.recalculation_id),
+    }
+    .enqueue(
+        app_state.clone(),
+        format!("rating recalculation {}", recalculation.recalculation_id),
+    )
+    .await
+    .wrap_err("Failed to enqueue rating recalculation")?;
+
+    Ok(Some(recalculation))
+}
+
+/// Replay every finished game into the shadow table and swap it in, marking
+/// the recalculation failed if anything goes wrong
+pub async fn run_recalculation(app_state: &AppState, recalculation_id: Uuid) -> cja::Result<()> {
+    let pool = &app_state.db;
+    let recalculation = rating::get_recalculation(pool, recalculation_id)
+        .await?
+        .ok_or_else(|| eyre!("Rating recalculation {} not found", recalculation_id))?;
+    // A retried job finds its recalculation already finished
+    if recalculation.status != "running" {
+        tracing::warn!(
+            recalculation_id = %recalculation_id,
+            "Rating recalculation is already {}, skipping",
+            recalculation.status
+        );
+        return Ok(());
+    }
+
+    let snakes = match replay_history(app_state, recalculation_id).await {
+        Ok(snakes) => snakes,
+        Err(e) => {
+            fail_recalculation(
+                pool,
+                recalculation_id,
+                &format!("{:#}", e),
+                app_state.clock.now(),
+            )
+            .await?;
+            return Err(e);
+        }
+    };
+    crate::domain_events::record(
+        app_state,
+        None,
+        crate::domain_events::DomainEvent::RatingUpdated {
+            recalculation_id,
+            algorithm_version: recalculation.algorithm_version,
+            snakes,
+        },
+    )
+    .await;
+    Ok(())
+}
+
+/// Returns how many snakes were rated
+async fn replay_history(app_state: &AppState, recalculation_id: Uuid) -> cja::Result<usize> {
+    let pool = &app_state.db;
+    let rated_before = get_rating_cutoff(pool, app_state.clock.now()).await?;
+    let placements = get_placements_created_between(pool, None, Some(rated_before)).await?;
+    let games: Vec<&[FinishedPlacement]> =
+        placements.chunk_by(|a, b| a.game_id == b.game_id).collect();
+    start_recalculation(
+        pool,
+        recalculation_id,
+        rated_before,
+        games.len() as i32,
+        app_state.clock.now(),
+    )
+    .await?;
+
+    let mut ratings = HashMap::new();
+    let mut games_rated = 0;
+    for chunk in games.chunks(PROGRESS_EVERY_GAMES) {
+        for game in chunk {
+            rate_games(&mut ratings, game);
+        }
+        games_rated += chunk.len();
+        record_recalculation_progress(
+            pool,
+            recalculation_id,
+            games_rated as i32,
+            app_state.clock.now(),
+        )
+        .await?;
+        tracing::info!(
+            recalculation_id = %recalculation_id,
+            "Rating recalculation replayed {}/{} games",
+            games_rated,
+            games.len()
+        );
+    }
+
+    write_shadow_ratings(pool, &ratings).await?;
+    swap_in_shadow_ratings(pool, recalculation_id, app_state.clock.now()).await?;
+    tracing::info!(
+        recalculation_id = %recalculation_id,
+        "Rating recalculation swapped in ratings for {} snakes",
+        ratings.len()
+    );
+
+    Ok(ratings.len())
+}
+
+/// `arena admin recalculate-ratings`: start a recalculation, and with `wait`
+/// print its progress until it's done
+pub async fn run_recalculate_command(app_state: &AppState, wait: bool) -> cja::Result<()> {
+    let Some(recalculation) = enqueue_recalculation(app_state).await? else {
+        return Err(eyre!("A rating recalculation is already running"));
+    };
+    println!(
+        "Started rating recalculation {} (algorithm version {})",
+        recalculation.recalculation_id, recalculation.algorithm_version
+    );
+    if !wait {
+        println!("It runs on the job worker; pass --wait to follow its progress");
+        return Ok(());
+    }
+
+    let mut last_reported = None;
+    loop {
+        let recalculation =
+            rating::get_recalculation(&app_state.db, recalculation.recalculation_id)
+                .await?
+                .ok_or_else(|| eyre!("Rating recalculation disappeared"))?;
+
+        match recalculation.status.as_str() {
+            "completed" => {
+                println!("Done: replayed {} games", recalculation.games_total);
+                return Ok(());
+            }
+            "failed" => {
+                return Err(eyre!(
+                    "Rating recalculation failed: {}",
+                    recalculation.error.unwrap_or_default()
+                ));
+            }
+            _ => {
+                let progress = (recalculation.games_rated, recalculation.games_total);
+                if recalculation.rated_before.is_some() && last_reported != Some(progress) {
+                    println!("Replayed {}/{} games", progress.0, progress.1);
+                    last_reported = Some(progress);
+                }
+            }
+        }
+
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    }
+}
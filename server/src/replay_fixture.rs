@@ -0,0 +1,525 @@
+//! Replay fixtures: recorded games that engine changes must reproduce exactly
+//!
+//! A fixture holds a finished game's starting board, every move each snake made
+//! and the outcome (placements, final turn, each snake's health and length).
+//! Replaying feeds the recorded moves back through the engine with
+//! [`ReplayMoves`] and diffs the result against the recorded outcome, so a rules
+//! change that alters how historic games play out shows up as a failure.
+//!
+//! `arena admin record-fixture <game-id> --out <file>` records a game from the
+//! database and `arena admin replay-fixtures <path>...` replays fixture files or
+//! directories of them. The fixtures in `server/fixtures/replays` are also
+//! replayed by `cargo test`.
+//!
+//! Fixtures are JSON, or game notation (see [`crate::notation`]) when the file
+//! ends in `.sgn`, which is easier to read and diff in review.
+
+use battlesnake_game_types::types::Move;
+use battlesnake_game_types::wire_representation::{BattleSnake, Board, Game, Position};
+use color_eyre::eyre::{Context as _, eyre};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::engine::frame::{FrameCoord, StoredFrame};
+use crate::engine::move_provider::{MoveProvider, MoveProviders, ReplayMoves};
+use crate::engine::{GameResult, create_game_from_board, engine_seed, simulate_game};
+use crate::models::game::{GameStatus, GameType, get_game_by_id, get_game_settings};
+use crate::models::game_battlesnake::get_battlesnakes_by_game_id;
+use crate::notation;
+use crate::snake_client::parse_direction;
+use crate::state::AppState;
+use crate::turn_archive::{load_snake_turns, load_turns};
+
+/// Bumped whenever the fixture format changes incompatibly
+pub const FIXTURE_VERSION: u32 = 1;
+
+/// A recorded game, ready to replay
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayFixture {
+    pub version: u32,
+    /// The game the fixture was recorded from, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_game_id: Option<Uuid>,
+    pub width: u32,
+    pub height: u32,
+    /// Game type as shown in the UI, e.g. "Standard"
+    pub game_type: String,
+    /// Whether food spawns as the game is played. Fixtures recorded before the
+    /// engine spawned food leave it out and replay without it.
+    #[serde(default)]
+    pub spawn_food: bool,
+    /// The game's seed, if it had one; otherwise food is seeded from `source_game_id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    pub initial: FixtureBoard,
+    /// Entry `i` holds each alive snake's move (by snake ID) made on turn `i`
+    pub moves: Vec<BTreeMap<String, String>>,
+    pub outcome: FixtureOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixtureBoard {
+    pub food: Vec<FixtureCoord>,
+    #[serde(default)]
+    pub hazards: Vec<FixtureCoord>,
+    pub snakes: Vec<FixtureSnake>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixtureSnake {
+    pub id: String,
+    pub name: String,
+    pub health: i32,
+    /// Head first
+    pub body: Vec<FixtureCoord>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixtureCoord {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// How a recorded game ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixtureOutcome {
+    pub final_turn: i32,
+    /// Snake IDs, winner first
+    pub placements: Vec<String>,
+    pub snakes: Vec<FixtureSnakeOutcome>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixtureSnakeOutcome {
+    pub id: String,
+    pub health: i32,
+    pub length: usize,
+}
+
+impl From<FixtureCoord> for Position {
+    fn from(coord: FixtureCoord) -> Self {
+        Position::new(coord.x, coord.y)
+    }
+}
+
+impl From<FrameCoord> for FixtureCoord {
+    fn from(coord: FrameCoord) -> Self {
+        FixtureCoord {
+            x: coord.x,
+            y: coord.y,
+        }
+    }
+}
+
+fn fixture_coords(coords: &[FrameCoord]) -> Vec<FixtureCoord> {
+    coords.iter().map(|&c| c.into()).collect()
+}
+
+impl FixtureOutcome {
+    fn from_result(result: &GameResult) -> Self {
+        FixtureOutcome {
+            final_turn: result.final_turn,
+            placements: result.placements.clone(),
+            snakes: result
+                .final_board
+                .snakes
+                .iter()
+                .map(|s| FixtureSnakeOutcome {
+                    id: s.id.clone(),
+                    health: s.health.max(0),
+                    length: s.body.len(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Human-readable differences from `expected`, empty if they match
+    fn differences(&self, expected: &FixtureOutcome) -> Vec<String> {
+        let mut differences = Vec::new();
+
+        if self.final_turn != expected.final_turn {
+            differences.push(format!(
+                "final turn: expected {}, got {}",
+                expected.final_turn, self.final_turn
+            ));
+        }
+        if self.placements != expected.placements {
+            differences.push(format!(
+                "placements: expected {:?}, got {:?}",
+                expected.placements, self.placements
+            ));
+        }
+        for want in &expected.snakes {
+            match self.snakes.iter().find(|s| s.id == want.id) {
+                None => differences.push(format!("snake {}: missing from the result", want.id)),
+                Some(got) if got != want => differences.push(format!(
+                    "snake {}: expected health {} length {}, got health {} length {}",
+                    want.id, want.health, want.length, got.health, got.length
+                )),
+                Some(_) => {}
+            }
+        }
+
+        differences
+    }
+}
+
+impl ReplayFixture {
+    pub fn load(path: &Path) -> cja::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read fixture {}", path.display()))?;
+        let fixture: ReplayFixture = if is_notation(path) {
+            notation::from_notation(&contents)
+        } else {
+            serde_json::from_str(&contents).map_err(Into::into)
+        }
+        .wrap_err_with(|| format!("Failed to parse fixture {}", path.display()))?;
+
+        if fixture.version != FIXTURE_VERSION {
+            return Err(eyre!(
+                "Fixture {} is version {}, expected {}",
+                path.display(),
+                fixture.version,
+                FIXTURE_VERSION
+            ));
+        }
+
+        Ok(fixture)
+    }
+
+    /// Write the fixture as JSON, or as notation if `path` ends in `.sgn`
+    pub fn save(&self, path: &Path) -> cja::Result<()> {
+        let contents = if is_notation(path) {
+            notation::to_notation(self)?
+        } else {
+            serde_json::to_string_pretty(self).wrap_err("Failed to serialize fixture")? + "\n"
+        };
+        std::fs::write(path, contents)
+            .wrap_err_with(|| format!("Failed to write fixture {}", path.display()))
+    }
+
+    /// The game as it stood before the first move, and the seed it's played with
+    pub fn start(&self) -> cja::Result<(Game, u64)> {
+        let game_type: GameType = self.game_type.parse()?;
+        let board = Board {
+            height: self.height,
+            width: self.width,
+            food: self.initial.food.iter().map(|&c| c.into()).collect(),
+            hazards: self.initial.hazards.iter().map(|&c| c.into()).collect(),
+            snakes: self
+                .initial
+                .snakes
+                .iter()
+                .map(|s| -> cja::Result<BattleSnake> {
+                    let head = s
+                        .body
+                        .first()
+                        .copied()
+                        .ok_or_else(|| eyre!("Snake {} has an empty body", s.id))?;
+                    Ok(BattleSnake {
+                        id: s.id.clone(),
+                        name: s.name.clone(),
+                        head: head.into(),
+                        body: s.body.iter().map(|&c| c.into()).collect(),
+                        health: s.health,
+                        shout: None,
+                        actual_length: None,
+                    })
+                })
+                .collect::<cja::Result<_>>()?,
+        };
+        let game_id = self.source_game_id.unwrap_or_else(Uuid::nil);
+        let mut game = create_game_from_board(game_id, game_type, board);
+        if !self.spawn_food
+            && let Some(settings) = game.game.ruleset.settings.as_mut()
+        {
+            settings.food_spawn_chance = 0;
+            settings.minimum_food = 0;
+        }
+
+        Ok((game, engine_seed(&game_id.to_string(), self.seed)))
+    }
+
+    /// Run the recorded moves through the engine and return the outcome
+    pub async fn play(&self) -> cja::Result<FixtureOutcome> {
+        let (game, seed) = self.start()?;
+
+        let mut providers: MoveProviders = HashMap::new();
+        for snake in &self.initial.snakes {
+            let moves = self
+                .moves
+                .iter()
+                .enumerate()
+                .map(|(turn, moves)| {
+                    moves
+                        .get(&snake.id)
+                        .map(|m| {
+                            parse_direction(m).ok_or_else(|| {
+                                eyre!("Invalid move {:?} for {} on turn {}", m, snake.id, turn)
+                            })
+                        })
+                        .transpose()
+                })
+                .collect::<cja::Result<Vec<Option<Move>>>>()?;
+            let provider: Box<dyn MoveProvider> = Box::new(ReplayMoves::new(moves));
+            providers.insert(snake.id.clone(), provider);
+        }
+
+        let result = simulate_game(game, &providers, seed).await;
+        Ok(FixtureOutcome::from_result(&result))
+    }
+
+    /// Replay the fixture, returning how the outcome differs from the recorded one
+    pub async fn replay(&self) -> cja::Result<Vec<String>> {
+        Ok(self.play().await?.differences(&self.outcome))
+    }
+}
+
+/// Record a finished game from the database as a fixture
+///
+/// The game is replayed before it's returned, so a game the current engine
+/// doesn't reproduce (one that ran under different rules, say) is rejected
+/// rather than saved as a fixture that can never pass.
+pub async fn record(app_state: &AppState, game_id: Uuid) -> cja::Result<ReplayFixture> {
+    let pool = &app_state.db;
+
+    let game = get_game_by_id(pool, game_id)
+        .await?
+        .ok_or_else(|| eyre!("Game {} not found", game_id))?;
+    if game.status != GameStatus::Finished {
+        return Err(eyre!("Game {} hasn't finished", game_id));
+    }
+
+    let turns = load_turns(app_state, game_id).await?;
+    let frame = |turn_number: i32| -> cja::Result<StoredFrame> {
+        let data = turns
+            .iter()
+            .find(|t| t.turn_number == turn_number)
+            .and_then(|t| t.frame_data.clone())
+            .ok_or_else(|| eyre!("Game {} has no frame for turn {}", game_id, turn_number))?;
+        serde_json::from_value(data)
+            .wrap_err_with(|| format!("Failed to parse frame {} of game {}", turn_number, game_id))
+    };
+    let final_turn = turns
+        .iter()
+        .map(|t| t.turn_number)
+        .max()
+        .ok_or_else(|| eyre!("Game {} has no turns", game_id))?;
+    let (initial, last) = (frame(0)?, frame(final_turn)?);
+
+    // Moves stored with turn N were made on turn N - 1
+    let mut moves: Vec<BTreeMap<String, String>> =
+        vec![BTreeMap::new(); usize::try_from(final_turn).unwrap_or_default()];
+    for snake_turn in load_snake_turns(app_state, game_id).await? {
+        let made_on = usize::try_from(snake_turn.turn_number - 1)
+            .ok()
+            .and_then(|i| moves.get_mut(i));
+        if let Some(turn_moves) = made_on {
+            let direction = parse_direction(&snake_turn.direction).ok_or_else(|| {
+                eyre!(
+                    "Invalid stored move {:?} on turn {}",
+                    snake_turn.direction,
+                    snake_turn.turn_number
+                )
+            })?;
+            turn_moves.insert(
+                snake_turn.game_battlesnake_id.to_string(),
+                direction.to_string(),
+            );
+        }
+    }
+
+    let placements = get_battlesnakes_by_game_id(pool, game_id)
+        .await?
+        .into_iter()
+        .filter(|gb| gb.placement.is_some())
+        .map(|gb| gb.game_battlesnake_id.to_string())
+        .collect();
+
+    let (width, height) = game.board_size.dimensions();
+    let fixture = ReplayFixture {
+        version: FIXTURE_VERSION,
+        source_game_id: Some(game_id),
+        width,
+        height,
+        game_type: game.game_type.as_str().to_string(),
+        spawn_food: true,
+        seed: get_game_settings(pool, game_id).await?.seed,
+        initial: FixtureBoard {
+            food: fixture_coords(&initial.food),
+            hazards: fixture_coords(&initial.hazards),
+            snakes: initial
+                .snakes
+                .into_iter()
+                .map(|s| FixtureSnake {
+                    id: s.id,
+                    name: s.name,
+                    health: s.health,
+                    body: fixture_coords(&s.body),
+                })
+                .collect(),
+        },
+        moves,
+        outcome: FixtureOutcome {
+            final_turn,
+            placements,
+            snakes: last
+                .snakes
+                .into_iter()
+                .map(|s| FixtureSnakeOutcome {
+                    id: s.id,
+                    health: s.health.max(0),
+                    length: s.body.len(),
+                })
+                .collect(),
+        },
+    };
+
+    let differences = fixture.replay().await?;
+    if !differences.is_empty() {
+        return Err(eyre!(
+            "Game {} doesn't replay to its recorded result: {}",
+            game_id,
+            differences.join("; ")
+        ));
+    }
+
+    Ok(fixture)
+}
+
+/// Record a game and write it to `out`
+pub async fn run_record(app_state: &AppState, game_id: Uuid, out: &Path) -> cja::Result<()> {
+    let fixture = record(app_state, game_id).await?;
+    fixture.save(out)?;
+    println!(
+        "Recorded game {} ({} turns) to {}",
+        game_id,
+        fixture.outcome.final_turn,
+        out.display()
+    );
+    Ok(())
+}
+
+fn is_notation(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == notation::NOTATION_EXTENSION)
+}
+
+/// Every fixture file under the given paths, directories expanded to their
+/// `.json` and `.sgn` files
+pub fn fixture_paths(paths: &[PathBuf]) -> cja::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut entries: Vec<PathBuf> = std::fs::read_dir(path)
+                .wrap_err_with(|| format!("Failed to read {}", path.display()))?
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<Result<_, _>>()?;
+            entries.retain(|p| p.extension().is_some_and(|ext| ext == "json") || is_notation(p));
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+/// Replay fixtures, printing each difference, and fail if any don't match
+pub async fn run_replay(paths: &[PathBuf]) -> cja::Result<()> {
+    let files = fixture_paths(paths)?;
+    let mut failed = 0;
+
+    for file in &files {
+        let differences = ReplayFixture::load(file)?.replay().await?;
+        if differences.is_empty() {
+            println!("{}: ok", file.display());
+            continue;
+        }
+
+        failed += 1;
+        println!("{}: {} difference(s)", file.display(), differences.len());
+        for difference in &differences {
+            println!("  {difference}");
+        }
+    }
+
+    if failed > 0 {
+        return Err(eyre!(
+            "{} of {} fixture(s) no longer replay to their recorded outcome",
+            failed,
+            files.len()
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/replays")
+    }
+
+    #[tokio::test]
+    async fn test_recorded_fixtures_replay_identically() {
+        let files = fixture_paths(&[fixtures_dir()]).unwrap();
+        assert!(!files.is_empty(), "no fixtures in {:?}", fixtures_dir());
+
+        for file in files {
+            let fixture = ReplayFixture::load(&file).unwrap();
+            assert_eq!(
+                fixture.replay().await.unwrap(),
+                Vec::<String>::new(),
+                "{} diverged",
+                file.display()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_changed_moves_are_reported() {
+        let mut fixture = ReplayFixture::load(&fixtures_dir().join("wall-and-food.json")).unwrap();
+
+        // Beta skips the food, so it ends up shorter and hungrier
+        fixture.moves[2].insert("beta".to_string(), "left".to_string());
+
+        let differences = fixture.replay().await.unwrap();
+        assert_eq!(
+            differences,
+            vec!["snake beta: expected health 97 length 4, got health 94 length 3".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_fixture_round_trips_through_json() {
+        let fixture = ReplayFixture::load(&fixtures_dir().join("wall-and-food.json")).unwrap();
+        let json = serde_json::to_string(&fixture).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ReplayFixture>(&json).unwrap(),
+            fixture
+        );
+    }
+
+    #[test]
+    fn test_stored_frame_coords_convert() {
+        let frame: StoredFrame = serde_json::from_value(serde_json::json!({
+            "Snakes": [{"ID": "a", "Name": "A", "Body": [{"X": 1, "Y": 2}], "Health": 100}],
+            "Food": [{"X": 3, "Y": 4}],
+            "Hazards": []
+        }))
+        .unwrap();
+        assert_eq!(
+            fixture_coords(&frame.snakes[0].body),
+            vec![FixtureCoord { x: 1, y: 2 }]
+        );
+        assert_eq!(
+            fixture_coords(&frame.food),
+            vec![FixtureCoord { x: 3, y: 4 }]
+        );
+    }
+}
@@ -0,0 +1,639 @@
+//! Animated GIF replays of finished games
+//!
+//! When a game ends, [`ReplayGifHook`] queues a `RenderGameJob`, which draws
+//! each stored frame as a simple 2D board and encodes them as an animated
+//! GIF, served at `/games/{id}/replay.gif` for sharing where the board viewer
+//! can't go, like Discord. Renders are uploaded to storage when it's
+//! configured, and kept in the database otherwise.
+//!
+//! There's no image crate in the tree, so the GIF encoder (with its LZW
+//! compression) is here too. Every frame shares one 256 color palette, so
+//! frames are written as palette indices with no quantizing.
+
+use std::collections::HashMap;
+
+use color_eyre::eyre::{Context as _, eyre};
+use uuid::Uuid;
+
+use crate::engine::hooks::{EngineHook, GameEnded};
+use crate::engine_models::{EngineGameFrame, Point};
+use crate::jobs::{LOW_PRIORITY, RenderGameJob, enqueue_at};
+use crate::models::{game, game_render};
+use crate::state::AppState;
+use crate::turn_archive;
+
+/// Most frames in one GIF; longer games skip turns evenly to fit
+pub const MAX_GIF_FRAMES: usize = 600;
+
+/// How long each frame shows, in hundredths of a second
+const FRAME_DELAY_CS: u16 = 10;
+
+/// How long the final board shows before the GIF loops
+const FINAL_FRAME_DELAY_CS: u16 = 300;
+
+/// Roughly how wide the board is drawn, whatever its size
+const TARGET_BOARD_PX: usize = 320;
+
+/// Space between squares, in pixels
+const GAP_PX: usize = 1;
+
+// Palette indices. Snakes take two entries each from SNAKE_COLORS_START: body, then head.
+const GRID: u8 = 0;
+const SQUARE: u8 = 1;
+const FOOD: u8 = 2;
+const HAZARD: u8 = 3;
+const SNAKE_COLORS_START: usize = 4;
+
+/// The most snakes a palette has room for
+const MAX_PALETTE_SNAKES: usize = (256 - SNAKE_COLORS_START) / 2;
+
+/// Color for snakes without one of their own
+const FALLBACK_SNAKE_COLOR: [u8; 3] = [0x88, 0x88, 0x88];
+
+/// A finished render, ready to store
+pub struct RenderedGif {
+    pub gif: Vec<u8>,
+    /// Frames in the GIF, after skipping turns
+    pub frame_count: usize,
+}
+
+/// Draw a game's frames as an animated GIF of a `width` by `height` board
+pub fn render_gif(width: usize, height: usize, frames: &[EngineGameFrame]) -> RenderedGif {
+    let cell = (TARGET_BOARD_PX / width.max(height).max(1)).max(4);
+    let px_width = width * cell + GAP_PX;
+    let px_height = height * cell + GAP_PX;
+
+    // One palette entry pair per snake, in the order they first appear
+    let mut snake_ids: Vec<&str> = Vec::new();
+    let mut palette = vec![
+        [0x22, 0x28, 0x31],
+        [0xf0, 0xf0, 0xf0],
+        [0xff, 0x5c, 0x75],
+        [0xb4, 0xb4, 0xb4],
+    ];
+    for snake in frames.iter().flat_map(|frame| &frame.snakes) {
+        if snake_ids.len() == MAX_PALETTE_SNAKES || snake_ids.contains(&snake.id.as_str()) {
+            continue;
+        }
+        snake_ids.push(&snake.id);
+        let body = snake
+            .color
+            .as_deref()
+            .and_then(parse_hex_color)
+            .unwrap_or(FALLBACK_SNAKE_COLOR);
+        palette.push(body);
+        palette.push(body.map(|c| c / 2));
+    }
+
+    let sampled = sample_frames(frames, MAX_GIF_FRAMES);
+    let mut gif = GifEncoder::new(px_width as u16, px_height as u16, &palette);
+    for (i, frame) in sampled.iter().enumerate() {
+        let mut canvas = Canvas::new(px_width, px_height, cell, height);
+        canvas.draw_frame(frame, &snake_ids);
+        let delay = if i + 1 == sampled.len() {
+            FINAL_FRAME_DELAY_CS
+        } else {
+            FRAME_DELAY_CS
+        };
+        gif.add_frame(&canvas.pixels, delay);
+    }
+
+    RenderedGif {
+        gif: gif.finish(),
+        frame_count: sampled.len(),
+    }
+}
+
+/// Draw one frame as a still GIF of a `width` by `height` board
+pub fn render_still(width: usize, height: usize, frame: &EngineGameFrame) -> Vec<u8> {
+    render_gif(width, height, std::slice::from_ref(frame)).gif
+}
+
+/// At most `max` frames spread evenly across the game, always keeping the last
+fn sample_frames(frames: &[EngineGameFrame], max: usize) -> Vec<&EngineGameFrame> {
+    if frames.len() <= max {
+        return frames.iter().collect();
+    }
+    let step = frames.len().div_ceil(max);
+    let mut sampled: Vec<&EngineGameFrame> = frames.iter().step_by(step).collect();
+    let last = frames.last().expect("frames is longer than max");
+    if !std::ptr::eq(
+        *sampled.last().expect("step_by keeps the first frame"),
+        last,
+    ) {
+        if sampled.len() == max {
+            sampled.pop();
+        }
+        sampled.push(last);
+    }
+    sampled
+}
+
+/// "#rrggbb" (or "rrggbb") as RGB
+fn parse_hex_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// One frame's pixels, as palette indices
+struct Canvas {
+    width: usize,
+    cell: usize,
+    board_height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize, cell: usize, board_height: usize) -> Self {
+        Self {
+            width,
+            cell,
+            board_height,
+            pixels: vec![GRID; width * height],
+        }
+    }
+
+    fn fill(&mut self, x: usize, y: usize, w: usize, h: usize, color: u8) {
+        let height = self.pixels.len() / self.width;
+        for row in y..(y + h).min(height) {
+            let start = row * self.width + x.min(self.width);
+            let end = row * self.width + (x + w).min(self.width);
+            self.pixels[start..end].fill(color);
+        }
+    }
+
+    /// Top left pixel of a square; the board's y axis points up
+    fn origin(&self, point: &Point) -> Option<(usize, usize)> {
+        let (x, y) = (
+            usize::try_from(point.x).ok()?,
+            usize::try_from(point.y).ok()?,
+        );
+        if y >= self.board_height {
+            return None;
+        }
+        let row = self.board_height - 1 - y;
+        Some((x * self.cell + GAP_PX, row * self.cell + GAP_PX))
+    }
+
+    /// Fill a square, leaving `inset` pixels clear on each side
+    fn square(&mut self, point: &Point, inset: usize, color: u8) {
+        if let Some((x, y)) = self.origin(point) {
+            let size = (self.cell - GAP_PX).saturating_sub(inset * 2);
+            self.fill(x + inset, y + inset, size, size, color);
+        }
+    }
+
+    /// Fill the gap between two adjacent squares, so a snake's body reads as one piece
+    fn bridge(&mut self, a: &Point, b: &Point, inset: usize, color: u8) {
+        let (Some((ax, ay)), Some((bx, by))) = (self.origin(a), self.origin(b)) else {
+            return;
+        };
+        let size = (self.cell - GAP_PX).saturating_sub(inset * 2);
+        if ay == by && ax.abs_diff(bx) == self.cell {
+            self.fill(
+                ax.max(bx) - GAP_PX - inset,
+                ay + inset,
+                GAP_PX + inset * 2,
+                size,
+                color,
+            );
+        } else if ax == bx && ay.abs_diff(by) == self.cell {
+            self.fill(
+                ax + inset,
+                ay.max(by) - GAP_PX - inset,
+                size,
+                GAP_PX + inset * 2,
+                color,
+            );
+        }
+    }
+
+    fn draw_frame(&mut self, frame: &EngineGameFrame, snake_ids: &[&str]) {
+        let board_width = self.width / self.cell;
+        for y in 0..self.board_height {
+            for x in 0..board_width {
+                let point = Point {
+                    x: x as i32,
+                    y: y as i32,
+                };
+                self.square(&point, 0, SQUARE);
+            }
+        }
+        for hazard in &frame.hazards {
+            self.square(hazard, 0, HAZARD);
+        }
+        for food in &frame.food {
+            self.square(food, self.cell / 4, FOOD);
+        }
+
+        let inset = self.cell / 10;
+        for snake in frame.snakes.iter().filter(|snake| snake.death.is_none()) {
+            let Some(index) = snake_ids.iter().position(|id| *id == snake.id) else {
+                continue;
+            };
+            let body = (SNAKE_COLORS_START + index * 2) as u8;
+            // Tail first, so the head is drawn on top of stacked segments
+            for (i, segment) in snake.body.iter().enumerate().rev() {
+                let color = if i == 0 { body + 1 } else { body };
+                self.square(segment, inset, color);
+                if let Some(next) = snake.body.get(i + 1) {
+                    self.bridge(segment, next, inset, body);
+                }
+            }
+        }
+    }
+}
+
+/// Writes an animated GIF89a that loops forever, frame by frame
+struct GifEncoder {
+    out: Vec<u8>,
+    width: u16,
+    height: u16,
+}
+
+/// Bits per pixel of the global color table, which always has 256 entries
+const COLOR_BITS: u8 = 8;
+
+impl GifEncoder {
+    fn new(width: u16, height: u16, palette: &[[u8; 3]]) -> Self {
+        let mut out = Vec::new();
+        out.extend_from_slice(b"GIF89a");
+        out.extend_from_slice(&width.to_le_bytes());
+        out.extend_from_slice(&height.to_le_bytes());
+        // Global color table of 2^(7+1) entries, 8 bits per channel
+        out.push(0x80 | ((COLOR_BITS - 1) << 4) | (COLOR_BITS - 1));
+        out.push(GRID); // background color index
+        out.push(0); // no pixel aspect ratio
+        for i in 0..(1usize << COLOR_BITS) {
+            out.extend_from_slice(palette.get(i).unwrap_or(&[0, 0, 0]));
+        }
+        // NETSCAPE2.0 extension: loop forever
+        out.extend_from_slice(&[0x21, 0xff, 0x0b]);
+        out.extend_from_slice(b"NETSCAPE2.0");
+        out.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+        Self { out, width, height }
+    }
+
+    fn add_frame(&mut self, pixels: &[u8], delay_cs: u16) {
+        // Graphic control extension: how long to show the frame
+        self.out.extend_from_slice(&[0x21, 0xf9, 0x04, 0x00]);
+        self.out.extend_from_slice(&delay_cs.to_le_bytes());
+        self.out.extend_from_slice(&[0x00, 0x00]);
+
+        // Image descriptor covering the whole screen, using the global color table
+        self.out.push(0x2c);
+        self.out.extend_from_slice(&[0, 0, 0, 0]);
+        self.out.extend_from_slice(&self.width.to_le_bytes());
+        self.out.extend_from_slice(&self.height.to_le_bytes());
+        self.out.push(0x00);
+
+        self.out.push(COLOR_BITS);
+        for block in lzw_encode(pixels, COLOR_BITS).chunks(255) {
+            self.out.push(block.len() as u8);
+            self.out.extend_from_slice(block);
+        }
+        self.out.push(0x00);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.out.push(0x3b);
+        self.out
+    }
+}
+
+/// Packs variable width codes into bytes, least significant bit first
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= u32::from(code) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// GIF's variable width LZW, with codes up to 12 bits and a clear code when
+/// the table fills up
+fn lzw_encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    const MAX_CODE: u16 = 4096;
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut writer = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut size = min_code_size + 1;
+    writer.write(clear, size);
+
+    let Some((&first, rest)) = indices.split_first() else {
+        writer.write(end, size);
+        return writer.finish();
+    };
+    let mut prefix = u16::from(first);
+    for &index in rest {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        writer.write(prefix, size);
+        if next == MAX_CODE {
+            writer.write(clear, size);
+            table.clear();
+            next = end + 1;
+            size = min_code_size + 1;
+        } else {
+            if next >= 1 << size {
+                size += 1;
+            }
+            table.insert((prefix, index), next);
+            next += 1;
+        }
+        prefix = u16::from(index);
+    }
+    writer.write(prefix, size);
+    writer.write(end, size);
+    writer.finish()
+}
+
+/// Render a finished game and store the GIF
+pub async fn render_game(app_state: &AppState, game_id: Uuid) -> cja::Result<()> {
+    let game = game::get_game_by_id(&app_state.db, game_id)
+        .await?
+        .ok_or_else(|| eyre!("Game {} not found", game_id))?;
+    let frames = turn_archive::load_turns(app_state, game_id)
+        .await?
+        .into_iter()
+        .filter_map(|t| t.frame_data)
+        .map(serde_json::from_value::<EngineGameFrame>)
+        .collect::<Result<Vec<_>, _>>()
+        .wrap_err_with(|| format!("Failed to read frames of game {}", game_id))?;
+    if frames.is_empty() {
+        return Err(eyre!("Game {} has no frames to render", game_id));
+    }
+
+    let (width, height) = game.board_size.dimensions();
+    // Drawing is CPU bound, so keep it off the async workers
+    let rendered =
+        tokio::task::spawn_blocking(move || render_gif(width as usize, height as usize, &frames))
+            .await
+            .wrap_err("Render task failed")?;
+
+    match &app_state.storage {
+        Some(storage) => {
+            let path = gcs_path(game_id);
+            storage
+                .upload(&path, rendered.gif.clone(), "image/gif")
+                .await
+                .wrap_err("Failed to upload replay GIF")?;
+            game_render::save_game_render(
+                &app_state.db,
+                game_id,
+                Some(path.as_str()),
+                None,
+                rendered.gif.len(),
+                rendered.frame_count,
+                app_state.clock.now(),
+            )
+            .await?;
+        }
+        None => {
+            game_render::save_game_render(
+                &app_state.db,
+                game_id,
+                None,
+                Some(rendered.gif.as_slice()),
+                rendered.gif.len(),
+                rendered.frame_count,
+                app_state.clock.now(),
+            )
+            .await?;
+        }
+    }
+
+    tracing::info!(
+        game_id = %game_id,
+        bytes = rendered.gif.len(),
+        frames = rendered.frame_count,
+        "Rendered replay GIF"
+    );
+    Ok(())
+}
+
+/// Queue a render for a finished game, unless one is already queued or done
+///
+/// A render that's been queued for an hour without finishing is queued again.
+pub async fn request_render(app_state: &AppState, game_id: Uuid) -> cja::Result<bool> {
+    let mut tx = app_state
+        .db
+        .begin()
+        .await
+        .wrap_err("Failed to start transaction")?;
+    let requested =
+        game_render::request_game_render(&mut *tx, game_id, app_state.clock.now()).await?;
+    if requested {
+        enqueue_at(
+            &mut *tx,
+            RenderGameJob { game_id },
+            format!("Render replay GIF for game {}", game_id),
+            LOW_PRIORITY,
+            app_state.clock.now(),
+        )
+        .await?;
+    }
+    tx.commit().await.wrap_err("Failed to commit transaction")?;
+    Ok(requested)
+}
+
+/// A stored render's GIF, from the database or storage
+pub async fn load_gif(
+    app_state: &AppState,
+    render: game_render::GameRender,
+) -> cja::Result<Vec<u8>> {
+    if let Some(gif) = render.gif {
+        return Ok(gif);
+    }
+    let (Some(path), Some(storage)) = (&render.gcs_path, &app_state.storage) else {
+        return Err(eyre!(
+            "Replay GIF for game {} is in storage, which isn't configured",
+            render.game_id
+        ));
+    };
+    storage.download(path).await
+}
+
+fn gcs_path(game_id: Uuid) -> String {
+    format!("renders/{}.gif", game_id)
+}
+
+/// Queues a replay GIF for every game that ends
+pub struct ReplayGifHook;
+
+#[async_trait::async_trait]
+impl EngineHook for ReplayGifHook {
+    fn name(&self) -> &'static str {
+        "replay_gif"
+    }
+
+    async fn on_game_end(&self, app_state: &AppState, event: &GameEnded<'_>) -> cja::Result<()> {
+        request_render(app_state, event.game_id).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine_models::EngineSnake;
+
+    fn snake(id: &str, color: &str, body: &[(i32, i32)]) -> EngineSnake {
+        serde_json::from_value(serde_json::json!({
+            "ID": id,
+            "Name": id,
+            "Color": color,
+            "Health": 100,
+            "Body": body.iter().map(|(x, y)| serde_json::json!({"X": x, "Y": y})).collect::<Vec<_>>(),
+        }))
+        .unwrap()
+    }
+
+    fn frame(turn: i32, snakes: Vec<EngineSnake>) -> EngineGameFrame {
+        serde_json::from_value(serde_json::json!({
+            "Turn": turn,
+            "Food": [{"X": 5, "Y": 5}],
+            "Hazards": [{"X": 0, "Y": 6}],
+        }))
+        .map(|mut frame: EngineGameFrame| {
+            frame.snakes = snakes;
+            frame
+        })
+        .unwrap()
+    }
+
+    /// Decode GIF LZW data back into palette indices
+    fn lzw_decode(data: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let end = clear + 1;
+        let reset = || -> Vec<Vec<u8>> {
+            (0..clear)
+                .map(|i| vec![i as u8])
+                .chain([vec![], vec![]])
+                .collect()
+        };
+        let mut table = reset();
+        let mut size = min_code_size + 1;
+        let (mut buffer, mut bits, mut pos) = (0u32, 0u8, 0usize);
+        let mut previous: Option<Vec<u8>> = None;
+        let mut out = Vec::new();
+        loop {
+            while bits < size {
+                buffer |= u32::from(data[pos]) << bits;
+                pos += 1;
+                bits += 8;
+            }
+            let code = (buffer & ((1 << size) - 1)) as usize;
+            buffer >>= size;
+            bits -= size;
+
+            if code == clear {
+                table = reset();
+                size = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => {
+                    let mut entry = previous.clone();
+                    entry.push(previous[0]);
+                    entry
+                }
+                (None, None) => panic!("code {code} before any output"),
+            };
+            out.extend_from_slice(&entry);
+            if let Some(previous) = previous {
+                if table.len() < 4096 {
+                    let mut new = previous;
+                    new.push(entry[0]);
+                    table.push(new);
+                }
+                if table.len() == 1 << size && size < 12 {
+                    size += 1;
+                }
+            }
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn test_lzw_round_trips() {
+        let mut indices: Vec<u8> = (0..20_000u32)
+            .map(|i| ((i * 7 + i / 13) % 256) as u8)
+            .collect();
+        indices.extend(std::iter::repeat_n(3, 10_000));
+        let decoded = lzw_decode(&lzw_encode(&indices, 8), 8);
+        assert_eq!(decoded, indices);
+    }
+
+    #[test]
+    fn test_render_gif() {
+        let frames = vec![
+            frame(0, vec![snake("a", "#00ff00", &[(1, 1), (1, 1), (1, 1)])]),
+            frame(1, vec![snake("a", "#00ff00", &[(1, 2), (1, 1), (1, 1)])]),
+            frame(2, vec![snake("a", "#00ff00", &[(2, 2), (1, 2), (1, 1)])]),
+        ];
+        let rendered = render_gif(7, 7, &frames);
+        let gif = rendered.gif;
+
+        assert_eq!(rendered.frame_count, 3);
+        assert_eq!(&gif[..6], b"GIF89a");
+        // 7 squares of 45px plus the outer gap
+        assert_eq!(u16::from_le_bytes([gif[6], gif[7]]), 316);
+        assert_eq!(u16::from_le_bytes([gif[8], gif[9]]), 316);
+        // The snake's color follows the four fixed colors in the palette
+        let color = 13 + SNAKE_COLORS_START * 3;
+        assert_eq!(&gif[color..color + 3], &[0x00, 0xff, 0x00]);
+        assert_eq!(&gif[color + 3..color + 6], &[0x00, 0x7f, 0x00]);
+        assert_eq!(*gif.last().unwrap(), 0x3b);
+    }
+
+    #[test]
+    fn test_sample_frames_keeps_the_last() {
+        let frames: Vec<EngineGameFrame> = (0..1001).map(|turn| frame(turn, vec![])).collect();
+        let sampled = sample_frames(&frames, MAX_GIF_FRAMES);
+        assert!(sampled.len() <= MAX_GIF_FRAMES);
+        assert_eq!(sampled[0].turn, 0);
+        assert_eq!(sampled.last().unwrap().turn, 1000);
+        assert_eq!(sample_frames(&frames[..10], MAX_GIF_FRAMES).len(), 10);
+    }
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#ff5c75"), Some([0xff, 0x5c, 0x75]));
+        assert_eq!(parse_hex_color("00AA00"), Some([0x00, 0xaa, 0x00]));
+        assert_eq!(parse_hex_color("red"), None);
+        assert_eq!(parse_hex_color("#fff"), None);
+    }
+}
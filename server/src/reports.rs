@@ -0,0 +1,380 @@
+//! CSV reports of games and results
+//!
+//! Reports are streamed a page of games at a time, so exporting thousands of
+//! games never holds more than one page in memory. Tournaments aren't modelled
+//! yet, so the only report is a user's own games.
+//!
+//! Archived games' death causes come from their archives, so a page of them
+//! downloads each one that isn't already cached.
+
+use color_eyre::eyre::Context as _;
+use futures::{Stream, TryStreamExt};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::frame_compression::decompress_frame;
+use crate::state::AppState;
+use crate::turn_archive::load_turns;
+
+/// Games fetched per query while streaming a report
+const PAGE_SIZE: i64 = 200;
+
+pub const GAMES_CSV_HEADER: &str = "game_id,created_at,board_size,game_type,status,turns,duration_ms,snakes,placements,death_causes\n";
+
+#[derive(Debug)]
+struct GameReportRow {
+    game_id: Uuid,
+    board_size: String,
+    game_type: String,
+    status: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    /// Last turn, None until the game finishes
+    turns: Option<i32>,
+    /// Time from the first turn to the last, None until the game finishes
+    duration_ms: Option<i64>,
+    /// Whether the game's turns have been archived and pruned
+    archived: bool,
+}
+
+#[derive(Debug)]
+struct SnakeReportRow {
+    game_id: Uuid,
+    game_battlesnake_id: Uuid,
+    name: String,
+    placement: Option<i32>,
+}
+
+#[derive(Debug)]
+struct DeathReportRow {
+    game_id: Uuid,
+    snake_id: String,
+    cause: Option<String>,
+}
+
+#[derive(Debug)]
+struct CompressedFrameRow {
+    game_id: Uuid,
+    frame_compressed: Vec<u8>,
+}
+
+/// Each dead snake in a frame with its death cause, as `(snake ID, cause)`
+fn frame_death_causes(frame: &serde_json::Value) -> Vec<(String, String)> {
+    frame["Snakes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|s| {
+            let id = s["ID"].as_str()?;
+            let cause = s["Death"]["Cause"].as_str()?;
+            Some((id.to_string(), cause.to_string()))
+        })
+        .collect()
+}
+
+/// Quote a CSV field if it needs it
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One game's snakes, in placement order, as they appear in a report
+#[derive(Debug, Default)]
+struct GameSnakes {
+    names: Vec<String>,
+    placements: Vec<String>,
+    death_causes: Vec<String>,
+}
+
+/// One CSV line for a game
+///
+/// Snakes, placements and death causes are `;`-separated lists in the same
+/// order, so the Nth entry of each describes the same snake.
+fn game_line(game: &GameReportRow, snakes: &GameSnakes) -> String {
+    let fields = [
+        game.game_id.to_string(),
+        game.created_at.to_rfc3339(),
+        game.board_size.clone(),
+        game.game_type.clone(),
+        game.status.clone(),
+        game.turns.map(|t| t.to_string()).unwrap_or_default(),
+        game.duration_ms.map(|d| d.to_string()).unwrap_or_default(),
+        snakes.names.join("; "),
+        snakes.placements.join("; "),
+        snakes.death_causes.join("; "),
+    ];
+    let mut line = fields
+        .iter()
+        .map(|f| csv_field(f))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push('\n');
+    line
+}
+
+/// A page of a user's games, newest first, starting after `cursor`
+async fn user_games_page(
+    pool: &PgPool,
+    user_id: Uuid,
+    cursor: Option<(chrono::DateTime<chrono::Utc>, Uuid)>,
+) -> cja::Result<Vec<GameReportRow>> {
+    sqlx::query_as!(
+        GameReportRow,
+        r#"
+        SELECT
+            g.game_id,
+            g.board_size,
+            g.game_type,
+            g.status,
+            g.created_at,
+            g.final_turn AS turns,
+            g.turns_duration_ms AS duration_ms,
+            g.archived_at IS NOT NULL AS "archived!"
+        FROM games g
+        WHERE EXISTS (
+            SELECT 1
+            FROM game_battlesnakes gb
+            JOIN battlesnakes b ON gb.battlesnake_id = b.battlesnake_id
+            WHERE gb.game_id = g.game_id AND b.user_id = $1
+        )
+        AND ($2::TIMESTAMPTZ IS NULL OR (g.created_at, g.game_id) < ($2, $3))
+        ORDER BY g.created_at DESC, g.game_id DESC
+        LIMIT $4
+        "#,
+        user_id,
+        cursor.map(|(created_at, _)| created_at),
+        cursor.map(|(_, game_id)| game_id),
+        PAGE_SIZE
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch games for report")
+}
+
+/// The snakes in each of `games`, with their death causes from their frames
+///
+/// Games that drop dead snakes only have a snake's death in the frame of the
+/// turn it died, so every frame is searched rather than just the last.
+async fn games_snakes(
+    app_state: &AppState,
+    games: &[GameReportRow],
+) -> cja::Result<HashMap<Uuid, GameSnakes>> {
+    let pool = &app_state.db;
+    let game_ids: Vec<Uuid> = games.iter().map(|g| g.game_id).collect();
+    let snakes = sqlx::query_as!(
+        SnakeReportRow,
+        r#"
+        SELECT gb.game_id, gb.game_battlesnake_id, b.name, gb.placement
+        FROM game_battlesnakes gb
+        JOIN battlesnakes b ON gb.battlesnake_id = b.battlesnake_id
+        WHERE gb.game_id = ANY($1)
+        ORDER BY gb.game_id, gb.placement NULLS LAST, gb.created_at ASC
+        "#,
+        &game_ids
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch snakes for report")?;
+
+    let deaths = sqlx::query_as!(
+        DeathReportRow,
+        r#"
+        SELECT DISTINCT ON (t.game_id, s->>'ID')
+            t.game_id, s->>'ID' AS "snake_id!", s->'Death'->>'Cause' AS cause
+        FROM turns t
+        CROSS JOIN LATERAL jsonb_array_elements(t.frame_data->'Snakes') s
+        WHERE t.game_id = ANY($1)
+          AND jsonb_typeof(s->'Death') = 'object'
+        ORDER BY t.game_id, s->>'ID', t.turn_number
+        "#,
+        &game_ids
+    )
+    .fetch_all(pool)
+    .await
+    .wrap_err("Failed to fetch death causes for report")?;
+    let mut causes: HashMap<(Uuid, String), String> = deaths
+        .into_iter()
+        .filter_map(|d| d.cause.map(|cause| ((d.game_id, d.snake_id), cause)))
+        .collect();
+
+    // Compressed frames can't be searched in SQL, so they're streamed and
+    // decompressed here, oldest first
+    let mut compressed = sqlx::query_as!(
+        CompressedFrameRow,
+        r#"
+        SELECT game_id, frame_compressed AS "frame_compressed!"
+        FROM turns
+        WHERE game_id = ANY($1) AND frame_compressed IS NOT NULL
+        ORDER BY game_id, turn_number
+        "#,
+        &game_ids
+    )
+    .fetch(pool);
+    while let Some(row) = compressed
+        .try_next()
+        .await
+        .wrap_err("Failed to fetch compressed frames for report")?
+    {
+        let frame = match decompress_frame(&row.frame_compressed) {
+            Ok(frame) => frame,
+            Err(e) => {
+                tracing::warn!(game_id = %row.game_id, "Skipping frame in report: {}", e);
+                continue;
+            }
+        };
+        for (snake_id, cause) in frame_death_causes(&frame) {
+            causes.entry((row.game_id, snake_id)).or_insert(cause);
+        }
+    }
+
+    // Archived games have no turn rows left to search
+    for game in games.iter().filter(|g| g.archived) {
+        for frame in load_turns(app_state, game.game_id)
+            .await?
+            .iter()
+            .filter_map(|t| t.frame_data.as_ref())
+        {
+            for (snake_id, cause) in frame_death_causes(frame) {
+                causes.entry((game.game_id, snake_id)).or_insert(cause);
+            }
+        }
+    }
+
+    let mut by_game: HashMap<Uuid, GameSnakes> = HashMap::new();
+    for snake in snakes {
+        let entry = by_game.entry(snake.game_id).or_default();
+        entry.names.push(snake.name);
+        entry
+            .placements
+            .push(snake.placement.map(|p| p.to_string()).unwrap_or_default());
+        entry.death_causes.push(
+            causes
+                .get(&(snake.game_id, snake.game_battlesnake_id.to_string()))
+                .cloned()
+                .unwrap_or_default(),
+        );
+    }
+
+    Ok(by_game)
+}
+
+/// The CSV lines for one page of a user's games and the cursor for the next,
+/// None after the last page
+async fn user_games_chunk(
+    app_state: &AppState,
+    user_id: Uuid,
+    cursor: Option<(chrono::DateTime<chrono::Utc>, Uuid)>,
+) -> cja::Result<(String, Option<(chrono::DateTime<chrono::Utc>, Uuid)>)> {
+    let games = user_games_page(&app_state.db, user_id, cursor).await?;
+    let snakes = games_snakes(app_state, &games).await?;
+
+    let chunk: String = games
+        .iter()
+        .map(|g| game_line(g, snakes.get(&g.game_id).unwrap_or(&GameSnakes::default())))
+        .collect();
+    let next = if games.len() < PAGE_SIZE as usize {
+        None
+    } else {
+        games.last().map(|g| (g.created_at, g.game_id))
+    };
+
+    Ok((chunk, next))
+}
+
+enum ReportState {
+    Header,
+    Page(Option<(chrono::DateTime<chrono::Utc>, Uuid)>),
+    Done,
+}
+
+/// Every game with one of the user's snakes in it as CSV, newest first,
+/// one chunk per page of games
+pub fn user_games_csv(
+    app_state: AppState,
+    user_id: Uuid,
+) -> impl Stream<Item = Result<String, std::io::Error>> {
+    futures::stream::unfold(ReportState::Header, move |state| {
+        let app_state = app_state.clone();
+        async move {
+            match state {
+                ReportState::Header => {
+                    Some((Ok(GAMES_CSV_HEADER.to_string()), ReportState::Page(None)))
+                }
+                ReportState::Page(cursor) => {
+                    match user_games_chunk(&app_state, user_id, cursor).await {
+                        Ok((chunk, Some(next))) => Some((Ok(chunk), ReportState::Page(Some(next)))),
+                        Ok((chunk, None)) => Some((Ok(chunk), ReportState::Done)),
+                        Err(e) => {
+                            // Headers are already sent, so all we can do is cut the download short
+                            tracing::error!("Failed to stream games report: {:?}", e);
+                            Some((Err(std::io::Error::other(e.to_string())), ReportState::Done))
+                        }
+                    }
+                }
+                ReportState::Done => None,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_field_quotes_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn test_game_line() {
+        let game = GameReportRow {
+            game_id: Uuid::nil(),
+            board_size: "11x11".to_string(),
+            game_type: "Standard".to_string(),
+            status: "finished".to_string(),
+            created_at: chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            turns: Some(42),
+            duration_ms: Some(1500),
+            archived: false,
+        };
+        let snakes = GameSnakes {
+            names: vec!["Winner".to_string(), "Snake, Jr.".to_string()],
+            placements: vec!["1".to_string(), "2".to_string()],
+            death_causes: vec!["".to_string(), "wall-collision".to_string()],
+        };
+
+        assert_eq!(
+            game_line(&game, &snakes),
+            "00000000-0000-0000-0000-000000000000,2024-01-01T00:00:00+00:00,11x11,Standard,finished,42,1500,\"Winner; Snake, Jr.\",1; 2,; wall-collision\n"
+        );
+        assert_eq!(
+            GAMES_CSV_HEADER.split(',').count(),
+            game_line(&game, &GameSnakes::default()).split(',').count()
+        );
+    }
+
+    #[test]
+    fn test_frame_death_causes() {
+        let frame = serde_json::json!({
+            "Snakes": [
+                {"ID": "alive", "Death": null},
+                {"ID": "dead", "Death": {"Cause": "wall-collision", "Turn": 3}},
+                {"ID": "old-frame"}
+            ]
+        });
+        assert_eq!(
+            frame_death_causes(&frame),
+            vec![("dead".to_string(), "wall-collision".to_string())]
+        );
+        assert!(frame_death_causes(&serde_json::json!({})).is_empty());
+    }
+}
@@ -1,20 +1,29 @@
 use axum::{
     extract::State,
-    http::StatusCode,
+    http::{StatusCode, header},
     response::IntoResponse,
     routing::{delete, get, post, put},
 };
 use maud::html;
 use tower_http::cors::{Any, CorsLayer};
 
-use crate::{components::page_factory::PageFactory, errors::ServerResult, state::AppState};
+use crate::{
+    api_version, auth_lockout, components::page_factory::PageFactory, errors::ServerResult,
+    load_shedding, public_url::base_path, state::AppState,
+};
 
 // Include route modules
 pub mod api;
 pub mod auth;
 pub mod battlesnake;
+pub mod demo;
 pub mod game;
 pub mod github_auth;
+pub mod leaderboard;
+pub mod leagues;
+pub mod notifications;
+pub mod settings;
+pub mod tv;
 
 pub fn routes(app_state: AppState) -> axum::Router {
     // CORS layer for API routes - allows board.battlesnake.com to access our API
@@ -25,28 +34,215 @@ pub fn routes(app_state: AppState) -> axum::Router {
 
     // API routes with CORS enabled (for board viewer and CLI/programmatic access)
     let api_routes = axum::Router::new()
+        .route("/capabilities", get(api::capabilities::capabilities))
+        .route(
+            "/announcements",
+            get(api::announcements::active_announcements),
+        )
         .route("/games/{id}", get(game::get_game_info))
         .route("/games/{id}/events", get(game::game_events_websocket))
+        .route("/games/{id}/events/sse", get(game::game_events_sse))
+        .route("/games/{id}/spectators", get(game::game_spectators))
+        // Board viewer URLs carrying a stream ticket, for private games
+        .route("/viewer/{ticket}/games/{id}", get(game::viewer_game_info))
+        .route(
+            "/viewer/{ticket}/games/{id}/events",
+            get(game::viewer_game_events_websocket),
+        )
         .route("/tokens", post(api::tokens::create_token))
         .route("/tokens", get(api::tokens::list_tokens))
         .route("/tokens/{id}", delete(api::tokens::revoke_token))
         // Snake management endpoints
         .route("/snakes", get(api::snakes::list_snakes))
         .route("/snakes", post(api::snakes::create_snake))
+        // Declarative roster sync
+        .route("/snakes/export", get(api::snakes::export_snakes))
+        .route("/snakes/import", post(api::snakes::import_snakes))
+        // Public snake directory, filtered by tags
+        .route("/snakes/public", get(api::tags::snake_directory))
+        .route("/tags", get(api::tags::public_tags))
         .route("/snakes/{id}", get(api::snakes::get_snake))
         .route("/snakes/{id}", put(api::snakes::update_snake))
         .route("/snakes/{id}", delete(api::snakes::delete_snake))
+        // Link a snake to its GitHub repository for check statuses
+        .route("/snakes/{id}/github", get(api::snakes::get_github_repo))
+        .route("/snakes/{id}/github", put(api::snakes::set_github_repo))
+        .route(
+            "/snakes/{id}/github",
+            delete(api::snakes::delete_github_repo),
+        )
+        // Opting a snake in to ranked games
+        .route("/snakes/{id}/ranked", get(api::snakes::get_ranked_opt_in))
+        .route("/snakes/{id}/ranked", put(api::snakes::set_ranked_opt_in))
+        // A snake's totals, from the domain event log's snake_stats projection
+        .route("/snakes/{id}/stats", get(api::snakes::get_snake_stats))
+        // Tagging snakes by language or approach, like rust or tree-search
+        .route("/snakes/{id}/tags", get(api::tags::list_tags))
+        .route("/snakes/{id}/tags", put(api::tags::set_tags))
+        .route("/snakes/{id}/tags", post(api::tags::add_tag))
+        .route("/snakes/{id}/tags/{tag}", delete(api::tags::remove_tag))
+        // Versions of a snake, so results are attributed to the code that played them
+        .route("/snakes/{id}/versions", get(api::snakes::list_versions))
+        .route("/snakes/{id}/versions", post(api::snakes::create_version))
+        .route(
+            "/snakes/{id}/versions/{version_id}/activate",
+            post(api::snakes::activate_version),
+        )
+        // Step-by-step checks of a snake's URL from the server's network
+        .route("/snakes/{id}/diagnose", post(api::snakes::diagnose_snake))
+        // URL templates, instantiated as one snake per deployed tag
+        .route(
+            "/snake-templates",
+            get(api::snake_templates::list_templates),
+        )
+        .route(
+            "/snake-templates",
+            post(api::snake_templates::create_template),
+        )
+        .route(
+            "/snake-templates/{id}",
+            get(api::snake_templates::show_template),
+        )
+        .route(
+            "/snake-templates/{id}",
+            delete(api::snake_templates::delete_template),
+        )
+        .route(
+            "/snake-templates/{id}/variants",
+            post(api::snake_templates::instantiate_template),
+        )
+        // Uploaded WASM rulesets, run after each turn of the games that pick them
+        .route("/rulesets", get(api::rulesets::list_rulesets))
+        .route("/rulesets", post(api::rulesets::upload_ruleset))
+        // Win rate alerts on a snake
+        .route("/snakes/{id}/alerts", get(api::alerts::list_alerts))
+        .route("/snakes/{id}/alerts", post(api::alerts::create_alert))
+        .route("/alerts/{id}", delete(api::alerts::delete_alert))
+        // Regression checks for CI
+        .route("/checks", post(api::checks::create_check))
+        .route("/checks/{id}", get(api::checks::show_check))
+        // Federation routes (public, signed)
+        .route("/federation", get(api::federation::federation_info))
+        .route("/federation/export", get(api::federation::export))
+        // Leagues, with their own private leaderboards
+        .route("/leagues", post(api::leagues::create_league))
+        .route("/leagues", get(api::leagues::list_leagues))
+        .route("/leagues/{id}", get(api::leagues::show_league))
+        .route("/leagues/{id}/members", post(api::leagues::add_member))
+        .route("/leagues/{id}/awards", put(api::leagues::update_awards))
+        .route(
+            "/leagues/{id}/complete",
+            post(api::leagues::complete_league),
+        )
+        .route(
+            "/leagues/{id}/announcement",
+            get(api::leagues::announcement),
+        )
+        .route("/leaderboard", get(api::leagues::leaderboard))
+        // The caller's notification inbox
+        .route(
+            "/notifications",
+            get(api::notifications::list_notifications),
+        )
+        .route(
+            "/notifications/read",
+            post(api::notifications::mark_all_read),
+        )
+        .route(
+            "/notifications/{id}/read",
+            post(api::notifications::mark_read),
+        )
+        // How two snakes have done against each other
+        .route("/matchups", get(api::matchups::head_to_head))
+        // What the TV channel is showing
+        .route("/tv", get(api::tv::now_playing))
+        // Reports
+        .route("/me/games.csv", get(api::reports::my_games_csv))
+        // Preferences, shared with the settings page
+        .route("/me/preferences", get(api::preferences::get_preferences))
+        .route("/me/preferences", put(api::preferences::update_preferences))
         // Games API endpoints (list, create, details)
         .route("/games", post(api::games::create_game))
         .route("/games", get(api::games::list_games))
+        .route("/games/batch", post(api::games::create_game_batch))
+        .route("/games/predict", get(api::games::predict_game))
+        .route("/games/diff", get(api::games::diff_games))
         .route("/games/{id}/details", get(api::games::show_game))
+        .route("/games/{id}/debug", get(api::games::debug_game))
+        .route("/games/{id}/log", get(api::games::game_log))
+        .route("/games/{id}/timing", get(api::games::game_timing))
+        .route(
+            "/games/{id}/frames.ndjson",
+            get(api::games::game_frames_ndjson),
+        )
+        .route("/games/{id}/notation", get(api::games::game_notation))
+        .route("/games/{id}/export", get(api::games::game_export))
+        .route(
+            "/games/{id}/descriptions",
+            get(api::games::game_descriptions),
+        )
+        .route(
+            "/games/{id}/stream-ticket",
+            post(api::games::create_stream_ticket),
+        )
+        .route("/games/{id}/branches", post(api::games::create_branch))
+        .route("/games/{id}/branches", get(api::games::list_branches))
+        // Games archived to GCS from the Engine
+        .route(
+            "/archive/games/{engine_game_id}",
+            get(api::archive::archived_game),
+        )
+        // Operator endpoints for this instance, behind ARENA_ADMIN_TOKEN
+        .route("/admin/drain", post(api::admin::start_drain))
+        .route("/admin/drain", get(api::admin::drain_status))
+        .route("/admin/load", get(api::admin::load_status))
+        .route(
+            "/admin/archives/reencode",
+            post(api::admin::reencode_archives),
+        )
+        .route("/admin/archives/tarball", get(api::admin::archive_tarball))
+        .route("/admin/games/{id}/pause", post(api::admin::pause_game))
+        .route("/admin/games/{id}/step", post(api::admin::step_game))
+        .route("/admin/games/{id}/resume", post(api::admin::resume_game))
+        .route(
+            "/admin/announcements",
+            get(api::announcements::list_announcements),
+        )
+        .route(
+            "/admin/announcements",
+            post(api::announcements::create_announcement),
+        )
+        .route(
+            "/admin/announcements/{id}",
+            put(api::announcements::update_announcement),
+        )
+        .route(
+            "/admin/announcements/{id}",
+            delete(api::announcements::delete_announcement),
+        )
+        // Brute-force protection for Bearer token auth
+        .layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            auth_lockout::enforce_auth_lockout,
+        ))
         .layer(cors);
 
-    axum::Router::new()
+    let base_path = app_state.public_url.base_path();
+
+    let router = axum::Router::new()
         // Public pages
         .route("/", get(root_page))
+        // Bots-only demo games, for visitors who aren't logged in
+        .route("/demo", axum::routing::post(demo::start_demo))
+        .route("/demo/{id}", get(demo::view_demo))
         // Profile page - requires authentication
         .route("/me", get(profile_page))
+        .route("/settings", get(settings::settings_page))
+        .route("/settings", post(settings::update_settings))
+        // Notification inbox
+        .route("/notifications", get(notifications::inbox))
+        .route("/notifications/read", post(notifications::mark_all_read))
+        .route("/notifications/{id}", get(notifications::open_notification))
         // GitHub OAuth routes
         .route("/auth/github", get(github_auth::github_auth))
         .route(
@@ -70,6 +266,14 @@ pub fn routes(app_state: AppState) -> axum::Router {
             "/battlesnakes/{id}/update",
             axum::routing::post(battlesnake::update_battlesnake),
         )
+        .route(
+            "/battlesnakes/{id}/sandbox",
+            axum::routing::post(battlesnake::test_battlesnake),
+        )
+        .route(
+            "/battlesnakes/{id}/diagnose",
+            axum::routing::post(battlesnake::diagnose_battlesnake),
+        )
         .route(
             "/battlesnakes/{id}/delete",
             axum::routing::post(battlesnake::delete_battlesnake),
@@ -78,10 +282,28 @@ pub fn routes(app_state: AppState) -> axum::Router {
             "/battlesnakes/{id}/profile",
             get(battlesnake::view_battlesnake_profile),
         )
+        .route("/leaderboard", get(leaderboard::leaderboard))
+        .route(
+            "/leagues/{id}/announcement",
+            get(leagues::league_announcement),
+        )
+        // Arena TV, cycling through running public games
+        .route("/tv", get(tv::tv_page))
+        .route(
+            "/leaderboard/federated",
+            get(leaderboard::federated_leaderboard),
+        )
         // Game routes
         .route("/games", get(game::list_games))
         .route("/games/new", get(game::new_game))
         .route("/games/{id}", get(game::view_game))
+        .route("/games/{id}/replay.gif", get(game::replay_gif))
+        .route("/games/{id}/board.gif", get(game::board_gif))
+        .route("/games/{id}/branch", axum::routing::post(game::branch_game))
+        .route(
+            "/games/{id}/control",
+            axum::routing::post(game::control_game_form),
+        )
         .route("/games/flow/{id}", get(game::show_game_flow))
         .route(
             "/games/flow/{id}/reset",
@@ -101,7 +323,25 @@ pub fn routes(app_state: AppState) -> axum::Router {
         )
         .route("/games/flow/{id}/search", get(game::search_battlesnakes))
         // Game API routes for board viewer (with CORS)
-        .nest("/api", api_routes)
+        .nest(
+            "/api/v1",
+            api_routes
+                .clone()
+                .layer(axum::middleware::from_fn(|request, next| {
+                    api_version::negotiate_version(1, request, next)
+                })),
+        )
+        // Unversioned aliases of the current version, from before the prefix
+        .nest(
+            "/api",
+            api_routes
+                .layer(axum::middleware::from_fn(
+                    api_version::deprecate_unversioned,
+                ))
+                .layer(axum::middleware::from_fn(|request, next| {
+                    api_version::negotiate_version(api_version::CURRENT_VERSION, request, next)
+                })),
+        )
         // Static files
         .route(
             "/static/{*path}",
@@ -109,9 +349,23 @@ pub fn routes(app_state: AppState) -> axum::Router {
         )
         // Internal routes
         .route("/_/version", get(version_page))
+        .route("/_/metrics", get(metrics_page))
+        // 503 for non-essential routes while the database is under pressure
+        .layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            load_shedding::shed_load,
+        ))
         // Add trace layer for debugging
         .layer(tower_http::trace::TraceLayer::new_for_http())
-        .with_state(app_state)
+        .with_state(app_state);
+
+    // Behind a proxy that forwards a path prefix (BASE_URL=https://example.com/arena),
+    // serve every route under that prefix
+    if base_path.is_empty() {
+        router
+    } else {
+        axum::Router::new().nest(&base_path, router)
+    }
 }
 
 async fn root_page(
@@ -131,15 +385,21 @@ async fn root_page(
                             p { "Name: " (name) }
                         }
                         div class="user-actions" style="margin-top: 10px;" {
-                            a href="/me" class="btn btn-primary" { "Profile" }
-                            a href="/battlesnakes" class="btn btn-primary" { "Battlesnakes" }
-                            a href="/auth/logout" class="btn btn-secondary" { "Logout" }
+                            a href={(base_path())"/me"} class="btn btn-primary" { "Profile" }
+                            a href={(base_path())"/battlesnakes"} class="btn btn-primary" { "Battlesnakes" }
+                            a href={(base_path())"/leaderboard"} class="btn btn-primary" { "Leaderboard" }
+                            a href={(base_path())"/tv"} class="btn btn-primary" { "Arena TV" }
+                            a href={(base_path())"/auth/logout"} class="btn btn-secondary" { "Logout" }
                         }
                     }
                 } @else {
                     div class="login" {
                         p { "You are not logged in." }
-                        a href="/auth/github" { "Login with GitHub" }
+                        a href={(base_path())"/auth/github"} { "Login with GitHub" }
+                        form action={(base_path())"/demo"} method="post" style="margin-top: 10px;" {
+                            button type="submit" class="btn btn-primary" { "Watch a demo game" }
+                        }
+                        a href={(base_path())"/tv"} { "Watch Arena TV" }
                     }
                 }
                 div class="content" style="margin-top: 20px;" {
@@ -187,27 +447,55 @@ async fn profile_page(
                     div class="profile-actions" style="margin-top: 20px;" {
                         h3 { "Your Battlesnakes" }
                         p { "Manage your Battlesnake collection." }
-                        a href="/battlesnakes" class="btn btn-primary" { "Manage Battlesnakes" }
+                        a href={(base_path())"/battlesnakes"} class="btn btn-primary" { "Manage Battlesnakes" }
+
+                        h3 class="mt-4" { "Settings" }
+                        p { "Defaults for new games, notifications, replay speed and theme." }
+                        a href={(base_path())"/settings"} class="btn btn-primary" { "Edit Settings" }
 
                         h3 class="mt-4" { "Games" }
                         p { "Create and view games with your Battlesnakes." }
                         div {
-                            a href="/games/new" class="btn btn-primary" { "Create New Game" }
-                            a href="/games" class="btn btn-secondary ms-2" { "View All Games" }
+                            a href={(base_path())"/games/new"} class="btn btn-primary" { "Create New Game" }
+                            a href={(base_path())"/games"} class="btn btn-secondary ms-2" { "View All Games" }
+                            a href={(base_path())"/api/v1/me/games.csv"} class="btn btn-outline-secondary ms-2" { "Download Games (CSV)" }
                         }
                     }
                 }
 
                 div class="nav" style="margin-top: 20px;" {
-                    a href="/" { "Back to Home" }
+                    a href={(base_path())"/"} { "Back to Home" }
                     span { " | " }
-                    a href="/auth/logout" { "Logout" }
+                    a href={(base_path())"/auth/logout"} { "Logout" }
                 }
             }
         }),
     ))
 }
 
+/// Per-stage game loop histograms in Prometheus' text format
+async fn metrics_page(State(state): State<AppState>) -> impl IntoResponse {
+    let queued = match crate::runner_pool::queued_games(&state.db).await {
+        Ok(queued) => Some(queued),
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to count queued games for metrics");
+            None
+        }
+    };
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        format!(
+            "{}{}{}",
+            state.turn_metrics.render(),
+            state.game_channels.render_spectator_metrics().await,
+            state.runner_pool.render(queued)
+        ),
+    )
+}
+
 /// Version info page showing build metadata
 async fn version_page() -> impl IntoResponse {
     html! {
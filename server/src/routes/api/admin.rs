@@ -0,0 +1,234 @@
+use axum::{
+    Json,
+    body::Body,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    backup::{self, ARCHIVE_VERSION, ArchivePeriod},
+    game_control::{ControlAction, ControlError, GameControl, control_game},
+    jobs::{LOW_PRIORITY, ReencodeArchiveJob, enqueue_at},
+    models::game,
+    routes::auth::AdminApi,
+    state::AppState,
+};
+
+/// Most re-encode jobs one request enqueues, by default and at most
+const DEFAULT_REENCODE_LIMIT: i64 = 1000;
+const MAX_REENCODE_LIMIT: i64 = 10_000;
+
+/// POST /api/admin/drain - Stop this instance taking jobs, for a rolling deploy
+///
+/// Games already running play out. Poll GET /api/admin/drain until
+/// `safe_to_terminate` is true before stopping the process. Draining can't be
+/// undone short of restarting.
+pub async fn start_drain(State(state): State<AppState>, _admin: AdminApi) -> impl IntoResponse {
+    state.drain.start(state.clock.now());
+    (StatusCode::ACCEPTED, Json(state.drain.status()))
+}
+
+/// GET /api/admin/drain - Whether this instance is draining, and if it's done
+pub async fn drain_status(State(state): State<AppState>, _admin: AdminApi) -> impl IntoResponse {
+    Json(state.drain.status())
+}
+
+/// GET /api/admin/load - Whether this instance is shedding load, and why
+pub async fn load_status(State(state): State<AppState>, _admin: AdminApi) -> impl IntoResponse {
+    Json(state.load_shedder.status())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReencodeArchivesQuery {
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReencodeArchivesResponse {
+    /// The format archives are rewritten in
+    pub archive_version: i32,
+    /// Archived games in an older format, before this request
+    pub outdated: i64,
+    /// Re-encode jobs enqueued by this request
+    pub enqueued: usize,
+}
+
+/// POST /api/admin/archives/reencode - Rewrite archives in the current format
+///
+/// Enqueues a ReencodeArchiveJob for each archived game in an older format, up
+/// to `?limit=` of them (1000 by default), oldest archives first. Call it again
+/// once those have run to work through the rest. The jobs skip games that are
+/// already current, so calling it again early is harmless.
+pub async fn reencode_archives(
+    State(state): State<AppState>,
+    _admin: AdminApi,
+    Query(query): Query<ReencodeArchivesQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to enqueue archive re-encoding: {:?}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to enqueue archive re-encoding".to_string(),
+        )
+    };
+
+    if state.storage.is_none() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Game archives aren't configured on this server".to_string(),
+        ));
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_REENCODE_LIMIT)
+        .clamp(1, MAX_REENCODE_LIMIT);
+
+    let outdated = game::count_outdated_archives(&state.db, ARCHIVE_VERSION)
+        .await
+        .map_err(internal_error)?;
+    let engine_game_ids = game::get_outdated_archives(&state.db, ARCHIVE_VERSION, limit)
+        .await
+        .map_err(internal_error)?;
+
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|e| internal_error(e.into()))?;
+    for engine_game_id in &engine_game_ids {
+        enqueue_at(
+            &mut *tx,
+            ReencodeArchiveJob {
+                engine_game_id: engine_game_id.clone(),
+            },
+            format!("re-encode archive of game {}", engine_game_id),
+            LOW_PRIORITY,
+            state.clock.now(),
+        )
+        .await
+        .map_err(internal_error)?;
+    }
+    tx.commit().await.map_err(|e| internal_error(e.into()))?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(ReencodeArchivesResponse {
+            archive_version: ARCHIVE_VERSION,
+            outdated,
+            enqueued: engine_game_ids.len(),
+        }),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ArchiveTarballQuery {
+    /// A day as `YYYY-MM-DD` or a month as `YYYY-MM`
+    period: String,
+}
+
+/// GET /api/admin/archives/tarball - A tar of every game archived for a day or month
+///
+/// Each game is its zstd-compressed archive, at the same path as in storage,
+/// fetched as the tar is streamed out. The `X-Archive-Count` header says how
+/// many games it holds. A download that fails part way ends without the tar's
+/// end-of-archive blocks, so it can't be mistaken for a complete one.
+pub async fn archive_tarball(
+    State(state): State<AppState>,
+    _admin: AdminApi,
+    Query(query): Query<ArchiveTarballQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let storage = state.storage.clone().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Game archives aren't configured on this server".to_string(),
+    ))?;
+    let period = ArchivePeriod::parse(query.period.trim()).ok_or((
+        StatusCode::BAD_REQUEST,
+        "period must be a day (YYYY-MM-DD) or a month (YYYY-MM)".to_string(),
+    ))?;
+    let (start, end) = period.bounds().ok_or((
+        StatusCode::BAD_REQUEST,
+        "period is out of range".to_string(),
+    ))?;
+
+    let archives = game::get_archived_games_between(&state.db, start, end)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list archived games: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?;
+    tracing::info!(period = %period, games = archives.len(), "Streaming archive tarball");
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-tar".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"arena-games-{}.tar\"", period),
+            ),
+            (
+                header::HeaderName::from_static("x-archive-count"),
+                archives.len().to_string(),
+            ),
+        ],
+        Body::from_stream(backup::archive_tarball(storage, archives)),
+    ))
+}
+
+async fn control(
+    state: &AppState,
+    game_id: Uuid,
+    action: ControlAction,
+) -> Result<Json<GameControl>, (StatusCode, String)> {
+    match control_game(state, game_id, action).await {
+        Ok(control) => Ok(Json(control)),
+        Err(ControlError::NotFound) => Err((StatusCode::NOT_FOUND, "Game not found".to_string())),
+        Err(ControlError::Invalid(message)) => Err((StatusCode::CONFLICT, message)),
+        Err(ControlError::Internal(e)) => {
+            tracing::error!("Failed to {} game: {:?}", action.as_str(), e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            ))
+        }
+    }
+}
+
+/// POST /api/admin/games/{id}/pause - Hold a game before its next turn
+///
+/// Works on any instance, whichever one is running the game. A game that
+/// hasn't started yet is held before its first turn. Paused games resume on
+/// their own after a while, so one left paused doesn't hold a runner forever.
+pub async fn pause_game(
+    State(state): State<AppState>,
+    _admin: AdminApi,
+    Path(game_id): Path<Uuid>,
+) -> Result<Json<GameControl>, (StatusCode, String)> {
+    control(&state, game_id, ControlAction::Pause).await
+}
+
+/// POST /api/admin/games/{id}/step - Play exactly one more turn of a paused game
+///
+/// Steps add up, so stepping twice plays two turns. Stepping a game that isn't
+/// paused pauses it after its next turn.
+pub async fn step_game(
+    State(state): State<AppState>,
+    _admin: AdminApi,
+    Path(game_id): Path<Uuid>,
+) -> Result<Json<GameControl>, (StatusCode, String)> {
+    control(&state, game_id, ControlAction::Step).await
+}
+
+/// POST /api/admin/games/{id}/resume - Carry on playing a paused game
+pub async fn resume_game(
+    State(state): State<AppState>,
+    _admin: AdminApi,
+    Path(game_id): Path<Uuid>,
+) -> Result<Json<GameControl>, (StatusCode, String)> {
+    control(&state, game_id, ControlAction::Resume).await
+}
@@ -0,0 +1,213 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        battlesnake,
+        snake_alert::{self, SnakeAlert, SnakeAlertEvent},
+    },
+    routes::auth::ApiUser,
+    state::AppState,
+};
+
+/// Smallest and largest number of games in an alert's rolling window
+pub(crate) const MIN_WINDOW_GAMES: i32 = 5;
+pub(crate) const MAX_WINDOW_GAMES: i32 = 500;
+
+/// Latest events returned with a snake's alerts
+const RECENT_EVENTS: i64 = 20;
+
+/// Response format for alert endpoints
+#[derive(Debug, Serialize)]
+pub struct AlertResponse {
+    pub id: Uuid,
+    pub snake_id: Uuid,
+    pub window_games: i32,
+    pub drop_percent: f64,
+    pub firing: bool,
+    pub last_checked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<SnakeAlert> for AlertResponse {
+    fn from(alert: SnakeAlert) -> Self {
+        Self {
+            id: alert.alert_id,
+            snake_id: alert.battlesnake_id,
+            window_games: alert.window_games,
+            drop_percent: alert.drop_percent,
+            firing: alert.firing,
+            last_checked_at: alert.last_checked_at,
+            created_at: alert.created_at,
+        }
+    }
+}
+
+/// One time an alert fired
+#[derive(Debug, Serialize)]
+pub struct AlertEventResponse {
+    pub alert_id: Uuid,
+    pub previous_win_rate: f64,
+    pub current_win_rate: f64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<SnakeAlertEvent> for AlertEventResponse {
+    fn from(event: SnakeAlertEvent) -> Self {
+        Self {
+            alert_id: event.alert_id,
+            previous_win_rate: event.previous_win_rate,
+            current_win_rate: event.current_win_rate,
+            created_at: event.created_at,
+        }
+    }
+}
+
+/// A snake's alerts and the latest times they fired
+#[derive(Debug, Serialize)]
+pub struct SnakeAlertsResponse {
+    pub alerts: Vec<AlertResponse>,
+    pub events: Vec<AlertEventResponse>,
+}
+
+/// Request body for creating an alert
+#[derive(Debug, Deserialize)]
+pub struct CreateAlertRequest {
+    #[serde(default = "default_window_games")]
+    pub window_games: i32,
+    #[serde(default = "default_drop_percent")]
+    pub drop_percent: f64,
+}
+
+fn default_window_games() -> i32 {
+    50
+}
+
+fn default_drop_percent() -> f64 {
+    15.0
+}
+
+/// Validate an alert's window and threshold
+fn validate_alert(request: &CreateAlertRequest) -> Result<(), &'static str> {
+    if !(MIN_WINDOW_GAMES..=MAX_WINDOW_GAMES).contains(&request.window_games) {
+        return Err("window_games must be between 5 and 500");
+    }
+    if !(request.drop_percent > 0.0 && request.drop_percent <= 100.0) {
+        return Err("drop_percent must be more than 0 and at most 100");
+    }
+    Ok(())
+}
+
+/// 404 unless the snake exists and belongs to the user
+async fn check_snake_owner(
+    state: &AppState,
+    snake_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), (StatusCode, String)> {
+    let owned = battlesnake::belongs_to_user(&state.db, snake_id, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check snake ownership: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to get snake".to_string(),
+            )
+        })?;
+
+    if owned {
+        Ok(())
+    } else {
+        Err((StatusCode::NOT_FOUND, "Snake not found".to_string()))
+    }
+}
+
+/// GET /api/snakes/{id}/alerts - List a snake's alerts and recent events
+pub async fn list_alerts(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(snake_id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    check_snake_owner(&state, snake_id, user.user_id).await?;
+
+    let alerts = snake_alert::get_alerts_for_battlesnake(&state.db, snake_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list alerts: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to list alerts".to_string(),
+            )
+        })?;
+    let events = snake_alert::get_recent_alert_events(&state.db, snake_id, RECENT_EVENTS)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list alert events: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to list alerts".to_string(),
+            )
+        })?;
+
+    Ok(Json(SnakeAlertsResponse {
+        alerts: alerts.into_iter().map(AlertResponse::from).collect(),
+        events: events.into_iter().map(AlertEventResponse::from).collect(),
+    }))
+}
+
+/// POST /api/snakes/{id}/alerts - Alert on a drop in the snake's win rate
+pub async fn create_alert(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(snake_id): Path<Uuid>,
+    Json(request): Json<CreateAlertRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    check_snake_owner(&state, snake_id, user.user_id).await?;
+
+    if let Err(e) = validate_alert(&request) {
+        return Err((StatusCode::BAD_REQUEST, e.to_string()));
+    }
+
+    let alert = snake_alert::create_snake_alert(
+        &state.db,
+        snake_id,
+        user.user_id,
+        request.window_games,
+        request.drop_percent,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create alert: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to create alert".to_string(),
+        )
+    })?;
+
+    Ok((StatusCode::CREATED, Json(AlertResponse::from(alert))))
+}
+
+/// DELETE /api/alerts/{id} - Delete an alert
+pub async fn delete_alert(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(alert_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let deleted = snake_alert::delete_snake_alert(&state.db, alert_id, user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete alert: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,105 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    errors::{ServerResult, WithStatus},
+    models::announcement::{self, AnnouncementFields, Severity},
+    routes::auth::AdminApi,
+    state::AppState,
+};
+
+/// Request body for creating or replacing an announcement
+#[derive(Debug, Deserialize)]
+pub struct AnnouncementRequest {
+    /// Markdown
+    pub message: String,
+    #[serde(default)]
+    pub severity: Severity,
+    /// Now if left out
+    pub starts_at: Option<DateTime<Utc>>,
+    /// Runs until it's taken down if left out
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+impl AnnouncementRequest {
+    fn fields(self, now: DateTime<Utc>) -> ServerResult<AnnouncementFields, StatusCode> {
+        AnnouncementFields {
+            message: self.message,
+            severity: self.severity,
+            starts_at: self.starts_at.unwrap_or(now),
+            ends_at: self.ends_at,
+        }
+        .validated()
+        .with_status(StatusCode::BAD_REQUEST)
+    }
+}
+
+/// GET /api/announcements - Announcements running now, most severe first
+///
+/// Public, like the banners on the site, so the CLI can show them before login.
+pub async fn active_announcements(
+    State(state): State<AppState>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let announcements =
+        announcement::get_active_announcements(&state.db, state.clock.now()).await?;
+    Ok(Json(announcements))
+}
+
+/// GET /api/admin/announcements - Every announcement, past, running and upcoming
+pub async fn list_announcements(
+    State(state): State<AppState>,
+    _admin: AdminApi,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let announcements = announcement::get_all_announcements(&state.db).await?;
+    Ok(Json(announcements))
+}
+
+/// POST /api/admin/announcements - Schedule an announcement
+pub async fn create_announcement(
+    State(state): State<AppState>,
+    _admin: AdminApi,
+    Json(request): Json<AnnouncementRequest>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let fields = request.fields(state.clock.now())?;
+    let created = announcement::create_announcement(&state.db, &fields).await?;
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// PUT /api/admin/announcements/{id} - Replace an announcement
+///
+/// End one early by setting `ends_at` to now.
+pub async fn update_announcement(
+    State(state): State<AppState>,
+    _admin: AdminApi,
+    Path(announcement_id): Path<Uuid>,
+    Json(request): Json<AnnouncementRequest>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let fields = request.fields(state.clock.now())?;
+    let updated =
+        announcement::update_announcement(&state.db, announcement_id, &fields, state.clock.now())
+            .await?
+            .ok_or_else(|| "Announcement not found".to_string())
+            .with_status(StatusCode::NOT_FOUND)?;
+    Ok(Json(updated))
+}
+
+/// DELETE /api/admin/announcements/{id} - Take an announcement down for good
+pub async fn delete_announcement(
+    State(state): State<AppState>,
+    _admin: AdminApi,
+    Path(announcement_id): Path<Uuid>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let deleted = announcement::delete_announcement(&state.db, announcement_id).await?;
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err("Announcement not found".to_string()).with_status(StatusCode::NOT_FOUND)
+    }
+}
@@ -0,0 +1,62 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+
+use crate::{
+    backup,
+    models::{game, game_battlesnake},
+    routes::{api::games::ensure_can_view, auth::ApiUser},
+    state::AppState,
+};
+
+/// GET /api/archive/games/{engine_game_id} - A game archived from the Engine
+///
+/// Returns the GameExport JSON the backup job wrote to GCS. The archive is
+/// decompressed as it's downloaded and streamed straight out, so long games
+/// aren't buffered in memory.
+pub async fn archived_game(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(engine_game_id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to read archived game: {:?}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    };
+
+    let storage = state.storage.as_ref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Game archives aren't available on this server".to_string(),
+    ))?;
+
+    let archived = game::get_archived_game(&state.db, &engine_game_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Archived game not found".to_string()))?;
+
+    let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, archived.game_id)
+        .await
+        .map_err(internal_error)?;
+    ensure_can_view(&state, archived.game_id, &battlesnakes, user.user_id).await?;
+
+    let json = backup::download_archive(storage.as_ref(), &archived.gcs_path)
+        .await
+        .map_err(internal_error)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"game-{}.json\"", archived.game_id),
+            ),
+        ],
+        Body::from_stream(json),
+    ))
+}
@@ -0,0 +1,148 @@
+use axum::{Json, extract::State, response::IntoResponse};
+use serde::Serialize;
+
+use crate::{
+    api_version::{CURRENT_VERSION, SUPPORTED_VERSIONS},
+    branch::BRANCHES_PER_USER_PER_HOUR,
+    engine::DEFAULT_MOVE_TIMEOUT_MS,
+    engine::wasm_ruleset::{FUEL_PER_TURN, MAX_RULESET_BYTES},
+    routes::api::{
+        alerts::{MAX_WINDOW_GAMES, MIN_WINDOW_GAMES},
+        checks::MAX_CHECK_GAMES,
+        games::{
+            DEAD_SNAKES, DUEL_BOARD_SIZES, GAME_TYPES, MAX_BATCH_GAMES, MAX_GAME_SNAKES,
+            MAX_LIST_LIMIT, RANKED_GAMES_PER_SNAKE_PER_DAY, SPAWNS, TURN_LIMIT_TIEBREAKS,
+            VALIDATION_MODES, VISIBILITIES,
+        },
+        rulesets::MAX_RULESETS_PER_USER,
+    },
+    state::AppState,
+};
+
+/// Response format for GET /api/capabilities
+#[derive(Debug, Serialize)]
+pub struct CapabilitiesResponse {
+    /// The version the unversioned /api routes serve
+    pub api_version: u32,
+    /// Every version served under its own /api/v{n} prefix
+    pub api_versions: &'static [u32],
+    /// The server's build, from git
+    pub server_version: &'static str,
+    /// Accepted values of each game creation option
+    pub game_types: &'static [&'static str],
+    /// The board sizes this server allows, which may be fewer than the engine plays
+    pub board_sizes: Vec<&'static str>,
+    /// Boards a duel can be played on
+    pub duel_board_sizes: &'static [&'static str],
+    /// Named board layouts; none are supported yet
+    pub maps: Vec<String>,
+    pub spawns: &'static [&'static str],
+    pub turn_limit_tiebreaks: &'static [&'static str],
+    pub dead_snakes: &'static [&'static str],
+    pub validation_modes: &'static [&'static str],
+    pub visibilities: &'static [&'static str],
+    pub limits: Limits,
+    pub features: Features,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Limits {
+    pub min_snakes: usize,
+    pub max_snakes: usize,
+    pub max_turns: i32,
+    /// A game's move timeout when it doesn't set one
+    pub default_move_timeout_ms: i32,
+    pub min_move_timeout_ms: i32,
+    pub max_move_timeout_ms: i32,
+    /// Longest a game may take with every snake using its full timeout every turn
+    pub max_game_minutes: i64,
+    pub max_check_games: i32,
+    pub max_check_opponents: usize,
+    pub min_alert_window_games: i32,
+    pub max_alert_window_games: i32,
+    pub max_games_per_list: u32,
+    pub max_games_per_batch: usize,
+    /// Casual games aren't limited
+    pub ranked_games_per_snake_per_day: i64,
+    pub branches_per_user_per_hour: i64,
+    pub max_rulesets_per_user: i64,
+    pub max_ruleset_bytes: usize,
+    /// Roughly one instruction each
+    pub ruleset_fuel_per_turn: u64,
+}
+
+/// Which optional features this server has; clients should hide the ones it doesn't
+#[derive(Debug, Serialize)]
+pub struct Features {
+    pub tournaments: bool,
+    pub ladders: bool,
+    /// Uploaded WASM rulesets, run in a sandbox after each turn
+    pub custom_rulesets: bool,
+    pub royale: bool,
+    pub mirror_matches: bool,
+    /// 1v1 games with their own leaderboard
+    pub duels: bool,
+    pub branches: bool,
+    pub regression_checks: bool,
+    /// Check results posted to GitHub; needs a GitHub App
+    pub github_commit_statuses: bool,
+    pub win_rate_alerts: bool,
+    pub win_predictions: bool,
+    /// Shares snakes and results with other arenas
+    pub federation: bool,
+    /// Logging in with GitHub; without it no API tokens can be created
+    pub github_login: bool,
+}
+
+/// GET /api/capabilities - What this server supports, so clients can adapt
+pub async fn capabilities(State(state): State<AppState>) -> impl IntoResponse {
+    Json(CapabilitiesResponse {
+        api_version: CURRENT_VERSION,
+        api_versions: SUPPORTED_VERSIONS,
+        server_version: env!("VERGEN_GIT_SHA"),
+        game_types: GAME_TYPES,
+        board_sizes: state.game_limits.board_sizes.clone(),
+        duel_board_sizes: DUEL_BOARD_SIZES,
+        maps: Vec::new(),
+        spawns: SPAWNS,
+        turn_limit_tiebreaks: TURN_LIMIT_TIEBREAKS,
+        dead_snakes: DEAD_SNAKES,
+        validation_modes: VALIDATION_MODES,
+        visibilities: VISIBILITIES,
+        limits: Limits {
+            min_snakes: 1,
+            max_snakes: MAX_GAME_SNAKES,
+            max_turns: state.game_limits.max_turns,
+            default_move_timeout_ms: DEFAULT_MOVE_TIMEOUT_MS,
+            min_move_timeout_ms: state.game_limits.min_move_timeout_ms,
+            max_move_timeout_ms: state.game_limits.max_move_timeout_ms,
+            max_game_minutes: state.game_limits.max_game_minutes,
+            max_check_games: MAX_CHECK_GAMES,
+            max_check_opponents: MAX_GAME_SNAKES - 1,
+            min_alert_window_games: MIN_WINDOW_GAMES,
+            max_alert_window_games: MAX_WINDOW_GAMES,
+            max_games_per_list: MAX_LIST_LIMIT,
+            max_games_per_batch: MAX_BATCH_GAMES,
+            ranked_games_per_snake_per_day: RANKED_GAMES_PER_SNAKE_PER_DAY,
+            branches_per_user_per_hour: BRANCHES_PER_USER_PER_HOUR,
+            max_rulesets_per_user: MAX_RULESETS_PER_USER,
+            max_ruleset_bytes: MAX_RULESET_BYTES,
+            ruleset_fuel_per_turn: FUEL_PER_TURN,
+        },
+        features: Features {
+            tournaments: false,
+            ladders: false,
+            custom_rulesets: true,
+            royale: GAME_TYPES.contains(&"royale"),
+            mirror_matches: true,
+            duels: true,
+            branches: true,
+            regression_checks: true,
+            github_commit_statuses: state.github_app.is_some(),
+            win_rate_alerts: true,
+            win_predictions: true,
+            federation: state.federation.is_some(),
+            github_login: state.github_oauth_config.is_some(),
+        },
+    })
+}
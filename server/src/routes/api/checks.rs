@@ -0,0 +1,326 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    checks::{CheckProgress, check_progress, report_check_to_github},
+    game_settings::{self, GameLimits},
+    models::{
+        battlesnake,
+        check::{self, Check, CheckGame},
+        game::{CreateGameWithSnakes, GameSettings, SpawnStrategy},
+    },
+    routes::{
+        api::games::{
+            MAX_GAME_SNAKES, parse_board_size, parse_game_type, start_game,
+            validate_accessible_snakes,
+        },
+        auth::ApiUser,
+    },
+    state::AppState,
+};
+
+/// Most games a single check can run
+pub(crate) const MAX_CHECK_GAMES: i32 = 100;
+
+/// Request body for starting a check
+#[derive(Debug, Deserialize)]
+pub struct CreateCheckRequest {
+    /// The snake being checked; must be one of the user's own
+    pub snake: Uuid,
+    /// Snakes to play against in every game (1-3)
+    pub opponents: Vec<Uuid>,
+    /// Games to play (default: 10)
+    #[serde(default = "default_games")]
+    pub games: i32,
+    /// Percent of the games the snake has to win to pass
+    pub required_win_rate: f64,
+    /// Board size: "7x7", "11x11", or "19x19" (default: "11x11")
+    #[serde(default = "default_board")]
+    pub board: String,
+    /// Game type: "standard", "royale", "constrictor", "snail", or "wrapped" (default: "standard")
+    #[serde(default = "default_game_type")]
+    pub game_type: String,
+    /// Full SHA of the commit being checked; the result is posted to it on
+    /// GitHub if the snake is linked to a repository
+    #[serde(default)]
+    pub commit_sha: Option<String>,
+}
+
+fn default_games() -> i32 {
+    10
+}
+
+fn default_board() -> String {
+    "11x11".to_string()
+}
+
+fn default_game_type() -> String {
+    "standard".to_string()
+}
+
+/// Response format for check endpoints
+#[derive(Debug, Serialize)]
+pub struct CheckResponse {
+    pub id: Uuid,
+    pub snake_id: Uuid,
+    /// "running", "passed" or "failed"
+    pub status: String,
+    pub games: i32,
+    pub games_finished: u32,
+    pub wins: u32,
+    pub wins_needed: u32,
+    pub required_win_rate: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_sha: Option<String>,
+    pub game_ids: Vec<Uuid>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+fn build_check_response(check: Check, games: &[CheckGame]) -> CheckResponse {
+    let CheckProgress {
+        status,
+        games_finished,
+        wins,
+        wins_needed,
+    } = check_progress(&check, games);
+
+    CheckResponse {
+        id: check.check_id,
+        snake_id: check.battlesnake_id,
+        status: status.as_str().to_string(),
+        games: check.games_requested,
+        games_finished,
+        wins,
+        wins_needed,
+        required_win_rate: check.required_win_rate,
+        commit_sha: check.commit_sha,
+        game_ids: games.iter().map(|g| g.game_id).collect(),
+        created_at: check.created_at,
+    }
+}
+
+/// Validate a check's opponents, game count and win rate, and its board
+/// against the configured game limits
+fn validate_check(request: &CreateCheckRequest, limits: &GameLimits) -> Result<(), String> {
+    if request.opponents.is_empty() || request.opponents.len() > MAX_GAME_SNAKES - 1 {
+        return Err("A check needs between 1 and 3 opponents".to_string());
+    }
+    if request.opponents.contains(&request.snake) {
+        return Err("The checked snake can't also be an opponent".to_string());
+    }
+    if !(1..=MAX_CHECK_GAMES).contains(&request.games) {
+        return Err("games must be between 1 and 100".to_string());
+    }
+    if !(request.required_win_rate > 0.0 && request.required_win_rate <= 100.0) {
+        return Err("required_win_rate must be more than 0 and at most 100".to_string());
+    }
+    if let Some(sha) = &request.commit_sha
+        && !(sha.len() == 40 && sha.chars().all(|c| c.is_ascii_hexdigit()))
+    {
+        return Err("commit_sha must be a full 40 character commit SHA".to_string());
+    }
+    game_settings::validate(limits, Some(&request.board), None, None)
+}
+
+/// POST /api/checks - Start a regression check
+pub async fn create_check(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Json(request): Json<CreateCheckRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    validate_check(&request, &state.game_limits).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let board_size =
+        parse_board_size(&request.board).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let game_type = parse_game_type(&request.game_type)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    // Only the snake's owner can gate on it
+    let owned = battlesnake::belongs_to_user(&state.db, request.snake, user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check snake ownership: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?;
+    if !owned {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Snake {} not found or not yours", request.snake),
+        ));
+    }
+    validate_accessible_snakes(&state, user.user_id, &request.opponents).await?;
+
+    let check = check::create_check(
+        &state.db,
+        user.user_id,
+        request.snake,
+        request.games,
+        request.required_win_rate,
+        request.commit_sha.as_deref(),
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create check: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to create check".to_string(),
+        )
+    })?;
+
+    let battlesnake_ids: Vec<Uuid> = std::iter::once(request.snake)
+        .chain(request.opponents.iter().copied())
+        .collect();
+    for _ in 0..request.games {
+        let create_request = CreateGameWithSnakes {
+            board_size,
+            game_type,
+            battlesnake_ids: battlesnake_ids.clone(),
+        };
+        let settings = GameSettings {
+            max_turns: state.game_limits.turn_limit(None),
+            ..GameSettings::default()
+        };
+        let game = start_game(&state, create_request, settings, &SpawnStrategy::Random).await?;
+
+        check::add_game_to_check(&state.db, check.check_id, game.game_id)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to add game to check: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to create check".to_string(),
+                )
+            })?;
+    }
+
+    let games = check::get_check_games(&state.db, &check)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get check games: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to create check".to_string(),
+            )
+        })?;
+
+    // Mark the commit pending; the cron job retries if GitHub can't be reached
+    if let Err(e) = report_check_to_github(&state, &check).await {
+        tracing::warn!(check_id = %check.check_id, "Failed to post check to GitHub: {:?}", e);
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(build_check_response(check, &games)),
+    ))
+}
+
+/// GET /api/checks/{id} - Get a check's status
+pub async fn show_check(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(check_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let check = check::get_check_by_id(&state.db, check_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get check: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if check.user_id != user.user_id {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let games = check::get_check_games(&state.db, &check)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get check games: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(build_check_response(check, &games)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(opponents: Vec<Uuid>, games: i32, required_win_rate: f64) -> CreateCheckRequest {
+        CreateCheckRequest {
+            snake: Uuid::nil(),
+            opponents,
+            games,
+            required_win_rate,
+            board: default_board(),
+            game_type: default_game_type(),
+            commit_sha: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_check() {
+        let opponent = Uuid::new_v4();
+        let limits = GameLimits::default();
+        assert!(validate_check(&request(vec![opponent], 10, 60.0), &limits).is_ok());
+        assert!(validate_check(&request(vec![], 10, 60.0), &limits).is_err());
+        assert!(validate_check(&request(vec![Uuid::nil()], 10, 60.0), &limits).is_err());
+        assert!(validate_check(&request(vec![opponent], 0, 60.0), &limits).is_err());
+        assert!(validate_check(&request(vec![opponent], 101, 60.0), &limits).is_err());
+        assert!(validate_check(&request(vec![opponent], 10, 0.0), &limits).is_err());
+        assert!(validate_check(&request(vec![opponent], 10, 100.5), &limits).is_err());
+
+        let with_sha = |sha: &str| CreateCheckRequest {
+            commit_sha: Some(sha.to_string()),
+            ..request(vec![opponent], 10, 60.0)
+        };
+        assert!(
+            validate_check(
+                &with_sha("0123456789abcdef0123456789abcdef01234567"),
+                &limits
+            )
+            .is_ok()
+        );
+        assert!(validate_check(&with_sha("0123456"), &limits).is_err());
+        assert!(validate_check(&with_sha("main"), &limits).is_err());
+    }
+
+    #[test]
+    fn test_validate_check_rejects_boards_outside_the_limits() {
+        let opponent = Uuid::new_v4();
+        let on_board = |board: &str| CreateCheckRequest {
+            board: board.to_string(),
+            ..request(vec![opponent], 10, 60.0)
+        };
+        let limits = GameLimits {
+            board_sizes: vec!["11x11"],
+            ..GameLimits::default()
+        };
+
+        assert!(validate_check(&on_board("11x11"), &limits).is_ok());
+        let err = validate_check(&on_board("19x19"), &limits).unwrap_err();
+        assert_eq!(err, "board must be one of 11x11");
+        assert!(validate_check(&on_board("13x13"), &GameLimits::default()).is_err());
+    }
+
+    #[test]
+    fn test_validate_check_rejects_games_over_the_time_limit() {
+        let opponent = Uuid::new_v4();
+        // The default turn limit at the default move timeout takes far longer
+        let limits = GameLimits {
+            max_game_minutes: 1,
+            ..GameLimits::default()
+        };
+        let err = validate_check(&request(vec![opponent], 10, 60.0), &limits).unwrap_err();
+        assert!(err.contains("minute limit"), "{}", err);
+    }
+}
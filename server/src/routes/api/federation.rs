@@ -0,0 +1,108 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    federation::{EXPORT_PAGE_SIZE, ExportCursor, FederationExport, SIGNATURE_HEADER},
+    models::federation,
+    state::AppState,
+};
+
+/// Response format for GET /api/federation
+#[derive(Debug, Serialize)]
+pub struct FederationInfoResponse {
+    /// This arena's base URL, which peers know it by
+    pub instance: String,
+    /// Base64 Ed25519 key exports are signed with
+    pub public_key: String,
+    /// Base URLs of the arenas this one pulls from
+    pub peers: Vec<String>,
+}
+
+/// Query parameters for the export; both or neither
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub after: Option<chrono::DateTime<chrono::Utc>>,
+    pub after_game_id: Option<Uuid>,
+}
+
+/// GET /api/federation - This arena's federation identity
+pub async fn federation_info(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let config = state.federation.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(FederationInfoResponse {
+        instance: config.instance_url.clone(),
+        public_key: config.public_key(),
+        peers: config.peers.iter().map(|p| p.base_url.clone()).collect(),
+    }))
+}
+
+/// GET /api/federation/export - A signed page of public snakes and results
+pub async fn export(
+    State(state): State<AppState>,
+    Query(query): Query<ExportQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let config = state.federation.as_ref().ok_or((
+        StatusCode::NOT_FOUND,
+        "Federation isn't configured".to_string(),
+    ))?;
+
+    let cursor = match (query.after, query.after_game_id) {
+        (Some(after), Some(after_game_id)) => Some((after, after_game_id)),
+        (None, None) => None,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "after and after_game_id must be given together".to_string(),
+            ));
+        }
+    };
+
+    let internal_error = |e: color_eyre::Report| {
+        tracing::error!("Failed to build federation export: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    };
+    let snakes = federation::get_local_federated_snakes(&state.db)
+        .await
+        .map_err(internal_error)?;
+    let results = federation::get_local_federated_results(&state.db, cursor, EXPORT_PAGE_SIZE)
+        .await
+        .map_err(internal_error)?;
+
+    let next = if results.len() as i64 == EXPORT_PAGE_SIZE {
+        results.last().map(|last| ExportCursor {
+            after: last.finished_at,
+            after_game_id: last.game_id,
+        })
+    } else {
+        None
+    };
+    let export = FederationExport {
+        instance: config.instance_url.clone(),
+        snakes,
+        results,
+        next,
+    };
+
+    // Signed over the exact bytes sent, so peers verify before parsing
+    let body = serde_json::to_vec(&export).map_err(|e| internal_error(e.into()))?;
+    let signature = config.sign(&body);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (header::HeaderName::from_static(SIGNATURE_HEADER), signature),
+        ],
+        body,
+    ))
+}
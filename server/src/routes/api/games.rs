@@ -1,57 +1,190 @@
 use axum::{
     Json,
+    body::Body,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{StatusCode, header},
     response::IntoResponse,
 };
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    jobs::GameRunnerJob,
+    board_description::describe_frame,
+    branch::{self, BranchError, BranchRequest},
+    certification::{VerificationStatus, chain_digest},
+    diagnose::{CheckStatus, diagnose_url},
+    engine::{engine_seed, validate_spawn_points},
+    engine_models::EngineGameFrame,
+    game_diff::{self, DiffError},
+    game_export,
+    game_retry::get_retry_count,
+    game_settings,
+    jobs::{GameRunnerJob, enqueue_at},
+    mirror::{
+        self, MirrorRound, SeriesResults, get_mirror_round, get_series_results, mirrored_spawn,
+    },
     models::{
-        game::{self, CreateGameWithSnakes, Game, GameBoardSize, GameStatus, GameType},
-        game_battlesnake::{self, GameBattlesnakeWithDetails},
-        turn,
+        battlesnake::{self, Visibility},
+        battlesnake_version,
+        game::{
+            self, CreateGameWithSnakes, DeadSnakes, Game, GameBoardSize, GameSettings, GameStatus,
+            GameType, MoveValidationMode, SpawnPoint, SpawnStrategy, TurnLimitTiebreak,
+        },
+        game_battlesnake::{self, GameBattlesnakeWithDetails, SnakeInstance},
+        game_log,
+        game_stage_timing::get_game_stage_timings,
+        game_stream_ticket::create_game_stream_ticket,
+        league, mirror_series, ruleset,
+        snake_template::get_archived_snake_ids,
+        user_preferences::get_user_preferences,
     },
-    routes::auth::ApiUser,
+    notation,
+    public_url::PublicUrl,
+    queue::{QueueEstimate, queue_estimate},
+    ratings, replay_fixture,
+    routes::{auth::ApiUser, game::api::can_view_game},
     state::AppState,
+    stats::{INITIAL_RATING, placement_probabilities},
+    turn_archive,
+    turn_metrics::TimingReport,
 };
 
+/// Most snakes in one game
+pub(crate) const MAX_GAME_SNAKES: usize = 4;
+
+/// Most games one list request returns
+pub(crate) const MAX_LIST_LIMIT: u32 = 100;
+
+/// Most games one batch request creates
+pub(crate) const MAX_BATCH_GAMES: usize = 100;
+
+/// Frames one page of game details returns when `limit` is left out
+const DETAILS_PAGE_SIZE: i64 = 100;
+
+/// Most frames one page of game details returns
+const MAX_DETAILS_PAGE_SIZE: i64 = 1000;
+
+/// Most ranked games a snake can be entered in over a day; casual games aren't limited
+pub(crate) const RANKED_GAMES_PER_SNAKE_PER_DAY: i64 = 100;
+
+/// The names each create request option accepts, as listed by /api/capabilities
+pub(crate) const GAME_TYPES: &[&str] = &["standard", "royale", "constrictor", "snail", "wrapped"];
+pub(crate) const BOARD_SIZES: &[&str] = game_settings::ENGINE_BOARD_SIZES;
+pub(crate) const DUEL_BOARD_SIZES: &[&str] = &["7x7", "11x11"];
+pub(crate) const VALIDATION_MODES: &[&str] = &["strict", "lenient"];
+pub(crate) const VISIBILITIES: &[&str] = &["public", "private"];
+pub(crate) const SPAWNS: &[&str] = &["random", "fixed", "manual"];
+pub(crate) const TURN_LIMIT_TIEBREAKS: &[&str] = &["longest", "most_food", "draw"];
+pub(crate) const DEAD_SNAKES: &[&str] = &["drop", "ghost"];
+
 /// Request body for creating a game
 #[derive(Debug, Deserialize)]
 pub struct CreateGameRequest {
     /// Snake IDs to include in the game (1-4 required)
     pub snakes: Vec<Uuid>,
-    /// Board size: "7x7", "11x11", or "19x19" (default: "11x11")
-    #[serde(default = "default_board")]
-    pub board: String,
-    /// Game type: "standard", "royale", "constrictor", or "snail" (default: "standard")
-    #[serde(default = "default_game_type")]
-    pub game_type: String,
+    /// Board size: "7x7", "11x11", or "19x19" (default: the user's preferred board, or "11x11")
+    #[serde(default)]
+    pub board: Option<String>,
+    /// Game type: "standard", "royale", "constrictor", "snail", or "wrapped" (default: the
+    /// user's preferred game type, or "standard")
+    #[serde(default)]
+    pub game_type: Option<String>,
+    /// How strictly to parse snake move responses: "strict" or "lenient" (default: "lenient")
+    #[serde(default = "default_validation_mode")]
+    pub validation_mode: String,
+    /// Extend each snake's move deadline by its measured /start round trip (default: false)
+    #[serde(default)]
+    pub latency_compensation: bool,
+    /// "public" or "private"; private games only stream to owners of their snakes (default: "public")
+    #[serde(default = "default_visibility")]
+    pub visibility: String,
+    /// Where snakes start: "random" (official corners/cardinals), "fixed" or "manual" (default: "random")
+    #[serde(default = "default_spawn")]
+    pub spawn: String,
+    /// For "fixed" spawns, how many points along each snake starts; alternate it across a series
+    #[serde(default)]
+    pub spawn_rotation: i32,
+    /// For "manual" spawns, one position per snake in the order of `snakes`
+    #[serde(default)]
+    pub spawn_positions: Vec<SpawnPoint>,
+    /// How snakes still alive at the turn limit are placed: "longest", "most_food" or "draw" (default: "longest")
+    #[serde(default = "default_turn_limit_tiebreak")]
+    pub turn_limit_tiebreak: String,
+    /// What frames show of eliminated snakes: "drop" (after the turn they die) or "ghost" (default: "drop")
+    #[serde(default = "default_dead_snakes")]
+    pub dead_snakes: String,
+    /// Also play a second game with the two snakes' spawns swapped, scored with this one as a round
+    #[serde(default)]
+    pub mirror: bool,
+    /// Play the mirror match as the first round of a best-of-N series, N odd and up to 9
+    #[serde(default)]
+    pub best_of: Option<i32>,
+    /// Turn the game ends on if more than one snake is alive, up to 5000 (default: 5000, or 500
+    /// for self-play practice)
+    #[serde(default)]
+    pub max_turns: Option<i32>,
+    /// Milliseconds each snake has to answer /move, from 50 to 5000 (default: 500)
+    #[serde(default)]
+    pub move_timeout_ms: Option<i32>,
+    /// Seed for spawns, food and built-in bots' moves; the same seed and snake moves replay the
+    /// game exactly (default: random)
+    #[serde(default)]
+    pub seed: Option<i64>,
+    /// Play in the ranked pool, which counts towards ratings and leaderboards. Every snake must
+    /// be opted in and pass a health check, and each can play a limited number of ranked games a
+    /// day. Self-play games (one snake in every slot) are always practice (default: false, a
+    /// casual game)
+    #[serde(default)]
+    pub ranked: Option<bool>,
+    /// Play the game in a league, counting towards its own leaderboard; the caller and every
+    /// snake's owner must be members
+    #[serde(default)]
+    pub league_id: Option<Uuid>,
+    /// A 1v1 duel between exactly 2 different snakes on a 7x7 or 11x11 board, with the board
+    /// defaulting to 11x11 rather than the user's preferred one. Ranked duels are also rated on
+    /// the duel leaderboard (default: false)
+    #[serde(default)]
+    pub duel: bool,
+    /// One of the caller's uploaded rulesets to run after each turn; games with a custom
+    /// ruleset can't be ranked
+    #[serde(default)]
+    pub ruleset_id: Option<Uuid>,
+}
+
+fn default_validation_mode() -> String {
+    "lenient".to_string()
+}
+
+fn default_visibility() -> String {
+    "public".to_string()
+}
+
+fn default_spawn() -> String {
+    "random".to_string()
 }
 
-fn default_board() -> String {
-    "11x11".to_string()
+fn default_turn_limit_tiebreak() -> String {
+    "longest".to_string()
 }
 
-fn default_game_type() -> String {
-    "standard".to_string()
+fn default_dead_snakes() -> String {
+    "drop".to_string()
 }
 
 /// Parse game_type string case-insensitively
-fn parse_game_type(s: &str) -> Result<GameType, &'static str> {
+pub(crate) fn parse_game_type(s: &str) -> Result<GameType, &'static str> {
     match s.to_lowercase().as_str() {
         "standard" => Ok(GameType::Standard),
         "royale" => Ok(GameType::Royale),
         "constrictor" => Ok(GameType::Constrictor),
         "snail" | "snailmode" | "snail_mode" | "snail mode" => Ok(GameType::SnailMode),
-        _ => Err("Invalid game type. Use standard, royale, constrictor, or snail"),
+        "wrapped" => Ok(GameType::Wrapped),
+        _ => Err("Invalid game type. Use standard, royale, constrictor, snail, or wrapped"),
     }
 }
 
 /// Parse board size string
-fn parse_board_size(s: &str) -> Result<GameBoardSize, &'static str> {
+pub(crate) fn parse_board_size(s: &str) -> Result<GameBoardSize, &'static str> {
     match s.to_lowercase().as_str() {
         "7x7" => Ok(GameBoardSize::Small),
         "11x11" => Ok(GameBoardSize::Medium),
@@ -60,29 +193,190 @@ fn parse_board_size(s: &str) -> Result<GameBoardSize, &'static str> {
     }
 }
 
+/// Parse the spawn fields of a create request, checking manual positions fit the game
+fn parse_spawn_strategy(
+    request: &CreateGameRequest,
+    board_size: GameBoardSize,
+) -> Result<SpawnStrategy, String> {
+    match request.spawn.to_lowercase().as_str() {
+        "random" => Ok(SpawnStrategy::Random),
+        "fixed" => Ok(SpawnStrategy::Fixed {
+            rotation: request.spawn_rotation,
+        }),
+        "manual" => {
+            let (width, height) = board_size.dimensions();
+            validate_spawn_points(
+                width as i32,
+                height as i32,
+                request.snakes.len(),
+                &request.spawn_positions,
+            )?;
+            Ok(SpawnStrategy::Manual(request.spawn_positions.clone()))
+        }
+        _ => Err("Invalid spawn. Use random, fixed, or manual".to_string()),
+    }
+}
+
+/// Parse turn limit tiebreak string case-insensitively
+fn parse_turn_limit_tiebreak(s: &str) -> Result<TurnLimitTiebreak, &'static str> {
+    match s.to_lowercase().as_str() {
+        "longest" => Ok(TurnLimitTiebreak::Longest),
+        "most_food" | "most food" | "food" => Ok(TurnLimitTiebreak::MostFood),
+        "draw" => Ok(TurnLimitTiebreak::Draw),
+        _ => Err("Invalid turn limit tiebreak. Use longest, most_food, or draw"),
+    }
+}
+
+/// Parse dead snakes option case-insensitively
+fn parse_dead_snakes(s: &str) -> Result<DeadSnakes, &'static str> {
+    match s.to_lowercase().as_str() {
+        "drop" => Ok(DeadSnakes::Drop),
+        "ghost" => Ok(DeadSnakes::Ghost),
+        _ => Err("Invalid dead_snakes. Use drop or ghost"),
+    }
+}
+
+/// Parse validation mode string case-insensitively
+fn parse_validation_mode(s: &str) -> Result<MoveValidationMode, &'static str> {
+    match s.to_lowercase().as_str() {
+        "strict" => Ok(MoveValidationMode::Strict),
+        "lenient" => Ok(MoveValidationMode::Lenient),
+        _ => Err("Invalid validation mode. Use strict or lenient"),
+    }
+}
+
+/// Request body for creating many games at once
+///
+/// Either `matchups` lists the snakes for each game, or `snakes` and `count`
+/// play the same snakes `count` times. Every game shares the other options,
+/// which work as they do for a single game.
+#[derive(Debug, Deserialize)]
+pub struct CreateGameBatchRequest {
+    /// Snake IDs for each game, 1-4 per game
+    #[serde(default)]
+    pub matchups: Vec<Vec<Uuid>>,
+    /// Snake IDs to play in every game, with `count`
+    #[serde(default)]
+    pub snakes: Vec<Uuid>,
+    /// How many games to create from `snakes`
+    #[serde(default)]
+    pub count: Option<usize>,
+    #[serde(default)]
+    pub board: Option<String>,
+    #[serde(default)]
+    pub game_type: Option<String>,
+    #[serde(default = "default_validation_mode")]
+    pub validation_mode: String,
+    #[serde(default)]
+    pub latency_compensation: bool,
+    #[serde(default = "default_visibility")]
+    pub visibility: String,
+    #[serde(default = "default_turn_limit_tiebreak")]
+    pub turn_limit_tiebreak: String,
+    #[serde(default = "default_dead_snakes")]
+    pub dead_snakes: String,
+    #[serde(default)]
+    pub max_turns: Option<i32>,
+    #[serde(default)]
+    pub move_timeout_ms: Option<i32>,
+}
+
+impl CreateGameBatchRequest {
+    /// The snakes for each game, from whichever of the two forms was used
+    fn games(&self) -> Result<Vec<Vec<Uuid>>, String> {
+        let count = match (self.matchups.is_empty(), self.count) {
+            (false, None) if self.snakes.is_empty() => self.matchups.len(),
+            (true, Some(count)) if !self.snakes.is_empty() => count,
+            _ => return Err("Give either matchups, or snakes and a count".to_string()),
+        };
+        if count == 0 || count > MAX_BATCH_GAMES {
+            return Err(format!(
+                "A batch can create between 1 and {} games",
+                MAX_BATCH_GAMES
+            ));
+        }
+
+        let games = if self.matchups.is_empty() {
+            vec![self.snakes.clone(); count]
+        } else {
+            self.matchups.clone()
+        };
+        if let Some(index) = games
+            .iter()
+            .position(|snakes| snakes.is_empty() || snakes.len() > MAX_GAME_SNAKES)
+        {
+            return Err(format!(
+                "Game {} in the batch needs between 1 and {} snakes",
+                index + 1,
+                MAX_GAME_SNAKES
+            ));
+        }
+
+        Ok(games)
+    }
+}
+
+/// Response for a created batch of games
+#[derive(Debug, Serialize)]
+pub struct CreateGameBatchResponse {
+    /// The games created, in the order they were requested
+    pub ids: Vec<Uuid>,
+}
+
 /// Response for a created game (minimal)
 #[derive(Debug, Serialize)]
 pub struct CreateGameResponse {
     pub id: Uuid,
     pub status: String,
+    /// The second game of a mirror match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirror_id: Option<Uuid>,
+    /// The series a mirror match is the first round of
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series_id: Option<Uuid>,
+    /// Where the game is in the queue
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue: Option<QueueEstimate>,
 }
 
 /// Snake info in game responses
 #[derive(Debug, Serialize)]
 pub struct SnakeInfo {
+    /// The canonical snake ID, shared by every instance of the snake in a game
     pub id: Uuid,
+    /// Unique per instance in the game: "{id}-{n}"
+    pub instance_id: String,
+    /// Snake name, numbered when the snake is entered more than once
     pub name: String,
     pub url: String,
 }
 
-impl From<&GameBattlesnakeWithDetails> for SnakeInfo {
-    fn from(snake: &GameBattlesnakeWithDetails) -> Self {
-        Self {
-            id: snake.battlesnake_id,
-            name: snake.name.clone(),
-            url: snake.url.clone(),
-        }
-    }
+/// Build SnakeInfo for every snake in a game, labelling duplicate instances
+fn build_snake_infos(battlesnakes: &[GameBattlesnakeWithDetails]) -> Vec<SnakeInfo> {
+    let instances = game_battlesnake::snake_instances(battlesnakes);
+
+    battlesnakes
+        .iter()
+        .map(|snake| {
+            let SnakeInstance {
+                instance_id,
+                display_name,
+            } = instances
+                .get(&snake.game_battlesnake_id)
+                .cloned()
+                .unwrap_or_else(|| SnakeInstance {
+                    instance_id: format!("{}-1", snake.battlesnake_id),
+                    display_name: snake.name.clone(),
+                });
+
+            SnakeInfo {
+                id: snake.battlesnake_id,
+                instance_id,
+                name: display_name,
+                url: snake.url.clone(),
+            }
+        })
+        .collect()
 }
 
 /// Response for game list items (without frames)
@@ -90,7 +384,12 @@ impl From<&GameBattlesnakeWithDetails> for SnakeInfo {
 pub struct GameListItem {
     pub id: Uuid,
     pub status: String,
+    /// None for a draw
     pub winner: Option<Uuid>,
+    /// More than one snake shares first place
+    pub draw: bool,
+    /// Played in the ranked pool rather than a casual or practice game
+    pub ranked: bool,
     pub snakes: Vec<SnakeInfo>,
     pub board: String,
     pub game_type: String,
@@ -102,18 +401,104 @@ pub struct GameListItem {
 pub struct GameResponse {
     pub id: Uuid,
     pub status: String,
+    /// None for a draw
     pub winner: Option<Uuid>,
+    /// More than one snake shares first place
+    pub draw: bool,
     pub snakes: Vec<SnakeInfo>,
+    /// Every frame, or one page of them when `from_turn` or `limit` is given
     pub frames: Vec<serde_json::Value>,
+    /// Turns stored so far: the last stored turn's number plus one
+    pub turn_count: i32,
+    /// `from_turn` for the next page, None when this page ends at the last stored turn
+    pub next_from_turn: Option<i32>,
     pub board: String,
     pub game_type: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// None for a page of frames, since checking the digest needs every frame
+    pub certification: Option<CertificationResponse>,
+    /// Where the game was branched from, if it's a "what if" branch
+    pub branch: Option<BranchOriginResponse>,
+    /// Both games of the mirror match this game is part of, scored as one round
+    pub mirror: Option<MirrorRound>,
+    /// Every round of the series this game's mirror match is part of, and the series standings
+    pub series: Option<SeriesResults>,
+    /// The tiebreak that placed the survivors, if the turn limit ended the game with more than one alive
+    pub tiebreak_applied: Option<String>,
+    /// Where the game is in the queue, while it's waiting
+    pub queue: Option<QueueEstimate>,
+    /// Seed the game's random draws come from; create a game with it to replay them
+    pub seed: i64,
+    /// False for practice games, which don't count towards ratings or leaderboards
+    pub ranked: bool,
+    /// A 1v1 duel, rated on the duel leaderboard when it's ranked
+    pub duel: bool,
+    /// Turn the game ends on if more than one snake is still alive
+    pub max_turns: i32,
+    /// Milliseconds each snake has to answer /move
+    pub move_timeout_ms: i32,
+    /// Times the game's run failed on the server and was retried
+    pub retry_count: i32,
+}
+
+/// The game and turn a branch was simulated from
+#[derive(Debug, Serialize)]
+pub struct BranchOriginResponse {
+    pub parent_id: Uuid,
+    pub branched_from_turn: i32,
+}
+
+/// A "what if" branch of a game
+#[derive(Debug, Serialize)]
+pub struct BranchResponse {
+    pub id: Uuid,
+    pub parent_id: Uuid,
+    pub branched_from_turn: i32,
+    pub status: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Whether a game's frames match the digest recorded when it finished
+#[derive(Debug, Serialize)]
+pub struct CertificationResponse {
+    /// Hash chain digest stored with the result, None until the game finishes
+    pub digest: Option<String>,
+    /// Digest of the frames as they're stored now
+    pub computed_digest: String,
+    pub status: VerificationStatus,
+}
+
+/// A single snake's move in the debug response
+#[derive(Debug, Serialize)]
+pub struct SnakeMoveDebug {
+    pub turn: i32,
+    /// The game_battlesnake ID, unique per snake instance in the game
+    pub snake: Uuid,
+    pub direction: String,
+    pub latency_ms: Option<i32>,
+    pub compensated_latency_ms: Option<i32>,
+    pub timed_out: bool,
+    pub parse_error: Option<String>,
+}
+
+/// Response for the per-turn debug view of a game
+#[derive(Debug, Serialize)]
+pub struct GameDebugResponse {
+    pub id: Uuid,
+    pub validation_mode: String,
+    pub latency_compensation: bool,
+    pub parse_failures: usize,
+    pub moves: Vec<SnakeMoveDebug>,
 }
 
 /// Query parameters for listing games
 #[derive(Debug, Deserialize)]
 pub struct ListGamesQuery {
     pub snake_id: Option<Uuid>,
+    /// Only ranked games (true) or only casual and practice games (false)
+    pub ranked: Option<bool>,
+    /// Only games a version of a snake played, implying its snake_id
+    pub version_id: Option<Uuid>,
     #[serde(default = "default_limit")]
     pub limit: u32,
 }
@@ -122,19 +507,65 @@ fn default_limit() -> u32 {
     20
 }
 
+/// Query parameters for paging through a game's frames in its details
+#[derive(Debug, Default, Deserialize)]
+pub struct GameDetailsQuery {
+    /// First turn to return the frame of
+    pub from_turn: Option<i32>,
+    /// Most frames to return, up to 1000
+    pub limit: Option<i64>,
+}
+
+impl GameDetailsQuery {
+    /// The first turn and page size asked for, None for every frame
+    fn page(&self) -> Option<(i32, i64)> {
+        if self.from_turn.is_none() && self.limit.is_none() {
+            return None;
+        }
+        Some((
+            self.from_turn.unwrap_or(0).max(0),
+            self.limit
+                .unwrap_or(DETAILS_PAGE_SIZE)
+                .clamp(1, MAX_DETAILS_PAGE_SIZE),
+        ))
+    }
+}
+
+/// Where the page after the one ending at `last_turn` starts, None if it's the
+/// last stored turn
+fn next_page_turn(last_turn: Option<i32>, turn_count: i32) -> Option<i32> {
+    last_turn
+        .map(|turn| turn + 1)
+        .filter(|next| *next < turn_count)
+}
+
+/// The snake that won a game, and whether it was a draw instead: more than
+/// one snake in first place, counting each instance of a snake on its own
+fn game_winner(battlesnakes: &[GameBattlesnakeWithDetails]) -> (Option<Uuid>, bool) {
+    let mut first = battlesnakes.iter().filter(|b| b.placement == Some(1));
+    match (first.next(), first.next()) {
+        (Some(winner), None) => (Some(winner.battlesnake_id), false),
+        (Some(_), Some(_)) => (None, true),
+        _ => (None, false),
+    }
+}
+
 /// Build a GameListItem from game and battlesnakes
-fn build_game_list_item(game: &Game, battlesnakes: &[GameBattlesnakeWithDetails]) -> GameListItem {
-    let winner = battlesnakes
-        .iter()
-        .find(|b| b.placement == Some(1))
-        .map(|b| b.battlesnake_id);
+fn build_game_list_item(
+    game: &Game,
+    ranked: bool,
+    battlesnakes: &[GameBattlesnakeWithDetails],
+) -> GameListItem {
+    let (winner, draw) = game_winner(battlesnakes);
 
-    let snakes: Vec<SnakeInfo> = battlesnakes.iter().map(SnakeInfo::from).collect();
+    let snakes: Vec<SnakeInfo> = build_snake_infos(battlesnakes);
 
     GameListItem {
         id: game.game_id,
         status: game.status.as_str().to_string(),
         winner,
+        draw,
+        ranked,
         snakes,
         board: game.board_size.as_str().to_string(),
         game_type: game.game_type.as_str().to_string(),
@@ -148,14 +579,67 @@ pub async fn create_game(
     ApiUser(user): ApiUser,
     Json(request): Json<CreateGameRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
+    // Board size and game type default to the user's preferences
+    let preferences = get_user_preferences(&state.db, user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get preferences: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?;
+
     // Parse board size
-    let board_size =
-        parse_board_size(&request.board).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let board_size = match &request.board {
+        Some(board) => {
+            parse_board_size(board).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+        }
+        None if request.duel => game::DUEL_BOARD_SIZE,
+        None => preferences.board_size,
+    };
 
     // Parse game type
-    let game_type = parse_game_type(&request.game_type)
+    let game_type = match &request.game_type {
+        Some(game_type) => {
+            parse_game_type(game_type).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+        }
+        None => preferences.game_type,
+    };
+
+    // Parse validation mode
+    let validation_mode = parse_validation_mode(&request.validation_mode)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    // Parse visibility
+    let visibility = Visibility::from_str(&request.visibility).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Invalid visibility. Use public or private".to_string(),
+        )
+    })?;
+
+    // Parse turn limit tiebreak
+    let turn_limit_tiebreak = parse_turn_limit_tiebreak(&request.turn_limit_tiebreak)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    // Parse dead snakes option
+    let dead_snakes = parse_dead_snakes(&request.dead_snakes)
         .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
 
+    // Check the board, turn limit and move timeout against the configured limits
+    game_settings::validate(
+        &state.game_limits,
+        Some(board_size.as_str()),
+        request.max_turns,
+        request.move_timeout_ms,
+    )
+    .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    // Parse spawn strategy
+    let spawn =
+        parse_spawn_strategy(&request, board_size).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
     // Validate snake count
     if request.snakes.is_empty() {
         return Err((
@@ -163,16 +647,312 @@ pub async fn create_game(
             "At least one snake is required".to_string(),
         ));
     }
-    if request.snakes.len() > 4 {
+    if request.snakes.len() > MAX_GAME_SNAKES {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Maximum of {} snakes allowed", MAX_GAME_SNAKES),
+        ));
+    }
+    if request.mirror && request.snakes.len() != 2 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Mirror matches need exactly 2 snakes".to_string(),
+        ));
+    }
+    if let Some(best_of) = request.best_of {
+        if !request.mirror {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Series are played as mirror matches; set mirror as well as best_of".to_string(),
+            ));
+        }
+        mirror::validate_best_of(best_of).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+    if request.duel {
+        game::validate_duel(&request.snakes, board_size)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+    // Games are casual unless they ask for the ranked pool, and a snake
+    // playing only itself is practice, which can't move its rating
+    let self_play = game::is_self_play(&request.snakes);
+    let ranked = request.ranked.unwrap_or(false);
+    if ranked && self_play {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Self-play games are practice and can't be ranked".to_string(),
+        ));
+    }
+    if ranked && request.ruleset_id.is_some() {
         return Err((
             StatusCode::BAD_REQUEST,
-            "Maximum of 4 snakes allowed".to_string(),
+            "Games with a custom ruleset can't be ranked".to_string(),
         ));
     }
 
+    // Random spawns aren't stored, so a mirror match starts from fixed ones it can swap
+    let spawn = match spawn {
+        SpawnStrategy::Random if request.mirror => SpawnStrategy::Fixed {
+            rotation: request.spawn_rotation,
+        },
+        spawn => spawn,
+    };
+
+    validate_accessible_snakes(&state, user.user_id, &request.snakes).await?;
+    if ranked {
+        // Every round of a series is ranked, so the whole series has to fit
+        let games = if request.mirror {
+            2 * i64::from(request.best_of.unwrap_or(1))
+        } else {
+            1
+        };
+        validate_ranked_snakes(&state, &request.snakes, games).await?;
+    }
+    if let Some(league_id) = request.league_id {
+        validate_league_snakes(&state, league_id, user.user_id, &request.snakes).await?;
+    }
+    if let Some(ruleset_id) = request.ruleset_id {
+        validate_ruleset(&state, user.user_id, ruleset_id).await?;
+    }
+
+    // Create the game
+    let create_request = CreateGameWithSnakes {
+        board_size,
+        game_type,
+        battlesnake_ids: request.snakes,
+    };
+    let settings = GameSettings {
+        validation_mode,
+        latency_compensation: request.latency_compensation,
+        visibility,
+        turn_limit_tiebreak,
+        dead_snakes,
+        seed: Some(request.seed.unwrap_or_else(rand::random)),
+        max_turns: state.game_limits.turn_limit(
+            request
+                .max_turns
+                .or(self_play.then_some(game::PRACTICE_MAX_TURNS)),
+        ),
+        move_timeout_ms: request.move_timeout_ms,
+        ranked,
+        duel: request.duel,
+        ruleset_id: request.ruleset_id,
+    };
+
+    // The mirror game is the same game with the spawns swapped. A series
+    // starts with its first round; the rest start as each round finishes.
+    let (game, mirror_id, series_id) = if request.mirror {
+        let series = match request.best_of {
+            Some(best_of) => Some(
+                mirror_series::create_mirror_series(
+                    &state.db,
+                    user.user_id,
+                    best_of,
+                    &create_request.battlesnake_ids,
+                    request.league_id,
+                    state.clock.now(),
+                )
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to create mirror series: {}", e);
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to prepare game".to_string(),
+                    )
+                })?,
+            ),
+            None => None,
+        };
+        let series_id = series.map(|series| series.series_id);
+        let [game, mirror] = start_mirror_round(
+            &state,
+            create_request,
+            settings,
+            &spawn,
+            series_id,
+            request.league_id,
+        )
+        .await?;
+        (game, Some(mirror.game_id), series_id)
+    } else {
+        let game = start_game(&state, create_request, settings, &spawn).await?;
+        if let Some(league_id) = request.league_id {
+            set_league(&state, game.game_id, league_id).await?;
+        }
+        (game, None, None)
+    };
+
+    let queue = queue_estimate(&state, &game).await.map_err(|e| {
+        tracing::error!("Failed to estimate queue position: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateGameResponse {
+            id: game.game_id,
+            status: game.status.as_str().to_string(),
+            mirror_id,
+            series_id,
+            queue,
+        }),
+    ))
+}
+
+/// POST /api/games/batch - Create up to 100 casual games in one transaction
+pub async fn create_game_batch(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Json(request): Json<CreateGameBatchRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let games = request.games().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    // Board size and game type default to the user's preferences
+    let preferences = get_user_preferences(&state.db, user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get preferences: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?;
+    let board_size = match &request.board {
+        Some(board) => {
+            parse_board_size(board).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+        }
+        None => preferences.board_size,
+    };
+    let game_type = match &request.game_type {
+        Some(game_type) => {
+            parse_game_type(game_type).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+        }
+        None => preferences.game_type,
+    };
+
+    let validation_mode = parse_validation_mode(&request.validation_mode)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let visibility = Visibility::from_str(&request.visibility).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Invalid visibility. Use public or private".to_string(),
+        )
+    })?;
+    let turn_limit_tiebreak = parse_turn_limit_tiebreak(&request.turn_limit_tiebreak)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let dead_snakes = parse_dead_snakes(&request.dead_snakes)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    game_settings::validate(
+        &state.game_limits,
+        Some(board_size.as_str()),
+        request.max_turns,
+        request.move_timeout_ms,
+    )
+    .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let all_snakes: Vec<Uuid> = games.iter().flatten().copied().collect();
+    validate_accessible_snakes(&state, user.user_id, &all_snakes).await?;
+
+    // Batch games are casual, each with its own seed, and self-play matchups
+    // are practice like any other
+    let games = games
+        .into_iter()
+        .map(|battlesnake_ids| {
+            let self_play = game::is_self_play(&battlesnake_ids);
+            let settings = GameSettings {
+                validation_mode,
+                latency_compensation: request.latency_compensation,
+                visibility,
+                turn_limit_tiebreak,
+                dead_snakes,
+                seed: Some(rand::random()),
+                max_turns: state.game_limits.turn_limit(
+                    request
+                        .max_turns
+                        .or(self_play.then_some(game::PRACTICE_MAX_TURNS)),
+                ),
+                move_timeout_ms: request.move_timeout_ms,
+                ranked: false,
+                duel: false,
+                ruleset_id: None,
+            };
+            let create_request = CreateGameWithSnakes {
+                board_size,
+                game_type,
+                battlesnake_ids,
+            };
+            (create_request, settings)
+        })
+        .collect();
+
+    let ids = start_game_batch(&state, games).await?;
+
+    Ok((StatusCode::CREATED, Json(CreateGameBatchResponse { ids })))
+}
+
+/// Create and queue every game in a batch in one transaction, so either all
+/// of them are created or none are
+async fn start_game_batch(
+    state: &AppState,
+    games: Vec<(CreateGameWithSnakes, GameSettings)>,
+) -> Result<Vec<Uuid>, (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to create game batch: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to create games".to_string(),
+        )
+    };
+
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|e| internal_error(e.into()))?;
+    let enqueued_at = state.clock.now();
+
+    let mut ids = Vec::with_capacity(games.len());
+    for (create_request, settings) in games {
+        let game = game::insert_game_with_snakes(&mut tx, create_request)
+            .await
+            .map_err(internal_error)?;
+        game::set_game_settings(&mut *tx, game.game_id, settings)
+            .await
+            .map_err(internal_error)?;
+        game::set_game_enqueued_at(&mut *tx, game.game_id, enqueued_at)
+            .await
+            .map_err(internal_error)?;
+        enqueue_at(
+            &mut *tx,
+            GameRunnerJob {
+                game_id: game.game_id,
+            },
+            format!("Game {} created via batch API", game.game_id),
+            0,
+            state.clock.now(),
+        )
+        .await
+        .map_err(internal_error)?;
+        ids.push(game.game_id);
+    }
+
+    tx.commit().await.map_err(|e| internal_error(e.into()))?;
+
+    Ok(ids)
+}
+
+/// Check that every snake exists and is accessible to the user (owned by
+/// them or public)
+pub(crate) async fn validate_accessible_snakes(
+    state: &AppState,
+    user_id: Uuid,
+    snake_ids: &[Uuid],
+) -> Result<(), (StatusCode, String)> {
     // Get unique snake IDs to validate (duplicates are allowed but we only need to check each once)
     let unique_snake_ids: Vec<Uuid> = {
-        let mut ids = request.snakes.clone();
+        let mut ids = snake_ids.to_vec();
         ids.sort();
         ids.dedup();
         ids
@@ -188,7 +968,7 @@ pub async fn create_game(
           AND (user_id = $2 OR visibility = 'public')
         "#,
         &unique_snake_ids as &[Uuid],
-        user.user_id
+        user_id
     )
     .fetch_all(&state.db)
     .await
@@ -211,256 +991,1366 @@ pub async fn create_game(
         }
     }
 
-    // Create the game
-    let create_request = CreateGameWithSnakes {
-        board_size,
-        game_type,
-        battlesnake_ids: request.snakes,
-    };
-
-    let game = game::create_game_with_snakes(&state.db, create_request)
+    // Archived template variants stay out of new games until they're redeployed
+    let archived = get_archived_snake_ids(&state.db, &unique_snake_ids)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to create game: {}", e);
+            tracing::error!("Failed to check for archived snakes: {}", e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to create game".to_string(),
+                "Internal server error".to_string(),
             )
         })?;
+    if let Some(snake_id) = archived.first() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Snake {} is an archived variant; instantiate its tag again to use it",
+                snake_id
+            ),
+        ));
+    }
 
-    // Set enqueued_at timestamp before enqueueing the job
-    game::set_game_enqueued_at(&state.db, game.game_id, chrono::Utc::now())
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to set enqueued_at: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to prepare game".to_string(),
-            )
-        })?;
+    Ok(())
+}
 
-    // Enqueue the game runner job
-    let job = GameRunnerJob {
-        game_id: game.game_id,
-    };
-    cja::jobs::Job::enqueue(job, state, format!("Game {} created via API", game.game_id))
+/// Check that a ruleset exists and was uploaded by the user
+async fn validate_ruleset(
+    state: &AppState,
+    user_id: Uuid,
+    ruleset_id: Uuid,
+) -> Result<(), (StatusCode, String)> {
+    let ruleset = ruleset::get_ruleset_by_id(&state.db, ruleset_id)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to enqueue game runner job: {}", e);
+            tracing::error!("Failed to fetch ruleset: {}", e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to start game".to_string(),
+                "Internal server error".to_string(),
             )
         })?;
-
-    Ok((
-        StatusCode::CREATED,
-        Json(CreateGameResponse {
-            id: game.game_id,
-            status: game.status.as_str().to_string(),
-        }),
-    ))
+    match ruleset {
+        Some(ruleset) if ruleset.user_id == user_id => Ok(()),
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            format!("Ruleset {} not found", ruleset_id),
+        )),
+    }
 }
 
-/// GET /api/games - List games
-pub async fn list_games(
+/// Check that every snake can enter `games` more ranked games: it's opted
+/// in, hasn't used up its ranked games for the day and passes a health check
+async fn validate_ranked_snakes(
+    state: &AppState,
+    snake_ids: &[Uuid],
+    games: i64,
+) -> Result<(), (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to check snakes for a ranked game: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    };
+
+    let mut unique_snake_ids = snake_ids.to_vec();
+    unique_snake_ids.sort();
+    unique_snake_ids.dedup();
+    if unique_snake_ids.len() < 2 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Ranked games need at least two different snakes".to_string(),
+        ));
+    }
+
+    let not_opted_in = battlesnake::get_snakes_not_opted_in(&state.db, &unique_snake_ids)
+        .await
+        .map_err(internal_error)?;
+    if let Some(snake_id) = not_opted_in.first() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Snake {} isn't opted in to ranked games; its owner can opt in with PUT /api/snakes/{}/ranked",
+                snake_id, snake_id
+            ),
+        ));
+    }
+
+    let since = state.clock.now() - chrono::Duration::days(1);
+    for snake_id in &unique_snake_ids {
+        let played = game::count_ranked_games_since(&state.db, *snake_id, since)
+            .await
+            .map_err(internal_error)?;
+        if played + games > RANKED_GAMES_PER_SNAKE_PER_DAY {
+            return Err((
+                StatusCode::TOO_MANY_REQUESTS,
+                format!(
+                    "Snake {} has played its {} ranked games for the day; casual games aren't limited",
+                    snake_id, RANKED_GAMES_PER_SNAKE_PER_DAY
+                ),
+            ));
+        }
+    }
+
+    // Check every snake at once, since each check can take a few seconds
+    let mut urls = Vec::with_capacity(unique_snake_ids.len());
+    for snake_id in &unique_snake_ids {
+        let snake = battlesnake::get_battlesnake_by_id(&state.db, *snake_id)
+            .await
+            .map_err(internal_error)?
+            .ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Snake {} not found or not accessible", snake_id),
+                )
+            })?;
+        urls.push(snake.url);
+    }
+    let diagnoses = futures::future::join_all(urls.iter().map(|url| diagnose_url(url))).await;
+    for (snake_id, diagnosis) in unique_snake_ids.iter().zip(diagnoses) {
+        if let Some(failed) = diagnosis
+            .checks
+            .iter()
+            .find(|check| check.status == CheckStatus::Fail)
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Snake {} failed its health check ({}: {}); ranked games need healthy snakes",
+                    snake_id, failed.name, failed.detail
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that the user and the owner of every snake are members of the league,
+/// and that the league is still running
+async fn validate_league_snakes(
+    state: &AppState,
+    league_id: Uuid,
+    user_id: Uuid,
+    snake_ids: &[Uuid],
+) -> Result<(), (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to check snakes for a league game: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    };
+
+    if !league::is_league_member(&state.db, league_id, user_id)
+        .await
+        .map_err(internal_error)?
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("League {} not found", league_id),
+        ));
+    }
+
+    // The announced standings are final
+    let awards = league::get_league_awards(&state.db, league_id)
+        .await
+        .map_err(internal_error)?;
+    if awards.completed_at.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("League {} is completed", league_id),
+        ));
+    }
+
+    let outside = league::get_snakes_outside_league(&state.db, league_id, snake_ids)
+        .await
+        .map_err(internal_error)?;
+    if let Some(snake_id) = outside.first() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Snake {} belongs to someone who isn't a member of the league",
+                snake_id
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Record the league a game was created in
+async fn set_league(
+    state: &AppState,
+    game_id: Uuid,
+    league_id: Uuid,
+) -> Result<(), (StatusCode, String)> {
+    league::set_game_league(&state.db, game_id, league_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to set game league: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to prepare game".to_string(),
+            )
+        })
+}
+
+/// Create a game with its settings and spawns, and enqueue it to run
+pub(crate) async fn start_game(
+    state: &AppState,
+    create_request: CreateGameWithSnakes,
+    settings: GameSettings,
+    spawn: &SpawnStrategy,
+) -> Result<Game, (StatusCode, String)> {
+    let mut game = prepare_game(state, create_request, settings, spawn).await?;
+    enqueue_game(state, &mut game).await?;
+    Ok(game)
+}
+
+/// Create both games of a mirror round, linked to each other and to their
+/// series and league before either is queued, so the round is complete by the
+/// time either game can finish. Returns the original game, then its mirror.
+pub(crate) async fn start_mirror_round(
+    state: &AppState,
+    create_request: CreateGameWithSnakes,
+    settings: GameSettings,
+    spawn: &SpawnStrategy,
+    series_id: Option<Uuid>,
+    league_id: Option<Uuid>,
+) -> Result<[Game; 2], (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to link mirror game: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to prepare game".to_string(),
+        )
+    };
+
+    let mut games = [
+        prepare_game(state, create_request.clone(), settings, spawn).await?,
+        prepare_game(state, create_request, settings, &mirrored_spawn(spawn)).await?,
+    ];
+    game::set_game_mirror_of(&state.db, games[1].game_id, games[0].game_id)
+        .await
+        .map_err(internal_error)?;
+    for game in &games {
+        if let Some(series_id) = series_id {
+            mirror_series::set_game_mirror_series(&state.db, game.game_id, series_id)
+                .await
+                .map_err(internal_error)?;
+        }
+        if let Some(league_id) = league_id {
+            set_league(state, game.game_id, league_id).await?;
+        }
+    }
+
+    for game in &mut games {
+        enqueue_game(state, game).await?;
+    }
+    Ok(games)
+}
+
+/// Create a game with its settings and spawns, without queueing it
+async fn prepare_game(
+    state: &AppState,
+    create_request: CreateGameWithSnakes,
+    settings: GameSettings,
+    spawn: &SpawnStrategy,
+) -> Result<Game, (StatusCode, String)> {
+    let game = game::create_game_with_snakes(&state.db, create_request)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create game: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to create game".to_string(),
+            )
+        })?;
+
+    if settings != GameSettings::default() {
+        game::set_game_settings(&state.db, game.game_id, settings)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to set game settings: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to prepare game".to_string(),
+                )
+            })?;
+    }
+
+    if *spawn != SpawnStrategy::Random {
+        game::set_game_spawn_strategy(&state.db, game.game_id, spawn)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to set spawn strategy: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to prepare game".to_string(),
+                )
+            })?;
+    }
+
+    Ok(game)
+}
+
+/// Queue a prepared game to run
+async fn enqueue_game(state: &AppState, game: &mut Game) -> Result<(), (StatusCode, String)> {
+    // Set enqueued_at timestamp before enqueueing the job
+    let enqueued_at = state.clock.now();
+    game::set_game_enqueued_at(&state.db, game.game_id, enqueued_at)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to set enqueued_at: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to prepare game".to_string(),
+            )
+        })?;
+    game.enqueued_at = Some(enqueued_at);
+
+    // Enqueue the game runner job
+    let job = GameRunnerJob {
+        game_id: game.game_id,
+    };
+    cja::jobs::Job::enqueue(
+        job,
+        state.clone(),
+        format!("Game {} created via API", game.game_id),
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to enqueue game runner job: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to start game".to_string(),
+        )
+    })?;
+
+    Ok(())
+}
+
+/// A game as the list endpoint reads it
+#[derive(Debug)]
+struct GameListRow {
+    game_id: Uuid,
+    board_size: String,
+    game_type: String,
+    status: String,
+    enqueued_at: Option<chrono::DateTime<chrono::Utc>>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+    ranked: bool,
+}
+
+/// GET /api/games - List games
+pub async fn list_games(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Query(query): Query<ListGamesQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let limit = query.limit.min(MAX_LIST_LIMIT) as i64;
+
+    // A version filter is a filter on its snake too
+    let snake_id = match query.version_id {
+        Some(version_id) => {
+            let version = battlesnake_version::get_version(&state.db, version_id)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to get snake version: {}", e);
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Internal server error".to_string(),
+                    )
+                })?
+                .ok_or((
+                    StatusCode::BAD_REQUEST,
+                    "Version not found or not accessible".to_string(),
+                ))?;
+            if query
+                .snake_id
+                .is_some_and(|snake_id| snake_id != version.battlesnake_id)
+            {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "version_id is not a version of snake_id".to_string(),
+                ));
+            }
+            Some(version.battlesnake_id)
+        }
+        None => query.snake_id,
+    };
+
+    // If filtering by snake_id, validate access first
+    if let Some(snake_id) = snake_id {
+        let accessible = sqlx::query!(
+            r#"
+            SELECT battlesnake_id
+            FROM battlesnakes
+            WHERE battlesnake_id = $1
+              AND (user_id = $2 OR visibility = 'public')
+            "#,
+            snake_id,
+            user.user_id
+        )
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to validate snake: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?;
+
+        if accessible.is_none() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Snake not found or not accessible".to_string(),
+            ));
+        }
+    }
+
+    // List the snake's games, or else the games where the user has a snake playing
+    let rows = sqlx::query_as!(
+        GameListRow,
+        r#"
+        SELECT DISTINCT g.game_id, g.board_size, g.game_type, g.status, g.enqueued_at,
+            g.created_at, g.updated_at, g.ranked
+        FROM games g
+        JOIN game_battlesnakes gb ON g.game_id = gb.game_id
+        JOIN battlesnakes b ON gb.battlesnake_id = b.battlesnake_id
+        WHERE (gb.battlesnake_id = $1 OR ($1::UUID IS NULL AND b.user_id = $2))
+          AND ($3::BOOLEAN IS NULL OR g.ranked = $3)
+          AND ($5::UUID IS NULL OR gb.battlesnake_version_id = $5)
+        ORDER BY g.created_at DESC
+        LIMIT $4
+        "#,
+        snake_id,
+        user.user_id,
+        query.ranked,
+        limit,
+        query.version_id
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to list games: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    })?;
+
+    let games: Vec<(Game, bool)> = rows
+        .into_iter()
+        .filter_map(|row| {
+            let board_size = GameBoardSize::from_str(&row.board_size).ok()?;
+            let game_type = GameType::from_str(&row.game_type).ok()?;
+            let status = GameStatus::from_str(&row.status).ok()?;
+            let game = Game {
+                game_id: row.game_id,
+                board_size,
+                game_type,
+                status,
+                enqueued_at: row.enqueued_at,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            };
+            Some((game, row.ranked))
+        })
+        .collect();
+
+    // Fetch battlesnakes for each game
+    let mut response: Vec<GameListItem> = Vec::with_capacity(games.len());
+    for (game, ranked) in &games {
+        let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game.game_id)
+            .await
+            .map_err(|e| {
+                tracing::error!(
+                    "Failed to get battlesnakes for game {}: {}",
+                    game.game_id,
+                    e
+                );
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_string(),
+                )
+            })?;
+        response.push(build_game_list_item(game, *ranked, &battlesnakes));
+    }
+
+    Ok(Json(response))
+}
+
+/// Query parameters for predicting a game
+#[derive(Debug, Deserialize)]
+pub struct PredictQuery {
+    /// Comma-separated snake IDs, one per entry (1-4; repeats allowed)
+    pub snakes: String,
+}
+
+/// One entry's predicted finish
+#[derive(Debug, Serialize)]
+pub struct PlacementPrediction {
+    pub snake_id: Uuid,
+    pub rating: f64,
+    pub win_probability: f64,
+    /// Chance of finishing 1st, 2nd, ... in order
+    pub placement_probabilities: Vec<f64>,
+}
+
+/// Response format for GET /api/games/predict
+#[derive(Debug, Serialize)]
+pub struct PredictResponse {
+    pub snakes: Vec<PlacementPrediction>,
+}
+
+/// Parse a comma-separated list of 1-4 snake IDs
+fn parse_snake_list(s: &str) -> Result<Vec<Uuid>, String> {
+    let snakes = s
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| Uuid::parse_str(id).map_err(|_| format!("Invalid snake ID: {}", id)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if snakes.is_empty() || snakes.len() > MAX_GAME_SNAKES {
+        return Err(format!(
+            "snakes must list between 1 and {} snake IDs",
+            MAX_GAME_SNAKES
+        ));
+    }
+    Ok(snakes)
+}
+
+/// GET /api/games/predict - Each snake's chance of each placement, from current ratings
+pub async fn predict_game(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Query(query): Query<PredictQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let snakes = parse_snake_list(&query.snakes).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    validate_accessible_snakes(&state, user.user_id, &snakes).await?;
+
+    let ratings = ratings::current_ratings(&state.db).await.map_err(|e| {
+        tracing::error!("Failed to get ratings: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    })?;
+    let entry_ratings: Vec<f64> = snakes
+        .iter()
+        .map(|id| ratings.get(id).map_or(INITIAL_RATING, |r| r.rating))
+        .collect();
+    let probabilities = placement_probabilities(&entry_ratings);
+
+    Ok(Json(PredictResponse {
+        snakes: snakes
+            .into_iter()
+            .zip(entry_ratings)
+            .zip(probabilities)
+            .map(
+                |((snake_id, rating), placement_probabilities)| PlacementPrediction {
+                    snake_id,
+                    rating,
+                    win_probability: placement_probabilities[0],
+                    placement_probabilities,
+                },
+            )
+            .collect(),
+    }))
+}
+
+/// GET /api/games/{id}/details - Show game details with frames, all of them
+/// or a page from `?from_turn=` and `?limit=`
+pub async fn show_game(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(game_id): Path<Uuid>,
+    Query(query): Query<GameDetailsQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    // Fetch the game
+    let game = game::get_game_by_id(&state.db, game_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get game: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Game not found".to_string()))?;
+
+    // Fetch battlesnakes
+    let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get battlesnakes: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?;
+
+    ensure_can_view(&state, game_id, &battlesnakes, user.user_id).await?;
+
+    let (frames, turn_count, next_from_turn, certification) = match query.page() {
+        Some((from_turn, limit)) => {
+            let turns = turn_archive::load_turns_page(&state, game_id, from_turn, limit)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to get turns: {}", e);
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Internal server error".to_string(),
+                    )
+                })?;
+            let turn_count = turn_archive::load_turn_at(&state, game_id, None)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to get latest turn: {}", e);
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Internal server error".to_string(),
+                    )
+                })?
+                .map_or(0, |t| t.turn_number + 1);
+            let next_from_turn = next_page_turn(turns.last().map(|t| t.turn_number), turn_count);
+            let frames = turns.into_iter().filter_map(|t| t.frame_data).collect();
+            (frames, turn_count, next_from_turn, None)
+        }
+        None => {
+            // Fetch all turns
+            let turns = turn_archive::load_turns(&state, game_id)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to get turns: {}", e);
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Internal server error".to_string(),
+                    )
+                })?;
+            let turn_count = turns
+                .iter()
+                .rev()
+                .find(|t| t.frame_data.is_some())
+                .map_or(0, |t| t.turn_number + 1);
+
+            // Extract frames from turns
+            let frames: Vec<serde_json::Value> =
+                turns.into_iter().filter_map(|t| t.frame_data).collect();
+
+            // Check the frames against the digest certified when the game finished
+            let digest = game::get_game_result_digest(&state.db, game_id)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to get result digest: {}", e);
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Internal server error".to_string(),
+                    )
+                })?;
+            let computed_digest = chain_digest(game_id, &frames);
+            let certification = CertificationResponse {
+                status: VerificationStatus::check(digest.as_deref(), &computed_digest),
+                digest,
+                computed_digest,
+            };
+            (frames, turn_count, None, Some(certification))
+        }
+    };
+
+    let (winner, draw) = game_winner(&battlesnakes);
+
+    let snakes: Vec<SnakeInfo> = build_snake_infos(&battlesnakes);
+
+    let branch = game::get_game_branch_origin(&state.db, game_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get branch origin: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?
+        .map(|origin| BranchOriginResponse {
+            parent_id: origin.parent_game_id,
+            branched_from_turn: origin.branched_from_turn,
+        });
+
+    let mirror = get_mirror_round(&state.db, game_id).await.map_err(|e| {
+        tracing::error!("Failed to get mirror match: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    })?;
+    let series = get_series_results(&state.db, game_id).await.map_err(|e| {
+        tracing::error!("Failed to get mirror series: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    })?;
+
+    let tiebreak_applied = game::get_game_tiebreak_applied(&state.db, game_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get tiebreak: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?
+        .map(|tiebreak| tiebreak.as_str().to_string());
+
+    let queue = queue_estimate(&state, &game).await.map_err(|e| {
+        tracing::error!("Failed to estimate queue position: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    })?;
+
+    // Games from before seeding report the seed derived from their ID
+    let settings = game::get_game_settings(&state.db, game_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get game settings: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?;
+    let seed = engine_seed(&game_id.to_string(), settings.seed) as i64;
+
+    let retry_count = get_retry_count(&state.db, game_id).await.map_err(|e| {
+        tracing::error!("Failed to get retry count: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    })?;
+
+    Ok(Json(GameResponse {
+        id: game.game_id,
+        status: game.status.as_str().to_string(),
+        winner,
+        draw,
+        snakes,
+        frames,
+        turn_count,
+        next_from_turn,
+        board: game.board_size.as_str().to_string(),
+        game_type: game.game_type.as_str().to_string(),
+        created_at: game.created_at,
+        certification,
+        branch,
+        mirror,
+        series,
+        tiebreak_applied,
+        queue,
+        seed,
+        ranked: settings.ranked,
+        duel: settings.duel,
+        max_turns: settings.turn_limit(),
+        move_timeout_ms: settings.move_timeout_ms(),
+        retry_count,
+    }))
+}
+
+/// Frames read per query while streaming NDJSON
+const NDJSON_PAGE_SIZE: i64 = 100;
+
+/// A game's frames as NDJSON lines, fetched a page of turns at a time
+fn ndjson_frames(
+    state: AppState,
+    game_id: Uuid,
+) -> impl futures::Stream<Item = Result<String, std::io::Error>> {
+    // The next turn number to fetch, None once every turn has been sent
+    futures::stream::unfold(Some(0), move |from_turn| {
+        let state = state.clone();
+        async move {
+            let from_turn = from_turn?;
+            match turn_archive::load_turns_page(&state, game_id, from_turn, NDJSON_PAGE_SIZE).await
+            {
+                Ok(turns) if turns.is_empty() => None,
+                Ok(turns) => {
+                    let next = if turns.len() as i64 == NDJSON_PAGE_SIZE {
+                        turns.last().map(|t| t.turn_number + 1)
+                    } else {
+                        None
+                    };
+                    let lines: String = turns
+                        .into_iter()
+                        .filter_map(|t| t.frame_data)
+                        .map(|frame| format!("{}\n", frame))
+                        .collect();
+                    Some((Ok(lines), next))
+                }
+                Err(e) => {
+                    // Headers are already sent, so all we can do is cut the stream short
+                    tracing::error!("Failed to stream frames for game {}: {:?}", game_id, e);
+                    Some((Err(std::io::Error::other(e.to_string())), None))
+                }
+            }
+        }
+    })
+}
+
+/// GET /api/games/{id}/frames.ndjson - A game's frames, one JSON object per line
+///
+/// Streamed with chunked transfer encoding, so long games can be processed
+/// frame by frame instead of as one large JSON array.
+pub async fn game_frames_ndjson(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(game_id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to load game frames: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    };
+
+    game::get_game_by_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Game not found".to_string()))?;
+
+    let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?;
+    ensure_can_view(&state, game_id, &battlesnakes, user.user_id).await?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(ndjson_frames(state, game_id)),
+    ))
+}
+
+/// Query parameters for GET /api/games/diff
+#[derive(Debug, Deserialize)]
+pub struct GameDiffQuery {
+    pub game_a: Uuid,
+    pub game_b: Uuid,
+}
+
+/// GET /api/games/diff?game_a=X&game_b=Y - Compare two games turn by turn
+///
+/// See [`crate::game_diff`]. The caller must be able to view both games.
+pub async fn diff_games(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Query(query): Query<GameDiffQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to diff games: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    };
+
+    for game_id in [query.game_a, query.game_b] {
+        game::get_game_by_id(&state.db, game_id)
+            .await
+            .map_err(internal_error)?
+            .ok_or((StatusCode::NOT_FOUND, format!("Game {} not found", game_id)))?;
+
+        let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game_id)
+            .await
+            .map_err(internal_error)?;
+        ensure_can_view(&state, game_id, &battlesnakes, user.user_id).await?;
+    }
+
+    let diff = game_diff::diff_games(&state, query.game_a, query.game_b)
+        .await
+        .map_err(|e| match e {
+            DiffError::Invalid(message) => (StatusCode::BAD_REQUEST, message),
+            DiffError::Internal(e) => internal_error(e),
+        })?;
+
+    Ok(Json(diff))
+}
+
+/// GET /api/games/{id}/notation - A finished game in text notation
+///
+/// See [`crate::notation`] for the format. Only games the engine replays to
+/// their recorded result can be written out, since the annotations come from
+/// replaying them.
+pub async fn game_notation(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(game_id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to write game notation: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    };
+
+    let game = game::get_game_by_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Game not found".to_string()))?;
+
+    let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?;
+    ensure_can_view(&state, game_id, &battlesnakes, user.user_id).await?;
+
+    if game.status != GameStatus::Finished {
+        return Err((
+            StatusCode::CONFLICT,
+            "Only finished games can be written as notation".to_string(),
+        ));
+    }
+
+    let fixture = replay_fixture::record(&state, game_id).await.map_err(|e| {
+        tracing::warn!(game_id = %game_id, error = ?e, "Game can't be written as notation");
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("This game can't be written as notation: {}", e),
+        )
+    })?;
+    let text = notation::to_notation(&fixture).map_err(internal_error)?;
+
+    Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], text))
+}
+
+/// GET /api/games/{id}/export - A finished game and all its frames as one JSON document
+///
+/// In the engine's format, so the board's offline replay importer can load
+/// it. See [`crate::game_export`].
+pub async fn game_export(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(game_id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to export game: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    };
+
+    let game = game::get_game_by_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Game not found".to_string()))?;
+
+    let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?;
+    ensure_can_view(&state, game_id, &battlesnakes, user.user_id).await?;
+
+    if game.status != GameStatus::Finished {
+        return Err((
+            StatusCode::CONFLICT,
+            "Only finished games can be exported".to_string(),
+        ));
+    }
+
+    let settings = game::get_game_settings(&state.db, game_id)
+        .await
+        .map_err(internal_error)?;
+    let frames = turn_archive::load_turns(&state, game_id)
+        .await
+        .map_err(internal_error)?
+        .into_iter()
+        .filter_map(|t| t.frame_data)
+        .collect();
+    let export = game_export::build_export(&game, &settings, frames, state.clock.now())
+        .map_err(internal_error)?;
+
+    Ok((
+        [(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"game-{}.json\"", game_id),
+        )],
+        Json(export),
+    ))
+}
+
+/// One turn's board in words
+#[derive(Debug, Serialize)]
+pub struct TurnDescription {
+    pub turn: i32,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GameDescriptionsResponse {
+    pub game_id: Uuid,
+    pub turns: Vec<TurnDescription>,
+}
+
+/// GET /api/games/{id}/descriptions - A text description of every stored turn
+///
+/// For screen readers, which can't see the board viewer. See
+/// [`crate::board_description`].
+pub async fn game_descriptions(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(game_id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to describe game: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    };
+
+    game::get_game_by_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Game not found".to_string()))?;
+
+    let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?;
+    ensure_can_view(&state, game_id, &battlesnakes, user.user_id).await?;
+
+    let turns = turn_archive::load_turns(&state, game_id)
+        .await
+        .map_err(internal_error)?
+        .into_iter()
+        .filter_map(|t| t.frame_data)
+        .map(|frame| {
+            let frame: EngineGameFrame = serde_json::from_value(frame)
+                .map_err(|e| internal_error(cja::color_eyre::Report::new(e)))?;
+            Ok(TurnDescription {
+                turn: frame.turn,
+                description: describe_frame(&frame),
+            })
+        })
+        .collect::<Result<Vec<_>, (StatusCode, String)>>()?;
+
+    Ok(Json(GameDescriptionsResponse { game_id, turns }))
+}
+
+/// POST /api/games/{id}/branches - Simulate a "what if" branch of a finished game
+///
+/// Replays the game from `turn` with the given moves changed and returns the
+/// branch once it has finished simulating.
+pub async fn create_branch(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(game_id): Path<Uuid>,
+    Json(request): Json<BranchRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to branch game: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    };
+
+    game::get_game_by_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Game not found".to_string()))?;
+
+    let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?;
+    ensure_can_view(&state, game_id, &battlesnakes, user.user_id).await?;
+
+    let branch_id = branch::create_branch(&state, user.user_id, game_id, &request)
+        .await
+        .map_err(|e| match e {
+            BranchError::Invalid(message) => (StatusCode::BAD_REQUEST, message),
+            BranchError::RateLimited(message) => (StatusCode::TOO_MANY_REQUESTS, message),
+            BranchError::Internal(e) => internal_error(e),
+        })?;
+    let branch = game::get_game_by_id(&state.db, branch_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Branch not found".to_string()))?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(BranchResponse {
+            id: branch.game_id,
+            parent_id: game_id,
+            branched_from_turn: request.turn,
+            status: branch.status.as_str().to_string(),
+            created_at: branch.created_at,
+        }),
+    ))
+}
+
+/// GET /api/games/{id}/branches - A game's "what if" branches, oldest first
+pub async fn list_branches(
     State(state): State<AppState>,
     ApiUser(user): ApiUser,
-    Query(query): Query<ListGamesQuery>,
+    Path(game_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let limit = query.limit.min(100) as i64;
-
-    // If filtering by snake_id, validate access first
-    if let Some(snake_id) = query.snake_id {
-        let accessible = sqlx::query!(
-            r#"
-            SELECT battlesnake_id
-            FROM battlesnakes
-            WHERE battlesnake_id = $1
-              AND (user_id = $2 OR visibility = 'public')
-            "#,
-            snake_id,
-            user.user_id
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to list game branches: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
         )
-        .fetch_optional(&state.db)
+    };
+
+    game::get_game_by_id(&state.db, game_id)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to validate snake: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Internal server error".to_string(),
-            )
-        })?;
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Game not found".to_string()))?;
 
-        if accessible.is_none() {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                "Snake not found or not accessible".to_string(),
-            ));
-        }
-    }
+    let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?;
+    ensure_can_view(&state, game_id, &battlesnakes, user.user_id).await?;
 
-    // Build query based on whether we're filtering by snake
-    let games: Vec<Game> = if let Some(snake_id) = query.snake_id {
-        // Filter by specific snake
-        let rows = sqlx::query!(
-            r#"
-            SELECT DISTINCT g.game_id, g.board_size, g.game_type, g.status, g.enqueued_at, g.created_at, g.updated_at
-            FROM games g
-            JOIN game_battlesnakes gb ON g.game_id = gb.game_id
-            WHERE gb.battlesnake_id = $1
-            ORDER BY g.created_at DESC
-            LIMIT $2
-            "#,
-            snake_id,
-            limit
-        )
-        .fetch_all(&state.db)
+    let branches: Vec<BranchResponse> = game::get_game_branches(&state.db, game_id)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to list games: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
-        })?;
+        .map_err(internal_error)?
+        .into_iter()
+        .map(|b| BranchResponse {
+            id: b.game_id,
+            parent_id: game_id,
+            branched_from_turn: b.branched_from_turn,
+            status: b.status,
+            created_at: b.created_at,
+        })
+        .collect();
 
-        rows.into_iter()
-            .filter_map(|row| {
-                let board_size = GameBoardSize::from_str(&row.board_size).ok()?;
-                let game_type = GameType::from_str(&row.game_type).ok()?;
-                let status = GameStatus::from_str(&row.status).ok()?;
-                Some(Game {
-                    game_id: row.game_id,
-                    board_size,
-                    game_type,
-                    status,
-                    enqueued_at: row.enqueued_at,
-                    created_at: row.created_at,
-                    updated_at: row.updated_at,
-                })
-            })
-            .collect()
-    } else {
-        // List games where user has a snake participating
-        let rows = sqlx::query!(
-            r#"
-            SELECT DISTINCT g.game_id, g.board_size, g.game_type, g.status, g.enqueued_at, g.created_at, g.updated_at
-            FROM games g
-            JOIN game_battlesnakes gb ON g.game_id = gb.game_id
-            JOIN battlesnakes b ON gb.battlesnake_id = b.battlesnake_id
-            WHERE b.user_id = $1
-            ORDER BY g.created_at DESC
-            LIMIT $2
-            "#,
-            user.user_id,
-            limit
+    Ok(Json(branches))
+}
+
+/// GET /api/games/{id}/debug - Every snake's move per turn, with latency and parse failures
+pub async fn debug_game(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(game_id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to load game debug info: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
         )
-        .fetch_all(&state.db)
+    };
+
+    let game = game::get_game_by_id(&state.db, game_id)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to list games: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
-        })?;
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Game not found".to_string()))?;
 
-        rows.into_iter()
-            .filter_map(|row| {
-                let board_size = GameBoardSize::from_str(&row.board_size).ok()?;
-                let game_type = GameType::from_str(&row.game_type).ok()?;
-                let status = GameStatus::from_str(&row.status).ok()?;
-                Some(Game {
-                    game_id: row.game_id,
-                    board_size,
-                    game_type,
-                    status,
-                    enqueued_at: row.enqueued_at,
-                    created_at: row.created_at,
-                    updated_at: row.updated_at,
-                })
-            })
-            .collect()
+    let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?;
+    ensure_can_view(&state, game_id, &battlesnakes, user.user_id).await?;
+
+    let settings = game::get_game_settings(&state.db, game_id)
+        .await
+        .map_err(internal_error)?;
+
+    let moves: Vec<SnakeMoveDebug> = turn_archive::load_snake_turns(&state, game_id)
+        .await
+        .map_err(internal_error)?
+        .into_iter()
+        .map(|st| SnakeMoveDebug {
+            turn: st.turn_number,
+            snake: st.game_battlesnake_id,
+            direction: st.direction,
+            latency_ms: st.latency_ms,
+            compensated_latency_ms: st.compensated_latency_ms,
+            timed_out: st.timed_out,
+            parse_error: st.parse_error,
+        })
+        .collect();
+
+    Ok(Json(GameDebugResponse {
+        id: game.game_id,
+        validation_mode: settings.validation_mode.as_str().to_string(),
+        latency_compensation: settings.latency_compensation,
+        parse_failures: moves.iter().filter(|m| m.parse_error.is_some()).count(),
+        moves,
+    }))
+}
+
+/// GET /api/games/{id}/timing - Where the game runner's time went, stage by stage
+///
+/// Recorded when the game finishes; 404 until then.
+pub async fn game_timing(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(game_id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to load game timing: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
     };
 
-    // Fetch battlesnakes for each game
-    let mut response: Vec<GameListItem> = Vec::with_capacity(games.len());
-    for game in &games {
-        let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game.game_id)
-            .await
-            .map_err(|e| {
-                tracing::error!(
-                    "Failed to get battlesnakes for game {}: {}",
-                    game.game_id,
-                    e
-                );
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Internal server error".to_string(),
-                )
-            })?;
-        response.push(build_game_list_item(game, &battlesnakes));
+    game::get_game_by_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Game not found".to_string()))?;
+
+    let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?;
+    ensure_can_view(&state, game_id, &battlesnakes, user.user_id).await?;
+
+    let timings = get_game_stage_timings(&state.db, game_id)
+        .await
+        .map_err(internal_error)?;
+    if timings.is_empty() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "No timings recorded for this game; they're saved when it finishes".to_string(),
+        ));
     }
 
-    Ok(Json(response))
+    Ok(Json(TimingReport::new(&timings)))
 }
 
-/// GET /api/games/{id}/details - Show game details with frames
-pub async fn show_game(
+/// GET /api/games/{id}/log - The game runner's log, for owners of the game's snakes
+///
+/// Returned as a plain text download, one entry per line.
+pub async fn game_log(
     State(state): State<AppState>,
-    ApiUser(_user): ApiUser,
+    ApiUser(user): ApiUser,
     Path(game_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    // Fetch the game
-    let game = game::get_game_by_id(&state.db, game_id)
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to load game log: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    };
+
+    game::get_game_by_id(&state.db, game_id)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to get game: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Internal server error".to_string(),
-            )
-        })?
+        .map_err(internal_error)?
         .ok_or((StatusCode::NOT_FOUND, "Game not found".to_string()))?;
 
-    // Fetch battlesnakes
     let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game_id)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to get battlesnakes: {}", e);
+        .map_err(internal_error)?;
+
+    if !battlesnakes.iter().any(|bs| bs.user_id == user.user_id) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only owners of snakes in this game can view its log".to_string(),
+        ));
+    }
+
+    let entries = game_log::get_game_log_entries(&state.db, game_id)
+        .await
+        .map_err(internal_error)?;
+
+    Ok((
+        [
             (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Internal server error".to_string(),
-            )
-        })?;
+                header::CONTENT_TYPE,
+                "text/plain; charset=utf-8".to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"game-{}.log\"", game_id),
+            ),
+        ],
+        crate::game_log::format_game_log(&entries),
+    ))
+}
+
+/// Response for issuing a stream ticket
+#[derive(Debug, Serialize)]
+pub struct StreamTicketResponse {
+    pub ticket: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    /// Engine URL for the board viewer that carries the ticket in its path
+    pub board_viewer_engine: String,
+}
+
+/// POST /api/games/{id}/stream-ticket - A short-lived ticket for streaming a game's frames
+///
+/// For clients that can't authenticate the WebSocket upgrade with a header or
+/// cookie. Pass it as `?ticket=` to `/api/games/{id}/events`, or give the board
+/// viewer `board_viewer_engine` as its engine URL.
+pub async fn create_stream_ticket(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(game_id): Path<Uuid>,
+    public_url: PublicUrl,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to create stream ticket: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    };
+
+    game::get_game_by_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Game not found".to_string()))?;
+
+    let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game_id)
+        .await
+        .map_err(internal_error)?;
+    ensure_can_view(&state, game_id, &battlesnakes, user.user_id).await?;
+
+    let ticket = create_game_stream_ticket(&state.db, game_id, user.user_id, state.clock.now())
+        .await
+        .map_err(internal_error)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(StreamTicketResponse {
+            board_viewer_engine: public_url.url(&format!("/api/v1/viewer/{}", ticket.ticket)),
+            ticket: ticket.ticket,
+            expires_at: ticket.expires_at,
+        }),
+    ))
+}
 
-    // Fetch all turns
-    let turns = turn::get_turns_by_game_id(&state.db, game_id)
+/// Reject users who don't own a snake in a private game
+pub(crate) async fn ensure_can_view(
+    state: &AppState,
+    game_id: Uuid,
+    battlesnakes: &[GameBattlesnakeWithDetails],
+    user_id: Uuid,
+) -> Result<(), (StatusCode, String)> {
+    let settings = game::get_game_settings(&state.db, game_id)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to get turns: {}", e);
+            tracing::error!("Failed to get game settings: {}", e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal server error".to_string(),
             )
         })?;
 
-    // Extract frames from turns
-    let frames: Vec<serde_json::Value> = turns.into_iter().filter_map(|t| t.frame_data).collect();
-
-    // Find winner
-    let winner = battlesnakes
-        .iter()
-        .find(|b| b.placement == Some(1))
-        .map(|b| b.battlesnake_id);
-
-    let snakes: Vec<SnakeInfo> = battlesnakes.iter().map(SnakeInfo::from).collect();
+    let owner_ids: Vec<Uuid> = battlesnakes.iter().map(|bs| bs.user_id).collect();
+    if !can_view_game(settings.visibility, &owner_ids, Some(user_id)) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only owners of snakes in this private game can view it".to_string(),
+        ));
+    }
 
-    Ok(Json(GameResponse {
-        id: game.game_id,
-        status: game.status.as_str().to_string(),
-        winner,
-        snakes,
-        frames,
-        board: game.board_size.as_str().to_string(),
-        game_type: game.game_type.as_str().to_string(),
-        created_at: game.created_at,
-    }))
+    Ok(())
 }
 
 // Import FromStr for parsing enums
@@ -470,6 +2360,35 @@ use std::str::FromStr;
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_self_play() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        assert!(game::is_self_play(&[a, a]));
+        assert!(game::is_self_play(&[a, a, a, a]));
+        assert!(!game::is_self_play(&[a, b]));
+        assert!(!game::is_self_play(&[a, a, b]));
+        // A snake on its own isn't playing anyone
+        assert!(!game::is_self_play(&[a]));
+    }
+
+    #[test]
+    fn test_validate_duel() {
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        assert!(game::validate_duel(&[a, b], GameBoardSize::Medium).is_ok());
+        assert!(game::validate_duel(&[a, b], GameBoardSize::Small).is_ok());
+
+        assert!(game::validate_duel(&[a], GameBoardSize::Medium).is_err());
+        assert!(game::validate_duel(&[a, b, c], GameBoardSize::Medium).is_err());
+        assert!(game::validate_duel(&[a, a], GameBoardSize::Medium).is_err());
+        assert!(game::validate_duel(&[a, b], GameBoardSize::Large).is_err());
+
+        // Every board a duel can ask for is one it can be played on
+        for board in DUEL_BOARD_SIZES {
+            let board = parse_board_size(board).unwrap();
+            assert!(game::validate_duel(&[a, b], board).is_ok());
+        }
+    }
+
     #[test]
     fn test_parse_game_type() {
         // Standard cases
@@ -511,10 +2430,49 @@ mod tests {
             Ok(GameType::SnailMode)
         ));
 
+        // Wrapped
+        assert!(matches!(parse_game_type("wrapped"), Ok(GameType::Wrapped)));
+        assert!(matches!(parse_game_type("Wrapped"), Ok(GameType::Wrapped)));
+
         // Invalid
         assert!(parse_game_type("invalid").is_err());
     }
 
+    #[test]
+    fn test_listed_options_parse() {
+        // /api/capabilities lists these, so every one has to be accepted
+        for name in GAME_TYPES {
+            assert!(parse_game_type(name).is_ok(), "{}", name);
+        }
+        for name in BOARD_SIZES {
+            assert!(parse_board_size(name).is_ok(), "{}", name);
+        }
+        for name in VALIDATION_MODES {
+            assert!(parse_validation_mode(name).is_ok(), "{}", name);
+        }
+        for name in VISIBILITIES {
+            assert!(Visibility::from_str(name).is_ok(), "{}", name);
+        }
+        for name in TURN_LIMIT_TIEBREAKS {
+            assert!(parse_turn_limit_tiebreak(name).is_ok(), "{}", name);
+        }
+        for name in DEAD_SNAKES {
+            assert!(parse_dead_snakes(name).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_parse_snake_list() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(
+            parse_snake_list(&format!("{a}, {b},{a}")).unwrap(),
+            vec![a, b, a]
+        );
+        assert!(parse_snake_list("").is_err());
+        assert!(parse_snake_list("not-a-uuid").is_err());
+        assert!(parse_snake_list(&[a; 5].map(|id| id.to_string()).join(",")).is_err());
+    }
+
     #[test]
     fn test_parse_board_size() {
         assert!(matches!(parse_board_size("7x7"), Ok(GameBoardSize::Small)));
@@ -536,20 +2494,163 @@ mod tests {
     fn test_create_game_request_defaults() {
         let json = r#"{"snakes": ["550e8400-e29b-41d4-a716-446655440000"]}"#;
         let request: CreateGameRequest = serde_json::from_str(json).unwrap();
-        assert_eq!(request.board, "11x11");
-        assert_eq!(request.game_type, "standard");
+        // Left to the user's preferences
+        assert_eq!(request.board, None);
+        assert_eq!(request.game_type, None);
+        assert_eq!(request.validation_mode, "lenient");
+        assert!(!request.latency_compensation);
+        assert_eq!(request.spawn, "random");
+        assert!(!request.mirror);
+        assert_eq!(request.best_of, None);
+        assert_eq!(request.turn_limit_tiebreak, "longest");
+        assert_eq!(request.dead_snakes, "drop");
+    }
+
+    #[test]
+    fn test_batch_request_games() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let batch = |json: serde_json::Value| {
+            serde_json::from_value::<CreateGameBatchRequest>(json)
+                .unwrap()
+                .games()
+        };
+
+        let games = batch(serde_json::json!({"matchups": [[a, b], [b, a, a]]})).unwrap();
+        assert_eq!(games, vec![vec![a, b], vec![b, a, a]]);
+
+        let games = batch(serde_json::json!({"snakes": [a, b], "count": 3})).unwrap();
+        assert_eq!(games, vec![vec![a, b]; 3]);
+
+        // One form or the other
+        assert!(batch(serde_json::json!({})).is_err());
+        assert!(batch(serde_json::json!({"snakes": [a, b]})).is_err());
+        assert!(
+            batch(serde_json::json!({"matchups": [[a, b]], "snakes": [a], "count": 1})).is_err()
+        );
+
+        // Between 1 and 100 games of 1-4 snakes
+        assert!(batch(serde_json::json!({"snakes": [a], "count": 0})).is_err());
+        assert!(batch(serde_json::json!({"snakes": [a], "count": 100})).is_ok());
+        assert!(batch(serde_json::json!({"snakes": [a], "count": 101})).is_err());
+        assert!(batch(serde_json::json!({"matchups": [[a, b], []]})).is_err());
+        assert!(batch(serde_json::json!({"matchups": [[a, a, a, a, b]]})).is_err());
+    }
+
+    #[test]
+    fn test_parse_spawn_strategy() {
+        let request = |json: serde_json::Value| -> CreateGameRequest {
+            serde_json::from_value(json).unwrap()
+        };
+        let snakes = ["550e8400-e29b-41d4-a716-446655440000"; 2];
+
+        assert_eq!(
+            parse_spawn_strategy(
+                &request(serde_json::json!({"snakes": snakes})),
+                GameBoardSize::Small
+            ),
+            Ok(SpawnStrategy::Random)
+        );
+        assert_eq!(
+            parse_spawn_strategy(
+                &request(
+                    serde_json::json!({"snakes": snakes, "spawn": "Fixed", "spawn_rotation": 1})
+                ),
+                GameBoardSize::Small
+            ),
+            Ok(SpawnStrategy::Fixed { rotation: 1 })
+        );
+        assert_eq!(
+            parse_spawn_strategy(
+                &request(serde_json::json!({
+                    "snakes": snakes,
+                    "spawn": "manual",
+                    "spawn_positions": [{"x": 0, "y": 0}, {"x": 6, "y": 6}]
+                })),
+                GameBoardSize::Small
+            ),
+            Ok(SpawnStrategy::Manual(vec![
+                SpawnPoint { x: 0, y: 0 },
+                SpawnPoint { x: 6, y: 6 }
+            ]))
+        );
+
+        // Manual positions must fit the board and the snakes
+        assert!(
+            parse_spawn_strategy(
+                &request(serde_json::json!({
+                    "snakes": snakes,
+                    "spawn": "manual",
+                    "spawn_positions": [{"x": 0, "y": 0}, {"x": 7, "y": 6}]
+                })),
+                GameBoardSize::Small
+            )
+            .is_err()
+        );
+        assert!(
+            parse_spawn_strategy(
+                &request(serde_json::json!({"snakes": snakes, "spawn": "manual"})),
+                GameBoardSize::Small
+            )
+            .is_err()
+        );
+        assert!(
+            parse_spawn_strategy(
+                &request(serde_json::json!({"snakes": snakes, "spawn": "scattered"})),
+                GameBoardSize::Small
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_turn_limit_tiebreak() {
+        assert_eq!(
+            parse_turn_limit_tiebreak("longest"),
+            Ok(TurnLimitTiebreak::Longest)
+        );
+        assert_eq!(
+            parse_turn_limit_tiebreak("Most_Food"),
+            Ok(TurnLimitTiebreak::MostFood)
+        );
+        assert_eq!(
+            parse_turn_limit_tiebreak("draw"),
+            Ok(TurnLimitTiebreak::Draw)
+        );
+        assert!(parse_turn_limit_tiebreak("coin flip").is_err());
+    }
+
+    #[test]
+    fn test_parse_dead_snakes() {
+        assert_eq!(parse_dead_snakes("drop"), Ok(DeadSnakes::Drop));
+        assert_eq!(parse_dead_snakes("Ghost"), Ok(DeadSnakes::Ghost));
+        assert!(parse_dead_snakes("keep").is_err());
+    }
+
+    #[test]
+    fn test_parse_validation_mode() {
+        assert!(matches!(
+            parse_validation_mode("strict"),
+            Ok(MoveValidationMode::Strict)
+        ));
+        assert!(matches!(
+            parse_validation_mode("Lenient"),
+            Ok(MoveValidationMode::Lenient)
+        ));
+        assert!(parse_validation_mode("loose").is_err());
     }
 
     #[test]
     fn test_snake_info_serialization() {
         let snake = SnakeInfo {
             id: Uuid::parse_str("550e8400-e29b-41d4-a716-446655440000").unwrap(),
+            instance_id: "550e8400-e29b-41d4-a716-446655440000-1".to_string(),
             name: "Test Snake".to_string(),
             url: "http://example.com".to_string(),
         };
 
         let json = serde_json::to_string(&snake).unwrap();
         assert!(json.contains("\"id\":"));
+        assert!(json.contains("\"instance_id\":\"550e8400-e29b-41d4-a716-446655440000-1\""));
         assert!(json.contains("\"name\":\"Test Snake\""));
         assert!(json.contains("\"url\":\"http://example.com\""));
     }
@@ -560,18 +2661,69 @@ mod tests {
             id: Uuid::parse_str("550e8400-e29b-41d4-a716-446655440000").unwrap(),
             status: "waiting".to_string(),
             winner: None,
+            draw: false,
             snakes: vec![],
             frames: vec![],
+            turn_count: 0,
+            next_from_turn: None,
             board: "11x11".to_string(),
             game_type: "Standard".to_string(),
             created_at: chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
                 .unwrap()
                 .with_timezone(&chrono::Utc),
+            certification: Some(CertificationResponse {
+                digest: None,
+                computed_digest: "abc123".to_string(),
+                status: VerificationStatus::Uncertified,
+            }),
+            branch: Some(BranchOriginResponse {
+                parent_id: Uuid::parse_str("550e8400-e29b-41d4-a716-446655440001").unwrap(),
+                branched_from_turn: 12,
+            }),
+            mirror: None,
+            series: None,
+            tiebreak_applied: Some("longest".to_string()),
+            queue: None,
+            seed: 42,
+            ranked: true,
+            duel: false,
+            max_turns: 300,
+            move_timeout_ms: 250,
+            retry_count: 1,
         };
 
         let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains("\"branched_from_turn\":12"));
+        assert!(json.contains("\"retry_count\":1"));
+        assert!(json.contains("\"tiebreak_applied\":\"longest\""));
+        assert!(json.contains("\"seed\":42"));
+        assert!(json.contains("\"max_turns\":300"));
+        assert!(json.contains("\"move_timeout_ms\":250"));
         assert!(json.contains("\"status\":\"waiting\""));
         assert!(json.contains("\"board\":\"11x11\""));
         assert!(json.contains("\"game_type\":\"Standard\""));
+        assert!(json.contains("\"turn_count\":0"));
+    }
+
+    #[test]
+    fn test_game_details_pages() {
+        assert_eq!(GameDetailsQuery::default().page(), None);
+
+        let query = GameDetailsQuery {
+            from_turn: Some(50),
+            limit: None,
+        };
+        assert_eq!(query.page(), Some((50, DETAILS_PAGE_SIZE)));
+
+        let query = GameDetailsQuery {
+            from_turn: Some(-3),
+            limit: Some(1_000_000),
+        };
+        assert_eq!(query.page(), Some((0, MAX_DETAILS_PAGE_SIZE)));
+
+        // A page ending before the last stored turn points at the next one
+        assert_eq!(next_page_turn(Some(99), 500), Some(100));
+        assert_eq!(next_page_turn(Some(499), 500), None);
+        assert_eq!(next_page_turn(None, 500), None);
     }
 }
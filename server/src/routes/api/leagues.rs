@@ -0,0 +1,320 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use color_eyre::eyre::eyre;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    errors::{ServerError, ServerResult, WithStatus},
+    league_awards::{self, LeagueAnnouncement},
+    models::league::{self, League, LeagueMember, LeaguePrize},
+    models::user::get_user_id_by_github_login,
+    routes::auth::{ApiUser, OptionalApiUser},
+    routes::leaderboard::{
+        LeaderboardQuery, LeaderboardScope, leaderboard_rows, leaderboard_scope,
+    },
+    state::AppState,
+};
+
+/// Longest league name
+const MAX_LEAGUE_NAME_LEN: usize = 100;
+
+/// Response format for league endpoints
+#[derive(Debug, Serialize)]
+pub struct LeagueResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub owner_id: Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<League> for LeagueResponse {
+    fn from(league: League) -> Self {
+        Self {
+            id: league.league_id,
+            name: league.name,
+            owner_id: league.owner_user_id,
+            created_at: league.created_at,
+        }
+    }
+}
+
+/// A league with its members and awards
+#[derive(Debug, Serialize)]
+pub struct LeagueDetailsResponse {
+    #[serde(flatten)]
+    pub league: LeagueResponse,
+    pub members: Vec<LeagueMember>,
+    pub prizes: Vec<LeaguePrize>,
+    pub writeup: Option<String>,
+    /// When the league was completed, None while it's running
+    pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Request body for creating a league
+#[derive(Debug, Deserialize)]
+pub struct CreateLeagueRequest {
+    pub name: String,
+}
+
+/// Request body for adding a member to a league
+#[derive(Debug, Deserialize)]
+pub struct AddLeagueMemberRequest {
+    /// The GitHub login the user signs in with
+    pub github_login: String,
+}
+
+/// Request body for setting a league's prizes and write-up
+#[derive(Debug, Deserialize)]
+pub struct UpdateLeagueAwardsRequest {
+    #[serde(default)]
+    pub prizes: Vec<LeaguePrize>,
+    pub writeup: Option<String>,
+}
+
+/// One snake's place on a leaderboard
+#[derive(Debug, Serialize)]
+pub struct LeaderboardEntry {
+    pub rank: usize,
+    pub snake_id: Uuid,
+    pub name: String,
+    pub rating: f64,
+    pub games: usize,
+    pub win_rate: f64,
+    /// Average places per game finished above (or below) what opponents' ratings predicted
+    pub performance_vs_expected: Option<f64>,
+}
+
+/// Response format for GET /api/leaderboard
+#[derive(Debug, Serialize)]
+pub struct LeaderboardResponse {
+    /// The league the leaderboard is rated from, or None for the whole arena
+    pub league_id: Option<Uuid>,
+    /// Rated from ranked 1v1 duels alone
+    pub duels: bool,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+/// The league, if the user is a member; 404 otherwise, so non-members can't
+/// tell private leagues exist
+async fn member_league(
+    state: &AppState,
+    league_id: Uuid,
+    user_id: Uuid,
+) -> ServerResult<League, StatusCode> {
+    let not_found = || ServerError(eyre!("League not found"), StatusCode::NOT_FOUND);
+    if !league::is_league_member(&state.db, league_id, user_id).await? {
+        return Err(not_found());
+    }
+
+    league::get_league_by_id(&state.db, league_id)
+        .await?
+        .ok_or_else(not_found)
+}
+
+/// The league, if the user owns it; 403 for other members
+async fn owner_league(
+    state: &AppState,
+    league_id: Uuid,
+    user_id: Uuid,
+) -> ServerResult<League, StatusCode> {
+    let league = member_league(state, league_id, user_id).await?;
+    if league.owner_user_id != user_id {
+        return Err("Only the league's owner can do that".to_string())
+            .with_status(StatusCode::FORBIDDEN);
+    }
+    Ok(league)
+}
+
+fn already_completed() -> ServerError<StatusCode> {
+    ServerError(eyre!("League is already completed"), StatusCode::CONFLICT)
+}
+
+async fn league_details(
+    state: &AppState,
+    league: League,
+) -> ServerResult<LeagueDetailsResponse, StatusCode> {
+    let members = league::get_league_members(&state.db, league.league_id).await?;
+    let awards = league::get_league_awards(&state.db, league.league_id).await?;
+
+    Ok(LeagueDetailsResponse {
+        league: LeagueResponse::from(league),
+        members,
+        prizes: awards.prizes,
+        writeup: awards.writeup,
+        completed_at: awards.completed_at,
+    })
+}
+
+/// A league's announcement; 404 until it's completed
+pub async fn league_announcement(
+    state: &AppState,
+    league_id: Uuid,
+) -> ServerResult<LeagueAnnouncement, StatusCode> {
+    league::get_league_awards(&state.db, league_id)
+        .await?
+        .announcement
+        .ok_or_else(|| "League hasn't been completed".to_string())
+        .with_status(StatusCode::NOT_FOUND)
+}
+
+/// POST /api/leagues - Create a league, with the caller as its owner and first member
+pub async fn create_league(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Json(request): Json<CreateLeagueRequest>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let name = request.name.trim();
+    if name.is_empty() || name.len() > MAX_LEAGUE_NAME_LEN {
+        return Err(format!(
+            "League name must be 1 to {} characters",
+            MAX_LEAGUE_NAME_LEN
+        ))
+        .with_status(StatusCode::BAD_REQUEST);
+    }
+
+    let league = league::create_league(&state.db, user.user_id, name).await?;
+
+    Ok((StatusCode::CREATED, Json(LeagueResponse::from(league))))
+}
+
+/// GET /api/leagues - List the leagues the caller is a member of
+pub async fn list_leagues(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let leagues = league::get_leagues_for_user(&state.db, user.user_id).await?;
+
+    Ok(Json(
+        leagues
+            .into_iter()
+            .map(LeagueResponse::from)
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// GET /api/leagues/{id} - Show a league and its members
+pub async fn show_league(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(league_id): Path<Uuid>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let league = member_league(&state, league_id, user.user_id).await?;
+    Ok(Json(league_details(&state, league).await?))
+}
+
+/// POST /api/leagues/{id}/members - Add a user to a league; only its owner can
+pub async fn add_member(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(league_id): Path<Uuid>,
+    Json(request): Json<AddLeagueMemberRequest>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let league = member_league(&state, league_id, user.user_id).await?;
+    if league.owner_user_id != user.user_id {
+        return Err("Only the league's owner can add members".to_string())
+            .with_status(StatusCode::FORBIDDEN);
+    }
+
+    let member_id = get_user_id_by_github_login(&state.db, request.github_login.trim())
+        .await?
+        .ok_or_else(|| {
+            format!(
+                "No user has signed in as {}; they need to log in once first",
+                request.github_login.trim()
+            )
+        })
+        .with_status(StatusCode::BAD_REQUEST)?;
+    league::add_league_member(&state.db, league_id, member_id).await?;
+
+    Ok(Json(league_details(&state, league).await?))
+}
+
+/// PUT /api/leagues/{id}/awards - Set a league's prizes and write-up; only its
+/// owner can, and only until it's completed
+pub async fn update_awards(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(league_id): Path<Uuid>,
+    Json(request): Json<UpdateLeagueAwardsRequest>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let league = owner_league(&state, league_id, user.user_id).await?;
+    let (prizes, writeup) = league_awards::normalize_awards(request.prizes, request.writeup)
+        .with_status(StatusCode::BAD_REQUEST)?;
+
+    if !league::set_league_awards(&state.db, league_id, &prizes, writeup.as_deref()).await? {
+        return Err(already_completed());
+    }
+
+    Ok(Json(league_details(&state, league).await?))
+}
+
+/// POST /api/leagues/{id}/complete - Complete a league and generate its
+/// announcement; only its owner can. No more games can be played in it.
+pub async fn complete_league(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(league_id): Path<Uuid>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let league = owner_league(&state, league_id, user.user_id).await?;
+    let announcement = league_awards::complete_league(&state, &league)
+        .await?
+        .ok_or_else(already_completed)?;
+
+    Ok(Json(announcement))
+}
+
+/// GET /api/leagues/{id}/announcement - A completed league's announcement
+pub async fn announcement(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(league_id): Path<Uuid>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    member_league(&state, league_id, user.user_id).await?;
+    let announcement = league_announcement(&state, league_id).await?;
+
+    Ok(Json(announcement))
+}
+
+/// GET /api/leaderboard - The arena's leaderboard, with `duels=true` its duel
+/// ladder, or with `league` a league's own, which only its members can see
+pub async fn leaderboard(
+    State(state): State<AppState>,
+    OptionalApiUser(user): OptionalApiUser,
+    Query(query): Query<LeaderboardQuery>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    query.validate().with_status(StatusCode::BAD_REQUEST)?;
+    let scope = leaderboard_scope(&state.db, &query, user.map(|u| u.user_id))
+        .await?
+        .ok_or_else(|| "League not found".to_string())
+        .with_status(StatusCode::NOT_FOUND)?;
+    let rows = leaderboard_rows(&state.db, &scope).await?;
+
+    let league_id = match &scope {
+        LeaderboardScope::Arena | LeaderboardScope::Duels => None,
+        LeaderboardScope::League(league) => Some(league.league_id),
+    };
+    let entries = rows
+        .into_iter()
+        .enumerate()
+        .map(|(i, row)| LeaderboardEntry {
+            rank: i + 1,
+            snake_id: row.battlesnake_id,
+            name: row.name,
+            rating: row.rating.rating,
+            games: row.rating.games,
+            win_rate: row.rating.win_rate(),
+            performance_vs_expected: row.rating.performance_vs_expected(),
+        })
+        .collect();
+
+    Ok(Json(LeaderboardResponse {
+        league_id,
+        duels: matches!(scope, LeaderboardScope::Duels),
+        entries,
+    }))
+}
@@ -0,0 +1,199 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        battlesnake_version,
+        game_battlesnake::{self, HeadToHeadGame},
+    },
+    routes::{api::games::validate_accessible_snakes, auth::ApiUser},
+    state::AppState,
+};
+
+/// Latest games listed in a matchup
+const RECENT_GAMES: usize = 10;
+
+/// Query parameters for GET /api/matchups
+#[derive(Debug, Deserialize)]
+pub struct MatchupQuery {
+    pub snake_a: Uuid,
+    pub snake_b: Uuid,
+    /// Only games snake_a played as this version of itself
+    pub version_a: Option<Uuid>,
+    /// Only games snake_b played as this version of itself
+    pub version_b: Option<Uuid>,
+}
+
+/// Response format for GET /api/matchups
+///
+/// Wins and losses are `snake_a`'s: a win is a game it placed above
+/// `snake_b`, a draw one they placed level.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct MatchupResponse {
+    pub snake_a: Uuid,
+    pub snake_b: Uuid,
+    pub games: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    /// Average number of turns per game, None before they've played
+    pub average_turns: Option<f64>,
+    /// The latest games between them, newest first
+    pub recent_game_ids: Vec<Uuid>,
+}
+
+impl MatchupResponse {
+    /// Tally games between two snakes, given newest first
+    fn from_games(snake_a: Uuid, snake_b: Uuid, games: &[HeadToHeadGame]) -> Self {
+        let count = |won: fn(&HeadToHeadGame) -> bool| games.iter().filter(|g| won(g)).count();
+        let turns: Vec<i32> = games.iter().filter_map(|g| g.turns).collect();
+
+        Self {
+            snake_a,
+            snake_b,
+            games: games.len(),
+            wins: count(|g| g.placement_a < g.placement_b),
+            losses: count(|g| g.placement_a > g.placement_b),
+            draws: count(|g| g.placement_a == g.placement_b),
+            average_turns: (!turns.is_empty())
+                .then(|| turns.iter().map(|&t| f64::from(t)).sum::<f64>() / turns.len() as f64),
+            recent_game_ids: games.iter().take(RECENT_GAMES).map(|g| g.game_id).collect(),
+        }
+    }
+}
+
+/// GET /api/matchups?snake_a=X&snake_b=Y - How two snakes have done against each other
+///
+/// Both snakes must be the caller's or public. Counts the finished games the
+/// caller can see; a game either snake played more than once in counts its
+/// best placement.
+pub async fn head_to_head(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Query(query): Query<MatchupQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    if query.snake_a == query.snake_b {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "snake_a and snake_b must be different snakes".to_string(),
+        ));
+    }
+    validate_accessible_snakes(&state, user.user_id, &[query.snake_a, query.snake_b]).await?;
+    for (version_id, snake_id) in [
+        (query.version_a, query.snake_a),
+        (query.version_b, query.snake_b),
+    ] {
+        if let Some(version_id) = version_id {
+            ensure_version_of(&state, version_id, snake_id).await?;
+        }
+    }
+
+    let games = game_battlesnake::get_head_to_head_games(
+        &state.db,
+        query.snake_a,
+        query.snake_b,
+        query.version_a,
+        query.version_b,
+        user.user_id,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch matchup: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    })?;
+
+    Ok(Json(MatchupResponse::from_games(
+        query.snake_a,
+        query.snake_b,
+        &games,
+    )))
+}
+
+/// 400 unless the version is one of the snake's
+async fn ensure_version_of(
+    state: &AppState,
+    version_id: Uuid,
+    snake_id: Uuid,
+) -> Result<(), (StatusCode, String)> {
+    let version = battlesnake_version::get_version(&state.db, version_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get snake version: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?;
+
+    match version {
+        Some(version) if version.battlesnake_id == snake_id => Ok(()),
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Version {} is not a version of snake {}",
+                version_id, snake_id
+            ),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(placement_a: i32, placement_b: i32, turns: Option<i32>) -> HeadToHeadGame {
+        HeadToHeadGame {
+            game_id: Uuid::new_v4(),
+            placement_a,
+            placement_b,
+            turns,
+        }
+    }
+
+    #[test]
+    fn test_matchup_tallies_games() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let games = vec![
+            game(1, 2, Some(40)),
+            game(1, 1, Some(20)),
+            game(3, 1, None),
+            game(2, 3, Some(60)),
+        ];
+
+        let matchup = MatchupResponse::from_games(a, b, &games);
+        assert_eq!(matchup.games, 4);
+        assert_eq!((matchup.wins, matchup.losses, matchup.draws), (2, 1, 1));
+        assert_eq!(matchup.average_turns, Some(40.0));
+        assert_eq!(
+            matchup.recent_game_ids,
+            games.iter().map(|g| g.game_id).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_matchup_without_games() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let matchup = MatchupResponse::from_games(a, b, &[]);
+        assert_eq!(matchup.games, 0);
+        assert_eq!(matchup.average_turns, None);
+        assert!(matchup.recent_game_ids.is_empty());
+    }
+
+    #[test]
+    fn test_recent_games_are_capped() {
+        let games: Vec<_> = (0..25).map(|_| game(1, 2, Some(10))).collect();
+        let matchup = MatchupResponse::from_games(Uuid::new_v4(), Uuid::new_v4(), &games);
+        assert_eq!(matchup.wins, 25);
+        assert_eq!(matchup.recent_game_ids.len(), RECENT_GAMES);
+        assert_eq!(matchup.recent_game_ids[0], games[0].game_id);
+    }
+}
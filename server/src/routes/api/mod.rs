@@ -1,3 +1,19 @@
+pub mod admin;
+pub mod alerts;
+pub mod announcements;
+pub mod archive;
+pub mod capabilities;
+pub mod checks;
+pub mod federation;
 pub mod games;
+pub mod leagues;
+pub mod matchups;
+pub mod notifications;
+pub mod preferences;
+pub mod reports;
+pub mod rulesets;
+pub mod snake_templates;
 pub mod snakes;
+pub mod tags;
 pub mod tokens;
+pub mod tv;
@@ -0,0 +1,98 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    errors::{ServerResult, WithStatus},
+    models::notification::{self, Notification},
+    routes::auth::ApiUser,
+    state::AppState,
+};
+
+/// Notifications returned when no limit is given
+const DEFAULT_LIMIT: i64 = 50;
+
+/// Most notifications returned at once
+const MAX_LIMIT: i64 = 200;
+
+/// Query parameters for GET /api/notifications
+#[derive(Debug, Deserialize)]
+pub struct NotificationsQuery {
+    /// Only return unread notifications
+    #[serde(default)]
+    pub unread: bool,
+    pub limit: Option<i64>,
+}
+
+/// Response format for GET /api/notifications
+#[derive(Debug, Serialize)]
+pub struct NotificationsResponse {
+    pub unread_count: i64,
+    /// Newest first
+    pub notifications: Vec<Notification>,
+}
+
+/// Response format for POST /api/notifications/read
+#[derive(Debug, Serialize)]
+pub struct MarkAllReadResponse {
+    /// How many notifications were unread
+    pub marked: u64,
+}
+
+/// GET /api/notifications - The caller's latest notifications and unread count
+pub async fn list_notifications(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Query(query): Query<NotificationsQuery>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(format!("limit must be between 1 and {}", MAX_LIMIT))
+            .with_status(StatusCode::BAD_REQUEST);
+    }
+
+    let notifications =
+        notification::get_notifications(&state.db, user.user_id, query.unread, limit).await?;
+    let unread_count = notification::count_unread_notifications(&state.db, user.user_id).await?;
+
+    Ok(Json(NotificationsResponse {
+        unread_count,
+        notifications,
+    }))
+}
+
+/// POST /api/notifications/{id}/read - Mark one of the caller's notifications read
+pub async fn mark_read(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(notification_id): Path<Uuid>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let notification = notification::mark_notification_read(
+        &state.db,
+        user.user_id,
+        notification_id,
+        state.clock.now(),
+    )
+    .await?
+    .ok_or_else(|| "Notification not found".to_string())
+    .with_status(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(notification))
+}
+
+/// POST /api/notifications/read - Mark all of the caller's notifications read
+pub async fn mark_all_read(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let marked =
+        notification::mark_all_notifications_read(&state.db, user.user_id, state.clock.now())
+            .await?;
+
+    Ok(Json(MarkAllReadResponse { marked }))
+}
@@ -0,0 +1,254 @@
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::{
+    models::snake_tag,
+    models::user_preferences::{
+        self, MAX_PLAYBACK_SPEED, MIN_PLAYBACK_SPEED, NotificationChannel, Theme, UserPreferences,
+    },
+    routes::{
+        api::games::{parse_board_size, parse_game_type},
+        auth::ApiUser,
+    },
+    state::AppState,
+};
+
+/// Response format for preferences endpoints
+#[derive(Debug, Serialize)]
+pub struct PreferencesResponse {
+    /// Board size new games default to
+    pub board: &'static str,
+    /// Game type new games default to
+    pub game_type: &'static str,
+    pub timezone: String,
+    pub notification_channels: Vec<&'static str>,
+    /// Replay speed, as a multiple of the board viewer's normal speed
+    pub playback_speed: f64,
+    /// "system", "light" or "dark"
+    pub theme: &'static str,
+    /// Public snakes with any of these tags come first when searching for opponents
+    pub preferred_opponent_tags: Vec<String>,
+}
+
+impl From<&UserPreferences> for PreferencesResponse {
+    fn from(preferences: &UserPreferences) -> Self {
+        Self {
+            board: preferences.board_size.as_str(),
+            game_type: preferences.game_type.as_str(),
+            timezone: preferences.timezone.clone(),
+            notification_channels: preferences
+                .notification_channels
+                .iter()
+                .map(|c| c.as_str())
+                .collect(),
+            playback_speed: preferences.playback_speed,
+            theme: preferences.theme.as_str(),
+            preferred_opponent_tags: preferences.preferred_opponent_tags.clone(),
+        }
+    }
+}
+
+/// Request body for updating preferences; fields left out keep their current value
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdatePreferencesRequest {
+    pub board: Option<String>,
+    pub game_type: Option<String>,
+    pub timezone: Option<String>,
+    pub notification_channels: Option<Vec<String>>,
+    pub playback_speed: Option<f64>,
+    pub theme: Option<String>,
+    pub preferred_opponent_tags: Option<Vec<String>>,
+}
+
+/// Apply an update to a user's preferences, checking every field given
+///
+/// Time zones are only checked for shape here; `update_user_preferences` checks
+/// the database knows them.
+pub(crate) fn apply_preferences_update(
+    mut preferences: UserPreferences,
+    update: &UpdatePreferencesRequest,
+) -> Result<UserPreferences, String> {
+    if let Some(board) = &update.board {
+        preferences.board_size = parse_board_size(board)?;
+    }
+    if let Some(game_type) = &update.game_type {
+        preferences.game_type = parse_game_type(game_type)?;
+    }
+    if let Some(timezone) = &update.timezone {
+        let timezone = timezone.trim();
+        if timezone.is_empty() || timezone.len() > 64 {
+            return Err("Invalid timezone. Use an IANA name like Europe/Berlin".to_string());
+        }
+        preferences.timezone = timezone.to_string();
+    }
+    if let Some(channels) = &update.notification_channels {
+        let mut parsed = Vec::new();
+        for channel in channels {
+            let channel = NotificationChannel::from_str(channel).map_err(|_| {
+                format!(
+                    "Invalid notification channel {}. Use {}",
+                    channel,
+                    NotificationChannel::ALL.map(|c| c.as_str()).join(" or ")
+                )
+            })?;
+            if !parsed.contains(&channel) {
+                parsed.push(channel);
+            }
+        }
+        preferences.notification_channels = parsed;
+    }
+    if let Some(speed) = update.playback_speed {
+        if !(MIN_PLAYBACK_SPEED..=MAX_PLAYBACK_SPEED).contains(&speed) {
+            return Err(format!(
+                "playback_speed must be between {} and {}",
+                MIN_PLAYBACK_SPEED, MAX_PLAYBACK_SPEED
+            ));
+        }
+        preferences.playback_speed = speed;
+    }
+    if let Some(theme) = &update.theme {
+        preferences.theme = Theme::from_str(theme)
+            .map_err(|_| "Invalid theme. Use system, light or dark".to_string())?;
+    }
+    if let Some(tags) = &update.preferred_opponent_tags {
+        preferences.preferred_opponent_tags = snake_tag::normalize_tags(tags)?;
+    }
+
+    Ok(preferences)
+}
+
+/// Apply an update to a user's saved preferences and save the result
+pub(crate) async fn update_user_preferences(
+    state: &AppState,
+    user_id: uuid::Uuid,
+    update: &UpdatePreferencesRequest,
+) -> Result<UserPreferences, (StatusCode, String)> {
+    let internal_error = |e: color_eyre::Report| {
+        tracing::error!("Failed to update preferences: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    };
+
+    let current = user_preferences::get_user_preferences(&state.db, user_id)
+        .await
+        .map_err(internal_error)?;
+    let updated = apply_preferences_update(current, update)
+        .map_err(|message| (StatusCode::BAD_REQUEST, message))?;
+
+    if update.timezone.is_some() {
+        let known = user_preferences::is_known_timezone(&state.db, &updated.timezone)
+            .await
+            .map_err(internal_error)?;
+        if !known {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Unknown timezone {}", updated.timezone),
+            ));
+        }
+    }
+
+    user_preferences::save_user_preferences(&state.db, user_id, &updated, state.clock.now())
+        .await
+        .map_err(internal_error)?;
+
+    Ok(updated)
+}
+
+/// GET /api/me/preferences - The current user's preferences
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+) -> Result<impl IntoResponse, StatusCode> {
+    let preferences = user_preferences::get_user_preferences(&state.db, user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get preferences: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(PreferencesResponse::from(&preferences)))
+}
+
+/// PUT /api/me/preferences - Update the current user's preferences
+pub async fn update_preferences(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Json(request): Json<UpdatePreferencesRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let preferences = update_user_preferences(&state, user.user_id, &request).await?;
+
+    Ok(Json(PreferencesResponse::from(&preferences)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::game::{GameBoardSize, GameType};
+
+    #[test]
+    fn test_apply_preferences_update() {
+        let update = UpdatePreferencesRequest {
+            board: Some("7x7".to_string()),
+            notification_channels: Some(vec![
+                "email".to_string(),
+                "Web".to_string(),
+                "email".to_string(),
+            ]),
+            theme: Some("dark".to_string()),
+            preferred_opponent_tags: Some(vec!["Tree Search".to_string(), "rust".to_string()]),
+            ..Default::default()
+        };
+        let updated = apply_preferences_update(UserPreferences::default(), &update).unwrap();
+
+        assert_eq!(updated.board_size, GameBoardSize::Small);
+        // Left out, so unchanged
+        assert_eq!(updated.game_type, GameType::Standard);
+        assert_eq!(updated.timezone, "UTC");
+        assert_eq!(
+            updated.notification_channels,
+            vec![NotificationChannel::Email, NotificationChannel::Web]
+        );
+        assert_eq!(updated.theme, Theme::Dark);
+        assert_eq!(updated.preferred_opponent_tags, vec!["tree-search", "rust"]);
+    }
+
+    #[test]
+    fn test_apply_preferences_update_rejects_invalid_values() {
+        let invalid = [
+            UpdatePreferencesRequest {
+                board: Some("10x10".to_string()),
+                ..Default::default()
+            },
+            UpdatePreferencesRequest {
+                notification_channels: Some(vec!["carrier pigeon".to_string()]),
+                ..Default::default()
+            },
+            UpdatePreferencesRequest {
+                playback_speed: Some(10.0),
+                ..Default::default()
+            },
+            UpdatePreferencesRequest {
+                timezone: Some("  ".to_string()),
+                ..Default::default()
+            },
+            UpdatePreferencesRequest {
+                theme: Some("sepia".to_string()),
+                ..Default::default()
+            },
+            UpdatePreferencesRequest {
+                preferred_opponent_tags: Some(vec!["<b>".to_string()]),
+                ..Default::default()
+            },
+        ];
+        for update in invalid {
+            assert!(
+                apply_preferences_update(UserPreferences::default(), &update).is_err(),
+                "{:?}",
+                update
+            );
+        }
+    }
+}
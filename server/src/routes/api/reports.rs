@@ -0,0 +1,23 @@
+use axum::{body::Body, extract::State, http::header, response::IntoResponse};
+
+use crate::{reports::user_games_csv, routes::auth::ApiUser, state::AppState};
+
+/// GET /api/me/games.csv - Every game with one of your snakes in it, as CSV
+///
+/// One row per game with its snakes, placements, turns, duration and death
+/// causes. Streamed, so large exports start downloading right away.
+pub async fn my_games_csv(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+) -> impl IntoResponse {
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"games.csv\"",
+            ),
+        ],
+        Body::from_stream(user_games_csv(state, user.user_id)),
+    )
+}
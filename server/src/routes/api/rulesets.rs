@@ -0,0 +1,107 @@
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    engine::wasm_ruleset::WasmRuleset,
+    models::ruleset::{self, Ruleset},
+    routes::auth::ApiUser,
+    state::AppState,
+};
+
+/// Most rulesets one user can upload
+pub(crate) const MAX_RULESETS_PER_USER: i64 = 20;
+
+/// Response format for ruleset endpoints
+#[derive(Debug, Serialize)]
+pub struct RulesetResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<Ruleset> for RulesetResponse {
+    fn from(ruleset: Ruleset) -> Self {
+        Self {
+            id: ruleset.ruleset_id,
+            name: ruleset.name,
+            created_at: ruleset.created_at,
+        }
+    }
+}
+
+/// Request body for uploading a ruleset
+#[derive(Debug, Deserialize)]
+pub struct CreateRulesetRequest {
+    pub name: String,
+    /// The compiled WASM module, base64 encoded
+    pub wasm: String,
+}
+
+/// GET /api/rulesets - List the user's rulesets
+pub async fn list_rulesets(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+) -> Result<impl IntoResponse, StatusCode> {
+    let rulesets = ruleset::get_rulesets_by_user_id(&state.db, user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list rulesets: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let response: Vec<RulesetResponse> = rulesets.into_iter().map(RulesetResponse::from).collect();
+    Ok(Json(response))
+}
+
+/// POST /api/rulesets - Upload a ruleset, checking that it compiles and
+/// exports the ruleset interface
+pub async fn upload_ruleset(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Json(request): Json<CreateRulesetRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to create ruleset: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to create ruleset".to_string(),
+        )
+    };
+
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "name can't be empty".to_string()));
+    }
+    let wasm = STANDARD.decode(&request.wasm).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("wasm must be base64 encoded: {}", e),
+        )
+    })?;
+    WasmRuleset::compile(&wasm).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let count = ruleset::count_rulesets_by_user_id(&state.db, user.user_id)
+        .await
+        .map_err(internal_error)?;
+    if count >= MAX_RULESETS_PER_USER {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("You can have at most {} rulesets", MAX_RULESETS_PER_USER),
+        ));
+    }
+
+    let ruleset = ruleset::create_ruleset(&state.db, user.user_id, name, &wasm, state.clock.now())
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::CONFLICT,
+                format!("You already have a ruleset named '{}'", name),
+            )
+        })?;
+
+    Ok((StatusCode::CREATED, Json(RulesetResponse::from(ruleset))))
+}
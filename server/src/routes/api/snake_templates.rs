@@ -0,0 +1,333 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    models::battlesnake::Visibility,
+    models::snake_template::{
+        self, CreateSnakeTemplate, SnakeTemplate, SnakeVariant, create_snake_template,
+        get_snake_template_by_id,
+    },
+    routes::{
+        api::snakes::{SnakeResponse, validate_url},
+        auth::ApiUser,
+    },
+    snake_templates::{TAG_PLACEHOLDER, instantiate_variant, render_url, validate_tag},
+    state::AppState,
+};
+
+/// Days a variant can go without a game before it's archived, by default
+const DEFAULT_ARCHIVE_AFTER_DAYS: i32 = 14;
+
+/// Longest archive_after_days accepted
+const MAX_ARCHIVE_AFTER_DAYS: i32 = 365;
+
+/// Response format for template endpoints
+#[derive(Debug, Serialize)]
+pub struct SnakeTemplateResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub url_template: String,
+    pub is_public: bool,
+    pub archive_after_days: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// The template's variants, newest first; only on GET /api/snake-templates/{id}
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variants: Option<Vec<SnakeVariantResponse>>,
+}
+
+impl From<SnakeTemplate> for SnakeTemplateResponse {
+    fn from(template: SnakeTemplate) -> Self {
+        Self {
+            id: template.template_id,
+            name: template.name,
+            url_template: template.url_template,
+            is_public: template.visibility == Visibility::Public,
+            archive_after_days: template.archive_after_days,
+            created_at: template.created_at,
+            variants: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SnakeVariantResponse {
+    pub snake_id: Uuid,
+    pub tag: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub archived_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<SnakeVariant> for SnakeVariantResponse {
+    fn from(variant: SnakeVariant) -> Self {
+        Self {
+            snake_id: variant.battlesnake_id,
+            tag: variant.tag,
+            created_at: variant.created_at,
+            archived_at: variant.archived_at,
+        }
+    }
+}
+
+/// Request body for creating a template
+#[derive(Debug, Deserialize)]
+pub struct CreateSnakeTemplateRequest {
+    pub name: String,
+    /// Snake URL with a {tag} placeholder, e.g. "https://snake.example.com/{tag}"
+    pub url_template: String,
+    #[serde(default)]
+    pub is_public: bool,
+    /// Days a variant can go without a game before it's archived (default: 14)
+    pub archive_after_days: Option<i32>,
+}
+
+/// Request body for instantiating a template
+#[derive(Debug, Deserialize)]
+pub struct InstantiateRequest {
+    pub tag: String,
+}
+
+/// Response format for POST /api/snake-templates/{id}/variants
+#[derive(Debug, Serialize)]
+pub struct InstantiateResponse {
+    pub tag: String,
+    /// False when the tag already had a variant
+    pub created: bool,
+    /// True when the tag's variant had been archived and is back now
+    pub unarchived: bool,
+    pub snake: SnakeResponse,
+}
+
+/// Check a new template's fields
+pub(crate) fn validate_template(request: &CreateSnakeTemplateRequest) -> Result<(), String> {
+    if request.name.trim().is_empty() {
+        return Err("name can't be empty".to_string());
+    }
+    if !request.url_template.contains(TAG_PLACEHOLDER) {
+        return Err(format!(
+            "url_template must contain the {} placeholder",
+            TAG_PLACEHOLDER
+        ));
+    }
+    validate_url(&render_url(&request.url_template, "v1"))
+        .map_err(|e| format!("url_template: {}", e))?;
+    if let Some(days) = request.archive_after_days
+        && !(1..=MAX_ARCHIVE_AFTER_DAYS).contains(&days)
+    {
+        return Err(format!(
+            "archive_after_days must be between 1 and {}",
+            MAX_ARCHIVE_AFTER_DAYS
+        ));
+    }
+    Ok(())
+}
+
+/// Get a template, 404 unless it belongs to the user
+async fn owned_template(
+    state: &AppState,
+    template_id: Uuid,
+    user_id: Uuid,
+) -> Result<SnakeTemplate, (StatusCode, String)> {
+    let template = get_snake_template_by_id(&state.db, template_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get snake template: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?;
+
+    match template {
+        Some(template) if template.user_id == user_id => Ok(template),
+        _ => Err((StatusCode::NOT_FOUND, "Template not found".to_string())),
+    }
+}
+
+/// GET /api/snake-templates - List the user's templates
+pub async fn list_templates(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+) -> Result<impl IntoResponse, StatusCode> {
+    let templates = snake_template::get_snake_templates_by_user_id(&state.db, user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list snake templates: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let response: Vec<SnakeTemplateResponse> = templates
+        .into_iter()
+        .map(SnakeTemplateResponse::from)
+        .collect();
+    Ok(Json(response))
+}
+
+/// POST /api/snake-templates - Create a template
+pub async fn create_template(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Json(request): Json<CreateSnakeTemplateRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    validate_template(&request).map_err(|message| (StatusCode::BAD_REQUEST, message))?;
+
+    let template = create_snake_template(
+        &state.db,
+        user.user_id,
+        CreateSnakeTemplate {
+            name: request.name.trim().to_string(),
+            url_template: request.url_template,
+            visibility: if request.is_public {
+                Visibility::Public
+            } else {
+                Visibility::Private
+            },
+            archive_after_days: request
+                .archive_after_days
+                .unwrap_or(DEFAULT_ARCHIVE_AFTER_DAYS),
+        },
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create snake template: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to create template".to_string(),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::CONFLICT,
+            format!(
+                "You already have a template named '{}'",
+                request.name.trim()
+            ),
+        )
+    })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(SnakeTemplateResponse::from(template)),
+    ))
+}
+
+/// GET /api/snake-templates/{id} - A template and its variants
+pub async fn show_template(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(template_id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let template = owned_template(&state, template_id, user.user_id).await?;
+
+    let variants = snake_template::get_snake_variants(&state.db, template_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get snake variants: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?;
+
+    let mut response = SnakeTemplateResponse::from(template);
+    response.variants = Some(
+        variants
+            .into_iter()
+            .map(SnakeVariantResponse::from)
+            .collect(),
+    );
+    Ok(Json(response))
+}
+
+/// DELETE /api/snake-templates/{id} - Delete a template, keeping its variants as snakes
+pub async fn delete_template(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(template_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let deleted = snake_template::delete_snake_template(&state.db, template_id, user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete snake template: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/snake-templates/{id}/variants - Get or create the snake for a tag
+///
+/// Safe to call on every deploy: a tag that already has a snake gets the same
+/// one back, brought back from the archive if need be.
+pub async fn instantiate_template(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(template_id): Path<Uuid>,
+    Json(request): Json<InstantiateRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let template = owned_template(&state, template_id, user.user_id).await?;
+    validate_tag(&request.tag).map_err(|message| (StatusCode::BAD_REQUEST, message))?;
+
+    let instantiated = instantiate_variant(&state, &template, &request.tag)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to instantiate snake template: {}", e);
+            let msg = e.to_string();
+            if msg.contains("already have a battlesnake named") {
+                (StatusCode::CONFLICT, msg)
+            } else {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to create variant".to_string(),
+                )
+            }
+        })?;
+
+    let status = if instantiated.created {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    Ok((
+        status,
+        Json(InstantiateResponse {
+            tag: request.tag,
+            created: instantiated.created,
+            unarchived: instantiated.unarchived,
+            snake: SnakeResponse::from(instantiated.snake),
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(url_template: &str, archive_after_days: Option<i32>) -> CreateSnakeTemplateRequest {
+        CreateSnakeTemplateRequest {
+            name: "my-snake".to_string(),
+            url_template: url_template.to_string(),
+            is_public: false,
+            archive_after_days,
+        }
+    }
+
+    #[test]
+    fn test_validate_template() {
+        assert!(validate_template(&request("https://snake.example.com/{tag}", None)).is_ok());
+        assert!(validate_template(&request("https://{tag}.snake.example.com", Some(30))).is_ok());
+        // No placeholder
+        assert!(validate_template(&request("https://snake.example.com", None)).is_err());
+        assert!(validate_template(&request("ftp://snake.example.com/{tag}", None)).is_err());
+        assert!(validate_template(&request("https://snake.example.com/{tag}", Some(0))).is_err());
+    }
+}
@@ -5,11 +5,21 @@ use axum::{
     response::IntoResponse,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use url::Url;
 use uuid::Uuid;
 
 use crate::{
-    models::battlesnake::{self, Battlesnake, CreateBattlesnake, UpdateBattlesnake, Visibility},
+    diagnose::diagnose_url,
+    domain_events::{self, SnakeStatsRollup},
+    engine::move_provider::BuiltInBot,
+    models::battlesnake::{
+        self, Battlesnake, CreateBattlesnake, SnakeProfile, UpdateBattlesnake, Visibility,
+    },
+    models::battlesnake_version::{self, VersionSummary},
+    models::snake_github_repo::{self, SnakeGitHubRepo},
+    models::snake_health::{self, SnakeHealth},
+    models::snake_tag,
     routes::auth::ApiUser,
     state::AppState,
 };
@@ -23,6 +33,24 @@ pub struct SnakeResponse {
     pub is_public: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    /// Markdown shown on the snake's public pages
+    pub description: Option<String>,
+    pub homepage_url: Option<String>,
+    pub source_url: Option<String>,
+    pub tags: Vec<String>,
+    /// The latest automated check of the snake's GET /, None until it's had one
+    pub health: Option<HealthResponse>,
+}
+
+impl SnakeResponse {
+    fn with_profile(self, profile: SnakeProfile) -> Self {
+        Self {
+            description: profile.description,
+            homepage_url: profile.homepage_url,
+            source_url: profile.source_url,
+            ..self
+        }
+    }
 }
 
 impl From<Battlesnake> for SnakeResponse {
@@ -34,10 +62,69 @@ impl From<Battlesnake> for SnakeResponse {
             is_public: snake.visibility == Visibility::Public,
             created_at: snake.created_at,
             updated_at: snake.updated_at,
+            description: None,
+            homepage_url: None,
+            source_url: None,
+            tags: Vec::new(),
+            health: None,
         }
     }
 }
 
+/// Response format for a snake's latest health check
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    /// GET / answered 200 with a valid Battlesnake info response
+    pub healthy: bool,
+    /// None if no response came back
+    pub status_code: Option<i32>,
+    pub latency_ms: Option<i32>,
+    /// Why the check failed
+    pub error: Option<String>,
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<SnakeHealth> for HealthResponse {
+    fn from(health: SnakeHealth) -> Self {
+        Self {
+            healthy: health.healthy,
+            status_code: health.status_code,
+            latency_ms: health.latency_ms,
+            error: health.error,
+            checked_at: health.checked_at,
+        }
+    }
+}
+
+/// Snake responses with each snake's profile, tags and latest health check
+async fn with_health(
+    state: &AppState,
+    snakes: Vec<Battlesnake>,
+) -> cja::Result<Vec<SnakeResponse>> {
+    let ids: Vec<Uuid> = snakes.iter().map(|s| s.battlesnake_id).collect();
+    let mut health: HashMap<Uuid, SnakeHealth> = snake_health::get_snake_health(&state.db, &ids)
+        .await?
+        .into_iter()
+        .map(|h| (h.battlesnake_id, h))
+        .collect();
+    let mut profiles = battlesnake::get_snake_profiles(&state.db, &ids).await?;
+    let mut tags = snake_tag::get_tags_for_snakes(&state.db, &ids).await?;
+
+    Ok(snakes
+        .into_iter()
+        .map(|snake| {
+            let health = health.remove(&snake.battlesnake_id);
+            let profile = profiles.remove(&snake.battlesnake_id).unwrap_or_default();
+            SnakeResponse {
+                health: health.map(HealthResponse::from),
+                tags: tags.remove(&snake.battlesnake_id).unwrap_or_default(),
+                ..SnakeResponse::from(snake)
+            }
+            .with_profile(profile)
+        })
+        .collect())
+}
+
 /// Request body for creating a snake
 #[derive(Debug, Deserialize)]
 pub struct CreateSnakeRequest {
@@ -45,28 +132,101 @@ pub struct CreateSnakeRequest {
     pub url: String,
     #[serde(default)]
     pub is_public: bool,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub homepage_url: Option<String>,
+    #[serde(default)]
+    pub source_url: Option<String>,
 }
 
 /// Request body for updating a snake
+///
+/// Profile fields that are left out keep their value; an empty string clears one.
 #[derive(Debug, Deserialize)]
 pub struct UpdateSnakeRequest {
     pub name: Option<String>,
     pub url: Option<String>,
     pub is_public: Option<bool>,
+    pub description: Option<String>,
+    pub homepage_url: Option<String>,
+    pub source_url: Option<String>,
 }
 
-/// Validate that a URL is a valid HTTP or HTTPS URL
-fn validate_url(url: &str) -> Result<(), &'static str> {
-    match Url::parse(url) {
-        Ok(parsed) => {
-            if parsed.scheme() == "http" || parsed.scheme() == "https" {
-                Ok(())
-            } else {
-                Err("URL must use HTTP or HTTPS scheme")
-            }
+/// Response format for a snake's GitHub repository
+#[derive(Debug, Serialize)]
+pub struct GitHubRepoResponse {
+    pub repo: String,
+    pub installation_id: i64,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<SnakeGitHubRepo> for GitHubRepoResponse {
+    fn from(repo: SnakeGitHubRepo) -> Self {
+        Self {
+            repo: repo.repo,
+            installation_id: repo.installation_id,
+            updated_at: repo.updated_at,
+        }
+    }
+}
+
+/// Request body for linking a snake to its GitHub repository
+#[derive(Debug, Deserialize)]
+pub struct SetGitHubRepoRequest {
+    /// "owner/name"
+    pub repo: String,
+    /// The GitHub App's installation ID on the repository's owner
+    pub installation_id: i64,
+}
+
+/// Validate a GitHub repository name in "owner/name" form
+fn validate_github_repo(repo: &str) -> Result<(), &'static str> {
+    let valid_part = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    match repo.split_once('/') {
+        Some((owner, name)) if valid_part(owner) && valid_part(name) => Ok(()),
+        _ => Err("repo must be a GitHub repository in owner/name form"),
+    }
+}
+
+/// Validate that a URL is a valid HTTP or HTTPS URL, or names a built-in bot
+///
+/// IPv6 literals (in brackets), any port and percent-encoded paths are fine;
+/// shapes the snake client can't call are rejected with the reason.
+pub(crate) fn validate_url(url: &str) -> Result<(), &'static str> {
+    if BuiltInBot::from_url(url).is_some() {
+        return Ok(());
+    }
+
+    let parsed = Url::parse(url).map_err(|e| match e {
+        url::ParseError::EmptyHost => "URL must include a host",
+        url::ParseError::InvalidPort => "URL port must be a number from 1 to 65535",
+        url::ParseError::InvalidIpv6Address => {
+            "Invalid IPv6 address; write it in brackets, like http://[::1]:8000"
         }
-        Err(_) => Err("Invalid URL format"),
+        _ => "Invalid URL format",
+    })?;
+
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err("URL must use HTTP or HTTPS scheme");
+    }
+    if parsed.host().is_none() {
+        return Err("URL must include a host");
+    }
+    if parsed.port() == Some(0) {
+        return Err("URL port must be a number from 1 to 65535");
     }
+    // Endpoints are appended to the path, so anything after a # would be lost
+    if parsed.fragment().is_some() {
+        return Err("URL can't include a fragment (#...)");
+    }
+
+    Ok(())
 }
 
 /// GET /api/snakes - List user's snakes
@@ -81,7 +241,10 @@ pub async fn list_snakes(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-    let response: Vec<SnakeResponse> = snakes.into_iter().map(SnakeResponse::from).collect();
+    let response = with_health(&state, snakes).await.map_err(|e| {
+        tracing::error!("Failed to get snake health: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
     Ok(Json(response))
 }
 
@@ -95,6 +258,13 @@ pub async fn create_snake(
     if let Err(e) = validate_url(&request.url) {
         return Err((StatusCode::BAD_REQUEST, e.to_string()));
     }
+    let profile = SnakeProfile {
+        description: request.description,
+        homepage_url: request.homepage_url,
+        source_url: request.source_url,
+    }
+    .normalized()
+    .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
 
     let create_data = CreateBattlesnake {
         name: request.name,
@@ -106,23 +276,39 @@ pub async fn create_snake(
         },
     };
 
-    let snake = battlesnake::create_battlesnake(&state.db, user.user_id, create_data)
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to create snake: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to create snake".to_string(),
+        )
+    };
+
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|e| internal_error(e.into()))?;
+    let snake = battlesnake::create_battlesnake(&mut *tx, user.user_id, create_data)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to create snake: {}", e);
             // Return the error message for unique constraint violations
             let msg = e.to_string();
             if msg.contains("already have a battlesnake named") {
                 (StatusCode::CONFLICT, msg)
             } else {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to create snake".to_string(),
-                )
+                internal_error(e)
             }
         })?;
+    battlesnake::set_snake_profile(&mut *tx, snake.battlesnake_id, &profile)
+        .await
+        .map_err(internal_error)?;
+    tx.commit().await.map_err(|e| internal_error(e.into()))?;
 
-    Ok((StatusCode::CREATED, Json(SnakeResponse::from(snake))))
+    Ok((
+        StatusCode::CREATED,
+        Json(SnakeResponse::from(snake).with_profile(profile)),
+    ))
 }
 
 /// GET /api/snakes/{id} - Get snake details
@@ -144,7 +330,15 @@ pub async fn get_snake(
         return Err(StatusCode::NOT_FOUND);
     }
 
-    Ok(Json(SnakeResponse::from(snake)))
+    let response = with_health(&state, vec![snake])
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get snake health: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .pop()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(response))
 }
 
 /// PUT /api/snakes/{id} - Update snake
@@ -179,6 +373,25 @@ pub async fn update_snake(
         return Err((StatusCode::BAD_REQUEST, e.to_string()));
     }
 
+    let internal_error = |e: cja::color_eyre::Report| {
+        tracing::error!("Failed to update snake: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to update snake".to_string(),
+        )
+    };
+
+    let existing_profile = battlesnake::get_snake_profile(&state.db, snake_id)
+        .await
+        .map_err(internal_error)?;
+    let profile = SnakeProfile {
+        description: request.description.or(existing_profile.description),
+        homepage_url: request.homepage_url.or(existing_profile.homepage_url),
+        source_url: request.source_url.or(existing_profile.source_url),
+    }
+    .normalized()
+    .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
     let update_data = UpdateBattlesnake {
         name: request.name.unwrap_or(existing.name),
         url: new_url,
@@ -189,22 +402,27 @@ pub async fn update_snake(
         },
     };
 
-    let snake = battlesnake::update_battlesnake(&state.db, snake_id, user.user_id, update_data)
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|e| internal_error(e.into()))?;
+    let snake = battlesnake::update_battlesnake(&mut *tx, snake_id, user.user_id, update_data)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to update snake: {}", e);
             let msg = e.to_string();
             if msg.contains("already have a battlesnake named") {
                 (StatusCode::CONFLICT, msg)
             } else {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to update snake".to_string(),
-                )
+                internal_error(e)
             }
         })?;
+    battlesnake::set_snake_profile(&mut *tx, snake_id, &profile)
+        .await
+        .map_err(internal_error)?;
+    tx.commit().await.map_err(|e| internal_error(e.into()))?;
 
-    Ok(Json(SnakeResponse::from(snake)))
+    Ok(Json(SnakeResponse::from(snake).with_profile(profile)))
 }
 
 /// DELETE /api/snakes/{id} - Delete snake
@@ -234,3 +452,778 @@ pub async fn delete_snake(
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// 404 unless the snake exists and belongs to the user
+async fn ensure_owns_snake(
+    state: &AppState,
+    snake_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), StatusCode> {
+    let owned = battlesnake::belongs_to_user(&state.db, snake_id, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check snake ownership: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if owned {
+        Ok(())
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// GET /api/snakes/{id}/github - Get the snake's GitHub repository
+pub async fn get_github_repo(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(snake_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    ensure_owns_snake(&state, snake_id, user.user_id).await?;
+
+    let repo = snake_github_repo::get_snake_github_repo(&state.db, snake_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get GitHub repo: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(GitHubRepoResponse::from(repo)))
+}
+
+/// PUT /api/snakes/{id}/github - Link the snake to its GitHub repository
+pub async fn set_github_repo(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(snake_id): Path<Uuid>,
+    Json(request): Json<SetGitHubRepoRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    ensure_owns_snake(&state, snake_id, user.user_id)
+        .await
+        .map_err(|status| (status, "Snake not found".to_string()))?;
+
+    if let Err(e) = validate_github_repo(&request.repo) {
+        return Err((StatusCode::BAD_REQUEST, e.to_string()));
+    }
+    if request.installation_id <= 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "installation_id must be a GitHub App installation ID".to_string(),
+        ));
+    }
+
+    let repo = snake_github_repo::set_snake_github_repo(
+        &state.db,
+        snake_id,
+        &request.repo,
+        request.installation_id,
+        state.clock.now(),
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to set GitHub repo: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to set GitHub repo".to_string(),
+        )
+    })?;
+
+    Ok(Json(GitHubRepoResponse::from(repo)))
+}
+
+/// DELETE /api/snakes/{id}/github - Unlink the snake from its GitHub repository
+pub async fn delete_github_repo(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(snake_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    ensure_owns_snake(&state, snake_id, user.user_id).await?;
+
+    let deleted = snake_github_repo::delete_snake_github_repo(&state.db, snake_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete GitHub repo: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Request and response body for a snake's ranked opt-in
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RankedOptIn {
+    pub opt_in: bool,
+}
+
+/// GET /api/snakes/{id}/ranked - Whether the snake is opted in to ranked games
+pub async fn get_ranked_opt_in(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(snake_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    ensure_owns_snake(&state, snake_id, user.user_id).await?;
+
+    let opt_in = battlesnake::get_ranked_opt_in(&state.db, snake_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get ranked opt-in: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(RankedOptIn { opt_in }))
+}
+
+/// A snake's totals over its finished games
+#[derive(Debug, Serialize)]
+pub struct SnakeStatsResponse {
+    pub games: i32,
+    pub wins: i32,
+    /// None until the snake has finished a game
+    pub average_placement: Option<f64>,
+    pub turns: i64,
+    pub last_played_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<Option<SnakeStatsRollup>> for SnakeStatsResponse {
+    fn from(stats: Option<SnakeStatsRollup>) -> Self {
+        Self {
+            games: stats.as_ref().map_or(0, |s| s.games),
+            wins: stats.as_ref().map_or(0, |s| s.wins),
+            average_placement: stats.as_ref().and_then(|s| s.average_placement()),
+            turns: stats.as_ref().map_or(0, |s| s.turns),
+            last_played_at: stats.map(|s| s.last_played_at),
+        }
+    }
+}
+
+/// GET /api/snakes/{id}/stats - The snake's totals over its finished games
+pub async fn get_snake_stats(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(snake_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    ensure_owns_snake(&state, snake_id, user.user_id).await?;
+
+    let stats = domain_events::get_snake_stats(&state.db, snake_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get snake stats: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(SnakeStatsResponse::from(stats)))
+}
+
+/// PUT /api/snakes/{id}/ranked - Opt the snake in to, or out of, ranked games
+pub async fn set_ranked_opt_in(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(snake_id): Path<Uuid>,
+    Json(request): Json<RankedOptIn>,
+) -> Result<impl IntoResponse, StatusCode> {
+    ensure_owns_snake(&state, snake_id, user.user_id).await?;
+
+    battlesnake::set_ranked_opt_in(&state.db, snake_id, request.opt_in)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to set ranked opt-in: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(request))
+}
+
+/// Longest label a snake version can have
+const MAX_VERSION_LABEL_LEN: usize = 64;
+
+/// Response format for a snake version, with its results in finished games
+#[derive(Debug, Serialize)]
+pub struct VersionResponse {
+    pub id: Uuid,
+    pub label: String,
+    pub url: String,
+    /// New games are attributed to this version
+    pub active: bool,
+    pub games: i64,
+    /// Games it placed first in, shared first places included
+    pub wins: i64,
+    pub average_placement: Option<f64>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<VersionSummary> for VersionResponse {
+    fn from(version: VersionSummary) -> Self {
+        Self {
+            id: version.battlesnake_version_id,
+            label: version.label,
+            url: version.url,
+            active: version.active,
+            games: version.games,
+            wins: version.wins,
+            average_placement: version.average_placement,
+            created_at: version.created_at,
+        }
+    }
+}
+
+/// Request body for creating a snake version
+#[derive(Debug, Deserialize)]
+pub struct CreateVersionRequest {
+    /// e.g. "v2" or a commit SHA
+    pub label: String,
+    /// Defaults to the snake's current URL
+    pub url: Option<String>,
+    /// Point the snake at the version's URL and attribute new games to it
+    #[serde(default = "default_activate")]
+    pub activate: bool,
+}
+
+fn default_activate() -> bool {
+    true
+}
+
+/// Validate a version label: not blank, and short enough to show in a list
+fn validate_version_label(label: &str) -> Result<(), &'static str> {
+    if label.is_empty() {
+        return Err("label can't be blank");
+    }
+    if label.chars().count() > MAX_VERSION_LABEL_LEN {
+        return Err("label can be at most 64 characters");
+    }
+    Ok(())
+}
+
+/// GET /api/snakes/{id}/versions - The snake's versions, newest first, with their results
+pub async fn list_versions(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(snake_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    ensure_owns_snake(&state, snake_id, user.user_id).await?;
+
+    let versions = battlesnake_version::get_version_summaries(&state.db, snake_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list snake versions: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let response: Vec<VersionResponse> = versions.into_iter().map(VersionResponse::from).collect();
+    Ok(Json(response))
+}
+
+/// POST /api/snakes/{id}/versions - Record a new version of the snake
+///
+/// Games created while a version is active are attributed to it. Activating
+/// a version sets the snake's URL to the version's.
+pub async fn create_version(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(snake_id): Path<Uuid>,
+    Json(request): Json<CreateVersionRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let snake = battlesnake::get_battlesnake_by_id(&state.db, snake_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get snake: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to get snake".to_string(),
+            )
+        })?
+        .filter(|snake| snake.user_id == user.user_id)
+        .ok_or((StatusCode::NOT_FOUND, "Snake not found".to_string()))?;
+
+    let label = request.label.trim();
+    if let Err(e) = validate_version_label(label) {
+        return Err((StatusCode::BAD_REQUEST, e.to_string()));
+    }
+    let url = request.url.unwrap_or(snake.url);
+    if let Err(e) = validate_url(&url) {
+        return Err((StatusCode::BAD_REQUEST, e.to_string()));
+    }
+
+    let version =
+        battlesnake_version::create_version(&state.db, snake_id, label, &url, request.activate)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to create snake version: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to create version".to_string(),
+                )
+            })?
+            .ok_or_else(|| {
+                (
+                    StatusCode::CONFLICT,
+                    format!("This snake already has a version labelled '{}'", label),
+                )
+            })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(VersionResponse {
+            id: version.battlesnake_version_id,
+            label: version.label,
+            url: version.url,
+            active: request.activate,
+            games: 0,
+            wins: 0,
+            average_placement: None,
+            created_at: version.created_at,
+        }),
+    ))
+}
+
+/// POST /api/snakes/{id}/versions/{version_id}/activate - Switch the snake to one of its versions
+///
+/// Sets the snake's URL to the version's, so it's also how to roll back.
+pub async fn activate_version(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path((snake_id, version_id)): Path<(Uuid, Uuid)>,
+) -> Result<impl IntoResponse, StatusCode> {
+    ensure_owns_snake(&state, snake_id, user.user_id).await?;
+
+    battlesnake_version::activate_version(&state.db, snake_id, version_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to activate snake version: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/snakes/{id}/diagnose - Check the snake's URL step by step
+///
+/// Calls the snake from this server, so it reports what the game runner sees.
+pub async fn diagnose_snake(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(snake_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let snake = battlesnake::get_battlesnake_by_id(&state.db, snake_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get snake: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if snake.user_id != user.user_id {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(Json(diagnose_url(&snake.url).await))
+}
+
+/// Most snakes a roster import can list
+const MAX_IMPORT_SNAKES: usize = 200;
+
+/// A snake as it appears in an exported or imported roster
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RosterSnake {
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub is_public: bool,
+}
+
+/// Response format for GET /api/snakes/export, and the shape imports take
+#[derive(Debug, Serialize)]
+pub struct RosterResponse {
+    pub snakes: Vec<RosterSnake>,
+}
+
+/// Request body for syncing the user's snakes to a roster
+#[derive(Debug, Deserialize)]
+pub struct ImportSnakesRequest {
+    pub snakes: Vec<RosterSnake>,
+    /// Delete snakes that aren't in the roster
+    #[serde(default)]
+    pub prune: bool,
+    /// Report the changes without making them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RosterAction {
+    Create,
+    Update,
+    Delete,
+    Unchanged,
+}
+
+/// What an import does, or would do, to one snake
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RosterChange {
+    pub action: RosterAction,
+    /// The snake's ID, None for a snake not created yet
+    pub id: Option<Uuid>,
+    pub name: String,
+    pub url: String,
+    pub is_public: bool,
+    /// The URL before an update that changes it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_url: Option<String>,
+    /// Visibility before an update that changes it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_is_public: Option<bool>,
+}
+
+/// Response format for POST /api/snakes/import
+#[derive(Debug, Serialize)]
+pub struct ImportSnakesResponse {
+    pub dry_run: bool,
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    pub unchanged: usize,
+    /// Every snake in the roster or owned by the user, by name
+    pub changes: Vec<RosterChange>,
+}
+
+/// Work out the changes that bring a user's snakes in line with a roster
+///
+/// Snakes are matched by name, which is unique per user. Snakes missing from
+/// the roster are only deleted with `prune`.
+pub(crate) fn plan_roster_changes(
+    existing: &[Battlesnake],
+    roster: &[RosterSnake],
+    prune: bool,
+) -> Result<Vec<RosterChange>, String> {
+    if roster.len() > MAX_IMPORT_SNAKES {
+        return Err(format!(
+            "A roster can list at most {} snakes",
+            MAX_IMPORT_SNAKES
+        ));
+    }
+
+    let mut changes = Vec::new();
+    for (i, snake) in roster.iter().enumerate() {
+        if snake.name.trim().is_empty() {
+            return Err(format!("Snake {} in the roster has no name", i + 1));
+        }
+        if roster[..i].iter().any(|other| other.name == snake.name) {
+            return Err(format!("The roster lists {} more than once", snake.name));
+        }
+        validate_url(&snake.url).map_err(|e| format!("{}: {}", snake.name, e))?;
+
+        let change = match existing.iter().find(|e| e.name == snake.name) {
+            None => RosterChange {
+                action: RosterAction::Create,
+                id: None,
+                name: snake.name.clone(),
+                url: snake.url.clone(),
+                is_public: snake.is_public,
+                previous_url: None,
+                previous_is_public: None,
+            },
+            Some(current) => {
+                let was_public = current.visibility == Visibility::Public;
+                let previous_url = (current.url != snake.url).then(|| current.url.clone());
+                let previous_is_public = (was_public != snake.is_public).then_some(was_public);
+                let action = if previous_url.is_none() && previous_is_public.is_none() {
+                    RosterAction::Unchanged
+                } else {
+                    RosterAction::Update
+                };
+                RosterChange {
+                    action,
+                    id: Some(current.battlesnake_id),
+                    name: snake.name.clone(),
+                    url: snake.url.clone(),
+                    is_public: snake.is_public,
+                    previous_url,
+                    previous_is_public,
+                }
+            }
+        };
+        changes.push(change);
+    }
+
+    if prune {
+        for current in existing {
+            if !roster.iter().any(|snake| snake.name == current.name) {
+                changes.push(RosterChange {
+                    action: RosterAction::Delete,
+                    id: Some(current.battlesnake_id),
+                    name: current.name.clone(),
+                    url: current.url.clone(),
+                    is_public: current.visibility == Visibility::Public,
+                    previous_url: None,
+                    previous_is_public: None,
+                });
+            }
+        }
+    }
+
+    changes.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(changes)
+}
+
+/// GET /api/snakes/export - The user's snakes as a roster for import
+pub async fn export_snakes(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+) -> Result<impl IntoResponse, StatusCode> {
+    let snakes = battlesnake::get_battlesnakes_by_user_id(&state.db, user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to export snakes: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let snakes = snakes
+        .into_iter()
+        .map(|snake| RosterSnake {
+            is_public: snake.visibility == Visibility::Public,
+            name: snake.name,
+            url: snake.url,
+        })
+        .collect();
+
+    Ok(Json(RosterResponse { snakes }))
+}
+
+/// POST /api/snakes/import - Sync the user's snakes to a roster
+///
+/// All the changes are made together, or none are.
+pub async fn import_snakes(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Json(request): Json<ImportSnakesRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let internal_error = |e: color_eyre::Report| {
+        tracing::error!("Failed to import snakes: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to import snakes".to_string(),
+        )
+    };
+
+    let existing = battlesnake::get_battlesnakes_by_user_id(&state.db, user.user_id)
+        .await
+        .map_err(internal_error)?;
+    let mut changes = plan_roster_changes(&existing, &request.snakes, request.prune)
+        .map_err(|message| (StatusCode::BAD_REQUEST, message))?;
+
+    if !request.dry_run {
+        let mut tx = state
+            .db
+            .begin()
+            .await
+            .map_err(|e| internal_error(e.into()))?;
+
+        for change in &mut changes {
+            let visibility = if change.is_public {
+                Visibility::Public
+            } else {
+                Visibility::Private
+            };
+            match (change.action, change.id) {
+                (RosterAction::Create, _) => {
+                    let snake = battlesnake::create_battlesnake(
+                        &mut *tx,
+                        user.user_id,
+                        CreateBattlesnake {
+                            name: change.name.clone(),
+                            url: change.url.clone(),
+                            visibility,
+                        },
+                    )
+                    .await
+                    .map_err(internal_error)?;
+                    change.id = Some(snake.battlesnake_id);
+                }
+                (RosterAction::Update, Some(id)) => {
+                    battlesnake::update_battlesnake(
+                        &mut *tx,
+                        id,
+                        user.user_id,
+                        UpdateBattlesnake {
+                            name: change.name.clone(),
+                            url: change.url.clone(),
+                            visibility,
+                        },
+                    )
+                    .await
+                    .map_err(internal_error)?;
+                }
+                (RosterAction::Delete, Some(id)) => {
+                    battlesnake::delete_battlesnake(&mut *tx, id, user.user_id)
+                        .await
+                        .map_err(internal_error)?;
+                }
+                _ => {}
+            }
+        }
+
+        tx.commit().await.map_err(|e| internal_error(e.into()))?;
+    }
+
+    let count = |action| changes.iter().filter(|c| c.action == action).count();
+    Ok(Json(ImportSnakesResponse {
+        dry_run: request.dry_run,
+        created: count(RosterAction::Create),
+        updated: count(RosterAction::Update),
+        deleted: count(RosterAction::Delete),
+        unchanged: count(RosterAction::Unchanged),
+        changes,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_url() {
+        assert!(validate_url("https://snake.example.com").is_ok());
+        assert!(validate_url("bot:random").is_ok());
+        assert!(validate_url("http://[::1]:8000").is_ok());
+        assert!(validate_url("http://[2001:db8::1]/snake/").is_ok());
+        assert!(validate_url("http://192.168.1.10:65535/api").is_ok());
+        assert!(validate_url("https://example.com/my%20snake?token=a%2Fb").is_ok());
+
+        assert_eq!(
+            validate_url("ftp://snake.example.com"),
+            Err("URL must use HTTP or HTTPS scheme")
+        );
+        assert_eq!(validate_url("not a url"), Err("Invalid URL format"));
+        assert_eq!(validate_url("http://"), Err("URL must include a host"));
+        assert_eq!(
+            validate_url("http://[fe80::1%25eth0]:8000"),
+            Err("Invalid IPv6 address; write it in brackets, like http://[::1]:8000")
+        );
+        assert_eq!(
+            validate_url("http://[::1:8000"),
+            Err("Invalid IPv6 address; write it in brackets, like http://[::1]:8000")
+        );
+        assert_eq!(
+            validate_url("http://example.com:70000"),
+            Err("URL port must be a number from 1 to 65535")
+        );
+        assert_eq!(
+            validate_url("http://example.com:0"),
+            Err("URL port must be a number from 1 to 65535")
+        );
+        assert_eq!(
+            validate_url("https://example.com/#/snake"),
+            Err("URL can't include a fragment (#...)")
+        );
+    }
+
+    #[test]
+    fn test_validate_github_repo() {
+        assert!(validate_github_repo("coreyja/my-snake").is_ok());
+        assert!(validate_github_repo("some_org/snake.rs").is_ok());
+        assert!(validate_github_repo("my-snake").is_err());
+        assert!(validate_github_repo("/my-snake").is_err());
+        assert!(validate_github_repo("owner/name/extra").is_err());
+        assert!(validate_github_repo("owner/name?x=1").is_err());
+    }
+
+    #[test]
+    fn test_validate_version_label() {
+        assert!(validate_version_label("v2").is_ok());
+        assert!(validate_version_label("3f9c2ab").is_ok());
+        assert!(validate_version_label(&"é".repeat(MAX_VERSION_LABEL_LEN)).is_ok());
+        assert!(validate_version_label("").is_err());
+        assert!(validate_version_label(&"a".repeat(MAX_VERSION_LABEL_LEN + 1)).is_err());
+    }
+
+    fn existing_snake(name: &str, url: &str, visibility: Visibility) -> Battlesnake {
+        Battlesnake {
+            battlesnake_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            name: name.to_string(),
+            url: url.to_string(),
+            visibility,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn roster_snake(name: &str, url: &str, is_public: bool) -> RosterSnake {
+        RosterSnake {
+            name: name.to_string(),
+            url: url.to_string(),
+            is_public,
+        }
+    }
+
+    #[test]
+    fn test_plan_roster_changes() {
+        let existing = vec![
+            existing_snake("main", "https://snake.example.com/main", Visibility::Public),
+            existing_snake(
+                "old-branch",
+                "https://snake.example.com/old",
+                Visibility::Private,
+            ),
+            existing_snake(
+                "feature",
+                "https://snake.example.com/v1",
+                Visibility::Private,
+            ),
+        ];
+        let roster = vec![
+            roster_snake("main", "https://snake.example.com/main", true),
+            roster_snake("feature", "https://snake.example.com/v2", true),
+            roster_snake("new-branch", "https://snake.example.com/new", false),
+        ];
+
+        let changes = plan_roster_changes(&existing, &roster, false).unwrap();
+        let actions: Vec<_> = changes
+            .iter()
+            .map(|c| (c.name.as_str(), c.action))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                ("feature", RosterAction::Update),
+                ("main", RosterAction::Unchanged),
+                ("new-branch", RosterAction::Create),
+            ]
+        );
+        assert_eq!(
+            changes[0].previous_url.as_deref(),
+            Some("https://snake.example.com/v1")
+        );
+        assert_eq!(changes[0].previous_is_public, Some(false));
+
+        // Pruning deletes the snake the roster leaves out
+        let changes = plan_roster_changes(&existing, &roster, true).unwrap();
+        let deleted: Vec<_> = changes
+            .iter()
+            .filter(|c| c.action == RosterAction::Delete)
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(deleted, vec![Some(existing[1].battlesnake_id)]);
+    }
+
+    #[test]
+    fn test_plan_roster_changes_rejects_invalid_rosters() {
+        let duplicate = vec![
+            roster_snake("main", "https://snake.example.com/a", true),
+            roster_snake("main", "https://snake.example.com/b", true),
+        ];
+        assert!(plan_roster_changes(&[], &duplicate, false).is_err());
+
+        let bad_url = vec![roster_snake("main", "ftp://snake.example.com", true)];
+        assert!(plan_roster_changes(&[], &bad_url, false).is_err());
+
+        let unnamed = vec![roster_snake(" ", "https://snake.example.com", true)];
+        assert!(plan_roster_changes(&[], &unnamed, false).is_err());
+    }
+}
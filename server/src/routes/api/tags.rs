@@ -0,0 +1,196 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    errors::{ServerResult, WithStatus},
+    models::battlesnake,
+    models::snake_tag::{self, MAX_TAGS_PER_SNAKE, SnakeSearch},
+    models::user_preferences,
+    routes::auth::ApiUser,
+    state::AppState,
+};
+
+/// Most snakes the public snake directory returns
+const MAX_DIRECTORY_RESULTS: i64 = 100;
+
+/// A snake's tags, in requests and responses
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagsBody {
+    pub tags: Vec<String>,
+}
+
+/// Request body for adding one tag to a snake
+#[derive(Debug, Deserialize)]
+pub struct AddTagRequest {
+    pub tag: String,
+}
+
+/// 404 unless the snake exists and belongs to the user
+async fn check_snake_owner(
+    state: &AppState,
+    snake_id: Uuid,
+    user_id: Uuid,
+) -> ServerResult<(), StatusCode> {
+    let owned = battlesnake::belongs_to_user(&state.db, snake_id, user_id).await?;
+
+    if owned {
+        Ok(())
+    } else {
+        Err("Snake not found".to_string()).with_status(StatusCode::NOT_FOUND)
+    }
+}
+
+/// GET /api/snakes/{id}/tags - A snake's tags
+///
+/// Anyone can see a public snake's tags; a private snake's are only for its owner.
+pub async fn list_tags(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(snake_id): Path<Uuid>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let snake = battlesnake::get_battlesnake_by_id(&state.db, snake_id)
+        .await?
+        .filter(|snake| {
+            snake.user_id == user.user_id || snake.visibility == battlesnake::Visibility::Public
+        })
+        .ok_or_else(|| "Snake not found".to_string())
+        .with_status(StatusCode::NOT_FOUND)?;
+
+    let tags = snake_tag::get_snake_tags(&state.db, snake.battlesnake_id).await?;
+    Ok(Json(TagsBody { tags }))
+}
+
+/// PUT /api/snakes/{id}/tags - Replace a snake's tags
+pub async fn set_tags(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(snake_id): Path<Uuid>,
+    Json(request): Json<TagsBody>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    check_snake_owner(&state, snake_id, user.user_id).await?;
+    let tags = snake_tag::normalize_tags(&request.tags).with_status(StatusCode::BAD_REQUEST)?;
+
+    snake_tag::set_snake_tags(&state.db, snake_id, &tags).await?;
+    let tags = snake_tag::get_snake_tags(&state.db, snake_id).await?;
+    Ok(Json(TagsBody { tags }))
+}
+
+/// POST /api/snakes/{id}/tags - Add one tag to a snake
+///
+/// Adding a tag the snake already has changes nothing.
+pub async fn add_tag(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path(snake_id): Path<Uuid>,
+    Json(request): Json<AddTagRequest>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    check_snake_owner(&state, snake_id, user.user_id).await?;
+    let tag = snake_tag::normalize_tag(&request.tag).with_status(StatusCode::BAD_REQUEST)?;
+
+    let mut tags = snake_tag::get_snake_tags(&state.db, snake_id).await?;
+    if !tags.contains(&tag) {
+        if tags.len() >= MAX_TAGS_PER_SNAKE {
+            return Err(format!(
+                "A snake can have up to {} tags",
+                MAX_TAGS_PER_SNAKE
+            ))
+            .with_status(StatusCode::BAD_REQUEST);
+        }
+        tags.push(tag);
+        snake_tag::set_snake_tags(&state.db, snake_id, &tags).await?;
+        tags.sort();
+    }
+
+    Ok((StatusCode::CREATED, Json(TagsBody { tags })))
+}
+
+/// DELETE /api/snakes/{id}/tags/{tag} - Remove a tag from a snake
+pub async fn remove_tag(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Path((snake_id, tag)): Path<(Uuid, String)>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    check_snake_owner(&state, snake_id, user.user_id).await?;
+    // Tags are stored normalized, so "Tree Search" removes "tree-search"
+    let tag = snake_tag::normalize_tag(&tag).with_status(StatusCode::BAD_REQUEST)?;
+
+    let removed = snake_tag::remove_snake_tag(&state.db, snake_id, &tag).await?;
+    if removed {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err("Snake doesn't have that tag".to_string()).with_status(StatusCode::NOT_FOUND)
+    }
+}
+
+/// GET /api/tags - Tags on public snakes, most used first
+pub async fn public_tags(
+    State(state): State<AppState>,
+    ApiUser(_user): ApiUser,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let tags = snake_tag::get_public_tags(&state.db).await?;
+    Ok(Json(tags))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DirectoryQuery {
+    /// Part of the snake's name
+    pub q: Option<String>,
+    /// Comma-separated tags the snakes must all have
+    pub tags: Option<String>,
+}
+
+/// A public snake in the directory
+#[derive(Debug, Serialize)]
+pub struct DirectorySnake {
+    pub id: Uuid,
+    pub name: String,
+    pub url: String,
+    pub tags: Vec<String>,
+}
+
+/// GET /api/snakes/public - The public snake directory, filtered by name and tags
+///
+/// Snakes tagged with any of the user's preferred opponent tags come first.
+pub async fn snake_directory(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    Query(query): Query<DirectoryQuery>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let tags =
+        snake_tag::normalize_tags(&snake_tag::split_tags(query.tags.as_deref().unwrap_or("")))
+            .with_status(StatusCode::BAD_REQUEST)?;
+    let preferences = user_preferences::get_user_preferences(&state.db, user.user_id).await?;
+
+    let snakes = snake_tag::search_public_battlesnakes(
+        &state.db,
+        &SnakeSearch {
+            exclude_user_id: None,
+            name: query.q.as_deref().unwrap_or("").trim(),
+            tags: &tags,
+            preferred_tags: &preferences.preferred_opponent_tags,
+            limit: MAX_DIRECTORY_RESULTS,
+        },
+    )
+    .await?;
+
+    let ids: Vec<Uuid> = snakes.iter().map(|s| s.battlesnake_id).collect();
+    let mut snake_tags = snake_tag::get_tags_for_snakes(&state.db, &ids).await?;
+
+    Ok(Json(
+        snakes
+            .into_iter()
+            .map(|snake| DirectorySnake {
+                tags: snake_tags.remove(&snake.battlesnake_id).unwrap_or_default(),
+                id: snake.battlesnake_id,
+                name: snake.name,
+                url: snake.url,
+            })
+            .collect::<Vec<_>>(),
+    ))
+}
@@ -0,0 +1,79 @@
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{models::game::get_live_public_games, public_url::PublicUrl, state::AppState};
+
+/// Most running games listed at once
+const MAX_LIVE_GAMES: i64 = 20;
+
+/// Response format for GET /api/tv
+#[derive(Debug, Serialize)]
+pub struct TvResponse {
+    /// The game the TV channel is showing, None when no public game is running
+    pub now_playing: Option<TvGame>,
+    /// Every running public game, the longest running first
+    pub games: Vec<TvGame>,
+    /// The TV channel page
+    pub tv_url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TvGame {
+    pub game_id: Uuid,
+    pub board_size: String,
+    pub game_type: String,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// The latest turn played, None before the first
+    pub turn: Option<i32>,
+    pub snakes: Vec<String>,
+    /// The game in the board viewer
+    pub viewer_url: String,
+}
+
+/// The board viewer showing a game, streamed from this arena
+pub fn board_viewer_url(public_url: &PublicUrl, game_id: Uuid) -> String {
+    format!(
+        "https://board.battlesnake.com/?engine={}&game={}&autoplay=true",
+        urlencoding::encode(&public_url.url("/api/v1")),
+        game_id
+    )
+}
+
+/// GET /api/tv - What the TV channel is showing, for "now playing" embeds
+pub async fn now_playing(
+    State(state): State<AppState>,
+    public_url: PublicUrl,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let live = get_live_public_games(&state.db, MAX_LIVE_GAMES)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get live games: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?;
+
+    let games: Vec<TvGame> = live
+        .into_iter()
+        .map(|game| TvGame {
+            viewer_url: board_viewer_url(&public_url, game.game_id),
+            game_id: game.game_id,
+            board_size: game.board_size,
+            game_type: game.game_type,
+            started_at: game.started_at,
+            turn: game.turn,
+            snakes: game.snake_names,
+        })
+        .collect();
+
+    // The longest running game is on until it ends, so every viewer sees the same one
+    let now_playing = games.first().cloned();
+
+    Ok(Json(TvResponse {
+        now_playing,
+        games,
+        tv_url: public_url.url("/tv"),
+    }))
+}
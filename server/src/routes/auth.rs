@@ -54,7 +54,7 @@ impl FromRequestParts<AppState> for CurrentSession {
             Some(id) => id,
             None => {
                 // No session found, create a new one
-                let new_session = match create_session(&app_state.db).await {
+                let new_session = match create_session(&app_state.db, app_state.clock.now()).await {
                     Ok(session) => session,
                     Err(_e) => {
                         tracing::error!("Session creation failed: {}", _e);
@@ -66,8 +66,7 @@ impl FromRequestParts<AppState> for CurrentSession {
                 let mut cookie =
                     Cookie::new(SESSION_COOKIE_NAME, new_session.session_id.to_string());
                 cookie.set_http_only(true);
-                cookie.set_secure(true);
-                cookie.set_same_site(cja::server::cookies::SameSite::Lax);
+                app_state.public_url.apply_cookie_attributes(&mut cookie);
                 cookie.set_max_age(time::Duration::seconds(SESSION_EXPIRATION_SECONDS));
                 cookie_jar.add(cookie);
 
@@ -76,20 +75,21 @@ impl FromRequestParts<AppState> for CurrentSession {
         };
 
         // Get session and user from database
-        let result = match get_session_with_user(&app_state.db, session_id).await {
-            Ok(result) => result,
-            Err(_e) => {
-                tracing::error!("Session fetch failed: {}", _e);
-                return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
-            }
-        };
+        let result =
+            match get_session_with_user(&app_state.db, session_id, app_state.clock.now()).await {
+                Ok(result) => result,
+                Err(_e) => {
+                    tracing::error!("Session fetch failed: {}", _e);
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+                }
+            };
 
         // If session doesn't exist, create a new one
         match result {
             Some((session, user)) => Ok(CurrentSession { session, user }),
             None => {
                 // Session expired or doesn't exist, create a new one
-                let new_session = match create_session(&app_state.db).await {
+                let new_session = match create_session(&app_state.db, app_state.clock.now()).await {
                     Ok(session) => session,
                     Err(_e) => {
                         tracing::error!("Session creation failed: {}", _e);
@@ -101,8 +101,7 @@ impl FromRequestParts<AppState> for CurrentSession {
                 let mut cookie =
                     Cookie::new(SESSION_COOKIE_NAME, new_session.session_id.to_string());
                 cookie.set_http_only(true);
-                cookie.set_secure(true);
-                cookie.set_same_site(cja::server::cookies::SameSite::Lax);
+                app_state.public_url.apply_cookie_attributes(&mut cookie);
                 cookie.set_max_age(time::Duration::seconds(SESSION_EXPIRATION_SECONDS));
                 cookie_jar.add(cookie);
 
@@ -286,3 +285,97 @@ impl FromRequestParts<AppState> for ApiUser {
             .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Authentication required").into_response())
     }
 }
+
+/// Extractor for optionally authenticating via Bearer token OR session cookie
+///
+/// Like ApiUser, but yields None instead of rejecting when there is no valid
+/// token or logged in session. An invalid token is treated the same as no
+/// token. Unlike OptionalUser, no session is created for anonymous requests,
+/// so it's safe to use on endpoints hit by the board viewer.
+pub struct OptionalApiUser(pub Option<User>);
+
+impl FromRequestParts<AppState> for OptionalApiUser {
+    type Rejection = axum::response::Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        if let BearerAuthResult::Authenticated(user) = try_bearer_auth(parts, state).await {
+            return Ok(OptionalApiUser(Some(user)));
+        }
+
+        let cookie_jar = CookieJar::from_request_parts(parts, state)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+
+        let Some(session_id) = cookie_jar
+            .get(SESSION_COOKIE_NAME)
+            .and_then(|cookie| cookie.value().parse::<Uuid>().ok())
+        else {
+            return Ok(OptionalApiUser(None));
+        };
+
+        match get_session_with_user(&state.db, session_id, state.clock.now()).await {
+            Ok(result) => Ok(OptionalApiUser(result.and_then(|(_, user)| user))),
+            Err(e) => {
+                tracing::error!("Session fetch failed: {}", e);
+                Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+            }
+        }
+    }
+}
+
+/// Extractor for the operator endpoints under `/api/admin`
+///
+/// Requires `Authorization: Bearer <ARENA_ADMIN_TOKEN>`. User API tokens and
+/// sessions don't count. Without an admin token configured the endpoints
+/// answer 404, as if they weren't there.
+pub struct AdminApi;
+
+impl FromRequestParts<AppState> for AdminApi {
+    type Rejection = axum::response::Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(admin_token) = &state.admin_token else {
+            return Err(StatusCode::NOT_FOUND.into_response());
+        };
+
+        let token = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        match token {
+            Some(token) if tokens_match(token, admin_token) => Ok(AdminApi),
+            _ => Err((StatusCode::UNAUTHORIZED, "Invalid admin token").into_response()),
+        }
+    }
+}
+
+/// Compare tokens in time that doesn't depend on where they differ
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("secret-token", "secret-token"));
+        assert!(!tokens_match("secret-tokem", "secret-token"));
+        assert!(!tokens_match("secret", "secret-token"));
+        assert!(!tokens_match("", "secret-token"));
+    }
+}
@@ -1,24 +1,67 @@
 use axum::{
     Form,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Redirect},
 };
 use color_eyre::eyre::Context as _;
 use maud::html;
+use serde::Deserialize;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::{
     components::page_factory::PageFactory,
+    diagnose::{CheckStatus, diagnose_url},
     errors::{ServerResult, WithStatus},
-    models::battlesnake::{self, CreateBattlesnake, UpdateBattlesnake, Visibility},
+    markdown,
+    models::battlesnake::{self, CreateBattlesnake, SnakeProfile, UpdateBattlesnake, Visibility},
     models::game_battlesnake,
     models::session,
+    models::snake_alert,
+    models::snake_health::{self, SnakeHealth},
+    models::snake_tag,
     models::user::get_user_by_id,
+    public_url::{PublicUrl, base_path, path},
+    ratings,
+    routes::api::snakes::validate_url,
     routes::auth::{CurrentUser, CurrentUserWithSession},
+    sandbox::{SANDBOX_MAX_TURNS, SandboxBotNameTaken, start_sandbox_game},
     state::AppState,
+    stats::{compute_stats, format_performance},
 };
 
+// The new and edit snake forms: the snake itself plus its profile
+#[derive(Debug, Deserialize)]
+pub struct BattlesnakeForm {
+    name: String,
+    url: String,
+    visibility: Visibility,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    homepage_url: String,
+    #[serde(default)]
+    source_url: String,
+    // Comma separated
+    #[serde(default)]
+    tags: String,
+}
+
+impl BattlesnakeForm {
+    fn profile(&self) -> SnakeProfile {
+        SnakeProfile {
+            description: Some(self.description.clone()),
+            homepage_url: Some(self.homepage_url.clone()),
+            source_url: Some(self.source_url.clone()),
+        }
+    }
+
+    fn tags(&self) -> Result<Vec<String>, String> {
+        snake_tag::normalize_tags(&snake_tag::split_tags(&self.tags))
+    }
+}
+
 // List all battlesnakes for the current user
 pub async fn list_battlesnakes(
     State(state): State<AppState>,
@@ -30,6 +73,15 @@ pub async fn list_battlesnakes(
         .await
         .wrap_err("Failed to get battlesnakes")?;
 
+    // Latest automated health check of each snake
+    let ids: Vec<Uuid> = battlesnakes.iter().map(|s| s.battlesnake_id).collect();
+    let health: HashMap<Uuid, SnakeHealth> = snake_health::get_snake_health(&state.db, &ids)
+        .await
+        .wrap_err("Failed to get snake health")?
+        .into_iter()
+        .map(|h| (h.battlesnake_id, h))
+        .collect();
+
     // Use flash from page_factory (already extracted and cleared from DB)
     let flash = page_factory.flash.clone();
 
@@ -58,6 +110,7 @@ pub async fn list_battlesnakes(
                                     th { "Name" }
                                     th { "URL" }
                                     th { "Visibility" }
+                                    th { "Health" }
                                     th { "Actions" }
                                 }
                             }
@@ -75,10 +128,21 @@ pub async fn list_battlesnakes(
                                                 span class="badge bg-secondary text-white" { "Private" }
                                             }
                                         }
+                                        td class="snake-health" {
+                                            @if let Some(h) = health.get(&snake.battlesnake_id) {
+                                                @if h.healthy {
+                                                    span class="badge bg-success text-white" title=(health_detail(h)) { "● Healthy" }
+                                                } @else {
+                                                    span class="badge bg-danger text-white" title=(health_detail(h)) { "● Down" }
+                                                }
+                                            } @else {
+                                                span class="badge bg-secondary text-white" title="Snakes are checked every 10 minutes" { "Not checked yet" }
+                                            }
+                                        }
                                         td class="actions" {
-                                            a href={"/battlesnakes/"(snake.battlesnake_id)"/profile"} class="btn btn-sm btn-info" { "View" }
-                                            a href={"/battlesnakes/"(snake.battlesnake_id)"/edit"} class="btn btn-sm btn-primary" { "Edit" }
-                                            form action={"/battlesnakes/"(snake.battlesnake_id)"/delete"} method="post" style="display: inline;" {
+                                            a href={(base_path())"/battlesnakes/"(snake.battlesnake_id)"/profile"} class="btn btn-sm btn-info" { "View" }
+                                            a href={(base_path())"/battlesnakes/"(snake.battlesnake_id)"/edit"} class="btn btn-sm btn-primary" { "Edit" }
+                                            form action={(base_path())"/battlesnakes/"(snake.battlesnake_id)"/delete"} method="post" style="display: inline;" {
                                                 button type="submit" class="btn btn-sm btn-danger" onclick="return confirm('Are you sure you want to delete this battlesnake?');" { "Delete" }
                                             }
                                         }
@@ -90,8 +154,8 @@ pub async fn list_battlesnakes(
                 }
 
                 div class="actions" style="margin-top: 20px;" {
-                    a href="/battlesnakes/new" class="btn btn-primary" { "Add New Battlesnake" }
-                    a href="/me" class="btn btn-secondary" { "Back to Profile" }
+                    a href={(base_path())"/battlesnakes/new"} class="btn btn-primary" { "Add New Battlesnake" }
+                    a href={(base_path())"/me"} class="btn btn-secondary" { "Back to Profile" }
                 }
             }
         }),
@@ -99,6 +163,16 @@ pub async fn list_battlesnakes(
     ))
 }
 
+// What a snake's latest health check found, for the indicator's tooltip
+fn health_detail(health: &SnakeHealth) -> String {
+    let checked = health.checked_at.format("%Y-%m-%d %H:%M UTC");
+    match (&health.error, health.latency_ms) {
+        (Some(error), _) => format!("{} (checked {})", error, checked),
+        (None, Some(ms)) => format!("GET / answered in {}ms (checked {})", ms, checked),
+        (None, None) => format!("Built-in bot (checked {})", checked),
+    }
+}
+
 // Show the form to create a new battlesnake
 pub async fn new_battlesnake(
     CurrentUser(_): CurrentUser,
@@ -119,7 +193,7 @@ pub async fn new_battlesnake(
                     }
                 }
 
-                form action="/battlesnakes" method="post" {
+                form action={(base_path())"/battlesnakes"} method="post" {
                     div class="form-group" {
                         label for="name" { "Name" }
                         input type="text" id="name" name="name" class="form-control" required {}
@@ -140,9 +214,31 @@ pub async fn new_battlesnake(
                         small class="form-text text-muted" { "Control who can add this snake to games" }
                     }
 
+                    div class="form-group" {
+                        label for="description" { "Description" }
+                        textarea id="description" name="description" class="form-control" rows="5" maxlength=(battlesnake::MAX_DESCRIPTION_CHARS) {}
+                        small class="form-text text-muted" { "Shown on your snake's profile. Markdown: **bold**, *italics*, `code`, [links](https://...), lists and headings." }
+                    }
+
+                    div class="form-group" {
+                        label for="homepage_url" { "Homepage" }
+                        input type="url" id="homepage_url" name="homepage_url" class="form-control" placeholder="https://" {}
+                    }
+
+                    div class="form-group" {
+                        label for="source_url" { "Source code" }
+                        input type="url" id="source_url" name="source_url" class="form-control" placeholder="https://github.com/you/your-snake" {}
+                    }
+
+                    div class="form-group" {
+                        label for="tags" { "Tags" }
+                        input type="text" id="tags" name="tags" class="form-control" placeholder="rust, tree-search" {}
+                        small class="form-text text-muted" { "Comma separated, like its language or approach. Other users can filter public snakes by tag." }
+                    }
+
                     div class="form-group" style="margin-top: 20px;" {
                         button type="submit" class="btn btn-primary" { "Create Battlesnake" }
-                        a href="/battlesnakes" class="btn btn-secondary" { "Cancel" }
+                        a href={(base_path())"/battlesnakes"} class="btn btn-secondary" { "Cancel" }
                     }
                 }
             }
@@ -155,7 +251,7 @@ pub async fn new_battlesnake(
 pub async fn create_battlesnake(
     State(state): State<AppState>,
     CurrentUserWithSession { user, session }: CurrentUserWithSession,
-    Form(create_data): Form<CreateBattlesnake>,
+    Form(form): Form<BattlesnakeForm>,
 ) -> ServerResult<impl IntoResponse, StatusCode> {
     tracing::info!(
         "create_battlesnake: session_id={}, user_id={}, has_flash={:?}",
@@ -164,12 +260,40 @@ pub async fn create_battlesnake(
         session.flash_message.is_some()
     );
 
+    // Reject URLs the snake client can't call and bad profiles, saying why
+    let checked = validate_url(&form.url)
+        .and_then(|()| form.profile().normalized())
+        .map_err(str::to_string)
+        .and_then(|profile| Ok((profile, form.tags()?)));
+    let (profile, tags) = match checked {
+        Ok(checked) => checked,
+        Err(e) => {
+            session::set_flash_message(&state.db, session.session_id, e, session::FLASH_TYPE_ERROR)
+                .await
+                .wrap_err("Failed to set flash message")?;
+
+            return Ok(Redirect::to(&path("/battlesnakes/new")).into_response());
+        }
+    };
+
     // Create the new battlesnake in the database
+    let create_data = CreateBattlesnake {
+        name: form.name,
+        url: form.url,
+        visibility: form.visibility,
+    };
     let battlesnake_result =
-        battlesnake::create_battlesnake(&state.db, user.user_id, create_data.clone()).await;
+        battlesnake::create_battlesnake(&state.db, user.user_id, create_data).await;
 
     match battlesnake_result {
-        Ok(_) => {
+        Ok(snake) => {
+            battlesnake::set_snake_profile(&state.db, snake.battlesnake_id, &profile)
+                .await
+                .wrap_err("Failed to save snake profile")?;
+            snake_tag::set_snake_tags(&state.db, snake.battlesnake_id, &tags)
+                .await
+                .wrap_err("Failed to save snake tags")?;
+
             // Flash message for success and redirect
             let updated_session = session::set_flash_message(
                 &state.db,
@@ -186,7 +310,7 @@ pub async fn create_battlesnake(
                 updated_session.flash_message
             );
 
-            Ok(Redirect::to("/battlesnakes").into_response())
+            Ok(Redirect::to(&path("/battlesnakes")).into_response())
         }
         Err(err) => {
             // Check if it's a name uniqueness error
@@ -202,7 +326,7 @@ pub async fn create_battlesnake(
                 .wrap_err("Failed to set flash message")?;
 
                 // Redirect back to the form
-                Ok(Redirect::to("/battlesnakes/new").into_response())
+                Ok(Redirect::to(&path("/battlesnakes/new")).into_response())
             } else {
                 // For other errors, propagate them
                 Err(err).wrap_err("Failed to create battlesnake")?
@@ -211,11 +335,19 @@ pub async fn create_battlesnake(
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct EditBattlesnakeQuery {
+    // Sandbox game to show the replay of, set after "Test this snake"
+    sandbox_game: Option<Uuid>,
+}
+
 // Show the form to edit an existing battlesnake
 pub async fn edit_battlesnake(
     State(state): State<AppState>,
     CurrentUser(user): CurrentUser,
     Path(battlesnake_id): Path<Uuid>,
+    Query(query): Query<EditBattlesnakeQuery>,
+    public_url: PublicUrl,
     page_factory: PageFactory,
 ) -> ServerResult<impl IntoResponse, StatusCode> {
     // Get the battlesnake by ID
@@ -231,6 +363,24 @@ pub async fn edit_battlesnake(
             .with_status(StatusCode::FORBIDDEN);
     }
 
+    // Only show sandbox games this snake actually played in
+    let sandbox_game = match query.sandbox_game {
+        Some(game_id) => game_battlesnake::get_battlesnakes_by_game_id(&state.db, game_id)
+            .await
+            .wrap_err("Failed to get sandbox game snakes")?
+            .iter()
+            .any(|gb| gb.battlesnake_id == battlesnake_id)
+            .then_some(game_id),
+        None => None,
+    };
+    let engine_url = public_url.url("/api/v1");
+    let profile = battlesnake::get_snake_profile(&state.db, battlesnake_id)
+        .await
+        .wrap_err("Failed to get snake profile")?;
+    let tags = snake_tag::get_snake_tags(&state.db, battlesnake_id)
+        .await
+        .wrap_err("Failed to get snake tags")?;
+
     // Use flash from page_factory (already extracted and cleared from DB)
     let flash = page_factory.flash.clone();
 
@@ -246,7 +396,7 @@ pub async fn edit_battlesnake(
                     }
                 }
 
-                form action={"/battlesnakes/"(battlesnake_id)"/update"} method="post" {
+                form action={(base_path())"/battlesnakes/"(battlesnake_id)"/update"} method="post" {
                     div class="form-group" {
                         label for="name" { "Name" }
                         input type="text" id="name" name="name" class="form-control" required value=(battlesnake.name) {}
@@ -267,9 +417,69 @@ pub async fn edit_battlesnake(
                         small class="form-text text-muted" { "Control who can add this snake to games" }
                     }
 
+                    div class="form-group" {
+                        label for="description" { "Description" }
+                        textarea id="description" name="description" class="form-control" rows="5" maxlength=(battlesnake::MAX_DESCRIPTION_CHARS) { (profile.description.as_deref().unwrap_or_default()) }
+                        small class="form-text text-muted" { "Shown on your snake's profile. Markdown: **bold**, *italics*, `code`, [links](https://...), lists and headings." }
+                    }
+
+                    div class="form-group" {
+                        label for="homepage_url" { "Homepage" }
+                        input type="url" id="homepage_url" name="homepage_url" class="form-control" placeholder="https://" value=[profile.homepage_url.as_deref()] {}
+                    }
+
+                    div class="form-group" {
+                        label for="source_url" { "Source code" }
+                        input type="url" id="source_url" name="source_url" class="form-control" placeholder="https://github.com/you/your-snake" value=[profile.source_url.as_deref()] {}
+                    }
+
+                    div class="form-group" {
+                        label for="tags" { "Tags" }
+                        input type="text" id="tags" name="tags" class="form-control" placeholder="rust, tree-search" value=(tags.join(", ")) {}
+                        small class="form-text text-muted" { "Comma separated, like its language or approach. Other users can filter public snakes by tag." }
+                    }
+
                     div class="form-group" style="margin-top: 20px;" {
                         button type="submit" class="btn btn-primary" { "Update Battlesnake" }
-                        a href="/battlesnakes" class="btn btn-secondary" { "Cancel" }
+                        a href={(base_path())"/battlesnakes"} class="btn btn-secondary" { "Cancel" }
+                    }
+                }
+
+                div class="card mt-4" id="sandbox" {
+                    div class="card-header" {
+                        h2 class="mb-0" { "Sandbox" }
+                    }
+                    div class="card-body" {
+                        p { "Play a quick 7x7 game (up to " (SANDBOX_MAX_TURNS) " turns) against a built-in bot to check your snake responds." }
+                        form action={(base_path())"/battlesnakes/"(battlesnake_id)"/sandbox"} method="post" {
+                            button type="submit" class="btn btn-success" { "Test this snake" }
+                        }
+
+                        @if let Some(game_id) = sandbox_game {
+                            div class="board-viewer-container mt-3" {
+                                iframe
+                                    id="sandbox-viewer"
+                                    src={ "https://board.battlesnake.com/?engine=" (urlencoding::encode(&engine_url)) "&game=" (game_id) "&autoplay=true" }
+                                    title="Sandbox Game Replay"
+                                    allowfullscreen {}
+                            }
+                            p class="mt-2" {
+                                a href={(base_path())"/games/"(game_id)} { "Open the full game page" }
+                                " for the game log and move latencies."
+                            }
+                        }
+                    }
+                }
+
+                div class="card mt-4" id="diagnose" {
+                    div class="card-header" {
+                        h2 class="mb-0" { "Diagnose" }
+                    }
+                    div class="card-body" {
+                        p { "Snake never moves? Check its URL step by step from the arena's network: DNS, connection, TLS, the response to GET /, and latency." }
+                        form action={(base_path())"/battlesnakes/"(battlesnake_id)"/diagnose"} method="post" {
+                            button type="submit" class="btn btn-info" { "Diagnose this snake" }
+                        }
                     }
                 }
             }
@@ -278,12 +488,116 @@ pub async fn edit_battlesnake(
     ))
 }
 
+// Run a quick sandbox game for a battlesnake and show its replay on the edit page
+pub async fn test_battlesnake(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(battlesnake_id): Path<Uuid>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let battlesnake = battlesnake::get_battlesnake_by_id(&state.db, battlesnake_id)
+        .await
+        .wrap_err("Failed to get battlesnake")?
+        .ok_or_else(|| "Battlesnake not found".to_string())
+        .with_status(StatusCode::NOT_FOUND)?;
+
+    if battlesnake.user_id != user.user_id {
+        return Err("You don't have permission to test this battlesnake".to_string())
+            .with_status(StatusCode::FORBIDDEN);
+    }
+
+    let game_id = match start_sandbox_game(&state, &battlesnake).await {
+        Ok(game_id) => game_id,
+        Err(err) if err.downcast_ref::<SandboxBotNameTaken>().is_some() => {
+            return Err(err).with_status(StatusCode::CONFLICT);
+        }
+        Err(err) => return Err(err.wrap_err("Failed to start sandbox game").into()),
+    };
+
+    Ok(Redirect::to(&path(&format!(
+        "/battlesnakes/{}/edit?sandbox_game={}#sandbox",
+        battlesnake_id, game_id
+    ))))
+}
+
+// Check a battlesnake's URL step by step and show the report
+pub async fn diagnose_battlesnake(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(battlesnake_id): Path<Uuid>,
+    page_factory: PageFactory,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let battlesnake = battlesnake::get_battlesnake_by_id(&state.db, battlesnake_id)
+        .await
+        .wrap_err("Failed to get battlesnake")?
+        .ok_or_else(|| "Battlesnake not found".to_string())
+        .with_status(StatusCode::NOT_FOUND)?;
+
+    if battlesnake.user_id != user.user_id {
+        return Err("You don't have permission to diagnose this battlesnake".to_string())
+            .with_status(StatusCode::FORBIDDEN);
+    }
+
+    let diagnosis = diagnose_url(&battlesnake.url).await;
+
+    Ok(page_factory.create_page(
+        format!("Diagnose Battlesnake: {}", battlesnake.name),
+        Box::new(html! {
+            div class="container" {
+                h1 { "Diagnose Battlesnake: " (battlesnake.name) }
+                p { "Checked " code { (diagnosis.url) } " from the arena's network." }
+
+                @if diagnosis.healthy {
+                    div class="alert alert-success" {
+                        p { "Every check passed or only warned, so the arena can reach your snake." }
+                    }
+                } @else {
+                    div class="alert alert-danger" {
+                        p { "The arena can't use your snake as it is. Fix the first failed check below, then run the diagnosis again." }
+                    }
+                }
+
+                table class="table" {
+                    thead {
+                        tr {
+                            th { "Check" }
+                            th { "Result" }
+                            th { "Details" }
+                        }
+                    }
+                    tbody {
+                        @for check in &diagnosis.checks {
+                            tr {
+                                td { (check.name) }
+                                td {
+                                    span class={"badge text-white " (match check.status {
+                                        CheckStatus::Pass => "bg-success",
+                                        CheckStatus::Warn => "bg-warning",
+                                        CheckStatus::Fail => "bg-danger",
+                                        CheckStatus::Skipped => "bg-secondary",
+                                    })} { (check.status.as_str()) }
+                                }
+                                td { (check.detail) }
+                            }
+                        }
+                    }
+                }
+
+                form action={(base_path())"/battlesnakes/"(battlesnake_id)"/diagnose"} method="post" style="display: inline;" {
+                    button type="submit" class="btn btn-info" { "Run again" }
+                }
+                " "
+                a href={(base_path())"/battlesnakes/"(battlesnake_id)"/edit"} class="btn btn-secondary" { "Back to the snake" }
+            }
+        }),
+    ))
+}
+
 // Handle the update of an existing battlesnake
 pub async fn update_battlesnake(
     State(state): State<AppState>,
     CurrentUserWithSession { user, session }: CurrentUserWithSession,
     Path(battlesnake_id): Path<Uuid>,
-    Form(update_data): Form<UpdateBattlesnake>,
+    Form(form): Form<BattlesnakeForm>,
 ) -> ServerResult<impl IntoResponse, StatusCode> {
     // First check if the battlesnake exists and belongs to the user
     let exists = battlesnake::belongs_to_user(&state.db, battlesnake_id, user.user_id)
@@ -295,17 +609,42 @@ pub async fn update_battlesnake(
             .with_status(StatusCode::FORBIDDEN);
     }
 
+    let checked = validate_url(&form.url)
+        .and_then(|()| form.profile().normalized())
+        .map_err(str::to_string)
+        .and_then(|profile| Ok((profile, form.tags()?)));
+    let (profile, tags) = match checked {
+        Ok(checked) => checked,
+        Err(e) => {
+            session::set_flash_message(&state.db, session.session_id, e, session::FLASH_TYPE_ERROR)
+                .await
+                .wrap_err("Failed to set flash message")?;
+
+            return Ok(
+                Redirect::to(&path(&format!("/battlesnakes/{}/edit", battlesnake_id)))
+                    .into_response(),
+            );
+        }
+    };
+
     // Update the battlesnake
-    let update_result = battlesnake::update_battlesnake(
-        &state.db,
-        battlesnake_id,
-        user.user_id,
-        update_data.clone(),
-    )
-    .await;
+    let update_data = UpdateBattlesnake {
+        name: form.name,
+        url: form.url,
+        visibility: form.visibility,
+    };
+    let update_result =
+        battlesnake::update_battlesnake(&state.db, battlesnake_id, user.user_id, update_data).await;
 
     match update_result {
         Ok(_) => {
+            battlesnake::set_snake_profile(&state.db, battlesnake_id, &profile)
+                .await
+                .wrap_err("Failed to save snake profile")?;
+            snake_tag::set_snake_tags(&state.db, battlesnake_id, &tags)
+                .await
+                .wrap_err("Failed to save snake tags")?;
+
             // Flash message for success and redirect
             session::set_flash_message(
                 &state.db,
@@ -316,7 +655,7 @@ pub async fn update_battlesnake(
             .await
             .wrap_err("Failed to set flash message")?;
 
-            Ok(Redirect::to("/battlesnakes").into_response())
+            Ok(Redirect::to(&path("/battlesnakes")).into_response())
         }
         Err(err) => {
             // Check if it's a name uniqueness error
@@ -332,7 +671,10 @@ pub async fn update_battlesnake(
                 .wrap_err("Failed to set flash message")?;
 
                 // Redirect back to the edit form
-                Ok(Redirect::to(&format!("/battlesnakes/{}/edit", battlesnake_id)).into_response())
+                Ok(
+                    Redirect::to(&path(&format!("/battlesnakes/{}/edit", battlesnake_id)))
+                        .into_response(),
+                )
             } else {
                 // For other errors, propagate them
                 Err(err).wrap_err("Failed to update battlesnake")?
@@ -372,71 +714,13 @@ pub async fn delete_battlesnake(
     .await
     .wrap_err("Failed to set flash message")?;
 
-    Ok(Redirect::to("/battlesnakes").into_response())
-}
-
-struct BattlesnakeStats {
-    total_games: usize,
-    finished_games: usize,
-    wins: usize,
-    second_places: usize,
-    third_places: usize,
-    fourth_places: usize,
-    win_rate: f64,
-    average_placement: f64,
+    Ok(Redirect::to(&path("/battlesnakes")).into_response())
 }
 
-fn compute_stats(history: &[game_battlesnake::GameHistoryEntry]) -> BattlesnakeStats {
-    use crate::models::game::GameStatus;
-
-    let total_games = history.len();
-    let mut finished_games = 0usize;
-    let mut wins = 0usize;
-    let mut second_places = 0usize;
-    let mut third_places = 0usize;
-    let mut fourth_places = 0usize;
-    let mut placement_sum = 0i64;
-    let mut placement_count = 0usize;
-
-    for entry in history {
-        if entry.status == GameStatus::Finished {
-            finished_games += 1;
-            if let Some(placement) = entry.placement {
-                match placement {
-                    1 => wins += 1,
-                    2 => second_places += 1,
-                    3 => third_places += 1,
-                    4 => fourth_places += 1,
-                    _ => {}
-                }
-                placement_sum += i64::from(placement);
-                placement_count += 1;
-            }
-        }
-    }
-
-    let win_rate = if finished_games > 0 {
-        (wins as f64 / finished_games as f64) * 100.0
-    } else {
-        0.0
-    };
-
-    let average_placement = if placement_count > 0 {
-        placement_sum as f64 / placement_count as f64
-    } else {
-        0.0
-    };
-
-    BattlesnakeStats {
-        total_games,
-        finished_games,
-        wins,
-        second_places,
-        third_places,
-        fourth_places,
-        win_rate,
-        average_placement,
-    }
+#[derive(Debug, Deserialize)]
+pub struct ProfileQuery {
+    // "ranked" or "casual" to show only that pool's games; all games otherwise
+    pool: Option<String>,
 }
 
 // View a battlesnake's profile with game history and stats
@@ -445,6 +729,7 @@ pub async fn view_battlesnake_profile(
     State(state): State<AppState>,
     CurrentUser(user): CurrentUser,
     Path(battlesnake_id): Path<Uuid>,
+    Query(query): Query<ProfileQuery>,
     page_factory: PageFactory,
 ) -> ServerResult<impl IntoResponse, StatusCode> {
     // Fetch the battlesnake
@@ -459,17 +744,58 @@ pub async fn view_battlesnake_profile(
         .await
         .wrap_err("Failed to get owner user")?;
 
-    // Fetch game history
-    let history = game_battlesnake::get_game_history_for_battlesnake(&state.db, battlesnake_id)
+    // Fetch game history, from just one pool if asked
+    let pool = query
+        .pool
+        .as_deref()
+        .filter(|p| matches!(*p, "ranked" | "casual"));
+    let mut history = game_battlesnake::get_game_history_for_battlesnake(&state.db, battlesnake_id)
         .await
         .wrap_err("Failed to get game history")?;
+    if let Some(pool) = pool {
+        history.retain(|entry| entry.ranked == (pool == "ranked"));
+    }
 
     let flash = page_factory.flash.clone();
 
     // Compute stats
     let stats = compute_stats(&history);
 
+    // Rate against every finished game, so opponents' strength is taken into account
+    let rating = ratings::current_ratings(&state.db)
+        .await
+        .wrap_err("Failed to get ratings")?
+        .get(&battlesnake_id)
+        .copied()
+        .unwrap_or_default();
+    // Ranked duels are rated on their own as well, for snakes that have played any
+    let duel_rating = ratings::duel_ratings(&state.db)
+        .await
+        .wrap_err("Failed to get duel ratings")?
+        .get(&battlesnake_id)
+        .filter(|r| r.rated_games > 0)
+        .copied();
+
     let is_owner = user.user_id == snake.user_id;
+    let profile = battlesnake::get_snake_profile(&state.db, battlesnake_id)
+        .await
+        .wrap_err("Failed to get snake profile")?;
+    let tags = snake_tag::get_snake_tags(&state.db, battlesnake_id)
+        .await
+        .wrap_err("Failed to get snake tags")?;
+
+    // Win rate alerts are only shown to the snake's owner
+    let (alerts, alert_events) = if is_owner {
+        let alerts = snake_alert::get_alerts_for_battlesnake(&state.db, battlesnake_id)
+            .await
+            .wrap_err("Failed to get snake alerts")?;
+        let events = snake_alert::get_recent_alert_events(&state.db, battlesnake_id, 5)
+            .await
+            .wrap_err("Failed to get snake alert events")?;
+        (alerts, events)
+    } else {
+        (Vec::new(), Vec::new())
+    };
 
     // Owner display info
     let owner_login = owner
@@ -507,16 +833,34 @@ pub async fn view_battlesnake_profile(
                                 } @else {
                                     span class="badge bg-secondary text-white" { "Private" }
                                 }
+                                @if !tags.is_empty() {
+                                    div id="snake-tags" class="mt-2" {
+                                        @for tag in &tags {
+                                            span class="badge bg-info text-dark me-1" { (tag) }
+                                        }
+                                    }
+                                }
                                 p class="mt-2" {
                                     "URL: "
                                     a href=(snake.url) target="_blank" { (snake.url) }
                                 }
+                                @if profile.homepage_url.is_some() || profile.source_url.is_some() {
+                                    p id="snake-links" {
+                                        @if let Some(homepage) = &profile.homepage_url {
+                                            a href=(homepage) rel="nofollow ugc noopener" target="_blank" { "Homepage" }
+                                            " "
+                                        }
+                                        @if let Some(source) = &profile.source_url {
+                                            a href=(source) rel="nofollow ugc noopener" target="_blank" { "Source code" }
+                                        }
+                                    }
+                                }
                                 p { "Created: " (snake.created_at.format("%Y-%m-%d %H:%M")) }
                             }
                             @if is_owner {
                                 div {
-                                    a href={"/battlesnakes/"(battlesnake_id)"/edit"} class="btn btn-sm btn-primary" { "Edit" }
-                                    form action={"/battlesnakes/"(battlesnake_id)"/delete"} method="post" class="inline" style="display: inline;" {
+                                    a href={(base_path())"/battlesnakes/"(battlesnake_id)"/edit"} class="btn btn-sm btn-primary" { "Edit" }
+                                    form action={(base_path())"/battlesnakes/"(battlesnake_id)"/delete"} method="post" class="inline" style="display: inline;" {
                                         button type="submit" class="btn btn-sm btn-danger" onclick="return confirm('Are you sure you want to delete this battlesnake?');" { "Delete" }
                                     }
                                 }
@@ -525,9 +869,27 @@ pub async fn view_battlesnake_profile(
                     }
                 }
 
+                @if let Some(description) = &profile.description {
+                    div class="card mb-4" {
+                        div class="card-body" id="snake-description" {
+                            (markdown::render(description))
+                        }
+                    }
+                }
+
                 // Statistics Section
                 h2 { "Statistics" }
 
+                div class="btn-group mb-3" id="pool-filter" {
+                    @for (label, value) in [("All games", None), ("Ranked", Some("ranked")), ("Casual", Some("casual"))] {
+                        @let href = match value {
+                            Some(value) => format!("{}/battlesnakes/{}/profile?pool={}", base_path(), battlesnake_id, value),
+                            None => format!("{}/battlesnakes/{}/profile", base_path(), battlesnake_id),
+                        };
+                        a href=(href) class=(if pool == value { "btn btn-sm btn-primary" } else { "btn btn-sm btn-secondary" }) { (label) }
+                    }
+                }
+
                 div class="d-flex" style="gap: 16px; flex-wrap: wrap; margin-bottom: 20px;" {
                     div class="card mb-4" style="flex: 1; min-width: 150px;" {
                         div class="card-body" {
@@ -547,6 +909,26 @@ pub async fn view_battlesnake_profile(
                             }
                         }
                     }
+                    div class="card mb-4" style="flex: 1; min-width: 150px;" {
+                        div class="card-body" {
+                            h5 { "Rating" }
+                            p style="font-size: 2em; margin: 0;" { (format!("{:.0}", rating.rating)) }
+                        }
+                    }
+                    @if let Some(duel_rating) = duel_rating {
+                        div class="card mb-4" style="flex: 1; min-width: 150px;" id="duel-rating" {
+                            div class="card-body" {
+                                h5 title="Rated from ranked 1v1 duels alone" { "Duel Rating" }
+                                p style="font-size: 2em; margin: 0;" { (format!("{:.0}", duel_rating.rating)) }
+                            }
+                        }
+                    }
+                    div class="card mb-4" style="flex: 1; min-width: 150px;" {
+                        div class="card-body" {
+                            h5 title="Average places per game finished above or below what opponents' ratings predicted" { "Vs. Expected" }
+                            p style="font-size: 2em; margin: 0;" { (format_performance(rating.performance_vs_expected())) }
+                        }
+                    }
                     div class="card mb-4" style="flex: 1; min-width: 150px;" {
                         div class="card-body" {
                             h5 { "Wins" }
@@ -584,6 +966,38 @@ pub async fn view_battlesnake_profile(
                     }
                 }
 
+                // Win Rate Alerts
+                @if !alerts.is_empty() {
+                    div class="card mb-4" id="win-rate-alerts" {
+                        div class="card-body" {
+                            h5 { "Win Rate Alerts" }
+                            ul {
+                                @for alert in &alerts {
+                                    li {
+                                        "Win rate over the last " (alert.window_games) " games drops more than "
+                                        (format!("{:.0}", alert.drop_percent)) " points"
+                                        @if alert.firing {
+                                            " "
+                                            span class="badge bg-danger text-white" { "Firing" }
+                                        }
+                                    }
+                                }
+                            }
+                            @if !alert_events.is_empty() {
+                                h6 { "Recently Fired" }
+                                ul {
+                                    @for event in &alert_events {
+                                        li {
+                                            (event.created_at.format("%Y-%m-%d %H:%M")) ": "
+                                            (format!("{:.1}% → {:.1}%", event.previous_win_rate, event.current_win_rate))
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
                 // Game History Table
                 h2 { "Game History" }
 
@@ -598,6 +1012,7 @@ pub async fn view_battlesnake_profile(
                                 tr {
                                     th { "Game Type" }
                                     th { "Board Size" }
+                                    th { "Pool" }
                                     th { "Snakes" }
                                     th { "Placement" }
                                     th { "Winner" }
@@ -610,6 +1025,7 @@ pub async fn view_battlesnake_profile(
                                     tr {
                                         td { (entry.game_type.as_str()) }
                                         td { (entry.board_size.as_str()) }
+                                        td { @if entry.ranked { "Ranked" } @else { "Casual" } }
                                         td { (entry.snake_count) }
                                         td {
                                             @if let Some(placement) = entry.placement {
@@ -624,7 +1040,9 @@ pub async fn view_battlesnake_profile(
                                             }
                                         }
                                         td {
-                                            @if let Some(winner) = &entry.winner_name {
+                                            @if entry.draw {
+                                                span class="badge bg-warning text-dark" { "🤝 Draw" }
+                                            } @else if let Some(winner) = &entry.winner_name {
                                                 (winner)
                                             } @else {
                                                 @if entry.status == crate::models::game::GameStatus::Finished {
@@ -636,7 +1054,7 @@ pub async fn view_battlesnake_profile(
                                         }
                                         td { (entry.created_at.format("%Y-%m-%d %H:%M")) }
                                         td {
-                                            a href={"/games/"(entry.game_id)} class="btn btn-sm btn-primary" { "View" }
+                                            a href={(base_path())"/games/"(entry.game_id)} class="btn btn-sm btn-primary" { "View" }
                                         }
                                     }
                                 }
@@ -647,11 +1065,11 @@ pub async fn view_battlesnake_profile(
 
                 // Navigation Links
                 div class="mt-4" {
-                    a href="/games" class="btn btn-primary" { "All Games" }
+                    a href={(base_path())"/games"} class="btn btn-primary" { "All Games" }
                     @if is_owner {
-                        a href="/battlesnakes" class="btn btn-secondary ms-2" { "Your Battlesnakes" }
+                        a href={(base_path())"/battlesnakes"} class="btn btn-secondary ms-2" { "Your Battlesnakes" }
                     }
-                    a href="/me" class="btn btn-secondary ms-2" { "My Profile" }
+                    a href={(base_path())"/me"} class="btn btn-secondary ms-2" { "My Profile" }
                 }
             }
         }),
@@ -0,0 +1,93 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Redirect},
+};
+use color_eyre::eyre::Context as _;
+use maud::html;
+use uuid::Uuid;
+
+use crate::{
+    components::page_factory::PageFactory,
+    demo::{DEMO_MAX_TURNS, start_or_reuse_demo_game},
+    errors::{ServerResult, WithStatus},
+    models::{
+        demo_game::is_demo_game,
+        game::{GameStatus, get_game_by_id},
+    },
+    public_url::{PublicUrl, base_path, path},
+    state::AppState,
+};
+
+// Start a demo game, or pick a recent one, and watch it
+pub async fn start_demo(
+    State(state): State<AppState>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let game_id = start_or_reuse_demo_game(&state)
+        .await
+        .wrap_err("Failed to start demo game")?;
+
+    Ok(Redirect::to(&path(&format!("/demo/{}", game_id))))
+}
+
+// Watch a demo game, no login needed
+pub async fn view_demo(
+    State(state): State<AppState>,
+    Path(game_id): Path<Uuid>,
+    public_url: PublicUrl,
+    page_factory: PageFactory,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    // Only demo games are shown here; other public games need a login
+    let is_demo = is_demo_game(&state.db, game_id)
+        .await
+        .wrap_err("Failed to check for demo game")?;
+    if !is_demo {
+        return Err("Demo game not found".to_string()).with_status(StatusCode::NOT_FOUND);
+    }
+
+    let game = get_game_by_id(&state.db, game_id)
+        .await
+        .wrap_err("Failed to get game")?
+        .ok_or_else(|| "Demo game not found".to_string())
+        .with_status(StatusCode::NOT_FOUND)?;
+    let engine_url = public_url.url("/api/v1");
+
+    Ok(page_factory.create_page(
+        "Demo Game".to_string(),
+        Box::new(html! {
+            div class="container" {
+                h1 { "Demo Game" }
+                p {
+                    "Four of the arena's built-in bots play each other on an 11x11 board, for up to "
+                    (DEMO_MAX_TURNS) " turns. Two head for the nearest food and two move at random."
+                }
+
+                @if game.status == GameStatus::Waiting {
+                    div class="alert alert-info mb-4" id="demo-waiting" {
+                        p class="mb-0" {
+                            "This game is waiting for a free worker. "
+                            a href="" onclick="location.reload(); return false;" { "Refresh" }
+                            " in a moment."
+                        }
+                    }
+                }
+
+                div class="board-viewer-container mb-4" {
+                    iframe
+                        id="board-viewer"
+                        src={ "https://board.battlesnake.com/?engine=" (urlencoding::encode(&engine_url)) "&game=" (game_id) }
+                        title="Battlesnake Board Viewer"
+                        allow="accelerometer; autoplay; clipboard-write; encrypted-media; gyroscope; picture-in-picture"
+                        allowfullscreen {}
+                }
+
+                div class="d-flex gap-2" {
+                    form action={(base_path())"/demo"} method="post" {
+                        button type="submit" class="btn btn-secondary" { "Watch another demo game" }
+                    }
+                    a href={(base_path())"/auth/github"} class="btn btn-primary" { "Login with GitHub to enter your own snake" }
+                }
+            }
+        }),
+    ))
+}
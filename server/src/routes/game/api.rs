@@ -1,25 +1,124 @@
+use std::sync::Arc;
+
 use axum::{
     Json,
     extract::{
-        Path, State, WebSocketUpgrade,
+        Path, Query, State, WebSocketUpgrade,
         ws::{Message, WebSocket},
     },
-    http::StatusCode,
-    response::IntoResponse,
+    http::{HeaderMap, StatusCode, header},
+    response::{
+        IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
 };
-use color_eyre::eyre::Context as _;
+use color_eyre::eyre::{Context as _, eyre};
 use futures::{SinkExt, StreamExt};
-use serde::Serialize;
-use tokio::sync::broadcast;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Notify, broadcast, mpsc};
 use uuid::Uuid;
 
 use crate::{
-    errors::ServerResult,
-    models::game::{GameStatus, get_game_by_id},
-    models::turn::get_turns_by_game_id,
+    errors::{ServerError, ServerResult},
+    frame_delta,
+    models::battlesnake::Visibility,
+    models::game::{Game, GameStatus, get_game_by_id, get_game_settings},
+    models::game_battlesnake::get_battlesnakes_by_game_id,
+    models::game_stream_ticket::validate_game_stream_ticket,
+    models::turn::{Turn, get_turns_from},
+    models::user::User,
+    routes::auth::OptionalApiUser,
     state::AppState,
+    turn_archive,
 };
 
+/// Query params for the board viewer endpoints
+#[derive(Debug, Deserialize)]
+pub struct StreamQuery {
+    /// Stream ticket from `POST /api/games/{id}/stream-ticket`, for clients that
+    /// can't send an Authorization header or session cookie
+    pub ticket: Option<String>,
+    /// Start from the latest keyframe and send deltas, instead of every frame
+    /// since turn 0, for spectators joining a long game part way through
+    #[serde(default)]
+    pub fast_forward: bool,
+}
+
+/// WebSocket subprotocol a client offers in its handshake to be sent `patch`es
+/// instead of whole frames
+const PATCH_PROTOCOL: &str = "arena.patches";
+
+/// Whether a WebSocket upgrade request offers [`PATCH_PROTOCOL`]
+fn offers_patches(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|protocol| protocol.trim() == PATCH_PROTOCOL)
+}
+
+/// Whether a viewer may see a game's frames
+///
+/// Public games are open to anyone, private games only to the owners of the
+/// snakes playing in them.
+pub fn can_view_game(visibility: Visibility, owner_ids: &[Uuid], viewer: Option<Uuid>) -> bool {
+    match visibility {
+        Visibility::Public => true,
+        Visibility::Private => viewer.is_some_and(|viewer| owner_ids.contains(&viewer)),
+    }
+}
+
+/// Load a game, checking the viewer is allowed to see its frames
+///
+/// The viewer is whoever the ticket was issued to, falling back to the user
+/// authenticated by token or session. Returns 404 for unknown games, 401 for
+/// anonymous viewers of private games and 403 for everyone else not allowed in.
+pub async fn authorize_game_viewer(
+    state: &AppState,
+    game_id: Uuid,
+    ticket: Option<&str>,
+    user: Option<&User>,
+) -> ServerResult<Game, StatusCode> {
+    let game = get_game_by_id(&state.db, game_id)
+        .await
+        .wrap_err("Failed to fetch game")?
+        .ok_or_else(|| ServerError(eyre!("Game not found"), StatusCode::NOT_FOUND))?;
+
+    let settings = get_game_settings(&state.db, game_id).await?;
+    if settings.visibility == Visibility::Public {
+        return Ok(game);
+    }
+
+    let ticket_user_id = match ticket {
+        Some(ticket) => {
+            validate_game_stream_ticket(&state.db, ticket, game_id, state.clock.now()).await?
+        }
+        None => None,
+    };
+    let Some(viewer) = ticket_user_id.or(user.map(|u| u.user_id)) else {
+        return Err(ServerError(
+            eyre!("Authentication required to view private game {}", game_id),
+            StatusCode::UNAUTHORIZED,
+        ));
+    };
+
+    let owner_ids: Vec<Uuid> = get_battlesnakes_by_game_id(&state.db, game_id)
+        .await?
+        .iter()
+        .map(|bs| bs.user_id)
+        .collect();
+
+    if !can_view_game(settings.visibility, &owner_ids, Some(viewer)) {
+        return Err(ServerError(
+            eyre!("User {} may not view private game {}", viewer, game_id),
+            StatusCode::FORBIDDEN,
+        ));
+    }
+
+    Ok(game)
+}
+
 /// Response format for the board viewer's game info endpoint
 /// Uses PascalCase to match the Battlesnake board viewer expectations
 #[derive(Debug, Serialize)]
@@ -40,25 +139,37 @@ pub struct BoardViewerGame {
 pub async fn get_game_info(
     State(state): State<AppState>,
     Path(game_id): Path<Uuid>,
+    Query(query): Query<StreamQuery>,
+    OptionalApiUser(user): OptionalApiUser,
 ) -> ServerResult<impl IntoResponse, StatusCode> {
-    let game = get_game_by_id(&state.db, game_id)
-        .await
-        .wrap_err("Failed to fetch game")?
-        .ok_or_else(|| {
-            crate::errors::ServerError(
-                color_eyre::eyre::eyre!("Game not found"),
-                StatusCode::NOT_FOUND,
-            )
-        })?;
+    let game =
+        authorize_game_viewer(&state, game_id, query.ticket.as_deref(), user.as_ref()).await?;
 
+    Ok(board_viewer_game_info(&game))
+}
+
+/// GET /api/viewer/{ticket}/games/{id}
+/// Game info for a board viewer given `/api/viewer/{ticket}` as its engine URL,
+/// as it can't add query params or headers to its requests
+pub async fn viewer_game_info(
+    State(state): State<AppState>,
+    Path((ticket, game_id)): Path<(String, Uuid)>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let game = authorize_game_viewer(&state, game_id, Some(&ticket), None).await?;
+
+    Ok(board_viewer_game_info(&game))
+}
+
+fn board_viewer_game_info(game: &Game) -> Json<BoardViewerGameResponse> {
     let (width, height) = game.board_size.dimensions();
 
-    Ok(Json(BoardViewerGameResponse {
+    Json(BoardViewerGameResponse {
         game: BoardViewerGame { width, height },
-    }))
+    })
 }
 
-/// WebSocket message types for the board viewer
+/// Messages on a game's event stream, sent as JSON over the WebSocket and as
+/// the data of each server-sent event
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct WebSocketMessage {
@@ -68,44 +179,373 @@ pub struct WebSocketMessage {
     pub data: serde_json::Value,
 }
 
+impl WebSocketMessage {
+    fn frame(data: serde_json::Value) -> Self {
+        Self {
+            message_type: "frame".to_string(),
+            data,
+        }
+    }
+
+    /// A whole frame, sent first and every [`frame_delta::KEYFRAME_INTERVAL`]
+    /// turns on a fast-forwarded stream
+    fn keyframe(data: serde_json::Value) -> Self {
+        Self {
+            message_type: "keyframe".to_string(),
+            data,
+        }
+    }
+
+    /// What changed since the frame before, on a fast-forwarded stream
+    fn delta(data: serde_json::Value) -> Self {
+        Self {
+            message_type: "delta".to_string(),
+            data,
+        }
+    }
+
+    /// What changed since the frame before, on a stream that negotiated
+    /// [`PATCH_PROTOCOL`]
+    fn patch(data: serde_json::Value) -> Self {
+        Self {
+            message_type: "patch".to_string(),
+            data,
+        }
+    }
+
+    fn game_end() -> Self {
+        Self {
+            message_type: "game_end".to_string(),
+            data: serde_json::json!({}),
+        }
+    }
+
+    fn error(message: &str) -> Self {
+        Self {
+            message_type: "error".to_string(),
+            data: serde_json::json!({ "message": message }),
+        }
+    }
+
+    fn spectators(count: usize) -> Self {
+        Self {
+            message_type: "spectators".to_string(),
+            data: serde_json::json!({ "Count": count }),
+        }
+    }
+}
+
+/// Messages a client sends on the WebSocket. `{"Type": "keyframe"}` asks for the
+/// frame it's up to in whole, for a client that lost track of the deltas.
+#[derive(Debug, Deserialize)]
+struct ClientMessage {
+    #[serde(rename = "Type")]
+    message_type: String,
+}
+
+/// Messages buffered between a game's event stream and a slow client
+const EVENT_BUFFER: usize = 32;
+
+/// How often a live game's event stream reports how many are watching
+const SPECTATORS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Response format for GET /api/games/{id}/spectators
+#[derive(Debug, Serialize)]
+pub struct SpectatorsResponse {
+    pub game_id: Uuid,
+    /// Clients streaming the game's events over WebSocket or SSE right now
+    pub spectators: usize,
+}
+
+/// GET /api/games/{id}/spectators
+/// How many clients are watching a game, with the same auth as its event stream
+pub async fn game_spectators(
+    State(state): State<AppState>,
+    Path(game_id): Path<Uuid>,
+    Query(query): Query<StreamQuery>,
+    OptionalApiUser(user): OptionalApiUser,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    authorize_game_viewer(&state, game_id, query.ticket.as_deref(), user.as_ref()).await?;
+
+    Ok(Json(SpectatorsResponse {
+        game_id,
+        spectators: state.game_channels.spectator_count(game_id).await,
+    }))
+}
+
 /// GET /api/games/{id}/events
 /// WebSocket endpoint for streaming game frames
+///
+/// Private games need a `ticket` query param, Bearer token or session cookie
+/// on the upgrade request. Clients that offer [`PATCH_PROTOCOL`] get `patch`es.
 pub async fn game_events_websocket(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
     Path(game_id): Path<Uuid>,
-) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_game_websocket(socket, state, game_id))
+    Query(query): Query<StreamQuery>,
+    OptionalApiUser(user): OptionalApiUser,
+    headers: HeaderMap,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    authorize_game_viewer(&state, game_id, query.ticket.as_deref(), user.as_ref()).await?;
+
+    let fast_forward = query.fast_forward;
+    let format = FrameFormat::negotiate(fast_forward, offers_patches(&headers));
+    Ok(ws.protocols([PATCH_PROTOCOL]).on_upgrade(move |socket| {
+        handle_game_websocket(socket, state, game_id, fast_forward, format)
+    }))
+}
+
+/// GET /api/viewer/{ticket}/games/{id}/events
+/// WebSocket endpoint for a board viewer given `/api/viewer/{ticket}` as its engine URL
+pub async fn viewer_game_events_websocket(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Path((ticket, game_id)): Path<(String, Uuid)>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    authorize_game_viewer(&state, game_id, Some(&ticket), None).await?;
+
+    // The board viewer needs every frame to replay from the start
+    Ok(ws.on_upgrade(move |socket| {
+        handle_game_websocket(socket, state, game_id, false, FrameFormat::Whole)
+    }))
+}
+
+/// GET /api/games/{id}/events/sse
+/// Server-sent events with the same messages as the WebSocket, for clients
+/// behind proxies that block WebSockets
+///
+/// Each message is an event named after its `Type`, with the whole message as
+/// its data. Private games need the same auth as the WebSocket.
+pub async fn game_events_sse(
+    State(state): State<AppState>,
+    Path(game_id): Path<Uuid>,
+    Query(query): Query<StreamQuery>,
+    OptionalApiUser(user): OptionalApiUser,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    authorize_game_viewer(&state, game_id, query.ticket.as_deref(), user.as_ref()).await?;
+
+    let (tx, rx) = mpsc::channel(EVENT_BUFFER);
+    let format = FrameFormat::negotiate(query.fast_forward, false);
+    tokio::spawn(send_game_events(
+        state,
+        game_id,
+        query.fast_forward,
+        format,
+        Arc::new(Notify::new()),
+        tx,
+    ));
+
+    let events = futures::stream::unfold(rx, |mut rx| async move {
+        let message = rx.recv().await?;
+        let event = Event::default()
+            .event(message.message_type.clone())
+            .json_data(&message);
+        Some((event, rx))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
 }
 
-async fn handle_game_websocket(socket: WebSocket, state: AppState, game_id: Uuid) {
+async fn handle_game_websocket(
+    socket: WebSocket,
+    state: AppState,
+    game_id: Uuid,
+    fast_forward: bool,
+    format: FrameFormat,
+) {
     let (mut sender, mut receiver) = socket.split();
 
+    let (tx, mut events) = mpsc::channel(EVENT_BUFFER);
+    let keyframe_requested = Arc::new(Notify::new());
+    tokio::spawn(send_game_events(
+        state,
+        game_id,
+        fast_forward,
+        format,
+        keyframe_requested.clone(),
+        tx,
+    ));
+
+    loop {
+        tokio::select! {
+            // Handle incoming WebSocket messages (mostly for ping/pong and close)
+            msg = receiver.next() => {
+                match msg {
+                    Some(Ok(Message::Close(_))) | None => {
+                        // Client disconnected
+                        break;
+                    }
+                    Some(Ok(Message::Ping(data))) => {
+                        if sender.send(Message::Pong(data)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(Message::Text(text))) => {
+                        if serde_json::from_str::<ClientMessage>(text.as_str())
+                            .is_ok_and(|message| message.message_type == "keyframe")
+                        {
+                            keyframe_requested.notify_one();
+                        }
+                    }
+                    Some(Ok(_)) => {
+                        // Ignore other messages
+                    }
+                    Some(Err(_)) => {
+                        // Connection error
+                        break;
+                    }
+                }
+            }
+            event = events.recv() => {
+                // None once the stream is over: the game ended or an error was sent
+                let Some(event) = event else {
+                    break;
+                };
+                if sender
+                    .send(Message::Text(serde_json::to_string(&event).unwrap().into()))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// How a stream sends its frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameFormat {
+    /// Each one whole as a `frame`
+    Whole,
+    /// A `keyframe`, then a `delta` for each turn, for `?fast_forward=true`
+    Delta,
+    /// A `keyframe`, then a `patch` for each turn, for WebSocket clients that
+    /// negotiated [`PATCH_PROTOCOL`]
+    Patch,
+}
+
+impl FrameFormat {
+    fn negotiate(fast_forward: bool, patches: bool) -> Self {
+        match (fast_forward, patches) {
+            (_, true) => Self::Patch,
+            (true, false) => Self::Delta,
+            (false, false) => Self::Whole,
+        }
+    }
+}
+
+/// Turns a stream's frames into messages in its [`FrameFormat`], with a
+/// `keyframe` every [`frame_delta::KEYFRAME_INTERVAL`] turns unless it sends
+/// them whole
+struct FrameEncoder {
+    format: FrameFormat,
+    last_frame: Option<serde_json::Value>,
+}
+
+impl FrameEncoder {
+    fn new(format: FrameFormat) -> Self {
+        Self {
+            format,
+            last_frame: None,
+        }
+    }
+
+    fn message(&mut self, turn_number: i32, frame: serde_json::Value) -> WebSocketMessage {
+        if self.format == FrameFormat::Whole {
+            return WebSocketMessage::frame(frame);
+        }
+
+        let message = match &self.last_frame {
+            Some(last) if !frame_delta::is_keyframe(turn_number) => match self.format {
+                FrameFormat::Patch => WebSocketMessage::patch(frame_delta::patch(last, &frame)),
+                _ => WebSocketMessage::delta(frame_delta::diff(last, &frame)),
+            },
+            _ => WebSocketMessage::keyframe(frame.clone()),
+        };
+        self.last_frame = Some(frame);
+        message
+    }
+
+    /// The last frame sent, whole, for a client that asked for a keyframe.
+    /// Later deltas follow on from it.
+    fn keyframe(&self) -> Option<WebSocketMessage> {
+        self.last_frame.clone().map(WebSocketMessage::keyframe)
+    }
+}
+
+/// Frames of a running game's turns from `from_turn` on: those the runner
+/// hasn't stored yet from memory, the rest from the database
+async fn frames_from(
+    state: &AppState,
+    game_id: Uuid,
+    from_turn: i32,
+) -> cja::Result<Vec<(i32, serde_json::Value)>> {
+    let unstored = state
+        .game_channels
+        .unstored_frames(game_id, from_turn)
+        .await;
+    if unstored.first().is_some_and(|(turn, _)| *turn == from_turn) {
+        return Ok(unstored);
+    }
+
+    let mut frames: Vec<(i32, serde_json::Value)> = get_turns_from(&state.db, game_id, from_turn)
+        .await?
+        .into_iter()
+        .filter_map(|turn| turn.frame_data.map(|frame| (turn.turn_number, frame)))
+        .collect();
+    let last_stored = frames.last().map_or(from_turn - 1, |(turn, _)| *turn);
+    frames.extend(unstored.into_iter().filter(|(turn, _)| *turn > last_stored));
+    Ok(frames)
+}
+
+/// The stored turns a new stream starts with: all of them, or when
+/// fast-forwarding, those from the latest keyframe on
+async fn catch_up_turns(
+    state: &AppState,
+    game_id: Uuid,
+    fast_forward: bool,
+) -> cja::Result<Vec<Turn>> {
+    if !fast_forward {
+        return turn_archive::load_turns(state, game_id).await;
+    }
+
+    match turn_archive::load_turn_at(state, game_id, None).await? {
+        Some(latest) => {
+            let from = frame_delta::keyframe_turn(latest.turn_number);
+            turn_archive::load_turns_from(state, game_id, from).await
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Produce a game's event stream, shared by the WebSocket and SSE endpoints
+///
+/// Sends every frame so far, then each new frame as the runner plays it, then
+/// `game_end` once the game is finished. With `fast_forward` it starts from the
+/// latest keyframe instead. Frames are sent in `format`. While the game is live
+/// it also sends `spectators` with how many are watching, straight away and
+/// then every [`SPECTATORS_INTERVAL`], and a `keyframe` whenever
+/// `keyframe_requested` is notified. Stops as soon as the client goes away and
+/// `tx` closes.
+async fn send_game_events(
+    state: AppState,
+    game_id: Uuid,
+    fast_forward: bool,
+    format: FrameFormat,
+    keyframe_requested: Arc<Notify>,
+    tx: mpsc::Sender<WebSocketMessage>,
+) {
     // Check if game exists
     let game = match get_game_by_id(&state.db, game_id).await {
         Ok(Some(game)) => game,
         Ok(None) => {
-            let error_msg = WebSocketMessage {
-                message_type: "error".to_string(),
-                data: serde_json::json!({"message": "Game not found"}),
-            };
-            let _ = sender
-                .send(Message::Text(
-                    serde_json::to_string(&error_msg).unwrap().into(),
-                ))
-                .await;
+            let _ = tx.send(WebSocketMessage::error("Game not found")).await;
             return;
         }
         Err(e) => {
-            tracing::error!(error = ?e, "Failed to fetch game for WebSocket");
-            let error_msg = WebSocketMessage {
-                message_type: "error".to_string(),
-                data: serde_json::json!({"message": "Internal server error"}),
-            };
-            let _ = sender
-                .send(Message::Text(
-                    serde_json::to_string(&error_msg).unwrap().into(),
-                ))
+            tracing::error!(error = ?e, "Failed to fetch game for event stream");
+            let _ = tx
+                .send(WebSocketMessage::error("Internal server error"))
                 .await;
             return;
         }
@@ -114,19 +554,17 @@ async fn handle_game_websocket(socket: WebSocket, state: AppState, game_id: Uuid
     // Subscribe to broadcast channel FIRST (buffer incoming notifications)
     let mut broadcast_receiver = state.game_channels.subscribe(game_id).await;
 
+    // Then the turns the runner hasn't stored yet. Taken before the stored
+    // turns, so a batch stored in between is in one or the other.
+    let unstored = state.game_channels.unstored_frames(game_id, 0).await;
+
     // Fetch existing frames from database
-    let existing_turns = match get_turns_by_game_id(&state.db, game_id).await {
+    let existing_turns = match catch_up_turns(&state, game_id, fast_forward).await {
         Ok(turns) => turns,
         Err(e) => {
-            tracing::error!(error = ?e, "Failed to fetch turns for WebSocket");
-            let error_msg = WebSocketMessage {
-                message_type: "error".to_string(),
-                data: serde_json::json!({"message": "Failed to fetch game frames"}),
-            };
-            let _ = sender
-                .send(Message::Text(
-                    serde_json::to_string(&error_msg).unwrap().into(),
-                ))
+            tracing::error!(error = ?e, "Failed to fetch turns for event stream");
+            let _ = tx
+                .send(WebSocketMessage::error("Failed to fetch game frames"))
                 .await;
             return;
         }
@@ -134,143 +572,112 @@ async fn handle_game_websocket(socket: WebSocket, state: AppState, game_id: Uuid
 
     // Track the last turn we sent
     let mut last_sent_turn = -1i32;
+    let mut encoder = FrameEncoder::new(format);
 
     // Send all existing frames
     for turn in existing_turns {
         if let Some(frame_data) = turn.frame_data {
-            let frame_msg = WebSocketMessage {
-                message_type: "frame".to_string(),
-                data: frame_data,
-            };
-            if sender
-                .send(Message::Text(
-                    serde_json::to_string(&frame_msg).unwrap().into(),
-                ))
-                .await
-                .is_err()
-            {
+            let message = encoder.message(turn.turn_number, frame_data);
+            if tx.send(message).await.is_err() {
                 // Client disconnected
                 return;
             }
             last_sent_turn = turn.turn_number;
         }
     }
+    for (turn_number, frame_data) in unstored {
+        if turn_number <= last_sent_turn {
+            continue;
+        }
+        if tx
+            .send(encoder.message(turn_number, frame_data))
+            .await
+            .is_err()
+        {
+            return;
+        }
+        last_sent_turn = turn_number;
+    }
 
-    // If game is finished, send game_end and close
+    // If game is finished, send game_end and stop
     if game.status == GameStatus::Finished {
-        let end_msg = WebSocketMessage {
-            message_type: "game_end".to_string(),
-            data: serde_json::json!({}),
-        };
-        let _ = sender
-            .send(Message::Text(
-                serde_json::to_string(&end_msg).unwrap().into(),
-            ))
-            .await;
+        let _ = tx.send(WebSocketMessage::game_end()).await;
         return;
     }
 
-    // For running games, listen for new frames
+    // For running games, listen for new frames. The first tick is immediate.
+    let mut spectators_tick = tokio::time::interval(SPECTATORS_INTERVAL);
     loop {
-        tokio::select! {
-            // Handle incoming WebSocket messages (mostly for ping/pong and close)
-            msg = receiver.next() => {
-                match msg {
-                    Some(Ok(Message::Close(_))) | None => {
-                        // Client disconnected
-                        break;
-                    }
-                    Some(Ok(Message::Ping(data))) => {
-                        if sender.send(Message::Pong(data)).await.is_err() {
-                            break;
-                        }
-                    }
-                    Some(Ok(_)) => {
-                        // Ignore other messages
-                    }
-                    Some(Err(_)) => {
-                        // Connection error
-                        break;
-                    }
+        let notification = tokio::select! {
+            _ = tx.closed() => return,
+            _ = spectators_tick.tick() => {
+                let count = state.game_channels.spectator_count(game_id).await;
+                if tx.send(WebSocketMessage::spectators(count)).await.is_err() {
+                    return;
+                }
+                continue;
+            }
+            _ = keyframe_requested.notified() => {
+                if let Some(keyframe) = encoder.keyframe()
+                    && tx.send(keyframe).await.is_err()
+                {
+                    return;
                 }
+                continue;
             }
-            // Handle broadcast notifications
-            notification = broadcast_receiver.recv() => {
-                match notification {
-                    Ok(turn_notification) => {
-                        // Skip if we've already sent this turn
-                        if turn_notification.turn_number <= last_sent_turn {
+            notification = broadcast_receiver.recv() => notification,
+        };
+
+        match notification {
+            Ok(turn_notification) => {
+                // Skip if we've already sent this turn
+                if turn_notification.turn_number <= last_sent_turn {
+                    continue;
+                }
+
+                // Fetch the frame data, from memory until the runner stores it
+                if let Ok(frames) =
+                    frames_from(&state, game_id, turn_notification.turn_number).await
+                {
+                    for (turn_number, frame_data) in frames {
+                        if turn_number <= last_sent_turn {
                             continue;
                         }
-
-                        // Fetch the frame data from DB
-                        if let Ok(turns) = crate::models::turn::get_turns_from(
-                            &state.db,
-                            game_id,
-                            turn_notification.turn_number
-                        ).await {
-                            for turn in turns {
-                                if turn.turn_number <= last_sent_turn {
-                                    continue;
-                                }
-                                if let Some(frame_data) = turn.frame_data {
-                                    let frame_msg = WebSocketMessage {
-                                        message_type: "frame".to_string(),
-                                        data: frame_data,
-                                    };
-                                    if sender
-                                        .send(Message::Text(serde_json::to_string(&frame_msg).unwrap().into()))
-                                        .await
-                                        .is_err()
-                                    {
-                                        return;
-                                    }
-                                    last_sent_turn = turn.turn_number;
-                                }
-                            }
+                        let message = encoder.message(turn_number, frame_data);
+                        if tx.send(message).await.is_err() {
+                            return;
                         }
-
-                        // Check if game is now finished
-                        if let Ok(Some(game)) = get_game_by_id(&state.db, game_id).await
-                            && game.status == GameStatus::Finished {
-                                let end_msg = WebSocketMessage {
-                                    message_type: "game_end".to_string(),
-                                    data: serde_json::json!({}),
-                                };
-                                let _ = sender
-                                    .send(Message::Text(serde_json::to_string(&end_msg).unwrap().into()))
-                                    .await;
-                                return;
-                            }
-                    }
-                    Err(broadcast::error::RecvError::Lagged(count)) => {
-                        // We fell behind - close and let client reconnect
-                        tracing::warn!(game_id = %game_id, lagged = count, "WebSocket lagged, closing");
-                        let error_msg = WebSocketMessage {
-                            message_type: "error".to_string(),
-                            data: serde_json::json!({"message": "Connection lagged, please reconnect"}),
-                        };
-                        let _ = sender
-                            .send(Message::Text(serde_json::to_string(&error_msg).unwrap().into()))
-                            .await;
-                        return;
-                    }
-                    Err(broadcast::error::RecvError::Closed) => {
-                        // Channel closed (game ended or channel cleanup)
-                        // Check final game state
-                        if let Ok(Some(game)) = get_game_by_id(&state.db, game_id).await
-                            && game.status == GameStatus::Finished {
-                                let end_msg = WebSocketMessage {
-                                    message_type: "game_end".to_string(),
-                                    data: serde_json::json!({}),
-                                };
-                                let _ = sender
-                                    .send(Message::Text(serde_json::to_string(&end_msg).unwrap().into()))
-                                    .await;
-                            }
-                        return;
+                        last_sent_turn = turn_number;
                     }
                 }
+
+                // Check if game is now finished
+                if let Ok(Some(game)) = get_game_by_id(&state.db, game_id).await
+                    && game.status == GameStatus::Finished
+                {
+                    let _ = tx.send(WebSocketMessage::game_end()).await;
+                    return;
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                // We fell behind - stop and let client reconnect
+                tracing::warn!(game_id = %game_id, lagged = count, "Event stream lagged, closing");
+                let _ = tx
+                    .send(WebSocketMessage::error(
+                        "Connection lagged, please reconnect",
+                    ))
+                    .await;
+                return;
+            }
+            Err(broadcast::error::RecvError::Closed) => {
+                // Channel closed (game ended or channel cleanup)
+                // Check final game state
+                if let Ok(Some(game)) = get_game_by_id(&state.db, game_id).await
+                    && game.status == GameStatus::Finished
+                {
+                    let _ = tx.send(WebSocketMessage::game_end()).await;
+                }
+                return;
             }
         }
     }
@@ -304,4 +711,85 @@ mod tests {
         assert!(json.contains("\"Type\":\"frame\""));
         assert!(json.contains("\"Data\""));
     }
+
+    #[test]
+    fn test_frame_encoder_sends_keyframes_and_deltas() {
+        let frame = |turn: i32| serde_json::json!({ "Turn": turn, "Food": [], "Snakes": [] });
+
+        let mut whole = FrameEncoder::new(FrameFormat::Whole);
+        assert_eq!(whole.message(437, frame(437)).message_type, "frame");
+
+        // A fast-forwarded stream joining at turn 437 starts from turn 400
+        let mut fast = FrameEncoder::new(FrameFormat::Delta);
+        let first = fast.message(400, frame(400));
+        assert_eq!(first.message_type, "keyframe");
+        assert_eq!(first.data, frame(400));
+
+        let next = fast.message(401, frame(401));
+        assert_eq!(next.message_type, "delta");
+        assert_eq!(next.data, serde_json::json!({ "Turn": 401 }));
+
+        assert_eq!(fast.message(450, frame(450)).message_type, "keyframe");
+    }
+
+    #[test]
+    fn test_frame_encoder_sends_patches() {
+        let frame = |turn: i32, x: i32| {
+            serde_json::json!({
+                "Turn": turn,
+                "Food": [],
+                "Snakes": [{ "ID": "a", "Body": [{ "X": x, "Y": 0 }, { "X": x - 1, "Y": 0 }] }]
+            })
+        };
+
+        let mut encoder = FrameEncoder::new(FrameFormat::Patch);
+        assert!(encoder.keyframe().is_none());
+        assert_eq!(encoder.message(0, frame(0, 1)).message_type, "keyframe");
+
+        let next = encoder.message(1, frame(1, 2));
+        assert_eq!(next.message_type, "patch");
+        assert_eq!(
+            next.data,
+            serde_json::json!({
+                "Turn": 1,
+                "Snakes": [{ "ID": "a", "Head": [{ "X": 2, "Y": 0 }], "Tail": 1 }]
+            })
+        );
+
+        // Asked for, the keyframe is the frame the client is up to
+        let keyframe = encoder.keyframe().unwrap();
+        assert_eq!(keyframe.message_type, "keyframe");
+        assert_eq!(keyframe.data, frame(1, 2));
+    }
+
+    #[test]
+    fn test_patches_are_negotiated_by_subprotocol() {
+        let mut headers = HeaderMap::new();
+        assert!(!offers_patches(&headers));
+
+        headers.insert(
+            header::SEC_WEBSOCKET_PROTOCOL,
+            "graphql-ws, arena.patches".parse().unwrap(),
+        );
+        assert!(offers_patches(&headers));
+
+        assert_eq!(FrameFormat::negotiate(false, false), FrameFormat::Whole);
+        assert_eq!(FrameFormat::negotiate(true, false), FrameFormat::Delta);
+        assert_eq!(FrameFormat::negotiate(true, true), FrameFormat::Patch);
+        assert_eq!(FrameFormat::negotiate(false, true), FrameFormat::Patch);
+    }
+
+    #[test]
+    fn test_can_view_game() {
+        let owner = Uuid::new_v4();
+        let stranger = Uuid::new_v4();
+        let owners = [owner, Uuid::new_v4()];
+
+        assert!(can_view_game(Visibility::Public, &owners, None));
+        assert!(can_view_game(Visibility::Public, &owners, Some(stranger)));
+
+        assert!(can_view_game(Visibility::Private, &owners, Some(owner)));
+        assert!(!can_view_game(Visibility::Private, &owners, Some(stranger)));
+        assert!(!can_view_game(Visibility::Private, &owners, None));
+    }
 }
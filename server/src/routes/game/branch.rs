@@ -0,0 +1,78 @@
+use axum::{
+    Form,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Redirect},
+};
+use color_eyre::eyre::Context as _;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    branch::{BranchError, BranchRequest, MoveOverride, create_branch},
+    errors::{ServerResult, WithStatus},
+    models::game::get_game_settings,
+    models::game_battlesnake,
+    models::session,
+    public_url::path,
+    routes::{auth::CurrentUserWithSession, game::api::can_view_game},
+    state::AppState,
+};
+
+// The "What If?" form on the game page: change one snake's move on one turn
+#[derive(Debug, Deserialize)]
+pub struct BranchForm {
+    pub turn: i32,
+    pub snake_id: Uuid,
+    pub direction: String,
+}
+
+// Branch a finished game from the game page and go watch the branch
+pub async fn branch_game(
+    State(state): State<AppState>,
+    CurrentUserWithSession { user, session }: CurrentUserWithSession,
+    Path(game_id): Path<Uuid>,
+    Form(form): Form<BranchForm>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game_id)
+        .await
+        .wrap_err("Failed to get battlesnakes for game")?;
+    let settings = get_game_settings(&state.db, game_id)
+        .await
+        .wrap_err("Failed to get game settings")
+        .with_status(StatusCode::NOT_FOUND)?;
+    let owner_ids: Vec<Uuid> = battlesnakes.iter().map(|bs| bs.user_id).collect();
+    if !can_view_game(settings.visibility, &owner_ids, Some(user.user_id)) {
+        return Err(color_eyre::eyre::eyre!(
+            "Private game {} is not visible to this user",
+            game_id
+        ))
+        .with_status(StatusCode::NOT_FOUND);
+    }
+
+    let request = BranchRequest {
+        turn: form.turn,
+        moves: vec![MoveOverride {
+            snake_id: form.snake_id,
+            turn: form.turn,
+            direction: form.direction,
+        }],
+    };
+
+    match create_branch(&state, user.user_id, game_id, &request).await {
+        Ok(branch_id) => Ok(Redirect::to(&path(&format!("/games/{}", branch_id))).into_response()),
+        Err(BranchError::Invalid(message) | BranchError::RateLimited(message)) => {
+            session::set_flash_message(
+                &state.db,
+                session.session_id,
+                message,
+                session::FLASH_TYPE_ERROR,
+            )
+            .await
+            .wrap_err("Failed to set flash message")?;
+
+            Ok(Redirect::to(&path(&format!("/games/{}#branches", game_id))).into_response())
+        }
+        Err(BranchError::Internal(err)) => Err(err).wrap_err("Failed to branch game")?,
+    }
+}
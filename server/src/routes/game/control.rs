@@ -0,0 +1,107 @@
+use axum::{
+    Form,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Redirect},
+};
+use color_eyre::eyre::Context as _;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    errors::{ServerResult, WithStatus},
+    game_control::{ControlAction, ControlError, control_game},
+    models::game::get_game_settings,
+    models::game_battlesnake,
+    models::session,
+    public_url::path,
+    routes::auth::CurrentUserWithSession,
+    state::AppState,
+};
+
+// The pause, step and resume buttons on the game page
+#[derive(Debug, Deserialize)]
+pub struct ControlForm {
+    pub action: String,
+}
+
+// Whether a user can pause a game: they own one of its snakes, and it's not
+// ranked, so nobody can stall a rated game against them
+pub fn can_control_game(ranked: bool, owner_ids: &[Uuid], user_id: Uuid) -> bool {
+    !ranked && owner_ids.contains(&user_id)
+}
+
+// Pause, step or resume a game from the game page
+pub async fn control_game_form(
+    State(state): State<AppState>,
+    CurrentUserWithSession { user, session }: CurrentUserWithSession,
+    Path(game_id): Path<Uuid>,
+    Form(form): Form<ControlForm>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let action = match form.action.as_str() {
+        "pause" => ControlAction::Pause,
+        "step" => ControlAction::Step,
+        "resume" => ControlAction::Resume,
+        _ => {
+            return Err(color_eyre::eyre::eyre!(
+                "Unknown game control {}",
+                form.action
+            ))
+            .with_status(StatusCode::BAD_REQUEST);
+        }
+    };
+
+    let battlesnakes = game_battlesnake::get_battlesnakes_by_game_id(&state.db, game_id)
+        .await
+        .wrap_err("Failed to get battlesnakes for game")?;
+    let settings = get_game_settings(&state.db, game_id)
+        .await
+        .wrap_err("Failed to get game settings")
+        .with_status(StatusCode::NOT_FOUND)?;
+    let owner_ids: Vec<Uuid> = battlesnakes.iter().map(|bs| bs.user_id).collect();
+    if !can_control_game(settings.ranked, &owner_ids, user.user_id) {
+        return Err(color_eyre::eyre::eyre!(
+            "User {} can't control game {}",
+            user.user_id,
+            game_id
+        ))
+        .with_status(StatusCode::FORBIDDEN);
+    }
+
+    match control_game(&state, game_id, action).await {
+        Ok(_) => {}
+        Err(ControlError::NotFound) => {
+            return Err(color_eyre::eyre::eyre!("Game {} not found", game_id))
+                .with_status(StatusCode::NOT_FOUND);
+        }
+        Err(ControlError::Invalid(message)) => {
+            session::set_flash_message(
+                &state.db,
+                session.session_id,
+                message,
+                session::FLASH_TYPE_ERROR,
+            )
+            .await
+            .wrap_err("Failed to set flash message")?;
+        }
+        Err(ControlError::Internal(err)) => {
+            Err(err).wrap_err_with(|| format!("Failed to {} game", action.as_str()))?
+        }
+    }
+
+    Ok(Redirect::to(&path(&format!("/games/{}#game-controls", game_id))).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_can_control_game() {
+        let owner = Uuid::from_u128(1);
+        let other = Uuid::from_u128(2);
+        assert!(can_control_game(false, &[owner, other], owner));
+        assert!(!can_control_game(false, &[other], owner));
+        assert!(!can_control_game(true, &[owner], owner));
+    }
+}
@@ -5,21 +5,30 @@ use axum::{
     response::{IntoResponse, Redirect},
 };
 use axum_macros::debug_handler;
-use color_eyre::eyre::Context as _;
+use color_eyre::eyre::{Context as _, eyre};
 use maud::html;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::str::FromStr;
 use uuid::Uuid;
 
 use crate::{
     components::flash::Flash,
     components::page_factory::PageFactory,
+    engine::DEFAULT_MOVE_TIMEOUT_MS,
     errors::{ServerResult, WithStatus},
+    game_settings::{self, GameLimits},
     models::flow::GameCreationFlow,
-    models::game::{GameBoardSize, GameType},
+    models::game::{GameBoardSize, GameType, PRACTICE_MAX_TURNS},
     models::session,
+    models::snake_tag,
+    models::snake_template::get_archived_snake_ids,
+    models::user_preferences::get_user_preferences,
+    public_url::{base_path, path},
+    ratings,
     routes::auth::{CurrentUser, CurrentUserWithSession},
     state::AppState,
+    stats::{INITIAL_RATING, placement_probabilities},
 };
 
 // Initial game creation page - redirect to a new flow
@@ -28,13 +37,21 @@ pub async fn new_game(
     State(state): State<AppState>,
     CurrentUser(user): CurrentUser,
 ) -> ServerResult<impl IntoResponse, StatusCode> {
-    // Create a new flow for this user
-    let flow = GameCreationFlow::create_for_user(&state.db, user.user_id)
+    // Create a new flow for this user, starting from their preferred board and game type
+    let preferences = get_user_preferences(&state.db, user.user_id)
         .await
-        .wrap_err("Failed to create game flow")?;
+        .wrap_err("Failed to get preferences")?;
+    let flow = GameCreationFlow::create_for_user(
+        &state.db,
+        user.user_id,
+        preferences.board_size,
+        preferences.game_type,
+    )
+    .await
+    .wrap_err("Failed to create game flow")?;
 
     // Redirect to the flow page
-    Ok(Redirect::to(&format!("/games/flow/{}", flow.flow_id)).into_response())
+    Ok(Redirect::to(&path(&format!("/games/flow/{}", flow.flow_id))).into_response())
 }
 
 // Game create form - show the game creation form with the flow state
@@ -65,6 +82,27 @@ pub async fn show_game_flow(
         .await
         .wrap_err("Failed to get selected battlesnakes")?;
 
+    // Each selected snake's chance of winning from current ratings, summed
+    // over its entries when it's entered more than once
+    let mut win_chances: HashMap<Uuid, f64> = HashMap::new();
+    if flow.selected_count() > 1 {
+        let ratings = ratings::current_ratings(&state.db)
+            .await
+            .wrap_err("Failed to get ratings")?;
+        let entry_ratings: Vec<f64> = flow
+            .selected_battlesnake_ids
+            .iter()
+            .map(|id| ratings.get(id).map_or(INITIAL_RATING, |r| r.rating))
+            .collect();
+        for (id, probabilities) in flow
+            .selected_battlesnake_ids
+            .iter()
+            .zip(placement_probabilities(&entry_ratings))
+        {
+            *win_chances.entry(*id).or_default() += probabilities[0];
+        }
+    }
+
     // Render the game creation form
     Ok(page_factory.create_page_with_flash(
         "Create New Game".to_string(),
@@ -78,7 +116,7 @@ pub async fn show_game_flow(
                     }
                 }
 
-                form action={"/games/flow/"(flow_id)"/create"} method="post" class="mb-4" {
+                form action={(base_path())"/games/flow/"(flow_id)"/create"} method="post" class="mb-4" {
                     div class="form-group mb-3" {
                         label for="board_size" { "Board Size" }
                         select id="board_size" name="board_size" class="form-control" required {
@@ -95,9 +133,22 @@ pub async fn show_game_flow(
                             option value="Royale" selected[flow.game_type == GameType::Royale] { "Royale" }
                             option value="Constrictor" selected[flow.game_type == GameType::Constrictor] { "Constrictor" }
                             option value="Snail Mode" selected[flow.game_type == GameType::SnailMode] { "Snail Mode" }
+                            option value="Wrapped" selected[flow.game_type == GameType::Wrapped] { "Wrapped" }
                         }
                     }
 
+                    div class="form-group mb-3" {
+                        label for="max_turns" { "Turn Limit" }
+                        input type="number" id="max_turns" name="max_turns" class="form-control" min="1" max=(state.game_limits.max_turns) placeholder=(state.game_limits.max_turns) {}
+                        small class="form-text text-muted" { "The game ends on this turn if more than one snake is still alive. Leave blank for the default; a snake playing itself stops at " (PRACTICE_MAX_TURNS) "." }
+                    }
+
+                    div class="form-group mb-3" {
+                        label for="move_timeout_ms" { "Move Timeout (ms)" }
+                        input type="number" id="move_timeout_ms" name="move_timeout_ms" class="form-control" min=(state.game_limits.min_move_timeout_ms) max=(state.game_limits.max_move_timeout_ms) placeholder=(DEFAULT_MOVE_TIMEOUT_MS) {}
+                        small class="form-text text-muted" { "How long each snake has to answer each move." }
+                    }
+
                     // Display current selection count if any
                     @if flow.selected_count() > 0 {
                         div class="alert alert-info mb-3" {
@@ -117,8 +168,14 @@ pub async fn show_game_flow(
                                                         " "
                                                         span class="badge bg-secondary" { "×" (count) }
                                                     }
+                                                    @if let Some(chance) = win_chances.get(&snake.battlesnake_id) {
+                                                        " "
+                                                        small class="text-muted win-chance" title="Chance of winning, from current ratings" {
+                                                            (format!("{:.0}% to win", chance * 100.0))
+                                                        }
+                                                    }
                                                 }
-                                                form action={"/games/flow/"(flow_id)"/remove-snake/"(snake.battlesnake_id)} method="post" class="d-inline" {
+                                                form action={(base_path())"/games/flow/"(flow_id)"/remove-snake/"(snake.battlesnake_id)} method="post" class="d-inline" {
                                                     button type="submit" class="btn btn-sm btn-danger" { "Remove" }
                                                 }
                                             }
@@ -130,7 +187,7 @@ pub async fn show_game_flow(
                             div class="mt-3" {
                                 button type="submit" class="btn btn-success me-2" { "Create Game" }
 
-                                form action={"/games/flow/"(flow_id)"/reset"} method="post" class="d-inline" {
+                                form action={(base_path())"/games/flow/"(flow_id)"/reset"} method="post" class="d-inline" {
                                     button type="submit" class="btn btn-secondary" { "Reset Selection" }
                                 }
                             }
@@ -147,7 +204,7 @@ pub async fn show_game_flow(
                 @if user_battlesnakes.is_empty() {
                     div class="alert alert-warning" {
                         p { "You don't have any battlesnakes yet." }
-                        a href="/battlesnakes/new" class="btn btn-primary" { "Create a Battlesnake" }
+                        a href={(base_path())"/battlesnakes/new"} class="btn btn-primary" { "Create a Battlesnake" }
                     }
                 } @else {
                     div class="row row-cols-1 row-cols-md-3 g-4 mb-4" {
@@ -171,13 +228,13 @@ pub async fn show_game_flow(
                                     div class="card-footer d-flex gap-2" {
                                         // Always show Add button if under 4 total snakes
                                         @if can_add {
-                                            form action={"/games/flow/"(flow_id)"/add-snake/"(snake.battlesnake_id)} method="post" class="flex-grow-1" {
+                                            form action={(base_path())"/games/flow/"(flow_id)"/add-snake/"(snake.battlesnake_id)} method="post" class="flex-grow-1" {
                                                 button type="submit" class="btn btn-primary w-100" { "Add to Game" }
                                             }
                                         }
                                         // Show Remove button if this snake is selected
                                         @if count > 0 {
-                                            form action={"/games/flow/"(flow_id)"/remove-snake/"(snake.battlesnake_id)} method="post" class="flex-grow-1" {
+                                            form action={(base_path())"/games/flow/"(flow_id)"/remove-snake/"(snake.battlesnake_id)} method="post" class="flex-grow-1" {
                                                 button type="submit" class="btn btn-danger w-100" { "Remove" }
                                             }
                                         }
@@ -194,11 +251,12 @@ pub async fn show_game_flow(
 
                 h2 class="mt-4" { "Search for Public Battlesnakes" }
 
-                form action={"/games/flow/"(flow_id)"/search"} method="get" class="mb-3" {
+                form action={(base_path())"/games/flow/"(flow_id)"/search"} method="get" class="mb-3" {
                     div class="input-group" {
-                        input type="text" name="q" class="form-control" placeholder="Search by name..." value=(flow.search_query.as_deref().unwrap_or("")) {}
+                        input type="text" name="q" class="form-control" placeholder="Search by name, or tag:rust..." value=(flow.search_query.as_deref().unwrap_or("")) {}
                         button type="submit" class="btn btn-outline-secondary" { "Search" }
                     }
+                    small class="form-text text-muted" { "Add tag:name to only find snakes with that tag. Snakes with your preferred opponent tags from Settings come first." }
                 }
 
                 // If we have search results from other users, show them
@@ -209,7 +267,7 @@ pub async fn show_game_flow(
                 }
 
                 div class="mt-4" {
-                    a href="/me" class="btn btn-secondary" { "Back to Profile" }
+                    a href={(base_path())"/me"} class="btn btn-secondary" { "Back to Profile" }
                 }
             }
         }),
@@ -217,12 +275,48 @@ pub async fn show_game_flow(
     ))
 }
 
-// Configure the game (board size and game type)
+// Configure the game (board size, game type, turn limit and move timeout)
 #[derive(Debug, Deserialize)]
 pub struct ConfigureGameForm {
     // Optional parameters since they might not be provided in the form
     pub board_size: String,
     pub game_type: String,
+    // Blank for the defaults
+    #[serde(default)]
+    pub max_turns: String,
+    #[serde(default)]
+    pub move_timeout_ms: String,
+}
+
+impl ConfigureGameForm {
+    // The turn limit and move timeout, None where they were left blank,
+    // checked against the configured limits for a game on `board_size`
+    fn game_limits(
+        &self,
+        limits: &GameLimits,
+        board_size: GameBoardSize,
+    ) -> Result<(Option<i32>, Option<i32>), String> {
+        let parse = |value: &str, name: &str| {
+            let value = value.trim();
+            if value.is_empty() {
+                return Ok(None);
+            }
+            value
+                .parse::<i32>()
+                .map(Some)
+                .map_err(|_| format!("{} must be a whole number", name))
+        };
+        let max_turns = parse(&self.max_turns, "Turn limit")?;
+        let move_timeout_ms = parse(&self.move_timeout_ms, "Move timeout")?;
+        game_settings::validate(
+            limits,
+            Some(board_size.as_str()),
+            max_turns,
+            move_timeout_ms,
+        )?;
+
+        Ok((max_turns, move_timeout_ms))
+    }
 }
 
 // Reset the snake selections in the flow
@@ -248,7 +342,7 @@ pub async fn reset_snake_selections(
         .wrap_err("Failed to update game flow")?;
 
     // Redirect back to the flow page
-    Ok(Redirect::to(&format!("/games/flow/{}", flow_id)).into_response())
+    Ok(Redirect::to(&path(&format!("/games/flow/{}", flow_id))).into_response())
 }
 
 // Add a battlesnake to the selection
@@ -265,6 +359,23 @@ pub async fn add_battlesnake(
         .ok_or_else(|| "Game flow not found".to_string())
         .with_status(StatusCode::NOT_FOUND)?;
 
+    // Archived template variants stay out of new games until they're redeployed
+    let archived = get_archived_snake_ids(&state.db, &[battlesnake_id])
+        .await
+        .wrap_err("Failed to check for archived snakes")?;
+    if !archived.is_empty() {
+        session::set_flash_message(
+            &state.db,
+            session.session_id,
+            "That snake is an archived variant; instantiate its tag again to use it".to_string(),
+            session::FLASH_TYPE_WARNING,
+        )
+        .await
+        .wrap_err("Failed to set flash message")?;
+
+        return Ok(Redirect::to(&path(&format!("/games/flow/{}", flow_id))).into_response());
+    }
+
     // Add the battlesnake
     let added = flow.add_battlesnake(battlesnake_id);
 
@@ -287,7 +398,7 @@ pub async fn add_battlesnake(
         .wrap_err("Failed to update game flow")?;
 
     // Redirect back to the flow page
-    Ok(Redirect::to(&format!("/games/flow/{}", flow_id)).into_response())
+    Ok(Redirect::to(&path(&format!("/games/flow/{}", flow_id))).into_response())
 }
 
 // Remove a battlesnake from the selection
@@ -313,7 +424,7 @@ pub async fn remove_battlesnake(
         .wrap_err("Failed to update game flow")?;
 
     // Redirect back to the flow page
-    Ok(Redirect::to(&format!("/games/flow/{}", flow_id)).into_response())
+    Ok(Redirect::to(&path(&format!("/games/flow/{}", flow_id))).into_response())
 }
 
 // Search for public battlesnakes
@@ -345,7 +456,7 @@ pub async fn search_battlesnakes(
         .wrap_err("Failed to update game flow")?;
 
     // Redirect back to the flow page
-    Ok(Redirect::to(&format!("/games/flow/{}", flow_id)).into_response())
+    Ok(Redirect::to(&path(&format!("/games/flow/{}", flow_id))).into_response())
 }
 
 // Create the game with selected snakes
@@ -378,12 +489,15 @@ pub async fn create_game(
         .wrap_err("Failed to update game flow")?;
 
     // Validate and create the game
-    let validate_result = flow.validate();
+    let validate_result = flow.validate().and_then(|()| {
+        data.game_limits(&state.game_limits, flow.board_size)
+            .map_err(|e| eyre!(e))
+    });
     match validate_result {
-        Ok(_) => {
+        Ok((max_turns, move_timeout_ms)) => {
             // Create the game and enqueue a job to run it
             let game_id = flow
-                .create_game_and_enqueue(state.clone())
+                .create_game_and_enqueue(state.clone(), max_turns, move_timeout_ms)
                 .await
                 .wrap_err("Failed to create game")?;
 
@@ -403,7 +517,7 @@ pub async fn create_game(
             .wrap_err("Failed to set flash message")?;
 
             // Redirect to the game details page
-            Ok(Redirect::to(&format!("/games/{}", game_id)).into_response())
+            Ok(Redirect::to(&path(&format!("/games/{}", game_id))).into_response())
         }
         Err(error) => {
             // Set an error flash message in the session
@@ -417,7 +531,7 @@ pub async fn create_game(
             .wrap_err("Failed to set flash message")?;
 
             // Redirect back to the flow page
-            Ok(Redirect::to(&format!("/games/flow/{}", flow_id)).into_response())
+            Ok(Redirect::to(&path(&format!("/games/flow/{}", flow_id))).into_response())
         }
     }
 }
@@ -429,6 +543,10 @@ async fn render_search_results(flow: &GameCreationFlow, db: &sqlx::PgPool) -> ma
         .search_public_battlesnakes(db)
         .await
         .unwrap_or_default();
+    let ids: Vec<Uuid> = search_results.iter().map(|s| s.battlesnake_id).collect();
+    let tags = snake_tag::get_tags_for_snakes(db, &ids)
+        .await
+        .unwrap_or_default();
 
     let can_add = flow.selected_count() < 4;
 
@@ -455,17 +573,24 @@ async fn render_search_results(flow: &GameCreationFlow, db: &sqlx::PgPool) -> ma
                                 p class="card-text" {
                                     a href=(snake.url) target="_blank" { (snake.url) }
                                 }
+                                @if let Some(tags) = tags.get(&snake.battlesnake_id) {
+                                    p class="card-text snake-tags" {
+                                        @for tag in tags {
+                                            span class="badge bg-info text-dark me-1" { (tag) }
+                                        }
+                                    }
+                                }
                             }
                             div class="card-footer d-flex gap-2" {
                                 // Always show Add button if under 4 total snakes
                                 @if can_add {
-                                    form action={"/games/flow/"(flow.flow_id)"/add-snake/"(snake.battlesnake_id)} method="post" class="flex-grow-1" {
+                                    form action={(base_path())"/games/flow/"(flow.flow_id)"/add-snake/"(snake.battlesnake_id)} method="post" class="flex-grow-1" {
                                         button type="submit" class="btn btn-primary w-100" { "Add to Game" }
                                     }
                                 }
                                 // Show Remove button if this snake is selected
                                 @if count > 0 {
-                                    form action={"/games/flow/"(flow.flow_id)"/remove-snake/"(snake.battlesnake_id)} method="post" class="flex-grow-1" {
+                                    form action={(base_path())"/games/flow/"(flow.flow_id)"/remove-snake/"(snake.battlesnake_id)} method="post" class="flex-grow-1" {
                                         button type="submit" class="btn btn-danger w-100" { "Remove" }
                                     }
                                 }
@@ -1,11 +1,20 @@
 pub mod api;
+pub mod branch;
+pub mod control;
 pub mod create;
+pub mod replay;
 pub mod view;
 
 // Re-export the functions we need
-pub use api::{game_events_websocket, get_game_info};
+pub use api::{
+    game_events_sse, game_events_websocket, game_spectators, get_game_info,
+    viewer_game_events_websocket, viewer_game_info,
+};
+pub use branch::branch_game;
+pub use control::control_game_form;
 pub use create::{
     add_battlesnake, create_game, new_game, remove_battlesnake, reset_snake_selections,
     search_battlesnakes, show_game_flow,
 };
+pub use replay::{board_gif, replay_gif};
 pub use view::{list_games, view_game};
@@ -0,0 +1,103 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use color_eyre::eyre::{Context as _, eyre};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    engine_models::EngineGameFrame,
+    errors::{ServerResult, WithStatus},
+    models::game::GameStatus,
+    models::game_render::get_game_render,
+    replay_gif::{load_gif, render_still, request_render},
+    routes::{auth::OptionalApiUser, game::api::authorize_game_viewer},
+    state::AppState,
+    turn_archive,
+};
+
+/// GET /games/{id}/replay.gif
+/// A finished game as an animated GIF, for sharing. Public games need no
+/// login, so chat apps can unfurl the link.
+///
+/// Answers 202 with Retry-After while the GIF is being rendered, queueing the
+/// render if nothing has yet.
+pub async fn replay_gif(
+    State(state): State<AppState>,
+    Path(game_id): Path<Uuid>,
+    OptionalApiUser(user): OptionalApiUser,
+) -> ServerResult<Response, StatusCode> {
+    let game = authorize_game_viewer(&state, game_id, None, user.as_ref()).await?;
+    if game.status != GameStatus::Finished {
+        return Err(eyre!("Game {} hasn't finished", game_id)).with_status(StatusCode::CONFLICT);
+    }
+
+    match get_game_render(&state.db, game_id).await? {
+        Some(render) if render.rendered_at.is_some() => {
+            let gif = load_gif(&state, render).await?;
+            Ok((
+                [
+                    (header::CONTENT_TYPE, "image/gif"),
+                    (header::CACHE_CONTROL, "private, max-age=3600"),
+                ],
+                gif,
+            )
+                .into_response())
+        }
+        _ => {
+            request_render(&state, game_id).await?;
+            Ok((
+                StatusCode::ACCEPTED,
+                [(header::RETRY_AFTER, "10")],
+                "The replay GIF is being rendered. Try again in a few seconds.",
+            )
+                .into_response())
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BoardQuery {
+    pub turn: Option<i32>,
+}
+
+/// GET /games/{id}/board.gif?turn=N
+/// The board at one turn as a still image, for link previews. Without a turn,
+/// or past the last one played, it's the latest board.
+pub async fn board_gif(
+    State(state): State<AppState>,
+    Path(game_id): Path<Uuid>,
+    Query(query): Query<BoardQuery>,
+    OptionalApiUser(user): OptionalApiUser,
+) -> ServerResult<Response, StatusCode> {
+    let game = authorize_game_viewer(&state, game_id, None, user.as_ref()).await?;
+
+    let Some(frame) = turn_archive::load_turn_at(&state, game_id, query.turn)
+        .await?
+        .and_then(|turn| turn.frame_data)
+    else {
+        return Err(eyre!("Game {} has no turns yet", game_id)).with_status(StatusCode::NOT_FOUND);
+    };
+    let frame: EngineGameFrame = serde_json::from_value(frame)
+        .wrap_err_with(|| format!("Failed to read frame of game {}", game_id))?;
+
+    let (width, height) = game.board_size.dimensions();
+    let gif = render_still(width as usize, height as usize, &frame);
+
+    // Later turns of a live game change what "latest" means
+    let cache_control = if game.status == GameStatus::Finished {
+        "private, max-age=3600"
+    } else {
+        "no-cache"
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, "image/gif"),
+            (header::CACHE_CONTROL, cache_control),
+        ],
+        gif,
+    )
+        .into_response())
+}
@@ -1,29 +1,58 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
 };
 use axum_macros::debug_handler;
 use color_eyre::eyre::Context as _;
 use maud::html;
+use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::{
+    board_description::describe_frame,
     components::flash::Flash,
+    components::page::OpenGraph,
     components::page_factory::PageFactory,
+    engine_models::EngineGameFrame,
     errors::{ServerResult, WithStatus},
-    models::game::GameStatus,
+    game_control::get_game_control,
+    mirror::{get_mirror_round, get_series_results},
+    models::battlesnake::Visibility,
+    models::game::{
+        GameStatus, SpawnStrategy, get_game_branch_origin, get_game_branches, get_game_settings,
+        get_game_spawn_strategy, get_game_tiebreak_applied, is_self_play,
+    },
     models::game_battlesnake,
-    routes::auth::CurrentUser,
+    models::game_stream_ticket::create_game_stream_ticket,
+    models::user_preferences::{UserPreferences, get_user_preferences},
+    public_url::{PublicUrl, base_path},
+    queue::{format_eta, queue_estimate},
+    routes::{
+        auth::{CurrentUser, OptionalUser},
+        game::{api::can_view_game, control::can_control_game},
+    },
     state::AppState,
+    turn_archive,
 };
 
+#[derive(Debug, Deserialize)]
+pub struct ViewGameQuery {
+    /// Open the replay at this turn
+    pub turn: Option<i32>,
+}
+
 // Display game details
+//
+// Public games are open to visitors who aren't logged in, so links to them
+// unfurl in chat apps.
 #[debug_handler]
 pub async fn view_game(
     State(state): State<AppState>,
-    CurrentUser(_): CurrentUser,
+    OptionalUser(user): OptionalUser,
     Path(game_id): Path<Uuid>,
+    Query(query): Query<ViewGameQuery>,
+    public_url: PublicUrl,
     page_factory: PageFactory,
     flash: Flash,
 ) -> ServerResult<impl IntoResponse, StatusCode> {
@@ -32,9 +61,165 @@ pub async fn view_game(
         .await
         .wrap_err("Failed to get game details")
         .with_status(StatusCode::NOT_FOUND)?;
+    let instances = game_battlesnake::snake_instances(&battlesnakes);
+
+    let settings = get_game_settings(&state.db, game_id)
+        .await
+        .wrap_err("Failed to get game settings")?;
+    let owner_ids: Vec<Uuid> = battlesnakes.iter().map(|bs| bs.user_id).collect();
+    let snake_ids: Vec<Uuid> = battlesnakes.iter().map(|bs| bs.battlesnake_id).collect();
+    if !can_view_game(
+        settings.visibility,
+        &owner_ids,
+        user.as_ref().map(|u| u.user_id),
+    ) {
+        // Visitors who aren't logged in may be able to see it once they are
+        let status = if user.is_some() {
+            StatusCode::NOT_FOUND
+        } else {
+            StatusCode::UNAUTHORIZED
+        };
+        return Err(color_eyre::eyre::eyre!(
+            "Private game {} is not visible to this user",
+            game_id
+        ))
+        .with_status(status);
+    }
+
+    // Replay speed and theme for the board viewer
+    let preferences = match &user {
+        Some(user) => get_user_preferences(&state.db, user.user_id)
+            .await
+            .wrap_err("Failed to get preferences")?,
+        None => UserPreferences::default(),
+    };
+    let viewer_params = preferences.board_viewer_params();
+
+    // The board viewer can't send our cookies, so private games hand it a
+    // ticket in the engine URL instead
+    let engine_url = match (settings.visibility, &user) {
+        (Visibility::Private, Some(user)) => {
+            let ticket =
+                create_game_stream_ticket(&state.db, game_id, user.user_id, state.clock.now())
+                    .await
+                    .wrap_err("Failed to create stream ticket")?;
+            public_url.url(&format!("/api/v1/viewer/{}", ticket.ticket))
+        }
+        // Only owners get this far with a private game, so this is a public one
+        _ => public_url.url("/api/v1"),
+    };
+
+    let spawn = get_game_spawn_strategy(&state.db, game_id)
+        .await
+        .wrap_err("Failed to get spawn strategy")?;
+    let tiebreak_applied = get_game_tiebreak_applied(&state.db, game_id)
+        .await
+        .wrap_err("Failed to get tiebreak")?;
+    let queue = queue_estimate(&state, &game)
+        .await
+        .wrap_err("Failed to estimate queue position")?;
+
+    // "What if" branches of this game, and the game this is a branch of
+    let origin = get_game_branch_origin(&state.db, game_id)
+        .await
+        .wrap_err("Failed to get branch origin")?;
+    let branches = get_game_branches(&state.db, game_id)
+        .await
+        .wrap_err("Failed to get game branches")?;
+    let mirror = get_mirror_round(&state.db, game_id)
+        .await
+        .wrap_err("Failed to get mirror match")?;
+    let series = get_series_results(&state.db, game_id)
+        .await
+        .wrap_err("Failed to get mirror series")?;
+
+    let spectators = state.game_channels.spectator_count(game_id).await;
+
+    // Snake owners can pause and step their casual games while they play
+    let control = match game.status {
+        GameStatus::Finished => None,
+        _ => get_game_control(&state.db, game_id)
+            .await
+            .wrap_err("Failed to get game controls")?,
+    };
+    let can_control = user
+        .as_ref()
+        .is_some_and(|u| can_control_game(settings.ranked, &owner_ids, u.user_id));
+    let paused = control.as_ref().is_some_and(|c| c.is_paused());
+
+    // Links can open the replay at a turn
+    let turn = query.turn.filter(|turn| *turn >= 0);
+
+    // With data saver on, the board viewer only loads when asked, since it
+    // downloads every frame of the game
+    let board_url = format!(
+        "https://board.battlesnake.com/?engine={}&game={}{}{}",
+        urlencoding::encode(&engine_url),
+        game_id,
+        viewer_params,
+        turn.map(|turn| format!("&turn={}", turn))
+            .unwrap_or_default()
+    );
+    let reduced_data = page_factory.reduced_data;
+
+    // Screen readers get the board in words, starting from the linked turn
+    let first_description = turn_archive::load_turn_at(&state, game_id, Some(turn.unwrap_or(0)))
+        .await
+        .wrap_err("Failed to get first turn")?
+        .and_then(|t| t.frame_data)
+        .and_then(|frame| serde_json::from_value::<EngineGameFrame>(frame).ok())
+        .map(|frame| describe_frame(&frame));
+
+    // Link previews show the linked turn, or the latest one, where the result is
+    let open_graph = match settings.visibility {
+        Visibility::Public => {
+            let preview_description = match turn {
+                Some(_) => first_description.clone(),
+                None => turn_archive::load_turn_at(&state, game_id, None)
+                    .await
+                    .wrap_err("Failed to get latest turn")?
+                    .and_then(|t| t.frame_data)
+                    .and_then(|frame| serde_json::from_value::<EngineGameFrame>(frame).ok())
+                    .map(|frame| describe_frame(&frame)),
+            };
+            let names: Vec<&str> = battlesnakes
+                .iter()
+                .map(|bs| match instances.get(&bs.game_battlesnake_id) {
+                    Some(instance) => instance.display_name.as_str(),
+                    None => bs.name.as_str(),
+                })
+                .collect();
+            let winners: Vec<&str> = battlesnakes
+                .iter()
+                .zip(&names)
+                .filter(|(bs, _)| bs.placement == Some(1))
+                .map(|(_, name)| *name)
+                .collect();
+            let turn_query = turn
+                .map(|turn| format!("?turn={}", turn))
+                .unwrap_or_default();
+            // Games with no turns yet have no board to show
+            let image = preview_description
+                .is_some()
+                .then(|| public_url.url(&format!("/games/{}/board.gif{}", game_id, turn_query)));
+            Some(OpenGraph {
+                title: share_title(&names, &winners, game.status, turn),
+                description: preview_description.unwrap_or_else(|| {
+                    format!(
+                        "A {} {} Battlesnake game",
+                        game.board_size.as_str(),
+                        game.game_type.as_str()
+                    )
+                }),
+                url: public_url.url(&format!("/games/{}{}", game_id, turn_query)),
+                image,
+            })
+        }
+        Visibility::Private => None,
+    };
 
     // Render the game details page
-    Ok(page_factory.create_page_with_flash(
+    let page = page_factory.create_page_with_flash(
         format!("Game Details: {}", game_id),
         Box::new(html! {
             div class="container" {
@@ -50,6 +235,7 @@ pub async fn view_game(
                     div class="card-header d-flex justify-content-between align-items-center" {
                         h2 class="mb-0" { "Game " (game_id) }
                         @match game.status {
+                            _ if paused => span class="badge bg-warning text-dark" id="paused-badge" { "Paused" },
                             GameStatus::Waiting => span class="badge bg-secondary" { "Waiting" },
                             GameStatus::Running => span class="badge bg-primary" { "Running..." },
                             GameStatus::Finished => span class="badge bg-success" { "Finished" },
@@ -57,29 +243,197 @@ pub async fn view_game(
                     }
                     div class="card-body" {
                         // Board viewer iframe - always show, it handles waiting/empty games gracefully
-                        div class="board-viewer-container mb-4" style="width: 100%; max-width: 600px; aspect-ratio: 1;" {
+                        div class="board-viewer-container mb-4" role="region" aria-label="Game board" {
                             iframe
                                 id="board-viewer"
-                                src={ "https://board.battlesnake.com/?engine=" (format!("{}/api", std::env::var("BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string()))) "&game=" (game_id) }
-                                style="width: 100%; height: 100%; border: 1px solid #ccc; border-radius: 8px;"
+                                src=[(!reduced_data).then_some(&board_url)]
+                                data-src=(board_url)
+                                loading="lazy"
                                 title="Battlesnake Board Viewer"
                                 allow="accelerometer; autoplay; clipboard-write; encrypted-media; gyroscope; picture-in-picture"
                                 allowfullscreen {}
                         }
 
+                        // Driven by static/board.js
+                        div class="replay-controls mb-4" id="replay-controls" role="toolbar" aria-label="Board viewer controls" {
+                            @if reduced_data {
+                                p class="text-muted" id="reduced-data-note" {
+                                    "Data saver is on, so the board loads when you ask for it."
+                                }
+                                button type="button" class="btn btn-primary" id="load-board" data-action="load" { "Load the board" }
+                            }
+                            button type="button" class="btn btn-outline-secondary" data-action="restart" data-needs-board disabled[reduced_data] aria-label="Restart the replay" { "⟲ Restart" }
+                            button type="button" class="btn btn-outline-secondary" data-action="slower" data-needs-board disabled[reduced_data] aria-label="Replay at half speed" { "Slower" }
+                            button type="button" class="btn btn-outline-secondary" data-action="faster" data-needs-board disabled[reduced_data] aria-label="Replay at double speed" { "Faster" }
+                            button type="button" class="btn btn-outline-secondary" data-action="fullscreen" aria-label="Show the board full screen" { "Full screen" }
+                        }
+
+                        // Turn by turn replay in words, driven by static/boardDescription.js
+                        section class="board-description mb-4" id="board-description-panel" aria-labelledby="board-description-heading"
+                            data-url={(base_path())"/api/games/"(game_id)"/descriptions"}
+                            data-live[game.status != GameStatus::Finished]
+                            data-turn=[turn] {
+                            h3 id="board-description-heading" { "Board in words" }
+                            div class="replay-controls" role="toolbar" aria-label="Turn by turn replay" {
+                                button type="button" class="btn btn-outline-secondary" data-step="previous" aria-label="Previous turn" aria-keyshortcuts="ArrowLeft" { "◀" }
+                                button type="button" class="btn btn-outline-secondary" data-step="play" id="describe-play" aria-label="Play" aria-keyshortcuts="Space" { "Play" }
+                                button type="button" class="btn btn-outline-secondary" data-step="next" aria-label="Next turn" aria-keyshortcuts="ArrowRight" { "▶" }
+                                span id="describe-speed" { "Speed 3" }
+                            }
+                            p id="board-description" aria-live="polite" {
+                                (first_description.as_deref().unwrap_or("No turns have been played yet."))
+                            }
+                            p class="text-muted" id="replay-shortcuts" {
+                                "Keyboard: space plays or pauses, the left and right arrows step a turn, and 1 to 9 set the speed in turns per second."
+                            }
+                        }
+
                         div class="game-info" {
                             p { "Board Size: " (game.board_size.as_str()) }
                             p { "Game Type: " (game.game_type.as_str()) }
+                            p { "Visibility: " (settings.visibility.as_str()) }
+                            p {
+                                "Spawns: " (spawn.as_str())
+                                @if let SpawnStrategy::Fixed { rotation } = spawn {
+                                    " (rotation " (rotation) ")"
+                                }
+                            }
+                            p { "Turn Limit Tiebreak: " (settings.turn_limit_tiebreak.label()) }
+                            p { "Dead Snakes: " (settings.dead_snakes.as_str()) }
+                            p id="turn-limit" { "Turn Limit: " (settings.turn_limit()) }
+                            p id="move-timeout" { "Move Timeout: " (settings.move_timeout_ms()) "ms" }
+                            p id="game-pool" {
+                                @if settings.ranked {
+                                    "Pool: ranked"
+                                } @else {
+                                    "Pool: casual (doesn't count towards ratings or leaderboards)"
+                                }
+                            }
+                            @if settings.duel {
+                                p id="duel-game" {
+                                    "Duel: 1v1"
+                                    @if settings.ranked {
+                                        ", rated on the "
+                                        a href={(base_path())"/leaderboard?duels=true"} { "duel leaderboard" }
+                                    }
+                                }
+                            }
+                            @if is_self_play(&snake_ids) {
+                                p id="practice-game" { "Practice game: a snake playing itself" }
+                            }
                             p { "Status: " (game.status.as_str()) }
+                            // Kept up to date by static/spectators.js while the game is live
+                            @if game.status != GameStatus::Finished {
+                                p id="spectators" data-url={(base_path())"/api/games/"(game_id)"/spectators"} {
+                                    (spectators) " watching"
+                                }
+                            }
                             p { "Created: " (game.created_at.format("%Y-%m-%d %H:%M:%S")) }
                         }
                     }
                 }
 
+                @if let Some(control) = control.as_ref().filter(|_| can_control) {
+                    div class="card mb-4" id="game-controls" {
+                        div class="card-header" {
+                            h3 class="mb-0" { "Debug Controls" }
+                        }
+                        div class="card-body" {
+                            p {
+                                @if control.is_paused() {
+                                    "Paused. Step to play one turn at a time, then watch it arrive on the board."
+                                    @if control.pending_steps > 0 {
+                                        " Turns still to play: " (control.pending_steps) "."
+                                    }
+                                } @else {
+                                    "Pause the game to hold it between turns while you look at the board."
+                                }
+                            }
+                            form action={(base_path())"/games/"(game_id)"/control"} method="post" class="d-inline" {
+                                @if control.is_paused() {
+                                    button type="submit" name="action" value="step" class="btn btn-outline-primary" { "Step one turn" }
+                                    button type="submit" name="action" value="resume" class="btn btn-primary ms-2" { "Resume" }
+                                } @else {
+                                    button type="submit" name="action" value="pause" class="btn btn-warning" { "Pause" }
+                                    button type="submit" name="action" value="step" class="btn btn-outline-primary ms-2" { "Step one turn" }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                @if let Some(origin) = origin {
+                    div class="alert alert-secondary mb-4" id="branch-origin" {
+                        p class="mb-0" {
+                            "What-if branch of "
+                            a href={(base_path())"/games/"(origin.parent_game_id)} { "game " (origin.parent_game_id) }
+                            " from turn " (origin.branched_from_turn) "."
+                        }
+                    }
+                }
+
+                @if let Some(round) = &mirror {
+                    div class="alert alert-secondary mb-4" id="mirror-round" {
+                        p {
+                            "Mirror match: "
+                            @for (i, id) in round.game_ids.iter().enumerate() {
+                                @if i > 0 { " and " }
+                                @if *id == game_id {
+                                    "this game"
+                                } @else {
+                                    a href={(base_path())"/games/"(id)} { "game " (id) }
+                                }
+                            }
+                            ", with the spawns swapped."
+                        }
+                        p class="mb-0" {
+                            "Round: "
+                            @for (i, standing) in round.standings.iter().enumerate() {
+                                @if i > 0 { ", " }
+                                (standing.name) " " (standing.wins)
+                            }
+                            @if let Some(winner) = round.winner() {
+                                " (" (winner.name) " wins the round)"
+                            } @else if round.complete {
+                                " (split round)"
+                            } @else {
+                                " (in progress)"
+                            }
+                        }
+                        @if let Some(series) = &series {
+                            p class="mb-0 mt-2" id="mirror-series" {
+                                "Best of " (series.best_of) " series, "
+                                (series.rounds.len()) " of " (series.best_of) " rounds started: "
+                                @for (i, standing) in series.standings.iter().enumerate() {
+                                    @if i > 0 { ", " }
+                                    (standing.name) " " (standing.rounds_won)
+                                    " (" (standing.games_won) " games)"
+                                }
+                                @if let Some(winner) = series.winner() {
+                                    " (" (winner.name) " wins the series)"
+                                } @else if series.complete {
+                                    " (series tied)"
+                                } @else {
+                                    " (in progress)"
+                                }
+                            }
+                        }
+                    }
+                }
+
                 @if game.status == GameStatus::Waiting {
                     div class="alert alert-info mb-4" {
                         p class="mb-0" {
                             "This game is waiting to start. "
+                            @if let Some(queue) = queue {
+                                span id="queue-estimate" {
+                                    "It's number " (queue.position) " in the queue"
+                                    @if let Some(eta) = queue.eta_seconds {
+                                        " and should start in " (format_eta(eta))
+                                    }
+                                    ". "
+                                }
+                            }
                             a href="" onclick="location.reload(); return false;" { "Refresh" }
                             " to check for updates."
                         }
@@ -88,6 +442,19 @@ pub async fn view_game(
 
                 h3 { "Game Results" }
 
+                @if let Some(tiebreak) = tiebreak_applied {
+                    p class="text-muted" id="tiebreak-applied" {
+                        "The turn limit ended this game with more than one snake alive; survivors were placed by tiebreak: "
+                        (tiebreak.label()) "."
+                    }
+                }
+
+                @if battlesnakes.iter().filter(|b| b.placement == Some(1)).count() > 1 {
+                    p class="text-muted" id="game-draw" {
+                        "Draw: the last snakes were eliminated on the same turn, so they share first place."
+                    }
+                }
+
                 div class="table-responsive" {
                     table class="table table-striped" {
                         thead {
@@ -99,7 +466,7 @@ pub async fn view_game(
                             }
                         }
                         tbody {
-                            @for battlesnake in battlesnakes {
+                            @for battlesnake in &battlesnakes {
                                 tr {
                                     td {
                                         @if let Some(placement) = battlesnake.placement {
@@ -113,7 +480,12 @@ pub async fn view_game(
                                             span class="badge bg-info text-dark" { "In Progress" }
                                         }
                                     }
-                                    td { (battlesnake.name) }
+                                    td {
+                                        @match instances.get(&battlesnake.game_battlesnake_id) {
+                                            Some(instance) => (instance.display_name),
+                                            None => (battlesnake.name),
+                                        }
+                                    }
                                     td { "User " (battlesnake.user_id) }
                                     td {
                                         a href=(battlesnake.url) target="_blank" { (battlesnake.url) }
@@ -124,29 +496,120 @@ pub async fn view_game(
                     }
                 }
 
+                // Branching needs a login
+                @if game.status == GameStatus::Finished && user.is_some() {
+                    div class="card mt-4" id="branches" {
+                        div class="card-header" {
+                            h3 class="mb-0" { "What If?" }
+                        }
+                        div class="card-body" {
+                            p {
+                                "Replay this game from a turn with one snake's move changed. "
+                                "Every other move is the one actually made, and snakes that run out of moves keep going straight."
+                            }
+                            form action={(base_path())"/games/"(game_id)"/branch"} method="post" class="row g-2 align-items-end mb-3" {
+                                div class="col-auto" {
+                                    label for="branch-turn" class="form-label" { "Turn" }
+                                    input type="number" id="branch-turn" name="turn" class="form-control" min="0" value="0" required;
+                                }
+                                div class="col-auto" {
+                                    label for="branch-snake" class="form-label" { "Snake" }
+                                    select id="branch-snake" name="snake_id" class="form-select" {
+                                        @for battlesnake in &battlesnakes {
+                                            option value=(battlesnake.game_battlesnake_id) {
+                                                @match instances.get(&battlesnake.game_battlesnake_id) {
+                                                    Some(instance) => (instance.display_name),
+                                                    None => (battlesnake.name),
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                                div class="col-auto" {
+                                    label for="branch-direction" class="form-label" { "Move" }
+                                    select id="branch-direction" name="direction" class="form-select" {
+                                        option value="up" { "Up" }
+                                        option value="down" { "Down" }
+                                        option value="left" { "Left" }
+                                        option value="right" { "Right" }
+                                    }
+                                }
+                                div class="col-auto" {
+                                    button type="submit" class="btn btn-primary" { "Branch" }
+                                }
+                            }
+
+                            @if !branches.is_empty() {
+                                ul class="list-group" {
+                                    @for branch in &branches {
+                                        li class="list-group-item d-flex justify-content-between align-items-center" {
+                                            a href={(base_path())"/games/"(branch.game_id)} { "From turn " (branch.branched_from_turn) }
+                                            span class="text-muted" { (branch.created_at.format("%Y-%m-%d %H:%M:%S")) }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
                 div class="mt-4" {
-                    a href="/games" class="btn btn-primary" { "All Games" }
-                    a href="/games/new" class="btn btn-secondary ms-2" { "Create Another Game" }
-                    a href="/me" class="btn btn-secondary ms-2" { "Back to Profile" }
+                    a href={(base_path())"/games"} class="btn btn-primary" { "All Games" }
+                    a href={(base_path())"/games/new"} class="btn btn-secondary ms-2" { "Create Another Game" }
+                    a href={(base_path())"/me"} class="btn btn-secondary ms-2" { "Back to Profile" }
+                    @if game.status == GameStatus::Finished {
+                        a href={(base_path())"/api/games/"(game_id)"/notation"} id="notation-link" class="btn btn-outline-secondary ms-2" { "Notation" }
+                        a href={(base_path())"/api/games/"(game_id)"/export"} id="export-link" class="btn btn-outline-secondary ms-2" { "Export" }
+                        a href={(base_path())"/games/"(game_id)"/replay.gif"} id="replay-gif-link" class="btn btn-outline-secondary ms-2" { "Replay GIF" }
+                    }
                 }
             }
+            script src={(base_path())"/static/spectators.js"} {}
+            script src={(base_path())"/static/board.js"} {}
+            script src={(base_path())"/static/boardDescription.js"} {}
         }),
         flash,
-    ))
+    );
+
+    Ok(match open_graph {
+        Some(open_graph) => page.with_open_graph(open_graph),
+        None => page,
+    })
+}
+
+// "Alpha vs Beta: Alpha won", with the turn when a link opens the replay at one
+fn share_title(names: &[&str], winners: &[&str], status: GameStatus, turn: Option<i32>) -> String {
+    let snakes = if names.is_empty() {
+        "Battlesnake game".to_string()
+    } else {
+        names.join(" vs ")
+    };
+    let result = match (status, winners) {
+        (GameStatus::Waiting, _) => "waiting to start".to_string(),
+        (GameStatus::Running, _) => "live".to_string(),
+        (GameStatus::Finished, []) => "no winner".to_string(),
+        (GameStatus::Finished, [winner]) => format!("{} won", winner),
+        (GameStatus::Finished, _) => "draw".to_string(),
+    };
+    match turn {
+        Some(turn) => format!("{}: {} (turn {})", snakes, result, turn),
+        None => format!("{}: {}", snakes, result),
+    }
 }
 
 // List all games
 #[debug_handler]
 pub async fn list_games(
     State(state): State<AppState>,
-    CurrentUser(_): CurrentUser,
+    CurrentUser(user): CurrentUser,
     page_factory: PageFactory,
     flash: Flash,
 ) -> ServerResult<impl IntoResponse, StatusCode> {
-    // Get all games with winners
-    let games_with_winners = crate::models::game::get_all_games_with_winners(&state.db)
-        .await
-        .wrap_err("Failed to get games list with winners")?;
+    // Get all games with winners, leaving out other users' private games
+    let games_with_winners =
+        crate::models::game::get_all_games_with_winners(&state.db, user.user_id)
+            .await
+            .wrap_err("Failed to get games list with winners")?;
 
     // Render the games list page
     Ok(page_factory.create_page_with_flash(
@@ -180,13 +643,15 @@ pub async fn list_games(
                                 }
                             }
                             tbody {
-                                @for (game, winner) in &games_with_winners {
+                                @for (game, winners) in &games_with_winners {
                                     tr {
                                         td { (game.game_id) }
                                         td { (game.board_size.as_str()) }
                                         td { (game.game_type.as_str()) }
                                         td {
-                                            @if let Some(winner_name) = winner {
+                                            @if winners.len() > 1 {
+                                                span class="badge bg-warning text-dark" title=(winners.join(", ")) { "🤝 Draw" }
+                                            } @else if let Some(winner_name) = winners.first() {
                                                 span class="badge bg-warning text-dark" { "🏆 " (winner_name) }
                                             } @else {
                                                 @if game.status == crate::models::game::GameStatus::Finished {
@@ -199,7 +664,7 @@ pub async fn list_games(
                                         td { (game.status.as_str()) }
                                         td { (game.created_at.format("%Y-%m-%d %H:%M:%S")) }
                                         td {
-                                            a href={"/games/"(game.game_id)} class="btn btn-sm btn-primary" { "View" }
+                                            a href={(base_path())"/games/"(game.game_id)} class="btn btn-sm btn-primary" { "View" }
                                         }
                                     }
                                 }
@@ -209,11 +674,41 @@ pub async fn list_games(
                 }
 
                 div class="mt-4" {
-                    a href="/games/new" class="btn btn-primary" { "Create New Game" }
-                    a href="/me" class="btn btn-secondary" { "Back to Profile" }
+                    a href={(base_path())"/games/new"} class="btn btn-primary" { "Create New Game" }
+                    a href={(base_path())"/me"} class="btn btn-secondary" { "Back to Profile" }
                 }
             }
         }),
         flash,
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_title() {
+        assert_eq!(
+            share_title(&["Alpha", "Beta"], &["Alpha"], GameStatus::Finished, None),
+            "Alpha vs Beta: Alpha won"
+        );
+        assert_eq!(
+            share_title(
+                &["Alpha", "Beta"],
+                &["Alpha", "Beta"],
+                GameStatus::Finished,
+                None
+            ),
+            "Alpha vs Beta: draw"
+        );
+        assert_eq!(
+            share_title(&["Alpha", "Beta"], &[], GameStatus::Running, Some(12)),
+            "Alpha vs Beta: live (turn 12)"
+        );
+        assert_eq!(
+            share_title(&[], &[], GameStatus::Waiting, None),
+            "Battlesnake game: waiting to start"
+        );
+    }
+}
@@ -21,6 +21,7 @@ use crate::{
         },
         user::create_or_update_user,
     },
+    public_url::{PublicUrl, base_path, path},
     state::AppState,
 };
 
@@ -164,9 +165,14 @@ pub async fn github_auth_callback(
         .wrap_err("Failed to create or update user")?;
 
     // Associate the user with the current session
-    associate_user_with_session(&state.db, current_session.session.session_id, user.user_id)
-        .await
-        .wrap_err("Failed to associate user with session")?;
+    associate_user_with_session(
+        &state.db,
+        current_session.session.session_id,
+        user.user_id,
+        state.clock.now(),
+    )
+    .await
+    .wrap_err("Failed to associate user with session")?;
 
     // If CLI auth, create an API token and redirect to the token display page
     if is_cli_auth {
@@ -175,17 +181,17 @@ pub async fn github_auth_callback(
             .wrap_err("Failed to create API token for CLI")?;
 
         // Redirect to the CLI token display page with the token as a query param
-        return Ok(Redirect::to(&format!(
+        return Ok(Redirect::to(&path(&format!(
             "/auth/cli-token?token={}",
             new_token.secret
-        )));
+        ))));
     }
 
     // Redirect to home page with success message
     flasher
         .add_flash("Successfully logged in with GitHub!")
         .await?;
-    Ok(Redirect::to("/"))
+    Ok(Redirect::to(&path("/")))
 }
 
 // Route handler for logging out
@@ -196,7 +202,12 @@ pub async fn logout(
 ) -> impl IntoResponse {
     // Disassociate user from the session (if logged in)
     if current_session.user.is_some() {
-        let _ = disassociate_user_from_session(&state.db, current_session.session.session_id).await;
+        let _ = disassociate_user_from_session(
+            &state.db,
+            current_session.session.session_id,
+            state.clock.now(),
+        )
+        .await;
     }
 
     // Add flash message, but don't fail the request if it doesn't work
@@ -204,7 +215,7 @@ pub async fn logout(
         tracing::warn!(?err, "Failed to set logout flash message");
     }
 
-    Redirect::to("/")
+    Redirect::to(&path("/"))
 }
 
 /// Query parameters for CLI token display
@@ -216,6 +227,7 @@ pub struct CliTokenQuery {
 /// Route handler for displaying CLI token after OAuth
 pub async fn cli_token_page(
     Query(query): Query<CliTokenQuery>,
+    public_url: PublicUrl,
     page_factory: PageFactory,
 ) -> impl IntoResponse {
     page_factory.create_page(
@@ -244,9 +256,20 @@ pub async fn cli_token_page(
                     }
                 }
 
+                div style="margin: 20px 0;" {
+                    p {
+                        "If the CLI isn't already pointed at this server, set its API URL in "
+                        code { "~/.config/arena/config.toml" }
+                        ":"
+                    }
+                    pre style="background: #f5f5f5; border: 1px solid #ddd; border-radius: 4px; padding: 10px;" {
+                        "api_url = \"" (public_url.url("")) "\""
+                    }
+                }
+
                 div style="margin-top: 20px;" {
                     p { "You can close this browser tab and return to the CLI." }
-                    a href="/" class="btn btn-secondary" { "Go to Home" }
+                    a href={(base_path())"/"} class="btn btn-secondary" { "Go to Home" }
                 }
             }
         }),
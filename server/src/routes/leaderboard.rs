@@ -0,0 +1,396 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use color_eyre::eyre::Context as _;
+use maud::html;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    components::page_factory::PageFactory,
+    errors::{ServerResult, WithStatus},
+    federation::combined_placements,
+    models::battlesnake,
+    models::federation,
+    models::league::{self, League},
+    public_url::base_path,
+    ratings,
+    routes::auth::OptionalUser,
+    state::AppState,
+    stats::{SnakeRating, compute_ratings, format_performance},
+};
+
+// Which games a leaderboard is rated from
+pub enum LeaderboardScope {
+    // Every ranked game in the arena, listing public snakes
+    Arena,
+    // Only ranked 1v1 duels in the arena, listing public snakes
+    Duels,
+    // Only a league's games, listing every snake that played in them
+    League(League),
+}
+
+// A snake's place on a leaderboard
+pub struct LeaderboardRow {
+    pub battlesnake_id: Uuid,
+    pub name: String,
+    pub rating: SnakeRating,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardQuery {
+    // A league to show the leaderboard of, instead of the arena's
+    pub league: Option<Uuid>,
+    // The arena's duel ladder instead of its leaderboard of every ranked game
+    #[serde(default)]
+    pub duels: bool,
+}
+
+impl LeaderboardQuery {
+    // Leagues have a single leaderboard, so there's no league duel ladder
+    pub fn validate(&self) -> Result<(), String> {
+        if self.duels && self.league.is_some() {
+            return Err("Duel leaderboards are arena-wide; leave out the league".to_string());
+        }
+        Ok(())
+    }
+}
+
+// The scope a leaderboard request asks for, or None when it names a league
+// that doesn't exist or that the user isn't a member of
+pub async fn leaderboard_scope(
+    pool: &PgPool,
+    query: &LeaderboardQuery,
+    user_id: Option<Uuid>,
+) -> cja::Result<Option<LeaderboardScope>> {
+    let Some(league_id) = query.league else {
+        return Ok(Some(if query.duels {
+            LeaderboardScope::Duels
+        } else {
+            LeaderboardScope::Arena
+        }));
+    };
+    let Some(user_id) = user_id else {
+        return Ok(None);
+    };
+    if !league::is_league_member(pool, league_id, user_id).await? {
+        return Ok(None);
+    }
+
+    Ok(league::get_league_by_id(pool, league_id)
+        .await?
+        .map(LeaderboardScope::League))
+}
+
+// Snakes that have played rated games in the scope, best rating first
+pub async fn leaderboard_rows(
+    pool: &PgPool,
+    scope: &LeaderboardScope,
+) -> cja::Result<Vec<LeaderboardRow>> {
+    let (snakes, ratings) = match scope {
+        LeaderboardScope::Arena | LeaderboardScope::Duels => {
+            let snakes = battlesnake::get_public_battlesnakes(pool)
+                .await
+                .wrap_err("Failed to get public battlesnakes")?
+                .into_iter()
+                .map(|snake| (snake.battlesnake_id, snake.name))
+                .collect::<Vec<_>>();
+            let ratings = match scope {
+                LeaderboardScope::Duels => ratings::duel_ratings(pool).await,
+                _ => ratings::current_ratings(pool).await,
+            }
+            .wrap_err("Failed to get ratings")?;
+            (snakes, ratings)
+        }
+        LeaderboardScope::League(league) => {
+            let snakes = league::get_league_snakes(pool, league.league_id)
+                .await?
+                .into_iter()
+                .map(|snake| (snake.battlesnake_id, snake.name))
+                .collect::<Vec<_>>();
+            let ratings = ratings::league_ratings(pool, league.league_id).await?;
+            (snakes, ratings)
+        }
+    };
+
+    let mut rows: Vec<LeaderboardRow> = snakes
+        .into_iter()
+        .filter_map(|(battlesnake_id, name)| {
+            let rating = ratings
+                .get(&battlesnake_id)
+                .filter(|r| r.rated_games > 0)
+                .copied()?;
+            Some(LeaderboardRow {
+                battlesnake_id,
+                name,
+                rating,
+            })
+        })
+        .collect();
+    rows.sort_by(|a, b| b.rating.rating.total_cmp(&a.rating.rating));
+
+    Ok(rows)
+}
+
+// Snakes that have played rated games, best rating first: public snakes in
+// the arena's ranked games or its ranked duels, or every snake in a league's games
+pub async fn leaderboard(
+    State(state): State<AppState>,
+    OptionalUser(user): OptionalUser,
+    Query(query): Query<LeaderboardQuery>,
+    page_factory: PageFactory,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    query.validate().with_status(StatusCode::BAD_REQUEST)?;
+    let user_id = user.as_ref().map(|u| u.user_id);
+    let scope = leaderboard_scope(&state.db, &query, user_id)
+        .await?
+        .ok_or_else(|| "League not found".to_string())
+        .with_status(StatusCode::NOT_FOUND)?;
+    let rows = leaderboard_rows(&state.db, &scope).await?;
+
+    // The leagues the user can switch to
+    let leagues = match user_id {
+        Some(user_id) => league::get_leagues_for_user(&state.db, user_id)
+            .await
+            .wrap_err("Failed to get leagues")?,
+        None => Vec::new(),
+    };
+    let (title, current_league) = match &scope {
+        LeaderboardScope::Arena => ("Leaderboard".to_string(), None),
+        LeaderboardScope::Duels => ("Duel Leaderboard".to_string(), None),
+        LeaderboardScope::League(league) => (
+            format!("Leaderboard: {}", league.name),
+            Some(league.league_id),
+        ),
+    };
+    let completed = match current_league {
+        Some(league_id) => league::get_league_awards(&state.db, league_id)
+            .await?
+            .completed_at
+            .is_some(),
+        None => false,
+    };
+
+    Ok(page_factory.create_page(
+        title.clone(),
+        Box::new(html! {
+            div class="container" {
+                h1 { (title) }
+                div class="btn-group mb-3" id="leaderboard-scope" {
+                    a href={(base_path())"/leaderboard"} class=(if matches!(scope, LeaderboardScope::Arena) { "btn btn-sm btn-primary" } else { "btn btn-sm btn-secondary" }) { "This arena" }
+                    a href={(base_path())"/leaderboard?duels=true"} class=(if matches!(scope, LeaderboardScope::Duels) { "btn btn-sm btn-primary" } else { "btn btn-sm btn-secondary" }) { "Duels" }
+                    @for league in &leagues {
+                        a href={(base_path())"/leaderboard?league="(league.league_id)} class=(if current_league == Some(league.league_id) { "btn btn-sm btn-primary" } else { "btn btn-sm btn-secondary" }) { (league.name) }
+                    }
+                }
+                p {
+                    "Ratings account for the strength of each snake's opponents. "
+                    strong { "Vs. Expected" }
+                    " is the average number of places per game a snake finished above (or below) what its opponents' ratings predicted."
+                }
+                @if let Some(league_id) = current_league {
+                    p { "Only games played in this league count, and only its members can see it." }
+                    @if completed {
+                        div class="alert alert-success" id="league-completed" {
+                            "This league is completed. "
+                            a href={(base_path())"/leagues/"(league_id)"/announcement"} { "See the final standings and prizes" }
+                        }
+                    }
+                } @else if matches!(scope, LeaderboardScope::Duels) {
+                    p { "Only ranked 1v1 duels count, so placements in games with more snakes don't mix into these ratings." }
+                } @else if state.federation.is_some() {
+                    p {
+                        a href={(base_path())"/leaderboard/federated"} { "See the combined leaderboard" }
+                        " with snakes from federated arenas."
+                    }
+                }
+
+                @if rows.is_empty() {
+                    div class="alert alert-info" {
+                        @if current_league.is_some() {
+                            p { "No snakes have played against another snake in this league yet." }
+                        } @else if matches!(scope, LeaderboardScope::Duels) {
+                            p { "No public snakes have played a ranked duel yet." }
+                        } @else {
+                            p { "No public snakes have played against another snake yet." }
+                        }
+                    }
+                } @else {
+                    div class="table-responsive" {
+                        table class="table table-striped" id="leaderboard" {
+                            thead {
+                                tr {
+                                    th { "Rank" }
+                                    th { "Snake" }
+                                    th { "Rating" }
+                                    th { "Games" }
+                                    th { "Win Rate" }
+                                    th { "Vs. Expected" }
+                                }
+                            }
+                            tbody {
+                                @for (rank, row) in rows.iter().enumerate() {
+                                    tr {
+                                        td { (rank + 1) }
+                                        td {
+                                            a href={(base_path())"/battlesnakes/"(row.battlesnake_id)"/profile"} { (row.name) }
+                                        }
+                                        td { (format!("{:.0}", row.rating.rating)) }
+                                        td { (row.rating.games) }
+                                        td { (format!("{:.1}%", row.rating.win_rate())) }
+                                        td { (format_performance(row.rating.performance_vs_expected())) }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }),
+    ))
+}
+
+// A snake on the combined leaderboard and the arena it plays on
+struct FederatedEntrant {
+    name: String,
+    owner: String,
+    // The peer's base URL, None for this arena's own snakes
+    peer_url: Option<String>,
+}
+
+// Public snakes from this arena and its federation peers, rated together
+// across every game each arena recognizes
+pub async fn federated_leaderboard(
+    State(state): State<AppState>,
+    page_factory: PageFactory,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    state
+        .federation
+        .as_ref()
+        .ok_or_else(|| "Federation isn't configured".to_string())
+        .with_status(StatusCode::NOT_FOUND)?;
+
+    let local_snakes = federation::get_local_federated_snakes(&state.db)
+        .await
+        .wrap_err("Failed to get public battlesnakes")?;
+    let local_results = federation::get_local_federated_results(&state.db, None, i64::MAX)
+        .await
+        .wrap_err("Failed to get local results")?;
+    let peer_snakes = federation::get_peer_snakes(&state.db)
+        .await
+        .wrap_err("Failed to get federated snakes")?;
+    let peer_results = federation::get_peer_results(&state.db)
+        .await
+        .wrap_err("Failed to get federated results")?;
+
+    let local_ids: HashSet<Uuid> = local_snakes.iter().map(|s| s.snake_id).collect();
+    let mut entrants: HashMap<Uuid, FederatedEntrant> = HashMap::new();
+    for snake in local_snakes {
+        entrants.insert(
+            snake.snake_id,
+            FederatedEntrant {
+                name: snake.name,
+                owner: snake.owner,
+                peer_url: None,
+            },
+        );
+    }
+    for snake in peer_snakes {
+        // A peer can't speak for this arena's snakes
+        if local_ids.contains(&snake.remote_snake_id) {
+            continue;
+        }
+        entrants.insert(
+            snake.remote_snake_id,
+            FederatedEntrant {
+                name: snake.name,
+                owner: snake.owner,
+                peer_url: Some(snake.peer_url),
+            },
+        );
+    }
+
+    let results = local_results
+        .into_iter()
+        .chain(peer_results.into_iter().filter(|result| {
+            !result
+                .placements
+                .iter()
+                .any(|p| local_ids.contains(&p.snake_id))
+        }))
+        .collect();
+    let ratings = compute_ratings(&combined_placements(results));
+
+    let mut rows: Vec<(Uuid, &FederatedEntrant, SnakeRating)> = entrants
+        .iter()
+        .filter_map(|(id, entrant)| {
+            let rating = ratings.get(id).filter(|r| r.rated_games > 0).copied()?;
+            Some((*id, entrant, rating))
+        })
+        .collect();
+    rows.sort_by(|(_, _, a), (_, _, b)| b.rating.total_cmp(&a.rating));
+
+    Ok(page_factory.create_page(
+        "Combined Leaderboard".to_string(),
+        Box::new(html! {
+            div class="container" {
+                h1 { "Combined Leaderboard" }
+                p {
+                    "Public snakes from this arena and the arenas it federates with, rated across every game each arena has shared. "
+                    "Results sync every 15 minutes. "
+                    a href={(base_path())"/leaderboard"} { "Back to this arena's leaderboard" }
+                }
+
+                @if rows.is_empty() {
+                    div class="alert alert-info" {
+                        p { "No public snakes have played against another snake yet." }
+                    }
+                } @else {
+                    div class="table-responsive" {
+                        table class="table table-striped" id="federated-leaderboard" {
+                            thead {
+                                tr {
+                                    th { "Rank" }
+                                    th { "Snake" }
+                                    th { "Owner" }
+                                    th { "Arena" }
+                                    th { "Rating" }
+                                    th { "Games" }
+                                    th { "Win Rate" }
+                                }
+                            }
+                            tbody {
+                                @for (rank, (id, entrant, rating)) in rows.iter().enumerate() {
+                                    tr {
+                                        td { (rank + 1) }
+                                        td {
+                                            @match &entrant.peer_url {
+                                                Some(peer_url) => a href={(peer_url)"/battlesnakes/"(id)"/profile"} { (entrant.name) },
+                                                None => a href={(base_path())"/battlesnakes/"(id)"/profile"} { (entrant.name) },
+                                            }
+                                        }
+                                        td { (entrant.owner) }
+                                        td {
+                                            @match &entrant.peer_url {
+                                                Some(peer_url) => (peer_url),
+                                                None => "This arena",
+                                            }
+                                        }
+                                        td { (format!("{:.0}", rating.rating)) }
+                                        td { (rating.games) }
+                                        td { (format!("{:.1}%", rating.win_rate())) }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }),
+    ))
+}
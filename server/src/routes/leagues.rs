@@ -0,0 +1,148 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use color_eyre::eyre::Context as _;
+use maud::html;
+use uuid::Uuid;
+
+use crate::{
+    components::page_factory::PageFactory,
+    errors::{ServerResult, WithStatus},
+    models::{battlesnake, league},
+    public_url::base_path,
+    routes::auth::CurrentUser,
+    state::AppState,
+};
+
+// A completed league's announcement: its write-up, highlights, final
+// standings with prizes and every game played. Only members can see it.
+pub async fn league_announcement(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(league_id): Path<Uuid>,
+    page_factory: PageFactory,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let is_member = league::is_league_member(&state.db, league_id, user.user_id)
+        .await
+        .wrap_err("Failed to check league membership")?;
+    let announcement = if is_member {
+        league::get_league_awards(&state.db, league_id)
+            .await?
+            .announcement
+    } else {
+        None
+    };
+    let announcement = announcement
+        .ok_or_else(|| "League announcement not found".to_string())
+        .with_status(StatusCode::NOT_FOUND)?;
+
+    // Homepage and source links snakes' owners have added
+    let snake_ids: Vec<Uuid> = announcement.standings.iter().map(|s| s.snake_id).collect();
+    let profiles = battlesnake::get_snake_profiles(&state.db, &snake_ids)
+        .await
+        .wrap_err("Failed to get snake profiles")?;
+
+    let title = format!("{}: Final Standings", announcement.league_name);
+    Ok(page_factory.create_page(
+        title.clone(),
+        Box::new(html! {
+            div class="container" {
+                h1 { (title) }
+                p class="text-muted" {
+                    "Completed " (announcement.completed_at.format("%Y-%m-%d %H:%M UTC"))
+                }
+
+                @if let Some(writeup) = &announcement.writeup {
+                    div id="league-writeup" class="mb-4" {
+                        @for paragraph in writeup.split("\n\n") {
+                            p { (paragraph) }
+                        }
+                    }
+                }
+
+                h3 { "Highlights" }
+                ul id="league-highlights" {
+                    @for highlight in &announcement.highlights {
+                        li { (highlight) }
+                    }
+                }
+
+                h3 { "Standings" }
+                @if announcement.standings.is_empty() {
+                    div class="alert alert-info" {
+                        p { "No snakes played against another snake in this league." }
+                    }
+                } @else {
+                    div class="table-responsive" {
+                        table class="table table-striped" id="league-standings" {
+                            thead {
+                                tr {
+                                    th { "Rank" }
+                                    th { "Snake" }
+                                    th { "Rating" }
+                                    th { "Games" }
+                                    th { "Wins" }
+                                    th { "Prize" }
+                                }
+                            }
+                            tbody {
+                                @for standing in &announcement.standings {
+                                    tr {
+                                        td { (standing.rank) }
+                                        td {
+                                            a href={(base_path())"/battlesnakes/"(standing.snake_id)"/profile"} { (standing.name) }
+                                            @if let Some(profile) = profiles.get(&standing.snake_id) {
+                                                @if let Some(homepage) = &profile.homepage_url {
+                                                    " "
+                                                    a href=(homepage) class="snake-link" rel="nofollow ugc noopener" target="_blank" { "homepage" }
+                                                }
+                                                @if let Some(source) = &profile.source_url {
+                                                    " "
+                                                    a href=(source) class="snake-link" rel="nofollow ugc noopener" target="_blank" { "source" }
+                                                }
+                                            }
+                                        }
+                                        td { (format!("{:.0}", standing.rating)) }
+                                        td { (standing.games) }
+                                        td { (standing.wins) }
+                                        td { (standing.prize.as_deref().unwrap_or("")) }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                h3 { "Games" }
+                div class="table-responsive" {
+                    table class="table table-sm" id="league-games" {
+                        thead {
+                            tr {
+                                th { "Game" }
+                                th { "Played" }
+                                th { "Snakes" }
+                                th { "Turns" }
+                                th { "Won by" }
+                            }
+                        }
+                        tbody {
+                            @for (round, game) in announcement.games.iter().enumerate() {
+                                tr {
+                                    td {
+                                        a href={(base_path())"/games/"(game.game_id)} { "Game " (round + 1) }
+                                    }
+                                    td { (game.played_at.format("%Y-%m-%d %H:%M")) }
+                                    td { (game.snakes) }
+                                    td { (game.turns.map(|t| t.to_string()).unwrap_or_default()) }
+                                    td { (game.winners.join(", ")) }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }),
+    ))
+}
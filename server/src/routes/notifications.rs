@@ -0,0 +1,117 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Redirect},
+};
+use color_eyre::eyre::Context as _;
+use maud::html;
+use uuid::Uuid;
+
+use crate::{
+    components::page_factory::PageFactory,
+    errors::{ServerResult, WithStatus},
+    models::notification,
+    public_url::{base_path, path},
+    routes::auth::CurrentUser,
+    state::AppState,
+};
+
+// Notifications shown in the inbox
+const INBOX_NOTIFICATIONS: i64 = 100;
+
+// The user's notification inbox, newest first
+pub async fn inbox(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    page_factory: PageFactory,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let notifications =
+        notification::get_notifications(&state.db, user.user_id, false, INBOX_NOTIFICATIONS)
+            .await
+            .wrap_err("Failed to get notifications")?;
+    let unread = notifications.iter().filter(|n| n.read_at.is_none()).count();
+
+    Ok(page_factory.create_page(
+        "Notifications".to_string(),
+        Box::new(html! {
+            div class="container" {
+                h1 { "Notifications" }
+                p {
+                    "Your games, snakes and leagues. Showing the latest " (INBOX_NOTIFICATIONS) "."
+                }
+                @if unread > 0 {
+                    form action={(base_path())"/notifications/read"} method="post" class="mb-3" {
+                        button type="submit" class="btn btn-secondary" { "Mark all as read" }
+                    }
+                }
+
+                @if notifications.is_empty() {
+                    div class="alert alert-info" {
+                        p { "No notifications yet." }
+                    }
+                } @else {
+                    div class="table-responsive" {
+                        table class="table" id="notifications" {
+                            thead {
+                                tr {
+                                    th { "" }
+                                    th { "Notification" }
+                                    th { "When" }
+                                }
+                            }
+                            tbody {
+                                @for notification in &notifications {
+                                    tr class=(if notification.read_at.is_none() { "notification unread" } else { "notification" }) {
+                                        td {
+                                            @if notification.read_at.is_none() {
+                                                span class="badge bg-primary" { "New" }
+                                            }
+                                        }
+                                        td {
+                                            a href={(base_path())"/notifications/"(notification.notification_id)} {
+                                                strong { (notification.title) }
+                                            }
+                                            br;
+                                            (notification.body)
+                                        }
+                                        td { (notification.created_at.format("%Y-%m-%d %H:%M")) }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }),
+    ))
+}
+
+// Mark a notification read and go where it leads, or back to the inbox
+pub async fn open_notification(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(notification_id): Path<Uuid>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let notification = notification::mark_notification_read(
+        &state.db,
+        user.user_id,
+        notification_id,
+        state.clock.now(),
+    )
+    .await?
+    .ok_or_else(|| "Notification not found".to_string())
+    .with_status(StatusCode::NOT_FOUND)?;
+
+    let link = notification.link.as_deref().unwrap_or("/notifications");
+    Ok(Redirect::to(&path(link)))
+}
+
+// Mark all of the user's notifications read
+pub async fn mark_all_read(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    notification::mark_all_notifications_read(&state.db, user.user_id, state.clock.now()).await?;
+
+    Ok(Redirect::to(&path("/notifications")))
+}
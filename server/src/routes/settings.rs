@@ -0,0 +1,186 @@
+use axum::{
+    Form,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Redirect},
+};
+use color_eyre::eyre::Context as _;
+use maud::html;
+use serde::Deserialize;
+
+use crate::{
+    components::page_factory::PageFactory,
+    errors::{ServerResult, WithStatus},
+    models::{
+        game::{GameBoardSize, GameType},
+        session, snake_tag,
+        user_preferences::{
+            MAX_PLAYBACK_SPEED, MIN_PLAYBACK_SPEED, NotificationChannel, Theme,
+            get_user_preferences,
+        },
+    },
+    public_url::{base_path, path},
+    routes::{
+        api::preferences::{UpdatePreferencesRequest, update_user_preferences},
+        auth::{CurrentUser, CurrentUserWithSession},
+    },
+    state::AppState,
+};
+
+// Show the settings form with the user's current preferences
+pub async fn settings_page(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    page_factory: PageFactory,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let preferences = get_user_preferences(&state.db, user.user_id)
+        .await
+        .wrap_err("Failed to get preferences")?;
+
+    let flash = page_factory.flash.clone();
+
+    Ok(page_factory.create_page_with_flash(
+        "Settings".to_string(),
+        Box::new(html! {
+            div class="container" {
+                h1 { "Settings" }
+
+                @if let Some(message) = flash.message() {
+                    div class=(flash.class()) {
+                        p { (message) }
+                    }
+                }
+
+                form action={(base_path())"/settings"} method="post" {
+                    h3 { "New Games" }
+                    div class="form-group" {
+                        label for="board_size" { "Board Size" }
+                        select id="board_size" name="board_size" class="form-control" {
+                            option value="7x7" selected[preferences.board_size == GameBoardSize::Small] { "Small (7x7)" }
+                            option value="11x11" selected[preferences.board_size == GameBoardSize::Medium] { "Medium (11x11)" }
+                            option value="19x19" selected[preferences.board_size == GameBoardSize::Large] { "Large (19x19)" }
+                        }
+                    }
+                    div class="form-group" {
+                        label for="game_type" { "Game Type" }
+                        select id="game_type" name="game_type" class="form-control" {
+                            option value="Standard" selected[preferences.game_type == GameType::Standard] { "Standard" }
+                            option value="Royale" selected[preferences.game_type == GameType::Royale] { "Royale" }
+                            option value="Constrictor" selected[preferences.game_type == GameType::Constrictor] { "Constrictor" }
+                            option value="Snail Mode" selected[preferences.game_type == GameType::SnailMode] { "Snail Mode" }
+                            option value="Wrapped" selected[preferences.game_type == GameType::Wrapped] { "Wrapped" }
+                        }
+                    }
+
+                    div class="form-group" {
+                        label for="preferred_opponent_tags" { "Preferred Opponents" }
+                        input type="text" id="preferred_opponent_tags" name="preferred_opponent_tags" class="form-control"
+                            placeholder="tree-search, rust" value=(preferences.preferred_opponent_tags.join(", ")) {}
+                        small class="form-text text-muted" { "Comma separated tags. Public snakes with any of them come first when you search for opponents." }
+                    }
+
+                    h3 class="mt-4" { "Notifications" }
+                    div class="form-group" {
+                        label for="timezone" { "Time Zone" }
+                        input type="text" id="timezone" name="timezone" class="form-control" value=(preferences.timezone) required {}
+                        small class="form-text text-muted" { "An IANA name like Europe/Berlin or America/New_York" }
+                    }
+                    div class="form-check" {
+                        input type="checkbox" id="notify_email" name="notify_email" value="true" class="form-check-input"
+                            checked[preferences.notification_channels.contains(&NotificationChannel::Email)] {}
+                        label for="notify_email" class="form-check-label" { "Email" }
+                    }
+                    div class="form-check" {
+                        input type="checkbox" id="notify_web" name="notify_web" value="true" class="form-check-input"
+                            checked[preferences.notification_channels.contains(&NotificationChannel::Web)] {}
+                        label for="notify_web" class="form-check-label" { "Browser" }
+                    }
+
+                    h3 class="mt-4" { "Display" }
+                    div class="form-group" {
+                        label for="playback_speed" { "Replay Speed" }
+                        input type="number" id="playback_speed" name="playback_speed" class="form-control"
+                            min=(MIN_PLAYBACK_SPEED) max=(MAX_PLAYBACK_SPEED) step="0.25"
+                            value=(preferences.playback_speed) required {}
+                        small class="form-text text-muted" { "1 is the board viewer's normal speed, 2 twice as fast" }
+                    }
+                    div class="form-group" {
+                        label for="theme" { "Theme" }
+                        select id="theme" name="theme" class="form-control" {
+                            option value="light" selected[preferences.theme == Theme::Light] { "Light" }
+                            option value="dark" selected[preferences.theme == Theme::Dark] { "Dark" }
+                            option value="system" selected[preferences.theme == Theme::System] { "Same as my browser" }
+                        }
+                    }
+
+                    div class="form-group" style="margin-top: 20px;" {
+                        button type="submit" class="btn btn-primary" { "Save Settings" }
+                        a href={(base_path())"/me"} class="btn btn-secondary" { "Back to Profile" }
+                    }
+                }
+            }
+        }),
+        flash,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SettingsForm {
+    board_size: String,
+    game_type: String,
+    timezone: String,
+    // Checkboxes are only sent when checked
+    notify_email: Option<String>,
+    notify_web: Option<String>,
+    playback_speed: f64,
+    theme: String,
+    #[serde(default)]
+    preferred_opponent_tags: String,
+}
+
+impl From<SettingsForm> for UpdatePreferencesRequest {
+    fn from(form: SettingsForm) -> Self {
+        let mut channels = Vec::new();
+        if form.notify_email.is_some() {
+            channels.push(NotificationChannel::Email.as_str().to_string());
+        }
+        if form.notify_web.is_some() {
+            channels.push(NotificationChannel::Web.as_str().to_string());
+        }
+
+        Self {
+            board: Some(form.board_size),
+            game_type: Some(form.game_type),
+            timezone: Some(form.timezone),
+            notification_channels: Some(channels),
+            playback_speed: Some(form.playback_speed),
+            theme: Some(form.theme),
+            preferred_opponent_tags: Some(
+                snake_tag::split_tags(&form.preferred_opponent_tags)
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect(),
+            ),
+        }
+    }
+}
+
+// Save the settings form
+pub async fn update_settings(
+    State(state): State<AppState>,
+    CurrentUserWithSession { user, session }: CurrentUserWithSession,
+    Form(form): Form<SettingsForm>,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let (message, flash_type) =
+        match update_user_preferences(&state, user.user_id, &form.into()).await {
+            Ok(_) => ("Settings saved!".to_string(), session::FLASH_TYPE_SUCCESS),
+            Err((StatusCode::BAD_REQUEST, message)) => (message, session::FLASH_TYPE_ERROR),
+            Err((status, message)) => Err(message).with_status(status)?,
+        };
+
+    session::set_flash_message(&state.db, session.session_id, message, flash_type)
+        .await
+        .wrap_err("Failed to set flash message")?;
+
+    Ok(Redirect::to(&path("/settings")))
+}
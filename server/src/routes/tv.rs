@@ -0,0 +1,63 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use color_eyre::eyre::Context as _;
+use maud::html;
+
+use crate::{
+    components::page_factory::PageFactory,
+    errors::ServerResult,
+    models::game::get_live_public_games,
+    public_url::{PublicUrl, base_path},
+    routes::api::tv::board_viewer_url,
+    state::AppState,
+};
+
+// Arena TV: running public games one after another, no login needed
+// static/tv.js switches to the next game when the one on ends
+pub async fn tv_page(
+    State(state): State<AppState>,
+    public_url: PublicUrl,
+    page_factory: PageFactory,
+) -> ServerResult<impl IntoResponse, StatusCode> {
+    let now_playing = get_live_public_games(&state.db, 1)
+        .await
+        .wrap_err("Failed to get live games")?
+        .into_iter()
+        .next();
+
+    Ok(page_factory.create_page(
+        "Arena TV".to_string(),
+        Box::new(html! {
+            div class="container" id="tv" data-api={(base_path())"/api/v1/tv"} data-games={(base_path())"/api/v1/games"} {
+                h1 { "Arena TV" }
+                p { "Public games as they're played. When one ends, the next one comes on." }
+
+                p id="tv-now-playing" class="lead" {
+                    @if let Some(game) = &now_playing {
+                        (game.snake_names.join(" vs "))
+                    }
+                }
+
+                div class="alert alert-secondary" id="tv-off-air" hidden[now_playing.is_some()] {
+                    p class="mb-0" { "No public games are running right now. This page will tune in when one starts." }
+                }
+
+                div class="board-viewer-container mb-4" {
+                    iframe
+                        id="board-viewer"
+                        src=[now_playing.as_ref().map(|game| board_viewer_url(&public_url, game.game_id))]
+                        hidden[now_playing.is_none()]
+                        title="Battlesnake Board Viewer"
+                        allow="accelerometer; autoplay; clipboard-write; encrypted-media; gyroscope; picture-in-picture"
+                        allowfullscreen {}
+                }
+
+                p class="text-muted" {
+                    "Embedding \"now playing\" somewhere else? "
+                    code { "GET " (public_url.url("/api/v1/tv")) }
+                    " returns it as JSON."
+                }
+            }
+            script src={(base_path())"/static/tv.js"} {}
+        }),
+    ))
+}
@@ -0,0 +1,295 @@
+//! Limits on how many games run at once
+//!
+//! Every game runs in its own `GameRunnerJob`, and each running game holds
+//! database connections and snake requests for as long as it lasts. Before a
+//! job plays its game it takes a permit from this instance's pool, and checks
+//! that none of the game's snake owners already has too many games running
+//! across every instance. If either check fails the game isn't played yet: the
+//! job is queued again a few seconds later and the worker moves on, so a
+//! stress test queueing thousands of games waits its turn instead of starving
+//! everything else of connections.
+//!
+//! The per-user check counts running games in the database, so two workers
+//! starting the same user's games at the same moment can both get in. The
+//! quota is a brake, not an exact limit.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+use std::time::Duration;
+
+use color_eyre::eyre::Context as _;
+use sqlx::PgPool;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use uuid::Uuid;
+
+use crate::config::env_or;
+use crate::jobs::{self, GameRunnerJob};
+use crate::state::AppState;
+
+/// How many games may run at once
+#[derive(Debug, Clone)]
+pub struct RunnerPoolConfig {
+    /// Games this instance runs at once
+    pub max_running_games: usize,
+    /// Games with a user's snakes in them running at once, across every instance
+    pub max_running_games_per_user: i64,
+    /// How long a game that couldn't start waits before its job runs again
+    pub retry_delay: Duration,
+}
+
+impl Default for RunnerPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_running_games: 16,
+            max_running_games_per_user: 4,
+            retry_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RunnerPoolConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let config = Self {
+            max_running_games: env_or("ARENA_MAX_RUNNING_GAMES", defaults.max_running_games).max(1),
+            max_running_games_per_user: env_or(
+                "ARENA_MAX_RUNNING_GAMES_PER_USER",
+                defaults.max_running_games_per_user,
+            )
+            .max(1),
+            retry_delay: Duration::from_secs(
+                env_or(
+                    "ARENA_RUNNER_RETRY_DELAY_SECS",
+                    defaults.retry_delay.as_secs(),
+                )
+                .max(1),
+            ),
+        };
+
+        tracing::info!(
+            max_running_games = config.max_running_games,
+            max_running_games_per_user = config.max_running_games_per_user,
+            retry_delay_secs = config.retry_delay.as_secs(),
+            "Game runner pool configured"
+        );
+
+        config
+    }
+}
+
+/// Whether a game can start now
+#[derive(Debug)]
+pub enum Admission {
+    /// Run it, holding the permit until the game is done
+    Run(OwnedSemaphorePermit),
+    /// This instance is already running as many games as it may
+    PoolFull,
+    /// One of the game's snake owners already has this many games running
+    OverQuota { user_id: Uuid, running: i64 },
+}
+
+#[derive(Debug)]
+struct OwnerLoad {
+    user_id: Uuid,
+    running: i64,
+}
+
+/// The running-game permits for this instance, and counts of games that had
+/// to wait for one
+#[derive(Debug, Clone)]
+pub struct RunnerPool {
+    config: RunnerPoolConfig,
+    permits: Arc<Semaphore>,
+    deferred_pool_full: Arc<AtomicU64>,
+    deferred_over_quota: Arc<AtomicU64>,
+}
+
+impl RunnerPool {
+    pub fn new(config: RunnerPoolConfig) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(config.max_running_games)),
+            config,
+            deferred_pool_full: Arc::default(),
+            deferred_over_quota: Arc::default(),
+        }
+    }
+
+    pub fn config(&self) -> &RunnerPoolConfig {
+        &self.config
+    }
+
+    /// Games this instance is running now
+    pub fn running(&self) -> usize {
+        self.config.max_running_games - self.permits.available_permits()
+    }
+
+    /// Check whether `game_id` can start, taking a permit if it can
+    pub async fn admit(&self, pool: &PgPool, game_id: Uuid) -> cja::Result<Admission> {
+        let Ok(permit) = self.permits.clone().try_acquire_owned() else {
+            self.deferred_pool_full.fetch_add(1, Ordering::Relaxed);
+            return Ok(Admission::PoolFull);
+        };
+
+        if let Some(owner) = busiest_owner(pool, game_id).await?
+            && owner.running >= self.config.max_running_games_per_user
+        {
+            self.deferred_over_quota.fetch_add(1, Ordering::Relaxed);
+            return Ok(Admission::OverQuota {
+                user_id: owner.user_id,
+                running: owner.running,
+            });
+        }
+
+        Ok(Admission::Run(permit))
+    }
+
+    /// The pool's gauges and counters in Prometheus' text exposition format.
+    /// `queued` is how many game jobs are waiting to run, across every instance.
+    pub fn render(&self, queued: Option<i64>) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP arena_running_games Games this instance is running right now\n");
+        out.push_str("# TYPE arena_running_games gauge\n");
+        out.push_str(&format!("arena_running_games {}\n", self.running()));
+        out.push_str("# HELP arena_running_games_limit Games this instance may run at once\n");
+        out.push_str("# TYPE arena_running_games_limit gauge\n");
+        out.push_str(&format!(
+            "arena_running_games_limit {}\n",
+            self.config.max_running_games
+        ));
+        if let Some(queued) = queued {
+            out.push_str("# HELP arena_queued_games Game jobs waiting for a worker\n");
+            out.push_str("# TYPE arena_queued_games gauge\n");
+            out.push_str(&format!("arena_queued_games {}\n", queued));
+        }
+        out.push_str("# HELP arena_deferred_games_total Games put back in the queue because they couldn't start\n");
+        out.push_str("# TYPE arena_deferred_games_total counter\n");
+        out.push_str(&format!(
+            "arena_deferred_games_total{{reason=\"pool_full\"}} {}\n",
+            self.deferred_pool_full.load(Ordering::Relaxed)
+        ));
+        out.push_str(&format!(
+            "arena_deferred_games_total{{reason=\"user_quota\"}} {}\n",
+            self.deferred_over_quota.load(Ordering::Relaxed)
+        ));
+        out
+    }
+}
+
+/// The owner of one of `game_id`'s snakes with the most other games running
+async fn busiest_owner(pool: &PgPool, game_id: Uuid) -> cja::Result<Option<OwnerLoad>> {
+    sqlx::query_as!(
+        OwnerLoad,
+        r#"
+        SELECT owners.user_id, COUNT(DISTINCT g.game_id) AS "running!"
+        FROM (
+            SELECT DISTINCT b.user_id
+            FROM game_battlesnakes gb
+            JOIN battlesnakes b ON b.battlesnake_id = gb.battlesnake_id
+            WHERE gb.game_id = $1
+        ) owners
+        JOIN battlesnakes b ON b.user_id = owners.user_id
+        JOIN game_battlesnakes gb ON gb.battlesnake_id = b.battlesnake_id
+        JOIN games g ON g.game_id = gb.game_id
+        WHERE g.status = 'running' AND g.game_id <> $1
+        GROUP BY owners.user_id
+        ORDER BY COUNT(DISTINCT g.game_id) DESC
+        LIMIT 1
+        "#,
+        game_id
+    )
+    .fetch_optional(pool)
+    .await
+    .wrap_err("Failed to count running games for the game's snake owners")
+}
+
+/// Game jobs waiting for a worker, due now or deferred
+pub async fn queued_games(pool: &PgPool) -> cja::Result<i64> {
+    sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM jobs WHERE name = $1 AND locked_at IS NULL"#,
+        <GameRunnerJob as cja::jobs::Job<AppState>>::NAME
+    )
+    .fetch_one(pool)
+    .await
+    .wrap_err("Failed to count queued games")
+}
+
+/// Run `game_id` if the pool and its owners' quotas allow it, or queue it
+/// again for later
+pub async fn run_or_defer(app_state: &AppState, game_id: Uuid) -> cja::Result<()> {
+    let pool = &app_state.runner_pool;
+    let permit = match pool.admit(&app_state.db, game_id).await? {
+        Admission::Run(permit) => permit,
+        Admission::PoolFull => {
+            tracing::debug!(game_id = %game_id, running = pool.running(), "Runner pool full, deferring game");
+            return defer(app_state, game_id).await;
+        }
+        Admission::OverQuota { user_id, running } => {
+            tracing::info!(
+                game_id = %game_id,
+                user_id = %user_id,
+                running,
+                "Snake owner is at their running game quota, deferring game"
+            );
+            return defer(app_state, game_id).await;
+        }
+    };
+
+    let result = crate::game_runner::run_game(app_state, game_id).await;
+    drop(permit);
+    result
+}
+
+async fn defer(app_state: &AppState, game_id: Uuid) -> cja::Result<()> {
+    let delay = chrono::Duration::from_std(app_state.runner_pool.config().retry_delay)
+        .wrap_err("Runner retry delay out of range")?;
+    jobs::enqueue_at(
+        &app_state.db,
+        GameRunnerJob { game_id },
+        format!("Deferred game {}", game_id),
+        0,
+        app_state.clock.now() + delay,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_running_counts_held_permits() {
+        let pool = RunnerPool::new(RunnerPoolConfig {
+            max_running_games: 2,
+            ..Default::default()
+        });
+        assert_eq!(pool.running(), 0);
+
+        let first = pool.permits.clone().try_acquire_owned().unwrap();
+        let second = pool.permits.clone().try_acquire_owned().unwrap();
+        assert_eq!(pool.running(), 2);
+        assert!(pool.permits.clone().try_acquire_owned().is_err());
+
+        drop(first);
+        assert_eq!(pool.running(), 1);
+        drop(second);
+        assert_eq!(pool.running(), 0);
+    }
+
+    #[test]
+    fn test_render() {
+        let pool = RunnerPool::new(RunnerPoolConfig::default());
+        let _permit = pool.permits.clone().try_acquire_owned().unwrap();
+        pool.deferred_pool_full.fetch_add(3, Ordering::Relaxed);
+
+        let metrics = pool.render(Some(7));
+        assert!(metrics.contains("arena_running_games 1\n"));
+        assert!(metrics.contains("arena_running_games_limit 16\n"));
+        assert!(metrics.contains("arena_queued_games 7\n"));
+        assert!(metrics.contains("arena_deferred_games_total{reason=\"pool_full\"} 3\n"));
+        assert!(metrics.contains("arena_deferred_games_total{reason=\"user_quota\"} 0\n"));
+
+        assert!(!pool.render(None).contains("arena_queued_games"));
+    }
+}
@@ -0,0 +1,109 @@
+//! Quick test games from the snake edit page
+//!
+//! "Test this snake" plays the snake against a built-in bot on a 7x7 board with
+//! a short turn limit. The game is enqueued ahead of regular games, so the
+//! replay is ready moments after registering or editing a snake's URL.
+//! Sandbox games are unranked, so testing a snake never moves its rating.
+
+use color_eyre::eyre::Context as _;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::jobs::{GameRunnerJob, HIGH_PRIORITY, enqueue_with_priority};
+use crate::models::battlesnake::{
+    Battlesnake, CreateBattlesnake, Visibility, create_battlesnake, get_battlesnakes_by_user_id,
+};
+use crate::models::game::{
+    CreateGameWithSnakes, GameBoardSize, GameSettings, GameType, create_game_with_snakes,
+    set_game_enqueued_at, set_game_settings,
+};
+use crate::state::AppState;
+
+/// Turn limit for sandbox games, enough to see a snake eat and avoid walls
+pub const SANDBOX_MAX_TURNS: i32 = 150;
+
+/// The built-in bot sandbox games are played against
+const SANDBOX_BOT_NAME: &str = "Sandbox Bot";
+const SANDBOX_BOT_URL: &str = "bot:food";
+
+/// The user already has a snake of their own named like the sandbox bot
+///
+/// Snake names are unique per user, so the bot can't be created next to it.
+#[derive(Debug, thiserror::Error)]
+#[error("You already have a snake named '{SANDBOX_BOT_NAME}'. Rename it to test your snakes.")]
+pub struct SandboxBotNameTaken;
+
+/// The user's sandbox opponent, created the first time they test a snake
+async fn sandbox_bot(pool: &PgPool, user_id: Uuid) -> cja::Result<Battlesnake> {
+    let existing = get_battlesnakes_by_user_id(pool, user_id)
+        .await?
+        .into_iter()
+        .find(|s| s.name == SANDBOX_BOT_NAME);
+    if let Some(snake) = existing {
+        // Never repoint one of the user's real snakes at the bot
+        if snake.url != SANDBOX_BOT_URL {
+            return Err(SandboxBotNameTaken.into());
+        }
+        return Ok(snake);
+    }
+
+    create_battlesnake(
+        pool,
+        user_id,
+        CreateBattlesnake {
+            name: SANDBOX_BOT_NAME.to_string(),
+            url: SANDBOX_BOT_URL.to_string(),
+            visibility: Visibility::Private,
+        },
+    )
+    .await
+    .wrap_err("Failed to create sandbox bot")
+}
+
+/// Start a sandbox game for a snake against its owner's sandbox bot, returning the game ID
+pub async fn start_sandbox_game(
+    app_state: &AppState,
+    battlesnake: &Battlesnake,
+) -> cja::Result<Uuid> {
+    let pool = &app_state.db;
+    let bot = sandbox_bot(pool, battlesnake.user_id).await?;
+
+    let game = create_game_with_snakes(
+        pool,
+        CreateGameWithSnakes {
+            board_size: GameBoardSize::Small,
+            game_type: GameType::Standard,
+            battlesnake_ids: vec![battlesnake.battlesnake_id, bot.battlesnake_id],
+        },
+    )
+    .await
+    .wrap_err("Failed to create sandbox game")?;
+
+    set_game_settings(
+        pool,
+        game.game_id,
+        GameSettings {
+            max_turns: Some(SANDBOX_MAX_TURNS),
+            // A quick test against a bot shouldn't move the snake's rating
+            ranked: false,
+            ..GameSettings::default()
+        },
+    )
+    .await?;
+    set_game_enqueued_at(pool, game.game_id, app_state.clock.now()).await?;
+
+    enqueue_with_priority(
+        GameRunnerJob {
+            game_id: game.game_id,
+        },
+        app_state,
+        format!(
+            "Sandbox game {} for snake {}",
+            game.game_id, battlesnake.battlesnake_id
+        ),
+        HIGH_PRIORITY,
+    )
+    .await?;
+
+    Ok(game.game_id)
+}
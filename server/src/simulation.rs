@@ -0,0 +1,440 @@
+//! In-process scheduler simulation, for capacity planning
+//!
+//! `arena admin simulate` (and the stress test's `--shadow` mode, which runs
+//! it) creates games through the same code the create game API uses and runs
+//! them with the real job worker and game runner, all in one process. Every
+//! snake is a built-in `bot:` snake, so no turn waits on the network and what's
+//! measured is the engine and the database. When the games are done it reports
+//! the throughput seen and the ceilings the stage timings imply: how many turns
+//! a second one core can apply and serialize, and how many the database pool
+//! can store. Those are the numbers to size hardware for a big tournament with.
+//!
+//! It writes real games to the database, so like seeding it refuses to run
+//! against anything but a local database without `--force`.
+
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::{Context as _, eyre};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::{
+    jobs,
+    models::{
+        battlesnake::{self, CreateBattlesnake, Visibility},
+        game::{CreateGameWithSnakes, GameBoardSize, GameSettings, GameType, SpawnStrategy},
+    },
+    routes::api::games::{MAX_GAME_SNAKES, parse_board_size, parse_game_type, start_game},
+    runner_pool::{RunnerPool, RunnerPoolConfig},
+    seed,
+    state::AppState,
+    turn_metrics::{Stage, TurnMetrics},
+};
+
+/// GitHub ID of the user the simulated snakes belong to, next to the seed users'
+const SIMULATION_GITHUB_ID: i64 = 9_000_000_100;
+
+/// How often the job workers look for new jobs while simulating
+const SIMULATION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Options for the simulate command
+#[derive(Debug, Clone)]
+pub struct SimulationOptions {
+    /// Games to create
+    pub games: usize,
+    /// Games created a second; None creates them all at once
+    pub rate: Option<f64>,
+    pub snakes_per_game: usize,
+    /// Board size and game type, as the create game API takes them
+    pub board: String,
+    pub game_type: String,
+    pub max_turns: Option<i32>,
+    /// Job workers to run, like running that many instances
+    pub workers: usize,
+    /// Give up on games that haven't finished after this long
+    pub timeout: Duration,
+    /// Simulate even if DATABASE_URL isn't a local database
+    pub force: bool,
+}
+
+/// What a simulation measured
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationReport {
+    pub games_created: usize,
+    pub games_finished: i64,
+    pub turns: i64,
+    /// From the first game created to the last one finishing
+    pub elapsed: Duration,
+    /// Mean time to create and queue a game
+    pub mean_create: Duration,
+    /// Mean time per turn the engine spent applying moves and serializing frames
+    pub mean_engine: Duration,
+    /// Mean time per turn spent storing the turn
+    pub mean_persist: Duration,
+    /// Connections in the database pool
+    pub pool_connections: u32,
+}
+
+impl SimulationReport {
+    pub fn games_per_second(&self) -> f64 {
+        per_second(self.games_finished as f64, self.elapsed)
+    }
+
+    pub fn turns_per_second(&self) -> f64 {
+        per_second(self.turns as f64, self.elapsed)
+    }
+
+    /// Turns a second one core can apply and serialize
+    pub fn engine_ceiling(&self) -> f64 {
+        per_second(1.0, self.mean_engine)
+    }
+
+    /// Turns a second the database pool can store, every connection busy
+    pub fn database_ceiling(&self) -> f64 {
+        per_second(f64::from(self.pool_connections), self.mean_persist)
+    }
+
+    /// Games a second that can be created and queued, one at a time
+    pub fn creation_ceiling(&self) -> f64 {
+        per_second(1.0, self.mean_create)
+    }
+
+    fn print(&self) {
+        let turns_per_game = self.turns as f64 / self.games_finished.max(1) as f64;
+        println!();
+        println!("=== Simulation Results ===");
+        println!(
+            "Games: {} created, {} finished in {:.1}s",
+            self.games_created,
+            self.games_finished,
+            self.elapsed.as_secs_f64()
+        );
+        println!(
+            "Observed: {:.1} games/s, {:.0} turns/s ({:.0} turns/game)",
+            self.games_per_second(),
+            self.turns_per_second(),
+            turns_per_game
+        );
+        println!(
+            "Create and queue: {:.1}ms per game, ceiling {:.0} games/s",
+            ms(self.mean_create),
+            self.creation_ceiling()
+        );
+        println!(
+            "Engine: {:.2}ms per turn, ceiling {:.0} turns/s per core",
+            ms(self.mean_engine),
+            self.engine_ceiling()
+        );
+        println!(
+            "Database: {:.2}ms per turn stored, ceiling {:.0} turns/s with {} connections",
+            ms(self.mean_persist),
+            self.database_ceiling(),
+            self.pool_connections
+        );
+        if turns_per_game > 0.0 {
+            println!(
+                "At {:.0} turns/game that's {:.1} games/s per core, or {:.1} games/s for the database",
+                turns_per_game,
+                self.engine_ceiling() / turns_per_game,
+                self.database_ceiling() / turns_per_game
+            );
+        }
+    }
+}
+
+fn per_second(count: f64, elapsed: Duration) -> f64 {
+    if elapsed.is_zero() {
+        return 0.0;
+    }
+    count / elapsed.as_secs_f64()
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Mean time per turn across `stages`
+fn mean_per_turn(metrics: &TurnMetrics, stages: &[Stage]) -> Duration {
+    stages.iter().map(|stage| metrics.mean(*stage).1).sum()
+}
+
+/// The simulation user's bot snakes, created the first time they're needed
+async fn simulation_snakes(app_state: &AppState, count: usize) -> cja::Result<Vec<Uuid>> {
+    let user = seed::seed_user(
+        app_state,
+        SIMULATION_GITHUB_ID,
+        "sim-capacity",
+        "Capacity simulation",
+    )
+    .await?;
+    let existing = battlesnake::get_battlesnakes_by_user_id(&app_state.db, user.user_id).await?;
+
+    let mut snakes = Vec::with_capacity(count);
+    for i in 0..count {
+        // Alternate bots, so games have snakes that eat as well as wander
+        let bot = if i % 2 == 0 { "random" } else { "food" };
+        let name = format!("sim {} {}", bot, i + 1);
+        if let Some(snake) = existing.iter().find(|s| s.name == name) {
+            snakes.push(snake.battlesnake_id);
+            continue;
+        }
+
+        let snake = battlesnake::create_battlesnake(
+            &app_state.db,
+            user.user_id,
+            CreateBattlesnake {
+                name,
+                url: format!("bot:{}", bot),
+                visibility: Visibility::Private,
+            },
+        )
+        .await
+        .wrap_err("Failed to create simulation snake")?;
+        snakes.push(snake.battlesnake_id);
+    }
+    Ok(snakes)
+}
+
+/// What every simulated game is created with
+struct GameTemplate {
+    board_size: GameBoardSize,
+    game_type: GameType,
+    max_turns: Option<i32>,
+    snakes: Vec<Uuid>,
+}
+
+/// Create and queue one game the way `POST /api/v1/games` does
+async fn create_game(app_state: &AppState, template: &GameTemplate) -> cja::Result<Uuid> {
+    let settings = GameSettings {
+        max_turns: template.max_turns,
+        seed: Some(rand::random()),
+        ranked: false,
+        ..GameSettings::default()
+    };
+    let create_request = CreateGameWithSnakes {
+        board_size: template.board_size,
+        game_type: template.game_type,
+        battlesnake_ids: template.snakes.clone(),
+    };
+    let game = start_game(app_state, create_request, settings, &SpawnStrategy::Random)
+        .await
+        .map_err(|(status, message)| eyre!("Failed to create game ({}): {}", status, message))?;
+    Ok(game.game_id)
+}
+
+async fn count_games(
+    app_state: &AppState,
+    game_ids: &[Uuid],
+    finished_only: bool,
+) -> cja::Result<i64> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM games
+        WHERE game_id = ANY($1) AND ($2 = FALSE OR status = 'finished')
+        "#,
+        game_ids,
+        finished_only
+    )
+    .fetch_one(&app_state.db)
+    .await
+    .wrap_err("Failed to count simulated games")
+}
+
+async fn count_turns(app_state: &AppState, game_ids: &[Uuid]) -> cja::Result<i64> {
+    sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM turns WHERE game_id = ANY($1)"#,
+        game_ids
+    )
+    .fetch_one(&app_state.db)
+    .await
+    .wrap_err("Failed to count simulated turns")
+}
+
+/// Run a simulation and print its report
+pub async fn run(app_state: &AppState, options: SimulationOptions) -> cja::Result<()> {
+    let database_url = std::env::var("DATABASE_URL").wrap_err("DATABASE_URL must be set")?;
+    if !options.force && !seed::is_local_database(&database_url) {
+        return Err(eyre!(
+            "Refusing to simulate against a non-local database, pass --force if you really mean it"
+        ));
+    }
+    if options.games == 0 {
+        return Err(eyre!("Nothing to simulate, --games must be at least 1"));
+    }
+    if !(1..=MAX_GAME_SNAKES).contains(&options.snakes_per_game) {
+        return Err(eyre!(
+            "--snakes-per-game must be between 1 and {}",
+            MAX_GAME_SNAKES
+        ));
+    }
+    if options.rate.is_some_and(|rate| rate <= 0.0) {
+        return Err(eyre!("--rate must be positive"));
+    }
+    let board_size = parse_board_size(&options.board).map_err(|e| eyre!(e))?;
+    let game_type = parse_game_type(&options.game_type).map_err(|e| eyre!(e))?;
+    crate::game_settings::validate(&app_state.game_limits, None, options.max_turns, None)
+        .map_err(|e| eyre!(e))?;
+
+    // Every simulated game belongs to one user, so their quota would cap the
+    // run at a handful of games. The instance-wide limit still applies.
+    let mut app_state = app_state.clone();
+    app_state.runner_pool = RunnerPool::new(RunnerPoolConfig {
+        max_running_games_per_user: i64::MAX,
+        ..app_state.runner_pool.config().clone()
+    });
+    app_state.turn_metrics = TurnMetrics::default();
+
+    let template = GameTemplate {
+        board_size,
+        game_type,
+        max_turns: app_state.game_limits.turn_limit(options.max_turns),
+        snakes: simulation_snakes(&app_state, options.snakes_per_game).await?,
+    };
+    println!(
+        "Simulating {} games of {} bot snakes with {} job workers, at most {} games running at once",
+        options.games,
+        template.snakes.len(),
+        options.workers.max(1),
+        app_state.runner_pool.config().max_running_games
+    );
+
+    let cancel = CancellationToken::new();
+    let workers: Vec<_> = (0..options.workers.max(1))
+        .map(|_| {
+            tokio::spawn(cja::jobs::worker::job_worker(
+                app_state.clone(),
+                jobs::Jobs,
+                SIMULATION_POLL_INTERVAL,
+                cja::jobs::worker::DEFAULT_MAX_RETRIES,
+                cancel.clone(),
+                cja::jobs::worker::DEFAULT_LOCK_TIMEOUT,
+            ))
+        })
+        .collect();
+
+    let started = Instant::now();
+    let mut game_ids = Vec::with_capacity(options.games);
+    let mut create_time = Duration::ZERO;
+    let mut interval = options
+        .rate
+        .map(|rate| tokio::time::interval(Duration::from_secs_f64(1.0 / rate)));
+    for _ in 0..options.games {
+        if let Some(interval) = interval.as_mut() {
+            interval.tick().await;
+        }
+        let create_started = Instant::now();
+        game_ids.push(create_game(&app_state, &template).await?);
+        create_time += create_started.elapsed();
+    }
+    println!(
+        "Created {} games in {:.1}s",
+        game_ids.len(),
+        started.elapsed().as_secs_f64()
+    );
+
+    let deadline = started + options.timeout;
+    let mut finished = 0;
+    while Instant::now() < deadline {
+        finished = count_games(&app_state, &game_ids, true).await?;
+        println!(
+            "[{:.0}s] {} of {} games finished, {} running",
+            started.elapsed().as_secs_f64(),
+            finished,
+            game_ids.len(),
+            app_state.runner_pool.running()
+        );
+        if finished as usize >= game_ids.len() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    let elapsed = started.elapsed();
+
+    cancel.cancel();
+    for worker in workers {
+        match worker.await {
+            Ok(Err(e)) => tracing::warn!(error = %e, "Simulation job worker failed"),
+            Err(e) => tracing::warn!(error = %e, "Simulation job worker panicked"),
+            Ok(Ok(())) => {}
+        }
+    }
+
+    if (finished as usize) < game_ids.len() {
+        println!(
+            "Gave up on {} games after {}s",
+            game_ids.len() - finished as usize,
+            options.timeout.as_secs()
+        );
+    }
+
+    let report = SimulationReport {
+        games_created: count_games(&app_state, &game_ids, false).await? as usize,
+        games_finished: finished,
+        turns: count_turns(&app_state, &game_ids).await?,
+        elapsed,
+        mean_create: create_time / game_ids.len() as u32,
+        mean_engine: mean_per_turn(
+            &app_state.turn_metrics,
+            &[Stage::ApplyTurn, Stage::SerializeFrame],
+        ),
+        mean_persist: mean_per_turn(&app_state.turn_metrics, &[Stage::Persist]),
+        pool_connections: app_state.db.options().get_max_connections(),
+    };
+    report.print();
+    tracing::info!(
+        games_created = report.games_created,
+        games_finished = report.games_finished,
+        turns = report.turns,
+        games_per_second = report.games_per_second(),
+        turns_per_second = report.turns_per_second(),
+        engine_ceiling = report.engine_ceiling(),
+        database_ceiling = report.database_ceiling(),
+        creation_ceiling = report.creation_ceiling(),
+        "simulation_report"
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_ceilings() {
+        let report = SimulationReport {
+            games_created: 10,
+            games_finished: 10,
+            turns: 2000,
+            elapsed: Duration::from_secs(4),
+            mean_create: Duration::from_millis(5),
+            mean_engine: Duration::from_micros(250),
+            mean_persist: Duration::from_millis(2),
+            pool_connections: 5,
+        };
+
+        assert_eq!(report.games_per_second(), 2.5);
+        assert_eq!(report.turns_per_second(), 500.0);
+        assert_eq!(report.engine_ceiling(), 4000.0);
+        assert_eq!(report.database_ceiling(), 2500.0);
+        assert_eq!(report.creation_ceiling(), 200.0);
+    }
+
+    #[test]
+    fn test_report_with_nothing_measured() {
+        let report = SimulationReport {
+            games_created: 0,
+            games_finished: 0,
+            turns: 0,
+            elapsed: Duration::ZERO,
+            mean_create: Duration::ZERO,
+            mean_engine: Duration::ZERO,
+            mean_persist: Duration::ZERO,
+            pool_connections: 5,
+        };
+        assert_eq!(report.turns_per_second(), 0.0);
+        assert_eq!(report.engine_ceiling(), 0.0);
+        assert_eq!(report.database_ceiling(), 0.0);
+    }
+}
@@ -5,12 +5,13 @@
 
 use battlesnake_game_types::types::Move;
 use battlesnake_game_types::wire_representation::{BattleSnake, Game};
-use reqwest::Client;
-use serde::Deserialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
 use url::Url;
 
+use crate::models::game::MoveValidationMode;
+use crate::snake_http::SnakeHttpClient;
+
 /// Response from a snake's /move endpoint
 #[derive(Debug, Deserialize)]
 pub struct MoveResponse {
@@ -19,14 +20,94 @@ pub struct MoveResponse {
     pub shout: Option<String>,
 }
 
+/// What a snake's GET / says about it: its API version and customizations
+///
+/// Fields the snake left out, or sent as something other than a string, are None.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnakeInfo {
+    pub apiversion: Option<String>,
+    pub author: Option<String>,
+    pub color: Option<String>,
+    pub head: Option<String>,
+    pub tail: Option<String>,
+}
+
+impl SnakeInfo {
+    /// The snake's color, if it's a hex color the board viewer can draw
+    pub fn hex_color(&self) -> Option<&str> {
+        self.color.as_deref().filter(|c| {
+            c.len() == 7 && c.starts_with('#') && c[1..].chars().all(|ch| ch.is_ascii_hexdigit())
+        })
+    }
+}
+
 /// Result of a move request including timing info
 #[derive(Debug, Clone)]
 pub struct MoveResult {
     pub snake_id: String,
     pub direction: Move,
     pub latency_ms: Option<i64>,
+    /// `latency_ms` minus the snake's network allowance, when latency compensation is on
+    pub compensated_latency_ms: Option<i64>,
     pub timed_out: bool,
     pub shout: Option<String>,
+    /// Why the response couldn't be parsed, if it couldn't
+    pub parse_error: Option<String>,
+    /// Why no response arrived (network error or timeout), if it didn't
+    pub request_error: Option<String>,
+}
+
+/// How long to wait for a snake's /move response
+///
+/// With latency compensation the snake gets its full `timeout` to compute a move,
+/// plus an allowance for the network round trip measured during /start, matching
+/// how the official engine budgets time rather than cutting everyone off at a flat
+/// client timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveDeadline {
+    pub timeout: Duration,
+    /// Network round trip to add on top of `timeout`, if latency compensation is on
+    pub network_allowance: Option<Duration>,
+}
+
+impl MoveDeadline {
+    /// A flat deadline with no compensation
+    pub fn flat(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            network_allowance: None,
+        }
+    }
+
+    /// A deadline extended by a measured round trip, capped at `timeout` so a
+    /// very slow /start can't more than double the wait
+    pub fn compensated(timeout: Duration, rtt: Duration) -> Self {
+        Self {
+            timeout,
+            network_allowance: Some(rtt.min(timeout)),
+        }
+    }
+
+    /// Total time to wait for the response
+    pub fn total(&self) -> Duration {
+        self.timeout + self.network_allowance.unwrap_or_default()
+    }
+
+    /// The part of a measured latency that counts against the snake's compute budget
+    pub fn compensated_latency_ms(&self, latency_ms: i64) -> Option<i64> {
+        self.network_allowance
+            .map(|allowance| (latency_ms - allowance.as_millis() as i64).max(0))
+    }
+}
+
+/// The only shape strict validation accepts
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StrictMoveResponse {
+    #[serde(rename = "move")]
+    direction: String,
+    #[serde(default)]
+    shout: Option<String>,
 }
 
 /// Build the request body for a specific snake
@@ -43,7 +124,7 @@ fn build_request_for_snake(game: &Game, snake: &BattleSnake) -> Game {
 }
 
 /// Parse a direction string into a Move enum
-fn parse_direction(s: &str) -> Option<Move> {
+pub(crate) fn parse_direction(s: &str) -> Option<Move> {
     match s.to_lowercase().as_str() {
         "up" => Some(Move::Up),
         "down" => Some(Move::Down),
@@ -53,6 +134,97 @@ fn parse_direction(s: &str) -> Option<Move> {
     }
 }
 
+/// Longest snippet of a snake's response we'll echo back in a parse error
+const PARSE_ERROR_SNIPPET_LEN: usize = 32;
+
+fn snippet(s: &str) -> String {
+    s.chars().take(PARSE_ERROR_SNIPPET_LEN).collect()
+}
+
+/// Parse the body of a /move response into a direction and shout
+///
+/// Strict mode only accepts the documented shape: a JSON object with a lowercase
+/// `move` and an optional string `shout`, and nothing else. Lenient mode ignores
+/// extra fields, tolerates any casing and surrounding whitespace in the key and
+/// value, and accepts a bare direction instead of an object.
+///
+/// The error is a short human-readable reason, recorded per turn for debugging.
+pub fn parse_move_response(
+    body: &str,
+    mode: MoveValidationMode,
+) -> Result<(Move, Option<String>), String> {
+    match mode {
+        MoveValidationMode::Strict => {
+            let response: StrictMoveResponse =
+                serde_json::from_str(body).map_err(|e| e.to_string())?;
+            let direction = match response.direction.as_str() {
+                "up" => Move::Up,
+                "down" => Move::Down,
+                "left" => Move::Left,
+                "right" => Move::Right,
+                other => return Err(format!("invalid move {:?}", snippet(other))),
+            };
+            Ok((direction, response.shout))
+        }
+        MoveValidationMode::Lenient => {
+            let value = serde_json::from_str(body)
+                .unwrap_or_else(|_| serde_json::Value::String(body.to_string()));
+
+            let (direction, shout) = match &value {
+                serde_json::Value::Object(fields) => {
+                    let field = |name: &str| {
+                        fields
+                            .iter()
+                            .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+                            .map(|(_, value)| value)
+                    };
+                    let direction = field("move")
+                        .ok_or_else(|| "missing \"move\" field".to_string())?
+                        .as_str()
+                        .ok_or_else(|| "\"move\" is not a string".to_string())?;
+                    let shout = field("shout").and_then(|s| s.as_str()).map(str::to_string);
+                    (direction, shout)
+                }
+                serde_json::Value::String(s) => (s.as_str(), None),
+                _ => return Err("expected a JSON object".to_string()),
+            };
+
+            let direction = direction.trim().trim_matches('"').trim();
+            parse_direction(direction)
+                .map(|d| (d, shout))
+                .ok_or_else(|| format!("invalid move {:?}", snippet(direction)))
+        }
+    }
+}
+
+/// Parse the body of a GET / response
+///
+/// Only a body that isn't a JSON object is an error; anything missing or
+/// malformed inside it is left out.
+pub fn parse_info_response(body: &str) -> Result<SnakeInfo, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(body).map_err(|_| format!("not JSON: {:?}", snippet(body)))?;
+    let fields = value
+        .as_object()
+        .ok_or_else(|| "expected a JSON object".to_string())?;
+    let field = |name: &str| {
+        fields
+            .get(name)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+
+    Ok(SnakeInfo {
+        apiversion: field("apiversion"),
+        author: field("author"),
+        color: field("color"),
+        head: field("head"),
+        tail: field("tail"),
+    })
+}
+
 /// Build a URL for a snake endpoint, properly handling query parameters
 ///
 /// This appends the endpoint path (e.g., "move", "start", "end") to the base URL
@@ -74,51 +246,75 @@ fn build_endpoint_url(base_url: &str, endpoint: &str) -> String {
 /// Call a snake's /move endpoint
 ///
 /// On timeout or error, falls back to the last direction (or Up if no last direction).
+/// Responses that fail `mode` validation also fall back; in strict mode they're
+/// additionally counted as a timeout.
 pub async fn request_move(
-    client: &Client,
+    client: &SnakeHttpClient,
     url: &str,
     game: &Game,
     snake: &BattleSnake,
-    timeout: Duration,
+    deadline: MoveDeadline,
     last_direction: Option<Move>,
+    mode: MoveValidationMode,
 ) -> MoveResult {
     let request_body = build_request_for_snake(game, snake);
     let move_url = build_endpoint_url(url, "move");
 
+    // Wait for a per-host slot before starting the clock, so queueing behind
+    // other games isn't counted against the snake
+    let _permit = client.acquire(&move_url).await;
+
     let start = Instant::now();
 
-    let result =
-        tokio::time::timeout(timeout, client.post(&move_url).json(&request_body).send()).await;
+    let result = tokio::time::timeout(
+        deadline.total(),
+        client
+            .client()
+            .post(&move_url)
+            .timeout(deadline.total())
+            .json(&request_body)
+            .send(),
+    )
+    .await;
 
     let elapsed = start.elapsed().as_millis() as i64;
+    let compensated_elapsed = deadline.compensated_latency_ms(elapsed);
 
     match result {
         Ok(Ok(response)) => {
-            match response.json::<MoveResponse>().await {
-                Ok(move_response) => {
-                    let direction = parse_direction(&move_response.direction)
-                        .unwrap_or_else(|| last_direction.unwrap_or(Move::Up));
-                    MoveResult {
-                        snake_id: snake.id.clone(),
-                        direction,
-                        latency_ms: Some(elapsed),
-                        timed_out: false,
-                        shout: move_response.shout,
-                    }
-                }
+            let parsed = match response.text().await {
+                Ok(body) => parse_move_response(&body, mode),
+                Err(e) => Err(format!("failed to read response body: {e}")),
+            };
+
+            match parsed {
+                Ok((direction, shout)) => MoveResult {
+                    snake_id: snake.id.clone(),
+                    direction,
+                    latency_ms: Some(elapsed),
+                    compensated_latency_ms: compensated_elapsed,
+                    timed_out: false,
+                    shout,
+                    parse_error: None,
+                    request_error: None,
+                },
                 Err(e) => {
-                    // JSON parse error - use fallback
+                    // Malformed response - use fallback
                     tracing::warn!(
                         snake_id = %snake.id,
                         error = %e,
+                        validation_mode = mode.as_str(),
                         "Failed to parse move response, using fallback"
                     );
                     MoveResult {
                         snake_id: snake.id.clone(),
                         direction: last_direction.unwrap_or(Move::Up),
                         latency_ms: Some(elapsed),
-                        timed_out: false,
+                        compensated_latency_ms: compensated_elapsed,
+                        timed_out: mode == MoveValidationMode::Strict,
                         shout: None,
+                        parse_error: Some(e),
+                        request_error: None,
                     }
                 }
             }
@@ -134,157 +330,195 @@ pub async fn request_move(
                 snake_id: snake.id.clone(),
                 direction: last_direction.unwrap_or(Move::Up),
                 latency_ms: None,
+                compensated_latency_ms: None,
                 timed_out: true,
                 shout: None,
+                parse_error: None,
+                request_error: Some(format!("request failed: {e}")),
             }
         }
         Err(_) => {
             // Timeout - continue in same direction
             tracing::warn!(
                 snake_id = %snake.id,
-                timeout_ms = timeout.as_millis(),
+                timeout_ms = deadline.total().as_millis(),
                 "Snake timed out, using fallback"
             );
             MoveResult {
                 snake_id: snake.id.clone(),
                 direction: last_direction.unwrap_or(Move::Up),
                 latency_ms: None,
+                compensated_latency_ms: None,
                 timed_out: true,
                 shout: None,
+                parse_error: None,
+                request_error: Some(format!(
+                    "timed out after {}ms",
+                    deadline.total().as_millis()
+                )),
             }
         }
     }
 }
 
-/// Call /start endpoint (fire and forget, no response expected)
+/// Call /start endpoint (no response body expected)
+///
+/// Returns the round trip time if the snake responded, which latency
+/// compensation uses as the snake's network allowance for /move, or a
+/// short description of what went wrong.
 pub async fn request_start(
-    client: &Client,
+    client: &SnakeHttpClient,
     url: &str,
     game: &Game,
     snake: &BattleSnake,
     timeout: Duration,
-) {
+) -> Result<Duration, String> {
     let request_body = build_request_for_snake(game, snake);
     let start_url = build_endpoint_url(url, "start");
 
-    // Fire and forget - ignore result but log errors
-    match tokio::time::timeout(timeout, client.post(&start_url).json(&request_body).send()).await {
+    let _permit = client.acquire(&start_url).await;
+    let start = Instant::now();
+
+    // Ignore the response but log errors
+    match tokio::time::timeout(
+        timeout,
+        client
+            .client()
+            .post(&start_url)
+            .timeout(timeout)
+            .json(&request_body)
+            .send(),
+    )
+    .await
+    {
         Ok(Ok(_)) => {
-            tracing::debug!(snake_id = %snake.id, "Called /start successfully");
+            let rtt = start.elapsed();
+            tracing::debug!(
+                snake_id = %snake.id,
+                rtt_ms = rtt.as_millis() as u64,
+                "Called /start successfully"
+            );
+            Ok(rtt)
         }
         Ok(Err(e)) => {
             tracing::warn!(snake_id = %snake.id, error = %e, "Failed to call /start");
+            Err(format!("request failed: {e}"))
         }
         Err(_) => {
             tracing::warn!(snake_id = %snake.id, "Timeout calling /start");
+            Err(format!("timed out after {}ms", timeout.as_millis()))
         }
     }
 }
 
-/// Call /end endpoint (fire and forget, no response expected)
+/// Call a snake's GET / for its info
+///
+/// Returns a short description of what went wrong if there's no usable answer.
+pub async fn request_info(
+    client: &SnakeHttpClient,
+    url: &str,
+    timeout: Duration,
+) -> Result<SnakeInfo, String> {
+    let _permit = client.acquire(url).await;
+
+    let response =
+        match tokio::time::timeout(timeout, client.client().get(url).timeout(timeout).send()).await
+        {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => return Err(format!("request failed: {e}")),
+            Err(_) => return Err(format!("timed out after {}ms", timeout.as_millis())),
+        };
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("returned {}", status.as_u16()));
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("couldn't read the response: {e}"))?;
+    parse_info_response(&body)
+}
+
+/// Call /end endpoint (no response expected)
+///
+/// Errors are only reported, there's nothing to retry at the end of a game.
 pub async fn request_end(
-    client: &Client,
+    client: &SnakeHttpClient,
     url: &str,
     game: &Game,
     snake: &BattleSnake,
     timeout: Duration,
-) {
+) -> Result<(), String> {
     let request_body = build_request_for_snake(game, snake);
     let end_url = build_endpoint_url(url, "end");
 
-    // Fire and forget - ignore result but log errors
-    match tokio::time::timeout(timeout, client.post(&end_url).json(&request_body).send()).await {
+    let _permit = client.acquire(&end_url).await;
+
+    // Ignore the response but log errors
+    match tokio::time::timeout(
+        timeout,
+        client
+            .client()
+            .post(&end_url)
+            .timeout(timeout)
+            .json(&request_body)
+            .send(),
+    )
+    .await
+    {
         Ok(Ok(_)) => {
             tracing::debug!(snake_id = %snake.id, "Called /end successfully");
+            Ok(())
         }
         Ok(Err(e)) => {
             tracing::warn!(snake_id = %snake.id, error = %e, "Failed to call /end");
+            Err(format!("request failed: {e}"))
         }
         Err(_) => {
             tracing::warn!(snake_id = %snake.id, "Timeout calling /end");
+            Err(format!("timed out after {}ms", timeout.as_millis()))
         }
     }
 }
 
-/// Request moves from all alive snakes in parallel
-///
-/// Returns a MoveResult for each alive snake.
-pub async fn request_moves_parallel(
-    client: &Client,
-    game: &Game,
-    snake_urls: &[(String, String)], // (snake_id, url)
-    timeout: Duration,
-    last_moves: &HashMap<String, Move>,
-) -> Vec<MoveResult> {
-    let futures: Vec<_> = game
-        .board
-        .snakes
-        .iter()
-        .filter(|s| s.health > 0)
-        .filter_map(|snake| {
-            snake_urls
-                .iter()
-                .find(|(id, _)| id == &snake.id)
-                .map(|(_, url)| {
-                    let last_direction = last_moves.get(&snake.id).copied();
-                    request_move(client, url, game, snake, timeout, last_direction)
-                })
-        })
-        .collect();
-
-    futures::future::join_all(futures).await
-}
-
-/// Call /start for all snakes in parallel
-pub async fn request_start_parallel(
-    client: &Client,
-    game: &Game,
-    snake_urls: &[(String, String)],
-    timeout: Duration,
-) {
-    let futures: Vec<_> = game
-        .board
-        .snakes
-        .iter()
-        .filter_map(|snake| {
-            snake_urls
-                .iter()
-                .find(|(id, _)| id == &snake.id)
-                .map(|(_, url)| request_start(client, url, game, snake, timeout))
-        })
-        .collect();
-
-    futures::future::join_all(futures).await;
-}
-
-/// Call /end for all snakes in parallel
-pub async fn request_end_parallel(
-    client: &Client,
-    game: &Game,
-    snake_urls: &[(String, String)],
-    timeout: Duration,
-) {
-    let futures: Vec<_> = game
-        .board
-        .snakes
-        .iter()
-        .filter_map(|snake| {
-            snake_urls
-                .iter()
-                .find(|(id, _)| id == &snake.id)
-                .map(|(_, url)| request_end(client, url, game, snake, timeout))
-        })
-        .collect();
-
-    futures::future::join_all(futures).await;
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use battlesnake_game_types::wire_representation::{Board, NestedGame, Position, Ruleset};
     use std::collections::VecDeque;
 
+    #[test]
+    fn test_parse_info_response() {
+        let info = parse_info_response(
+            r##"{"apiversion": "1", "author": "coreyja", "color": "#FF8800", "head": "smart-caterpillar", "tail": "bolt", "version": "0.1"}"##,
+        )
+        .unwrap();
+        assert_eq!(
+            info,
+            SnakeInfo {
+                apiversion: Some("1".to_string()),
+                author: Some("coreyja".to_string()),
+                color: Some("#FF8800".to_string()),
+                head: Some("smart-caterpillar".to_string()),
+                tail: Some("bolt".to_string()),
+            }
+        );
+        assert_eq!(info.hex_color(), Some("#FF8800"));
+
+        // Missing, empty and non-string fields are left out
+        let info = parse_info_response(r#"{"apiversion": "1", "color": 5, "head": ""}"#).unwrap();
+        assert_eq!(info.color, None);
+        assert_eq!(info.head, None);
+        assert_eq!(info.tail, None);
+
+        let info = parse_info_response(r#"{"color": "red"}"#).unwrap();
+        assert_eq!(info.hex_color(), None);
+
+        assert!(parse_info_response("<html></html>").is_err());
+        assert!(parse_info_response("[1]").is_err());
+    }
+
     #[test]
     fn test_build_endpoint_url_simple() {
         let url = build_endpoint_url("https://example.com", "move");
@@ -321,6 +555,44 @@ mod tests {
         assert_eq!(url, "https://example.com/api/start?token=secret");
     }
 
+    #[test]
+    fn test_build_endpoint_url_with_ipv6_host() {
+        assert_eq!(
+            build_endpoint_url("http://[::1]:8000", "move"),
+            "http://[::1]:8000/move"
+        );
+        assert_eq!(
+            build_endpoint_url("http://[2001:db8::1]/snake/?token=abc", "start"),
+            "http://[2001:db8::1]/snake/start?token=abc"
+        );
+    }
+
+    #[test]
+    fn test_build_endpoint_url_with_port() {
+        assert_eq!(
+            build_endpoint_url("http://localhost:8443/api", "move"),
+            "http://localhost:8443/api/move"
+        );
+        // The scheme's default port is dropped
+        assert_eq!(
+            build_endpoint_url("https://example.com:443/api", "move"),
+            "https://example.com/api/move"
+        );
+    }
+
+    #[test]
+    fn test_build_endpoint_url_with_encoded_path() {
+        // Existing escapes are kept as they are, not encoded twice
+        assert_eq!(
+            build_endpoint_url("https://example.com/my%20snake/a%2Fb?token=x%26y", "move"),
+            "https://example.com/my%20snake/a%2Fb/move?token=x%26y"
+        );
+        assert_eq!(
+            build_endpoint_url("https://example.com/my snake", "end"),
+            "https://example.com/my%20snake/end"
+        );
+    }
+
     #[test]
     fn test_build_endpoint_url_all_endpoints() {
         let base = "https://snake.example.com?auth=abc123";
@@ -400,8 +672,11 @@ mod tests {
             snake_id: "test".to_string(),
             direction: Move::Up,
             latency_ms: Some(100),
+            compensated_latency_ms: None,
             timed_out: false,
             shout: Some("hello".to_string()),
+            parse_error: None,
+            request_error: None,
         };
         let cloned = result.clone();
         assert_eq!(cloned.snake_id, "test");
@@ -468,4 +743,111 @@ mod tests {
         // parse_direction handles case normalization
         assert_eq!(parse_direction(&response.direction), Some(Move::Left));
     }
+
+    #[test]
+    fn test_parse_move_response_valid_in_both_modes() {
+        let body = r#"{"move": "left", "shout": "hi"}"#;
+        for mode in [MoveValidationMode::Strict, MoveValidationMode::Lenient] {
+            assert_eq!(
+                parse_move_response(body, mode),
+                Ok((Move::Left, Some("hi".to_string())))
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_move_response_strict_rejects_extra_fields() {
+        let body = r#"{"move": "up", "taunt": "old api"}"#;
+        let err = parse_move_response(body, MoveValidationMode::Strict).unwrap_err();
+        assert!(err.contains("taunt"), "unexpected error: {err}");
+
+        assert_eq!(
+            parse_move_response(body, MoveValidationMode::Lenient),
+            Ok((Move::Up, None))
+        );
+    }
+
+    #[test]
+    fn test_parse_move_response_casing() {
+        let body = r#"{"Move": " DOWN "}"#;
+        assert!(parse_move_response(body, MoveValidationMode::Strict).is_err());
+        assert_eq!(
+            parse_move_response(body, MoveValidationMode::Lenient),
+            Ok((Move::Down, None))
+        );
+
+        let body = r#"{"move": "Right"}"#;
+        assert_eq!(
+            parse_move_response(body, MoveValidationMode::Strict),
+            Err("invalid move \"Right\"".to_string())
+        );
+        assert_eq!(
+            parse_move_response(body, MoveValidationMode::Lenient),
+            Ok((Move::Right, None))
+        );
+    }
+
+    #[test]
+    fn test_parse_move_response_lenient_bare_direction() {
+        assert_eq!(
+            parse_move_response("up", MoveValidationMode::Lenient),
+            Ok((Move::Up, None))
+        );
+        assert_eq!(
+            parse_move_response(r#""left""#, MoveValidationMode::Lenient),
+            Ok((Move::Left, None))
+        );
+        assert!(parse_move_response("up", MoveValidationMode::Strict).is_err());
+    }
+
+    #[test]
+    fn test_parse_move_response_garbage_fails_in_both_modes() {
+        for body in [
+            "",
+            "<html>502 Bad Gateway</html>",
+            "[1, 2]",
+            r#"{"move": 3}"#,
+        ] {
+            for mode in [MoveValidationMode::Strict, MoveValidationMode::Lenient] {
+                assert!(
+                    parse_move_response(body, mode).is_err(),
+                    "{body:?} should fail in {mode:?}"
+                );
+            }
+        }
+
+        assert_eq!(
+            parse_move_response(r#"{"move": "sideways"}"#, MoveValidationMode::Lenient),
+            Err("invalid move \"sideways\"".to_string())
+        );
+        assert_eq!(
+            parse_move_response(r#"{"shout": "hi"}"#, MoveValidationMode::Lenient),
+            Err("missing \"move\" field".to_string())
+        );
+    }
+
+    #[test]
+    fn test_move_deadline_flat() {
+        let deadline = MoveDeadline::flat(Duration::from_millis(500));
+        assert_eq!(deadline.total(), Duration::from_millis(500));
+        assert_eq!(deadline.compensated_latency_ms(320), None);
+    }
+
+    #[test]
+    fn test_move_deadline_compensated() {
+        let deadline =
+            MoveDeadline::compensated(Duration::from_millis(500), Duration::from_millis(80));
+        assert_eq!(deadline.total(), Duration::from_millis(580));
+        assert_eq!(deadline.compensated_latency_ms(320), Some(240));
+        // Faster than the measured round trip never goes negative
+        assert_eq!(deadline.compensated_latency_ms(50), Some(0));
+    }
+
+    #[test]
+    fn test_move_deadline_caps_allowance_at_timeout() {
+        let deadline =
+            MoveDeadline::compensated(Duration::from_millis(500), Duration::from_secs(3));
+        assert_eq!(deadline.network_allowance, Some(Duration::from_millis(500)));
+        assert_eq!(deadline.total(), Duration::from_millis(1000));
+    }
 }
@@ -0,0 +1,177 @@
+//! Automated health checks of every snake
+//!
+//! A cron job calls each snake's `GET /` every few minutes, the way a game
+//! starts by asking for its info, and records whether it answered 200 with a
+//! valid Battlesnake info response and how long that took. Only the latest
+//! check is kept; it's what the green or red indicator on the battlesnakes list
+//! and `GET /api/snakes` show. Built-in bots run inside the arena, so they're
+//! always healthy. A snake's owner is notified when it starts failing. For a
+//! step-by-step look at a failing snake, see [`crate::diagnose`].
+
+use std::time::{Duration, Instant};
+
+use futures::StreamExt as _;
+
+use crate::diagnose::check_info_response;
+use crate::engine::move_provider::BuiltInBot;
+use crate::models::snake_health::{self, SnakeHealth, SnakeToCheck};
+use crate::notifications;
+use crate::snake_http::SnakeHttpClient;
+use crate::state::AppState;
+
+/// How long a snake has to answer `GET /`
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Snakes checked at once; per-host limits still apply on top
+const CONCURRENT_CHECKS: usize = 16;
+
+/// What calling a snake's `GET /` found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckOutcome {
+    pub healthy: bool,
+    /// None if no response came back
+    pub status_code: Option<u16>,
+    pub latency_ms: Option<u64>,
+    /// Why the check failed
+    pub error: Option<String>,
+}
+
+/// Judge a snake's answer to `GET /`
+pub fn judge_response(status: u16, body: &str) -> Result<(), String> {
+    if status != 200 {
+        return Err(format!(
+            "GET / returned {}; snakes must answer it with 200 and their info",
+            status
+        ));
+    }
+    check_info_response(body).map(|_warnings| ())
+}
+
+/// Call a snake's `GET /` once
+pub async fn check_snake(http: &SnakeHttpClient, url: &str) -> CheckOutcome {
+    if BuiltInBot::from_url(url).is_some() {
+        return CheckOutcome {
+            healthy: true,
+            status_code: None,
+            latency_ms: None,
+            error: None,
+        };
+    }
+
+    let _permit = http.acquire(url).await;
+    let started = Instant::now();
+    let response = match http.client().get(url).timeout(CHECK_TIMEOUT).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            let error = if e.is_timeout() {
+                format!("GET / got no response within {}s", CHECK_TIMEOUT.as_secs())
+            } else {
+                format!("GET / failed: {}", e)
+            };
+            return CheckOutcome {
+                healthy: false,
+                status_code: None,
+                latency_ms: None,
+                error: Some(error),
+            };
+        }
+    };
+
+    let status = response.status().as_u16();
+    let verdict = match response.text().await {
+        Ok(body) => judge_response(status, &body),
+        Err(e) => Err(format!("Couldn't read the response to GET /: {}", e)),
+    };
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    CheckOutcome {
+        healthy: verdict.is_ok(),
+        status_code: Some(status),
+        latency_ms: Some(latency_ms),
+        error: verdict.err(),
+    }
+}
+
+/// Check every snake and record the results
+pub async fn check_all_snakes(app_state: &AppState) -> cja::Result<()> {
+    let snakes = snake_health::get_snakes_to_check(&app_state.db).await?;
+    let total = snakes.len();
+
+    let unhealthy = futures::stream::iter(snakes)
+        .map(|snake| check_and_record(app_state, snake))
+        .buffer_unordered(CONCURRENT_CHECKS)
+        .filter(|healthy| std::future::ready(!healthy))
+        .count()
+        .await;
+
+    tracing::info!(total, unhealthy, "Checked snake health");
+    Ok(())
+}
+
+/// Check one snake, returning whether it's healthy, and tell its owner when it
+/// starts failing. A result that can't be saved is logged, so one bad write
+/// doesn't stop the rest.
+async fn check_and_record(app_state: &AppState, snake: SnakeToCheck) -> bool {
+    let outcome = check_snake(&app_state.http_client, &snake.url).await;
+    let health = SnakeHealth {
+        battlesnake_id: snake.battlesnake_id,
+        healthy: outcome.healthy,
+        status_code: outcome.status_code.map(i32::from),
+        latency_ms: outcome
+            .latency_ms
+            .map(|ms| i32::try_from(ms).unwrap_or(i32::MAX)),
+        error: outcome.error,
+        checked_at: app_state.clock.now(),
+    };
+
+    if let Err(e) = snake_health::record_snake_health(&app_state.db, &health).await {
+        tracing::error!(
+            battlesnake_id = %snake.battlesnake_id,
+            "Failed to record snake health: {:#}",
+            e
+        );
+    }
+
+    if !health.healthy && snake.was_healthy != Some(false) {
+        let notification =
+            notifications::snake_unhealthy(snake.user_id, &snake.name, health.error.as_deref());
+        if let Err(e) = notifications::notify(app_state, vec![notification]).await {
+            tracing::error!(
+                battlesnake_id = %snake.battlesnake_id,
+                "Failed to notify owner of unhealthy snake: {:#}",
+                e
+            );
+        }
+    }
+    health.healthy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_judge_response() {
+        assert_eq!(judge_response(200, r#"{"apiversion": "1"}"#), Ok(()));
+        // Warnings about optional fields don't make a snake unhealthy
+        assert_eq!(
+            judge_response(200, r#"{"apiversion": "1", "color": "red"}"#),
+            Ok(())
+        );
+
+        let error = judge_response(500, r#"{"apiversion": "1"}"#).unwrap_err();
+        assert!(error.contains("returned 500"), "{error}");
+        let error = judge_response(200, "<html>Not a snake</html>").unwrap_err();
+        assert!(error.contains("didn't return JSON"), "{error}");
+        let error = judge_response(200, r#"{"apiversion": "2"}"#).unwrap_err();
+        assert!(error.contains("apiversion"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn test_built_in_bots_are_healthy() {
+        let http = SnakeHttpClient::new(&crate::snake_http::SnakeHttpConfig::default()).unwrap();
+        let outcome = check_snake(&http, "bot:food").await;
+        assert!(outcome.healthy);
+        assert_eq!(outcome.error, None);
+    }
+}
@@ -0,0 +1,234 @@
+//! Connection settings for the HTTP client that calls snake servers
+//!
+//! Many games often run against the same few snakes at once, so the client is
+//! tuned to keep connections to those hosts warm: TCP keep-alive, a longer idle
+//! pool, optional HTTP/2 (multiplexing every game's requests over one connection),
+//! and an optional cap on concurrent requests per host so one busy snake can't
+//! soak up every socket.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use color_eyre::eyre::Context as _;
+use reqwest::Client;
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+use url::Url;
+
+use crate::config::env_or;
+
+/// Tunables for the snake-facing HTTP client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnakeHttpConfig {
+    /// Speak HTTP/2 to plain `http://` snakes without an HTTP/1.1 upgrade.
+    /// Only safe when every snake supports h2c; `https://` snakes negotiate
+    /// HTTP/2 via ALPN regardless of this setting.
+    pub http2_prior_knowledge: bool,
+    /// Interval between HTTP/2 PING frames that keep idle connections open
+    pub http2_keep_alive_interval: Duration,
+    /// TCP keep-alive probe interval
+    pub tcp_keep_alive: Duration,
+    /// How long an idle pooled connection is kept before being closed
+    pub pool_idle_timeout: Duration,
+    /// Idle connections kept per host
+    pub pool_max_idle_per_host: usize,
+    /// Concurrent requests allowed per host, 0 for no limit
+    pub max_connections_per_host: usize,
+    /// Backstop timeout for requests that don't set their own. Calls to a
+    /// snake's /start, /move and /end use the game's deadline instead, so a game
+    /// with a longer move timeout isn't cut short.
+    pub request_timeout: Duration,
+}
+
+impl Default for SnakeHttpConfig {
+    fn default() -> Self {
+        Self {
+            http2_prior_knowledge: false,
+            http2_keep_alive_interval: Duration::from_secs(10),
+            tcp_keep_alive: Duration::from_secs(30),
+            pool_idle_timeout: Duration::from_secs(90),
+            pool_max_idle_per_host: 32,
+            max_connections_per_host: 0,
+            request_timeout: Duration::from_millis(1_100),
+        }
+    }
+}
+
+impl SnakeHttpConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            http2_prior_knowledge: env_or(
+                "ARENA_SNAKE_HTTP2_PRIOR_KNOWLEDGE",
+                defaults.http2_prior_knowledge,
+            ),
+            http2_keep_alive_interval: Duration::from_secs(env_or(
+                "ARENA_SNAKE_HTTP2_KEEPALIVE_SECS",
+                defaults.http2_keep_alive_interval.as_secs(),
+            )),
+            tcp_keep_alive: Duration::from_secs(env_or(
+                "ARENA_SNAKE_TCP_KEEPALIVE_SECS",
+                defaults.tcp_keep_alive.as_secs(),
+            )),
+            pool_idle_timeout: Duration::from_secs(env_or(
+                "ARENA_SNAKE_POOL_IDLE_TIMEOUT_SECS",
+                defaults.pool_idle_timeout.as_secs(),
+            )),
+            pool_max_idle_per_host: env_or(
+                "ARENA_SNAKE_POOL_MAX_IDLE_PER_HOST",
+                defaults.pool_max_idle_per_host,
+            ),
+            max_connections_per_host: env_or(
+                "ARENA_SNAKE_MAX_CONNECTIONS_PER_HOST",
+                defaults.max_connections_per_host,
+            ),
+            request_timeout: Duration::from_millis(env_or(
+                "ARENA_SNAKE_HTTP_TIMEOUT_MS",
+                defaults.request_timeout.as_millis() as u64,
+            )),
+        }
+    }
+}
+
+/// HTTP client for calling snake APIs, plus the per-host concurrency limits
+#[derive(Debug, Clone)]
+pub struct SnakeHttpClient {
+    client: Client,
+    host_limits: HostLimiter,
+}
+
+impl SnakeHttpClient {
+    pub fn new(config: &SnakeHttpConfig) -> cja::Result<Self> {
+        let mut builder = Client::builder()
+            .timeout(config.request_timeout)
+            .tcp_nodelay(true)
+            .tcp_keepalive(config.tcp_keep_alive)
+            .pool_idle_timeout(config.pool_idle_timeout)
+            .pool_max_idle_per_host(config.pool_max_idle_per_host)
+            .http2_keep_alive_interval(config.http2_keep_alive_interval)
+            .http2_keep_alive_while_idle(true)
+            .http2_adaptive_window(true);
+
+        if config.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+
+        let client = builder.build().wrap_err("Failed to create HTTP client")?;
+
+        Ok(Self {
+            client,
+            host_limits: HostLimiter::new(config.max_connections_per_host),
+        })
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Wait for a free slot for the host behind `url`
+    ///
+    /// Hold the returned permit for the duration of the request. Returns `None`
+    /// when per-host limits are disabled.
+    pub async fn acquire(&self, url: &str) -> Option<OwnedSemaphorePermit> {
+        self.host_limits.acquire(url).await
+    }
+}
+
+/// Caps concurrent requests per `host:port`
+#[derive(Debug, Clone)]
+struct HostLimiter {
+    max_per_host: usize,
+    hosts: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+}
+
+impl HostLimiter {
+    fn new(max_per_host: usize) -> Self {
+        Self {
+            max_per_host,
+            hosts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    async fn acquire(&self, url: &str) -> Option<OwnedSemaphorePermit> {
+        if self.max_per_host == 0 {
+            return None;
+        }
+
+        let semaphore = {
+            let mut hosts = self.hosts.lock().await;
+            hosts
+                .entry(host_key(url))
+                .or_insert_with(|| Arc::new(Semaphore::new(self.max_per_host)))
+                .clone()
+        };
+
+        // The semaphore is never closed, so this only fails if that changes
+        semaphore.acquire_owned().await.ok()
+    }
+}
+
+/// The `host:port` a snake URL connects to, which is what connection limits apply to
+fn host_key(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            let host = url.host_str()?.to_string();
+            Some(match url.port_or_known_default() {
+                Some(port) => format!("{host}:{port}"),
+                None => host,
+            })
+        })
+        .unwrap_or_else(|| url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_key() {
+        assert_eq!(
+            host_key("https://snake.example.com/api"),
+            "snake.example.com:443"
+        );
+        assert_eq!(host_key("http://localhost:8082/safe"), "localhost:8082");
+        assert_eq!(
+            host_key("http://localhost:8082/random?token=abc"),
+            "localhost:8082"
+        );
+        assert_eq!(host_key("not a url"), "not a url");
+    }
+
+    #[tokio::test]
+    async fn test_host_limiter_caps_per_host() {
+        let limiter = HostLimiter::new(1);
+
+        let first = limiter.acquire("http://localhost:8082/up").await;
+        assert!(first.is_some());
+
+        // Same host is full until the first permit is dropped
+        let blocked = tokio::time::timeout(
+            Duration::from_millis(20),
+            limiter.acquire("http://localhost:8082/down"),
+        )
+        .await;
+        assert!(blocked.is_err());
+
+        // Other hosts are unaffected
+        assert!(limiter.acquire("http://localhost:9000/up").await.is_some());
+
+        drop(first);
+        assert!(
+            limiter
+                .acquire("http://localhost:8082/down")
+                .await
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_host_limiter_disabled() {
+        let limiter = HostLimiter::new(0);
+        assert!(limiter.acquire("http://localhost:8082/up").await.is_none());
+    }
+}
@@ -0,0 +1,158 @@
+//! Snake variants from URL templates, for benchmarking each deployed version
+//!
+//! A template is a snake URL with a `{tag}` placeholder, like
+//! `https://snake.example.com/{tag}`. Instantiating it with a tag, typically
+//! from a deploy pipeline, creates a snake named `{name}@{tag}` pointing at
+//! that version. Doing it again with the same tag returns the same snake.
+//!
+//! Variants that haven't played a game for the template's
+//! `archive_after_days` are archived: made private and kept out of new games,
+//! with their past games left as they are. The newest variant is never
+//! archived, and instantiating an archived variant's tag brings it back.
+
+use color_eyre::eyre::Context as _;
+
+use crate::models::battlesnake::{self, Battlesnake, CreateBattlesnake};
+use crate::models::snake_template::{
+    SnakeTemplate, archive_inactive_snake_variants, create_snake_variant, get_snake_variant_by_tag,
+    unarchive_snake_variant,
+};
+use crate::state::AppState;
+
+/// The placeholder a template's URL must contain
+pub const TAG_PLACEHOLDER: &str = "{tag}";
+
+/// Longest tag accepted
+const MAX_TAG_LENGTH: usize = 64;
+
+/// Check a tag can go in a snake URL and name as-is
+pub fn validate_tag(tag: &str) -> Result<(), String> {
+    if tag.is_empty() || tag.len() > MAX_TAG_LENGTH {
+        return Err(format!(
+            "tag must be 1 to {} characters long",
+            MAX_TAG_LENGTH
+        ));
+    }
+    if !tag
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err("tag can only contain letters, digits, '-', '_' and '.'".to_string());
+    }
+    Ok(())
+}
+
+/// The URL of a template's variant for a tag
+pub fn render_url(url_template: &str, tag: &str) -> String {
+    url_template.replace(TAG_PLACEHOLDER, tag)
+}
+
+/// The name of a template's variant for a tag
+pub fn variant_name(template_name: &str, tag: &str) -> String {
+    format!("{}@{}", template_name, tag)
+}
+
+/// What instantiating a template did
+#[derive(Debug)]
+pub struct Instantiated {
+    pub snake: Battlesnake,
+    /// False when the tag already had a variant
+    pub created: bool,
+    /// True when the tag's variant was archived and has been brought back
+    pub unarchived: bool,
+}
+
+/// Get or create the snake for a template's tag
+pub async fn instantiate_variant(
+    app_state: &AppState,
+    template: &SnakeTemplate,
+    tag: &str,
+) -> cja::Result<Instantiated> {
+    let mut tx = app_state
+        .db
+        .begin()
+        .await
+        .wrap_err("Failed to start transaction")?;
+
+    if let Some(variant) = get_snake_variant_by_tag(&mut *tx, template.template_id, tag).await? {
+        let unarchived = variant.archived_at.is_some();
+        if unarchived {
+            unarchive_snake_variant(&mut *tx, variant.battlesnake_id, template.visibility).await?;
+        }
+        tx.commit().await.wrap_err("Failed to commit transaction")?;
+
+        let snake = battlesnake::get_battlesnake_by_id(&app_state.db, variant.battlesnake_id)
+            .await?
+            .ok_or_else(|| {
+                color_eyre::eyre::eyre!("Variant snake {} is missing", variant.battlesnake_id)
+            })?;
+        return Ok(Instantiated {
+            snake,
+            created: false,
+            unarchived,
+        });
+    }
+
+    let snake = battlesnake::create_battlesnake(
+        &mut *tx,
+        template.user_id,
+        CreateBattlesnake {
+            name: variant_name(&template.name, tag),
+            url: render_url(&template.url_template, tag),
+            visibility: template.visibility,
+        },
+    )
+    .await?;
+    create_snake_variant(&mut *tx, snake.battlesnake_id, template.template_id, tag).await?;
+    tx.commit().await.wrap_err("Failed to commit transaction")?;
+
+    tracing::info!(
+        template_id = %template.template_id,
+        tag,
+        battlesnake_id = %snake.battlesnake_id,
+        "Created snake variant"
+    );
+
+    Ok(Instantiated {
+        snake,
+        created: true,
+        unarchived: false,
+    })
+}
+
+/// Archive every variant that's been inactive too long
+pub async fn archive_inactive_variants(app_state: &AppState) -> cja::Result<()> {
+    let archived = archive_inactive_snake_variants(&app_state.db, app_state.clock.now()).await?;
+    if !archived.is_empty() {
+        tracing::info!(count = archived.len(), "Archived inactive snake variants");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_tag() {
+        assert!(validate_tag("v1.2.3").is_ok());
+        assert!(validate_tag("feature_x-2").is_ok());
+        assert!(validate_tag("").is_err());
+        assert!(validate_tag("release/1.0").is_err());
+        assert!(validate_tag("a b").is_err());
+        assert!(validate_tag(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn test_render_url_and_name() {
+        assert_eq!(
+            render_url("https://snake.example.com/{tag}", "v2"),
+            "https://snake.example.com/v2"
+        );
+        assert_eq!(
+            render_url("https://{tag}.snake.example.com/{tag}", "v2"),
+            "https://v2.snake.example.com/v2"
+        );
+        assert_eq!(variant_name("my-snake", "v2"), "my-snake@v2");
+    }
+}
@@ -1,22 +1,62 @@
 use color_eyre::eyre::{Context as _, eyre};
 use sqlx::{PgPool, postgres::PgPoolOptions};
 
+use crate::auth_lockout::{AuthLockoutConfig, AuthLockouts};
+use crate::clock::{SharedClock, SystemClock};
+use crate::engine::hooks::EngineHooks;
+use crate::federation::FederationConfig;
 use crate::game_channels::GameChannels;
+use crate::game_settings::GameLimits;
+use crate::github::app::GitHubAppConfig;
 use crate::github::auth::GitHubOAuthConfig;
+use crate::load_shedding::{LoadShedder, LoadSheddingConfig};
+use crate::public_url::PublicUrlConfig;
+use crate::runner_pool::{RunnerPool, RunnerPoolConfig};
+use crate::snake_http::{SnakeHttpClient, SnakeHttpConfig};
+use crate::storage::{self, SharedStorage};
+use crate::turn_archive::{TurnArchive, TurnArchiveConfig};
+use crate::turn_metrics::TurnMetrics;
+use crate::worker::Drain;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: sqlx::Pool<sqlx::Postgres>,
     pub cookie_key: cja::server::cookies::CookieKey,
     pub github_oauth_config: Option<GitHubOAuthConfig>,
+    /// GitHub App for posting check results to snake repositories
+    pub github_app: Option<GitHubAppConfig>,
     /// Connection to the legacy Battlesnake Engine database (for game backup)
     pub engine_db: Option<sqlx::Pool<sqlx::Postgres>>,
-    /// GCS bucket name for game backups
-    pub gcs_bucket: Option<String>,
+    /// Where game archives and replay GIFs go: a GCS bucket, or a local directory in development
+    pub storage: Option<SharedStorage>,
     /// Broadcast channels for live game updates
     pub game_channels: GameChannels,
     /// HTTP client for calling snake APIs
-    pub http_client: reqwest::Client,
+    pub http_client: SnakeHttpClient,
+    /// Failed API token auth tracking for brute-force lockouts
+    pub auth_lockouts: AuthLockouts,
+    /// Source of the current time (swap for a `MockClock` in tests)
+    pub clock: SharedClock,
+    /// Public URL, path prefix and cookie settings for running behind a proxy
+    pub public_url: PublicUrlConfig,
+    /// Key and peers for federating with other arenas, None if not configured
+    pub federation: Option<FederationConfig>,
+    /// Bearer token for the operator endpoints under `/api/admin`, None to disable them
+    pub admin_token: Option<String>,
+    /// Whether this instance is draining for a deploy
+    pub drain: Drain,
+    /// Features told about games as the runner plays them
+    pub engine_hooks: EngineHooks,
+    /// Whether non-essential endpoints are shedding load, from the database's health
+    pub load_shedder: LoadShedder,
+    /// Histograms of how long each stage of the game loop takes, for `/_/metrics`
+    pub turn_metrics: TurnMetrics,
+    /// When finished games' turns are pruned, and the archived games read back recently
+    pub turn_archive: TurnArchive,
+    /// Caps on how many games run at once, on this instance and per user
+    pub runner_pool: RunnerPool,
+    /// Bounds on the board sizes, turn limits and move timeouts new games can use
+    pub game_limits: GameLimits,
 }
 
 impl AppState {
@@ -65,8 +105,25 @@ impl AppState {
 
         let cookie_key = cja::server::cookies::CookieKey::from_env_or_generate()?;
 
+        let public_url = PublicUrlConfig::from_env()?;
+        public_url.install();
+        if let Some(base_url) = public_url.configured_base_url() {
+            tracing::info!(base_url = %base_url, "Serving under BASE_URL");
+        }
+
+        // Optional: federation with other arenas, skipped if not configured
+        let federation = FederationConfig::from_env(&public_url)?;
+        match &federation {
+            Some(config) => tracing::info!(
+                peers = config.peers.len(),
+                "Federation configured with public key {}",
+                config.public_key()
+            ),
+            None => tracing::info!("ARENA_FEDERATION_KEY not set, federation disabled"),
+        }
+
         // Initialize GitHub OAuth config (optional - auth disabled if not configured)
-        let github_oauth_config = match GitHubOAuthConfig::from_env() {
+        let github_oauth_config = match GitHubOAuthConfig::from_env(&public_url) {
             Ok(config) => {
                 tracing::info!("GitHub OAuth configured");
                 Some(config)
@@ -77,6 +134,16 @@ impl AppState {
             }
         };
 
+        // Optional: GitHub App for commit statuses, skipped if not configured
+        let github_app = if std::env::var("GITHUB_APP_ID").is_ok() {
+            let config = GitHubAppConfig::from_env()?;
+            tracing::info!(app_id = %config.app_id, "GitHub App configured for commit statuses");
+            Some(config)
+        } else {
+            tracing::info!("GITHUB_APP_ID not set, GitHub commit statuses disabled");
+            None
+        };
+
         // Optional: Engine database for game backup
         let engine_db = match std::env::var("ENGINE_DATABASE_URL") {
             Ok(url) => {
@@ -94,28 +161,50 @@ impl AppState {
             }
         };
 
-        // Optional: GCS bucket for game backup
-        let gcs_bucket = std::env::var("GCS_BUCKET").ok();
-        if gcs_bucket.is_some() {
-            tracing::info!("GCS bucket configured for game backup");
+        // Optional: storage for game backup, from STORAGE_URL or GCS_BUCKET
+        let storage = storage::from_env()?;
+        if let Some(storage) = &storage {
+            tracing::info!(storage = %storage.describe(), "Storage configured for game backup");
+        }
+
+        // Optional: operator endpoints such as /api/admin/drain
+        let admin_token = std::env::var("ARENA_ADMIN_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty());
+        if admin_token.is_none() {
+            tracing::info!("ARENA_ADMIN_TOKEN not set, /api/admin endpoints disabled");
         }
 
-        // HTTP client for calling snake APIs (connection pooling, timeout slightly longer than game timeout)
-        let http_client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_millis(600))
-            .pool_max_idle_per_host(10)
-            .build()
-            .wrap_err("Failed to create HTTP client")?;
-        tracing::info!("HTTP client initialized for snake API calls");
+        let clock: SharedClock = std::sync::Arc::new(SystemClock);
+        let engine_hooks = EngineHooks::builtin();
+        tracing::info!(hooks = ?engine_hooks.names(), "Engine hooks registered");
+
+        // HTTP client for calling snake APIs (keep-alive, pooling and per-host limits from env)
+        let snake_http_config = SnakeHttpConfig::from_env();
+        let http_client = SnakeHttpClient::new(&snake_http_config)?;
+        tracing::info!(config = ?snake_http_config, "HTTP client initialized for snake API calls");
 
         Ok(Self {
             db: pool,
             cookie_key,
             github_oauth_config,
+            github_app,
             engine_db,
-            gcs_bucket,
+            storage,
             game_channels: GameChannels::new(),
             http_client,
+            auth_lockouts: AuthLockouts::new(AuthLockoutConfig::from_env(), clock.clone()),
+            clock,
+            public_url,
+            federation,
+            admin_token,
+            drain: Drain::default(),
+            engine_hooks,
+            load_shedder: LoadShedder::new(LoadSheddingConfig::from_env()),
+            turn_metrics: TurnMetrics::default(),
+            turn_archive: TurnArchive::new(TurnArchiveConfig::from_env()),
+            runner_pool: RunnerPool::new(RunnerPoolConfig::from_env()),
+            game_limits: GameLimits::from_env(),
         })
     }
 }
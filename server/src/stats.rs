@@ -0,0 +1,388 @@
+//! Snake statistics: win rates, placements and opponent-adjusted performance
+//!
+//! Raw win rate says little about a snake that only plays weak bots. Ratings
+//! come from replaying every finished game, oldest first, as pairwise Elo
+//! matchups between the snakes in it. Each game also compares how many
+//! opponents a snake was expected to finish ahead of, given everyone's rating
+//! going in, with how many it actually beat. Averaged over games that's the
+//! snake's performance vs. expected, in places: positive when it beats stronger
+//! snakes than it "should", negative when it loses to weaker ones.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::models::game::GameStatus;
+use crate::models::game_battlesnake::{FinishedPlacement, GameHistoryEntry};
+
+/// Rating every snake starts from
+pub const INITIAL_RATING: f64 = 1500.0;
+
+/// How far a single game can move a rating
+const K_FACTOR: f64 = 32.0;
+
+/// Bump whenever a change here would rate the same games differently, so
+/// stored ratings from the old algorithm stop being used until they're
+/// recalculated
+pub const RATING_ALGORITHM_VERSION: i32 = 1;
+
+pub struct BattlesnakeStats {
+    pub total_games: usize,
+    pub finished_games: usize,
+    pub wins: usize,
+    pub second_places: usize,
+    pub third_places: usize,
+    pub fourth_places: usize,
+    pub win_rate: f64,
+    pub average_placement: f64,
+}
+
+pub fn compute_stats(history: &[GameHistoryEntry]) -> BattlesnakeStats {
+    let total_games = history.len();
+    let mut finished_games = 0usize;
+    let mut wins = 0usize;
+    let mut second_places = 0usize;
+    let mut third_places = 0usize;
+    let mut fourth_places = 0usize;
+    let mut placement_sum = 0i64;
+    let mut placement_count = 0usize;
+
+    for entry in history {
+        if entry.status == GameStatus::Finished {
+            finished_games += 1;
+            if let Some(placement) = entry.placement {
+                match placement {
+                    1 => wins += 1,
+                    2 => second_places += 1,
+                    3 => third_places += 1,
+                    4 => fourth_places += 1,
+                    _ => {}
+                }
+                placement_sum += i64::from(placement);
+                placement_count += 1;
+            }
+        }
+    }
+
+    let win_rate = if finished_games > 0 {
+        (wins as f64 / finished_games as f64) * 100.0
+    } else {
+        0.0
+    };
+
+    let average_placement = if placement_count > 0 {
+        placement_sum as f64 / placement_count as f64
+    } else {
+        0.0
+    };
+
+    BattlesnakeStats {
+        total_games,
+        finished_games,
+        wins,
+        second_places,
+        third_places,
+        fourth_places,
+        win_rate,
+        average_placement,
+    }
+}
+
+/// A snake's rating and how it has done against the opponents it's faced
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnakeRating {
+    pub rating: f64,
+    /// Finished games, including solo games
+    pub games: usize,
+    pub wins: usize,
+    /// Finished games against at least one other snake
+    pub rated_games: usize,
+    /// Sum over rated games of opponents beaten minus opponents expected to be beaten
+    pub(crate) places_gained: f64,
+}
+
+impl Default for SnakeRating {
+    fn default() -> Self {
+        Self {
+            rating: INITIAL_RATING,
+            games: 0,
+            wins: 0,
+            rated_games: 0,
+            places_gained: 0.0,
+        }
+    }
+}
+
+impl SnakeRating {
+    /// Percentage of finished games won
+    pub fn win_rate(&self) -> f64 {
+        if self.games > 0 {
+            (self.wins as f64 / self.games as f64) * 100.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Average places per game finished above (positive) or below what the
+    /// snake's opponents' ratings predicted, `None` before any rated game
+    pub fn performance_vs_expected(&self) -> Option<f64> {
+        (self.rated_games > 0).then(|| self.places_gained / self.rated_games as f64)
+    }
+}
+
+/// Show performance vs. expected as signed places, e.g. "+0.42"
+pub fn format_performance(performance: Option<f64>) -> String {
+    match performance {
+        Some(places) => format!("{:+.2}", places),
+        None => "N/A".to_string(),
+    }
+}
+
+/// Chance a snake rated `rating` finishes ahead of one rated `opponent`
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+/// Chance of each entrant finishing in each place, from their ratings
+///
+/// Entrants finish in order like draws without replacement, each weighted by
+/// its Elo strength `10^(rating/400)`: first place goes to entrant `i` with
+/// probability proportional to its strength, second to one of the rest the
+/// same way, and so on. Between two snakes that's exactly [`expected_score`].
+/// `result[i][p]` is the chance entrant `i` finishes in place `p + 1`.
+pub fn placement_probabilities(ratings: &[f64]) -> Vec<Vec<f64>> {
+    let n = ratings.len();
+    // Relative to the top rating, so strengths can't overflow
+    let top = ratings.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let strengths: Vec<f64> = ratings
+        .iter()
+        .map(|r| 10f64.powf((r - top) / 400.0))
+        .collect();
+
+    let mut probabilities = vec![vec![0.0; n]; n];
+    let mut remaining: Vec<usize> = (0..n).collect();
+    add_orderings(&strengths, &mut remaining, 0, 1.0, &mut probabilities);
+    probabilities
+}
+
+/// Spread `chance` of reaching `place` with `remaining` unplaced over every
+/// order they can finish in
+fn add_orderings(
+    strengths: &[f64],
+    remaining: &mut Vec<usize>,
+    place: usize,
+    chance: f64,
+    probabilities: &mut [Vec<f64>],
+) {
+    let total: f64 = remaining.iter().map(|&i| strengths[i]).sum();
+    for k in 0..remaining.len() {
+        let entrant = remaining.remove(k);
+        let next = chance * strengths[entrant] / total;
+        probabilities[entrant][place] += next;
+        add_orderings(strengths, remaining, place + 1, next, probabilities);
+        remaining.insert(k, entrant);
+    }
+}
+
+/// Rate every snake from finished games' placements
+///
+/// `placements` must be grouped by game, oldest game first, since each game is
+/// rated with the ratings snakes had going into it.
+pub fn compute_ratings(placements: &[FinishedPlacement]) -> HashMap<Uuid, SnakeRating> {
+    let mut ratings: HashMap<Uuid, SnakeRating> = HashMap::new();
+    rate_games(&mut ratings, placements);
+    ratings
+}
+
+/// Carry on rating from `ratings` with more finished games
+///
+/// Same ordering rules as [`compute_ratings`]; every game in `placements` has
+/// to come after the games `ratings` were built from.
+pub fn rate_games(ratings: &mut HashMap<Uuid, SnakeRating>, placements: &[FinishedPlacement]) {
+    for game in placements.chunk_by(|a, b| a.game_id == b.game_id) {
+        // A snake entered more than once counts once, at its best placement
+        let mut entrants: Vec<(Uuid, i32)> = Vec::new();
+        for p in game {
+            match entrants.iter_mut().find(|(id, _)| *id == p.battlesnake_id) {
+                Some(entrant) => entrant.1 = entrant.1.min(p.placement),
+                None => entrants.push((p.battlesnake_id, p.placement)),
+            }
+        }
+
+        let before: Vec<f64> = entrants
+            .iter()
+            .map(|(id, _)| ratings.get(id).map_or(INITIAL_RATING, |r| r.rating))
+            .collect();
+
+        for (i, &(id, placement)) in entrants.iter().enumerate() {
+            let entry = ratings.entry(id).or_default();
+            entry.games += 1;
+            if placement == 1 {
+                entry.wins += 1;
+            }
+
+            let opponents = entrants.len() - 1;
+            if opponents == 0 {
+                continue;
+            }
+
+            let mut actual = 0.0;
+            let mut expected = 0.0;
+            for (j, &(_, other_placement)) in entrants.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                actual += match placement.cmp(&other_placement) {
+                    Ordering::Less => 1.0,
+                    Ordering::Equal => 0.5,
+                    Ordering::Greater => 0.0,
+                };
+                expected += expected_score(before[i], before[j]);
+            }
+
+            entry.rating += K_FACTOR * (actual - expected) / opponents as f64;
+            entry.rated_games += 1;
+            entry.places_gained += actual - expected;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(game_id: Uuid, placements: &[(Uuid, i32)]) -> Vec<FinishedPlacement> {
+        placements
+            .iter()
+            .map(|&(battlesnake_id, placement)| FinishedPlacement {
+                game_id,
+                battlesnake_id,
+                placement,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_expected_score() {
+        assert!((expected_score(1500.0, 1500.0) - 0.5).abs() < 1e-9);
+        assert!((expected_score(1900.0, 1500.0) - 10.0 / 11.0).abs() < 1e-9);
+        assert!(
+            (expected_score(1600.0, 1700.0) + expected_score(1700.0, 1600.0) - 1.0).abs() < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_first_game_between_new_snakes() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let ratings = compute_ratings(&game(Uuid::new_v4(), &[(a, 1), (b, 2)]));
+
+        assert_eq!(ratings[&a].rating, INITIAL_RATING + 16.0);
+        assert_eq!(ratings[&b].rating, INITIAL_RATING - 16.0);
+        assert_eq!(ratings[&a].performance_vs_expected(), Some(0.5));
+        assert_eq!(ratings[&b].performance_vs_expected(), Some(-0.5));
+        assert_eq!(ratings[&a].win_rate(), 100.0);
+    }
+
+    #[test]
+    fn test_beating_weak_snakes_counts_for_less() {
+        let (strong, weak, farmer, giant_killer) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+
+        // Establish a strong and a weak snake
+        let mut placements = Vec::new();
+        for _ in 0..10 {
+            placements.extend(game(Uuid::new_v4(), &[(strong, 1), (weak, 2)]));
+        }
+        // One snake only beats the weak snake, the other only beats the strong one
+        for _ in 0..5 {
+            placements.extend(game(Uuid::new_v4(), &[(farmer, 1), (weak, 2)]));
+            placements.extend(game(Uuid::new_v4(), &[(giant_killer, 1), (strong, 2)]));
+        }
+        let ratings = compute_ratings(&placements);
+
+        // Same raw win rate, very different performance vs. expected
+        assert_eq!(
+            ratings[&farmer].win_rate(),
+            ratings[&giant_killer].win_rate()
+        );
+        assert!(
+            ratings[&giant_killer].performance_vs_expected().unwrap()
+                > ratings[&farmer].performance_vs_expected().unwrap()
+        );
+        assert!(ratings[&giant_killer].rating > ratings[&farmer].rating);
+    }
+
+    #[test]
+    fn test_solo_games_and_duplicates() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut placements = game(Uuid::new_v4(), &[(a, 1)]);
+        // `a` entered twice: only its best placement counts
+        placements.extend(game(Uuid::new_v4(), &[(a, 1), (b, 2), (a, 3)]));
+        let ratings = compute_ratings(&placements);
+
+        assert_eq!(ratings[&a].games, 2);
+        assert_eq!(ratings[&a].wins, 2);
+        assert_eq!(ratings[&a].rated_games, 1);
+        assert_eq!(ratings[&a].rating, INITIAL_RATING + 16.0);
+        assert_eq!(ratings[&b].rating, INITIAL_RATING - 16.0);
+    }
+
+    #[test]
+    fn test_rating_in_pieces_matches_rating_at_once() {
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut placements = Vec::new();
+        for _ in 0..4 {
+            placements.extend(game(Uuid::new_v4(), &[(a, 1), (b, 2), (c, 3)]));
+            placements.extend(game(Uuid::new_v4(), &[(c, 1), (a, 2)]));
+        }
+
+        // Split on a game boundary, as stored ratings are
+        let (before, after) = placements.split_at(5);
+        let mut ratings = compute_ratings(before);
+        rate_games(&mut ratings, after);
+
+        assert_eq!(ratings, compute_ratings(&placements));
+    }
+
+    #[test]
+    fn test_placement_probabilities() {
+        // Two snakes: the same as Elo's expected score
+        let two = placement_probabilities(&[1600.0, 1500.0]);
+        assert!((two[0][0] - expected_score(1600.0, 1500.0)).abs() < 1e-9);
+        assert!((two[0][1] - two[1][0]).abs() < 1e-9);
+
+        // Every place is filled once and every entrant finishes somewhere
+        let four = placement_probabilities(&[1700.0, 1500.0, 1500.0, 1300.0]);
+        for place in 0..4 {
+            let filled: f64 = four.iter().map(|p| p[place]).sum();
+            assert!((filled - 1.0).abs() < 1e-9);
+        }
+        for entrant in &four {
+            assert!((entrant.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+
+        // Equal ratings are a coin toss, the strongest is likeliest to win
+        assert!((four[1][0] - four[2][0]).abs() < 1e-9);
+        assert!(four[0][0] > four[1][0] && four[1][0] > four[3][0]);
+        assert!(four[3][3] > four[0][3]);
+
+        assert_eq!(placement_probabilities(&[1500.0]), vec![vec![1.0]]);
+    }
+
+    #[test]
+    fn test_format_performance() {
+        assert_eq!(format_performance(Some(0.4249)), "+0.42");
+        assert_eq!(format_performance(Some(-1.0)), "-1.00");
+        assert_eq!(format_performance(None), "N/A");
+    }
+
+    #[test]
+    fn test_no_rated_games() {
+        assert_eq!(SnakeRating::default().performance_vs_expected(), None);
+        assert_eq!(SnakeRating::default().win_rate(), 0.0);
+    }
+}
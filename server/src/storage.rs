@@ -0,0 +1,359 @@
+//! Where game archives and replay GIFs are stored
+//!
+//! Production uses a GCS bucket. Development can point `STORAGE_URL` at a
+//! local directory with `file:///path/to/dir` instead, so the whole backup
+//! flow runs without any cloud credentials. Both backends store the same
+//! bytes under the same paths, so archives can be copied from one to the other.
+
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use color_eyre::eyre::{Context as _, eyre};
+use futures::{StreamExt, TryStreamExt, stream::BoxStream};
+use google_cloud_storage::{
+    client::{Client as GcsClient, ClientConfig},
+    http::objects::{
+        delete::DeleteObjectRequest,
+        download::Range,
+        get::GetObjectRequest,
+        upload::{Media, UploadObjectRequest, UploadType},
+    },
+};
+use tokio::io::AsyncReadExt as _;
+
+/// Bytes read at a time when streaming a local file
+const LOCAL_READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A place to keep objects by path, like `games/2026/03/10/{id}.json.zst`
+#[async_trait::async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Where objects go, for logs: `gs://bucket` or `file:///dir`
+    fn describe(&self) -> String;
+
+    /// Write an object, replacing any already at `path`
+    async fn upload(&self, path: &str, data: Vec<u8>, content_type: &str) -> cja::Result<()>;
+
+    /// Read a whole object
+    async fn download(&self, path: &str) -> cja::Result<Vec<u8>>;
+
+    /// Read an object a piece at a time, for ones too big to hold in memory
+    async fn download_stream(
+        &self,
+        path: &str,
+    ) -> cja::Result<BoxStream<'static, io::Result<Bytes>>>;
+
+    /// Delete an object
+    async fn delete(&self, path: &str) -> cja::Result<()>;
+}
+
+/// Shared by everything that reads or writes storage
+pub type SharedStorage = Arc<dyn StorageBackend>;
+
+/// The storage configured in the environment, if any
+///
+/// `STORAGE_URL` takes `gs://bucket` or `file:///path/to/dir`. Without it,
+/// `GCS_BUCKET` names a bucket as it always has.
+pub fn from_env() -> cja::Result<Option<SharedStorage>> {
+    let url = std::env::var("STORAGE_URL")
+        .ok()
+        .filter(|url| !url.is_empty());
+    let bucket = std::env::var("GCS_BUCKET")
+        .ok()
+        .filter(|bucket| !bucket.is_empty());
+
+    match (url, bucket) {
+        (Some(url), _) => parse_storage_url(&url).map(Some),
+        (None, Some(bucket)) => Ok(Some(Arc::new(GcsStorage::new(bucket)))),
+        (None, None) => Ok(None),
+    }
+}
+
+/// The backend a `STORAGE_URL` names
+fn parse_storage_url(url: &str) -> cja::Result<SharedStorage> {
+    if let Some(bucket) = url.strip_prefix("gs://") {
+        let bucket = bucket.trim_end_matches('/');
+        if bucket.is_empty() || bucket.contains('/') {
+            return Err(eyre!("STORAGE_URL {} must be gs://<bucket>", url));
+        }
+        return Ok(Arc::new(GcsStorage::new(bucket.to_string())));
+    }
+
+    if url.starts_with("file://") {
+        let parsed =
+            url::Url::parse(url).wrap_err_with(|| format!("Invalid STORAGE_URL {}", url))?;
+        let root = parsed
+            .to_file_path()
+            .map_err(|()| eyre!("STORAGE_URL {} must be file:///<absolute path>", url))?;
+        return Ok(Arc::new(LocalStorage::new(root)));
+    }
+
+    Err(eyre!(
+        "Unsupported STORAGE_URL {}; use gs://<bucket> or file:///<path>",
+        url
+    ))
+}
+
+/// Objects in a Google Cloud Storage bucket
+pub struct GcsStorage {
+    bucket: String,
+}
+
+impl GcsStorage {
+    pub fn new(bucket: String) -> Self {
+        Self { bucket }
+    }
+
+    /// A client with credentials from the environment, made as it's needed so
+    /// a server without them still starts
+    async fn client(&self) -> cja::Result<GcsClient> {
+        let config = ClientConfig::default()
+            .with_auth()
+            .await
+            .wrap_err("Failed to configure GCS client")?;
+        Ok(GcsClient::new(config))
+    }
+
+    fn get_request(&self, path: &str) -> GetObjectRequest {
+        GetObjectRequest {
+            bucket: self.bucket.clone(),
+            object: path.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl StorageBackend for GcsStorage {
+    fn describe(&self) -> String {
+        format!("gs://{}", self.bucket)
+    }
+
+    async fn upload(&self, path: &str, data: Vec<u8>, content_type: &str) -> cja::Result<()> {
+        let mut media = Media::new(path.to_string());
+        media.content_type = content_type.to_string().into();
+        self.client()
+            .await?
+            .upload_object(
+                &UploadObjectRequest {
+                    bucket: self.bucket.clone(),
+                    ..Default::default()
+                },
+                data,
+                &UploadType::Simple(media),
+            )
+            .await
+            .wrap_err_with(|| format!("Failed to upload {} to GCS", path))?;
+        Ok(())
+    }
+
+    async fn download(&self, path: &str) -> cja::Result<Vec<u8>> {
+        self.client()
+            .await?
+            .download_object(&self.get_request(path), &Range::default())
+            .await
+            .wrap_err_with(|| format!("Failed to download {} from GCS", path))
+    }
+
+    async fn download_stream(
+        &self,
+        path: &str,
+    ) -> cja::Result<BoxStream<'static, io::Result<Bytes>>> {
+        let stream = self
+            .client()
+            .await?
+            .download_streamed_object(&self.get_request(path), &Range::default())
+            .await
+            .wrap_err_with(|| format!("Failed to download {} from GCS", path))?;
+        Ok(stream.map_err(|e| io::Error::other(e.to_string())).boxed())
+    }
+
+    async fn delete(&self, path: &str) -> cja::Result<()> {
+        self.client()
+            .await?
+            .delete_object(&DeleteObjectRequest {
+                bucket: self.bucket.clone(),
+                object: path.to_string(),
+                ..Default::default()
+            })
+            .await
+            .wrap_err_with(|| format!("Failed to delete {} from GCS", path))
+    }
+}
+
+/// Objects as files under a local directory, for development
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Where an object's file goes. Paths are relative and can't climb out of
+    /// the root, since parts of them come from game IDs.
+    fn file_path(&self, path: &str) -> cja::Result<PathBuf> {
+        let relative = Path::new(path);
+        let plain = relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if path.is_empty() || !plain {
+            return Err(eyre!("Invalid storage path {}", path));
+        }
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait::async_trait]
+impl StorageBackend for LocalStorage {
+    fn describe(&self) -> String {
+        format!("file://{}", self.root.display())
+    }
+
+    async fn upload(&self, path: &str, data: Vec<u8>, _content_type: &str) -> cja::Result<()> {
+        let file = self.file_path(path)?;
+        if let Some(dir) = file.parent() {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .wrap_err_with(|| format!("Failed to create {}", dir.display()))?;
+        }
+
+        // Written beside the object and renamed over it, so a reader never sees half a file
+        let partial = file.with_file_name(format!(
+            ".{}.{}.partial",
+            file.file_name().unwrap_or_default().to_string_lossy(),
+            uuid::Uuid::new_v4()
+        ));
+        tokio::fs::write(&partial, data)
+            .await
+            .wrap_err_with(|| format!("Failed to write {}", partial.display()))?;
+        tokio::fs::rename(&partial, &file)
+            .await
+            .wrap_err_with(|| format!("Failed to move {} into place", file.display()))?;
+        Ok(())
+    }
+
+    async fn download(&self, path: &str) -> cja::Result<Vec<u8>> {
+        let file = self.file_path(path)?;
+        tokio::fs::read(&file)
+            .await
+            .wrap_err_with(|| format!("Failed to read {}", file.display()))
+    }
+
+    async fn download_stream(
+        &self,
+        path: &str,
+    ) -> cja::Result<BoxStream<'static, io::Result<Bytes>>> {
+        let file = self.file_path(path)?;
+        let reader = tokio::fs::File::open(&file)
+            .await
+            .wrap_err_with(|| format!("Failed to open {}", file.display()))?;
+
+        Ok(
+            futures::stream::try_unfold(reader, |mut reader| async move {
+                let mut buffer = vec![0; LOCAL_READ_CHUNK_SIZE];
+                let read = reader.read(&mut buffer).await?;
+                if read == 0 {
+                    return Ok(None);
+                }
+                buffer.truncate(read);
+                Ok(Some((Bytes::from(buffer), reader)))
+            })
+            .boxed(),
+        )
+    }
+
+    async fn delete(&self, path: &str) -> cja::Result<()> {
+        let file = self.file_path(path)?;
+        tokio::fs::remove_file(&file)
+            .await
+            .wrap_err_with(|| format!("Failed to delete {}", file.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_storage_url() {
+        assert_eq!(
+            parse_storage_url("gs://arena-archives").unwrap().describe(),
+            "gs://arena-archives"
+        );
+        assert_eq!(
+            parse_storage_url("file:///tmp/arena-archives")
+                .unwrap()
+                .describe(),
+            "file:///tmp/arena-archives"
+        );
+
+        assert!(parse_storage_url("gs://").is_err());
+        assert!(parse_storage_url("gs://bucket/prefix").is_err());
+        assert!(parse_storage_url("s3://bucket").is_err());
+        assert!(parse_storage_url("/tmp/arena-archives").is_err());
+    }
+
+    #[test]
+    fn test_local_paths_stay_under_root() {
+        let storage = LocalStorage::new(PathBuf::from("/tmp/arena"));
+        assert_eq!(
+            storage
+                .file_path("games/2026/03/10/game-1.json.zst")
+                .unwrap(),
+            PathBuf::from("/tmp/arena/games/2026/03/10/game-1.json.zst")
+        );
+
+        assert!(storage.file_path("").is_err());
+        assert!(storage.file_path("../etc/passwd").is_err());
+        assert!(storage.file_path("games/../../etc/passwd").is_err());
+        assert!(storage.file_path("/etc/passwd").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path().to_path_buf());
+        let data: Vec<u8> = (0..LOCAL_READ_CHUNK_SIZE * 2 + 7)
+            .map(|i| (i % 251) as u8)
+            .collect();
+
+        storage
+            .upload(
+                "games/2026/03/10/game-1.json.zst",
+                data.clone(),
+                "application/zstd",
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            storage
+                .download("games/2026/03/10/game-1.json.zst")
+                .await
+                .unwrap(),
+            data
+        );
+
+        let streamed: Vec<Bytes> = storage
+            .download_stream("games/2026/03/10/game-1.json.zst")
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(streamed.concat(), data);
+
+        storage
+            .delete("games/2026/03/10/game-1.json.zst")
+            .await
+            .unwrap();
+        assert!(
+            storage
+                .download("games/2026/03/10/game-1.json.zst")
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Just enough of the tar format to stream files out as an archive
+//!
+//! Each file is a 512 byte ustar header followed by its contents, padded to a
+//! multiple of 512 bytes, and the archive ends with two empty blocks. Only
+//! regular files are written, which is all bulk archive downloads need.
+
+use color_eyre::eyre::eyre;
+
+const BLOCK_SIZE: usize = 512;
+
+/// Longest name that fits in the header's name field
+const NAME_LEN: usize = 100;
+/// Longest directory that fits in the header's prefix field
+const PREFIX_LEN: usize = 155;
+
+/// The header for a regular file of `size` bytes at `path`
+pub fn file_header(path: &str, size: u64, modified: i64) -> cja::Result<[u8; BLOCK_SIZE]> {
+    let (prefix, name) = split_path(path)?;
+
+    let mut header = [0u8; BLOCK_SIZE];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o644);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], size);
+    write_octal(&mut header[136..148], modified.max(0) as u64);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    // The checksum is summed with its own field as spaces
+    header[148..156].fill(b' ');
+    let checksum: u32 = header.iter().map(|&b| b as u32).sum();
+    write_octal(&mut header[148..155], checksum as u64);
+    header[155] = b' ';
+
+    Ok(header)
+}
+
+/// Zeros to pad a file of `size` bytes out to a whole block
+pub fn padding(size: usize) -> Vec<u8> {
+    vec![0; (BLOCK_SIZE - size % BLOCK_SIZE) % BLOCK_SIZE]
+}
+
+/// The two empty blocks that end an archive
+pub fn end_of_archive() -> Vec<u8> {
+    vec![0; BLOCK_SIZE * 2]
+}
+
+/// Split a path into ustar's prefix and name fields, at a `/` when it's too
+/// long for the name alone
+fn split_path(path: &str) -> cja::Result<(&str, &str)> {
+    if path.is_empty() || !path.is_ascii() {
+        return Err(eyre!("Can't add {:?} to a tar archive", path));
+    }
+    if path.len() <= NAME_LEN {
+        return Ok(("", path));
+    }
+
+    path.match_indices('/')
+        .map(|(i, _)| (&path[..i], &path[i + 1..]))
+        .find(|(prefix, name)| {
+            prefix.len() <= PREFIX_LEN && name.len() <= NAME_LEN && !name.is_empty()
+        })
+        .ok_or_else(|| eyre!("{} is too long for a tar archive", path))
+}
+
+/// Write `value` as zero-padded octal, leaving the field's last byte as NUL
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    let last = field.len() - 1;
+    field[..last].copy_from_slice(digits.as_bytes());
+    field[last] = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_header() {
+        let header = file_header("games/2026/03/10/game-1.json.zst", 1234, 1_773_100_800).unwrap();
+
+        assert_eq!(&header[..32], b"games/2026/03/10/game-1.json.zst");
+        assert_eq!(header[32], 0);
+        assert_eq!(&header[124..136], b"00000002322\0");
+        assert_eq!(&header[136..148], b"15153657400\0");
+        assert_eq!(header[156], b'0');
+        assert_eq!(&header[257..265], b"ustar\x0000");
+
+        // Checksum is the sum of the header with the checksum field as spaces
+        let mut unsummed = header;
+        unsummed[148..156].fill(b' ');
+        let sum: u32 = unsummed.iter().map(|&b| b as u32).sum();
+        assert_eq!(&header[148..156], format!("{:06o}\0 ", sum).as_bytes());
+    }
+
+    #[test]
+    fn test_long_paths_use_the_prefix() {
+        let dir = format!("games/{}", "d".repeat(90));
+        let path = format!("{}/game-1.json.zst", dir);
+        let header = file_header(&path, 0, 0).unwrap();
+
+        assert_eq!(&header[..16], b"game-1.json.zst\0");
+        assert_eq!(&header[345..345 + dir.len()], dir.as_bytes());
+
+        assert!(file_header(&"a".repeat(NAME_LEN + 1), 0, 0).is_err());
+        assert!(file_header("", 0, 0).is_err());
+    }
+
+    #[test]
+    fn test_padding() {
+        assert_eq!(padding(0).len(), 0);
+        assert_eq!(padding(1).len(), 511);
+        assert_eq!(padding(512).len(), 0);
+        assert_eq!(padding(1000).len(), 24);
+        assert_eq!(end_of_archive().len(), 1024);
+    }
+}
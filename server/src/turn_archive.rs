@@ -0,0 +1,600 @@
+//! Pruning finished games' turns once they're archived, and reading them back
+//!
+//! A game played here that finished more than `ARENA_ARCHIVE_TURNS_AFTER_DAYS`
+//! days ago (30 by default) is exported to storage in the same format as
+//! games backed up from the Engine, with each snake's moves from `snake_turns`
+//! alongside it in `<game>.moves.json.zst`. Its `archived_at` and `gcs_path`
+//! are set and its turn rows deleted in one transaction, so the turns table
+//! only holds recent games. The last turn and how long the turns took are kept
+//! on the game. Nothing is pruned on servers without storage.
+//!
+//! Everything that reads turns goes through [`load_turns`] and friends, and
+//! moves through [`load_snake_turns`], which fall back to the archive when a
+//! game has no turn rows. Archives are decoded back into turns and the last
+//! few games read are kept in memory, so a replay that's being watched or
+//! stepped through downloads its archive once.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use chrono::Duration;
+use color_eyre::eyre::{Context as _, eyre};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::backup::{ARCHIVE_VERSION, archive_path, decode_archive, encode_archive};
+use crate::engine_models::EngineGameFrame;
+use crate::frame_schema::{CURRENT_FRAME_SCHEMA_VERSION, upgrade_frame};
+use crate::game_export::build_export;
+use crate::models::game::{self, GameStatus};
+use crate::models::turn::{self, SnakeTurnWithNumber, Turn};
+use crate::state::AppState;
+
+/// Most games archived by one run of the archive job
+const ARCHIVE_BATCH_SIZE: i64 = 200;
+
+/// Games whose rehydrated turns are kept in memory
+const REHYDRATED_GAMES_CACHED: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct TurnArchiveConfig {
+    /// How long after a game finishes its turns are archived and pruned
+    pub archive_after: Duration,
+}
+
+impl Default for TurnArchiveConfig {
+    fn default() -> Self {
+        Self {
+            archive_after: Duration::days(30),
+        }
+    }
+}
+
+impl TurnArchiveConfig {
+    pub fn from_env() -> Self {
+        let days = std::env::var("ARENA_ARCHIVE_TURNS_AFTER_DAYS")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(Self::default().archive_after.num_days())
+            .max(1);
+        let config = Self {
+            archive_after: Duration::days(days),
+        };
+
+        tracing::info!(
+            archive_after_days = config.archive_after.num_days(),
+            "Turn archiving configured"
+        );
+
+        config
+    }
+}
+
+/// Pruning settings and the games recently read back from their archives
+#[derive(Clone)]
+pub struct TurnArchive {
+    pub config: TurnArchiveConfig,
+    cache: Arc<Mutex<RehydratedGames>>,
+}
+
+impl TurnArchive {
+    pub fn new(config: TurnArchiveConfig) -> Self {
+        Self {
+            config,
+            cache: Arc::new(Mutex::new(RehydratedGames::default())),
+        }
+    }
+}
+
+/// The most recently read archived games' turns, least recent dropped first
+#[derive(Default)]
+struct RehydratedGames {
+    turns: HashMap<Uuid, Arc<Vec<Turn>>>,
+    order: VecDeque<Uuid>,
+}
+
+impl RehydratedGames {
+    fn get(&mut self, game_id: Uuid) -> Option<Arc<Vec<Turn>>> {
+        let turns = self.turns.get(&game_id)?.clone();
+        self.order.retain(|id| *id != game_id);
+        self.order.push_back(game_id);
+        Some(turns)
+    }
+
+    fn insert(&mut self, game_id: Uuid, turns: Arc<Vec<Turn>>) {
+        if self.turns.insert(game_id, turns).is_none() {
+            self.order.push_back(game_id);
+        }
+        while self.order.len() > REHYDRATED_GAMES_CACHED {
+            if let Some(oldest) = self.order.pop_front() {
+                self.turns.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Archive and prune the turns of games that finished long enough ago, a
+/// batch at a time. Returns how many games were archived.
+pub async fn archive_finished_games(app_state: &AppState) -> cja::Result<usize> {
+    if app_state.storage.is_none() {
+        tracing::debug!("Storage not configured, not archiving turns");
+        return Ok(0);
+    }
+
+    let finished_before = app_state.clock.now() - app_state.turn_archive.config.archive_after;
+    let game_ids =
+        game::get_games_to_archive(&app_state.db, finished_before, ARCHIVE_BATCH_SIZE).await?;
+
+    let mut archived = 0;
+    for game_id in game_ids {
+        // One bad game shouldn't hold up the rest
+        match archive_game_turns(app_state, game_id).await {
+            Ok(true) => archived += 1,
+            Ok(false) => {}
+            Err(e) => tracing::error!(game_id = %game_id, "Failed to archive turns: {:?}", e),
+        }
+    }
+
+    tracing::info!(archived, "Archived finished games' turns");
+    Ok(archived)
+}
+
+/// Export a finished game to storage and delete its turn rows. Returns whether
+/// it was archived; games that aren't finished or are already archived are
+/// left alone.
+pub async fn archive_game_turns(app_state: &AppState, game_id: Uuid) -> cja::Result<bool> {
+    let storage = app_state
+        .storage
+        .clone()
+        .ok_or_else(|| eyre!("Archive storage not configured"))?;
+
+    let Some(game) = game::get_game_by_id(&app_state.db, game_id).await? else {
+        return Ok(false);
+    };
+    if game.status != GameStatus::Finished {
+        return Ok(false);
+    }
+    let settings = game::get_game_settings(&app_state.db, game_id).await?;
+    let frames = turn::get_turns_by_game_id(&app_state.db, game_id)
+        .await?
+        .into_iter()
+        .filter_map(|t| t.frame_data)
+        .collect();
+
+    let moves = turn::get_snake_turns_by_game_id(&app_state.db, game_id).await?;
+
+    let now = app_state.clock.now();
+    let export = build_export(&game, &settings, frames, now)?;
+    let path = archive_path(&export.game, ARCHIVE_VERSION);
+    storage
+        .upload(&path, encode_archive(&export)?, "application/zstd")
+        .await
+        .wrap_err_with(|| format!("Failed to upload archive of game {}", game_id))?;
+
+    // Games seeded or imported without moves have nothing to keep
+    let moves_path = if moves.is_empty() {
+        None
+    } else {
+        let moves_path = moves_archive_path(&path);
+        storage
+            .upload(&moves_path, encode_moves(&moves)?, "application/zstd")
+            .await
+            .wrap_err_with(|| format!("Failed to upload moves of game {}", game_id))?;
+        Some(moves_path)
+    };
+
+    let mut tx = app_state
+        .db
+        .begin()
+        .await
+        .wrap_err("Failed to start transaction")?;
+    let marked = game::mark_game_archived(
+        &mut *tx,
+        game_id,
+        &path,
+        moves_path.as_deref(),
+        ARCHIVE_VERSION,
+        now,
+    )
+    .await?;
+    if !marked {
+        // Archived by another run while this one uploaded; its archive is the same game
+        return Ok(false);
+    }
+    // Games finished before the last turn was kept on the game still need it
+    game::record_final_turn(&mut *tx, game_id).await?;
+    let pruned = turn::delete_game_turns(&mut *tx, game_id).await?;
+    tx.commit().await.wrap_err("Failed to commit transaction")?;
+
+    tracing::info!(game_id = %game_id, path = %path, pruned, "Archived game turns");
+    Ok(true)
+}
+
+/// Every turn of a game, from its turn rows or its archive
+pub async fn load_turns(app_state: &AppState, game_id: Uuid) -> cja::Result<Vec<Turn>> {
+    let turns = turn::get_turns_by_game_id(&app_state.db, game_id).await?;
+    if !turns.is_empty() {
+        return Ok(turns);
+    }
+
+    Ok(rehydrate(app_state, game_id)
+        .await?
+        .map(|turns| turns.to_vec())
+        .unwrap_or_default())
+}
+
+/// A game's turns from `from_turn` on, from its turn rows or its archive
+pub async fn load_turns_from(
+    app_state: &AppState,
+    game_id: Uuid,
+    from_turn: i32,
+) -> cja::Result<Vec<Turn>> {
+    let turns = turn::get_turns_from(&app_state.db, game_id, from_turn).await?;
+    if !turns.is_empty() {
+        return Ok(turns);
+    }
+
+    rehydrated_from(app_state, game_id, from_turn, usize::MAX).await
+}
+
+/// Up to `limit` of a game's turns from `from_turn` on, from its turn rows or
+/// its archive
+pub async fn load_turns_page(
+    app_state: &AppState,
+    game_id: Uuid,
+    from_turn: i32,
+    limit: i64,
+) -> cja::Result<Vec<Turn>> {
+    let turns = turn::get_turns_page(&app_state.db, game_id, from_turn, limit).await?;
+    if !turns.is_empty() {
+        return Ok(turns);
+    }
+
+    let limit = usize::try_from(limit).unwrap_or_default();
+    rehydrated_from(app_state, game_id, from_turn, limit).await
+}
+
+/// The latest turn with a frame, up to turn `at` when given, from a game's
+/// turn rows or its archive
+pub async fn load_turn_at(
+    app_state: &AppState,
+    game_id: Uuid,
+    at: Option<i32>,
+) -> cja::Result<Option<Turn>> {
+    if let Some(found) = turn::get_turn_at(&app_state.db, game_id, at).await? {
+        return Ok(Some(found));
+    }
+
+    Ok(rehydrate(app_state, game_id).await?.and_then(|turns| {
+        turns
+            .iter()
+            .rev()
+            .find(|t| t.frame_data.is_some() && at.is_none_or(|at| t.turn_number <= at))
+            .cloned()
+    }))
+}
+
+/// Every snake's moves in a game, from its turn rows or its archive. Empty for
+/// games without any, such as seeded ones.
+///
+/// Only replays are cached, so each call for an archived game downloads its
+/// moves.
+pub async fn load_snake_turns(
+    app_state: &AppState,
+    game_id: Uuid,
+) -> cja::Result<Vec<SnakeTurnWithNumber>> {
+    let moves = turn::get_snake_turns_by_game_id(&app_state.db, game_id).await?;
+    if !moves.is_empty() {
+        return Ok(moves);
+    }
+
+    let Some(moves_path) = game::get_game_archive(&app_state.db, game_id)
+        .await?
+        .and_then(|archive| archive.moves_gcs_path)
+    else {
+        return Ok(Vec::new());
+    };
+    let storage = app_state
+        .storage
+        .as_ref()
+        .ok_or_else(|| eyre!("Game {} is archived, but storage isn't configured", game_id))?;
+
+    let compressed = storage.download(&moves_path).await?;
+    decode_moves(&compressed).wrap_err_with(|| format!("Failed to decode moves {}", moves_path))
+}
+
+/// Up to `limit` of an archived game's turns from `from_turn` on, empty for
+/// games that aren't archived
+async fn rehydrated_from(
+    app_state: &AppState,
+    game_id: Uuid,
+    from_turn: i32,
+    limit: usize,
+) -> cja::Result<Vec<Turn>> {
+    Ok(rehydrate(app_state, game_id)
+        .await?
+        .map(|turns| {
+            turns
+                .iter()
+                .filter(|t| t.turn_number >= from_turn)
+                .take(limit)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// An archived game's turns, decoded from its archive or the cache. None for
+/// games that aren't archived.
+async fn rehydrate(app_state: &AppState, game_id: Uuid) -> cja::Result<Option<Arc<Vec<Turn>>>> {
+    if let Some(turns) = app_state.turn_archive.cache.lock().unwrap().get(game_id) {
+        return Ok(Some(turns));
+    }
+
+    let Some(archive) = game::get_game_archive(&app_state.db, game_id).await? else {
+        return Ok(None);
+    };
+    let storage = app_state
+        .storage
+        .as_ref()
+        .ok_or_else(|| eyre!("Game {} is archived, but storage isn't configured", game_id))?;
+
+    let compressed = storage.download(&archive.gcs_path).await?;
+    let export = decode_archive(archive.archive_version.unwrap_or(1), &compressed)
+        .wrap_err_with(|| format!("Failed to decode archive {}", archive.gcs_path))?;
+
+    let turns = export
+        .frames
+        .into_iter()
+        .map(|frame| {
+            Ok(Turn {
+                turn_id: Uuid::new_v4(),
+                game_id,
+                turn_number: frame.turn,
+                frame_data: Some(stored_frame(frame)?),
+                frame_schema_version: CURRENT_FRAME_SCHEMA_VERSION,
+                created_at: export.exported_at,
+            })
+        })
+        .collect::<cja::Result<Vec<_>>>()?;
+    let turns = Arc::new(turns);
+
+    tracing::debug!(game_id = %game_id, turns = turns.len(), "Rehydrated archived turns");
+    app_state
+        .turn_archive
+        .cache
+        .lock()
+        .unwrap()
+        .insert(game_id, turns.clone());
+    Ok(Some(turns))
+}
+
+/// Where a game's moves are archived, next to the archive at `path`
+fn moves_archive_path(path: &str) -> String {
+    let stem = path.strip_suffix(".json.zst").unwrap_or(path);
+    format!("{}.moves.json.zst", stem)
+}
+
+fn encode_moves(moves: &[SnakeTurnWithNumber]) -> cja::Result<Vec<u8>> {
+    let json = serde_json::to_vec(moves).wrap_err("Failed to serialize moves")?;
+    zstd::encode_all(json.as_slice(), 3).wrap_err("Failed to compress moves")
+}
+
+fn decode_moves(compressed: &[u8]) -> cja::Result<Vec<SnakeTurnWithNumber>> {
+    let json = zstd::decode_all(compressed).wrap_err("Failed to decompress moves")?;
+    serde_json::from_slice(&json).wrap_err("Failed to parse archived moves")
+}
+
+/// A frame from an archive as it's stored in the turns table
+///
+/// The engine format leaves out fields the board viewer reads, such as
+/// `EliminatedCause`, so the frame is upgraded as if it were written before
+/// frames were versioned.
+fn stored_frame(frame: EngineGameFrame) -> cja::Result<Value> {
+    let mut value = serde_json::to_value(frame).wrap_err("Failed to serialize archived frame")?;
+    upgrade_frame(&mut value, 1)?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::TryStreamExt;
+    use serde_json::json;
+
+    use crate::branch::{BranchRequest, create_branch};
+    use crate::engine::hooks::EngineHooks;
+    use crate::game_diff::diff_games;
+    use crate::models::battlesnake::{CreateBattlesnake, Visibility, create_battlesnake};
+    use crate::models::game::{
+        CreateGameWithSnakes, GameBoardSize, GameSettings, GameType, create_game_with_snakes,
+        set_game_settings,
+    };
+    use crate::reports::user_games_csv;
+    use crate::storage::LocalStorage;
+
+    fn cached_turns(game_id: Uuid) -> Arc<Vec<Turn>> {
+        Arc::new(vec![Turn {
+            turn_id: Uuid::new_v4(),
+            game_id,
+            turn_number: 0,
+            frame_data: None,
+            frame_schema_version: CURRENT_FRAME_SCHEMA_VERSION,
+            created_at: chrono::Utc::now(),
+        }])
+    }
+
+    #[test]
+    fn test_rehydrated_games_drop_least_recent() {
+        let mut cache = RehydratedGames::default();
+        let games: Vec<Uuid> = (0..=REHYDRATED_GAMES_CACHED)
+            .map(|_| Uuid::new_v4())
+            .collect();
+
+        for game_id in &games[..REHYDRATED_GAMES_CACHED] {
+            cache.insert(*game_id, cached_turns(*game_id));
+        }
+        // Reading the oldest makes it the most recent
+        assert!(cache.get(games[0]).is_some());
+
+        let newest = games[REHYDRATED_GAMES_CACHED];
+        cache.insert(newest, cached_turns(newest));
+        assert!(cache.get(games[0]).is_some());
+        assert!(cache.get(games[1]).is_none());
+        assert_eq!(cache.turns.len(), REHYDRATED_GAMES_CACHED);
+    }
+
+    // Needs a migrated database, so it's skipped when DATABASE_URL isn't set
+    #[tokio::test]
+    async fn test_pruned_game_branches_diffs_and_reports() {
+        if std::env::var("DATABASE_URL").is_err() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let mut app_state = AppState::from_env().await.unwrap();
+        app_state.storage = Some(Arc::new(LocalStorage::new(dir.path().to_path_buf())));
+        app_state.engine_hooks = EngineHooks::default();
+
+        let github_id = i64::from(rand::random::<u32>()) + 1_000_000_000;
+        let user = crate::seed::seed_user(&app_state, github_id, "archive-test", "Archive test")
+            .await
+            .unwrap();
+        let mut snake_ids = Vec::new();
+        for bot in ["random", "food"] {
+            let snake = create_battlesnake(
+                &app_state.db,
+                user.user_id,
+                CreateBattlesnake {
+                    name: format!("archive {}", bot),
+                    url: format!("bot:{}", bot),
+                    visibility: Visibility::Private,
+                },
+            )
+            .await
+            .unwrap();
+            snake_ids.push(snake.battlesnake_id);
+        }
+        let game = create_game_with_snakes(
+            &app_state.db,
+            CreateGameWithSnakes {
+                board_size: GameBoardSize::Small,
+                game_type: GameType::Standard,
+                battlesnake_ids: snake_ids,
+            },
+        )
+        .await
+        .unwrap();
+        let settings = GameSettings {
+            max_turns: Some(20),
+            seed: Some(42),
+            ..GameSettings::default()
+        };
+        set_game_settings(&app_state.db, game.game_id, settings)
+            .await
+            .unwrap();
+        crate::game_runner::run_game(&app_state, game.game_id)
+            .await
+            .unwrap();
+
+        let turns = turn::get_turns_by_game_id(&app_state.db, game.game_id)
+            .await
+            .unwrap();
+        let final_turn = turns.iter().map(|t| t.turn_number).max().unwrap();
+        let moves = turn::get_snake_turns_by_game_id(&app_state.db, game.game_id)
+            .await
+            .unwrap();
+        assert!(!moves.is_empty());
+        let report_line = || async {
+            let report: String = user_games_csv(app_state.clone(), user.user_id)
+                .try_collect()
+                .await
+                .unwrap();
+            report
+                .lines()
+                .find(|line| line.starts_with(&game.game_id.to_string()))
+                .unwrap()
+                .to_string()
+        };
+        let unpruned_line = report_line().await;
+
+        assert!(archive_game_turns(&app_state, game.game_id).await.unwrap());
+        assert!(
+            turn::get_turns_by_game_id(&app_state.db, game.game_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            load_turns(&app_state, game.game_id).await.unwrap().len(),
+            turns.len()
+        );
+        assert_eq!(
+            load_snake_turns(&app_state, game.game_id)
+                .await
+                .unwrap()
+                .len(),
+            moves.len()
+        );
+
+        // Replaying the recorded moves from a pruned game plays it out the same
+        let branch_id = create_branch(
+            &app_state,
+            user.user_id,
+            game.game_id,
+            &BranchRequest {
+                turn: 0,
+                moves: Vec::new(),
+            },
+        )
+        .await
+        .unwrap();
+        let diff = diff_games(&app_state, game.game_id, branch_id)
+            .await
+            .unwrap();
+        assert_eq!(diff.turns_a, final_turn);
+        assert_eq!(diff.turns_b, final_turn);
+        assert!(diff.first_divergence.is_none());
+
+        // Turns, duration and death causes all outlast the turn rows
+        let line = report_line().await;
+        assert_eq!(line, unpruned_line);
+        assert_eq!(
+            line.split(',').nth(5),
+            Some(final_turn.to_string().as_str())
+        );
+    }
+
+    #[test]
+    fn test_stored_frame_fills_in_eliminations() {
+        let frame: EngineGameFrame = serde_json::from_value(json!({
+            "Turn": 12,
+            "Snakes": [
+                {
+                    "ID": "a",
+                    "Name": "Alpha",
+                    "Body": [{ "X": 1, "Y": 1 }],
+                    "Health": 0,
+                    "Death": { "Cause": "head-collision", "Turn": 12, "EliminatedBy": "b" },
+                },
+                {
+                    "ID": "b",
+                    "Name": "Beta",
+                    "Body": [{ "X": 1, "Y": 2 }],
+                    "Health": 88,
+                    "Death": null,
+                },
+            ],
+            "Food": [{ "X": 3, "Y": 3 }],
+        }))
+        .unwrap();
+
+        let stored = stored_frame(frame).unwrap();
+        assert_eq!(stored["Turn"], 12);
+        assert_eq!(stored["Food"], json!([{ "X": 3, "Y": 3 }]));
+        assert_eq!(stored["Snakes"][0]["EliminatedCause"], "head-collision");
+        assert_eq!(stored["Snakes"][0]["EliminatedBy"], "b");
+        assert_eq!(stored["Snakes"][1]["EliminatedCause"], "");
+        assert_eq!(stored["Hazards"], json!([]));
+        assert_eq!(stored["Snakes"][1]["Body"], json!([{ "X": 1, "Y": 2 }]));
+    }
+}
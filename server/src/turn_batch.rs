@@ -0,0 +1,166 @@
+//! Storing a running game's turns a batch at a time
+//!
+//! Writing each turn as it's played costs the runner a database round trip
+//! per turn. Instead it buffers turns here and stores them [`TURN_BATCH_SIZE`]
+//! at a time, or once the oldest has waited [`TURN_BATCH_MAX_AGE`], with one
+//! insert for the turns and one for their snake turns. The rest go when the
+//! game ends or is paused.
+//!
+//! Spectators don't wait for the batch. The runner still broadcasts every turn
+//! as it's played, and [`GameChannels`] holds each frame until it's stored, so
+//! the live stream serves it from memory until then.
+//!
+//! The game's checkpoint is saved after each batch, so a retried run resumes
+//! from the last stored turn and plays the unstored ones again.
+
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::game_channels::GameChannels;
+use crate::game_retry::{Checkpoint, save_checkpoint};
+use crate::models::turn::{CreateTurn, create_turns};
+
+/// Turns stored in each batch
+pub const TURN_BATCH_SIZE: usize = 10;
+
+/// Longest a played turn waits to be stored
+pub const TURN_BATCH_MAX_AGE: Duration = Duration::from_millis(250);
+
+/// A running game's turns that haven't been stored yet
+pub struct TurnBatch {
+    game_id: Uuid,
+    seed: u64,
+    pending: Vec<CreateTurn>,
+    /// When the oldest pending turn was played
+    oldest: Option<Instant>,
+    /// The runner's state after the newest pending turn
+    checkpoint: Option<Checkpoint>,
+}
+
+impl TurnBatch {
+    pub fn new(game_id: Uuid, seed: u64) -> Self {
+        Self {
+            game_id,
+            seed,
+            pending: Vec::new(),
+            oldest: None,
+            checkpoint: None,
+        }
+    }
+
+    /// Buffer a played turn, holding its frame for subscribers until it's stored
+    pub async fn push(
+        &mut self,
+        game_channels: &GameChannels,
+        turn: CreateTurn,
+        checkpoint: Checkpoint,
+    ) {
+        game_channels
+            .stage_frame(self.game_id, turn.turn_number, turn.frame_data.clone())
+            .await;
+        self.oldest.get_or_insert_with(Instant::now);
+        self.pending.push(turn);
+        self.checkpoint = Some(checkpoint);
+    }
+
+    /// Whether the pending turns should be stored before the next is played
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.pending.len() >= TURN_BATCH_SIZE
+            || self
+                .oldest
+                .is_some_and(|oldest| now.duration_since(oldest) >= TURN_BATCH_MAX_AGE)
+    }
+
+    /// Store the pending turns, then the checkpoint after them
+    pub async fn flush(
+        &mut self,
+        pool: &PgPool,
+        game_channels: &GameChannels,
+        now: DateTime<Utc>,
+    ) -> cja::Result<()> {
+        let Some(last_turn) = self.pending.last().map(|t| t.turn_number) else {
+            return Ok(());
+        };
+
+        create_turns(pool, self.game_id, &self.pending).await?;
+        if let Some(checkpoint) = &self.checkpoint {
+            save_checkpoint(pool, self.game_id, self.seed, checkpoint, now).await?;
+        }
+        game_channels.frames_stored(self.game_id, last_turn).await;
+
+        tracing::debug!(
+            game_id = %self.game_id,
+            turns = self.pending.len(),
+            last_turn,
+            "Stored batch of turns"
+        );
+        self.pending.clear();
+        self.oldest = None;
+        self.checkpoint = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::engine::FoodTally;
+
+    fn checkpoint() -> Checkpoint {
+        let game = crate::engine::create_game_from_board(
+            Uuid::from_u128(1),
+            crate::models::game::GameType::Standard,
+            battlesnake_game_types::wire_representation::Board {
+                height: 11,
+                width: 11,
+                food: vec![],
+                hazards: vec![],
+                snakes: vec![],
+            },
+        );
+        Checkpoint::new(
+            &game,
+            &HashMap::new(),
+            &[],
+            &[],
+            &FoodTally::default(),
+            &HashMap::new(),
+        )
+    }
+
+    fn turn(turn_number: i32) -> CreateTurn {
+        CreateTurn {
+            turn_number,
+            frame_data: serde_json::json!({ "Turn": turn_number }),
+            snake_turns: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_is_due_when_full_or_old() {
+        let channels = GameChannels::new();
+        let game_id = Uuid::new_v4();
+        let mut batch = TurnBatch::new(game_id, 0);
+        let start = Instant::now();
+        assert!(!batch.is_due(start + TURN_BATCH_MAX_AGE * 2));
+
+        batch.push(&channels, turn(1), checkpoint()).await;
+        assert!(!batch.is_due(Instant::now()));
+        assert!(batch.is_due(Instant::now() + TURN_BATCH_MAX_AGE));
+
+        for turn_number in 2..=TURN_BATCH_SIZE as i32 {
+            batch.push(&channels, turn(turn_number), checkpoint()).await;
+        }
+        assert!(batch.is_due(Instant::now()));
+
+        // Subscribers can read every pending frame before it's stored
+        let frames = channels.unstored_frames(game_id, 0).await;
+        assert_eq!(frames.len(), TURN_BATCH_SIZE);
+        assert_eq!(frames[0], (1, serde_json::json!({ "Turn": 1 })));
+    }
+}
@@ -0,0 +1,387 @@
+//! Per-stage timing of the game loop
+//!
+//! Each turn goes through the same stages: gather every snake's move, apply
+//! the moves, serialize the frame, persist it, then tell WebSocket subscribers
+//! and engine hooks about it. The runner times each stage and records it twice:
+//! in this instance's [`TurnMetrics`] histograms, served in Prometheus' text
+//! format at `/_/metrics`, and in the game's [`StageTimings`], saved when the
+//! game finishes for its timing report. Between them it's clear whether slow
+//! games are down to the snakes, SQL or serialization.
+
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// The stages of processing a turn, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// Calling every alive snake's /move, the snakes' share of a turn
+    GatherMoves,
+    /// The engine applying the moves
+    ApplyTurn,
+    /// Building and serializing the frame, and hashing it for certification
+    SerializeFrame,
+    /// Storing the turn, each snake's move and the game log
+    Persist,
+    /// Notifying WebSocket subscribers and engine hooks
+    Broadcast,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Stage::GatherMoves,
+        Stage::ApplyTurn,
+        Stage::SerializeFrame,
+        Stage::Persist,
+        Stage::Broadcast,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::GatherMoves => "gather_moves",
+            Stage::ApplyTurn => "apply_turn",
+            Stage::SerializeFrame => "serialize_frame",
+            Stage::Persist => "persist",
+            Stage::Broadcast => "broadcast",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|stage| stage.as_str() == name)
+    }
+
+    /// What it means when this stage dominates a game's time
+    pub fn culprit(&self) -> &'static str {
+        match self {
+            Stage::GatherMoves => "snakes",
+            Stage::ApplyTurn => "the engine",
+            Stage::SerializeFrame => "serialization",
+            Stage::Persist => "SQL",
+            Stage::Broadcast => "subscribers and hooks",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Upper bounds of the histogram buckets, in seconds
+const BUCKETS: [f64; 14] = [
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// A latency histogram that can be recorded into from any thread
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Observations per bucket, not cumulative; the last one is above every bound
+    buckets: [AtomicU64; BUCKETS.len() + 1],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let bucket = BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(BUCKETS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Append the histogram in Prometheus' text format
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (bound, bucket) in BUCKETS.iter().zip(&self.buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{bound}\"}} {cumulative}");
+        }
+        cumulative += self.buckets[BUCKETS.len()].load(Ordering::Relaxed);
+        let _ = writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {cumulative}");
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {sum}");
+        let _ = writeln!(
+            out,
+            "{name}_count{{{labels}}} {}",
+            self.count.load(Ordering::Relaxed)
+        );
+    }
+}
+
+/// Histograms of how long each stage of a turn takes, across every game this
+/// instance has run since it started
+#[derive(Debug, Clone, Default)]
+pub struct TurnMetrics {
+    stages: Arc<[Histogram; Stage::ALL.len()]>,
+}
+
+impl TurnMetrics {
+    pub fn observe(&self, stage: Stage, elapsed: Duration) {
+        self.stages[stage.index()].observe(elapsed);
+    }
+
+    /// How many times `stage` has been timed, and its mean time
+    pub fn mean(&self, stage: Stage) -> (u64, Duration) {
+        let histogram = &self.stages[stage.index()];
+        let count = histogram.count.load(Ordering::Relaxed);
+        let sum_micros = histogram.sum_micros.load(Ordering::Relaxed);
+        (count, Duration::from_micros(sum_micros / count.max(1)))
+    }
+
+    /// The histograms in Prometheus' text exposition format
+    pub fn render(&self) -> String {
+        let name = "arena_turn_stage_duration_seconds";
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# HELP {name} Time each stage of processing a game turn takes"
+        );
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        for stage in Stage::ALL {
+            self.stages[stage.index()].render(
+                &mut out,
+                name,
+                &format!("stage=\"{}\"", stage.as_str()),
+            );
+        }
+        out
+    }
+}
+
+/// One stage's totals over a game
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageTotal {
+    pub turns: i32,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+/// How long each stage took over one game, also fed into the instance's histograms
+#[derive(Debug, Clone)]
+pub struct StageTimings {
+    metrics: TurnMetrics,
+    totals: [StageTotal; Stage::ALL.len()],
+}
+
+impl StageTimings {
+    pub fn new(metrics: TurnMetrics) -> Self {
+        Self {
+            metrics,
+            totals: Default::default(),
+        }
+    }
+
+    pub fn record(&mut self, stage: Stage, elapsed: Duration) {
+        self.metrics.observe(stage, elapsed);
+        let total = &mut self.totals[stage.index()];
+        total.turns += 1;
+        total.total += elapsed;
+        total.max = total.max.max(elapsed);
+    }
+
+    /// Every stage's totals, in the order the stages run
+    pub fn totals(&self) -> impl Iterator<Item = (Stage, StageTotal)> + '_ {
+        Stage::ALL
+            .into_iter()
+            .map(|stage| (stage, self.totals[stage.index()]))
+    }
+}
+
+/// One stage's line in a game's timing report
+#[derive(Debug, Clone, Serialize)]
+pub struct StageReport {
+    pub stage: Stage,
+    pub turns: i32,
+    pub total_ms: f64,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+    /// Fraction of the game's processing time spent in this stage
+    pub share: f64,
+}
+
+/// Where a game's turn processing time went
+#[derive(Debug, Clone, Serialize)]
+pub struct TimingReport {
+    pub total_ms: f64,
+    pub stages: Vec<StageReport>,
+    /// The stage the most time went to, None if nothing took any time
+    pub slowest_stage: Option<Stage>,
+    pub verdict: String,
+}
+
+impl TimingReport {
+    pub fn new(totals: &[(Stage, StageTotal)]) -> Self {
+        let total: Duration = totals.iter().map(|(_, t)| t.total).sum();
+        let share_of = |d: Duration| {
+            if total.is_zero() {
+                0.0
+            } else {
+                d.as_secs_f64() / total.as_secs_f64()
+            }
+        };
+
+        let stages = totals
+            .iter()
+            .map(|(stage, t)| StageReport {
+                stage: *stage,
+                turns: t.turns,
+                total_ms: millis(t.total),
+                mean_ms: if t.turns > 0 {
+                    millis(t.total) / f64::from(t.turns)
+                } else {
+                    0.0
+                },
+                max_ms: millis(t.max),
+                share: share_of(t.total),
+            })
+            .collect();
+
+        let slowest = totals
+            .iter()
+            .filter(|(_, t)| !t.total.is_zero())
+            .max_by_key(|(_, t)| t.total);
+        let (slowest_stage, verdict) = match slowest {
+            Some((stage, t)) => (
+                Some(*stage),
+                format!(
+                    "Most of this game's time went to {} ({}, {:.0}%)",
+                    stage.culprit(),
+                    stage.as_str(),
+                    share_of(t.total) * 100.0
+                ),
+            ),
+            None => (None, "No time was recorded for this game".to_string()),
+        };
+
+        Self {
+            total_ms: millis(total),
+            stages,
+            slowest_stage,
+            verdict,
+        }
+    }
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_names_round_trip() {
+        for stage in Stage::ALL {
+            assert_eq!(Stage::from_name(stage.as_str()), Some(stage));
+        }
+        assert_eq!(Stage::from_name("nap"), None);
+    }
+
+    #[test]
+    fn test_histogram_renders_cumulative_buckets() {
+        let metrics = TurnMetrics::default();
+        metrics.observe(Stage::Persist, Duration::from_micros(300));
+        metrics.observe(Stage::Persist, Duration::from_millis(20));
+        metrics.observe(Stage::Persist, Duration::from_secs(30));
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE arena_turn_stage_duration_seconds histogram"));
+        assert!(text.contains(
+            "arena_turn_stage_duration_seconds_bucket{stage=\"persist\",le=\"0.0005\"} 1\n"
+        ));
+        assert!(text.contains(
+            "arena_turn_stage_duration_seconds_bucket{stage=\"persist\",le=\"0.025\"} 2\n"
+        ));
+        assert!(
+            text.contains(
+                "arena_turn_stage_duration_seconds_bucket{stage=\"persist\",le=\"10\"} 2\n"
+            )
+        );
+        assert!(text.contains(
+            "arena_turn_stage_duration_seconds_bucket{stage=\"persist\",le=\"+Inf\"} 3\n"
+        ));
+        assert!(
+            text.contains("arena_turn_stage_duration_seconds_sum{stage=\"persist\"} 30.0203\n")
+        );
+        assert!(text.contains("arena_turn_stage_duration_seconds_count{stage=\"persist\"} 3\n"));
+        assert!(
+            text.contains("arena_turn_stage_duration_seconds_count{stage=\"gather_moves\"} 0\n")
+        );
+    }
+
+    #[test]
+    fn test_mean() {
+        let metrics = TurnMetrics::default();
+        assert_eq!(metrics.mean(Stage::ApplyTurn), (0, Duration::ZERO));
+
+        metrics.observe(Stage::ApplyTurn, Duration::from_micros(100));
+        metrics.observe(Stage::ApplyTurn, Duration::from_micros(300));
+        assert_eq!(
+            metrics.mean(Stage::ApplyTurn),
+            (2, Duration::from_micros(200))
+        );
+    }
+
+    #[test]
+    fn test_stage_timings_total_each_stage() {
+        let metrics = TurnMetrics::default();
+        let mut timings = StageTimings::new(metrics.clone());
+        timings.record(Stage::GatherMoves, Duration::from_millis(40));
+        timings.record(Stage::GatherMoves, Duration::from_millis(90));
+        timings.record(Stage::Persist, Duration::from_millis(5));
+
+        let totals: Vec<_> = timings.totals().collect();
+        assert_eq!(totals.len(), Stage::ALL.len());
+        assert_eq!(
+            totals[0],
+            (
+                Stage::GatherMoves,
+                StageTotal {
+                    turns: 2,
+                    total: Duration::from_millis(130),
+                    max: Duration::from_millis(90),
+                }
+            )
+        );
+        assert_eq!(totals[1].1, StageTotal::default());
+        // The instance's histograms see the same observations
+        assert!(
+            metrics
+                .render()
+                .contains("arena_turn_stage_duration_seconds_count{stage=\"gather_moves\"} 2\n")
+        );
+    }
+
+    #[test]
+    fn test_timing_report_blames_the_slowest_stage() {
+        let mut timings = StageTimings::new(TurnMetrics::default());
+        timings.record(Stage::GatherMoves, Duration::from_millis(20));
+        timings.record(Stage::Persist, Duration::from_millis(60));
+        timings.record(Stage::Persist, Duration::from_millis(20));
+        let totals: Vec<_> = timings.totals().collect();
+
+        let report = TimingReport::new(&totals);
+        assert_eq!(report.total_ms, 100.0);
+        assert_eq!(report.slowest_stage, Some(Stage::Persist));
+        assert_eq!(
+            report.verdict,
+            "Most of this game's time went to SQL (persist, 80%)"
+        );
+        let persist = &report.stages[3];
+        assert_eq!(persist.mean_ms, 40.0);
+        assert_eq!(persist.max_ms, 60.0);
+        assert_eq!(report.stages[1].mean_ms, 0.0);
+
+        let empty = TimingReport::new(&[]);
+        assert_eq!(empty.slowest_stage, None);
+    }
+}
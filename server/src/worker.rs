@@ -0,0 +1,364 @@
+//! Which worker is running each game
+//!
+//! Each run of a game takes a lease naming this worker and heartbeats it every
+//! [`HEARTBEAT_INTERVAL`] until the game ends. That tells a game whose worker
+//! died (no heartbeat for [`LEASE_TIMEOUT_SECONDS`]) from one that's just slow:
+//! the stuck-game sweeper puts the first back in the queue and leaves the
+//! second alone, however long it's been running.
+//!
+//! A worker that loses its lease, because it stalled long enough to be swept,
+//! stops its run at the next turn rather than racing the worker that took over.
+//!
+//! For rolling deploys a worker can be drained: its job worker stops taking
+//! jobs, the games it's running play out, and [`DrainStatus`] says when the
+//! process can be stopped without killing one mid-run.
+
+use std::sync::{
+    Arc, Mutex, OnceLock,
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+use std::time::Duration;
+
+use color_eyre::eyre::Context as _;
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::game_retry::retry_failed_game;
+use crate::models::game::set_game_enqueued_at;
+use crate::models::game_lease::{
+    acquire_game_lease, expire_stale_game_leases, get_active_game_leases, get_worker_loads,
+    heartbeat_game_lease, release_game_lease,
+};
+use crate::state::AppState;
+
+/// How often a running game's lease is heartbeated
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long a lease can go without a heartbeat before its worker is taken for dead
+pub const LEASE_TIMEOUT_SECONDS: i64 = 60;
+
+static WORKER_ID: OnceLock<String> = OnceLock::new();
+
+/// This process's worker ID: `ARENA_WORKER_ID` if set, otherwise `{hostname}-{pid}`
+pub fn worker_id() -> &'static str {
+    WORKER_ID.get_or_init(|| {
+        std::env::var("ARENA_WORKER_ID")
+            .ok()
+            .filter(|id| !id.trim().is_empty())
+            .unwrap_or_else(|| {
+                let host = hostname::get()
+                    .ok()
+                    .and_then(|host| host.into_string().ok())
+                    .unwrap_or_else(|| "unknown".to_string());
+                format!("{}-{}", host, std::process::id())
+            })
+    })
+}
+
+/// Leases without a heartbeat since this are stale
+pub fn lease_cutoff(now: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::Utc> {
+    now - chrono::Duration::seconds(LEASE_TIMEOUT_SECONDS)
+}
+
+/// Whether a lease last heartbeated at `heartbeat_at` belongs to a dead worker
+pub fn is_lease_stale(
+    heartbeat_at: chrono::DateTime<chrono::Utc>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> bool {
+    heartbeat_at < lease_cutoff(now)
+}
+
+/// Whether this instance is draining, and what it's still doing
+#[derive(Clone, Default)]
+pub struct Drain {
+    inner: Arc<DrainInner>,
+}
+
+#[derive(Default)]
+struct DrainInner {
+    /// Cancelled to stop the job worker taking jobs
+    token: CancellationToken,
+    draining_since: Mutex<Option<chrono::DateTime<chrono::Utc>>>,
+    job_worker_running: AtomicBool,
+    running_games: AtomicUsize,
+}
+
+/// Response format for the drain endpoints
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DrainStatus {
+    pub worker_id: String,
+    pub draining: bool,
+    pub draining_since: Option<chrono::DateTime<chrono::Utc>>,
+    /// False once the job worker has finished its last job and stopped
+    pub job_worker_running: bool,
+    /// Games this instance is running now
+    pub running_games: usize,
+    /// True once draining has finished and the process can be stopped
+    pub safe_to_terminate: bool,
+}
+
+impl Drain {
+    /// The token the job worker stops on
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.inner.token.clone()
+    }
+
+    /// Stop taking jobs; draining again keeps the original start time
+    pub fn start(&self, now: chrono::DateTime<chrono::Utc>) {
+        let mut draining_since = self
+            .inner
+            .draining_since
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if draining_since.is_none() {
+            *draining_since = Some(now);
+            self.inner.token.cancel();
+            tracing::info!(worker_id = worker_id(), "Draining, no longer taking jobs");
+        }
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.inner.token.is_cancelled()
+    }
+
+    pub fn set_job_worker_running(&self, running: bool) {
+        self.inner
+            .job_worker_running
+            .store(running, Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> DrainStatus {
+        let draining_since = *self
+            .inner
+            .draining_since
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let job_worker_running = self.inner.job_worker_running.load(Ordering::Relaxed);
+        let running_games = self.inner.running_games.load(Ordering::Relaxed);
+
+        DrainStatus {
+            worker_id: worker_id().to_string(),
+            draining: draining_since.is_some(),
+            draining_since,
+            job_worker_running,
+            running_games,
+            safe_to_terminate: draining_since.is_some()
+                && !job_worker_running
+                && running_games == 0,
+        }
+    }
+}
+
+/// This worker's lease on a game, heartbeated in the background until released
+pub struct Lease {
+    game_id: Uuid,
+    lease_id: Uuid,
+    lost: Arc<AtomicBool>,
+    heartbeat: tokio::task::JoinHandle<()>,
+    /// Counts the lease among the instance's running games until dropped
+    drain: Drain,
+}
+
+impl Lease {
+    /// Take the lease on a game, or None if another worker holds a live one
+    pub async fn acquire(app_state: &AppState, game_id: Uuid) -> cja::Result<Option<Self>> {
+        let now = app_state.clock.now();
+        let Some(lease_id) =
+            acquire_game_lease(&app_state.db, game_id, worker_id(), now, lease_cutoff(now)).await?
+        else {
+            return Ok(None);
+        };
+
+        let lost = Arc::new(AtomicBool::new(false));
+        let heartbeat = tokio::spawn(heartbeat(
+            app_state.clone(),
+            game_id,
+            lease_id,
+            lost.clone(),
+        ));
+
+        let drain = app_state.drain.clone();
+        drain.inner.running_games.fetch_add(1, Ordering::Relaxed);
+
+        tracing::info!(game_id = %game_id, worker_id = worker_id(), "Acquired game lease");
+        Ok(Some(Self {
+            game_id,
+            lease_id,
+            lost,
+            heartbeat,
+            drain,
+        }))
+    }
+
+    /// True once the lease has been swept and the game handed to another run
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Relaxed)
+    }
+
+    /// Stop heartbeating and release the lease
+    pub async fn release(self, app_state: &AppState) -> cja::Result<()> {
+        self.heartbeat.abort();
+        release_game_lease(&app_state.db, self.lease_id, app_state.clock.now())
+            .await
+            .wrap_err_with(|| format!("Failed to release lease on game {}", self.game_id))
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        self.heartbeat.abort();
+        self.drain
+            .inner
+            .running_games
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+async fn heartbeat(app_state: AppState, game_id: Uuid, lease_id: Uuid, lost: Arc<AtomicBool>) {
+    let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+    // The first tick is immediate, and acquiring the lease was a heartbeat
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        match heartbeat_game_lease(&app_state.db, lease_id, app_state.clock.now()).await {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::warn!(
+                    game_id = %game_id,
+                    worker_id = worker_id(),
+                    "Lost game lease, another run has taken over"
+                );
+                lost.store(true, Ordering::Relaxed);
+                return;
+            }
+            // Keep trying; the lease only goes stale after several misses
+            Err(e) => tracing::warn!(
+                game_id = %game_id,
+                error = %e,
+                "Failed to heartbeat game lease"
+            ),
+        }
+    }
+}
+
+/// Retry the running games whose workers have stopped heartbeating
+///
+/// Each game is picked back up from its last checkpoint, see
+/// [`crate::game_retry`]. Running games without a lease, from before leases
+/// existed, are left as they are.
+pub async fn sweep_stuck_games(app_state: &AppState) -> cja::Result<()> {
+    let now = app_state.clock.now();
+    let expired = expire_stale_game_leases(&app_state.db, lease_cutoff(now), now).await?;
+
+    for lease in expired {
+        tracing::warn!(
+            game_id = %lease.game_id,
+            worker_id = %lease.worker_id,
+            last_heartbeat_at = %lease.heartbeat_at,
+            "Worker stopped heartbeating, retrying game"
+        );
+        set_game_enqueued_at(&app_state.db, lease.game_id, now).await?;
+        retry_failed_game(
+            app_state,
+            lease.game_id,
+            &format!("Worker {} stopped heartbeating", lease.worker_id),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Print each worker's load and the games being run now, for `arena admin workers`
+pub async fn run_workers_command(app_state: &AppState) -> cja::Result<()> {
+    let now = app_state.clock.now();
+    let loads = get_worker_loads(&app_state.db, now - chrono::Duration::hours(1)).await?;
+    let leases = get_active_game_leases(&app_state.db).await?;
+
+    if loads.is_empty() {
+        println!("No workers have run a game in the last hour");
+        return Ok(());
+    }
+
+    println!(
+        "{:<40} {:>8} {:>14} {:>16}",
+        "WORKER", "RUNNING", "FINISHED (1H)", "LAST HEARTBEAT"
+    );
+    for load in &loads {
+        let heartbeat = format!("{}s ago", (now - load.last_heartbeat_at).num_seconds());
+        println!(
+            "{:<40} {:>8} {:>14} {:>16}",
+            load.worker_id, load.running_games, load.finished_games, heartbeat,
+        );
+    }
+
+    if !leases.is_empty() {
+        println!();
+        println!(
+            "{:<36} {:<40} {:>10} {:>16}",
+            "GAME", "WORKER", "RUNNING", "HEARTBEAT"
+        );
+        for lease in &leases {
+            let heartbeat = if is_lease_stale(lease.heartbeat_at, now) {
+                "stale (dead)".to_string()
+            } else {
+                format!("{}s ago", (now - lease.heartbeat_at).num_seconds())
+            };
+            let running = format!("{}s", (now - lease.acquired_at).num_seconds());
+            println!(
+                "{:<36} {:<40} {:>10} {:>16}",
+                lease.game_id, lease.worker_id, running, heartbeat,
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_lease_stale() {
+        let now = chrono::Utc::now();
+        assert!(!is_lease_stale(now, now));
+        assert!(!is_lease_stale(now - chrono::Duration::seconds(30), now));
+        assert!(!is_lease_stale(
+            now - chrono::Duration::seconds(LEASE_TIMEOUT_SECONDS),
+            now
+        ));
+        assert!(is_lease_stale(
+            now - chrono::Duration::seconds(LEASE_TIMEOUT_SECONDS + 1),
+            now
+        ));
+    }
+
+    #[test]
+    fn test_drain_status() {
+        let drain = Drain::default();
+        drain.set_job_worker_running(true);
+        let status = drain.status();
+        assert!(!status.draining);
+        assert!(!status.safe_to_terminate);
+
+        let started = chrono::Utc::now();
+        drain.start(started);
+        assert!(drain.cancellation_token().is_cancelled());
+        assert!(drain.status().draining);
+        // Still finishing its last job
+        assert!(!drain.status().safe_to_terminate);
+
+        drain.set_job_worker_running(false);
+        drain.inner.running_games.fetch_add(1, Ordering::Relaxed);
+        assert!(!drain.status().safe_to_terminate);
+        drain.inner.running_games.fetch_sub(1, Ordering::Relaxed);
+        assert!(drain.status().safe_to_terminate);
+
+        // Draining again keeps the original start
+        drain.start(started + chrono::Duration::seconds(30));
+        assert_eq!(drain.status().draining_since, Some(started));
+    }
+}
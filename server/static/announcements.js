@@ -0,0 +1,37 @@
+// Dismissing an announcement banner hides it for good in this browser. The
+// dismissed ids are kept in localStorage, so each new announcement shows once.
+(function () {
+  const container = document.getElementById("announcements");
+  if (!container) return;
+
+  const KEY = "dismissedAnnouncements";
+
+  function dismissed() {
+    try {
+      return JSON.parse(localStorage.getItem(KEY)) || [];
+    } catch (e) {
+      return [];
+    }
+  }
+
+  const current = Array.from(container.querySelectorAll("[data-announcement-id]"));
+  const ids = current.map((banner) => banner.dataset.announcementId);
+  for (const banner of current) {
+    if (dismissed().includes(banner.dataset.announcementId)) banner.remove();
+  }
+
+  container.addEventListener("click", (event) => {
+    const button = event.target.closest(".announcement-dismiss");
+    if (!button) return;
+    const banner = button.closest("[data-announcement-id]");
+    // Forget announcements that have ended, so the list doesn't grow forever
+    const kept = dismissed().filter((id) => ids.includes(id));
+    kept.push(banner.dataset.announcementId);
+    try {
+      localStorage.setItem(KEY, JSON.stringify(kept));
+    } catch (e) {
+      // Private browsing; it's hidden until the next page at least
+    }
+    banner.remove();
+  });
+})();
@@ -0,0 +1,50 @@
+// Replay controls for the board viewer on game pages, sized for touch. The
+// viewer is a cross-origin iframe, so changing speed or restarting reloads it
+// with a new URL. In reduced-data mode the page renders the viewer without a
+// src, and it only loads (and downloads the game's frames) when asked to.
+(function () {
+  const viewer = document.getElementById("board-viewer");
+  const controls = document.getElementById("replay-controls");
+  if (!viewer || !controls) return;
+
+  const MIN_FPS = 1;
+  const MAX_FPS = 30;
+
+  function load(src) {
+    viewer.src = src;
+    controls.querySelectorAll("[data-needs-board]").forEach((button) => {
+      button.disabled = false;
+    });
+    const loadButton = document.getElementById("load-board");
+    if (loadButton) loadButton.hidden = true;
+  }
+
+  function withFps(change) {
+    const url = new URL(viewer.src);
+    const fps = Number(url.searchParams.get("fps")) || 6;
+    url.searchParams.set("fps", String(Math.min(MAX_FPS, Math.max(MIN_FPS, change(fps)))));
+    return url.toString();
+  }
+
+  const actions = {
+    load: () => load(viewer.dataset.src),
+    restart: () => load(viewer.src),
+    slower: () => load(withFps((fps) => Math.round(fps / 2))),
+    faster: () => load(withFps((fps) => fps * 2)),
+    fullscreen: () => {
+      const container = viewer.parentElement;
+      if (document.fullscreenElement) {
+        document.exitFullscreen();
+      } else if (container.requestFullscreen) {
+        container.requestFullscreen();
+      }
+    },
+  };
+
+  controls.addEventListener("click", (event) => {
+    const button = event.target.closest("button[data-action]");
+    if (!button || button.disabled) return;
+    const action = actions[button.dataset.action];
+    if (action) action();
+  });
+})();
@@ -0,0 +1,121 @@
+// The board in words on game pages: steps through the server's description
+// of each turn, with keyboard shortcuts. Space plays or pauses, the left and
+// right arrows step a turn, and 1 to 9 set the speed in turns per second.
+// The board viewer iframe keeps its own controls; these drive the text.
+// Stepping keeps ?turn= in the address bar, so copying it links to the turn.
+(function () {
+  const panel = document.getElementById("board-description-panel");
+  if (!panel) return;
+
+  const description = document.getElementById("board-description");
+  const playButton = document.getElementById("describe-play");
+  const speedLabel = document.getElementById("describe-speed");
+  const live = panel.hasAttribute("data-live");
+
+  let turns = [];
+  let index = 0;
+  // The turn the page opened at, until the descriptions have loaded
+  let startTurn = panel.dataset.turn === undefined ? null : Number(panel.dataset.turn);
+  let speed = 3;
+  let timer = null;
+
+  async function load() {
+    try {
+      const response = await fetch(panel.dataset.url, { credentials: "same-origin" });
+      if (!response.ok) return;
+      turns = (await response.json()).turns;
+      if (startTurn !== null) {
+        // The latest turn played up to the linked one, as the server described
+        const start = turns.findLastIndex((turn) => turn.turn <= startTurn);
+        index = Math.max(start, 0);
+        startTurn = null;
+      }
+    } catch (e) {
+      // Keep what we had; the next step past the end tries again
+    }
+  }
+
+  function show() {
+    const turn = turns[index];
+    if (!turn) return;
+    description.textContent = turn.description;
+
+    const url = new URL(window.location.href);
+    url.searchParams.set("turn", turn.turn);
+    history.replaceState(history.state, "", url);
+  }
+
+  async function step(by) {
+    // Live games get new turns, so look for more at the end
+    if (live && index + by >= turns.length) await load();
+    if (turns.length === 0) return false;
+    const next = Math.min(Math.max(index + by, 0), turns.length - 1);
+    if (next === index) return false;
+    index = next;
+    show();
+    return true;
+  }
+
+  function pause() {
+    clearInterval(timer);
+    timer = null;
+    playButton.textContent = "Play";
+    playButton.setAttribute("aria-label", "Play");
+    // Read out each turn while stepping, but not every turn of a playing replay
+    description.setAttribute("aria-live", "polite");
+  }
+
+  function play() {
+    if (index >= turns.length - 1 && !live) index = 0;
+    description.setAttribute("aria-live", "off");
+    playButton.textContent = "Pause";
+    playButton.setAttribute("aria-label", "Pause");
+    timer = setInterval(async () => {
+      if (!(await step(1)) && !live) pause();
+    }, 1000 / speed);
+  }
+
+  function toggle() {
+    if (timer) pause();
+    else play();
+  }
+
+  function setSpeed(turnsPerSecond) {
+    speed = turnsPerSecond;
+    speedLabel.textContent = `Speed ${speed}`;
+    if (timer) {
+      clearInterval(timer);
+      play();
+    }
+  }
+
+  panel.addEventListener("click", (event) => {
+    const button = event.target.closest("button[data-step]");
+    if (!button) return;
+    if (button.dataset.step === "play") toggle();
+    else step(button.dataset.step === "next" ? 1 : -1);
+  });
+
+  document.addEventListener("keydown", (event) => {
+    if (event.ctrlKey || event.metaKey || event.altKey) return;
+    const target = event.target;
+    if (target.isContentEditable || ["INPUT", "SELECT", "TEXTAREA"].includes(target.tagName)) {
+      return;
+    }
+
+    if (event.key === " ") {
+      // Space on a focused button already presses it
+      if (target.tagName === "BUTTON") return;
+      event.preventDefault();
+      toggle();
+    } else if (event.key === "ArrowLeft" || event.key === "ArrowRight") {
+      event.preventDefault();
+      pause();
+      step(event.key === "ArrowRight" ? 1 : -1);
+    } else if (/^[1-9]$/.test(event.key)) {
+      setSpeed(Number(event.key));
+    }
+  });
+
+  load();
+})();
@@ -0,0 +1,20 @@
+// Reduced-data mode: on when the server saw `Save-Data: on` (the page's body
+// has data-reduced-data) or the browser reports data saver or a 2G/3G
+// connection. Pages that poll ask arenaPollInterval how long to wait.
+(function () {
+  // Polls are this many times further apart in reduced-data mode
+  const REDUCED_DATA_POLL_FACTOR = 3;
+  const SLOW_CONNECTIONS = ["slow-2g", "2g", "3g"];
+
+  function reducedData() {
+    if (document.body && document.body.hasAttribute("data-reduced-data")) return true;
+    const connection = navigator.connection;
+    if (!connection) return false;
+    return Boolean(connection.saveData) || SLOW_CONNECTIONS.includes(connection.effectiveType);
+  }
+
+  window.arenaReducedData = reducedData;
+  window.arenaPollInterval = function (ms) {
+    return reducedData() ? ms * REDUCED_DATA_POLL_FACTOR : ms;
+  };
+})();
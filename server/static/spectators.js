@@ -0,0 +1,25 @@
+// "N watching" on game pages: polls the game's spectator count while it's
+// live. The page's #spectators element carries the URL to poll.
+(function () {
+  const element = document.getElementById("spectators");
+  if (!element) return;
+
+  const url = element.dataset.url;
+  // Streams report the count every 5 seconds; polling a little slower is
+  // plenty, and slower still in reduced-data mode
+  const POLL_MS = window.arenaPollInterval(10000);
+
+  async function refresh() {
+    try {
+      const response = await fetch(url, { credentials: "same-origin" });
+      if (!response.ok) return;
+      const { spectators } = await response.json();
+      element.textContent = `${spectators} watching`;
+    } catch (e) {
+      // Keep the last count; the next poll will try again
+    }
+  }
+
+  refresh();
+  setInterval(refresh, POLL_MS);
+})();
@@ -0,0 +1,90 @@
+// Arena TV: shows the longest running public game, and switches to the next
+// one when it ends. The page's #tv element carries the URLs it needs.
+(function () {
+  const tv = document.getElementById("tv");
+  if (!tv) return;
+
+  const apiUrl = tv.dataset.api;
+  const gamesUrl = tv.dataset.games;
+  const viewer = document.getElementById("board-viewer");
+  const caption = document.getElementById("tv-now-playing");
+  const offAir = document.getElementById("tv-off-air");
+
+  // How often to check for a game while nothing is on, less often in
+  // reduced-data mode
+  const POLL_MS = window.arenaPollInterval(10000);
+  // Time to let the viewer play out the last turns before switching
+  const SWITCH_DELAY_MS = 5000;
+
+  let current = null;
+  let socket = null;
+
+  function describe(game, turn) {
+    const names = game.snakes.join(" vs ");
+    return turn == null ? names : `${names} · turn ${turn}`;
+  }
+
+  function show(game) {
+    current = game;
+    if (!game) {
+      viewer.hidden = true;
+      caption.textContent = "";
+      offAir.hidden = false;
+      return;
+    }
+    offAir.hidden = true;
+    viewer.hidden = false;
+    if (viewer.src !== game.viewer_url) viewer.src = game.viewer_url;
+    caption.textContent = describe(game, game.turn);
+  }
+
+  // Follow the game over the spectator WebSocket to know when it ends. Only
+  // the turn number is needed, so fast-forward past the game's history and
+  // take patches rather than whole frames.
+  function watch(game) {
+    if (socket) socket.close();
+    const scheme = location.protocol === "https:" ? "wss:" : "ws:";
+    const ws = new WebSocket(
+      `${scheme}//${location.host}${gamesUrl}/${game.game_id}/events?fast_forward=true`,
+      ["arena.patches"]
+    );
+    const FRAMES = ["frame", "keyframe", "delta", "patch"];
+    socket = ws;
+
+    ws.onmessage = (event) => {
+      const message = JSON.parse(event.data);
+      if (FRAMES.includes(message.Type) && current && current.game_id === game.game_id) {
+        caption.textContent = describe(game, message.Data.Turn);
+      }
+    };
+    ws.onclose = () => {
+      // Closed by us when switching, or by the server when the game ends
+      if (socket === ws) {
+        socket = null;
+        setTimeout(tune, SWITCH_DELAY_MS);
+      }
+    };
+  }
+
+  async function tune() {
+    let data;
+    try {
+      const response = await fetch(apiUrl);
+      data = await response.json();
+    } catch (e) {
+      setTimeout(tune, POLL_MS);
+      return;
+    }
+
+    const game = data.now_playing;
+    if (!game) {
+      show(null);
+      setTimeout(tune, POLL_MS);
+      return;
+    }
+    if (!current || current.game_id !== game.game_id) show(game);
+    watch(game);
+  }
+
+  tune();
+})();